@@ -2,7 +2,7 @@ use crate::error::AlerterError;
 use configuration::TelegramConfig;
 use reqwest::Client;
 use serde::Serialize;
-use events::{LogLevel, WsMessage};
+use events::{EventSubscriber, LogLevel, WsMessage};
 use tokio::sync::broadcast;
 pub mod error;
 
@@ -58,11 +58,11 @@ impl TelegramAlerter {
         Ok(())
     }
 }
-/// A long-running service that listens to a broadcast channel of `WsMessage` events
+/// A long-running service that listens to the `EventBus` for `WsMessage` events
 /// and sends Telegram alerts for critical events.
 pub async fn run_alerter_service(
     alerter: TelegramAlerter,
-    mut event_rx: broadcast::Receiver<WsMessage>,
+    mut event_rx: EventSubscriber,
 ) {
     tracing::info!("Alerter service started. Listening for critical events.");
 
@@ -88,9 +88,15 @@ pub async fn run_alerter_service(
                     WsMessage::TradeExecuted(exec) => {
                         let side = format!("{:?}", exec.side).to_uppercase();
                         let icon = if side == "BUY" { "📈" } else { "📉" };
+                        let improvement = exec
+                            .placement
+                            .as_ref()
+                            .and_then(|placement| placement.price_improvement_bps)
+                            .map(|bps| format!("\n`{:.2}` bps better than crossing the spread", bps))
+                            .unwrap_or_default();
                         Some(format!(
-                            "{} *{} {}* `@{}`\n`{:.4}` units",
-                            icon, side, escape_markdown(&exec.symbol), exec.price, exec.quantity
+                            "{} *{} {}* `@{}`\n`{:.4}` units{}",
+                            icon, side, escape_markdown(&exec.symbol), exec.price, exec.quantity, improvement
                         ))
                     }
                     _ => None, // Ignore PortfolioState, Connected, etc.
@@ -0,0 +1,130 @@
+//! Thinning of equity curves for storage, and of klines for charting.
+//!
+//! A full-resolution curve has one point per bar, which is far more than a chart or a ranking
+//! needs. Every equity function here keeps the first and last points, and the peak and trough
+//! of the maximum drawdown, so a thinned curve still reports the same maximum drawdown as the
+//! original.
+//!
+//! Klines are never dropped, since a candle chart with bars missing misstates the range price
+//! traded in. They are instead merged into wider candles by `aggregate_klines`.
+
+use chrono::{DateTime, Utc};
+use core_types::Kline;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::BTreeSet;
+
+type EquityPoint = (DateTime<Utc>, Decimal);
+
+/// Finds the maximum drawdown of `equity_curve`, returning the indices of its peak and trough.
+/// Returns `None` if the curve never falls below a previous high.
+pub fn max_drawdown_points(equity_curve: &[EquityPoint]) -> Option<(usize, usize)> {
+    let mut peak = 0;
+    let mut worst: Option<(usize, usize)> = None;
+    let mut max_drawdown = Decimal::ZERO;
+
+    for (i, &(_, equity)) in equity_curve.iter().enumerate() {
+        if equity > equity_curve[peak].1 {
+            peak = i;
+        }
+        let drawdown = equity_curve[peak].1 - equity;
+        if drawdown > max_drawdown {
+            max_drawdown = drawdown;
+            worst = Some((peak, i));
+        }
+    }
+    worst
+}
+
+/// Keeps every `n`th point of the curve, plus the first, last and drawdown points.
+pub fn every_nth(equity_curve: &[EquityPoint], n: usize) -> Vec<EquityPoint> {
+    let sampled = (0..equity_curve.len()).step_by(n.max(1));
+    with_anchors(equity_curve, sampled)
+}
+
+/// Downsamples the curve to about `threshold` points with the Largest-Triangle-Three-Buckets
+/// algorithm, which keeps the points that contribute most to the curve's visual shape. The
+/// first, last and drawdown points are always kept, so the result may exceed `threshold` by
+/// up to two points.
+pub fn lttb(equity_curve: &[EquityPoint], threshold: usize) -> Vec<EquityPoint> {
+    let len = equity_curve.len();
+    if len <= threshold {
+        return equity_curve.to_vec();
+    }
+    if threshold < 3 {
+        return with_anchors(equity_curve, []);
+    }
+
+    let x = |i: usize| equity_curve[i].0.timestamp_millis() as f64;
+    let y = |i: usize| equity_curve[i].1.to_f64().unwrap_or_default();
+
+    // The first and last points are their own buckets; the rest are split evenly.
+    let bucket_size = (len - 2) as f64 / (threshold - 2) as f64;
+    let bucket_start = |b: usize| (b as f64 * bucket_size) as usize + 1;
+
+    let mut selected = Vec::with_capacity(threshold);
+    let mut previous = 0;
+    for bucket in 0..threshold - 2 {
+        let (start, end) = (bucket_start(bucket), bucket_start(bucket + 1).min(len - 1));
+
+        // The third vertex of each triangle is the average of the next bucket.
+        let (next_start, next_end) = (end, bucket_start(bucket + 2).min(len - 1).max(end + 1));
+        let count = (next_end - next_start) as f64;
+        let avg_x = (next_start..next_end).map(x).sum::<f64>() / count;
+        let avg_y = (next_start..next_end).map(y).sum::<f64>() / count;
+
+        let (px, py) = (x(previous), y(previous));
+        let area = |i: usize| ((px - avg_x) * (y(i) - py) - (px - x(i)) * (avg_y - py)).abs();
+        let chosen = (start..end).max_by(|&a, &b| area(a).total_cmp(&area(b))).unwrap_or(start);
+
+        selected.push(chosen);
+        previous = chosen;
+    }
+    with_anchors(equity_curve, selected)
+}
+
+/// Collects the points at `indices`, plus the first, last and drawdown points, in curve order.
+fn with_anchors(equity_curve: &[EquityPoint], indices: impl IntoIterator<Item = usize>) -> Vec<EquityPoint> {
+    if equity_curve.is_empty() {
+        return Vec::new();
+    }
+    let mut keep: BTreeSet<usize> = indices.into_iter().collect();
+    keep.insert(0);
+    keep.insert(equity_curve.len() - 1);
+    if let Some((peak, trough)) = max_drawdown_points(equity_curve) {
+        keep.insert(peak);
+        keep.insert(trough);
+    }
+    keep.into_iter().map(|i| equity_curve[i]).collect()
+}
+
+/// The number of consecutive klines `aggregate_klines` must merge into each candle so that
+/// `count` klines fit in `max_points` candles.
+pub fn kline_bucket_size(count: usize, max_points: usize) -> usize {
+    count.div_ceil(max_points.max(1)).max(1)
+}
+
+/// Merges every `bucket_size` consecutive klines into one candle spanning them all: the first
+/// open, the highest high, the lowest low, the last close and the total volume. The final
+/// candle covers whatever klines remain. Candles keep the interval of the klines they merge.
+pub fn aggregate_klines(klines: &[Kline], bucket_size: usize) -> Vec<Kline> {
+    if bucket_size <= 1 {
+        return klines.to_vec();
+    }
+    klines
+        .chunks(bucket_size)
+        .map(|bucket| {
+            let (first, last) = (&bucket[0], &bucket[bucket.len() - 1]);
+            Kline {
+                open_time: first.open_time,
+                open: first.open,
+                high: bucket.iter().map(|k| k.high).max().unwrap_or(first.high),
+                low: bucket.iter().map(|k| k.low).min().unwrap_or(first.low),
+                close: last.close,
+                volume: bucket.iter().map(|k| k.volume).sum(),
+                close_time: last.close_time,
+                interval: first.interval.clone(),
+            }
+        })
+        .collect()
+}
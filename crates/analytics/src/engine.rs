@@ -1,10 +1,14 @@
 use crate::error::AnalyticsError;
-use crate::report::PerformanceReport;
+use crate::report::{ExitStats, PerformanceReport, RDistribution};
 use chrono::{DateTime, Duration, Utc};
-use core_types::{OrderSide, Trade};
+use core_types::{CloseReason, Interval, OrderSide, Period, Trade};
+use std::collections::HashMap;
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
 
+/// The trading days in a year, by which the Sharpe Ratio is annualized.
+const TRADING_DAYS_PER_YEAR: f64 = 252.0;
+
 /// A stateless calculator for deriving performance metrics from trading activity.
 #[derive(Debug, Default)]
 pub struct AnalyticsEngine {}
@@ -14,12 +18,31 @@ impl AnalyticsEngine {
         Self::default()
     }
 
+    /// Counts the trades closed for each reason (signal, stop-loss, time limit, ...).
+    /// Reasons no trade was closed for are absent.
+    pub fn close_reason_counts(&self, trades: &[Trade]) -> HashMap<CloseReason, usize> {
+        let mut counts = HashMap::new();
+        for trade in trades {
+            *counts.entry(trade.close_reason).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Each trade's R-multiple: its PnL over the risk it took at entry. None for trades
+    /// without a known initial risk.
+    pub fn r_multiples(&self, trades: &[Trade]) -> Vec<Option<Decimal>> {
+        trades.iter().map(r_multiple).collect()
+    }
+
     /// The main entry point for calculating performance metrics.
     /// The `interval` string is required to correctly annualize the Sharpe Ratio.
+    /// `exposure` is the gross notional of the open positions at each point of the equity
+    /// curve; the capital efficiency metrics are left unset when it is empty.
     pub fn calculate(
         &self,
         trades: &[Trade],
         equity_curve: &[(DateTime<Utc>, Decimal)],
+        exposure: &[Decimal],
         initial_capital: Decimal,
         interval: &str, // <-- FIX: Added interval for annualization
     ) -> Result<PerformanceReport, AnalyticsError> {
@@ -56,8 +79,25 @@ impl AnalyticsEngine {
             average_win: profitability_report.average_win,
             average_loss: profitability_report.average_loss,
             payoff_ratio: profitability_report.payoff_ratio,
+            max_consecutive_losses: profitability_report.max_consecutive_losses,
             average_holding_period: time_metrics_report.average_holding_period,
+            maker_fees_paid: profitability_report.maker_fees_paid,
+            taker_fees_paid: profitability_report.taker_fees_paid,
+            total_spread_cost: profitability_report.total_spread_cost,
+            // Filled in by the backtester, which knows each stop's price.
+            gap_slippage: Decimal::ZERO,
+            exit_breakdown: self.calculate_exit_breakdown(trades),
+            average_r: None,
+            expectancy_r: None,
+            r_distribution: RDistribution::default(),
+            average_exposure_pct: None,
+            peak_exposure_pct: None,
+            time_in_market_pct: None,
+            return_on_margin_pct: None,
+            exposure_adjusted_sharpe: None,
         };
+        self.calculate_r_metrics(trades, &mut report);
+        self.calculate_capital_efficiency(equity_curve, exposure, interval, &mut report)?;
         
         // Extract the fields needed for calculate_ratios
         let total_return_pct = report.total_return_pct;
@@ -75,6 +115,60 @@ impl AnalyticsEngine {
         Ok(report)
     }
 
+    /// Calculates the metrics of one period of a longer history, such as a day of live
+    /// trading. Unlike `calculate`, a period without trades or with a short equity curve still
+    /// gets every metric its data supports. Nothing is annualized, so the Sharpe and Calmar
+    /// ratios are left unset. Returns are relative to `opening_equity`.
+    pub fn calculate_period(
+        &self,
+        trades: &[Trade],
+        equity_curve: &[(DateTime<Utc>, Decimal)],
+        opening_equity: Decimal,
+    ) -> Result<PerformanceReport, AnalyticsError> {
+        let mut report = PerformanceReport::new();
+        self.calculate_profitability(trades, opening_equity, &mut report)?;
+        if !equity_curve.is_empty() {
+            self.calculate_drawdown(equity_curve, &mut report)?;
+        }
+        self.calculate_time_metrics(trades, &mut report)?;
+        report.exit_breakdown = self.calculate_exit_breakdown(trades);
+        self.calculate_r_metrics(trades, &mut report);
+        Ok(report)
+    }
+
+    /// The Sharpe Ratio of the returns between consecutive points of `equity_curve`, annualized
+    /// by the points per year of `interval`. None with fewer than two returns or without
+    /// volatility. Unlike `calculate`, it needs no trades, so a curve resampled to daily closes
+    /// can be scored on its own.
+    pub fn sharpe_ratio(&self, equity_curve: &[(DateTime<Utc>, Decimal)], interval: &str) -> Result<Option<Decimal>, AnalyticsError> {
+        let returns: Vec<Decimal> = equity_curve
+            .windows(2)
+            .map(|w| (w[1].1 - w[0].1) / w[0].1)
+            .collect();
+
+        if returns.len() < 2 {
+            return Ok(None);
+        }
+
+        let mean_return: Decimal = returns.iter().sum::<Decimal>() / Decimal::from(returns.len());
+        
+        let std_dev: Decimal = {
+            let variance = returns
+                .iter()
+                .map(|r| (*r - mean_return) * (*r - mean_return))
+                .sum::<Decimal>()
+                / Decimal::from(returns.len());
+            variance.sqrt().ok_or_else(|| AnalyticsError::InternalError("Could not calculate standard deviation.".to_string()))?
+        };
+
+        if std_dev.is_zero() {
+            return Ok(None);
+        }
+        let periods_in_year = self.get_periods_in_year(interval)?;
+        let annualization_factor = Decimal::from(periods_in_year).sqrt().ok_or_else(|| AnalyticsError::InternalError("Could not get annualization factor.".to_string()))?;
+        Ok(Some((mean_return / std_dev) * annualization_factor))
+    }
+
     /// Calculates all profitability-related metrics.
     fn calculate_profitability(
         &self,
@@ -83,29 +177,30 @@ impl AnalyticsEngine {
         report: &mut PerformanceReport,
     ) -> Result<(), AnalyticsError> {
         report.total_trades = trades.len();
+        let mut consecutive_losses = 0;
 
         for trade in trades {
-            // --- FIX #1: Correctly calculate PnL based on trade side ---
-            let pnl = match trade.entry_execution.side {
-                OrderSide::Buy => {
-                    (trade.exit_execution.price - trade.entry_execution.price)
-                        * trade.exit_execution.quantity
-                }
-                OrderSide::Sell => {
-                    (trade.entry_execution.price - trade.exit_execution.price)
-                        * trade.exit_execution.quantity
-                }
-            };
-            // --- END FIX #1 ---
-
+            let pnl = trade_pnl(trade);
             report.total_net_profit += pnl;
 
+            for execution in [&trade.entry_execution, &trade.exit_execution] {
+                if execution.is_maker {
+                    report.maker_fees_paid += execution.fee;
+                } else {
+                    report.taker_fees_paid += execution.fee;
+                }
+                report.total_spread_cost += execution.spread_cost;
+            }
+
             if pnl.is_sign_positive() {
                 report.gross_profit += pnl;
                 report.winning_trades += 1;
+                consecutive_losses = 0;
             } else {
                 report.gross_loss += pnl.abs();
                 report.losing_trades += 1;
+                consecutive_losses += 1;
+                report.max_consecutive_losses = report.max_consecutive_losses.max(consecutive_losses);
             }
         }
 
@@ -138,6 +233,54 @@ impl AnalyticsEngine {
         Ok(())
     }
 
+    /// Breaks the trades' PnL down by the reason each was closed, so exits forced by a stop,
+    /// a time limit or a trading blackout can be compared with the strategy's own. Trades saved before reasons
+    /// were recorded all read as closed by a signal.
+    fn calculate_exit_breakdown(&self, trades: &[Trade]) -> Vec<ExitStats> {
+        let reasons = [CloseReason::Signal, CloseReason::StopLoss, CloseReason::TakeProfit, CloseReason::TimeLimit, CloseReason::Blackout];
+        reasons
+            .into_iter()
+            .filter_map(|reason| {
+                let pnls: Vec<Decimal> = trades.iter().filter(|trade| trade.close_reason == reason).map(trade_pnl).collect();
+                if pnls.is_empty() {
+                    return None;
+                }
+                let count = Decimal::from(pnls.len());
+                let net_pnl: Decimal = pnls.iter().sum();
+                // Counted as in `calculate_profitability`: a break-even trade is a win.
+                let winners = pnls.iter().filter(|pnl| pnl.is_sign_positive()).count();
+                Some(ExitStats {
+                    close_reason: reason,
+                    trades: pnls.len(),
+                    net_pnl,
+                    win_rate_pct: Decimal::from(winners) / count * Decimal::from(100),
+                    average_pnl: net_pnl / count,
+                })
+            })
+            .collect()
+    }
+
+    /// Calculates the R-multiple metrics over the trades with a known initial risk. The rest,
+    /// such as trades saved before risk was recorded, are left out rather than counted as 0R.
+    fn calculate_r_metrics(&self, trades: &[Trade], report: &mut PerformanceReport) {
+        let r_multiples: Vec<Decimal> = trades.iter().filter_map(r_multiple).collect();
+        if r_multiples.is_empty() {
+            return;
+        }
+        let count = Decimal::from(r_multiples.len());
+        // Counted as in `calculate_profitability`: a break-even trade is a win.
+        let (wins, losses): (Vec<Decimal>, Vec<Decimal>) = r_multiples.iter().partition(|r| r.is_sign_positive());
+        let average = |rs: &[Decimal]| if rs.is_empty() { Decimal::ZERO } else { rs.iter().sum::<Decimal>() / Decimal::from(rs.len()) };
+        let win_rate = Decimal::from(wins.len()) / count;
+        let loss_rate = Decimal::from(losses.len()) / count;
+
+        report.average_r = Some(r_multiples.iter().sum::<Decimal>() / count);
+        report.expectancy_r = Some(win_rate * average(&wins) - loss_rate * average(&losses).abs());
+        for r in r_multiples {
+            report.r_distribution.record(r);
+        }
+    }
+
     /// Calculates maximum drawdown from the equity curve.
     fn calculate_drawdown(
         &self,
@@ -180,36 +323,76 @@ impl AnalyticsEngine {
             new_report.calmar_ratio = Some(total_return_pct / max_drawdown_pct);
         }
 
-        // --- FIX #2: Sharpe Ratio Annualization ---
-        let returns: Vec<Decimal> = equity_curve
-            .windows(2)
-            .map(|w| (w[1].1 - w[0].1) / w[0].1)
-            .collect();
+        new_report.sharpe_ratio = self.sharpe_ratio(equity_curve, interval)?;
 
-        if returns.len() < 2 {
-            new_report.sharpe_ratio = None;
+        Ok(())
+    }
+
+    /// Calculates how much of the capital was put to work: the gross exposure (notional over
+    /// equity) on average and at its peak, the share of points with a position open, the net
+    /// profit over the average notional deployed, and a Sharpe Ratio whose volatility weighs
+    /// each return by the exposure held through it, so that flat periods do not dilute it.
+    fn calculate_capital_efficiency(
+        &self,
+        equity_curve: &[(DateTime<Utc>, Decimal)],
+        exposure: &[Decimal],
+        interval: &str,
+        report: &mut PerformanceReport,
+    ) -> Result<(), AnalyticsError> {
+        if exposure.is_empty() {
             return Ok(());
         }
+        if exposure.len() != equity_curve.len() {
+            return Err(AnalyticsError::InternalError(format!(
+                "{} exposure points were given for an equity curve of {}",
+                exposure.len(),
+                equity_curve.len()
+            )));
+        }
 
-        let mean_return: Decimal = returns.iter().sum::<Decimal>() / Decimal::from(returns.len());
-        
-        let std_dev: Decimal = {
-            let variance = returns
-                .iter()
-                .map(|r| (*r - mean_return) * (*r - mean_return))
-                .sum::<Decimal>()
-                / Decimal::from(returns.len());
-            variance.sqrt().ok_or_else(|| AnalyticsError::InternalError("Could not calculate standard deviation.".to_string()))?
-        };
+        let hundred = Decimal::from(100);
+        let points = Decimal::from(exposure.len());
+        // The gross exposure at each point; nothing is exposed once the equity is gone.
+        let ratios: Vec<Decimal> = equity_curve
+            .iter()
+            .zip(exposure)
+            .map(|((_, equity), notional)| if *equity > Decimal::ZERO { *notional / *equity } else { Decimal::ZERO })
+            .collect();
+
+        report.average_exposure_pct = Some(ratios.iter().sum::<Decimal>() / points * hundred);
+        report.peak_exposure_pct = ratios.iter().max().map(|peak| *peak * hundred);
+        let in_market = exposure.iter().filter(|notional| **notional > Decimal::ZERO).count();
+        report.time_in_market_pct = Some(Decimal::from(in_market) / points * hundred);
 
+        // A backtest pays the full notional of a position, so the margin deployed is its notional.
+        let average_notional = exposure.iter().sum::<Decimal>() / points;
+        if average_notional > Decimal::ZERO {
+            report.return_on_margin_pct = Some(report.total_net_profit / average_notional * hundred);
+        }
+
+        // Each return is weighted by the exposure held from the point before it.
+        let returns: Vec<(Decimal, Decimal)> = equity_curve
+            .windows(2)
+            .zip(&ratios)
+            .filter(|(w, _)| !w[0].1.is_zero())
+            .map(|(w, weight)| ((w[1].1 - w[0].1) / w[0].1, *weight))
+            .collect();
+        let total_weight: Decimal = returns.iter().map(|(_, weight)| *weight).sum();
+        if returns.len() < 2 || total_weight.is_zero() {
+            return Ok(());
+        }
+        let mean_return = returns.iter().map(|(r, _)| *r).sum::<Decimal>() / Decimal::from(returns.len());
+        let variance = returns
+            .iter()
+            .map(|(r, weight)| *weight * (*r - mean_return) * (*r - mean_return))
+            .sum::<Decimal>()
+            / total_weight;
+        let std_dev = variance.sqrt().ok_or_else(|| AnalyticsError::InternalError("Could not calculate standard deviation.".to_string()))?;
         if std_dev > Decimal::ZERO {
             let periods_in_year = self.get_periods_in_year(interval)?;
             let annualization_factor = Decimal::from(periods_in_year).sqrt().ok_or_else(|| AnalyticsError::InternalError("Could not get annualization factor.".to_string()))?;
-            
-            let sharpe_ratio = (mean_return / std_dev) * annualization_factor;
-            new_report.sharpe_ratio = Some(sharpe_ratio);
+            report.exposure_adjusted_sharpe = Some((mean_return / std_dev) * annualization_factor);
         }
-        // --- END FIX #2 ---
 
         Ok(())
     }
@@ -237,16 +420,23 @@ impl AnalyticsEngine {
     
     // Helper function to determine the annualization factor for the Sharpe Ratio.
     fn get_periods_in_year(&self, interval: &str) -> Result<u32, AnalyticsError> {
-        // This is a simplified mapping. A more robust solution might parse the interval string.
         // Assuming 252 trading days in a year for crypto for simplicity.
-        match interval {
-            "1m" => Ok(252 * 24 * 60),
-            "5m" => Ok(252 * 24 * 12),
-            "15m" => Ok(252 * 24 * 4),
-            "1h" => Ok(252 * 24),
-            "4h" => Ok(252 * 6),
-            "1d" => Ok(252),
-            _ => Err(AnalyticsError::InternalError(format!("Unsupported interval for Sharpe Ratio annualization: {}", interval))),
-        }
+        let interval: Interval = interval
+            .parse()
+            .map_err(|e| AnalyticsError::InternalError(format!("Cannot annualize the Sharpe Ratio: {}", e)))?;
+        Ok((interval.bars_per(Period::Day) * TRADING_DAYS_PER_YEAR).round() as u32)
+    }
+}
+
+/// A trade's price PnL over its initial risk. None without a positive initial risk.
+fn r_multiple(trade: &Trade) -> Option<Decimal> {
+    trade.initial_risk.filter(|risk| *risk > Decimal::ZERO).map(|risk| trade_pnl(trade) / risk)
+}
+
+/// The price PnL of a trade, by the side it was entered on.
+pub fn trade_pnl(trade: &Trade) -> Decimal {
+    match trade.entry_execution.side {
+        OrderSide::Buy => (trade.exit_execution.price - trade.entry_execution.price) * trade.exit_execution.quantity,
+        OrderSide::Sell => (trade.entry_execution.price - trade.exit_execution.price) * trade.exit_execution.quantity,
     }
-}
\ No newline at end of file
+}
@@ -16,13 +16,16 @@
 //! - `AnalyticsEngine`: The main struct that contains the calculation logic.
 //! - `PerformanceReport`: The standardized struct that holds all 17+ performance metrics.
 //! - `AnalyticsError`: The specific error types that can be returned from this crate.
+//! - `downsample`: Thinning of equity curves for storage, preserving the maximum drawdown,
+//!   and aggregation of klines into wider candles for charting.
 
 // Declare the modules that constitute this crate.
+pub mod downsample;
 pub mod engine;
 pub mod error;
 pub mod report;
 
 // Re-export the key components to create a clean, public-facing API.
-pub use engine::AnalyticsEngine;
+pub use engine::{trade_pnl, AnalyticsEngine};
 pub use error::AnalyticsError;
-pub use report::PerformanceReport;
\ No newline at end of file
+pub use report::{ExitStats, PerformanceReport, RDistribution};
\ No newline at end of file
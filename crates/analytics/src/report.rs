@@ -1,4 +1,5 @@
 use chrono::Duration;
+use core_types::CloseReason;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::time::Duration as StdDuration;
@@ -54,10 +55,121 @@ pub struct PerformanceReport {
     pub average_win: Decimal,
     pub average_loss: Decimal,
     pub payoff_ratio: Option<Decimal>, // Option<> because avg_loss can be 0
+    /// The longest run of losing trades in a row.
+    #[serde(default)]
+    pub max_consecutive_losses: usize,
 
     // IV. Time-Based Metrics
     #[serde(with = "duration_serde")]
     pub average_holding_period: Duration,
+
+    // V. Trading Costs
+    /// Fees paid on fills that rested on the book.
+    #[serde(default)]
+    pub maker_fees_paid: Decimal,
+    /// Fees paid on fills that took liquidity.
+    #[serde(default)]
+    pub taker_fees_paid: Decimal,
+    /// What crossing the bid-ask spread cost, already included in the fill prices and so in
+    /// the profit figures above.
+    #[serde(default)]
+    pub total_spread_cost: Decimal,
+    /// What stops lost by filling beyond their price after the market gapped through them,
+    /// summed over the stopped-out trades: the difference between each stop price and the
+    /// open it filled at, times the quantity. Spread and slippage come on top. Only the
+    /// backtester's gap-aware stop fills produce it.
+    #[serde(default)]
+    pub gap_slippage: Decimal,
+
+    // VI. Exit Breakdown
+    /// Per-exit-type statistics, one entry for each close reason that closed at least one
+    /// trade, in the order the reasons are declared.
+    #[serde(default)]
+    pub exit_breakdown: Vec<ExitStats>,
+
+    // VII. Risk Multiples
+    // Over the trades with a known initial risk; an R-multiple is a trade's PnL over the risk
+    // it took at entry. Unset when no trade has one.
+    /// The mean R-multiple.
+    #[serde(default)]
+    pub average_r: Option<Decimal>,
+    /// Win rate × average winning R − loss rate × average losing R, with a break-even trade
+    /// counted as a win. It comes to the same value as `average_r`, reached the way
+    /// expectancy is usually quoted.
+    #[serde(default)]
+    pub expectancy_r: Option<Decimal>,
+    /// How many trades fell in each band of R-multiples.
+    #[serde(default)]
+    pub r_distribution: RDistribution,
+
+    // VIII. Capital Efficiency
+    // From the gross notional of the open positions at each point of the equity curve; the
+    // exposure at a point is that notional over the equity. Unset when the exposure was not
+    // recorded.
+    /// The mean exposure over every point, flat ones included, in percent.
+    #[serde(default)]
+    pub average_exposure_pct: Option<Decimal>,
+    /// The highest exposure at any point, in percent.
+    #[serde(default)]
+    pub peak_exposure_pct: Option<Decimal>,
+    /// The share of the points at which a position was open, in percent.
+    #[serde(default)]
+    pub time_in_market_pct: Option<Decimal>,
+    /// The net profit over the mean notional deployed across every point, in percent. A
+    /// backtest pays the full notional of its positions, so the margin it deploys is their
+    /// notional. Unset when nothing was deployed.
+    #[serde(default)]
+    pub return_on_margin_pct: Option<Decimal>,
+    /// The Sharpe ratio with each return's deviation weighted by the exposure held over it, so
+    /// the flat bars of a strategy that is rarely in the market do not dilute its volatility.
+    #[serde(default)]
+    pub exposure_adjusted_sharpe: Option<Decimal>,
+}
+
+/// Trade counts by R-multiple band. Each band includes its lower bound: a trade stopped out at
+/// exactly its stop, -1R, counts towards `minus_one_to_zero`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RDistribution {
+    /// Below -1R, e.g. a stop filled beyond its price.
+    pub below_minus_one: usize,
+    pub minus_one_to_zero: usize,
+    pub zero_to_one: usize,
+    pub one_to_two: usize,
+    /// 2R and above.
+    pub two_and_above: usize,
+}
+
+impl RDistribution {
+    /// Counts `r_multiple` in its band.
+    pub fn record(&mut self, r_multiple: Decimal) {
+        let band = if r_multiple < -Decimal::ONE {
+            &mut self.below_minus_one
+        } else if r_multiple < Decimal::ZERO {
+            &mut self.minus_one_to_zero
+        } else if r_multiple < Decimal::ONE {
+            &mut self.zero_to_one
+        } else if r_multiple < Decimal::TWO {
+            &mut self.one_to_two
+        } else {
+            &mut self.two_and_above
+        };
+        *band += 1;
+    }
+
+    /// The number of trades counted.
+    pub fn total(&self) -> usize {
+        self.below_minus_one + self.minus_one_to_zero + self.zero_to_one + self.one_to_two + self.two_and_above
+    }
+}
+
+/// The trades closed for one reason (strategy signal, stop-loss, time limit, ...).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExitStats {
+    pub close_reason: CloseReason,
+    pub trades: usize,
+    pub net_pnl: Decimal,
+    pub win_rate_pct: Decimal,
+    pub average_pnl: Decimal,
 }
 
 impl PerformanceReport {
@@ -81,7 +193,21 @@ impl PerformanceReport {
             average_win: Decimal::ZERO,
             average_loss: Decimal::ZERO,
             payoff_ratio: None,
+            max_consecutive_losses: 0,
             average_holding_period: Duration::zero(),
+            maker_fees_paid: Decimal::ZERO,
+            taker_fees_paid: Decimal::ZERO,
+            total_spread_cost: Decimal::ZERO,
+            gap_slippage: Decimal::ZERO,
+            exit_breakdown: Vec::new(),
+            average_r: None,
+            expectancy_r: None,
+            r_distribution: RDistribution::default(),
+            average_exposure_pct: None,
+            peak_exposure_pct: None,
+            time_in_market_pct: None,
+            return_on_margin_pct: None,
+            exposure_adjusted_sharpe: None,
         }
     }
 }
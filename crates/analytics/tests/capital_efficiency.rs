@@ -0,0 +1,104 @@
+//! Checks the capital efficiency metrics: how much of the equity a run kept in the market.
+
+use analytics::AnalyticsEngine;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn at(hour: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+}
+
+fn execution(side: OrderSide, price: Decimal, hour: i64) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        quantity: dec!(100),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: at(hour),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// A long of 100 units entered at 100 and exited at 105: 500 of profit on 10000 of capital.
+fn trade() -> Trade {
+    Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        entry_execution: execution(OrderSide::Buy, dec!(100), 0),
+        exit_execution: execution(OrderSide::Sell, dec!(105), 5),
+        close_reason: CloseReason::Signal,
+        initial_risk: None,
+    }
+}
+
+/// Ten hourly points: the equity climbs by 100 an hour while the whole capital is deployed
+/// over the first five, then stays flat at 10500 with nothing deployed.
+fn run() -> (Vec<(DateTime<Utc>, Decimal)>, Vec<Decimal>) {
+    (0..10)
+        .map(|hour| {
+            let equity = dec!(10000) + Decimal::from(hour.min(5) * 100);
+            let notional = if hour < 5 { dec!(10000) } else { Decimal::ZERO };
+            ((at(hour), equity), notional)
+        })
+        .unzip()
+}
+
+#[test]
+fn a_run_in_the_market_half_the_time_earns_double_on_its_deployed_margin() {
+    let (equity_curve, exposure) = run();
+
+    let report = AnalyticsEngine::new().calculate(&[trade()], &equity_curve, &exposure, dec!(10000), "1h").unwrap();
+
+    assert_eq!(report.total_return_pct, dec!(5));
+    assert_eq!(report.time_in_market_pct, Some(dec!(50)));
+    assert_eq!(report.return_on_margin_pct, Some(dec!(10)));
+    assert_eq!(report.return_on_margin_pct, Some(report.total_return_pct * dec!(2)));
+    // 10000 over equities of 10000 to 10400, then nothing.
+    assert_eq!(report.peak_exposure_pct, Some(dec!(100)));
+    assert_eq!(report.average_exposure_pct.map(|pct| pct.round_dp(2)), Some(dec!(49.03)));
+    // The flat hours' zero returns no longer count as calm.
+    let sharpe = report.sharpe_ratio.unwrap();
+    assert!(report.exposure_adjusted_sharpe.unwrap() > sharpe, "{:?} vs {}", report.exposure_adjusted_sharpe, sharpe);
+}
+
+#[test]
+fn without_recorded_exposure_the_metrics_are_unset() {
+    let (equity_curve, _) = run();
+
+    let report = AnalyticsEngine::new().calculate(&[trade()], &equity_curve, &[], dec!(10000), "1h").unwrap();
+
+    assert_eq!(
+        (report.average_exposure_pct, report.peak_exposure_pct, report.time_in_market_pct),
+        (None, None, None)
+    );
+    assert_eq!((report.return_on_margin_pct, report.exposure_adjusted_sharpe), (None, None));
+}
+
+#[test]
+fn a_run_that_never_deploys_capital_has_no_return_on_margin() {
+    let (equity_curve, exposure) = run();
+    let flat = vec![Decimal::ZERO; exposure.len()];
+
+    let report = AnalyticsEngine::new().calculate(&[trade()], &equity_curve, &flat, dec!(10000), "1h").unwrap();
+
+    assert_eq!(report.time_in_market_pct, Some(Decimal::ZERO));
+    assert_eq!(report.return_on_margin_pct, None);
+    assert_eq!(report.exposure_adjusted_sharpe, None);
+}
+
+#[test]
+fn exposure_must_cover_the_equity_curve() {
+    let (equity_curve, exposure) = run();
+
+    assert!(AnalyticsEngine::new().calculate(&[trade()], &equity_curve, &exposure[1..], dec!(10000), "1h").is_err());
+}
@@ -0,0 +1,152 @@
+use analytics::downsample::{aggregate_klines, every_nth, kline_bucket_size, lttb, max_drawdown_points};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::Kline;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+type Curve = Vec<(DateTime<Utc>, Decimal)>;
+
+/// A wavy, rising curve of `len` points with a sharp one-bar crash at `crash_at`.
+fn curve(len: usize, crash_at: usize) -> Curve {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..len)
+        .map(|i| {
+            let wave = ((i as f64) / 25.0).sin() * 300.0;
+            let mut equity = 10_000.0 + i as f64 * 0.5 + wave;
+            if i == crash_at {
+                equity -= 4_000.0;
+            }
+            let equity = Decimal::from_f64_retain(equity).unwrap().round_dp(2);
+            (start + Duration::minutes(i as i64), equity)
+        })
+        .collect()
+}
+
+fn max_drawdown(curve: &[(DateTime<Utc>, Decimal)]) -> Decimal {
+    max_drawdown_points(curve).map_or(Decimal::ZERO, |(peak, trough)| curve[peak].1 - curve[trough].1)
+}
+
+fn assert_keeps_drawdown(original: &Curve, thinned: &Curve) {
+    let (peak, trough) = max_drawdown_points(original).unwrap();
+    assert!(thinned.contains(&original[peak]), "peak {:?} dropped", original[peak]);
+    assert!(thinned.contains(&original[trough]), "trough {:?} dropped", original[trough]);
+    assert_eq!(max_drawdown(thinned), max_drawdown(original));
+    assert_eq!(thinned.first(), original.first());
+    assert_eq!(thinned.last(), original.last());
+    assert!(thinned.windows(2).all(|pair| pair[0].0 < pair[1].0), "points out of order");
+}
+
+#[test]
+fn max_drawdown_points_finds_the_deepest_fall() {
+    let original = curve(10_000, 6_543);
+    let (peak, trough) = max_drawdown_points(&original).unwrap();
+    assert_eq!(trough, 6_543);
+    assert!(peak < trough);
+    assert!(original[..=trough].iter().all(|&(_, equity)| equity <= original[peak].1));
+
+    let rising: Curve = original.iter().enumerate().map(|(i, &(t, _))| (t, Decimal::from(i))).collect();
+    assert_eq!(max_drawdown_points(&rising), None);
+}
+
+#[test]
+fn every_nth_keeps_the_drawdown_trough_off_the_grid() {
+    let original = curve(10_000, 6_543);
+    let thinned = every_nth(&original, 100);
+
+    assert_keeps_drawdown(&original, &thinned);
+    assert!(thinned.len() <= 10_000 / 100 + 3);
+    assert_eq!(every_nth(&original, 1), original);
+}
+
+#[test]
+fn lttb_keeps_the_drawdown_trough_and_about_the_requested_points() {
+    for crash_at in [1, 777, 6_543, 9_998] {
+        let original = curve(10_000, crash_at);
+        let thinned = lttb(&original, 500);
+
+        assert_keeps_drawdown(&original, &thinned);
+        assert!((500..=502).contains(&thinned.len()), "{} points", thinned.len());
+    }
+}
+
+#[test]
+fn short_curves_are_left_alone() {
+    let original = curve(50, 20);
+    assert_eq!(lttb(&original, 500), original);
+    assert!(lttb(&[], 500).is_empty());
+    assert!(every_nth(&[], 10).is_empty());
+}
+
+fn kline(i: i64, open: Decimal, high: Decimal, low: Decimal, close: Decimal, volume: Decimal) -> Kline {
+    let open_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(i);
+    Kline {
+        open_time,
+        open,
+        high,
+        low,
+        close,
+        volume,
+        close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+        interval: "1h".to_string(),
+    }
+}
+
+#[test]
+fn aggregated_candles_span_their_buckets() {
+    let klines = vec![
+        kline(0, dec!(100), dec!(105), dec!(99), dec!(104), dec!(1)),
+        kline(1, dec!(104), dec!(110), dec!(103), dec!(108), dec!(2)),
+        kline(2, dec!(108), dec!(109), dec!(95), dec!(96), dec!(3)),
+        kline(3, dec!(96), dec!(101), dec!(94), dec!(100), dec!(4)),
+        kline(4, dec!(100), dec!(102), dec!(98), dec!(99), dec!(5)),
+    ];
+
+    let candles = aggregate_klines(&klines, 3);
+    assert_eq!(candles.len(), 2);
+
+    let first = &candles[0];
+    assert_eq!(first.open_time, klines[0].open_time);
+    assert_eq!(first.close_time, klines[2].close_time);
+    assert_eq!(first.open, dec!(100));
+    assert_eq!(first.high, dec!(110));
+    assert_eq!(first.low, dec!(95));
+    assert_eq!(first.close, dec!(96));
+    assert_eq!(first.volume, dec!(6));
+
+    // The last bucket holds the two remaining klines.
+    let last = &candles[1];
+    assert_eq!(last.open_time, klines[3].open_time);
+    assert_eq!(last.close_time, klines[4].close_time);
+    assert_eq!((last.open, last.high, last.low, last.close, last.volume), (dec!(96), dec!(102), dec!(94), dec!(99), dec!(9)));
+}
+
+#[test]
+fn aggregation_keeps_the_overall_range_and_volume() {
+    let klines: Vec<Kline> = (0..1000)
+        .map(|i| {
+            let mid = Decimal::from(100 + (i * 37) % 50);
+            kline(i, mid, mid + Decimal::from(i % 7), mid - Decimal::from(i % 5), mid, Decimal::ONE)
+        })
+        .collect();
+
+    let bucket_size = kline_bucket_size(klines.len(), 300);
+    assert_eq!(bucket_size, 4);
+    let candles = aggregate_klines(&klines, bucket_size);
+    assert_eq!(candles.len(), 250);
+
+    let high = |ks: &[Kline]| ks.iter().map(|k| k.high).max().unwrap();
+    let low = |ks: &[Kline]| ks.iter().map(|k| k.low).min().unwrap();
+    assert_eq!(high(&candles), high(&klines));
+    assert_eq!(low(&candles), low(&klines));
+    assert_eq!(candles.iter().map(|k| k.volume).sum::<Decimal>(), dec!(1000));
+    assert_eq!(candles.last().unwrap().close, klines.last().unwrap().close);
+}
+
+#[test]
+fn klines_within_the_limit_are_returned_as_is() {
+    let klines: Vec<Kline> = (0..10).map(|i| kline(i, dec!(1), dec!(2), dec!(0.5), dec!(1.5), dec!(1))).collect();
+    assert_eq!(kline_bucket_size(klines.len(), 10), 1);
+    assert_eq!(kline_bucket_size(klines.len(), 0), 10);
+    assert_eq!(aggregate_klines(&klines, 1), klines);
+    assert!(aggregate_klines(&[], 5).is_empty());
+}
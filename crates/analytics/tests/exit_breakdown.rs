@@ -0,0 +1,117 @@
+//! Checks the performance report's breakdown of trades by close reason.
+
+use analytics::{AnalyticsEngine, ExitStats};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn at(hour: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+}
+
+fn execution(side: OrderSide, price: Decimal, hour: i64) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        quantity: dec!(1),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: at(hour),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// One trade per entry of `exits`, entered at 100 on `side` and closed for the given P&L.
+fn trades(side: OrderSide, exits: &[(CloseReason, i64)]) -> Vec<Trade> {
+    exits
+        .iter()
+        .enumerate()
+        .map(|(i, &(close_reason, pnl))| {
+            let hour = 2 * i as i64;
+            let exit_price = match side {
+                OrderSide::Buy => dec!(100) + Decimal::from(pnl),
+                OrderSide::Sell => dec!(100) - Decimal::from(pnl),
+            };
+            Trade {
+                trade_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                entry_execution: execution(side, dec!(100), hour),
+                exit_execution: execution(side.opposite(), exit_price, hour + 1),
+                close_reason,
+                initial_risk: None,
+            }
+        })
+        .collect()
+}
+
+fn breakdown(trades: &[Trade]) -> Vec<ExitStats> {
+    let equity_curve = [(at(0), dec!(10000)), (at(24), dec!(10000))];
+    AnalyticsEngine::new().calculate(trades, &equity_curve, &[], dec!(10000), "1h").unwrap().exit_breakdown
+}
+
+#[test]
+fn trades_are_broken_down_by_close_reason() {
+    let mut trades = trades(
+        OrderSide::Buy,
+        &[
+            (CloseReason::Signal, 10),
+            (CloseReason::StopLoss, -8),
+            (CloseReason::TimeLimit, 3),
+            (CloseReason::Signal, 6),
+            (CloseReason::StopLoss, -12),
+            (CloseReason::TimeLimit, -1),
+        ],
+    );
+    // A losing short closed by its strategy.
+    trades.extend(self::trades(OrderSide::Sell, &[(CloseReason::Signal, -4)]));
+
+    let breakdown = breakdown(&trades);
+
+    // Reasons in declaration order; no trade hit a take-profit, so it has no entry.
+    let reasons: Vec<_> = breakdown.iter().map(|exit| exit.close_reason).collect();
+    assert_eq!(reasons, [CloseReason::Signal, CloseReason::StopLoss, CloseReason::TimeLimit]);
+
+    let signal = &breakdown[0];
+    assert_eq!(signal.trades, 3);
+    assert_eq!(signal.net_pnl, dec!(12));
+    assert_eq!(signal.win_rate_pct.round_dp(2), dec!(66.67));
+    assert_eq!(signal.average_pnl, dec!(4));
+
+    let stop_loss = &breakdown[1];
+    assert_eq!(stop_loss.trades, 2);
+    assert_eq!(stop_loss.net_pnl, dec!(-20));
+    assert_eq!(stop_loss.win_rate_pct, dec!(0));
+    assert_eq!(stop_loss.average_pnl, dec!(-10));
+
+    let time_limit = &breakdown[2];
+    assert_eq!(time_limit.trades, 2);
+    assert_eq!(time_limit.net_pnl, dec!(2));
+    assert_eq!(time_limit.win_rate_pct, dec!(50));
+    assert_eq!(time_limit.average_pnl, dec!(1));
+}
+
+#[test]
+fn the_breakdown_adds_up_to_the_report_totals() {
+    let trades = trades(OrderSide::Buy, &[(CloseReason::Signal, 5), (CloseReason::StopLoss, -3), (CloseReason::TakeProfit, 9)]);
+    let equity_curve = [(at(0), dec!(10000)), (at(24), dec!(10011))];
+
+    let report = AnalyticsEngine::new().calculate(&trades, &equity_curve, &[], dec!(10000), "1h").unwrap();
+
+    let net_pnl: Decimal = report.exit_breakdown.iter().map(|exit| exit.net_pnl).sum();
+    let count: usize = report.exit_breakdown.iter().map(|exit| exit.trades).sum();
+    assert_eq!(net_pnl, report.total_net_profit);
+    assert_eq!(count, report.total_trades);
+}
+
+#[test]
+fn a_run_without_trades_has_no_breakdown() {
+    assert!(breakdown(&[]).is_empty());
+}
@@ -0,0 +1,93 @@
+//! Checks the R-multiple metrics: each trade's PnL in units of the risk it took at entry.
+
+use analytics::{AnalyticsEngine, PerformanceReport, RDistribution};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn at(hour: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+}
+
+fn execution(side: OrderSide, price: Decimal, hour: i64) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        quantity: dec!(2),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: at(hour),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// A trade of two units entered at 100 on `side`, exited at `exit_price`, with `initial_risk`.
+fn trade(side: OrderSide, exit_price: Decimal, initial_risk: Option<Decimal>) -> Trade {
+    Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        entry_execution: execution(side, dec!(100), 0),
+        exit_execution: execution(side.opposite(), exit_price, 1),
+        close_reason: CloseReason::Signal,
+        initial_risk,
+    }
+}
+
+/// Longs with a stop at 95, so 10 of risk for the two units, exited at `exit_price`.
+fn long(exit_price: Decimal) -> Trade {
+    trade(OrderSide::Buy, exit_price, Some(dec!(10)))
+}
+
+fn report(trades: &[Trade]) -> PerformanceReport {
+    let equity_curve = [(at(0), dec!(10000)), (at(1), dec!(10000))];
+    AnalyticsEngine::new().calculate(trades, &equity_curve, &[], dec!(10000), "1h").unwrap()
+}
+
+#[test]
+fn trades_are_measured_in_units_of_their_initial_risk() {
+    let trades = [
+        long(dec!(115)),                                       // 3R
+        long(dec!(110)),                                       // 2R, the lower bound of its band
+        long(dec!(107.5)),                                     // 1.5R
+        long(dec!(100)),                                       // 0R
+        trade(OrderSide::Sell, dec!(97.5), Some(dec!(10))),   // A short, 0.5R
+        long(dec!(95)),                                        // -1R, stopped out at the stop
+        long(dec!(98)),                                        // -0.4R
+        long(dec!(92.5)),                                      // -1.5R, filled beyond the stop
+        trade(OrderSide::Buy, dec!(125), None),               // 25 of profit at an unknown risk
+    ];
+
+    let r_multiples = AnalyticsEngine::new().r_multiples(&trades);
+    let expected = [dec!(3), dec!(2), dec!(1.5), dec!(0), dec!(0.5), dec!(-1), dec!(-0.4), dec!(-1.5)];
+    assert_eq!(r_multiples, expected.into_iter().map(Some).chain([None]).collect::<Vec<_>>());
+
+    let report = report(&trades);
+    assert_eq!(
+        report.r_distribution,
+        RDistribution { below_minus_one: 1, minus_one_to_zero: 2, zero_to_one: 2, one_to_two: 1, two_and_above: 2 }
+    );
+    assert_eq!(report.r_distribution.total(), 8);
+    // 4.1R over the eight trades of known risk.
+    assert_eq!(report.average_r, Some(dec!(0.5125)));
+    // Five wins averaging 1.4R and three losses averaging 2.9R / 3.
+    assert_eq!(report.expectancy_r, Some(dec!(0.5125)));
+    // The trade of unknown risk still counts towards the PnL metrics.
+    assert_eq!(report.total_trades, 9);
+}
+
+#[test]
+fn trades_of_unknown_risk_leave_the_r_metrics_unset() {
+    let report = report(&[trade(OrderSide::Buy, dec!(110), None), trade(OrderSide::Buy, dec!(90), Some(Decimal::ZERO))]);
+
+    assert_eq!(report.total_trades, 2);
+    assert_eq!((report.average_r, report.expectancy_r), (None, None));
+    assert_eq!(report.r_distribution, RDistribution::default());
+}
@@ -0,0 +1,70 @@
+//! Checks the trade-level statistics of the performance report.
+
+use analytics::AnalyticsEngine;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn at(hour: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(hour)
+}
+
+fn execution(side: OrderSide, price: Decimal, hour: i64) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        quantity: dec!(1),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: at(hour),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// One long trade per entry of `pnls`, each held for an hour, closed at 100 + its P&L.
+fn trades(pnls: &[i64]) -> Vec<Trade> {
+    pnls.iter()
+        .enumerate()
+        .map(|(i, &pnl)| {
+            let hour = 2 * i as i64;
+            Trade {
+                trade_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                entry_execution: execution(OrderSide::Buy, dec!(100), hour),
+                exit_execution: execution(OrderSide::Sell, dec!(100) + Decimal::from(pnl), hour + 1),
+                close_reason: CloseReason::Signal,
+                initial_risk: None,
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn the_longest_losing_streak_is_reported() {
+    // Losing streaks of 2, then 3, then 1.
+    let trades = trades(&[5, -1, -2, 4, -1, -1, -3, 6, -1]);
+    let equity_curve = [(at(0), dec!(10000)), (at(20), dec!(10006))];
+
+    let report = AnalyticsEngine::new().calculate(&trades, &equity_curve, &[], dec!(10000), "1h").unwrap();
+
+    assert_eq!(report.losing_trades, 6);
+    assert_eq!(report.max_consecutive_losses, 3);
+}
+
+#[test]
+fn a_run_without_losses_has_no_losing_streak() {
+    let trades = trades(&[1, 2, 3]);
+    let equity_curve = [(at(0), dec!(10000)), (at(6), dec!(10006))];
+
+    let report = AnalyticsEngine::new().calculate(&trades, &equity_curve, &[], dec!(10000), "1h").unwrap();
+
+    assert_eq!(report.max_consecutive_losses, 0);
+}
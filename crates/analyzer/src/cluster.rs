@@ -0,0 +1,230 @@
+//! Grouping a job's best parameter sets into distinct regions of the parameter space.
+//!
+//! The top of a ranking is usually a crowd of near-identical neighbours of one peak, which
+//! hides the next-best region entirely. Each parameter is scaled to [0, 1] over the job's grid,
+//! and the best runs are clustered by density (DBSCAN): two runs are neighbours if they are at
+//! most one grid step apart in every parameter, and runs with enough neighbours grow a
+//! cluster through each other. Best runs with too few neighbours belong to no cluster.
+
+use crate::RankedReport;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use uuid::Uuid;
+
+/// Tunes which runs are clustered and how dense a cluster must be.
+#[derive(Debug, Clone)]
+pub struct ClusterOptions {
+    /// The share of the ranked runs that are clustered, best first.
+    pub top_fraction: f64,
+    /// How many grid steps apart two runs may be, in every parameter, to be neighbours.
+    pub neighbour_steps: f64,
+    /// The neighbours a run needs, itself included, to start or extend a cluster.
+    pub min_points: usize,
+}
+
+impl Default for ClusterOptions {
+    fn default() -> Self {
+        Self { top_fraction: 0.25, neighbour_steps: 1.0, min_points: 2 }
+    }
+}
+
+/// One numeric parameter of a job's grid.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Dimension {
+    pub name: String,
+    pub min: f64,
+    pub max: f64,
+    /// The smallest gap between two of the grid's values, scaled to [0, 1]. Infinite if the
+    /// parameter never varies.
+    pub step: f64,
+}
+
+/// The numeric parameters of a job's grid, along which runs are clustered.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParameterSpace {
+    pub dimensions: Vec<Dimension>,
+    /// The parameters left out because some run lacks them or they are not numbers.
+    pub excluded: Vec<String>,
+}
+
+impl ParameterSpace {
+    /// Finds the span and step of every parameter over `grid`, the parameters of each of a
+    /// job's runs. Decimal parameters are stored as strings, and count as numbers.
+    pub fn from_grid<'a>(grid: impl IntoIterator<Item = &'a Value>) -> Self {
+        let runs: Vec<&Value> = grid.into_iter().collect();
+        let names: BTreeSet<&String> = runs.iter().filter_map(|parameters| parameters.as_object()).flat_map(|fields| fields.keys()).collect();
+
+        let mut space = Self::default();
+        for name in names {
+            let values: Option<Vec<f64>> = runs.iter().map(|parameters| parameters.get(name).and_then(as_number)).collect();
+            let Some(mut values) = values else {
+                tracing::warn!(parameter = %name, "Parameter is missing or not a number in some runs; clustering without it.");
+                space.excluded.push(name.clone());
+                continue;
+            };
+            values.sort_by(f64::total_cmp);
+            values.dedup();
+            let (min, max) = (values[0], values[values.len() - 1]);
+            let step = values
+                .windows(2)
+                .map(|pair| (pair[1] - pair[0]) / (max - min))
+                .fold(f64::INFINITY, f64::min);
+            space.dimensions.push(Dimension { name: name.clone(), min, max, step });
+        }
+        space
+    }
+
+    /// `parameters` scaled to [0, 1] along each dimension. None if a dimension is missing.
+    fn scale(&self, parameters: &Value) -> Option<Vec<f64>> {
+        self.dimensions
+            .iter()
+            .map(|dimension| {
+                let value = parameters.get(&dimension.name).and_then(as_number)?;
+                let span = dimension.max - dimension.min;
+                Some(if span > 0.0 { (value - dimension.min) / span } else { 0.0 })
+            })
+            .collect()
+    }
+}
+
+/// A parameter as a number: JSON numbers, and strings that parse as one.
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(number) => number.as_f64(),
+        Value::String(text) => text.parse().ok(),
+        _ => None,
+    }
+}
+
+/// A region of good parameter sets.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ClusterSummary {
+    /// The cluster's number. Clusters are numbered from 0 in the order of their best run.
+    pub cluster: usize,
+    /// The cluster's runs, in ranking order.
+    pub run_ids: Vec<Uuid>,
+    pub best_run: Uuid,
+    pub best_score: Decimal,
+    pub mean_score: Decimal,
+    /// The mean of each clustered parameter over the cluster's runs, in the parameter's own units.
+    pub centroid: BTreeMap<String, f64>,
+}
+
+impl ClusterSummary {
+    pub fn members(&self) -> usize {
+        self.run_ids.len()
+    }
+}
+
+/// The clusters among a job's best runs.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Clustering {
+    pub space: ParameterSpace,
+    pub clusters: Vec<ClusterSummary>,
+    /// The cluster of each run that is in one.
+    pub assignments: BTreeMap<Uuid, usize>,
+}
+
+impl Clustering {
+    /// The cluster `run_id` belongs to, if any.
+    pub fn cluster_of(&self, run_id: Uuid) -> Option<usize> {
+        self.assignments.get(&run_id).copied()
+    }
+}
+
+/// Clusters the best of `ranked`, which must be in ranking order, in `space`.
+pub fn cluster_runs(space: ParameterSpace, ranked: &[RankedReport], options: &ClusterOptions) -> Clustering {
+    let top = ((ranked.len() as f64 * options.top_fraction).ceil() as usize).clamp(ranked.len().min(1), ranked.len());
+    let points: Vec<(&RankedReport, Vec<f64>)> = ranked[..top]
+        .iter()
+        .filter_map(|run| space.scale(&run.parameters).map(|point| (run, point)))
+        .collect();
+
+    let neighbours = |i: usize| -> Vec<usize> {
+        (0..points.len())
+            .filter(|&j| {
+                points[i].1.iter().zip(&points[j].1).zip(&space.dimensions).all(|((a, b), dimension)| {
+                    // A little slack, so runs exactly one step apart are neighbours despite rounding.
+                    (a - b).abs() <= dimension.step * options.neighbour_steps * (1.0 + 1e-9)
+                })
+            })
+            .collect()
+    };
+
+    // DBSCAN, starting from the best unvisited run each time.
+    let mut labels: Vec<Option<usize>> = vec![None; points.len()];
+    let mut visited = vec![false; points.len()];
+    let mut cluster_count = 0;
+    for start in 0..points.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let around = neighbours(start);
+        if around.len() < options.min_points {
+            continue;
+        }
+        let cluster = cluster_count;
+        cluster_count += 1;
+        labels[start] = Some(cluster);
+        let mut queue: VecDeque<usize> = around.into();
+        while let Some(next) = queue.pop_front() {
+            labels[next].get_or_insert(cluster);
+            if visited[next] {
+                continue;
+            }
+            visited[next] = true;
+            let around = neighbours(next);
+            if around.len() >= options.min_points {
+                queue.extend(around);
+            }
+        }
+    }
+
+    // A run that was not dense enough to start a cluster may still join a later one, so
+    // the clusters are renumbered in the order of their best run.
+    let mut order: Vec<usize> = Vec::new();
+    for label in labels.iter().flatten() {
+        if !order.contains(label) {
+            order.push(*label);
+        }
+    }
+    let clusters: Vec<ClusterSummary> = order
+        .iter()
+        .enumerate()
+        .map(|(number, &label)| {
+            let members: Vec<&(&RankedReport, Vec<f64>)> = points.iter().zip(&labels).filter(|(_, l)| **l == Some(label)).map(|(point, _)| point).collect();
+            summarize(number, &space, &members)
+        })
+        .collect();
+    let assignments = clusters
+        .iter()
+        .flat_map(|summary| summary.run_ids.iter().map(move |run_id| (*run_id, summary.cluster)))
+        .collect();
+
+    Clustering { space, clusters, assignments }
+}
+
+fn summarize(cluster: usize, space: &ParameterSpace, members: &[&(&RankedReport, Vec<f64>)]) -> ClusterSummary {
+    let best = members[0].0;
+    let total: Decimal = members.iter().map(|(run, _)| run.score).sum();
+    let centroid = space
+        .dimensions
+        .iter()
+        .enumerate()
+        .map(|(i, dimension)| {
+            let mean = members.iter().map(|(_, point)| point[i]).sum::<f64>() / members.len() as f64;
+            (dimension.name.clone(), dimension.min + mean * (dimension.max - dimension.min))
+        })
+        .collect();
+    ClusterSummary {
+        cluster,
+        run_ids: members.iter().map(|(run, _)| run.report.run_id).collect(),
+        best_run: best.report.run_id,
+        best_score: best.score,
+        mean_score: total / Decimal::from(members.len()),
+        centroid,
+    }
+}
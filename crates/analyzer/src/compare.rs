@@ -0,0 +1,309 @@
+//! Side-by-side comparison of two saved backtest runs.
+//!
+//! Comparing works on already-loaded `BacktestRunDetails`, so it needs no database. It reports
+//! how each report metric moved, which trades only one of the runs took, and where the equity
+//! curves first part ways. Runs over different date ranges are compared over their overlap,
+//! and their metrics recomputed over it.
+
+use crate::error::AnalyzerError;
+use analytics::{AnalyticsEngine, PerformanceReport};
+use chrono::{DateTime, Duration, Utc};
+use database::{BacktestRunDetails, FullReport};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Tunes how closely two runs must agree to count as the same.
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// The largest equity difference that is not yet a divergence.
+    pub equity_epsilon: Decimal,
+    /// How far apart two entries may be and still be the same trade. None uses one bar,
+    /// inferred from the closest pair of equity points.
+    pub bar: Option<Duration>,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self { equity_epsilon: Decimal::new(1, 2), bar: None }
+    }
+}
+
+/// The stretch of time both runs cover, over which their trades and equity are compared.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ComparisonWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// True if the runs cover different ranges, so only their overlap is compared.
+    pub overlap_only: bool,
+}
+
+/// How one report metric changed from run A to run B.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MetricDelta {
+    pub metric: &'static str,
+    pub a: Option<Decimal>,
+    pub b: Option<Decimal>,
+    /// `b - a`, if both runs report the metric.
+    pub delta: Option<Decimal>,
+    /// The delta as a percentage of `|a|`, if `a` is non-zero.
+    pub delta_pct: Option<Decimal>,
+}
+
+/// The trades of both runs within the window, matched by entry time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TradeOverlap {
+    pub shared: usize,
+    pub only_in_a: usize,
+    pub only_in_b: usize,
+}
+
+/// The result of comparing run B against run A.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunComparison {
+    pub run_a: Uuid,
+    pub run_b: Uuid,
+    pub window: ComparisonWindow,
+    /// The deltas of the report metrics. Runs over the same range compare their stored
+    /// reports; runs compared over their overlap compare reports recomputed from the trades
+    /// closed and the equity recorded within it, which leave the annualized ratios, the gap
+    /// slippage and the exposure metrics unset.
+    pub metrics: Vec<MetricDelta>,
+    pub trades: TradeOverlap,
+    /// The first timestamp at which the equity curves differ by more than the epsilon.
+    pub equity_divergence: Option<DateTime<Utc>>,
+}
+
+/// Compares run `b` against run `a`.
+pub fn compare_runs(
+    a: &BacktestRunDetails,
+    b: &BacktestRunDetails,
+    options: &CompareOptions,
+) -> Result<RunComparison, AnalyzerError> {
+    let no_overlap = || AnalyzerError::NoOverlap(a.report.run_id, b.report.run_id);
+    let (a_start, a_end) = run_range(a).ok_or_else(no_overlap)?;
+    let (b_start, b_end) = run_range(b).ok_or_else(no_overlap)?;
+    let window = ComparisonWindow {
+        start: a_start.max(b_start),
+        end: a_end.min(b_end),
+        overlap_only: (a_start, a_end) != (b_start, b_end),
+    };
+    if window.start > window.end {
+        return Err(no_overlap());
+    }
+
+    let bar = options.bar.unwrap_or_else(|| infer_bar(a, b));
+
+    Ok(RunComparison {
+        run_a: a.report.run_id,
+        run_b: b.report.run_id,
+        metrics: metric_deltas(&window_metrics(a, &window)?, &window_metrics(b, &window)?),
+        trades: match_trades(&entry_times(a, &window), &entry_times(b, &window), bar),
+        equity_divergence: first_divergence(a, b, &window, options.equity_epsilon),
+        window,
+    })
+}
+
+/// The first and last timestamps a run covers, from its equity curve or else its trades.
+fn run_range(run: &BacktestRunDetails) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    if let (Some(first), Some(last)) = (run.equity_curve.first(), run.equity_curve.last()) {
+        return Some((first.timestamp, last.timestamp));
+    }
+    let start = run.trades.iter().map(|t| t.entry_execution.timestamp).min()?;
+    let end = run.trades.iter().map(|t| t.exit_execution.timestamp).max()?;
+    Some((start, end))
+}
+
+/// The smallest gap between consecutive equity points of either run. Thinned curves only
+/// ever widen gaps, so this is one bar as long as either curve has two adjacent bars.
+fn infer_bar(a: &BacktestRunDetails, b: &BacktestRunDetails) -> Duration {
+    [a, b]
+        .iter()
+        .flat_map(|run| run.equity_curve.windows(2).map(|pair| pair[1].timestamp - pair[0].timestamp))
+        .filter(|gap| *gap > Duration::zero())
+        .min()
+        .unwrap_or_else(Duration::zero)
+}
+
+fn entry_times(run: &BacktestRunDetails, window: &ComparisonWindow) -> Vec<DateTime<Utc>> {
+    let mut times: Vec<_> = run
+        .trades
+        .iter()
+        .map(|t| t.entry_execution.timestamp)
+        .filter(|t| (window.start..=window.end).contains(t))
+        .collect();
+    times.sort_unstable();
+    times
+}
+
+/// Pairs entries that are at most `bar` apart, walking both sorted lists in step.
+fn match_trades(a: &[DateTime<Utc>], b: &[DateTime<Utc>], bar: Duration) -> TradeOverlap {
+    let mut overlap = TradeOverlap::default();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        if (a[i] - b[j]).abs() <= bar {
+            overlap.shared += 1;
+            i += 1;
+            j += 1;
+        } else if a[i] < b[j] {
+            overlap.only_in_a += 1;
+            i += 1;
+        } else {
+            overlap.only_in_b += 1;
+            j += 1;
+        }
+    }
+    overlap.only_in_a += a.len() - i;
+    overlap.only_in_b += b.len() - j;
+    overlap
+}
+
+/// Walks run A's curve through the window and returns the first timestamp at which run B's
+/// curve has a point that differs by more than `epsilon`.
+fn first_divergence(
+    a: &BacktestRunDetails,
+    b: &BacktestRunDetails,
+    window: &ComparisonWindow,
+    epsilon: Decimal,
+) -> Option<DateTime<Utc>> {
+    let b_equity: HashMap<_, _> = b.equity_curve.iter().map(|p| (p.timestamp, p.equity)).collect();
+    a.equity_curve
+        .iter()
+        .filter(|p| (window.start..=window.end).contains(&p.timestamp))
+        .find(|p| b_equity.get(&p.timestamp).is_some_and(|equity| (p.equity - equity).abs() > epsilon))
+        .map(|p| p.timestamp)
+}
+
+/// The compared metrics, in the order they are reported.
+const METRICS: [&str; 25] = [
+    "Total Net Profit",
+    "Gross Profit",
+    "Gross Loss",
+    "Profit Factor",
+    "Total Return %",
+    "Max Drawdown",
+    "Max Drawdown %",
+    "Sharpe Ratio",
+    "Calmar Ratio",
+    "Total Trades",
+    "Winning Trades",
+    "Losing Trades",
+    "Win Rate %",
+    "Average Win",
+    "Average Loss",
+    "Payoff Ratio",
+    "Maker Fees Paid",
+    "Taker Fees Paid",
+    "Spread Cost",
+    "Gap Slippage",
+    "Average Exposure %",
+    "Peak Exposure %",
+    "Time in Market %",
+    "Return on Margin %",
+    "Exposure-Adjusted Sharpe",
+];
+
+/// The values of `METRICS` in a report, `None` where it lacks one.
+type Metrics = [Option<Decimal>; METRICS.len()];
+
+/// A run's metrics over `window`: those of its stored report if the window is its full range,
+/// or else recomputed from its trades and equity within the window.
+fn window_metrics(run: &BacktestRunDetails, window: &ComparisonWindow) -> Result<Metrics, AnalyzerError> {
+    if !window.overlap_only {
+        return Ok(stored_metrics(&run.report));
+    }
+    let within = |timestamp: &DateTime<Utc>| (window.start..=window.end).contains(timestamp);
+    let trades: Vec<_> = run
+        .trades
+        .iter()
+        .filter(|t| within(&t.entry_execution.timestamp) && within(&t.exit_execution.timestamp))
+        .cloned()
+        .collect();
+    let equity_curve: Vec<_> = run.equity_curve.iter().filter(|p| within(&p.timestamp)).map(|p| (p.timestamp, p.equity)).collect();
+    // Without equity in the window, returns have nothing to be relative to.
+    let Some(&(_, opening_equity)) = equity_curve.first() else {
+        return Ok([None; METRICS.len()]);
+    };
+    let report = AnalyticsEngine::new().calculate_period(&trades, &equity_curve, opening_equity)?;
+    Ok(recomputed_metrics(&report))
+}
+
+fn stored_metrics(report: &FullReport) -> Metrics {
+    let count = |value: Option<i32>| value.map(Decimal::from);
+    [
+        report.total_net_profit,
+        report.gross_profit,
+        report.gross_loss,
+        report.profit_factor,
+        report.total_return_pct,
+        report.max_drawdown,
+        report.max_drawdown_pct,
+        report.sharpe_ratio,
+        report.calmar_ratio,
+        count(report.total_trades),
+        count(report.winning_trades),
+        count(report.losing_trades),
+        report.win_rate_pct,
+        report.average_win,
+        report.average_loss,
+        report.payoff_ratio,
+        report.maker_fees_paid,
+        report.taker_fees_paid,
+        report.total_spread_cost,
+        report.gap_slippage,
+        report.average_exposure_pct,
+        report.peak_exposure_pct,
+        report.time_in_market_pct,
+        report.return_on_margin_pct,
+        report.exposure_adjusted_sharpe,
+    ]
+}
+
+/// The metrics of a report computed over a window. Only the backtester knows each stop's
+/// gap slippage, so it is left unset.
+fn recomputed_metrics(report: &PerformanceReport) -> Metrics {
+    let count = |value: usize| Some(Decimal::from(value));
+    [
+        Some(report.total_net_profit),
+        Some(report.gross_profit),
+        Some(report.gross_loss),
+        report.profit_factor,
+        Some(report.total_return_pct),
+        Some(report.max_drawdown),
+        Some(report.max_drawdown_pct),
+        report.sharpe_ratio,
+        report.calmar_ratio,
+        count(report.total_trades),
+        count(report.winning_trades),
+        count(report.losing_trades),
+        report.win_rate_pct,
+        Some(report.average_win),
+        Some(report.average_loss),
+        report.payoff_ratio,
+        Some(report.maker_fees_paid),
+        Some(report.taker_fees_paid),
+        Some(report.total_spread_cost),
+        None,
+        report.average_exposure_pct,
+        report.peak_exposure_pct,
+        report.time_in_market_pct,
+        report.return_on_margin_pct,
+        report.exposure_adjusted_sharpe,
+    ]
+}
+
+fn metric_deltas(a: &Metrics, b: &Metrics) -> Vec<MetricDelta> {
+    METRICS
+        .into_iter()
+        .zip(a.iter().zip(b))
+        .map(|(metric, (&a, &b))| {
+            let delta = a.zip(b).map(|(a, b)| b - a);
+            let delta_pct = a
+                .filter(|a| !a.is_zero())
+                .zip(delta)
+                .map(|(a, delta)| delta / a.abs() * Decimal::ONE_HUNDRED);
+            MetricDelta { metric, a, b, delta, delta_pct }
+        })
+        .collect()
+}
@@ -8,6 +8,33 @@ pub enum AnalyzerError {
     #[error("No completed backtest runs found for job ID: {0}")]
     NoRunsFound(uuid::Uuid),
 
+    #[error("Runs {0} and {1} do not cover any common time range")]
+    NoOverlap(uuid::Uuid, uuid::Uuid),
+
+    #[error("Only {passing} of the top {requested} runs of job {job_id} pass the analysis filters")]
+    TooFewPassingRuns { job_id: uuid::Uuid, requested: usize, passing: usize },
+
+    #[error("Unknown strategy in optimization job: {0}")]
+    UnknownStrategy(String),
+
+    #[error("Cannot export to {0}; use a .csv or .json file")]
+    UnsupportedExportFormat(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::ser::Error),
+
+    #[error("Analytics error: {0}")]
+    Analytics(#[from] analytics::AnalyticsError),
+
     #[error("An internal calculation error occurred: {0}")]
     Calculation(String),
 }
\ No newline at end of file
@@ -0,0 +1,188 @@
+//! Exporting an optimization job's ranked results.
+//!
+//! The full ranking can be written to CSV, with every report metric and parameter flattened
+//! into its own column, or to JSON. The best parameter sets can also be turned into a
+//! portfolio definition, one bot per set, so they can be backtested together with
+//! `portfolio-run`.
+
+use crate::error::AnalyzerError;
+use crate::{Analyzer, RankedReport};
+use configuration::{normalize_params, OverlapPolicy, PortfolioBotConfig, PortfolioConfig, Versioned};
+use core_types::enums::{KlineTransform, StrategyId, TradeDirection};
+use database::DbOptimizationJob;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Writes every ranked run to `path`, as CSV or JSON depending on its extension.
+pub fn export_ranked_reports(ranked: &[RankedReport], path: &Path) -> Result<(), AnalyzerError> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_lowercase();
+    let writer = || -> Result<_, AnalyzerError> { Ok(BufWriter::new(File::create(path)?)) };
+    match extension.as_str() {
+        "csv" => write_csv(ranked, writer()?),
+        "json" => write_json(ranked, writer()?),
+        _ => Err(AnalyzerError::UnsupportedExportFormat(path.display().to_string())),
+    }
+}
+
+/// Writes one row per ranked run: its rank and score, every report metric, then every
+/// parameter as a `param.<name>` column. Runs without a parameter leave its column empty.
+pub fn write_csv<W: Write>(ranked: &[RankedReport], writer: W) -> Result<(), AnalyzerError> {
+    let reports = ranked
+        .iter()
+        .map(|r| match serde_json::to_value(&r.report)? {
+            Value::Object(mut fields) => {
+                fields.remove("parameters");
+                Ok(fields)
+            }
+            _ => unreachable!("a report serializes to an object"),
+        })
+        .collect::<Result<Vec<_>, serde_json::Error>>()?;
+    let metric_columns: Vec<&String> = reports.first().map(|fields| fields.keys().collect()).unwrap_or_default();
+    let param_columns: BTreeSet<&String> = ranked
+        .iter()
+        .filter_map(|r| r.parameters.as_object())
+        .flat_map(|params| params.keys())
+        .collect();
+
+    let mut csv = csv::Writer::from_writer(writer);
+    let header = ["rank".to_string(), "score".to_string()]
+        .into_iter()
+        .chain(metric_columns.iter().map(|name| name.to_string()))
+        .chain(param_columns.iter().map(|name| format!("param.{}", name)));
+    csv.write_record(header)?;
+
+    for (i, (ranked, fields)) in ranked.iter().zip(&reports).enumerate() {
+        let metrics = metric_columns.iter().map(|name| cell(fields.get(*name)));
+        let params = param_columns.iter().map(|name| cell(ranked.parameters.get(name.as_str())));
+        let row = [(i + 1).to_string(), ranked.score.to_string()].into_iter().chain(metrics).chain(params);
+        csv.write_record(row)?;
+    }
+    csv.flush()?;
+    Ok(())
+}
+
+/// Writes the ranked runs as a JSON array, best first.
+pub fn write_json<W: Write>(ranked: &[RankedReport], writer: W) -> Result<(), AnalyzerError> {
+    #[derive(Serialize)]
+    struct RankedRow<'a> {
+        rank: usize,
+        #[serde(flatten)]
+        ranked: &'a RankedReport,
+    }
+
+    let rows: Vec<_> = ranked.iter().enumerate().map(|(i, ranked)| RankedRow { rank: i + 1, ranked }).collect();
+    serde_json::to_writer_pretty(writer, &rows)?;
+    Ok(())
+}
+
+fn cell(value: Option<&Value>) -> String {
+    match value {
+        None | Some(Value::Null) => String::new(),
+        Some(Value::String(s)) => s.clone(),
+        Some(other) => other.to_string(),
+    }
+}
+
+impl Analyzer {
+    /// Builds a portfolio with one bot per parameter set of the job's `top` best runs, named
+    /// `rank-1`, `rank-2` and so on.
+    ///
+    /// Refuses if fewer than `top` runs pass the hard filters, so a job with too few good
+    /// runs never produces a portfolio padded with bad ones.
+    pub fn portfolio_from_top_runs(
+        &self,
+        job: &DbOptimizationJob,
+        ranked: &[RankedReport],
+        top: usize,
+    ) -> Result<PortfolioConfig, AnalyzerError> {
+        let strategy_id: StrategyId = serde_json::from_value(Value::String(job.strategy_id.clone()))
+            .map_err(|_| AnalyzerError::UnknownStrategy(job.strategy_id.clone()))?;
+        let passing = ranked.iter().take(top).take_while(|r| self.passes_filters(&r.report)).count();
+        if passing < top {
+            return Err(AnalyzerError::TooFewPassingRuns { job_id: job.job_id, requested: top, passing });
+        }
+
+        let bots = ranked
+            .iter()
+            .take(top)
+            .enumerate()
+            .map(|(i, ranked)| PortfolioBotConfig {
+                symbol: job.symbol.clone(),
+                strategy_id,
+                name: Some(format!("rank-{}", i + 1)),
+                kline_transform: KlineTransform::default(),
+                direction: TradeDirection::default(),
+                risk: None,
+                params: toml_params(&ranked.parameters),
+            })
+            .collect();
+        Ok(PortfolioConfig {
+            config_version: PortfolioConfig::CURRENT_VERSION,
+            initial_capital: None,
+            interval: None,
+            max_positions_per_symbol: None,
+            overlap_policy: OverlapPolicy::default(),
+            bots,
+        })
+    }
+}
+
+/// Renders a portfolio built by `portfolio_from_top_runs` as TOML, headed by a comment that
+/// records the job and the score and run ID behind each bot.
+pub fn portfolio_toml(
+    job: &DbOptimizationJob,
+    ranked: &[RankedReport],
+    portfolio: &PortfolioConfig,
+) -> Result<String, AnalyzerError> {
+    let mut toml = format!(
+        "# Generated from the top {} runs of optimization job {} ({} {}).\n",
+        portfolio.bots.len(),
+        job.job_id,
+        job.strategy_id,
+        job.symbol
+    );
+    for (i, ranked) in ranked.iter().take(portfolio.bots.len()).enumerate() {
+        toml.push_str(&format!("# rank-{}: score {:.4}, run {}\n", i + 1, ranked.score, ranked.report.run_id));
+    }
+    toml.push('\n');
+    toml.push_str(&toml::to_string(portfolio)?);
+    Ok(toml)
+}
+
+/// Converts a run's stored parameters into the form the portfolio loader expects.
+///
+/// The parameters are put in the canonical form of `normalize_params` first, in which
+/// integral numbers are JSON integers and the others decimal strings. Integers stay integers,
+/// so they still load into `usize` fields. Decimal strings become floats, which load into
+/// `Decimal` fields, unless the float would not read back as the same decimal; those stay
+/// strings, which `Decimal` fields accept as well.
+fn toml_params(params: &Value) -> Value {
+    let params = normalize_params(params.clone());
+    let Some(params) = params.as_object() else {
+        return params;
+    };
+    let converted: Map<String, Value> = params
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(s) => decimal_as_float(s).unwrap_or_else(|| value.clone()),
+                _ => value.clone(),
+            };
+            (name.clone(), value)
+        })
+        .collect();
+    Value::Object(converted)
+}
+
+fn decimal_as_float(s: &str) -> Option<Value> {
+    let decimal = Decimal::from_str(s).ok()?;
+    let float = f64::from_str(s).ok()?;
+    let exact = Decimal::from_str(&float.to_string()).is_ok_and(|back| back == decimal);
+    exact.then(|| serde_json::Number::from_f64(float).map(Value::Number)).flatten()
+}
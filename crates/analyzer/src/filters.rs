@@ -0,0 +1,97 @@
+//! The analyzer's hard filters, and the funnel that counts how many runs each one removed.
+
+use crate::metrics::ReportMetrics;
+use configuration::optimizer_config::Filters;
+use database::repository::FullReport;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use std::fmt;
+
+/// A single hard rule a run must satisfy to be ranked.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HardFilter {
+    MinTotalTrades(usize),
+    MaxDrawdownPct(Decimal),
+    MinSharpeRatio(Decimal),
+    MinWinRatePct(Decimal),
+    MinProfitFactor(Decimal),
+    MaxConsecutiveLosses(usize),
+}
+
+impl HardFilter {
+    /// The configured filters, in the order the funnel applies them. Optional filters that
+    /// are not set are left out.
+    pub fn from_config(filters: &Filters) -> Vec<Self> {
+        let mut hard_filters = vec![Self::MinTotalTrades(filters.min_total_trades), Self::MaxDrawdownPct(filters.max_drawdown_pct)];
+        hard_filters.extend(filters.min_sharpe_ratio.map(Self::MinSharpeRatio));
+        hard_filters.extend(filters.min_win_rate_pct.map(Self::MinWinRatePct));
+        hard_filters.extend(filters.min_profit_factor.map(Self::MinProfitFactor));
+        hard_filters.extend(filters.max_consecutive_losses.map(Self::MaxConsecutiveLosses));
+        hard_filters
+    }
+
+    /// Whether `report` satisfies this filter. A run missing the metric of an optional filter
+    /// fails it.
+    pub fn passes(&self, report: &impl ReportMetrics) -> bool {
+        match *self {
+            Self::MinTotalTrades(min) => report.total_trades() >= min,
+            // A run without a drawdown figure counts as a 100% drawdown.
+            Self::MaxDrawdownPct(max) => report.max_drawdown_pct().unwrap_or_else(|| Decimal::new(100, 0)) < max,
+            Self::MinSharpeRatio(min) => report.sharpe_ratio().is_some_and(|sharpe| sharpe >= min),
+            Self::MinWinRatePct(min) => report.win_rate_pct().is_some_and(|win_rate| win_rate >= min),
+            Self::MinProfitFactor(min) => report.profit_factor().is_some_and(|profit_factor| profit_factor >= min),
+            Self::MaxConsecutiveLosses(max) => report.max_consecutive_losses().is_some_and(|losses| losses <= max),
+        }
+    }
+}
+
+impl fmt::Display for HardFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MinTotalTrades(min) => write!(f, "min_total_trades = {}", min),
+            Self::MaxDrawdownPct(max) => write!(f, "max_drawdown_pct = {}", max),
+            Self::MinSharpeRatio(min) => write!(f, "min_sharpe_ratio = {}", min),
+            Self::MinWinRatePct(min) => write!(f, "min_win_rate_pct = {}", min),
+            Self::MinProfitFactor(min) => write!(f, "min_profit_factor = {}", min),
+            Self::MaxConsecutiveLosses(max) => write!(f, "max_consecutive_losses = {}", max),
+        }
+    }
+}
+
+/// How many runs one filter eliminated, and how many were left after it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FunnelStage {
+    /// The filter and its threshold, as written in the config.
+    pub filter: String,
+    pub eliminated: usize,
+    pub remaining: usize,
+}
+
+/// How a job's runs were narrowed down by the hard filters. Each eliminated run is counted
+/// against the first filter it failed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FilterFunnel {
+    pub total: usize,
+    pub stages: Vec<FunnelStage>,
+}
+
+impl FilterFunnel {
+    /// Applies `filters` in order to `reports`.
+    pub fn new(filters: &[HardFilter], reports: &[FullReport]) -> Self {
+        let mut remaining: Vec<&FullReport> = reports.iter().collect();
+        let stages = filters
+            .iter()
+            .map(|filter| {
+                let before = remaining.len();
+                remaining.retain(|report| filter.passes(report));
+                FunnelStage { filter: filter.to_string(), eliminated: before - remaining.len(), remaining: remaining.len() }
+            })
+            .collect();
+        Self { total: reports.len(), stages }
+    }
+
+    /// The number of runs that passed every filter.
+    pub fn passed(&self) -> usize {
+        self.stages.last().map_or(self.total, |stage| stage.remaining)
+    }
+}
@@ -0,0 +1,84 @@
+//! Reports over hand-picked trades, such as every trade annotated with the same tag.
+//!
+//! The trades of such a subset rarely come from one continuous run, so the report covers them
+//! as if they had been taken one after another from a common opening equity. Nothing is
+//! annualized.
+
+use crate::error::AnalyzerError;
+use analytics::{trade_pnl, AnalyticsEngine, PerformanceReport};
+use core_types::{Execution, OrderSide, Trade};
+use database::{DbRepository, LivePosition, LivePositionFilter, LivePositionStatus};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// Runs the `AnalyticsEngine` over the backtest trades and closed live positions with the
+/// given IDs, as annotations target either. Returns and drawdowns are relative to
+/// `opening_equity`. Unknown IDs and open positions are skipped.
+pub async fn report_for_trades(
+    db_repo: &DbRepository,
+    trade_ids: &[Uuid],
+    opening_equity: Decimal,
+) -> Result<PerformanceReport, AnalyzerError> {
+    let mut trades = db_repo.get_trades(trade_ids).await?;
+    let filter = LivePositionFilter { status: Some(LivePositionStatus::Closed), position_ids: Some(trade_ids.to_vec()), ..Default::default() };
+    trades.extend(db_repo.get_live_positions(&filter).await?.iter().filter_map(position_trade));
+    subset_report(&trades, opening_equity)
+}
+
+/// A closed live position as one trade of its largest size. It enters at the average entry
+/// price and exits at the price that gives its realized P&L before fees, with every fee
+/// charged on the exit; adds and partial exits are folded into those two fills. None for a
+/// position opened before its fills were recorded.
+fn position_trade(position: &LivePosition) -> Option<Trade> {
+    let entry_price = position.avg_entry_price?;
+    let exit_time = position.exit_time?;
+    if position.max_quantity.is_zero() {
+        return None;
+    }
+    let move_per_unit = (position.realized_pnl + position.fees) / position.max_quantity;
+    let (exit_side, exit_price) = match position.side {
+        OrderSide::Buy => (OrderSide::Sell, entry_price + move_per_unit),
+        OrderSide::Sell => (OrderSide::Buy, entry_price - move_per_unit),
+    };
+    let execution = |side, price, fee, timestamp| Execution {
+        execution_id: Uuid::nil(),
+        client_order_id: Uuid::nil(),
+        symbol: position.symbol.clone(),
+        side,
+        price,
+        quantity: position.max_quantity,
+        fee,
+        fee_asset: String::new(),
+        timestamp,
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    Some(Trade {
+        trade_id: position.position_id,
+        symbol: position.symbol.clone(),
+        entry_execution: execution(position.side, entry_price, Decimal::ZERO, position.entry_time?),
+        exit_execution: execution(exit_side, exit_price, position.fees, exit_time),
+        close_reason: position.close_reason.unwrap_or_default(),
+        initial_risk: None,
+    })
+}
+
+/// The report of `trades`, their P&L added to `opening_equity` in the order they were closed.
+pub fn subset_report(trades: &[Trade], opening_equity: Decimal) -> Result<PerformanceReport, AnalyzerError> {
+    let mut trades = trades.to_vec();
+    trades.sort_by_key(|trade| trade.exit_execution.timestamp);
+
+    let mut equity_curve = Vec::with_capacity(trades.len() + 1);
+    if let Some(first) = trades.iter().map(|trade| trade.entry_execution.timestamp).min() {
+        equity_curve.push((first, opening_equity));
+    }
+    let mut equity = opening_equity;
+    for trade in &trades {
+        equity += trade_pnl(trade);
+        equity_curve.push((trade.exit_execution.timestamp, equity));
+    }
+
+    Ok(AnalyticsEngine::new().calculate_period(&trades, &equity_curve, opening_equity)?)
+}
@@ -1,14 +1,34 @@
 use crate::error::AnalyzerError;
-use configuration::optimizer_config::AnalysisConfig;
-use database::DbRepository;
+use configuration::optimizer_config::{AnalysisConfig, Weights};
+use configuration::{normalize_params, JobConfigSnapshot};
+use database::{DbError, DbRepository};
 use database::repository::FullReport;
 use rust_decimal::Decimal;
 use serde::Serialize;
 use serde_json::Value;
 use std::cmp::Ordering;
+use std::collections::BTreeSet;
 use uuid::Uuid;
 
+pub mod cluster;
+pub mod compare;
 pub mod error;
+pub mod export;
+pub mod filters;
+pub mod journal;
+pub mod metrics;
+pub mod prune;
+pub mod rolling;
+pub mod rollup;
+
+pub use cluster::{cluster_runs, ClusterOptions, ClusterSummary, Clustering, ParameterSpace};
+pub use compare::{compare_runs, CompareOptions, RunComparison};
+pub use export::{export_ranked_reports, portfolio_toml};
+pub use filters::{FilterFunnel, FunnelStage, HardFilter};
+pub use journal::{report_for_trades, subset_report};
+pub use metrics::ReportMetrics;
+pub use prune::PruneSummary;
+pub use rollup::RecomputeSummary;
 
 /// A report that includes the raw performance data, the parameters that produced it,
 /// and the final analysis score.
@@ -19,6 +39,17 @@ pub struct RankedReport {
     pub report: FullReport,
 }
 
+/// The configuration snapshot an optimization job was saved with. `None` for unknown jobs and
+/// for jobs saved without one, which are analysed with the current `optimizer.toml` instead.
+pub async fn stored_job_config(db_repo: &DbRepository, job_id: Uuid) -> Result<Option<JobConfigSnapshot>, AnalyzerError> {
+    let job_config = match db_repo.get_job_config(job_id).await {
+        Ok(job_config) => job_config,
+        Err(DbError::NotFound) => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(job_config.map(serde_json::from_value).transpose()?)
+}
+
 /// The main analysis engine.
 pub struct Analyzer {
     config: AnalysisConfig,
@@ -35,45 +66,94 @@ impl Analyzer {
         db_repo: &DbRepository,
         job_id: Uuid,
     ) -> Result<Vec<RankedReport>, AnalyzerError> {
-        // 1. Fetch
-        let all_reports = db_repo.get_full_reports_for_job(job_id).await?;
+        self.run_with_funnel(db_repo, job_id).await.map(|(ranked, _)| ranked)
+    }
+
+    /// Like `run`, but also returns how many runs each hard filter eliminated.
+    pub async fn run_with_funnel(
+        &self,
+        db_repo: &DbRepository,
+        job_id: Uuid,
+    ) -> Result<(Vec<RankedReport>, FilterFunnel), AnalyzerError> {
+        let all_reports = self.fetch_reports(db_repo, job_id).await?;
+        self.rank(job_id, all_reports)
+    }
+
+    /// Like `run_with_funnel`, but also clusters the best runs into distinct regions of the
+    /// job's parameter grid.
+    pub async fn run_with_clusters(
+        &self,
+        db_repo: &DbRepository,
+        job_id: Uuid,
+        options: &ClusterOptions,
+    ) -> Result<(Vec<RankedReport>, FilterFunnel, Clustering), AnalyzerError> {
+        let all_reports = self.fetch_reports(db_repo, job_id).await?;
+        let space = ParameterSpace::from_grid(all_reports.iter().map(|r| &r.parameters));
+        let (ranked, funnel) = self.rank(job_id, all_reports)?;
+        let clustering = cluster_runs(space, &ranked, options);
+        Ok((ranked, funnel, clustering))
+    }
+
+    /// Fetches every report of a job, with its parameters in the canonical form, so runs
+    /// stored before the optimizer normalized its sets compare equal to the newer ones.
+    async fn fetch_reports(&self, db_repo: &DbRepository, job_id: Uuid) -> Result<Vec<FullReport>, AnalyzerError> {
+        let mut all_reports = db_repo.get_full_reports_for_job(job_id).await?;
         if all_reports.is_empty() {
             return Err(AnalyzerError::NoRunsFound(job_id));
         }
+        for report in &mut all_reports {
+            report.parameters = normalize_params(std::mem::take(&mut report.parameters));
+        }
+        Ok(all_reports)
+    }
+
+    /// Filters, scores and ranks a job's reports.
+    fn rank(&self, job_id: Uuid, all_reports: Vec<FullReport>) -> Result<(Vec<RankedReport>, FilterFunnel), AnalyzerError> {
+        // Every run of a job should share one configuration; several hashes mean the base
+        // config changed while the job was running, so the runs may not be comparable.
+        let config_hashes: BTreeSet<&str> = all_reports.iter().filter_map(|r| r.config_hash.as_deref()).collect();
+        if config_hashes.len() > 1 {
+            tracing::warn!(
+                job_id = %job_id,
+                hashes = ?config_hashes,
+                "Runs in this job were produced under {} different configurations; their rankings may not be comparable.",
+                config_hashes.len()
+            );
+        }
 
         // 2. Filter
+        let funnel = self.filter_funnel(&all_reports);
         let filtered_reports = self.filter_reports(all_reports);
         if filtered_reports.is_empty() {
-            return Ok(vec![]); // Return empty if all were filtered out
+            return Ok((vec![], funnel)); // Return empty if all were filtered out
         }
 
         // 3. Score
         let scored_reports = self.score_reports(filtered_reports)?;
 
-        // 4. Rank
+        // 4. Rank (ties are broken by run ID so the order doesn't depend on row order from the database)
         let mut ranked_reports = scored_reports;
         ranked_reports.sort_by(|a, b| {
             b.score.partial_cmp(&a.score).unwrap_or(Ordering::Equal)
+                .then_with(|| a.report.run_id.cmp(&b.report.run_id))
         });
 
-        Ok(ranked_reports)
+        Ok((ranked_reports, funnel))
     }
 
     /// Applies hard filters to remove unacceptable runs.
     fn filter_reports(&self, reports: Vec<FullReport>) -> Vec<FullReport> {
-        reports
-            .into_iter()
-            .filter(|r| {
-                // Safely unwrap or provide default values for Option types
-                let total_trades = r.total_trades.unwrap_or(0);
-                let max_drawdown_pct = r.max_drawdown_pct.unwrap_or_else(|| Decimal::new(100, 0)); // Default to 100% if None
-                
-                let passes_trades = total_trades as usize >= self.config.filters.min_total_trades;
-                let passes_drawdown = max_drawdown_pct < self.config.filters.max_drawdown_pct;
-                
-                passes_trades && passes_drawdown
-            })
-            .collect()
+        reports.into_iter().filter(|r| self.passes_filters(r)).collect()
+    }
+
+    /// Whether a run passes the hard filters.
+    pub fn passes_filters(&self, report: &impl ReportMetrics) -> bool {
+        HardFilter::from_config(&self.config.filters).iter().all(|filter| filter.passes(report))
+    }
+
+    /// Counts how many of `reports` each hard filter eliminates.
+    pub fn filter_funnel(&self, reports: &[FullReport]) -> FilterFunnel {
+        FilterFunnel::new(&HardFilter::from_config(&self.config.filters), reports)
     }
     
     /// Normalizes and applies the weighted scoring function to each report.
@@ -82,6 +162,9 @@ impl Analyzer {
         let (min_pf, max_pf) = find_min_max(&reports, |r| r.profit_factor);
         let (min_cr, max_cr) = find_min_max(&reports, |r| r.calmar_ratio);
         let (min_pr, max_pr) = find_min_max(&reports, |r| r.payoff_ratio);
+        let (min_ex, max_ex) = find_min_max(&reports, |r| r.expectancy_r);
+        let (min_rom, max_rom) = find_min_max(&reports, |r| r.return_on_margin_pct);
+        let (min_eas, max_eas) = find_min_max(&reports, |r| r.exposure_adjusted_sharpe);
         
         reports
             .into_iter()
@@ -89,12 +172,18 @@ impl Analyzer {
                 let norm_pf = normalize(r.profit_factor.unwrap_or_default(), min_pf, max_pf);
                 let norm_cr = normalize(r.calmar_ratio.unwrap_or_default(), min_cr, max_cr);
                 let norm_pr = normalize(r.payoff_ratio.unwrap_or_default(), min_pr, max_pr);
+                let norm_ex = normalize(r.expectancy_r.unwrap_or_default(), min_ex, max_ex);
+                let norm_rom = normalize(r.return_on_margin_pct.unwrap_or_default(), min_rom, max_rom);
+                let norm_eas = normalize(r.exposure_adjusted_sharpe.unwrap_or_default(), min_eas, max_eas);
                 
                 let w = &self.config.scoring_weights;
                 
                 let score = (norm_pf * w.weight_profit_factor)
                           + (norm_cr * w.weight_calmar_ratio)
-                          + (norm_pr * w.weight_avg_win_loss_ratio);
+                          + (norm_pr * w.weight_avg_win_loss_ratio)
+                          + (norm_ex * w.weight_expectancy)
+                          + (norm_rom * w.weight_return_on_margin)
+                          + (norm_eas * w.weight_exposure_adjusted_sharpe);
                 
                 Ok(RankedReport {
                     parameters: r.parameters.clone(),
@@ -106,7 +195,28 @@ impl Analyzer {
     }
 }
 
+/// The scoring weights applied to a single run's raw metrics, without the normalization
+/// across a job's runs that `Analyzer::run` applies. Missing metrics count as zero.
+pub fn weighted_score(weights: &Weights, report: &impl ReportMetrics) -> Decimal {
+    report.profit_factor().unwrap_or_default() * weights.weight_profit_factor
+        + report.calmar_ratio().unwrap_or_default() * weights.weight_calmar_ratio
+        + report.payoff_ratio().unwrap_or_default() * weights.weight_avg_win_loss_ratio
+        + report.expectancy_r().unwrap_or_default() * weights.weight_expectancy
+        + report.return_on_margin_pct().unwrap_or_default() * weights.weight_return_on_margin
+        + report.exposure_adjusted_sharpe().unwrap_or_default() * weights.weight_exposure_adjusted_sharpe
+}
+
+/// Re-ranks `ranked` by each run's stored objective value, highest first. Runs without one
+/// (they failed the hard filters, or predate objectives) go last; ties are broken by run ID.
+pub fn sort_by_objective(ranked: &mut [RankedReport]) {
+    ranked.sort_by(|a, b| {
+        b.report.objective_value.cmp(&a.report.objective_value).then_with(|| a.report.run_id.cmp(&b.report.run_id))
+    });
+}
+
 /// A helper function to find the min and max of a specific metric in a Vec of reports.
+/// When no report has the metric, such as the expectancy of runs saved before it was
+/// computed, both are zero, so every report normalizes to the same value.
 fn find_min_max<F>(reports: &[FullReport], accessor: F) -> (Decimal, Decimal)
 where
     F: Fn(&FullReport) -> Option<Decimal>,
@@ -114,9 +224,11 @@ where
     reports
         .iter()
         .filter_map(|r| accessor(r))
-        .fold((Decimal::MAX, Decimal::MIN), |(min, max), val| {
-            (min.min(val), max.max(val))
+        .fold(None, |range: Option<(Decimal, Decimal)>, val| match range {
+            Some((min, max)) => Some((min.min(val), max.max(val))),
+            None => Some((val, val)),
         })
+        .unwrap_or_default()
 }
 
 /// Normalizes a value to a 0.0-1.0 scale.
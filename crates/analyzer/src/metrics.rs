@@ -0,0 +1,173 @@
+//! The metrics runs are filtered and scored on, read the same way from a stored report
+//! (`FullReport`) and from one just computed by the analytics engine (`PerformanceReport`).
+
+use analytics::PerformanceReport;
+use database::repository::FullReport;
+use rust_decimal::Decimal;
+
+/// Read access to a run's ranking metrics. A metric the report does not have is `None`.
+pub trait ReportMetrics {
+    fn total_trades(&self) -> usize;
+    fn total_net_profit(&self) -> Option<Decimal>;
+    fn max_drawdown_pct(&self) -> Option<Decimal>;
+    fn sharpe_ratio(&self) -> Option<Decimal>;
+    fn calmar_ratio(&self) -> Option<Decimal>;
+    fn profit_factor(&self) -> Option<Decimal>;
+    fn payoff_ratio(&self) -> Option<Decimal>;
+    fn win_rate_pct(&self) -> Option<Decimal>;
+    fn max_consecutive_losses(&self) -> Option<usize>;
+    fn expectancy_r(&self) -> Option<Decimal>;
+    fn return_on_margin_pct(&self) -> Option<Decimal>;
+    fn exposure_adjusted_sharpe(&self) -> Option<Decimal>;
+}
+
+impl ReportMetrics for FullReport {
+    fn total_trades(&self) -> usize {
+        self.total_trades.unwrap_or(0) as usize
+    }
+
+    fn total_net_profit(&self) -> Option<Decimal> {
+        self.total_net_profit
+    }
+
+    fn max_drawdown_pct(&self) -> Option<Decimal> {
+        self.max_drawdown_pct
+    }
+
+    fn sharpe_ratio(&self) -> Option<Decimal> {
+        self.sharpe_ratio
+    }
+
+    fn calmar_ratio(&self) -> Option<Decimal> {
+        self.calmar_ratio
+    }
+
+    fn profit_factor(&self) -> Option<Decimal> {
+        self.profit_factor
+    }
+
+    fn payoff_ratio(&self) -> Option<Decimal> {
+        self.payoff_ratio
+    }
+
+    fn win_rate_pct(&self) -> Option<Decimal> {
+        self.win_rate_pct
+    }
+
+    fn max_consecutive_losses(&self) -> Option<usize> {
+        self.max_consecutive_losses.map(|losses| losses as usize)
+    }
+
+    fn expectancy_r(&self) -> Option<Decimal> {
+        self.expectancy_r
+    }
+
+    fn return_on_margin_pct(&self) -> Option<Decimal> {
+        self.return_on_margin_pct
+    }
+
+    fn exposure_adjusted_sharpe(&self) -> Option<Decimal> {
+        self.exposure_adjusted_sharpe
+    }
+}
+
+impl ReportMetrics for PerformanceReport {
+    fn total_trades(&self) -> usize {
+        self.total_trades
+    }
+
+    fn total_net_profit(&self) -> Option<Decimal> {
+        Some(self.total_net_profit)
+    }
+
+    fn max_drawdown_pct(&self) -> Option<Decimal> {
+        Some(self.max_drawdown_pct)
+    }
+
+    fn sharpe_ratio(&self) -> Option<Decimal> {
+        self.sharpe_ratio
+    }
+
+    fn calmar_ratio(&self) -> Option<Decimal> {
+        self.calmar_ratio
+    }
+
+    fn profit_factor(&self) -> Option<Decimal> {
+        self.profit_factor
+    }
+
+    fn payoff_ratio(&self) -> Option<Decimal> {
+        self.payoff_ratio
+    }
+
+    fn win_rate_pct(&self) -> Option<Decimal> {
+        self.win_rate_pct
+    }
+
+    fn max_consecutive_losses(&self) -> Option<usize> {
+        Some(self.max_consecutive_losses)
+    }
+
+    fn expectancy_r(&self) -> Option<Decimal> {
+        self.expectancy_r
+    }
+
+    fn return_on_margin_pct(&self) -> Option<Decimal> {
+        self.return_on_margin_pct
+    }
+
+    fn exposure_adjusted_sharpe(&self) -> Option<Decimal> {
+        self.exposure_adjusted_sharpe
+    }
+}
+
+/// Lets callers iterating over borrowed reports pass them straight through.
+impl<T: ReportMetrics + ?Sized> ReportMetrics for &T {
+    fn total_trades(&self) -> usize {
+        (**self).total_trades()
+    }
+
+    fn total_net_profit(&self) -> Option<Decimal> {
+        (**self).total_net_profit()
+    }
+
+    fn max_drawdown_pct(&self) -> Option<Decimal> {
+        (**self).max_drawdown_pct()
+    }
+
+    fn sharpe_ratio(&self) -> Option<Decimal> {
+        (**self).sharpe_ratio()
+    }
+
+    fn calmar_ratio(&self) -> Option<Decimal> {
+        (**self).calmar_ratio()
+    }
+
+    fn profit_factor(&self) -> Option<Decimal> {
+        (**self).profit_factor()
+    }
+
+    fn payoff_ratio(&self) -> Option<Decimal> {
+        (**self).payoff_ratio()
+    }
+
+    fn win_rate_pct(&self) -> Option<Decimal> {
+        (**self).win_rate_pct()
+    }
+
+    fn max_consecutive_losses(&self) -> Option<usize> {
+        (**self).max_consecutive_losses()
+    }
+
+    fn expectancy_r(&self) -> Option<Decimal> {
+        (**self).expectancy_r()
+    }
+
+    fn return_on_margin_pct(&self) -> Option<Decimal> {
+        (**self).return_on_margin_pct()
+    }
+
+    fn exposure_adjusted_sharpe(&self) -> Option<Decimal> {
+        (**self).exposure_adjusted_sharpe()
+    }
+}
@@ -0,0 +1,83 @@
+//! Maintenance of stored equity curves.
+//!
+//! Optimizer sweeps store an equity curve for every run, though only the best few are ever
+//! looked at closely. Pruning replaces the full-resolution curves of an old job's low-ranked
+//! runs with thinned copies, which keep the curve's shape and its maximum drawdown.
+
+use crate::error::AnalyzerError;
+use crate::Analyzer;
+use analytics::downsample;
+use chrono::{DateTime, Utc};
+use database::DbRepository;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// What a pruning pass did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PruneSummary {
+    pub jobs_scanned: usize,
+    pub runs_pruned: usize,
+    pub points_deleted: u64,
+}
+
+impl Analyzer {
+    /// Thins the equity curves of every run outside the top `keep_top` of each optimization
+    /// job created before `cutoff` to about `points` points.
+    ///
+    /// Runs are ranked by the analyzer's score; runs it filters out follow, ordered by net
+    /// profit. Curves that are already thinned are left alone, so pruning the same jobs again
+    /// is a no-op.
+    pub async fn prune_equity_curves(
+        &self,
+        db_repo: &DbRepository,
+        cutoff: DateTime<Utc>,
+        keep_top: usize,
+        points: usize,
+    ) -> Result<PruneSummary, AnalyzerError> {
+        let mut summary = PruneSummary::default();
+
+        for job in db_repo.get_optimization_jobs_created_before(cutoff).await? {
+            summary.jobs_scanned += 1;
+            for run_id in self.rank_runs(db_repo, job.job_id).await?.into_iter().skip(keep_top) {
+                let curve = db_repo.get_equity_curve(run_id).await?;
+                // `lttb` may keep two drawdown points beyond `points`; a curve within that
+                // margin has already been thinned.
+                if curve.len() <= points + 2 {
+                    continue;
+                }
+                let thinned = downsample::lttb(&curve, points);
+                db_repo.replace_equity_curve(run_id, &thinned).await?;
+
+                summary.runs_pruned += 1;
+                summary.points_deleted += (curve.len() - thinned.len()) as u64;
+                tracing::debug!(job_id = %job.job_id, run_id = %run_id, from = curve.len(), to = thinned.len(), "Equity curve thinned.");
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Ranks every reported run of a job, best first: scored runs by score, then the runs the
+    /// filters rejected by net profit.
+    async fn rank_runs(&self, db_repo: &DbRepository, job_id: Uuid) -> Result<Vec<Uuid>, AnalyzerError> {
+        let scored = match self.run(db_repo, job_id).await {
+            Ok(ranked) => ranked,
+            Err(AnalyzerError::NoRunsFound(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        let mut ranked: Vec<Uuid> = scored.iter().map(|r| r.report.run_id).collect();
+
+        let seen: HashSet<Uuid> = ranked.iter().copied().collect();
+        let mut unscored: Vec<_> = db_repo
+            .get_full_reports_for_job(job_id)
+            .await?
+            .into_iter()
+            .filter(|r| !seen.contains(&r.run_id))
+            .collect();
+        unscored.sort_by(|a, b| {
+            b.total_net_profit.unwrap_or_default().cmp(&a.total_net_profit.unwrap_or_default())
+                .then_with(|| a.run_id.cmp(&b.run_id))
+        });
+        ranked.extend(unscored.into_iter().map(|r| r.run_id));
+        Ok(ranked)
+    }
+}
@@ -0,0 +1,68 @@
+//! Live performance over trailing windows, for the live dashboard.
+//!
+//! Unlike the rollups, which cover calendar periods, each window ends when it is computed.
+//! Its P&L, trade count and win rate are scored by `AnalyticsEngine` from the fills recorded
+//! in it, as a rollup's are. Its Sharpe Ratio comes from the live equity sampled at each day
+//! boundary of the window, so how often the engine records its equity does not change it.
+
+use crate::error::AnalyzerError;
+use crate::rollup::closing_trade;
+use analytics::AnalyticsEngine;
+use chrono::{DateTime, Duration, Utc};
+use core_types::Trade;
+use database::{DbRepository, LiveFill};
+use events::{RollingStats, RollingWindowStats};
+use rust_decimal::Decimal;
+
+/// The lengths in days of the trailing windows, shortest first.
+pub const ROLLING_WINDOW_DAYS: [u32; 2] = [7, 30];
+
+/// Computes the live performance of every window in `ROLLING_WINDOW_DAYS`, each ending at `now`,
+/// from the stored live fills and equity.
+pub async fn compute_rolling_stats(db_repo: &DbRepository, now: DateTime<Utc>) -> Result<RollingStats, AnalyzerError> {
+    let longest = ROLLING_WINDOW_DAYS.iter().max().copied().unwrap_or_default();
+    let from = now - Duration::days(longest.into());
+    let fills = db_repo.get_live_fills(from, now).await?;
+    let mut equity: Vec<(DateTime<Utc>, Decimal)> = db_repo.get_live_equity_before(from).await?.into_iter().collect();
+    equity.extend(db_repo.get_live_equity(from, now).await?);
+
+    let windows = ROLLING_WINDOW_DAYS
+        .iter()
+        .map(|&days| window_stats(days, now, &fills, &equity))
+        .collect::<Result<_, _>>()?;
+    Ok(RollingStats { computed_at: now, windows })
+}
+
+/// The stats of the `days` ending at `now`, from the `fills` and `equity` of a span covering
+/// them. Everything is left null unless `equity` was recorded by the window's start.
+fn window_stats(days: u32, now: DateTime<Utc>, fills: &[LiveFill], equity: &[(DateTime<Utc>, Decimal)]) -> Result<RollingWindowStats, AnalyzerError> {
+    let from = now - Duration::days(days.into());
+    let mut stats = RollingWindowStats { days, from, net_pnl: None, trade_count: None, win_rate_pct: None, sharpe_ratio: None };
+    let Some(daily_equity) = daily_equity(equity, from, days) else {
+        return Ok(stats);
+    };
+
+    let fills: Vec<&LiveFill> = fills.iter().filter(|fill| fill.executed_at >= from && fill.executed_at < now).collect();
+    let trades: Vec<Trade> = fills.iter().filter(|fill| !fill.is_entry).map(|fill| closing_trade(fill)).collect();
+    let analytics = AnalyticsEngine::new();
+    let report = analytics.calculate_period(&trades, &[], Decimal::ZERO)?;
+    let fees: Decimal = fills.iter().map(|fill| fill.fee).sum();
+
+    stats.net_pnl = Some(report.total_net_profit - fees);
+    stats.trade_count = Some(report.total_trades as u32);
+    stats.win_rate_pct = report.win_rate_pct;
+    stats.sharpe_ratio = analytics.sharpe_ratio(&daily_equity, "1d")?;
+    Ok(stats)
+}
+
+/// The equity at `from` and at each of the `days` day boundaries after it: the last recorded
+/// at or before each. None when nothing was recorded by `from`.
+fn daily_equity(equity: &[(DateTime<Utc>, Decimal)], from: DateTime<Utc>, days: u32) -> Option<Vec<(DateTime<Utc>, Decimal)>> {
+    (0..=days)
+        .map(|day| {
+            let at = from + Duration::days(day.into());
+            let recorded = equity.partition_point(|(recorded_at, _)| *recorded_at <= at);
+            recorded.checked_sub(1).map(|last| (at, equity[last].1))
+        })
+        .collect()
+}
@@ -0,0 +1,144 @@
+//! Daily and weekly rollups of live trading performance.
+//!
+//! The dashboard compares periods ("this week vs last week") from stored rollups rather than
+//! from the raw fills. Each rollup is computed by `AnalyticsEngine` from the period's slice
+//! of the recorded live fills and equity, so recomputing a period from the same data always
+//! stores the same figures.
+
+use crate::error::AnalyzerError;
+use analytics::AnalyticsEngine;
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use database::{DbRepository, LiveFill, PerformanceRollup, RollupGranularity};
+use rust_decimal::Decimal;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// What a recompute did.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecomputeSummary {
+    pub days: usize,
+    pub weeks: usize,
+}
+
+/// The first day of the period of `granularity` that contains `day`.
+pub fn period_start(granularity: RollupGranularity, day: NaiveDate) -> NaiveDate {
+    match granularity {
+        RollupGranularity::Daily => day,
+        RollupGranularity::Weekly => day - Days::new(day.weekday().num_days_from_monday().into()),
+    }
+}
+
+/// The UTC span `[start, end)` of the period of `granularity` starting on `period_start`.
+pub fn period_bounds(granularity: RollupGranularity, period_start: NaiveDate) -> (DateTime<Utc>, DateTime<Utc>) {
+    let length = match granularity {
+        RollupGranularity::Daily => Days::new(1),
+        RollupGranularity::Weekly => Days::new(7),
+    };
+    let start = period_start.and_time(Default::default()).and_utc();
+    let end = (period_start + length).and_time(Default::default()).and_utc();
+    (start, end)
+}
+
+/// Computes the rollup of the period of `granularity` starting on `period_start` from the
+/// stored live fills and equity. Nothing is saved.
+pub async fn compute_rollup(
+    db_repo: &DbRepository,
+    granularity: RollupGranularity,
+    period_start: NaiveDate,
+) -> Result<PerformanceRollup, AnalyzerError> {
+    let (start, end) = period_bounds(granularity, period_start);
+    let fills = db_repo.get_live_fills(start, end).await?;
+
+    // The equity the period opened with, carried over from before it, is where its
+    // drawdown is measured from.
+    let mut equity_curve: Vec<(DateTime<Utc>, Decimal)> =
+        db_repo.get_live_equity_before(start).await?.map(|(_, equity)| (start, equity)).into_iter().collect();
+    equity_curve.extend(db_repo.get_live_equity(start, end).await?);
+    let opening_equity = equity_curve.first().map_or(Decimal::ZERO, |(_, equity)| *equity);
+
+    let trades: Vec<Trade> = fills.iter().filter(|fill| !fill.is_entry).map(closing_trade).collect();
+    let report = AnalyticsEngine::new().calculate_period(&trades, &equity_curve, opening_equity)?;
+    let fees: Decimal = fills.iter().map(|fill| fill.fee).sum();
+
+    Ok(PerformanceRollup {
+        period_start,
+        net_pnl: report.total_net_profit - fees,
+        fees,
+        trade_count: report.total_trades as i32,
+        winning_trades: report.winning_trades as i32,
+        win_rate_pct: report.win_rate_pct,
+        max_drawdown: report.max_drawdown,
+        max_drawdown_pct: report.max_drawdown_pct,
+        ending_equity: equity_curve.last().map(|(_, equity)| *equity),
+        computed_at: Utc::now(),
+    })
+}
+
+/// Computes and saves the rollup of `day` and of the week it belongs to, replacing any
+/// earlier ones. A week is rolled up again each day until it is complete.
+pub async fn roll_up_day(db_repo: &DbRepository, day: NaiveDate) -> Result<(), AnalyzerError> {
+    for granularity in [RollupGranularity::Daily, RollupGranularity::Weekly] {
+        let rollup = compute_rollup(db_repo, granularity, period_start(granularity, day)).await?;
+        db_repo.upsert_performance_rollup(granularity, &rollup).await?;
+    }
+    Ok(())
+}
+
+/// Recomputes every day from `from` to `to`, both inclusive, and every week those days
+/// belong to, from the stored data. Recomputing the same days again stores the same figures.
+pub async fn recompute(db_repo: &DbRepository, from: NaiveDate, to: NaiveDate) -> Result<RecomputeSummary, AnalyzerError> {
+    let mut summary = RecomputeSummary::default();
+    let mut weeks = BTreeSet::new();
+    for day in from.iter_days().take_while(|day| *day <= to) {
+        let rollup = compute_rollup(db_repo, RollupGranularity::Daily, day).await?;
+        db_repo.upsert_performance_rollup(RollupGranularity::Daily, &rollup).await?;
+        weeks.insert(period_start(RollupGranularity::Weekly, day));
+        summary.days += 1;
+    }
+    for week in weeks {
+        let rollup = compute_rollup(db_repo, RollupGranularity::Weekly, week).await?;
+        db_repo.upsert_performance_rollup(RollupGranularity::Weekly, &rollup).await?;
+        summary.weeks += 1;
+    }
+    Ok(summary)
+}
+
+/// A reducing or closing fill as a `Trade` that `AnalyticsEngine` can score. The exit is
+/// the fill itself; the entry is at the position's average entry price, recovered from the
+/// P&L the fill locked in, so the trade's P&L is exactly that. Entry fees belong to the
+/// period of the fill that paid them and are left out here.
+pub(crate) fn closing_trade(fill: &LiveFill) -> Trade {
+    let pnl_per_unit = if fill.quantity.is_zero() { Decimal::ZERO } else { fill.realized_pnl / fill.quantity };
+    let entry_price = match fill.position_side {
+        OrderSide::Buy => fill.price - pnl_per_unit,
+        OrderSide::Sell => fill.price + pnl_per_unit,
+    };
+    let exit_execution = Execution {
+        execution_id: fill.execution_id,
+        client_order_id: Uuid::nil(),
+        symbol: fill.symbol.clone(),
+        side: fill.position_side.opposite(),
+        price: fill.price,
+        quantity: fill.quantity,
+        fee: fill.fee,
+        fee_asset: fill.fee_asset.clone(),
+        timestamp: fill.executed_at,
+        decision_id: None,
+        is_maker: fill.is_maker,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    let entry_execution =
+        Execution { side: fill.position_side, price: entry_price, fee: Decimal::ZERO, is_maker: false, ..exit_execution.clone() };
+    Trade {
+        trade_id: fill.execution_id,
+        symbol: fill.symbol.clone(),
+        entry_execution,
+        exit_execution,
+        // A partial close carries no reason of its own.
+        close_reason: fill.close_reason.unwrap_or(CloseReason::Signal),
+        // Live fills do not record the stop they were entered with.
+        initial_risk: None,
+    }
+}
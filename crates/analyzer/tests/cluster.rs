@@ -0,0 +1,164 @@
+//! Clusters the best runs of synthetic parameter grids into distinct regions.
+
+use analyzer::{cluster_runs, ClusterOptions, ParameterSpace, RankedReport};
+use database::FullReport;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+/// A run with `parameters`; its metrics are all missing.
+fn report(parameters: Value) -> FullReport {
+    FullReport {
+        run_id: Uuid::new_v4(),
+        job_id: Uuid::nil(),
+        parameters,
+        report_id: Some(Uuid::new_v4()),
+        total_net_profit: None,
+        gross_profit: None,
+        gross_loss: None,
+        profit_factor: None,
+        total_return_pct: None,
+        max_drawdown: None,
+        max_drawdown_pct: None,
+        sharpe_ratio: None,
+        calmar_ratio: None,
+        total_trades: None,
+        winning_trades: None,
+        losing_trades: None,
+        win_rate_pct: None,
+        average_win: None,
+        average_loss: None,
+        payoff_ratio: None,
+        max_consecutive_losses: None,
+        average_holding_period: None,
+        maker_fees_paid: None,
+        taker_fees_paid: None,
+        total_spread_cost: None,
+        gap_slippage: None,
+        exit_breakdown: None,
+        average_r: None,
+        expectancy_r: None,
+        r_distribution: None,
+        average_exposure_pct: None,
+        peak_exposure_pct: None,
+        time_in_market_pct: None,
+        return_on_margin_pct: None,
+        exposure_adjusted_sharpe: None,
+        started_at: None,
+        finished_at: None,
+        bars_processed: None,
+        signals_filtered: None,
+        brackets_stop_first: None,
+        brackets_target_first: None,
+        engine_version: None,
+        config_hash: None,
+        objective_value: None,
+    }
+}
+
+/// Ranks runs of `parameters` by `score`, best first.
+fn ranked(runs: Vec<(Value, f64)>) -> Vec<RankedReport> {
+    let mut ranked: Vec<RankedReport> = runs
+        .into_iter()
+        .map(|(parameters, score)| RankedReport {
+            parameters: parameters.clone(),
+            score: Decimal::from_f64(score).unwrap(),
+            report: report(parameters),
+        })
+        .collect();
+    ranked.sort_by_key(|run| std::cmp::Reverse(run.score));
+    ranked
+}
+
+/// A 7 x 8 grid of MA periods (fast 5 to 35, slow 20 to 90) scored by two peaks: the best at
+/// fast 15 / slow 40 and a slightly lower one at fast 30 / slow 70. The score falls by one per
+/// squared grid step from each peak. The slow period is stored as a decimal string and every
+/// run has the same non-numeric mode, like the optimizer stores them.
+fn two_peaks() -> Vec<RankedReport> {
+    let mut runs = Vec::new();
+    for i in 0..7 {
+        for j in 0..8 {
+            let from = |(pi, pj): (i32, i32)| f64::from((i - pi).pow(2) + (j - pj).pow(2));
+            let score = (10.0 - from((2, 2))).max(9.05 - from((5, 5)));
+            let parameters = json!({
+                "ma_fast_period": 5 + 5 * i,
+                "ma_slow_period": format!("{}.0", 20 + 10 * j),
+                "mode": "close",
+            });
+            runs.push((parameters, score));
+        }
+    }
+    ranked(runs)
+}
+
+#[test]
+fn a_two_peak_landscape_yields_two_clusters_around_the_peaks() {
+    let ranked = two_peaks();
+    let space = ParameterSpace::from_grid(ranked.iter().map(|run| &run.parameters));
+    assert_eq!(space.excluded, ["mode"]);
+
+    // The top quarter is the 3 x 3 block around the best peak and the cross around the other.
+    let clustering = cluster_runs(space, &ranked, &ClusterOptions::default());
+
+    assert_eq!(clustering.clusters.len(), 2);
+    let expected = [((15.0, 40.0), 9, dec(10.0)), ((30.0, 70.0), 5, dec(9.05))];
+    for (cluster, ((fast, slow), members, best_score)) in clustering.clusters.iter().zip(expected) {
+        assert!((cluster.centroid["ma_fast_period"] - fast).abs() < 1e-9, "{:?}", cluster.centroid);
+        assert!((cluster.centroid["ma_slow_period"] - slow).abs() < 1e-9, "{:?}", cluster.centroid);
+        assert_eq!(cluster.members(), members);
+        assert_eq!(cluster.best_score, best_score);
+    }
+    assert_eq!(clustering.clusters[0].best_run, ranked[0].report.run_id);
+    assert_eq!(clustering.clusters[1].best_run, ranked[1].report.run_id);
+    // The block around the best peak: 10, four runs of 9 and four of 8.
+    assert_eq!(clustering.clusters[0].mean_score, Decimal::from(78) / Decimal::from(9));
+
+    assert_eq!(clustering.assignments.len(), 14);
+    assert_eq!(clustering.cluster_of(ranked[0].report.run_id), Some(0));
+    assert_eq!(clustering.cluster_of(ranked[1].report.run_id), Some(1));
+    assert_eq!(clustering.cluster_of(ranked[14].report.run_id), None);
+}
+
+#[test]
+fn an_isolated_top_run_belongs_to_no_cluster() {
+    // A lone spike far from a plateau of four neighbouring runs.
+    let ranked = ranked(vec![
+        (json!({ "period": 10 }), 5.0),
+        (json!({ "period": 40 }), 4.0),
+        (json!({ "period": 45 }), 4.0),
+        (json!({ "period": 50 }), 4.0),
+        (json!({ "period": 55 }), 4.0),
+        (json!({ "period": 15 }), 0.0),
+        (json!({ "period": 20 }), 0.0),
+        (json!({ "period": 25 }), 0.0),
+        (json!({ "period": 30 }), 0.0),
+        (json!({ "period": 35 }), 0.0),
+    ]);
+    let space = ParameterSpace::from_grid(ranked.iter().map(|run| &run.parameters));
+    let options = ClusterOptions { top_fraction: 0.5, ..ClusterOptions::default() };
+
+    let clustering = cluster_runs(space, &ranked, &options);
+
+    assert_eq!(clustering.clusters.len(), 1);
+    assert_eq!(clustering.clusters[0].members(), 4);
+    assert!((clustering.clusters[0].centroid["period"] - 47.5).abs() < 1e-9);
+    assert_eq!(clustering.cluster_of(ranked[0].report.run_id), None);
+}
+
+#[test]
+fn a_parameter_some_runs_lack_is_left_out() {
+    let grid = [json!({ "period": 10, "threshold": "1.5" }), json!({ "period": 20 }), json!({ "period": 30, "threshold": "2.5" })];
+
+    let space = ParameterSpace::from_grid(&grid);
+
+    assert_eq!(space.dimensions.len(), 1);
+    assert_eq!(space.dimensions[0].name, "period");
+    assert_eq!((space.dimensions[0].min, space.dimensions[0].max), (10.0, 30.0));
+    assert_eq!(space.dimensions[0].step, 0.5);
+    assert_eq!(space.excluded, ["threshold"]);
+}
+
+fn dec(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap()
+}
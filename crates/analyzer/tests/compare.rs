@@ -0,0 +1,172 @@
+//! Checks the side-by-side comparison of two backtest runs.
+
+use analyzer::compare::{CompareOptions, MetricDelta, TradeOverlap};
+use analyzer::compare_runs;
+use analyzer::error::AnalyzerError;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use database::{BacktestRunDetails, EquityDataPoint, FullReport};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+fn hour(n: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(n)
+}
+
+fn execution(side: OrderSide, timestamp: DateTime<Utc>) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price: Decimal::from(100),
+        quantity: Decimal::ONE,
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp,
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+fn report(net_profit: Decimal, total_trades: i32) -> FullReport {
+    FullReport {
+        run_id: Uuid::new_v4(),
+        job_id: Uuid::new_v4(),
+        parameters: serde_json::json!({}),
+        report_id: Some(Uuid::new_v4()),
+        total_net_profit: Some(net_profit),
+        gross_profit: None,
+        gross_loss: None,
+        profit_factor: None,
+        total_return_pct: None,
+        max_drawdown: None,
+        max_drawdown_pct: None,
+        sharpe_ratio: Some(Decimal::ZERO),
+        calmar_ratio: None,
+        total_trades: Some(total_trades),
+        winning_trades: None,
+        losing_trades: None,
+        win_rate_pct: None,
+        average_win: None,
+        average_loss: None,
+        payoff_ratio: None,
+        max_consecutive_losses: None,
+        average_holding_period: None,
+        maker_fees_paid: None,
+        taker_fees_paid: None,
+        total_spread_cost: None,
+        gap_slippage: None,
+        exit_breakdown: None,
+        average_r: None,
+        expectancy_r: None,
+        r_distribution: None,
+        average_exposure_pct: None,
+        peak_exposure_pct: None,
+        time_in_market_pct: None,
+        return_on_margin_pct: None,
+        exposure_adjusted_sharpe: None,
+        started_at: None,
+        finished_at: None,
+        bars_processed: None,
+        signals_filtered: None,
+        brackets_stop_first: None,
+        brackets_target_first: None,
+        engine_version: None,
+        config_hash: None,
+        objective_value: None,
+    }
+}
+
+/// An hourly run over `hours`, with the given equity at each hour and a two-hour trade
+/// entered at each hour of `entries`.
+fn run(hours: std::ops::Range<i64>, equity: impl Fn(i64) -> i64, entries: &[i64], net_profit: Decimal) -> BacktestRunDetails {
+    BacktestRunDetails {
+        report: report(net_profit, entries.len() as i32),
+        trades: entries
+            .iter()
+            .map(|&h| Trade {
+                trade_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                entry_execution: execution(OrderSide::Buy, hour(h)),
+                exit_execution: execution(OrderSide::Sell, hour(h + 2)),
+                close_reason: CloseReason::Signal,
+                initial_risk: None,
+            })
+            .collect(),
+        equity_curve: hours.map(|h| EquityDataPoint { timestamp: hour(h), equity: Decimal::from(equity(h)) }).collect(),
+    }
+}
+
+fn metric<'a>(metrics: &'a [MetricDelta], name: &str) -> &'a MetricDelta {
+    metrics.iter().find(|m| m.metric == name).unwrap()
+}
+
+#[test]
+fn runs_over_the_same_range_are_compared_in_full() {
+    let a = run(0..100, |_| 10_000, &[10, 30, 50, 70], Decimal::from(200));
+    // Entry at 30 shifted by one bar, 70 gone, 90 new; equity diverges from hour 40.
+    let b = run(0..100, |h| if h < 40 { 10_000 } else { 10_050 }, &[10, 31, 50, 90], Decimal::from(250));
+
+    let comparison = compare_runs(&a, &b, &CompareOptions::default()).unwrap();
+
+    assert!(!comparison.window.overlap_only);
+    assert_eq!((comparison.window.start, comparison.window.end), (hour(0), hour(99)));
+    assert_eq!(comparison.trades, TradeOverlap { shared: 3, only_in_a: 1, only_in_b: 1 });
+    assert_eq!(comparison.equity_divergence, Some(hour(40)));
+
+    let profit = metric(&comparison.metrics, "Total Net Profit");
+    assert_eq!((profit.delta, profit.delta_pct), (Some(Decimal::from(50)), Some(Decimal::from(25))));
+    let sharpe = metric(&comparison.metrics, "Sharpe Ratio");
+    assert_eq!((sharpe.delta, sharpe.delta_pct), (Some(Decimal::ZERO), None));
+    assert_eq!(metric(&comparison.metrics, "Gross Profit").delta, None);
+}
+
+#[test]
+fn the_equity_epsilon_and_bar_are_configurable() {
+    let a = run(0..10, |_| 10_000, &[2], Decimal::ZERO);
+    let b = run(0..10, |h| 10_000 + h, &[4], Decimal::ZERO);
+
+    let loose = CompareOptions { equity_epsilon: Decimal::from(5), bar: Some(Duration::hours(2)) };
+    let comparison = compare_runs(&a, &b, &loose).unwrap();
+    assert_eq!(comparison.equity_divergence, Some(hour(6)));
+    assert_eq!(comparison.trades, TradeOverlap { shared: 1, only_in_a: 0, only_in_b: 0 });
+
+    let strict = compare_runs(&a, &b, &CompareOptions::default()).unwrap();
+    assert_eq!(strict.equity_divergence, Some(hour(1)));
+    assert_eq!(strict.trades, TradeOverlap { shared: 0, only_in_a: 1, only_in_b: 1 });
+}
+
+#[test]
+fn runs_over_different_ranges_compare_only_their_overlap() {
+    let a = run(0..100, |_| 10_000, &[10, 60], Decimal::ZERO);
+    // The entries at 10 and 150 fall outside the overlap; the jump at hour 95 is inside it.
+    let b = run(50..200, |h| if h < 95 { 10_000 } else { 12_000 }, &[60, 95, 150], Decimal::ZERO);
+    let comparison = compare_runs(&a, &b, &CompareOptions::default()).unwrap();
+
+    assert!(comparison.window.overlap_only);
+    assert_eq!((comparison.window.start, comparison.window.end), (hour(50), hour(99)));
+    assert_eq!(comparison.trades, TradeOverlap { shared: 1, only_in_a: 0, only_in_b: 1 });
+    assert_eq!(comparison.equity_divergence, Some(hour(95)));
+
+    let disjoint = run(200..300, |_| 10_000, &[], Decimal::ZERO);
+    assert!(matches!(compare_runs(&a, &disjoint, &CompareOptions::default()), Err(AnalyzerError::NoOverlap(..))));
+}
+
+#[test]
+fn runs_over_different_ranges_compare_metrics_recomputed_over_their_overlap() {
+    let a = run(0..100, |_| 10_000, &[10, 60], Decimal::from(500));
+    let b = run(50..200, |_| 10_000, &[60, 95, 150], Decimal::from(900));
+    let comparison = compare_runs(&a, &b, &CompareOptions::default()).unwrap();
+
+    // Only the trades closed within hours 50 to 99 count, and each of them breaks even,
+    // whatever the stored reports say of the full ranges.
+    let trades = metric(&comparison.metrics, "Total Trades");
+    assert_eq!((trades.a, trades.b), (Some(Decimal::ONE), Some(Decimal::from(2))));
+    let profit = metric(&comparison.metrics, "Total Net Profit");
+    assert_eq!((profit.a, profit.b, profit.delta), (Some(Decimal::ZERO), Some(Decimal::ZERO), Some(Decimal::ZERO)));
+    // The recomputed reports are not annualized.
+    assert_eq!(metric(&comparison.metrics, "Sharpe Ratio").a, None);
+}
@@ -0,0 +1,177 @@
+//! Checks the export of ranked results and the portfolio generated from the best runs.
+
+use analyzer::error::AnalyzerError;
+use analyzer::export::{write_csv, write_json};
+use analyzer::{portfolio_toml, Analyzer, RankedReport};
+use chrono::Utc;
+use configuration::load_portfolio_config;
+use configuration::optimizer_config::AnalysisConfig;
+use database::{DbOptimizationJob, FullReport};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use strategies::{create_strategy_from_params, merge_params};
+use testing::test_config;
+use uuid::Uuid;
+
+fn report(parameters: Value, total_trades: i32, max_drawdown_pct: Decimal) -> FullReport {
+    FullReport {
+        run_id: Uuid::new_v4(),
+        job_id: Uuid::new_v4(),
+        parameters,
+        report_id: Some(Uuid::new_v4()),
+        total_net_profit: Some(Decimal::new(12345, 2)),
+        gross_profit: None,
+        gross_loss: None,
+        profit_factor: Some(Decimal::new(15, 1)),
+        total_return_pct: None,
+        max_drawdown: None,
+        max_drawdown_pct: Some(max_drawdown_pct),
+        sharpe_ratio: None,
+        calmar_ratio: None,
+        total_trades: Some(total_trades),
+        winning_trades: None,
+        losing_trades: None,
+        win_rate_pct: None,
+        average_win: None,
+        average_loss: None,
+        payoff_ratio: None,
+        max_consecutive_losses: None,
+        average_holding_period: None,
+        maker_fees_paid: None,
+        taker_fees_paid: None,
+        total_spread_cost: None,
+        gap_slippage: None,
+        exit_breakdown: None,
+        average_r: None,
+        expectancy_r: None,
+        r_distribution: None,
+        average_exposure_pct: None,
+        peak_exposure_pct: None,
+        time_in_market_pct: None,
+        return_on_margin_pct: None,
+        exposure_adjusted_sharpe: None,
+        started_at: None,
+        finished_at: None,
+        bars_processed: None,
+        signals_filtered: None,
+        brackets_stop_first: None,
+        brackets_target_first: None,
+        engine_version: None,
+        config_hash: None,
+        objective_value: None,
+    }
+}
+
+/// SuperTrend runs, best first, with parameters stored the way the optimizer stores them:
+/// integers as numbers and decimals as strings.
+fn ranked(count: usize) -> Vec<RankedReport> {
+    (0..count)
+        .map(|i| {
+            let parameters = json!({
+                "atr_period": 10 + i,
+                "atr_multiplier": format!("{}.5", 2 + i),
+                "adx_threshold": "25",
+                "adx_period": 14,
+            });
+            RankedReport {
+                parameters: parameters.clone(),
+                score: Decimal::ONE - Decimal::new(i as i64, 1),
+                report: report(parameters, 40, Decimal::from(10)),
+            }
+        })
+        .collect()
+}
+
+fn job() -> DbOptimizationJob {
+    DbOptimizationJob {
+        job_id: Uuid::new_v4(),
+        strategy_id: "SuperTrend".to_string(),
+        symbol: "BTCUSDT".to_string(),
+        job_status: "Completed".to_string(),
+        created_at: Utc::now(),
+        completed_runs: 2,
+        total_runs: 2,
+        updated_at: Utc::now(),
+        estimated_completion_at: None,
+    }
+}
+
+#[test]
+fn csv_export_flattens_metrics_and_parameters_into_columns() {
+    let mut ranked = ranked(2);
+    ranked[1].parameters.as_object_mut().unwrap().remove("adx_period");
+    let mut csv = Vec::new();
+    write_csv(&ranked, &mut csv).unwrap();
+
+    let csv = String::from_utf8(csv).unwrap();
+    let rows: Vec<Vec<&str>> = csv.lines().map(|line| line.split(',').collect()).collect();
+    assert_eq!(rows.len(), 3);
+    let column = |name: &str| rows[0].iter().position(|c| *c == name).unwrap_or_else(|| panic!("no column {}", name));
+    assert_eq!(&rows[0][..2], ["rank", "score"]);
+    assert!(!rows[0].contains(&"parameters"));
+
+    assert_eq!(rows[1][column("rank")], "1");
+    assert_eq!(rows[2][column("rank")], "2");
+    assert_eq!(rows[1][column("total_net_profit")], "123.45");
+    assert_eq!(rows[1][column("total_trades")], "40");
+    assert_eq!(rows[1][column("sharpe_ratio")], "");
+    assert_eq!(rows[2][column("param.atr_multiplier")], "3.5");
+    assert_eq!(rows[1][column("param.adx_period")], "14");
+    assert_eq!(rows[2][column("param.adx_period")], "");
+}
+
+#[test]
+fn json_export_keeps_the_ranking() {
+    let ranked = ranked(3);
+    let mut json = Vec::new();
+    write_json(&ranked, &mut json).unwrap();
+
+    let rows: Vec<Value> = serde_json::from_slice(&json).unwrap();
+    assert_eq!(rows.len(), 3);
+    assert_eq!(rows[2]["rank"], 3);
+    assert_eq!(rows[2]["parameters"]["atr_period"], 12);
+    assert_eq!(rows[2]["report"]["run_id"], ranked[2].report.run_id.to_string());
+}
+
+#[test]
+fn generated_portfolio_loads_and_builds_its_strategies() {
+    let (job, ranked) = (job(), ranked(5));
+    let analyzer = Analyzer::new(AnalysisConfig::default());
+    let portfolio = analyzer.portfolio_from_top_runs(&job, &ranked, 3).unwrap();
+    let toml = portfolio_toml(&job, &ranked, &portfolio).unwrap();
+
+    let path = std::env::temp_dir().join(format!("portfolio-{}.toml", Uuid::new_v4()));
+    std::fs::write(&path, &toml).unwrap();
+    let loaded = load_portfolio_config(&path);
+    std::fs::remove_file(&path).unwrap();
+    let loaded = loaded.unwrap_or_else(|e| panic!("{}\n{}", e, toml));
+
+    let base_config = test_config(100).expect("load config");
+    assert_eq!(loaded.bots.len(), 3);
+    for (i, bot) in loaded.bots.iter().enumerate() {
+        assert_eq!(bot.symbol, "BTCUSDT");
+        assert_eq!(bot.name.as_deref(), Some(format!("rank-{}", i + 1).as_str()));
+        // Integers stay integers and decimals become floats.
+        assert_eq!(bot.params["atr_period"], json!(10 + i));
+        assert_eq!(bot.params["atr_multiplier"], json!(2.5 + i as f64));
+
+        let params = merge_params(bot.strategy_id, &base_config, &bot.params).unwrap();
+        create_strategy_from_params(bot.strategy_id, &params, &bot.symbol).unwrap();
+    }
+}
+
+#[test]
+fn generator_refuses_runs_that_fail_the_filters() {
+    let (job, mut ranked) = (job(), ranked(5));
+    let analyzer = Analyzer::new(AnalysisConfig::default());
+
+    // Too few runs to fill the portfolio.
+    let error = analyzer.portfolio_from_top_runs(&job, &ranked, 6).unwrap_err();
+    assert!(matches!(error, AnalyzerError::TooFewPassingRuns { requested: 6, passing: 5, .. }));
+
+    // A top run with too few trades.
+    ranked[1].report.total_trades = Some(3);
+    let error = analyzer.portfolio_from_top_runs(&job, &ranked, 3).unwrap_err();
+    assert!(matches!(error, AnalyzerError::TooFewPassingRuns { requested: 3, passing: 1, .. }));
+    assert!(analyzer.portfolio_from_top_runs(&job, &ranked, 1).is_ok());
+}
@@ -0,0 +1,143 @@
+//! Checks the analyzer's hard filters and the funnel counting the runs each one removes.
+
+use analyzer::{Analyzer, FunnelStage};
+use configuration::optimizer_config::{AnalysisConfig, Filters};
+use database::FullReport;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use uuid::Uuid;
+
+/// A run with the metrics the filters look at; everything else is missing.
+fn report(total_trades: i32, max_drawdown_pct: Decimal, sharpe: Option<Decimal>, win_rate: Option<Decimal>, profit_factor: Option<Decimal>, losing_streak: Option<i32>) -> FullReport {
+    FullReport {
+        run_id: Uuid::new_v4(),
+        job_id: Uuid::nil(),
+        parameters: json!({}),
+        report_id: Some(Uuid::new_v4()),
+        total_net_profit: None,
+        gross_profit: None,
+        gross_loss: None,
+        profit_factor,
+        total_return_pct: None,
+        max_drawdown: None,
+        max_drawdown_pct: Some(max_drawdown_pct),
+        sharpe_ratio: sharpe,
+        calmar_ratio: None,
+        total_trades: Some(total_trades),
+        winning_trades: None,
+        losing_trades: None,
+        win_rate_pct: win_rate,
+        average_win: None,
+        average_loss: None,
+        payoff_ratio: None,
+        max_consecutive_losses: losing_streak,
+        average_holding_period: None,
+        maker_fees_paid: None,
+        taker_fees_paid: None,
+        total_spread_cost: None,
+        gap_slippage: None,
+        exit_breakdown: None,
+        average_r: None,
+        expectancy_r: None,
+        r_distribution: None,
+        average_exposure_pct: None,
+        peak_exposure_pct: None,
+        time_in_market_pct: None,
+        return_on_margin_pct: None,
+        exposure_adjusted_sharpe: None,
+        started_at: None,
+        finished_at: None,
+        bars_processed: None,
+        signals_filtered: None,
+        brackets_stop_first: None,
+        brackets_target_first: None,
+        engine_version: None,
+        config_hash: None,
+        objective_value: None,
+    }
+}
+
+/// A run passing every filter of `strict_filters`.
+fn good() -> FullReport {
+    report(60, dec!(10), Some(dec!(1.2)), Some(dec!(45)), Some(dec!(1.6)), Some(4))
+}
+
+fn analyzer(filters: Filters) -> Analyzer {
+    Analyzer::new(AnalysisConfig { filters, ..AnalysisConfig::default() })
+}
+
+fn strict_filters() -> Filters {
+    Filters {
+        min_total_trades: 20,
+        max_drawdown_pct: dec!(25),
+        min_sharpe_ratio: Some(dec!(0.5)),
+        min_win_rate_pct: Some(dec!(35)),
+        min_profit_factor: Some(dec!(1.2)),
+        max_consecutive_losses: Some(8),
+    }
+}
+
+fn stage(filter: &str, eliminated: usize, remaining: usize) -> FunnelStage {
+    FunnelStage { filter: filter.to_string(), eliminated, remaining }
+}
+
+#[test]
+fn funnel_counts_each_run_against_the_first_filter_it_fails() {
+    let reports = vec![
+        good(),
+        good(),
+        // Too few trades and a low Sharpe: counted against the trade count only.
+        report(5, dec!(10), Some(dec!(0.1)), Some(dec!(45)), Some(dec!(1.6)), Some(4)),
+        report(60, dec!(40), Some(dec!(1.2)), Some(dec!(45)), Some(dec!(1.6)), Some(4)),
+        report(60, dec!(10), Some(dec!(0.3)), Some(dec!(45)), Some(dec!(1.6)), Some(4)),
+        // A flat equity curve has no Sharpe ratio, which fails a Sharpe filter.
+        report(60, dec!(10), None, Some(dec!(45)), Some(dec!(1.6)), Some(4)),
+        report(60, dec!(10), Some(dec!(1.2)), Some(dec!(20)), Some(dec!(1.6)), Some(4)),
+        report(60, dec!(10), Some(dec!(1.2)), Some(dec!(45)), Some(dec!(1.1)), Some(4)),
+        // Sixty trades and a small drawdown, but a twelve-trade losing streak.
+        report(60, dec!(10), Some(dec!(1.2)), Some(dec!(45)), Some(dec!(1.6)), Some(12)),
+        // Recorded before streaks were measured.
+        report(60, dec!(10), Some(dec!(1.2)), Some(dec!(45)), Some(dec!(1.6)), None),
+    ];
+
+    let analyzer = analyzer(strict_filters());
+    let funnel = analyzer.filter_funnel(&reports);
+
+    assert_eq!(funnel.total, 10);
+    assert_eq!(
+        funnel.stages,
+        [
+            stage("min_total_trades = 20", 1, 9),
+            stage("max_drawdown_pct = 25", 1, 8),
+            stage("min_sharpe_ratio = 0.5", 2, 6),
+            stage("min_win_rate_pct = 35", 1, 5),
+            stage("min_profit_factor = 1.2", 1, 4),
+            stage("max_consecutive_losses = 8", 2, 2),
+        ]
+    );
+    assert_eq!(funnel.passed(), 2);
+    assert_eq!(reports.iter().filter(|r| analyzer.passes_filters(r)).count(), 2);
+}
+
+#[test]
+fn unset_filters_are_skipped_and_missing_metrics_pass_them() {
+    let reports = vec![good(), report(60, dec!(10), None, None, None, None), report(5, dec!(10), None, None, None, None)];
+
+    let analyzer = analyzer(Filters { min_total_trades: 20, max_drawdown_pct: dec!(25), ..Filters::default() });
+    let funnel = analyzer.filter_funnel(&reports);
+
+    assert_eq!(funnel.stages, [stage("min_total_trades = 20", 1, 2), stage("max_drawdown_pct = 25", 0, 2)]);
+    assert!(analyzer.passes_filters(&reports[1]));
+}
+
+#[test]
+fn existing_filter_configs_still_load() {
+    let filters: Filters = toml::from_str("min_total_trades = 20\nmax_drawdown_pct = 30.0").unwrap();
+    assert_eq!(filters.min_sharpe_ratio, None);
+    assert_eq!(filters.max_consecutive_losses, None);
+
+    let filters: Filters = toml::from_str("min_total_trades = 20\nmax_drawdown_pct = 30.0\nmin_win_rate_pct = 40\nmax_consecutive_losses = 6").unwrap();
+    assert_eq!(filters.min_win_rate_pct, Some(dec!(40)));
+    assert_eq!(filters.max_consecutive_losses, Some(6));
+}
@@ -0,0 +1,128 @@
+//! Checks the reports over hand-picked trades, such as every trade annotated with a tag.
+//!
+//! The database test is ignored by default. Run it with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p analyzer -- --ignored
+//! ```
+
+use analyzer::{report_for_trades, subset_report};
+use chrono::{Duration, Utc};
+use core_types::{CloseReason, Execution, OrderSide, PositionFill, Trade};
+use database::{Annotation, AnnotationFilter, AnnotationTarget, DbRepository};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{seed_start, TestDatabase, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// A long bought at 100 `hour` hours into the seeded series and sold at 100 + `pnl` an hour later.
+fn trade(hour: i64, pnl: i64) -> Trade {
+    let execution = |side, price: i64, hours| Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side,
+        price: Decimal::from(price),
+        quantity: Decimal::ONE,
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: seed_start() + Duration::hours(hours),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        entry_execution: execution(OrderSide::Buy, 100, hour),
+        exit_execution: execution(OrderSide::Sell, 100 + pnl, hour + 1),
+        close_reason: CloseReason::Signal,
+        initial_risk: None,
+    }
+}
+
+/// Records a live long of one unit bought at 100 `hour` hours into the seeded series and sold
+/// at 100 + `pnl` an hour later, paying 0.1 on each fill. Returns its position ID.
+async fn live_position(repo: &DbRepository, hour: i64, pnl: i64) -> Uuid {
+    let position_id = Uuid::new_v4();
+    for (side, price, hours, is_entry) in [(OrderSide::Buy, 100, hour, true), (OrderSide::Sell, 100 + pnl, hour + 1, false)] {
+        let mut execution = trade(0, 0).entry_execution;
+        (execution.side, execution.price, execution.fee) = (side, Decimal::from(price), dec!(0.1));
+        execution.timestamp = seed_start() + Duration::hours(hours);
+        let fill = PositionFill {
+            position_id,
+            position_side: OrderSide::Buy,
+            is_entry,
+            quantity: Decimal::ONE,
+            fee: dec!(0.1),
+            realized_pnl: if is_entry { Decimal::ZERO } else { Decimal::from(pnl) },
+            position_quantity: if is_entry { Decimal::ONE } else { Decimal::ZERO },
+        };
+        repo.save_live_execution(&execution, &[fill], (!is_entry).then_some(CloseReason::Signal)).await.unwrap();
+    }
+    position_id
+}
+
+#[test]
+fn a_subset_is_taken_in_the_order_its_trades_closed() {
+    // Out of order: the loss closes between the two wins.
+    let trades = [trade(4, 20), trade(0, 10), trade(2, -15)];
+
+    let report = subset_report(&trades, dec!(1000)).unwrap();
+
+    assert_eq!(report.total_trades, 3);
+    assert_eq!(report.total_net_profit, dec!(15));
+    assert_eq!(report.total_return_pct, dec!(1.5));
+    assert_eq!(report.win_rate_pct.map(|pct| pct.round_dp(2)), Some(dec!(66.67)));
+    // From 1010 down to 995.
+    assert_eq!(report.max_drawdown, dec!(15));
+    assert!(report.sharpe_ratio.is_none());
+}
+
+#[test]
+fn an_empty_subset_reports_nothing() {
+    let report = subset_report(&[], dec!(1000)).unwrap();
+    assert_eq!(report.total_trades, 0);
+    assert_eq!(report.total_net_profit, Decimal::ZERO);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn the_trades_tagged_with_a_condition_are_reported_alone() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let job_id = Uuid::new_v4();
+    let run_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &serde_json::json!({}), "Completed").await.unwrap();
+    let trades = [trade(0, 10), trade(2, -15), trade(4, 20), trade(6, -5)];
+    repo.save_trades(run_id, &trades).await.unwrap();
+    let mut targets: Vec<(AnnotationTarget, Uuid)> = trades.iter().map(|trade| (AnnotationTarget::Trade, trade.trade_id)).collect();
+    targets.push((AnnotationTarget::Position, live_position(&repo, 8, 30).await));
+    for ((target_type, target_id), tag) in targets.into_iter().zip(["news-event", "quiet", "news-event", "news-event", "news-event"]) {
+        let annotation = Annotation {
+            annotation_id: Uuid::new_v4(),
+            target_type,
+            target_id,
+            author: "ana".to_string(),
+            text: "Tagged for review".to_string(),
+            tags: vec![tag.to_string()],
+            created_at: Utc::now(),
+        };
+        repo.save_annotation(&annotation).await.unwrap();
+    }
+
+    let tagged = repo.get_annotations(&AnnotationFilter { tag: Some("news-event".to_string()), ..Default::default() }).await.unwrap();
+    let trade_ids: Vec<Uuid> = tagged.iter().map(|annotation| annotation.target_id).collect();
+    let report = report_for_trades(&repo, &trade_ids, dec!(1000)).await.unwrap();
+
+    // The live position counts alongside the backtest trades, with its fees.
+    assert_eq!(report.total_trades, 4);
+    assert_eq!(report.total_net_profit, dec!(55));
+    assert_eq!(report.winning_trades, 3);
+    assert_eq!(report.taker_fees_paid, dec!(0.2));
+    assert_eq!(report.max_drawdown, dec!(5));
+
+    db.teardown().await.expect("drop test database");
+}
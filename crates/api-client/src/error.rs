@@ -16,4 +16,7 @@ pub enum ApiError {
 
     #[error("Binance API Error (code: {0}): {1}")]
     BinanceError(i16, String),
+
+    #[error("Fetching took more than {0} requests; narrow the requested range.")]
+    TooManyPages(usize),
 }
\ No newline at end of file
@@ -2,23 +2,27 @@ use crate::auth::sign_request;
 use crate::error::ApiError;
 use async_trait::async_trait;
 use chrono::{DateTime, TimeZone, Utc};
-use configuration::settings::ApiConfig;
-use core_types::{Kline, OrderRequest};
+use configuration::settings::{ApiConfig, ApiKeys};
+use core_types::{Kline, OrderRequest, OrderType, TimeInForce};
 use reqwest::header::{HeaderMap, HeaderValue};
 use rust_decimal::Decimal;
 use serde::{de::DeserializeOwned, Deserialize};
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashSet};
 use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 mod auth;
 pub mod error;
 pub mod responses;
 pub mod live_connector;
+#[cfg(feature = "scripted")]
+pub mod scripted;
 
 // --- Public API ---
-pub use responses::{BalanceResponse, OrderResponse, PositionResponse, ApiErrorResponse, ExchangeInfoResponse, PositionModeResponse};
-pub use live_connector::{BookTickerUpdate, LiveConnector, MarkPriceUpdate};
+pub use responses::{BalanceResponse, OrderResponse, PositionResponse, ApiErrorResponse, ExchangeInfoResponse, PositionModeResponse, UserTradeResponse, MarkPriceResponse, LeverageBracket, SymbolBracketsResponse, IncomeRecord, IncomeType};
+pub use live_connector::{BookTickerUpdate, LiveConnector, MarkPriceUpdate, MarketDataConnector};
 /// The generic, abstract interface for a trading exchange API client.
 /// This trait is the contract that the live engine will use, allowing the
 /// underlying implementation (live or mock) to be swapped out.
@@ -33,15 +37,32 @@ pub trait ApiClient: Send + Sync {
         end_time: DateTime<Utc>,
     ) -> Result<Vec<Kline>, ApiError>;
 
+    /// Fetches public historical mark price klines. Their volume is always zero.
+    async fn fetch_mark_price_klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Kline>, ApiError>;
+
     /// Sets the leverage for a given symbol. (Authenticated)
     async fn set_leverage(&self, symbol: &str, leverage: u8) -> Result<(), ApiError>;
 
-    /// Places a new order on the exchange. (Authenticated)
+    /// Places a new order on the exchange. LIMIT orders are sent with their price and time
+    /// in force, and reduce-only orders with `reduceOnly`. (Authenticated)
     async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse, ApiError>;
 
     /// Places a new Post-Only LIMIT order on the exchange. (Authenticated)
     async fn place_limit_order(&self, order: &OrderRequest) -> Result<OrderResponse, ApiError>;
 
+    /// Fetches the individual fills (with their commissions) for a given order. (Authenticated)
+    async fn get_user_trades(&self, symbol: &str, order_id: i64) -> Result<Vec<UserTradeResponse>, ApiError>;
+
+    /// Looks up the order placed on `symbol` with `client_order_id`. None when the exchange
+    /// does not know it, e.g. because it never arrived. (Authenticated)
+    async fn get_order(&self, symbol: &str, client_order_id: &str) -> Result<Option<OrderResponse>, ApiError>;
+
     /// Fetches the current account balance for all assets. (Authenticated)
     async fn get_account_balance(&self) -> Result<Vec<BalanceResponse>, ApiError>;
 
@@ -51,6 +72,17 @@ pub trait ApiClient: Send + Sync {
     /// Fetches exchange information including symbol precision. (Public)
     async fn get_exchange_info(&self) -> Result<ExchangeInfoResponse, ApiError>;
 
+    /// Fetches the current mark price of a symbol. (Public)
+    async fn get_mark_price(&self, symbol: &str) -> Result<Decimal, ApiError>;
+
+    /// Fetches the notional brackets of a symbol, which cap the leverage it can be traded
+    /// at. (Authenticated)
+    async fn get_leverage_brackets(&self, symbol: &str) -> Result<SymbolBracketsResponse, ApiError>;
+
+    /// Fetches the account's income history booked in `[start_time, end_time)`, oldest
+    /// first: realized P&L, commissions, funding and the rest. (Authenticated)
+    async fn get_income_history(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Result<Vec<IncomeRecord>, ApiError>;
+
     /// Gets the current position mode (one-way vs hedge). (Authenticated)
     async fn get_position_mode(&self) -> Result<bool, ApiError>;
 
@@ -58,6 +90,57 @@ pub trait ApiClient: Send + Sync {
     async fn set_position_mode(&self, dual_side: bool) -> Result<(), ApiError>;
 }
 
+/// The Binance error code of a lookup of an order it does not know.
+pub const ORDER_NOT_FOUND_CODE: i16 = -2013;
+
+/// The most klines Binance returns for a single request.
+const KLINE_PAGE_LIMIT: usize = 1000;
+
+/// A sanity cap on the requests made by one `fetch_klines` or `fetch_mark_price_klines`
+/// call: over two years of 1m bars.
+const MAX_KLINE_PAGES: usize = 1100;
+
+/// The minimum time between two kline requests from one client and its clones. A full page
+/// costs 5 request weight, so this keeps kline fetching well under Binance's 2400 weight/min.
+const KLINE_REQUEST_SPACING: Duration = Duration::from_millis(150);
+
+/// The most income records Binance returns for a single request.
+const INCOME_PAGE_LIMIT: usize = 1000;
+
+/// A sanity cap on the requests made by one `get_income_history` call.
+const MAX_INCOME_PAGES: usize = 1000;
+
+/// The minimum time between two income history requests. Each costs 30 request weight, so
+/// this keeps a long history well under Binance's 2400 weight/min.
+const INCOME_REQUEST_SPACING: Duration = Duration::from_millis(1000);
+
+/// Spaces requests at least `spacing` apart, across every clone that shares it.
+#[derive(Clone)]
+struct RequestPacer {
+    spacing: Duration,
+    next_slot: Arc<tokio::sync::Mutex<tokio::time::Instant>>,
+}
+
+impl RequestPacer {
+    fn new(spacing: Duration) -> Self {
+        Self {
+            spacing,
+            next_slot: Arc::new(tokio::sync::Mutex::new(tokio::time::Instant::now())),
+        }
+    }
+
+    /// Waits until this caller's turn to send a request.
+    async fn wait(&self) {
+        let slot = {
+            let mut next_slot = self.next_slot.lock().await;
+            let slot = (*next_slot).max(tokio::time::Instant::now());
+            *next_slot = slot + self.spacing;
+            slot
+        };
+        tokio::time::sleep_until(slot).await;
+    }
+}
+
 /// A concrete implementation of the `ApiClient` for the Binance exchange.
 #[derive(Clone)]
 pub struct BinanceClient {
@@ -65,19 +148,21 @@ pub struct BinanceClient {
     base_url: String,
 
     api_secret: String,
+    kline_pacer: RequestPacer,
+    income_pacer: RequestPacer,
 }
 
 impl BinanceClient {
     pub fn new(live_mode: bool, api_config: &ApiConfig) -> Self {
-        let (base_url, keys) = if live_mode {
-            ("https://fapi.binance.com".to_string(), &api_config.production)
+        if live_mode {
+            Self::with_base_url("https://fapi.binance.com", &api_config.production)
         } else {
-            (
-                "https://testnet.binancefuture.com".to_string(),
-                &api_config.testnet,
-            )
-        };
+            Self::with_base_url("https://testnet.binancefuture.com", &api_config.testnet)
+        }
+    }
 
+    /// Creates a client for a Binance-compatible API at `base_url` (e.g., a local mock server).
+    pub fn with_base_url(base_url: &str, keys: &ApiKeys) -> Self {
         let mut headers = HeaderMap::new();
         headers.insert(
             "X-MBX-APIKEY",
@@ -89,10 +174,108 @@ impl BinanceClient {
                 .default_headers(headers)
                 .build()
                 .expect("Failed to build reqwest client"),
-            base_url,
+            base_url: base_url.to_string(),
 
             api_secret: keys.secret.clone(),
+            kline_pacer: RequestPacer::new(KLINE_REQUEST_SPACING),
+            income_pacer: RequestPacer::new(INCOME_REQUEST_SPACING),
+        }
+    }
+
+    /// Fetches the klines of `symbol` on `interval` opening within the range from the kline
+    /// endpoint at `path`.
+    async fn fetch_kline_range(
+        &self,
+        path: &str,
+        symbol: &str,
+        interval: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Kline>, ApiError> {
+        // Binance caps each response at `KLINE_PAGE_LIMIT` bars, so longer ranges are
+        // fetched page by page, each starting just after the previous page's last bar.
+        let end_ms = end_time.timestamp_millis();
+        let mut page_start_ms = start_time.timestamp_millis();
+        let mut klines: Vec<Kline> = Vec::new();
+
+        for _ in 0..MAX_KLINE_PAGES {
+            if page_start_ms > end_ms {
+                return Ok(klines);
+            }
+            let page = self.fetch_kline_page(path, symbol, interval, page_start_ms, end_ms).await?;
+            let Some(last) = page.last() else {
+                return Ok(klines);
+            };
+            let next_start_ms = last.close_time.timestamp_millis() + 1;
+            let is_last_page = page.len() < KLINE_PAGE_LIMIT || next_start_ms <= page_start_ms;
+
+            // Skip any bar the previous page already returned.
+            let last_open_time = klines.last().map(|k| k.open_time);
+            klines.extend(page.into_iter().filter(|k| last_open_time.is_none_or(|t| k.open_time > t)));
+
+            if is_last_page {
+                return Ok(klines);
+            }
+            page_start_ms = next_start_ms;
+        }
+
+        Err(ApiError::TooManyPages(MAX_KLINE_PAGES))
+    }
+
+    /// Fetches a single page of at most `KLINE_PAGE_LIMIT` klines opening within the range.
+    async fn fetch_kline_page(
+        &self,
+        path: &str,
+        symbol: &str,
+        interval: &str,
+        start_ms: i64,
+        end_ms: i64,
+    ) -> Result<Vec<Kline>, ApiError> {
+        self.kline_pacer.wait().await;
+
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .client
+            .get(&url)
+            .query(&[
+                ("symbol", symbol),
+                ("interval", interval),
+                ("startTime", &start_ms.to_string()),
+                ("endTime", &end_ms.to_string()),
+                ("limit", &KLINE_PAGE_LIMIT.to_string()),
+            ])
+            .send()
+            .await?;
+        let status = response.status();
+        let text = response.text().await?;
+
+        if !status.is_success() {
+            let api_error: ApiErrorResponse = serde_json::from_str(&text)
+                .map_err(|e| ApiError::Deserialization(format!("Failed to deserialize error response: {}. Original text: {}", e, text)))?;
+            return Err(ApiError::BinanceError(api_error.code, api_error.msg));
         }
+        let raw_klines: Vec<RawKline> =
+            serde_json::from_str(&text).map_err(|e| ApiError::Deserialization(e.to_string()))?;
+
+        // A bar that cannot be real fails the fetch, rather than being stored and skewing
+        // every backtest over it.
+        raw_klines
+            .into_iter()
+            .map(|raw| {
+                let kline = Kline {
+                    open_time: Utc.timestamp_millis_opt(raw.0).single().ok_or_else(|| ApiError::InvalidData(format!("Invalid open_time: {}", raw.0)))?,
+                    open: Decimal::from_str(&raw.1).map_err(|e| ApiError::Deserialization(e.to_string()))?,
+                    high: Decimal::from_str(&raw.2).map_err(|e| ApiError::Deserialization(e.to_string()))?,
+                    low: Decimal::from_str(&raw.3).map_err(|e| ApiError::Deserialization(e.to_string()))?,
+                    close: Decimal::from_str(&raw.4).map_err(|e| ApiError::Deserialization(e.to_string()))?,
+                    volume: Decimal::from_str(&raw.5).map_err(|e| ApiError::Deserialization(e.to_string()))?,
+                    close_time: Utc.timestamp_millis_opt(raw.6).single().ok_or_else(|| ApiError::InvalidData(format!("Invalid close_time: {}", raw.6)))?,
+                    interval: interval.to_string(),
+                };
+                kline.validate().map_err(|e| ApiError::InvalidData(format!("{}: {}", symbol, e)))?;
+                Ok(kline)
+            })
+            .collect()
     }
 
     async fn _get_signed<T: DeserializeOwned>(
@@ -160,6 +343,37 @@ impl BinanceClient {
     }
 }
 
+/// Builds the parameters of a `POST /fapi/v1/order` request, before the timestamp and signature.
+fn order_params(order: &OrderRequest) -> Result<BTreeMap<&'static str, String>, ApiError> {
+    let mut params = BTreeMap::new();
+    params.insert("symbol", order.symbol.clone());
+    params.insert("side", order.side.as_str().to_string());
+    params.insert("type", format!("{:?}", order.order_type).to_uppercase());
+    params.insert("quantity", order.quantity.to_string());
+    params.insert("newClientOrderId", order.client_order_id.to_string());
+
+    if order.order_type == OrderType::Limit {
+        let price = order.price.ok_or_else(|| {
+            ApiError::InvalidData("Limit order request must have a price.".to_string())
+        })?;
+        params.insert("price", price.to_string());
+        params.insert("timeInForce", order.time_in_force.unwrap_or(TimeInForce::Gtc).as_str().to_string());
+    }
+
+    // Add position side if specified (for hedge mode)
+    if let Some(position_side) = order.position_side {
+        params.insert("positionSide", format!("{:?}", position_side).to_uppercase());
+    }
+
+    // Binance rejects `reduceOnly` in hedge mode, where trading against the
+    // position side already makes an order reduce-only.
+    if order.reduce_only && order.position_side.is_none() {
+        params.insert("reduceOnly", "true".to_string());
+    }
+
+    Ok(params)
+}
+
 // Intermediate struct for deserializing klines from Binance API
 #[derive(Deserialize)]
 struct RawKline(i64, String, String, String, String, String, i64, String, i64, String, String, String);
@@ -173,40 +387,17 @@ impl ApiClient for BinanceClient {
         start_time: DateTime<Utc>,
         end_time: DateTime<Utc>,
     ) -> Result<Vec<Kline>, ApiError> {
-        let url = format!("{}/fapi/v1/klines", self.base_url);
-
-        let response = self
-            .client
-            .get(&url)
-            .query(&[
-                ("symbol", symbol),
-                ("interval", interval),
-                ("startTime", &start_time.timestamp_millis().to_string()),
-                ("endTime", &end_time.timestamp_millis().to_string()),
-                ("limit", "1000"),
-            ])
-            .send()
-            .await?
-            .json::<Vec<RawKline>>()
-            .await?;
-
-        let klines = response
-            .into_iter()
-            .map(|raw| {
-                Ok(Kline {
-                    open_time: Utc.timestamp_millis_opt(raw.0).single().ok_or_else(|| ApiError::InvalidData(format!("Invalid open_time: {}", raw.0)))?,
-                    open: Decimal::from_str(&raw.1).map_err(|e| ApiError::Deserialization(e.to_string()))?,
-                    high: Decimal::from_str(&raw.2).map_err(|e| ApiError::Deserialization(e.to_string()))?,
-                    low: Decimal::from_str(&raw.3).map_err(|e| ApiError::Deserialization(e.to_string()))?,
-                    close: Decimal::from_str(&raw.4).map_err(|e| ApiError::Deserialization(e.to_string()))?,
-                    volume: Decimal::from_str(&raw.5).map_err(|e| ApiError::Deserialization(e.to_string()))?,
-                    close_time: Utc.timestamp_millis_opt(raw.6).single().ok_or_else(|| ApiError::InvalidData(format!("Invalid close_time: {}", raw.6)))?,
-                    interval: interval.to_string(),
-                })
-            })
-            .collect::<Result<Vec<Kline>, ApiError>>()?;
+        self.fetch_kline_range("/fapi/v1/klines", symbol, interval, start_time, end_time).await
+    }
 
-        Ok(klines)
+    async fn fetch_mark_price_klines(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start_time: DateTime<Utc>,
+        end_time: DateTime<Utc>,
+    ) -> Result<Vec<Kline>, ApiError> {
+        self.fetch_kline_range("/fapi/v1/markPriceKlines", symbol, interval, start_time, end_time).await
     }
 
     async fn set_leverage(&self, symbol: &str, leverage: u8) -> Result<(), ApiError> {
@@ -225,48 +416,36 @@ impl ApiClient for BinanceClient {
     }
 
     async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse, ApiError> {
-        let mut params = BTreeMap::new();
-        params.insert("symbol", order.symbol.clone());
-        params.insert("side", format!("{:?}", order.side).to_uppercase());
-        params.insert("type", format!("{:?}", order.order_type).to_uppercase());
-        params.insert("quantity", order.quantity.to_string());
-        params.insert("newClientOrderId", order.client_order_id.to_string());
-        
-        // Add position side if specified (for hedge mode)
-        if let Some(position_side) = order.position_side {
-            params.insert("positionSide", format!("{:?}", position_side).to_uppercase());
-        }
-        
+        let mut params = order_params(order)?;
         self._post_signed("/fapi/v1/order", &mut params).await
     }
 
     /// Places a new Post-Only LIMIT order on the exchange.
     async fn place_limit_order(&self, order: &OrderRequest) -> Result<OrderResponse, ApiError> {
-        let mut params = BTreeMap::new();
-        params.insert("symbol", order.symbol.clone());
-        params.insert("side", format!("{:?}", order.side).to_uppercase());
-        params.insert("type", "LIMIT".to_string()); // Explicitly set to LIMIT
-        params.insert("quantity", order.quantity.to_string());
-        
-        // --- KEY ADDITIONS FOR LIMIT ORDERS ---
-        let price = order.price.ok_or_else(|| {
-            ApiError::InvalidData("Limit order request must have a price.".to_string())
-        })?;
-        params.insert("price", price.to_string());
-        
+        let mut limit_order = order.clone();
+        limit_order.order_type = OrderType::Limit;
         // "GTX" is the API code for a Post-Only order.
         // This ensures the order is only a "Maker" and never a "Taker".
-        params.insert("timeInForce", "GTX".to_string());
-        // --- END KEY ADDITIONS ---
-        
-        params.insert("newClientOrderId", order.client_order_id.to_string());
-        
-        // Add position side if specified (for hedge mode)
-        if let Some(position_side) = order.position_side {
-            params.insert("positionSide", format!("{:?}", position_side).to_uppercase());
+        limit_order.time_in_force = Some(TimeInForce::Gtx);
+        self.place_order(&limit_order).await
+    }
+
+    async fn get_user_trades(&self, symbol: &str, order_id: i64) -> Result<Vec<UserTradeResponse>, ApiError> {
+        let mut params = BTreeMap::new();
+        params.insert("symbol", symbol.to_string());
+        params.insert("orderId", order_id.to_string());
+        self._get_signed("/fapi/v1/userTrades", &mut params).await
+    }
+
+    async fn get_order(&self, symbol: &str, client_order_id: &str) -> Result<Option<OrderResponse>, ApiError> {
+        let mut params = BTreeMap::new();
+        params.insert("symbol", symbol.to_string());
+        params.insert("origClientOrderId", client_order_id.to_string());
+        match self._get_signed("/fapi/v1/order", &mut params).await {
+            Ok(order) => Ok(Some(order)),
+            Err(ApiError::BinanceError(ORDER_NOT_FOUND_CODE, _)) => Ok(None),
+            Err(e) => Err(e),
         }
-        
-        self._post_signed("/fapi/v1/order", &mut params).await
     }
 
     async fn get_account_balance(&self) -> Result<Vec<BalanceResponse>, ApiError> {
@@ -286,6 +465,79 @@ impl ApiClient for BinanceClient {
         serde_json::from_str(&text).map_err(|e| ApiError::Deserialization(e.to_string()))
     }
 
+    async fn get_mark_price(&self, symbol: &str) -> Result<Decimal, ApiError> {
+        let url = format!("{}/fapi/v1/premiumIndex?symbol={}", self.base_url, symbol);
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+        let text = response.text().await?;
+
+        if status.is_success() {
+            let mark: MarkPriceResponse = serde_json::from_str(&text).map_err(|e| ApiError::Deserialization(e.to_string()))?;
+            Ok(mark.mark_price)
+        } else {
+            let api_error: ApiErrorResponse = serde_json::from_str(&text)
+                .map_err(|e| ApiError::Deserialization(format!("Failed to deserialize error response: {}. Original text: {}", e, text)))?;
+            Err(ApiError::BinanceError(api_error.code, api_error.msg))
+        }
+    }
+
+    async fn get_leverage_brackets(&self, symbol: &str) -> Result<SymbolBracketsResponse, ApiError> {
+        let mut params = BTreeMap::new();
+        params.insert("symbol", symbol.to_string());
+        // Depending on the account, a single symbol's brackets come alone or in a list.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum BracketsResponse {
+            One(SymbolBracketsResponse),
+            Many(Vec<SymbolBracketsResponse>),
+        }
+        match self._get_signed::<BracketsResponse>("/fapi/v1/leverageBracket", &mut params).await? {
+            BracketsResponse::One(brackets) => Ok(brackets),
+            BracketsResponse::Many(list) => list
+                .into_iter()
+                .find(|brackets| brackets.symbol == symbol)
+                .ok_or_else(|| ApiError::InvalidData(format!("No leverage brackets returned for {}", symbol))),
+        }
+    }
+
+    async fn get_income_history(&self, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Result<Vec<IncomeRecord>, ApiError> {
+        // Pages are at most `INCOME_PAGE_LIMIT` records, oldest first. Each page starts at the
+        // time of the previous page's last record, since more records may share it, and the
+        // records both pages return are skipped.
+        let end_ms = end_time.timestamp_millis() - 1;
+        let mut page_start_ms = start_time.timestamp_millis();
+        let mut records: Vec<IncomeRecord> = Vec::new();
+        let mut seen = HashSet::new();
+
+        for _ in 0..MAX_INCOME_PAGES {
+            if page_start_ms > end_ms {
+                return Ok(records);
+            }
+            self.income_pacer.wait().await;
+            let mut params = BTreeMap::new();
+            params.insert("startTime", page_start_ms.to_string());
+            params.insert("endTime", end_ms.to_string());
+            params.insert("limit", INCOME_PAGE_LIMIT.to_string());
+            let page: Vec<IncomeRecord> = self._get_signed("/fapi/v1/income", &mut params).await?;
+
+            let Some(last) = page.last() else {
+                return Ok(records);
+            };
+            let last_ms = last.time.timestamp_millis();
+            let is_last_page = page.len() < INCOME_PAGE_LIMIT;
+            // A full page within a single millisecond cannot be paged past otherwise.
+            let next_start_ms = if last_ms > page_start_ms { last_ms } else { page_start_ms + 1 };
+
+            records.extend(page.into_iter().filter(|record| seen.insert((record.tran_id, record.income_type, record.trade_id.clone()))));
+            if is_last_page {
+                return Ok(records);
+            }
+            page_start_ms = next_start_ms;
+        }
+
+        Err(ApiError::TooManyPages(MAX_INCOME_PAGES))
+    }
+
     async fn get_position_mode(&self) -> Result<bool, ApiError> {
         let mut params = BTreeMap::new();
         let response: PositionModeResponse = self._get_signed("/fapi/v1/positionSide/dual", &mut params).await?;
@@ -1,5 +1,5 @@
 use crate::error::ApiError;
-use core_types::Kline;
+use core_types::{BookTicker, Kline};
 use futures_util::stream::StreamExt;
 use rust_decimal::Decimal;
 use serde::Deserialize;
@@ -8,7 +8,7 @@ use tokio::sync::mpsc;
 use tokio_tungstenite::{connect_async, tungstenite::Message};
 use tracing;
 use url::Url;
-use chrono::{TimeZone, Utc};
+use chrono::{DateTime, TimeZone, Utc};
 use serde::de::DeserializeOwned;
 // --- Book Ticker Stream Deserialization ---
 
@@ -26,6 +26,25 @@ pub struct BookTickerUpdate {
     pub best_ask_price: Decimal,
     #[serde(rename = "A")]
     pub best_ask_qty: Decimal,
+    /// When the quote changed, in milliseconds since the Unix epoch; 0 on streams that
+    /// don't send it.
+    #[serde(rename = "T", default)]
+    pub transaction_time: i64,
+}
+
+impl BookTickerUpdate {
+    /// The quote, stamped with its transaction time, or with `received` when the stream
+    /// didn't send one.
+    pub fn to_book_ticker(&self, received: DateTime<Utc>) -> BookTicker {
+        let timestamp = Utc.timestamp_millis_opt(self.transaction_time).single().filter(|_| self.transaction_time > 0);
+        BookTicker {
+            timestamp: timestamp.unwrap_or(received),
+            best_bid_price: self.best_bid_price,
+            best_bid_qty: self.best_bid_qty,
+            best_ask_price: self.best_ask_price,
+            best_ask_qty: self.best_ask_qty,
+        }
+    }
 }
 
 // --- Mark Price Stream Deserialization ---
@@ -37,8 +56,14 @@ pub struct MarkPriceUpdate {
     pub symbol: String,
     #[serde(rename = "p")]
     pub mark_price: Decimal,
+    /// The index price, the average spot price the mark price is anchored to.
+    #[serde(rename = "i", default)]
+    pub index_price: Option<Decimal>,
     #[serde(rename = "r")]
     pub funding_rate: Decimal,
+    /// The next funding time, in milliseconds since the Unix epoch.
+    #[serde(rename = "T", default)]
+    pub next_funding_time: i64,
 }
 // --- WebSocket Deserialization Structs ---
 #[derive(Debug, Deserialize)]
@@ -77,6 +102,51 @@ struct WsKline {
     is_closed: bool,
 }
 
+/// Parses a combined-stream kline message into the symbol and the kline, if the message is a
+/// kline event for a closed kline. Open klines and other events are `None`. A closed kline
+/// with an unparseable value, or that fails `Kline::validate`, is an error.
+pub fn parse_closed_kline(text: &str) -> Result<Option<(String, Kline)>, ApiError> {
+    let wrapper = serde_json::from_str::<WsStreamWrapper<WsKlineEvent>>(text)
+        .map_err(|e| ApiError::Deserialization(e.to_string()))?;
+    let WsKlineEvent { event_type, symbol, kline: k } = wrapper.data;
+    if event_type != "kline" || !k.is_closed {
+        return Ok(None);
+    }
+
+    let time = |millis: i64| {
+        Utc.timestamp_millis_opt(millis).single().ok_or_else(|| ApiError::InvalidData(format!("kline time {} is out of range", millis)))
+    };
+    let price = |value: &str| Decimal::from_str(value).map_err(|e| ApiError::InvalidData(format!("kline value '{}': {}", value, e)));
+    let kline = Kline {
+        open_time: time(k.open_time)?,
+        open: price(&k.open)?,
+        high: price(&k.high)?,
+        low: price(&k.low)?,
+        close: price(&k.close)?,
+        volume: price(&k.volume)?,
+        close_time: time(k.close_time)?,
+        interval: k.interval,
+    };
+    kline.validate().map_err(|e| ApiError::InvalidData(format!("{}: {}", symbol, e)))?;
+    Ok(Some((symbol, kline)))
+}
+
+/// A source of real-time market data streams. The engine subscribes through this trait, so
+/// a scripted connector can stand in for the exchange in tests and offline simulations.
+///
+/// Each subscription returns a receiver that stays open across reconnects; it only closes
+/// when the connector gives up for good.
+pub trait MarketDataConnector: Send + Sync {
+    /// Subscribes to the closed klines of `symbols` on `interval`, as `(symbol, Kline)`.
+    fn subscribe_to_klines(&self, symbols: &[String], interval: &str) -> Result<mpsc::Receiver<(String, Kline)>, ApiError>;
+
+    /// Subscribes to the best bid and ask of `symbols`.
+    fn subscribe_to_book_tickers(&self, symbols: &[String]) -> Result<mpsc::Receiver<BookTickerUpdate>, ApiError>;
+
+    /// Subscribes to the mark prices, and the funding rates they carry, of `symbols`.
+    fn subscribe_to_mark_prices(&self, symbols: &[String]) -> Result<mpsc::Receiver<MarkPriceUpdate>, ApiError>;
+}
+
 /// Handles connection to the Binance WebSocket API and manages data stream subscriptions.
 pub struct LiveConnector {
     base_url: Url,
@@ -93,7 +163,10 @@ impl LiveConnector {
             base_url: Url::parse(base_url).expect("Failed to parse WebSocket base URL"),
         }
     }
-    pub fn subscribe_to_book_tickers(
+}
+
+impl MarketDataConnector for LiveConnector {
+    fn subscribe_to_book_tickers(
         &self,
         symbols: &[String],
     ) -> Result<mpsc::Receiver<BookTickerUpdate>, ApiError> {
@@ -129,7 +202,7 @@ impl LiveConnector {
     }
 
     /// Subscribes to the Mark Price stream for a list of symbols.
-    pub fn subscribe_to_mark_prices(
+    fn subscribe_to_mark_prices(
         &self,
         symbols: &[String],
     ) -> Result<mpsc::Receiver<MarkPriceUpdate>, ApiError> {
@@ -165,7 +238,7 @@ impl LiveConnector {
     }
 
     /// Subscribes to kline streams and returns a channel Receiver for `(symbol, Kline)` data.
-    pub fn subscribe_to_klines(
+    fn subscribe_to_klines(
         &self,
         symbols: &[String],
         interval: &str,
@@ -199,58 +272,26 @@ impl LiveConnector {
                             match msg {
                                 Ok(Message::Text(text)) => {
                                     // We only care about klines that are closed.
-                                    match serde_json::from_str::<WsStreamWrapper<WsKlineEvent>>(&text) {
-                                        Ok(wrapper) => {
-                                            if wrapper.data.event_type == "kline" {
-                                                if wrapper.data.kline.is_closed {
-                                                    tracing::debug!("Raw WebSocket message (CLOSED kline): {}", text);
-                                                    tracing::debug!("Processing closed kline for symbol: {}", wrapper.data.symbol);
-                                                    tracing::debug!("Raw kline data: {:?}", wrapper.data.kline);
-                                                    
-                                                    // Convert to our standard Kline type.
-                                                    let k = wrapper.data.kline;
-                                                    
-                                                    // Debug the conversion process
-                                                    tracing::debug!("Converting kline data:");
-                                                    tracing::debug!("  Open time: {} -> {:?}", k.open_time, Utc.timestamp_millis_opt(k.open_time));
-                                                    tracing::debug!("  Close time: {} -> {:?}", k.close_time, Utc.timestamp_millis_opt(k.close_time));
-                                                    tracing::debug!("  Open: {} -> {:?}", k.open, Decimal::from_str(&k.open));
-                                                    tracing::debug!("  High: {} -> {:?}", k.high, Decimal::from_str(&k.high));
-                                                    tracing::debug!("  Low: {} -> {:?}", k.low, Decimal::from_str(&k.low));
-                                                    tracing::debug!("  Close: {} -> {:?}", k.close, Decimal::from_str(&k.close));
-                                                    tracing::debug!("  Volume: {} -> {:?}", k.volume, Decimal::from_str(&k.volume));
-                                                    
-                                                    let kline = Kline {
-                                                        open_time: Utc.timestamp_millis_opt(k.open_time).single().unwrap(),
-                                                        open: Decimal::from_str(&k.open).unwrap(),
-                                                        high: Decimal::from_str(&k.high).unwrap(),
-                                                        low: Decimal::from_str(&k.low).unwrap(),
-                                                        close: Decimal::from_str(&k.close).unwrap(),
-                                                        volume: Decimal::from_str(&k.volume).unwrap(),
-                                                        close_time: Utc.timestamp_millis_opt(k.close_time).single().unwrap(),
-                                                        interval: k.interval,
-                                                    };
+                                    match parse_closed_kline(&text) {
+                                        Ok(Some((symbol, kline))) => {
+                                            tracing::debug!("Converted kline: {:?}", kline);
 
-                                                    tracing::debug!("Converted kline: {:?}", kline);
-
-                                                    // Send the symbol and kline to the engine. If it fails, the engine is gone, so we exit.
-                                                    match tx.send((wrapper.data.symbol.clone(), kline)).await {
-                                                        Ok(_) => {
-                                                            tracing::debug!("Successfully sent kline for symbol: {}", wrapper.data.symbol);
-                                                        }
-                                                        Err(e) => {
-                                                            tracing::error!("Failed to send kline for symbol {}: {:?}. Channel may be full or receiver dropped.", wrapper.data.symbol, e);
-                                                            tracing::error!("Receiver dropped. Closing WebSocket connection.");
-                                                            return;
-                                                        }
-                                                    }
+                                            // Send the symbol and kline to the engine. If it fails, the engine is gone, so we exit.
+                                            match tx.send((symbol.clone(), kline)).await {
+                                                Ok(_) => {
+                                                    tracing::debug!("Successfully sent kline for symbol: {}", symbol);
+                                                }
+                                                Err(e) => {
+                                                    tracing::error!("Failed to send kline for symbol {}: {:?}. Channel may be full or receiver dropped.", symbol, e);
+                                                    tracing::error!("Receiver dropped. Closing WebSocket connection.");
+                                                    return;
                                                 }
-                                                // Skip non-closed klines silently
                                             }
-                                            // Skip non-kline events silently
                                         }
+                                        // Skip non-kline events and non-closed klines silently
+                                        Ok(None) => {}
                                         Err(e) => {
-                                            tracing::warn!("Failed to parse WebSocket message as WsStreamWrapper: {}", e);
+                                            tracing::warn!("Failed to parse WebSocket kline message: {}", e);
                                         }
                                     }
                                 }
@@ -1,14 +1,19 @@
+use chrono::{DateTime, Utc};
 use core_types::OrderSide;
 use rust_decimal::Decimal;
 use serde::Deserialize;
+use std::collections::BTreeSet;
 
 // Using `#[serde(rename_all = "camelCase")]` to automatically map from JSON camelCase to Rust snake_case.
 
-/// The response from a successful `POST /fapi/v1/order` request.
+/// The response from a successful `POST /fapi/v1/order` request, or an order looked up with
+/// `GET /fapi/v1/order`.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderResponse {
     pub client_order_id: String,
+    /// Not reported by order lookups, where it reads as zero; `executed_qty` is.
+    #[serde(default)]
     pub cum_qty: Decimal,
     pub cum_quote: Decimal,
     pub executed_qty: Decimal,
@@ -24,9 +29,38 @@ pub struct OrderResponse {
     pub time_in_force: String,
     #[serde(rename = "type")]
     pub order_type: String,
+    /// When the order last changed, in milliseconds since the epoch, e.g. its last fill.
+    #[serde(default)]
+    pub update_time: Option<i64>,
     // There are more fields, but these are the most important for us.
 }
 
+/// A single fill belonging to an order, from `GET /fapi/v1/userTrades`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserTradeResponse {
+    pub id: i64,
+    pub order_id: i64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub qty: Decimal,
+    pub quote_qty: Decimal,
+    pub realized_pnl: Decimal,
+    pub commission: Decimal,
+    pub commission_asset: String,
+    pub maker: bool,
+    pub time: i64,
+}
+
+/// A symbol's mark price from `GET /fapi/v1/premiumIndex`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MarkPriceResponse {
+    pub symbol: String,
+    pub mark_price: Decimal,
+}
+
 /// A single asset's balance from `GET /fapi/v2/balance`.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -68,11 +102,25 @@ pub struct ExchangeInfoResponse {
     pub symbols: Vec<SymbolInfo>,
 }
 
+impl ExchangeInfoResponse {
+    /// The symbols open for trading. A symbol without a status is taken to be open.
+    pub fn tradable_symbols(&self) -> BTreeSet<String> {
+        self.symbols
+            .iter()
+            .filter(|info| info.status.as_deref().is_none_or(|status| status == "TRADING"))
+            .map(|info| info.symbol.clone())
+            .collect()
+    }
+}
+
 /// Information about a single trading symbol.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SymbolInfo {
     pub symbol: String,
+    /// "TRADING" for symbols open for trading; others are pending, settling or delisted.
+    #[serde(default)]
+    pub status: Option<String>,
     pub filters: Vec<Filter>,
 }
 
@@ -85,6 +133,64 @@ pub struct Filter {
     pub tick_size: Option<String>,
 }
 
+/// A symbol's notional brackets from `GET /fapi/v1/leverageBracket`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SymbolBracketsResponse {
+    pub symbol: String,
+    pub brackets: Vec<LeverageBracket>,
+}
+
+impl SymbolBracketsResponse {
+    /// The highest leverage the symbol allows, that of its smallest bracket.
+    pub fn max_leverage(&self) -> Option<u32> {
+        self.brackets.iter().map(|bracket| bracket.initial_leverage).max()
+    }
+}
+
+/// The leverage allowed for positions within a range of notional value.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LeverageBracket {
+    pub bracket: u32,
+    pub initial_leverage: u32,
+    pub notional_cap: Decimal,
+    pub notional_floor: Decimal,
+    pub maint_margin_ratio: Decimal,
+}
+
+/// The kind of an `IncomeRecord`. Only the kinds P&L reconciliation compares are told apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum IncomeType {
+    RealizedPnl,
+    Commission,
+    FundingFee,
+    /// Transfers, rebates, insurance clearances and every other kind.
+    #[serde(other)]
+    Other,
+}
+
+/// One entry of the account's income history from `GET /fapi/v1/income`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IncomeRecord {
+    /// Empty for account-wide income such as transfers.
+    #[serde(default)]
+    pub symbol: String,
+    pub income_type: IncomeType,
+    /// Signed: commissions and funding paid are negative.
+    pub income: Decimal,
+    pub asset: String,
+    /// When the exchange booked the income, which can be after the fill it belongs to.
+    #[serde(with = "chrono::serde::ts_milliseconds")]
+    pub time: DateTime<Utc>,
+    pub tran_id: i64,
+    /// The exchange trade the income came from; empty for funding and transfers.
+    #[serde(default)]
+    pub trade_id: String,
+}
+
 /// Response from the position mode endpoint.
 #[derive(Debug, Clone, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,123 @@
+//! A market data connector that replays a script instead of connecting to the exchange.
+//!
+//! Scripts describe what a kline stream delivers, including the failures a live connection
+//! suffers: outages, klines replayed after a reconnect, and klines delivered out of order.
+//! Delays are measured with `tokio::time`, so tests on a paused clock run them instantly.
+
+use crate::error::ApiError;
+use crate::live_connector::{BookTickerUpdate, MarkPriceUpdate, MarketDataConnector};
+use core_types::Kline;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+/// One step of a kline stream's script.
+#[derive(Debug, Clone)]
+pub enum ScriptEvent {
+    /// Delivers a closed kline of `symbol`.
+    EmitKline { symbol: String, kline: Kline },
+    /// Delivers the last kline emitted again, as the exchange may after a reconnect.
+    EmitDuplicate,
+    /// Delivers the next two klines in swapped order.
+    Reorder,
+    /// Drops the connection for `duration`. Nothing is delivered meanwhile; the stream then
+    /// resumes on the same receiver, as the live connector's reconnect loop does.
+    Disconnect { duration: Duration },
+    /// Delivers nothing for `duration` while staying connected, like a quiet market.
+    Wait { duration: Duration },
+}
+
+/// A `MarketDataConnector` whose kline streams play scripts, one per interval.
+///
+/// Streams without a script, and every book ticker and mark price stream, stay open but
+/// silent. A stream that has played its script also stays open, so the subscriber never sees
+/// the feed end.
+#[derive(Debug, Default)]
+pub struct ScriptedConnector {
+    kline_scripts: Mutex<HashMap<String, Vec<ScriptEvent>>>,
+}
+
+impl ScriptedConnector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the script played by the kline stream of `interval`.
+    pub fn with_kline_script(self, interval: &str, script: Vec<ScriptEvent>) -> Self {
+        self.kline_scripts.lock().unwrap().insert(interval.to_string(), script);
+        self
+    }
+}
+
+impl MarketDataConnector for ScriptedConnector {
+    fn subscribe_to_klines(&self, _symbols: &[String], interval: &str) -> Result<mpsc::Receiver<(String, Kline)>, ApiError> {
+        let script = self.kline_scripts.lock().unwrap().remove(interval).unwrap_or_default();
+        let (tx, rx) = mpsc::channel(1024);
+        let interval = interval.to_string();
+        tokio::spawn(async move {
+            if play(&interval, script, &tx).await.is_ok() {
+                tx.closed().await;
+            }
+        });
+        Ok(rx)
+    }
+
+    fn subscribe_to_book_tickers(&self, _symbols: &[String]) -> Result<mpsc::Receiver<BookTickerUpdate>, ApiError> {
+        Ok(silent_stream())
+    }
+
+    fn subscribe_to_mark_prices(&self, _symbols: &[String]) -> Result<mpsc::Receiver<MarkPriceUpdate>, ApiError> {
+        Ok(silent_stream())
+    }
+}
+
+/// Plays `script` into `tx`. Fails once the receiver is dropped.
+async fn play(interval: &str, script: Vec<ScriptEvent>, tx: &mpsc::Sender<(String, Kline)>) -> Result<(), mpsc::error::SendError<(String, Kline)>> {
+    let mut last: Option<(String, Kline)> = None;
+    let mut held: Option<(String, Kline)> = None;
+    let mut reorder = false;
+    for event in script {
+        match event {
+            ScriptEvent::EmitKline { symbol, kline } => {
+                let emitted = (symbol, kline);
+                if reorder {
+                    reorder = false;
+                    held = Some(emitted);
+                    continue;
+                }
+                tx.send(emitted.clone()).await?;
+                if let Some(held) = held.take() {
+                    tx.send(held.clone()).await?;
+                    last = Some(held);
+                } else {
+                    last = Some(emitted);
+                }
+            }
+            ScriptEvent::EmitDuplicate => {
+                if let Some(last) = &last {
+                    tx.send(last.clone()).await?;
+                }
+            }
+            ScriptEvent::Reorder => reorder = true,
+            ScriptEvent::Disconnect { duration } => {
+                tracing::warn!("[WS-Scripted] {} kline stream disconnected for {:?}.", interval, duration);
+                tokio::time::sleep(duration).await;
+                tracing::info!("[WS-Scripted] {} kline stream reconnected.", interval);
+            }
+            ScriptEvent::Wait { duration } => tokio::time::sleep(duration).await,
+        }
+    }
+    // A reordered kline with nothing after it to swap with is delivered at the end.
+    if let Some(held) = held {
+        tx.send(held).await?;
+    }
+    Ok(())
+}
+
+/// A receiver that stays open until dropped but never receives anything.
+fn silent_stream<T: Send + 'static>() -> mpsc::Receiver<T> {
+    let (tx, rx) = mpsc::channel(1);
+    tokio::spawn(async move { tx.closed().await });
+    rx
+}
@@ -0,0 +1,98 @@
+//! Checks that `get_income_history` pages through long histories, against a mock Binance server.
+
+use api_client::{ApiClient, BinanceClient, IncomeType};
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{Duration, TimeZone, Utc};
+use configuration::settings::ApiKeys;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::sync::Mutex;
+
+/// A mock `/fapi/v1/income` endpoint serving `records`, oldest first, and remembering each
+/// request's `startTime`.
+struct MockExchange {
+    records: Vec<Value>,
+    page_starts: Mutex<Vec<i64>>,
+}
+
+async fn income(State(exchange): State<Arc<MockExchange>>, Query(params): Query<HashMap<String, String>>) -> Json<Value> {
+    let param = |name: &str| params[name].parse::<i64>().unwrap();
+    let (start_ms, end_ms, limit) = (param("startTime"), param("endTime"), param("limit"));
+    exchange.page_starts.lock().unwrap().push(start_ms);
+
+    let page: Vec<Value> = exchange
+        .records
+        .iter()
+        .filter(|record| (start_ms..=end_ms).contains(&record["time"].as_i64().unwrap()))
+        .take(limit as usize)
+        .cloned()
+        .collect();
+    Json(Value::Array(page))
+}
+
+async fn serve(exchange: Arc<MockExchange>) -> BinanceClient {
+    let app = Router::new().route("/fapi/v1/income", get(income)).with_state(exchange);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let keys = ApiKeys { key: "test".to_string(), secret: "test".to_string() };
+    BinanceClient::with_base_url(&format!("http://{}", address), &keys)
+}
+
+#[tokio::test]
+async fn records_sharing_a_page_boundary_are_returned_once() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+    // Each fill books a realized P&L and a commission in the same millisecond. After the
+    // opening funding fee, the first page ends between the two records of fill 499.
+    let funding = json!({"symbol": "BTCUSDT", "incomeType": "FUNDING_FEE", "income": "-0.3", "asset": "USDT", "time": start.timestamp_millis(), "tranId": 8888});
+    let records: Vec<Value> = std::iter::once(funding)
+        .chain((0..1100i64).flat_map(|fill| {
+            let time = start.timestamp_millis() + fill * 1000;
+            let trade_id = (5000 + fill).to_string();
+            [
+                json!({"symbol": "BTCUSDT", "incomeType": "REALIZED_PNL", "income": "1.5", "asset": "USDT", "time": time, "tranId": 2 * fill, "tradeId": trade_id}),
+                json!({"symbol": "BTCUSDT", "incomeType": "COMMISSION", "income": "-0.1", "asset": "USDT", "time": time, "tranId": 2 * fill + 1, "tradeId": trade_id}),
+            ]
+        }))
+        .chain([json!({"symbol": "", "incomeType": "TRANSFER", "income": "100", "asset": "USDT", "time": start.timestamp_millis() + 1_200_000, "tranId": 9999, "tradeId": ""})])
+        .collect();
+    let exchange = Arc::new(MockExchange { records, page_starts: Mutex::new(Vec::new()) });
+    let client = serve(Arc::clone(&exchange)).await;
+
+    let income = client.get_income_history(start, start + Duration::days(1)).await.expect("income history");
+
+    assert_eq!(income.len(), 2202);
+    let unique: HashSet<(i64, String)> = income.iter().map(|record| (record.tran_id, record.trade_id.clone())).collect();
+    assert_eq!(unique.len(), income.len());
+    assert_eq!(income.iter().filter(|record| record.income_type == IncomeType::Commission).count(), 1100);
+    assert_eq!(income.last().map(|record| record.income_type), Some(IncomeType::Other));
+
+    // Each page starts at the last record of the one before it.
+    let page_starts = exchange.page_starts.lock().unwrap().clone();
+    let fill_ms = |fill: i64| start.timestamp_millis() + fill * 1000;
+    assert_eq!(page_starts, [fill_ms(0), fill_ms(499), fill_ms(998)]);
+}
+
+#[tokio::test]
+async fn the_end_of_the_range_is_exclusive() {
+    let start = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+    let end = start + Duration::hours(8);
+    let records = vec![
+        json!({"symbol": "ETHUSDT", "incomeType": "FUNDING_FEE", "income": "-0.42", "asset": "USDT", "time": start.timestamp_millis(), "tranId": 1}),
+        json!({"symbol": "ETHUSDT", "incomeType": "FUNDING_FEE", "income": "-0.40", "asset": "USDT", "time": end.timestamp_millis(), "tranId": 2}),
+    ];
+    let exchange = Arc::new(MockExchange { records, page_starts: Mutex::new(Vec::new()) });
+    let client = serve(exchange).await;
+
+    let income = client.get_income_history(start, end).await.expect("income history");
+
+    assert_eq!(income.len(), 1);
+    assert_eq!(income[0].tran_id, 1);
+    assert_eq!(income[0].income_type, IncomeType::FundingFee);
+    assert_eq!(income[0].time, start);
+    assert!(income[0].trade_id.is_empty());
+}
@@ -0,0 +1,141 @@
+//! Checks that `fetch_klines` stitches multi-page ranges together, against a mock Binance server.
+
+use api_client::{ApiClient, BinanceClient};
+use axum::extract::{Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use configuration::settings::ApiKeys;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const MINUTE_MS: i64 = 60_000;
+
+/// A mock `/fapi/v1/klines` endpoint serving 1m bars that exist from `first_open_ms` to `last_open_ms`.
+struct MockExchange {
+    first_open_ms: i64,
+    last_open_ms: i64,
+    /// Also return the bar just before `startTime`, as if the exchange repeated the boundary bar.
+    repeat_boundary: bool,
+    /// Serve this payload instead of the bar opening at the given time.
+    bad_bar: Option<(i64, Value)>,
+    requests: AtomicUsize,
+}
+
+async fn klines(State(exchange): State<Arc<MockExchange>>, Query(params): Query<HashMap<String, String>>) -> Json<Value> {
+    exchange.requests.fetch_add(1, Ordering::SeqCst);
+    let param = |name: &str| params[name].parse::<i64>().unwrap();
+    let (start_ms, end_ms, limit) = (param("startTime"), param("endTime"), param("limit"));
+
+    let mut open_ms = exchange.first_open_ms.max(start_ms.div_euclid(MINUTE_MS) * MINUTE_MS);
+    if open_ms < start_ms {
+        open_ms += MINUTE_MS;
+    }
+    if exchange.repeat_boundary && open_ms > exchange.first_open_ms {
+        open_ms -= MINUTE_MS;
+    }
+
+    let mut bars = Vec::new();
+    while open_ms <= end_ms.min(exchange.last_open_ms) && (bars.len() as i64) < limit {
+        let close_ms = open_ms + MINUTE_MS - 1;
+        match &exchange.bad_bar {
+            Some((bad_open_ms, bar)) if *bad_open_ms == open_ms => bars.push(bar.clone()),
+            _ => bars.push(json!([open_ms, "100.0", "101.0", "99.0", "100.5", "10.0", close_ms, "1005.0", 42, "5.0", "502.5", "0"])),
+        }
+        open_ms += MINUTE_MS;
+    }
+    Json(Value::Array(bars))
+}
+
+async fn serve(exchange: Arc<MockExchange>) -> BinanceClient {
+    let app = Router::new().route("/fapi/v1/klines", get(klines)).with_state(exchange);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let keys = ApiKeys { key: "test".to_string(), secret: "test".to_string() };
+    BinanceClient::with_base_url(&format!("http://{}", address), &keys)
+}
+
+fn assert_contiguous(klines: &[core_types::Kline], first_open: DateTime<Utc>) {
+    assert_eq!(klines[0].open_time, first_open);
+    for pair in klines.windows(2) {
+        assert_eq!(pair[1].open_time - pair[0].open_time, Duration::minutes(1), "gap or duplicate at {}", pair[1].open_time);
+    }
+}
+
+#[tokio::test]
+async fn three_pages_are_stitched_without_gaps_or_duplicates() {
+    let start = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+    let bars = 2500;
+    let exchange = Arc::new(MockExchange {
+        first_open_ms: start.timestamp_millis(),
+        last_open_ms: start.timestamp_millis() + (bars - 1) * MINUTE_MS,
+        repeat_boundary: true,
+        bad_bar: None,
+        requests: AtomicUsize::new(0),
+    });
+    let client = serve(Arc::clone(&exchange)).await;
+
+    let klines = client
+        .fetch_klines("BTCUSDT", "1m", start, start + Duration::days(7))
+        .await
+        .expect("fetch klines");
+
+    assert_eq!(exchange.requests.load(Ordering::SeqCst), 3);
+    assert_eq!(klines.len(), bars as usize);
+    assert_contiguous(&klines, start);
+}
+
+#[tokio::test]
+async fn one_month_of_1m_bars_is_not_truncated() {
+    // Backfill requests one calendar month at a time; April has 30 days of 1,440 bars.
+    let start = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(2024, 4, 30, 23, 59, 59).unwrap();
+    let exchange = Arc::new(MockExchange {
+        first_open_ms: Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap().timestamp_millis(),
+        last_open_ms: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap().timestamp_millis(),
+        repeat_boundary: false,
+        bad_bar: None,
+        requests: AtomicUsize::new(0),
+    });
+    let client = serve(Arc::clone(&exchange)).await;
+
+    let klines = client.fetch_klines("BTCUSDT", "1m", start, end).await.expect("fetch klines");
+
+    assert_eq!(klines.len(), 43_200);
+    assert_contiguous(&klines, start);
+    assert_eq!(klines.last().unwrap().open_time, Utc.with_ymd_and_hms(2024, 4, 30, 23, 59, 0).unwrap());
+    assert_eq!(exchange.requests.load(Ordering::SeqCst), 44);
+}
+
+#[tokio::test]
+async fn a_malformed_bar_fails_the_fetch() {
+    let start = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+    let bad_open_ms = start.timestamp_millis() + 10 * MINUTE_MS;
+    let bad_bars = [
+        // The high is below the low.
+        json!([bad_open_ms, "100.0", "98.0", "99.0", "100.5", "10.0", bad_open_ms + MINUTE_MS - 1, "1005.0", 42, "5.0", "502.5", "0"]),
+        // A zero-volume bar with no prices.
+        json!([bad_open_ms, "0", "0", "0", "0", "0", bad_open_ms + MINUTE_MS - 1, "0", 0, "0", "0", "0"]),
+        // A bar closing before it opens.
+        json!([bad_open_ms, "100.0", "101.0", "99.0", "100.5", "10.0", bad_open_ms - 1, "1005.0", 42, "5.0", "502.5", "0"]),
+        // A price that does not parse.
+        json!([bad_open_ms, "100.0", "101.0", "n/a", "100.5", "10.0", bad_open_ms + MINUTE_MS - 1, "1005.0", 42, "5.0", "502.5", "0"]),
+    ];
+    for bar in bad_bars {
+        let exchange = Arc::new(MockExchange {
+            first_open_ms: start.timestamp_millis(),
+            last_open_ms: start.timestamp_millis() + 59 * MINUTE_MS,
+            repeat_boundary: false,
+            bad_bar: Some((bad_open_ms, bar.clone())),
+            requests: AtomicUsize::new(0),
+        });
+        let client = serve(exchange).await;
+
+        let result = client.fetch_klines("BTCUSDT", "1m", start, start + Duration::hours(1)).await;
+        assert!(result.is_err(), "{} was accepted", bar);
+    }
+}
@@ -0,0 +1,214 @@
+//! Checks the exact parameters `place_order` sends for each kind of order, against a mock
+//! Binance server.
+
+use api_client::{ApiClient, BinanceClient};
+use axum::extract::{Query, State};
+use axum::routing::post;
+use axum::{Json, Router};
+use chrono::Utc;
+use configuration::settings::ApiKeys;
+use configuration::{LimitAction, OrderLimits, ReverseMode, RiskManagement};
+use core_types::enums::PositionSide;
+use core_types::{OrderRequest, OrderSide, OrderType, Position, Signal, SignalIntent, TimeInForce};
+use events::PortfolioState;
+use risk::{RiskManager, SimpleRiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+type Captured = Arc<Mutex<Vec<BTreeMap<String, String>>>>;
+
+/// A mock `/fapi/v1/order` endpoint that records each request's parameters and acknowledges it.
+async fn order(State(captured): State<Captured>, Query(params): Query<BTreeMap<String, String>>) -> Json<Value> {
+    let response = json!({
+        "clientOrderId": params["newClientOrderId"],
+        "cumQty": "0",
+        "cumQuote": "0",
+        "executedQty": "0",
+        "orderId": 1,
+        "avgPrice": "0",
+        "origQty": params["quantity"],
+        "price": params.get("price").map_or("0", String::as_str),
+        "reduceOnly": params.contains_key("reduceOnly"),
+        "side": params["side"],
+        "status": "NEW",
+        "stopPrice": "0",
+        "symbol": params["symbol"],
+        "timeInForce": params.get("timeInForce").map_or("GTC", String::as_str),
+        "type": params["type"],
+    });
+    captured.lock().unwrap().push(params);
+    Json(response)
+}
+
+async fn serve(captured: Captured) -> BinanceClient {
+    let app = Router::new().route("/fapi/v1/order", post(order)).with_state(captured);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let keys = ApiKeys { key: "test".to_string(), secret: "test".to_string() };
+    BinanceClient::with_base_url(&format!("http://{}", address), &keys)
+}
+
+fn order_request(side: OrderSide, order_type: OrderType, price: Option<Decimal>) -> OrderRequest {
+    OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        order_type,
+        quantity: Decimal::new(15, 3),
+        price,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: false,
+        decision_id: None,
+    }
+}
+
+/// Places `order` and returns the parameters the server received, less the timestamp and signature.
+async fn sent_params(order: &OrderRequest) -> BTreeMap<String, String> {
+    let captured = Captured::default();
+    let client = serve(captured.clone()).await;
+    client.place_order(order).await.expect("place order");
+
+    let mut params = captured.lock().unwrap().pop().expect("one request");
+    assert!(params.remove("timestamp").is_some());
+    assert!(params.remove("signature").is_some());
+    params
+}
+
+fn expected(order: &OrderRequest, extra: &[(&str, &str)]) -> BTreeMap<String, String> {
+    let mut params = BTreeMap::from([
+        ("symbol".to_string(), "BTCUSDT".to_string()),
+        ("side".to_string(), order.side.as_str().to_string()),
+        ("type".to_string(), format!("{:?}", order.order_type).to_uppercase()),
+        ("quantity".to_string(), "0.015".to_string()),
+        ("newClientOrderId".to_string(), order.client_order_id.to_string()),
+    ]);
+    params.extend(extra.iter().map(|(k, v)| (k.to_string(), v.to_string())));
+    params
+}
+
+#[tokio::test]
+async fn market_entries_send_no_price_or_time_in_force() {
+    let order = order_request(OrderSide::Buy, OrderType::Market, None);
+    assert_eq!(sent_params(&order).await, expected(&order, &[]));
+}
+
+#[tokio::test]
+async fn limit_entries_send_their_price_and_time_in_force() {
+    let mut order = order_request(OrderSide::Sell, OrderType::Limit, Some(Decimal::new(6543210, 2)));
+    assert_eq!(
+        sent_params(&order).await,
+        expected(&order, &[("price", "65432.10"), ("timeInForce", "GTC")])
+    );
+
+    order.time_in_force = Some(TimeInForce::Ioc);
+    assert_eq!(
+        sent_params(&order).await,
+        expected(&order, &[("price", "65432.10"), ("timeInForce", "IOC")])
+    );
+
+    order.price = None;
+    let captured = Captured::default();
+    let client = serve(captured.clone()).await;
+    assert!(client.place_order(&order).await.is_err());
+    assert!(captured.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn reduce_only_market_exits_send_reduce_only() {
+    let mut order = order_request(OrderSide::Sell, OrderType::Market, None);
+    order.reduce_only = true;
+    assert_eq!(sent_params(&order).await, expected(&order, &[("reduceOnly", "true")]));
+
+    // In hedge mode the position side makes the order reduce-only instead.
+    order.position_side = Some(PositionSide::Long);
+    assert_eq!(sent_params(&order).await, expected(&order, &[("positionSide", "LONG")]));
+}
+
+/// The order a risk manager, for an account in hedge mode or not, sizes to close a long of
+/// 0.015 BTCUSDT.
+fn risk_managed_close(hedge_mode: bool) -> OrderRequest {
+    let manager = SimpleRiskManager::new(RiskManagement {
+        risk_per_trade_pct: dec!(0.01),
+        stop_loss_pct: dec!(0.02),
+        margin_buffer_pct: dec!(0.05),
+        max_position_adds: None,
+        add_spacing_pct: Decimal::ZERO,
+        reverse_mode: ReverseMode::Separate,
+        break_even_trigger_pct: None,
+        break_even_buffer_pct: Decimal::ZERO,
+        take_profit_pct: None,
+        order_limits: OrderLimits::default(),
+        symbol_limits: Default::default(),
+        limit_action: LimitAction::Clamp,
+        max_holding_bars: None,
+        exit_at_session_end: false,
+        min_expected_move: None,
+    })
+    .unwrap()
+    .with_hedge_mode(hedge_mode);
+    let signal = Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        order_request: order_request(OrderSide::Sell, OrderType::Market, None),
+        confidence: Decimal::ONE,
+        intent: Some(SignalIntent::Close),
+    };
+    let portfolio = PortfolioState {
+        timestamp: Utc::now(),
+        quote_asset: "USDT".to_string(),
+        cash: dec!(10000),
+        balances: Default::default(),
+        total_value: dec!(10000),
+        positions: vec![Position {
+            position_id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            quantity: dec!(0.015),
+            entry_price: dec!(100),
+            unrealized_pnl: Decimal::ZERO,
+            last_updated: Utc::now(),
+            adds: 0,
+            last_entry_price: dec!(100),
+            estimated_liquidation_price: None,
+        }],
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    };
+    let mut plan = manager.evaluate_signal(&signal, &portfolio, dec!(100)).unwrap();
+    assert_eq!(plan.legs.len(), 1);
+    plan.legs.remove(0)
+}
+
+#[tokio::test]
+async fn risk_managed_closes_are_reduce_only_in_one_way_mode() {
+    // One-way mode takes no position side, so the close must be sent reduce-only.
+    let close = risk_managed_close(false);
+    assert_eq!(sent_params(&close).await, expected(&close, &[("reduceOnly", "true")]));
+
+    // In hedge mode it trades against the long side instead.
+    let close = risk_managed_close(true);
+    assert_eq!(sent_params(&close).await, expected(&close, &[("positionSide", "LONG")]));
+}
+
+#[tokio::test]
+async fn post_only_limit_orders_share_the_same_parameters() {
+    let order = order_request(OrderSide::Buy, OrderType::Market, Some(Decimal::new(100, 0)));
+    let captured = Captured::default();
+    let client = serve(captured.clone()).await;
+    client.place_limit_order(&order).await.expect("place limit order");
+
+    let mut params = captured.lock().unwrap().pop().expect("one request");
+    params.remove("timestamp");
+    params.remove("signature");
+    let mut limit = order.clone();
+    limit.order_type = OrderType::Limit;
+    assert_eq!(params, expected(&limit, &[("price", "100"), ("timeInForce", "GTX")]));
+}
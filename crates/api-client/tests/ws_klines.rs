@@ -0,0 +1,80 @@
+//! Parses the kline messages of Binance's combined WebSocket streams.
+
+use api_client::live_connector::parse_closed_kline;
+use chrono::{TimeZone, Utc};
+use core_types::Interval;
+use rust_decimal_macros::dec;
+use serde_json::json;
+
+/// A combined-stream kline event for BTCUSDT on `interval`, opening at 2025-01-01 00:00 UTC.
+fn message(interval: Interval, closed: bool) -> String {
+    let open_time = 1_735_689_600_000_i64;
+    let close_time = open_time + interval.duration().as_millis() as i64 - 1;
+    json!({
+        "stream": format!("btcusdt@kline_{}", interval),
+        "data": {
+            "e": "kline",
+            "E": close_time + 1,
+            "s": "BTCUSDT",
+            "k": {
+                "t": open_time,
+                "T": close_time,
+                "s": "BTCUSDT",
+                "i": interval.as_str(),
+                "o": "42000.10",
+                "c": "42100.00",
+                "h": "42150.50",
+                "l": "41990.00",
+                "v": "12.345",
+                "x": closed,
+            }
+        }
+    })
+    .to_string()
+}
+
+#[test]
+fn closed_klines_keep_the_canonical_interval_of_every_stream() {
+    for interval in Interval::ALL {
+        let (symbol, kline) = parse_closed_kline(&message(interval, true)).unwrap().expect("a closed kline");
+        assert_eq!(symbol, "BTCUSDT");
+        assert_eq!(kline.interval.parse::<Interval>().unwrap(), interval);
+        assert_eq!(kline.open_time, Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap());
+        let bar = (kline.close_time - kline.open_time + chrono::Duration::milliseconds(1)).to_std().unwrap();
+        assert_eq!(bar, interval.duration(), "{}", interval);
+        assert_eq!((kline.open, kline.high, kline.low, kline.close, kline.volume), (dec!(42000.10), dec!(42150.50), dec!(41990.00), dec!(42100.00), dec!(12.345)));
+    }
+}
+
+#[test]
+fn open_klines_and_other_events_are_skipped() {
+    assert!(parse_closed_kline(&message(Interval::H1, false)).unwrap().is_none());
+
+    let other = message(Interval::H1, true).replace("\"e\":\"kline\"", "\"e\":\"continuous_kline\"");
+    assert!(parse_closed_kline(&other).unwrap().is_none());
+}
+
+#[test]
+fn malformed_messages_are_errors() {
+    assert!(parse_closed_kline("{\"result\":null,\"id\":1}").is_err());
+    let bad_price = message(Interval::H1, true).replace("42100.00", "n/a");
+    assert!(parse_closed_kline(&bad_price).is_err());
+}
+
+#[test]
+fn impossible_bars_are_errors_rather_than_klines() {
+    // A high below the close, as a payload parsed before the bar settled can carry.
+    let high_below_close = message(Interval::H1, true).replace("42150.50", "42050.00");
+    let error = parse_closed_kline(&high_below_close).unwrap_err().to_string();
+    assert!(error.contains("BTCUSDT") && error.contains("the high 42050.00 is below the body top 42100.00"), "{}", error);
+
+    let zero_low = message(Interval::H1, true).replace("41990.00", "0");
+    assert!(parse_closed_kline(&zero_low).is_err());
+
+    // A 1m stream whose bar claims to span an hour.
+    let wrong_span = message(Interval::H1, true).replace("\"i\":\"1h\"", "\"i\":\"1m\"");
+    assert!(parse_closed_kline(&wrong_span).is_err());
+
+    // Still-open bars are skipped before they are checked.
+    assert!(parse_closed_kline(&message(Interval::H1, false).replace("42150.50", "42050.00")).unwrap().is_none());
+}
@@ -0,0 +1,70 @@
+//! Measures the per-run cost of the backtest hot loop, as executed by each optimizer run.
+//!
+//! Run with `cargo bench -p backtester`. No database is required: klines are generated
+//! in memory and the repository is backed by a lazy pool that never connects. Each run is
+//! measured on the smooth fixture series and on a seeded random series that switches
+//! between trends and chop, which trades far more often. Entries are capped at a fixed
+//! notional, so a run's equity grows with its bars rather than compounding out of `Decimal`'s
+//! range over a million of them.
+
+use backtester::Backtester;
+use core_types::StrategyId;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use database::DbRepository;
+use executor::{Portfolio, SimulatedExecutor};
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use sqlx::postgres::PgPoolOptions;
+use strategies::create_strategy;
+use testing::synthetic::{regime_switching, sine_trend, Regime, SeriesSpec, SineTrend};
+use testing::{test_config, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+const BARS: usize = 1_000_000;
+const MAX_NOTIONAL: u32 = 50_000;
+const SEED: u64 = 42;
+
+/// Up, sideways, down and sideways again, repeated until `BARS` bars.
+fn regimes() -> Vec<Regime> {
+    let cycle = [Regime::trend(2000, 0.0005, 0.003), Regime::chop(500, 0.004), Regime::trend(2000, -0.0005, 0.003), Regime::chop(500, 0.004)];
+    let bars_per_cycle: usize = cycle.iter().map(|regime| regime.bars).sum();
+    cycle.into_iter().cycle().take(BARS / bars_per_cycle * cycle.len()).collect()
+}
+
+fn bench_simulate(c: &mut Criterion) {
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+    let spec = SeriesSpec::default();
+    let series = [("sine_trend", sine_trend(&spec, BARS, &SineTrend::default())), ("regimes", regime_switching(&spec, &regimes(), SEED))];
+    let mut config = test_config(BARS).expect("load config");
+    config.risk_management.order_limits.max_notional = Some(Decimal::from(MAX_NOTIONAL));
+    let db_repo = {
+        let _guard = runtime.enter();
+        DbRepository::new(PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap())
+    };
+
+    let mut group = c.benchmark_group("backtester");
+    group.sample_size(10);
+    for (name, klines) in &series {
+        group.bench_with_input(BenchmarkId::new("simulate_ma_crossover_1m_bars", name), klines, |b, klines| {
+            b.iter(|| {
+                let mut backtester = Backtester::new(
+                    Uuid::new_v4(),
+                    TEST_SYMBOL.to_string(),
+                    TEST_INTERVAL.to_string(),
+                    config.clone(),
+                    Portfolio::new(config.backtest.initial_capital),
+                    create_strategy(StrategyId::MACrossover, &config, TEST_SYMBOL).unwrap(),
+                    Box::new(SimpleRiskManager::new(config.risk_management.clone()).unwrap()),
+                    Box::new(SimulatedExecutor::new(config.simulation.clone())),
+                    analytics::AnalyticsEngine::new(),
+                    db_repo.clone(),
+                );
+                runtime.block_on(backtester.simulate(klines)).unwrap()
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_simulate);
+criterion_main!(benches);
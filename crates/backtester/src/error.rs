@@ -1,3 +1,4 @@
+use chrono::{DateTime, Utc};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -22,6 +23,24 @@ pub enum BacktestError {
 
     #[error("Historical data for the requested range is incomplete or missing.")]
     DataUnavailable,
+
+    #[error("This backtester has no database to load klines from or save results to.")]
+    NoDatabase,
+
+    #[error(
+        "Mark prices for {symbol} {interval} are missing for {missing} of the {bars} bars, the first opening at {first_missing}. \
+         Backfill them with `backfill --price-type mark`, or set `valuation_price = \"last\"`."
+    )]
+    MarkPricesUnavailable {
+        symbol: String,
+        interval: String,
+        missing: usize,
+        bars: usize,
+        first_missing: DateTime<Utc>,
+    },
+
+    #[error("The simulation task failed: {0}")]
+    JoinError(String),
 }
 
 impl From<indicatif::style::TemplateError> for BacktestError {
@@ -1,19 +1,36 @@
 use crate::error::BacktestError;
-use analytics::{AnalyticsEngine, PerformanceReport};
+use analytics::{downsample, AnalyticsEngine, PerformanceReport};
 use chrono::{DateTime, Utc};
-use configuration::Config; // We need the full config for stop-loss pct
-use core_types::{Execution, OrderRequest, OrderSide, OrderType, Signal, Trade};
-use database::DbRepository;
-use events; // For PortfolioState
+use configuration::{BracketResolution, Config, EquityCurveResolution, StopFillModel, TradingBlackouts};
+use core_types::{CloseReason, Execution, Kline, OrderRequest, OrderSide, OrderType, Position, PriceType, Signal, SignalIntent, Trade, TradeDirection};
+use database::{DbRepository, RunMetadata};
+use events::BacktestProgress;
 use executor::{Executor, Portfolio};
 use indicatif::{ProgressBar, ProgressStyle};
-use risk::RiskManager;
+use risk::{constrain_direction, DrawdownScaler, ExpectedMoveFilter, OrderPlan, RiskError, RiskManager, TimeExit};
 use rust_decimal::Decimal;
 use std::collections::HashMap;
-use strategies::Strategy;
+use std::sync::Arc;
+use strategies::{KlineTransformer, Strategy};
 use uuid::Uuid;
 
 pub mod error;
+pub mod spec;
+
+pub use spec::{run_backtest, run_backtest_with_progress, BacktestOutput, BacktestSpec, KlineSource};
+
+/// The engine version recorded with every backtest run.
+pub const ENGINE_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// How many bars `simulate` replays between progress reports.
+pub const PROGRESS_INTERVAL_BARS: usize = 5000;
+
+/// The interval of the klines the `sub_bar` bracket resolution replays a bar's path from.
+pub const SUB_BAR_INTERVAL: &str = "1m";
+
+/// Receives a simulation's progress every `PROGRESS_INTERVAL_BARS` bars and once it is done.
+/// It runs on the simulation's thread, so it should return quickly.
+pub type ProgressCallback = Arc<dyn Fn(BacktestProgress) + Send + Sync>;
 
 /// The main backtesting engine.
 ///
@@ -23,14 +40,44 @@ pub struct Backtester {
     run_id: Uuid, // The unique ID for this specific run, used as a foreign key.
     symbol: String,
     interval: String,
-    config: Config, // Store the full config for stop-loss access
+    stop_loss_pct: Decimal, // Distance of the protective stop from the entry price
+    stop_fill_model: StopFillModel, // Whether stops fill at the open of a bar that gapped through them
+    take_profit_pct: Option<Decimal>, // Distance of the take-profit from the entry price, if positions have one
+    bracket_resolution: BracketResolution, // Which exit a bar that reached both the stop and the take-profit closes at
+    sub_bars: Vec<Kline>, // The 1m klines of the range, for the `sub_bar` bracket resolution
+    break_even_trigger_pct: Option<Decimal>, // Favorable move after which the stop moves to break-even
+    break_even_buffer_pct: Decimal, // Distance of the break-even stop beyond the entry price
+    time_exit: TimeExit, // Closes positions held too long or open at the end of the session
+    expected_move_filter: ExpectedMoveFilter, // Refuses entries whose expected move does not cover their costs
+    valuation_price: PriceType, // The price stops trigger on and positions are valued at
+    mark_prices: Vec<Kline>, // The mark price klines of the range, when valuing at the mark price
+    trading_blackouts: TradingBlackouts, // Windows without new trades, honoured if `apply_in_backtests` is set
+    drawdown_scaler: Option<DrawdownScaler>, // Scales down the risk of new entries in drawdowns, if `apply_in_backtests` is set
+    config_hash: String, // Fingerprint of the configuration sections this run was built from
     // --- Components ---
     portfolio: Portfolio,
     strategy: Box<dyn Strategy>,
+    kline_transform: KlineTransformer, // Preprocesses the klines the strategy sees
+    direction: TradeDirection, // The sides the strategy may take positions on
     risk_manager: Box<dyn RiskManager>,
     executor: Box<dyn Executor>,
     analytics_engine: AnalyticsEngine,
-    db_repo: DbRepository,
+    /// Where klines are loaded from and results saved to. `None` for in-memory runs
+    /// (see `run_backtest`), which can only be driven through `simulate`.
+    db_repo: Option<DbRepository>,
+    /// How much of the equity curve `finalize` stores. The report always uses the full curve.
+    equity_curve_resolution: EquityCurveResolution,
+    /// Reports the simulation's progress in place of the terminal progress bar, if set.
+    progress: Option<ProgressCallback>,
+    // --- Execution Metadata ---
+    started_at: Option<DateTime<Utc>>,
+    bars_processed: i64,
+    signals_filtered: i64, // Approved signals the expected-move filter refused
+    gap_slippage: Decimal, // What stops lost by filling at the open of a bar that gapped through them
+    brackets_stop_first: i64, // Bars that reached both exits and closed the position at the stop
+    brackets_target_first: i64, // Bars that reached both exits and closed the position at the take-profit
+    exposure: Vec<Decimal>, // The gross notional held at each point of the equity curve
+    data_range: Option<(DateTime<Utc>, DateTime<Utc>)>, // First open and last close time of the replayed bars
 }
 
 impl Backtester {
@@ -47,18 +94,120 @@ impl Backtester {
         analytics_engine: AnalyticsEngine,
         db_repo: DbRepository,
     ) -> Self {
+        let kline_transform = KlineTransformer::new(config.backtest.kline_transform);
         Self {
             run_id, // <-- ADDED
             symbol,
             interval,
-            config, // Store the full config
+            stop_loss_pct: config.risk_management.stop_loss_pct,
+            stop_fill_model: config.simulation.stop_fill_model,
+            take_profit_pct: config.risk_management.take_profit_pct,
+            bracket_resolution: config.simulation.bracket_resolution,
+            sub_bars: Vec::new(),
+            break_even_trigger_pct: config.risk_management.break_even_trigger_pct,
+            break_even_buffer_pct: config.risk_management.break_even_buffer_pct,
+            time_exit: TimeExit::new(&config.risk_management),
+            expected_move_filter: ExpectedMoveFilter::new(&config.risk_management, &config.simulation),
+            valuation_price: config.backtest.valuation_price,
+            mark_prices: Vec::new(),
+            trading_blackouts: config.trading_blackouts.clone(),
+            drawdown_scaler: config.global_risk.dynamic_leverage.as_ref().filter(|d| d.apply_in_backtests).map(DrawdownScaler::new),
+            config_hash: configuration::config_hash(&config.backtest, &config.simulation, &config.risk_management),
             portfolio,
             strategy,
+            kline_transform,
+            direction: config.backtest.direction,
             risk_manager,
             executor,
             analytics_engine,
-            db_repo,
+            db_repo: Some(db_repo),
+            equity_curve_resolution: EquityCurveResolution::Full,
+            progress: None,
+            started_at: None,
+            bars_processed: 0,
+            signals_filtered: 0,
+            gap_slippage: Decimal::ZERO,
+            brackets_stop_first: 0,
+            brackets_target_first: 0,
+            exposure: Vec::new(),
+            data_range: None,
+        }
+    }
+
+    /// Sets how much of the equity curve is persisted. Optimizer sweeps use this to thin
+    /// their curves; single runs keep the default of every point.
+    pub fn with_equity_curve_resolution(mut self, resolution: EquityCurveResolution) -> Self {
+        self.equity_curve_resolution = resolution;
+        self
+    }
+
+    /// Reports the simulation's progress to `progress` instead of drawing a progress bar,
+    /// for runs nobody watches in a terminal.
+    pub fn with_progress(mut self, progress: ProgressCallback) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Supplies the mark price klines that stops trigger on and positions are valued at,
+    /// when the configuration values them at the mark price. `simulate` needs one for every
+    /// kline it replays.
+    pub fn with_mark_prices(mut self, mark_prices: Vec<Kline>) -> Self {
+        self.mark_prices = mark_prices;
+        self
+    }
+
+    /// Loads the mark price klines of the range from the database, if this run values
+    /// positions at the mark price. `run` calls this itself; callers that drive `simulate`
+    /// directly call it first.
+    pub async fn load_mark_prices(
+        &mut self,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<(), BacktestError> {
+        if self.valuation_price == PriceType::Mark {
+            self.mark_prices = self.db_repo()?.get_mark_price_klines_by_date_range(&self.symbol, &self.interval, start_date, end_date).await?;
+        }
+        Ok(())
+    }
+
+    /// Supplies the 1m klines the `sub_bar` bracket resolution replays a bar that reached both
+    /// exits from. Bars without them fall back to the stop-loss.
+    pub fn with_sub_bars(mut self, mut sub_bars: Vec<Kline>) -> Self {
+        sub_bars.sort_by_key(|kline| kline.open_time);
+        self.sub_bars = sub_bars;
+        self
+    }
+
+    /// Whether this run resolves the bars that reached both exits from their 1m klines: it
+    /// uses the `sub_bar` bracket resolution, its positions have a take-profit, and it runs on
+    /// a coarser interval than the 1m klines.
+    pub fn uses_sub_bars(&self) -> bool {
+        self.bracket_resolution == BracketResolution::SubBar
+            && self.take_profit_pct.is_some()
+            && self.interval != SUB_BAR_INTERVAL
+    }
+
+    /// Loads the 1m klines from `start` to `end` from the database, if this run resolves
+    /// bars from them (see `uses_sub_bars`). `run` calls this itself; callers that drive
+    /// `simulate` directly call it first.
+    pub async fn load_sub_bars(
+        &mut self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<(), BacktestError> {
+        if self.uses_sub_bars() {
+            self.sub_bars = sub_bar_klines(self.db_repo()?, &self.symbol, self.valuation_price, start, end).await?;
         }
+        Ok(())
+    }
+
+    fn db_repo(&self) -> Result<&DbRepository, BacktestError> {
+        self.db_repo.as_ref().ok_or(BacktestError::NoDatabase)
+    }
+
+    /// The trading blackouts, if the configuration has backtests honour them.
+    fn trading_blackouts(&self) -> Option<&TradingBlackouts> {
+        self.trading_blackouts.apply_in_backtests.then_some(&self.trading_blackouts)
     }
 
     /// Runs the simulation and saves all results to the database upon completion.
@@ -67,15 +216,261 @@ impl Backtester {
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
     ) -> Result<PerformanceReport, BacktestError> {
-        let klines = self.db_repo.get_klines_by_date_range(&self.symbol, &self.interval, start_date, end_date).await?;
+        self.started_at.get_or_insert_with(Utc::now);
+        let db_repo = self.db_repo()?;
+        let klines = db_repo.get_klines_by_date_range(&self.symbol, &self.interval, start_date, end_date).await?;
         if klines.is_empty() { return Err(BacktestError::DataUnavailable); }
+        let warmup = db_repo.get_klines_before(&self.symbol, &self.interval, start_date, self.strategy.required_warmup_bars()).await?;
+        self.load_mark_prices(start_date, end_date).await?;
+        // The 1m klines run to the close of the last bar, past `end_date`.
+        self.load_sub_bars(start_date, klines[klines.len() - 1].close_time).await?;
+
+        self.warm_up(&warmup)?;
+
+        let (completed_trades, equity_curve) = self.simulate(&klines).await?;
+
+        self.finalize(&completed_trades, &equity_curve).await
+    }
+
+    /// Analyzes the output of `simulate` and persists the report, trades and equity curve.
+    ///
+    /// `run` calls this after simulating; callers that drive `simulate` themselves
+    /// (e.g., the optimizer, which moves it off the async reactor) call it directly.
+    pub async fn finalize(
+        &mut self,
+        completed_trades: &[Trade],
+        equity_curve: &[(DateTime<Utc>, Decimal)],
+    ) -> Result<PerformanceReport, BacktestError> {
+        // 4. Generate Final Report
+        let initial_capital = self.portfolio.cash + self.portfolio.positions.values().map(|p| p.entry_price * p.quantity).sum::<Decimal>();
+        let mut report = self.analytics_engine.calculate(
+            completed_trades,
+            equity_curve,
+            &self.exposure,
+            initial_capital,
+            &self.interval,
+        )?;
+        report.gap_slippage = self.gap_slippage;
+        
+        // --- 5. Persist All Results to Database ---
+        let stored_curve = thin_equity_curve(equity_curve, self.equity_curve_resolution);
+        save_results(self.db_repo()?, self.run_id, &report, completed_trades, &stored_curve, &self.metadata()).await?;
+
+        Ok(report)
+    }
+
+    /// Describes this run as of now: when it started, which bars it replayed, how many
+    /// signals it filtered out, and which engine version and configuration produced it.
+    pub fn metadata(&self) -> RunMetadata {
+        let finished_at = Utc::now();
+        RunMetadata {
+            started_at: self.started_at.unwrap_or(finished_at),
+            finished_at,
+            bars_processed: self.bars_processed,
+            signals_filtered: self.signals_filtered,
+            brackets_stop_first: self.brackets_stop_first,
+            brackets_target_first: self.brackets_target_first,
+            engine_version: ENGINE_VERSION.to_string(),
+            config_hash: self.config_hash.clone(),
+            interval: self.interval.clone(),
+            data_start: self.data_range.map(|(start, _)| start),
+            data_end: self.data_range.map(|(_, end)| end),
+        }
+    }
+
+    /// What the stops of the latest `simulate` lost by filling at the open of a bar that
+    /// gapped through them, which `finalize` reports as `gap_slippage`.
+    pub fn gap_slippage(&self) -> Decimal {
+        self.gap_slippage
+    }
+
+    /// The gross notional of the open position at each point of the equity curve of the
+    /// latest `simulate`, valued at the bar's valuation price. Zero while flat.
+    pub fn exposure(&self) -> &[Decimal] {
+        &self.exposure
+    }
+
+    /// The gross notional of the open positions at `price`.
+    fn gross_notional(&self, price: Decimal) -> Decimal {
+        self.portfolio.positions.values().map(|position| position.quantity * price).sum()
+    }
+
+    /// Derives a stable ID for an object created during this run, so that replaying the
+    /// same run produces byte-identical trades and executions.
+    fn simulation_id(&self, kind: &str, sequence: u64) -> Uuid {
+        Uuid::new_v5(&self.run_id, format!("{}:{}", kind, sequence).as_bytes())
+    }
+
+    /// Sends an order to the executor, stamping the order and its execution with IDs
+    /// derived from the run instead of random ones.
+    async fn execute_order(
+        &self,
+        mut order: OrderRequest,
+        kline: &Kline,
+        sequence: &mut u64,
+    ) -> Result<Execution, BacktestError> {
+        order.client_order_id = self.simulation_id("order", *sequence);
+        let mut execution = self.executor.execute(&order, kline, None, None).await?;
+        execution.execution_id = self.simulation_id("execution", *sequence);
+        *sequence += 1;
+        Ok(execution)
+    }
+
+    /// The mark price kline opening with each of `klines`, if positions are valued at the
+    /// mark price. Fails before anything is simulated if any of them is missing.
+    fn valuation_klines(&self, klines: &[Kline]) -> Result<Option<Vec<Kline>>, BacktestError> {
+        if self.valuation_price == PriceType::Last {
+            return Ok(None);
+        }
+        let by_open_time: HashMap<DateTime<Utc>, &Kline> = self.mark_prices.iter().map(|mark| (mark.open_time, mark)).collect();
+        let missing: Vec<&Kline> = klines.iter().filter(|kline| !by_open_time.contains_key(&kline.open_time)).collect();
+        if let Some(first) = missing.first() {
+            return Err(BacktestError::MarkPricesUnavailable {
+                symbol: self.symbol.clone(),
+                interval: self.interval.clone(),
+                missing: missing.len(),
+                bars: klines.len(),
+                first_missing: first.open_time,
+            });
+        }
+        Ok(Some(klines.iter().map(|kline| by_open_time[&kline.open_time].clone()).collect()))
+    }
+
+    /// The price a stop at `stop` on a position of `side` fills at on `kline`, before the spread
+    /// and slippage: the stop itself, or with `StopFillModel::GapAware`, the bar's open if it
+    /// opened beyond the stop. A stop the mark price triggered fills within the last price's
+    /// range, which may not have reached it.
+    fn stop_fill_price(&self, side: OrderSide, stop: Decimal, kline: &Kline) -> Decimal {
+        let gapped = match side {
+            OrderSide::Buy => kline.open < stop,
+            OrderSide::Sell => kline.open > stop,
+        };
+        let price = if self.stop_fill_model == StopFillModel::GapAware && gapped { kline.open } else { stop };
+        match self.valuation_price {
+            PriceType::Mark => price.clamp(kline.low, kline.high),
+            PriceType::Last => price,
+        }
+    }
+
+    /// The price a take-profit at `target` on a position of `side` fills at on `kline`, before
+    /// the spread and slippage: the take-profit itself, or with `StopFillModel::GapAware`, the
+    /// bar's open if it opened beyond the target. A take-profit the mark price triggered fills
+    /// within the last price's range.
+    fn take_profit_fill_price(&self, side: OrderSide, target: Decimal, kline: &Kline) -> Decimal {
+        let gapped = match side {
+            OrderSide::Buy => kline.open > target,
+            OrderSide::Sell => kline.open < target,
+        };
+        let price = if self.stop_fill_model == StopFillModel::GapAware && gapped { kline.open } else { target };
+        match self.valuation_price {
+            PriceType::Mark => price.clamp(kline.low, kline.high),
+            PriceType::Last => price,
+        }
+    }
+
+    /// The exit `bar` triggers for a `side` position with its stop-loss at `stop` and its
+    /// take-profit at `target`, if any, and whether the bar reached both.
+    fn bracket_exit(&self, side: OrderSide, stop: Decimal, target: Option<Decimal>, bar: &Kline) -> Option<(CloseReason, bool)> {
+        match (target, exits_reached(side, stop, target, bar)) {
+            (Some(target), (true, true)) => Some((self.resolve_bracket(side, stop, target, bar), true)),
+            (_, (true, false)) => Some((CloseReason::StopLoss, false)),
+            (_, (false, true)) => Some((CloseReason::TakeProfit, false)),
+            _ => None,
+        }
+    }
+
+    /// Which exit a `bar` that reached both `stop` and `target` reached first. Assumed to be
+    /// the stop, unless the `sub_bar` resolution finds a 1m kline of the bar that reached the
+    /// take-profit before any reached the stop. A 1m kline reaching both is the stop too.
+    fn resolve_bracket(&self, side: OrderSide, stop: Decimal, target: Decimal, bar: &Kline) -> CloseReason {
+        if self.uses_sub_bars() {
+            let first = self.sub_bars.partition_point(|sub_bar| sub_bar.open_time < bar.open_time);
+            let sub_bars = self.sub_bars[first..].iter().take_while(|sub_bar| sub_bar.open_time <= bar.close_time);
+            for sub_bar in sub_bars {
+                match exits_reached(side, stop, Some(target), sub_bar) {
+                    (true, _) => return CloseReason::StopLoss,
+                    (false, true) => return CloseReason::TakeProfit,
+                    (false, false) => {}
+                }
+            }
+        }
+        CloseReason::StopLoss
+    }
+
+    /// The break-even stop for `position`, if this bar moved far enough in its favor to
+    /// trigger one: the entry price plus the buffer for longs, minus it for shorts.
+    fn break_even_stop(&self, position: &Position, kline: &Kline) -> Option<Decimal> {
+        let trigger_pct = self.break_even_trigger_pct?;
+        let entry = position.entry_price;
+        match position.side {
+            OrderSide::Buy => (kline.high >= entry * (Decimal::ONE + trigger_pct))
+                .then(|| entry * (Decimal::ONE + self.break_even_buffer_pct)),
+            OrderSide::Sell => (kline.low <= entry * (Decimal::ONE - trigger_pct))
+                .then(|| entry * (Decimal::ONE - self.break_even_buffer_pct)),
+        }
+    }
+
+    /// Feeds klines from before the test range through the strategy (and the kline
+    /// transform) so its indicators are warm when `simulate` starts. Any signals are
+    /// discarded: warm-up bars place no trades and add nothing to the equity curve.
+    ///
+    /// `run` fetches the strategy's `required_warmup_bars` itself; callers that drive
+    /// `simulate` directly warm up first.
+    pub fn warm_up(&mut self, klines: &[Kline]) -> Result<(), BacktestError> {
+        for kline in klines {
+            let strategy_kline = self.kline_transform.apply(kline);
+            self.strategy.evaluate(&strategy_kline)?;
+        }
+        Ok(())
+    }
+
+    /// The number of bars the strategy wants to see before the test range starts.
+    pub fn required_warmup_bars(&self) -> usize {
+        self.strategy.required_warmup_bars()
+    }
 
+    /// The portfolio as of the last bar simulated.
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.portfolio
+    }
+
+    /// Replays the given klines through the strategy, risk manager and executor.
+    ///
+    /// This is the hot loop of every backtest. It performs no I/O, so it can be driven
+    /// directly (e.g., from benchmarks) with pre-loaded data. Returns the completed trades
+    /// and the per-bar equity curve.
+    ///
+    /// Stops and take-profits trigger on, and the equity curve values positions at, the price
+    /// the configuration's `valuation_price` names. Orders fill at the last price, stops at the
+    /// price `stop_fill_model` gives and take-profits at their own, with the spread and
+    /// slippage on top. A bar that reached both exits closes at the one `bracket_resolution` picks.
+    pub async fn simulate(
+        &mut self,
+        klines: &[Kline],
+    ) -> Result<(Vec<Trade>, Vec<(DateTime<Utc>, Decimal)>), BacktestError> {
+        let valuation_klines = self.valuation_klines(klines)?;
+        self.started_at.get_or_insert_with(Utc::now);
+        self.bars_processed = klines.len() as i64;
+        self.signals_filtered = 0;
+        self.gap_slippage = Decimal::ZERO;
+        self.brackets_stop_first = 0;
+        self.brackets_target_first = 0;
+        self.exposure = Vec::with_capacity(klines.len());
+        self.data_range = klines.first().zip(klines.last()).map(|(first, last)| (first.open_time, last.close_time));
         let mut equity_curve = Vec::with_capacity(klines.len());
-        let mut completed_trades = Vec::new();
+        // Preallocate for a generous trade count so long runs do not repeatedly regrow the vector.
+        let mut completed_trades = Vec::with_capacity(klines.len() / 100);
         let mut pending_entry: Option<Execution> = None;
         let mut stop_loss_price: Option<Decimal> = None; // Track the stop-loss for the open position
+        let mut take_profit_price: Option<Decimal> = None; // And its take-profit, if positions have one
+        let mut initial_risk: Option<Decimal> = None; // What the open position stood to lose at its initial stop
+        let mut bars_held: u32 = 0; // Bars closed since the open position was entered
+        let mut order_sequence: u64 = 0; // Numbers the orders of this run for deterministic IDs
+        // Set when a plan fails after some of its legs filled, which halts a live bot. The
+        // strategy's signals are ignored from then on, as the live engine would.
+        let mut halted = false;
 
-        let progress_bar = ProgressBar::new(klines.len() as u64);
+        let progress_bar = if self.progress.is_some() { ProgressBar::hidden() } else { ProgressBar::new(klines.len() as u64) };
         progress_bar.set_style(
             ProgressStyle::default_bar()
                 .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta})")
@@ -83,132 +478,365 @@ impl Backtester {
                 .progress_chars("=>-"),
         );
 
-        for kline in klines.iter() {
+        for (index, kline) in klines.iter().enumerate() {
+            if index > 0 && index % PROGRESS_INTERVAL_BARS == 0 {
+                self.report_progress(index, klines.len());
+            }
             let mut signal_from_strategy: Option<Signal> = None;
+            // Advance the transform on every bar, including stopped-out ones. Only the
+            // strategy sees the transformed kline; stops and executions use the real one.
+            let strategy_kline = self.kline_transform.apply(kline);
+            // The bar of the price stops trigger on and positions are valued at.
+            let valuation = valuation_klines.as_ref().map_or(kline, |marks| &marks[index]);
 
-            // --- 1. STOP-LOSS CHECK (NEW LOGIC) ---
-            // Check for stop-loss triggers *before* evaluating the strategy.
+            // --- 1. STOP-LOSS AND TAKE-PROFIT CHECK ---
+            // Check for stop-loss and take-profit triggers *before* evaluating the strategy.
             if let Some(position) = self.portfolio.get_position(&self.symbol) {
                 if let Some(sl_price) = stop_loss_price {
-                    let should_stop = match position.side {
-                        OrderSide::Buy => kline.low <= sl_price,
-                        OrderSide::Sell => kline.high >= sl_price,
-                    };
+                    let exit = self.bracket_exit(position.side, sl_price, take_profit_price, valuation);
+                    if let Some((close_reason, true)) = exit {
+                        match close_reason {
+                            CloseReason::TakeProfit => self.brackets_target_first += 1,
+                            _ => self.brackets_stop_first += 1,
+                        }
+                    }
 
-                    if should_stop {
-                        // Create a synthetic "stop-loss signal" to close the position.
+                    if let Some((close_reason, _)) = exit {
+                        let fill_price = match (close_reason, take_profit_price) {
+                            (CloseReason::TakeProfit, Some(target)) => self.take_profit_fill_price(position.side, target, kline),
+                            _ => {
+                                let fill_price = self.stop_fill_price(position.side, sl_price, kline);
+                                self.gap_slippage += (fill_price - sl_price).abs() * position.quantity;
+                                fill_price
+                            }
+                        };
+                        // Create a synthetic exit signal to close the position.
                         let close_signal = Signal {
                             signal_id: Uuid::new_v4(),
+                            decision_id: Uuid::new_v4(),
                             timestamp: kline.close_time,
                             confidence: "1.0".parse().unwrap(),
+                            intent: Some(SignalIntent::Close),
                             order_request: OrderRequest {
                                 client_order_id: Uuid::new_v4(), // New order ID for the exit
                                 symbol: self.symbol.clone(),
                                 side: if position.side == OrderSide::Buy { OrderSide::Sell } else { OrderSide::Buy },
                                 order_type: OrderType::Market,
                                 quantity: position.quantity, // Close the full position
-                                price: Some(sl_price), // Execute at the SL price for realism
+                                price: Some(fill_price),
                                 position_side: None, // Will be set by engine
+                                time_in_force: None,
+                                reduce_only: true,
+                                decision_id: None,
                             },
                         };
                         
-                        // Execute the stop-loss order
-                        let execution = self.executor.execute(&close_signal.order_request, kline, None, None).await?;
+                        // Execute the exit order against the triggering bar, as if it closed at
+                        // the fill price, so the spread and slippage still apply on top.
+                        let fill_bar = Kline { close: fill_price, ..kline.clone() };
+                        let execution = self.execute_order(close_signal.order_request, &fill_bar, &mut order_sequence).await?;
                         self.portfolio.update_with_execution(&execution)?;
                         
                         // Match the trade
                         if let Some(entry_execution) = pending_entry.take() {
                             completed_trades.push(Trade {
-                                trade_id: Uuid::new_v4(),
+                                trade_id: self.simulation_id("trade", completed_trades.len() as u64),
                                 symbol: self.symbol.clone(),
                                 entry_execution,
                                 exit_execution: execution,
+                                close_reason,
+                                initial_risk: initial_risk.take(),
                             });
                         }
-                        stop_loss_price = None; // Clear the stop-loss
-                        continue; // Skip strategy evaluation for this bar, as we were stopped out.
+                        stop_loss_price = None; // Clear the stop-loss and take-profit
+                        take_profit_price = None;
+                        // The bar still gets its equity point, so the curve has one per bar.
+                        let total_equity = self.portfolio.calculate_total_equity_single(&self.symbol, valuation.close)?;
+                        equity_curve.push((kline.close_time, total_equity));
+                        self.exposure.push(self.gross_notional(valuation.close));
+                        progress_bar.inc(1);
+                        continue; // Skip strategy evaluation for this bar, as the position was closed.
+                    }
+
+                    // Move the stop to break-even once this bar reached the trigger. Which of the
+                    // bar's extremes came first is unknown, so the new stop applies from the next bar.
+                    if let Some(break_even) = self.break_even_stop(position, valuation) {
+                        // Never loosen a stop that is already tighter.
+                        stop_loss_price = Some(match position.side {
+                            OrderSide::Buy => sl_price.max(break_even),
+                            OrderSide::Sell => sl_price.min(break_even),
+                        });
                     }
                 }
             } else {
-                 // If there's no position, there should be no stop loss. Clean up.
+                 // If there's no position, there should be no stop loss or take-profit. Clean up.
                  stop_loss_price = None;
+                 take_profit_price = None;
+            }
+
+            // --- 1b. TIME EXIT ---
+            // Close a position held for its maximum number of bars, still open at the end of
+            // the session, or due to be flattened ahead of a trading blackout, at this bar's close.
+            let flatten_for_blackout = self.trading_blackouts().is_some_and(|blackouts| blackouts.flattening_at(kline.close_time).is_some());
+            let time_exit_order = self.portfolio.get_position(&self.symbol).and_then(|position| {
+                bars_held += 1;
+                let close_reason = if flatten_for_blackout {
+                    CloseReason::Blackout
+                } else if self.time_exit.is_due(bars_held, kline) {
+                    CloseReason::TimeLimit
+                } else {
+                    return None;
+                };
+                let order = OrderRequest {
+                    client_order_id: Uuid::new_v4(),
+                    symbol: self.symbol.clone(),
+                    side: position.side.opposite(),
+                    order_type: OrderType::Market,
+                    quantity: position.quantity,
+                    price: None,
+                    position_side: None,
+                    time_in_force: None,
+                    reduce_only: true,
+                    decision_id: None,
+                };
+                Some((order, close_reason))
+            });
+            let closed_on_time = time_exit_order.is_some();
+            if let Some((order, close_reason)) = time_exit_order {
+                let execution = self.execute_order(order, kline, &mut order_sequence).await?;
+                self.portfolio.update_with_execution(&execution)?;
+                if let Some(entry_execution) = pending_entry.take() {
+                    completed_trades.push(Trade {
+                        trade_id: self.simulation_id("trade", completed_trades.len() as u64),
+                        symbol: self.symbol.clone(),
+                        entry_execution,
+                        exit_execution: execution,
+                        close_reason,
+                        initial_risk: initial_risk.take(),
+                    });
+                }
+                stop_loss_price = None;
+                take_profit_price = None;
             }
 
             // --- 2. STRATEGY EVALUATION ---
-            signal_from_strategy = self.strategy.evaluate(kline)?;
+            // The strategy sees every bar, but its signal on a time exit's bar is discarded so
+            // the exit is not immediately re-entered.
+            signal_from_strategy = self.strategy.evaluate(&strategy_kline)?.filter(|_| !closed_on_time && !halted);
+            if let Some(signal) = &signal_from_strategy
+                && let Some(window) = self.trading_blackouts().and_then(|blackouts| blackouts.blocking(signal))
+            {
+                tracing::debug!("Dropping the signal at {} inside the trading blackout {}", kline.close_time, window);
+                signal_from_strategy = None;
+            }
+            signal_from_strategy = signal_from_strategy
+                .and_then(|signal| constrain_direction(self.direction, signal, self.portfolio.get_position(&self.symbol)));
+
+            // Mark the portfolio to market once per bar. The value is reused by the risk check
+            // and only recomputed below if an execution changes the portfolio.
+            let mut total_equity = self.portfolio.calculate_total_equity_single(&self.symbol, valuation.close)?;
+            let risk_multiplier = match &mut self.drawdown_scaler {
+                Some(scaler) => {
+                    if let Some(change) = scaler.update(total_equity) {
+                        tracing::debug!(
+                            "Scaling the risk of new entries from {}x to {}x at a {:.2}% drawdown ({})",
+                            change.from, change.to, change.drawdown * Decimal::ONE_HUNDRED, kline.close_time
+                        );
+                    }
+                    scaler.multiplier()
+                }
+                None => Decimal::ONE,
+            };
 
             // --- 3. SIGNAL PROCESSING ---
-            if let Some(signal) = signal_from_strategy {
-                let position_before = self.portfolio.get_position(&self.symbol).cloned();
-                
-                let total_equity = self.portfolio.calculate_total_equity(&HashMap::from([(self.symbol.clone(), kline.close)]))?;
-                
-                let order_request = self.risk_manager.evaluate_signal(
+            let order_plan = match signal_from_strategy {
+                Some(signal) => match self.risk_manager.evaluate_scaled_signal(
                     &signal,
                     &events::PortfolioState { 
                         timestamp: kline.close_time,
+                        quote_asset: self.portfolio.quote_asset.clone(),
                         cash: self.portfolio.cash,
+                        balances: self.portfolio.balances.clone(),
                         total_value: total_equity,
-                        positions: self.portfolio.positions.values().cloned().collect()
+                        positions: self.portfolio.positions.values().cloned().collect(),
+                        realized_pnl: self.portfolio.realized_pnl,
+                        total_fees_paid: self.portfolio.total_fees_paid,
                     },
-                    kline.close
-                )?;
+                    kline.close,
+                    risk_multiplier,
+                ).and_then(|order_plan| self.expected_move_filter.check(&order_plan, kline).map(|_| order_plan)) {
+                    Ok(order_plan) => order_plan,
+                    Err(e @ RiskError::InsufficientExpectedMove { .. }) => {
+                        tracing::debug!("Filtering the signal at {}: {}", kline.close_time, e);
+                        self.signals_filtered += 1;
+                        OrderPlan::default()
+                    }
+                    // A declined scale-in or close is a normal outcome, not a failure.
+                    Err(e) if e.is_declined() => {
+                        tracing::debug!("Skipping signal: {}", e);
+                        OrderPlan::default()
+                    }
+                    Err(e) => return Err(e.into()),
+                },
+                None => OrderPlan::default(),
+            };
+            let continues_after_failure = order_plan.continues_after_failure();
+            let leg_count = order_plan.legs.len();
+            let mut filled_legs = 0;
+
+            // Each leg is placed once the previous one has filled and updated the portfolio.
+            for (leg, order_request) in order_plan.legs.into_iter().enumerate() {
+                let position_before = self.portfolio.get_position(&self.symbol).cloned();
 
-                let execution = self.executor.execute(&order_request, kline, None, None).await?;
+                let execution = match self.execute_order(order_request, kline, &mut order_sequence).await {
+                    Ok(execution) => execution,
+                    Err(e) if continues_after_failure => {
+                        tracing::warn!("Leg {} of {} failed; placing the rest: {}", leg + 1, leg_count, e);
+                        continue;
+                    }
+                    Err(e) if filled_legs == 0 => return Err(e),
+                    Err(e) => {
+                        tracing::error!(
+                            "CRITICAL: Leg {} of {} failed after {} filled: {}. Ignoring the strategy's signals for the rest of the run.",
+                            leg + 1, leg_count, filled_legs, e
+                        );
+                        halted = true;
+                        break;
+                    }
+                };
+                filled_legs += 1;
                 self.portfolio.update_with_execution(&execution)?;
-                
+                total_equity = self.portfolio.calculate_total_equity_single(&self.symbol, valuation.close)?;
+
                 let position_after = self.portfolio.get_position(&self.symbol);
 
-                match (position_before, position_after) {
-                    (None, Some(pos_after)) => { // Opened a new position
-                        pending_entry = Some(execution);
-                        // SET THE STOP-LOSS PRICE
-                        let sl_pct = self.config.risk_management.stop_loss_pct;
-                        stop_loss_price = Some(match pos_after.side {
-                            OrderSide::Buy => pos_after.entry_price * (Decimal::ONE - sl_pct),
-                            OrderSide::Sell => pos_after.entry_price * (Decimal::ONE + sl_pct),
-                        });
+                // A netted reversal both closes the old position and opens the new one.
+                let (exit_execution, entry_execution) = match (&position_before, position_after) {
+                    (None, Some(_)) => (None, Some(execution)),
+                    (Some(_), None) => (Some(execution), None),
+                    (Some(before), Some(after)) if before.side != after.side => {
+                        let (closing, opening) = execution.split_at(before.quantity);
+                        (Some(closing), Some(opening))
                     }
-                    (Some(_), None) => { // Closed an existing position
-                        if let Some(entry_execution) = pending_entry.take() {
-                            completed_trades.push(Trade {
-                                trade_id: Uuid::new_v4(),
-                                symbol: self.symbol.clone(),
-                                entry_execution,
-                                exit_execution: execution,
-                            });
-                        }
-                        stop_loss_price = None; // Clear SL on close
+                    _ => (None, None),
+                };
+
+                if let Some(exit_execution) = exit_execution { // Closed an existing position
+                    if let Some(entry_execution) = pending_entry.take() {
+                        completed_trades.push(Trade {
+                            trade_id: self.simulation_id("trade", completed_trades.len() as u64),
+                            symbol: self.symbol.clone(),
+                            entry_execution,
+                            exit_execution,
+                            close_reason: CloseReason::Signal,
+                            initial_risk: initial_risk.take(),
+                        });
                     }
-                    _ => {}
+                    stop_loss_price = None; // Clear SL and TP on close
+                    take_profit_price = None;
+                }
+                if let (Some(entry_execution), Some(pos_after)) = (entry_execution, position_after) { // Opened a new position
+                    pending_entry = Some(entry_execution);
+                    bars_held = 0;
+                    // SET THE STOP-LOSS PRICE
+                    let sl_pct = self.stop_loss_pct;
+                    let stop = match pos_after.side {
+                        OrderSide::Buy => pos_after.entry_price * (Decimal::ONE - sl_pct),
+                        OrderSide::Sell => pos_after.entry_price * (Decimal::ONE + sl_pct),
+                    };
+                    stop_loss_price = Some(stop);
+                    take_profit_price = self.take_profit_pct.map(|tp_pct| match pos_after.side {
+                        OrderSide::Buy => pos_after.entry_price * (Decimal::ONE + tp_pct),
+                        OrderSide::Sell => pos_after.entry_price * (Decimal::ONE - tp_pct),
+                    });
+                    // A stop at the entry price risks nothing, so the trade gets no R-multiple.
+                    initial_risk = Some((pos_after.entry_price - stop).abs() * pos_after.quantity).filter(|risk| !risk.is_zero());
                 }
             }
 
             // --- 4. RECORD EQUITY ---
-            let market_prices = HashMap::from([(self.symbol.clone(), kline.close)]);
-            let total_equity = self.portfolio.calculate_total_equity(&market_prices)?;
             equity_curve.push((kline.close_time, total_equity));
+            self.exposure.push(self.gross_notional(valuation.close));
             progress_bar.inc(1);
         }
 
+        if self.brackets_stop_first + self.brackets_target_first > 0 {
+            tracing::info!(
+                "{} bars reached both a stop-loss and a take-profit: {} closed at the stop-loss, {} at the take-profit ({:?} resolution).",
+                self.brackets_stop_first + self.brackets_target_first, self.brackets_stop_first, self.brackets_target_first, self.bracket_resolution
+            );
+        }
         progress_bar.finish_with_message("Simulation complete. Analyzing and saving results...");
+        self.report_progress(klines.len(), klines.len());
 
-        // 4. Generate Final Report
-        let initial_capital = self.portfolio.cash + self.portfolio.positions.values().map(|p| p.entry_price * p.quantity).sum::<Decimal>();
-        let report = self.analytics_engine.calculate(
-            &completed_trades,
-            &equity_curve,
-            initial_capital,
-            &self.interval,
-        )?;
-        
-        // --- 5. Persist All Results to Database ---
-        self.db_repo.save_performance_report(self.run_id, &report).await?;
-        self.db_repo.save_trades(self.run_id, &completed_trades).await?;
-        self.db_repo.save_equity_curve(self.run_id, &equity_curve).await?;
-        
-        progress_bar.finish_with_message("Results saved successfully.");
+        Ok((completed_trades, equity_curve))
+    }
 
-        Ok(report)
+    fn report_progress(&self, bars_done: usize, total_bars: usize) {
+        if let Some(progress) = &self.progress {
+            let pct = if total_bars == 0 { 100.0 } else { bars_done as f64 * 100.0 / total_bars as f64 };
+            progress(BacktestProgress { run_id: self.run_id, pct, bars_done: bars_done as u64 });
+        }
+    }
+}
+
+/// Whether `bar` reached the stop-loss at `stop` and the take-profit at `target`, if any, of a
+/// `side` position.
+fn exits_reached(side: OrderSide, stop: Decimal, target: Option<Decimal>, bar: &Kline) -> (bool, bool) {
+    match side {
+        OrderSide::Buy => (bar.low <= stop, target.is_some_and(|target| bar.high >= target)),
+        OrderSide::Sell => (bar.high >= stop, target.is_some_and(|target| bar.low <= target)),
+    }
+}
+
+/// The 1m klines of `symbol` opening from `start` to `end` that the `sub_bar` bracket
+/// resolution replays: mark price klines for runs valued at the mark price, last price ones otherwise.
+async fn sub_bar_klines(
+    db_repo: &DbRepository,
+    symbol: &str,
+    valuation_price: PriceType,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<Vec<Kline>, BacktestError> {
+    let sub_bars = match valuation_price {
+        PriceType::Last => db_repo.get_klines_by_date_range(symbol, SUB_BAR_INTERVAL, start, end).await?,
+        PriceType::Mark => db_repo.get_mark_price_klines_by_date_range(symbol, SUB_BAR_INTERVAL, start, end).await?,
+    };
+    if sub_bars.is_empty() {
+        tracing::warn!(
+            "No {} klines of {} are stored from {} to {}; bars reaching both exits close at the stop-loss.",
+            SUB_BAR_INTERVAL, symbol, start, end
+        );
+    }
+    Ok(sub_bars)
+}
+
+/// Thins an equity curve to the given resolution for storage.
+pub fn thin_equity_curve(
+    equity_curve: &[(DateTime<Utc>, Decimal)],
+    resolution: EquityCurveResolution,
+) -> Vec<(DateTime<Utc>, Decimal)> {
+    match resolution {
+        EquityCurveResolution::Full => equity_curve.to_vec(),
+        EquityCurveResolution::EveryNth(n) => downsample::every_nth(equity_curve, n),
+        EquityCurveResolution::Lttb(points) => downsample::lttb(equity_curve, points),
     }
-}
\ No newline at end of file
+}
+
+/// Persists the report, trades, equity curve and execution metadata of a run under `run_id`.
+async fn save_results(
+    db_repo: &DbRepository,
+    run_id: Uuid,
+    report: &PerformanceReport,
+    trades: &[Trade],
+    equity_curve: &[(DateTime<Utc>, Decimal)],
+    metadata: &RunMetadata,
+) -> Result<(), BacktestError> {
+    db_repo.save_performance_report(run_id, report).await?;
+    db_repo.save_trades(run_id, trades).await?;
+    db_repo.save_equity_curve(run_id, equity_curve).await?;
+    db_repo.save_run_metadata(run_id, metadata).await?;
+
+    tracing::info!("Results saved successfully for run {}.", run_id);
+    Ok(())
+}
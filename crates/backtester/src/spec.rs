@@ -0,0 +1,351 @@
+//! A self-contained entry point for running a single backtest from code.
+//!
+//! `run_backtest` wires the strategy, risk manager, simulated executor and analytics
+//! together from one `BacktestSpec`, so embedding a backtest (in a research script, a
+//! notebook kernel, ...) does not require copying the CLI's setup.
+
+use crate::error::BacktestError;
+use crate::{save_results, sub_bar_klines, Backtester, ProgressCallback};
+use analytics::{AnalyticsEngine, PerformanceReport};
+use chrono::{DateTime, Utc};
+use configuration::settings::Backtest;
+use configuration::{config_hash, Config, DynamicLeverage, EquityCurveResolution, QuoteAssets, RiskManagement, Simulation, TradingBlackouts};
+use core_types::{KlineTransform, Kline, PriceType, StrategyId, Trade, TradeDirection};
+use database::{DbRepository, RunMetadata};
+use executor::{Portfolio, SimulatedExecutor};
+use risk::{DrawdownScaler, ExpectedMoveFilter, SimpleRiskManager, TimeExit};
+use rust_decimal::Decimal;
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use strategies::{create_strategy_from_params, strategy_params, KlineTransformer};
+use tokio::runtime::Handle;
+use uuid::Uuid;
+
+/// Where a backtest gets its klines from.
+#[derive(Debug, Clone)]
+pub enum KlineSource {
+    /// Load the klines for the spec's symbol, interval and date range from the database.
+    Database(DbRepository),
+    /// Use klines already in memory. Only those opening within the spec's date range are replayed;
+    /// those just before it warm the strategy up.
+    InMemory(Vec<Kline>),
+    /// Like `InMemory`, for klines shared by many backtests, e.g. the runs of an optimization
+    /// job, which are loaded once for all of them.
+    Shared(Arc<Vec<Kline>>),
+}
+
+impl KlineSource {
+    /// The `warmup_bars` klines just before `start`, and those opening from `start` to `end`.
+    pub async fn load(
+        &self,
+        symbol: &str,
+        interval: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        warmup_bars: usize,
+    ) -> Result<(Vec<Kline>, Vec<Kline>), BacktestError> {
+        match self {
+            Self::Database(db_repo) => Ok((
+                db_repo.get_klines_before(symbol, interval, start, warmup_bars).await?,
+                db_repo.get_klines_by_date_range(symbol, interval, start, end).await?,
+            )),
+            Self::InMemory(klines) => Ok(split_klines(klines, start, end, warmup_bars)),
+            Self::Shared(klines) => Ok(split_klines(klines, start, end, warmup_bars)),
+        }
+    }
+}
+
+/// The `warmup_bars` klines before `start`, and those opening from `start` to `end`.
+fn split_klines(klines: &[Kline], start: DateTime<Utc>, end: DateTime<Utc>, warmup_bars: usize) -> (Vec<Kline>, Vec<Kline>) {
+    let (mut warmup, klines): (Vec<_>, Vec<_>) = klines
+        .iter()
+        .filter(|kline| kline.open_time <= end)
+        .cloned()
+        .partition(|kline| kline.open_time < start);
+    warmup.drain(..warmup.len().saturating_sub(warmup_bars));
+    (warmup, klines)
+}
+
+/// Everything needed to run one backtest.
+#[derive(Debug, Clone)]
+pub struct BacktestSpec {
+    /// Seeds the IDs of the simulated orders, executions and trades; reusing it reproduces a run exactly.
+    pub run_id: Uuid,
+    pub strategy_id: StrategyId,
+    /// The strategy's parameter set, in the same shape as its table in `config.toml`.
+    pub params: JsonValue,
+    pub symbol: String,
+    pub interval: String,
+    /// The first kline open time to replay, inclusive.
+    pub start: DateTime<Utc>,
+    /// The last kline open time to replay, inclusive.
+    pub end: DateTime<Utc>,
+    pub initial_capital: Decimal,
+    /// The asset the capital, equity and fees are in, e.g. "USDT".
+    pub quote_asset: String,
+    /// The `sub_bar` bracket resolution replays 1m klines from the database, so runs on
+    /// in-memory klines fall back to the assumption.
+    pub simulation: Simulation,
+    pub risk_management: RiskManagement,
+    pub kline_transform: KlineTransform,
+    /// The sides the strategy may take positions on.
+    pub direction: TradeDirection,
+    /// The price stops trigger on and positions are valued at. Mark prices are loaded from
+    /// the database, so in-memory klines can only be valued at the last price.
+    pub valuation_price: PriceType,
+    /// Windows without new trades. Honoured only when `apply_in_backtests` is set.
+    pub trading_blackouts: TradingBlackouts,
+    /// Drawdown tiers scaling down the risk of new entries. Honoured only when `apply_in_backtests` is set.
+    pub dynamic_leverage: Option<DynamicLeverage>,
+    pub klines: KlineSource,
+}
+
+impl BacktestSpec {
+    /// Builds a spec from the `[backtest]` section of a configuration, covering whole days
+    /// from `start_date` to `end_date`, with a fresh run ID.
+    pub fn from_config(config: &Config, klines: KlineSource) -> Result<Self, BacktestError> {
+        let backtest = &config.backtest;
+        Ok(Self {
+            run_id: Uuid::new_v4(),
+            strategy_id: backtest.strategy_id,
+            params: strategy_params(backtest.strategy_id, config)?,
+            symbol: backtest.symbol.clone(),
+            interval: backtest.interval.clone(),
+            start: backtest.start_date.and_hms_opt(0, 0, 0).unwrap().and_utc(),
+            end: backtest.end_date.and_hms_opt(23, 59, 59).unwrap().and_utc(),
+            initial_capital: backtest.initial_capital,
+            quote_asset: config.execution.quote_asset.clone(),
+            simulation: config.simulation.clone(),
+            risk_management: config.risk_management.clone(),
+            kline_transform: backtest.kline_transform,
+            direction: backtest.direction,
+            valuation_price: backtest.valuation_price,
+            trading_blackouts: config.trading_blackouts.clone(),
+            dynamic_leverage: config.global_risk.dynamic_leverage.clone(),
+            klines,
+        })
+    }
+
+    /// The configuration hash recorded with the spec's run (see `configuration::config_hash`).
+    /// The spec is hashed as the `[backtest]` section it corresponds to, so a spec built with
+    /// `from_config` carries the same hash as that configuration.
+    pub fn config_hash(&self) -> String {
+        let backtest = Backtest {
+            strategy_id: self.strategy_id,
+            symbol: self.symbol.clone(),
+            interval: self.interval.clone(),
+            initial_capital: self.initial_capital,
+            start_date: self.start.date_naive(),
+            end_date: self.end.date_naive(),
+            kline_transform: self.kline_transform,
+            direction: self.direction,
+            valuation_price: self.valuation_price,
+        };
+        config_hash(&backtest, &self.simulation, &self.risk_management)
+    }
+}
+
+/// The results of a backtest. Nothing is written to the database.
+#[derive(Debug, Clone)]
+pub struct BacktestOutput {
+    pub run_id: Uuid,
+    pub report: PerformanceReport,
+    pub trades: Vec<Trade>,
+    pub equity_curve: Vec<(DateTime<Utc>, Decimal)>,
+    pub metadata: RunMetadata,
+}
+
+impl BacktestOutput {
+    /// Persists the report, trades, equity curve and metadata under `run_id`, whose backtest
+    /// run record must already exist.
+    pub async fn save(&self, db_repo: &DbRepository) -> Result<(), BacktestError> {
+        save_results(db_repo, self.run_id, &self.report, &self.trades, &self.equity_curve, &self.metadata).await
+    }
+}
+
+/// Runs a single backtest described by `spec` and returns its results.
+///
+/// The simulation is CPU-bound, so it runs on the blocking pool rather than on the caller's
+/// async worker.
+///
+/// # Example
+///
+/// ```
+/// use backtester::{run_backtest, BacktestSpec, KlineSource};
+/// use chrono::{Duration, TimeZone, Utc};
+/// use configuration::{BracketResolution, LimitAction, OrderLimits, ReverseMode, RiskManagement, Simulation, StopFillModel};
+/// use core_types::{Kline, KlineTransform, PriceType, StrategyId, TradeDirection};
+/// use rust_decimal::Decimal;
+/// use uuid::Uuid;
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), backtester::error::BacktestError> {
+/// let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+/// // A slow sine wave, so the moving averages cross a few times.
+/// let klines: Vec<Kline> = (0..1000)
+///     .map(|i| {
+///         let close = Decimal::from(100 + ((i as f64 / 20.0).sin() * 10.0).round() as i64);
+///         let open_time = start + Duration::hours(i);
+///         Kline {
+///             open_time,
+///             open: close,
+///             high: close + Decimal::ONE,
+///             low: close - Decimal::ONE,
+///             close,
+///             volume: Decimal::from(1000),
+///             close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+///             interval: "1h".to_string(),
+///         }
+///     })
+///     .collect();
+///
+/// let output = run_backtest(BacktestSpec {
+///     run_id: Uuid::new_v4(),
+///     strategy_id: StrategyId::MACrossover,
+///     params: serde_json::json!({ "ma_fast_period": 5, "ma_slow_period": 20, "trend_filter_period": 50 }),
+///     symbol: "BTCUSDT".to_string(),
+///     interval: "1h".to_string(),
+///     start,
+///     end: start + Duration::days(30),
+///     initial_capital: Decimal::from(10_000),
+///     quote_asset: "USDT".to_string(),
+///     simulation: Simulation {
+///         taker_fee_pct: Decimal::new(4, 4),
+///         maker_fee_pct: Decimal::new(2, 4),
+///         fee_discount_pct: Decimal::ZERO,
+///         slippage_pct: Decimal::new(1, 1),
+///         simulated_spread_pct: Decimal::ZERO,
+///         symbol_spread_pct: Default::default(),
+///         stop_fill_model: StopFillModel::Exact,
+///         bracket_resolution: BracketResolution::Assumption,
+///     },
+///     risk_management: RiskManagement {
+///         risk_per_trade_pct: Decimal::new(1, 2),
+///         stop_loss_pct: Decimal::new(2, 2),
+///         margin_buffer_pct: Decimal::new(5, 2),
+///         max_position_adds: None,
+///         add_spacing_pct: Decimal::ZERO,
+///         reverse_mode: ReverseMode::Separate,
+///         break_even_trigger_pct: None,
+///         break_even_buffer_pct: Decimal::ZERO,
+///         take_profit_pct: None,
+///         order_limits: OrderLimits::default(),
+///         symbol_limits: Default::default(),
+///         limit_action: LimitAction::Clamp,
+///         max_holding_bars: None,
+///         exit_at_session_end: false,
+///         min_expected_move: None,
+///     },
+///     kline_transform: KlineTransform::None,
+///     direction: TradeDirection::Both,
+///     valuation_price: PriceType::Last,
+///     trading_blackouts: Default::default(),
+///     dynamic_leverage: None,
+///     klines: KlineSource::InMemory(klines),
+/// })
+/// .await?;
+///
+/// assert!(!output.trades.is_empty());
+/// assert_eq!(output.report.total_trades, output.trades.len());
+/// # Ok(())
+/// # }
+/// ```
+pub async fn run_backtest(spec: BacktestSpec) -> Result<BacktestOutput, BacktestError> {
+    run_backtest_with_progress(spec, None).await
+}
+
+/// Like `run_backtest`, but reports the simulation's progress to `progress` (see
+/// `Backtester::with_progress`) instead of drawing a progress bar.
+pub async fn run_backtest_with_progress(
+    spec: BacktestSpec,
+    progress: Option<ProgressCallback>,
+) -> Result<BacktestOutput, BacktestError> {
+    let started_at = Utc::now();
+    let config_hash = spec.config_hash();
+    let strategy = create_strategy_from_params(spec.strategy_id, &spec.params, &spec.symbol)?;
+    let risk_manager = SimpleRiskManager::new(spec.risk_management.clone())?;
+
+    // The strategy is warmed up on the bars just before the range, where the source has them.
+    let warmup_bars = strategy.required_warmup_bars();
+    let (warmup, klines) = spec.klines.load(&spec.symbol, &spec.interval, spec.start, spec.end, warmup_bars).await?;
+    let mark_prices = match (&spec.klines, spec.valuation_price) {
+        (KlineSource::Database(db_repo), PriceType::Mark) => {
+            db_repo.get_mark_price_klines_by_date_range(&spec.symbol, &spec.interval, spec.start, spec.end).await?
+        }
+        _ => Vec::new(),
+    };
+    if klines.is_empty() {
+        return Err(BacktestError::DataUnavailable);
+    }
+
+    let mut backtester = Backtester {
+        run_id: spec.run_id,
+        symbol: spec.symbol,
+        interval: spec.interval,
+        stop_loss_pct: spec.risk_management.stop_loss_pct,
+        stop_fill_model: spec.simulation.stop_fill_model,
+        take_profit_pct: spec.risk_management.take_profit_pct,
+        bracket_resolution: spec.simulation.bracket_resolution,
+        sub_bars: Vec::new(),
+        break_even_trigger_pct: spec.risk_management.break_even_trigger_pct,
+        break_even_buffer_pct: spec.risk_management.break_even_buffer_pct,
+        time_exit: TimeExit::new(&spec.risk_management),
+        expected_move_filter: ExpectedMoveFilter::new(&spec.risk_management, &spec.simulation),
+        valuation_price: spec.valuation_price,
+        mark_prices,
+        trading_blackouts: spec.trading_blackouts,
+        drawdown_scaler: spec.dynamic_leverage.as_ref().filter(|d| d.apply_in_backtests).map(DrawdownScaler::new),
+        config_hash,
+        portfolio: Portfolio::new(spec.initial_capital).with_quote_asset(spec.quote_asset.clone()),
+        strategy,
+        kline_transform: KlineTransformer::new(spec.kline_transform),
+        direction: spec.direction,
+        risk_manager: Box::new(risk_manager),
+        executor: Box::new(SimulatedExecutor::new(spec.simulation).with_quote_assets(QuoteAssets::new(spec.quote_asset))),
+        analytics_engine: AnalyticsEngine::new(),
+        db_repo: None,
+        equity_curve_resolution: EquityCurveResolution::Full,
+        progress,
+        started_at: Some(started_at),
+        bars_processed: 0,
+        signals_filtered: 0,
+        gap_slippage: Decimal::ZERO,
+        brackets_stop_first: 0,
+        brackets_target_first: 0,
+        exposure: Vec::new(),
+        data_range: None,
+    };
+
+    // Only klines from the database have their 1m klines at hand.
+    if let KlineSource::Database(db_repo) = &spec.klines
+        && backtester.uses_sub_bars()
+    {
+        let end = klines[klines.len() - 1].close_time;
+        backtester.sub_bars = sub_bar_klines(db_repo, &backtester.symbol, backtester.valuation_price, spec.start, end).await?;
+    }
+
+    let handle = Handle::current();
+    let (backtester, simulated) = tokio::task::spawn_blocking(move || {
+        let simulated = backtester.warm_up(&warmup).and_then(|()| handle.block_on(backtester.simulate(&klines)));
+        (backtester, simulated)
+    })
+    .await
+    .map_err(|e| BacktestError::JoinError(e.to_string()))?;
+    let (trades, equity_curve) = simulated?;
+    let mut report = backtester.analytics_engine.calculate(
+        &trades,
+        &equity_curve,
+        &backtester.exposure,
+        spec.initial_capital,
+        &backtester.interval,
+    )?;
+    report.gap_slippage = backtester.gap_slippage;
+
+    Ok(BacktestOutput {
+        run_id: spec.run_id,
+        report,
+        trades,
+        equity_curve,
+        metadata: backtester.metadata(),
+    })
+}
@@ -0,0 +1,118 @@
+//! Checks that backtests honour trading blackouts only when the configuration asks them to,
+//! dropping entries inside a window and flattening positions ahead of one.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::{Config, TradingBlackouts};
+use core_types::{CloseReason, Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use strategies::{Strategy, StrategyError};
+use testing::{backtester, test_config, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// Goes long on the first bar and closes on the fifth.
+struct LongThenClose {
+    bar: usize,
+}
+
+impl Strategy for LongThenClose {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        self.bar += 1;
+        let (intent, side) = match self.bar {
+            1 => (SignalIntent::OpenLong, OrderSide::Buy),
+            5 => (SignalIntent::Close, OrderSide::Sell),
+            _ => return Ok(None),
+        };
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: Some(intent),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// Eight flat hourly bars from Monday 2024-01-01 00:00 UTC.
+fn klines() -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..8)
+        .map(|i| {
+            let open_time = start + Duration::hours(i);
+            Kline {
+                open_time,
+                open: dec!(100),
+                high: dec!(100),
+                low: dec!(100),
+                close: dec!(100),
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn config(trading_blackouts: TradingBlackouts) -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.risk_management.break_even_trigger_pct = None;
+    config.trading_blackouts = trading_blackouts;
+    config
+}
+
+async fn trades(trading_blackouts: TradingBlackouts) -> Vec<Trade> {
+    let config = config(trading_blackouts);
+    let mut backtester = backtester(&config, Box::new(LongThenClose { bar: 0 }));
+    backtester.simulate(&klines()).await.expect("simulate").0
+}
+
+fn weekly(window: &str, apply_in_backtests: bool) -> TradingBlackouts {
+    TradingBlackouts { weekly: vec![window.parse().unwrap()], apply_in_backtests, ..Default::default() }
+}
+
+#[tokio::test]
+async fn an_entry_inside_a_window_is_dropped_only_when_backtests_apply_blackouts() {
+    let klines = klines();
+
+    let trades_ignoring = trades(weekly("Mon 00:00-01:00", false)).await;
+    assert_eq!(trades_ignoring.len(), 1);
+    assert_eq!(trades_ignoring[0].entry_execution.timestamp, klines[0].close_time);
+    assert_eq!(trades_ignoring[0].close_reason, CloseReason::Signal);
+
+    // The entry falls in the window, so there is nothing to close later.
+    assert!(trades(weekly("Mon 00:00-01:00", true)).await.is_empty());
+}
+
+#[tokio::test]
+async fn a_close_inside_a_window_goes_through() {
+    // The window covers the fifth bar, where the strategy closes.
+    let trades = trades(weekly("Mon 04:00-06:00", true)).await;
+
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].close_reason, CloseReason::Signal);
+    assert_eq!(trades[0].exit_execution.timestamp, klines()[4].close_time);
+}
+
+#[tokio::test]
+async fn a_position_is_flattened_ahead_of_a_window() {
+    let blackouts = TradingBlackouts { flatten_before_blackout_minutes: Some(120), ..weekly("Mon 05:00-06:00", true) };
+
+    let trades = trades(blackouts).await;
+
+    // The third bar closes at 02:59:59.999, just short of two hours ahead; the fourth is within.
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].close_reason, CloseReason::Blackout);
+    assert_eq!(trades[0].exit_execution.timestamp, klines()[3].close_time);
+}
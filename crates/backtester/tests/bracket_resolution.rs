@@ -0,0 +1,124 @@
+//! Checks which exit a bar reaching both a position's stop-loss and its take-profit closes it
+//! at, under each `BracketResolution`, and that the ambiguous bars are counted.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use configuration::{BracketResolution, Config};
+use core_types::{CloseReason, Kline, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, EnterOnce, TEST_INTERVAL};
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+fn kline(open_time: DateTime<Utc>, length: Duration, interval: &str, (open, high, low, close): (Decimal, Decimal, Decimal, Decimal)) -> Kline {
+    Kline {
+        open_time,
+        open,
+        high,
+        low,
+        close,
+        volume: Decimal::ONE,
+        close_time: open_time + length - Duration::milliseconds(1),
+        interval: interval.to_string(),
+    }
+}
+
+/// A long entered at 100 on the first hourly bar, with its stop at 98 and its take-profit at
+/// 102, then a bar reaching both.
+fn hourly_bars() -> Vec<Kline> {
+    vec![
+        kline(start(), Duration::hours(1), TEST_INTERVAL, (dec!(100), dec!(100.5), dec!(99.5), dec!(100))),
+        kline(start() + Duration::hours(1), Duration::hours(1), TEST_INTERVAL, (dec!(100), dec!(103), dec!(97), dec!(99))),
+    ]
+}
+
+/// The second hourly bar's 1m klines: quiet, except for a spike to 103 in its `high_minute`
+/// and a drop to 97 in its `low_minute`.
+fn minute_bars(high_minute: i64, low_minute: i64) -> Vec<Kline> {
+    (0..60)
+        .map(|minute| {
+            let prices = if minute == high_minute {
+                (dec!(100), dec!(103), dec!(100), dec!(100))
+            } else if minute == low_minute {
+                (dec!(100), dec!(100), dec!(97), dec!(100))
+            } else {
+                (dec!(100), dec!(100.5), dec!(99.5), dec!(100))
+            };
+            kline(start() + Duration::hours(1) + Duration::minutes(minute), Duration::minutes(1), "1m", prices)
+        })
+        .collect()
+}
+
+/// A frictionless config with a 2% stop-loss and a 2% take-profit.
+fn config(bracket_resolution: BracketResolution) -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = Decimal::ZERO;
+    config.simulation.maker_fee_pct = Decimal::ZERO;
+    config.simulation.slippage_pct = Decimal::ZERO;
+    config.simulation.simulated_spread_pct = Decimal::ZERO;
+    config.simulation.bracket_resolution = bracket_resolution;
+    config.risk_management.stop_loss_pct = dec!(0.02);
+    config.risk_management.take_profit_pct = Some(dec!(0.02));
+    config.risk_management.break_even_trigger_pct = None;
+    config
+}
+
+/// The trade of the run, and its bars that reached both exits, closed at the stop-loss and
+/// at the take-profit.
+async fn run(bracket_resolution: BracketResolution, klines: &[Kline], sub_bars: Vec<Kline>) -> (Trade, (i64, i64)) {
+    let config = config(bracket_resolution);
+    let mut backtester = backtester(&config, Box::new(EnterOnce::long()))
+    .with_sub_bars(sub_bars);
+    let (trades, _) = backtester.simulate(klines).await.expect("simulate");
+    assert_eq!(trades.len(), 1);
+    let metadata = backtester.metadata();
+    (trades[0].clone(), (metadata.brackets_stop_first, metadata.brackets_target_first))
+}
+
+#[tokio::test]
+async fn a_bar_whose_1m_path_reached_the_take_profit_first_closes_there_only_when_resolved_from_it() {
+    let sub_bars = minute_bars(10, 40);
+
+    let (trade, brackets) = run(BracketResolution::Assumption, &hourly_bars(), sub_bars.clone()).await;
+    assert_eq!((trade.close_reason, trade.exit_execution.price), (CloseReason::StopLoss, dec!(98)));
+    assert_eq!(brackets, (1, 0));
+
+    let (trade, brackets) = run(BracketResolution::SubBar, &hourly_bars(), sub_bars).await;
+    assert_eq!((trade.close_reason, trade.exit_execution.price), (CloseReason::TakeProfit, dec!(102)));
+    assert_eq!(brackets, (0, 1));
+}
+
+#[tokio::test]
+async fn a_bar_whose_1m_path_reached_the_stop_first_closes_at_the_stop() {
+    let (trade, brackets) = run(BracketResolution::SubBar, &hourly_bars(), minute_bars(40, 10)).await;
+
+    assert_eq!((trade.close_reason, trade.exit_execution.price), (CloseReason::StopLoss, dec!(98)));
+    assert_eq!(brackets, (1, 0));
+}
+
+#[tokio::test]
+async fn without_1m_klines_or_with_one_reaching_both_the_stop_is_assumed() {
+    let (trade, brackets) = run(BracketResolution::SubBar, &hourly_bars(), Vec::new()).await;
+    assert_eq!(trade.close_reason, CloseReason::StopLoss);
+    assert_eq!(brackets, (1, 0));
+
+    // The spike and the drop in the same minute.
+    let mut sub_bars = minute_bars(10, 40);
+    sub_bars[10].low = dec!(97);
+    let (trade, brackets) = run(BracketResolution::SubBar, &hourly_bars(), sub_bars).await;
+    assert_eq!(trade.close_reason, CloseReason::StopLoss);
+    assert_eq!(brackets, (1, 0));
+}
+
+#[tokio::test]
+async fn a_bar_reaching_only_the_take_profit_closes_there_and_is_not_counted() {
+    let mut klines = hourly_bars();
+    klines[1].low = dec!(99);
+
+    let (trade, brackets) = run(BracketResolution::Assumption, &klines, Vec::new()).await;
+
+    assert_eq!((trade.close_reason, trade.exit_execution.price), (CloseReason::TakeProfit, dec!(102)));
+    assert_eq!(brackets, (0, 0));
+}
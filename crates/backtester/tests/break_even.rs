@@ -0,0 +1,94 @@
+//! Checks that the stop-loss moves to break-even once a position has moved far enough in
+//! its favor, and that it never loosens.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::Config;
+use core_types::{Kline, OrderSide, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, EnterOnce, TEST_INTERVAL};
+
+/// Hourly bars from (high, low, close) triples.
+fn klines(bars: &[(Decimal, Decimal, Decimal)]) -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    bars.iter()
+        .enumerate()
+        .map(|(i, &(high, low, close))| {
+            let open_time = start + Duration::hours(i as i64);
+            Kline {
+                open_time,
+                open: close,
+                high,
+                low,
+                close,
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// A frictionless config with a 2% stop, so fills happen exactly at each bar's close.
+fn config(break_even_trigger_pct: Option<Decimal>) -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = Decimal::ZERO;
+    config.simulation.maker_fee_pct = Decimal::ZERO;
+    config.simulation.slippage_pct = Decimal::ZERO;
+    config.risk_management.stop_loss_pct = dec!(0.02);
+    config.risk_management.break_even_trigger_pct = break_even_trigger_pct;
+    config.risk_management.break_even_buffer_pct = dec!(0.005);
+    config
+}
+
+async fn run(config: Config, side: OrderSide, bars: &[(Decimal, Decimal, Decimal)]) -> Vec<Trade> {
+    let mut backtester = backtester(&config, Box::new(EnterOnce::new(side)));
+    backtester.simulate(&klines(bars)).await.expect("simulate").0
+}
+
+// Stops fill at the triggering bar's close, so each stop bar closes at the stop level.
+const LONG_BARS: &[(Decimal, Decimal, Decimal)] = &[
+    (dec!(100), dec!(100), dec!(100)), // Entry at 100; the stop starts at 98.
+    (dec!(103.5), dec!(101), dec!(103)), // Past the 3% trigger: the stop moves to 100.5.
+    (dec!(103), dec!(100), dec!(100.5)), // Retraces to entry: stopped out at 100.5.
+    (dec!(99), dec!(98), dec!(98)), // Would hit the original stop at 98.
+];
+
+#[tokio::test]
+async fn a_long_past_the_trigger_exits_at_break_even() {
+    let trades = run(config(Some(dec!(0.03))), OrderSide::Buy, LONG_BARS).await;
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].entry_execution.price, dec!(100));
+    assert_eq!(trades[0].exit_execution.price, dec!(100.5));
+
+    // Without break-even, the retracement is ridden down to the original stop.
+    let trades = run(config(None), OrderSide::Buy, LONG_BARS).await;
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].exit_execution.price, dec!(98));
+}
+
+#[tokio::test]
+async fn a_short_past_the_trigger_exits_at_break_even() {
+    let bars = [
+        (dec!(100), dec!(100), dec!(100)), // Entry at 100; the stop starts at 102.
+        (dec!(99), dec!(96.5), dec!(97)), // Past the 3% trigger: the stop moves to 99.5.
+        (dec!(100), dec!(97), dec!(99.5)), // Stopped out at 99.5.
+    ];
+    let trades = run(config(Some(dec!(0.03))), OrderSide::Sell, &bars).await;
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].exit_execution.price, dec!(99.5));
+}
+
+#[tokio::test]
+async fn the_trigger_bar_itself_is_not_stopped_at_break_even() {
+    // The trigger bar dips to entry, but only after the trigger is it known to be a break-even
+    // trade, so the original stop still applies to it.
+    let bars = [
+        (dec!(100), dec!(100), dec!(100)),
+        (dec!(103.5), dec!(99), dec!(101)),
+        (dec!(101), dec!(100.4), dec!(100.5)),
+    ];
+    let trades = run(config(Some(dec!(0.03))), OrderSide::Buy, &bars).await;
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].exit_execution.timestamp, klines(&bars)[2].close_time);
+}
@@ -0,0 +1,109 @@
+//! Checks that a backtest is a pure function of its inputs: no wall-clock time or random IDs
+//! leak into its results.
+
+use backtester::Backtester;
+use chrono::Duration;
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal, StrategyId};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use strategies::{create_strategy, Strategy, StrategyError};
+use testing::{
+    generate_klines, seed_start, test_config, TestBacktester, TEST_INTERVAL, TEST_SYMBOL,
+};
+use uuid::Uuid;
+
+/// Enters long every `period` bars and exits `hold` bars later.
+struct FixedHold {
+    period: usize,
+    hold: usize,
+    bar: usize,
+}
+
+impl Strategy for FixedHold {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let phase = self.bar % self.period;
+        self.bar += 1;
+        let side = match phase {
+            0 => OrderSide::Buy,
+            p if p == self.hold => OrderSide::Sell,
+            _ => return Ok(None),
+        };
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: None,
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+fn backtester(run_id: Uuid, strategy: Box<dyn Strategy>, bars: usize) -> Backtester {
+    let config = test_config(bars).expect("load config");
+    TestBacktester::new(&config, strategy).with_run_id(run_id).build()
+}
+
+#[tokio::test]
+async fn repeated_runs_produce_identical_trades() {
+    const BARS: usize = 3000;
+    let klines = generate_klines(BARS);
+    let config = test_config(BARS).expect("load config");
+    let run_id = Uuid::from_u128(0x5eed);
+
+    let mut results = Vec::new();
+    for _ in 0..2 {
+        let strategy = create_strategy(StrategyId::MACrossover, &config, TEST_SYMBOL).unwrap();
+        let (trades, equity_curve) = backtester(run_id, strategy, BARS).simulate(&klines).await.expect("simulate");
+        assert!(!trades.is_empty());
+        results.push(serde_json::to_string(&(trades, equity_curve)).unwrap());
+    }
+
+    assert_eq!(results[0], results[1]);
+}
+
+#[tokio::test]
+async fn holding_period_is_measured_in_bars() {
+    const BARS: usize = 200;
+    const HOLD: usize = 10;
+    // A flat market, so no stop-loss ever interrupts the fixed holding period.
+    let klines: Vec<Kline> = (0..BARS)
+        .map(|i| {
+            let open_time = seed_start() + Duration::hours(i as i64);
+            Kline {
+                open_time,
+                open: dec!(100),
+                high: dec!(100.1),
+                low: dec!(99.9),
+                close: dec!(100),
+                volume: dec!(1000),
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect();
+
+    let strategy = Box::new(FixedHold { period: 25, hold: HOLD, bar: 0 });
+    let mut backtester = backtester(Uuid::new_v4(), strategy, BARS);
+    let (trades, equity_curve) = backtester.simulate(&klines).await.expect("simulate");
+    assert_eq!(trades.len(), BARS / 25);
+
+    for trade in &trades {
+        assert_eq!(trade.exit_execution.timestamp - trade.entry_execution.timestamp, Duration::hours(HOLD as i64));
+    }
+    let report = analytics::AnalyticsEngine::new()
+        .calculate(&trades, &equity_curve, backtester.exposure(), dec!(10000), TEST_INTERVAL)
+        .expect("analytics");
+    assert_eq!(report.average_holding_period, Duration::hours(HOLD as i64));
+}
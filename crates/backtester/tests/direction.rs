@@ -0,0 +1,77 @@
+//! Checks that a long-only or short-only backtest never holds a position on the other side.
+
+use backtester::{run_backtest, BacktestSpec, KlineSource};
+use core_types::{Kline, OrderSide, StrategyId, TradeDirection};
+use rust_decimal::Decimal;
+use strategies::create_strategy;
+use testing::synthetic::{gbm, SeriesSpec};
+use testing::{test_config, RecordingExecutor, TestBacktester, TEST_SYMBOL};
+
+const BARS: usize = 2000;
+const SEED: u64 = 7;
+
+/// A down-trending series with rallies enough for SuperTrend to turn long now and then.
+fn downtrend() -> Vec<Kline> {
+    gbm(&SeriesSpec::default(), BARS, -0.0005, 0.006, SEED)
+}
+
+/// Runs SuperTrend over `klines` in `direction` and returns the net position after each of
+/// its executions, positive when long.
+async fn net_positions(klines: &[Kline], direction: TradeDirection) -> Vec<Decimal> {
+    let mut config = test_config(klines.len()).expect("load config");
+    config.backtest.direction = direction;
+    let executor = RecordingExecutor::new(config.simulation.clone());
+    let executions = executor.executions();
+    let strategy = create_strategy(StrategyId::SuperTrend, &config, TEST_SYMBOL).unwrap();
+    let mut backtester = TestBacktester::new(&config, strategy).with_executor(Box::new(executor)).build();
+    backtester.simulate(klines).await.expect("simulate");
+
+    let executions = executions.lock().unwrap();
+    executions
+        .iter()
+        .scan(Decimal::ZERO, |net, execution| {
+            *net += match execution.side {
+                OrderSide::Buy => execution.quantity,
+                OrderSide::Sell => -execution.quantity,
+            };
+            Some(*net)
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn long_only_super_trend_only_exits_a_downtrend() {
+    let klines = downtrend();
+    assert!(klines.last().unwrap().close < klines[0].open, "the series does not trend down");
+
+    // Trading both sides, SuperTrend goes short on the way down.
+    let both = net_positions(&klines, TradeDirection::Both).await;
+    assert!(both.iter().any(|net| *net < Decimal::ZERO), "no short was taken in the downtrend");
+
+    // Long-only, its short signals only close the longs it took, so it is long or flat.
+    let long_only = net_positions(&klines, TradeDirection::LongOnly).await;
+    assert!(long_only.iter().any(|net| *net > Decimal::ZERO), "no long was taken");
+    assert!(long_only.iter().all(|net| *net >= Decimal::ZERO), "a short was taken: {:?}", long_only);
+    assert!(long_only.iter().any(|net| net.is_zero()), "no long was closed");
+}
+
+#[tokio::test]
+async fn short_only_super_trend_never_goes_long() {
+    let short_only = net_positions(&downtrend(), TradeDirection::ShortOnly).await;
+    assert!(short_only.iter().any(|net| *net < Decimal::ZERO), "no short was taken");
+    assert!(short_only.iter().all(|net| *net <= Decimal::ZERO), "a long was taken: {:?}", short_only);
+}
+
+#[tokio::test]
+async fn a_spec_carries_the_direction_of_its_config() {
+    let klines = downtrend();
+    let mut config = test_config(BARS).expect("load config");
+    config.backtest.strategy_id = StrategyId::SuperTrend;
+    config.backtest.direction = TradeDirection::LongOnly;
+    let spec = BacktestSpec::from_config(&config, KlineSource::InMemory(klines)).expect("spec");
+    assert_eq!(spec.direction, TradeDirection::LongOnly);
+
+    let output = run_backtest(spec).await.expect("run_backtest");
+    assert!(!output.trades.is_empty());
+    assert!(output.trades.iter().all(|trade| trade.entry_execution.side == OrderSide::Buy));
+}
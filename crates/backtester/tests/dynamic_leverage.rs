@@ -0,0 +1,66 @@
+//! Checks that a backtest scales entries down by the drawdown tiers only when the
+//! configuration's `apply_in_backtests` is set.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::{Config, DrawdownTier, DynamicLeverage};
+use core_types::Kline;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, InAndOut, TEST_INTERVAL};
+
+/// `count` one-minute bars falling by 2 a bar from 100, so every long loses.
+fn klines(count: usize) -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..count)
+        .map(|i| {
+            let open_time = start + Duration::minutes(i as i64);
+            let close = dec!(100) - Decimal::from(2 * i as i64);
+            Kline {
+                open_time,
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::minutes(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// Entries of half the equity, a stop no bar reaches and half risk from a 5% drawdown.
+fn config(apply_in_backtests: bool) -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.risk_management.risk_per_trade_pct = dec!(0.02);
+    config.risk_management.stop_loss_pct = dec!(0.04);
+    config.risk_management.min_expected_move = None;
+    config.global_risk.dynamic_leverage = Some(DynamicLeverage {
+        tiers: vec![DrawdownTier { drawdown_pct: dec!(0.05), multiplier: dec!(0.5) }],
+        recovery_buffer_pct: dec!(0.01),
+        apply_in_backtests,
+    });
+    config
+}
+
+/// The entry quantity of each trade.
+async fn entry_quantities(config: Config) -> Vec<Decimal> {
+    let mut backtester = backtester(&config, Box::new(InAndOut::default()));
+    let (trades, _) = backtester.simulate(&klines(20)).await.expect("simulate");
+    trades.iter().map(|trade| trade.entry_execution.quantity).collect()
+}
+
+#[tokio::test]
+async fn entries_are_scaled_down_once_the_drawdown_reaches_a_tier() {
+    let full = entry_quantities(config(false)).await;
+    let scaled = entry_quantities(config(true)).await;
+    assert_eq!((full.len(), scaled.len()), (10, 10));
+
+    // The first trades are taken at full risk in both runs.
+    assert_eq!(scaled[0], full[0]);
+    // By the last trades, each of which lost over 1% of the equity, the drawdown is past 5%.
+    for (scaled, full) in scaled.iter().zip(&full).skip(7) {
+        assert!(*scaled < full * dec!(0.6), "{} against {} at full risk", scaled, full);
+    }
+}
+
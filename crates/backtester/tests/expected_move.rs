@@ -0,0 +1,63 @@
+//! Checks that the minimum expected-move filter keeps entries whose stop is too close to pay
+//! for their round trip out of a backtest, and counts them in the run's metadata.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::{Config, MinExpectedMove};
+use core_types::{Kline, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, InAndOut, TEST_INTERVAL};
+
+/// `count` flat one-minute bars closing at 100, each 0.1% wide.
+fn klines(count: usize) -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..count)
+        .map(|i| {
+            let open_time = start + Duration::minutes(i as i64);
+            Kline {
+                open_time,
+                open: dec!(100),
+                high: dec!(100.05),
+                low: dec!(99.95),
+                close: dec!(100),
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::minutes(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// A 0.04% taker fee, no spread, and the filter at its defaults under a `stop_loss_pct` stop.
+fn config(stop_loss_pct: Decimal) -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = dec!(0.0004);
+    config.simulation.fee_discount_pct = Decimal::ZERO;
+    config.simulation.slippage_pct = dec!(0.1);
+    config.simulation.simulated_spread_pct = Decimal::ZERO;
+    config.risk_management.stop_loss_pct = stop_loss_pct;
+    config.risk_management.min_expected_move = Some(MinExpectedMove::default());
+    config
+}
+
+async fn run(config: Config, klines: &[Kline]) -> (Vec<Trade>, i64) {
+    let mut backtester = backtester(&config, Box::new(InAndOut::default()));
+    let trades = backtester.simulate(klines).await.expect("simulate").0;
+    (trades, backtester.metadata().signals_filtered)
+}
+
+#[tokio::test]
+async fn a_stop_inside_the_round_trip_cost_filters_every_entry() {
+    let (trades, signals_filtered) = run(config(dec!(0.0005)), &klines(20)).await;
+
+    assert!(trades.is_empty());
+    assert_eq!(signals_filtered, 10);
+}
+
+#[tokio::test]
+async fn a_wider_stop_trades_every_entry() {
+    let (trades, signals_filtered) = run(config(dec!(0.005)), &klines(20)).await;
+
+    assert_eq!(trades.len(), 10);
+    assert_eq!(signals_filtered, 0);
+}
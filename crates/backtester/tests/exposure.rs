@@ -0,0 +1,91 @@
+//! Checks that the simulation records the notional it holds at each point of the equity curve.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::Config;
+use core_types::{Kline, Signal};
+use events::PortfolioState;
+use risk::{OrderPlan, RiskError, RiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, EnterAt, TestBacktester, TEST_INTERVAL};
+
+/// Sizes every entry to the whole of the portfolio's value.
+struct AllIn;
+
+impl RiskManager for AllIn {
+    fn evaluate_signal(&self, signal: &Signal, portfolio_state: &PortfolioState, entry_price: Decimal) -> Result<OrderPlan, RiskError> {
+        let mut order = signal.order_request.clone();
+        order.quantity = portfolio_state.total_value / entry_price;
+        Ok(OrderPlan::single(order))
+    }
+}
+
+/// `count` hourly bars at 100, except the fifteenth, which closes at 110.
+fn klines(count: usize) -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..count)
+        .map(|i| {
+            let open_time = start + Duration::hours(i as i64);
+            let close = if i == 14 { dec!(110) } else { dec!(100) };
+            Kline {
+                open_time,
+                open: dec!(100),
+                high: close + Decimal::ONE,
+                low: dec!(99),
+                close,
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// A frictionless config whose positions are closed after ten bars.
+fn config() -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = Decimal::ZERO;
+    config.simulation.maker_fee_pct = Decimal::ZERO;
+    config.simulation.slippage_pct = Decimal::ZERO;
+    config.risk_management.stop_loss_pct = dec!(0.05);
+    config.risk_management.max_holding_bars = Some(10);
+    config
+}
+
+#[tokio::test]
+async fn exposure_is_recorded_for_every_bar_a_position_is_held() {
+    let config = config();
+    let mut backtester = backtester(&config, Box::new(EnterAt::bar(4)));
+
+    let (trades, equity_curve) = backtester.simulate(&klines(20)).await.expect("simulate");
+
+    // Entered on the fifth bar's close and closed ten bars later.
+    assert_eq!(trades.len(), 1);
+    let notional = trades[0].entry_execution.quantity * dec!(100);
+    let expected: Vec<Decimal> = (0..20).map(|bar| if (4..14).contains(&bar) { notional } else { Decimal::ZERO }).collect();
+    assert_eq!(backtester.exposure(), expected.as_slice());
+    assert_eq!(backtester.exposure().len(), equity_curve.len());
+
+    let report = analytics::AnalyticsEngine::new()
+        .calculate(&trades, &equity_curve, backtester.exposure(), config.backtest.initial_capital, TEST_INTERVAL)
+        .expect("report");
+    assert_eq!(report.time_in_market_pct, Some(dec!(50)));
+}
+
+#[tokio::test]
+async fn a_run_in_the_market_half_the_time_earns_double_on_its_deployed_margin() {
+    let config = config();
+    let mut backtester = TestBacktester::new(&config, Box::new(EnterAt::bar(4))).with_risk_manager(Box::new(AllIn)).build();
+
+    let (trades, equity_curve) = backtester.simulate(&klines(20)).await.expect("simulate");
+
+    // The whole capital is deployed over half the bars, and the exit bar's close is 10% up.
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].exit_execution.price, dec!(110));
+    let report = analytics::AnalyticsEngine::new()
+        .calculate(&trades, &equity_curve, backtester.exposure(), config.backtest.initial_capital, TEST_INTERVAL)
+        .expect("report");
+    assert_eq!(report.time_in_market_pct, Some(dec!(50)));
+    assert_eq!(report.total_return_pct, dec!(10));
+    assert_eq!(report.return_on_margin_pct, Some(report.total_return_pct * dec!(2)));
+}
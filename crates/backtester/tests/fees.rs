@@ -0,0 +1,120 @@
+//! Checks that simulated fills pay the maker fee for limit orders and the taker fee for
+//! market orders, with the fee discount applied to both.
+
+use configuration::Config;
+use core_types::{Execution, Kline, OrderRequest, OrderSide, OrderType, Signal, Trade};
+use events::PortfolioState;
+use risk::{OrderPlan, RiskError, RiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use strategies::{Strategy, StrategyError};
+use testing::{generate_klines, test_config, TestBacktester, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+const BARS: usize = 2000;
+
+/// Buys on the 5th bar of every 20 and sells on the 15th, placing orders of `order_type`.
+struct Alternating {
+    order_type: OrderType,
+    seen: usize,
+}
+
+impl Strategy for Alternating {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        self.seen += 1;
+        let side = match self.seen % 20 {
+            5 => OrderSide::Buy,
+            15 => OrderSide::Sell,
+            _ => return Ok(None),
+        };
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: None,
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side,
+                order_type: self.order_type,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// Trades one unit, or closes the open position, so sizing does not depend on equity and
+/// both runs fill identically.
+struct OneUnit;
+
+impl RiskManager for OneUnit {
+    fn evaluate_signal(&self, signal: &Signal, portfolio_state: &PortfolioState, _: Decimal) -> Result<OrderPlan, RiskError> {
+        let mut order = signal.order_request.clone();
+        order.quantity = portfolio_state
+            .positions
+            .iter()
+            .find(|p| p.side != order.side)
+            .map_or(Decimal::ONE, |p| p.quantity);
+        Ok(OrderPlan::single(order))
+    }
+}
+
+async fn run(config: &Config, run_id: Uuid, order_type: OrderType) -> (Vec<Trade>, Decimal, analytics::PerformanceReport) {
+    let mut backtester = TestBacktester::new(config, Box::new(Alternating { order_type, seen: 0 }))
+        .with_run_id(run_id)
+        .with_risk_manager(Box::new(OneUnit))
+        .build();
+    let (trades, equity_curve) = backtester.simulate(&generate_klines(BARS)).await.expect("simulate");
+    let report = analytics::AnalyticsEngine::new()
+        .calculate(&trades, &equity_curve, backtester.exposure(), config.backtest.initial_capital, TEST_INTERVAL)
+        .expect("report");
+    (trades, equity_curve.last().unwrap().1, report)
+}
+
+fn executions(trades: &[Trade]) -> impl Iterator<Item = &Execution> {
+    trades.iter().flat_map(|t| [&t.entry_execution, &t.exit_execution])
+}
+
+#[tokio::test]
+async fn limit_orders_earn_exactly_the_fee_differential() {
+    let mut config = test_config(BARS).expect("load config");
+    config.simulation.taker_fee_pct = dec!(0.0005);
+    config.simulation.maker_fee_pct = dec!(0.0002);
+    config.simulation.fee_discount_pct = dec!(0.1);
+    let run_id = Uuid::new_v4();
+
+    let (market_trades, market_equity, market_report) = run(&config, run_id, OrderType::Market).await;
+    let (limit_trades, limit_equity, limit_report) = run(&config, run_id, OrderType::Limit).await;
+
+    assert!(market_trades.len() > 10);
+    assert_eq!(market_trades.len(), limit_trades.len());
+    let mut differential = Decimal::ZERO;
+    for (market, limit) in executions(&market_trades).zip(executions(&limit_trades)) {
+        assert_eq!((market.price, market.quantity, market.side), (limit.price, limit.quantity, limit.side));
+        let notional = market.price * market.quantity;
+        assert_eq!(market.fee, notional * dec!(0.0005) * dec!(0.9));
+        assert!(!market.is_maker);
+        if limit.is_maker {
+            assert_eq!(limit.fee, notional * dec!(0.0002) * dec!(0.9));
+        } else {
+            // Stop-loss exits are market orders, whatever the strategy places.
+            assert_eq!(limit.fee, market.fee);
+        }
+        differential += market.fee - limit.fee;
+    }
+
+    assert!(differential > Decimal::ZERO);
+    assert_eq!(limit_equity - market_equity, differential);
+    assert_eq!(market_report.maker_fees_paid, Decimal::ZERO);
+    assert_eq!(
+        market_report.taker_fees_paid - limit_report.taker_fees_paid - limit_report.maker_fees_paid,
+        differential
+    );
+    assert!(limit_report.maker_fees_paid > Decimal::ZERO);
+}
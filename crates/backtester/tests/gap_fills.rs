@@ -0,0 +1,120 @@
+//! Checks where stop-losses and take-profits fill under each `StopFillModel`, and that what
+//! gaps through the stop cost is reported.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::{Config, StopFillModel};
+use core_types::{CloseReason, Kline, OrderSide, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, EnterOnce, TEST_INTERVAL};
+
+/// Hourly bars from midnight, each given as (open, high, low, close).
+fn klines(bars: &[(Decimal, Decimal, Decimal, Decimal)]) -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    bars.iter()
+        .enumerate()
+        .map(|(i, &(open, high, low, close))| {
+            let open_time = start + Duration::hours(i as i64);
+            Kline {
+                open_time,
+                open,
+                high,
+                low,
+                close,
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// A frictionless config with a 20% stop: at 100 for a long entered at 125, and at 120 for
+/// a short entered at 100.
+fn config(stop_fill_model: StopFillModel) -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = Decimal::ZERO;
+    config.simulation.maker_fee_pct = Decimal::ZERO;
+    config.simulation.slippage_pct = Decimal::ZERO;
+    config.simulation.simulated_spread_pct = Decimal::ZERO;
+    config.simulation.stop_fill_model = stop_fill_model;
+    config.risk_management.stop_loss_pct = dec!(0.2);
+    config.risk_management.break_even_trigger_pct = None;
+    config
+}
+
+async fn run(stop_fill_model: StopFillModel, side: OrderSide, klines: &[Kline]) -> (Vec<Trade>, Decimal) {
+    run_with(config(stop_fill_model), side, klines).await
+}
+
+async fn run_with(config: Config, side: OrderSide, klines: &[Kline]) -> (Vec<Trade>, Decimal) {
+    let mut backtester = backtester(&config, Box::new(EnterOnce::new(side)));
+    let (trades, _) = backtester.simulate(klines).await.expect("simulate");
+    (trades, backtester.gap_slippage())
+}
+
+/// A long entered at 125, with its stop at 100, then a bar that opens at 97, below the stop.
+fn long_gapped_through_its_stop() -> Vec<Kline> {
+    klines(&[(dec!(125), dec!(126), dec!(124), dec!(125)), (dec!(97), dec!(98), dec!(96), dec!(97.5))])
+}
+
+#[tokio::test]
+async fn a_gapped_stop_fills_at_the_open_when_gap_aware() {
+    let (trades, gap_slippage) = run(StopFillModel::GapAware, OrderSide::Buy, &long_gapped_through_its_stop()).await;
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].close_reason, CloseReason::StopLoss);
+    assert_eq!(trades[0].exit_execution.price, dec!(97));
+    assert_eq!(gap_slippage, dec!(3) * trades[0].exit_execution.quantity);
+}
+
+#[tokio::test]
+async fn a_gapped_stop_fills_at_its_price_when_exact() {
+    let (trades, gap_slippage) = run(StopFillModel::Exact, OrderSide::Buy, &long_gapped_through_its_stop()).await;
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].close_reason, CloseReason::StopLoss);
+    assert_eq!(trades[0].exit_execution.price, dec!(100));
+    assert_eq!(gap_slippage, Decimal::ZERO);
+}
+
+#[tokio::test]
+async fn a_stop_reached_within_the_bar_fills_at_its_price_either_way() {
+    // The bar opens above the stop and trades down through it.
+    let bars = klines(&[(dec!(125), dec!(126), dec!(124), dec!(125)), (dec!(101), dec!(102), dec!(96), dec!(97))]);
+    for model in [StopFillModel::Exact, StopFillModel::GapAware] {
+        let (trades, gap_slippage) = run(model, OrderSide::Buy, &bars).await;
+        assert_eq!(trades[0].exit_execution.price, dec!(100), "{:?}", model);
+        assert_eq!(gap_slippage, Decimal::ZERO, "{:?}", model);
+    }
+}
+
+#[tokio::test]
+async fn a_short_s_stop_gapped_up_through_fills_at_the_open() {
+    // A short entered at 100 has its stop at 120; the next bar opens at 126.
+    let bars = klines(&[(dec!(100), dec!(101), dec!(99), dec!(100)), (dec!(126), dec!(128), dec!(125), dec!(127))]);
+    let (trades, gap_slippage) = run(StopFillModel::GapAware, OrderSide::Sell, &bars).await;
+    assert_eq!(trades[0].close_reason, CloseReason::StopLoss);
+    assert_eq!(trades[0].exit_execution.price, dec!(126));
+    assert_eq!(gap_slippage, dec!(6) * trades[0].exit_execution.quantity);
+}
+
+#[tokio::test]
+async fn a_take_profit_gapped_through_fills_at_the_open_when_gap_aware() {
+    // A long entered at 125 has its take-profit at 150; the next bar opens at 160.
+    let bars = klines(&[(dec!(125), dec!(126), dec!(124), dec!(125)), (dec!(160), dec!(162), dec!(158), dec!(161))]);
+    for (model, fill) in [(StopFillModel::GapAware, dec!(160)), (StopFillModel::Exact, dec!(150))] {
+        let mut config = config(model);
+        config.risk_management.take_profit_pct = Some(dec!(0.2));
+        let (trades, gap_slippage) = run_with(config, OrderSide::Buy, &bars).await;
+        assert_eq!(trades[0].close_reason, CloseReason::TakeProfit, "{:?}", model);
+        assert_eq!(trades[0].exit_execution.price, fill, "{:?}", model);
+        assert_eq!(gap_slippage, Decimal::ZERO, "{:?}", model);
+    }
+
+    // A short entered at 100 has its take-profit at 80; the next bar opens at 75.
+    let bars = klines(&[(dec!(100), dec!(101), dec!(99), dec!(100)), (dec!(75), dec!(77), dec!(74), dec!(76))]);
+    let mut config = config(StopFillModel::GapAware);
+    config.risk_management.take_profit_pct = Some(dec!(0.2));
+    let (trades, _) = run_with(config, OrderSide::Sell, &bars).await;
+    assert_eq!(trades[0].close_reason, CloseReason::TakeProfit);
+    assert_eq!(trades[0].exit_execution.price, dec!(75));
+}
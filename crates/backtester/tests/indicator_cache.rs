@@ -0,0 +1,56 @@
+//! Checks that optimizer-style runs, sharing their klines and an `IndicatorCache`, trade
+//! exactly as runs loading their own klines and computing their own indicators.
+
+use backtester::KlineSource;
+use chrono::{DateTime, Utc};
+use configuration::{Config, MACrossoverParams};
+use core_types::Trade;
+use std::sync::Arc;
+use strategies::{create_strategy_from_params, create_strategy_from_params_with_cache, IndicatorCache, Strategy, StrategyId};
+use testing::synthetic::{regime_switching, Regime, SeriesSpec};
+use testing::{test_config, TestBacktester, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// Warms `strategy` up on the klines `source` has before `start` and replays those from it.
+async fn run(config: &Config, run_id: Uuid, strategy: Box<dyn Strategy>, source: &KlineSource, start: DateTime<Utc>) -> Vec<Trade> {
+    let mut backtester = TestBacktester::new(config, strategy).with_run_id(run_id).build();
+    let (warmup, klines) = source
+        .load(TEST_SYMBOL, TEST_INTERVAL, start, DateTime::<Utc>::MAX_UTC, backtester.required_warmup_bars())
+        .await
+        .expect("load klines");
+    backtester.warm_up(&warmup).expect("warm up");
+    backtester.simulate(&klines).await.expect("simulate").0
+}
+
+#[tokio::test]
+async fn cached_runs_trade_as_uncached_ones() {
+    let regimes = [Regime::trend(500, 0.002, 0.01), Regime::chop(500, 0.02), Regime::trend(500, -0.002, 0.01)];
+    let klines = regime_switching(&SeriesSpec::default(), &regimes, 3);
+    let config = test_config(klines.len()).expect("load config");
+    let start = klines[300].open_time;
+
+    let shared = Arc::new(klines.clone());
+    let cache = Arc::new(IndicatorCache::new(shared.clone()));
+    let shared = KlineSource::Shared(shared);
+    let in_memory = KlineSource::InMemory(klines);
+
+    let mut traded = 0;
+    for (fast, slow, ma_type) in [(10, 60, "sma"), (20, 60, "sma"), (10, 100, "ema"), (25, 100, "ema")] {
+        let params = serde_json::to_value(MACrossoverParams {
+            ma_fast_period: fast,
+            ma_slow_period: slow,
+            trend_filter_period: 200,
+            ma_type: ma_type.to_string(),
+            confirmation_bars: 0,
+        })
+        .unwrap();
+        let run_id = Uuid::new_v4();
+        let uncached = create_strategy_from_params(StrategyId::MACrossover, &params, TEST_SYMBOL).unwrap();
+        let cached = create_strategy_from_params_with_cache(StrategyId::MACrossover, &params, TEST_SYMBOL, Some(cache.clone())).unwrap();
+
+        let expected = run(&config, run_id, uncached, &in_memory, start).await;
+        assert_eq!(run(&config, run_id, cached, &shared, start).await, expected, "{}", params);
+        traded += expected.len();
+    }
+    assert!(traded > 0, "the runs need trades to compare");
+}
@@ -0,0 +1,54 @@
+//! Checks that a kline transform only changes what the strategy sees, never the fills.
+
+use async_trait::async_trait;
+use core_types::{Execution, Kline, KlineTransform, OrderRequest, StrategyId};
+use executor::{Executor, ExecutorError, SimulatedExecutor};
+use rust_decimal::Decimal;
+use std::sync::{Arc, Mutex};
+use strategies::create_strategy;
+use testing::{generate_klines, test_config, TestBacktester, TEST_SYMBOL};
+
+const BARS: usize = 3000;
+
+/// Wraps the simulated executor and records every kline it is asked to fill against.
+struct RecordingExecutor {
+    inner: SimulatedExecutor,
+    seen: Arc<Mutex<Vec<Kline>>>,
+}
+
+#[async_trait]
+impl Executor for RecordingExecutor {
+    async fn execute(
+        &self,
+        order: &OrderRequest,
+        kline: &Kline,
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
+    ) -> Result<Execution, ExecutorError> {
+        self.seen.lock().unwrap().push(kline.clone());
+        self.inner.execute(order, kline, best_bid, best_ask).await
+    }
+}
+
+#[tokio::test]
+async fn heikin_ashi_does_not_change_execution_klines() {
+    let klines = generate_klines(BARS);
+    let mut config = test_config(BARS).expect("load config");
+    config.backtest.kline_transform = KlineTransform::HeikinAshi;
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let strategy = create_strategy(StrategyId::MACrossover, &config, TEST_SYMBOL).unwrap();
+    let mut backtester = TestBacktester::new(&config, strategy)
+        .with_executor(Box::new(RecordingExecutor { inner: SimulatedExecutor::new(config.simulation.clone()), seen: Arc::clone(&seen) }))
+        .build();
+
+    let (trades, _) = backtester.simulate(&klines).await.expect("simulate");
+    assert!(!trades.is_empty());
+
+    let seen = seen.lock().unwrap();
+    assert!(!seen.is_empty());
+    for filled_against in seen.iter() {
+        let real = klines.iter().find(|k| k.open_time == filled_against.open_time).expect("known bar");
+        assert_eq!(filled_against, real);
+    }
+}
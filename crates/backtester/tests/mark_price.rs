@@ -0,0 +1,116 @@
+//! Checks that stops trigger on, and equity is valued at, the mark price when the
+//! configuration asks for it, while fills stay at the last price.
+
+use backtester::error::BacktestError;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use configuration::Config;
+use core_types::{CloseReason, Kline, PriceType, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, EnterOnce, TEST_INTERVAL};
+
+/// Hourly bars from (high, low, close) triples.
+fn klines(bars: &[(Decimal, Decimal, Decimal)]) -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    bars.iter()
+        .enumerate()
+        .map(|(i, &(high, low, close))| {
+            let open_time = start + Duration::hours(i as i64);
+            Kline {
+                open_time,
+                open: close,
+                high,
+                low,
+                close,
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+// The last price never reaches the 2% stop at 98, but the mark price spikes through it on
+// the second bar.
+const LAST_BARS: &[(Decimal, Decimal, Decimal)] = &[
+    (dec!(100), dec!(100), dec!(100)), // Entry at 100; the stop starts at 98.
+    (dec!(101), dec!(99), dec!(100)),
+    (dec!(102), dec!(100), dec!(101)),
+];
+const MARK_BARS: &[(Decimal, Decimal, Decimal)] = &[
+    (dec!(100), dec!(100), dec!(100)),
+    (dec!(101), dec!(97.5), dec!(99.5)), // Dips through the stop.
+    (dec!(102), dec!(100), dec!(101)),
+];
+
+/// A frictionless config with a 2% stop, so orders fill exactly at each bar's close and
+/// stops at their price.
+fn config(valuation_price: PriceType) -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = Decimal::ZERO;
+    config.simulation.maker_fee_pct = Decimal::ZERO;
+    config.simulation.slippage_pct = Decimal::ZERO;
+    config.risk_management.stop_loss_pct = dec!(0.02);
+    config.risk_management.break_even_trigger_pct = None;
+    config.backtest.valuation_price = valuation_price;
+    config
+}
+
+type Simulated = (Vec<Trade>, Vec<(DateTime<Utc>, Decimal)>);
+
+async fn run(valuation_price: PriceType, mark_prices: Vec<Kline>) -> Result<Simulated, BacktestError> {
+    let config = config(valuation_price);
+    let mut backtester = backtester(&config, Box::new(EnterOnce::long()))
+    .with_mark_prices(mark_prices);
+    backtester.simulate(&klines(LAST_BARS)).await
+}
+
+#[tokio::test]
+async fn a_mark_price_spike_stops_out_only_when_valuing_at_the_mark() {
+    let (trades, _) = run(PriceType::Last, klines(MARK_BARS)).await.expect("simulate");
+    assert!(trades.is_empty(), "the last price never reached the stop");
+
+    let (trades, _) = run(PriceType::Mark, klines(MARK_BARS)).await.expect("simulate");
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].close_reason, CloseReason::StopLoss);
+    assert_eq!(trades[0].exit_execution.timestamp, klines(LAST_BARS)[1].close_time);
+    // The stop fills at the last price, not the mark: as close to the stop as the last
+    // price's low of the bar.
+    assert_eq!(trades[0].exit_execution.price, dec!(99));
+}
+
+#[tokio::test]
+async fn equity_is_valued_at_the_mark_price() {
+    // The same spike, but shallow enough not to reach the stop.
+    let marks: Vec<Kline> = klines(MARK_BARS)
+        .into_iter()
+        .map(|mark| Kline { low: mark.low.max(dec!(98.5)), ..mark })
+        .collect();
+    let (_, last_curve) = run(PriceType::Last, marks.clone()).await.expect("simulate");
+    let (trades, mark_curve) = run(PriceType::Mark, marks).await.expect("simulate");
+    assert!(trades.is_empty());
+
+    // Only the second bar's close differs, by half a point on the whole position.
+    assert_eq!(last_curve[0], mark_curve[0]);
+    assert_eq!(last_curve[2], mark_curve[2]);
+    assert!(mark_curve[1].1 < last_curve[1].1);
+    // The last close rises by one point on the third bar, which gives the position's size.
+    let quantity = last_curve[2].1 - last_curve[1].1;
+    assert_eq!(last_curve[1].1 - mark_curve[1].1, quantity * dec!(0.5));
+}
+
+#[tokio::test]
+async fn missing_mark_prices_fail_before_simulating() {
+    let marks = klines(MARK_BARS)[..1].to_vec();
+    let error = run(PriceType::Mark, marks).await.unwrap_err();
+
+    let BacktestError::MarkPricesUnavailable { missing, bars, first_missing, .. } = &error else {
+        panic!("expected missing mark prices, got {error}");
+    };
+    assert_eq!((*missing, *bars), (2, 3));
+    assert_eq!(*first_missing, klines(LAST_BARS)[1].open_time);
+    assert!(error.to_string().contains("backfill --price-type mark"));
+
+    // Valuing at the last price needs no mark prices at all.
+    assert!(run(PriceType::Last, Vec::new()).await.is_ok());
+}
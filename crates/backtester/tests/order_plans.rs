@@ -0,0 +1,114 @@
+//! Checks that the backtester places every leg of an order plan, and that a plan failing after
+//! some of its legs filled stops the strategy trading, as it halts a live bot.
+
+use async_trait::async_trait;
+use core_types::{Execution, Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent, Trade};
+use executor::{Executor, ExecutorError, SimulatedExecutor};
+use rust_decimal::Decimal;
+use std::sync::{Arc, Mutex};
+use strategies::{Strategy, StrategyError};
+use testing::synthetic::{sine_trend, SeriesSpec, SineTrend};
+use testing::{test_config, TestBacktester, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// Emits the `n`th of `signals` on the `n`th bar it sees, and nothing after them.
+struct Scripted {
+    signals: Vec<Option<(SignalIntent, OrderSide)>>,
+    seen: usize,
+}
+
+impl Strategy for Scripted {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let signal = self.signals.get(self.seen).copied().flatten();
+        self.seen += 1;
+        Ok(signal.map(|(intent, side)| Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: Some(intent),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// Wraps the simulated executor, records every execution, and rejects the `fail_on`th order.
+struct FailingExecutor {
+    inner: SimulatedExecutor,
+    fail_on: Option<usize>,
+    executions: Arc<Mutex<Vec<Execution>>>,
+    placed: Mutex<usize>,
+}
+
+#[async_trait]
+impl Executor for FailingExecutor {
+    async fn execute(&self, order: &OrderRequest, kline: &Kline, best_bid: Option<Decimal>, best_ask: Option<Decimal>) -> Result<Execution, ExecutorError> {
+        let placed = {
+            let mut placed = self.placed.lock().unwrap();
+            *placed += 1;
+            *placed
+        };
+        if Some(placed) == self.fail_on {
+            return Err(ExecutorError::Rejected("order rejected".to_string()));
+        }
+        let execution = self.inner.execute(order, kline, best_bid, best_ask).await?;
+        self.executions.lock().unwrap().push(execution.clone());
+        Ok(execution)
+    }
+}
+
+/// Runs `signals` over flat bars with the `fail_on`th order rejected, returning the trades,
+/// the executions and the position left open.
+async fn run(signals: Vec<Option<(SignalIntent, OrderSide)>>, fail_on: Option<usize>) -> (Vec<Trade>, Vec<Execution>, Option<OrderSide>) {
+    let klines = sine_trend(&SeriesSpec::default(), 20, &SineTrend { slope: 0.0, amplitude: 0.0, ..SineTrend::default() });
+    let config = test_config(klines.len()).expect("load config");
+    let executions = Arc::new(Mutex::new(Vec::new()));
+    let executor = FailingExecutor { inner: SimulatedExecutor::new(config.simulation.clone()), fail_on, executions: executions.clone(), placed: Mutex::new(0) };
+    let mut backtester =
+        TestBacktester::new(&config, Box::new(Scripted { signals, seen: 0 })).with_executor(Box::new(executor)).build();
+
+    let (trades, _) = backtester.simulate(&klines).await.expect("simulate");
+    let position = backtester.portfolio().get_position(TEST_SYMBOL).map(|position| position.side);
+    let executions = executions.lock().unwrap().clone();
+    (trades, executions, position)
+}
+
+#[tokio::test]
+async fn an_open_against_the_position_closes_it_then_enters() {
+    let signals = vec![Some((SignalIntent::OpenLong, OrderSide::Buy)), Some((SignalIntent::OpenShort, OrderSide::Sell))];
+    let (trades, executions, position) = run(signals, None).await;
+
+    assert_eq!(executions.len(), 3);
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].entry_execution.side, OrderSide::Buy);
+    // The re-entry is not lost: the short is opened on the bar the long is closed.
+    assert_eq!(position, Some(OrderSide::Sell));
+    assert_eq!(executions[1].timestamp, executions[2].timestamp);
+}
+
+#[tokio::test]
+async fn a_failed_second_leg_stops_the_strategy_trading() {
+    let signals = vec![
+        Some((SignalIntent::OpenLong, OrderSide::Buy)),
+        Some((SignalIntent::Reverse, OrderSide::Sell)),
+        Some((SignalIntent::OpenLong, OrderSide::Buy)),
+        Some((SignalIntent::OpenShort, OrderSide::Sell)),
+    ];
+    // The reversal's close fills but its entry is rejected.
+    let (trades, executions, position) = run(signals, Some(3)).await;
+
+    assert_eq!(executions.len(), 2);
+    assert_eq!(trades.len(), 1);
+    assert_eq!(position, None);
+}
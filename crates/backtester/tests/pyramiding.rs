@@ -0,0 +1,86 @@
+//! Checks that pyramiding adds to a winning position a bounded number of times, spaced by price.
+
+use chrono::Duration;
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use strategies::{Strategy, StrategyError};
+use testing::{seed_start, test_config, RecordingExecutor, TestBacktester, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+const BARS: usize = 60;
+
+/// Signals a long entry on every bar.
+struct AlwaysLong;
+
+impl Strategy for AlwaysLong {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: None,
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// Hourly klines whose close rises 0.5% per bar, with no wicks.
+fn trending_klines(count: usize) -> Vec<Kline> {
+    let mut close = dec!(100);
+    (0..count)
+        .map(|i| {
+            let open = close;
+            close = (close * dec!(1.005)).round_dp(4);
+            let open_time = seed_start() + Duration::hours(i as i64);
+            Kline {
+                open_time,
+                open,
+                high: close,
+                low: open,
+                close,
+                volume: dec!(1000),
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn trending_series_adds_exactly_max_position_adds_times() {
+    let max_adds = 3;
+    let spacing = dec!(0.02);
+
+    let mut config = test_config(BARS).expect("load config");
+    config.risk_management.risk_per_trade_pct = dec!(0.001);
+    config.risk_management.max_position_adds = Some(max_adds);
+    config.risk_management.add_spacing_pct = spacing;
+    config.simulation.slippage_pct = Decimal::ZERO;
+
+    let executor = RecordingExecutor::new(config.simulation.clone());
+    let executions = executor.executions();
+    let mut backtester = TestBacktester::new(&config, Box::new(AlwaysLong)).with_executor(Box::new(executor)).build();
+
+    backtester.simulate(&trending_klines(BARS)).await.expect("simulate");
+
+    let executions = executions.lock().unwrap();
+    assert_eq!(executions.len(), max_adds as usize + 1);
+    assert!(executions.iter().all(|e| e.side == OrderSide::Buy));
+    for pair in executions.windows(2) {
+        let favorable_move = (pair[1].price - pair[0].price) / pair[0].price;
+        assert!(favorable_move >= spacing, "adds spaced by only {}", favorable_move);
+    }
+}
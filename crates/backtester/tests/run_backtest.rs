@@ -0,0 +1,36 @@
+//! Checks that the `run_backtest` facade wires a backtest the same way as a hand-built `Backtester`.
+
+use backtester::{run_backtest, BacktestSpec, KlineSource};
+use strategies::create_strategy;
+use testing::{generate_klines, test_config, TestBacktester, TEST_SYMBOL};
+
+const BARS: usize = 3000;
+
+#[tokio::test]
+async fn in_memory_spec_matches_a_hand_wired_backtester() {
+    let config = test_config(BARS).expect("load config");
+    let klines = generate_klines(BARS);
+
+    let spec = BacktestSpec::from_config(&config, KlineSource::InMemory(klines.clone())).expect("spec");
+    let run_id = spec.run_id;
+    let output = run_backtest(spec).await.expect("run_backtest");
+
+    let strategy = create_strategy(config.backtest.strategy_id, &config, TEST_SYMBOL).unwrap();
+    let mut backtester = TestBacktester::new(&config, strategy).with_run_id(run_id).build();
+    let (trades, equity_curve) = backtester.simulate(&klines).await.expect("simulate");
+
+    assert!(!output.trades.is_empty());
+    assert_eq!(output.report.total_trades, output.trades.len());
+    assert_eq!(serde_json::to_string(&output.trades).unwrap(), serde_json::to_string(&trades).unwrap());
+    assert_eq!(output.equity_curve, equity_curve);
+}
+
+#[tokio::test]
+async fn klines_outside_the_date_range_are_unavailable() {
+    let config = test_config(BARS).expect("load config");
+    let mut spec = BacktestSpec::from_config(&config, KlineSource::InMemory(generate_klines(BARS))).expect("spec");
+    spec.start = spec.end + chrono::Duration::days(1);
+    spec.end = spec.start + chrono::Duration::days(1);
+
+    assert!(matches!(run_backtest(spec).await, Err(backtester::error::BacktestError::DataUnavailable)));
+}
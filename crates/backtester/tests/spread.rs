@@ -0,0 +1,137 @@
+//! Checks that simulated fills cross the bid-ask spread: a synthetic one around the close
+//! when no quotes are given, or the real quotes when they are, with slippage on top.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::{BracketResolution, Simulation, StopFillModel};
+use core_types::{CloseReason, Execution, Kline, OrderRequest, OrderSide, OrderType, Trade};
+use executor::{Executor, Portfolio, SimulatedExecutor};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+const SYMBOL: &str = "BTCUSDT";
+
+fn simulation(spread: Decimal, slippage: Decimal) -> Simulation {
+    Simulation {
+        taker_fee_pct: Decimal::ZERO,
+        maker_fee_pct: Decimal::ZERO,
+        fee_discount_pct: Decimal::ZERO,
+        slippage_pct: slippage,
+        simulated_spread_pct: spread,
+        symbol_spread_pct: BTreeMap::new(),
+        stop_fill_model: StopFillModel::Exact,
+        bracket_resolution: BracketResolution::Assumption,
+    }
+}
+
+/// A bar closing at `close`, `range` wide.
+fn kline(close: Decimal, range: Decimal) -> Kline {
+    let open_time = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    Kline {
+        open_time,
+        open: close,
+        high: close + range / Decimal::TWO,
+        low: close - range / Decimal::TWO,
+        close,
+        volume: dec!(100),
+        close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+        interval: "1h".to_string(),
+    }
+}
+
+fn order(symbol: &str, side: OrderSide, quantity: Decimal) -> OrderRequest {
+    OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        side,
+        order_type: OrderType::Market,
+        quantity,
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: false,
+        decision_id: None,
+    }
+}
+
+async fn fill(executor: &SimulatedExecutor, side: OrderSide, kline: &Kline, quotes: (Option<Decimal>, Option<Decimal>)) -> Execution {
+    executor.execute(&order(SYMBOL, side, dec!(0.5)), kline, quotes.0, quotes.1).await.unwrap()
+}
+
+#[tokio::test]
+async fn an_immediate_round_trip_loses_exactly_the_spread() {
+    let executor = SimulatedExecutor::new(simulation(dec!(0.001), Decimal::ZERO));
+    let kline = kline(dec!(20000), dec!(100));
+    let notional = kline.close * dec!(0.5);
+
+    let buy = fill(&executor, OrderSide::Buy, &kline, (None, None)).await;
+    let sell = fill(&executor, OrderSide::Sell, &kline, (None, None)).await;
+    assert_eq!((buy.price, sell.price), (dec!(20010), dec!(19990)));
+
+    let initial_capital = dec!(100000);
+    let mut portfolio = Portfolio::new(initial_capital);
+    portfolio.update_with_execution(&buy).unwrap();
+    portfolio.update_with_execution(&sell).unwrap();
+    assert_eq!(portfolio.realized_pnl, -notional * dec!(0.001));
+
+    let trade = Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: SYMBOL.to_string(),
+        entry_execution: buy,
+        exit_execution: sell,
+        close_reason: CloseReason::Signal,
+        initial_risk: None,
+    };
+    let equity_curve = [(kline.open_time, initial_capital), (kline.close_time, initial_capital + portfolio.realized_pnl)];
+    let report = analytics::AnalyticsEngine::new().calculate(&[trade], &equity_curve, &[], initial_capital, "1h").unwrap();
+    assert_eq!(report.total_spread_cost, notional * dec!(0.001));
+    assert_eq!(report.total_net_profit, -report.total_spread_cost);
+}
+
+#[tokio::test]
+async fn real_quotes_take_precedence_over_the_synthetic_spread() {
+    let executor = SimulatedExecutor::new(simulation(dec!(0.001), Decimal::ZERO));
+    let kline = kline(dec!(20000), dec!(100));
+    let quotes = (Some(dec!(20002)), Some(dec!(20006)));
+
+    let buy = fill(&executor, OrderSide::Buy, &kline, quotes).await;
+    let sell = fill(&executor, OrderSide::Sell, &kline, quotes).await;
+    assert_eq!((buy.price, sell.price), (dec!(20006), dec!(20002)));
+    assert_eq!((buy.spread_cost, sell.spread_cost), (dec!(1), dec!(1)));
+
+    // A one-sided or crossed book is not usable, so the synthetic spread applies.
+    let one_sided = fill(&executor, OrderSide::Buy, &kline, (Some(dec!(20002)), None)).await;
+    let crossed = fill(&executor, OrderSide::Buy, &kline, (Some(dec!(20006)), Some(dec!(20002)))).await;
+    assert_eq!((one_sided.price, crossed.price), (dec!(20010), dec!(20010)));
+}
+
+#[tokio::test]
+async fn slippage_is_applied_beyond_the_quote() {
+    let executor = SimulatedExecutor::new(simulation(dec!(0.001), dec!(0.1)));
+    let kline = kline(dec!(20000), dec!(100));
+
+    let buy = fill(&executor, OrderSide::Buy, &kline, (None, None)).await;
+    let sell = fill(&executor, OrderSide::Sell, &kline, (None, None)).await;
+    assert_eq!((buy.price, sell.price), (dec!(20020), dec!(19980)));
+    // Only the distance to the quote counts as spread cost, not the slippage.
+    assert_eq!((buy.spread_cost, sell.spread_cost), (dec!(5), dec!(5)));
+}
+
+#[tokio::test]
+async fn a_symbol_spread_overrides_the_default() {
+    let mut params = simulation(dec!(0.001), Decimal::ZERO);
+    params.symbol_spread_pct.insert("DOGEUSDT".to_string(), dec!(0.004));
+    let executor = SimulatedExecutor::new(params);
+    let kline = kline(dec!(0.2), Decimal::ZERO);
+
+    let doge = executor.execute(&order("DOGEUSDT", OrderSide::Buy, dec!(1000)), &kline, None, None).await.unwrap();
+    let other = executor.execute(&order("XRPUSDT", OrderSide::Buy, dec!(1000)), &kline, None, None).await.unwrap();
+    assert_eq!((doge.price, other.price), (dec!(0.2004), dec!(0.2001)));
+    assert_eq!(doge.spread_cost, dec!(0.4));
+
+    // Without a spread, fills stay at the close as before.
+    let executor = SimulatedExecutor::new(simulation(Decimal::ZERO, Decimal::ZERO));
+    let unspread = executor.execute(&order("XRPUSDT", OrderSide::Buy, dec!(1000)), &kline, None, None).await.unwrap();
+    assert_eq!((unspread.price, unspread.spread_cost), (kline.close, Decimal::ZERO));
+}
@@ -0,0 +1,33 @@
+//! A strategy error ends the backtest with that error, so the run is marked Failed rather
+//! than saved with results that silently skipped bars.
+
+use backtester::error::BacktestError;
+use core_types::{Kline, Signal};
+use strategies::{Strategy, StrategyError};
+use testing::{backtester, generate_klines, test_config};
+
+/// Never signals, and fails on its `fail_on`th bar.
+struct FailsOn {
+    fail_on: usize,
+    seen: usize,
+}
+
+impl Strategy for FailsOn {
+    fn evaluate(&mut self, _kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        self.seen += 1;
+        if self.seen == self.fail_on {
+            return Err(StrategyError::IndicatorError("bad bar".to_string()));
+        }
+        Ok(None)
+    }
+}
+
+#[tokio::test]
+async fn a_strategy_error_fails_the_backtest() {
+    let klines = generate_klines(100);
+    let config = test_config(klines.len()).expect("load config");
+    let mut backtester = backtester(&config, Box::new(FailsOn { fail_on: 50, seen: 0 }));
+
+    let result = backtester.simulate(&klines).await;
+    assert!(matches!(result, Err(BacktestError::Strategy(StrategyError::IndicatorError(_)))), "{:?}", result.err());
+}
@@ -0,0 +1,63 @@
+//! Property-style checks of the backtester and MACrossover on seeded synthetic series.
+
+use core_types::{Kline, OrderSide, StrategyId};
+use rust_decimal::Decimal;
+use strategies::create_strategy;
+use testing::synthetic::{gbm, regime_switching, Regime, SeriesSpec};
+use testing::{backtester, test_config, TEST_SYMBOL};
+
+const BARS: usize = 2000;
+const SEEDS: [u64; 10] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10];
+
+/// Runs MACrossover over `klines` and returns its profit after fees, open position
+/// included, in percent of the initial capital.
+async fn ma_crossover_return(klines: &[Kline]) -> Decimal {
+    let config = test_config(klines.len()).expect("load config");
+    let mut backtester = backtester(&config, create_strategy(StrategyId::MACrossover, &config, TEST_SYMBOL).unwrap());
+    let (_, equity_curve) = backtester.simulate(klines).await.expect("simulate");
+    assert_eq!(equity_curve.len(), klines.len(), "one equity point per bar");
+
+    let portfolio = backtester.portfolio();
+    let last_close = klines.last().unwrap().close;
+    let unrealized: Decimal = portfolio
+        .positions
+        .values()
+        .map(|position| match position.side {
+            OrderSide::Buy => (last_close - position.entry_price) * position.quantity,
+            OrderSide::Sell => (position.entry_price - last_close) * position.quantity,
+        })
+        .sum();
+    (portfolio.realized_pnl + unrealized) / config.backtest.initial_capital * Decimal::from(100)
+}
+
+#[tokio::test]
+async fn ma_crossover_profits_from_a_strong_trend() {
+    for seed in SEEDS {
+        let klines = gbm(&SeriesSpec::default(), BARS, 0.001, 0.002, seed);
+        let profit = ma_crossover_return(&klines).await;
+        assert!(profit > Decimal::from(5), "seed {}: {}%", seed, profit);
+    }
+}
+
+#[tokio::test]
+async fn ma_crossover_is_roughly_flat_after_fees_on_noise() {
+    let mut total = Decimal::ZERO;
+    for seed in SEEDS {
+        let klines = gbm(&SeriesSpec::default(), BARS, 0.0, 0.002, seed);
+        let profit = ma_crossover_return(&klines).await;
+        // Each crossover closes the position and enters the other side, paying fees on both.
+        assert!(profit.abs() < Decimal::from(20), "seed {}: {}%", seed, profit);
+        total += profit;
+    }
+    // A driftless walk has no edge to find, so on average the fees and slippage are lost.
+    let mean = total / Decimal::from(SEEDS.len());
+    assert!(mean < Decimal::ZERO && mean > Decimal::from(-10), "mean {}%", mean);
+}
+
+#[tokio::test]
+async fn the_equity_curve_has_a_point_per_bar_through_regime_changes() {
+    let regimes = [Regime::trend(700, 0.001, 0.003), Regime::chop(600, 0.004), Regime::trend(700, -0.001, 0.003)];
+    for seed in SEEDS {
+        ma_crossover_return(&regime_switching(&SeriesSpec::default(), &regimes, seed)).await;
+    }
+}
@@ -0,0 +1,102 @@
+//! Checks that positions are closed once held for `max_holding_bars`, or at the end of the
+//! session, and that those trades are attributed to the time limit.
+
+use chrono::{Duration, TimeZone, Utc};
+use configuration::Config;
+use core_types::{CloseReason, Kline, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{backtester, test_config, EnterAt, TEST_INTERVAL};
+
+/// `count` hourly bars from midnight, closing at `close(i)` with a 1-point range around it.
+fn klines(count: usize, close: impl Fn(usize) -> Decimal) -> Vec<Kline> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..count)
+        .map(|i| {
+            let open_time = start + Duration::hours(i as i64);
+            let close = close(i);
+            Kline {
+                open_time,
+                open: close,
+                high: close + Decimal::ONE,
+                low: close - Decimal::ONE,
+                close,
+                volume: Decimal::ONE,
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: TEST_INTERVAL.to_string(),
+            }
+        })
+        .collect()
+}
+
+/// A frictionless config with a 5% stop, well outside a flat series' range.
+fn config() -> Config {
+    let mut config = test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = Decimal::ZERO;
+    config.simulation.maker_fee_pct = Decimal::ZERO;
+    config.simulation.slippage_pct = Decimal::ZERO;
+    config.risk_management.stop_loss_pct = dec!(0.05);
+    config
+}
+
+async fn run(config: Config, entry_bar: usize, klines: &[Kline]) -> Vec<Trade> {
+    let mut backtester = backtester(&config, Box::new(EnterAt::bar(entry_bar)));
+    backtester.simulate(klines).await.expect("simulate").0
+}
+
+#[tokio::test]
+async fn a_position_is_flat_max_holding_bars_after_its_entry() {
+    let mut config = config();
+    config.risk_management.max_holding_bars = Some(10);
+    let bars = klines(150, |_| dec!(100));
+
+    let trades = run(config, 100, &bars).await;
+
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].entry_execution.timestamp, bars[100].close_time);
+    assert_eq!(trades[0].exit_execution.timestamp, bars[110].close_time);
+    assert!(trades[0].exit_execution.timestamp < bars[111].close_time);
+    assert_eq!(trades[0].close_reason, CloseReason::TimeLimit);
+}
+
+#[tokio::test]
+async fn without_a_limit_the_position_is_held() {
+    let trades = run(config(), 100, &klines(150, |_| dec!(100))).await;
+    assert!(trades.is_empty());
+}
+
+#[tokio::test]
+async fn a_stop_hit_before_the_limit_is_attributed_to_the_stop() {
+    let mut config = config();
+    config.risk_management.max_holding_bars = Some(10);
+    // Entry at 100; the price slides past the 5% stop at 95 within the holding period.
+    let bars = klines(20, |i| if i < 5 { dec!(100) } else { dec!(100) - Decimal::from(i - 4) * dec!(2) });
+
+    let trades = run(config, 2, &bars).await;
+
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].close_reason, CloseReason::StopLoss);
+    assert!(trades[0].exit_execution.timestamp < bars[12].close_time);
+    // Five points of risk per unit between the entry and the stop. The stop triggers on the
+    // bar whose low reaches 95 and fills at its price, losing all of them.
+    assert_eq!(trades[0].initial_risk, Some(dec!(5) * trades[0].entry_execution.quantity));
+    assert_eq!(trades[0].exit_execution.price, dec!(95));
+    assert_eq!(analytics::AnalyticsEngine::new().r_multiples(&trades), [Some(dec!(-1))]);
+}
+
+#[tokio::test]
+async fn a_position_open_at_the_end_of_the_day_is_closed_on_its_last_bar() {
+    let mut config = config();
+    config.risk_management.exit_at_session_end = true;
+    let bars = klines(30, |_| dec!(100));
+
+    let trades = run(config, 3, &bars).await;
+
+    assert_eq!(trades.len(), 1);
+    assert_eq!(trades[0].exit_execution.timestamp, bars[23].close_time);
+    assert_eq!(trades[0].close_reason, CloseReason::TimeLimit);
+
+    let counts = analytics::AnalyticsEngine::new().close_reason_counts(&trades);
+    assert_eq!(counts.get(&CloseReason::TimeLimit), Some(&1));
+    assert_eq!(counts.get(&CloseReason::Signal), None);
+}
@@ -0,0 +1,93 @@
+//! Checks that strategies are warmed up on the bars before a backtest's range, and that
+//! those bars place no trades and add nothing to the equity curve.
+
+use backtester::{run_backtest, BacktestSpec, KlineSource};
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal};
+use rust_decimal::Decimal;
+use strategies::{create_strategy, Strategy, StrategyError};
+use testing::{backtester, generate_klines, test_config, TEST_SYMBOL};
+use uuid::Uuid;
+
+const WARMUP: usize = 50;
+
+/// Stays silent for its first `WARMUP` bars, then flips between long and short every ten bars.
+struct SlowStarter {
+    seen: usize,
+}
+
+impl Strategy for SlowStarter {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        self.seen += 1;
+        if self.seen <= WARMUP || !self.seen.is_multiple_of(10) {
+            return Ok(None);
+        }
+        let side = if (self.seen / 10).is_multiple_of(2) { OrderSide::Buy } else { OrderSide::Sell };
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: None,
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+
+    fn required_warmup_bars(&self) -> usize {
+        WARMUP
+    }
+}
+
+#[tokio::test]
+async fn a_warmed_up_strategy_trades_a_range_as_long_as_its_warmup() {
+    let config = test_config(2 * WARMUP).expect("load config");
+    let klines = generate_klines(2 * WARMUP);
+    let (before, range) = klines.split_at(WARMUP);
+
+    let mut cold = backtester(&config, Box::new(SlowStarter { seen: 0 }));
+    let (trades, _) = cold.simulate(range).await.expect("simulate");
+    assert!(trades.is_empty());
+
+    let mut warm = backtester(&config, Box::new(SlowStarter { seen: 0 }));
+    assert_eq!(warm.required_warmup_bars(), WARMUP);
+    warm.warm_up(before).expect("warm up");
+    let (trades, equity_curve) = warm.simulate(range).await.expect("simulate");
+
+    assert!(!trades.is_empty());
+    assert!(trades.iter().all(|t| t.entry_execution.timestamp >= range[0].open_time));
+    assert_eq!(equity_curve.len(), range.len());
+    assert_eq!(warm.metadata().bars_processed, range.len() as i64);
+}
+
+#[tokio::test]
+async fn in_memory_specs_warm_up_on_the_klines_before_their_start() {
+    const BARS: usize = 3000;
+    let config = test_config(BARS).expect("load config");
+    let klines = generate_klines(BARS);
+    let start = BARS / 2;
+
+    let mut spec = BacktestSpec::from_config(&config, KlineSource::InMemory(klines.clone())).expect("spec");
+    spec.start = klines[start].open_time;
+    let output = run_backtest(spec).await.expect("run_backtest");
+
+    let strategy = create_strategy(config.backtest.strategy_id, &config, TEST_SYMBOL).unwrap();
+    let warmup = strategy.required_warmup_bars();
+    assert!(warmup > 0);
+    let mut backtester = backtester(&config, strategy);
+    backtester.warm_up(&klines[start - warmup..start]).expect("warm up");
+    let (trades, equity_curve) = backtester.simulate(&klines[start..]).await.expect("simulate");
+
+    assert_eq!(output.equity_curve.len(), BARS - start);
+    assert_eq!(output.equity_curve, equity_curve);
+    assert_eq!(output.trades.len(), trades.len());
+}
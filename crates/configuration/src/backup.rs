@@ -0,0 +1,191 @@
+//! Scheduled database backups, from the `[backup]` section.
+//!
+//! The schedule is a cron expression of five fields, "minute hour day-of-month month
+//! day-of-week", evaluated in UTC. Each field is `*`, a number, a range like `1-5`, a step
+//! like `*/15` or `0-30/10`, or a comma-separated list of those. Days of the week run from 0
+//! (Sunday) to 6, and 7 is Sunday too. As in cron, when both day fields are restricted a day
+//! matching either one fires. "@hourly", "@daily" and "@weekly" stand for "0 * * * *",
+//! "0 0 * * *" and "0 0 * * 0".
+
+use crate::error::ConfigError;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::Deserialize;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// How far ahead `next_after` looks for a time the schedule fires, in days. Five years is
+/// enough to reach the next 29 February.
+const SEARCH_DAYS: i64 = 5 * 366;
+
+/// The `[backup]` section: when the serve and run modes back the database up, and how many
+/// backups they keep.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BackupConfig {
+    /// When to take a backup, e.g. "0 3 * * *" for 03:00 UTC every day.
+    pub schedule: BackupSchedule,
+    /// The directory backups are written to, created if it does not exist.
+    #[serde(default = "default_backup_directory")]
+    pub directory: PathBuf,
+    /// How many scheduled backups to keep; older ones are deleted after each new one.
+    #[serde(default = "default_backup_keep")]
+    pub keep: usize,
+}
+
+fn default_backup_directory() -> PathBuf {
+    PathBuf::from("backups")
+}
+
+fn default_backup_keep() -> usize {
+    7
+}
+
+impl BackupConfig {
+    /// Checks that at least one backup is kept.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.keep == 0 {
+            return Err(ConfigError::validation("backup.keep must be at least 1".to_string()));
+        }
+        Ok(())
+    }
+}
+
+/// A cron-like schedule; see the module documentation for the syntax.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct BackupSchedule {
+    spec: String,
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Whether the day-of-month field is `*`, and so whether the days of the week alone decide.
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+impl BackupSchedule {
+    /// The first minute after `at` that the schedule fires at, or None if it never does.
+    pub fn next_after(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut next = at.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = at + Duration::days(SEARCH_DAYS);
+        while next <= limit {
+            if !has(self.months, next.month()) {
+                let (year, month) = if next.month() == 12 { (next.year() + 1, 1) } else { (next.year(), next.month() + 1) };
+                next = Utc.from_utc_datetime(&NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?);
+            } else if !self.fires_on(next) {
+                next = Utc.from_utc_datetime(&next.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?);
+            } else if !has(self.hours, next.hour()) {
+                next = next.with_minute(0)? + Duration::hours(1);
+            } else if !has(self.minutes, next.minute()) {
+                next += Duration::minutes(1);
+            } else {
+                return Some(next);
+            }
+        }
+        None
+    }
+
+    /// Whether the day of `at` matches the day fields.
+    fn fires_on(&self, at: DateTime<Utc>) -> bool {
+        let day_of_month = has(self.days_of_month, at.day());
+        let day_of_week = has(self.days_of_week, at.weekday().num_days_from_sunday());
+        match (self.any_day_of_month, self.any_day_of_week) {
+            (true, true) => true,
+            (true, false) => day_of_week,
+            (false, true) => day_of_month,
+            (false, false) => day_of_month || day_of_week,
+        }
+    }
+}
+
+fn has(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+impl fmt::Display for BackupSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+impl FromStr for BackupSchedule {
+    type Err = ConfigError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: String| {
+            ConfigError::validation(format!("invalid backup schedule \"{}\": {}; expected e.g. \"0 3 * * *\"", spec, reason))
+        };
+        let expanded = match spec.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minutes, hours, days_of_month, months, days_of_week] = fields[..] else {
+            return Err(invalid(format!("it has {} fields, not 5", fields.len())));
+        };
+        let field = |name: &str, text: &str, min: u32, max: u32| parse_field(text, min, max).map_err(|reason| invalid(format!("{}: {}", name, reason)));
+
+        let mut days_of_week_set = field("day of week", days_of_week, 0, 7)?;
+        // 7 is another name for Sunday.
+        if has(days_of_week_set, 7) {
+            days_of_week_set |= 1;
+        }
+        let schedule = Self {
+            spec: spec.trim().to_string(),
+            minutes: field("minute", minutes, 0, 59)?,
+            hours: field("hour", hours, 0, 23)?,
+            days_of_month: field("day of month", days_of_month, 1, 31)?,
+            months: field("month", months, 1, 12)?,
+            days_of_week: days_of_week_set,
+            any_day_of_month: days_of_month == "*",
+            any_day_of_week: days_of_week == "*",
+        };
+        // Leap years repeat every four years, so a schedule that fires at all does so within five.
+        let epoch = Utc.with_ymd_and_hms(2000, 1, 1, 0, 0, 0).unwrap();
+        if schedule.next_after(epoch).is_none() {
+            return Err(invalid("it never fires".to_string()));
+        }
+        Ok(schedule)
+    }
+}
+
+impl TryFrom<String> for BackupSchedule {
+    type Error = ConfigError;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        spec.parse()
+    }
+}
+
+/// The set of values, as bits, that a field from `min` to `max` matches.
+fn parse_field(text: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0u64;
+    for part in text.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|step| *step > 0).ok_or(format!("\"{}\" is not a step", step))?),
+            None => (part, 1),
+        };
+        let number = |value: &str| {
+            value.parse::<u32>().ok().filter(|value| (min..=max).contains(value)).ok_or(format!("\"{}\" is not from {} to {}", value, min, max))
+        };
+        let (start, end) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((start, end)) => (number(start)?, number(end)?),
+            // A lone number with a step runs from it to the end, as in cron.
+            None if step > 1 => (number(range)?, max),
+            None => (number(range)?, number(range)?),
+        };
+        if start > end {
+            return Err(format!("the range \"{}\" is backwards", range));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
@@ -0,0 +1,211 @@
+//! Trading blackouts: windows, such as the exchange's scheduled maintenance, in which no new
+//! trades are entered.
+//!
+//! Windows are either recurring weekly, written like "Wed 02:00-04:00", or one-off ranges of
+//! datetimes. All times are UTC; there is no time zone or daylight saving to account for. A
+//! window covers its start and runs up to, but not including, its end. Strategy signals in
+//! a window are dropped, except those that close the position.
+
+use crate::error::ConfigError;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc, Weekday};
+use core_types::{Signal, SignalIntent};
+use serde::Deserialize;
+use std::fmt;
+use std::str::FromStr;
+
+const MINUTES_PER_DAY: i64 = 24 * 60;
+const MILLIS_PER_WEEK: i64 = 7 * MINUTES_PER_DAY * 60 * 1000;
+
+/// The trading blackouts from the `[trading_blackouts]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TradingBlackouts {
+    /// Windows that recur every week, e.g. "Wed 02:00-04:00".
+    #[serde(default)]
+    pub weekly: Vec<WeeklyWindow>,
+    /// Windows that happen once.
+    #[serde(default)]
+    pub one_off: Vec<OneOffWindow>,
+    /// Signals are also dropped this many minutes before a window starts.
+    #[serde(default)]
+    pub buffer_minutes: u32,
+    /// When set, open positions are closed from this many minutes before a window starts
+    /// until it ends.
+    #[serde(default)]
+    pub flatten_before_blackout_minutes: Option<u32>,
+    /// Whether backtests honour the windows too, so they reflect the live policy.
+    #[serde(default)]
+    pub apply_in_backtests: bool,
+}
+
+impl TradingBlackouts {
+    /// True when no window is configured.
+    pub fn is_empty(&self) -> bool {
+        self.weekly.is_empty() && self.one_off.is_empty()
+    }
+
+    /// The window that covers `at`, with each window starting `lead_minutes` early.
+    pub fn window_at(&self, at: DateTime<Utc>, lead_minutes: u32) -> Option<BlackoutWindow<'_>> {
+        let lead = Duration::minutes(i64::from(lead_minutes));
+        let weekly = self.weekly.iter().find(|window| window.covers(at, lead)).map(BlackoutWindow::Weekly);
+        weekly.or_else(|| self.one_off.iter().find(|window| window.covers(at, lead)).map(BlackoutWindow::OneOff))
+    }
+
+    /// The window in which signals arriving at `at` are dropped: one that covers `at`, or
+    /// starts within `buffer_minutes` of it. Positions being flattened ahead of a window are
+    /// not re-entered either, so a longer `flatten_before_blackout_minutes` widens the buffer.
+    pub fn blocking_signals_at(&self, at: DateTime<Utc>) -> Option<BlackoutWindow<'_>> {
+        let lead = self.buffer_minutes.max(self.flatten_before_blackout_minutes.unwrap_or(0));
+        self.window_at(at, lead)
+    }
+
+    /// The window `signal` is dropped in, going by its timestamp. Signals that only close the
+    /// position are never dropped, so a position can still be closed ahead of a window.
+    pub fn blocking(&self, signal: &Signal) -> Option<BlackoutWindow<'_>> {
+        match signal.intent {
+            Some(SignalIntent::Close) => None,
+            _ => self.blocking_signals_at(signal.timestamp),
+        }
+    }
+
+    /// The window ahead of which positions open at `at` are closed, if flattening is enabled.
+    pub fn flattening_at(&self, at: DateTime<Utc>) -> Option<BlackoutWindow<'_>> {
+        self.flatten_before_blackout_minutes.and_then(|minutes| self.window_at(at, minutes))
+    }
+
+    /// Checks that every one-off window ends after it starts.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        match self.one_off.iter().find(|window| window.end <= window.start) {
+            Some(window) => Err(ConfigError::validation(format!("trading_blackouts.one_off: {} must end after it starts", window))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A window of a trading blackout.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlackoutWindow<'a> {
+    Weekly(&'a WeeklyWindow),
+    OneOff(&'a OneOffWindow),
+}
+
+impl fmt::Display for BlackoutWindow<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BlackoutWindow::Weekly(window) => write!(f, "weekly window {}", window),
+            BlackoutWindow::OneOff(window) => write!(f, "window {}", window),
+        }
+    }
+}
+
+/// A window that recurs every week, from a start time on one day for up to 24 hours. A
+/// window that ends before its start time on the clock, like "Sun 23:00-01:00", runs past
+/// midnight into the next day (here Monday).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct WeeklyWindow {
+    pub day: Weekday,
+    /// The start, in minutes after midnight.
+    pub start_minute: u32,
+    /// The length, in minutes.
+    pub length_minutes: u32,
+}
+
+impl WeeklyWindow {
+    /// Whether the window, starting `lead` early, covers `at`.
+    pub fn covers(&self, at: DateTime<Utc>, lead: Duration) -> bool {
+        let start = i64::from(self.day.num_days_from_monday()) * MINUTES_PER_DAY + i64::from(self.start_minute);
+        let start_millis = start * 60 * 1000 - lead.num_milliseconds();
+        let length_millis = i64::from(self.length_minutes) * 60 * 1000 + lead.num_milliseconds();
+        (millis_into_week(at) - start_millis).rem_euclid(MILLIS_PER_WEEK) < length_millis
+    }
+}
+
+/// How far `at` is into its week, which starts at midnight between Sunday and Monday.
+fn millis_into_week(at: DateTime<Utc>) -> i64 {
+    let day = i64::from(at.weekday().num_days_from_monday());
+    let seconds = day * MINUTES_PER_DAY * 60 + i64::from(at.num_seconds_from_midnight());
+    seconds * 1000 + i64::from(at.nanosecond() / 1_000_000)
+}
+
+impl FromStr for WeeklyWindow {
+    type Err = ConfigError;
+
+    /// Parses "<day> HH:MM-HH:MM", where the day is a weekday's name or its first three
+    /// letters. The end may be "24:00" for midnight.
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = |reason: &str| {
+            ConfigError::validation(format!("invalid blackout window \"{}\": {}; expected e.g. \"Wed 02:00-04:00\"", spec, reason))
+        };
+        let (day, times) = spec.trim().split_once(char::is_whitespace).ok_or_else(|| invalid("missing the times"))?;
+        let day: Weekday = day.parse().map_err(|_| invalid("unknown day"))?;
+        let (start, end) = times.trim().split_once('-').ok_or_else(|| invalid("missing the end time"))?;
+        let start_minute = parse_time(start, false).ok_or_else(|| invalid("the start is not a time from 00:00 to 23:59"))?;
+        let end_minute = parse_time(end, true).ok_or_else(|| invalid("the end is not a time from 00:00 to 24:00"))?;
+        if start_minute == end_minute {
+            return Err(invalid("the window is empty"));
+        }
+        let length_minutes = (i64::from(end_minute) - i64::from(start_minute)).rem_euclid(MINUTES_PER_DAY) as u32;
+        // Only "00:00-24:00" wraps all the way round, to a whole day.
+        let length_minutes = if length_minutes == 0 { MINUTES_PER_DAY as u32 } else { length_minutes };
+        Ok(Self { day, start_minute, length_minutes })
+    }
+}
+
+impl TryFrom<String> for WeeklyWindow {
+    type Error = ConfigError;
+
+    fn try_from(spec: String) -> Result<Self, Self::Error> {
+        spec.parse()
+    }
+}
+
+/// "HH:MM" in minutes after midnight; "24:00" only when `allow_midnight_end` is set.
+fn parse_time(time: &str, allow_midnight_end: bool) -> Option<u32> {
+    let (hours, minutes) = time.trim().split_once(':')?;
+    if hours.len() != 2 || minutes.len() != 2 {
+        return None;
+    }
+    let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+    match (hours, minutes) {
+        (24, 0) if allow_midnight_end => Some(24 * 60),
+        (0..=23, 0..=59) => Some(hours * 60 + minutes),
+        _ => None,
+    }
+}
+
+impl fmt::Display for WeeklyWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let end = (self.start_minute + self.length_minutes) % MINUTES_PER_DAY as u32;
+        write!(
+            f,
+            "{} {:02}:{:02}-{:02}:{:02}",
+            self.day,
+            self.start_minute / 60,
+            self.start_minute % 60,
+            end / 60,
+            end % 60
+        )
+    }
+}
+
+/// A window that happens once, from `start` up to `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OneOffWindow {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+impl OneOffWindow {
+    /// Whether the window, starting `lead` early, covers `at`.
+    pub fn covers(&self, at: DateTime<Utc>, lead: Duration) -> bool {
+        self.start - lead <= at && at < self.end
+    }
+}
+
+impl fmt::Display for OneOffWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} to {}", self.start.format("%Y-%m-%d %H:%M UTC"), self.end.format("%Y-%m-%d %H:%M UTC"))
+    }
+}
@@ -0,0 +1,122 @@
+//! Per-bot risk overrides: a `[bot.risk]` block in `live.toml` or a portfolio file setting
+//! some of the `[risk_management]` fields for one bot, e.g. a tighter stop and a smaller
+//! risk per trade for a mean-reversion bot next to a trend follower.
+//!
+//! A bot's risk settings are the global section with its overrides merged over it, and are
+//! validated as the global section is. An override can set a setting but not unset one:
+//! TOML has no null, so a bot cannot turn pyramiding off when the global section enables it.
+
+use crate::error::ConfigError;
+use crate::settings::{
+    Config, LimitAction, LiveBotConfig, LiveConfig, MinExpectedMove, OrderLimits, PortfolioBotConfig, PortfolioConfig, ReverseMode,
+    RiskManagement,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// The `[risk_management]` fields a bot sets for itself. Each unset field keeps the global
+/// value; `order_limits` and `symbol_limits` replace the global ones as a whole.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RiskOverrides {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_per_trade_pct: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss_pct: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub margin_buffer_pct: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_position_adds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub add_spacing_pct: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse_mode: Option<ReverseMode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub break_even_trigger_pct: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub break_even_buffer_pct: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit_pct: Option<Decimal>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_limits: Option<OrderLimits>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol_limits: Option<BTreeMap<String, OrderLimits>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_action: Option<LimitAction>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_holding_bars: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_at_session_end: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_expected_move: Option<MinExpectedMove>,
+}
+
+impl RiskManagement {
+    /// These settings with `overrides` applied over them.
+    pub fn with_overrides(&self, overrides: &RiskOverrides) -> RiskManagement {
+        RiskManagement {
+            risk_per_trade_pct: overrides.risk_per_trade_pct.unwrap_or(self.risk_per_trade_pct),
+            stop_loss_pct: overrides.stop_loss_pct.unwrap_or(self.stop_loss_pct),
+            margin_buffer_pct: overrides.margin_buffer_pct.unwrap_or(self.margin_buffer_pct),
+            max_position_adds: overrides.max_position_adds.or(self.max_position_adds),
+            add_spacing_pct: overrides.add_spacing_pct.unwrap_or(self.add_spacing_pct),
+            reverse_mode: overrides.reverse_mode.unwrap_or(self.reverse_mode),
+            break_even_trigger_pct: overrides.break_even_trigger_pct.or(self.break_even_trigger_pct),
+            break_even_buffer_pct: overrides.break_even_buffer_pct.unwrap_or(self.break_even_buffer_pct),
+            take_profit_pct: overrides.take_profit_pct.or(self.take_profit_pct),
+            order_limits: overrides.order_limits.unwrap_or(self.order_limits),
+            symbol_limits: overrides.symbol_limits.clone().unwrap_or_else(|| self.symbol_limits.clone()),
+            limit_action: overrides.limit_action.unwrap_or(self.limit_action),
+            max_holding_bars: overrides.max_holding_bars.or(self.max_holding_bars),
+            exit_at_session_end: overrides.exit_at_session_end.unwrap_or(self.exit_at_session_end),
+            min_expected_move: overrides.min_expected_move.or(self.min_expected_move),
+        }
+    }
+}
+
+/// The risk settings of the bot on `symbol` with `overrides`: `global` with them merged over
+/// it, checked as the global section is. Errors name the bot's block.
+pub fn bot_risk_management(global: &RiskManagement, symbol: &str, overrides: Option<&RiskOverrides>) -> Result<RiskManagement, ConfigError> {
+    let Some(overrides) = overrides else {
+        return Ok(global.clone());
+    };
+    let merged = global.with_overrides(overrides);
+    crate::validate_risk_management(&merged).map_err(|e| match e {
+        ConfigError::ValidationError(message) => ConfigError::ValidationError(format!("bots.{}.risk: {}", symbol, message)),
+        other => other,
+    })?;
+    Ok(merged)
+}
+
+impl LiveBotConfig {
+    /// This bot's risk settings: `config`'s `[risk_management]` with its overrides applied.
+    pub fn risk_management(&self, config: &Config) -> Result<RiskManagement, ConfigError> {
+        bot_risk_management(&config.risk_management, &self.symbol, self.risk.as_ref())
+    }
+}
+
+impl PortfolioBotConfig {
+    /// This bot's risk settings: `config`'s `[risk_management]` with its overrides applied.
+    pub fn risk_management(&self, config: &Config) -> Result<RiskManagement, ConfigError> {
+        bot_risk_management(&config.risk_management, &self.symbol, self.risk.as_ref())
+    }
+}
+
+/// Checks the merged risk settings of every enabled bot with overrides, so an override that
+/// is only invalid on top of the global section (a break-even buffer above the global
+/// trigger, say) fails at startup rather than when the bot is built.
+pub fn validate_bot_risk(config: &Config, live_config: &LiveConfig) -> Result<(), ConfigError> {
+    for bot in live_config.bots.iter().filter(|bot| bot.enabled) {
+        bot.risk_management(config)?;
+    }
+    Ok(())
+}
+
+/// Checks the merged risk settings of every bot of a portfolio.
+pub fn validate_portfolio_risk(config: &Config, portfolio_config: &PortfolioConfig) -> Result<(), ConfigError> {
+    for bot in &portfolio_config.bots {
+        bot.risk_management(config)?;
+    }
+    Ok(())
+}
@@ -14,6 +14,15 @@ pub enum ConfigError {
     
     #[error("JSON deserialization error: {0}")] 
     JsonError(#[from] SerdeJsonError),
+
+    #[error("Failed to read configuration file: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Failed to parse configuration file: {0}")]
+    ParseError(#[from] toml_edit::TomlError),
+
+    #[error("{file} has config_version {version}, but this build only reads up to version {supported}; upgrade zenith to use it")]
+    UnsupportedVersion { file: String, version: u32, supported: u32 },
 }
 
 impl ConfigError {
@@ -0,0 +1,58 @@
+//! Stable fingerprints of configuration sections.
+
+use crate::settings::{Backtest, RiskManagement, Simulation};
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+
+/// Returns a hex-encoded SHA-256 hash of the sections that shape every backtest run:
+/// `[backtest]`, `[simulation]` and `[risk_management]`.
+///
+/// Two configurations hash equal exactly when those sections hold equal values, whatever
+/// order their keys were written in, so runs produced under different settings can be told apart.
+pub fn config_hash(backtest: &Backtest, simulation: &Simulation, risk_management: &RiskManagement) -> String {
+    canonical_hash(&serde_json::json!({
+        "backtest": backtest,
+        "simulation": simulation,
+        "risk_management": risk_management,
+    }))
+}
+
+/// Hashes a JSON value in a canonical form, with object keys sorted at every level.
+pub fn canonical_hash(value: &JsonValue) -> String {
+    let mut canonical = String::new();
+    write_canonical(value, &mut canonical);
+    Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn write_canonical(value: &JsonValue, out: &mut String) {
+    match value {
+        JsonValue::Object(map) => {
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(b.0));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&JsonValue::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
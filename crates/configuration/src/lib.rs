@@ -1,21 +1,36 @@
 use std::path::Path;
 pub mod optimizer_config;
-pub use optimizer_config::{OptimizerConfig, ParameterRange, BaseConfig};
+pub use optimizer_config::{OptimizerConfig, ParameterRange, BaseConfig, EquityCurveResolution, JobConfigSnapshot};
 
 use rust_decimal_macros::dec;
 use crate::error::ConfigError;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 // Declare the modules that make up this crate.
+pub mod backup;
+pub mod blackout;
+pub mod bot_risk;
 pub mod error;
+pub mod hash;
+pub mod params;
+pub mod quote;
 pub mod settings;
+pub mod versioning;
 
 // Re-export the core types to provide a clean public API.
 pub use settings::{
-    LiveBotConfig, LiveConfig,Config, FundingRateArbParams, MACrossoverParams, ProbReversionParams, RiskManagement,PortfolioBotConfig, PortfolioConfig,
-    Simulation, Strategies, SuperTrendParams, LoggingConfig, TelegramConfig,
+    DailyLossLimit, DailyLossLimits, DataConfig, DrawdownTier, DynamicLeverage, LimitAction, LiveBotConfig, LiveConfig,Config, OrphanPositionPolicy, EnsembleParams, PnlReconciliationConfig, FundingRateArbParams, MACrossoverParams, MinExpectedMove, OrderLimits, ProbReversionParams, ReplayConfig, RiskManagement,OverlapPolicy, PortfolioBotConfig, PortfolioConfig,
+    ReverseMode, ServerConfig, Simulation, StopFillModel, BracketResolution, Strategies, SuperTrendParams, LoggingConfig, TelegramConfig, FilteredSignals, VolumeFilterParams,
 };
 
+pub use backup::{BackupConfig, BackupSchedule};
+pub use blackout::{BlackoutWindow, OneOffWindow, TradingBlackouts, WeeklyWindow};
+pub use bot_risk::{bot_risk_management, validate_bot_risk, validate_portfolio_risk, RiskOverrides};
+pub use hash::{canonical_hash, config_hash};
+pub use params::{normalize_params, parse_decimal};
+pub use quote::{is_coin_margined, settles_in, validate_quote_assets, QuoteAssets, DEFAULT_QUOTE_ASSET};
+pub use versioning::{check_file_version, check_version, migrate, AddedSetting, SettingDefault, VersionReport, Versioned};
+
 #[cfg(feature = "clap")]
 pub use settings::ExecutionMode;
 
@@ -44,6 +59,7 @@ pub fn load_config(config_path: Option<&str>) -> Result<Config, ConfigError> {
     if !Path::new(config_path).exists() {
         return Err(ConfigError::FileNotFound(config_path.to_string()));
     }
+    check_file_version::<Config>(Path::new(config_path))?;
 
     let builder = config::Config::builder()
         // Load configuration from the specified file
@@ -71,36 +87,172 @@ pub fn load_config(config_path: Option<&str>) -> Result<Config, ConfigError> {
     Ok(config)
 }
 
+/// Checks that `interval`, the value of `setting`, is a supported kline interval.
+fn validate_interval(setting: &str, interval: &str) -> Result<(), ConfigError> {
+    interval
+        .parse::<core_types::Interval>()
+        .map(|_| ())
+        .map_err(|e| ConfigError::ValidationError(format!("{}: {}", setting, e)))
+}
+
 /// Validates the configuration values after loading.
 fn validate_config(config: &Config) -> Result<(), ConfigError> {
+    validate_interval("backtest.interval", &config.backtest.interval)?;
+
     // Validate simulation parameters
     if config.simulation.taker_fee_pct.is_sign_negative() || config.simulation.taker_fee_pct > dec!(1.0) {
         return Err(ConfigError::ValidationError("taker_fee_pct must be between 0 and 1".into()));
     }
 
+    if config.simulation.maker_fee_pct.is_sign_negative() || config.simulation.maker_fee_pct > dec!(1.0) {
+        return Err(ConfigError::ValidationError("maker_fee_pct must be between 0 and 1".into()));
+    }
+
+    if config.simulation.fee_discount_pct.is_sign_negative() || config.simulation.fee_discount_pct > dec!(1.0) {
+        return Err(ConfigError::ValidationError("fee_discount_pct must be between 0 and 1".into()));
+    }
+
     if config.simulation.slippage_pct.is_sign_negative() || config.simulation.slippage_pct > dec!(1.0) {
         return Err(ConfigError::ValidationError("slippage_pct must be between 0 and 1".into()));
     }
 
-    // Validate risk management parameters
-    if config.risk_management.risk_per_trade_pct <= dec!(0.0) || config.risk_management.risk_per_trade_pct > dec!(0.1) {
+    let symbol_spreads = config.simulation.symbol_spread_pct.iter().map(|(symbol, spread)| (format!("symbol_spread_pct.{}", symbol), spread));
+    for (setting, spread) in std::iter::once(("simulated_spread_pct".to_string(), &config.simulation.simulated_spread_pct)).chain(symbol_spreads) {
+        if spread.is_sign_negative() || *spread >= dec!(1.0) {
+            return Err(ConfigError::ValidationError(format!("{} must be at least 0 and below 1", setting)));
+        }
+    }
+
+    validate_risk_management(&config.risk_management)?;
+
+    // Validate global risk parameters
+    if config.global_risk.maintenance_margin_rate.is_sign_negative() || config.global_risk.maintenance_margin_rate >= dec!(1.0) {
+        return Err(ConfigError::ValidationError("maintenance_margin_rate must be between 0 and 1".into()));
+    }
+
+    if config.global_risk.liquidation_warning_pct.is_sign_negative() || config.global_risk.liquidation_warning_pct >= dec!(1.0) {
+        return Err(ConfigError::ValidationError("liquidation_warning_pct must be between 0 and 1".into()));
+    }
+
+    if let Some(dynamic_leverage) = &config.global_risk.dynamic_leverage {
+        validate_dynamic_leverage(dynamic_leverage)?;
+    }
+
+    if let Some(daily_loss) = &config.global_risk.daily_loss {
+        validate_daily_loss(daily_loss)?;
+    }
+
+    config.trading_blackouts.validate()?;
+
+    if let Some(backup) = &config.backup {
+        backup.validate()?;
+    }
+
+    // Add more validation as needed
+
+    Ok(())
+}
+
+/// Checks the trade-level risk settings, of `[risk_management]` or of a bot overriding it.
+pub(crate) fn validate_risk_management(risk: &RiskManagement) -> Result<(), ConfigError> {
+    if risk.risk_per_trade_pct <= dec!(0.0) || risk.risk_per_trade_pct > dec!(0.1) {
         return Err(ConfigError::ValidationError("risk_per_trade_pct must be between 0 and 0.1 (10%)".into()));
     }
 
-    if config.risk_management.stop_loss_pct <= dec!(0.0) || config.risk_management.stop_loss_pct > dec!(0.2) {
+    if risk.stop_loss_pct <= dec!(0.0) || risk.stop_loss_pct > dec!(0.2) {
         return Err(ConfigError::ValidationError("stop_loss_pct must be between 0 and 0.2 (20%)".into()));
     }
 
-    // Add more validation as needed
+    if risk.margin_buffer_pct.is_sign_negative() || risk.margin_buffer_pct >= dec!(1.0) {
+        return Err(ConfigError::ValidationError("margin_buffer_pct must be between 0 and 1".into()));
+    }
+
+    if let Some(trigger) = risk.break_even_trigger_pct {
+        if trigger <= dec!(0.0) {
+            return Err(ConfigError::ValidationError("break_even_trigger_pct must be greater than 0".into()));
+        }
+        if risk.break_even_buffer_pct.is_sign_negative() || risk.break_even_buffer_pct >= trigger {
+            return Err(ConfigError::ValidationError("break_even_buffer_pct must be between 0 and break_even_trigger_pct".into()));
+        }
+    }
+
+    if risk.take_profit_pct.is_some_and(|take_profit| take_profit <= dec!(0.0)) {
+        return Err(ConfigError::ValidationError("take_profit_pct must be greater than 0".into()));
+    }
+
+    if risk.max_holding_bars == Some(0) {
+        return Err(ConfigError::ValidationError("max_holding_bars must be greater than 0".into()));
+    }
+
+    if let Some(min_expected_move) = risk.min_expected_move {
+        if min_expected_move.cost_multiple <= dec!(0.0) {
+            return Err(ConfigError::ValidationError("min_expected_move.cost_multiple must be greater than 0".into()));
+        }
+        if min_expected_move.payoff_ratio <= dec!(0.0) {
+            return Err(ConfigError::ValidationError("min_expected_move.payoff_ratio must be greater than 0".into()));
+        }
+    }
+
+    let symbol_limits = risk.symbol_limits.iter().map(|(symbol, limits)| (format!("symbol_limits.{}", symbol), limits));
+    for (section, limits) in std::iter::once(("order_limits".to_string(), &risk.order_limits)).chain(symbol_limits) {
+        if limits.max_notional.is_some_and(|max| max <= dec!(0.0)) {
+            return Err(ConfigError::ValidationError(format!("{}.max_notional must be greater than 0", section)));
+        }
+        if limits.max_quantity.is_some_and(|max| max <= dec!(0.0)) {
+            return Err(ConfigError::ValidationError(format!("{}.max_quantity must be greater than 0", section)));
+        }
+    }
+    Ok(())
+}
 
+/// Checks that the drawdown tiers deepen in order and only ever scale risk down.
+fn validate_dynamic_leverage(dynamic_leverage: &DynamicLeverage) -> Result<(), ConfigError> {
+    let Some(first) = dynamic_leverage.tiers.first() else {
+        return Err(ConfigError::ValidationError("dynamic_leverage.tiers must not be empty".into()));
+    };
+    for tier in &dynamic_leverage.tiers {
+        if tier.drawdown_pct <= dec!(0.0) || tier.drawdown_pct >= dec!(1.0) {
+            return Err(ConfigError::ValidationError("dynamic_leverage tier drawdown_pct must be between 0 and 1".into()));
+        }
+        if tier.multiplier <= dec!(0.0) || tier.multiplier > dec!(1.0) {
+            return Err(ConfigError::ValidationError("dynamic_leverage tier multiplier must be greater than 0 and at most 1".into()));
+        }
+    }
+    if dynamic_leverage.tiers.windows(2).any(|pair| pair[1].drawdown_pct <= pair[0].drawdown_pct) {
+        return Err(ConfigError::ValidationError("dynamic_leverage.tiers must be in order of increasing drawdown_pct".into()));
+    }
+    if dynamic_leverage.recovery_buffer_pct.is_sign_negative() || dynamic_leverage.recovery_buffer_pct >= first.drawdown_pct {
+        return Err(ConfigError::ValidationError("dynamic_leverage.recovery_buffer_pct must be at least 0 and below the first tier's drawdown_pct".into()));
+    }
     Ok(())
 }
+
+/// Checks that the rollover is an hour of the day and every daily loss limit can be reached.
+fn validate_daily_loss(daily_loss: &DailyLossLimits) -> Result<(), ConfigError> {
+    if daily_loss.rollover_hour_utc > 23 {
+        return Err(ConfigError::ValidationError("daily_loss.rollover_hour_utc must be between 0 and 23".into()));
+    }
+    let symbols = daily_loss.symbols.iter().map(|(symbol, limit)| (format!("daily_loss.symbols.{}", symbol), *limit));
+    for (section, limit) in std::iter::once(("daily_loss".to_string(), daily_loss.account())).chain(symbols) {
+        if limit.max_daily_loss_abs.is_some_and(|max| max <= dec!(0.0)) {
+            return Err(ConfigError::ValidationError(format!("{}.max_daily_loss_abs must be greater than 0", section)));
+        }
+        if limit.max_daily_loss_pct.is_some_and(|max| max <= dec!(0.0) || max > dec!(1.0)) {
+            return Err(ConfigError::ValidationError(format!("{}.max_daily_loss_pct must be greater than 0 and at most 1", section)));
+        }
+    }
+    Ok(())
+}
+
 /// Loads the optimizer configuration from a specific TOML file path.
 pub fn load_optimizer_config(path: &Path) -> Result<OptimizerConfig, ConfigError> {
+    check_file_version::<OptimizerConfig>(path)?;
     let builder = config::Config::builder()
         .add_source(config::File::from(path))
         .build()?;
-    builder.try_deserialize::<OptimizerConfig>().map_err(Into::into)
+    let config = builder.try_deserialize::<OptimizerConfig>()?;
+    validate_interval("base_config.interval", &config.base_config.interval)?;
+    Ok(config)
 }
 
 /// Initializes tracing based on the provided logging configuration.
@@ -150,16 +302,93 @@ pub fn init_tracing(logging_config: &LoggingConfig) -> Result<(), ConfigError> {
 
 /// Loads the portfolio configuration from a specific TOML file path.
 pub fn load_portfolio_config(path: &Path) -> Result<PortfolioConfig, ConfigError> {
+    check_file_version::<PortfolioConfig>(path)?;
     let builder = config::Config::builder()
         .add_source(config::File::from(path))
         .build()?;
-    builder.try_deserialize::<PortfolioConfig>().map_err(Into::into)
+    let config = builder.try_deserialize::<PortfolioConfig>()?;
+
+    validate_portfolio_config(&config)?;
+
+    Ok(config)
+}
+
+/// Validates a portfolio definition after loading.
+pub fn validate_portfolio_config(config: &PortfolioConfig) -> Result<(), ConfigError> {
+    if config.initial_capital.is_some_and(|capital| capital <= dec!(0.0)) {
+        return Err(ConfigError::ValidationError("initial_capital must be positive".into()));
+    }
+    if let Some(interval) = &config.interval {
+        validate_interval("interval", interval)?;
+    }
+    if config.max_positions_per_symbol == Some(0) {
+        return Err(ConfigError::ValidationError("max_positions_per_symbol must be at least 1".into()));
+    }
+
+    // Each (symbol, strategy) pair may only be defined once per bot name.
+    let mut seen = std::collections::HashSet::new();
+    for bot in &config.bots {
+        if !seen.insert((bot.symbol.as_str(), bot.strategy_id, bot.name.as_deref())) {
+            let name = bot.name.as_deref().map(|name| format!(" named {:?}", name)).unwrap_or_default();
+            return Err(ConfigError::ValidationError(format!(
+                "bot {} with strategy {:?}{} is defined more than once",
+                bot.symbol, bot.strategy_id, name
+            )));
+        }
+    }
+
+    Ok(())
 }
 
 /// Loads the live trading configuration from a specific TOML file path.
 pub fn load_live_config(path: &Path) -> Result<LiveConfig, ConfigError> {
+    check_file_version::<LiveConfig>(path)?;
     let builder = config::Config::builder()
         .add_source(config::File::from(path))
         .build()?;
-    builder.try_deserialize::<LiveConfig>().map_err(Into::into)
+    let live_config = builder.try_deserialize::<LiveConfig>()?;
+    validate_live_config(&live_config)?;
+    Ok(live_config)
+}
+
+/// Validates the live trading configuration after loading.
+pub fn validate_live_config(live_config: &LiveConfig) -> Result<(), ConfigError> {
+    if live_config.collateral_assets.is_empty() || live_config.collateral_assets.iter().any(|asset| asset.trim().is_empty()) {
+        return Err(ConfigError::ValidationError("collateral_assets must list at least one asset, with no empty names".into()));
+    }
+    if !(live_config.replay.speed.is_finite() && live_config.replay.speed > 0.0) {
+        return Err(ConfigError::ValidationError("replay.speed must be a positive number".into()));
+    }
+    if live_config.replay.spread_pct.is_sign_negative() || live_config.replay.spread_pct >= dec!(1.0) {
+        return Err(ConfigError::ValidationError("replay.spread_pct must be between 0 and 1".into()));
+    }
+    if let Some(reconciliation) = &live_config.pnl_reconciliation
+        && (reconciliation.tolerance.is_sign_negative() || reconciliation.alert_threshold.is_sign_negative())
+    {
+        return Err(ConfigError::ValidationError("pnl_reconciliation.tolerance and alert_threshold must not be negative".into()));
+    }
+    validate_interval("interval", &live_config.interval)?;
+    for bot in &live_config.bots {
+        if let Some(interval) = &bot.interval {
+            validate_interval(&format!("bots.{}.interval", bot.symbol), interval)?;
+        }
+    }
+    Ok(())
+}
+
+/// Checks that no two enabled bots trade the same symbol on the same interval. Klines are
+/// routed to bots by symbol and interval, so each pair may only run one bot. A bot without an
+/// interval runs on `backtest.interval` from `config.toml`, and clashes with a bot naming it.
+pub fn validate_bot_intervals(config: &Config, live_config: &LiveConfig) -> Result<(), ConfigError> {
+    let mut seen = std::collections::HashSet::new();
+    for bot in live_config.bots.iter().filter(|bot| bot.enabled) {
+        let interval = bot.interval.as_deref().unwrap_or(&config.backtest.interval);
+        if !seen.insert((bot.symbol.as_str(), interval)) {
+            return Err(ConfigError::ValidationError(format!(
+                "more than one enabled bot trades {} on the {} interval",
+                bot.symbol, interval
+            )));
+        }
+    }
+    Ok(())
 }
\ No newline at end of file
@@ -1,21 +1,83 @@
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use core_types::enums::StrategyId;
 use std::collections::HashMap;
+use crate::settings::{Backtest, Config, RiskManagement, Simulation};
 
 /// Defines an optimization job. This is deserialized from the `optimizer.toml` file.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OptimizerConfig {
+    /// The schema version the file was written for; see `versioning`.
+    #[serde(default = "crate::versioning::first_version")]
+    pub config_version: u32,
     pub base_config: BaseConfig,
     pub parameter_space: HashMap<String, ParameterRange>,
     #[serde(default)]
     pub analysis: AnalysisConfig,
     #[serde(default)]
     pub wfo: Option<WfoConfig>,
+    /// The maximum number of backtest runs in flight at once.
+    /// Defaults to the number of physical CPU cores when omitted.
+    #[serde(default)]
+    pub max_concurrency: Option<usize>,
+    /// How much of each run's equity curve is stored. Defaults to every point.
+    #[serde(default)]
+    pub equity_curve_resolution: EquityCurveResolution,
+    /// What each run is scored on as soon as it finishes. Defaults to the analyzer's score.
+    #[serde(default)]
+    pub objective: ObjectiveName,
+}
+
+/// Everything an optimization or WFO job ran with, stored alongside the job so its results
+/// can be read back without the files it was started from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobConfigSnapshot {
+    pub optimizer: OptimizerConfig,
+    pub simulation: Simulation,
+    pub risk_management: RiskManagement,
+    /// The backtest settings the job's date range, interval and capital come from.
+    pub backtest: Backtest,
+}
+
+impl JobConfigSnapshot {
+    pub fn new(optimizer: &OptimizerConfig, config: &Config) -> Self {
+        Self {
+            optimizer: optimizer.clone(),
+            simulation: config.simulation.clone(),
+            risk_management: config.risk_management.clone(),
+            backtest: config.backtest.clone(),
+        }
+    }
+}
+
+/// The value an optimization job maximizes, as named in `optimizer.toml`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectiveName {
+    SharpeRatio,
+    CalmarRatio,
+    NetProfit,
+    /// The analyzer's weighted score, on the run's own (unnormalized) metrics.
+    #[default]
+    AnalyzerScore,
+}
+
+/// How densely an optimizer run's equity curve is persisted. Thinned curves always keep the
+/// first and last points and the maximum drawdown's peak and trough.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EquityCurveResolution {
+    /// Store every point.
+    #[default]
+    Full,
+    /// Store every Nth point.
+    EveryNth(usize),
+    /// Store about this many points, chosen by Largest-Triangle-Three-Buckets downsampling.
+    Lttb(usize),
 }
 
 /// Base settings for the optimization job.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BaseConfig {
     pub strategy_id: StrategyId,
     pub symbol: String,
@@ -23,7 +85,7 @@ pub struct BaseConfig {
 }
 
 /// Contains parameters for a Walk-Forward Optimization job.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WfoConfig {
     /// The length of the In-Sample (training) period in weeks.
     pub in_sample_weeks: i64,
@@ -32,7 +94,7 @@ pub struct WfoConfig {
 }
 
 /// Configuration for the analysis and ranking of optimization results.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnalysisConfig {
     pub filters: Filters,
     pub scoring_weights: Weights,
@@ -40,17 +102,39 @@ pub struct AnalysisConfig {
 
 // ... (Filters, Weights, Default implementations, and ParameterRange are unchanged) ...
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Filters {
     pub min_total_trades: usize,
     pub max_drawdown_pct: Decimal,
+    /// The optional filters below are off unless set. When set, a run missing the metric
+    /// (e.g. no Sharpe ratio for a flat equity curve) fails the filter.
+    #[serde(default)]
+    pub min_sharpe_ratio: Option<Decimal>,
+    #[serde(default)]
+    pub min_win_rate_pct: Option<Decimal>,
+    #[serde(default)]
+    pub min_profit_factor: Option<Decimal>,
+    /// The longest tolerated run of losing trades in a row.
+    #[serde(default)]
+    pub max_consecutive_losses: Option<usize>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Weights {
     pub weight_profit_factor: Decimal,
     pub weight_calmar_ratio: Decimal,
     pub weight_avg_win_loss_ratio: Decimal,
+    /// The weight of the expectancy in R-multiples. Runs without one, such as those whose
+    /// trades had no stop-loss, score zero on it.
+    #[serde(default)]
+    pub weight_expectancy: Decimal,
+    /// The weight of the return on the average margin deployed. Runs that did not record
+    /// their exposure score zero on it.
+    #[serde(default)]
+    pub weight_return_on_margin: Decimal,
+    /// The weight of the Sharpe ratio whose volatility counts only the time in the market.
+    #[serde(default)]
+    pub weight_exposure_adjusted_sharpe: Decimal,
 }
 
 impl Default for AnalysisConfig {
@@ -67,6 +151,10 @@ impl Default for Filters {
         Self {
             min_total_trades: 20,
             max_drawdown_pct: Decimal::from(25),
+            min_sharpe_ratio: None,
+            min_win_rate_pct: None,
+            min_profit_factor: None,
+            max_consecutive_losses: None,
         }
     }
 }
@@ -77,15 +165,21 @@ impl Default for Weights {
             weight_profit_factor: "0.4".parse().unwrap(),
             weight_calmar_ratio: "0.4".parse().unwrap(),
             weight_avg_win_loss_ratio: "0.2".parse().unwrap(),
+            weight_expectancy: Decimal::ZERO,
+            weight_return_on_margin: Decimal::ZERO,
+            weight_exposure_adjusted_sharpe: Decimal::ZERO,
         }
     }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum ParameterRange {
     DiscreteInt(Vec<i64>),
     DiscreteDecimal(Vec<Decimal>),
+    /// A list of choices, e.g. `["sma", "ema"]`. Listed after the decimals, which also
+    /// deserialize from strings, so numeric strings still become decimals.
+    DiscreteString(Vec<String>),
     LinearInt { start: i64, end: i64, step: i64 },
     LinearDecimal { start: Decimal, end: Decimal, step: Decimal },
 }
\ No newline at end of file
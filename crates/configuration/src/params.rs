@@ -0,0 +1,71 @@
+//! The canonical JSON form of strategy parameter sets.
+//!
+//! A decimal parameter reaches JSON by several routes: `Decimal`'s own serializer writes a
+//! string that keeps the scale (`"0.10"`), a TOML float becomes a JSON float, and a hand-written
+//! set may use scientific notation (`"1e-6"`). The strategies read any of these, but sets that
+//! hold the same numbers in different forms compare and hash unequal, and a float only holds
+//! the decimal it was parsed from as long as nothing does arithmetic on it.
+//!
+//! In the canonical form every integral number is a JSON integer, so it still loads into the
+//! `usize` fields, and every other number is its shortest exact decimal string (`"0.0001"`),
+//! which loads into the `Decimal` fields without passing through `f64`. Parameter sets are
+//! normalized before they are stored or compared.
+
+use rust_decimal::Decimal;
+use serde_json::{Map, Number, Value as JsonValue};
+
+/// Returns `params` in the canonical form, at every level of nesting. Strings that are not
+/// numbers, and numbers too large or too precise for a `Decimal`, are left as they are.
+pub fn normalize_params(params: JsonValue) -> JsonValue {
+    match params {
+        JsonValue::Object(fields) => {
+            JsonValue::Object(fields.into_iter().map(|(name, value)| (name, normalize_params(value))).collect::<Map<_, _>>())
+        }
+        JsonValue::Array(items) => JsonValue::Array(items.into_iter().map(normalize_params).collect()),
+        JsonValue::Number(number) => match number_as_decimal(&number) {
+            Some(decimal) => canonical_decimal(decimal),
+            None => JsonValue::Number(number),
+        },
+        JsonValue::String(text) => match parse_decimal(&text) {
+            Some(decimal) => canonical_decimal(decimal),
+            None => JsonValue::String(text),
+        },
+        other => other,
+    }
+}
+
+/// Parses a decimal parameter written as a plain (`"0.0001"`) or scientific (`"1e-4"`)
+/// string, exactly.
+pub fn parse_decimal(text: &str) -> Option<Decimal> {
+    let text = text.trim();
+    if text.is_empty() {
+        return None;
+    }
+    Decimal::from_str_exact(text).or_else(|_| Decimal::from_scientific(text)).ok()
+}
+
+/// The decimal a JSON number holds. A float is read from its shortest representation, the
+/// digits it was written with, rather than from its binary value.
+fn number_as_decimal(number: &Number) -> Option<Decimal> {
+    if let Some(integer) = number.as_i64() {
+        return Some(Decimal::from(integer));
+    }
+    if let Some(integer) = number.as_u64() {
+        return Some(Decimal::from(integer));
+    }
+    let float = number.as_f64()?;
+    Decimal::from_str_exact(&float.to_string()).ok()
+}
+
+fn canonical_decimal(decimal: Decimal) -> JsonValue {
+    let decimal = decimal.normalize();
+    if decimal.is_integer() {
+        if let Ok(integer) = i64::try_from(decimal) {
+            return JsonValue::from(integer);
+        }
+        if let Ok(integer) = u64::try_from(decimal) {
+            return JsonValue::from(integer);
+        }
+    }
+    JsonValue::String(decimal.to_string())
+}
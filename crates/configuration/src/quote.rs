@@ -0,0 +1,116 @@
+//! Quote assets: the assets symbols are quoted and margined in, and accounts are kept in.
+//!
+//! The default comes from `execution.quote_asset` in `config.toml`; a bot in `live.toml`
+//! may override it for its own symbol, e.g. to trade a USDC pair next to USDT ones. The
+//! portfolio's cash and equity are always denominated in the default.
+
+use crate::error::ConfigError;
+use crate::settings::{Config, LiveBotConfig, LiveConfig};
+use std::collections::HashMap;
+
+/// The quote asset used when none is configured.
+pub const DEFAULT_QUOTE_ASSET: &str = "USDT";
+
+/// The quote asset of each traded symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuoteAssets {
+    default: String,
+    by_symbol: HashMap<String, String>,
+}
+
+impl QuoteAssets {
+    /// Quotes every symbol in `default`.
+    pub fn new(default: impl Into<String>) -> Self {
+        Self { default: default.into(), by_symbol: HashMap::new() }
+    }
+
+    /// The quote assets of the live engine: the configured default, overridden by each
+    /// enabled bot that sets its own.
+    pub fn for_live(config: &Config, live_config: &LiveConfig) -> Self {
+        let mut quote_assets = Self::new(config.execution.quote_asset.clone());
+        for bot in live_config.bots.iter().filter(|bot| bot.enabled) {
+            if let Some(asset) = &bot.quote_asset {
+                quote_assets.by_symbol.insert(bot.symbol.clone(), asset.clone());
+            }
+        }
+        quote_assets
+    }
+
+    /// The asset accounts are denominated in.
+    pub fn default_asset(&self) -> &str {
+        &self.default
+    }
+
+    /// The quote asset of `symbol`.
+    pub fn for_symbol(&self, symbol: &str) -> &str {
+        self.by_symbol.get(symbol).map_or(&self.default, String::as_str)
+    }
+
+    /// Every quote asset in use, the default first, each listed once.
+    pub fn assets(&self) -> Vec<String> {
+        let mut assets = vec![self.default.clone()];
+        for asset in self.by_symbol.values() {
+            if !assets.contains(asset) {
+                assets.push(asset.clone());
+            }
+        }
+        assets
+    }
+}
+
+impl Default for QuoteAssets {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUOTE_ASSET)
+    }
+}
+
+impl LiveBotConfig {
+    /// This bot's quote asset: its own, or else `default`.
+    pub fn quote_asset_or<'a>(&'a self, default: &'a str) -> &'a str {
+        self.quote_asset.as_deref().unwrap_or(default)
+    }
+}
+
+/// Whether `symbol` settles in `asset`: USDⓈ-margined symbols end with it, dated contracts
+/// before their "_" suffix (e.g. "BTCUSDT_250926"). COIN-margined contracts settle in no
+/// quote asset the engine trades.
+pub fn settles_in(symbol: &str, asset: &str) -> bool {
+    if asset.is_empty() || is_coin_margined(symbol) {
+        return false;
+    }
+    let pair = symbol.split('_').next().unwrap_or(symbol);
+    pair.ends_with(asset) && pair.len() > asset.len()
+}
+
+/// Whether `symbol` is a COIN-margined contract, quoted in USD and margined in its base
+/// asset (e.g. "BTCUSD_PERP"). The engine trades USDⓈ-margined futures only.
+pub fn is_coin_margined(symbol: &str) -> bool {
+    symbol.split_once('_').is_some_and(|(pair, _)| pair.ends_with("USD"))
+}
+
+/// Checks that the default quote asset is named and that every enabled bot trades a
+/// USDⓈ-margined symbol settling in its quote asset, so a USDC bot configured against a USDT
+/// symbol, or a bot on a COIN-margined contract, fails at startup rather than at its first
+/// order.
+pub fn validate_quote_assets(config: &Config, live_config: &LiveConfig) -> Result<(), ConfigError> {
+    let default = config.execution.quote_asset.trim();
+    if default.is_empty() {
+        return Err(ConfigError::validation("execution.quote_asset must name an asset"));
+    }
+    for bot in live_config.bots.iter().filter(|bot| bot.enabled) {
+        if is_coin_margined(&bot.symbol) {
+            return Err(ConfigError::validation(format!(
+                "bot {} trades a COIN-margined contract; only USDⓈ-margined futures are supported",
+                bot.symbol
+            )));
+        }
+        let asset = bot.quote_asset_or(default);
+        if !settles_in(&bot.symbol, asset) {
+            return Err(ConfigError::validation(format!(
+                "bot {} has quote asset {}, but its symbol does not settle in it; set the bot's quote_asset",
+                bot.symbol, asset
+            )));
+        }
+    }
+    Ok(())
+}
@@ -1,14 +1,22 @@
 use rust_decimal::Decimal;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use chrono::NaiveDate;
 use serde_json::Value as JsonValue;
-use core_types::enums::StrategyId;
+use core_types::enums::{KlineTransform, PriceType, StrategyId, TradeDirection};
+use core_types::InvalidKlinePolicy;
+use crate::backup::BackupConfig;
+use crate::blackout::TradingBlackouts;
+use crate::bot_risk::RiskOverrides;
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 #[cfg(feature = "clap")]
 use clap::ValueEnum;
 /// The root configuration structure for the entire application.
 #[derive(Debug, Clone, Deserialize)]
 pub struct Config {
+    /// The schema version the file was written for; see `versioning`.
+    #[serde(default = "crate::versioning::first_version")]
+    pub config_version: u32,
     pub api: ApiConfig,
     pub simulation: Simulation,
     pub global_risk: GlobalRiskConfig,
@@ -22,6 +30,27 @@ pub struct Config {
     /// Configuration for logging and tracing
     #[serde(default)]
     pub logging: LoggingConfig,
+    /// Access control of the web server.
+    #[serde(default)]
+    pub server: ServerConfig,
+    /// Windows in which no new trades are entered.
+    #[serde(default)]
+    pub trading_blackouts: TradingBlackouts,
+    /// Scheduled database backups in the serve and run modes. When unset, only
+    /// `zenith backup` backs the database up.
+    #[serde(default)]
+    pub backup: Option<BackupConfig>,
+    /// How klines are checked as they are stored.
+    #[serde(default)]
+    pub data: DataConfig,
+}
+
+/// How klines are checked as they are stored.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DataConfig {
+    /// What saving a kline that fails `Kline::validate` does, in backfills and imports.
+    #[serde(default)]
+    pub invalid_klines: InvalidKlinePolicy,
 }
 
 /// Holds the secrets for the Telegram alerting service.
@@ -33,6 +62,27 @@ pub struct TelegramConfig {
     pub chat_id: String,
 }
 
+/// Access control of the web server's REST API and WebSocket.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ServerConfig {
+    /// When set, REST requests must send `Authorization: Bearer <token>` and WebSocket
+    /// clients must authenticate before receiving data. The health endpoint stays public.
+    #[serde(default)]
+    pub api_token: Option<String>,
+    /// The origins allowed to make cross-origin requests. Empty, or containing "*",
+    /// allows any origin.
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+    /// The most backtests started through the API that run at once; later submissions wait
+    /// their turn as Pending. Defaults to 2 when omitted.
+    #[serde(default)]
+    pub max_concurrent_backtests: Option<usize>,
+    /// The most closed klines per symbol kept in memory for `GET /api/live/klines/:symbol`,
+    /// so dashboards joining mid-session can draw a chart. Defaults to 500 when omitted.
+    #[serde(default)]
+    pub kline_history_bars: Option<usize>,
+}
+
 /// Holds the API connection details and secrets for different environments.
 #[derive(Debug, Clone, Deserialize)]
 pub struct ApiConfig {
@@ -48,12 +98,15 @@ pub struct ApiConfig {
 pub struct ExecutionConfig {
     /// The default order type to use. "Market" or "Limit".
     pub order_type: String,
+    /// The asset cash, equity and fees are accounted in, unless a bot overrides it.
+    #[serde(default = "default_quote_asset")]
+    pub quote_asset: String,
 }
 /// Contains parameters for the portfolio-level circuit breakers.
 #[derive(Debug, Clone, Deserialize)]
 pub struct GlobalRiskConfig {
-    /// The maximum allowed drawdown from the session's peak equity.
-    /// If breached, all trading is halted for the session. E.g., 0.10 for 10%.
+    /// The maximum allowed drawdown from the trading day's peak equity.
+    /// If breached, all trading is halted until the next rollover. E.g., 0.10 for 10%.
     pub max_daily_drawdown_pct: Decimal,
     
     /// The maximum number of consecutive losing trades for any single bot.
@@ -66,6 +119,121 @@ pub struct GlobalRiskConfig {
     /// The maximum number of open positions a single bot can have.
     /// For our current system, this should be set to 1.
     pub max_open_positions_per_asset: u32,
+
+    /// The maintenance margin rate assumed when estimating positions' liquidation prices.
+    /// E.g., 0.004 for 0.4%, the lowest tier of most USDT-margined perpetuals.
+    #[serde(default = "default_maintenance_margin_rate")]
+    pub maintenance_margin_rate: Decimal,
+
+    /// Warn when a position's mark price comes within this fraction of its estimated
+    /// liquidation price. E.g., 0.05 warns within 5%.
+    #[serde(default = "default_liquidation_warning_pct")]
+    pub liquidation_warning_pct: Decimal,
+
+    /// Scales down the risk of new entries as the account's drawdown deepens. When unset,
+    /// entries are always sized at the full `risk_per_trade_pct`.
+    #[serde(default)]
+    pub dynamic_leverage: Option<DynamicLeverage>,
+
+    /// Caps the loss of each trading day, per symbol and for the whole account. When unset,
+    /// only the drawdown from peak equity limits a day's losses.
+    #[serde(default)]
+    pub daily_loss: Option<DailyLossLimits>,
+}
+
+impl GlobalRiskConfig {
+    /// The UTC hour each trading day starts at: the daily loss limits' `rollover_hour_utc`, or
+    /// midnight without them. The daily rollover of the risk state happens then too, so it
+    /// and the daily loss accounting always agree on the trading day.
+    pub fn rollover_hour_utc(&self) -> u32 {
+        self.daily_loss.as_ref().map_or(0, |limits| limits.rollover_hour_utc)
+    }
+}
+
+/// Limits on the loss of a trading day: the P&L realized since the day's rollover, net of
+/// fees, plus the unrealized P&L of the open positions. A breach halts the bot, or every
+/// bot for the account's limit, until the next rollover.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DailyLossLimits {
+    /// The UTC hour (0-23) each trading day starts at, for these limits and for the daily
+    /// rollover of the risk state.
+    #[serde(default)]
+    pub rollover_hour_utc: u32,
+    /// The account's limit, in quote currency (e.g., 500 for $500).
+    #[serde(default)]
+    pub max_daily_loss_abs: Option<Decimal>,
+    /// The account's limit, as a fraction of its equity at the rollover (e.g., 0.05 for 5%).
+    #[serde(default)]
+    pub max_daily_loss_pct: Option<Decimal>,
+    /// Per-symbol limits, keyed by symbol. Symbols without an entry are only limited by the
+    /// account's limit.
+    #[serde(default)]
+    pub symbols: BTreeMap<String, DailyLossLimit>,
+}
+
+impl DailyLossLimits {
+    /// The account's limits.
+    pub fn account(&self) -> DailyLossLimit {
+        DailyLossLimit { max_daily_loss_abs: self.max_daily_loss_abs, max_daily_loss_pct: self.max_daily_loss_pct }
+    }
+}
+
+/// A daily loss limit, by amount, by fraction of the account's equity at the rollover, or
+/// both, in which case the tighter one applies.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+pub struct DailyLossLimit {
+    #[serde(default)]
+    pub max_daily_loss_abs: Option<Decimal>,
+    #[serde(default)]
+    pub max_daily_loss_pct: Option<Decimal>,
+}
+
+impl DailyLossLimit {
+    /// The loss the limit allows, in quote currency, given the account's equity at the
+    /// rollover. `None` when neither limit is set.
+    pub fn allowed_loss(&self, start_equity: Decimal) -> Option<Decimal> {
+        let by_pct = self.max_daily_loss_pct.map(|pct| pct * start_equity);
+        match (self.max_daily_loss_abs, by_pct) {
+            (Some(abs), Some(by_pct)) => Some(abs.min(by_pct)),
+            (abs, by_pct) => abs.or(by_pct),
+        }
+    }
+}
+
+/// Drawdown tiers that scale the risk of new entries down as the account's equity falls
+/// from its peak. Open positions are never resized.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DynamicLeverage {
+    /// The tiers, by increasing drawdown. Below the first, entries take full risk.
+    pub tiers: Vec<DrawdownTier>,
+    /// How far back above a tier's boundary the drawdown must recover, as a fraction of the
+    /// peak equity, before the tier is left. E.g., 0.01 leaves a 5% tier at a 4% drawdown.
+    #[serde(default = "default_recovery_buffer_pct")]
+    pub recovery_buffer_pct: Decimal,
+    /// Applies the tiers to backtests too, against each run's own equity curve.
+    #[serde(default)]
+    pub apply_in_backtests: bool,
+}
+
+/// One tier of `DynamicLeverage`.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct DrawdownTier {
+    /// The drawdown from peak equity the tier starts at (e.g., 0.05 for 5%).
+    pub drawdown_pct: Decimal,
+    /// The fraction of `risk_per_trade_pct` entries take within the tier (e.g., 0.5).
+    pub multiplier: Decimal,
+}
+
+fn default_recovery_buffer_pct() -> Decimal {
+    Decimal::new(1, 2)
+}
+
+fn default_maintenance_margin_rate() -> Decimal {
+    Decimal::new(4, 3)
+}
+
+fn default_liquidation_warning_pct() -> Decimal {
+    Decimal::new(5, 2)
 }
 
 /// A structure to hold an API key and secret pair.
@@ -75,7 +243,7 @@ pub struct ApiKeys {
     pub secret: String,
 }
 /// Contains parameters for a single backtest run.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Backtest {
     /// The strategy to use for the backtest.
     pub strategy_id: StrategyId,
@@ -89,11 +257,25 @@ pub struct Backtest {
     pub start_date: NaiveDate,
     /// The default end date for the backtest period.
     pub end_date: NaiveDate,
+    /// The preprocessing applied to klines before the strategy evaluates them.
+    #[serde(default)]
+    pub kline_transform: KlineTransform,
+    /// The sides the strategy may take positions on. Its entries on the other side only close
+    /// an open position.
+    #[serde(default)]
+    pub direction: TradeDirection,
+    /// The price positions are valued at and stop-losses trigger on. `Mark` needs mark price
+    /// klines backfilled for the whole range; fills still happen at the last price.
+    #[serde(default)]
+    pub valuation_price: PriceType,
 }
 
 /// Defines the configuration for the live trading engine.
 #[derive(Debug, Clone, Deserialize)]
 pub struct LiveConfig {
+    /// The schema version the file was written for; see `versioning`.
+    #[serde(default = "crate::versioning::first_version")]
+    pub config_version: u32,
     /// A master safety switch to enable or disable all live trading.
     pub live_trading_enabled: bool,
     /// The timeframe interval to use for all bots (e.g., "1m", "5m", "1h").
@@ -101,14 +283,163 @@ pub struct LiveConfig {
     /// Enable broadcasting kline data to WebSocket clients.
     #[serde(default = "default_broadcast_klines")]
     pub broadcast_klines: bool,
+    /// How often, in seconds, the marked-to-market portfolio is broadcast to WebSocket clients,
+    /// independent of trade activity.
+    #[serde(default = "default_portfolio_broadcast_secs")]
+    pub portfolio_broadcast_secs: u64,
+    /// Arms the dead-man's switch: when the data feed stays silent, flatten all open positions
+    /// and halt all bots. When false, a silent feed only raises alerts.
+    #[serde(default)]
+    pub dead_mans_switch_enabled: bool,
+    /// Seconds without a kline or mark price for a symbol before its feed counts as silent.
+    /// Defaults to three times the smallest bot interval.
+    #[serde(default)]
+    pub feed_timeout_secs: Option<u64>,
+    /// Seconds of continued silence, after the silent-feed alert, before positions are flattened.
+    #[serde(default = "default_flatten_after_secs")]
+    pub flatten_after_secs: u64,
+    /// How often, in seconds, the per-symbol decision latency percentiles are logged and
+    /// broadcast to WebSocket clients. Each report covers the klines since the previous one.
+    #[serde(default = "default_latency_report_secs")]
+    pub latency_report_secs: u64,
+    /// How often, in seconds, the health of each symbol and interval's market data feeds is
+    /// broadcast to WebSocket clients.
+    #[serde(default = "default_feed_status_secs")]
+    pub feed_status_secs: u64,
+    /// While a file exists at this path, the engine places no new orders; orders that only
+    /// close or reduce a position still go through. Setting the `ZENITH_KILL_SWITCH`
+    /// environment variable has the same effect.
+    #[serde(default)]
+    pub kill_switch_file: Option<PathBuf>,
+    /// The most positions the engine holds at once across all bots. Orders that would open a
+    /// position beyond it are refused.
+    #[serde(default)]
+    pub max_open_positions: Option<usize>,
+    /// The most orders a single bot may place in any rolling hour. A bot exceeding it is halted.
+    #[serde(default)]
+    pub max_orders_per_hour: Option<u32>,
+    /// How many times in a row a bot's strategy may fail to evaluate a kline before the bot
+    /// is halted. Each failure first resets the strategy and re-warms it from recent klines.
+    #[serde(default = "default_max_strategy_errors")]
+    pub max_strategy_errors: u32,
+    /// The account assets counted as collateral, besides the quote assets, which always are.
+    /// Balances in other assets are valued at the mark price of their pair with the quote
+    /// asset; assets not listed are ignored.
+    #[serde(default = "default_collateral_assets")]
+    pub collateral_assets: Vec<String>,
+    /// Records every book ticker update into the database, for ML datasets with order book
+    /// features. Replays never record.
+    #[serde(default)]
+    pub record_book_tickers: bool,
+    /// What the engine does at startup with an open exchange position it has no recorded
+    /// context for, e.g. one opened by hand or before contexts were recorded.
+    #[serde(default)]
+    pub orphan_position_policy: OrphanPositionPolicy,
+    /// Reconciles the week's live P&L against the exchange's income history every Monday.
+    /// When unset, only `zenith reconcile-pnl` reconciles it.
+    #[serde(default)]
+    pub pnl_reconciliation: Option<PnlReconciliationConfig>,
+    /// How recorded klines are played back in replay mode.
+    #[serde(default)]
+    pub replay: ReplayConfig,
     /// A collection of individual trading bots to run.
     #[serde(rename = "bot")]
     pub bots: Vec<LiveBotConfig>,
 }
 
+/// The weekly reconciliation of the live P&L against the exchange's income history.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct PnlReconciliationConfig {
+    /// The largest difference, in quote currency, between a symbol's day of local and
+    /// exchange figures that still counts as a match.
+    #[serde(default = "default_reconciliation_tolerance")]
+    pub tolerance: Decimal,
+    /// Raises an error alert when the week's mismatches diverge by more than this in total,
+    /// in quote currency.
+    pub alert_threshold: Decimal,
+}
+
+fn default_reconciliation_tolerance() -> Decimal {
+    Decimal::new(1, 2)
+}
+
+/// What the live engine does with an orphaned position: one open on the exchange that no
+/// recorded position context accounts for.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrphanPositionPolicy {
+    /// Manage it as the symbol's bot would have, with a stop `risk_management.stop_loss_pct`
+    /// from its entry price.
+    #[default]
+    AdoptWithDefaultStop,
+    /// Close it with a market order.
+    Flatten,
+    /// Leave it unmanaged, without a stop, and raise an error alert.
+    IgnoreWithAlert,
+}
+
+/// Playback settings for replaying recorded klines through the live engine.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayConfig {
+    /// How much faster than real time klines are played back: 1 is real time, 60 plays an
+    /// hour of klines per minute. Must be positive.
+    #[serde(default = "default_replay_speed")]
+    pub speed: f64,
+    /// The distance of the synthesized best bid and ask from each kline's close, as a
+    /// fraction of it (e.g., 0.0005 for 0.05%).
+    #[serde(default = "default_replay_spread_pct")]
+    pub spread_pct: Decimal,
+}
+
+impl Default for ReplayConfig {
+    fn default() -> Self {
+        Self { speed: default_replay_speed(), spread_pct: default_replay_spread_pct() }
+    }
+}
+
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+fn default_replay_spread_pct() -> Decimal {
+    Decimal::new(5, 4)
+}
+
 fn default_broadcast_klines() -> bool {
     false
 }
+
+fn default_portfolio_broadcast_secs() -> u64 {
+    15
+}
+
+fn default_flatten_after_secs() -> u64 {
+    300
+}
+
+fn default_latency_report_secs() -> u64 {
+    60
+}
+
+fn default_feed_status_secs() -> u64 {
+    5
+}
+
+fn default_max_strategy_errors() -> u32 {
+    3
+}
+
+fn default_quote_asset() -> String {
+    crate::quote::DEFAULT_QUOTE_ASSET.to_string()
+}
+
+fn default_collateral_assets() -> Vec<String> {
+    vec!["USDT".to_string()]
+}
+
+fn default_maker_fee_pct() -> Decimal {
+    Decimal::new(2, 4)
+}
 // --- Execution Mode ---
 // Defines the possible execution environments for the `run` command.
 #[cfg(feature = "clap")]
@@ -120,6 +451,9 @@ pub enum ExecutionMode {
     Testnet,
     /// Live data, real orders sent to the Binance Production exchange (REAL MONEY).
     Live,
+    /// Recorded klines from the database, replayed through the live engine with simulated
+    /// execution.
+    Replay,
 }
 /// Defines a single trading bot for the live engine.
 #[derive(Debug, Clone, Deserialize)]
@@ -133,30 +467,246 @@ pub struct LiveBotConfig {
     /// Optional: The leverage for this specific bot.
     /// If not provided, a default value will be used.
     pub leverage: Option<u8>,
+    /// The preprocessing applied to klines before this bot's strategy evaluates them.
+    #[serde(default)]
+    pub kline_transform: KlineTransform,
+    /// The sides this bot may take positions on. Its entries on the other side only close an
+    /// open position.
+    #[serde(default)]
+    pub direction: TradeDirection,
+    /// Optional: The asset this bot's symbol is quoted and margined in.
+    /// If not provided, `execution.quote_asset` from `config.toml` will be used.
+    #[serde(default)]
+    pub quote_asset: Option<String>,
+    /// Optional: The `[risk_management]` settings this bot overrides for itself.
+    #[serde(default)]
+    pub risk: Option<RiskOverrides>,
     /// The specific parameters for this bot's strategy.
     pub params: JsonValue,
 }
 
 /// Contains parameters for the backtesting and simulation engine.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Simulation {
     /// The trading fees charged by the exchange for a "taker" order.
     /// 0.0004 corresponds to 0.04%.
     pub taker_fee_pct: Decimal,
-    
+
+    /// The trading fees charged by the exchange for a "maker" order, i.e. a limit order that
+    /// rests on the book. 0.0002 corresponds to 0.02%.
+    #[serde(default = "default_maker_fee_pct")]
+    pub maker_fee_pct: Decimal,
+
+    /// A discount applied to both fee rates, e.g. for paying fees in BNB.
+    /// 0.1 means every fee is 10% lower.
+    #[serde(default)]
+    pub fee_discount_pct: Decimal,
+
     /// The assumed price slippage for market orders.
     /// This is a simple model where slippage is a percentage of the bar's high-low range.
     /// 0.1 means we assume we get a price that is 10% worse than the close.
     pub slippage_pct: Decimal,
+
+    /// The bid-ask spread assumed when no quotes are available, as a fraction of the price.
+    /// Buys fill at `close * (1 + spread / 2)` and sells at `close * (1 - spread / 2)`, before
+    /// slippage. 0.001 corresponds to 10 bps.
+    #[serde(default)]
+    pub simulated_spread_pct: Decimal,
+
+    /// Per-symbol spreads, keyed by symbol, overriding `simulated_spread_pct`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbol_spread_pct: BTreeMap<String, Decimal>,
+
+    /// The price a triggered stop-loss or take-profit fills at, before the spread and slippage.
+    #[serde(default)]
+    pub stop_fill_model: StopFillModel,
+
+    /// Which exit a backtest takes when a bar reached both a position's stop-loss and its
+    /// take-profit.
+    #[serde(default)]
+    pub bracket_resolution: BracketResolution,
+}
+
+/// Where a backtest fills a stop-loss or take-profit whose bar reached it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopFillModel {
+    /// At the stop or take-profit price.
+    #[default]
+    Exact,
+    /// At the bar's open when the bar opened beyond the stop or take-profit, as a triggered
+    /// market order does after a gap; at its price otherwise.
+    GapAware,
+}
+
+/// How a backtest decides whether the stop-loss or the take-profit of a position was touched
+/// first, on a bar that reached both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BracketResolution {
+    /// The stop-loss, the conservative assumption the live engine makes too.
+    #[default]
+    Assumption,
+    /// By replaying the bar's 1m klines, when the backtest runs on a coarser interval and they
+    /// are stored. Falls back to the stop-loss where they are missing or a single 1m kline
+    /// reached both levels.
+    SubBar,
+}
+
+impl Simulation {
+    /// The spread assumed for `symbol` when no quotes are available.
+    pub fn spread_pct(&self, symbol: &str) -> Decimal {
+        self.symbol_spread_pct.get(symbol).copied().unwrap_or(self.simulated_spread_pct)
+    }
 }
 
 /// Contains parameters for trade-level risk management.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct RiskManagement {
     /// The fraction of total portfolio equity to risk on a single trade (e.g., 0.01 for 1%).
     pub risk_per_trade_pct: Decimal,
     /// The percentage distance from the entry price to set the stop-loss for position sizing calculations.
     pub stop_loss_pct: Decimal,
+    /// The fraction of available margin held back when sizing leveraged orders (e.g., 0.05 for 5%).
+    /// Protects against fees and price movement between sizing and fill.
+    #[serde(default = "default_margin_buffer_pct")]
+    pub margin_buffer_pct: Decimal,
+    /// Enables pyramiding: the maximum number of times an open position may be added to.
+    /// When unset, a same-direction signal resizes the position toward its risk-sized target.
+    #[serde(default)]
+    pub max_position_adds: Option<u32>,
+    /// The minimum favorable move since the last entry, as a fraction of that entry's price,
+    /// before a position may be added to (e.g., 0.01 for 1%). Only used with `max_position_adds`.
+    #[serde(default)]
+    pub add_spacing_pct: Decimal,
+    /// How a `Reverse` signal against an open position is placed.
+    #[serde(default)]
+    pub reverse_mode: ReverseMode,
+    /// The favorable move from a position's entry price, as a fraction of it, after which
+    /// its stop-loss moves to break-even (e.g., 0.03 for 3%). When unset, stops never move.
+    #[serde(default)]
+    pub break_even_trigger_pct: Option<Decimal>,
+    /// How far beyond the entry price the break-even stop sits, as a fraction of it
+    /// (e.g., 0.002 for 0.2%), so the exit still covers fees. Must be below the trigger.
+    #[serde(default)]
+    pub break_even_buffer_pct: Decimal,
+    /// The favorable move from a position's entry price, as a fraction of it, at which it is
+    /// closed with a profit (e.g., 0.04 for 4%). When unset, positions have no take-profit.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit_pct: Option<Decimal>,
+    /// Absolute caps on the size of any entry order, whatever the sizing math says.
+    /// Used for symbols without an entry in `symbol_limits`, or for the caps it leaves unset.
+    #[serde(default, skip_serializing_if = "OrderLimits::is_unlimited")]
+    pub order_limits: OrderLimits,
+    /// Per-symbol caps, keyed by symbol, overriding `order_limits`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub symbol_limits: BTreeMap<String, OrderLimits>,
+    /// What happens to an entry order that exceeds its caps.
+    #[serde(default, skip_serializing_if = "LimitAction::is_default")]
+    pub limit_action: LimitAction,
+    /// Closes a position once this many bars have closed after the bar it was opened on.
+    /// When unset, positions are held until a signal or stop closes them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_holding_bars: Option<u32>,
+    /// Closes any open position on the last bar of each UTC day.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub exit_at_session_end: bool,
+    /// Refuses entries whose expected move does not cover their round-trip costs enough times.
+    /// When unset, entries are never refused for their costs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_expected_move: Option<MinExpectedMove>,
+}
+
+impl RiskManagement {
+    /// The caps that apply to orders on `symbol`: its own, falling back to `order_limits`
+    /// for each one it leaves unset.
+    pub fn limits_for(&self, symbol: &str) -> OrderLimits {
+        let symbol_limits = self.symbol_limits.get(symbol).copied().unwrap_or_default();
+        OrderLimits {
+            max_notional: symbol_limits.max_notional.or(self.order_limits.max_notional),
+            max_quantity: symbol_limits.max_quantity.or(self.order_limits.max_quantity),
+        }
+    }
+}
+
+/// Absolute caps on a single order. An unset cap does not limit anything.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize, Serialize)]
+pub struct OrderLimits {
+    /// The largest order value, in quote currency (e.g., USDT), at the order's price.
+    #[serde(default)]
+    pub max_notional: Option<Decimal>,
+    /// The largest order quantity, in the base asset (e.g., BTC).
+    #[serde(default)]
+    pub max_quantity: Option<Decimal>,
+}
+
+impl OrderLimits {
+    /// True when neither cap is set.
+    pub fn is_unlimited(&self) -> bool {
+        self.max_notional.is_none() && self.max_quantity.is_none()
+    }
+}
+
+/// The filter refusing entries whose expected move is too small to pay for their costs.
+///
+/// An entry's expected move is its take-profit distance (`take_profit_pct`) when one is set,
+/// otherwise its stop distance (`stop_loss_pct`) times `payoff_ratio`. Its round-trip cost
+/// is a taker fee each way, the spread and the slippage of both fills.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Serialize)]
+pub struct MinExpectedMove {
+    /// How many times the round-trip cost the expected move must reach (e.g., 2).
+    #[serde(default = "default_cost_multiple")]
+    pub cost_multiple: Decimal,
+    /// The assumed reward-to-risk of a trade: its expected move as a multiple of its stop
+    /// distance (e.g., 1.5). Unused when `take_profit_pct` is set.
+    #[serde(default = "default_payoff_ratio")]
+    pub payoff_ratio: Decimal,
+}
+
+impl Default for MinExpectedMove {
+    fn default() -> Self {
+        Self { cost_multiple: default_cost_multiple(), payoff_ratio: default_payoff_ratio() }
+    }
+}
+
+fn default_cost_multiple() -> Decimal {
+    Decimal::TWO
+}
+
+fn default_payoff_ratio() -> Decimal {
+    Decimal::ONE
+}
+
+/// What the risk manager does with an entry order that exceeds its `OrderLimits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitAction {
+    /// Shrink the order to the caps and log a warning.
+    #[default]
+    Clamp,
+    /// Refuse the order.
+    Reject,
+}
+
+impl LimitAction {
+    fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn default_margin_buffer_pct() -> Decimal {
+    Decimal::new(5, 2)
+}
+
+/// How a reversal is sent to the exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReverseMode {
+    /// A reduce-only order that closes the position, then a separately sized entry.
+    #[default]
+    Separate,
+    /// One order for the position's quantity plus the entry's. Only for one-way accounts.
+    Netted,
 }
 
 /// Contains the parameter sets for all available strategies.
@@ -168,24 +718,89 @@ pub struct Strategies {
     pub funding_rate_arb: FundingRateArbParams,
     #[serde(default)] // Make ML params optional in config
     pub ml_strategy: MlStrategyParams,
+    #[serde(default)]
+    pub ensemble: EnsembleParams,
+    /// Suppresses the signals of the strategies it lists on low-volume bars. Unset, no
+    /// strategy is filtered.
+    #[serde(default)]
+    pub volume_filter: Option<VolumeFilterParams>,
+}
+/// Parameters of the volume filter, which drops a strategy's signals on bars whose volume
+/// is low relative to the recent average, or whose dollar volume is too small.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct VolumeFilterParams {
+    /// The strategies whose parameter sets take this filter, under a `volume_filter` key.
+    /// Only read from `[strategies.volume_filter]`; a strategy's own set leaves it out.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strategies: Vec<StrategyId>,
+    /// How many bars, the signal's own included, the average volume is taken over.
+    pub lookback: usize,
+    /// The smallest multiple of the average volume a bar must trade for its signal to pass,
+    /// e.g. 0.8. Bars before the average has `lookback` bars pass this check.
+    pub min_relative_volume: Decimal,
+    /// The smallest dollar volume (close times volume) a bar must trade for its signal to
+    /// pass. Unset, there is no floor.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_dollar_volume: Option<Decimal>,
+    /// Which signals are filtered.
+    #[serde(default)]
+    pub signals: FilteredSignals,
+}
+
+/// The signals a volume filter applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilteredSignals {
+    /// Only signals that open or reverse a position; exits always pass.
+    #[default]
+    Entries,
+    /// Every signal, exits included.
+    All,
 }
 /// Parameters for the ML Strategy.
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
 pub struct MlStrategyParams {
     /// The file path to the serialized, trained model artifact.
     pub model_path: PathBuf,
+    /// The most recent klines the features are computed over. Unset keeps 500; it must cover
+    /// the longest feature lookback.
+    #[serde(default)]
+    pub buffer_capacity: Option<usize>,
+    /// How many klines to see before trading. Unset waits for the longest feature lookback,
+    /// which is also the least that is honoured.
+    #[serde(default)]
+    pub min_bars_before_trading: Option<usize>,
 }
 /// Parameters for the Triple Moving Average Crossover strategy.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct MACrossoverParams {
     pub ma_fast_period: usize,
     pub ma_slow_period: usize,
-    /// A long-term MA to act as a trend filter.
+    /// The period of a long-term simple MA acting as a trend filter: buys need the close
+    /// above it and sells below it. 0, the default, disables the filter; otherwise it must be
+    /// at least `ma_slow_period`.
+    #[serde(default)]
     pub trend_filter_period: usize,
+    /// The kind of moving average used for the fast and slow MAs: "sma", "ema", "wma" or
+    /// "hma". The trend filter is always simple.
+    #[serde(default = "default_ma_type")]
+    pub ma_type: String,
+    /// How many further closes a crossover must hold for before it is traded. Zero trades
+    /// on the crossing bar itself.
+    #[serde(default)]
+    pub confirmation_bars: usize,
+}
+
+fn default_ma_type() -> String {
+    "sma".to_string()
 }
 
 /// Parameters for the SuperTrend strategy with an ADX trend filter.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct SuperTrendParams {
     pub atr_period: usize,
     pub atr_multiplier: Decimal,
@@ -195,7 +810,8 @@ pub struct SuperTrendParams {
 }
 
 /// Parameters for the multi-factor Probabilistic Mean Reversion strategy.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct ProbReversionParams {
     pub bb_period: usize,
     pub bb_std_dev: Decimal,
@@ -208,25 +824,106 @@ pub struct ProbReversionParams {
 }
 
 /// Parameters for the Funding Rate Arbitrage strategy.
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(deny_unknown_fields)]
 pub struct FundingRateArbParams {
     /// The target funding rate threshold to trigger a position.
     pub target_rate_threshold: Decimal,
-    /// A safety threshold. If spot-perp basis expands beyond this, close the position.
+    /// The widest spot-perp basis, as a fraction of the index price, at which a position is
+    /// entered. Wider, the funding collected may not cover the basis converging.
     pub basis_safety_threshold: Decimal,
 }
+
+/// Parameters for the Ensemble meta-strategy, which trades on a vote of its member strategies.
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[serde(deny_unknown_fields)]
+pub struct EnsembleParams {
+    /// The strategies that vote, each at most once. An ensemble cannot be a member.
+    pub members: Vec<StrategyId>,
+    /// How many members must agree on a side before the ensemble takes it (K of N).
+    pub min_agreement: usize,
+    /// How many bars, counting the signal's own, a member's stance lasts after its latest
+    /// signal (M). Zero keeps it until the member's next signal.
+    #[serde(default)]
+    pub stance_bars: usize,
+    /// Each member's parameters. Members missing here take their section of `[strategies]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub member_params: BTreeMap<StrategyId, JsonValue>,
+}
 /// Defines a portfolio, which is a collection of individual trading bots.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PortfolioConfig {
+    /// The schema version the file was written for; see `versioning`.
+    #[serde(default = "crate::versioning::first_version")]
+    pub config_version: u32,
+    /// Optional: The starting capital for this portfolio.
+    /// If not provided, `backtest.initial_capital` from `config.toml` will be used.
+    #[serde(default)]
+    pub initial_capital: Option<Decimal>,
+    /// Optional: The kline interval shared by all bots in this portfolio.
+    /// If not provided, `backtest.interval` from `config.toml` will be used.
+    #[serde(default)]
+    pub interval: Option<String>,
+    /// Optional: How many bots may hold a position on one symbol at a time. Unlimited if not
+    /// provided. Bots on a symbol share its position, so each one entering on the side already
+    /// held adds to it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_positions_per_symbol: Option<u32>,
+    /// What becomes of an entry past `max_positions_per_symbol`.
+    #[serde(default)]
+    pub overlap_policy: OverlapPolicy,
     #[serde(rename = "bot")]
     pub bots: Vec<PortfolioBotConfig>,
 }
 
+/// What the portfolio backtester does with a bot's entry on the side of a position that
+/// `max_positions_per_symbol` other bots already hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OverlapPolicy {
+    /// The entry is skipped and counted in the report.
+    #[default]
+    Skip,
+    /// The entry only tops the position up to what the bot would have entered on its own.
+    Net,
+    /// The entry is placed, adding to the position as if there were no limit.
+    Allow,
+}
+
+impl PortfolioConfig {
+    /// Returns a copy of the base configuration with this portfolio's capital and
+    /// interval applied to its backtest settings, so everything downstream sees one value.
+    pub fn resolve_base_config(&self, base_config: &Config) -> Config {
+        let mut resolved = base_config.clone();
+        if let Some(initial_capital) = self.initial_capital {
+            resolved.backtest.initial_capital = initial_capital;
+        }
+        if let Some(interval) = &self.interval {
+            resolved.backtest.interval = interval.clone();
+        }
+        resolved
+    }
+}
+
 /// Defines a single trading bot with its parameters embedded directly.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct PortfolioBotConfig {
     pub symbol: String,
     pub strategy_id: StrategyId,
+    /// Optional: Tells apart several bots running the same strategy on one symbol,
+    /// e.g. the parameter sets written by `analyze --emit-portfolio`.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The preprocessing applied to klines before this bot's strategy evaluates them.
+    #[serde(default)]
+    pub kline_transform: KlineTransform,
+    /// The sides this bot may take positions on. Its entries on the other side only close an
+    /// open position.
+    #[serde(default)]
+    pub direction: TradeDirection,
+    /// Optional: The `[risk_management]` settings this bot overrides for itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk: Option<RiskOverrides>,
     /// The specific parameters for this bot, stored as a flexible JSON/TOML object.
     pub params: JsonValue,
 }
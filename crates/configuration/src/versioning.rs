@@ -0,0 +1,367 @@
+//! Schema versions of the configuration files.
+//!
+//! Each file records the schema it was written for as a top-level `config_version`; files
+//! from before the field existed are version 1. Loading a file older than this build warns
+//! about the settings added since, which silently take their defaults, and loading a newer
+//! one fails. `migrate` brings a file up to date without touching its existing values or
+//! comments.
+
+use crate::error::ConfigError;
+use crate::optimizer_config::OptimizerConfig;
+use crate::settings::{Config, LiveConfig, PortfolioConfig};
+use std::fmt;
+use std::path::Path;
+use toml_edit::{DocumentMut, Item, Key, Table, TableLike, Value};
+
+/// The version of files that do not set `config_version`.
+pub const FIRST_VERSION: u32 = 1;
+
+pub(crate) fn first_version() -> u32 {
+    FIRST_VERSION
+}
+
+/// The top-level key holding a file's schema version.
+pub const VERSION_KEY: &str = "config_version";
+
+/// The value a setting takes when a file leaves it out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingDefault {
+    /// This value, as a TOML literal.
+    Value(&'static str),
+    /// Unset, which turns the setting's feature off. `example` is a TOML literal shown in
+    /// the commented-out line `migrate` adds.
+    Unset { example: &'static str },
+}
+
+/// A setting introduced after the first version of a configuration file's schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddedSetting {
+    /// The first schema version with this setting.
+    pub version: u32,
+    /// The setting's dotted path, e.g. `risk_management.max_holding_bars`.
+    pub key: &'static str,
+    pub default: SettingDefault,
+}
+
+impl fmt::Display for AddedSetting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (added in version {}): ", self.key, self.version)?;
+        match self.default {
+            SettingDefault::Value(value) => write!(f, "defaults to {}", value),
+            SettingDefault::Unset { .. } => f.write_str("unset by default"),
+        }
+    }
+}
+
+/// A configuration file with a versioned schema.
+pub trait Versioned {
+    /// The file's usual name, used in messages.
+    const FILE_NAME: &'static str;
+    /// The newest schema version this build reads.
+    const CURRENT_VERSION: u32;
+    /// The settings introduced after version 1, oldest first.
+    const ADDED_SETTINGS: &'static [AddedSetting];
+}
+
+const fn value(version: u32, key: &'static str, default: &'static str) -> AddedSetting {
+    AddedSetting { version, key, default: SettingDefault::Value(default) }
+}
+
+const fn unset(version: u32, key: &'static str, example: &'static str) -> AddedSetting {
+    AddedSetting { version, key, default: SettingDefault::Unset { example } }
+}
+
+impl Versioned for Config {
+    const FILE_NAME: &'static str = "config.toml";
+    const CURRENT_VERSION: u32 = 19;
+    const ADDED_SETTINGS: &'static [AddedSetting] = &[
+        // Version 2: margin-aware sizing, pyramiding, maker fees, reversals, break-even
+        // stops, API access control and liquidation estimates.
+        value(2, "simulation.maker_fee_pct", "0.0002"),
+        value(2, "simulation.fee_discount_pct", "0"),
+        value(2, "global_risk.maintenance_margin_rate", "0.004"),
+        value(2, "global_risk.liquidation_warning_pct", "0.05"),
+        value(2, "risk_management.margin_buffer_pct", "0.05"),
+        unset(2, "risk_management.max_position_adds", "2"),
+        value(2, "risk_management.add_spacing_pct", "0"),
+        value(2, "risk_management.reverse_mode", "\"separate\""),
+        unset(2, "risk_management.break_even_trigger_pct", "0.03"),
+        value(2, "risk_management.break_even_buffer_pct", "0"),
+        value(2, "backtest.kline_transform", "\"none\""),
+        unset(2, "server.api_token", "\"change-me\""),
+        value(2, "server.allowed_origins", "[]"),
+        // Version 3: order caps, time-based exits and backtests started through the API.
+        value(3, "risk_management.limit_action", "\"clamp\""),
+        unset(3, "risk_management.max_holding_bars", "48"),
+        value(3, "risk_management.exit_at_session_end", "false"),
+        unset(3, "server.max_concurrent_backtests", "2"),
+        // Version 4: valuing backtests at the mark price.
+        value(4, "backtest.valuation_price", "\"last\""),
+        // Version 5: trading blackouts.
+        value(5, "trading_blackouts.weekly", "[]"),
+        value(5, "trading_blackouts.one_off", "[]"),
+        value(5, "trading_blackouts.buffer_minutes", "0"),
+        unset(5, "trading_blackouts.flatten_before_blackout_minutes", "30"),
+        value(5, "trading_blackouts.apply_in_backtests", "false"),
+        // Version 6: the quote asset accounts are kept in.
+        value(6, "execution.quote_asset", "\"USDT\""),
+        // Version 7: simulated bid-ask spreads.
+        value(7, "simulation.simulated_spread_pct", "0"),
+        // Version 8: refusing entries whose expected move does not cover their costs.
+        unset(8, "risk_management.min_expected_move", "{ cost_multiple = 2, payoff_ratio = 1 }"),
+        // Version 9: scaling the risk of new entries down as drawdown deepens.
+        unset(9, "global_risk.dynamic_leverage", "{ recovery_buffer_pct = 0.01, tiers = [{ drawdown_pct = 0.05, multiplier = 0.5 }, { drawdown_pct = 0.10, multiplier = 0.25 }] }"),
+        // Version 10: daily loss limits.
+        unset(10, "global_risk.daily_loss", "{ rollover_hour_utc = 0, max_daily_loss_abs = 500, symbols = { SOLUSDT = { max_daily_loss_abs = 200 } } }"),
+        // Version 11: the volume filter, which MlStrategy no longer applies on its own.
+        unset(11, "strategies.volume_filter", "{ strategies = [\"MlStrategy\"], lookback = 20, min_relative_volume = 0.8 }"),
+        // Version 12: filling stops at the open of a bar that gapped through them.
+        value(12, "simulation.stop_fill_model", "\"exact\""),
+        // Version 13: the live kline history of dashboards joining mid-session.
+        unset(13, "server.kline_history_bars", "500"),
+        // Version 14: restricting a strategy to long or short positions.
+        value(14, "backtest.direction", "\"both\""),
+        // Version 15: scheduled database backups.
+        unset(15, "backup", "{ schedule = \"0 3 * * *\", directory = \"backups\", keep = 7 }"),
+        // Version 16: what saving an invalid kline does.
+        value(16, "data.invalid_klines", "\"reject\""),
+        // Version 17: take-profits, and resolving bars that reached both exits from 1m klines.
+        unset(17, "risk_management.take_profit_pct", "0.04"),
+        value(17, "simulation.bracket_resolution", "\"assumption\""),
+        // Version 18: the daily rollover of the global risk state, which added no settings.
+        // Version 19: MlStrategy's kline buffer and the bars it waits for before trading.
+        unset(19, "strategies.ml_strategy.buffer_capacity", "500"),
+        unset(19, "strategies.ml_strategy.min_bars_before_trading", "252"),
+    ];
+}
+
+impl Versioned for LiveConfig {
+    const FILE_NAME: &'static str = "live.toml";
+    const CURRENT_VERSION: u32 = 7;
+    const ADDED_SETTINGS: &'static [AddedSetting] = &[
+        // Version 2: portfolio broadcasts, the dead-man's switch, latency reports, the kill
+        // switch, the open position cap, collateral assets and replay mode.
+        value(2, "portfolio_broadcast_secs", "15"),
+        value(2, "dead_mans_switch_enabled", "false"),
+        unset(2, "feed_timeout_secs", "180"),
+        value(2, "flatten_after_secs", "300"),
+        value(2, "latency_report_secs", "60"),
+        unset(2, "kill_switch_file", "\"KILL_SWITCH\""),
+        unset(2, "max_open_positions", "4"),
+        unset(2, "max_orders_per_hour", "20"),
+        value(2, "collateral_assets", "[\"USDT\"]"),
+        value(2, "replay.speed", "1.0"),
+        value(2, "replay.spread_pct", "0.0005"),
+        // Version 3: halting bots whose strategy keeps failing.
+        value(3, "max_strategy_errors", "3"),
+        // Version 4: recording book tickers for order book features.
+        value(4, "record_book_tickers", "false"),
+        // Version 5: handling positions without a recorded context after a restart.
+        value(5, "orphan_position_policy", "\"adopt_with_default_stop\""),
+        // Version 6: the weekly P&L reconciliation against the exchange.
+        unset(6, "pnl_reconciliation", "{ tolerance = 0.01, alert_threshold = 5 }"),
+        // Version 7: feed health broadcasts.
+        value(7, "feed_status_secs", "5"),
+    ];
+}
+
+impl Versioned for OptimizerConfig {
+    const FILE_NAME: &'static str = "optimizer.toml";
+    const CURRENT_VERSION: u32 = 4;
+    const ADDED_SETTINGS: &'static [AddedSetting] = &[
+        // Version 2: bounded concurrency, thinned equity curves and per-run objectives.
+        unset(2, "max_concurrency", "8"),
+        value(2, "equity_curve_resolution", "\"full\""),
+        value(2, "objective", "\"analyzer_score\""),
+        // Version 3: scoring on expectancy.
+        value(3, "analysis.scoring_weights.weight_expectancy", "0"),
+        // Version 4: scoring on capital efficiency.
+        value(4, "analysis.scoring_weights.weight_return_on_margin", "0"),
+        value(4, "analysis.scoring_weights.weight_exposure_adjusted_sharpe", "0"),
+    ];
+}
+
+impl Versioned for PortfolioConfig {
+    const FILE_NAME: &'static str = "portfolio.toml";
+    const CURRENT_VERSION: u32 = 2;
+    const ADDED_SETTINGS: &'static [AddedSetting] = &[
+        // Version 2: limiting how many bots hold a position on one symbol.
+        unset(2, "max_positions_per_symbol", "1"),
+        value(2, "overlap_policy", "\"skip\""),
+    ];
+}
+
+/// How a configuration file's schema version compares to this build's.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VersionReport {
+    /// The file's `config_version`.
+    pub file_version: u32,
+    /// The newest version this build reads.
+    pub current_version: u32,
+    /// The settings added after the file's version that it leaves out, so they take their
+    /// defaults.
+    pub new_settings: Vec<&'static AddedSetting>,
+}
+
+impl VersionReport {
+    pub fn is_outdated(&self) -> bool {
+        self.file_version < self.current_version
+    }
+}
+
+/// Compares the schema version of the TOML in `source` with this build's, failing when it is
+/// newer.
+pub fn check_version<T: Versioned>(source: &str) -> Result<VersionReport, ConfigError> {
+    let document: DocumentMut = source.parse()?;
+    version_report::<T>(&document)
+}
+
+/// `check_version` for the file at `path`, logging a warning for each setting it predates.
+pub fn check_file_version<T: Versioned>(path: &Path) -> Result<VersionReport, ConfigError> {
+    let report = check_version::<T>(&std::fs::read_to_string(path)?)?;
+    if report.is_outdated() {
+        tracing::warn!(
+            "{} is at config_version {}, but this build reads version {}. Run `zenith config migrate {}` to bring it up to date.",
+            path.display(),
+            report.file_version,
+            report.current_version,
+            path.display()
+        );
+        for setting in &report.new_settings {
+            tracing::warn!("{} does not set {}", path.display(), setting);
+        }
+    }
+    Ok(report)
+}
+
+/// Rewrites the TOML in `source` at this build's schema version. The settings added since its
+/// version that it leaves out are written with their defaults, or commented out when they are
+/// unset by default, each under a comment naming the version that added it. Existing values,
+/// comments and layout are kept.
+pub fn migrate<T: Versioned>(source: &str) -> Result<String, ConfigError> {
+    let mut document: DocumentMut = source.parse()?;
+    let report = version_report::<T>(&document)?;
+    if !report.is_outdated() {
+        return Ok(source.to_string());
+    }
+
+    let root = document.as_table_mut();
+    match root.get_mut(VERSION_KEY) {
+        Some(item) => *item = toml_edit::value(i64::from(report.current_version)),
+        None => {
+            // Set apart from the other top-level settings, if there are any.
+            let separator = if root.iter().any(|(_, item)| item.is_value()) { "\n" } else { "" };
+            let key = Key::new(VERSION_KEY).with_leaf_decor(toml_edit::Decor::new(
+                format!("{}# The schema version this file is written for. `zenith config migrate` upgrades older files.\n", separator),
+                " ",
+            ));
+            root.insert_formatted(&key, toml_edit::value(i64::from(report.current_version)));
+        }
+    }
+
+    // Settings are added table by table, in the order they were introduced. Commented-out
+    // lines are written above the next setting added to the same table.
+    let mut sections: Vec<&str> = Vec::new();
+    for setting in &report.new_settings {
+        let section = split_key(setting.key).0;
+        if !sections.contains(&section) {
+            sections.push(section);
+        }
+    }
+    for section in sections {
+        let settings = report.new_settings.iter().filter(|setting| split_key(setting.key).0 == section);
+        let Some(table) = section_table(root, section) else {
+            tracing::warn!("[{}] is not a table; skipping the settings added to it", section);
+            continue;
+        };
+        let mut pending = String::new();
+        for setting in settings {
+            let name = split_key(setting.key).1;
+            match setting.default {
+                SettingDefault::Value(default) => {
+                    let value: Value = default.parse()?;
+                    let prefix = format!("{}# Added in config_version {}.\n", pending, setting.version);
+                    let key = Key::new(name).with_leaf_decor(toml_edit::Decor::new(prefix, " "));
+                    table.insert_formatted(&key, Item::Value(value));
+                    pending.clear();
+                }
+                SettingDefault::Unset { example } => {
+                    pending.push_str(&format!(
+                        "# Added in config_version {}; unset by default.\n# {} = {}\n",
+                        setting.version, name, example
+                    ));
+                }
+            }
+        }
+        if !pending.is_empty() {
+            append_comments(table, section, &pending);
+        }
+    }
+    Ok(document.to_string())
+}
+
+fn version_report<T: Versioned>(document: &DocumentMut) -> Result<VersionReport, ConfigError> {
+    let file_version = match document.get(VERSION_KEY) {
+        None => FIRST_VERSION,
+        Some(item) => item
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= FIRST_VERSION)
+            .ok_or_else(|| ConfigError::validation(format!("{} must be a positive integer", VERSION_KEY)))?,
+    };
+    if file_version > T::CURRENT_VERSION {
+        return Err(ConfigError::UnsupportedVersion {
+            file: T::FILE_NAME.to_string(),
+            version: file_version,
+            supported: T::CURRENT_VERSION,
+        });
+    }
+    let new_settings = T::ADDED_SETTINGS
+        .iter()
+        .filter(|setting| setting.version > file_version && !contains_key(document.as_table(), setting.key))
+        .collect();
+    Ok(VersionReport { file_version, current_version: T::CURRENT_VERSION, new_settings })
+}
+
+/// Splits a dotted key into its table's path (empty for the root) and its own name.
+fn split_key(key: &str) -> (&str, &str) {
+    key.rsplit_once('.').unwrap_or(("", key))
+}
+
+fn contains_key(root: &Table, key: &str) -> bool {
+    let (section, name) = split_key(key);
+    let mut table: &dyn TableLike = root;
+    for part in section.split('.').filter(|part| !part.is_empty()) {
+        match table.get(part).and_then(Item::as_table_like) {
+            Some(inner) => table = inner,
+            None => return false,
+        }
+    }
+    table.contains_key(name)
+}
+
+/// The table at the dotted `section` path, created when missing. `None` when the path runs
+/// into something other than a table.
+fn section_table<'a>(root: &'a mut Table, section: &str) -> Option<&'a mut Table> {
+    let mut table = root;
+    for part in section.split('.').filter(|part| !part.is_empty()) {
+        table = table.entry(part).or_insert_with(toml_edit::table).as_table_mut()?;
+    }
+    Some(table)
+}
+
+/// Writes comment lines into `table` when no setting was added after them: below the table's
+/// header, or above `config_version` for the root table.
+fn append_comments(table: &mut Table, section: &str, comments: &str) {
+    if section.is_empty() {
+        if let Some(mut key) = table.key_mut(VERSION_KEY) {
+            let decor = key.leaf_decor_mut();
+            let prefix = decor.prefix().and_then(|prefix| prefix.as_str()).unwrap_or("").to_string();
+            decor.set_prefix(format!("{}{}", prefix, comments));
+        }
+        return;
+    }
+    let decor = table.decor_mut();
+    let suffix = decor.suffix().and_then(|suffix| suffix.as_str()).unwrap_or("").to_string();
+    decor.set_suffix(format!("{}\n{}", suffix, comments.trim_end_matches('\n')));
+}
@@ -0,0 +1,96 @@
+//! Parsing of `[backup]` schedules and the times they fire at.
+
+use chrono::{DateTime, TimeZone, Utc};
+use configuration::error::ConfigError;
+use configuration::{load_config, BackupSchedule, Config};
+use std::path::PathBuf;
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+/// Loads the repository's config.toml with `section` appended.
+fn load_with(name: &str, section: &str) -> Result<Config, ConfigError> {
+    let patched = format!("{}\n{}\n", std::fs::read_to_string(CONFIG_PATH).unwrap(), section);
+    let path = std::env::temp_dir().join(format!("zenith-backup-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, patched).unwrap();
+    let config = load_config(Some(path.to_str().unwrap()));
+    std::fs::remove_file(&path).unwrap();
+    config
+}
+
+fn schedule(spec: &str) -> BackupSchedule {
+    spec.parse().unwrap_or_else(|e| panic!("{} should parse: {}", spec, e))
+}
+
+fn at(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, month, day, hour, minute, 0).unwrap()
+}
+
+#[test]
+fn a_daily_schedule_fires_once_a_day() {
+    let daily = schedule("0 3 * * *");
+    assert_eq!(daily.next_after(at(9, 8, 1, 30)), Some(at(9, 8, 3, 0)));
+    // Not again at the minute it fired, but the next day.
+    assert_eq!(daily.next_after(at(9, 8, 3, 0)), Some(at(9, 9, 3, 0)));
+    assert_eq!(daily.next_after(Utc.with_ymd_and_hms(2025, 12, 31, 4, 0, 0).unwrap()), Some(Utc.with_ymd_and_hms(2026, 1, 1, 3, 0, 0).unwrap()));
+    assert_eq!(schedule("@daily").next_after(at(9, 8, 1, 30)), Some(at(9, 9, 0, 0)));
+}
+
+#[test]
+fn steps_ranges_and_lists_expand() {
+    let quarter_hourly = schedule("*/15 * * * *");
+    assert_eq!(quarter_hourly.next_after(at(9, 8, 10, 16)), Some(at(9, 8, 10, 30)));
+    assert_eq!(quarter_hourly.next_after(at(9, 8, 10, 50)), Some(at(9, 8, 11, 0)));
+
+    let office_hours = schedule("30 9-17/4 * * 1-5");
+    // Friday 2025-09-12 at 17:30, then Monday at 09:30.
+    assert_eq!(office_hours.next_after(at(9, 12, 14, 0)), Some(at(9, 12, 17, 30)));
+    assert_eq!(office_hours.next_after(at(9, 12, 17, 30)), Some(at(9, 15, 9, 30)));
+
+    let twice_monthly = schedule("0 0 1,15 * *");
+    assert_eq!(twice_monthly.next_after(at(9, 2, 0, 0)), Some(at(9, 15, 0, 0)));
+    assert_eq!(twice_monthly.next_after(at(9, 15, 0, 0)), Some(at(10, 1, 0, 0)));
+}
+
+#[test]
+fn day_fields_match_either_when_both_are_restricted() {
+    // The 13th, or any Friday: Friday 2025-09-12 comes before the 13th.
+    let either = schedule("0 0 13 * 5");
+    assert_eq!(either.next_after(at(9, 10, 0, 0)), Some(at(9, 12, 0, 0)));
+    assert_eq!(either.next_after(at(9, 12, 0, 0)), Some(at(9, 13, 0, 0)));
+    // 7 is Sunday, as 0 is.
+    assert_eq!(schedule("0 0 * * 7").next_after(at(9, 8, 0, 0)), schedule("@weekly").next_after(at(9, 8, 0, 0)));
+}
+
+#[test]
+fn a_leap_day_schedule_waits_for_the_next_leap_year() {
+    assert_eq!(schedule("0 0 29 2 *").next_after(at(3, 1, 0, 0)), Some(Utc.with_ymd_and_hms(2028, 2, 29, 0, 0, 0).unwrap()));
+}
+
+#[test]
+fn invalid_schedules_are_rejected() {
+    for (spec, reason) in [
+        ("0 3 * *", "it has 4 fields, not 5"),
+        ("60 3 * * *", "minute: \"60\" is not from 0 to 59"),
+        ("0 3 0 * *", "day of month: \"0\" is not from 1 to 31"),
+        ("0 5-3 * * *", "hour: the range \"5-3\" is backwards"),
+        ("*/0 * * * *", "minute: \"0\" is not a step"),
+        ("0 0 31 2 *", "it never fires"),
+    ] {
+        match spec.parse::<BackupSchedule>() {
+            Err(ConfigError::ValidationError(message)) => assert!(message.contains(reason), "{}: {}", spec, message),
+            other => panic!("{} should be rejected, got {:?}", spec, other),
+        }
+    }
+}
+
+#[test]
+fn the_backup_section_loads_with_defaults() {
+    assert!(load_config(Some(CONFIG_PATH)).unwrap().backup.is_none());
+
+    let backup = load_with("defaults", "[backup]\nschedule = \"0 3 * * *\"").unwrap().backup.expect("a [backup] section");
+    assert_eq!(backup.schedule, schedule("0 3 * * *"));
+    assert_eq!((backup.directory, backup.keep), (PathBuf::from("backups"), 7));
+
+    assert!(matches!(load_with("bad-schedule", "[backup]\nschedule = \"daily\""), Err(ConfigError::LoadError(_))));
+    assert!(matches!(load_with("keep-none", "[backup]\nschedule = \"@daily\"\nkeep = 0"), Err(ConfigError::ValidationError(_))));
+}
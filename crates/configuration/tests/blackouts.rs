@@ -0,0 +1,151 @@
+//! Parsing, validation and matching of `[trading_blackouts]` windows.
+
+use chrono::{DateTime, Duration, TimeZone, Utc, Weekday};
+use configuration::error::ConfigError;
+use configuration::{load_config, Config, OneOffWindow, TradingBlackouts, WeeklyWindow};
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+/// Loads the repository's config.toml with its `[trading_blackouts]` section replaced by
+/// `blackouts`.
+fn load_with(name: &str, blackouts: &str) -> Result<Config, ConfigError> {
+    let original = std::fs::read_to_string(CONFIG_PATH).unwrap();
+    let (before, _) = original.split_once("[trading_blackouts]\n").expect("config.toml has a [trading_blackouts] section");
+    let patched = format!("{}[trading_blackouts]\n{}\n", before, blackouts);
+
+    let path = std::env::temp_dir().join(format!("zenith-blackouts-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, patched).unwrap();
+    let config = load_config(Some(path.to_str().unwrap()));
+    std::fs::remove_file(&path).unwrap();
+    config
+}
+
+/// A UTC time in the week of Monday 2025-09-08; `day` 0 is that Monday.
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 8 + day, hour, minute, 0).unwrap()
+}
+
+fn window(spec: &str) -> WeeklyWindow {
+    spec.parse().unwrap_or_else(|e| panic!("{} should parse: {}", spec, e))
+}
+
+#[test]
+fn a_weekly_window_parses_its_day_and_times() {
+    let wednesday = window("Wed 02:00-04:00");
+    assert_eq!(wednesday, WeeklyWindow { day: Weekday::Wed, start_minute: 120, length_minutes: 120 });
+    assert_eq!(wednesday.to_string(), "Wed 02:00-04:00");
+
+    // Full day names, any case, and an end of midnight.
+    assert_eq!(window("friday 22:30-24:00"), WeeklyWindow { day: Weekday::Fri, start_minute: 22 * 60 + 30, length_minutes: 90 });
+    assert_eq!(window("Mon 00:00-24:00").length_minutes, 24 * 60);
+}
+
+#[test]
+fn a_weekly_window_covers_its_start_but_not_its_end() {
+    let wednesday = window("Wed 02:00-04:00");
+    let covers = |time: DateTime<Utc>| wednesday.covers(time, Duration::zero());
+
+    assert!(!covers(at(2, 2, 0) - Duration::milliseconds(1)));
+    assert!(covers(at(2, 2, 0)));
+    assert!(covers(at(2, 4, 0) - Duration::milliseconds(1)));
+    assert!(!covers(at(2, 4, 0)));
+    // The same time on another day, or a week later.
+    assert!(!covers(at(1, 3, 0)));
+    assert!(covers(at(9, 3, 0)));
+}
+
+#[test]
+fn a_window_ending_before_its_start_runs_past_midnight() {
+    let overnight = window("Tue 23:00-01:00");
+    assert_eq!(overnight.length_minutes, 120);
+    assert_eq!(overnight.to_string(), "Tue 23:00-01:00");
+    let covers = |time: DateTime<Utc>| overnight.covers(time, Duration::zero());
+    assert!(!covers(at(1, 22, 59)));
+    assert!(covers(at(1, 23, 30)));
+    assert!(covers(at(2, 0, 59)));
+    assert!(!covers(at(2, 1, 0)));
+
+    // Sunday night runs into the next week's Monday.
+    let sunday = window("Sun 23:00-01:00");
+    assert!(sunday.covers(at(6, 23, 0), Duration::zero()));
+    assert!(sunday.covers(at(7, 0, 30), Duration::zero()));
+    assert!(sunday.covers(at(0, 0, 30), Duration::zero()));
+    assert!(!sunday.covers(at(0, 1, 0), Duration::zero()));
+}
+
+#[test]
+fn windows_are_in_utc() {
+    let wednesday = window("Wed 02:00-04:00");
+    // 03:30 in UTC+2 is 01:30 UTC, before the window.
+    let local = DateTime::parse_from_rfc3339("2025-09-10T03:30:00+02:00").unwrap().with_timezone(&Utc);
+    assert!(!wednesday.covers(local, Duration::zero()));
+    let local = DateTime::parse_from_rfc3339("2025-09-10T05:30:00+02:00").unwrap().with_timezone(&Utc);
+    assert!(wednesday.covers(local, Duration::zero()));
+}
+
+#[test]
+fn malformed_windows_are_rejected() {
+    for (spec, reason) in [
+        ("Wed", "missing the times"),
+        ("Someday 02:00-04:00", "unknown day"),
+        ("Wed 02:00", "missing the end time"),
+        ("Wed 2:00-04:00", "the start is not a time"),
+        ("Wed 24:00-01:00", "the start is not a time"),
+        ("Wed 02:00-24:30", "the end is not a time"),
+        ("Wed 02:60-04:00", "the start is not a time"),
+        ("Wed 02:00-02:00", "the window is empty"),
+    ] {
+        let error = spec.parse::<WeeklyWindow>().expect_err(spec).to_string();
+        assert!(error.contains(&format!("\"{}\"", spec)) && error.contains(reason), "{}: {}", spec, error);
+    }
+}
+
+#[test]
+fn signals_are_blocked_from_the_buffer_and_the_flatten_lead() {
+    let mut blackouts = TradingBlackouts { weekly: vec![window("Wed 02:00-04:00")], buffer_minutes: 15, ..Default::default() };
+    assert!(blackouts.blocking_signals_at(at(2, 1, 44)).is_none());
+    assert!(blackouts.blocking_signals_at(at(2, 1, 45)).is_some());
+    assert!(blackouts.flattening_at(at(2, 3, 0)).is_none(), "flattening is off by default");
+
+    blackouts.flatten_before_blackout_minutes = Some(30);
+    assert!(blackouts.flattening_at(at(2, 1, 29)).is_none());
+    assert!(blackouts.flattening_at(at(2, 1, 30)).is_some());
+    assert!(blackouts.blocking_signals_at(at(2, 1, 30)).is_some());
+    assert!(blackouts.blocking_signals_at(at(2, 4, 0)).is_none());
+}
+
+#[test]
+fn the_section_loads_from_toml() {
+    let config = load_with(
+        "valid",
+        "weekly = [\"Wed 02:00-04:00\", \"Sun 23:00-01:00\"]\n\
+         one_off = [{ start = \"2025-09-12T10:00:00Z\", end = \"2025-09-12T11:30:00Z\" }]\n\
+         buffer_minutes = 10\n\
+         flatten_before_blackout_minutes = 20\n\
+         apply_in_backtests = true",
+    )
+    .unwrap();
+    let blackouts = &config.trading_blackouts;
+
+    assert_eq!(blackouts.weekly, [window("Wed 02:00-04:00"), window("Sun 23:00-01:00")]);
+    assert_eq!(blackouts.one_off, [OneOffWindow { start: at(4, 10, 0), end: at(4, 11, 30) }]);
+    assert_eq!((blackouts.buffer_minutes, blackouts.flatten_before_blackout_minutes), (10, Some(20)));
+    assert!(blackouts.apply_in_backtests);
+    assert_eq!(blackouts.window_at(at(4, 11, 0), 0).unwrap().to_string(), "window 2025-09-12 10:00 UTC to 2025-09-12 11:30 UTC");
+    assert_eq!(blackouts.window_at(at(2, 3, 0), 0).unwrap().to_string(), "weekly window Wed 02:00-04:00");
+    assert!(blackouts.window_at(at(4, 11, 30), 0).is_none());
+
+    // The repository's config has no windows.
+    assert!(load_config(Some(CONFIG_PATH)).unwrap().trading_blackouts.is_empty());
+}
+
+#[test]
+fn invalid_sections_fail_to_load() {
+    let error = load_with("bad-spec", "weekly = [\"Wed 02:00-02:00\"]").unwrap_err().to_string();
+    assert!(error.contains("invalid blackout window \"Wed 02:00-02:00\""), "{}", error);
+
+    let error = load_with("backwards", "one_off = [{ start = \"2025-09-12T11:00:00Z\", end = \"2025-09-12T10:00:00Z\" }]")
+        .unwrap_err()
+        .to_string();
+    assert!(error.contains("must end after it starts"), "{}", error);
+}
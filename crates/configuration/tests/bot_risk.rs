@@ -0,0 +1,133 @@
+//! Merging each bot's `[bot.risk]` block over `[risk_management]`, and validating the result.
+
+use configuration::{load_config, load_live_config, load_portfolio_config, validate_bot_risk, validate_portfolio_risk, Config, LiveConfig};
+use rust_decimal_macros::dec;
+use std::path::PathBuf;
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+fn config() -> Config {
+    load_config(Some(CONFIG_PATH)).unwrap()
+}
+
+/// Writes `contents` to a TOML file in the temp directory, named after the test.
+fn write_toml(name: &str, contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("zenith-bot-risk-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+/// A `live.toml` with a BTCUSDT bot whose risk block holds `risk`, and an ETHUSDT bot
+/// without one.
+fn live_config(name: &str, risk: &str) -> LiveConfig {
+    let path = write_toml(name, &format!(
+        r#"
+        live_trading_enabled = false
+        interval = "1m"
+
+        [[bot]]
+        enabled = true
+        symbol = "BTCUSDT"
+        strategy_id = "MACrossover"
+        [bot.risk]
+        {}
+        [bot.params]
+
+        [[bot]]
+        enabled = true
+        symbol = "ETHUSDT"
+        strategy_id = "MACrossover"
+        [bot.params]
+        "#,
+        risk
+    ));
+    let live_config = load_live_config(&path);
+    std::fs::remove_file(path).unwrap();
+    live_config.unwrap()
+}
+
+#[test]
+fn an_override_changes_only_the_settings_it_sets() {
+    let config = config();
+    let live_config = live_config("partial", "stop_loss_pct = 0.005");
+
+    let merged = live_config.bots[0].risk_management(&config).unwrap();
+    assert_eq!(merged.stop_loss_pct, dec!(0.005));
+    assert_eq!(merged.risk_per_trade_pct, config.risk_management.risk_per_trade_pct);
+    assert_eq!(merged.margin_buffer_pct, config.risk_management.margin_buffer_pct);
+    assert_eq!(merged.max_position_adds, config.risk_management.max_position_adds);
+    assert_eq!(merged.limit_action, config.risk_management.limit_action);
+
+    // A bot without a risk block trades by the global section.
+    let global = live_config.bots[1].risk_management(&config).unwrap();
+    assert_eq!(global.stop_loss_pct, config.risk_management.stop_loss_pct);
+    validate_bot_risk(&config, &live_config).unwrap();
+}
+
+#[test]
+fn an_invalid_override_is_rejected() {
+    let error = validate_bot_risk(&config(), &live_config("invalid", "risk_per_trade_pct = 0.5")).unwrap_err().to_string();
+    assert!(error.contains("bots.BTCUSDT.risk: risk_per_trade_pct must be between 0 and 0.1"), "{}", error);
+    let error = validate_bot_risk(&config(), &live_config("take-profit", "take_profit_pct = 0")).unwrap_err().to_string();
+    assert!(error.contains("bots.BTCUSDT.risk: take_profit_pct must be greater than 0"), "{}", error);
+}
+
+#[test]
+fn an_override_only_invalid_with_the_global_settings_is_rejected() {
+    // The buffer is fine on its own, but not below the global break-even trigger.
+    let mut config = config();
+    config.risk_management.break_even_trigger_pct = Some(dec!(0.01));
+    let error = validate_bot_risk(&config, &live_config("buffer", "break_even_buffer_pct = 0.02")).unwrap_err().to_string();
+    assert!(error.contains("bots.BTCUSDT.risk: break_even_buffer_pct must be between 0 and break_even_trigger_pct"), "{}", error);
+    // The bot's own trigger makes it valid again.
+    validate_bot_risk(&config, &live_config("trigger", "break_even_buffer_pct = 0.02\nbreak_even_trigger_pct = 0.04")).unwrap();
+}
+
+#[test]
+fn an_unknown_setting_fails_to_load() {
+    let path = write_toml(
+        "unknown",
+        r#"
+        live_trading_enabled = false
+        interval = "1m"
+
+        [[bot]]
+        enabled = true
+        symbol = "BTCUSDT"
+        strategy_id = "MACrossover"
+        [bot.risk]
+        stop_loss = 0.01
+        [bot.params]
+        "#,
+    );
+    let error = load_live_config(&path).unwrap_err().to_string();
+    std::fs::remove_file(path).unwrap();
+    assert!(error.contains("stop_loss"), "{}", error);
+}
+
+#[test]
+fn portfolio_bots_merge_their_overrides_too() {
+    let path = write_toml(
+        "portfolio",
+        r#"
+        [[bot]]
+        symbol = "BTCUSDT"
+        strategy_id = "MACrossover"
+        [bot.risk]
+        risk_per_trade_pct = 0.0025
+        [bot.params]
+        "#,
+    );
+    let portfolio_config = load_portfolio_config(&path);
+    std::fs::remove_file(path).unwrap();
+    let mut portfolio_config = portfolio_config.unwrap();
+    let config = config();
+
+    let merged = portfolio_config.bots[0].risk_management(&config).unwrap();
+    assert_eq!(merged.risk_per_trade_pct, dec!(0.0025));
+    assert_eq!(merged.stop_loss_pct, config.risk_management.stop_loss_pct);
+
+    portfolio_config.bots[0].risk.as_mut().unwrap().stop_loss_pct = Some(dec!(0.3));
+    let error = validate_portfolio_risk(&config, &portfolio_config).unwrap_err().to_string();
+    assert!(error.contains("bots.BTCUSDT.risk: stop_loss_pct must be between 0 and 0.2"), "{}", error);
+}
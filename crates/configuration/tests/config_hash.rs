@@ -0,0 +1,50 @@
+use configuration::{canonical_hash, config_hash, load_config, Config};
+use rust_decimal_macros::dec;
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+fn hash(config: &Config) -> String {
+    config_hash(&config.backtest, &config.simulation, &config.risk_management)
+}
+
+#[test]
+fn canonical_hash_ignores_key_order() {
+    let a: serde_json::Value = serde_json::from_str(r#"{"a": 1, "b": {"c": [1, {"x": 2, "y": 3}], "d": "e"}}"#).unwrap();
+    let b: serde_json::Value = serde_json::from_str(r#"{"b": {"d": "e", "c": [1, {"y": 3, "x": 2}]}, "a": 1}"#).unwrap();
+    assert_eq!(canonical_hash(&a), canonical_hash(&b));
+
+    // Array order is significant.
+    let c: serde_json::Value = serde_json::from_str(r#"{"a": 1, "b": {"c": [{"x": 2, "y": 3}, 1], "d": "e"}}"#).unwrap();
+    assert_ne!(canonical_hash(&a), canonical_hash(&c));
+}
+
+#[test]
+fn config_hash_ignores_key_order_in_the_file() {
+    let original = std::fs::read_to_string(CONFIG_PATH).unwrap();
+    let fee_line = original.lines().find(|l| l.starts_with("taker_fee_pct")).unwrap();
+    let slippage_line = original.lines().find(|l| l.starts_with("slippage_pct")).unwrap();
+    let reordered = original
+        .replace(fee_line, "__FEE__")
+        .replace(slippage_line, fee_line)
+        .replace("__FEE__", slippage_line);
+    assert_ne!(original, reordered);
+
+    let path = std::env::temp_dir().join(format!("zenith-config-hash-{}.toml", std::process::id()));
+    std::fs::write(&path, reordered).unwrap();
+    let reordered_config = load_config(Some(path.to_str().unwrap()));
+    std::fs::remove_file(&path).unwrap();
+
+    let config = load_config(Some(CONFIG_PATH)).unwrap();
+    assert_eq!(hash(&config), hash(&reordered_config.unwrap()));
+}
+
+#[test]
+fn config_hash_changes_with_a_fee_parameter() {
+    let config = load_config(Some(CONFIG_PATH)).unwrap();
+    let mut changed = config.clone();
+    changed.simulation.taker_fee_pct += dec!(0.0001);
+
+    assert_eq!(hash(&config), hash(&config.clone()));
+    assert_ne!(hash(&config), hash(&changed));
+    assert_eq!(hash(&config).len(), 64);
+}
@@ -0,0 +1,100 @@
+//! Tests for the checks `validate_live_config` runs on a loaded `live.toml`, and for
+//! `validate_bot_intervals`, which resolves each bot's interval against `config.toml`.
+
+use configuration::{load_config, validate_bot_intervals, validate_live_config, Config, LiveBotConfig, LiveConfig, Versioned};
+use core_types::StrategyId;
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+/// The repository's `config.toml`, with `interval` as the default bot interval.
+fn config(interval: &str) -> Config {
+    let mut config = load_config(Some(CONFIG_PATH)).unwrap();
+    config.backtest.interval = interval.to_string();
+    config
+}
+
+fn bot(symbol: &str, interval: Option<&str>, enabled: bool) -> LiveBotConfig {
+    LiveBotConfig {
+        enabled,
+        symbol: symbol.to_string(),
+        strategy_id: StrategyId::MACrossover,
+        interval: interval.map(str::to_string),
+        leverage: Some(5),
+        kline_transform: Default::default(),
+        direction: Default::default(),
+        quote_asset: None,
+        risk: None,
+        params: serde_json::json!({}),
+    }
+}
+
+fn live_config(bots: Vec<LiveBotConfig>) -> LiveConfig {
+    LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: true,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: None,
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots,
+    }
+}
+
+#[test]
+fn a_symbol_may_run_one_enabled_bot_per_interval() {
+    let live = live_config(vec![
+        bot("BTCUSDT", Some("15m"), true),
+        bot("BTCUSDT", Some("1h"), true),
+        bot("BTCUSDT", None, true),
+        // A disabled bot does not run, so it clashes with nothing.
+        bot("BTCUSDT", Some("1h"), false),
+        bot("ETHUSDT", Some("1h"), true),
+    ]);
+    validate_bot_intervals(&config("4h"), &live).unwrap();
+}
+
+#[test]
+fn two_enabled_bots_on_the_same_symbol_and_interval_are_rejected() {
+    let live = live_config(vec![bot("BTCUSDT", Some("1h"), true), bot("BTCUSDT", Some("1h"), true)]);
+    let error = validate_bot_intervals(&config("4h"), &live).unwrap_err().to_string();
+    assert!(error.contains("more than one enabled bot trades BTCUSDT on the 1h interval"), "{}", error);
+
+    let live = live_config(vec![bot("BTCUSDT", None, true), bot("BTCUSDT", None, true)]);
+    let error = validate_bot_intervals(&config("4h"), &live).unwrap_err().to_string();
+    assert!(error.contains("more than one enabled bot trades BTCUSDT on the 4h interval"), "{}", error);
+}
+
+#[test]
+fn a_bot_on_the_default_interval_clashes_with_one_naming_it() {
+    let live = live_config(vec![bot("BTCUSDT", None, true), bot("BTCUSDT", Some("1h"), true)]);
+    let error = validate_bot_intervals(&config("1h"), &live).unwrap_err().to_string();
+    assert!(error.contains("more than one enabled bot trades BTCUSDT on the 1h interval"), "{}", error);
+
+    validate_bot_intervals(&config("4h"), &live).unwrap();
+}
+
+#[test]
+fn unsupported_intervals_are_rejected_with_the_supported_ones() {
+    let config = live_config(vec![bot("BTCUSDT", Some("1h"), true), bot("ETHUSDT", Some("90s"), true)]);
+    let error = validate_live_config(&config).unwrap_err().to_string();
+    assert!(error.contains("bots.ETHUSDT.interval: Unsupported interval \"90s\""), "{}", error);
+    assert!(error.contains("supported intervals: 1m, 5m,"), "{}", error);
+
+    let mut config = live_config(Vec::new());
+    config.interval = "3m".to_string();
+    let error = validate_live_config(&config).unwrap_err().to_string();
+    assert!(error.contains("interval: Unsupported interval \"3m\""), "{}", error);
+}
@@ -0,0 +1,48 @@
+use configuration::{load_config, LimitAction, OrderLimits};
+use rust_decimal_macros::dec;
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+/// Loads the repository's config.toml with `risk_management` appended to its
+/// `[risk_management]` section.
+fn load_with(name: &str, risk_management: &str) -> Result<configuration::Config, configuration::error::ConfigError> {
+    let original = std::fs::read_to_string(CONFIG_PATH).unwrap();
+    let patched = original.replacen("[risk_management]\n", &format!("[risk_management]\n{}\n", risk_management), 1);
+    assert_ne!(original, patched);
+
+    let path = std::env::temp_dir().join(format!("zenith-order-limits-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, patched).unwrap();
+    let config = load_config(Some(path.to_str().unwrap()));
+    std::fs::remove_file(&path).unwrap();
+    config
+}
+
+#[test]
+fn symbol_limits_override_the_global_caps_one_by_one() {
+    let config = load_with(
+        "override",
+        "limit_action = \"reject\"\norder_limits = { max_notional = 10000, max_quantity = 2 }\nsymbol_limits = { BTCUSDT = { max_notional = 5000 } }",
+    )
+    .unwrap();
+    let risk = &config.risk_management;
+
+    assert_eq!(risk.limit_action, LimitAction::Reject);
+    assert_eq!(risk.limits_for("BTCUSDT"), OrderLimits { max_notional: Some(dec!(5000)), max_quantity: Some(dec!(2)) });
+    assert_eq!(risk.limits_for("ETHUSDT"), OrderLimits { max_notional: Some(dec!(10000)), max_quantity: Some(dec!(2)) });
+}
+
+#[test]
+fn limits_are_optional() {
+    let config = load_config(Some(CONFIG_PATH)).unwrap();
+    assert!(config.risk_management.limits_for("BTCUSDT").is_unlimited());
+    assert_eq!(config.risk_management.limit_action, LimitAction::Clamp);
+}
+
+#[test]
+fn non_positive_limits_are_rejected_at_load() {
+    let error = load_with("negative", "symbol_limits = { BTCUSDT = { max_quantity = -0.1 } }").unwrap_err();
+    assert!(error.to_string().contains("symbol_limits.BTCUSDT.max_quantity must be greater than 0"), "{}", error);
+
+    let error = load_with("zero", "order_limits = { max_notional = 0 }").unwrap_err();
+    assert!(error.to_string().contains("order_limits.max_notional must be greater than 0"), "{}", error);
+}
@@ -0,0 +1,72 @@
+//! The canonical JSON form of strategy parameter sets.
+
+use configuration::{normalize_params, parse_decimal};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::json;
+
+#[test]
+fn every_form_of_a_small_decimal_normalizes_to_the_same_string() {
+    for value in [json!(0.0001), json!("0.0001"), json!("0.00010"), json!("1e-4"), json!("1E-4")] {
+        assert_eq!(normalize_params(value.clone()), json!("0.0001"), "{}", value);
+    }
+    for value in [json!(1e-6), json!(0.000001), json!("0.000001"), json!("1e-6"), json!("10e-7")] {
+        assert_eq!(normalize_params(value.clone()), json!("0.000001"), "{}", value);
+    }
+    assert_eq!(parse_decimal("1e-6"), Some(dec!(0.000001)));
+    assert_eq!(parse_decimal("0.0001"), Some(dec!(0.0001)));
+}
+
+#[test]
+fn integral_numbers_become_integers() {
+    for value in [json!(20), json!(20.0), json!("20"), json!("20.000"), json!("2e1")] {
+        assert_eq!(normalize_params(value.clone()), json!(20), "{}", value);
+    }
+    assert_eq!(normalize_params(json!(-0.0)), json!(0));
+    assert_eq!(normalize_params(json!(u64::MAX)), json!(u64::MAX));
+}
+
+#[test]
+fn other_values_are_left_as_they_are() {
+    let params = json!({
+        "ma_type": "ema",
+        "model_path": "models/model.bin",
+        "members": ["MACrossover", "SuperTrend"],
+        "enabled": true,
+        "missing": null,
+        "empty": "",
+        "huge": 1e300,
+    });
+    assert_eq!(normalize_params(params.clone()), params);
+}
+
+#[test]
+fn nested_sets_are_normalized_too() {
+    let params = json!({
+        "min_agreement": 2.0,
+        "member_params": { "SuperTrend": { "atr_multiplier": "3.50", "adx_threshold": 25.0 } },
+        "volume_filter": { "lookback": 20, "min_relative_volume": 1.5 },
+    });
+    assert_eq!(
+        normalize_params(params),
+        json!({
+            "min_agreement": 2,
+            "member_params": { "SuperTrend": { "atr_multiplier": "3.5", "adx_threshold": 25 } },
+            "volume_filter": { "lookback": 20, "min_relative_volume": "1.5" },
+        })
+    );
+}
+
+#[test]
+fn normalized_decimals_read_back_exactly() {
+    // Every scale a Decimal parameter holds, with mantissas that do not fit an f64 exactly.
+    for scale in 0..=18 {
+        for mantissa in [1_i64, 7, 123_456_789, 9_007_199_254_740_993, -42] {
+            let decimal = Decimal::new(mantissa, scale);
+            let normalized = normalize_params(serde_json::to_value(decimal).unwrap());
+            let read: Decimal = serde_json::from_value(normalized.clone()).unwrap();
+            assert_eq!(read, decimal, "{} read back from {}", decimal, normalized);
+            assert_eq!(normalize_params(normalized.clone()), normalized, "{} is not stable", normalized);
+        }
+    }
+}
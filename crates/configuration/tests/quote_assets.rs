@@ -0,0 +1,118 @@
+//! Resolving and validating each bot's quote asset.
+
+use configuration::{is_coin_margined, load_config, settles_in, validate_quote_assets, Config, LiveBotConfig, LiveConfig, QuoteAssets, Versioned};
+use core_types::StrategyId;
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+fn bot(symbol: &str, quote_asset: Option<&str>, enabled: bool) -> LiveBotConfig {
+    LiveBotConfig {
+        enabled,
+        symbol: symbol.to_string(),
+        strategy_id: StrategyId::MACrossover,
+        interval: None,
+        leverage: Some(5),
+        kline_transform: Default::default(),
+        direction: Default::default(),
+        quote_asset: quote_asset.map(str::to_string),
+        risk: None,
+        params: serde_json::json!({}),
+    }
+}
+
+fn live_config(bots: Vec<LiveBotConfig>) -> LiveConfig {
+    LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: true,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: None,
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots,
+    }
+}
+
+fn config(quote_asset: &str) -> Config {
+    let mut config = load_config(Some(CONFIG_PATH)).unwrap();
+    config.execution.quote_asset = quote_asset.to_string();
+    config
+}
+
+#[test]
+fn the_repository_config_quotes_in_usdt() {
+    assert_eq!(load_config(Some(CONFIG_PATH)).unwrap().execution.quote_asset, "USDT");
+}
+
+#[test]
+fn symbols_settle_in_their_quote_asset() {
+    assert!(settles_in("BTCUSDT", "USDT"));
+    assert!(settles_in("ETHUSDC", "USDC"));
+    assert!(settles_in("BTCUSDT_250926", "USDT"));
+
+    assert!(!settles_in("BTCUSDT", "USDC"));
+    // COIN-margined contracts settle in nothing the engine trades.
+    assert!(!settles_in("BTCUSD_PERP", "BTC"));
+    assert!(!settles_in("ETHUSD_250926", "ETH"));
+    assert!(!settles_in("BTCUSD_PERP", "USD"));
+    assert!(!settles_in("USDT", "USDT"));
+    assert!(!settles_in("BTCUSDT", ""));
+
+    assert!(is_coin_margined("BTCUSD_PERP"));
+    assert!(is_coin_margined("ETHUSD_250926"));
+    assert!(!is_coin_margined("BTCUSDT_250926"));
+    assert!(!is_coin_margined("BTCUSDT"));
+}
+
+#[test]
+fn bots_override_the_default_quote_asset() {
+    let live_config = live_config(vec![
+        bot("BTCUSDT", None, true),
+        bot("ETHUSDC", Some("USDC"), true),
+        bot("BTCUSD_PERP", Some("BTC"), false),
+    ]);
+    let quote_assets = QuoteAssets::for_live(&config("USDT"), &live_config);
+
+    assert_eq!(quote_assets.default_asset(), "USDT");
+    assert_eq!(quote_assets.for_symbol("BTCUSDT"), "USDT");
+    assert_eq!(quote_assets.for_symbol("ETHUSDC"), "USDC");
+    // Disabled bots and unknown symbols take the default.
+    assert_eq!(quote_assets.for_symbol("BTCUSD_PERP"), "USDT");
+    assert_eq!(quote_assets.assets(), ["USDT", "USDC"]);
+    validate_quote_assets(&config("USDT"), &live_config).unwrap();
+}
+
+#[test]
+fn a_bot_whose_symbol_does_not_settle_in_its_quote_asset_is_rejected() {
+    let error = validate_quote_assets(&config("USDC"), &live_config(vec![bot("BTCUSDT", None, true)])).unwrap_err().to_string();
+    assert!(error.contains("bot BTCUSDT has quote asset USDC"), "{}", error);
+
+    let error = validate_quote_assets(&config("USDT"), &live_config(vec![bot("BTCUSDT", Some("BTC"), true)])).unwrap_err().to_string();
+    assert!(error.contains("bot BTCUSDT has quote asset BTC"), "{}", error);
+
+    // A disabled bot is not checked.
+    validate_quote_assets(&config("USDC"), &live_config(vec![bot("BTCUSDT", None, false)])).unwrap();
+
+    let error = validate_quote_assets(&config(" "), &live_config(Vec::new())).unwrap_err().to_string();
+    assert!(error.contains("execution.quote_asset must name an asset"), "{}", error);
+}
+
+#[test]
+fn a_bot_on_a_coin_margined_contract_is_rejected_whatever_its_quote_asset() {
+    for quote_asset in [None, Some("BTC"), Some("USD")] {
+        let error = validate_quote_assets(&config("USDT"), &live_config(vec![bot("BTCUSD_PERP", quote_asset, true)])).unwrap_err().to_string();
+        assert!(error.contains("bot BTCUSD_PERP trades a COIN-margined contract"), "{}", error);
+    }
+}
@@ -0,0 +1,151 @@
+use configuration::error::ConfigError;
+use configuration::{check_file_version, check_version, load_config, migrate, Config, LiveConfig, Versioned};
+use toml_edit::{DocumentMut, Item};
+
+const CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+
+/// The workspace `config.toml` as a version 1 file: without `config_version` or any of the
+/// settings added since.
+fn version_one_config() -> String {
+    let mut document: DocumentMut = std::fs::read_to_string(CONFIG_PATH).unwrap().parse().unwrap();
+    document.remove("config_version");
+    for setting in Config::ADDED_SETTINGS {
+        let (section, name) = setting.key.rsplit_once('.').unwrap_or(("", setting.key));
+        let table = if section.is_empty() { Some(document.as_item_mut()) } else { document.get_mut(section) };
+        if let Some(table) = table.and_then(Item::as_table_like_mut) {
+            table.remove(name);
+        }
+    }
+    document.to_string()
+}
+
+fn write_temp(name: &str, contents: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("zenith-versioning-{}-{}.toml", name, std::process::id()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn a_version_one_file_lists_the_settings_added_since() {
+    let path = write_temp("v1", &version_one_config());
+    let report = check_file_version::<Config>(&path);
+    let config = load_config(Some(path.to_str().unwrap()));
+    std::fs::remove_file(&path).unwrap();
+
+    let report = report.unwrap();
+    assert_eq!((report.file_version, report.current_version), (1, 19));
+    let keys: Vec<&str> = report.new_settings.iter().map(|setting| setting.key).collect();
+    assert_eq!(
+        keys,
+        [
+            "simulation.maker_fee_pct",
+            "simulation.fee_discount_pct",
+            "global_risk.maintenance_margin_rate",
+            "global_risk.liquidation_warning_pct",
+            "risk_management.margin_buffer_pct",
+            "risk_management.max_position_adds",
+            "risk_management.add_spacing_pct",
+            "risk_management.reverse_mode",
+            "risk_management.break_even_trigger_pct",
+            "risk_management.break_even_buffer_pct",
+            "backtest.kline_transform",
+            "server.api_token",
+            "server.allowed_origins",
+            "risk_management.limit_action",
+            "risk_management.max_holding_bars",
+            "risk_management.exit_at_session_end",
+            "server.max_concurrent_backtests",
+            "backtest.valuation_price",
+            "trading_blackouts.weekly",
+            "trading_blackouts.one_off",
+            "trading_blackouts.buffer_minutes",
+            "trading_blackouts.flatten_before_blackout_minutes",
+            "trading_blackouts.apply_in_backtests",
+            "execution.quote_asset",
+            "simulation.simulated_spread_pct",
+            "risk_management.min_expected_move",
+            "global_risk.dynamic_leverage",
+            "global_risk.daily_loss",
+            "strategies.volume_filter",
+            "simulation.stop_fill_model",
+            "server.kline_history_bars",
+            "backtest.direction",
+            "backup",
+            "data.invalid_klines",
+            "risk_management.take_profit_pct",
+            "simulation.bracket_resolution",
+            "strategies.ml_strategy.buffer_capacity",
+            "strategies.ml_strategy.min_bars_before_trading",
+        ]
+    );
+    assert_eq!(
+        report.new_settings[0].to_string(),
+        "simulation.maker_fee_pct (added in version 2): defaults to 0.0002"
+    );
+    assert_eq!(
+        report.new_settings[14].to_string(),
+        "risk_management.max_holding_bars (added in version 3): unset by default"
+    );
+
+    // An outdated file still loads, with the new settings at their defaults.
+    assert_eq!(config.unwrap().config_version, 1);
+}
+
+#[test]
+fn a_version_one_live_file_lists_the_open_position_cap() {
+    let report = check_version::<LiveConfig>("").unwrap();
+    let setting = report.new_settings.iter().find(|setting| setting.key == "max_open_positions").expect("listed");
+    assert_eq!(setting.to_string(), "max_open_positions (added in version 2): unset by default");
+}
+
+#[test]
+fn a_migrated_file_loads_the_same_config_at_the_current_version() {
+    let original = version_one_config();
+    let migrated = migrate::<Config>(&original).unwrap();
+    let original_path = write_temp("original", &original);
+    let migrated_path = write_temp("migrated", &migrated);
+    let report = check_file_version::<Config>(&migrated_path);
+    let original_config = load_config(Some(original_path.to_str().unwrap()));
+    let migrated_config = load_config(Some(migrated_path.to_str().unwrap()));
+    std::fs::remove_file(&original_path).unwrap();
+    std::fs::remove_file(&migrated_path).unwrap();
+
+    let report = report.unwrap();
+    assert!(!report.is_outdated());
+    assert!(report.new_settings.is_empty());
+
+    let mut original_config = original_config.unwrap();
+    let migrated_config = migrated_config.unwrap();
+    assert_eq!(migrated_config.config_version, Config::CURRENT_VERSION);
+    original_config.config_version = Config::CURRENT_VERSION;
+    assert_eq!(format!("{:?}", original_config), format!("{:?}", migrated_config));
+
+    // Existing comments stay; settings unset by default are added commented out.
+    assert!(migrated.contains("# If total portfolio equity drops this much from its peak for the session,"));
+    assert!(migrated.contains("# Added in config_version 3.\nexit_at_session_end = false"));
+    assert!(migrated.contains("# max_holding_bars = 48"));
+    assert!(migrated.contains("# max_concurrent_backtests = 2"));
+    assert!(migrated.contains("# min_expected_move = { cost_multiple = 2, payoff_ratio = 1 }"));
+    assert!(migrated.contains("# dynamic_leverage = { recovery_buffer_pct = 0.01, tiers = ["));
+    assert!(migrated.contains("# daily_loss = { rollover_hour_utc = 0, max_daily_loss_abs = 500,"));
+    assert!(migrated.contains("# volume_filter = { strategies = [\"MlStrategy\"], lookback = 20,"));
+
+    // Migrating again changes nothing.
+    assert_eq!(migrate::<Config>(&migrated).unwrap(), migrated);
+}
+
+#[test]
+fn a_file_newer_than_the_build_is_rejected() {
+    let original = std::fs::read_to_string(CONFIG_PATH).unwrap();
+    let newer = original.replace("config_version = 19", "config_version = 20");
+    assert_ne!(original, newer);
+
+    let path = write_temp("newer", &newer);
+    let config = load_config(Some(path.to_str().unwrap()));
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(
+        config,
+        Err(ConfigError::UnsupportedVersion { version: 20, supported: 19, .. })
+    ));
+}
@@ -0,0 +1,117 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A top-of-book quote: the best bid and ask with the quantities resting at them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookTicker {
+    pub timestamp: DateTime<Utc>,
+    pub best_bid_price: Decimal,
+    pub best_bid_qty: Decimal,
+    pub best_ask_price: Decimal,
+    pub best_ask_qty: Decimal,
+}
+
+impl BookTicker {
+    /// The spread as a fraction of the mid price, or None without a positive mid price.
+    pub fn relative_spread(&self) -> Option<f64> {
+        let mid = (self.best_bid_price + self.best_ask_price) / Decimal::TWO;
+        if mid <= Decimal::ZERO {
+            return None;
+        }
+        ((self.best_ask_price - self.best_bid_price) / mid).to_f64()
+    }
+
+    /// `(bid_qty - ask_qty) / (bid_qty + ask_qty)`, from -1 (all asks) to 1 (all bids), or
+    /// None when both sides are empty.
+    pub fn imbalance(&self) -> Option<f64> {
+        let total = self.best_bid_qty + self.best_ask_qty;
+        if total <= Decimal::ZERO {
+            return None;
+        }
+        ((self.best_bid_qty - self.best_ask_qty) / total).to_f64()
+    }
+}
+
+/// The book ticker updates received over a period, reduced to sums so that summaries of
+/// adjacent periods add up. Spreads and imbalances are counted apart, as an update can have
+/// a spread but no quantities.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct BookSummary {
+    pub spread_samples: u64,
+    pub relative_spread_sum: f64,
+    pub imbalance_samples: u64,
+    pub imbalance_sum: f64,
+}
+
+impl BookSummary {
+    pub fn record(&mut self, ticker: &BookTicker) {
+        if let Some(spread) = ticker.relative_spread() {
+            self.spread_samples += 1;
+            self.relative_spread_sum += spread;
+        }
+        if let Some(imbalance) = ticker.imbalance() {
+            self.imbalance_samples += 1;
+            self.imbalance_sum += imbalance;
+        }
+    }
+
+    pub fn merge(&mut self, other: &BookSummary) {
+        self.spread_samples += other.spread_samples;
+        self.relative_spread_sum += other.relative_spread_sum;
+        self.imbalance_samples += other.imbalance_samples;
+        self.imbalance_sum += other.imbalance_sum;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spread_samples == 0 && self.imbalance_samples == 0
+    }
+
+    pub fn mean_relative_spread(&self) -> Option<f64> {
+        (self.spread_samples > 0).then(|| self.relative_spread_sum / self.spread_samples as f64)
+    }
+
+    pub fn mean_imbalance(&self) -> Option<f64> {
+        (self.imbalance_samples > 0).then(|| self.imbalance_sum / self.imbalance_samples as f64)
+    }
+}
+
+/// Book ticker updates summarised per minute over the trailing day, so the summary of any
+/// recent kline of up to a day can be read back. Kline intervals are whole minutes, so a
+/// kline's summary is exactly the sum of the minutes it spans.
+#[derive(Debug, Clone, Default)]
+pub struct BookWindow {
+    minutes: BTreeMap<DateTime<Utc>, BookSummary>,
+}
+
+impl BookWindow {
+    /// How far back updates are kept, counted from the newest one.
+    pub const RETENTION: Duration = Duration::days(1);
+
+    pub fn record(&mut self, ticker: &BookTicker) {
+        let Ok(minute) = ticker.timestamp.duration_trunc(Duration::minutes(1)) else {
+            return;
+        };
+        let newest = self.minutes.last_key_value().map_or(minute, |(newest, _)| minute.max(*newest));
+        let cutoff = newest - Self::RETENTION;
+        if minute < cutoff {
+            return;
+        }
+        self.minutes.entry(minute).or_default().record(ticker);
+        while self.minutes.first_key_value().is_some_and(|(oldest, _)| *oldest < cutoff) {
+            self.minutes.pop_first();
+        }
+    }
+
+    /// The summary of the updates from `open_time` to `close_time`, both included, or None
+    /// if none were recorded.
+    pub fn summary(&self, open_time: DateTime<Utc>, close_time: DateTime<Utc>) -> Option<BookSummary> {
+        let mut summary = BookSummary::default();
+        for minute in self.minutes.range(open_time..=close_time).map(|(_, minute)| minute) {
+            summary.merge(minute);
+        }
+        (!summary.is_empty()).then_some(summary)
+    }
+}
@@ -1,15 +1,86 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub enum StrategyId {
     MACrossover,
     SuperTrend,
     ProbReversion,
     FundingRateArb,
     MlStrategy,
+    Ensemble,
+}
+
+/// A preprocessing step applied to klines before a strategy sees them.
+/// Stop-loss checks and execution always use the untransformed kline.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KlineTransform {
+    /// The strategy sees the raw klines.
+    #[default]
+    None,
+    /// The strategy sees Heikin-Ashi smoothed candles.
+    HeikinAshi,
+}
+
+/// Which sides of the market a bot may hold positions on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TradeDirection {
+    /// Long and short positions.
+    #[default]
+    Both,
+    /// Long positions only; short signals can only close them.
+    LongOnly,
+    /// Short positions only; long signals can only close them.
+    ShortOnly,
+}
+
+impl TradeDirection {
+    /// Whether a position on `side` may be opened or added to.
+    pub fn allows(self, side: OrderSide) -> bool {
+        match self {
+            TradeDirection::Both => true,
+            TradeDirection::LongOnly => side == OrderSide::Buy,
+            TradeDirection::ShortOnly => side == OrderSide::Sell,
+        }
+    }
+}
+
+/// Which price series a kline is built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceType {
+    /// The last traded price.
+    #[default]
+    Last,
+    /// The exchange's mark price, which drives liquidations on perpetual futures.
+    Mark,
+}
+
+impl PriceType {
+    /// Returns the name under which this price type is configured and stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceType::Last => "last",
+            PriceType::Mark => "mark",
+        }
+    }
+}
+
+impl std::str::FromStr for PriceType {
+    type Err = crate::CoreError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "last" => Ok(PriceType::Last),
+            "mark" => Ok(PriceType::Mark),
+            _ => Err(crate::CoreError::InvalidInput("price type".to_string(), format!("'{}'; expected \"last\" or \"mark\"", s))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema), schemars(rename_all = "UPPERCASE"))]
 pub enum OrderSide {
     Buy,
     Sell,
@@ -42,6 +113,14 @@ impl<'de> serde::Deserialize<'de> for OrderSide {
 }
 
 impl OrderSide {
+    /// Returns the name under which this side is serialized and stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderSide::Buy => "BUY",
+            OrderSide::Sell => "SELL",
+        }
+    }
+
     /// Returns the opposite side of the order
     pub fn opposite(&self) -> Self {
         match self {
@@ -57,6 +136,81 @@ pub enum OrderType {
     Limit,
 }
 
+/// What a strategy wants a signal to do to its position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignalIntent {
+    /// Enter or add to a long position.
+    OpenLong,
+    /// Enter or add to a short position.
+    OpenShort,
+    /// Exit the open position without entering a new one.
+    Close,
+    /// End up on the side of the signal's order, closing an opposite position first.
+    Reverse,
+}
+
+/// How long a LIMIT order stays on the book before the exchange cancels it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Good 'til cancelled.
+    Gtc,
+    /// Immediate or cancel: whatever does not fill at once is cancelled.
+    Ioc,
+    /// Post-only: the order is rejected rather than filled as a taker.
+    Gtx,
+}
+
+impl TimeInForce {
+    /// Returns the exchange's code for this time in force.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TimeInForce::Gtc => "GTC",
+            TimeInForce::Ioc => "IOC",
+            TimeInForce::Gtx => "GTX",
+        }
+    }
+}
+
+/// Why a trade's position was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum CloseReason {
+    /// The strategy signalled the exit (or a reversal).
+    #[default]
+    Signal,
+    /// The stop-loss price was touched.
+    StopLoss,
+    /// The take-profit price was touched.
+    TakeProfit,
+    /// The position was held for its maximum number of bars, or until the end of the session.
+    TimeLimit,
+    /// The position was flattened ahead of a trading blackout.
+    Blackout,
+}
+
+impl CloseReason {
+    /// Returns the name under which this reason is stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CloseReason::Signal => "Signal",
+            CloseReason::StopLoss => "StopLoss",
+            CloseReason::TakeProfit => "TakeProfit",
+            CloseReason::TimeLimit => "TimeLimit",
+            CloseReason::Blackout => "Blackout",
+        }
+    }
+
+    /// Parses a stored reason; unknown names are read as `Signal`.
+    pub fn from_db(name: &str) -> Self {
+        match name {
+            "StopLoss" => CloseReason::StopLoss,
+            "TakeProfit" => CloseReason::TakeProfit,
+            "TimeLimit" => CloseReason::TimeLimit,
+            "Blackout" => CloseReason::Blackout,
+            _ => CloseReason::Signal,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum PositionSide {
     Long,
@@ -71,4 +225,32 @@ impl PositionSide {
             OrderSide::Sell => PositionSide::Short,
         }
     }
-}
\ No newline at end of file
+}
+
+/// The stages a single trading decision passes through, from signal to portfolio update.
+/// Each stage is recorded in the decision audit trail under the signal's `decision_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DecisionStage {
+    Signal,
+    RiskApproved,
+    RiskRejected,
+    OrderSubmitted,
+    Executed,
+    ExecutionFailed,
+    PortfolioUpdated,
+}
+
+impl DecisionStage {
+    /// Returns the name under which this stage is stored in the database.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DecisionStage::Signal => "Signal",
+            DecisionStage::RiskApproved => "RiskApproved",
+            DecisionStage::RiskRejected => "RiskRejected",
+            DecisionStage::OrderSubmitted => "OrderSubmitted",
+            DecisionStage::Executed => "Executed",
+            DecisionStage::ExecutionFailed => "ExecutionFailed",
+            DecisionStage::PortfolioUpdated => "PortfolioUpdated",
+        }
+    }
+}
@@ -7,4 +7,7 @@ pub enum CoreError {
 
     #[error("Calculation error: {0}")]
     Calculation(String),
+
+    #[error("Unsupported interval {0:?}; supported intervals: {}", crate::Interval::supported())]
+    UnsupportedInterval(String),
 }
\ No newline at end of file
@@ -0,0 +1,127 @@
+use crate::CoreError;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// A kline interval zenith trades and analyses, written as Binance writes it ("1m", "4h",
+/// "1d"). Only fixed-length intervals are supported, so every bar has an exact duration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Interval {
+    M1,
+    M5,
+    M15,
+    M30,
+    H1,
+    H2,
+    H4,
+    H6,
+    H8,
+    H12,
+    D1,
+    W1,
+}
+
+/// A calendar span to count an interval's bars over. A year is 365 days, as crypto markets
+/// trade every day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+    Year,
+}
+
+impl Period {
+    /// The span's length.
+    pub fn duration(self) -> Duration {
+        const DAY: u64 = 24 * 60 * 60;
+        Duration::from_secs(match self {
+            Period::Day => DAY,
+            Period::Week => 7 * DAY,
+            Period::Year => 365 * DAY,
+        })
+    }
+}
+
+impl Interval {
+    /// Every supported interval, shortest first.
+    pub const ALL: [Interval; 12] = [
+        Interval::M1,
+        Interval::M5,
+        Interval::M15,
+        Interval::M30,
+        Interval::H1,
+        Interval::H2,
+        Interval::H4,
+        Interval::H6,
+        Interval::H8,
+        Interval::H12,
+        Interval::D1,
+        Interval::W1,
+    ];
+
+    /// The canonical form, as used in configuration files and by the exchange.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Interval::M1 => "1m",
+            Interval::M5 => "5m",
+            Interval::M15 => "15m",
+            Interval::M30 => "30m",
+            Interval::H1 => "1h",
+            Interval::H2 => "2h",
+            Interval::H4 => "4h",
+            Interval::H6 => "6h",
+            Interval::H8 => "8h",
+            Interval::H12 => "12h",
+            Interval::D1 => "1d",
+            Interval::W1 => "1w",
+        }
+    }
+
+    /// The length of one bar.
+    pub fn duration(self) -> Duration {
+        const MINUTE: u64 = 60;
+        const HOUR: u64 = 60 * MINUTE;
+        Duration::from_secs(match self {
+            Interval::M1 => MINUTE,
+            Interval::M5 => 5 * MINUTE,
+            Interval::M15 => 15 * MINUTE,
+            Interval::M30 => 30 * MINUTE,
+            Interval::H1 => HOUR,
+            Interval::H2 => 2 * HOUR,
+            Interval::H4 => 4 * HOUR,
+            Interval::H6 => 6 * HOUR,
+            Interval::H8 => 8 * HOUR,
+            Interval::H12 => 12 * HOUR,
+            Interval::D1 => 24 * HOUR,
+            Interval::W1 => 7 * 24 * HOUR,
+        })
+    }
+
+    /// How many bars fit in `period`; fractional when a bar is longer than the period.
+    pub fn bars_per(self, period: Period) -> f64 {
+        period.duration().as_secs_f64() / self.duration().as_secs_f64()
+    }
+
+    /// The supported intervals, comma-separated, for error messages.
+    pub fn supported() -> String {
+        Self::ALL.map(Interval::as_str).join(", ")
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Interval {
+    type Err = CoreError;
+
+    /// Parses the canonical form only: "1h" is an interval, "60m" and "1H" are not.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|interval| interval.as_str() == s)
+            .ok_or_else(|| CoreError::UnsupportedInterval(s.to_string()))
+    }
+}
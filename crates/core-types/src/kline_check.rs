@@ -0,0 +1,120 @@
+//! Integrity checks of klines as they are ingested.
+//!
+//! A bad fetch, or a WebSocket payload parsed before its bar closed, can produce bars no
+//! exchange would print: a high below the close, a zero price, a close time before the open.
+//! Stored, they silently skew indicators and stop-loss triggers, so every ingest path checks
+//! its klines with `Kline::validate` first.
+
+use crate::interval::Interval;
+use crate::structs::Kline;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A way in which a kline cannot be a real bar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KlineViolation {
+    /// The high is below the open or the close.
+    HighBelowBody { high: Decimal, body_top: Decimal },
+    /// The low is above the open or the close.
+    LowAboveBody { low: Decimal, body_bottom: Decimal },
+    /// A price that is zero or negative.
+    NonPositivePrice { field: &'static str, price: Decimal },
+    NegativeVolume(Decimal),
+    /// The close time is not after the open time.
+    CloseNotAfterOpen,
+    /// The interval is not one klines are stored at.
+    UnknownInterval(String),
+    /// The bar does not span its interval: its close time is not one millisecond short of
+    /// the next bar's open.
+    SpanMismatch { interval: Interval, span_ms: i64 },
+}
+
+impl fmt::Display for KlineViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KlineViolation::HighBelowBody { high, body_top } => write!(f, "the high {} is below the body top {}", high, body_top),
+            KlineViolation::LowAboveBody { low, body_bottom } => write!(f, "the low {} is above the body bottom {}", low, body_bottom),
+            KlineViolation::NonPositivePrice { field, price } => write!(f, "the {} {} is not positive", field, price),
+            KlineViolation::NegativeVolume(volume) => write!(f, "the volume {} is negative", volume),
+            KlineViolation::CloseNotAfterOpen => f.write_str("it does not close after it opens"),
+            KlineViolation::UnknownInterval(interval) => write!(f, "its interval {:?} is not supported", interval),
+            KlineViolation::SpanMismatch { interval, span_ms } => {
+                write!(f, "it spans {} ms, not the {} ms of a {} bar", span_ms + 1, interval.duration().as_millis(), interval)
+            }
+        }
+    }
+}
+
+/// A kline that failed `Kline::validate`, with everything wrong with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidKline {
+    pub open_time: DateTime<Utc>,
+    pub interval: String,
+    pub violations: Vec<KlineViolation>,
+}
+
+impl fmt::Display for InvalidKline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let violations: Vec<String> = self.violations.iter().map(ToString::to_string).collect();
+        write!(f, "the {} kline opening at {} is invalid: {}", self.interval, self.open_time, violations.join("; "))
+    }
+}
+
+impl std::error::Error for InvalidKline {}
+
+impl Kline {
+    /// Checks that the kline could be a real bar: its high is at least its open and close and
+    /// its low at most both, every price is positive, its volume is not negative, and it
+    /// closes one millisecond before the next bar of its interval opens.
+    pub fn validate(&self) -> Result<(), InvalidKline> {
+        let mut violations = Vec::new();
+        let body_top = self.open.max(self.close);
+        let body_bottom = self.open.min(self.close);
+        if self.high < body_top {
+            violations.push(KlineViolation::HighBelowBody { high: self.high, body_top });
+        }
+        if self.low > body_bottom {
+            violations.push(KlineViolation::LowAboveBody { low: self.low, body_bottom });
+        }
+        for (field, price) in [("open", self.open), ("high", self.high), ("low", self.low), ("close", self.close)] {
+            if price <= Decimal::ZERO {
+                violations.push(KlineViolation::NonPositivePrice { field, price });
+            }
+        }
+        if self.volume < Decimal::ZERO {
+            violations.push(KlineViolation::NegativeVolume(self.volume));
+        }
+
+        let span_ms = (self.close_time - self.open_time).num_milliseconds();
+        if span_ms <= 0 {
+            violations.push(KlineViolation::CloseNotAfterOpen);
+        }
+        match self.interval.parse::<Interval>() {
+            Ok(interval) => {
+                if span_ms > 0 && span_ms + 1 != interval.duration().as_millis() as i64 {
+                    violations.push(KlineViolation::SpanMismatch { interval, span_ms });
+                }
+            }
+            Err(_) => violations.push(KlineViolation::UnknownInterval(self.interval.clone())),
+        }
+
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(InvalidKline { open_time: self.open_time, interval: self.interval.clone(), violations })
+        }
+    }
+}
+
+/// What saving a kline that fails validation does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InvalidKlinePolicy {
+    /// Refuse it, failing the save.
+    #[default]
+    Reject,
+    /// Save it marked as suspect, for `data-check --validate` to report.
+    Flag,
+}
@@ -1,8 +1,18 @@
+pub mod book;
 pub mod enums;
 pub mod error;
+pub mod interval;
+pub mod kline_check;
+pub mod portfolio_event;
 pub mod structs;
+pub mod symbols;
 
 // Re-export the core types to provide a clean public API.
-pub use enums::{OrderSide, OrderType, StrategyId};
+pub use book::{BookSummary, BookTicker, BookWindow};
+pub use enums::{CloseReason, DecisionStage, KlineTransform, OrderSide, OrderType, PriceType, SignalIntent, StrategyId, TimeInForce, TradeDirection};
 pub use error::CoreError;
-pub use structs::{Execution, Kline, OrderRequest, Position, Signal, Trade};
\ No newline at end of file
+pub use interval::{Interval, Period};
+pub use kline_check::{InvalidKline, InvalidKlinePolicy, KlineViolation};
+pub use portfolio_event::{Correction, CorrectionSource, Holding, PortfolioEvent, RecordedPortfolioEvent};
+pub use structs::{Execution, Kline, MarketContext, OrderPlacement, OrderRequest, Position, PositionFill, Signal, Trade};
+pub use symbols::{check_symbols, suggest_symbol, UnknownSymbol, UnknownSymbols};
\ No newline at end of file
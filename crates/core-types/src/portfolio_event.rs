@@ -0,0 +1,189 @@
+//! The events that change a portfolio's state.
+//!
+//! Every change to a portfolio's cash, balances and positions is one of these events, applied
+//! in order. Recording them makes each change explainable after the fact: the reconciler
+//! overwriting a position with the exchange's view is a `ReconcilerCorrection` holding the
+//! old and new values, rather than a silent rewrite. Replaying the same events from the same
+//! starting capital rebuilds the same portfolio.
+
+use crate::enums::OrderSide;
+use crate::structs::{Execution, Position};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use uuid::Uuid;
+
+/// A change to a portfolio's state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PortfolioEvent {
+    /// A fill was applied to cash and to its symbol's position. A position it opened, or the
+    /// remainder of one it reversed, gets `opened_position_id`.
+    ExecutionApplied { execution: Execution, opened_position_id: Uuid },
+    /// A funding payment was received (positive) or paid (negative) for a symbol's position.
+    FundingApplied { symbol: String, amount: Decimal, at: DateTime<Utc> },
+    /// The portfolio was brought in line with the exchange's view of the account.
+    ReconcilerCorrection { correction: Correction, source: CorrectionSource, at: DateTime<Utc> },
+    /// An operator changed the portfolio by hand.
+    ManualAdjustment { correction: Correction, reason: String, at: DateTime<Utc> },
+}
+
+impl PortfolioEvent {
+    /// The event's type, as it is tagged when serialized.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PortfolioEvent::ExecutionApplied { .. } => "execution_applied",
+            PortfolioEvent::FundingApplied { .. } => "funding_applied",
+            PortfolioEvent::ReconcilerCorrection { .. } => "reconciler_correction",
+            PortfolioEvent::ManualAdjustment { .. } => "manual_adjustment",
+        }
+    }
+
+    /// The symbol whose position the event concerns, if any.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            PortfolioEvent::ExecutionApplied { execution, .. } => Some(&execution.symbol),
+            PortfolioEvent::FundingApplied { symbol, .. } => Some(symbol),
+            PortfolioEvent::ReconcilerCorrection { correction, .. } | PortfolioEvent::ManualAdjustment { correction, .. } => correction.symbol(),
+        }
+    }
+
+    /// When the event happened; for an execution, when it filled.
+    pub fn at(&self) -> DateTime<Utc> {
+        match self {
+            PortfolioEvent::ExecutionApplied { execution, .. } => execution.timestamp,
+            PortfolioEvent::FundingApplied { at, .. } | PortfolioEvent::ReconcilerCorrection { at, .. } | PortfolioEvent::ManualAdjustment { at, .. } => *at,
+        }
+    }
+}
+
+impl fmt::Display for PortfolioEvent {
+    /// Describes the event for an operator, e.g. "reconciler changed BTCUSDT quantity from
+    /// 0.010 to 0.012 at 2025-09-08 12:03:00 UTC".
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = self.at().format("%Y-%m-%d %H:%M:%S UTC");
+        match self {
+            PortfolioEvent::ExecutionApplied { execution, .. } => {
+                let verb = match execution.side {
+                    OrderSide::Buy => "bought",
+                    OrderSide::Sell => "sold",
+                };
+                write!(f, "{} {} {} at {} at {}", verb, execution.quantity, execution.symbol, execution.price, at)
+            }
+            PortfolioEvent::FundingApplied { symbol, amount, .. } => write!(f, "funding of {} on {} at {}", amount, symbol, at),
+            PortfolioEvent::ReconcilerCorrection { correction, source, .. } => write!(f, "{} changed {} at {}", source, correction, at),
+            PortfolioEvent::ManualAdjustment { correction, reason, .. } => write!(f, "manual adjustment changed {} at {}: {}", correction, at, reason),
+        }
+    }
+}
+
+/// A portfolio field set to a new value, with the value it replaced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum Correction {
+    /// The cash, in the quote asset.
+    Cash { old: Decimal, new: Decimal },
+    /// A collateral balance, in the asset's own units. Zero when the asset is not held.
+    Balance { asset: String, old: Decimal, new: Decimal },
+    /// A symbol's position; `None` when there is none.
+    Position { symbol: String, old: Option<Holding>, new: Option<Holding> },
+}
+
+impl Correction {
+    /// The symbol whose position is corrected, if it is a position.
+    pub fn symbol(&self) -> Option<&str> {
+        match self {
+            Correction::Position { symbol, .. } => Some(symbol),
+            Correction::Cash { .. } | Correction::Balance { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for Correction {
+    /// Names the field and its old and new values. For a position that stays on its side,
+    /// only the parts that changed are named.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Correction::Cash { old, new } => write!(f, "cash from {} to {}", old, new),
+            Correction::Balance { asset, old, new } => write!(f, "{} balance from {} to {}", asset, old, new),
+            Correction::Position { symbol, old: Some(old), new: Some(new) } if old.side == new.side => {
+                let mut changes = Vec::new();
+                if old.quantity != new.quantity {
+                    changes.push(format!("quantity from {} to {}", old.quantity, new.quantity));
+                }
+                if old.entry_price != new.entry_price {
+                    changes.push(format!("entry price from {} to {}", old.entry_price, new.entry_price));
+                }
+                if old.position_id != new.position_id {
+                    changes.push(format!("position ID from {} to {}", old.position_id, new.position_id));
+                }
+                if changes.is_empty() {
+                    write!(f, "nothing on {}", symbol)
+                } else {
+                    write!(f, "{} {}", symbol, changes.join(" and "))
+                }
+            }
+            Correction::Position { symbol, old, new } => {
+                let describe = |holding: &Option<Holding>| holding.as_ref().map_or_else(|| "flat".to_string(), Holding::to_string);
+                write!(f, "{} from {} to {}", symbol, describe(old), describe(new))
+            }
+        }
+    }
+}
+
+/// The recorded parts of a position: what it is, rather than how it is valued.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+    pub position_id: Uuid,
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    pub entry_price: Decimal,
+}
+
+impl Holding {
+    /// The holding of `position`.
+    pub fn of(position: &Position) -> Self {
+        Self { position_id: position.position_id, side: position.side, quantity: position.quantity, entry_price: position.entry_price }
+    }
+}
+
+impl fmt::Display for Holding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let side = match self.side {
+            OrderSide::Buy => "long",
+            OrderSide::Sell => "short",
+        };
+        write!(f, "{} {} @ {}", side, self.quantity, self.entry_price)
+    }
+}
+
+/// What brought the portfolio in line with the exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionSource {
+    /// The sync from the exchange when the engine starts.
+    StartupSync,
+    /// The periodic reconciliation against the exchange.
+    Reconciler,
+    /// Relinking a position to the context recorded before a restart.
+    ContextRestore,
+}
+
+impl fmt::Display for CorrectionSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CorrectionSource::StartupSync => "startup sync",
+            CorrectionSource::Reconciler => "reconciler",
+            CorrectionSource::ContextRestore => "context restore",
+        })
+    }
+}
+
+/// A portfolio event with its place in the order the portfolio applied its events.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedPortfolioEvent {
+    /// Counts up from 1 for each event the portfolio applied.
+    pub sequence: u64,
+    pub event: PortfolioEvent,
+}
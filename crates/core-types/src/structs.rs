@@ -1,4 +1,5 @@
-use crate::enums::{OrderSide, OrderType, PositionSide};
+use crate::book::BookSummary;
+use crate::enums::{CloseReason, OrderSide, OrderType, PositionSide, SignalIntent, TimeInForce};
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
@@ -6,6 +7,7 @@ use uuid::Uuid;
 
 /// Represents a single candlestick bar (K-line).
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[sqlx(type_name = "klines")]
 pub struct Kline {
     pub open_time: DateTime<Utc>,
@@ -32,10 +34,21 @@ pub struct OrderRequest {
     pub price: Option<Decimal>,
     /// Position side for hedge mode. None for one-way mode.
     pub position_side: Option<PositionSide>,
+    /// For LIMIT orders, how long the order rests on the book. None means good 'til cancelled.
+    #[serde(default)]
+    pub time_in_force: Option<TimeInForce>,
+    /// True if the order may only close or reduce an existing position, never open one.
+    #[serde(default)]
+    pub reduce_only: bool,
+    /// The decision this order belongs to, copied from the originating `Signal`.
+    /// None for orders that were not produced by a strategy signal.
+    #[serde(default)]
+    pub decision_id: Option<Uuid>,
 }
 
 /// Represents a confirmed trade execution from the exchange.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Execution {
     pub execution_id: Uuid,
     pub client_order_id: Uuid,
@@ -46,6 +59,106 @@ pub struct Execution {
     pub fee: Decimal,
     pub fee_asset: String,
     pub timestamp: DateTime<Utc>,
+    /// The decision that produced this execution, copied from the `OrderRequest`.
+    #[serde(default)]
+    pub decision_id: Option<Uuid>,
+    /// Whether the fill rested on the book (maker) rather than taking liquidity (taker),
+    /// which decides the fee rate it was charged.
+    #[serde(default)]
+    pub is_maker: bool,
+    /// What crossing the spread cost this fill: the distance from the mid price to the
+    /// quote it filled against, times its quantity. Included in its price; zero for fills
+    /// whose spread is not known.
+    #[serde(default)]
+    pub spread_cost: Decimal,
+    /// The book when the order was placed, for fills of live limit orders; `None` for
+    /// everything else.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub placement: Option<OrderPlacement>,
+}
+
+impl Execution {
+    /// Splits a fill that closed a position and opened the opposite one into its closing
+    /// part, of `closed_quantity`, and its opening part. The fee and spread cost are shared
+    /// pro rata; the opening part gets an execution ID derived from this one.
+    pub fn split_at(&self, closed_quantity: Decimal) -> (Execution, Execution) {
+        let share = |total: Decimal| if self.quantity.is_zero() { total } else { total * closed_quantity / self.quantity };
+        let mut closing = self.clone();
+        closing.quantity = closed_quantity;
+        closing.fee = share(self.fee);
+        closing.spread_cost = share(self.spread_cost);
+
+        let mut opening = self.clone();
+        opening.execution_id = Uuid::new_v5(&self.execution_id, b"opening");
+        opening.quantity = self.quantity - closed_quantity;
+        opening.fee = self.fee - closing.fee;
+        opening.spread_cost = self.spread_cost - closing.spread_cost;
+        (closing, opening)
+    }
+}
+
+/// Where a limit order was placed against the book, and, once the exchange confirms its fill,
+/// what it filled at and saved compared with crossing the spread as a market order would have.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct OrderPlacement {
+    pub best_bid: Decimal,
+    pub best_ask: Decimal,
+    pub limit_price: Decimal,
+    pub placed_at: DateTime<Utc>,
+    /// The average price of the confirmed fills. `None` until the fill is confirmed.
+    #[serde(default)]
+    pub fill_price: Option<Decimal>,
+    /// Whether every confirmed fill was a maker fill. `None` until the fill is confirmed.
+    #[serde(default)]
+    pub is_maker: Option<bool>,
+    /// When the last confirmed fill happened. `None` until the fill is confirmed.
+    #[serde(default)]
+    pub filled_at: Option<DateTime<Utc>>,
+    /// What the fill saved, in basis points of the mid price at placement: positive when it
+    /// filled better than the quote a market order would have taken (the ask for a buy, the
+    /// bid for a sell). `None` until the fill is confirmed.
+    pub price_improvement_bps: Option<Decimal>,
+}
+
+impl OrderPlacement {
+    /// A limit order placed at `limit_price` while the book was `best_bid`/`best_ask`, not
+    /// yet filled.
+    pub fn new(best_bid: Decimal, best_ask: Decimal, limit_price: Decimal, placed_at: DateTime<Utc>) -> Self {
+        Self { best_bid, best_ask, limit_price, placed_at, fill_price: None, is_maker: None, filled_at: None, price_improvement_bps: None }
+    }
+
+    pub fn mid_price(&self) -> Decimal {
+        (self.best_bid + self.best_ask) / Decimal::TWO
+    }
+
+    /// Whether the exchange has confirmed the order's fill.
+    pub fn is_confirmed(&self) -> bool {
+        self.fill_price.is_some()
+    }
+
+    /// What a `side` fill at `fill_price` saved against crossing the spread, in basis points
+    /// of the mid price, or `None` without a positive mid price.
+    pub fn improvement_bps(&self, side: OrderSide, fill_price: Decimal) -> Option<Decimal> {
+        let mid_price = self.mid_price();
+        if mid_price <= Decimal::ZERO {
+            return None;
+        }
+        let saved = match side {
+            OrderSide::Buy => self.best_ask - fill_price,
+            OrderSide::Sell => fill_price - self.best_bid,
+        };
+        Some(saved / mid_price * Decimal::from(10_000))
+    }
+
+    /// Records the fill the exchange confirmed: a `side` fill at `fill_price`, all of it as a
+    /// maker if `is_maker`, completed at `filled_at`. Replaces any earlier confirmation.
+    pub fn record_fill(&mut self, side: OrderSide, fill_price: Decimal, is_maker: bool, filled_at: DateTime<Utc>) {
+        self.fill_price = Some(fill_price);
+        self.is_maker = Some(is_maker);
+        self.filled_at = Some(filled_at);
+        self.price_improvement_bps = self.improvement_bps(side, fill_price);
+    }
 }
 
 /// A higher-level construct representing a complete, self-contained trade (e.g., one entry and one exit).
@@ -56,10 +169,38 @@ pub struct Trade {
     pub symbol: String,
     pub entry_execution: Execution,
     pub exit_execution: Execution,
+    /// Why the position was closed. Trades serialized before it was recorded read as `Signal`.
+    #[serde(default)]
+    pub close_reason: CloseReason,
+    /// What the trade stood to lose when it was entered: the distance from the entry price to
+    /// the stop-loss, times the quantity. None when no stop was known at entry, including for
+    /// trades recorded before it was tracked.
+    #[serde(default)]
+    pub initial_risk: Option<Decimal>,
+}
+
+/// The part of an execution that changed one position, as reported by the portfolio that
+/// applied it. A netted reversal yields two: the close of the old position and the opening
+/// of the new one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionFill {
+    pub position_id: Uuid,
+    /// The side of the position, not of the execution: `Buy` for a long.
+    pub position_side: OrderSide,
+    /// True when the fill opened or added to the position, false when it reduced or closed it.
+    pub is_entry: bool,
+    pub quantity: Decimal,
+    /// This fill's share of the execution's fee.
+    pub fee: Decimal,
+    /// The gross P&L the fill locked in against the average entry price. Zero for entries.
+    pub realized_pnl: Decimal,
+    /// The position's quantity after the fill; zero once it is closed.
+    pub position_quantity: Decimal,
 }
 
 /// Represents the current state of an open position for a single asset.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 pub struct Position {
     pub position_id: Uuid,
     pub symbol: String,
@@ -68,15 +209,60 @@ pub struct Position {
     pub entry_price: Decimal,
     pub unrealized_pnl: Decimal,
     pub last_updated: DateTime<Utc>,
+    /// The number of times this position has been added to since it was opened.
+    #[serde(default)]
+    pub adds: u32,
+    /// The fill price of the most recent entry: the opening fill, or the latest add.
+    #[serde(default)]
+    pub last_entry_price: Decimal,
+    /// An estimate of the mark price at which the exchange would liquidate this position,
+    /// from an isolated-margin approximation. Only the live engine fills it in.
+    #[serde(default)]
+    pub estimated_liquidation_price: Option<Decimal>,
 }
 
 /// Represents a trading signal generated by a strategy. It includes the desired order and metadata.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Signal {
     pub signal_id: Uuid,
+    /// A correlation ID that follows this signal through risk, order placement, and execution.
+    pub decision_id: Uuid,
     pub timestamp: DateTime<Utc>,
     pub order_request: OrderRequest,
     /// A score from 0.0 to 1.0 indicating the strategy's confidence in this signal.
     pub confidence: Decimal,
+    /// What the signal should do to the position. None for strategies that predate intents,
+    /// whose intent the risk manager infers from the order side.
+    #[serde(default)]
+    pub intent: Option<SignalIntent>,
+}
+
+impl Signal {
+    /// The side this signal wants a position on, or None if it only closes.
+    pub fn target_side(&self) -> Option<OrderSide> {
+        match self.intent {
+            Some(SignalIntent::OpenLong) => Some(OrderSide::Buy),
+            Some(SignalIntent::OpenShort) => Some(OrderSide::Sell),
+            Some(SignalIntent::Close) => None,
+            Some(SignalIntent::Reverse) | None => Some(self.order_request.side),
+        }
+    }
 }
 
+
+/// Real-time market context beyond the kline, supplied by the live engine to strategies
+/// that opt in via `Strategy::evaluate_with_context`. Fields are `None` until the
+/// corresponding stream has delivered data, and always `None` in backtests.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MarketContext {
+    pub mark_price: Option<Decimal>,
+    /// The index (spot) price the mark price is anchored to.
+    pub index_price: Option<Decimal>,
+    /// The funding rate that will be charged at `next_funding_time` (e.g., 0.0001 for 0.01%).
+    pub funding_rate: Option<Decimal>,
+    pub next_funding_time: Option<DateTime<Utc>>,
+    pub best_bid: Option<Decimal>,
+    pub best_ask: Option<Decimal>,
+    /// The book ticker updates received while the kline being evaluated was open.
+    pub book: Option<BookSummary>,
+}
@@ -0,0 +1,82 @@
+//! Checks configured symbols against the ones the exchange (or the kline store) knows, so a
+//! typo like "BTCUSD" fails before anything runs, with the closest known symbol suggested.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use thiserror::Error;
+
+/// The largest edit distance at which a known symbol is still suggested for an unknown one.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// A symbol missing from the known ones, with the closest known symbol, if any is close.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSymbol {
+    pub symbol: String,
+    pub suggestion: Option<String>,
+}
+
+impl fmt::Display for UnknownSymbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "symbol '{}' not found", self.symbol)?;
+        if let Some(suggestion) = &self.suggestion {
+            write!(f, "; did you mean {}?", suggestion)?;
+        }
+        Ok(())
+    }
+}
+
+/// The symbols `check_symbols` did not find, in the order they were given.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSymbols(pub Vec<UnknownSymbol>);
+
+impl fmt::Display for UnknownSymbols {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let messages: Vec<String> = self.0.iter().map(UnknownSymbol::to_string).collect();
+        f.write_str(&messages.join("\n"))
+    }
+}
+
+/// Checks that every one of `symbols` is in `known`, reporting each missing symbol once.
+pub fn check_symbols<'a>(symbols: impl IntoIterator<Item = &'a str>, known: &BTreeSet<String>) -> Result<(), UnknownSymbols> {
+    let mut unknown: Vec<UnknownSymbol> = Vec::new();
+    for symbol in symbols {
+        if known.contains(symbol) || unknown.iter().any(|u| u.symbol == symbol) {
+            continue;
+        }
+        unknown.push(UnknownSymbol {
+            symbol: symbol.to_string(),
+            suggestion: suggest_symbol(symbol, known.iter().map(String::as_str)).map(str::to_string),
+        });
+    }
+    if unknown.is_empty() { Ok(()) } else { Err(UnknownSymbols(unknown)) }
+}
+
+/// The known symbol closest to `symbol`, ignoring case, if it is within two edits. Ties go
+/// to the alphabetically first symbol.
+pub fn suggest_symbol<'a>(symbol: &str, known: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let symbol = symbol.to_ascii_uppercase();
+    known
+        .into_iter()
+        .map(|candidate| (edit_distance(&symbol, &candidate.to_ascii_uppercase()), candidate))
+        .filter(|(distance, _)| *distance <= MAX_SUGGESTION_DISTANCE)
+        .min()
+        .map(|(_, candidate)| candidate)
+}
+
+/// The Levenshtein distance between `a` and `b`: the fewest single-character insertions,
+/// deletions and substitutions turning one into the other.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    // The distances from the prefix of `a` processed so far to each prefix of `b`.
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a_char) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, b_char) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != *b_char);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
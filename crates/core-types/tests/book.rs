@@ -0,0 +1,60 @@
+//! Summarises book ticker updates per kline.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{BookSummary, BookTicker, BookWindow};
+use rust_decimal_macros::dec;
+
+fn at(minutes: i64, seconds: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap() + Duration::minutes(minutes) + Duration::seconds(seconds)
+}
+
+fn ticker(timestamp: DateTime<Utc>, bid_qty: rust_decimal::Decimal, ask_qty: rust_decimal::Decimal) -> BookTicker {
+    BookTicker { timestamp, best_bid_price: dec!(99.9), best_bid_qty: bid_qty, best_ask_price: dec!(100.1), best_ask_qty: ask_qty }
+}
+
+#[test]
+fn a_ticker_reports_its_relative_spread_and_imbalance() {
+    let quote = ticker(at(0, 0), dec!(3), dec!(1));
+    assert!((quote.relative_spread().unwrap() - 0.002).abs() < 1e-12);
+    assert_eq!(quote.imbalance(), Some(0.5));
+
+    let empty = ticker(at(0, 0), dec!(0), dec!(0));
+    assert_eq!(empty.imbalance(), None);
+    assert!(empty.relative_spread().is_some());
+}
+
+#[test]
+fn a_summary_averages_spreads_and_imbalances_apart() {
+    let mut summary = BookSummary::default();
+    summary.record(&ticker(at(0, 0), dec!(3), dec!(1)));
+    summary.record(&ticker(at(0, 1), dec!(0), dec!(0)));
+
+    assert_eq!((summary.spread_samples, summary.imbalance_samples), (2, 1));
+    assert_eq!(summary.mean_imbalance(), Some(0.5));
+    assert!(BookSummary::default().mean_relative_spread().is_none());
+}
+
+#[test]
+fn a_window_summarises_the_minutes_a_kline_spans() {
+    let mut window = BookWindow::default();
+    window.record(&ticker(at(0, 30), dec!(1), dec!(1)));
+    window.record(&ticker(at(4, 59), dec!(3), dec!(1)));
+    window.record(&ticker(at(5, 0), dec!(1), dec!(3)));
+
+    let first = window.summary(at(0, 0), at(5, 0) - Duration::milliseconds(1)).unwrap();
+    assert_eq!((first.imbalance_samples, first.imbalance_sum), (2, 0.5));
+    let second = window.summary(at(5, 0), at(10, 0) - Duration::milliseconds(1)).unwrap();
+    assert_eq!(second.mean_imbalance(), Some(-0.5));
+    assert_eq!(window.summary(at(10, 0), at(15, 0) - Duration::milliseconds(1)), None);
+}
+
+#[test]
+fn a_window_forgets_updates_older_than_a_day() {
+    let mut window = BookWindow::default();
+    window.record(&ticker(at(0, 0), dec!(1), dec!(1)));
+    window.record(&ticker(at(24 * 60 + 1, 0), dec!(1), dec!(1)));
+    window.record(&ticker(at(0, 10), dec!(1), dec!(1)));
+
+    assert_eq!(window.summary(at(0, 0), at(1, 0)), None);
+    assert!(window.summary(at(24 * 60, 0), at(24 * 60 + 2, 0)).is_some());
+}
@@ -0,0 +1,50 @@
+//! Parses and formats kline intervals, and counts their bars over calendar periods.
+
+use core_types::{CoreError, Interval, Period};
+use std::time::Duration;
+
+#[test]
+fn every_interval_round_trips_through_its_canonical_form() {
+    for interval in Interval::ALL {
+        assert_eq!(interval.to_string().parse::<Interval>().unwrap(), interval);
+        assert_eq!(interval.to_string(), interval.as_str());
+    }
+    let canonical: Vec<_> = Interval::ALL.iter().map(|interval| interval.as_str()).collect();
+    assert_eq!(canonical, ["1m", "5m", "15m", "30m", "1h", "2h", "4h", "6h", "8h", "12h", "1d", "1w"]);
+}
+
+#[test]
+fn intervals_are_ordered_by_duration() {
+    for pair in Interval::ALL.windows(2) {
+        assert!(pair[0] < pair[1]);
+        assert!(pair[0].duration() < pair[1].duration(), "{} is not shorter than {}", pair[0], pair[1]);
+    }
+}
+
+#[test]
+fn unsupported_and_non_canonical_intervals_are_rejected() {
+    for text in ["", "h", "3m", "90s", "60m", "1H", "01h", " 1h", "1M", "3d", "1mo"] {
+        let error = text.parse::<Interval>().unwrap_err();
+        assert!(matches!(&error, CoreError::UnsupportedInterval(rejected) if rejected == text), "{:?}", error);
+    }
+    assert_eq!(
+        "3m".parse::<Interval>().unwrap_err().to_string(),
+        "Unsupported interval \"3m\"; supported intervals: 1m, 5m, 15m, 30m, 1h, 2h, 4h, 6h, 8h, 12h, 1d, 1w"
+    );
+}
+
+#[test]
+fn durations_and_bar_counts() {
+    assert_eq!(Interval::M15.duration(), Duration::from_secs(900));
+    assert_eq!(Interval::H4.duration(), Duration::from_secs(4 * 3600));
+    assert_eq!(Interval::W1.duration(), Duration::from_secs(7 * 86_400));
+
+    assert_eq!(Interval::M1.bars_per(Period::Day), 1440.0);
+    assert_eq!(Interval::H4.bars_per(Period::Day), 6.0);
+    assert_eq!(Interval::H1.bars_per(Period::Week), 168.0);
+    assert_eq!(Interval::D1.bars_per(Period::Year), 365.0);
+    assert_eq!(Interval::W1.bars_per(Period::Day), 1.0 / 7.0);
+    for interval in Interval::ALL {
+        assert_eq!(interval.bars_per(Period::Week), interval.bars_per(Period::Day) * 7.0, "{}", interval);
+    }
+}
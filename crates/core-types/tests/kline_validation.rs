@@ -0,0 +1,70 @@
+//! Checks that `Kline::validate` accepts real bars and names everything wrong with others.
+
+use chrono::{Duration, TimeZone, Utc};
+use core_types::{Interval, Kline, KlineViolation};
+use rust_decimal_macros::dec;
+
+fn bar(interval: Interval) -> Kline {
+    let open_time = Utc.with_ymd_and_hms(2025, 9, 1, 0, 0, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(102),
+        low: dec!(99),
+        close: dec!(101),
+        volume: dec!(10),
+        close_time: open_time + Duration::from_std(interval.duration()).unwrap() - Duration::milliseconds(1),
+        interval: interval.to_string(),
+    }
+}
+
+fn violations(kline: &Kline) -> Vec<KlineViolation> {
+    kline.validate().map_or_else(|e| e.violations, |()| Vec::new())
+}
+
+#[test]
+fn real_bars_are_valid() {
+    for interval in Interval::ALL {
+        assert_eq!(bar(interval).validate(), Ok(()), "{}", interval);
+    }
+    // A flat bar with no trades is still a bar.
+    let flat = Kline { open: dec!(100), high: dec!(100), low: dec!(100), close: dec!(100), volume: dec!(0), ..bar(Interval::M1) };
+    assert_eq!(flat.validate(), Ok(()));
+}
+
+#[test]
+fn wicks_must_contain_the_body() {
+    let kline = Kline { high: dec!(98), low: dec!(100.5), ..bar(Interval::H1) };
+    assert_eq!(
+        violations(&kline),
+        [KlineViolation::HighBelowBody { high: dec!(98), body_top: dec!(101) }, KlineViolation::LowAboveBody { low: dec!(100.5), body_bottom: dec!(100) }]
+    );
+    assert_eq!(
+        kline.validate().unwrap_err().to_string(),
+        "the 1h kline opening at 2025-09-01 00:00:00 UTC is invalid: the high 98 is below the body top 101; the low 100.5 is above the body bottom 100"
+    );
+}
+
+#[test]
+fn prices_must_be_positive_and_volume_not_negative() {
+    let kline = Kline { low: dec!(0), volume: dec!(-1), ..bar(Interval::M5) };
+    assert_eq!(
+        violations(&kline),
+        [KlineViolation::NonPositivePrice { field: "low", price: dec!(0) }, KlineViolation::NegativeVolume(dec!(-1))]
+    );
+}
+
+#[test]
+fn a_bar_must_span_its_interval() {
+    let early = bar(Interval::M1);
+    let backwards = Kline { close_time: early.open_time - Duration::milliseconds(1), ..early.clone() };
+    assert_eq!(violations(&backwards), [KlineViolation::CloseNotAfterOpen]);
+
+    // An hour's bar labelled 1m.
+    let mislabelled = Kline { interval: "1m".to_string(), ..bar(Interval::H1) };
+    assert_eq!(violations(&mislabelled), [KlineViolation::SpanMismatch { interval: Interval::M1, span_ms: 3_599_999 }]);
+    assert!(mislabelled.validate().unwrap_err().to_string().ends_with("it spans 3600000 ms, not the 60000 ms of a 1m bar"));
+
+    let unknown = Kline { interval: "3m".to_string(), ..early };
+    assert_eq!(violations(&unknown), [KlineViolation::UnknownInterval("3m".to_string())]);
+}
@@ -0,0 +1,48 @@
+//! Measures what limit fills saved against crossing the spread.
+
+use chrono::{DateTime, TimeZone, Utc};
+use core_types::{OrderPlacement, OrderSide};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+fn placed_at() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// A book of 99.9 / 100.1, so 1 bp of the mid price is 0.01.
+fn placement(limit_price: Decimal) -> OrderPlacement {
+    OrderPlacement::new(dec!(99.9), dec!(100.1), limit_price, placed_at())
+}
+
+#[test]
+fn a_buy_saves_what_it_filled_below_the_ask() {
+    let mut buy = placement(dec!(99.92));
+    assert!(!buy.is_confirmed());
+    assert_eq!((buy.fill_price, buy.is_maker, buy.price_improvement_bps), (None, None, None));
+    buy.record_fill(OrderSide::Buy, dec!(99.92), true, placed_at());
+    assert!(buy.is_confirmed());
+    assert_eq!((buy.fill_price, buy.is_maker, buy.price_improvement_bps), (Some(dec!(99.92)), Some(true), Some(dec!(18))));
+}
+
+#[test]
+fn a_sell_saves_what_it_filled_above_the_bid() {
+    let mut sell = placement(dec!(100.08));
+    sell.record_fill(OrderSide::Sell, dec!(100.08), true, placed_at());
+    assert_eq!(sell.price_improvement_bps, Some(dec!(18)));
+}
+
+#[test]
+fn a_fill_worse_than_crossing_the_spread_saves_a_negative_amount() {
+    let mut buy = placement(dec!(99.92));
+    // A later confirmation replaces an earlier one.
+    buy.record_fill(OrderSide::Buy, dec!(99.92), true, placed_at());
+    buy.record_fill(OrderSide::Buy, dec!(100.15), false, placed_at());
+    assert_eq!((buy.is_maker, buy.price_improvement_bps), (Some(false), Some(dec!(-5))));
+}
+
+#[test]
+fn a_book_without_a_positive_mid_price_has_no_improvement() {
+    let mut empty = OrderPlacement::new(Decimal::ZERO, Decimal::ZERO, dec!(1), Utc::now());
+    empty.record_fill(OrderSide::Buy, dec!(1), true, Utc::now());
+    assert_eq!(empty.price_improvement_bps, None);
+}
@@ -0,0 +1,46 @@
+//! Suggests the closest known symbol for a mistyped one.
+
+use core_types::symbols::edit_distance;
+use core_types::{check_symbols, suggest_symbol, UnknownSymbol, UnknownSymbols};
+use std::collections::BTreeSet;
+
+fn known() -> BTreeSet<String> {
+    ["BTCUSDT", "ETHUSDT", "SOLUSDT", "BTCDOMUSDT", "ETHBTC"].iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn edit_distance_counts_insertions_deletions_and_substitutions() {
+    assert_eq!(edit_distance("BTCUSDT", "BTCUSDT"), 0);
+    assert_eq!(edit_distance("BTCUSD", "BTCUSDT"), 1);
+    assert_eq!(edit_distance("BTCUSDTT", "BTCUSDT"), 1);
+    assert_eq!(edit_distance("BTCUSDC", "BTCUSDT"), 1);
+    assert_eq!(edit_distance("TBCUSDT", "BTCUSDT"), 2);
+    assert_eq!(edit_distance("", "ETH"), 3);
+}
+
+#[test]
+fn the_closest_symbol_within_two_edits_is_suggested() {
+    let known = known();
+    let known = || known.iter().map(String::as_str);
+    assert_eq!(suggest_symbol("BTCUSD", known()), Some("BTCUSDT"));
+    assert_eq!(suggest_symbol("ETHUSTD", known()), Some("ETHUSDT"));
+    assert_eq!(suggest_symbol("solusdt", known()), Some("SOLUSDT"));
+    assert_eq!(suggest_symbol("DOGEUSDT", known()), None);
+    assert_eq!(suggest_symbol("BTCUSD", std::iter::empty()), None);
+}
+
+#[test]
+fn every_unknown_symbol_is_reported_once_in_order() {
+    let result = check_symbols(["BTCUSDT", "BTCUSD", "DOGEUSDT", "BTCUSD"], &known());
+
+    let unknown = result.unwrap_err();
+    assert_eq!(
+        unknown,
+        UnknownSymbols(vec![
+            UnknownSymbol { symbol: "BTCUSD".to_string(), suggestion: Some("BTCUSDT".to_string()) },
+            UnknownSymbol { symbol: "DOGEUSDT".to_string(), suggestion: None },
+        ])
+    );
+    assert_eq!(unknown.to_string(), "symbol 'BTCUSD' not found; did you mean BTCUSDT?\nsymbol 'DOGEUSDT' not found");
+    assert_eq!(check_symbols(["ETHUSDT", "SOLUSDT"], &known()), Ok(()));
+}
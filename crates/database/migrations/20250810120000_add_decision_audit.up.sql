@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Add the Decision Audit Table
+-- Records every stage of a live trading decision (signal -> risk -> order -> execution -> portfolio)
+-- so that any trade can be reconstructed after the fact from its decision_id.
+
+CREATE TABLE decision_audit (
+    audit_id BIGSERIAL PRIMARY KEY,
+    decision_id UUID NOT NULL,
+    stage TEXT NOT NULL, -- e.g., 'Signal', 'RiskApproved', 'RiskRejected', 'Executed'
+    symbol TEXT NOT NULL,
+    -- The structured snapshot captured at this stage (kline, signal, order, execution, etc.).
+    payload JSONB NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Add an index for quickly retrieving the full chain for a single decision.
+CREATE INDEX idx_decision_audit_decision_id ON decision_audit (decision_id);
@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE trades
+    DROP COLUMN IF EXISTS entry_side,
+    DROP COLUMN IF EXISTS entry_fee,
+    DROP COLUMN IF EXISTS exit_fee,
+    DROP COLUMN IF EXISTS fee_asset;
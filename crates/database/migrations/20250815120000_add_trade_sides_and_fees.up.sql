@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Record the direction and fees of each trade, so trades read back from the database
+-- no longer have to assume every trade was a long.
+
+ALTER TABLE trades
+    ADD COLUMN entry_side TEXT CHECK (entry_side IN ('BUY', 'SELL')),
+    ADD COLUMN entry_fee DECIMAL,
+    ADD COLUMN exit_fee DECIMAL,
+    ADD COLUMN fee_asset TEXT;
+
+-- Existing rows are left NULL: prices and quantities alone cannot tell a winning long from
+-- a losing short. Readers treat a missing side as a long and log a warning.
@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE backtest_runs
+    DROP COLUMN IF EXISTS started_at,
+    DROP COLUMN IF EXISTS finished_at,
+    DROP COLUMN IF EXISTS bars_processed,
+    DROP COLUMN IF EXISTS engine_version,
+    DROP COLUMN IF EXISTS config_hash;
@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Record when, how and from which configuration each backtest run was produced, so runs
+-- can be compared long after the fact.
+
+ALTER TABLE backtest_runs
+    ADD COLUMN started_at TIMESTAMPTZ,
+    ADD COLUMN finished_at TIMESTAMPTZ,
+    ADD COLUMN bars_processed BIGINT,
+    ADD COLUMN engine_version TEXT,
+    ADD COLUMN config_hash TEXT;
+
+-- Existing and unfinished runs are left NULL.
@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE performance_reports
+    DROP COLUMN IF EXISTS maker_fees_paid,
+    DROP COLUMN IF EXISTS taker_fees_paid;
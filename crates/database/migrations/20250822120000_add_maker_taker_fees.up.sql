@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- Split the fees a run paid into maker and taker fees, so passive strategies can be told
+-- apart from aggressive ones.
+
+ALTER TABLE performance_reports
+    ADD COLUMN maker_fees_paid DECIMAL,
+    ADD COLUMN taker_fees_paid DECIMAL;
+
+-- Existing reports are left NULL: their fees were not split.
@@ -0,0 +1,5 @@
+-- Add down migration script here
+ALTER TABLE backtest_runs
+    DROP COLUMN IF EXISTS interval,
+    DROP COLUMN IF EXISTS data_start,
+    DROP COLUMN IF EXISTS data_end;
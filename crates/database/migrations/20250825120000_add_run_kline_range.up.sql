@@ -0,0 +1,10 @@
+-- Add up migration script here
+-- Record the kline interval and the span of bars each backtest run replayed, so the price
+-- data behind a run can be fetched again for charting.
+
+ALTER TABLE backtest_runs
+    ADD COLUMN interval TEXT,
+    ADD COLUMN data_start TIMESTAMPTZ,
+    ADD COLUMN data_end TIMESTAMPTZ;
+
+-- Existing and unfinished runs are left NULL.
@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- Track how far each backfill has got, so an interrupted backfill resumes where it stopped
+-- instead of fetching every month again. A backfill is identified by the symbol, interval
+-- and start date it was run with; `last_completed_range_end` only advances over the months
+-- completed without gaps since `range_start`.
+
+CREATE TABLE backfill_progress (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    range_start TIMESTAMPTZ NOT NULL,
+    last_completed_range_end TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (symbol, interval, range_start)
+);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE performance_reports
+    DROP COLUMN IF EXISTS max_consecutive_losses;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Record each run's longest losing streak, so the analyzer can filter out runs whose
+-- drawdowns look small but whose losses come in long runs.
+
+ALTER TABLE performance_reports
+    ADD COLUMN max_consecutive_losses INTEGER;
+
+-- Existing reports are left NULL: their streaks were not measured.
@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- Record why each trade's position was closed (a strategy signal, a stop-loss, a time limit),
+-- so exits forced by risk rules can be told apart from the strategy's own.
+
+ALTER TABLE trades
+    ADD COLUMN close_reason TEXT NOT NULL DEFAULT 'Signal'
+        CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit'));
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Store each report's per-exit-type statistics (count, net and average PnL, win rate by close
+-- reason) as one JSON array rather than a column per reason and metric.
+
+ALTER TABLE performance_reports
+    ADD COLUMN exit_breakdown JSONB;
+
+-- Reports saved before the breakdown was computed are left NULL.
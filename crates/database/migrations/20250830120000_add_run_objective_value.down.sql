@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE backtest_runs
+    DROP COLUMN IF EXISTS objective_value;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Store each optimizer run's objective value (the metric or score the job maximizes),
+-- computed when the run finishes, so runs can be ranked without re-scoring the job.
+
+ALTER TABLE backtest_runs
+    ADD COLUMN objective_value NUMERIC;
+
+-- NULL for runs that failed the hard filters, lacked the metric, or predate objectives.
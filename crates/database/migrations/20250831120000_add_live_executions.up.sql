@@ -0,0 +1,36 @@
+-- Add up migration script here
+-- Record every live execution with the position it changed, so the live engine's positions
+-- can be rebuilt from their fills: opened, added to, reduced and closed.
+
+CREATE TABLE live_executions (
+    execution_id UUID NOT NULL,
+    -- The position the fill changed. A netted reversal is stored as two rows of the same
+    -- execution: the close of the old position and the opening of the new one.
+    position_id UUID NOT NULL,
+    client_order_id UUID NOT NULL,
+    decision_id UUID,
+    symbol TEXT NOT NULL,
+    -- The side of the position, not of the execution ('BUY' for a long).
+    position_side TEXT NOT NULL CHECK (position_side IN ('BUY', 'SELL')),
+    -- TRUE when the fill opened or added to the position, FALSE when it reduced or closed it.
+    is_entry BOOLEAN NOT NULL,
+    price NUMERIC NOT NULL,
+    quantity NUMERIC NOT NULL,
+    -- This fill's share of the execution's fee.
+    fee NUMERIC NOT NULL,
+    fee_asset TEXT NOT NULL,
+    is_maker BOOLEAN NOT NULL,
+    -- The gross P&L locked in against the average entry price; zero for entries.
+    realized_pnl NUMERIC NOT NULL,
+    -- The position's quantity after the fill; zero once it is closed.
+    position_quantity NUMERIC NOT NULL,
+    -- Why the position was closed, on the fill that closed it. NULL on other fills, and on
+    -- closes the engine made outside a trading decision (the dead-man's switch).
+    close_reason TEXT CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit')),
+    executed_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (execution_id, position_id)
+);
+
+-- Positions are rebuilt by grouping their fills, and listed by symbol.
+CREATE INDEX idx_live_executions_position_id ON live_executions (position_id);
+CREATE INDEX idx_live_executions_symbol_executed_at ON live_executions (symbol, executed_at);
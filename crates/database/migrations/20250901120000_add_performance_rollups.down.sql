@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS weekly_performance;
+DROP TABLE IF EXISTS daily_performance;
+DROP TABLE IF EXISTS live_equity;
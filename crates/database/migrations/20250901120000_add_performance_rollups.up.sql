@@ -0,0 +1,30 @@
+-- Add up migration script here
+-- Record the live engine's equity over time, and roll its performance up per UTC day and per
+-- week (Monday to Sunday) for the dashboard.
+
+CREATE TABLE live_equity (
+    recorded_at TIMESTAMPTZ PRIMARY KEY,
+    -- Cash plus the unrealized P&L of the open positions, marked to the latest prices.
+    equity NUMERIC NOT NULL
+);
+
+CREATE TABLE daily_performance (
+    -- The UTC day rolled up.
+    period_start DATE PRIMARY KEY,
+    -- The P&L the period's reducing and closing fills locked in, net of every fill's fee.
+    net_pnl NUMERIC NOT NULL,
+    fees NUMERIC NOT NULL,
+    -- Each reducing or closing fill counts as a trade.
+    trade_count INTEGER NOT NULL,
+    winning_trades INTEGER NOT NULL,
+    -- NULL for periods without trades.
+    win_rate_pct NUMERIC,
+    max_drawdown NUMERIC NOT NULL,
+    max_drawdown_pct NUMERIC NOT NULL,
+    -- NULL when no equity had been recorded by the end of the period.
+    ending_equity NUMERIC,
+    computed_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- The same rollup per week, keyed by the Monday it starts on.
+CREATE TABLE weekly_performance (LIKE daily_performance INCLUDING ALL);
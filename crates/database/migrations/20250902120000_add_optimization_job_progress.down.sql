@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE optimization_jobs
+    DROP COLUMN IF EXISTS completed_runs,
+    DROP COLUMN IF EXISTS total_runs,
+    DROP COLUMN IF EXISTS updated_at,
+    DROP COLUMN IF EXISTS estimated_completion_at;
@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- Track each optimization job's progress, so clients other than the CLI running it (the
+-- web UI's job list) can show how far along it is and when it should finish.
+
+ALTER TABLE optimization_jobs
+    ADD COLUMN completed_runs INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN total_runs INTEGER NOT NULL DEFAULT 0,
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    ADD COLUMN estimated_completion_at TIMESTAMPTZ;
+
+-- Jobs from before this migration report no runs; their runs are still in backtest_runs.
@@ -0,0 +1,7 @@
+-- Add down migration script here
+DELETE FROM backfill_progress WHERE price_type <> 'last';
+ALTER TABLE backfill_progress DROP CONSTRAINT backfill_progress_pkey;
+ALTER TABLE backfill_progress ADD PRIMARY KEY (symbol, interval, range_start);
+ALTER TABLE backfill_progress DROP COLUMN price_type;
+
+DROP TABLE IF EXISTS mark_price_klines;
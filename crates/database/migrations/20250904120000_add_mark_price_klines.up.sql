@@ -0,0 +1,21 @@
+-- Add up migration script here
+-- Mark price klines, for backtests that value positions and trigger stops on the mark
+-- price like the exchange does. Binance's mark price klines have no volume, so the table
+-- has none. Backfill progress is tracked per price type, so backfilling the mark prices of
+-- a range the last prices already cover still fetches it.
+
+CREATE TABLE mark_price_klines (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    open_time TIMESTAMPTZ NOT NULL,
+    close_time TIMESTAMPTZ NOT NULL,
+    open DECIMAL NOT NULL,
+    high DECIMAL NOT NULL,
+    low DECIMAL NOT NULL,
+    close DECIMAL NOT NULL,
+    PRIMARY KEY (symbol, interval, open_time)
+);
+
+ALTER TABLE backfill_progress ADD COLUMN price_type TEXT NOT NULL DEFAULT 'last';
+ALTER TABLE backfill_progress DROP CONSTRAINT backfill_progress_pkey;
+ALTER TABLE backfill_progress ADD PRIMARY KEY (symbol, interval, price_type, range_start);
@@ -0,0 +1,8 @@
+-- Add down migration script here
+ALTER TABLE performance_reports
+    DROP COLUMN IF EXISTS r_distribution,
+    DROP COLUMN IF EXISTS expectancy_r,
+    DROP COLUMN IF EXISTS average_r;
+
+ALTER TABLE trades
+    DROP COLUMN IF EXISTS initial_risk;
@@ -0,0 +1,14 @@
+-- Add up migration script here
+-- Record what each trade risked at entry (the distance to its stop-loss times the quantity),
+-- and each report's R-multiple metrics: its PnL measured in units of that risk.
+
+ALTER TABLE trades
+    ADD COLUMN initial_risk NUMERIC;
+
+ALTER TABLE performance_reports
+    ADD COLUMN average_r NUMERIC,
+    ADD COLUMN expectancy_r NUMERIC,
+    ADD COLUMN r_distribution JSONB;
+
+-- Trades and reports saved before risk was recorded are left NULL, so they drop out of the
+-- R-multiple metrics rather than counting as 0R.
@@ -0,0 +1,15 @@
+-- Add up migration script here
+-- Top-of-book quotes recorded by the live engine when `record_book_tickers` is on, for ML
+-- datasets with order book features. A symbol can receive several updates in the same
+-- millisecond, so there is no primary key; the index serves the per-kline aggregation.
+
+CREATE TABLE book_tickers (
+    symbol TEXT NOT NULL,
+    event_time TIMESTAMPTZ NOT NULL,
+    best_bid_price DECIMAL NOT NULL,
+    best_bid_qty DECIMAL NOT NULL,
+    best_ask_price DECIMAL NOT NULL,
+    best_ask_qty DECIMAL NOT NULL
+);
+
+CREATE INDEX idx_book_tickers_symbol_event_time ON book_tickers (symbol, event_time);
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Record what crossing the bid-ask spread cost each run, so its share of gross profit can
+-- be told apart from fees.
+
+ALTER TABLE performance_reports
+    ADD COLUMN total_spread_cost DECIMAL;
+
+-- Existing reports are left NULL: their fills did not model a spread.
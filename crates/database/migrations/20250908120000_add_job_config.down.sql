@@ -0,0 +1,6 @@
+-- Add down migration script here
+ALTER TABLE wfo_jobs
+    DROP COLUMN IF EXISTS job_config;
+
+ALTER TABLE optimization_jobs
+    DROP COLUMN IF EXISTS job_config;
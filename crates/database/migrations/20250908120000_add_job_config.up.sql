@@ -0,0 +1,11 @@
+-- Add up migration script here
+-- Keep the full configuration each optimization and WFO job ran with: parameter ranges,
+-- analysis filters and weights, and the simulation, risk and backtest settings.
+
+ALTER TABLE optimization_jobs
+    ADD COLUMN job_config JSONB;
+
+ALTER TABLE wfo_jobs
+    ADD COLUMN job_config JSONB;
+
+-- Jobs saved before this migration are left NULL; their configuration was not kept.
@@ -0,0 +1,18 @@
+-- Add up migration script here
+-- What the live engine needs to keep managing a position after a restart, which the exchange
+-- does not report: the strategy and signal that opened it and where its exits are. A context
+-- is open until the engine sees the position close; at most one is open per symbol.
+
+CREATE TABLE open_position_context (
+    position_id UUID PRIMARY KEY,
+    symbol TEXT NOT NULL,
+    position_side TEXT NOT NULL,
+    strategy_id TEXT,
+    entry_signal_id UUID,
+    stop_price DECIMAL,
+    take_profit_price DECIMAL,
+    opened_at TIMESTAMPTZ NOT NULL,
+    closed_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX idx_open_position_context_open_symbol ON open_position_context (symbol) WHERE closed_at IS NULL;
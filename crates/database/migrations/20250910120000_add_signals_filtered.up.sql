@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Record how many approved signals each run's minimum expected-move filter refused, so the
+-- signals it kills can be counted.
+
+ALTER TABLE backtest_runs
+    ADD COLUMN signals_filtered BIGINT;
+
+-- Runs saved before this migration are left NULL: they were never filtered.
@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- Notes attached by hand to a backtest trade, a backtest run or a live position, with free-form
+-- tags for pulling every target that shares a condition. Targets are not foreign keys, as
+-- positions have no table of their own; the API checks that a target exists.
+
+CREATE TABLE annotations (
+    annotation_id UUID PRIMARY KEY,
+    target_type TEXT NOT NULL CHECK (target_type IN ('trade', 'run', 'position')),
+    target_id UUID NOT NULL,
+    author TEXT NOT NULL,
+    text TEXT NOT NULL,
+    tags TEXT[] NOT NULL DEFAULT '{}',
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_annotations_target ON annotations (target_type, target_id);
+CREATE INDEX idx_annotations_tags ON annotations USING GIN (tags);
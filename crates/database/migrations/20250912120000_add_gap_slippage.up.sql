@@ -0,0 +1,7 @@
+-- Add up migration script here
+-- Record what stops lost to gaps each run, so a strategy's exposure to gap risk shows.
+
+ALTER TABLE performance_reports
+    ADD COLUMN gap_slippage DECIMAL;
+
+-- Existing reports are left NULL: their stops did not model gaps.
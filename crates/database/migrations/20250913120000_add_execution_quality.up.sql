@@ -0,0 +1,23 @@
+-- Add up migration script here
+-- The book each live limit order was placed against, and what its fill saved compared with
+-- crossing the spread. One row per order: a fill confirmed after placement replaces the
+-- figures recorded at placement.
+
+CREATE TABLE live_execution_quality (
+    client_order_id UUID PRIMARY KEY,
+    execution_id UUID NOT NULL,
+    symbol TEXT NOT NULL,
+    side TEXT NOT NULL CHECK (side IN ('BUY', 'SELL')),
+    quantity DECIMAL NOT NULL,
+    best_bid DECIMAL NOT NULL,
+    best_ask DECIMAL NOT NULL,
+    limit_price DECIMAL NOT NULL,
+    fill_price DECIMAL NOT NULL,
+    is_maker BOOLEAN NOT NULL,
+    -- NULL when the book at placement had no positive mid price.
+    price_improvement_bps DECIMAL,
+    placed_at TIMESTAMPTZ NOT NULL,
+    filled_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX idx_live_execution_quality_filled_at ON live_execution_quality (filled_at);
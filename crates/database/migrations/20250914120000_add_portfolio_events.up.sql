@@ -0,0 +1,17 @@
+-- Add up migration script here
+-- The events the live portfolio applied: executions, funding, and the corrections that
+-- brought it in line with the exchange, with the values they replaced. `sequence` counts up
+-- from 1 in each engine run; `event_id` orders events across runs.
+
+CREATE TABLE portfolio_events (
+    event_id BIGSERIAL PRIMARY KEY,
+    sequence BIGINT NOT NULL,
+    kind TEXT NOT NULL,
+    -- NULL for events not about a position, such as cash corrections.
+    symbol TEXT,
+    event JSONB NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_portfolio_events_symbol ON portfolio_events (symbol, event_id);
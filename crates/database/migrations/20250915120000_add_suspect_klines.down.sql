@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE klines DROP COLUMN IF EXISTS is_suspect;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- Mark klines saved despite failing validation (a high below the close, a zero price, a
+-- bar not spanning its interval), when saves are configured to flag rather than reject them.
+
+ALTER TABLE klines
+    ADD COLUMN is_suspect BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Existing klines are not checked here; `zenith data-check --validate` scans them.
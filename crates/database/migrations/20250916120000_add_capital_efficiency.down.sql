@@ -0,0 +1,7 @@
+-- Add down migration script here
+ALTER TABLE performance_reports
+    DROP COLUMN IF EXISTS average_exposure_pct,
+    DROP COLUMN IF EXISTS peak_exposure_pct,
+    DROP COLUMN IF EXISTS time_in_market_pct,
+    DROP COLUMN IF EXISTS return_on_margin_pct,
+    DROP COLUMN IF EXISTS exposure_adjusted_sharpe;
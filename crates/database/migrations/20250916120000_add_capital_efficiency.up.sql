@@ -0,0 +1,12 @@
+-- Add up migration script here
+-- Record how much of its capital each run put to work, so strategies that sit flat most of
+-- the time can be compared with ones that are always in the market.
+
+ALTER TABLE performance_reports
+    ADD COLUMN average_exposure_pct DECIMAL,
+    ADD COLUMN peak_exposure_pct DECIMAL,
+    ADD COLUMN time_in_market_pct DECIMAL,
+    ADD COLUMN return_on_margin_pct DECIMAL,
+    ADD COLUMN exposure_adjusted_sharpe DECIMAL;
+
+-- Existing reports are left NULL: their runs did not record their exposure.
@@ -0,0 +1,4 @@
+-- Add down migration script here
+ALTER TABLE backtest_runs
+    DROP COLUMN IF EXISTS brackets_stop_first,
+    DROP COLUMN IF EXISTS brackets_target_first;
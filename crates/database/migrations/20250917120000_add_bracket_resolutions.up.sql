@@ -0,0 +1,9 @@
+-- Add up migration script here
+-- Record how each run resolved the bars that reached both a position's stop-loss and its
+-- take-profit, so the share of results resting on that choice can be seen.
+
+ALTER TABLE backtest_runs
+    ADD COLUMN brackets_stop_first BIGINT,
+    ADD COLUMN brackets_target_first BIGINT;
+
+-- Runs saved before this migration are left NULL: they had no take-profits.
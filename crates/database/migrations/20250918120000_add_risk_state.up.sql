@@ -0,0 +1,23 @@
+-- Add up migration script here
+-- The global risk manager's state, so a restart neither resets its accounting nor lifts a
+-- halt early: the trading day's peak equity and last rollover under the 'global' scope, and
+-- each bot's losing streak and halt under its symbol. A row is overwritten on every change.
+
+CREATE TABLE risk_state (
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (scope, key)
+);
+
+-- The risk state as each daily rollover found it, by the trading day it belonged to.
+CREATE TABLE risk_state_history (
+    trading_day TIMESTAMPTZ NOT NULL,
+    scope TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    archived_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (trading_day, scope, key)
+);
@@ -0,0 +1,20 @@
+-- Add up migration script here
+-- The live engine's idempotency ledger: every order it is about to place, written before the
+-- order is sent, so a restart can ask the exchange what became of an order whose fill it never
+-- saw. An intent is 'sent' until resolved as 'filled' (with the execution recorded for it),
+-- 'expired' (the exchange never filled it) or 'failed' (the exchange rejected it).
+
+CREATE TABLE order_intents (
+    client_order_id UUID PRIMARY KEY,
+    decision_id UUID,
+    symbol TEXT NOT NULL,
+    side TEXT NOT NULL,
+    quantity NUMERIC NOT NULL,
+    reduce_only BOOLEAN NOT NULL,
+    state TEXT NOT NULL,
+    execution_id UUID,
+    created_at TIMESTAMPTZ NOT NULL,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX idx_order_intents_sent ON order_intents (symbol) WHERE state = 'sent';
@@ -0,0 +1,13 @@
+-- Blackout closes were recorded as time limits before.
+UPDATE trades SET close_reason = 'TimeLimit' WHERE close_reason = 'Blackout';
+UPDATE live_executions SET close_reason = 'TimeLimit' WHERE close_reason = 'Blackout';
+
+ALTER TABLE trades DROP CONSTRAINT IF EXISTS trades_close_reason_check;
+ALTER TABLE trades
+    ADD CONSTRAINT trades_close_reason_check
+        CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit'));
+
+ALTER TABLE live_executions DROP CONSTRAINT IF EXISTS live_executions_close_reason_check;
+ALTER TABLE live_executions
+    ADD CONSTRAINT live_executions_close_reason_check
+        CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit'));
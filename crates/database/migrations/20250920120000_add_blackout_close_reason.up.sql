@@ -0,0 +1,12 @@
+-- Positions flattened ahead of a trading blackout are recorded as closed by the blackout
+-- rather than by a time limit.
+
+ALTER TABLE trades DROP CONSTRAINT IF EXISTS trades_close_reason_check;
+ALTER TABLE trades
+    ADD CONSTRAINT trades_close_reason_check
+        CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit', 'Blackout'));
+
+ALTER TABLE live_executions DROP CONSTRAINT IF EXISTS live_executions_close_reason_check;
+ALTER TABLE live_executions
+    ADD CONSTRAINT live_executions_close_reason_check
+        CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit', 'Blackout'));
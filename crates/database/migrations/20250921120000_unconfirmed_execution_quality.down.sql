@@ -0,0 +1,7 @@
+-- Add down migration script here
+DELETE FROM live_execution_quality WHERE fill_price IS NULL OR is_maker IS NULL OR filled_at IS NULL;
+
+ALTER TABLE live_execution_quality
+    ALTER COLUMN fill_price SET NOT NULL,
+    ALTER COLUMN is_maker SET NOT NULL,
+    ALTER COLUMN filled_at SET NOT NULL;
@@ -0,0 +1,8 @@
+-- Add up migration script here
+-- A live limit order's placement is recorded when it is placed; its fill price, maker flag,
+-- price improvement and fill time stay NULL until the exchange confirms its fill.
+
+ALTER TABLE live_execution_quality
+    ALTER COLUMN fill_price DROP NOT NULL,
+    ALTER COLUMN is_maker DROP NOT NULL,
+    ALTER COLUMN filled_at DROP NOT NULL;
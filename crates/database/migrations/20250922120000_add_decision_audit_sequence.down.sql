@@ -0,0 +1,2 @@
+-- Add down migration script here
+ALTER TABLE decision_audit DROP COLUMN sequence;
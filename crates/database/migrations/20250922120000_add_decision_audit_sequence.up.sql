@@ -0,0 +1,5 @@
+-- Add up migration script here
+-- The order the engine recorded each stage in, so a decision's chain reads back in the
+-- order it was decided even when stages share a timestamp. NULL for stages recorded before.
+
+ALTER TABLE decision_audit ADD COLUMN sequence BIGINT;
@@ -0,0 +1,7 @@
+DROP TABLE IF EXISTS equity_curves;
+DROP TABLE IF EXISTS trades;
+DROP TABLE IF EXISTS performance_reports;
+DROP TABLE IF EXISTS backtest_runs;
+DROP TABLE IF EXISTS optimization_jobs;
+DROP TABLE IF EXISTS backfill_progress;
+DROP TABLE IF EXISTS klines;
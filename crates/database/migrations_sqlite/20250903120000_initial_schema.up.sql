@@ -0,0 +1,114 @@
+-- Zenith SQLite schema for local research.
+-- SQLite holds the subset of the PostgreSQL schema that single backtest runs need: klines
+-- and their backfill progress, optimization jobs and backtest runs, and each run's report,
+-- trades and equity curve. WFO, decision audit and live trading tables stay PostgreSQL-only.
+
+-- SQLite has no timestamp, decimal, UUID or JSON column types, so:
+-- Timestamps are RFC 3339 TEXT in UTC, which sorts and compares in time order.
+-- Decimals are TEXT, so no precision is lost to floating point.
+-- UUIDs are hyphenated TEXT and JSON documents are TEXT.
+
+CREATE TABLE klines (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    open_time TEXT NOT NULL,
+    open TEXT NOT NULL,
+    high TEXT NOT NULL,
+    low TEXT NOT NULL,
+    close TEXT NOT NULL,
+    volume TEXT NOT NULL,
+    close_time TEXT NOT NULL,
+    PRIMARY KEY (symbol, interval, open_time)
+);
+
+CREATE TABLE backfill_progress (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    range_start TEXT NOT NULL,
+    last_completed_range_end TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (symbol, interval, range_start)
+);
+
+CREATE TABLE optimization_jobs (
+    job_id TEXT PRIMARY KEY,
+    strategy_id TEXT NOT NULL,
+    symbol TEXT NOT NULL,
+    job_status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    completed_runs INTEGER NOT NULL DEFAULT 0,
+    total_runs INTEGER NOT NULL DEFAULT 0,
+    updated_at TEXT NOT NULL,
+    estimated_completion_at TEXT
+);
+
+CREATE TABLE backtest_runs (
+    run_id TEXT PRIMARY KEY,
+    job_id TEXT REFERENCES optimization_jobs(job_id) ON DELETE CASCADE,
+    parameters TEXT NOT NULL,
+    run_status TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT,
+    bars_processed INTEGER,
+    engine_version TEXT,
+    config_hash TEXT,
+    interval TEXT,
+    data_start TEXT,
+    data_end TEXT,
+    objective_value TEXT
+);
+
+CREATE INDEX idx_backtest_runs_job_id_status ON backtest_runs(job_id, run_status);
+
+CREATE TABLE performance_reports (
+    report_id TEXT PRIMARY KEY,
+    run_id TEXT NOT NULL UNIQUE REFERENCES backtest_runs(run_id) ON DELETE CASCADE,
+    total_net_profit TEXT NOT NULL,
+    gross_profit TEXT NOT NULL,
+    gross_loss TEXT NOT NULL,
+    profit_factor TEXT,
+    total_return_pct TEXT NOT NULL,
+    max_drawdown TEXT NOT NULL,
+    max_drawdown_pct TEXT NOT NULL,
+    sharpe_ratio TEXT,
+    calmar_ratio TEXT,
+    total_trades INTEGER NOT NULL,
+    winning_trades INTEGER NOT NULL,
+    losing_trades INTEGER NOT NULL,
+    win_rate_pct TEXT,
+    average_win TEXT NOT NULL,
+    average_loss TEXT NOT NULL,
+    payoff_ratio TEXT,
+    average_holding_period TEXT NOT NULL,
+    maker_fees_paid TEXT,
+    taker_fees_paid TEXT,
+    max_consecutive_losses INTEGER,
+    exit_breakdown TEXT
+);
+
+CREATE TABLE trades (
+    trade_id TEXT PRIMARY KEY,
+    run_id TEXT NOT NULL REFERENCES backtest_runs(run_id) ON DELETE CASCADE,
+    symbol TEXT NOT NULL,
+    entry_price TEXT NOT NULL,
+    entry_qty TEXT NOT NULL,
+    entry_timestamp TEXT NOT NULL,
+    exit_price TEXT NOT NULL,
+    exit_qty TEXT NOT NULL,
+    exit_timestamp TEXT NOT NULL,
+    entry_side TEXT CHECK (entry_side IN ('BUY', 'SELL')),
+    entry_fee TEXT,
+    exit_fee TEXT,
+    fee_asset TEXT,
+    close_reason TEXT NOT NULL DEFAULT 'Signal' CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit'))
+);
+
+CREATE INDEX idx_trades_run_id ON trades(run_id);
+
+CREATE TABLE equity_curves (
+    run_id TEXT NOT NULL REFERENCES backtest_runs(run_id) ON DELETE CASCADE,
+    timestamp TEXT NOT NULL,
+    equity TEXT NOT NULL,
+    PRIMARY KEY (run_id, timestamp)
+);
@@ -0,0 +1,14 @@
+CREATE TABLE backfill_progress_of_last_prices (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    range_start TEXT NOT NULL,
+    last_completed_range_end TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (symbol, interval, range_start)
+);
+INSERT INTO backfill_progress_of_last_prices (symbol, interval, range_start, last_completed_range_end, updated_at)
+SELECT symbol, interval, range_start, last_completed_range_end, updated_at FROM backfill_progress WHERE price_type = 'last';
+DROP TABLE backfill_progress;
+ALTER TABLE backfill_progress_of_last_prices RENAME TO backfill_progress;
+
+DROP TABLE IF EXISTS mark_price_klines;
@@ -0,0 +1,28 @@
+-- Mark price klines, and backfill progress per price type; see the PostgreSQL migration.
+-- SQLite cannot change a primary key, so backfill_progress is rebuilt.
+
+CREATE TABLE mark_price_klines (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    open_time TEXT NOT NULL,
+    close_time TEXT NOT NULL,
+    open TEXT NOT NULL,
+    high TEXT NOT NULL,
+    low TEXT NOT NULL,
+    close TEXT NOT NULL,
+    PRIMARY KEY (symbol, interval, open_time)
+);
+
+CREATE TABLE backfill_progress_by_price_type (
+    symbol TEXT NOT NULL,
+    interval TEXT NOT NULL,
+    price_type TEXT NOT NULL DEFAULT 'last',
+    range_start TEXT NOT NULL,
+    last_completed_range_end TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    PRIMARY KEY (symbol, interval, price_type, range_start)
+);
+INSERT INTO backfill_progress_by_price_type (symbol, interval, range_start, last_completed_range_end, updated_at)
+SELECT symbol, interval, range_start, last_completed_range_end, updated_at FROM backfill_progress;
+DROP TABLE backfill_progress;
+ALTER TABLE backfill_progress_by_price_type RENAME TO backfill_progress;
@@ -0,0 +1,5 @@
+ALTER TABLE performance_reports DROP COLUMN r_distribution;
+ALTER TABLE performance_reports DROP COLUMN expectancy_r;
+ALTER TABLE performance_reports DROP COLUMN average_r;
+
+ALTER TABLE trades DROP COLUMN initial_risk;
@@ -0,0 +1,7 @@
+-- Each trade's initial risk and each report's R-multiple metrics; see the PostgreSQL migration.
+
+ALTER TABLE trades ADD COLUMN initial_risk TEXT;
+
+ALTER TABLE performance_reports ADD COLUMN average_r TEXT;
+ALTER TABLE performance_reports ADD COLUMN expectancy_r TEXT;
+ALTER TABLE performance_reports ADD COLUMN r_distribution TEXT;
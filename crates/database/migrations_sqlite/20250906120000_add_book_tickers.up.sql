@@ -0,0 +1,12 @@
+-- Recorded top-of-book quotes; see the PostgreSQL migration.
+
+CREATE TABLE book_tickers (
+    symbol TEXT NOT NULL,
+    event_time TEXT NOT NULL,
+    best_bid_price TEXT NOT NULL,
+    best_bid_qty TEXT NOT NULL,
+    best_ask_price TEXT NOT NULL,
+    best_ask_qty TEXT NOT NULL
+);
+
+CREATE INDEX idx_book_tickers_symbol_event_time ON book_tickers (symbol, event_time);
@@ -0,0 +1 @@
+ALTER TABLE performance_reports DROP COLUMN total_spread_cost;
@@ -0,0 +1,3 @@
+-- Each report's spread cost; see the PostgreSQL migration.
+
+ALTER TABLE performance_reports ADD COLUMN total_spread_cost TEXT;
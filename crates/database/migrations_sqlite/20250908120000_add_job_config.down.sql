@@ -0,0 +1 @@
+ALTER TABLE optimization_jobs DROP COLUMN job_config;
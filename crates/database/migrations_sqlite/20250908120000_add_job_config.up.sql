@@ -0,0 +1,3 @@
+-- The configuration each optimization job ran with; see the PostgreSQL migration.
+
+ALTER TABLE optimization_jobs ADD COLUMN job_config TEXT;
@@ -0,0 +1 @@
+ALTER TABLE backtest_runs DROP COLUMN signals_filtered;
@@ -0,0 +1,3 @@
+-- The signals each run's expected-move filter refused; see the PostgreSQL migration.
+
+ALTER TABLE backtest_runs ADD COLUMN signals_filtered INTEGER;
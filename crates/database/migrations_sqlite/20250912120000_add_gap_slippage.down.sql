@@ -0,0 +1 @@
+ALTER TABLE performance_reports DROP COLUMN gap_slippage;
@@ -0,0 +1,3 @@
+-- Each report's gap slippage; see the PostgreSQL migration.
+
+ALTER TABLE performance_reports ADD COLUMN gap_slippage TEXT;
@@ -0,0 +1 @@
+ALTER TABLE klines DROP COLUMN is_suspect;
@@ -0,0 +1,3 @@
+-- Klines saved despite failing validation; see the PostgreSQL migration.
+
+ALTER TABLE klines ADD COLUMN is_suspect INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,5 @@
+ALTER TABLE performance_reports DROP COLUMN exposure_adjusted_sharpe;
+ALTER TABLE performance_reports DROP COLUMN return_on_margin_pct;
+ALTER TABLE performance_reports DROP COLUMN time_in_market_pct;
+ALTER TABLE performance_reports DROP COLUMN peak_exposure_pct;
+ALTER TABLE performance_reports DROP COLUMN average_exposure_pct;
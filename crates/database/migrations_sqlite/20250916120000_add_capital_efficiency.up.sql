@@ -0,0 +1,7 @@
+-- Each report's capital efficiency metrics; see the PostgreSQL migration.
+
+ALTER TABLE performance_reports ADD COLUMN average_exposure_pct TEXT;
+ALTER TABLE performance_reports ADD COLUMN peak_exposure_pct TEXT;
+ALTER TABLE performance_reports ADD COLUMN time_in_market_pct TEXT;
+ALTER TABLE performance_reports ADD COLUMN return_on_margin_pct TEXT;
+ALTER TABLE performance_reports ADD COLUMN exposure_adjusted_sharpe TEXT;
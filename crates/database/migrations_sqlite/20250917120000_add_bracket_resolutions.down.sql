@@ -0,0 +1,2 @@
+ALTER TABLE backtest_runs DROP COLUMN brackets_target_first;
+ALTER TABLE backtest_runs DROP COLUMN brackets_stop_first;
@@ -0,0 +1,4 @@
+-- How each run resolved the bars that reached both of a position's exits; see the PostgreSQL migration.
+
+ALTER TABLE backtest_runs ADD COLUMN brackets_stop_first INTEGER;
+ALTER TABLE backtest_runs ADD COLUMN brackets_target_first INTEGER;
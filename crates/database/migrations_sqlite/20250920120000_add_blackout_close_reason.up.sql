@@ -0,0 +1,27 @@
+-- The Blackout close reason; see the PostgreSQL migration.
+-- SQLite cannot change a CHECK constraint, so trades is rebuilt.
+
+CREATE TABLE trades_rebuilt (
+    trade_id TEXT PRIMARY KEY,
+    run_id TEXT NOT NULL REFERENCES backtest_runs(run_id) ON DELETE CASCADE,
+    symbol TEXT NOT NULL,
+    entry_price TEXT NOT NULL,
+    entry_qty TEXT NOT NULL,
+    entry_timestamp TEXT NOT NULL,
+    exit_price TEXT NOT NULL,
+    exit_qty TEXT NOT NULL,
+    exit_timestamp TEXT NOT NULL,
+    entry_side TEXT CHECK (entry_side IN ('BUY', 'SELL')),
+    entry_fee TEXT,
+    exit_fee TEXT,
+    fee_asset TEXT,
+    close_reason TEXT NOT NULL DEFAULT 'Signal' CHECK (close_reason IN ('Signal', 'StopLoss', 'TakeProfit', 'TimeLimit', 'Blackout')),
+    initial_risk TEXT
+);
+INSERT INTO trades_rebuilt
+SELECT trade_id, run_id, symbol, entry_price, entry_qty, entry_timestamp, exit_price, exit_qty, exit_timestamp,
+       entry_side, entry_fee, exit_fee, fee_asset, close_reason, initial_risk
+FROM trades;
+DROP TABLE trades;
+ALTER TABLE trades_rebuilt RENAME TO trades;
+CREATE INDEX idx_trades_run_id ON trades(run_id);
@@ -0,0 +1,280 @@
+//! Backing the PostgreSQL database up to a file, and restoring it from one.
+//!
+//! A backup is a gzipped file of JSON lines. The first line is a `BackupManifest`; the rows of
+//! each table it lists follow in its order, one JSON object per row. Tables are listed parents
+//! first, so loading them in order satisfies their foreign keys. Every table is read in one
+//! repeatable-read transaction, so the backup is a consistent snapshot even while the engine
+//! is writing.
+
+use crate::{DbError, DbRepository};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::PgConnection;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// The version of the backup file layout. `restore` refuses files of any other version.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// Rows inserted per statement when restoring.
+const RESTORE_BATCH: usize = 1000;
+
+/// The first line of a backup: what it holds and what wrote it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    /// The version of the zenith binary that wrote the backup.
+    pub engine_version: String,
+    /// The latest migration applied to the database when it was backed up.
+    pub migration_version: i64,
+    pub created_at: DateTime<Utc>,
+    /// The tables in the backup, in the order their rows follow the manifest.
+    pub tables: Vec<BackupTable>,
+}
+
+/// One table in a backup.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupTable {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: u64,
+}
+
+/// The latest migration this binary carries.
+pub fn latest_migration() -> i64 {
+    sqlx::migrate!("./migrations")
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+impl DbRepository {
+    /// Writes a snapshot of every table to a gzipped backup at `path`, tagged with
+    /// `engine_version` and the database's migration level, and returns its manifest.
+    ///
+    /// The file is written next to `path` and renamed into place once complete, so a backup
+    /// that fails part way never leaves a truncated file at `path`.
+    pub async fn backup(&self, path: &Path, engine_version: &str) -> Result<BackupManifest, DbError> {
+        let pool = self.postgres("backup")?;
+        let mut tx = pool.begin().await?;
+        sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY").execute(&mut *tx).await?;
+
+        let mut tables = Vec::new();
+        for (name, columns) in list_tables(&mut tx).await? {
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM {}", quote(&name))).fetch_one(&mut *tx).await?;
+            tables.push(BackupTable { name, columns, rows: rows as u64 });
+        }
+        let manifest = BackupManifest {
+            format_version: BACKUP_FORMAT_VERSION,
+            engine_version: engine_version.to_string(),
+            migration_version: applied_migration(&mut tx).await?,
+            created_at: Utc::now(),
+            tables,
+        };
+
+        let partial = path.with_file_name(format!(
+            "{}.partial",
+            path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default()
+        ));
+        let mut out = GzEncoder::new(BufWriter::new(File::create(&partial)?), Compression::default());
+        serde_json::to_writer(&mut out, &manifest)?;
+        out.write_all(b"\n")?;
+        for table in &manifest.tables {
+            let query = format!("SELECT row_to_json(t)::text FROM {} t", quote(&table.name));
+            let mut rows = sqlx::query_scalar::<_, String>(&query).fetch(&mut *tx);
+            while let Some(row) = rows.try_next().await? {
+                out.write_all(row.as_bytes())?;
+                out.write_all(b"\n")?;
+            }
+        }
+        out.finish()?.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+        tx.commit().await?;
+        std::fs::rename(&partial, path)?;
+
+        tracing::info!(
+            "Backed up {} tables ({} rows) at migration {} to {}",
+            manifest.tables.len(),
+            manifest.tables.iter().map(|table| table.rows).sum::<u64>(),
+            manifest.migration_version,
+            path.display()
+        );
+        Ok(manifest)
+    }
+
+    /// Loads the backup at `path` into the database and returns its manifest.
+    ///
+    /// The manifest is checked before anything is written: a backup in another format, or
+    /// from a schema newer than this binary's migrations, is refused. So is a database that
+    /// already holds data, unless `replace` is set, in which case every table is emptied
+    /// first. The load runs in one transaction, so a failed restore leaves the database as
+    /// it was.
+    pub async fn restore(&self, path: &Path, replace: bool) -> Result<BackupManifest, DbError> {
+        let mut lines = BufReader::new(GzDecoder::new(File::open(path)?)).lines();
+        let first = lines.next().ok_or_else(|| DbError::InvalidBackup("the file is empty".to_string()))??;
+        let manifest: BackupManifest = serde_json::from_str(&first)
+            .map_err(|e| DbError::InvalidBackup(format!("the manifest could not be read: {}", e)))?;
+        if manifest.format_version != BACKUP_FORMAT_VERSION {
+            return Err(DbError::InvalidBackup(format!(
+                "it is in format {}, and this binary reads format {}",
+                manifest.format_version, BACKUP_FORMAT_VERSION
+            )));
+        }
+        let supported = latest_migration();
+        if manifest.migration_version > supported {
+            return Err(DbError::NewerSchema { backup: manifest.migration_version, supported });
+        }
+
+        let pool = self.postgres("restore")?;
+        let mut tx = pool.begin().await?;
+        let targets: HashMap<String, Vec<String>> = list_tables(&mut tx).await?.into_iter().collect();
+        if let Some(table) = manifest.tables.iter().find(|table| !targets.contains_key(&table.name)) {
+            return Err(DbError::InvalidBackup(format!("table `{}` does not exist in this database", table.name)));
+        }
+
+        if replace {
+            let all = targets.keys().map(|name| quote(name)).collect::<Vec<_>>().join(", ");
+            sqlx::query(&format!("TRUNCATE {} RESTART IDENTITY CASCADE", all)).execute(&mut *tx).await?;
+        } else {
+            let mut names: Vec<&String> = targets.keys().collect();
+            names.sort();
+            for name in names {
+                let occupied: bool =
+                    sqlx::query_scalar(&format!("SELECT EXISTS (SELECT 1 FROM {})", quote(name))).fetch_one(&mut *tx).await?;
+                if occupied {
+                    return Err(DbError::NotEmpty(name.clone()));
+                }
+            }
+        }
+
+        for table in &manifest.tables {
+            // Columns the schema has dropped since the backup are left out; ones it has added
+            // since take their defaults.
+            let known: HashSet<&String> = targets[&table.name].iter().collect();
+            let columns = table.columns.iter().filter(|column| known.contains(column)).map(|column| quote(column)).collect::<Vec<_>>().join(", ");
+            let insert = format!(
+                "INSERT INTO {table} ({columns}) OVERRIDING SYSTEM VALUE SELECT {columns} FROM json_populate_recordset(NULL::{table}, $1::json)",
+                table = quote(&table.name),
+            );
+
+            let mut remaining = table.rows;
+            while remaining > 0 {
+                let take = remaining.min(RESTORE_BATCH as u64);
+                let mut batch = String::from("[");
+                for i in 0..take {
+                    let row = lines
+                        .next()
+                        .ok_or_else(|| DbError::InvalidBackup(format!("it ends part way through table `{}`", table.name)))??;
+                    if i > 0 {
+                        batch.push(',');
+                    }
+                    batch.push_str(&row);
+                }
+                batch.push(']');
+                sqlx::query(&insert).bind(batch).execute(&mut *tx).await?;
+                remaining -= take;
+            }
+
+            let restored: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM {}", quote(&table.name))).fetch_one(&mut *tx).await?;
+            if restored as u64 != table.rows {
+                return Err(DbError::InvalidBackup(format!(
+                    "table `{}` restored {} rows, and the manifest lists {}",
+                    table.name, restored, table.rows
+                )));
+            }
+        }
+        if lines.any(|line| line.map_or(true, |line| !line.is_empty())) {
+            return Err(DbError::InvalidBackup("it holds more rows than the manifest lists".to_string()));
+        }
+
+        // Move every sequence past the ids just loaded, so new rows do not collide with them.
+        let sequenced: Vec<(String, String)> = sqlx::query_as(
+            "SELECT table_name::text, column_name::text FROM information_schema.columns \
+             WHERE table_schema = current_schema() AND (column_default LIKE 'nextval(%' OR is_identity = 'YES')",
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for (table, column) in sequenced {
+            sqlx::query(&format!(
+                "SELECT setval(pg_get_serial_sequence($1, $2), COALESCE((SELECT MAX({column}) FROM {table}), 0) + 1, false)",
+                column = quote(&column),
+                table = quote(&table),
+            ))
+            .bind(quote(&table))
+            .bind(&column)
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+
+        tracing::info!(
+            "Restored {} tables ({} rows) from a backup taken at {} by zenith {} at migration {}",
+            manifest.tables.len(),
+            manifest.tables.iter().map(|table| table.rows).sum::<u64>(),
+            manifest.created_at,
+            manifest.engine_version,
+            manifest.migration_version
+        );
+        Ok(manifest)
+    }
+}
+
+/// The latest migration applied to the database.
+async fn applied_migration(conn: &mut PgConnection) -> Result<i64, DbError> {
+    Ok(sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) FROM _sqlx_migrations WHERE success").fetch_one(conn).await?)
+}
+
+/// The schema's tables other than sqlx's own, each with its writable columns, ordered so that
+/// every table comes after the tables its foreign keys reference.
+async fn list_tables(conn: &mut PgConnection) -> Result<Vec<(String, Vec<String>)>, DbError> {
+    let tables: Vec<(String, Vec<String>)> = sqlx::query_as(
+        "SELECT c.table_name::text, array_agg(c.column_name::text ORDER BY c.ordinal_position) \
+         FROM information_schema.columns c \
+         JOIN information_schema.tables t ON t.table_schema = c.table_schema AND t.table_name = c.table_name \
+         WHERE c.table_schema = current_schema() AND t.table_type = 'BASE TABLE' \
+           AND c.table_name <> '_sqlx_migrations' AND c.is_generated = 'NEVER' \
+         GROUP BY c.table_name ORDER BY c.table_name",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    let references: Vec<(String, String)> = sqlx::query_as(
+        "SELECT child.relname::text, parent.relname::text FROM pg_constraint c \
+         JOIN pg_class child ON child.oid = c.conrelid \
+         JOIN pg_class parent ON parent.oid = c.confrelid \
+         JOIN pg_namespace n ON n.oid = child.relnamespace \
+         WHERE c.contype = 'f' AND n.nspname = current_schema() AND c.conrelid <> c.confrelid",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    // Repeatedly take, in name order, the tables whose parents are all placed.
+    let mut ordered = Vec::with_capacity(tables.len());
+    let mut placed = HashSet::new();
+    let mut pending = tables;
+    while !pending.is_empty() {
+        let (ready, blocked): (Vec<_>, Vec<_>) = pending.into_iter().partition(|(name, _)| {
+            references.iter().all(|(child, parent)| child != name || placed.contains(parent))
+        });
+        if ready.is_empty() {
+            // A cycle of foreign keys; the order among its tables cannot satisfy them all.
+            ordered.extend(blocked);
+            break;
+        }
+        placed.extend(ready.iter().map(|(name, _)| name.clone()));
+        ordered.extend(ready);
+        pending = blocked;
+    }
+    Ok(ordered)
+}
+
+/// `identifier` quoted for use in SQL.
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
@@ -1,7 +1,12 @@
 use crate::error::DbError;
+use crate::repository::DbRepository;
 use dotenvy::dotenv;
-use sqlx::{postgres::PgPoolOptions, PgPool};
+use sqlx::migrate::Migrate;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::{postgres::PgPoolOptions, PgPool, SqlitePool};
+use std::collections::HashSet;
 use std::env;
+use std::str::FromStr;
 use std::time::Duration;
 
 /// Establishes a connection pool to the PostgreSQL database.
@@ -16,6 +21,8 @@ pub async fn connect() -> Result<PgPool, DbError> {
     let database_url = env::var("DATABASE_URL")
         .map_err(|_e| DbError::ConnectionConfigError("DATABASE_URL must be set.".to_string()))?;
 
+    // The optimizer holds up to one connection per in-flight run, so its `max_concurrency`
+    // must not exceed this limit.
     let pool = PgPoolOptions::new()
         .max_connections(10)
         .acquire_timeout(Duration::from_secs(5))
@@ -33,4 +40,60 @@ pub async fn run_migrations(pool: &PgPool) -> Result<(), DbError> {
     // Use a relative path from the crate root
     sqlx::migrate!("./migrations").run(pool).await?;
     Ok(())
-}
\ No newline at end of file
+}
+
+/// The migrations `run_migrations` would apply, as "<version> <description>", oldest first.
+///
+/// Nothing is changed: a database that has never been migrated has every migration pending.
+pub async fn pending_migrations(pool: &PgPool) -> Result<Vec<String>, DbError> {
+    let migrated: bool = sqlx::query_scalar("SELECT to_regclass('_sqlx_migrations') IS NOT NULL").fetch_one(pool).await?;
+    let applied: HashSet<i64> = if migrated {
+        let mut conn = pool.acquire().await?;
+        conn.list_applied_migrations().await?.into_iter().map(|migration| migration.version).collect()
+    } else {
+        HashSet::new()
+    };
+    Ok(sqlx::migrate!("./migrations")
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration() && !applied.contains(&migration.version))
+        .map(|migration| format!("{} {}", migration.version, migration.description))
+        .collect())
+}
+
+/// Connects to the database `DATABASE_URL` names and applies its migrations.
+///
+/// A `sqlite:` URL, such as `sqlite://zenith.db`, opens that SQLite file, creating it if it
+/// does not exist, for research on a machine without a PostgreSQL server. Any other URL is
+/// connected to as with `connect`.
+pub async fn connect_repository() -> Result<DbRepository, DbError> {
+    // A missing .env file is fine here; the variable may come from the environment.
+    let _ = dotenv();
+    let database_url = env::var("DATABASE_URL")
+        .map_err(|_e| DbError::ConnectionConfigError("DATABASE_URL must be set.".to_string()))?;
+
+    if database_url.starts_with("sqlite:") {
+        let pool = connect_sqlite(&database_url).await?;
+        run_sqlite_migrations(&pool).await?;
+        return Ok(DbRepository::sqlite(pool));
+    }
+    let pool = connect().await?;
+    run_migrations(&pool).await?;
+    Ok(DbRepository::new(pool))
+}
+
+/// Opens the SQLite database at `url`, creating the file if it does not exist.
+pub async fn connect_sqlite(url: &str) -> Result<SqlitePool, DbError> {
+    let options = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(4)
+        .acquire_timeout(Duration::from_secs(5))
+        .connect_with(options)
+        .await?;
+    Ok(pool)
+}
+
+/// Applies the SQLite migrations, which create the subset of the schema SQLite supports.
+pub async fn run_sqlite_migrations(pool: &SqlitePool) -> Result<(), DbError> {
+    sqlx::migrate!("./migrations_sqlite").run(pool).await?;
+    Ok(())
+}
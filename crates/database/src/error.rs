@@ -16,4 +16,22 @@ pub enum DbError {
 
     #[error("The requested data was not found in the database.")]
     NotFound,
-}
\ No newline at end of file
+
+    #[error("`{0}` is not supported on SQLite; it needs a PostgreSQL database.")]
+    Unsupported(&'static str),
+
+    #[error("Failed to read or write the backup file: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("The file is not a backup this version can restore: {0}")]
+    InvalidBackup(String),
+
+    #[error("The backup is at migration {backup}, newer than the {supported} this binary knows; restore it with a newer zenith.")]
+    NewerSchema { backup: i64, supported: i64 },
+
+    #[error("The database already holds data (table `{0}` is not empty); restore with --replace to overwrite it.")]
+    NotEmpty(String),
+
+    #[error("Refused to save an invalid {symbol} kline: {source}")]
+    InvalidKline { symbol: String, #[source] source: core_types::InvalidKline },
+}
@@ -1,7 +1,8 @@
 //! # Zenith Database Crate
 //!
 //! This crate acts as a high-level, application-specific interface to the
-//! PostgreSQL database. It is the system's "permanent archive."
+//! PostgreSQL database. It is the system's "permanent archive." For local research, a
+//! SQLite file can stand in for PostgreSQL in single backtest runs.
 //!
 //! ## Architectural Principles
 //!
@@ -9,7 +10,8 @@
 //!   logic. It provides a clean, abstract API to the rest of the application, hiding
 //!   the underlying SQL and database implementation details.
 //! - **Compile-Time Safety:** Uses `sqlx` to check all SQL queries against the live
-//!   database schema at compile time, preventing a large class of runtime errors.
+//!   database schema at compile time, preventing a large class of runtime errors. The
+//!   SQLite queries, which cannot be checked against the same database, are checked at runtime.
 //! - **Asynchronous & Pooled:** All operations are asynchronous, and it uses a
 //!   connection pool (`PgPool`) for high-performance, concurrent database access.
 //!
@@ -17,16 +19,24 @@
 //!
 //! - `connect`: The async function to establish the database connection pool.
 //! - `run_migrations`: A utility to apply database migrations, ensuring the schema is up-to-date.
+//! - `pending_migrations`: Lists the migrations `run_migrations` would apply, without applying them.
+//! - `connect_repository`: Connects to the PostgreSQL or SQLite database `DATABASE_URL` names
+//!   and migrates it, for commands that run on either.
 //! - `DbRepository`: The main struct that holds the connection pool and provides all
 //!   the high-level data access methods (e.g., `save_performance_report`).
+//! - `DbRepository::backup` / `DbRepository::restore`: Write a consistent snapshot of the
+//!   database to a compressed file with a `BackupManifest`, and load one back.
 //! - `DbError`: The specific error types that can be returned from this crate.
 
 // Declare the modules that constitute this crate.
+pub mod backup;
 pub mod connection;
 pub mod error;
 pub mod repository;
+mod sqlite;
 
 // Re-export the key components to create a clean, public-facing API.
-pub use connection::{connect, run_migrations};
+pub use backup::{latest_migration, BackupManifest, BackupTable, BACKUP_FORMAT_VERSION};
+pub use connection::{connect, connect_repository, connect_sqlite, pending_migrations, run_migrations, run_sqlite_migrations};
 pub use error::DbError;
-pub use repository::{DbBacktestRun, DbOptimizationJob, DbRepository, FullReport, WfoJob, WfoRun};
\ No newline at end of file
+pub use repository::{Annotation, AnnotationFilter, AnnotationTarget, BackfillProgress, BacktestRunDetails, DbBacktestRun, DbOptimizationJob, DbRepository, DecisionAuditEntry, DecisionAuditRecord, EquityDataPoint, FullReport, KlineCoverage, LiveFill, LivePosition, LivePositionFilter, LivePositionStatus, OrderIntent, OrderIntentState, PerformanceRollup, PositionContext, RiskStateEntry, RollupGranularity, RunKlineRange, RunMetadata, StoredPortfolioEvent, SuspectKline, SymbolExecutionQuality, WfoJob, WfoRun};
\ No newline at end of file
@@ -1,22 +1,44 @@
+use crate::sqlite;
 use crate::DbError;
-use analytics::PerformanceReport;
-use chrono::{DateTime, Utc};
-use core_types::{Kline, Trade, Execution, OrderSide};
+use analytics::{ExitStats, PerformanceReport, RDistribution};
+use chrono::{DateTime, NaiveDate, Utc};
+use core_types::{BookSummary, BookTicker, CloseReason, DecisionStage, Interval, InvalidKline, InvalidKlinePolicy, Kline, Trade, Execution, OrderPlacement, OrderSide, PortfolioEvent, PositionFill, PriceType, RecordedPortfolioEvent, StrategyId};
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use serde_json::Value as JsonValue;
 use sqlx::postgres::PgPool;
 use sqlx::postgres::Postgres;
+use sqlx::sqlite::SqlitePool;
 use sqlx::Row;
+use sqlx::types::Json;
 use serde::{Deserialize, Serialize};
 use sqlx::Transaction;
+use std::collections::HashMap;
 use uuid::Uuid;
 use sqlx::FromRow;
 
 /// The `DbRepository` provides a high-level, application-specific interface
 /// to the database. It encapsulates all SQL queries and data access logic.
+///
+/// A repository is backed by PostgreSQL or, for local research, by SQLite. SQLite supports
+/// what a single backtest run needs: klines, mark price klines and backfill progress,
+/// optimization jobs and backtest runs, and each run's report, trades and equity curve. It
+/// also stores recorded book tickers, for ML datasets.
+/// Everything else returns `DbError::Unsupported` on SQLite.
+///
+/// Klines are checked with `Kline::validate` before they are saved; what happens to those
+/// that fail is the repository's `InvalidKlinePolicy`, rejecting them by default.
 #[derive(Debug, Clone)]
 pub struct DbRepository {
-    pool: PgPool,
+    backend: Backend,
+    invalid_klines: InvalidKlinePolicy,
+}
+
+/// The connection pool of the database behind a repository.
+#[derive(Debug, Clone)]
+enum Backend {
+    Postgres(PgPool),
+    Sqlite(SqlitePool),
 }
 
 // Define a simple struct for an equity curve point
@@ -48,6 +70,13 @@ pub struct DbOptimizationJob {
     pub symbol: String,
     pub job_status: String,
     pub created_at: DateTime<Utc>,
+    /// The runs finished so far, failed ones included.
+    pub completed_runs: i32,
+    pub total_runs: i32,
+    /// When the progress was last saved.
+    pub updated_at: DateTime<Utc>,
+    /// When the remaining runs should be done, going by the recent runs' durations.
+    pub estimated_completion_at: Option<DateTime<Utc>>,
 }
 /// Represents a row from the `wfo_jobs` table.
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
@@ -98,7 +127,408 @@ pub struct FullReport {
     pub average_win: Option<Decimal>,
     pub average_loss: Option<Decimal>,
     pub payoff_ratio: Option<Decimal>,
+    pub max_consecutive_losses: Option<i32>,
     pub average_holding_period: Option<String>,
+    pub maker_fees_paid: Option<Decimal>,
+    pub taker_fees_paid: Option<Decimal>,
+    pub total_spread_cost: Option<Decimal>,
+    pub gap_slippage: Option<Decimal>,
+    /// Statistics per close reason. NULL for reports saved before it was computed.
+    pub exit_breakdown: Option<Json<Vec<ExitStats>>>,
+    /// R-multiple metrics. NULL for reports saved before they were computed, or without a
+    /// trade of known initial risk.
+    pub average_r: Option<Decimal>,
+    pub expectancy_r: Option<Decimal>,
+    pub r_distribution: Option<Json<RDistribution>>,
+    /// Capital efficiency metrics. NULL for reports saved before they were computed, or from
+    /// runs that did not record their exposure.
+    pub average_exposure_pct: Option<Decimal>,
+    pub peak_exposure_pct: Option<Decimal>,
+    pub time_in_market_pct: Option<Decimal>,
+    pub return_on_margin_pct: Option<Decimal>,
+    pub exposure_adjusted_sharpe: Option<Decimal>,
+
+    // Execution metadata from backtest_runs (NULL for runs that predate it or never finished)
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub bars_processed: Option<i64>,
+    pub signals_filtered: Option<i64>,
+    /// How the run resolved the bars that reached both a stop-loss and a take-profit.
+    pub brackets_stop_first: Option<i64>,
+    pub brackets_target_first: Option<i64>,
+    pub engine_version: Option<String>,
+    pub config_hash: Option<String>,
+    /// The optimizer objective's value for the run. NULL for runs that failed the hard
+    /// filters, lacked the metric, or predate objectives.
+    pub objective_value: Option<Decimal>,
+}
+
+/// Describes how a finished backtest run was produced.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub bars_processed: i64,
+    /// The approved signals refused by the minimum expected-move filter.
+    pub signals_filtered: i64,
+    /// The bars that reached both a position's stop-loss and its take-profit, and closed it
+    /// at the stop-loss.
+    pub brackets_stop_first: i64,
+    /// The bars that reached both and closed the position at the take-profit, which only the
+    /// `sub_bar` bracket resolution finds.
+    pub brackets_target_first: i64,
+    /// The version of the engine that produced the run.
+    pub engine_version: String,
+    /// A stable hash of the configuration sections that shape every run (see `configuration::config_hash`).
+    pub config_hash: String,
+    /// The kline interval the run replayed.
+    pub interval: String,
+    /// The open time of the first bar the run replayed. `None` if it replayed none.
+    pub data_start: Option<DateTime<Utc>>,
+    /// The close time of the last bar the run replayed.
+    pub data_end: Option<DateTime<Utc>>,
+}
+
+/// Where the price data behind a backtest run lives: its job's symbol, and the interval and
+/// span of bars recorded with the run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunKlineRange {
+    pub symbol: String,
+    pub interval: String,
+    pub data_start: DateTime<Utc>,
+    pub data_end: DateTime<Utc>,
+}
+
+/// The klines stored for one symbol and interval.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KlineCoverage {
+    pub symbol: String,
+    pub interval: String,
+    pub bars: i64,
+    pub first_open_time: DateTime<Utc>,
+    pub last_open_time: DateTime<Utc>,
+    /// How many were saved flagged as suspect.
+    pub suspect: i64,
+}
+
+/// A stored kline that fails `Kline::validate`, or that was saved flagged as suspect.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuspectKline {
+    pub symbol: String,
+    pub kline: Kline,
+    pub is_suspect: bool,
+    /// Why it fails validation; `None` for a flagged kline that passes it now.
+    pub error: Option<InvalidKline>,
+}
+
+/// How far a backfill started at `range_start` has got: every bar up to
+/// `last_completed_range_end` has been saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    pub range_start: DateTime<Utc>,
+    pub last_completed_range_end: DateTime<Utc>,
+}
+
+/// Represents a single stage of a trading decision from the `decision_audit` table.
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DecisionAuditRecord {
+    pub audit_id: i64,
+    pub decision_id: Uuid,
+    pub stage: String,
+    pub symbol: String,
+    pub payload: JsonValue,
+    pub recorded_at: DateTime<Utc>,
+    /// The order the stage was recorded in by its engine run. None for stages recorded before
+    /// it was kept.
+    pub sequence: Option<i64>,
+}
+
+/// An event the live portfolio applied, from the `portfolio_events` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredPortfolioEvent {
+    /// Orders the events across engine runs; `sequence` restarts at 1 in each.
+    pub event_id: i64,
+    pub sequence: u64,
+    pub event: PortfolioEvent,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// A stage of a trading decision to append to the `decision_audit` table, recorded at
+/// `recorded_at` rather than when it is written.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecisionAuditEntry {
+    pub decision_id: Uuid,
+    pub stage: DecisionStage,
+    pub symbol: String,
+    pub payload: JsonValue,
+    pub recorded_at: DateTime<Utc>,
+    /// The order of the stage among those its engine run recorded, which the chain of a
+    /// decision is read back in.
+    pub sequence: u64,
+}
+
+/// Whether a live position is still open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LivePositionStatus {
+    Open,
+    Closed,
+}
+
+/// Narrows `get_live_positions`. Unset fields do not filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct LivePositionFilter {
+    pub status: Option<LivePositionStatus>,
+    pub symbol: Option<String>,
+    /// The earliest entry time, inclusive.
+    pub from: Option<DateTime<Utc>>,
+    /// The latest entry time, inclusive.
+    pub to: Option<DateTime<Utc>>,
+    /// Only the positions with these IDs.
+    #[serde(skip)]
+    pub position_ids: Option<Vec<Uuid>>,
+}
+
+/// A live position rebuilt from its fills in the `live_executions` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LivePosition {
+    pub position_id: Uuid,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub status: LivePositionStatus,
+    /// The time of the position's first recorded fill. None for an open position without
+    /// recorded fills, e.g. one synced from the exchange at startup.
+    pub entry_time: Option<DateTime<Utc>>,
+    /// The time of the fill that closed the position.
+    pub exit_time: Option<DateTime<Utc>>,
+    /// The volume-weighted price of the opening fill and every add. None when the position
+    /// was opened before its fills were recorded.
+    pub avg_entry_price: Option<Decimal>,
+    /// The volume-weighted price of the fills that reduced or closed the position.
+    pub avg_exit_price: Option<Decimal>,
+    /// The position's current size; zero once closed.
+    pub quantity: Decimal,
+    /// The largest size the position reached.
+    pub max_quantity: Decimal,
+    /// The P&L locked in by the position's reducing and closing fills, net of all its fees.
+    pub realized_pnl: Decimal,
+    pub fees: Decimal,
+    /// The P&L of the open quantity at the latest mark, for open positions the running
+    /// engine reports.
+    pub unrealized_pnl: Option<Decimal>,
+    /// Why the position was closed. None while open, and for positions the engine closed
+    /// outside a trading decision (the dead-man's switch).
+    pub close_reason: Option<CloseReason>,
+}
+
+/// One row of the `live_executions` table: the part of a live execution that changed one
+/// position.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveFill {
+    pub execution_id: Uuid,
+    pub position_id: Uuid,
+    pub symbol: String,
+    /// The side of the position, not of the execution: `Buy` for a long.
+    pub position_side: OrderSide,
+    /// True when the fill opened or added to the position, false when it reduced or closed it.
+    pub is_entry: bool,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub fee: Decimal,
+    pub fee_asset: String,
+    pub is_maker: bool,
+    /// The gross P&L the fill locked in against the average entry price. Zero for entries.
+    pub realized_pnl: Decimal,
+    /// Why the position was closed, on the fill that closed it.
+    pub close_reason: Option<CloseReason>,
+    pub executed_at: DateTime<Utc>,
+}
+
+/// How one symbol's live limit orders filled over a period, from the `live_execution_quality`
+/// table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SymbolExecutionQuality {
+    pub symbol: String,
+    pub limit_fills: i64,
+    pub maker_fills: i64,
+    pub maker_fill_ratio: Decimal,
+    /// The mean saving of the fills against crossing the spread, in basis points of the mid
+    /// price. None when no fill's saving is known.
+    pub avg_improvement_bps: Option<Decimal>,
+}
+
+/// One row of the `open_position_context` table: what the live engine needs to keep managing
+/// a position after a restart, which the exchange does not report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionContext {
+    pub position_id: Uuid,
+    pub symbol: String,
+    pub side: OrderSide,
+    /// The strategy whose signal opened the position. None for positions the engine adopted.
+    pub strategy_id: Option<StrategyId>,
+    /// The signal that opened the position. None for positions the engine adopted.
+    pub entry_signal_id: Option<Uuid>,
+    pub stop_price: Option<Decimal>,
+    pub take_profit_price: Option<Decimal>,
+    pub opened_at: DateTime<Utc>,
+    /// When the engine saw the position close; None while it is open.
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+/// One row of the `risk_state` table: a value of the global risk manager's state, kept across
+/// restarts. `scope` is `"global"` or the symbol the value belongs to.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskStateEntry {
+    pub scope: String,
+    pub key: String,
+    /// The value; null when it was cleared.
+    pub value: JsonValue,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Where an order the live engine placed stands, in the `order_intents` table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OrderIntentState {
+    /// Written before the order was sent; what became of it is not known yet.
+    Sent,
+    /// The order filled, and its execution was recorded.
+    Filled,
+    /// The exchange never filled the order, e.g. because it never arrived.
+    Expired,
+    /// The exchange rejected the order.
+    Failed,
+}
+
+impl OrderIntentState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderIntentState::Sent => "sent",
+            OrderIntentState::Filled => "filled",
+            OrderIntentState::Expired => "expired",
+            OrderIntentState::Failed => "failed",
+        }
+    }
+
+    fn from_db(state: &str) -> Self {
+        match state {
+            "filled" => OrderIntentState::Filled,
+            "expired" => OrderIntentState::Expired,
+            "failed" => OrderIntentState::Failed,
+            _ => OrderIntentState::Sent,
+        }
+    }
+}
+
+/// One row of the `order_intents` table: an order the live engine was about to place, written
+/// before it was sent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderIntent {
+    pub client_order_id: Uuid,
+    pub decision_id: Option<Uuid>,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    pub reduce_only: bool,
+    pub state: OrderIntentState,
+    /// The execution recorded for the order, once it filled.
+    pub execution_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+/// The length of the periods live performance is rolled up over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RollupGranularity {
+    /// A UTC day.
+    #[default]
+    Daily,
+    /// A week from Monday to Sunday, UTC.
+    Weekly,
+}
+
+impl RollupGranularity {
+    /// The table the rollups of this granularity are stored in.
+    fn table(&self) -> &'static str {
+        match self {
+            RollupGranularity::Daily => "daily_performance",
+            RollupGranularity::Weekly => "weekly_performance",
+        }
+    }
+}
+
+/// The live engine's performance over one day or week, from the `daily_performance` or
+/// `weekly_performance` table.
+#[derive(Debug, Clone, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct PerformanceRollup {
+    /// The day rolled up, or the Monday the week starts on.
+    pub period_start: NaiveDate,
+    /// The P&L the period's reducing and closing fills locked in, net of every fill's fee.
+    pub net_pnl: Decimal,
+    pub fees: Decimal,
+    /// The number of reducing and closing fills; each counts as a trade.
+    pub trade_count: i32,
+    pub winning_trades: i32,
+    /// None for periods without trades.
+    pub win_rate_pct: Option<Decimal>,
+    /// The largest fall of the recorded equity from a peak within the period.
+    pub max_drawdown: Decimal,
+    pub max_drawdown_pct: Decimal,
+    /// The equity last recorded by the end of the period. None if none had been recorded.
+    pub ending_equity: Option<Decimal>,
+    pub computed_at: DateTime<Utc>,
+}
+
+/// What an annotation is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnotationTarget {
+    /// A trade of a backtest run, by its `trade_id`.
+    Trade,
+    /// A backtest run, by its `run_id`.
+    Run,
+    /// A live position, by its `position_id`.
+    Position,
+}
+
+impl AnnotationTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AnnotationTarget::Trade => "trade",
+            AnnotationTarget::Run => "run",
+            AnnotationTarget::Position => "position",
+        }
+    }
+
+    fn from_db(target_type: &str) -> Self {
+        match target_type {
+            "run" => AnnotationTarget::Run,
+            "position" => AnnotationTarget::Position,
+            _ => AnnotationTarget::Trade,
+        }
+    }
+}
+
+/// A note attached by hand to a trade, run or live position, from the `annotations` table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub annotation_id: Uuid,
+    pub target_type: AnnotationTarget,
+    pub target_id: Uuid,
+    pub author: String,
+    pub text: String,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Narrows `get_annotations`. Unset fields do not filter.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct AnnotationFilter {
+    pub target_type: Option<AnnotationTarget>,
+    pub target_id: Option<Uuid>,
+    /// Keeps the annotations carrying this tag.
+    pub tag: Option<String>,
 }
 
 /// Database-specific trade struct that matches the trades table schema
@@ -113,11 +543,119 @@ pub struct DbTrade {
     pub exit_price: Decimal,
     pub exit_qty: Decimal,
     pub exit_timestamp: DateTime<Utc>,
+    /// The side of the entry execution ('BUY' or 'SELL'). NULL for rows saved before it was recorded.
+    pub entry_side: Option<String>,
+    pub entry_fee: Option<Decimal>,
+    pub exit_fee: Option<Decimal>,
+    pub fee_asset: Option<String>,
+    pub close_reason: String,
+    /// NULL for trades without a stop at entry, or saved before risk was recorded.
+    pub initial_risk: Option<Decimal>,
 }
+
+impl DbTrade {
+    /// The side of the trade's entry. Rows saved before sides were recorded are read as longs.
+    fn entry_side(&self) -> OrderSide {
+        match self.entry_side.as_deref() {
+            Some("BUY") => OrderSide::Buy,
+            Some("SELL") => OrderSide::Sell,
+            other => {
+                tracing::warn!(trade_id = %self.trade_id, side = ?other, "Trade has no recorded entry side; assuming a long.");
+                OrderSide::Buy
+            }
+        }
+    }
+}
+
+impl From<DbTrade> for Trade {
+    /// Rebuilds a trade from its row. The executions' IDs are generated, as they are not stored.
+    fn from(db_trade: DbTrade) -> Self {
+        let entry_side = db_trade.entry_side();
+        let fee_asset = db_trade.fee_asset.clone().unwrap_or_default();
+
+        let entry_execution = Execution {
+            execution_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
+            client_order_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
+            symbol: db_trade.symbol.clone(),
+            side: entry_side,
+            price: db_trade.entry_price,
+            quantity: db_trade.entry_qty,
+            fee: db_trade.entry_fee.unwrap_or(Decimal::ZERO),
+            fee_asset: fee_asset.clone(),
+            timestamp: db_trade.entry_timestamp,
+            decision_id: None, // Not stored in DB
+            is_maker: false, // Not stored in DB
+            spread_cost: Decimal::ZERO, // Not stored in DB,
+            placement: None,
+        };
+
+        let exit_execution = Execution {
+            execution_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
+            client_order_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
+            symbol: db_trade.symbol.clone(),
+            side: entry_side.opposite(),
+            price: db_trade.exit_price,
+            quantity: db_trade.exit_qty,
+            fee: db_trade.exit_fee.unwrap_or(Decimal::ZERO),
+            fee_asset,
+            timestamp: db_trade.exit_timestamp,
+            decision_id: None, // Not stored in DB
+            is_maker: false, // Not stored in DB
+            spread_cost: Decimal::ZERO, // Not stored in DB,
+            placement: None,
+        };
+
+        Trade {
+            trade_id: db_trade.trade_id,
+            symbol: db_trade.symbol,
+            entry_execution,
+            exit_execution,
+            close_reason: CloseReason::from_db(&db_trade.close_reason),
+            initial_risk: db_trade.initial_risk,
+        }
+    }
+}
+
 impl DbRepository {
     /// Creates a new `DbRepository` with a shared database connection pool.
     pub fn new(pool: PgPool) -> Self {
-        Self { pool }
+        Self { backend: Backend::Postgres(pool), invalid_klines: InvalidKlinePolicy::default() }
+    }
+
+    /// Creates a `DbRepository` backed by a SQLite database, which supports the subset of
+    /// operations a single backtest run needs.
+    pub fn sqlite(pool: SqlitePool) -> Self {
+        Self { backend: Backend::Sqlite(pool), invalid_klines: InvalidKlinePolicy::default() }
+    }
+
+    /// Handles klines that fail validation according to `policy` when saving them.
+    pub fn with_invalid_kline_policy(mut self, policy: InvalidKlinePolicy) -> Self {
+        self.invalid_klines = policy;
+        self
+    }
+
+    /// Validates `symbol`'s `klines` before a save and returns which of them to flag as
+    /// suspect, failing on the first invalid one if they are rejected.
+    fn check_klines(&self, symbol: &str, klines: &[Kline]) -> Result<Vec<bool>, DbError> {
+        klines
+            .iter()
+            .map(|kline| match (kline.validate(), self.invalid_klines) {
+                (Ok(()), _) => Ok(false),
+                (Err(source), InvalidKlinePolicy::Reject) => Err(DbError::InvalidKline { symbol: symbol.to_string(), source }),
+                (Err(e), InvalidKlinePolicy::Flag) => {
+                    tracing::warn!(symbol, "Saving a suspect kline: {}", e);
+                    Ok(true)
+                }
+            })
+            .collect()
+    }
+
+    /// The Postgres pool, for an `operation` that SQLite does not support.
+    pub(crate) fn postgres(&self, operation: &'static str) -> Result<&PgPool, DbError> {
+        match &self.backend {
+            Backend::Postgres(pool) => Ok(pool),
+            Backend::Sqlite(_) => Err(DbError::Unsupported(operation)),
+        }
     }
 
     /// Fetches all optimization jobs from the database.
@@ -125,10 +663,19 @@ impl DbRepository {
     pub async fn get_all_optimization_jobs(&self) -> Result<Vec<DbOptimizationJob>, DbError> {
         let jobs = sqlx::query_as!(
             DbOptimizationJob,
-            "SELECT job_id, strategy_id, symbol, job_status, created_at FROM optimization_jobs ORDER BY created_at DESC"
-        ).fetch_all(&self.pool).await?;
+            "SELECT job_id, strategy_id, symbol, job_status, created_at, completed_runs, total_runs, updated_at, estimated_completion_at FROM optimization_jobs ORDER BY created_at DESC"
+        ).fetch_all(self.postgres("get_all_optimization_jobs")?).await?;
         Ok(jobs)
     }
+    /// Fetches a single optimization job by its ID.
+    pub async fn get_optimization_job(&self, job_id: Uuid) -> Result<DbOptimizationJob, DbError> {
+        let job = sqlx::query_as!(
+            DbOptimizationJob,
+            "SELECT job_id, strategy_id, symbol, job_status, created_at, completed_runs, total_runs, updated_at, estimated_completion_at FROM optimization_jobs WHERE job_id = $1",
+            job_id
+        ).fetch_optional(self.postgres("get_optimization_job")?).await?;
+        job.ok_or(DbError::NotFound)
+    }
     /// Fetches all backtest runs that were executed as 'Single Run' jobs.
     /// This joins with the performance report to provide a useful summary.
     pub async fn get_all_single_runs(&self) -> Result<Vec<FullReport>, DbError> {
@@ -136,7 +683,8 @@ impl DbRepository {
             FullReport,
             r#"
             SELECT
-                br.run_id as "run_id!", br.job_id as "job_id!", br.parameters as "parameters!", pr.report_id as "report_id?", pr.total_net_profit as "total_net_profit?", pr.gross_profit as "gross_profit?", pr.gross_loss as "gross_loss?", pr.profit_factor as "profit_factor?", pr.total_return_pct as "total_return_pct?", pr.max_drawdown as "max_drawdown?", pr.max_drawdown_pct as "max_drawdown_pct?", pr.sharpe_ratio as "sharpe_ratio?", pr.calmar_ratio as "calmar_ratio?", pr.total_trades as "total_trades?", pr.winning_trades as "winning_trades?", pr.losing_trades as "losing_trades?", pr.win_rate_pct as "win_rate_pct?", pr.average_win as "average_win?", pr.average_loss as "average_loss?", pr.payoff_ratio as "payoff_ratio?", pr.average_holding_period as "average_holding_period?"
+                br.run_id as "run_id!", br.job_id as "job_id!", br.parameters as "parameters!", pr.report_id as "report_id?", pr.total_net_profit as "total_net_profit?", pr.gross_profit as "gross_profit?", pr.gross_loss as "gross_loss?", pr.profit_factor as "profit_factor?", pr.total_return_pct as "total_return_pct?", pr.max_drawdown as "max_drawdown?", pr.max_drawdown_pct as "max_drawdown_pct?", pr.sharpe_ratio as "sharpe_ratio?", pr.calmar_ratio as "calmar_ratio?", pr.total_trades as "total_trades?", pr.winning_trades as "winning_trades?", pr.losing_trades as "losing_trades?", pr.win_rate_pct as "win_rate_pct?", pr.average_win as "average_win?", pr.average_loss as "average_loss?", pr.payoff_ratio as "payoff_ratio?", pr.max_consecutive_losses as "max_consecutive_losses?", pr.average_holding_period as "average_holding_period?", pr.maker_fees_paid as "maker_fees_paid?", pr.taker_fees_paid as "taker_fees_paid?", pr.total_spread_cost as "total_spread_cost?", pr.gap_slippage as "gap_slippage?", pr.exit_breakdown as "exit_breakdown?: Json<Vec<ExitStats>>", pr.average_r as "average_r?", pr.expectancy_r as "expectancy_r?", pr.r_distribution as "r_distribution?: Json<RDistribution>", pr.average_exposure_pct as "average_exposure_pct?", pr.peak_exposure_pct as "peak_exposure_pct?", pr.time_in_market_pct as "time_in_market_pct?", pr.return_on_margin_pct as "return_on_margin_pct?", pr.exposure_adjusted_sharpe as "exposure_adjusted_sharpe?",
+                br.started_at as "started_at?", br.finished_at as "finished_at?", br.bars_processed as "bars_processed?", br.signals_filtered as "signals_filtered?", br.brackets_stop_first as "brackets_stop_first?", br.brackets_target_first as "brackets_target_first?", br.engine_version as "engine_version?", br.config_hash as "config_hash?", br.objective_value as "objective_value?"
             FROM
                 performance_reports AS pr
             JOIN
@@ -149,7 +697,7 @@ impl DbRepository {
                 oj.created_at DESC
             "#
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.postgres("get_all_single_runs")?)
         .await?;
 
         Ok(reports)
@@ -157,11 +705,16 @@ impl DbRepository {
 
     /// Fetches the full, joined report for a single backtest run ID.
     pub async fn get_full_report_for_run(&self, run_id: Uuid) -> Result<FullReport, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_full_report_for_run(pool, run_id).await,
+        };
         let report = sqlx::query_as!(
             FullReport,
             r#"
             SELECT
-                br.run_id as "run_id!", br.job_id as "job_id!", br.parameters as "parameters!", pr.report_id as "report_id?", pr.total_net_profit as "total_net_profit?", pr.gross_profit as "gross_profit?", pr.gross_loss as "gross_loss?", pr.profit_factor as "profit_factor?", pr.total_return_pct as "total_return_pct?", pr.max_drawdown as "max_drawdown?", pr.max_drawdown_pct as "max_drawdown_pct?", pr.sharpe_ratio as "sharpe_ratio?", pr.calmar_ratio as "calmar_ratio?", pr.total_trades as "total_trades?", pr.winning_trades as "winning_trades?", pr.losing_trades as "losing_trades?", pr.win_rate_pct as "win_rate_pct?", pr.average_win as "average_win?", pr.average_loss as "average_loss?", pr.payoff_ratio as "payoff_ratio?", pr.average_holding_period as "average_holding_period?"
+                br.run_id as "run_id!", br.job_id as "job_id!", br.parameters as "parameters!", pr.report_id as "report_id?", pr.total_net_profit as "total_net_profit?", pr.gross_profit as "gross_profit?", pr.gross_loss as "gross_loss?", pr.profit_factor as "profit_factor?", pr.total_return_pct as "total_return_pct?", pr.max_drawdown as "max_drawdown?", pr.max_drawdown_pct as "max_drawdown_pct?", pr.sharpe_ratio as "sharpe_ratio?", pr.calmar_ratio as "calmar_ratio?", pr.total_trades as "total_trades?", pr.winning_trades as "winning_trades?", pr.losing_trades as "losing_trades?", pr.win_rate_pct as "win_rate_pct?", pr.average_win as "average_win?", pr.average_loss as "average_loss?", pr.payoff_ratio as "payoff_ratio?", pr.max_consecutive_losses as "max_consecutive_losses?", pr.average_holding_period as "average_holding_period?", pr.maker_fees_paid as "maker_fees_paid?", pr.taker_fees_paid as "taker_fees_paid?", pr.total_spread_cost as "total_spread_cost?", pr.gap_slippage as "gap_slippage?", pr.exit_breakdown as "exit_breakdown?: Json<Vec<ExitStats>>", pr.average_r as "average_r?", pr.expectancy_r as "expectancy_r?", pr.r_distribution as "r_distribution?: Json<RDistribution>", pr.average_exposure_pct as "average_exposure_pct?", pr.peak_exposure_pct as "peak_exposure_pct?", pr.time_in_market_pct as "time_in_market_pct?", pr.return_on_margin_pct as "return_on_margin_pct?", pr.exposure_adjusted_sharpe as "exposure_adjusted_sharpe?",
+                br.started_at as "started_at?", br.finished_at as "finished_at?", br.bars_processed as "bars_processed?", br.signals_filtered as "signals_filtered?", br.brackets_stop_first as "brackets_stop_first?", br.brackets_target_first as "brackets_target_first?", br.engine_version as "engine_version?", br.config_hash as "config_hash?", br.objective_value as "objective_value?"
             FROM
                 performance_reports AS pr
             JOIN
@@ -171,12 +724,90 @@ impl DbRepository {
             "#,
             run_id
         )
-        .fetch_one(&self.pool)
+        .fetch_one(pool)
         .await
         .map_err(|e| if let sqlx::Error::RowNotFound = e { DbError::NotFound } else { e.into() })?;
         
         Ok(report)
     }
+    /// Fetches the distinct symbols with stored klines, in alphabetical order.
+    pub async fn get_kline_symbols(&self) -> Result<Vec<String>, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_kline_symbols(pool).await,
+        };
+        let symbols = sqlx::query_scalar!("SELECT DISTINCT symbol FROM klines ORDER BY symbol")
+            .fetch_all(pool)
+            .await?;
+        Ok(symbols)
+    }
+
+    /// Summarizes the stored klines of each symbol and interval, in alphabetical order.
+    pub async fn get_kline_coverage(&self) -> Result<Vec<KlineCoverage>, DbError> {
+        let pool = self.postgres("get_kline_coverage")?;
+        let coverage = sqlx::query_as!(
+            KlineCoverage,
+            r#"
+            SELECT symbol, interval, COUNT(*) AS "bars!", MIN(open_time) AS "first_open_time!",
+                   MAX(open_time) AS "last_open_time!", COUNT(*) FILTER (WHERE is_suspect) AS "suspect!"
+            FROM klines
+            GROUP BY symbol, interval
+            ORDER BY symbol, interval
+            "#
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(coverage)
+    }
+
+    /// Scans the stored klines, of `symbol` or of every symbol, for those that fail
+    /// `Kline::validate` or were saved flagged as suspect, in symbol, interval and time order.
+    ///
+    /// The database narrows the scan to the rows breaking a rule it can check, so only those
+    /// are read and validated.
+    pub async fn find_suspect_klines(&self, symbol: Option<&str>) -> Result<Vec<SuspectKline>, DbError> {
+        let pool = self.postgres("find_suspect_klines")?;
+        let intervals: Vec<String> = Interval::ALL.iter().map(|interval| interval.as_str().to_string()).collect();
+        let spans: Vec<i64> = Interval::ALL.iter().map(|interval| interval.duration().as_millis() as i64 - 1).collect();
+        let rows = sqlx::query!(
+            r#"
+            SELECT k.symbol, k.interval, k.open_time, k.close_time, k.open, k.high, k.low, k.close, k.volume, k.is_suspect
+            FROM klines k
+            LEFT JOIN UNNEST($2::text[], $3::bigint[]) AS spans(interval, span_ms) ON spans.interval = k.interval
+            WHERE ($1::text IS NULL OR k.symbol = $1)
+              AND (k.is_suspect
+                   OR k.high < GREATEST(k.open, k.close)
+                   OR k.low > LEAST(k.open, k.close)
+                   OR LEAST(k.open, k.high, k.low, k.close) <= 0
+                   OR k.volume < 0
+                   OR spans.span_ms IS NULL
+                   OR (EXTRACT(EPOCH FROM k.close_time - k.open_time) * 1000)::bigint <> spans.span_ms)
+            ORDER BY k.symbol, k.interval, k.open_time
+            "#,
+            symbol,
+            &intervals,
+            &spans
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let kline = Kline {
+                    open_time: row.open_time,
+                    open: row.open,
+                    high: row.high,
+                    low: row.low,
+                    close: row.close,
+                    volume: row.volume,
+                    close_time: row.close_time,
+                    interval: row.interval,
+                };
+                SuspectKline { symbol: row.symbol, error: kline.validate().err(), kline, is_suspect: row.is_suspect }
+            })
+            .collect())
+    }
+
     /// Fetches all klines for a given symbol and interval within a date range.
     pub async fn get_klines_by_date_range(
         &self,
@@ -185,6 +816,10 @@ impl DbRepository {
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
     ) -> Result<Vec<Kline>, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_klines_by_date_range(pool, symbol, interval, start_date, end_date).await,
+        };
         let rows = sqlx::query(
             r#"
             SELECT open_time, open, high, low, close, volume, close_time
@@ -197,7 +832,7 @@ impl DbRepository {
         .bind(interval)
         .bind(start_date)
         .bind(end_date)
-        .fetch_all(&self.pool)
+        .fetch_all(pool)
         .await?;
 
         let klines = rows.into_iter().map(|row| {
@@ -216,35 +851,106 @@ impl DbRepository {
         Ok(klines)
     }
 
+    /// Fetches up to `count` klines that open before `before`, oldest first. Backtests use
+    /// these to warm their strategy up ahead of the test range.
+    pub async fn get_klines_before(
+        &self,
+        symbol: &str,
+        interval: &str,
+        before: DateTime<Utc>,
+        count: usize,
+    ) -> Result<Vec<Kline>, DbError> {
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_klines_before(pool, symbol, interval, before, count).await,
+        };
+        let rows = sqlx::query(
+            r#"
+            SELECT open_time, open, high, low, close, volume, close_time
+            FROM klines
+            WHERE symbol = $1 AND interval = $2 AND open_time < $3
+            ORDER BY open_time DESC
+            LIMIT $4
+            "#,
+        )
+        .bind(symbol)
+        .bind(interval)
+        .bind(before)
+        .bind(count as i64)
+        .fetch_all(pool)
+        .await?;
+
+        let mut klines: Vec<Kline> = rows.into_iter().map(|row| {
+            Kline {
+                open_time: row.get("open_time"),
+                open: row.get("open"),
+                high: row.get("high"),
+                low: row.get("low"),
+                close: row.get("close"),
+                volume: row.get("volume"),
+                close_time: row.get("close_time"),
+                interval: interval.to_string(),
+            }
+        }).collect();
+        klines.reverse();
+
+        Ok(klines)
+    }
+
      /// Fetches all backtest runs for a given job that have a 'Pending' status.
      pub async fn get_pending_runs(&self, job_id: Uuid) -> Result<Vec<DbBacktestRun>, DbError> {
         let runs = sqlx::query_as::<_, DbBacktestRun>(
             "SELECT run_id, job_id, parameters, run_status FROM backtest_runs WHERE job_id = $1 AND run_status = 'Pending'"
         )
         .bind(job_id)
-        .fetch_all(&self.pool)
+        .fetch_all(self.postgres("get_pending_runs")?)
         .await?;
         Ok(runs)
     }
 
+    /// Fetches the status of a backtest run: Pending, Running, Completed or Failed.
+    pub async fn get_run_status(&self, run_id: Uuid) -> Result<String, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_run_status(pool, run_id).await,
+        };
+        let status = sqlx::query_scalar!("SELECT run_status FROM backtest_runs WHERE run_id = $1", run_id)
+            .fetch_optional(pool)
+            .await?;
+        status.ok_or(DbError::NotFound)
+    }
+
     /// Updates the status of a specific backtest run.
     pub async fn update_run_status(&self, run_id: Uuid, status: &str) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::update_run_status(pool, run_id, status).await,
+        };
         sqlx::query("UPDATE backtest_runs SET run_status = $1 WHERE run_id = $2")
             .bind(status)
             .bind(run_id)
-            .execute(&self.pool)
+            .execute(pool)
             .await?;
         Ok(())
     }
 
     /// Saves a single Kline to the database.
     /// Uses `ON CONFLICT DO NOTHING` to be idempotent, so it can be called repeatedly
-    /// without causing errors if the data already exists.
+    /// without causing errors if the data already exists. A kline failing validation is
+    /// rejected or flagged, as the repository's `InvalidKlinePolicy` says.
     pub async fn save_kline(&self, symbol: &str, kline: &Kline) -> Result<(), DbError> { // <-- MODIFIED SIGNATURE
+        let is_suspect = self.check_klines(symbol, std::slice::from_ref(kline))?[0];
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_kline(pool, symbol, kline, is_suspect).await,
+        };
         sqlx::query!(
             r#"
-            INSERT INTO klines (symbol, interval, open_time, close_time, open, high, low, close, volume)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            INSERT INTO klines (symbol, interval, open_time, close_time, open, high, low, close, volume, is_suspect)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
             ON CONFLICT (symbol, interval, open_time) DO NOTHING
             "#,
             symbol,
@@ -255,73 +961,462 @@ impl DbRepository {
             kline.high,
             kline.low,
             kline.close,
-            kline.volume
+            kline.volume,
+            is_suspect
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
         Ok(())
     }
 
-    /// Creates a new record for a top-level optimization job.
-    pub async fn save_optimization_job(
-        &self,
-        job_id: Uuid,
-        strategy_id: &str,
-        symbol: &str,
-        status: &str,
-    ) -> Result<(), DbError> {
+    /// Saves a batch of klines of `symbol`, skipping those already stored as `save_kline`
+    /// does. Returns how many were new. Invalid klines are handled as `save_kline` does; when
+    /// they are rejected, none of the batch is saved.
+    pub async fn save_klines(&self, symbol: &str, klines: &[Kline]) -> Result<u64, DbError> {
+        let suspect = self.check_klines(symbol, klines)?;
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_klines(pool, symbol, klines, &suspect).await,
+        };
+        let mut inserted = 0;
+        let mut tx = pool.begin().await?;
+        for (chunk, suspect) in klines.chunks(KLINE_CHUNK_SIZE).zip(suspect.chunks(KLINE_CHUNK_SIZE)) {
+            let intervals: Vec<String> = chunk.iter().map(|kline| kline.interval.clone()).collect();
+            let open_times: Vec<DateTime<Utc>> = chunk.iter().map(|kline| kline.open_time).collect();
+            let close_times: Vec<DateTime<Utc>> = chunk.iter().map(|kline| kline.close_time).collect();
+            let opens: Vec<Decimal> = chunk.iter().map(|kline| kline.open).collect();
+            let highs: Vec<Decimal> = chunk.iter().map(|kline| kline.high).collect();
+            let lows: Vec<Decimal> = chunk.iter().map(|kline| kline.low).collect();
+            let closes: Vec<Decimal> = chunk.iter().map(|kline| kline.close).collect();
+            let volumes: Vec<Decimal> = chunk.iter().map(|kline| kline.volume).collect();
+            inserted += sqlx::query!(
+                r#"
+                INSERT INTO klines (symbol, interval, open_time, close_time, open, high, low, close, volume, is_suspect)
+                SELECT $1, * FROM UNNEST(
+                    $2::text[], $3::timestamptz[], $4::timestamptz[], $5::numeric[], $6::numeric[], $7::numeric[], $8::numeric[], $9::numeric[], $10::bool[]
+                )
+                ON CONFLICT (symbol, interval, open_time) DO NOTHING
+                "#,
+                symbol,
+                &intervals,
+                &open_times,
+                &close_times,
+                &opens,
+                &highs,
+                &lows,
+                &closes,
+                &volumes,
+                suspect
+            )
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+        tx.commit().await?;
+        Ok(inserted)
+    }
+
+    /// Saves a single mark price kline, ignoring its volume. Like `save_kline`, saving a
+    /// kline that is already stored does nothing.
+    pub async fn save_mark_price_kline(&self, symbol: &str, kline: &Kline) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_mark_price_kline(pool, symbol, kline).await,
+        };
         sqlx::query!(
-            "INSERT INTO optimization_jobs (job_id, strategy_id, symbol, job_status, created_at) VALUES ($1, $2, $3, $4, NOW())",
-            job_id,
-            strategy_id,
+            r#"
+            INSERT INTO mark_price_klines (symbol, interval, open_time, close_time, open, high, low, close)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (symbol, interval, open_time) DO NOTHING
+            "#,
             symbol,
-            status
+            kline.interval,
+            kline.open_time,
+            kline.close_time,
+            kline.open,
+            kline.high,
+            kline.low,
+            kline.close
         )
-        .execute(&self.pool)
+        .execute(pool)
         .await?;
         Ok(())
     }
 
-    /// Saves a record for a single backtest run, linked to an optimization job.
-    pub async fn save_backtest_run(
+    /// Fetches the mark price klines for a symbol and interval within a date range. Their
+    /// volume is zero.
+    pub async fn get_mark_price_klines_by_date_range(
         &self,
-        run_id: Uuid,
-        job_id: Uuid,
-        parameters: &JsonValue,
-        status: &str,
-    ) -> Result<(), DbError> {
-        sqlx::query!(
-            "INSERT INTO backtest_runs (run_id, job_id, parameters, run_status, created_at) VALUES ($1, $2, $3, $4, NOW())",
-            run_id,
-            job_id,
-            parameters,
-            status
+        symbol: &str,
+        interval: &str,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<Kline>, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_mark_price_klines_by_date_range(pool, symbol, interval, start_date, end_date).await,
+        };
+        let rows = sqlx::query(
+            r#"
+            SELECT open_time, open, high, low, close, close_time
+            FROM mark_price_klines
+            WHERE symbol = $1 AND interval = $2 AND open_time >= $3 AND open_time <= $4
+            ORDER BY open_time ASC
+            "#,
         )
-        .execute(&self.pool)
+        .bind(symbol)
+        .bind(interval)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(pool)
         .await?;
+
+        let klines = rows.into_iter().map(|row| {
+            Kline {
+                open_time: row.get("open_time"),
+                open: row.get("open"),
+                high: row.get("high"),
+                low: row.get("low"),
+                close: row.get("close"),
+                volume: Decimal::ZERO,
+                close_time: row.get("close_time"),
+                interval: interval.to_string(),
+            }
+        }).collect();
+
+        Ok(klines)
+    }
+
+    /// Saves recorded book ticker updates for a symbol.
+    pub async fn save_book_tickers(&self, symbol: &str, tickers: &[BookTicker]) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_book_tickers(pool, symbol, tickers).await,
+        };
+        for chunk in tickers.chunks(BOOK_TICKER_CHUNK_SIZE) {
+            let timestamps: Vec<DateTime<Utc>> = chunk.iter().map(|ticker| ticker.timestamp).collect();
+            let bid_prices: Vec<Decimal> = chunk.iter().map(|ticker| ticker.best_bid_price).collect();
+            let bid_qtys: Vec<Decimal> = chunk.iter().map(|ticker| ticker.best_bid_qty).collect();
+            let ask_prices: Vec<Decimal> = chunk.iter().map(|ticker| ticker.best_ask_price).collect();
+            let ask_qtys: Vec<Decimal> = chunk.iter().map(|ticker| ticker.best_ask_qty).collect();
+            sqlx::query!(
+                r#"
+                INSERT INTO book_tickers (symbol, event_time, best_bid_price, best_bid_qty, best_ask_price, best_ask_qty)
+                SELECT $1, * FROM UNNEST($2::timestamptz[], $3::numeric[], $4::numeric[], $5::numeric[], $6::numeric[])
+                "#,
+                symbol,
+                &timestamps,
+                &bid_prices,
+                &bid_qtys,
+                &ask_prices,
+                &ask_qtys
+            )
+            .execute(pool)
+            .await?;
+        }
         Ok(())
     }
 
-    
-    
-    /// Saves a full performance report linked to a specific backtest run.
-    pub async fn save_performance_report(
+    /// Summarises the recorded book tickers of each stored kline of a symbol and interval
+    /// within a date range, both ends of each kline included, as `BookSummary::record` does.
+    /// Returns one summary per kline with updates, keyed by its open time, in time order.
+    pub async fn get_book_summaries(
         &self,
-        run_id: Uuid,
-        report: &PerformanceReport,
-    ) -> Result<(), DbError> {
-        // The `humantime` crate is the standard for this, but to_string() is sufficient for now.
-        let avg_holding_period_str = report.average_holding_period.to_string();
+        symbol: &str,
+        interval: &str,
+        start_date: DateTime<Utc>,
+        end_date: DateTime<Utc>,
+    ) -> Result<Vec<(DateTime<Utc>, BookSummary)>, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_book_summaries(pool, symbol, interval, start_date, end_date).await,
+        };
+        let rows = sqlx::query(
+            r#"
+            SELECT open_time,
+                   COUNT(relative_spread) AS spread_samples,
+                   SUM(relative_spread) AS relative_spread_sum,
+                   COUNT(imbalance) AS imbalance_samples,
+                   SUM(imbalance) AS imbalance_sum
+            FROM (
+                SELECT k.open_time,
+                       (b.best_ask_price - b.best_bid_price) / NULLIF((b.best_bid_price + b.best_ask_price) / 2, 0) AS relative_spread,
+                       (b.best_bid_qty - b.best_ask_qty) / NULLIF(b.best_bid_qty + b.best_ask_qty, 0) AS imbalance
+                FROM klines k
+                JOIN book_tickers b ON b.symbol = k.symbol AND b.event_time >= k.open_time AND b.event_time <= k.close_time
+                WHERE k.symbol = $1 AND k.interval = $2 AND k.open_time >= $3 AND k.open_time <= $4
+            ) updates
+            GROUP BY open_time
+            ORDER BY open_time ASC
+            "#,
+        )
+        .bind(symbol)
+        .bind(interval)
+        .bind(start_date)
+        .bind(end_date)
+        .fetch_all(pool)
+        .await?;
 
-        // Prepare and execute the query with all parameters
-        let query = r#"
+        let summaries = rows.into_iter().map(|row| {
+            let sum = |column: &str| row.get::<Option<Decimal>, _>(column).and_then(|sum| sum.to_f64()).unwrap_or(0.0);
+            let summary = BookSummary {
+                spread_samples: row.get::<i64, _>("spread_samples") as u64,
+                relative_spread_sum: sum("relative_spread_sum"),
+                imbalance_samples: row.get::<i64, _>("imbalance_samples") as u64,
+                imbalance_sum: sum("imbalance_sum"),
+            };
+            (row.get("open_time"), summary)
+        }).collect();
+
+        Ok(summaries)
+    }
+
+    /// Fetches the progress of every backfill of `price_type` klines for a symbol and interval.
+    pub async fn get_backfill_progress(&self, symbol: &str, interval: &str, price_type: PriceType) -> Result<Vec<BackfillProgress>, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_backfill_progress(pool, symbol, interval, price_type).await,
+        };
+        let progress = sqlx::query_as!(
+            BackfillProgress,
+            r#"
+            SELECT range_start, last_completed_range_end
+            FROM backfill_progress
+            WHERE symbol = $1 AND interval = $2 AND price_type = $3
+            ORDER BY range_start
+            "#,
+            symbol,
+            interval,
+            price_type.as_str()
+        )
+        .fetch_all(pool)
+        .await?;
+        Ok(progress)
+    }
+
+    /// Records that the backfill of `price_type` klines started at `range_start` has saved
+    /// every bar up to `completed_end`. Progress never moves backwards, so re-running an
+    /// already completed range leaves it unchanged.
+    pub async fn save_backfill_progress(
+        &self,
+        symbol: &str,
+        interval: &str,
+        price_type: PriceType,
+        range_start: DateTime<Utc>,
+        completed_end: DateTime<Utc>,
+    ) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_backfill_progress(pool, symbol, interval, price_type, range_start, completed_end).await,
+        };
+        sqlx::query!(
+            r#"
+            INSERT INTO backfill_progress (symbol, interval, price_type, range_start, last_completed_range_end, updated_at)
+            VALUES ($1, $2, $3, $4, $5, NOW())
+            ON CONFLICT (symbol, interval, price_type, range_start) DO UPDATE
+            SET last_completed_range_end = GREATEST(backfill_progress.last_completed_range_end, EXCLUDED.last_completed_range_end),
+                updated_at = NOW()
+            "#,
+            symbol,
+            interval,
+            price_type.as_str(),
+            range_start,
+            completed_end
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Creates a new record for a top-level optimization job, with a snapshot of the
+    /// configuration it runs with, if it has one.
+    pub async fn save_optimization_job(
+        &self,
+        job_id: Uuid,
+        strategy_id: &str,
+        symbol: &str,
+        status: &str,
+        job_config: Option<&JsonValue>,
+    ) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_optimization_job(pool, job_id, strategy_id, symbol, status, job_config).await,
+        };
+        sqlx::query!(
+            "INSERT INTO optimization_jobs (job_id, strategy_id, symbol, job_status, created_at, job_config) VALUES ($1, $2, $3, $4, NOW(), $5)",
+            job_id,
+            strategy_id,
+            symbol,
+            status,
+            job_config
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Fetches the configuration snapshot an optimization job was saved with. `None` for jobs
+    /// saved without one, such as single runs and jobs older than the snapshots.
+    pub async fn get_job_config(&self, job_id: Uuid) -> Result<Option<JsonValue>, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_job_config(pool, job_id).await,
+        };
+        let job = sqlx::query!("SELECT job_config FROM optimization_jobs WHERE job_id = $1", job_id)
+            .fetch_optional(pool)
+            .await?;
+        job.map(|job| job.job_config).ok_or(DbError::NotFound)
+    }
+
+    /// Saves how many of an optimization job's runs have finished and when the rest should.
+    pub async fn save_job_progress(
+        &self,
+        job_id: Uuid,
+        completed_runs: i32,
+        total_runs: i32,
+        estimated_completion_at: Option<DateTime<Utc>>,
+    ) -> Result<(), DbError> {
+        sqlx::query!(
+            "UPDATE optimization_jobs SET completed_runs = $2, total_runs = $3, estimated_completion_at = $4, updated_at = NOW() WHERE job_id = $1",
+            job_id,
+            completed_runs,
+            total_runs,
+            estimated_completion_at
+        )
+        .execute(self.postgres("save_job_progress")?)
+        .await?;
+        Ok(())
+    }
+
+    /// Updates the status of an optimization job.
+    pub async fn update_job_status(&self, job_id: Uuid, status: &str) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::update_job_status(pool, job_id, status).await,
+        };
+        sqlx::query!("UPDATE optimization_jobs SET job_status = $2, updated_at = NOW() WHERE job_id = $1", job_id, status)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Records the execution metadata of a finished backtest run.
+    pub async fn save_run_metadata(&self, run_id: Uuid, metadata: &RunMetadata) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_run_metadata(pool, run_id, metadata).await,
+        };
+        sqlx::query!(
+            "UPDATE backtest_runs SET started_at = $1, finished_at = $2, bars_processed = $3, engine_version = $4, config_hash = $5, interval = $6, data_start = $7, data_end = $8, signals_filtered = $9, brackets_stop_first = $10, brackets_target_first = $11 WHERE run_id = $12",
+            metadata.started_at,
+            metadata.finished_at,
+            metadata.bars_processed,
+            metadata.engine_version,
+            metadata.config_hash,
+            metadata.interval,
+            metadata.data_start,
+            metadata.data_end,
+            metadata.signals_filtered,
+            metadata.brackets_stop_first,
+            metadata.brackets_target_first,
+            run_id
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Records the optimizer objective's value for a finished run; `None` stores NULL.
+    pub async fn save_run_objective(&self, run_id: Uuid, objective_value: Option<Decimal>) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_run_objective(pool, run_id, objective_value).await,
+        };
+        sqlx::query!("UPDATE backtest_runs SET objective_value = $1 WHERE run_id = $2", objective_value, run_id)
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Fetches the symbol, interval and span of bars a run replayed. Returns `DbError::NotFound`
+    /// if the run does not exist or predates its range being recorded.
+    pub async fn get_run_kline_range(&self, run_id: Uuid) -> Result<RunKlineRange, DbError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT j.symbol, r.interval, r.data_start, r.data_end
+            FROM backtest_runs r
+            JOIN optimization_jobs j ON j.job_id = r.job_id
+            WHERE r.run_id = $1
+            "#,
+            run_id
+        )
+        .fetch_optional(self.postgres("get_run_kline_range")?)
+        .await?
+        .ok_or(DbError::NotFound)?;
+
+        match (row.interval, row.data_start, row.data_end) {
+            (Some(interval), Some(data_start), Some(data_end)) => Ok(RunKlineRange {
+                symbol: row.symbol,
+                interval,
+                data_start,
+                data_end,
+            }),
+            _ => Err(DbError::NotFound),
+        }
+    }
+
+    /// Saves a record for a single backtest run, linked to an optimization job.
+    pub async fn save_backtest_run(
+        &self,
+        run_id: Uuid,
+        job_id: Uuid,
+        parameters: &JsonValue,
+        status: &str,
+    ) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_backtest_run(pool, run_id, job_id, parameters, status).await,
+        };
+        sqlx::query!(
+            "INSERT INTO backtest_runs (run_id, job_id, parameters, run_status, created_at) VALUES ($1, $2, $3, $4, NOW())",
+            run_id,
+            job_id,
+            parameters,
+            status
+        )
+        .execute(pool)
+        .await?;
+        Ok(())
+    }
+
+    
+    
+    /// Saves a full performance report linked to a specific backtest run.
+    pub async fn save_performance_report(
+        &self,
+        run_id: Uuid,
+        report: &PerformanceReport,
+    ) -> Result<(), DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_performance_report(pool, run_id, report).await,
+        };
+        // The `humantime` crate is the standard for this, but to_string() is sufficient for now.
+        let avg_holding_period_str = report.average_holding_period.to_string();
+
+        // Prepare and execute the query with all parameters
+        let query = r#"
             INSERT INTO performance_reports (
                 run_id, total_net_profit, gross_profit, gross_loss, profit_factor,
                 total_return_pct, max_drawdown, max_drawdown_pct, sharpe_ratio,
                 calmar_ratio, total_trades, winning_trades, losing_trades,
-                win_rate_pct, average_win, average_loss, payoff_ratio, average_holding_period
+                win_rate_pct, average_win, average_loss, payoff_ratio, average_holding_period,
+                maker_fees_paid, taker_fees_paid, max_consecutive_losses, exit_breakdown,
+                average_r, expectancy_r, r_distribution, total_spread_cost, gap_slippage,
+                average_exposure_pct, peak_exposure_pct, time_in_market_pct, return_on_margin_pct, exposure_adjusted_sharpe
             ) VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, $27,
+                $28, $29, $30, $31, $32
             )
             "#;
             
@@ -344,7 +1439,22 @@ impl DbRepository {
             .bind(&report.average_loss)      // Decimal
             .bind(report.payoff_ratio.as_ref())   // Option<Decimal>
             .bind(avg_holding_period_str)    // String
-            .execute(&self.pool)
+            .bind(report.maker_fees_paid)    // Decimal
+            .bind(report.taker_fees_paid)    // Decimal
+            .bind(report.max_consecutive_losses as i32) // i32
+            .bind(Json(&report.exit_breakdown)) // JSONB
+            .bind(report.average_r)          // Option<Decimal>
+            .bind(report.expectancy_r)       // Option<Decimal>
+            // Left NULL when no trade had a known risk, like the averages.
+            .bind(report.average_r.map(|_| Json(report.r_distribution))) // JSONB
+            .bind(report.total_spread_cost)  // Decimal
+            .bind(report.gap_slippage)       // Decimal
+            .bind(report.average_exposure_pct)     // Option<Decimal>
+            .bind(report.peak_exposure_pct)        // Option<Decimal>
+            .bind(report.time_in_market_pct)       // Option<Decimal>
+            .bind(report.return_on_margin_pct)     // Option<Decimal>
+            .bind(report.exposure_adjusted_sharpe) // Option<Decimal>
+            .execute(pool)
             .await?;
             
         Ok(())
@@ -352,15 +1462,19 @@ impl DbRepository {
 
     /// Saves a batch of trades from a backtest run within a single transaction for atomicity.
     pub async fn save_trades(&self, run_id: Uuid, trades: &[Trade]) -> Result<(), DbError> {
-        let mut tx = self.pool.begin().await?;
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_trades(pool, run_id, trades).await,
+        };
+        let mut tx = pool.begin().await?;
 
         for trade in trades {
             sqlx::query!(
                 r#"
                 INSERT INTO trades (
                     trade_id, run_id, symbol, entry_price, entry_qty, entry_timestamp,
-                    exit_price, exit_qty, exit_timestamp
-                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+                    exit_price, exit_qty, exit_timestamp, entry_side, entry_fee, exit_fee, fee_asset, close_reason, initial_risk
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
                 "#,
                 trade.trade_id,
                 run_id,
@@ -370,7 +1484,13 @@ impl DbRepository {
                 trade.entry_execution.timestamp,
                 &trade.exit_execution.price,
                 &trade.exit_execution.quantity,
-                trade.exit_execution.timestamp
+                trade.exit_execution.timestamp,
+                trade.entry_execution.side.as_str(),
+                &trade.entry_execution.fee,
+                &trade.exit_execution.fee,
+                trade.entry_execution.fee_asset,
+                trade.close_reason.as_str(),
+                trade.initial_risk
             )
             .execute(&mut *tx) // Note: must use the transaction object `tx` here
             .await?;
@@ -387,7 +1507,8 @@ impl DbRepository {
             FullReport,
             r#"
             SELECT
-                br.run_id as "run_id!", br.job_id as "job_id!", br.parameters as "parameters!", pr.report_id as "report_id?", pr.total_net_profit as "total_net_profit?", pr.gross_profit as "gross_profit?", pr.gross_loss as "gross_loss?", pr.profit_factor as "profit_factor?", pr.total_return_pct as "total_return_pct?", pr.max_drawdown as "max_drawdown?", pr.max_drawdown_pct as "max_drawdown_pct?", pr.sharpe_ratio as "sharpe_ratio?", pr.calmar_ratio as "calmar_ratio?", pr.total_trades as "total_trades?", pr.winning_trades as "winning_trades?", pr.losing_trades as "losing_trades?", pr.win_rate_pct as "win_rate_pct?", pr.average_win as "average_win?", pr.average_loss as "average_loss?", pr.payoff_ratio as "payoff_ratio?", pr.average_holding_period as "average_holding_period?"
+                br.run_id as "run_id!", br.job_id as "job_id!", br.parameters as "parameters!", pr.report_id as "report_id?", pr.total_net_profit as "total_net_profit?", pr.gross_profit as "gross_profit?", pr.gross_loss as "gross_loss?", pr.profit_factor as "profit_factor?", pr.total_return_pct as "total_return_pct?", pr.max_drawdown as "max_drawdown?", pr.max_drawdown_pct as "max_drawdown_pct?", pr.sharpe_ratio as "sharpe_ratio?", pr.calmar_ratio as "calmar_ratio?", pr.total_trades as "total_trades?", pr.winning_trades as "winning_trades?", pr.losing_trades as "losing_trades?", pr.win_rate_pct as "win_rate_pct?", pr.average_win as "average_win?", pr.average_loss as "average_loss?", pr.payoff_ratio as "payoff_ratio?", pr.max_consecutive_losses as "max_consecutive_losses?", pr.average_holding_period as "average_holding_period?", pr.maker_fees_paid as "maker_fees_paid?", pr.taker_fees_paid as "taker_fees_paid?", pr.total_spread_cost as "total_spread_cost?", pr.gap_slippage as "gap_slippage?", pr.exit_breakdown as "exit_breakdown?: Json<Vec<ExitStats>>", pr.average_r as "average_r?", pr.expectancy_r as "expectancy_r?", pr.r_distribution as "r_distribution?: Json<RDistribution>", pr.average_exposure_pct as "average_exposure_pct?", pr.peak_exposure_pct as "peak_exposure_pct?", pr.time_in_market_pct as "time_in_market_pct?", pr.return_on_margin_pct as "return_on_margin_pct?", pr.exposure_adjusted_sharpe as "exposure_adjusted_sharpe?",
+                br.started_at as "started_at?", br.finished_at as "finished_at?", br.bars_processed as "bars_processed?", br.signals_filtered as "signals_filtered?", br.brackets_stop_first as "brackets_stop_first?", br.brackets_target_first as "brackets_target_first?", br.engine_version as "engine_version?", br.config_hash as "config_hash?", br.objective_value as "objective_value?"
             FROM
                 performance_reports AS pr
             JOIN
@@ -397,34 +1518,73 @@ impl DbRepository {
             "#,
             job_id
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.postgres("get_full_reports_for_job")?)
         .await?;
 
         Ok(reports)
     }
-    /// Saves the full equity curve for a backtest run within a single transaction.
+    /// Saves the equity curve for a backtest run within a single transaction.
     pub async fn save_equity_curve(
         &self,
         run_id: Uuid,
         equity_curve: &[(DateTime<Utc>, Decimal)],
     ) -> Result<(), DbError> {
-        let mut tx: Transaction<Postgres> = self.pool.begin().await?;
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::save_equity_curve(pool, run_id, equity_curve).await,
+        };
+        let mut tx: Transaction<Postgres> = pool.begin().await?;
+        insert_equity_points(&mut tx, run_id, equity_curve).await?;
+        tx.commit().await?;
+        Ok(())
+    }
 
-        for (timestamp, equity) in equity_curve {
-            sqlx::query!(
-                "INSERT INTO equity_curves (run_id, timestamp, equity) VALUES ($1, $2, $3)",
-                run_id,
-                timestamp,
-                equity
-            )
+    /// Fetches the stored equity curve of a backtest run, oldest point first.
+    pub async fn get_equity_curve(&self, run_id: Uuid) -> Result<Vec<(DateTime<Utc>, Decimal)>, DbError> {
+        let pool = match &self.backend {
+            Backend::Postgres(pool) => pool,
+            Backend::Sqlite(pool) => return sqlite::get_equity_curve(pool, run_id).await,
+        };
+        let rows = sqlx::query!(
+            "SELECT timestamp, equity FROM equity_curves WHERE run_id = $1 ORDER BY timestamp ASC",
+            run_id
+        )
+        .fetch_all(pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.timestamp, row.equity)).collect())
+    }
+
+    /// Replaces the stored equity curve of a backtest run, atomically.
+    pub async fn replace_equity_curve(
+        &self,
+        run_id: Uuid,
+        equity_curve: &[(DateTime<Utc>, Decimal)],
+    ) -> Result<(), DbError> {
+        let mut tx: Transaction<Postgres> = self.postgres("replace_equity_curve")?.begin().await?;
+        sqlx::query!("DELETE FROM equity_curves WHERE run_id = $1", run_id)
             .execute(&mut *tx)
             .await?;
-        }
-
+        insert_equity_points(&mut tx, run_id, equity_curve).await?;
         tx.commit().await?;
         Ok(())
     }
-    /// Creates a new top-level record for a Walk-Forward Optimization job.
+
+    /// Fetches the optimization jobs created before `cutoff`, oldest first. Jobs that only
+    /// hold a single run are excluded.
+    pub async fn get_optimization_jobs_created_before(&self, cutoff: DateTime<Utc>) -> Result<Vec<DbOptimizationJob>, DbError> {
+        let jobs = sqlx::query_as!(
+            DbOptimizationJob,
+            "SELECT job_id, strategy_id, symbol, job_status, created_at, completed_runs, total_runs, updated_at, estimated_completion_at FROM optimization_jobs WHERE created_at < $1 AND job_status <> 'Single Run' ORDER BY created_at ASC",
+            cutoff
+        )
+        .fetch_all(self.postgres("get_optimization_jobs_created_before")?)
+        .await?;
+        Ok(jobs)
+    }
+    /// Creates a new top-level record for a Walk-Forward Optimization job, with a snapshot of
+    /// the configuration it runs with.
+    #[allow(clippy::too_many_arguments)]
     pub async fn save_wfo_job(
         &self,
         wfo_job_id: Uuid,
@@ -433,24 +1593,35 @@ impl DbRepository {
         in_sample_period_months: i32,
         out_of_sample_period_months: i32,
         wfo_status: &str,
+        job_config: Option<&JsonValue>,
     ) -> Result<(), DbError> {
         sqlx::query!(
             r#"
-            INSERT INTO wfo_jobs (wfo_job_id, strategy_id, symbol, in_sample_period_months, out_of_sample_period_months, wfo_status, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, NOW())
+            INSERT INTO wfo_jobs (wfo_job_id, strategy_id, symbol, in_sample_period_months, out_of_sample_period_months, wfo_status, created_at, job_config)
+            VALUES ($1, $2, $3, $4, $5, $6, NOW(), $7)
             "#,
             wfo_job_id,
             strategy_id,
             symbol,
             in_sample_period_months,
             out_of_sample_period_months,
-            wfo_status
+            wfo_status,
+            job_config
         )
-        .execute(&self.pool)
+        .execute(self.postgres("save_wfo_job")?)
         .await?;
         Ok(())
     }
 
+    /// Fetches the configuration snapshot a WFO job was saved with. `None` for jobs older than
+    /// the snapshots.
+    pub async fn get_wfo_job_config(&self, wfo_job_id: Uuid) -> Result<Option<JsonValue>, DbError> {
+        let job = sqlx::query!("SELECT job_config FROM wfo_jobs WHERE wfo_job_id = $1", wfo_job_id)
+            .fetch_optional(self.postgres("get_wfo_job_config")?)
+            .await?;
+        job.map(|job| job.job_config).ok_or(DbError::NotFound)
+    }
+
     /// Saves the record of a single, completed out-of-sample run within a WFO job.
     pub async fn save_wfo_run(
         &self,
@@ -473,7 +1644,7 @@ impl DbRepository {
             oos_start_date,
             oos_end_date
         )
-        .execute(&self.pool)
+        .execute(self.postgres("save_wfo_run")?)
         .await?;
         Ok(())
     }
@@ -482,51 +1653,19 @@ impl DbRepository {
         
         let trades_future = sqlx::query_as!(
             DbTrade,
-            r#"SELECT trade_id, run_id, symbol, entry_price, entry_qty, entry_timestamp, exit_price, exit_qty, exit_timestamp FROM trades WHERE run_id = $1 ORDER BY entry_timestamp ASC"#,
+            r#"SELECT trade_id, run_id, symbol, entry_price, entry_qty, entry_timestamp, exit_price, exit_qty, exit_timestamp, entry_side, entry_fee, exit_fee, fee_asset, close_reason, initial_risk FROM trades WHERE run_id = $1 ORDER BY entry_timestamp ASC"#,
             run_id
-        ).fetch_all(&self.pool);
+        ).fetch_all(self.postgres("get_run_details")?);
 
         let equity_curve_future = sqlx::query_as!(
             EquityDataPoint,
             "SELECT timestamp, equity FROM equity_curves WHERE run_id = $1 ORDER BY timestamp ASC",
             run_id
-        ).fetch_all(&self.pool);
+        ).fetch_all(self.postgres("get_run_details")?);
 
         let (report_res, trades_res, equity_curve_res) = tokio::join!(report_future, trades_future, equity_curve_future);
 
-        // Convert DbTrade to Trade (core_types)
-        let trades: Vec<Trade> = trades_res?.into_iter().map(|db_trade| {
-            let entry_execution = Execution {
-                execution_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
-                client_order_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
-                symbol: db_trade.symbol.clone(),
-                side: OrderSide::Buy, // We'll need to determine this from context
-                price: db_trade.entry_price,
-                quantity: db_trade.entry_qty,
-                fee: Decimal::ZERO, // Not stored in DB
-                fee_asset: String::new(), // Not stored in DB
-                timestamp: db_trade.entry_timestamp,
-            };
-            
-            let exit_execution = Execution {
-                execution_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
-                client_order_id: Uuid::new_v4(), // Generate new ID since we don't store it in DB
-                symbol: db_trade.symbol.clone(),
-                side: OrderSide::Sell, // We'll need to determine this from context
-                price: db_trade.exit_price,
-                quantity: db_trade.exit_qty,
-                fee: Decimal::ZERO, // Not stored in DB
-                fee_asset: String::new(), // Not stored in DB
-                timestamp: db_trade.exit_timestamp,
-            };
-
-            Trade {
-                trade_id: db_trade.trade_id,
-                symbol: db_trade.symbol,
-                entry_execution,
-                exit_execution,
-            }
-        }).collect();
+        let trades: Vec<Trade> = trades_res?.into_iter().map(Trade::from).collect();
 
         Ok(BacktestRunDetails {
             report: report_res?,
@@ -535,13 +1674,152 @@ impl DbRepository {
         })
     }
 
+    /// Fetches the trades with the given IDs, in the order they were entered. Unknown IDs are
+    /// skipped.
+    pub async fn get_trades(&self, trade_ids: &[Uuid]) -> Result<Vec<Trade>, DbError> {
+        let trades = sqlx::query_as!(
+            DbTrade,
+            r#"SELECT trade_id, run_id, symbol, entry_price, entry_qty, entry_timestamp, exit_price, exit_qty, exit_timestamp, entry_side, entry_fee, exit_fee, fee_asset, close_reason, initial_risk FROM trades WHERE trade_id = ANY($1) ORDER BY entry_timestamp ASC, trade_id"#,
+            trade_ids
+        )
+        .fetch_all(self.postgres("get_trades")?)
+        .await?;
+        Ok(trades.into_iter().map(Trade::from).collect())
+    }
+
+    /// Whether the trade, run or live position an annotation would be attached to exists. A
+    /// live position exists once a fill of it is recorded or the engine has opened it.
+    pub async fn annotation_target_exists(&self, target_type: AnnotationTarget, target_id: Uuid) -> Result<bool, DbError> {
+        let pool = self.postgres("annotation_target_exists")?;
+        let exists = match target_type {
+            AnnotationTarget::Trade => {
+                sqlx::query_scalar!(r#"SELECT EXISTS (SELECT 1 FROM trades WHERE trade_id = $1) AS "exists!""#, target_id)
+                    .fetch_one(pool)
+                    .await?
+            }
+            AnnotationTarget::Run => {
+                sqlx::query_scalar!(r#"SELECT EXISTS (SELECT 1 FROM backtest_runs WHERE run_id = $1) AS "exists!""#, target_id)
+                    .fetch_one(pool)
+                    .await?
+            }
+            AnnotationTarget::Position => {
+                sqlx::query_scalar!(
+                    r#"
+                    SELECT EXISTS (SELECT 1 FROM live_executions WHERE position_id = $1)
+                        OR EXISTS (SELECT 1 FROM open_position_context WHERE position_id = $1) AS "exists!"
+                    "#,
+                    target_id
+                )
+                .fetch_one(pool)
+                .await?
+            }
+        };
+        Ok(exists)
+    }
+
+    /// Saves a new annotation.
+    pub async fn save_annotation(&self, annotation: &Annotation) -> Result<(), DbError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO annotations (annotation_id, target_type, target_id, author, text, tags, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+            annotation.annotation_id,
+            annotation.target_type.as_str(),
+            annotation.target_id,
+            annotation.author,
+            annotation.text,
+            &annotation.tags,
+            annotation.created_at
+        )
+        .execute(self.postgres("save_annotation")?)
+        .await?;
+        Ok(())
+    }
+
+    /// The annotations matching `filter`, oldest first.
+    pub async fn get_annotations(&self, filter: &AnnotationFilter) -> Result<Vec<Annotation>, DbError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT annotation_id, target_type, target_id, author, text, tags, created_at
+            FROM annotations
+            WHERE ($1::TEXT IS NULL OR target_type = $1)
+              AND ($2::UUID IS NULL OR target_id = $2)
+              AND ($3::TEXT IS NULL OR $3 = ANY(tags))
+            ORDER BY created_at, annotation_id
+            "#,
+            filter.target_type.map(|target_type| target_type.as_str()),
+            filter.target_id,
+            filter.tag
+        )
+        .fetch_all(self.postgres("get_annotations")?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| Annotation {
+                annotation_id: row.annotation_id,
+                target_type: AnnotationTarget::from_db(&row.target_type),
+                target_id: row.target_id,
+                author: row.author,
+                text: row.text,
+                tags: row.tags,
+                created_at: row.created_at,
+            })
+            .collect())
+    }
+
+    /// The annotations of each of `target_ids`, oldest first. Targets without annotations
+    /// are left out.
+    pub async fn get_annotations_for(
+        &self,
+        target_type: AnnotationTarget,
+        target_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<Annotation>>, DbError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT annotation_id, target_id, author, text, tags, created_at
+            FROM annotations
+            WHERE target_type = $1 AND target_id = ANY($2)
+            ORDER BY created_at, annotation_id
+            "#,
+            target_type.as_str(),
+            target_ids
+        )
+        .fetch_all(self.postgres("get_annotations_for")?)
+        .await?;
+        let mut annotations: HashMap<Uuid, Vec<Annotation>> = HashMap::new();
+        for row in rows {
+            annotations.entry(row.target_id).or_default().push(Annotation {
+                annotation_id: row.annotation_id,
+                target_type,
+                target_id: row.target_id,
+                author: row.author,
+                text: row.text,
+                tags: row.tags,
+                created_at: row.created_at,
+            });
+        }
+        Ok(annotations)
+    }
+
+    /// Deletes an annotation. Returns `DbError::NotFound` if there is none with that ID.
+    pub async fn delete_annotation(&self, annotation_id: Uuid) -> Result<(), DbError> {
+        let result = sqlx::query!("DELETE FROM annotations WHERE annotation_id = $1", annotation_id)
+            .execute(self.postgres("delete_annotation")?)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(DbError::NotFound);
+        }
+        Ok(())
+    }
+
     /// Fetches all WFO jobs from the database.
     pub async fn get_all_wfo_jobs(&self) -> Result<Vec<WfoJob>, DbError> {
         let jobs = sqlx::query_as!(
             WfoJob,
             "SELECT wfo_job_id, strategy_id, symbol, in_sample_period_months, out_of_sample_period_months, wfo_status, created_at FROM wfo_jobs ORDER BY created_at DESC"
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.postgres("get_all_wfo_jobs")?)
         .await?;
         Ok(jobs)
     }
@@ -553,8 +1831,768 @@ impl DbRepository {
             "SELECT wfo_run_id, wfo_job_id, oos_run_id, best_in_sample_parameters, oos_start_date, oos_end_date FROM wfo_runs WHERE wfo_job_id = $1 ORDER BY oos_start_date ASC",
             wfo_job_id
         )
-        .fetch_all(&self.pool)
+        .fetch_all(self.postgres("get_wfo_runs_for_job")?)
         .await?;
         Ok(runs)
     }
-}
\ No newline at end of file
+
+    /// Appends a single stage of a trading decision to the audit trail.
+    pub async fn save_decision_audit(
+        &self,
+        decision_id: Uuid,
+        stage: DecisionStage,
+        symbol: &str,
+        payload: &JsonValue,
+        sequence: u64,
+    ) -> Result<(), DbError> {
+        sqlx::query!(
+            "INSERT INTO decision_audit (decision_id, stage, symbol, payload, recorded_at, sequence) VALUES ($1, $2, $3, $4, NOW(), $5)",
+            decision_id,
+            stage.as_str(),
+            symbol,
+            payload,
+            sequence as i64
+        )
+        .execute(self.postgres("save_decision_audit")?)
+        .await?;
+        Ok(())
+    }
+
+    /// Appends `audits` to the audit trail and records the `equity` points as
+    /// `save_live_equity` does, all in one transaction, for writers batching them.
+    pub async fn save_live_batch(&self, audits: &[DecisionAuditEntry], equity: &[(DateTime<Utc>, Decimal)]) -> Result<(), DbError> {
+        let mut tx = self.postgres("save_live_batch")?.begin().await?;
+        if !audits.is_empty() {
+            let decision_ids: Vec<Uuid> = audits.iter().map(|audit| audit.decision_id).collect();
+            let stages: Vec<&str> = audits.iter().map(|audit| audit.stage.as_str()).collect();
+            let symbols: Vec<&str> = audits.iter().map(|audit| audit.symbol.as_str()).collect();
+            let payloads: Vec<JsonValue> = audits.iter().map(|audit| audit.payload.clone()).collect();
+            let recorded_at: Vec<DateTime<Utc>> = audits.iter().map(|audit| audit.recorded_at).collect();
+            let sequences: Vec<i64> = audits.iter().map(|audit| audit.sequence as i64).collect();
+            sqlx::query!(
+                r#"
+                INSERT INTO decision_audit (decision_id, stage, symbol, payload, recorded_at, sequence)
+                SELECT * FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::jsonb[], $5::timestamptz[], $6::bigint[])
+                "#,
+                &decision_ids,
+                &stages as &[&str],
+                &symbols as &[&str],
+                &payloads,
+                &recorded_at,
+                &sequences
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        if !equity.is_empty() {
+            let recorded_at: Vec<DateTime<Utc>> = equity.iter().map(|(recorded_at, _)| *recorded_at).collect();
+            let values: Vec<Decimal> = equity.iter().map(|(_, equity)| *equity).collect();
+            sqlx::query!(
+                r#"
+                INSERT INTO live_equity (recorded_at, equity)
+                SELECT * FROM UNNEST($1::timestamptz[], $2::numeric[])
+                ON CONFLICT (recorded_at) DO NOTHING
+                "#,
+                &recorded_at,
+                &values
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Fetches the full audit chain for a decision, in the order its stages were decided.
+    /// Stages recorded without a sequence fall back to the order they were recorded in.
+    pub async fn get_decision_audit(&self, decision_id: Uuid) -> Result<Vec<DecisionAuditRecord>, DbError> {
+        let records = sqlx::query_as!(
+            DecisionAuditRecord,
+            "SELECT audit_id, decision_id, stage, symbol, payload, recorded_at, sequence FROM decision_audit WHERE decision_id = $1 ORDER BY sequence ASC, recorded_at ASC, audit_id ASC",
+            decision_id
+        )
+        .fetch_all(self.postgres("get_decision_audit")?)
+        .await?;
+        Ok(records)
+    }
+
+    /// Appends the live portfolio's `events`, in order, to `portfolio_events`.
+    pub async fn save_portfolio_events(&self, events: &[RecordedPortfolioEvent]) -> Result<(), DbError> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let sequences: Vec<i64> = events.iter().map(|recorded| recorded.sequence as i64).collect();
+        let kinds: Vec<&str> = events.iter().map(|recorded| recorded.event.kind()).collect();
+        let symbols: Vec<Option<String>> = events.iter().map(|recorded| recorded.event.symbol().map(str::to_string)).collect();
+        let payloads = events.iter().map(|recorded| serde_json::to_value(&recorded.event)).collect::<Result<Vec<JsonValue>, _>>()?;
+        let occurred_at: Vec<DateTime<Utc>> = events.iter().map(|recorded| recorded.event.at()).collect();
+        // WITH ORDINALITY keeps the events' order in their IDs.
+        sqlx::query!(
+            r#"
+            INSERT INTO portfolio_events (sequence, kind, symbol, event, occurred_at)
+            SELECT sequence, kind, symbol, event, occurred_at
+            FROM UNNEST($1::bigint[], $2::text[], $3::text[], $4::jsonb[], $5::timestamptz[])
+                WITH ORDINALITY AS e(sequence, kind, symbol, event, occurred_at, n)
+            ORDER BY n
+            "#,
+            &sequences,
+            &kinds as &[&str],
+            &symbols as &[Option<String>],
+            &payloads,
+            &occurred_at
+        )
+        .execute(self.postgres("save_portfolio_events")?)
+        .await?;
+        Ok(())
+    }
+
+    /// The `limit` most recent portfolio events, of `symbol`'s position if given, newest
+    /// first.
+    pub async fn get_portfolio_events(&self, symbol: Option<&str>, limit: i64) -> Result<Vec<StoredPortfolioEvent>, DbError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT event_id, sequence, event, recorded_at
+            FROM portfolio_events
+            WHERE ($1::TEXT IS NULL OR symbol = $1)
+            ORDER BY event_id DESC
+            LIMIT $2
+            "#,
+            symbol,
+            limit
+        )
+        .fetch_all(self.postgres("get_portfolio_events")?)
+        .await?;
+        rows.into_iter()
+            .map(|row| {
+                Ok(StoredPortfolioEvent {
+                    event_id: row.event_id,
+                    sequence: row.sequence as u64,
+                    event: serde_json::from_value(row.event)?,
+                    recorded_at: row.recorded_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Records a live execution, one row per position it changed. The fill that closes a
+    /// position carries `close_reason`. The order's intent in the order ledger, if still
+    /// sent, is resolved as filled in the same transaction.
+    pub async fn save_live_execution(
+        &self,
+        execution: &Execution,
+        fills: &[PositionFill],
+        close_reason: Option<CloseReason>,
+    ) -> Result<(), DbError> {
+        let mut tx = self.postgres("save_live_execution")?.begin().await?;
+        for fill in fills {
+            let closes = !fill.is_entry && fill.position_quantity.is_zero();
+            sqlx::query!(
+                r#"
+                INSERT INTO live_executions (
+                    execution_id, position_id, client_order_id, decision_id, symbol, position_side, is_entry,
+                    price, quantity, fee, fee_asset, is_maker, realized_pnl, position_quantity, close_reason, executed_at
+                ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                "#,
+                execution.execution_id,
+                fill.position_id,
+                execution.client_order_id,
+                execution.decision_id,
+                execution.symbol,
+                fill.position_side.as_str(),
+                fill.is_entry,
+                execution.price,
+                fill.quantity,
+                fill.fee,
+                execution.fee_asset,
+                execution.is_maker,
+                fill.realized_pnl,
+                fill.position_quantity,
+                close_reason.filter(|_| closes).map(|reason| reason.as_str()),
+                execution.timestamp
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        sqlx::query!(
+            r#"
+            UPDATE order_intents SET state = $2, execution_id = $3, resolved_at = $4
+            WHERE client_order_id = $1 AND state = 'sent'
+            "#,
+            execution.client_order_id,
+            OrderIntentState::Filled.as_str(),
+            execution.execution_id,
+            execution.timestamp
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Records where a live limit order was placed against the book and, once confirmed, how
+    /// it filled. Until then its fill price, maker flag, improvement and fill time are NULL. A
+    /// later record for the same order replaces the earlier one, but never with an unconfirmed
+    /// fill.
+    pub async fn save_execution_quality(&self, execution: &Execution, placement: &OrderPlacement) -> Result<(), DbError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO live_execution_quality (
+                client_order_id, execution_id, symbol, side, quantity, best_bid, best_ask, limit_price,
+                fill_price, is_maker, price_improvement_bps, placed_at, filled_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+            ON CONFLICT (client_order_id) DO UPDATE SET
+                execution_id = EXCLUDED.execution_id,
+                quantity = EXCLUDED.quantity,
+                fill_price = COALESCE(EXCLUDED.fill_price, live_execution_quality.fill_price),
+                is_maker = COALESCE(EXCLUDED.is_maker, live_execution_quality.is_maker),
+                price_improvement_bps = CASE WHEN EXCLUDED.fill_price IS NULL
+                    THEN live_execution_quality.price_improvement_bps ELSE EXCLUDED.price_improvement_bps END,
+                filled_at = COALESCE(EXCLUDED.filled_at, live_execution_quality.filled_at)
+            "#,
+            execution.client_order_id,
+            execution.execution_id,
+            execution.symbol,
+            execution.side.as_str(),
+            execution.quantity,
+            placement.best_bid,
+            placement.best_ask,
+            placement.limit_price,
+            placement.fill_price,
+            placement.is_maker,
+            placement.price_improvement_bps,
+            placement.placed_at,
+            placement.filled_at
+        )
+        .execute(self.postgres("save_execution_quality")?)
+        .await?;
+        Ok(())
+    }
+
+    /// How each symbol's live limit orders filled in `[from, to)`, by symbol, counting only
+    /// the orders whose fill was confirmed. Either bound may be left open.
+    pub async fn get_execution_quality(
+        &self,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<SymbolExecutionQuality>, DbError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT symbol,
+                   COUNT(*) AS "limit_fills!",
+                   COUNT(*) FILTER (WHERE is_maker) AS "maker_fills!",
+                   AVG(price_improvement_bps) AS avg_improvement_bps
+            FROM live_execution_quality
+            WHERE fill_price IS NOT NULL
+              AND is_maker IS NOT NULL
+              AND ($1::TIMESTAMPTZ IS NULL OR filled_at >= $1)
+              AND ($2::TIMESTAMPTZ IS NULL OR filled_at < $2)
+            GROUP BY symbol
+            ORDER BY symbol
+            "#,
+            from,
+            to
+        )
+        .fetch_all(self.postgres("get_execution_quality")?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| SymbolExecutionQuality {
+                maker_fill_ratio: Decimal::from(row.maker_fills) / Decimal::from(row.limit_fills),
+                symbol: row.symbol,
+                limit_fills: row.limit_fills,
+                maker_fills: row.maker_fills,
+                avg_improvement_bps: row.avg_improvement_bps,
+            })
+            .collect())
+    }
+
+    /// The live fills executed in `[from, to)`, in the order they were executed. The close
+    /// of a netted reversal comes before the opening of the new position.
+    pub async fn get_live_fills(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<LiveFill>, DbError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT execution_id, position_id, symbol, position_side, is_entry, price, quantity, fee, fee_asset,
+                   is_maker, realized_pnl, close_reason, executed_at
+            FROM live_executions
+            WHERE executed_at >= $1 AND executed_at < $2
+            ORDER BY executed_at, execution_id, is_entry
+            "#,
+            from,
+            to
+        )
+        .fetch_all(self.postgres("get_live_fills")?)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| LiveFill {
+                execution_id: row.execution_id,
+                position_id: row.position_id,
+                symbol: row.symbol,
+                position_side: if row.position_side == "SELL" { OrderSide::Sell } else { OrderSide::Buy },
+                is_entry: row.is_entry,
+                price: row.price,
+                quantity: row.quantity,
+                fee: row.fee,
+                fee_asset: row.fee_asset,
+                is_maker: row.is_maker,
+                realized_pnl: row.realized_pnl,
+                close_reason: row.close_reason.as_deref().map(CloseReason::from_db),
+                executed_at: row.executed_at,
+            })
+            .collect())
+    }
+
+    /// Records the live engine's equity at `recorded_at`. A second point at the same instant
+    /// is ignored.
+    pub async fn save_live_equity(&self, recorded_at: DateTime<Utc>, equity: Decimal) -> Result<(), DbError> {
+        sqlx::query!(
+            "INSERT INTO live_equity (recorded_at, equity) VALUES ($1, $2) ON CONFLICT (recorded_at) DO NOTHING",
+            recorded_at,
+            equity
+        )
+        .execute(self.postgres("save_live_equity")?)
+        .await?;
+        Ok(())
+    }
+
+    /// Records the context of a newly opened position. Any context still open for its symbol
+    /// is closed at the new one's opening, as only one position per symbol is open at a time.
+    pub async fn open_position_context(&self, context: &PositionContext) -> Result<(), DbError> {
+        let mut tx = self.postgres("open_position_context")?.begin().await?;
+        sqlx::query!(
+            "UPDATE open_position_context SET closed_at = $2 WHERE symbol = $1 AND closed_at IS NULL",
+            context.symbol,
+            context.opened_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        sqlx::query!(
+            r#"
+            INSERT INTO open_position_context (
+                position_id, symbol, position_side, strategy_id, entry_signal_id, stop_price, take_profit_price, opened_at, closed_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+            "#,
+            context.position_id,
+            context.symbol,
+            context.side.as_str(),
+            context.strategy_id.map(|strategy_id| format!("{:?}", strategy_id)),
+            context.entry_signal_id,
+            context.stop_price,
+            context.take_profit_price,
+            context.opened_at,
+            context.closed_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Marks the open position context of `symbol`, if any, as closed at `closed_at`.
+    pub async fn close_position_context(&self, symbol: &str, closed_at: DateTime<Utc>) -> Result<(), DbError> {
+        sqlx::query!(
+            "UPDATE open_position_context SET closed_at = $2 WHERE symbol = $1 AND closed_at IS NULL",
+            symbol,
+            closed_at
+        )
+        .execute(self.postgres("close_position_context")?)
+        .await?;
+        Ok(())
+    }
+
+    /// The contexts of every position the engine last saw open, by symbol.
+    pub async fn get_open_position_contexts(&self) -> Result<Vec<PositionContext>, DbError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT position_id, symbol, position_side, strategy_id, entry_signal_id, stop_price, take_profit_price, opened_at, closed_at
+            FROM open_position_context
+            WHERE closed_at IS NULL
+            ORDER BY symbol
+            "#
+        )
+        .fetch_all(self.postgres("get_open_position_contexts")?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| PositionContext {
+                position_id: row.position_id,
+                symbol: row.symbol,
+                side: if row.position_side == "SELL" { OrderSide::Sell } else { OrderSide::Buy },
+                strategy_id: row.strategy_id.and_then(|strategy_id| serde_json::from_value(JsonValue::String(strategy_id)).ok()),
+                entry_signal_id: row.entry_signal_id,
+                stop_price: row.stop_price,
+                take_profit_price: row.take_profit_price,
+                opened_at: row.opened_at,
+                closed_at: row.closed_at,
+            })
+            .collect())
+    }
+
+    /// Saves `entries` of the risk state in one transaction, each replacing the value of its
+    /// scope and key. An entry whose value is null deletes it.
+    pub async fn save_risk_state(&self, entries: &[RiskStateEntry]) -> Result<(), DbError> {
+        let mut tx = self.postgres("save_risk_state")?.begin().await?;
+        for entry in entries {
+            if entry.value.is_null() {
+                sqlx::query!("DELETE FROM risk_state WHERE scope = $1 AND key = $2", entry.scope, entry.key)
+                    .execute(&mut *tx)
+                    .await?;
+                continue;
+            }
+            sqlx::query!(
+                r#"
+                INSERT INTO risk_state (scope, key, value, updated_at) VALUES ($1, $2, $3, $4)
+                ON CONFLICT (scope, key) DO UPDATE SET value = EXCLUDED.value, updated_at = EXCLUDED.updated_at
+                "#,
+                entry.scope,
+                entry.key,
+                entry.value,
+                entry.updated_at
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// The whole risk state, by scope and key.
+    pub async fn get_risk_state(&self) -> Result<Vec<RiskStateEntry>, DbError> {
+        let rows = sqlx::query!("SELECT scope, key, value, updated_at FROM risk_state ORDER BY scope, key")
+            .fetch_all(self.postgres("get_risk_state")?)
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| RiskStateEntry { scope: row.scope, key: row.key, value: row.value, updated_at: row.updated_at })
+            .collect())
+    }
+
+    /// Copies the current risk state into `risk_state_history` as the state of the trading
+    /// day that started at `trading_day`. Archiving the same day again keeps the first copy.
+    /// Returns the number of values archived.
+    pub async fn archive_risk_state(&self, trading_day: DateTime<Utc>, archived_at: DateTime<Utc>) -> Result<u64, DbError> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO risk_state_history (trading_day, scope, key, value, updated_at, archived_at)
+            SELECT $1, scope, key, value, updated_at, $2 FROM risk_state
+            ON CONFLICT (trading_day, scope, key) DO NOTHING
+            "#,
+            trading_day,
+            archived_at
+        )
+        .execute(self.postgres("archive_risk_state")?)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// The risk state archived for the trading day that started at `trading_day`, by scope
+    /// and key.
+    pub async fn get_archived_risk_state(&self, trading_day: DateTime<Utc>) -> Result<Vec<RiskStateEntry>, DbError> {
+        let rows = sqlx::query!(
+            "SELECT scope, key, value, updated_at FROM risk_state_history WHERE trading_day = $1 ORDER BY scope, key",
+            trading_day
+        )
+        .fetch_all(self.postgres("get_archived_risk_state")?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| RiskStateEntry { scope: row.scope, key: row.key, value: row.value, updated_at: row.updated_at })
+            .collect())
+    }
+
+    /// Writes `intent` to the order ledger. Writing the same order again changes nothing.
+    pub async fn save_order_intent(&self, intent: &OrderIntent) -> Result<(), DbError> {
+        sqlx::query!(
+            r#"
+            INSERT INTO order_intents (
+                client_order_id, decision_id, symbol, side, quantity, reduce_only, state, execution_id, created_at, resolved_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (client_order_id) DO NOTHING
+            "#,
+            intent.client_order_id,
+            intent.decision_id,
+            intent.symbol,
+            intent.side.as_str(),
+            intent.quantity,
+            intent.reduce_only,
+            intent.state.as_str(),
+            intent.execution_id,
+            intent.created_at,
+            intent.resolved_at
+        )
+        .execute(self.postgres("save_order_intent")?)
+        .await?;
+        Ok(())
+    }
+
+    /// Resolves the sent order `client_order_id` as `state` at `resolved_at`. An order that
+    /// was resolved already keeps its first resolution. Returns whether the order was resolved.
+    pub async fn resolve_order_intent(
+        &self,
+        client_order_id: Uuid,
+        state: OrderIntentState,
+        execution_id: Option<Uuid>,
+        resolved_at: DateTime<Utc>,
+    ) -> Result<bool, DbError> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE order_intents SET state = $2, execution_id = $3, resolved_at = $4
+            WHERE client_order_id = $1 AND state = 'sent'
+            "#,
+            client_order_id,
+            state.as_str(),
+            execution_id,
+            resolved_at
+        )
+        .execute(self.postgres("resolve_order_intent")?)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// The order `client_order_id` from the order ledger.
+    pub async fn get_order_intent(&self, client_order_id: Uuid) -> Result<Option<OrderIntent>, DbError> {
+        let row = sqlx::query!(
+            r#"
+            SELECT client_order_id, decision_id, symbol, side, quantity, reduce_only, state, execution_id, created_at, resolved_at
+            FROM order_intents
+            WHERE client_order_id = $1
+            "#,
+            client_order_id
+        )
+        .fetch_optional(self.postgres("get_order_intent")?)
+        .await?;
+        Ok(row.map(|row| OrderIntent {
+            client_order_id: row.client_order_id,
+            decision_id: row.decision_id,
+            symbol: row.symbol,
+            side: if row.side == "SELL" { OrderSide::Sell } else { OrderSide::Buy },
+            quantity: row.quantity,
+            reduce_only: row.reduce_only,
+            state: OrderIntentState::from_db(&row.state),
+            execution_id: row.execution_id,
+            created_at: row.created_at,
+            resolved_at: row.resolved_at,
+        }))
+    }
+
+    /// The orders still sent, i.e. whose fate the engine has not learned yet, oldest first.
+    pub async fn get_unresolved_order_intents(&self) -> Result<Vec<OrderIntent>, DbError> {
+        let rows = sqlx::query!(
+            r#"
+            SELECT client_order_id, decision_id, symbol, side, quantity, reduce_only, state, execution_id, created_at, resolved_at
+            FROM order_intents
+            WHERE state = 'sent'
+            ORDER BY created_at, client_order_id
+            "#
+        )
+        .fetch_all(self.postgres("get_unresolved_order_intents")?)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| OrderIntent {
+                client_order_id: row.client_order_id,
+                decision_id: row.decision_id,
+                symbol: row.symbol,
+                side: if row.side == "SELL" { OrderSide::Sell } else { OrderSide::Buy },
+                quantity: row.quantity,
+                reduce_only: row.reduce_only,
+                state: OrderIntentState::from_db(&row.state),
+                execution_id: row.execution_id,
+                created_at: row.created_at,
+                resolved_at: row.resolved_at,
+            })
+            .collect())
+    }
+
+    /// The live equity recorded in `[from, to)`, oldest first.
+    pub async fn get_live_equity(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<(DateTime<Utc>, Decimal)>, DbError> {
+        let rows = sqlx::query!(
+            "SELECT recorded_at, equity FROM live_equity WHERE recorded_at >= $1 AND recorded_at < $2 ORDER BY recorded_at",
+            from,
+            to
+        )
+        .fetch_all(self.postgres("get_live_equity")?)
+        .await?;
+        Ok(rows.into_iter().map(|row| (row.recorded_at, row.equity)).collect())
+    }
+
+    /// The last live equity recorded before `at`, if any.
+    pub async fn get_live_equity_before(&self, at: DateTime<Utc>) -> Result<Option<(DateTime<Utc>, Decimal)>, DbError> {
+        let row = sqlx::query!(
+            "SELECT recorded_at, equity FROM live_equity WHERE recorded_at < $1 ORDER BY recorded_at DESC LIMIT 1",
+            at
+        )
+        .fetch_optional(self.postgres("get_live_equity_before")?)
+        .await?;
+        Ok(row.map(|row| (row.recorded_at, row.equity)))
+    }
+
+    /// Saves `rollup`, replacing any earlier rollup of the same period.
+    pub async fn upsert_performance_rollup(&self, granularity: RollupGranularity, rollup: &PerformanceRollup) -> Result<(), DbError> {
+        // The daily and weekly tables share their columns, so one statement serves both.
+        let query = format!(
+            r#"
+            INSERT INTO {} (
+                period_start, net_pnl, fees, trade_count, winning_trades, win_rate_pct,
+                max_drawdown, max_drawdown_pct, ending_equity, computed_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            ON CONFLICT (period_start) DO UPDATE SET
+                net_pnl = EXCLUDED.net_pnl,
+                fees = EXCLUDED.fees,
+                trade_count = EXCLUDED.trade_count,
+                winning_trades = EXCLUDED.winning_trades,
+                win_rate_pct = EXCLUDED.win_rate_pct,
+                max_drawdown = EXCLUDED.max_drawdown,
+                max_drawdown_pct = EXCLUDED.max_drawdown_pct,
+                ending_equity = EXCLUDED.ending_equity,
+                computed_at = EXCLUDED.computed_at
+            "#,
+            granularity.table()
+        );
+        sqlx::query(&query)
+            .bind(rollup.period_start)
+            .bind(rollup.net_pnl)
+            .bind(rollup.fees)
+            .bind(rollup.trade_count)
+            .bind(rollup.winning_trades)
+            .bind(rollup.win_rate_pct)
+            .bind(rollup.max_drawdown)
+            .bind(rollup.max_drawdown_pct)
+            .bind(rollup.ending_equity)
+            .bind(rollup.computed_at)
+            .execute(self.postgres("upsert_performance_rollup")?)
+            .await?;
+        Ok(())
+    }
+
+    /// The stored rollups whose period starts within `[from, to]`, oldest first. Unset
+    /// bounds do not filter.
+    pub async fn get_performance_rollups(
+        &self,
+        granularity: RollupGranularity,
+        from: Option<NaiveDate>,
+        to: Option<NaiveDate>,
+    ) -> Result<Vec<PerformanceRollup>, DbError> {
+        let query = format!(
+            r#"
+            SELECT period_start, net_pnl, fees, trade_count, winning_trades, win_rate_pct,
+                   max_drawdown, max_drawdown_pct, ending_equity, computed_at
+            FROM {}
+            WHERE ($1::DATE IS NULL OR period_start >= $1)
+              AND ($2::DATE IS NULL OR period_start <= $2)
+            ORDER BY period_start
+            "#,
+            granularity.table()
+        );
+        let rollups = sqlx::query_as::<_, PerformanceRollup>(&query).bind(from).bind(to).fetch_all(self.postgres("get_performance_rollups")?).await?;
+        Ok(rollups)
+    }
+
+    /// Rebuilds the live positions matching `filter` from their recorded fills, most
+    /// recently opened first.
+    pub async fn get_live_positions(&self, filter: &LivePositionFilter) -> Result<Vec<LivePosition>, DbError> {
+        let closed = filter.status.map(|status| status == LivePositionStatus::Closed);
+        let rows = sqlx::query!(
+            r#"
+            WITH positions AS (
+                SELECT
+                    position_id,
+                    symbol,
+                    position_side,
+                    MIN(executed_at) AS entry_time,
+                    MAX(executed_at) FILTER (WHERE NOT is_entry AND position_quantity = 0) AS exit_time,
+                    SUM(price * quantity) FILTER (WHERE is_entry) / NULLIF(SUM(quantity) FILTER (WHERE is_entry), 0) AS avg_entry_price,
+                    SUM(price * quantity) FILTER (WHERE NOT is_entry) / NULLIF(SUM(quantity) FILTER (WHERE NOT is_entry), 0) AS avg_exit_price,
+                    (ARRAY_AGG(position_quantity ORDER BY executed_at DESC, is_entry))[1] AS quantity,
+                    MAX(position_quantity) AS max_quantity,
+                    SUM(realized_pnl) - SUM(fee) AS realized_pnl,
+                    SUM(fee) AS fees,
+                    MAX(close_reason) AS close_reason
+                FROM live_executions
+                GROUP BY position_id, symbol, position_side
+            )
+            SELECT
+                position_id AS "position_id!",
+                symbol AS "symbol!",
+                position_side AS "position_side!",
+                entry_time AS "entry_time!",
+                exit_time,
+                avg_entry_price,
+                avg_exit_price,
+                quantity AS "quantity!",
+                max_quantity AS "max_quantity!",
+                realized_pnl AS "realized_pnl!",
+                fees AS "fees!",
+                close_reason
+            FROM positions
+            WHERE ($1::TEXT IS NULL OR symbol = $1)
+              AND ($2::TIMESTAMPTZ IS NULL OR entry_time >= $2)
+              AND ($3::TIMESTAMPTZ IS NULL OR entry_time <= $3)
+              AND ($4::BOOLEAN IS NULL OR (exit_time IS NOT NULL) = $4)
+              AND ($5::UUID[] IS NULL OR position_id = ANY($5))
+            ORDER BY entry_time DESC, position_id
+            "#,
+            filter.symbol,
+            filter.from,
+            filter.to,
+            closed,
+            filter.position_ids.as_deref()
+        )
+        .fetch_all(self.postgres("get_live_positions")?)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| LivePosition {
+                position_id: row.position_id,
+                symbol: row.symbol,
+                side: if row.position_side == "SELL" { OrderSide::Sell } else { OrderSide::Buy },
+                status: if row.exit_time.is_some() { LivePositionStatus::Closed } else { LivePositionStatus::Open },
+                entry_time: Some(row.entry_time),
+                exit_time: row.exit_time,
+                avg_entry_price: row.avg_entry_price.map(|price| price.normalize()),
+                avg_exit_price: row.avg_exit_price.map(|price| price.normalize()),
+                quantity: row.quantity,
+                max_quantity: row.max_quantity,
+                realized_pnl: row.realized_pnl,
+                fees: row.fees,
+                unrealized_pnl: None,
+                close_reason: row.close_reason.as_deref().map(CloseReason::from_db),
+            })
+            .collect())
+    }
+}
+
+/// The number of klines written per `INSERT` statement.
+const KLINE_CHUNK_SIZE: usize = 5_000;
+
+/// The number of book ticker updates written per `INSERT` statement.
+const BOOK_TICKER_CHUNK_SIZE: usize = 5_000;
+
+/// The number of equity curve points written per `INSERT` statement.
+const EQUITY_CURVE_CHUNK_SIZE: usize = 5_000;
+
+/// Inserts equity curve points in multi-row chunks, each a single `UNNEST` statement, rather
+/// than one round trip per point.
+async fn insert_equity_points(
+    tx: &mut Transaction<'_, Postgres>,
+    run_id: Uuid,
+    equity_curve: &[(DateTime<Utc>, Decimal)],
+) -> Result<(), DbError> {
+    for chunk in equity_curve.chunks(EQUITY_CURVE_CHUNK_SIZE) {
+        let (timestamps, equities): (Vec<DateTime<Utc>>, Vec<Decimal>) = chunk.iter().copied().unzip();
+        sqlx::query!(
+            r#"
+            INSERT INTO equity_curves (run_id, timestamp, equity)
+            SELECT $1, * FROM UNNEST($2::timestamptz[], $3::numeric[])
+            "#,
+            run_id,
+            &timestamps,
+            &equities
+        )
+        .execute(&mut **tx)
+        .await?;
+    }
+    Ok(())
+}
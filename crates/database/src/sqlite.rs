@@ -0,0 +1,621 @@
+//! The SQLite versions of the repository methods that single backtest runs need.
+//!
+//! The Postgres queries are checked against a live database at compile time, which only
+//! works for one backend, so these are runtime-checked queries against the schema in
+//! `migrations_sqlite`. SQLite has no decimal, UUID or timestamp types: decimals and UUIDs are
+//! stored as text through `Text`, and timestamps as RFC 3339 text, which compares in time
+//! order as long as every value is written by `chrono`.
+
+use crate::repository::{BackfillProgress, FullReport, RunMetadata};
+use crate::DbError;
+use analytics::{ExitStats, PerformanceReport, RDistribution};
+use chrono::{DateTime, Utc};
+use core_types::{BookSummary, BookTicker, Kline, PriceType, Trade};
+use rust_decimal::Decimal;
+use serde_json::Value as JsonValue;
+use sqlx::sqlite::{SqlitePool, SqliteRow};
+use sqlx::types::{Json, Text};
+use sqlx::Row;
+use uuid::Uuid;
+
+/// Reads a nullable decimal column.
+fn decimal(row: &SqliteRow, column: &str) -> Result<Option<Decimal>, sqlx::Error> {
+    Ok(row.try_get::<Option<Text<Decimal>>, _>(column)?.map(|text| text.0))
+}
+
+fn kline(row: &SqliteRow, interval: &str) -> Result<Kline, sqlx::Error> {
+    Ok(Kline {
+        open_time: row.try_get("open_time")?,
+        open: row.try_get::<Text<Decimal>, _>("open")?.0,
+        high: row.try_get::<Text<Decimal>, _>("high")?.0,
+        low: row.try_get::<Text<Decimal>, _>("low")?.0,
+        close: row.try_get::<Text<Decimal>, _>("close")?.0,
+        volume: row.try_get::<Text<Decimal>, _>("volume")?.0,
+        close_time: row.try_get("close_time")?,
+        interval: interval.to_string(),
+    })
+}
+
+pub(crate) async fn get_kline_symbols(pool: &SqlitePool) -> Result<Vec<String>, DbError> {
+    let symbols = sqlx::query_scalar("SELECT DISTINCT symbol FROM klines ORDER BY symbol")
+        .fetch_all(pool)
+        .await?;
+    Ok(symbols)
+}
+
+pub(crate) async fn get_klines_by_date_range(
+    pool: &SqlitePool,
+    symbol: &str,
+    interval: &str,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> Result<Vec<Kline>, DbError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT open_time, open, high, low, close, volume, close_time
+        FROM klines
+        WHERE symbol = ? AND interval = ? AND open_time >= ? AND open_time <= ?
+        ORDER BY open_time ASC
+        "#,
+    )
+    .bind(symbol)
+    .bind(interval)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(|row| kline(row, interval)).collect::<Result<_, _>>()?)
+}
+
+pub(crate) async fn get_klines_before(
+    pool: &SqlitePool,
+    symbol: &str,
+    interval: &str,
+    before: DateTime<Utc>,
+    count: usize,
+) -> Result<Vec<Kline>, DbError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT open_time, open, high, low, close, volume, close_time
+        FROM klines
+        WHERE symbol = ? AND interval = ? AND open_time < ?
+        ORDER BY open_time DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(symbol)
+    .bind(interval)
+    .bind(before)
+    .bind(count as i64)
+    .fetch_all(pool)
+    .await?;
+
+    let mut klines: Vec<Kline> = rows.iter().map(|row| kline(row, interval)).collect::<Result<_, _>>()?;
+    klines.reverse();
+    Ok(klines)
+}
+
+pub(crate) async fn save_kline(pool: &SqlitePool, symbol: &str, kline: &Kline, is_suspect: bool) -> Result<(), DbError> {
+    sqlx::query(
+        r#"
+        INSERT INTO klines (symbol, interval, open_time, close_time, open, high, low, close, volume, is_suspect)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (symbol, interval, open_time) DO NOTHING
+        "#,
+    )
+    .bind(symbol)
+    .bind(&kline.interval)
+    .bind(kline.open_time)
+    .bind(kline.close_time)
+    .bind(Text(kline.open))
+    .bind(Text(kline.high))
+    .bind(Text(kline.low))
+    .bind(Text(kline.close))
+    .bind(Text(kline.volume))
+    .bind(is_suspect)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn save_klines(pool: &SqlitePool, symbol: &str, klines: &[Kline], suspect: &[bool]) -> Result<u64, DbError> {
+    let mut inserted = 0;
+    let mut tx = pool.begin().await?;
+    for (kline, is_suspect) in klines.iter().zip(suspect) {
+        inserted += sqlx::query(
+            r#"
+            INSERT INTO klines (symbol, interval, open_time, close_time, open, high, low, close, volume, is_suspect)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT (symbol, interval, open_time) DO NOTHING
+            "#,
+        )
+        .bind(symbol)
+        .bind(&kline.interval)
+        .bind(kline.open_time)
+        .bind(kline.close_time)
+        .bind(Text(kline.open))
+        .bind(Text(kline.high))
+        .bind(Text(kline.low))
+        .bind(Text(kline.close))
+        .bind(Text(kline.volume))
+        .bind(is_suspect)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    }
+    tx.commit().await?;
+    Ok(inserted)
+}
+
+pub(crate) async fn save_mark_price_kline(pool: &SqlitePool, symbol: &str, kline: &Kline) -> Result<(), DbError> {
+    sqlx::query(
+        r#"
+        INSERT INTO mark_price_klines (symbol, interval, open_time, close_time, open, high, low, close)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT (symbol, interval, open_time) DO NOTHING
+        "#,
+    )
+    .bind(symbol)
+    .bind(&kline.interval)
+    .bind(kline.open_time)
+    .bind(kline.close_time)
+    .bind(Text(kline.open))
+    .bind(Text(kline.high))
+    .bind(Text(kline.low))
+    .bind(Text(kline.close))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn get_mark_price_klines_by_date_range(
+    pool: &SqlitePool,
+    symbol: &str,
+    interval: &str,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> Result<Vec<Kline>, DbError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT open_time, open, high, low, close, '0' AS volume, close_time
+        FROM mark_price_klines
+        WHERE symbol = ? AND interval = ? AND open_time >= ? AND open_time <= ?
+        ORDER BY open_time ASC
+        "#,
+    )
+    .bind(symbol)
+    .bind(interval)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.iter().map(|row| kline(row, interval)).collect::<Result<_, _>>()?)
+}
+
+pub(crate) async fn save_book_tickers(pool: &SqlitePool, symbol: &str, tickers: &[BookTicker]) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    for ticker in tickers {
+        sqlx::query(
+            r#"
+            INSERT INTO book_tickers (symbol, event_time, best_bid_price, best_bid_qty, best_ask_price, best_ask_qty)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(symbol)
+        .bind(ticker.timestamp)
+        .bind(Text(ticker.best_bid_price))
+        .bind(Text(ticker.best_bid_qty))
+        .bind(Text(ticker.best_ask_price))
+        .bind(Text(ticker.best_ask_qty))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Decimals are stored as text, so they are cast to REAL for the arithmetic: text that looks
+/// like an integer would otherwise divide as one.
+pub(crate) async fn get_book_summaries(
+    pool: &SqlitePool,
+    symbol: &str,
+    interval: &str,
+    start_date: DateTime<Utc>,
+    end_date: DateTime<Utc>,
+) -> Result<Vec<(DateTime<Utc>, BookSummary)>, DbError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT open_time,
+               COUNT(relative_spread) AS spread_samples,
+               TOTAL(relative_spread) AS relative_spread_sum,
+               COUNT(imbalance) AS imbalance_samples,
+               TOTAL(imbalance) AS imbalance_sum
+        FROM (
+            SELECT k.open_time,
+                   (CAST(b.best_ask_price AS REAL) - CAST(b.best_bid_price AS REAL))
+                       / NULLIF((CAST(b.best_bid_price AS REAL) + CAST(b.best_ask_price AS REAL)) / 2, 0) AS relative_spread,
+                   (CAST(b.best_bid_qty AS REAL) - CAST(b.best_ask_qty AS REAL))
+                       / NULLIF(CAST(b.best_bid_qty AS REAL) + CAST(b.best_ask_qty AS REAL), 0) AS imbalance
+            FROM klines k
+            JOIN book_tickers b ON b.symbol = k.symbol AND b.event_time >= k.open_time AND b.event_time <= k.close_time
+            WHERE k.symbol = ? AND k.interval = ? AND k.open_time >= ? AND k.open_time <= ?
+        ) updates
+        GROUP BY open_time
+        ORDER BY open_time ASC
+        "#,
+    )
+    .bind(symbol)
+    .bind(interval)
+    .bind(start_date)
+    .bind(end_date)
+    .fetch_all(pool)
+    .await?;
+
+    let summaries = rows
+        .iter()
+        .map(|row| {
+            let summary = BookSummary {
+                spread_samples: row.try_get::<i64, _>("spread_samples")? as u64,
+                relative_spread_sum: row.try_get("relative_spread_sum")?,
+                imbalance_samples: row.try_get::<i64, _>("imbalance_samples")? as u64,
+                imbalance_sum: row.try_get("imbalance_sum")?,
+            };
+            Ok((row.try_get("open_time")?, summary))
+        })
+        .collect::<Result<_, sqlx::Error>>()?;
+    Ok(summaries)
+}
+
+pub(crate) async fn get_backfill_progress(
+    pool: &SqlitePool,
+    symbol: &str,
+    interval: &str,
+    price_type: PriceType,
+) -> Result<Vec<BackfillProgress>, DbError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT range_start, last_completed_range_end
+        FROM backfill_progress
+        WHERE symbol = ? AND interval = ? AND price_type = ?
+        ORDER BY range_start
+        "#,
+    )
+    .bind(symbol)
+    .bind(interval)
+    .bind(price_type.as_str())
+    .fetch_all(pool)
+    .await?;
+
+    let progress = rows
+        .iter()
+        .map(|row| {
+            Ok(BackfillProgress {
+                range_start: row.try_get("range_start")?,
+                last_completed_range_end: row.try_get("last_completed_range_end")?,
+            })
+        })
+        .collect::<Result<_, sqlx::Error>>()?;
+    Ok(progress)
+}
+
+pub(crate) async fn save_backfill_progress(
+    pool: &SqlitePool,
+    symbol: &str,
+    interval: &str,
+    price_type: PriceType,
+    range_start: DateTime<Utc>,
+    completed_end: DateTime<Utc>,
+) -> Result<(), DbError> {
+    sqlx::query(
+        r#"
+        INSERT INTO backfill_progress (symbol, interval, price_type, range_start, last_completed_range_end, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT (symbol, interval, price_type, range_start) DO UPDATE
+        SET last_completed_range_end = MAX(backfill_progress.last_completed_range_end, excluded.last_completed_range_end),
+            updated_at = ?6
+        "#,
+    )
+    .bind(symbol)
+    .bind(interval)
+    .bind(price_type.as_str())
+    .bind(range_start)
+    .bind(completed_end)
+    .bind(Utc::now())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn save_optimization_job(
+    pool: &SqlitePool,
+    job_id: Uuid,
+    strategy_id: &str,
+    symbol: &str,
+    status: &str,
+    job_config: Option<&JsonValue>,
+) -> Result<(), DbError> {
+    let now = Utc::now();
+    sqlx::query(
+        "INSERT INTO optimization_jobs (job_id, strategy_id, symbol, job_status, created_at, updated_at, job_config) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(Text(job_id))
+    .bind(strategy_id)
+    .bind(symbol)
+    .bind(status)
+    .bind(now)
+    .bind(now)
+    .bind(job_config.map(Json))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn get_job_config(pool: &SqlitePool, job_id: Uuid) -> Result<Option<JsonValue>, DbError> {
+    let row = sqlx::query("SELECT job_config FROM optimization_jobs WHERE job_id = ?")
+        .bind(Text(job_id))
+        .fetch_optional(pool)
+        .await?
+        .ok_or(DbError::NotFound)?;
+    Ok(row.try_get::<Option<Json<JsonValue>>, _>("job_config")?.map(|config| config.0))
+}
+
+pub(crate) async fn update_job_status(pool: &SqlitePool, job_id: Uuid, status: &str) -> Result<(), DbError> {
+    sqlx::query("UPDATE optimization_jobs SET job_status = ?, updated_at = ? WHERE job_id = ?")
+        .bind(status)
+        .bind(Utc::now())
+        .bind(Text(job_id))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn save_backtest_run(
+    pool: &SqlitePool,
+    run_id: Uuid,
+    job_id: Uuid,
+    parameters: &JsonValue,
+    status: &str,
+) -> Result<(), DbError> {
+    sqlx::query("INSERT INTO backtest_runs (run_id, job_id, parameters, run_status, created_at) VALUES (?, ?, ?, ?, ?)")
+        .bind(Text(run_id))
+        .bind(Text(job_id))
+        .bind(Json(parameters))
+        .bind(status)
+        .bind(Utc::now())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn get_run_status(pool: &SqlitePool, run_id: Uuid) -> Result<String, DbError> {
+    let status = sqlx::query_scalar("SELECT run_status FROM backtest_runs WHERE run_id = ?")
+        .bind(Text(run_id))
+        .fetch_optional(pool)
+        .await?;
+    status.ok_or(DbError::NotFound)
+}
+
+pub(crate) async fn update_run_status(pool: &SqlitePool, run_id: Uuid, status: &str) -> Result<(), DbError> {
+    sqlx::query("UPDATE backtest_runs SET run_status = ? WHERE run_id = ?")
+        .bind(status)
+        .bind(Text(run_id))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn save_run_metadata(pool: &SqlitePool, run_id: Uuid, metadata: &RunMetadata) -> Result<(), DbError> {
+    sqlx::query(
+        "UPDATE backtest_runs SET started_at = ?, finished_at = ?, bars_processed = ?, engine_version = ?, config_hash = ?, interval = ?, data_start = ?, data_end = ?, signals_filtered = ?, brackets_stop_first = ?, brackets_target_first = ? WHERE run_id = ?",
+    )
+    .bind(metadata.started_at)
+    .bind(metadata.finished_at)
+    .bind(metadata.bars_processed)
+    .bind(&metadata.engine_version)
+    .bind(&metadata.config_hash)
+    .bind(&metadata.interval)
+    .bind(metadata.data_start)
+    .bind(metadata.data_end)
+    .bind(metadata.signals_filtered)
+    .bind(metadata.brackets_stop_first)
+    .bind(metadata.brackets_target_first)
+    .bind(Text(run_id))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn save_run_objective(pool: &SqlitePool, run_id: Uuid, objective_value: Option<Decimal>) -> Result<(), DbError> {
+    sqlx::query("UPDATE backtest_runs SET objective_value = ? WHERE run_id = ?")
+        .bind(objective_value.map(Text))
+        .bind(Text(run_id))
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub(crate) async fn save_performance_report(pool: &SqlitePool, run_id: Uuid, report: &PerformanceReport) -> Result<(), DbError> {
+    sqlx::query(
+        r#"
+        INSERT INTO performance_reports (
+            report_id, run_id, total_net_profit, gross_profit, gross_loss, profit_factor,
+            total_return_pct, max_drawdown, max_drawdown_pct, sharpe_ratio,
+            calmar_ratio, total_trades, winning_trades, losing_trades,
+            win_rate_pct, average_win, average_loss, payoff_ratio, average_holding_period,
+            maker_fees_paid, taker_fees_paid, max_consecutive_losses, exit_breakdown,
+            average_r, expectancy_r, r_distribution, total_spread_cost, gap_slippage,
+            average_exposure_pct, peak_exposure_pct, time_in_market_pct, return_on_margin_pct, exposure_adjusted_sharpe
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(Text(Uuid::new_v4()))
+    .bind(Text(run_id))
+    .bind(Text(report.total_net_profit))
+    .bind(Text(report.gross_profit))
+    .bind(Text(report.gross_loss))
+    .bind(report.profit_factor.map(Text))
+    .bind(Text(report.total_return_pct))
+    .bind(Text(report.max_drawdown))
+    .bind(Text(report.max_drawdown_pct))
+    .bind(report.sharpe_ratio.map(Text))
+    .bind(report.calmar_ratio.map(Text))
+    .bind(report.total_trades as i32)
+    .bind(report.winning_trades as i32)
+    .bind(report.losing_trades as i32)
+    .bind(report.win_rate_pct.map(Text))
+    .bind(Text(report.average_win))
+    .bind(Text(report.average_loss))
+    .bind(report.payoff_ratio.map(Text))
+    .bind(report.average_holding_period.to_string())
+    .bind(Text(report.maker_fees_paid))
+    .bind(Text(report.taker_fees_paid))
+    .bind(report.max_consecutive_losses as i32)
+    .bind(Json(&report.exit_breakdown))
+    .bind(report.average_r.map(Text))
+    .bind(report.expectancy_r.map(Text))
+    .bind(report.average_r.map(|_| Json(report.r_distribution)))
+    .bind(Text(report.total_spread_cost))
+    .bind(Text(report.gap_slippage))
+    .bind(report.average_exposure_pct.map(Text))
+    .bind(report.peak_exposure_pct.map(Text))
+    .bind(report.time_in_market_pct.map(Text))
+    .bind(report.return_on_margin_pct.map(Text))
+    .bind(report.exposure_adjusted_sharpe.map(Text))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub(crate) async fn get_full_report_for_run(pool: &SqlitePool, run_id: Uuid) -> Result<FullReport, DbError> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            br.run_id, br.job_id, br.parameters, pr.report_id, pr.total_net_profit, pr.gross_profit, pr.gross_loss, pr.profit_factor, pr.total_return_pct, pr.max_drawdown, pr.max_drawdown_pct, pr.sharpe_ratio, pr.calmar_ratio, pr.total_trades, pr.winning_trades, pr.losing_trades, pr.win_rate_pct, pr.average_win, pr.average_loss, pr.payoff_ratio, pr.max_consecutive_losses, pr.average_holding_period, pr.maker_fees_paid, pr.taker_fees_paid, pr.total_spread_cost, pr.gap_slippage, pr.exit_breakdown, pr.average_r, pr.expectancy_r, pr.r_distribution,
+            pr.average_exposure_pct, pr.peak_exposure_pct, pr.time_in_market_pct, pr.return_on_margin_pct, pr.exposure_adjusted_sharpe,
+            br.started_at, br.finished_at, br.bars_processed, br.signals_filtered, br.brackets_stop_first, br.brackets_target_first, br.engine_version, br.config_hash, br.objective_value
+        FROM
+            performance_reports AS pr
+        JOIN
+            backtest_runs AS br ON pr.run_id = br.run_id
+        WHERE
+            br.run_id = ?
+        "#,
+    )
+    .bind(Text(run_id))
+    .fetch_optional(pool)
+    .await?
+    .ok_or(DbError::NotFound)?;
+
+    Ok(FullReport {
+        run_id: row.try_get::<Text<Uuid>, _>("run_id")?.0,
+        job_id: row.try_get::<Text<Uuid>, _>("job_id")?.0,
+        parameters: row.try_get::<Json<JsonValue>, _>("parameters")?.0,
+        report_id: row.try_get::<Option<Text<Uuid>>, _>("report_id")?.map(|text| text.0),
+        total_net_profit: decimal(&row, "total_net_profit")?,
+        gross_profit: decimal(&row, "gross_profit")?,
+        gross_loss: decimal(&row, "gross_loss")?,
+        profit_factor: decimal(&row, "profit_factor")?,
+        total_return_pct: decimal(&row, "total_return_pct")?,
+        max_drawdown: decimal(&row, "max_drawdown")?,
+        max_drawdown_pct: decimal(&row, "max_drawdown_pct")?,
+        sharpe_ratio: decimal(&row, "sharpe_ratio")?,
+        calmar_ratio: decimal(&row, "calmar_ratio")?,
+        total_trades: row.try_get("total_trades")?,
+        winning_trades: row.try_get("winning_trades")?,
+        losing_trades: row.try_get("losing_trades")?,
+        win_rate_pct: decimal(&row, "win_rate_pct")?,
+        average_win: decimal(&row, "average_win")?,
+        average_loss: decimal(&row, "average_loss")?,
+        payoff_ratio: decimal(&row, "payoff_ratio")?,
+        max_consecutive_losses: row.try_get("max_consecutive_losses")?,
+        average_holding_period: row.try_get("average_holding_period")?,
+        maker_fees_paid: decimal(&row, "maker_fees_paid")?,
+        taker_fees_paid: decimal(&row, "taker_fees_paid")?,
+        total_spread_cost: decimal(&row, "total_spread_cost")?,
+        gap_slippage: decimal(&row, "gap_slippage")?,
+        exit_breakdown: row.try_get::<Option<Json<Vec<ExitStats>>>, _>("exit_breakdown")?,
+        average_r: decimal(&row, "average_r")?,
+        expectancy_r: decimal(&row, "expectancy_r")?,
+        r_distribution: row.try_get::<Option<Json<RDistribution>>, _>("r_distribution")?,
+        average_exposure_pct: decimal(&row, "average_exposure_pct")?,
+        peak_exposure_pct: decimal(&row, "peak_exposure_pct")?,
+        time_in_market_pct: decimal(&row, "time_in_market_pct")?,
+        return_on_margin_pct: decimal(&row, "return_on_margin_pct")?,
+        exposure_adjusted_sharpe: decimal(&row, "exposure_adjusted_sharpe")?,
+        started_at: row.try_get("started_at")?,
+        finished_at: row.try_get("finished_at")?,
+        bars_processed: row.try_get("bars_processed")?,
+        signals_filtered: row.try_get("signals_filtered")?,
+        brackets_stop_first: row.try_get("brackets_stop_first")?,
+        brackets_target_first: row.try_get("brackets_target_first")?,
+        engine_version: row.try_get("engine_version")?,
+        config_hash: row.try_get("config_hash")?,
+        objective_value: decimal(&row, "objective_value")?,
+    })
+}
+
+pub(crate) async fn save_trades(pool: &SqlitePool, run_id: Uuid, trades: &[Trade]) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    for trade in trades {
+        sqlx::query(
+            r#"
+            INSERT INTO trades (
+                trade_id, run_id, symbol, entry_price, entry_qty, entry_timestamp,
+                exit_price, exit_qty, exit_timestamp, entry_side, entry_fee, exit_fee, fee_asset, close_reason, initial_risk
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Text(trade.trade_id))
+        .bind(Text(run_id))
+        .bind(&trade.symbol)
+        .bind(Text(trade.entry_execution.price))
+        .bind(Text(trade.entry_execution.quantity))
+        .bind(trade.entry_execution.timestamp)
+        .bind(Text(trade.exit_execution.price))
+        .bind(Text(trade.exit_execution.quantity))
+        .bind(trade.exit_execution.timestamp)
+        .bind(trade.entry_execution.side.as_str())
+        .bind(Text(trade.entry_execution.fee))
+        .bind(Text(trade.exit_execution.fee))
+        .bind(&trade.entry_execution.fee_asset)
+        .bind(trade.close_reason.as_str())
+        .bind(trade.initial_risk.map(Text))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn save_equity_curve(
+    pool: &SqlitePool,
+    run_id: Uuid,
+    equity_curve: &[(DateTime<Utc>, Decimal)],
+) -> Result<(), DbError> {
+    let mut tx = pool.begin().await?;
+    for (timestamp, equity) in equity_curve {
+        sqlx::query("INSERT INTO equity_curves (run_id, timestamp, equity) VALUES (?, ?, ?)")
+            .bind(Text(run_id))
+            .bind(timestamp)
+            .bind(Text(equity))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub(crate) async fn get_equity_curve(pool: &SqlitePool, run_id: Uuid) -> Result<Vec<(DateTime<Utc>, Decimal)>, DbError> {
+    let rows = sqlx::query("SELECT timestamp, equity FROM equity_curves WHERE run_id = ? ORDER BY timestamp ASC")
+        .bind(Text(run_id))
+        .fetch_all(pool)
+        .await?;
+
+    let curve = rows
+        .iter()
+        .map(|row| Ok((row.try_get("timestamp")?, row.try_get::<Text<Decimal>, _>("equity")?.0)))
+        .collect::<Result<_, sqlx::Error>>()?;
+    Ok(curve)
+}
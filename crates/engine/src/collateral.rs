@@ -0,0 +1,84 @@
+//! Values an account's collateral in its quote asset.
+//!
+//! Futures accounts can post several assets as margin (USDT, USDC, BNB, ...). The engine
+//! counts the balances of the configured collateral assets, converting each asset other than
+//! the quote asset at the mark price of its pair with it. The available balances, which the
+//! exchange already reduced by the margin posted, make up the cash; the wallet balances,
+//! before any margin, are what the margin of new orders is checked against.
+
+use api_client::{ApiClient, BalanceResponse};
+use core_types::CorrectionSource;
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+
+/// The balances of an account's accepted collateral assets, with the price in the quote asset
+/// of each other asset that could be priced.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Collateral {
+    /// The available balance of each asset, net of the margin it posts.
+    pub balances: BTreeMap<String, Decimal>,
+    /// The wallet balance of each asset, margin included.
+    pub wallet_balances: BTreeMap<String, Decimal>,
+    pub prices: HashMap<String, Decimal>,
+}
+
+impl Collateral {
+    /// Sets the portfolio's balances and cash from this collateral, as corrections from
+    /// `source`, and its wallet balance. Returns the assets left out of cash for lack of a
+    /// price.
+    pub fn apply(self, portfolio: &mut Portfolio, source: CorrectionSource) -> Vec<String> {
+        let wallet_balance = self
+            .wallet_balances
+            .iter()
+            .filter_map(|(asset, amount)| {
+                if *asset == portfolio.quote_asset {
+                    Some(*amount)
+                } else {
+                    self.prices.get(asset).map(|price| amount * price)
+                }
+            })
+            .sum();
+        portfolio.set_wallet_balance(wallet_balance);
+        portfolio.set_collateral(self.balances, &self.prices, source)
+    }
+}
+
+/// Picks the available and wallet balances of the `accepted` assets and `quote_asset`, which
+/// is always accepted, out of `balances`, and fetches the mark price of each other asset's pair with
+/// `quote_asset`.
+///
+/// An asset whose pair cannot be priced (it does not exist, or the request fails) is logged
+/// and gets no price, so it is left out of the account's cash rather than failing the sync.
+pub async fn fetch_collateral(
+    api_client: &dyn ApiClient,
+    balances: &[BalanceResponse],
+    accepted: &[String],
+    quote_asset: &str,
+) -> Collateral {
+    let mut collateral = Collateral::default();
+    for balance in balances {
+        let is_accepted = balance.asset == quote_asset || accepted.iter().any(|asset| asset == &balance.asset);
+        if (balance.available_balance.is_zero() && balance.balance.is_zero()) || !is_accepted {
+            continue;
+        }
+        if !balance.available_balance.is_zero() {
+            collateral.balances.insert(balance.asset.clone(), balance.available_balance);
+        }
+        if !balance.balance.is_zero() {
+            collateral.wallet_balances.insert(balance.asset.clone(), balance.balance);
+        }
+        if balance.asset == quote_asset {
+            continue;
+        }
+
+        let pair = format!("{}{}", balance.asset, quote_asset);
+        match api_client.get_mark_price(&pair).await {
+            Ok(price) => {
+                collateral.prices.insert(balance.asset.clone(), price);
+            }
+            Err(e) => tracing::warn!(asset = %balance.asset, pair = %pair, error = %e, "Failed to price collateral asset."),
+        }
+    }
+    collateral
+}
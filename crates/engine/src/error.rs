@@ -20,7 +20,10 @@ pub enum EngineError {
     #[error("Portfolio state error: {0}")]
     Portfolio(#[from] executor::ExecutorError),
 
-    #[error("Bot with symbol '{0}' not found in the engine.")]
+    #[error("Unknown symbols in the live configuration:\n{0}")]
+    UnknownSymbols(#[from] core_types::UnknownSymbols),
+
+    #[error("Bot '{0}' not found in the engine.")]
     BotNotFound(String),
 
     #[error("Serialization/deserialization error: {0}")]
@@ -1,6 +1,8 @@
 use api_client::{BookTickerUpdate, MarkPriceUpdate};
-use core_types::Kline;
+use chrono::{DateTime, TimeZone, Utc};
+use core_types::{BookTicker, BookWindow, Kline, MarketContext};
 use rust_decimal::Decimal;
+use std::collections::BTreeMap;
 
 /// A complete, real-time snapshot of the market for a single symbol.
 /// The engine will maintain one of these structs for each active bot.
@@ -8,8 +10,85 @@ use rust_decimal::Decimal;
 pub struct MarketState {
     pub last_kline: Option<Kline>,
     pub mark_price: Option<Decimal>,
+    /// The index (spot) price, from the mark price stream.
+    pub index_price: Option<Decimal>,
     pub best_bid: Option<Decimal>,
     pub best_ask: Option<Decimal>,
+    /// When the best bid and ask were quoted.
+    pub quoted_at: Option<DateTime<Utc>>,
+    /// The funding rate for the upcoming funding event, from the mark price stream.
+    pub current_funding_rate: Option<Decimal>,
+    pub next_funding_time: Option<DateTime<Utc>>,
+    /// The book ticker updates of the trailing day, summarised per minute.
+    pub book: BookWindow,
+    /// The close time of the latest kline received, per interval.
+    pub kline_close_times: BTreeMap<String, DateTime<Utc>>,
+    /// When the latest mark price update was received.
+    pub mark_price_at: Option<DateTime<Utc>>,
+}
+
+impl MarketState {
+    /// Records a kline received for one of the symbol's intervals. `last_kline` keeps the
+    /// latest close among all of them.
+    pub fn apply_kline(&mut self, kline: &Kline) {
+        self.kline_close_times
+            .entry(kline.interval.clone())
+            .and_modify(|close_time| *close_time = (*close_time).max(kline.close_time))
+            .or_insert(kline.close_time);
+        if self.last_kline.as_ref().is_none_or(|last| kline.close_time >= last.close_time) {
+            self.last_kline = Some(kline.clone());
+        }
+    }
+
+    /// Records a mark price update received at `received_at`, including the funding
+    /// information it carries.
+    pub fn apply_mark_price(&mut self, update: &MarkPriceUpdate, received_at: DateTime<Utc>) {
+        self.mark_price_at = Some(received_at);
+        self.mark_price = Some(update.mark_price);
+        self.index_price = update.index_price;
+        self.current_funding_rate = Some(update.funding_rate);
+        self.next_funding_time = Utc.timestamp_millis_opt(update.next_funding_time).single().filter(|_| update.next_funding_time > 0);
+    }
+
+    /// Records a book ticker update as the best bid and ask, and in the book window.
+    pub fn apply_book_ticker(&mut self, ticker: &BookTicker) {
+        self.best_bid = Some(ticker.best_bid_price);
+        self.best_ask = Some(ticker.best_ask_price);
+        self.quoted_at = Some(ticker.timestamp);
+        self.book.record(ticker);
+    }
+
+    /// The best bid and ask to execute against at `kline`'s close: those quoted while it was
+    /// open or within one kline length after it closed. Older quotes no longer describe the
+    /// market, and later ones belong to a kline still to come, which a replay can deliver
+    /// early; for either, neither is returned.
+    pub fn execution_quotes(&self, kline: &Kline) -> (Option<Decimal>, Option<Decimal>) {
+        let latest = kline.close_time + (kline.close_time - kline.open_time);
+        match self.quoted_at {
+            Some(quoted_at) if quoted_at >= kline.open_time && quoted_at <= latest => (self.best_bid, self.best_ask),
+            _ => (None, None),
+        }
+    }
+
+    /// The context handed to strategies alongside `kline`, with the book ticker updates
+    /// received while it was open.
+    pub fn context(&self, kline: &Kline) -> MarketContext {
+        MarketContext {
+            mark_price: self.mark_price,
+            index_price: self.index_price,
+            funding_rate: self.current_funding_rate,
+            next_funding_time: self.next_funding_time,
+            best_bid: self.best_bid,
+            best_ask: self.best_ask,
+            book: self.book.summary(kline.open_time, kline.close_time),
+        }
+    }
+
+    /// The freshest price available for marking positions to market: the exchange mark
+    /// price if one has arrived, otherwise the close of the last kline.
+    pub fn latest_price(&self) -> Option<Decimal> {
+        self.mark_price.or_else(|| self.last_kline.as_ref().map(|kline| kline.close))
+    }
 }
 
 /// A unified enum that represents any possible real-time event the engine can receive.
@@ -19,4 +98,16 @@ pub enum LiveEvent {
     Kline((String, Kline)),
     BookTicker(BookTickerUpdate),
     MarkPrice(MarkPriceUpdate),
-}
\ No newline at end of file
+}
+
+impl LiveEvent {
+    /// The symbol whose data feed this event proves alive. Book ticker updates don't count:
+    /// they come from a separate stream and say nothing about the kline feed.
+    pub fn data_symbol(&self) -> Option<&str> {
+        match self {
+            LiveEvent::Kline((symbol, _)) => Some(symbol),
+            LiveEvent::MarkPrice(update) => Some(&update.symbol),
+            LiveEvent::BookTicker(_) => None,
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! The health of the market data feeds, broadcast for dashboards.
+//!
+//! A quiet market still closes a kline every interval, so how long ago a feed's latest kline
+//! closed tells a quiet market from a lagging or dead feed. A `FeedMonitor` judges every
+//! subscribed symbol and interval that way from the engine's `MarketState`s and broadcasts
+//! the result as a `FeedStatus` every `feed_status_secs`.
+
+use crate::event::MarketState;
+use chrono::{DateTime, Utc};
+use core_types::Interval;
+use events::{EventBus, FeedHealth, FeedState, FeedStatus, WsMessage};
+use std::collections::HashMap;
+
+/// How many intervals after its latest kline closed a feed counts as delayed: the next
+/// kline is overdue by more than a quarter of the interval.
+pub const DELAYED_AFTER_INTERVALS: f64 = 1.25;
+
+/// How many intervals after its latest kline closed a feed counts as stale. Three, like the
+/// default feed timeout of the dead-man's switch.
+pub const STALE_AFTER_INTERVALS: f64 = 3.0;
+
+/// The health of a feed on `interval` whose latest kline closed `since_last_kline` ago.
+pub fn feed_health(since_last_kline: chrono::Duration, interval: Interval) -> FeedHealth {
+    let intervals = since_last_kline.to_std().unwrap_or_default().as_secs_f64() / interval.duration().as_secs_f64();
+    if intervals > STALE_AFTER_INTERVALS {
+        FeedHealth::Stale
+    } else if intervals > DELAYED_AFTER_INTERVALS {
+        FeedHealth::Delayed
+    } else {
+        FeedHealth::Healthy
+    }
+}
+
+/// Judges the health of the subscribed feeds.
+#[derive(Debug, Clone)]
+pub struct FeedMonitor {
+    /// The subscribed symbols and intervals, by symbol and then shortest interval first.
+    feeds: Vec<(String, Interval)>,
+    /// When the feeds were subscribed to. A feed without a kline yet is judged from it.
+    started_at: DateTime<Utc>,
+}
+
+impl FeedMonitor {
+    /// Creates a monitor of `feeds`, subscribed to at `started_at`.
+    pub fn new(feeds: impl IntoIterator<Item = (String, Interval)>, started_at: DateTime<Utc>) -> Self {
+        let mut feeds: Vec<_> = feeds.into_iter().collect();
+        feeds.sort();
+        feeds.dedup();
+        Self { feeds, started_at }
+    }
+
+    /// The status of every feed at `now`, from the data recorded in `market_states`.
+    pub fn status(&self, market_states: &HashMap<String, MarketState>, now: DateTime<Utc>) -> FeedStatus {
+        let feeds = self
+            .feeds
+            .iter()
+            .map(|(symbol, interval)| {
+                let state = market_states.get(symbol);
+                let kline = state.and_then(|state| state.kline_close_times.get(interval.as_str()).copied());
+                FeedState {
+                    symbol: symbol.clone(),
+                    interval: interval.to_string(),
+                    kline,
+                    book_ticker: state.and_then(|state| state.quoted_at),
+                    mark_price: state.and_then(|state| state.mark_price_at),
+                    status: feed_health(now - kline.unwrap_or(self.started_at), *interval),
+                }
+            })
+            .collect();
+        FeedStatus { timestamp: now, feeds }
+    }
+
+    /// Broadcasts the status of every feed at `now`.
+    pub fn broadcast(&self, market_states: &HashMap<String, MarketState>, now: DateTime<Utc>, event_tx: &EventBus) {
+        let _ = event_tx.send(WsMessage::FeedStatus(self.status(market_states, now)));
+    }
+}
@@ -0,0 +1,149 @@
+//! Latency instrumentation of the live decision path.
+//!
+//! Each kline is timed from the moment the engine dequeues it: through the strategy, the
+//! risk manager and the executor's acknowledgement. The exchange's close time is compared
+//! with the local receipt time to measure the feed delay. A `LatencyTracker` keeps the
+//! samples per symbol and stage and summarises them into a `LatencyReport` once per window.
+
+use chrono::Utc;
+use events::{LatencyReport, LatencyStats, SymbolLatency};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// A measured stretch of the decision path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LatencyStage {
+    /// From the kline's exchange close time to its local receipt.
+    FeedDelay,
+    /// From receipt to the strategy's decision.
+    Strategy,
+    /// From the strategy's decision to the risk manager's.
+    Risk,
+    /// From the order's submission to the executor's acknowledgement.
+    Execution,
+    /// From receipt to the executor's acknowledgement.
+    Decision,
+}
+
+impl LatencyStage {
+    /// The field of the `kline_decision` span that carries this stage, in milliseconds.
+    pub fn span_field(self) -> &'static str {
+        match self {
+            LatencyStage::FeedDelay => "feed_delay_ms",
+            LatencyStage::Strategy => "strategy_ms",
+            LatencyStage::Risk => "risk_ms",
+            LatencyStage::Execution => "execution_ms",
+            LatencyStage::Decision => "decision_ms",
+        }
+    }
+}
+
+/// The latency samples of one stage over the current window.
+#[derive(Debug, Clone, Default)]
+pub struct LatencyHistogram {
+    samples: Vec<Duration>,
+}
+
+impl LatencyHistogram {
+    pub fn record(&mut self, elapsed: Duration) {
+        self.samples.push(elapsed);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The nearest-rank `quantile` (between 0 and 1) of the samples.
+    pub fn percentile(&self, quantile: f64) -> Option<Duration> {
+        nearest_rank(&self.sorted(), quantile)
+    }
+
+    /// The p50, p99 and maximum of the samples, or `None` if there are none.
+    pub fn stats(&self) -> Option<LatencyStats> {
+        let sorted = self.sorted();
+        Some(LatencyStats {
+            samples: sorted.len() as u64,
+            p50_ms: millis(nearest_rank(&sorted, 0.5)?),
+            p99_ms: millis(nearest_rank(&sorted, 0.99)?),
+            max_ms: millis(*sorted.last()?),
+        })
+    }
+
+    fn sorted(&self) -> Vec<Duration> {
+        let mut sorted = self.samples.clone();
+        sorted.sort_unstable();
+        sorted
+    }
+}
+
+fn nearest_rank(sorted: &[Duration], quantile: f64) -> Option<Duration> {
+    let rank = (quantile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Collects decision path latencies per symbol and stage between reports.
+#[derive(Debug, Default)]
+pub struct LatencyTracker {
+    histograms: BTreeMap<String, BTreeMap<LatencyStage, LatencyHistogram>>,
+}
+
+impl LatencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a sample, and sets the matching field of the current `kline_decision` span.
+    pub fn record(&mut self, symbol: &str, stage: LatencyStage, elapsed: Duration) {
+        tracing::Span::current().record(stage.span_field(), millis(elapsed));
+        self.histograms
+            .entry(symbol.to_string())
+            .or_default()
+            .entry(stage)
+            .or_default()
+            .record(elapsed);
+    }
+
+    /// The samples recorded for a symbol and stage since the last report.
+    pub fn histogram(&self, symbol: &str, stage: LatencyStage) -> Option<&LatencyHistogram> {
+        self.histograms.get(symbol)?.get(&stage)
+    }
+
+    /// Summarises the window that just ended, sorted by symbol, and starts a new one.
+    /// Returns `None` if nothing was recorded.
+    pub fn report(&mut self, window: Duration) -> Option<LatencyReport> {
+        if self.histograms.is_empty() {
+            return None;
+        }
+        let symbols = std::mem::take(&mut self.histograms)
+            .into_iter()
+            .map(|(symbol, stages)| {
+                let stats = |stage| stages.get(&stage).and_then(LatencyHistogram::stats);
+                SymbolLatency {
+                    feed_delay: stats(LatencyStage::FeedDelay),
+                    strategy: stats(LatencyStage::Strategy),
+                    risk: stats(LatencyStage::Risk),
+                    execution: stats(LatencyStage::Execution),
+                    decision: stats(LatencyStage::Decision),
+                    symbol,
+                }
+            })
+            .collect();
+        Some(LatencyReport { timestamp: Utc::now(), window_secs: window.as_secs(), symbols })
+    }
+}
+
+/// Formats a stage's percentiles for the logs, e.g. "p50 12.4ms / p99 80.1ms (n=42)".
+pub fn summary(stats: &Option<LatencyStats>) -> String {
+    match stats {
+        Some(stats) => format!("p50 {:.1}ms / p99 {:.1}ms (n={})", stats.p50_ms, stats.p99_ms, stats.samples),
+        None => "-".to_string(),
+    }
+}
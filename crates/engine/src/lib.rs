@@ -1,26 +1,117 @@
 use crate::error::EngineError;
 use crate::event::{LiveEvent, MarketState}; // <-- NEW
+use crate::feed_status::FeedMonitor;
+use crate::latency::LatencyStage;
+use crate::persistence::{Persistence, PersistenceSettings};
 use crate::risk_manager::GlobalRiskManager; // <-- ADD THIS
-use api_client::{ApiClient, BookTickerUpdate, LiveConnector, MarkPriceUpdate};
-use configuration::{Config, LiveConfig};
-use database::DbRepository;
+use crate::safety::SafetyGuard;
+use crate::valuation::LiquidationEstimator;
+use crate::watchdog::{DeadMansSwitch, FeedWatchdog};
+use api_client::{ApiClient, BookTickerUpdate, LiveConnector, MarkPriceUpdate, MarketDataConnector};
+use configuration::{Config, LiveBotConfig, LiveConfig, OrphanPositionPolicy, QuoteAssets};
+use core_types::{BookTicker, CorrectionSource, OrderRequest, OrderType, Signal, SignalIntent, StrategyId, TradeDirection};
+use database::{DbRepository, OrderIntentState};
 use executor::{Executor, Portfolio};
-use risk::RiskManager;
-use std::collections::HashMap;
+use risk::{RiskManager, SimpleRiskManager};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
 use std::sync::Arc;
-use strategies::Strategy;
-use tokio::sync::{broadcast, mpsc, Mutex}; // <-- Add MPSC
+use strategies::{KlineTransformer, Strategy, StrategyError};
+use tokio::sync::{mpsc, Mutex}; // <-- Add MPSC
+use tokio::time::{interval, Duration, Instant, MissedTickBehavior};
+use tracing::Instrument;
 use uuid::Uuid;
-use chrono::Utc;
-use events::{LogMessage, LogLevel, WsMessage};
+use chrono::{DateTime, Utc};
+use events::{BotActivity, EngineStats, EventBus, LogMessage, LogLevel, WsMessage};
 
+/// Logs `message` via tracing and broadcasts it as a `WsMessage::Log`, with each
+/// `key = value` pair attached to both: as a field of the tracing event and as a key of the
+/// `LogMessage`'s JSON `fields`. Values may be anything `Serialize`. The enclosing span's
+/// fields (symbol, interval, strategy, decision ID) are added by the subscriber and need not
+/// be repeated.
+macro_rules! log_with {
+    ($event_tx:expr, $level:expr, $message:expr $(,)?) => {{
+        let (level, message): (events::LogLevel, &str) = ($level, &$message);
+        match level {
+            events::LogLevel::Info => tracing::info!("{}", message),
+            events::LogLevel::Warn => tracing::warn!("{}", message),
+            events::LogLevel::Error => tracing::error!("{}", message),
+        }
+        $crate::broadcast_log(&$event_tx, level, message, None);
+    }};
+    ($event_tx:expr, $level:expr, $message:expr, $($key:ident = $value:expr),+ $(,)?) => {{
+        let (level, message): (events::LogLevel, &str) = ($level, &$message);
+        let fields = serde_json::json!({ $(stringify!($key): $value),+ });
+        match level {
+            events::LogLevel::Info => tracing::info!($($key = %fields[stringify!($key)],)+ "{}", message),
+            events::LogLevel::Warn => tracing::warn!($($key = %fields[stringify!($key)],)+ "{}", message),
+            events::LogLevel::Error => tracing::error!($($key = %fields[stringify!($key)],)+ "{}", message),
+        }
+        $crate::broadcast_log(&$event_tx, level, message, Some(fields));
+    }};
+}
+
+pub mod collateral;
 pub mod error;
 pub mod event;
+pub mod feed_status;
+pub mod latency;
+pub mod order_ledger;
+pub mod persistence;
+pub mod pipeline;
+pub mod position_context;
 pub mod reconciler;
+pub mod replay;
 pub mod util;
 pub mod risk_manager;
+pub mod risk_state;
+pub mod safety;
+pub mod valuation;
+pub mod watchdog;
 
+pub use order_ledger::{IntentRecovery, IntentResolution, OrderLedger, OrderLookup};
+pub use pipeline::{BotRisk, PipelineOutcome, SignalPipeline};
+pub use position_context::{PositionContexts, PositionRecovery, Recovery};
 pub use reconciler::StateReconciler;
+pub use replay::ReplayConnector;
+pub use risk_state::RiskState;
+
+/// The leverage a bot trades at when `live.toml` does not set one.
+pub const DEFAULT_LEVERAGE: u8 = 10;
+
+/// How often the live equity is recorded for the daily and weekly performance rollups.
+const EQUITY_RECORD_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often recorded book ticker updates are written to the database, when recording is on.
+const BOOK_TICKER_FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often open positions are checked against the trading blackouts they must be flat for.
+const BLACKOUT_FLATTEN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// A signal closing all of `position` at `kline`'s close, for an exit the engine rather than
+/// the strategy decides on: a time limit, a stop-loss or a take-profit.
+fn close_signal(position: &core_types::Position, kline: &core_types::Kline) -> Signal {
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: kline.close_time,
+        confidence: rust_decimal::Decimal::ONE,
+        intent: Some(SignalIntent::Close),
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: position.symbol.clone(),
+            side: position.side.opposite(),
+            order_type: OrderType::Market,
+            quantity: position.quantity,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: true,
+            decision_id: None,
+        },
+    }
+}
+
 /// Rounds quantity to the appropriate precision for the given symbol.
 /// This is a simple implementation - in production, you'd fetch this from exchange info.
 fn round_quantity_to_precision(symbol: &str, quantity: rust_decimal::Decimal) -> rust_decimal::Decimal {
@@ -37,6 +128,24 @@ fn round_quantity_to_precision(symbol: &str, quantity: rust_decimal::Decimal) ->
     (quantity * scale).round() / scale
 }
 
+/// Broadcasts `message` as a `WsMessage::Log`; see `log_with!`, which also logs it via tracing.
+fn broadcast_log(event_tx: &EventBus, level: LogLevel, message: &str, fields: Option<serde_json::Value>) {
+    let log_msg = WsMessage::Log(LogMessage {
+        timestamp: Utc::now(),
+        level,
+        message: message.to_string(),
+        fields,
+    });
+
+    // We don't care if there are no subscribers, so we ignore the error.
+    let _ = event_tx.send(log_msg);
+}
+
+/// The error alert raised when trading on `symbol` is halted, starting with `reason`.
+fn halted(reason: &str, symbol: &str) -> String {
+    format!("{} BOT HALTED: Trading for {} has been disabled. Restart the engine to resume it.", reason, symbol)
+}
+
 /// A wrapper for Kline data that includes the symbol information.
 /// This is needed because the Kline struct doesn't contain symbol information.
 #[derive(Debug, Clone)]
@@ -45,12 +154,77 @@ pub struct KlineWithSymbol {
     pub kline: core_types::Kline,
 }
 
+/// Identifies a bot by the symbol it trades and the kline interval its strategy runs on. A
+/// symbol may run several bots, one per interval.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BotId {
+    pub symbol: String,
+    pub interval: String,
+}
+
+impl BotId {
+    pub fn new(symbol: &str, interval: &str) -> Self {
+        Self { symbol: symbol.to_string(), interval: interval.to_string() }
+    }
+}
+
+impl fmt::Display for BotId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.symbol, self.interval)
+    }
+}
+
+/// Builds a bot's strategy from the base configuration and the bot's live configuration.
+pub type StrategyFactory = Arc<dyn Fn(&Config, &LiveBotConfig) -> Result<Box<dyn Strategy>, EngineError> + Send + Sync>;
+
 /// A container for the components related to a single trading instrument.
 pub struct Bot {
     pub symbol: String,
     pub interval: String, // <-- ADD
     pub leverage: u8,     // <-- ADD
+    pub strategy_id: StrategyId,
     pub strategy: Box<dyn Strategy>,
+    /// Preprocesses the klines this bot's strategy sees. Holds per-symbol state.
+    pub kline_transform: KlineTransformer,
+    /// The sides this bot may take positions on.
+    pub direction: TradeDirection,
+    /// The bot's entry in the engine's activity stats.
+    pub activity: Arc<BotActivity>,
+    /// The klines closed since the open position was entered; zero while flat.
+    pub holding_bars: u32,
+    /// The close time of the last kline processed, against which duplicate and late klines
+    /// are dropped.
+    pub last_close_time: Option<DateTime<Utc>>,
+    /// The last klines the strategy evaluated, as many as it needs to warm up, from which it
+    /// is re-warmed after a reset.
+    pub recent_klines: VecDeque<core_types::Kline>,
+    /// How many klines in a row the strategy has failed to evaluate.
+    pub consecutive_errors: u32,
+    /// The bot's own risk settings and risk manager, when its `[bot.risk]` block overrides
+    /// `[risk_management]`; the pipeline's otherwise.
+    pub risk: Option<Arc<BotRisk>>,
+}
+
+impl Bot {
+    /// Keeps `kline`, which the strategy has just evaluated, for re-warming it later.
+    fn remember(&mut self, kline: &core_types::Kline) {
+        let capacity = self.strategy.required_warmup_bars();
+        while !self.recent_klines.is_empty() && self.recent_klines.len() >= capacity {
+            self.recent_klines.pop_front();
+        }
+        if capacity > 0 {
+            self.recent_klines.push_back(kline.clone());
+        }
+    }
+
+    /// Resets the strategy and feeds it the recent klines again, discarding their signals.
+    fn rewarm(&mut self) -> Result<(), StrategyError> {
+        self.strategy.reset();
+        for kline in &self.recent_klines {
+            self.strategy.evaluate(kline)?;
+        }
+        Ok(())
+    }
 }
 
 /// The central orchestrator for the live trading application.
@@ -58,25 +232,46 @@ pub struct LiveEngine {
     // --- Configuration ---
     live_config: LiveConfig,
     base_config: Config,
+    /// Each bot symbol's quote asset; the portfolio is denominated in the default one.
+    quote_assets: QuoteAssets,
 
     // --- Shared, Thread-Safe Components ---
     api_client: Arc<dyn ApiClient>, // Still needed for state reconciliation
+    connector: Arc<dyn MarketDataConnector>, // The source of the market data streams
+    /// Replaying recorded data: the exchange account is never touched.
+    replay: bool,
     executor: Arc<dyn Executor>,   // The generic executor for placing orders
     db_repo: DbRepository,
+    /// Writes the engine's records behind the event loop.
+    persistence: Persistence,
     portfolio: Arc<Mutex<Portfolio>>,
-    risk_manager: Arc<dyn RiskManager>,
 
     // --- NEW: The event broadcaster ---
-    event_tx: broadcast::Sender<WsMessage>,
+    event_tx: EventBus,
 
     // --- NEW: Global Risk Components ---
     global_risk_manager: Arc<GlobalRiskManager>,
     trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
 
     // --- Bot Management ---
-    bots: HashMap<String, Bot>,
-    /// NEW: The engine's real-time view of the market for each symbol.
+    bots: HashMap<BotId, Bot>,
+    /// Builds each bot's strategy when the engine initializes.
+    strategy_factory: StrategyFactory,
+    /// NEW: The engine's real-time view of the market for each symbol, shared by the
+    /// symbol's bots.
     market_states: HashMap<String, MarketState>,
+    /// Estimates open positions' liquidation prices from each bot's leverage.
+    liquidation: LiquidationEstimator,
+    /// Turns each bot's klines into orders.
+    pipeline: SignalPipeline,
+    /// The open positions' stops and origins, kept by the pipeline.
+    position_contexts: Arc<PositionContexts>,
+    /// The orders sent but not resolved, whose symbols take no new entries.
+    order_ledger: Arc<OrderLedger>,
+    /// Activity counters read by the web server's stats endpoint.
+    stats: Arc<EngineStats>,
+    /// Book ticker updates awaiting their write to the database, per symbol.
+    recorded_book_tickers: HashMap<String, Vec<BookTicker>>,
 }
 
 
@@ -89,11 +284,12 @@ impl LiveEngine {
         executor: Arc<dyn Executor>, // <-- NEW: Accepts a generic executor
         db_repo: DbRepository,
         risk_manager: Arc<dyn RiskManager>,
-        event_tx: broadcast::Sender<WsMessage>, // <-- ADD THIS
+        event_tx: EventBus, // <-- ADD THIS
     ) -> Self {
-        let portfolio = Arc::new(Mutex::new(Portfolio::new(
-            base_config.backtest.initial_capital,
-        )));
+        let quote_assets = QuoteAssets::for_live(&base_config, &live_config);
+        let portfolio = Arc::new(Mutex::new(
+            Portfolio::new(base_config.backtest.initial_capital).with_quote_asset(quote_assets.default_asset()),
+        ));
 
         // --- NEW: Construct the GRM and its shared state ---
         let trading_enabled_flags = Arc::new(Mutex::new(HashMap::new()));
@@ -103,76 +299,265 @@ impl LiveEngine {
             Arc::clone(&trading_enabled_flags),
             event_tx.clone(),
             base_config.backtest.initial_capital, // Provide initial equity
+            RiskState::default(),
         ));
         // --- END NEW ---
+        let liquidation = LiquidationEstimator::new(base_config.global_risk.maintenance_margin_rate);
+        let safety = SafetyGuard::new(
+            live_config.kill_switch_file.clone(),
+            live_config.max_open_positions,
+            live_config.max_orders_per_hour,
+            Arc::clone(&trading_enabled_flags),
+            event_tx.clone(),
+        );
+
+        let stats = Arc::new(EngineStats::new());
+        let position_contexts = Arc::new(PositionContexts::new());
+        let order_ledger = Arc::new(OrderLedger::new());
+        let persistence = Persistence::spawn(db_repo.clone(), PersistenceSettings::default());
+        let pipeline = SignalPipeline::new(&base_config, risk_manager, Arc::clone(&executor), Arc::clone(&portfolio), persistence.clone(), event_tx.clone())
+            .with_trading_flags(Arc::clone(&trading_enabled_flags))
+            .with_risk_multiplier(global_risk_manager.risk_multiplier())
+            .with_daily_loss(global_risk_manager.daily_loss())
+            .with_position_contexts(Arc::clone(&position_contexts))
+            .with_order_ledger(Arc::clone(&order_ledger))
+            .with_order_lookups(Arc::clone(&api_client), IntentRecovery { quote_assets: quote_assets.clone() })
+            .with_safety(safety)
+            .with_stats(Arc::clone(&stats))
+            .with_kline_broadcasts(live_config.broadcast_klines);
+
+        let connector = Arc::new(LiveConnector::new(live_config.live_trading_enabled));
 
         Self {
             live_config,
             base_config,
+            quote_assets,
             api_client, // The ApiClient is now passed through
+            connector,
+            replay: false,
             executor,   // Store the generic executor
             db_repo,
+            persistence,
             portfolio,
-            risk_manager,
             event_tx, // <-- STORE IT
             bots: HashMap::new(),
+            strategy_factory: Arc::new(util::create_strategy_from_live_config),
             market_states: HashMap::new(),
+            liquidation,
+            pipeline,
+            position_contexts,
+            order_ledger,
+            stats,
+            recorded_book_tickers: HashMap::new(),
             global_risk_manager, // <-- STORE IT
             trading_enabled_flags, // <-- STORE IT
         }
     }
 
+    /// Replaces the exchange's WebSocket streams with `connector`, e.g. a scripted feed.
+    pub fn with_connector(mut self, connector: Arc<dyn MarketDataConnector>) -> Self {
+        self.connector = connector;
+        self
+    }
+
+    /// Records the engine's activity into `stats`, e.g. ones shared with the web server.
+    pub fn with_stats(mut self, stats: Arc<EngineStats>) -> Self {
+        self.pipeline = self.pipeline.with_stats(Arc::clone(&stats));
+        self.stats = stats;
+        self
+    }
+
+    /// Builds the bots' strategies with `factory` rather than from their configured strategy
+    /// and parameters, e.g. to run instrumented strategies in tests.
+    pub fn with_strategy_factory(mut self, factory: StrategyFactory) -> Self {
+        self.strategy_factory = factory;
+        self
+    }
+
+    /// Resumes the global risk manager from the `restored` state recorded before a restart,
+    /// and records its changes from now on. Without it the engine starts its risk accounting
+    /// afresh and records none of it, as replays do. The restored halts take effect in `init`.
+    pub fn with_risk_state(mut self, restored: RiskState) -> Self {
+        let global_risk_manager = GlobalRiskManager::new(
+            self.base_config.global_risk.clone(),
+            Arc::clone(&self.portfolio),
+            Arc::clone(&self.trading_enabled_flags),
+            self.event_tx.clone(),
+            self.base_config.backtest.initial_capital,
+            restored,
+        )
+        .with_persistence(self.persistence.clone());
+        self.pipeline = self
+            .pipeline
+            .with_risk_multiplier(global_risk_manager.risk_multiplier())
+            .with_daily_loss(global_risk_manager.daily_loss());
+        self.global_risk_manager = Arc::new(global_risk_manager);
+        self
+    }
+
+    /// Records nothing to the database: no audit trail, history or order ledger, and no
+    /// ledger to recover orders from at startup. Orders are still tracked in memory. For
+    /// engines trading an account that is not real, such as a mock in tests. Call it before
+    /// `with_risk_state`, which records through the engine's persistence.
+    pub fn without_persistence(mut self) -> Self {
+        self.persistence = Persistence::disabled();
+        self.pipeline = self.pipeline.with_persistence(self.persistence.clone());
+        self
+    }
+
+    /// Runs the engine on recorded data from `connector`, such as a `ReplayConnector`.
+    ///
+    /// The portfolio starts from the configured initial capital instead of the exchange
+    /// account, no leverage is set and no reconciliation runs. `run` returns once the
+    /// connector's streams have ended.
+    pub fn with_replay(mut self, connector: Arc<dyn MarketDataConnector>) -> Self {
+        self.connector = connector;
+        self.replay = true;
+        self.pipeline = self.pipeline.with_replay(true);
+        self
+    }
+
     /// A helper method to both log via tracing and broadcast a WsMessage::Log.
     fn log(&self, level: LogLevel, message: &str) {
-        let msg = message.to_string();
-        match level {
-            LogLevel::Info => tracing::info!("{}", msg),
-            LogLevel::Warn => tracing::warn!("{}", msg),
-            LogLevel::Error => tracing::error!("{}", msg),
+        log_with!(self.event_tx, level, message);
+    }
+
+    /// Records the portfolio's equity, marked to the latest known prices, for the performance
+    /// rollups and the dynamic leverage tiers, and checks the daily loss limits. The point is
+    /// queued for the persistence task, so a slow database never holds up the event loop.
+    /// Replays are not live history and are not recorded.
+    async fn record_equity(&self) {
+        let state = {
+            let mut portfolio = self.portfolio.lock().await;
+            valuation::mark_to_market(&mut portfolio, &self.market_states, &self.liquidation)
+        };
+        self.global_risk_manager.on_equity(state.total_value).await;
+        if self.replay {
+            return;
         }
-        
-        let log_msg = WsMessage::Log(LogMessage {
-            timestamp: Utc::now(),
-            level,
-            message: msg,
-        });
+        self.global_risk_manager.check_daily_loss(&state).await;
+        self.persistence.record_equity(state.timestamp, state.total_value);
+    }
 
-        // We don't care if there are no subscribers, so we ignore the error.
-        let _ = self.event_tx.send(log_msg);
+    /// Queues the book ticker updates recorded since the last flush, for datasets with order
+    /// book features. Like equity points, they are dropped if the persistence queue is full.
+    fn flush_book_tickers(&mut self) {
+        for (symbol, tickers) in self.recorded_book_tickers.drain() {
+            if !tickers.is_empty() {
+                self.persistence.record_book_tickers(symbol, tickers);
+            }
+        }
     }
-    
-    /// Helper to broadcast the current portfolio state.
+
+    /// Stops the engine's writes: queues the recorded book tickers, then waits until every
+    /// queued record is written. Records queued afterwards are dropped.
+    pub async fn shutdown(&mut self) {
+        self.flush_book_tickers();
+        self.persistence.shutdown().await;
+        if self.persistence.dropped() > 0 {
+            tracing::warn!(dropped = self.persistence.dropped(), "Records were dropped while the persistence queue was full.");
+        }
+    }
+
+    /// Closes the positions due to be flat for a trading blackout at `now`, rather than on
+    /// their bots' next kline, which may close inside the window. Broadcasts the portfolio if
+    /// any was closed.
+    async fn flatten_ahead_of_blackouts(&mut self, now: DateTime<Utc>) -> Result<(), EngineError> {
+        let default_state = MarketState::default();
+        let mut filled = false;
+        for bot in self.bots.values_mut() {
+            let market = self.market_states.get(&bot.symbol).unwrap_or(&default_state);
+            if let Some(outcome) = self.pipeline.flatten_ahead_of_blackout(bot, market, now).await {
+                filled |= outcome.filled();
+            }
+        }
+        if filled {
+            self.broadcast_portfolio_state().await?;
+        }
+        Ok(())
+    }
+
+    /// Copies the bots' halted flags and losing streaks, which live behind locks, into their
+    /// activity stats. Runs once a second, off the kline path.
+    async fn refresh_bot_stats(&self) {
+        let losses = self.global_risk_manager.consecutive_losses().await;
+        let flags = self.trading_enabled_flags.lock().await;
+        for bot in self.bots.values() {
+            bot.activity.set_halted(!flags.get(&bot.symbol).copied().unwrap_or(false));
+            bot.activity.set_consecutive_losses(losses.get(&bot.symbol).copied().unwrap_or(0));
+        }
+    }
+
+    /// Helper to broadcast the current portfolio state, marked to the latest known prices.
     async fn broadcast_portfolio_state(&self) -> Result<(), EngineError> {
-        let portfolio = self.portfolio.lock().await;
-        // In a real system, we'd need a map of all live mark prices.
-        // For now, we'll send a simplified state.
-        let state_msg = WsMessage::PortfolioState(events::PortfolioState {
-            timestamp: Utc::now(),
-            cash: portfolio.cash,
-            total_value: portfolio.cash, // Simplified for now
-            positions: portfolio.positions.values().cloned().collect(),
-        });
-        
-        if self.event_tx.send(state_msg).is_err() {
-             // Optional: log if there are no listeners
+        let mut portfolio = self.portfolio.lock().await;
+        let state = valuation::mark_to_market(&mut portfolio, &self.market_states, &self.liquidation);
+        drop(portfolio);
+
+        self.global_risk_manager.on_equity(state.total_value).await;
+        if !self.replay {
+            self.global_risk_manager.check_daily_loss(&state).await;
         }
+        self.event_tx.send(WsMessage::PortfolioState(state));
         Ok(())
     }
 
     /// Initializes the engine, now setting leverage on a per-bot basis.
     pub async fn init(&mut self) -> Result<(), EngineError> {
         self.log(events::LogLevel::Info, "Initializing trading engine...");
-        self.sync_portfolio_state().await?;
-        self.log(events::LogLevel::Info, "Portfolio state synchronized with exchange.");
+        configuration::validate_quote_assets(&self.base_config, &self.live_config)
+            .map_err(|e| EngineError::Configuration(e.to_string()))?;
+        configuration::validate_bot_intervals(&self.base_config, &self.live_config)
+            .map_err(|e| EngineError::Configuration(e.to_string()))?;
+        if self.replay {
+            self.log(events::LogLevel::Info, &format!("Replaying recorded data from an initial capital of {}.", self.base_config.backtest.initial_capital));
+        } else {
+            self.validate_symbols().await?;
+            self.check_position_mode().await?;
+            // Every change to the live portfolio from here on, the sync included, is stored.
+            self.portfolio.lock().await.set_event_sink(Arc::new(self.persistence.clone()));
+            self.sync_portfolio_state().await?;
+            self.log(events::LogLevel::Info, "Portfolio state synchronized with exchange.");
+        }
         
         // This method now also sets leverage
         self.populate_bots_and_set_leverage().await?;
+        if !self.replay {
+            self.resolve_order_intents().await?;
+            self.restore_position_contexts().await;
+            self.restore_daily_loss().await;
+            // A rollover missed while the engine was down runs first, so the halts it ends
+            // are not resumed.
+            let now = Utc::now();
+            self.global_risk_manager.roll_over_if_due(now).await;
+            self.global_risk_manager.resume_halts(now).await;
+        }
         
         self.log(events::LogLevel::Info, "Engine initialization complete.");
         self.broadcast_portfolio_state().await?;
         Ok(())
     }
 
+    /// Checks every enabled bot's symbol against the exchange's tradable symbols, so a typo
+    /// fails here rather than at the first leverage or order call.
+    async fn validate_symbols(&self) -> Result<(), EngineError> {
+        let tradable = self.api_client.get_exchange_info().await?.tradable_symbols();
+        let symbols = self.live_config.bots.iter().filter(|bot| bot.enabled).map(|bot| bot.symbol.as_str());
+        core_types::check_symbols(symbols, &tradable)?;
+        Ok(())
+    }
+
+    /// Checks that the account is in one-way mode. The engine tracks one position per symbol
+    /// and sends its orders without a position side, which an account in hedge mode rejects.
+    async fn check_position_mode(&self) -> Result<(), EngineError> {
+        if self.api_client.get_position_mode().await? {
+            return Err(EngineError::Configuration(
+                "The account is in hedge mode, but the engine trades in one-way mode. Switch the account to one-way mode.".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Fetches cash balance and open positions to create an accurate initial portfolio.
     async fn sync_portfolio_state(&mut self) -> Result<(), EngineError> {
         tracing::debug!("Fetching account balance and positions...");
@@ -180,55 +565,181 @@ impl LiveEngine {
         let positions = self.api_client.get_open_positions().await?;
         
         tracing::debug!("Found {} balance entries and {} open positions", balances.len(), positions.len());
-        
+
+        let collateral_assets = self.collateral_assets();
+        let quote_asset = self.quote_assets.default_asset();
+        let collateral = collateral::fetch_collateral(self.api_client.as_ref(), &balances, &collateral_assets, quote_asset).await;
+        if collateral.balances.is_empty() {
+            tracing::warn!("No balance in any collateral asset ({:?}) found in account.", collateral_assets);
+        }
+
         let mut portfolio = self.portfolio.lock().await;
 
-        // Find the USDT balance to set our cash value.
-        if let Some(usdt_balance) = balances.iter().find(|b| b.asset == "USDT") {
-            portfolio.cash = usdt_balance.available_balance;
-            tracing::info!("[ENGINE] Found USDT balance: {}", usdt_balance.available_balance);
-        } else {
-            // Handle case where there is no USDT, for now, we'll just log it.
-            tracing::warn!("No USDT balance found in account.");
-            portfolio.cash = "0".parse().unwrap();
+        // Cash is the quote asset value of every collateral asset that could be priced.
+        for asset in collateral.apply(&mut portfolio, CorrectionSource::StartupSync) {
+            tracing::warn!("[ENGINE] No {} price for collateral asset {}; leaving it out of equity.", quote_asset, asset);
         }
-        
+        tracing::info!("[ENGINE] Collateral balances: {:?}", portfolio.balances);
         tracing::info!("[ENGINE] Portfolio cash set to: {}", portfolio.cash);
 
-        // Clear any existing positions and reconstruct from the exchange's data.
-        portfolio.positions.clear();
-        let mut open_positions_count = 0;
-        let total_positions = positions.len();
-        for pos in positions {
-            // We only care about positions that are actually open (non-zero amount).
-            if pos.position_amt != rust_decimal::Decimal::ZERO {
-                open_positions_count += 1;
-                let side = if pos.position_amt.is_sign_positive() {
-                    core_types::OrderSide::Buy
-                } else {
-                    core_types::OrderSide::Sell
-                };
-                
-                let symbol = pos.symbol.clone();
-                let position = core_types::Position {
-                    position_id: Uuid::new_v4(),
-                    symbol: symbol.clone(),
-                    side,
-                    quantity: pos.position_amt.abs(),
-                    entry_price: pos.entry_price,
-                    unrealized_pnl: pos.un_realized_profit,
-                    last_updated: Utc::now(),
-                };
-                portfolio.positions.insert(symbol.clone(), position);
-                tracing::debug!("Added position: {} {:?} {:.4} @ {:.2}", 
-                    symbol, side, pos.position_amt.abs(), pos.entry_price);
+        // Bring the positions in line with the exchange's, as corrections to the portfolio.
+        for correction in portfolio.sync_positions(&positions, CorrectionSource::StartupSync) {
+            tracing::debug!("[ENGINE] Startup sync changed {}.", correction);
+        }
+        tracing::debug!("Total API positions: {}, Actual open positions: {}", positions.len(), portfolio.positions.len());
+
+        Ok(())
+    }
+
+    /// Asks the exchange what became of the orders recorded as sent before a restart. A filled
+    /// order's execution, which the engine never wrote, is recorded now; its effect is already
+    /// in the synced portfolio. An order the exchange never filled expires. An order still
+    /// working, or that could not be looked up, stays unresolved with an alert, and the
+    /// pipeline takes no entries on its symbol. When the ledger fails to load, none of them
+    /// can be checked, so the engine does not start. An engine without persistence has no
+    /// ledger to load.
+    ///
+    /// The recovered executions are written before this returns, so the position contexts and
+    /// the daily loss restored next include them.
+    async fn resolve_order_intents(&mut self) -> Result<(), EngineError> {
+        if self.persistence.is_disabled() {
+            return Ok(());
+        }
+        let stored = match self.db_repo.get_unresolved_order_intents().await {
+            Ok(stored) => stored,
+            Err(e) => {
+                self.log(LogLevel::Error, &format!("Could not load the order ledger: {}. Orders sent before the restart cannot be checked, so the engine does not start.", e));
+                return Err(e.into());
             }
+        };
+        if stored.is_empty() {
+            return Ok(());
         }
-        tracing::debug!("Total API positions: {}, Actual open positions: {}", total_positions, open_positions_count);
+        let contexts = self.db_repo.get_open_position_contexts().await.unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Failed to load the position contexts for recovered orders.");
+            Vec::new()
+        });
+        let recovery = IntentRecovery { quote_assets: self.quote_assets.clone() };
+        let now = Utc::now();
+        let resolutions = recovery
+            .run(stored, &contexts, &self.portfolio, &self.order_ledger, self.api_client.as_ref(), now)
+            .await;
 
+        for resolution in resolutions {
+            match resolution {
+                IntentResolution::Filled { intent, execution, fills } => {
+                    let message = format!("The {} order {} filled before the restart at {}; recording its execution.", order_ledger::describe(&intent), intent.client_order_id, execution.price);
+                    log_with!(self.event_tx, LogLevel::Warn, &message, intent = intent, execution = execution);
+                    self.pipeline.record_execution(&execution, &fills, None).await;
+                    self.pipeline.track_positions(None, &execution, &fills).await;
+                }
+                IntentResolution::Expired { intent, status } => {
+                    let status = status.unwrap_or_else(|| "unknown to the exchange".to_string());
+                    self.log(LogLevel::Info, &format!("The {} order {} sent before the restart never filled ({}).", order_ledger::describe(&intent), intent.client_order_id, status));
+                    self.persistence.resolve_order_intent(intent.client_order_id, OrderIntentState::Expired, None, now).await;
+                }
+                IntentResolution::Unresolved { intent, reason } => {
+                    let message = format!("The {} order {} sent before the restart is unresolved: {}. No entries are taken on {} until it is.", order_ledger::describe(&intent), intent.client_order_id, reason, intent.symbol);
+                    log_with!(self.event_tx, LogLevel::Error, &message, intent = intent);
+                }
+            }
+        }
+        self.persistence.flush().await;
         Ok(())
     }
 
+    /// Reattaches the contexts recorded before a restart to the positions the exchange still
+    /// holds, so they are managed by their stops again, and applies the orphan position policy
+    /// to the others. Without the recorded contexts no position can be told apart from an
+    /// orphan, so if they fail to load, every position is left unmanaged with an alert rather
+    /// than adopted or flattened.
+    async fn restore_position_contexts(&mut self) {
+        let (stored, policy) = match self.db_repo.get_open_position_contexts().await {
+            Ok(stored) => (stored, self.live_config.orphan_position_policy),
+            Err(e) => {
+                self.log(LogLevel::Warn, &format!("Could not load the recorded position contexts: {}. Open positions are left unmanaged.", e));
+                (Vec::new(), OrphanPositionPolicy::IgnoreWithAlert)
+            }
+        };
+        let recovery = PositionRecovery { policy, stop_loss_pct: self.base_config.risk_management.stop_loss_pct };
+        let now = Utc::now();
+        let recoveries = recovery
+            .run(stored, &self.portfolio, &self.position_contexts, self.api_client.as_ref(), self.executor.as_ref(), now)
+            .await;
+
+        for recovery in recoveries {
+            match recovery {
+                Recovery::Restored { context } => {
+                    log_with!(self.event_tx, LogLevel::Info, &format!("Restored the stop of the {} position opened at {}.", context.symbol, context.opened_at), context = context);
+                    self.check_position_managed(&context.symbol, context.strategy_id);
+                }
+                Recovery::Adopted { context } => {
+                    let message = format!("Adopted the {} position, which has no recorded context, with a stop at {}.", context.symbol, context.stop_price.unwrap_or_default());
+                    log_with!(self.event_tx, LogLevel::Warn, &message, context = context);
+                    if let Err(e) = self.db_repo.open_position_context(&context).await {
+                        tracing::warn!(symbol = %context.symbol, error = %e, "Failed to record an adopted position's context.");
+                    }
+                    self.check_position_managed(&context.symbol, None);
+                }
+                Recovery::Flattened { symbol, execution, fills } => {
+                    self.log(LogLevel::Warn, &format!("Closed the {} position, which has no recorded context, at {}.", symbol, execution.price));
+                    self.stats.record_fill(Utc::now());
+                    let _ = self.event_tx.send(WsMessage::TradeExecuted((*execution).clone()));
+                    self.pipeline.record_execution(&execution, &fills, None).await;
+                }
+                Recovery::FlattenFailed { symbol, error } => {
+                    self.log(LogLevel::Error, &format!("CRITICAL: Failed to close the {} position, which has no recorded context: {}. It is left unmanaged, without a stop.", symbol, error));
+                }
+                Recovery::Ignored { symbol } => {
+                    self.log(LogLevel::Error, &format!("The {} position has no recorded context. It is left unmanaged, without a stop.", symbol));
+                }
+                Recovery::ClosedWhileDown { context } => {
+                    self.log(LogLevel::Warn, &format!("The {} position recorded as open is no longer open on the exchange; closing its context.", context.symbol));
+                    if let Err(e) = self.db_repo.close_position_context(&context.symbol, now).await {
+                        tracing::warn!(symbol = %context.symbol, error = %e, "Failed to record a closed position's context.");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Rebuilds the current trading day's realized P&L from the live executions recorded since
+    /// its rollover, so a restart neither forgets the day's losses nor lifts a daily loss
+    /// halt early. The check at the end of `init` halts again whatever was breached. When the
+    /// executions cannot be read, the day starts over with a warning.
+    async fn restore_daily_loss(&self) {
+        let now = Utc::now();
+        let Some(day_start) = self.global_risk_manager.trading_day_start(now) else { return };
+        let fills = match self.db_repo.get_live_fills(day_start, now).await {
+            Ok(fills) => fills,
+            Err(e) => {
+                self.log(LogLevel::Warn, &format!("Failed to read today's executions; the daily loss limits count from now: {}", e));
+                return;
+            }
+        };
+        let start_equity = match self.db_repo.get_live_equity_before(day_start).await {
+            Ok(equity) => equity.map(|(_, equity)| equity),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to read the equity at the daily rollover; estimating it.");
+                None
+            }
+        };
+
+        let realized = self.global_risk_manager.restore_daily_loss(day_start, start_equity, &fills).await;
+        self.log(LogLevel::Info, &format!("Restored {} executions since the {} daily rollover, realizing {}.", fills.len(), day_start.format("%Y-%m-%d %H:%M UTC"), realized.round_dp(2)));
+    }
+
+    /// Warns when the restored position on `symbol` no longer has a bot to watch its stop, or
+    /// when no bot on it runs `strategy_id`, the strategy that opened it, any more.
+    fn check_position_managed(&self, symbol: &str, strategy_id: Option<StrategyId>) {
+        let strategies: Vec<StrategyId> = self.bots.values().filter(|bot| bot.symbol == symbol).map(|bot| bot.strategy_id).collect();
+        if strategies.is_empty() {
+            self.log(LogLevel::Warn, &format!("No bot trades {}, so nothing watches the stop of its open position.", symbol));
+        } else if let Some(strategy_id) = strategy_id.filter(|strategy_id| !strategies.contains(strategy_id)) {
+            self.log(LogLevel::Warn, &format!("The {} position was opened by {:?}, which no bot on it runs any more. Its stop still applies.", symbol, strategy_id));
+        }
+    }
+
     /// Combines bot creation, leverage setting, and trading flag initialization.
     async fn populate_bots_and_set_leverage(&mut self) -> Result<(), EngineError> {
         let default_interval = self.base_config.backtest.interval.clone();
@@ -237,23 +748,41 @@ impl LiveEngine {
         for bot_config in &self.live_config.bots {
             if bot_config.enabled {
                 let interval = bot_config.interval.clone().unwrap_or_else(|| default_interval.clone());
-                let leverage = bot_config.leverage.unwrap_or(10); // Default to 10x if not set
+                let leverage = bot_config.leverage.unwrap_or(DEFAULT_LEVERAGE);
 
                 self.log(events::LogLevel::Info, &format!("Loading bot for {} on {} interval with {}x leverage.", bot_config.symbol, interval, leverage));
                 
-                let strategy = util::create_strategy_from_live_config(&self.base_config, bot_config)?;
+                let bot_id = BotId::new(&bot_config.symbol, &interval);
+                if self.bots.contains_key(&bot_id) {
+                    return Err(EngineError::Configuration(format!("More than one enabled bot trades {}.", bot_id)));
+                }
+                let strategy = (self.strategy_factory)(&self.base_config, bot_config)?;
+                let risk = self.bot_risk(bot_config)?;
                 
                 // Set leverage on the exchange for this specific symbol
-                self.api_client.set_leverage(&bot_config.symbol, leverage).await?;
+                if !self.replay {
+                    self.api_client.set_leverage(&bot_config.symbol, leverage).await?;
+                }
 
                 let bot = Bot {
                     symbol: bot_config.symbol.clone(),
+                    activity: self.stats.register_bot(&bot_config.symbol, &interval),
                     interval,
                     leverage,
+                    strategy_id: bot_config.strategy_id,
                     strategy,
+                    kline_transform: KlineTransformer::new(bot_config.kline_transform),
+                    direction: bot_config.direction,
+                    holding_bars: 0,
+                    last_close_time: None,
+                    recent_klines: VecDeque::new(),
+                    consecutive_errors: 0,
+                    risk,
                 };
-                self.bots.insert(bot_config.symbol.clone(), bot);
+                self.bots.insert(bot_id, bot);
                 self.market_states.entry(bot_config.symbol.clone()).or_default();
+                self.liquidation.set_leverage(&bot_config.symbol, leverage);
+                self.pipeline.set_leverage(&bot_config.symbol, leverage);
 
                 // --- NEW: Initialize the trading flag for this bot ---
                 flags.insert(bot_config.symbol.clone(), true);
@@ -262,6 +791,17 @@ impl LiveEngine {
         Ok(())
     }
 
+    /// The risk of a bot overriding `[risk_management]`: a risk manager of its own, sizing by
+    /// the merged settings. `None` for bots trading by the engine's.
+    fn bot_risk(&self, bot_config: &LiveBotConfig) -> Result<Option<Arc<BotRisk>>, EngineError> {
+        if bot_config.risk.is_none() {
+            return Ok(None);
+        }
+        let settings = bot_config.risk_management(&self.base_config).map_err(|e| EngineError::Configuration(e.to_string()))?;
+        let manager = Arc::new(SimpleRiskManager::new(settings.clone())?);
+        Ok(Some(Arc::new(BotRisk::new(manager, settings, &self.base_config.simulation))))
+    }
+
     /// The main event loop, now capable of handling multiple intervals.
     pub async fn run(&mut self) -> Result<(), EngineError> {
         self.init().await?;
@@ -278,8 +818,7 @@ impl LiveEngine {
         }
 
         let (event_in_tx, mut event_in_rx) = mpsc::channel(1024);
-        let is_live = self.live_config.live_trading_enabled;
-        let connector = LiveConnector::new(is_live);
+        let connector = Arc::clone(&self.connector);
         
         // Subscribe to each interval group separately
         for (interval, symbols) in events_by_interval {
@@ -288,49 +827,268 @@ impl LiveEngine {
         }
         
         // Subscribe to universal streams for all symbols
-        let all_symbols: Vec<String> = self.bots.keys().cloned().collect();
+        let all_symbols = self.symbols();
         self.spawn_book_ticker_handler(connector.subscribe_to_book_tickers(&all_symbols)?, event_in_tx.clone());
-        self.spawn_mark_price_handler(connector.subscribe_to_mark_prices(&all_symbols)?, event_in_tx.clone());
+        self.spawn_mark_price_handler(connector.subscribe_to_mark_prices(&all_symbols)?, event_in_tx);
 
-        let reconciler = StateReconciler::new(
-            Arc::clone(&self.portfolio),
-            Arc::clone(&self.api_client),
-            self.db_repo.clone(),
-            self.event_tx.clone(), // Give the reconciler the sender
-        );
-        tokio::spawn(reconciler.start());
+        if !self.replay {
+            let reconciler = StateReconciler::new(
+                Arc::clone(&self.portfolio),
+                Arc::clone(&self.api_client),
+                self.db_repo.clone(),
+                self.event_tx.clone(), // Give the reconciler the sender
+                self.collateral_assets(),
+            )
+            .with_position_contexts(Arc::clone(&self.position_contexts));
+            tokio::spawn(reconciler.start());
+        }
         
         self.log(events::LogLevel::Info, "Engine is running. Waiting for market data...");
 
-        while let Some(event) = event_in_rx.recv().await {
-            if let Err(e) = self.handle_event(event).await {
-                self.log(events::LogLevel::Error, &format!("Failed to handle event: {:?}", e));
+        // Keeps dashboards fresh while a bot is quiet. Late ticks are dropped, not bunched up.
+        let mut broadcast_timer = interval(Duration::from_secs(self.live_config.portfolio_broadcast_secs.max(1)));
+        broadcast_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let mut dead_mans_switch = self.build_dead_mans_switch()?;
+        let mut watchdog_timer = interval(Duration::from_secs(1));
+        watchdog_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let mut equity_timer = interval(EQUITY_RECORD_INTERVAL);
+        equity_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let mut book_ticker_timer = interval(BOOK_TICKER_FLUSH_INTERVAL);
+        book_ticker_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let mut blackout_timer = interval(BLACKOUT_FLATTEN_INTERVAL);
+        blackout_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let latency_window = Duration::from_secs(self.live_config.latency_report_secs.max(1));
+        let mut latency_timer = interval(latency_window);
+        latency_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        let feed_monitor = self.build_feed_monitor()?;
+        let mut feed_status_timer = interval(Duration::from_secs(self.live_config.feed_status_secs.max(1)));
+        feed_status_timer.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                event = event_in_rx.recv() => {
+                    let Some(event) = event else { break };
+                    let received = Instant::now();
+                    if let Some(symbol) = event.data_symbol() {
+                        dead_mans_switch.on_data(symbol, received);
+                    }
+                    if let Err(e) = self.handle_event(event, received).await {
+                        self.log(events::LogLevel::Error, &format!("Failed to handle event: {:?}", e));
+                    }
+                }
+                _ = broadcast_timer.tick() => {
+                    valuation::try_broadcast_portfolio(&self.portfolio, &self.market_states, &self.liquidation, &self.event_tx);
+                }
+                _ = watchdog_timer.tick() => {
+                    let flattened = dead_mans_switch.check(tokio::time::Instant::now(), &self.market_states).await;
+                    if !flattened.is_empty() {
+                        for (execution, fills) in &flattened {
+                            self.stats.record_fill(Utc::now());
+                            self.pipeline.record_execution(execution, fills, None).await;
+                            self.pipeline.track_positions(None, execution, fills).await;
+                        }
+                        self.broadcast_portfolio_state().await?;
+                    }
+                    // Halts and rollovers follow the wall clock, which replays do not.
+                    if !self.replay {
+                        let now = Utc::now();
+                        self.global_risk_manager.roll_over_if_due(now).await;
+                        self.global_risk_manager.release_expired_halts(now).await;
+                    }
+                    self.refresh_bot_stats().await;
+                }
+                _ = latency_timer.tick() => {
+                    self.report_latency(latency_window);
+                }
+                // Recorded klines are replayed out of wall-clock time, so their feeds are not judged.
+                _ = feed_status_timer.tick(), if !self.replay => {
+                    feed_monitor.broadcast(&self.market_states, Utc::now(), &self.event_tx);
+                }
+                _ = equity_timer.tick() => {
+                    self.record_equity().await;
+                }
+                _ = book_ticker_timer.tick() => {
+                    self.flush_book_tickers();
+                }
+                // Blackouts follow the wall clock, which replays do not.
+                _ = blackout_timer.tick(), if !self.replay => {
+                    self.flatten_ahead_of_blackouts(Utc::now()).await?;
+                }
             }
         }
+        self.shutdown().await;
         
-        self.log(events::LogLevel::Error, "Main event stream ended unexpectedly.");
+        if self.replay {
+            self.log(events::LogLevel::Info, "Replay complete: all recorded klines were processed.");
+            self.broadcast_portfolio_state().await?;
+        } else {
+            self.log(events::LogLevel::Error, "Main event stream ended unexpectedly.");
+        }
         Ok(())
     }
 
+    /// The assets counted as collateral: those configured, and every quote asset in use.
+    fn collateral_assets(&self) -> Vec<String> {
+        let mut assets = self.live_config.collateral_assets.clone();
+        for asset in self.quote_assets.assets() {
+            if !assets.contains(&asset) {
+                assets.push(asset);
+            }
+        }
+        assets
+    }
+
+    /// The symbols the bots trade, each once.
+    fn symbols(&self) -> Vec<String> {
+        self.bots.keys().map(|id| id.symbol.clone()).collect::<BTreeSet<_>>().into_iter().collect()
+    }
+
+    /// Builds the feed watchdog for the running bots from the `live.toml` settings.
+    fn build_dead_mans_switch(&self) -> Result<DeadMansSwitch, EngineError> {
+        let feed_timeout = match self.live_config.feed_timeout_secs {
+            Some(secs) => Duration::from_secs(secs),
+            None => watchdog::default_feed_timeout(self.bots.values().map(|bot| bot.interval.as_str()))
+                .ok_or_else(|| EngineError::Configuration("Cannot derive feed_timeout_secs from the bot intervals; set it in live.toml.".to_string()))?,
+        };
+        let flatten_after = Duration::from_secs(self.live_config.flatten_after_secs);
+        let enabled = self.live_config.dead_mans_switch_enabled;
+        self.log(events::LogLevel::Info, &format!(
+            "Feed watchdog armed: silent after {}s, {} after {}s more.",
+            feed_timeout.as_secs(),
+            if enabled { "flattening" } else { "alerting only" },
+            flatten_after.as_secs(),
+        ));
+
+        let feed_watchdog = FeedWatchdog::new(self.symbols(), feed_timeout, flatten_after, tokio::time::Instant::now());
+        Ok(DeadMansSwitch::new(
+            feed_watchdog,
+            enabled,
+            Arc::clone(&self.portfolio),
+            Arc::clone(&self.executor),
+            Arc::clone(&self.trading_enabled_flags),
+            self.event_tx.clone(),
+        ))
+    }
+
+    /// Builds the monitor of the running bots' feeds, subscribed to as of now.
+    fn build_feed_monitor(&self) -> Result<FeedMonitor, EngineError> {
+        let feeds = self
+            .bots
+            .values()
+            .map(|bot| {
+                let interval = bot.interval.parse::<core_types::Interval>().map_err(|e| EngineError::Configuration(format!("Bot {}: {}", bot.symbol, e)))?;
+                Ok((bot.symbol.clone(), interval))
+            })
+            .collect::<Result<Vec<_>, EngineError>>()?;
+        Ok(FeedMonitor::new(feeds, Utc::now()))
+    }
+
+    /// Logs and broadcasts the latency percentiles of the window that just ended, if any
+    /// kline was processed during it.
+    fn report_latency(&self, window: Duration) {
+        let Some(report) = self.pipeline.latency_report(window) else { return };
+        for symbol in &report.symbols {
+            tracing::info!(
+                "[LATENCY] {} over {}s: decision {}, strategy {}, risk {}, execution {}, feed delay {}",
+                symbol.symbol,
+                report.window_secs,
+                latency::summary(&symbol.decision),
+                latency::summary(&symbol.strategy),
+                latency::summary(&symbol.risk),
+                latency::summary(&symbol.execution),
+                latency::summary(&symbol.feed_delay),
+            );
+        }
+        let _ = self.event_tx.send(WsMessage::LatencyReport(report));
+    }
+
     /// The new master event handler that routes events to specific logic.
-    async fn handle_event(&mut self, event: LiveEvent) -> Result<(), EngineError> {
+    ///
+    /// `received` is when the engine dequeued the event; a kline's decision latency is
+    /// measured from it.
+    async fn handle_event(&mut self, event: LiveEvent, received: Instant) -> Result<(), EngineError> {
         match event {
             LiveEvent::Kline((symbol, kline)) => {
-                // Update market state
-                self.market_states.entry(symbol.clone()).or_default().last_kline = Some(kline.clone());
-                // Process the kline for trading signals
-                self.process_kline_signal(&symbol, &kline).await?;
+                // A kline goes to the bot trading its symbol on its interval only, so a bot
+                // never sees the bars of another interval of the same symbol.
+                let bot_id = BotId::new(&symbol, &kline.interval);
+                let Some(bot) = self.bots.get_mut(&bot_id) else {
+                    tracing::debug!(bot = %bot_id, close_time = %kline.close_time, "Dropped a kline no bot trades.");
+                    return Ok(());
+                };
+
+                // A reconnect can replay the last closed kline, and a stream can deliver klines
+                // out of order. Each kline is processed once, in order: anything that does not
+                // close after the last one processed is dropped.
+                if let Some(last_close_time) = bot.last_close_time
+                    && kline.close_time <= last_close_time
+                {
+                    if kline.close_time == last_close_time {
+                        tracing::debug!(bot = %bot_id, close_time = %kline.close_time, "Dropped a duplicate kline.");
+                    } else {
+                        tracing::warn!(bot = %bot_id, close_time = %kline.close_time, last_close_time = %last_close_time, "Dropped a kline older than the last one processed.");
+                    }
+                    return Ok(());
+                }
+                bot.last_close_time = Some(kline.close_time);
+
+                // Every event of the decision is emitted inside this span, so a filter such as
+                // `RUST_LOG=engine[{symbol=BTCUSDT}]=debug` follows a single symbol's bots.
+                let span = tracing::info_span!(
+                    "kline_decision",
+                    symbol = %symbol,
+                    interval = %bot.interval,
+                    strategy_id = ?bot.strategy_id,
+                    decision_id = tracing::field::Empty,
+                    feed_delay_ms = tracing::field::Empty,
+                    strategy_ms = tracing::field::Empty,
+                    risk_ms = tracing::field::Empty,
+                    execution_ms = tracing::field::Empty,
+                    decision_ms = tracing::field::Empty,
+                );
+                async {
+                    // Clock skew can put the close time after receipt; count that as no delay.
+                    // Recorded klines have no meaningful feed delay.
+                    if !self.replay {
+                        let feed_delay = (Utc::now() - kline.close_time).to_std().unwrap_or_default();
+                        self.pipeline.record_latency(&symbol, LatencyStage::FeedDelay, feed_delay);
+                    }
+                    self.market_states.entry(symbol.clone()).or_default().apply_kline(&kline);
+                    // Process the kline for trading signals
+                    self.process_kline_signal(&bot_id, &kline, received).await
+                }
+                .instrument(span)
+                .await?;
             }
-            LiveEvent::BookTicker(ticker) => {
-                let state = self.market_states.entry(ticker.symbol.clone()).or_default();
-                state.best_bid = Some(ticker.best_bid_price);
-                state.best_ask = Some(ticker.best_ask_price);
+            LiveEvent::BookTicker(update) => {
+                let ticker = update.to_book_ticker(Utc::now());
+                self.market_states.entry(update.symbol.clone()).or_default().apply_book_ticker(&ticker);
+                if self.live_config.record_book_tickers && !self.replay {
+                    self.recorded_book_tickers.entry(update.symbol).or_default().push(ticker);
+                }
             }
             LiveEvent::MarkPrice(mark_price) => {
-                self.market_states.entry(mark_price.symbol.clone()).or_default().mark_price = Some(mark_price.mark_price);
+                self.market_states.entry(mark_price.symbol.clone()).or_default().apply_mark_price(&mark_price, Utc::now());
+
+                // Keep the open position's unrealized P&L current between portfolio syncs.
+                let marked = {
+                    let mut portfolio = self.portfolio.lock().await;
+                    portfolio.positions.get_mut(&mark_price.symbol).map(|position| {
+                        valuation::mark_position(position, mark_price.mark_price, &self.liquidation);
+                        position.clone()
+                    })
+                };
+                if let Some(position) = marked {
+                    self.global_risk_manager.check_liquidation_distance(&position, mark_price.mark_price).await;
+                }
             }
         }
-        // We can add a periodic portfolio broadcast here later.
         Ok(())
     }
 
@@ -359,144 +1117,46 @@ impl LiveEngine {
         });
     }
     
-    /// The core logic for processing a kline event to generate a trade.
-    async fn process_kline_signal(&mut self, symbol: &str, kline: &core_types::Kline) -> Result<(), EngineError> {
-        // --- 1. OBEY THE CIRCUIT BREAKER (Guard Clause) ---
-        let is_trading_enabled = {
-            let flags = self.trading_enabled_flags.lock().await;
-            *flags.get(symbol).unwrap_or(&false)
-        };
+    /// Recovers a bot whose strategy failed to evaluate a kline. The strategy is reset and
+    /// re-warmed from the bot's recent klines, so a transient fault costs a single kline. After
+    /// `max_strategy_errors` failures in a row, trading on the bot's symbol is halted instead.
+    async fn handle_strategy_error(&mut self, bot_id: &BotId, error: StrategyError) -> Result<(), EngineError> {
+        let max_errors = self.live_config.max_strategy_errors.max(1);
+        let bot = self.bots.get_mut(bot_id).ok_or_else(|| EngineError::BotNotFound(bot_id.to_string()))?;
+        bot.consecutive_errors += 1;
+        let errors = bot.consecutive_errors;
 
-        if !is_trading_enabled {
-            // Silently return if this bot has been halted.
-            // A debug log could be added here.
+        if errors >= max_errors {
+            self.pipeline.halt(&bot_id.symbol).await;
+            let reason = format!("Bot {} failed {} times in a row. Last error: {}.", bot_id, errors, error);
+            log_with!(self.event_tx, LogLevel::Error, halted(&reason, &bot_id.symbol), bot = bot_id.to_string(), consecutive_errors = errors, error = error.to_string());
             return Ok(());
         }
 
-        tracing::info!("[ENGINE] Processing kline for {}: broadcast_klines = {}", symbol, self.live_config.broadcast_klines);
-        // Broadcast kline data to WebSocket clients if enabled
-        if self.live_config.broadcast_klines {
-            let kline_data = events::KlineData {
-                symbol: symbol.to_string(),
-                kline: kline.clone(),
-            };
-            let msg = events::WsMessage::KlineData(kline_data);
-            tracing::info!("[ENGINE] Broadcasting kline data for {}: {:?}", symbol, msg);
-            match self.event_tx.send(msg) {
-                Ok(_) => {
-                    tracing::info!("[ENGINE] Successfully broadcast kline data for {}", symbol);
-                }
-                Err(e) => {
-                    tracing::error!("[ENGINE] Failed to broadcast kline data for {}: {:?}", symbol, e);
-                    // Check if it's a channel full error
-                    if e.to_string().contains("channel full") {
-                        tracing::error!("[ENGINE] Broadcast channel is full! Consider increasing capacity.");
-                    }
-                }
-            }
-        } else {
-            tracing::debug!("[ENGINE] Kline broadcasting is disabled in config");
-        }
-
-        let bot = self.bots.get_mut(symbol).ok_or_else(|| EngineError::BotNotFound(symbol.to_string()))?;
-
-        // --- 2. ENFORCE POSITION LIMIT (Guard Clause) ---
-        let position = self.portfolio.lock().await.get_position(symbol).cloned();
-        if let Some(pos) = position {
-            // If a position is already open, do not evaluate for a new entry signal.
-            // This enforces `max_open_positions_per_asset = 1`.
-            // We only proceed if the signal's side is opposite to the current position.
-            if let Some(signal) = bot.strategy.evaluate(kline)? {
-                if signal.order_request.side == pos.side {
-                    return Ok(());
-                }
-            }
-        }
-
-        if let Some(signal) = bot.strategy.evaluate(kline)? {
-            let bot_symbol = bot.symbol.clone();
-            let signal_side = signal.order_request.side;
-            let close_price = kline.close;
-            
-            self.log(LogLevel::Info, &format!("Signal generated for {}: {:?} at price {}", bot_symbol, signal_side, close_price));
-            tracing::info!("[ENGINE] About to enter risk management section for {}", bot_symbol);
-
-            let order_request = { // Scoped to release the lock quickly
-                tracing::info!("[ENGINE] About to lock portfolio for {}", bot_symbol);
-                let portfolio_guard = self.portfolio.lock().await;
-                tracing::info!("[ENGINE] Portfolio locked successfully for {}", bot_symbol);
-                // Create a map of all current prices needed for equity calculation
-                let mut market_prices = HashMap::new();
-                market_prices.insert(bot_symbol.clone(), close_price);
-                
-                // Add prices for any other symbols that have positions
-                for (pos_symbol, _) in &portfolio_guard.positions {
-                    if pos_symbol != &bot_symbol {
-                        // For now, we'll use the last known price or a default
-                        // In a real system, you'd fetch current prices for all symbols
-                        market_prices.insert(pos_symbol.clone(), rust_decimal_macros::dec!(0)); // Placeholder
-                    }
-                }
-                
-                let latest_equity = portfolio_guard.calculate_total_equity(&market_prices)?;
-                let portfolio_state = events::PortfolioState {
-                    timestamp: Utc::now(),
-                    cash: portfolio_guard.cash,
-                    total_value: latest_equity,
-                    positions: portfolio_guard.positions.values().cloned().collect(),
-                };
-                
-                tracing::info!("[ENGINE] Portfolio state - Cash: {}, Total Value: {}, Positions: {:?}", 
-                    portfolio_state.cash, portfolio_state.total_value, portfolio_state.positions);
-                
-                tracing::info!("[ENGINE] Calling risk manager with signal: {:?}", signal);
-                
-                match self.risk_manager.evaluate_signal(&signal, &portfolio_state, close_price) {
-                    Ok(order) => {
-                        tracing::info!("[ENGINE] Risk manager approved order: {:?}", order);
-                        order
-                    },
-                    Err(e) => {
-                        tracing::error!("[ENGINE] Risk management rejected signal: {:?}", e);
-                        self.log(LogLevel::Warn, &format!("Risk management rejected signal: {:?}", e));
-                        tracing::info!("[ENGINE] Skipping signal due to risk management rejection, but continuing to process klines");
-                        return Ok(()); // Skip this signal but continue processing
-                    }
-                }
-            };
-            self.log(LogLevel::Info, &format!("Risk assessment passed. Final Order: {:?} {} @ Market", order_request.quantity, order_request.symbol));
-
-            // Get the current market state for this symbol to provide best bid/ask prices
-            let default_state = MarketState::default();
-            let market_state = self.market_states.get(symbol).unwrap_or(&default_state);
-            let best_bid = market_state.best_bid;
-            let best_ask = market_state.best_ask;
-            
-            tracing::debug!("[ENGINE] Market state for {} - Best bid: {:?}, Best ask: {:?}", symbol, best_bid, best_ask);
-            
-            match self.executor.execute(&order_request, kline, best_bid, best_ask).await {
-                Ok(execution) => {
-                    self.log(LogLevel::Info, &format!("SUCCESS: Execution confirmed for {}: {:?}", execution.symbol, execution.price));
-                    
-                    // --- BROADCAST THE TRADE EVENT ---
-                    let _ = self.event_tx.send(events::WsMessage::TradeExecuted(execution.clone()));
-                    // --- END ---
+        let message = match bot.rewarm() {
+            Ok(()) => format!("Strategy of {} failed ({} of {} in a row): {}. Reset it and re-warmed it from {} recent klines.", bot_id, errors, max_errors, error, bot.recent_klines.len()),
+            Err(e) => format!("Strategy of {} failed ({} of {} in a row): {}. Reset it, but re-warming it failed too: {}", bot_id, errors, max_errors, error, e),
+        };
+        log_with!(self.event_tx, LogLevel::Warn, message, bot = bot_id.to_string(), consecutive_errors = errors, error = error.to_string());
+        Ok(())
+    }
 
-                    let mut portfolio = self.portfolio.lock().await;
-                    portfolio.update_with_execution(&execution)?;
-                    self.broadcast_portfolio_state().await?;
-                }
-                Err(e) => {
-                    self.log(LogLevel::Error, &format!("ERROR: Failed to execute order for {}: {:?}", bot_symbol, e));
-                }
-            }
+    /// Runs `kline` through the signal pipeline of the bot it belongs to and reacts to the
+    /// outcome: a failed strategy is recovered, and any fill is broadcast with the portfolio.
+    async fn process_kline_signal(&mut self, bot_id: &BotId, kline: &core_types::Kline, received: Instant) -> Result<(), EngineError> {
+        let bot = self.bots.get_mut(bot_id).ok_or_else(|| EngineError::BotNotFound(bot_id.to_string()))?;
+        let default_state = MarketState::default();
+        let market = self.market_states.get(&bot_id.symbol).unwrap_or(&default_state);
+        match self.pipeline.process(bot, kline, market, received).await {
+            PipelineOutcome::StrategyFailed { error } => self.handle_strategy_error(bot_id, error).await,
+            outcome if outcome.filled() => self.broadcast_portfolio_state().await,
+            _ => Ok(()),
         }
-        Ok(())
     }
 
     /// The core logic for processing a single market event (Kline).
     async fn process_kline(&mut self, symbol: &str, kline: &core_types::Kline) -> Result<(), EngineError> {
         // This method is kept for backward compatibility but now delegates to process_kline_signal
-        self.process_kline_signal(symbol, kline).await
+        self.process_kline_signal(&BotId::new(symbol, &kline.interval), kline, Instant::now()).await
     }
 }
@@ -0,0 +1,237 @@
+//! The idempotency ledger of the orders the live engine places.
+//!
+//! Before the pipeline sends an order, it records the order's intent in the `order_intents`
+//! table under its client order ID, and keeps it in the `OrderLedger` until the executor
+//! returns its execution or a rejection. A crash between the order leaving the engine and its
+//! execution being written would otherwise leave no trace of an order the exchange may well
+//! have filled. An order whose placement failed in any other way, e.g. timed out, may have
+//! been placed too: it stays in the ledger, and the pipeline asks the exchange what became of
+//! it before its next decision on the symbol. On startup,
+//! `IntentRecovery` asks the exchange what became of every intent still recorded as sent:
+//! filled orders get the execution the engine never wrote, orders the exchange never filled
+//! expire, and orders still working, or that could not be looked up, stay in the ledger,
+//! which refuses new entries on their symbol.
+
+use api_client::{ApiClient, OrderResponse};
+use chrono::{DateTime, Utc};
+use configuration::QuoteAssets;
+use core_types::{Execution, OrderRequest, OrderSide, PositionFill};
+use database::{OrderIntent, OrderIntentState, PositionContext};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// The orders sent but not resolved yet, by client order ID. Shared by the pipeline, which
+/// refuses entries on their symbols, and startup recovery, which resolves them.
+#[derive(Debug, Default)]
+pub struct OrderLedger {
+    unresolved: std::sync::Mutex<HashMap<Uuid, OrderIntent>>,
+}
+
+impl OrderLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks `intent` as sent and unresolved.
+    pub fn insert(&self, intent: OrderIntent) {
+        self.unresolved.lock().unwrap().insert(intent.client_order_id, intent);
+    }
+
+    /// Stops tracking the order `client_order_id`, returning its intent.
+    pub fn remove(&self, client_order_id: Uuid) -> Option<OrderIntent> {
+        self.unresolved.lock().unwrap().remove(&client_order_id)
+    }
+
+    /// The oldest unresolved order on `symbol`, if any.
+    pub fn unresolved_on(&self, symbol: &str) -> Option<OrderIntent> {
+        self.unresolved
+            .lock()
+            .unwrap()
+            .values()
+            .filter(|intent| intent.symbol == symbol)
+            .min_by_key(|intent| (intent.created_at, intent.client_order_id))
+            .cloned()
+    }
+
+    /// Every unresolved order, oldest first.
+    pub fn all(&self) -> Vec<OrderIntent> {
+        let mut intents: Vec<_> = self.unresolved.lock().unwrap().values().cloned().collect();
+        intents.sort_by_key(|intent| (intent.created_at, intent.client_order_id));
+        intents
+    }
+}
+
+/// The intent of `order`, about to be sent at `created_at`.
+pub fn intent(order: &OrderRequest, created_at: DateTime<Utc>) -> OrderIntent {
+    OrderIntent {
+        client_order_id: order.client_order_id,
+        decision_id: order.decision_id,
+        symbol: order.symbol.clone(),
+        side: order.side,
+        quantity: order.quantity,
+        reduce_only: order.reduce_only,
+        state: OrderIntentState::Sent,
+        execution_id: None,
+        created_at,
+        resolved_at: None,
+    }
+}
+
+/// What startup recovery learned about one order recorded as sent.
+#[derive(Debug, Clone, PartialEq)]
+pub enum IntentResolution {
+    /// The order filled before the engine wrote its execution, which is rebuilt from the
+    /// exchange's fills. The synced portfolio already holds its effect.
+    Filled { intent: OrderIntent, execution: Box<Execution>, fills: Vec<PositionFill> },
+    /// The exchange never filled the order: it is unknown, or ended without a fill.
+    Expired { intent: OrderIntent, status: Option<String> },
+    /// The order is still working on the exchange, or could not be looked up. It stays in the
+    /// ledger, and entries on its symbol are refused.
+    Unresolved { intent: OrderIntent, reason: String },
+}
+
+/// What the exchange says became of one order.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OrderLookup {
+    /// The order filled. Its execution is rebuilt from the exchange's fills, with their gross
+    /// realized P&L.
+    Filled { execution: Box<Execution>, realized_pnl: Decimal },
+    /// The exchange never filled the order: it is unknown, or ended without a fill.
+    Expired { status: Option<String> },
+    /// The order is still working on the exchange, or could not be looked up.
+    Unresolved { reason: String },
+}
+
+/// Resolves the orders recorded as sent before a restart against the exchange.
+#[derive(Debug, Clone)]
+pub struct IntentRecovery {
+    /// The quote asset of fees the exchange reports without fills.
+    pub quote_assets: QuoteAssets,
+}
+
+impl IntentRecovery {
+    /// Looks up every `stored` intent on the exchange. The fills of a filled order are
+    /// computed against the portfolio freshly synced from the exchange, and attributed to the
+    /// position of the symbol's recorded context in `contexts` where it has one, so they join
+    /// its history. The unresolved intents are tracked in `ledger`.
+    pub async fn run(
+        &self,
+        stored: Vec<OrderIntent>,
+        contexts: &[PositionContext],
+        portfolio: &Mutex<Portfolio>,
+        ledger: &OrderLedger,
+        api_client: &dyn ApiClient,
+        now: DateTime<Utc>,
+    ) -> Vec<IntentResolution> {
+        let mut resolutions = Vec::with_capacity(stored.len());
+        for intent in stored {
+            match self.look_up(&intent, api_client, now).await {
+                OrderLookup::Filled { execution, realized_pnl } => {
+                    let fills = vec![fill(&intent, &execution, realized_pnl, contexts, &*portfolio.lock().await)];
+                    resolutions.push(IntentResolution::Filled { intent, execution, fills });
+                }
+                OrderLookup::Expired { status } => resolutions.push(IntentResolution::Expired { intent, status }),
+                OrderLookup::Unresolved { reason } => {
+                    ledger.insert(intent.clone());
+                    resolutions.push(IntentResolution::Unresolved { intent, reason });
+                }
+            }
+        }
+        resolutions
+    }
+
+    /// Asks the exchange what became of `intent`'s order, by its client order ID.
+    pub async fn look_up(&self, intent: &OrderIntent, api_client: &dyn ApiClient, now: DateTime<Utc>) -> OrderLookup {
+        let order = match api_client.get_order(&intent.symbol, &intent.client_order_id.to_string()).await {
+            Ok(Some(order)) => order,
+            Ok(None) => return OrderLookup::Expired { status: None },
+            Err(e) => return OrderLookup::Unresolved { reason: format!("the order could not be looked up: {}", e) },
+        };
+        if is_working(&order.status) {
+            OrderLookup::Unresolved { reason: format!("the order is still {} on the exchange", order.status) }
+        } else if order.executed_qty.is_zero() {
+            OrderLookup::Expired { status: Some(order.status) }
+        } else {
+            let (execution, realized_pnl) = self.execution(intent, &order, api_client, now).await;
+            OrderLookup::Filled { execution: Box::new(execution), realized_pnl }
+        }
+    }
+
+    /// The execution of the filled `order`, with the fee and the gross realized P&L of its
+    /// fills. A failed lookup of the fills records a zero fee and P&L, as `LiveExecutor` does.
+    async fn execution(&self, intent: &OrderIntent, order: &OrderResponse, api_client: &dyn ApiClient, now: DateTime<Utc>) -> (Execution, Decimal) {
+        let quote_asset = self.quote_assets.for_symbol(&intent.symbol).to_string();
+        let (fee, fee_asset, is_maker, realized_pnl) = match api_client.get_user_trades(&intent.symbol, order.order_id).await {
+            Ok(trades) => (
+                executor::quote_fee(api_client, &trades, &quote_asset).await,
+                quote_asset,
+                !trades.is_empty() && trades.iter().all(|trade| trade.maker),
+                trades.iter().map(|trade| trade.realized_pnl).sum(),
+            ),
+            Err(e) => {
+                tracing::warn!(symbol = %intent.symbol, order_id = order.order_id, error = %e, "Failed to fetch the fills of a recovered order. Recording a zero fee.");
+                (Decimal::ZERO, quote_asset, false, Decimal::ZERO)
+            }
+        };
+        let execution = Execution {
+            execution_id: Uuid::new_v4(),
+            client_order_id: intent.client_order_id,
+            symbol: intent.symbol.clone(),
+            side: order.side,
+            price: order.avg_price,
+            quantity: order.executed_qty,
+            fee,
+            fee_asset,
+            timestamp: order.update_time.and_then(DateTime::from_timestamp_millis).unwrap_or(now),
+            decision_id: intent.decision_id,
+            is_maker,
+            spread_cost: Decimal::ZERO,
+            placement: None,
+        };
+        (execution, realized_pnl)
+    }
+}
+
+/// Whether an order in `status` can still fill.
+fn is_working(status: &str) -> bool {
+    matches!(status, "NEW" | "PARTIALLY_FILLED")
+}
+
+/// The position fill of a recovered `execution`, against the `portfolio` that already holds
+/// it: an entry leaves the position at its synced quantity, and an exit leaves whatever the
+/// exchange still holds on that side.
+fn fill(intent: &OrderIntent, execution: &Execution, realized_pnl: Decimal, contexts: &[PositionContext], portfolio: &Portfolio) -> PositionFill {
+    let position_side = if intent.reduce_only { execution.side.opposite() } else { execution.side };
+    let position = portfolio.get_position(&execution.symbol).filter(|position| position.side == position_side);
+    let context = contexts.iter().find(|context| context.symbol == execution.symbol && context.side == position_side);
+    let position_id = context
+        .map(|context| context.position_id)
+        .or(position.map(|position| position.position_id))
+        .unwrap_or_else(Uuid::new_v4);
+    let position_quantity = match position {
+        Some(position) => position.quantity,
+        None if intent.reduce_only => Decimal::ZERO,
+        None => execution.quantity,
+    };
+    PositionFill {
+        position_id,
+        position_side,
+        is_entry: !intent.reduce_only,
+        quantity: execution.quantity,
+        fee: execution.fee,
+        realized_pnl: if intent.reduce_only { realized_pnl } else { Decimal::ZERO },
+        position_quantity,
+    }
+}
+
+/// A short description of `intent`'s order for the logs, e.g. "buy 0.5 BTCUSDT".
+pub fn describe(intent: &OrderIntent) -> String {
+    let side = match intent.side {
+        OrderSide::Buy => "buy",
+        OrderSide::Sell => "sell",
+    };
+    format!("{} {} {}{}", side, intent.quantity, intent.symbol, if intent.reduce_only { " (reduce-only)" } else { "" })
+}
@@ -0,0 +1,387 @@
+//! Write-behind persistence of the live engine's records.
+//!
+//! Writing a decision's audit trail, its executions and the equity straight to the database
+//! would put several round trips between a kline and its order. The engine instead hands each
+//! record to `Persistence`, which only queues it, and a task of its own writes them. Audit
+//! rows and equity points are batched into one transaction every `batch_interval`, or sooner
+//! once `batch_size` are waiting, and the portfolio's events are written with them. Executions, order resolutions, position
+//! contexts and the risk state are written as soon as they are dequeued, after everything queued before them, and an execution's write can be
+//! awaited through its `Ack`. An order's intent skips the queue: the order waits for it, so it is written straight away,
+//! on the caller's task.
+//!
+//! The queue is bounded. When it is full, audit rows, equity points, portfolio events and
+//! book tickers are dropped and counted rather than holding up trading; executions, order
+//! resolutions, position contexts and the risk state wait for room. `shutdown` writes everything queued before it returns.
+
+use chrono::{DateTime, Utc};
+use core_types::{BookTicker, CloseReason, Execution, PositionFill, RecordedPortfolioEvent};
+use database::{DbError, DbRepository, DecisionAuditEntry, OrderIntent, OrderIntentState, PositionContext, RiskStateEntry};
+use executor::PortfolioEventSink;
+use rust_decimal::Decimal;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+use uuid::Uuid;
+
+/// How many records may wait for the persistence task.
+pub const DEFAULT_QUEUE_CAPACITY: usize = 10_000;
+
+/// How often the batched records are written.
+pub const DEFAULT_BATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many batched records are written at once, ahead of the interval.
+pub const DEFAULT_BATCH_SIZE: usize = 100;
+
+/// How the persistence task queues and batches its writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistenceSettings {
+    pub queue_capacity: usize,
+    pub batch_interval: Duration,
+    pub batch_size: usize,
+}
+
+impl Default for PersistenceSettings {
+    fn default() -> Self {
+        Self { queue_capacity: DEFAULT_QUEUE_CAPACITY, batch_interval: DEFAULT_BATCH_INTERVAL, batch_size: DEFAULT_BATCH_SIZE }
+    }
+}
+
+/// Answers whether an execution was written: `true` once it is committed, `false` if the
+/// write failed. The channel closes without an answer if the record never reached the task.
+pub type Ack = oneshot::Receiver<bool>;
+
+/// A record for the persistence task to write, or an instruction to it.
+#[derive(Debug)]
+pub enum PersistCommand {
+    /// A stage of a decision's audit trail. Batched; dropped when the queue is full.
+    Audit(DecisionAuditEntry),
+    /// A point of the live equity. Batched; dropped when the queue is full.
+    Equity { recorded_at: DateTime<Utc>, equity: Decimal },
+    /// An event the live portfolio applied. Batched; dropped when the queue is full.
+    PortfolioEvent(Box<RecordedPortfolioEvent>),
+    /// Recorded book ticker updates of `symbol`. Written with the next batch; dropped when
+    /// the queue is full.
+    BookTickers { symbol: String, tickers: Vec<BookTicker> },
+    /// A live execution with the positions it changed, and a limit order's placement for
+    /// execution quality. Written at once, then acknowledged on `ack`.
+    Execution { execution: Box<Execution>, fills: Vec<PositionFill>, close_reason: Option<CloseReason>, ack: oneshot::Sender<bool> },
+    /// Resolves a sent order of the order ledger. Written at once.
+    ResolveOrderIntent { client_order_id: Uuid, state: OrderIntentState, execution_id: Option<Uuid>, resolved_at: DateTime<Utc> },
+    /// The context of a newly opened position. Written at once.
+    OpenPositionContext(Box<PositionContext>),
+    /// Closes the open position context of `symbol`. Written at once.
+    ClosePositionContext { symbol: String, closed_at: DateTime<Utc> },
+    /// Changed values of the global risk manager's state. Written at once.
+    RiskState(Vec<RiskStateEntry>),
+    /// Archives the risk state as the state of the trading day that started at `trading_day`.
+    /// Written at once.
+    ArchiveRiskState { trading_day: DateTime<Utc>, archived_at: DateTime<Utc> },
+    /// Writes everything queued before it, then answers.
+    Flush(oneshot::Sender<()>),
+    /// Writes everything queued, stops the task, then answers.
+    Shutdown(oneshot::Sender<()>),
+}
+
+/// The engine's handle on the persistence task. Cloned handles queue to the same task.
+#[derive(Debug, Clone)]
+pub struct Persistence {
+    /// None when records are discarded.
+    tx: Option<mpsc::Sender<PersistCommand>>,
+    /// Writes the order intents, which skip the queue. None when records are discarded.
+    db_repo: Option<DbRepository>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl Persistence {
+    /// Starts a task writing the queued records to `db_repo`.
+    pub fn spawn(db_repo: DbRepository, settings: PersistenceSettings) -> Self {
+        let (tx, rx) = mpsc::channel(settings.queue_capacity.max(1));
+        let dropped = Arc::new(AtomicU64::new(0));
+        let writer = Writer {
+            db_repo: db_repo.clone(),
+            batch_size: settings.batch_size.max(1),
+            dropped: Arc::clone(&dropped),
+            reported_dropped: 0,
+            audits: Vec::new(),
+            equity: Vec::new(),
+            portfolio_events: Vec::new(),
+            book_tickers: Vec::new(),
+        };
+        tokio::spawn(writer.run(rx, settings.batch_interval));
+        Self { tx: Some(tx), db_repo: Some(db_repo), dropped }
+    }
+
+    /// A handle discarding every record, for engines with nothing to write to.
+    pub fn disabled() -> Self {
+        Self { tx: None, db_repo: None, dropped: Arc::new(AtomicU64::new(0)) }
+    }
+
+    /// Whether every record is discarded, as by a handle from `disabled`.
+    pub fn is_disabled(&self) -> bool {
+        self.db_repo.is_none()
+    }
+
+    /// The number of records dropped because the queue was full or the task had stopped.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Queues a stage of a decision's audit trail, recorded now.
+    pub fn record_audit(&self, entry: DecisionAuditEntry) {
+        self.try_send(PersistCommand::Audit(entry));
+    }
+
+    /// Queues a point of the live equity.
+    pub fn record_equity(&self, recorded_at: DateTime<Utc>, equity: Decimal) {
+        self.try_send(PersistCommand::Equity { recorded_at, equity });
+    }
+
+    /// Queues an event the live portfolio applied.
+    pub fn record_portfolio_event(&self, event: &RecordedPortfolioEvent) {
+        self.try_send(PersistCommand::PortfolioEvent(Box::new(event.clone())));
+    }
+
+    /// Queues recorded book ticker updates of `symbol`.
+    pub fn record_book_tickers(&self, symbol: String, tickers: Vec<BookTicker>) {
+        self.try_send(PersistCommand::BookTickers { symbol, tickers });
+    }
+
+    /// Queues a live execution, waiting for room if the queue is full. The returned `Ack`
+    /// answers once it is written; awaiting it is optional.
+    pub async fn record_execution(&self, execution: &Execution, fills: &[PositionFill], close_reason: Option<CloseReason>) -> Ack {
+        let (ack, acked) = oneshot::channel();
+        let execution = Box::new(execution.clone());
+        self.send(PersistCommand::Execution { execution, fills: fills.to_vec(), close_reason, ack }).await;
+        acked
+    }
+
+    /// Writes an order about to be sent to the order ledger, returning once it is written.
+    /// Unlike every other record, the intent is not queued: the order waits for it, and must
+    /// not wait behind the writes of earlier decisions. Records queued afterwards, such as
+    /// the order's execution, are written after it.
+    pub async fn record_order_intent(&self, intent: &OrderIntent) -> Result<(), DbError> {
+        match &self.db_repo {
+            Some(db_repo) => db_repo.save_order_intent(intent).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Queues the resolution of the sent order `client_order_id` as `state`, waiting for room
+    /// if the queue is full.
+    pub async fn resolve_order_intent(&self, client_order_id: Uuid, state: OrderIntentState, execution_id: Option<Uuid>, resolved_at: DateTime<Utc>) {
+        self.send(PersistCommand::ResolveOrderIntent { client_order_id, state, execution_id, resolved_at }).await;
+    }
+
+    /// Queues the context of a newly opened position, waiting for room if the queue is full.
+    pub async fn open_position_context(&self, context: PositionContext) {
+        self.send(PersistCommand::OpenPositionContext(Box::new(context))).await;
+    }
+
+    /// Queues the close of `symbol`'s position context, waiting for room if the queue is full.
+    pub async fn close_position_context(&self, symbol: &str, closed_at: DateTime<Utc>) {
+        self.send(PersistCommand::ClosePositionContext { symbol: symbol.to_string(), closed_at }).await;
+    }
+
+    /// Queues changed values of the risk state, waiting for room if the queue is full.
+    pub async fn record_risk_state(&self, entries: Vec<RiskStateEntry>) {
+        self.send(PersistCommand::RiskState(entries)).await;
+    }
+
+    /// Queues the archiving of the risk state as the trading day that started at
+    /// `trading_day`'s, waiting for room if the queue is full. Values recorded before it are
+    /// archived; values recorded after it are not.
+    pub async fn archive_risk_state(&self, trading_day: DateTime<Utc>, archived_at: DateTime<Utc>) {
+        self.send(PersistCommand::ArchiveRiskState { trading_day, archived_at }).await;
+    }
+
+    /// Waits until every record queued so far is written.
+    pub async fn flush(&self) {
+        let (done, flushed) = oneshot::channel();
+        self.send(PersistCommand::Flush(done)).await;
+        let _ = flushed.await;
+    }
+
+    /// Writes every record queued so far and stops the task. Records queued afterwards, from
+    /// any handle, are dropped.
+    pub async fn shutdown(&self) {
+        let (done, stopped) = oneshot::channel();
+        self.send(PersistCommand::Shutdown(done)).await;
+        let _ = stopped.await;
+    }
+
+    /// Queues `command` without waiting, dropping it if the queue is full.
+    fn try_send(&self, command: PersistCommand) {
+        let Some(tx) = &self.tx else { return };
+        if let Err(e) = tx.try_send(command) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            if let TrySendError::Closed(_) = e {
+                tracing::warn!("The persistence task has stopped; dropped a record.");
+            }
+        }
+    }
+
+    /// Queues `command`, waiting for room.
+    async fn send(&self, command: PersistCommand) {
+        let Some(tx) = &self.tx else { return };
+        if let Err(e) = tx.send(command).await {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            tracing::error!(command = ?e.0, "The persistence task has stopped; a record was not written.");
+        }
+    }
+}
+
+/// Stores the live portfolio's events as they are applied.
+impl PortfolioEventSink for Persistence {
+    fn record(&self, event: &RecordedPortfolioEvent) {
+        self.record_portfolio_event(event);
+    }
+}
+
+/// The persistence task: the batched records waiting for their write.
+struct Writer {
+    db_repo: DbRepository,
+    batch_size: usize,
+    dropped: Arc<AtomicU64>,
+    /// The drop count at the last warning about it.
+    reported_dropped: u64,
+    audits: Vec<DecisionAuditEntry>,
+    equity: Vec<(DateTime<Utc>, Decimal)>,
+    portfolio_events: Vec<RecordedPortfolioEvent>,
+    book_tickers: Vec<(String, Vec<BookTicker>)>,
+}
+
+impl Writer {
+    async fn run(mut self, mut rx: mpsc::Receiver<PersistCommand>, batch_interval: Duration) {
+        let mut timer = interval(batch_interval);
+        timer.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                command = rx.recv() => match command {
+                    Some(PersistCommand::Shutdown(done)) => {
+                        // Take what is already queued, refusing anything sent from now on.
+                        rx.close();
+                        let mut stopped = vec![done];
+                        while let Some(command) = rx.recv().await {
+                            match command {
+                                PersistCommand::Shutdown(done) => stopped.push(done),
+                                command => self.handle(command).await,
+                            }
+                        }
+                        self.write_batch().await;
+                        for done in stopped {
+                            let _ = done.send(());
+                        }
+                        return;
+                    }
+                    Some(command) => self.handle(command).await,
+                    // Every handle was dropped.
+                    None => break,
+                },
+                _ = timer.tick() => self.write_batch().await,
+            }
+        }
+        self.write_batch().await;
+    }
+
+    fn pending(&self) -> usize {
+        self.audits.len() + self.equity.len() + self.portfolio_events.len() + self.book_tickers.len()
+    }
+
+    async fn handle(&mut self, command: PersistCommand) {
+        match command {
+            PersistCommand::Audit(entry) => self.audits.push(entry),
+            PersistCommand::Equity { recorded_at, equity } => self.equity.push((recorded_at, equity)),
+            PersistCommand::PortfolioEvent(event) => self.portfolio_events.push(*event),
+            PersistCommand::BookTickers { symbol, tickers } => self.book_tickers.push((symbol, tickers)),
+            PersistCommand::Execution { execution, fills, close_reason, ack } => {
+                // The execution's audit rows go first, so the trail never lags the history.
+                self.write_batch().await;
+                let _ = ack.send(self.write_execution(&execution, &fills, close_reason).await);
+            }
+            PersistCommand::ResolveOrderIntent { client_order_id, state, execution_id, resolved_at } => {
+                self.write_batch().await;
+                if let Err(e) = self.db_repo.resolve_order_intent(client_order_id, state, execution_id, resolved_at).await {
+                    tracing::warn!(client_order_id = %client_order_id, state = state.as_str(), error = %e, "Failed to resolve an order intent.");
+                }
+            }
+            PersistCommand::OpenPositionContext(context) => {
+                self.write_batch().await;
+                if let Err(e) = self.db_repo.open_position_context(&context).await {
+                    tracing::warn!(symbol = %context.symbol, error = %e, "Failed to record an opened position's context.");
+                }
+            }
+            PersistCommand::ClosePositionContext { symbol, closed_at } => {
+                self.write_batch().await;
+                if let Err(e) = self.db_repo.close_position_context(&symbol, closed_at).await {
+                    tracing::warn!(symbol = %symbol, error = %e, "Failed to record a closed position's context.");
+                }
+            }
+            PersistCommand::RiskState(entries) => {
+                self.write_batch().await;
+                if let Err(e) = self.db_repo.save_risk_state(&entries).await {
+                    tracing::warn!(values = entries.len(), error = %e, "Failed to record the risk state.");
+                }
+            }
+            PersistCommand::ArchiveRiskState { trading_day, archived_at } => {
+                self.write_batch().await;
+                if let Err(e) = self.db_repo.archive_risk_state(trading_day, archived_at).await {
+                    tracing::warn!(trading_day = %trading_day, error = %e, "Failed to archive the risk state.");
+                }
+            }
+            PersistCommand::Flush(done) => {
+                self.write_batch().await;
+                let _ = done.send(());
+            }
+            PersistCommand::Shutdown(_) => unreachable!("shutdown is handled by the task's loop"),
+        }
+        if self.pending() >= self.batch_size {
+            self.write_batch().await;
+        }
+    }
+
+    /// Writes an execution and its execution quality. Failures are only logged, as trading
+    /// must carry on regardless.
+    async fn write_execution(&self, execution: &Execution, fills: &[PositionFill], close_reason: Option<CloseReason>) -> bool {
+        if let Err(e) = self.db_repo.save_live_execution(execution, fills, close_reason).await {
+            tracing::warn!(execution_id = %execution.execution_id, error = %e, "Failed to record live execution.");
+            return false;
+        }
+        if let Some(placement) = &execution.placement
+            && let Err(e) = self.db_repo.save_execution_quality(execution, placement).await
+        {
+            tracing::warn!(execution_id = %execution.execution_id, error = %e, "Failed to record execution quality.");
+        }
+        true
+    }
+
+    /// Writes the batched audit rows and equity points in one transaction, then the portfolio
+    /// events and the book tickers. A failed write is logged, losing those records.
+    async fn write_batch(&mut self) {
+        let dropped = self.dropped.load(Ordering::Relaxed);
+        if dropped > self.reported_dropped {
+            tracing::warn!(dropped = dropped - self.reported_dropped, total = dropped, "The persistence queue was full; dropped audit, equity, portfolio event or book ticker records.");
+            self.reported_dropped = dropped;
+        }
+
+        if !self.audits.is_empty() || !self.equity.is_empty() {
+            if let Err(e) = self.db_repo.save_live_batch(&self.audits, &self.equity).await {
+                tracing::warn!(audits = self.audits.len(), equity_points = self.equity.len(), error = %e, "Failed to record decision audits and live equity.");
+            }
+            self.audits.clear();
+            self.equity.clear();
+        }
+        if !self.portfolio_events.is_empty() {
+            if let Err(e) = self.db_repo.save_portfolio_events(&self.portfolio_events).await {
+                tracing::warn!(events = self.portfolio_events.len(), error = %e, "Failed to record portfolio events.");
+            }
+            self.portfolio_events.clear();
+        }
+        for (symbol, tickers) in self.book_tickers.drain(..) {
+            if let Err(e) = self.db_repo.save_book_tickers(&symbol, &tickers).await {
+                tracing::warn!(symbol = %symbol, updates = tickers.len(), error = %e, "Failed to record book tickers.");
+            }
+        }
+    }
+}
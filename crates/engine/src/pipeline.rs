@@ -0,0 +1,896 @@
+//! The decision path of a single kline: strategy, stops, time exits and blackouts, risk,
+//! safety nets, execution and the portfolio update.
+//!
+//! `SignalPipeline` holds the strategy-agnostic components the path needs and reports how
+//! each kline ended as a `PipelineOutcome`, so the engine only routes klines to bots and
+//! reacts to outcomes, and each branch can be driven on its own in tests.
+
+use crate::event::MarketState;
+use crate::latency::{LatencyStage, LatencyTracker};
+use crate::order_ledger::{self, IntentRecovery, OrderLedger, OrderLookup};
+use crate::persistence::Persistence;
+use crate::safety::SafetyGuard;
+use crate::position_context::{self, PositionContexts};
+use crate::watchdog::reference_kline;
+use crate::{close_signal, halted, Bot};
+use api_client::ApiClient;
+use chrono::{DateTime, Utc};
+use configuration::{Config, RiskManagement, Simulation, TradingBlackouts};
+use core_types::{CloseReason, DecisionStage, Execution, Kline, OrderRequest, PositionFill, Signal, StrategyId};
+use database::{DecisionAuditEntry, OrderIntentState};
+use events::{EngineStats, EventBus, LatencyReport, LogLevel, WsMessage};
+use executor::{Executor, Portfolio};
+use risk::{constrain_direction, DailyLossTracker, ExpectedMoveFilter, OrderPlan, RiskManager, TimeExit};
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use strategies::StrategyError;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long an order waits for its intent to be written to the order ledger. An entry whose
+/// intent is not written by then is refused; an exit is sent without it.
+pub const ORDER_INTENT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How the decision on a kline ended.
+#[derive(Debug)]
+pub enum PipelineOutcome {
+    /// Trading on the bot's symbol is halted; the kline was not evaluated.
+    Halted,
+    /// The strategy failed to evaluate the kline.
+    StrategyFailed { error: StrategyError },
+    /// The strategy had nothing to do.
+    NoSignal,
+    /// The signal was dropped before the risk manager saw it, e.g. inside a trading blackout.
+    Dropped { reason: String },
+    /// The risk manager, the expected-move filter or the margin check refused the signal.
+    RiskRejected { reason: String },
+    /// Every order of the plan filled and was applied to the portfolio.
+    Executed { executions: Vec<Execution> },
+    /// An order of the plan was blocked or failed to execute. `executions` are the orders of
+    /// the plan that did fill.
+    ExecutionFailed { error: String, executions: Vec<Execution> },
+    /// `execution` filled, but applying it to the portfolio failed, so the portfolio no longer
+    /// matches the exchange and the bot was halted. `executions` are the orders of the plan
+    /// applied before it.
+    PortfolioUpdateFailed { execution: Box<Execution>, error: String, executions: Vec<Execution> },
+}
+
+impl PipelineOutcome {
+    /// The executions applied to the portfolio.
+    pub fn executions(&self) -> &[Execution] {
+        match self {
+            PipelineOutcome::Executed { executions }
+            | PipelineOutcome::ExecutionFailed { executions, .. }
+            | PipelineOutcome::PortfolioUpdateFailed { executions, .. } => executions,
+            _ => &[],
+        }
+    }
+
+    /// Whether any order reached the exchange and filled, changing the account.
+    pub fn filled(&self) -> bool {
+        !self.executions().is_empty() || matches!(self, PipelineOutcome::PortfolioUpdateFailed { .. })
+    }
+}
+
+/// The risk settings a bot trades by, with the risk manager sizing its signals by them.
+pub struct BotRisk {
+    pub manager: Arc<dyn RiskManager>,
+    pub settings: RiskManagement,
+    expected_move_filter: ExpectedMoveFilter,
+}
+
+impl BotRisk {
+    /// `manager`, which sizes by `settings`, with the expected-move filter they configure
+    /// under `simulation`'s costs.
+    pub fn new(manager: Arc<dyn RiskManager>, settings: RiskManagement, simulation: &Simulation) -> Self {
+        let expected_move_filter = ExpectedMoveFilter::new(&settings, simulation);
+        Self { manager, settings, expected_move_filter }
+    }
+}
+
+/// What a bot decided to do, on a kline or ahead of a trading blackout, before it is sized.
+struct Decision {
+    risk: Arc<BotRisk>,
+    signal: Signal,
+    close_reason: CloseReason,
+    /// The window the position is flattened ahead of, if it is.
+    flatten_window: Option<String>,
+    holding_bars: u32,
+    /// When the strategy's evaluation ended.
+    evaluated: Instant,
+}
+
+/// Turns a bot's klines into orders: evaluates the strategy, applies time exits and trading
+/// blackouts, sizes the signal with the risk manager, places the resulting orders past the
+/// safety nets and applies their executions to the portfolio. Every stage is audited and
+/// timed.
+pub struct SignalPipeline {
+    /// The risk of bots without risk settings of their own.
+    risk: Arc<BotRisk>,
+    executor: Arc<dyn Executor>,
+    portfolio: Arc<Mutex<Portfolio>>,
+    event_tx: EventBus,
+    persistence: Persistence,
+    trading_blackouts: TradingBlackouts,
+    trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
+    risk_multiplier: Arc<Mutex<Decimal>>,
+    daily_loss: Option<Arc<Mutex<DailyLossTracker>>>,
+    position_contexts: Arc<PositionContexts>,
+    order_ledger: Arc<OrderLedger>,
+    /// Where the unresolved orders are looked up, by client order ID.
+    order_lookups: Option<(Arc<dyn ApiClient>, IntentRecovery)>,
+    safety: Mutex<SafetyGuard>,
+    latency: std::sync::Mutex<LatencyTracker>,
+    stats: Arc<EngineStats>,
+    broadcast_klines: bool,
+    /// The `seq` of the latest broadcast kline of each symbol and interval.
+    kline_sequences: std::sync::Mutex<HashMap<(String, String), u64>>,
+    /// The leverage each bot's symbol trades at, for the margin its open positions hold.
+    leverage: std::sync::Mutex<HashMap<String, u8>>,
+    replay: bool,
+    /// The sequence of the next audited stage.
+    audit_sequence: AtomicU64,
+}
+
+impl SignalPipeline {
+    /// Creates a pipeline trading by `config`'s risk management and blackout settings, unless
+    /// a bot has risk settings of its own, and queueing its records to `persistence`. No
+    /// symbol is enabled for trading and no safety net is armed until set otherwise.
+    pub fn new(
+        config: &Config,
+        risk_manager: Arc<dyn RiskManager>,
+        executor: Arc<dyn Executor>,
+        portfolio: Arc<Mutex<Portfolio>>,
+        persistence: Persistence,
+        event_tx: EventBus,
+    ) -> Self {
+        let trading_enabled_flags = Arc::new(Mutex::new(HashMap::new()));
+        let safety = SafetyGuard::new(None, None, None, Arc::clone(&trading_enabled_flags), event_tx.clone());
+        Self {
+            risk: Arc::new(BotRisk::new(risk_manager, config.risk_management.clone(), &config.simulation)),
+            executor,
+            portfolio,
+            event_tx,
+            persistence,
+            trading_blackouts: config.trading_blackouts.clone(),
+            trading_enabled_flags,
+            risk_multiplier: Arc::new(Mutex::new(Decimal::ONE)),
+            daily_loss: None,
+            position_contexts: Arc::new(PositionContexts::new()),
+            order_ledger: Arc::new(OrderLedger::new()),
+            order_lookups: None,
+            safety: Mutex::new(safety),
+            latency: std::sync::Mutex::new(LatencyTracker::new()),
+            stats: Arc::new(EngineStats::new()),
+            broadcast_klines: false,
+            kline_sequences: std::sync::Mutex::new(HashMap::new()),
+            leverage: std::sync::Mutex::new(HashMap::new()),
+            replay: false,
+            audit_sequence: AtomicU64::new(1),
+        }
+    }
+
+    /// Trades only the symbols enabled in `flags`, which halts also disable.
+    pub fn with_trading_flags(mut self, flags: Arc<Mutex<HashMap<String, bool>>>) -> Self {
+        self.trading_enabled_flags = flags;
+        self
+    }
+
+    /// Sizes entries from `multiplier` times `risk_per_trade_pct`, e.g. the global risk
+    /// manager's dynamic leverage.
+    pub fn with_risk_multiplier(mut self, multiplier: Arc<Mutex<Decimal>>) -> Self {
+        self.risk_multiplier = multiplier;
+        self
+    }
+
+    /// Records the P&L of every live fill into `tracker`, e.g. the global risk manager's daily
+    /// loss tracker.
+    pub fn with_daily_loss(mut self, tracker: Option<Arc<Mutex<DailyLossTracker>>>) -> Self {
+        self.daily_loss = tracker;
+        self
+    }
+
+    /// Keeps the open positions' contexts in `contexts`, e.g. ones shared with the reconciler.
+    pub fn with_position_contexts(mut self, contexts: Arc<PositionContexts>) -> Self {
+        self.position_contexts = contexts;
+        self
+    }
+
+    /// Tracks the orders sent but not resolved in `ledger`, e.g. one startup recovery fills
+    /// with the orders it could not resolve.
+    pub fn with_order_ledger(mut self, ledger: Arc<OrderLedger>) -> Self {
+        self.order_ledger = ledger;
+        self
+    }
+
+    /// Asks `api_client` what became of the unresolved orders on a symbol, such as one whose
+    /// placement timed out, before each decision on it. Without it they block entries on their
+    /// symbol until a restart resolves them.
+    pub fn with_order_lookups(mut self, api_client: Arc<dyn ApiClient>, recovery: IntentRecovery) -> Self {
+        self.order_lookups = Some((api_client, recovery));
+        self
+    }
+
+    /// Writes the decisions' records, and the order intents, through `persistence`.
+    pub fn with_persistence(mut self, persistence: Persistence) -> Self {
+        self.persistence = persistence;
+        self
+    }
+
+    /// Checks every order against `safety` right before it is placed.
+    pub fn with_safety(mut self, safety: SafetyGuard) -> Self {
+        self.safety = Mutex::new(safety);
+        self
+    }
+
+    /// Counts signals and fills in `stats`.
+    pub fn with_stats(mut self, stats: Arc<EngineStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Broadcasts each kline to WebSocket clients before it is evaluated, numbered per symbol.
+    pub fn with_kline_broadcasts(mut self, enabled: bool) -> Self {
+        self.broadcast_klines = enabled;
+        self
+    }
+
+    /// Marks the klines as replayed: their executions are not recorded as live history.
+    pub fn with_replay(mut self, replay: bool) -> Self {
+        self.replay = replay;
+        self
+    }
+
+    /// Records the leverage `symbol` trades at. The margin check holds back the margin of open
+    /// positions at their symbol's leverage, and at 1x in symbols without one.
+    pub fn set_leverage(&self, symbol: &str, leverage: u8) {
+        self.leverage.lock().unwrap().insert(symbol.to_string(), leverage);
+    }
+
+    /// Records a latency sample outside the pipeline's own stages, such as the feed delay.
+    pub fn record_latency(&self, symbol: &str, stage: LatencyStage, elapsed: Duration) {
+        self.latency.lock().unwrap().record(symbol, stage, elapsed);
+    }
+
+    /// Summarises the latencies of the window that just ended; see `LatencyTracker::report`.
+    pub fn latency_report(&self, window: Duration) -> Option<LatencyReport> {
+        self.latency.lock().unwrap().report(window)
+    }
+
+    /// Disables trading on `symbol` until the engine is restarted. The caller raises the error
+    /// alert, worded by `halted`.
+    pub async fn halt(&self, symbol: &str) {
+        self.trading_enabled_flags.lock().await.insert(symbol.to_string(), false);
+    }
+
+    /// Queues one stage of a trading decision for the audit trail, numbered in the order the
+    /// stages are decided. Auditing must never hold up trading, so the record is dropped if
+    /// the persistence queue is full.
+    fn audit(&self, decision_id: Uuid, stage: DecisionStage, symbol: &str, payload: serde_json::Value) {
+        let sequence = self.audit_sequence.fetch_add(1, Ordering::Relaxed);
+        self.persistence.record_audit(DecisionAuditEntry { decision_id, stage, symbol: symbol.to_string(), payload, recorded_at: Utc::now(), sequence });
+    }
+
+    /// Queues a live execution with the positions it changed, for the position history and
+    /// the daily loss limits, and a limit order's placement, for execution quality. The write
+    /// happens behind the pipeline, which does not wait for it. Replayed executions are not
+    /// live history and are not recorded.
+    pub async fn record_execution(&self, execution: &Execution, fills: &[PositionFill], close_reason: Option<CloseReason>) {
+        if self.replay {
+            return;
+        }
+        if let Some(tracker) = &self.daily_loss {
+            let mut tracker = tracker.lock().await;
+            for fill in fills {
+                tracker.record_fill(&execution.symbol, fill.realized_pnl - fill.fee, execution.timestamp);
+            }
+        }
+        // Trading goes on without waiting for the write to be acknowledged.
+        drop(self.persistence.record_execution(execution, fills, close_reason).await);
+    }
+
+    /// Records `order_request` in the order ledger before it is sent, waiting up to
+    /// `ORDER_INTENT_TIMEOUT` for the write, and returns why the write failed if it did.
+    ///
+    /// Without its intent, a restart could not tell whether the order filled. An entry is then
+    /// refused and leaves the ledger again. An exit still goes out, as closing a position must
+    /// never wait on the database; it is tracked in memory only.
+    async fn record_intent(&self, order_request: &OrderRequest) -> Result<(), String> {
+        let intent = order_ledger::intent(order_request, Utc::now());
+        self.order_ledger.insert(intent.clone());
+        let reason = match tokio::time::timeout(ORDER_INTENT_TIMEOUT, self.persistence.record_order_intent(&intent)).await {
+            Ok(Ok(())) => return Ok(()),
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => "the write timed out".to_string(),
+        };
+        let (level, outcome) = if order_request.reduce_only {
+            (LogLevel::Warn, "Sending the exit without one.")
+        } else {
+            self.order_ledger.remove(order_request.client_order_id);
+            (LogLevel::Error, "The entry is not sent.")
+        };
+        log_with!(
+            self.event_tx,
+            level,
+            &format!("Could not record the intent of an order for {}: {}. {}", order_request.symbol, reason, outcome),
+            symbol = order_request.symbol,
+            decision_id = order_request.decision_id,
+            client_order_id = order_request.client_order_id,
+        );
+        Err(reason)
+    }
+
+    /// Asks the exchange what became of the unresolved orders on `symbol`. A filled order's
+    /// execution is applied to the portfolio and recorded as if the executor had returned it,
+    /// so the decision that follows sees the position; an order the exchange never filled is
+    /// dropped. The others stay in the ledger and keep blocking entries.
+    async fn resolve_unresolved_orders(&self, symbol: &str) {
+        let Some((api_client, recovery)) = &self.order_lookups else {
+            return;
+        };
+        for intent in self.order_ledger.all().into_iter().filter(|intent| intent.symbol == symbol) {
+            let now = Utc::now();
+            match recovery.look_up(&intent, api_client.as_ref(), now).await {
+                OrderLookup::Filled { execution, .. } => {
+                    self.order_ledger.remove(intent.client_order_id);
+                    let message = format!("The {} order {} filled after all, at {}; applying its execution.", order_ledger::describe(&intent), intent.client_order_id, execution.price);
+                    log_with!(self.event_tx, LogLevel::Warn, &message, intent = intent, execution = execution);
+                    let update = self.portfolio.lock().await.update_with_execution(&execution);
+                    match update {
+                        Ok(fills) => {
+                            let _ = self.event_tx.send(WsMessage::TradeExecuted((*execution).clone()));
+                            self.record_execution(&execution, &fills, None).await;
+                            self.track_positions(None, &execution, &fills).await;
+                        }
+                        Err(e) => {
+                            self.halt(symbol).await;
+                            let reason = format!("CRITICAL: Failed to apply the execution of the {} order {} to the portfolio: {}.", order_ledger::describe(&intent), intent.client_order_id, e);
+                            log_with!(self.event_tx, LogLevel::Error, halted(&reason, symbol), symbol = symbol, execution = execution, error = e.to_string());
+                        }
+                    }
+                }
+                OrderLookup::Expired { status } => {
+                    self.order_ledger.remove(intent.client_order_id);
+                    let status = status.unwrap_or_else(|| "unknown to the exchange".to_string());
+                    log_with!(self.event_tx, LogLevel::Info, &format!("The {} order {} never filled ({}).", order_ledger::describe(&intent), intent.client_order_id, status), intent = intent);
+                    self.persistence.resolve_order_intent(intent.client_order_id, OrderIntentState::Expired, None, now).await;
+                }
+                OrderLookup::Unresolved { reason } => {
+                    tracing::debug!(symbol, client_order_id = %intent.client_order_id, reason, "An order is still unresolved.");
+                }
+            }
+        }
+    }
+
+    /// Keeps the position contexts up to date with the fills of `execution`: a position it
+    /// opened gets a context with the default stop and take-profit, recording `opened_by`'s strategy and
+    /// signal, and a position it closed loses its context. Outside replays the change is
+    /// persisted, so a restarted engine can pick the positions up again; like executions, the
+    /// write happens behind the pipeline.
+    pub async fn track_positions(&self, opened_by: Option<(StrategyId, Uuid)>, execution: &Execution, fills: &[PositionFill]) {
+        self.track_positions_with(&self.risk.settings, opened_by, execution, fills).await;
+    }
+
+    /// `track_positions`, with the stops and take-profits of opened positions set by `risk`.
+    async fn track_positions_with(&self, risk: &RiskManagement, opened_by: Option<(StrategyId, Uuid)>, execution: &Execution, fills: &[PositionFill]) {
+        for fill in fills {
+            if !fill.is_entry && fill.position_quantity.is_zero() {
+                self.position_contexts.remove(&execution.symbol);
+                if !self.replay {
+                    self.persistence.close_position_context(&execution.symbol, execution.timestamp).await;
+                }
+            } else if fill.is_entry && fill.position_quantity == fill.quantity {
+                let context = position_context::opened_context(execution, fill, opened_by, risk.stop_loss_pct, risk.take_profit_pct);
+                self.position_contexts.insert(context.clone());
+                if !self.replay {
+                    self.persistence.open_position_context(context).await;
+                }
+            }
+        }
+    }
+
+    /// Decides what `bot` does on `kline`, which closed and was dequeued at `received`, given
+    /// the latest `market` state of its symbol, and carries the decision out.
+    pub async fn process(&self, bot: &mut Bot, kline: &Kline, market: &MarketState, received: Instant) -> PipelineOutcome {
+        let symbol = bot.symbol.clone();
+        let risk = bot.risk.clone().unwrap_or_else(|| Arc::clone(&self.risk));
+        // Advance the bot's kline transform before any guard, so stateful transforms see
+        // every kline. Only the strategy sees the transformed kline; everything else uses
+        // the real one.
+        bot.activity.record_kline(kline.close_time);
+        let strategy_kline = bot.kline_transform.apply(kline);
+
+        // --- 1. OBEY THE CIRCUIT BREAKER (Guard Clause) ---
+        let is_trading_enabled = {
+            let flags = self.trading_enabled_flags.lock().await;
+            *flags.get(&symbol).unwrap_or(&false)
+        };
+        if !is_trading_enabled {
+            return PipelineOutcome::Halted;
+        }
+
+        // Broadcast kline data to WebSocket clients if enabled
+        if self.broadcast_klines {
+            let seq = {
+                let mut sequences = self.kline_sequences.lock().unwrap();
+                let seq = sequences.entry((symbol.clone(), bot.interval.clone())).or_default();
+                *seq += 1;
+                *seq
+            };
+            let kline_data =
+                events::KlineData { symbol: symbol.clone(), interval: bot.interval.clone(), kline: kline.clone(), seq };
+            let receivers = self.event_tx.send(WsMessage::KlineData(kline_data));
+            tracing::trace!(receivers, seq, close_time = %kline.close_time, "Broadcast kline.");
+        }
+
+        let pyramiding_enabled = risk.settings.max_position_adds.is_some();
+        let time_exit = TimeExit::new(&risk.settings);
+        let context = market.context(kline);
+
+        // --- 2. EVALUATE THE STRATEGY ---
+        // The strategy is evaluated exactly once per kline, so stateful strategies see
+        // each bar once. With pyramiding enabled, same-direction signals go to the risk
+        // manager, which decides whether the position may be added to.
+        let signal = match bot.strategy.evaluate_with_context(&strategy_kline, &context) {
+            Ok(signal) => {
+                bot.consecutive_errors = 0;
+                bot.remember(&strategy_kline);
+                signal
+            }
+            Err(error) => return PipelineOutcome::StrategyFailed { error },
+        };
+        let evaluated = Instant::now();
+        if signal.is_some() {
+            self.stats.record_signal(Utc::now());
+        }
+        self.record_latency(&symbol, LatencyStage::Strategy, evaluated - received);
+        // An order the engine lost track of may have filled; the decision must see it.
+        if !self.replay && self.order_ledger.unresolved_on(&symbol).is_some() {
+            self.resolve_unresolved_orders(&symbol).await;
+        }
+        let position = self.portfolio.lock().await.get_position(&symbol).cloned();
+
+        // --- STOP-LOSS, TAKE-PROFIT AND TIME EXIT ---
+        // A position whose stop-loss or take-profit this kline touched is closed at its close:
+        // the engine only sees a bar once it has closed. So is a position held for its maximum
+        // number of klines, or still open at the end of the session. The strategy's signal is
+        // discarded in favor of the close, which takes the usual risk, safety and execution path.
+        // A position due to be flattened ahead of a trading blackout is closed the same way.
+        bot.holding_bars = if position.is_some() { bot.holding_bars + 1 } else { 0 };
+        let holding_bars = bot.holding_bars;
+        let blackouts = &self.trading_blackouts;
+        let flatten_window = position.as_ref().and_then(|_| blackouts.flattening_at(kline.close_time));
+        let protective_exit = position.as_ref().and_then(|pos| {
+            let context = self.position_contexts.get(&symbol).filter(|context| context.side == pos.side)?;
+            position_context::exit_due(&context, kline)
+        });
+        let (signal, close_reason) = match (&position, protective_exit) {
+            (Some(pos), Some(reason)) => (Some(close_signal(pos, kline)), reason),
+            (Some(pos), None) if flatten_window.is_some() => (Some(close_signal(pos, kline)), CloseReason::Blackout),
+            (Some(pos), None) if time_exit.is_due(holding_bars, kline) => (Some(close_signal(pos, kline)), CloseReason::TimeLimit),
+            _ => (signal, CloseReason::Signal),
+        };
+
+        // --- TRADING BLACKOUT ---
+        // Inside a blackout (or its buffer), the strategy's signals are dropped before they
+        // reach the risk manager, unless they only close the position.
+        if close_reason == CloseReason::Signal
+            && let Some(window) = signal.as_ref().and_then(|signal| blackouts.blocking(signal))
+        {
+            let window = window.to_string();
+            log_with!(
+                self.event_tx,
+                LogLevel::Info,
+                &format!("Dropped a signal for {} inside the trading blackout {}.", symbol, window),
+                symbol = symbol,
+                window = window,
+                signal = signal,
+            );
+            return PipelineOutcome::Dropped { reason: format!("inside the trading blackout {}", window) };
+        }
+
+        let Some(signal) = signal else {
+            return PipelineOutcome::NoSignal;
+        };
+
+        // --- DIRECTION ---
+        // A bot restricted to one side turns its entries on the other into closes of its
+        // position, or drops them while it is flat, as the backtesters do.
+        let Some(signal) = constrain_direction(bot.direction, signal, position.as_ref()) else {
+            return PipelineOutcome::Dropped { reason: format!("the {:?} direction does not allow the entry", bot.direction) };
+        };
+
+        if let Some(pos) = position.filter(|_| !pyramiding_enabled) {
+            // If a position is already open, do not act on a new entry signal.
+            // This enforces `max_open_positions_per_asset = 1`.
+            // We only proceed if the signal closes the position or wants the opposite side.
+            if signal.target_side() == Some(pos.side) {
+                return PipelineOutcome::Dropped { reason: format!("a {:?} position is already open", pos.side) };
+            }
+        }
+
+        let decision = Decision { risk, signal, close_reason, flatten_window: flatten_window.map(|window| window.to_string()), holding_bars, evaluated };
+        self.carry_out(bot, kline, market, received, decision).await
+    }
+
+    /// Closes `bot`'s position at the latest price in `market` when a trading blackout it must
+    /// be flat for is due at `now`, without waiting for the bot's next kline, which may close
+    /// inside the window. The close takes the same risk, safety and execution path as one
+    /// decided on a kline. Returns `None` when there is nothing to flatten, or the bot is
+    /// halted.
+    pub async fn flatten_ahead_of_blackout(&self, bot: &mut Bot, market: &MarketState, now: DateTime<Utc>) -> Option<PipelineOutcome> {
+        let window = self.trading_blackouts.flattening_at(now)?.to_string();
+        let symbol = bot.symbol.clone();
+        if !self.trading_enabled_flags.lock().await.get(&symbol).copied().unwrap_or(false) {
+            return None;
+        }
+        let received = Instant::now();
+        if !self.replay && self.order_ledger.unresolved_on(&symbol).is_some() {
+            self.resolve_unresolved_orders(&symbol).await;
+        }
+        let position = self.portfolio.lock().await.get_position(&symbol).cloned()?;
+        let Some(kline) = reference_kline(market) else {
+            tracing::warn!(symbol = %symbol, window = %window, "Cannot flatten ahead of the trading blackout: no price has been received.");
+            return None;
+        };
+
+        let risk = bot.risk.clone().unwrap_or_else(|| Arc::clone(&self.risk));
+        let signal = close_signal(&position, &kline);
+        let holding_bars = bot.holding_bars;
+        let decision = Decision { risk, signal, close_reason: CloseReason::Blackout, flatten_window: Some(window), holding_bars, evaluated: Instant::now() };
+        Some(self.carry_out(bot, &kline, market, received, decision).await)
+    }
+
+    /// Sizes the decided `signal`, places its orders past the safety nets and applies their
+    /// executions to the portfolio.
+    async fn carry_out(&self, bot: &mut Bot, kline: &Kline, market: &MarketState, received: Instant, decision: Decision) -> PipelineOutcome {
+        let Decision { risk, mut signal, close_reason, flatten_window, holding_bars, evaluated } = decision;
+        let symbol = bot.symbol.clone();
+        let signal_side = signal.order_request.side;
+        let close_price = kline.close;
+        let opened_by = (bot.strategy_id, signal.signal_id);
+
+        // Every stage from here on is correlated by the signal's decision ID.
+        let decision_id = signal.decision_id;
+        signal.order_request.decision_id = Some(decision_id);
+        tracing::Span::current().record("decision_id", tracing::field::display(decision_id));
+        self.audit(decision_id, DecisionStage::Signal, &symbol, json!({ "kline": kline, "signal": signal, "close_reason": close_reason }));
+
+        if matches!(close_reason, CloseReason::StopLoss | CloseReason::TakeProfit) {
+            let context = self.position_contexts.get(&symbol);
+            let level = if close_reason == CloseReason::StopLoss { "stop-loss" } else { "take-profit" };
+            log_with!(
+                self.event_tx,
+                LogLevel::Info,
+                &format!("The {} of the {} position was touched; closing it.", level, symbol),
+                symbol = symbol,
+                decision_id = decision_id,
+                stop_price = context.as_ref().and_then(|context| context.stop_price),
+                take_profit_price = context.as_ref().and_then(|context| context.take_profit_price),
+                low = kline.low,
+                high = kline.high,
+                price = close_price,
+            );
+        } else if let Some(window) = flatten_window.filter(|_| close_reason == CloseReason::Blackout) {
+            log_with!(
+                self.event_tx,
+                LogLevel::Info,
+                &format!("Closing the {} position ahead of the trading blackout {}.", symbol, window),
+                symbol = symbol,
+                decision_id = decision_id,
+                window = window,
+                price = close_price,
+            );
+        } else if close_reason == CloseReason::TimeLimit {
+            log_with!(
+                self.event_tx,
+                LogLevel::Info,
+                &format!("Time limit reached for {}; closing the position.", symbol),
+                symbol = symbol,
+                decision_id = decision_id,
+                holding_bars = holding_bars,
+                price = close_price,
+            );
+        } else {
+            log_with!(
+                self.event_tx,
+                LogLevel::Info,
+                &format!("Signal generated for {}.", symbol),
+                symbol = symbol,
+                decision_id = decision_id,
+                signal_side = signal_side,
+                price = close_price,
+            );
+        }
+
+        // --- 3. SIZE THE SIGNAL ---
+        let risk_multiplier = *self.risk_multiplier.lock().await;
+        let (portfolio_state, risk_decision) = { // Scoped to release the lock quickly
+            let portfolio_guard = self.portfolio.lock().await;
+            // Create a map of all current prices needed for equity calculation
+            let mut market_prices = HashMap::new();
+            market_prices.insert(symbol.clone(), close_price);
+
+            // Add prices for any other symbols that have positions
+            for pos_symbol in portfolio_guard.positions.keys() {
+                if pos_symbol != &symbol {
+                    // For now, we'll use the last known price or a default
+                    // In a real system, you'd fetch current prices for all symbols
+                    market_prices.insert(pos_symbol.clone(), rust_decimal::Decimal::ZERO); // Placeholder
+                }
+            }
+
+            let latest_equity = match portfolio_guard.calculate_total_equity(&market_prices) {
+                Ok(equity) => equity,
+                Err(e) => return PipelineOutcome::RiskRejected { reason: format!("Could not value the portfolio: {}", e) },
+            };
+            let portfolio_state = events::PortfolioState {
+                timestamp: Utc::now(),
+                quote_asset: portfolio_guard.quote_asset.clone(),
+                cash: portfolio_guard.cash,
+                balances: portfolio_guard.balances.clone(),
+                total_value: latest_equity,
+                positions: portfolio_guard.positions.values().cloned().collect(),
+                realized_pnl: portfolio_guard.realized_pnl,
+                total_fees_paid: portfolio_guard.total_fees_paid,
+            };
+
+            tracing::debug!(
+                cash = %portfolio_state.cash,
+                total_value = %portfolio_state.total_value,
+                open_positions = portfolio_state.positions.len(),
+                risk_multiplier = %risk_multiplier,
+                "Evaluating signal against the portfolio."
+            );
+
+            let risk_decision = match risk.manager.evaluate_scaled_signal(&signal, &portfolio_state, close_price, risk_multiplier) {
+                Ok(plan) => {
+                    tracing::debug!(order_count = plan.legs.len(), policy = ?plan.policy, "Risk manager approved orders.");
+
+                    // --- Expected-Move Filter ---
+                    // The backtester refuses the same entries, so both trade on the same signals.
+                    if let Err(e) = risk.expected_move_filter.check(&plan, kline) {
+                        self.stats.record_filtered_signal(Utc::now());
+                        Err(format!("Expected-move filter rejected signal: {}", e))
+                    } else {
+                        // --- Margin Check ---
+                        // Orders that close or reduce a position free up margin, so only orders that
+                        // open or add to a position need to fit within the bot's leveraged margin,
+                        // out of what the open positions' margin leaves free.
+                        let policy = plan.policy;
+                        let free_margin = {
+                            let leverage = self.leverage.lock().unwrap();
+                            risk::free_margin(portfolio_guard.wallet_balance(), portfolio_guard.positions.values(), |symbol| leverage.get(symbol).copied())
+                        };
+                        plan.legs
+                            .into_iter()
+                            .map(|order| {
+                                if order.reduce_only {
+                                    return Ok(order);
+                                }
+                                risk::margin_check(order, close_price, free_margin, bot.leverage, risk.settings.margin_buffer_pct)
+                                    .map_err(|e| format!("Margin check rejected order: {}", e))
+                            })
+                            .collect::<Result<Vec<_>, _>>()
+                            .map(|legs| OrderPlan { legs, policy })
+                    }
+                }
+                Err(e) => Err(format!("Risk management rejected signal: {:?}", e)),
+            };
+            (portfolio_state, risk_decision)
+        };
+        self.record_latency(&symbol, LatencyStage::Risk, evaluated.elapsed());
+
+        let order_plan = match risk_decision {
+            Ok(plan) => {
+                self.audit(decision_id, DecisionStage::RiskApproved, &symbol, json!({ "orders": plan.legs, "policy": plan.policy, "portfolio": portfolio_state }));
+                plan
+            }
+            Err(reason) => {
+                log_with!(
+                    self.event_tx,
+                    LogLevel::Warn,
+                    &format!("Signal for {} rejected; skipping it.", symbol),
+                    symbol = symbol,
+                    decision_id = decision_id,
+                    signal_side = signal_side,
+                    rejection_reason = reason,
+                );
+                self.audit(decision_id, DecisionStage::RiskRejected, &symbol, json!({ "reason": reason, "portfolio": portfolio_state }));
+                return PipelineOutcome::RiskRejected { reason };
+            }
+        };
+
+        // --- 4. EXECUTE ---
+        let (best_bid, best_ask) = market.execution_quotes(kline);
+        tracing::debug!(best_bid = ?best_bid, best_ask = ?best_ask, "Market state for execution.");
+
+        // A reversal may be a close followed by an entry; each leg is only placed once the
+        // one before it has filled and updated the portfolio.
+        let continues_after_failure = order_plan.continues_after_failure();
+        let leg_count = order_plan.legs.len();
+        let mut executions = Vec::with_capacity(leg_count);
+        let mut failure = None;
+        for (leg, order_request) in order_plan.legs.into_iter().enumerate() {
+            log_with!(
+                self.event_tx,
+                LogLevel::Info,
+                &format!("Risk assessment passed; submitting market order for {}.", order_request.symbol),
+                symbol = order_request.symbol,
+                decision_id = decision_id,
+                order_side = order_request.side,
+                order_qty = order_request.quantity,
+                reduce_only = order_request.reduce_only,
+            );
+            self.audit(decision_id, DecisionStage::OrderSubmitted, &symbol, json!({ "order": order_request, "best_bid": best_bid, "best_ask": best_ask }));
+
+            // An entry waits for every order sent on its symbol to be resolved, so one whose
+            // fill the engine never saw is not doubled. Exits are never held back.
+            if !order_request.reduce_only
+                && let Some(pending) = self.order_ledger.unresolved_on(&symbol)
+            {
+                let error = format!("Order blocked: the {} order {} is unresolved", order_ledger::describe(&pending), pending.client_order_id);
+                self.audit(decision_id, DecisionStage::ExecutionFailed, &symbol, json!({ "error": error }));
+                failure = Some(error);
+                break;
+            }
+
+            // The safety nets get the last word, right before the order leaves the engine.
+            let blocked = {
+                let portfolio = self.portfolio.lock().await;
+                self.safety.lock().await.check(&order_request, &portfolio, Instant::now()).await
+            };
+            // A block is deliberate, e.g. the kill switch refusing the entry of a reversal
+            // after its close, so it ends the plan without halting the bot.
+            if let Err(block) = blocked {
+                let error = format!("Order blocked: {}", block);
+                self.audit(decision_id, DecisionStage::ExecutionFailed, &symbol, json!({ "error": error }));
+                failure = Some(error);
+                break;
+            }
+
+            // Replayed orders never reach an exchange, so they leave no intent behind.
+            if !self.replay
+                && let Err(reason) = self.record_intent(&order_request).await
+                && !order_request.reduce_only
+            {
+                let error = format!("Order blocked: its intent could not be recorded: {}", reason);
+                self.audit(decision_id, DecisionStage::ExecutionFailed, &symbol, json!({ "error": error }));
+                failure = Some(error);
+                break;
+            }
+            let submitted = Instant::now();
+            let result = self.executor.execute(&order_request, kline, best_bid, best_ask).await;
+            self.record_latency(&symbol, LatencyStage::Execution, submitted.elapsed());
+            // An execution resolves the order, as its write marks it filled, and so does a
+            // rejection. Any other error leaves it unknown whether the order was placed: it stays
+            // in the ledger, blocking entries on the symbol, until the exchange says what became
+            // of it.
+            let execution = match result {
+                Ok(execution) => {
+                    self.order_ledger.remove(order_request.client_order_id);
+                    execution
+                }
+                Err(e) => {
+                    if e.is_rejection() {
+                        self.order_ledger.remove(order_request.client_order_id);
+                        if !self.replay {
+                            self.persistence.resolve_order_intent(order_request.client_order_id, OrderIntentState::Failed, None, Utc::now()).await;
+                        }
+                    } else if !self.replay {
+                        log_with!(
+                            self.event_tx,
+                            LogLevel::Error,
+                            &format!("The order {} may have been placed despite the error. No entries are taken on {} until the exchange says what became of it.", order_request.client_order_id, symbol),
+                            symbol = symbol,
+                            decision_id = decision_id,
+                            client_order_id = order_request.client_order_id,
+                        );
+                    }
+                    log_with!(
+                        self.event_tx,
+                        LogLevel::Error,
+                        &format!("Failed to execute order for {}.", symbol),
+                        symbol = symbol,
+                        decision_id = decision_id,
+                        order_side = order_request.side,
+                        order_qty = order_request.quantity,
+                        error = e.to_string(),
+                    );
+                    self.audit(decision_id, DecisionStage::ExecutionFailed, &symbol, json!({ "error": e.to_string() }));
+                    failure.get_or_insert_with(|| e.to_string());
+                    if continues_after_failure {
+                        continue;
+                    }
+                    // The legs already filled cannot be undone safely, and the position is no
+                    // longer what the strategy believes it is.
+                    if !executions.is_empty() {
+                        self.halt(&symbol).await;
+                        let reason = format!("CRITICAL: Leg {} of {} for {} failed after {} filled, leaving the position incomplete: {}.", leg + 1, leg_count, symbol, executions.len(), e);
+                        log_with!(
+                            self.event_tx,
+                            LogLevel::Error,
+                            halted(&reason, &symbol),
+                            symbol = symbol,
+                            decision_id = decision_id,
+                            leg = leg + 1,
+                            legs = leg_count,
+                            error = e.to_string(),
+                        );
+                    }
+                    break;
+                }
+            };
+
+            self.stats.record_fill(Utc::now());
+            // Limit fills count towards the maker ratio once the exchange confirms them.
+            if let Some(placement) = &execution.placement
+                && let Some(is_maker) = placement.is_maker
+            {
+                bot.activity.record_limit_fill(is_maker, placement.price_improvement_bps);
+            }
+            log_with!(
+                self.event_tx,
+                LogLevel::Info,
+                &format!("Execution confirmed for {}.", execution.symbol),
+                symbol = execution.symbol,
+                decision_id = decision_id,
+                order_side = execution.side,
+                order_qty = execution.quantity,
+                execution_price = execution.price,
+            );
+            self.audit(decision_id, DecisionStage::Executed, &symbol, json!({ "execution": execution }));
+            let _ = self.event_tx.send(WsMessage::TradeExecuted(execution.clone()));
+
+            let update = {
+                let mut portfolio = self.portfolio.lock().await;
+                portfolio.update_with_execution(&execution).map(|fills| {
+                    let delta = json!({
+                        "cash": portfolio.cash,
+                        "position": portfolio.get_position(&symbol),
+                        "realized_pnl": portfolio.realized_pnl,
+                        "total_fees_paid": portfolio.total_fees_paid,
+                    });
+                    (fills, delta)
+                })
+            };
+            match update {
+                Ok((fills, portfolio_delta)) => {
+                    self.audit(decision_id, DecisionStage::PortfolioUpdated, &symbol, portfolio_delta);
+                    self.record_execution(&execution, &fills, Some(close_reason)).await;
+                    self.track_positions_with(&risk.settings, Some(opened_by), &execution, &fills).await;
+                    executions.push(execution);
+                }
+                Err(e) => {
+                    // The order filled on the exchange but the portfolio does not show it, so
+                    // every later decision on this symbol would rest on the wrong position.
+                    let error = e.to_string();
+                    self.audit(decision_id, DecisionStage::ExecutionFailed, &symbol, json!({ "execution": execution, "error": error }));
+                    self.halt(&symbol).await;
+                    let reason = format!("CRITICAL: An execution for {} filled but could not be applied to the portfolio: {}.", symbol, error);
+                    log_with!(
+                        self.event_tx,
+                        LogLevel::Error,
+                        halted(&reason, &symbol),
+                        symbol = symbol,
+                        decision_id = decision_id,
+                        execution_id = execution.execution_id,
+                        error = error,
+                    );
+                    self.record_latency(&symbol, LatencyStage::Decision, received.elapsed());
+                    return PipelineOutcome::PortfolioUpdateFailed { execution: Box::new(execution), error, executions };
+                }
+            }
+        }
+        self.record_latency(&symbol, LatencyStage::Decision, received.elapsed());
+
+        match failure {
+            Some(error) => PipelineOutcome::ExecutionFailed { error, executions },
+            None => PipelineOutcome::Executed { executions },
+        }
+    }
+}
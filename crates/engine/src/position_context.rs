@@ -0,0 +1,250 @@
+//! The management context of open positions: the strategy and signal that opened each one and
+//! where its stop-loss and take-profit sit, none of which the exchange reports.
+//!
+//! The pipeline records a context when it opens a position and drops it when the position
+//! closes, and exits positions whose stop or take-profit a kline touched. On startup,
+//! `PositionRecovery` matches the positions the exchange still holds to the contexts recorded
+//! before the restart, and applies the `orphan_position_policy` to positions without one.
+
+use crate::event::MarketState;
+use crate::watchdog::reference_kline;
+use api_client::ApiClient;
+use chrono::{DateTime, Utc};
+use configuration::OrphanPositionPolicy;
+use core_types::{CloseReason, Correction, CorrectionSource, Execution, Holding, Kline, OrderRequest, OrderSide, OrderType, Position, PositionFill, StrategyId};
+use database::PositionContext;
+use executor::{Executor, Portfolio};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// The contexts of the open positions, by symbol. Shared by the pipeline, which keeps them,
+/// and the reconciler, which checks the exchange's positions against them.
+#[derive(Debug, Default)]
+pub struct PositionContexts {
+    open: std::sync::Mutex<HashMap<String, PositionContext>>,
+}
+
+impl PositionContexts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The context of the position open on `symbol`, if it has one.
+    pub fn get(&self, symbol: &str) -> Option<PositionContext> {
+        self.open.lock().unwrap().get(symbol).cloned()
+    }
+
+    /// Every open position's context, in symbol order.
+    pub fn all(&self) -> Vec<PositionContext> {
+        let mut contexts: Vec<_> = self.open.lock().unwrap().values().cloned().collect();
+        contexts.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        contexts
+    }
+
+    /// Tracks `context` as its symbol's open position, replacing any context it had.
+    pub fn insert(&self, context: PositionContext) {
+        self.open.lock().unwrap().insert(context.symbol.clone(), context);
+    }
+
+    /// Stops tracking the position open on `symbol`, returning its context.
+    pub fn remove(&self, symbol: &str) -> Option<PositionContext> {
+        self.open.lock().unwrap().remove(symbol)
+    }
+}
+
+/// The stop `stop_loss_pct` from `entry_price` on the losing side of a `side` position, as the
+/// risk manager sizes it.
+pub fn default_stop(side: OrderSide, entry_price: Decimal, stop_loss_pct: Decimal) -> Decimal {
+    match side {
+        OrderSide::Buy => entry_price * (Decimal::ONE - stop_loss_pct),
+        OrderSide::Sell => entry_price * (Decimal::ONE + stop_loss_pct),
+    }
+}
+
+/// The take-profit `take_profit_pct` from `entry_price` on the winning side of a `side` position.
+pub fn take_profit(side: OrderSide, entry_price: Decimal, take_profit_pct: Decimal) -> Decimal {
+    match side {
+        OrderSide::Buy => entry_price * (Decimal::ONE + take_profit_pct),
+        OrderSide::Sell => entry_price * (Decimal::ONE - take_profit_pct),
+    }
+}
+
+/// The context of a position opened by `fill` of `execution`, with the default stop and, if
+/// `take_profit_pct` is set, a take-profit.
+pub fn opened_context(
+    execution: &Execution,
+    fill: &PositionFill,
+    opened_by: Option<(StrategyId, Uuid)>,
+    stop_loss_pct: Decimal,
+    take_profit_pct: Option<Decimal>,
+) -> PositionContext {
+    PositionContext {
+        position_id: fill.position_id,
+        symbol: execution.symbol.clone(),
+        side: fill.position_side,
+        strategy_id: opened_by.map(|(strategy_id, _)| strategy_id),
+        entry_signal_id: opened_by.map(|(_, signal_id)| signal_id),
+        stop_price: Some(default_stop(fill.position_side, execution.price, stop_loss_pct)),
+        take_profit_price: take_profit_pct.map(|pct| take_profit(fill.position_side, execution.price, pct)),
+        opened_at: execution.timestamp,
+        closed_at: None,
+    }
+}
+
+/// The exit `kline` triggers for the position `context` describes: the stop-loss if the bar
+/// reached it, else the take-profit. The stop comes first when the bar reached both, as
+/// which came first within the bar is unknown.
+pub fn exit_due(context: &PositionContext, kline: &Kline) -> Option<CloseReason> {
+    let (stop_hit, target_hit) = match context.side {
+        OrderSide::Buy => (
+            context.stop_price.is_some_and(|stop| kline.low <= stop),
+            context.take_profit_price.is_some_and(|target| kline.high >= target),
+        ),
+        OrderSide::Sell => (
+            context.stop_price.is_some_and(|stop| kline.high >= stop),
+            context.take_profit_price.is_some_and(|target| kline.low <= target),
+        ),
+    };
+    if stop_hit {
+        Some(CloseReason::StopLoss)
+    } else if target_hit {
+        Some(CloseReason::TakeProfit)
+    } else {
+        None
+    }
+}
+
+/// What startup recovery did about one position or recorded context.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Recovery {
+    /// The position's recorded context was restored, stop and all.
+    Restored { context: PositionContext },
+    /// The position had no context; it is managed from now on with the default stop.
+    Adopted { context: PositionContext },
+    /// The position had no context and was closed at market.
+    Flattened { symbol: String, execution: Box<Execution>, fills: Vec<PositionFill> },
+    /// The position had no context and closing it failed; it is left unmanaged.
+    FlattenFailed { symbol: String, error: String },
+    /// The position had no context and is left unmanaged.
+    Ignored { symbol: String },
+    /// The recorded context's position is no longer open on the exchange: it closed while
+    /// the engine was down, or the exchange now holds the opposite side.
+    ClosedWhileDown { context: PositionContext },
+}
+
+/// Reattaches the recorded contexts to the positions the exchange holds after a restart.
+#[derive(Debug, Clone)]
+pub struct PositionRecovery {
+    pub policy: OrphanPositionPolicy,
+    pub stop_loss_pct: Decimal,
+}
+
+impl PositionRecovery {
+    /// Matches the portfolio's positions, freshly synced from the exchange, to the `stored`
+    /// contexts, tracking the ones restored or adopted in `contexts`. A restored position
+    /// takes its context's position ID back, so its later fills join its recorded history.
+    ///
+    /// The contexts that no longer match a position come first in the result, so closing
+    /// them before recording the adopted ones never closes an adopted one.
+    pub async fn run(
+        &self,
+        stored: Vec<PositionContext>,
+        portfolio: &Mutex<Portfolio>,
+        contexts: &PositionContexts,
+        api_client: &dyn ApiClient,
+        executor: &dyn Executor,
+        now: DateTime<Utc>,
+    ) -> Vec<Recovery> {
+        let mut positions: Vec<Position> = portfolio.lock().await.positions.values().cloned().collect();
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        let mut stored: HashMap<String, PositionContext> = stored.into_iter().map(|context| (context.symbol.clone(), context)).collect();
+
+        let mut orphans = Vec::new();
+        let mut recoveries = Vec::new();
+        let mut restored = Vec::new();
+        for position in positions {
+            match stored.remove(&position.symbol) {
+                Some(context) if context.side == position.side => {
+                    if position.position_id != context.position_id {
+                        let old = Holding::of(&position);
+                        let new = Holding { position_id: context.position_id, ..old.clone() };
+                        let relink = Correction::Position { symbol: position.symbol.clone(), old: Some(old), new: Some(new) };
+                        portfolio.lock().await.correct(relink, CorrectionSource::ContextRestore);
+                    }
+                    contexts.insert(context.clone());
+                    restored.push(Recovery::Restored { context });
+                }
+                Some(context) => {
+                    recoveries.push(Recovery::ClosedWhileDown { context });
+                    orphans.push(position);
+                }
+                None => orphans.push(position),
+            }
+        }
+        let mut stale: Vec<_> = stored.into_values().collect();
+        stale.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        recoveries.extend(stale.into_iter().map(|context| Recovery::ClosedWhileDown { context }));
+        recoveries.extend(restored);
+
+        for position in orphans {
+            let recovery = match self.policy {
+                OrphanPositionPolicy::AdoptWithDefaultStop => {
+                    let context = PositionContext {
+                        position_id: position.position_id,
+                        symbol: position.symbol.clone(),
+                        side: position.side,
+                        strategy_id: None,
+                        entry_signal_id: None,
+                        stop_price: Some(default_stop(position.side, position.entry_price, self.stop_loss_pct)),
+                        take_profit_price: None,
+                        opened_at: now,
+                        closed_at: None,
+                    };
+                    contexts.insert(context.clone());
+                    Recovery::Adopted { context }
+                }
+                OrphanPositionPolicy::Flatten => match flatten(&position, portfolio, api_client, executor).await {
+                    Ok((execution, fills)) => Recovery::Flattened { symbol: position.symbol, execution: Box::new(execution), fills },
+                    Err(error) => Recovery::FlattenFailed { symbol: position.symbol, error },
+                },
+                OrphanPositionPolicy::IgnoreWithAlert => Recovery::Ignored { symbol: position.symbol },
+            };
+            recoveries.push(recovery);
+        }
+        recoveries
+    }
+}
+
+/// Closes `position` with a reduce-only market order, executed against a flat bar at the
+/// symbol's mark price, as no kline has arrived yet.
+async fn flatten(
+    position: &Position,
+    portfolio: &Mutex<Portfolio>,
+    api_client: &dyn ApiClient,
+    executor: &dyn Executor,
+) -> Result<(Execution, Vec<PositionFill>), String> {
+    let mark_price = api_client.get_mark_price(&position.symbol).await.map_err(|e| format!("no mark price: {}", e))?;
+    let state = MarketState { mark_price: Some(mark_price), ..MarketState::default() };
+    let kline = reference_kline(&state).ok_or_else(|| "no price to close at".to_string())?;
+    let order = OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: position.symbol.clone(),
+        side: position.side.opposite(),
+        order_type: OrderType::Market,
+        quantity: position.quantity,
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: true,
+        decision_id: None,
+    };
+    let execution = executor.execute(&order, &kline, None, None).await.map_err(|e| e.to_string())?;
+    let fills = portfolio
+        .lock()
+        .await
+        .update_with_execution(&execution)
+        .map_err(|e| format!("closed, but the portfolio update failed: {}", e))?;
+    Ok((execution, fills))
+}
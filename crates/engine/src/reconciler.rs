@@ -1,17 +1,17 @@
+use crate::collateral::fetch_collateral;
 use crate::error::EngineError;
+use crate::position_context::PositionContexts;
 use api_client::ApiClient;
 use database::DbRepository;
 use executor::Portfolio;
 use std::sync::Arc;
 use tokio::sync::Mutex;
-use tokio::sync::broadcast;
 use tokio::time::{interval, Duration};
 use tracing;
-use events::{WsMessage, LogLevel, LogMessage};
+use events::{EventBus, WsMessage, LogLevel, LogMessage};
 use rust_decimal::Decimal;
-use std::collections::HashMap;
-use uuid::Uuid;
-use chrono::Utc;
+use std::collections::{HashMap, HashSet};
+use core_types::CorrectionSource;
 
 /// The "Source of Truth Auditor" for the live engine.
 ///
@@ -25,7 +25,13 @@ pub struct StateReconciler {
     api_client: Arc<dyn ApiClient>,
     /// A database repository for logging discrepancies (future enhancement).
     db_repo: DbRepository,
-    event_tx: broadcast::Sender<WsMessage>,
+    event_tx: EventBus,
+    /// The account assets counted as collateral, besides the portfolio's quote asset.
+    collateral_assets: Vec<String>,
+    /// The open positions' contexts, to flag exchange positions no stop manages.
+    position_contexts: Option<Arc<PositionContexts>>,
+    /// The symbols already alerted as unmanaged, so each is alerted once while it stays so.
+    unmanaged_alerted: std::sync::Mutex<HashSet<String>>,
 }
 
 impl StateReconciler {
@@ -37,24 +43,57 @@ impl StateReconciler {
         portfolio: Arc<Mutex<Portfolio>>,
         api_client: Arc<dyn ApiClient>,
         db_repo: DbRepository,
-        event_tx: broadcast::Sender<WsMessage>,
+        event_tx: EventBus,
+        collateral_assets: Vec<String>,
     ) -> Self {
         Self {
             portfolio,
             api_client,
             db_repo,
             event_tx,
+            collateral_assets,
+            position_contexts: None,
+            unmanaged_alerted: std::sync::Mutex::new(HashSet::new()),
         }
     }
 
+    /// Checks each exchange position against `contexts`, alerting on the ones without a
+    /// context on their side: positions opened outside the engine, or whose context says
+    /// they closed, that no stop manages.
+    pub fn with_position_contexts(mut self, contexts: Arc<PositionContexts>) -> Self {
+        self.position_contexts = Some(contexts);
+        self
+    }
+
     fn log(&self, level: LogLevel, message: &str) {
         let _ = self.event_tx.send(WsMessage::Log(LogMessage {
             timestamp: chrono::Utc::now(),
             level,
             message: message.to_string(),
+            fields: None,
         }));
     }
 
+    /// Alerts once on each open position without a context on its side, and re-arms the
+    /// alert for symbols that are managed or flat again.
+    fn check_managed(&self, positions: &HashMap<String, core_types::Position>) {
+        let Some(contexts) = &self.position_contexts else {
+            return;
+        };
+        let mut alerted = self.unmanaged_alerted.lock().unwrap();
+        let mut unmanaged = HashSet::new();
+        for (symbol, position) in positions {
+            if contexts.get(symbol).is_some_and(|context| context.side == position.side) {
+                continue;
+            }
+            if !alerted.contains(symbol) {
+                self.log(LogLevel::Error, &format!("UNMANAGED POSITION: The exchange holds a {} position that the engine has no open context for. No stop manages it.", symbol));
+            }
+            unmanaged.insert(symbol.clone());
+        }
+        *alerted = unmanaged;
+    }
+
     pub async fn run_reconciliation(&self) -> Result<(), EngineError> {
         self.log(LogLevel::Info, "[RECONCILER] Running state check...");
 
@@ -65,65 +104,43 @@ impl StateReconciler {
         );
         let live_balances = balances_result?;
         let live_positions = positions_result?;
+        let quote_asset = self.portfolio.lock().await.quote_asset.clone();
+        let collateral = fetch_collateral(self.api_client.as_ref(), &live_balances, &self.collateral_assets, &quote_asset).await;
 
-        // Map live positions into a HashMap for efficient lookup.
-        let live_positions_map: HashMap<String, _> = live_positions
-            .into_iter()
-            .filter(|p| !p.position_amt.is_zero()) // Only care about open positions
-            .map(|p| (p.symbol.clone(), p))
-            .collect();
-            
         // 2. Acquire a lock on our local portfolio state.
         let mut portfolio = self.portfolio.lock().await;
 
-        // 3. Update Cash/Balance from exchange
-        if let Some(usdt_balance) = live_balances.iter().find(|b| b.asset == "USDT") {
-            let local_cash = portfolio.cash;
-            let live_cash = usdt_balance.available_balance;
-            
-            // Always update to exchange balance (source of truth)
-            if local_cash != live_cash {
-                self.log(LogLevel::Info, &format!("Updating cash balance: Local: {} -> Exchange: {}", local_cash, live_cash));
-                portfolio.cash = live_cash;
-            }
+        // 3. Update Cash/Balances from exchange (source of truth)
+        let local_cash = portfolio.cash;
+        for asset in collateral.apply(&mut portfolio, CorrectionSource::Reconciler) {
+            self.log(LogLevel::Warn, &format!("No {} price for collateral asset {}; leaving it out of equity.", quote_asset, asset));
+        }
+        if local_cash != portfolio.cash {
+            self.log(LogLevel::Info, &format!("Updating cash balance: Local: {} -> Exchange: {}", local_cash, portfolio.cash));
         }
 
-        // 4. Replace all local positions with exchange positions (source of truth)
-        self.log(LogLevel::Info, &format!("Replacing local positions with exchange positions. Local count: {}, Exchange count: {}", 
-            portfolio.positions.len(), live_positions_map.len()));
-        
-        // Clear all local positions and replace with exchange data
-        portfolio.positions.clear();
-        
-        for (symbol, live_pos) in &live_positions_map {
-            let side = if live_pos.position_amt.is_sign_positive() {
-                core_types::OrderSide::Buy
-            } else {
-                core_types::OrderSide::Sell
-            };
-            
-            let position = core_types::Position {
-                position_id: uuid::Uuid::new_v4(), // Generate new ID for exchange position
-                symbol: symbol.clone(),
-                side,
-                quantity: live_pos.position_amt.abs(),
-                entry_price: live_pos.entry_price,
-                unrealized_pnl: live_pos.un_realized_profit,
-                last_updated: chrono::Utc::now(),
-            };
-            
-            portfolio.positions.insert(symbol.clone(), position);
-            self.log(LogLevel::Info, &format!("Updated position: {} {} @ {}", symbol, live_pos.position_amt, live_pos.entry_price));
+        // 4. Correct local positions to the exchange positions (source of truth). Each change
+        // is recorded as a correction, so what was overwritten can be seen and undone.
+        let corrections = portfolio.sync_positions(&live_positions, CorrectionSource::Reconciler);
+        self.log(LogLevel::Info, &format!("Reconciled {} local positions against {} exchange positions; {} corrected.",
+            portfolio.positions.len(), live_positions.iter().filter(|p| !p.position_amt.is_zero()).count(), corrections.len()));
+        for correction in corrections {
+            self.log(LogLevel::Warn, &format!("Reconciler changed {}.", correction));
         }
+        self.check_managed(&portfolio.positions);
 
         // At the end of a successful reconciliation, broadcast the updated state.
         // This keeps the UI in sync even if no trades are happening.
         // Note: We already have the portfolio lock from above, so we can use it directly
         let state_msg = WsMessage::PortfolioState(events::PortfolioState {
             timestamp: chrono::Utc::now(),
+            quote_asset: portfolio.quote_asset.clone(),
             cash: portfolio.cash,
+            balances: portfolio.balances.clone(),
             positions: portfolio.positions.values().cloned().collect(),
             total_value: portfolio.cash, // Simplified for now - in a real system we'd calculate with current prices
+            realized_pnl: portfolio.realized_pnl,
+            total_fees_paid: portfolio.total_fees_paid,
         });
         let _ = self.event_tx.send(state_msg);
 
@@ -0,0 +1,152 @@
+//! Replays recorded klines from the database through the live engine.
+//!
+//! The `ReplayConnector` stands in for the exchange's WebSocket streams, so a replay runs the
+//! exact event pipeline of a live session: strategies, risk checks, safety nets and the
+//! executor all see the recorded klines as they would have arrived. This makes the live code
+//! path comparable to a backtest over the same period.
+
+use api_client::error::ApiError;
+use api_client::{BookTickerUpdate, MarkPriceUpdate, MarketDataConnector};
+use chrono::{DateTime, Utc};
+use core_types::Kline;
+use database::DbRepository;
+use rust_decimal::Decimal;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+/// A `MarketDataConnector` that plays the recorded klines opening between `start` and `end`.
+///
+/// Klines are delivered at their close time on a playback clock running `speed` times faster
+/// than real time, starting when the connector is created. Each kline is preceded by a book
+/// ticker quoting `spread_pct` below and above its close. Book tickers only accompany the kline
+/// streams subscribed before them, which is the order the engine subscribes in.
+///
+/// No mark prices are replayed. Once every kline has been delivered all streams close, which
+/// ends the engine's event loop.
+pub struct ReplayConnector {
+    db_repo: DbRepository,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    spread_pct: Decimal,
+    clock: PlaybackClock,
+    book_tx: Mutex<Option<mpsc::Sender<BookTickerUpdate>>>,
+    book_rx: Mutex<Option<mpsc::Receiver<BookTickerUpdate>>>,
+}
+
+impl ReplayConnector {
+    /// Creates a connector replaying the klines opening between `start` and `end`, inclusive,
+    /// as a backtest over the same range would.
+    /// `speed` must be positive.
+    pub fn new(db_repo: DbRepository, start: DateTime<Utc>, end: DateTime<Utc>, speed: f64, spread_pct: Decimal) -> Self {
+        let (book_tx, book_rx) = mpsc::channel(1024);
+        Self {
+            db_repo,
+            start,
+            end,
+            spread_pct,
+            clock: PlaybackClock { start, speed, started: Instant::now() },
+            book_tx: Mutex::new(Some(book_tx)),
+            book_rx: Mutex::new(Some(book_rx)),
+        }
+    }
+}
+
+impl MarketDataConnector for ReplayConnector {
+    fn subscribe_to_klines(&self, symbols: &[String], interval: &str) -> Result<mpsc::Receiver<(String, Kline)>, ApiError> {
+        let (tx, rx) = mpsc::channel(1024);
+        let book_tx = self.book_tx.lock().unwrap().clone();
+        let replay = ReplayStream {
+            db_repo: self.db_repo.clone(),
+            symbols: symbols.to_vec(),
+            interval: interval.to_string(),
+            start: self.start,
+            end: self.end,
+            spread_pct: self.spread_pct,
+        };
+        let clock = self.clock;
+        tokio::spawn(async move {
+            if let Err(e) = replay.play(clock, &tx, book_tx.as_ref()).await {
+                tracing::error!(interval = %replay.interval, error = %e, "[REPLAY] Failed to load the recorded klines.");
+            }
+        });
+        Ok(rx)
+    }
+
+    fn subscribe_to_book_tickers(&self, _symbols: &[String]) -> Result<mpsc::Receiver<BookTickerUpdate>, ApiError> {
+        // Dropping the connector's own sender lets the stream close with the kline streams.
+        self.book_tx.lock().unwrap().take();
+        Ok(self.book_rx.lock().unwrap().take().unwrap_or_else(|| mpsc::channel(1).1))
+    }
+
+    fn subscribe_to_mark_prices(&self, _symbols: &[String]) -> Result<mpsc::Receiver<MarkPriceUpdate>, ApiError> {
+        Ok(mpsc::channel(1).1)
+    }
+}
+
+/// Maps recorded close times to instants on the playback clock.
+#[derive(Clone, Copy)]
+struct PlaybackClock {
+    start: DateTime<Utc>,
+    speed: f64,
+    started: Instant,
+}
+
+impl PlaybackClock {
+    fn due(&self, kline: &Kline) -> Instant {
+        let elapsed = (kline.close_time - self.start).to_std().unwrap_or_default();
+        self.started + Duration::from_secs_f64(elapsed.as_secs_f64() / self.speed)
+    }
+}
+
+/// One replayed kline stream: the klines of `symbols` on `interval`.
+struct ReplayStream {
+    db_repo: DbRepository,
+    symbols: Vec<String>,
+    interval: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    spread_pct: Decimal,
+}
+
+impl ReplayStream {
+    /// Loads the stream's klines and delivers them in close time order, each on time.
+    async fn play(
+        &self,
+        clock: PlaybackClock,
+        tx: &mpsc::Sender<(String, Kline)>,
+        book_tx: Option<&mpsc::Sender<BookTickerUpdate>>,
+    ) -> Result<(), database::DbError> {
+        let mut klines = Vec::new();
+        for symbol in &self.symbols {
+            let recorded = self.db_repo.get_klines_by_date_range(symbol, &self.interval, self.start, self.end).await?;
+            klines.extend(recorded.into_iter().map(|kline| (symbol.clone(), kline)));
+        }
+        klines.sort_by(|(a_symbol, a), (b_symbol, b)| a.close_time.cmp(&b.close_time).then_with(|| a_symbol.cmp(b_symbol)));
+        tracing::info!("[REPLAY] Replaying {} {} klines of {:?}.", klines.len(), self.interval, self.symbols);
+
+        for (symbol, kline) in klines {
+            tokio::time::sleep_until(clock.due(&kline)).await;
+            if let Some(book_tx) = book_tx {
+                // Quotes are best effort: a full channel must not stall the klines.
+                let _ = book_tx.try_send(self.book_ticker(&symbol, &kline));
+            }
+            if tx.send((symbol, kline)).await.is_err() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// A quote `spread_pct` below and above the kline's close.
+    fn book_ticker(&self, symbol: &str, kline: &Kline) -> BookTickerUpdate {
+        BookTickerUpdate {
+            symbol: symbol.to_string(),
+            best_bid_price: kline.close * (Decimal::ONE - self.spread_pct),
+            best_bid_qty: Decimal::ZERO,
+            best_ask_price: kline.close * (Decimal::ONE + self.spread_pct),
+            best_ask_qty: Decimal::ZERO,
+            transaction_time: kline.close_time.timestamp_millis(),
+        }
+    }
+}
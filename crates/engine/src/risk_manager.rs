@@ -1,20 +1,30 @@
 use crate::error::EngineError;
+use crate::persistence::Persistence;
+use crate::risk_state::{self, BotHalt, HaltReason, RiskState, CONSECUTIVE_LOSSES_KEY, GLOBAL_SCOPE, HALT_KEY, LAST_ROLLOVER_KEY, LEVERAGE_PEAK_EQUITY_KEY, PEAK_EQUITY_KEY};
 use configuration::settings::GlobalRiskConfig;
-use core_types::{Trade, OrderSide};
-use events::{LogLevel, WsMessage, LogMessage};
+use database::{LiveFill, RiskStateEntry};
+use core_types::{Position, Trade, OrderSide};
+use events::{EventBus, LogLevel, WsMessage, LogMessage};
+use events::PortfolioState;
 use executor::Portfolio;
+use risk::{DailyLossAlert, DailyLossTracker, DrawdownScaler, LossLevel, LossScope, TierChange};
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
-use std::time::Duration;
-use tokio::sync::{broadcast, Mutex};
-use tokio::time::sleep;
-use chrono::Utc;
+use tokio::sync::Mutex;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
 
 /// The "Portfolio Pit Boss" - a concurrent, stateful supervisor.
 ///
 /// This component runs in the background, monitoring the overall health of the
 /// trading portfolio and enforcing a set of global, non-negotiable risk rules.
+///
+/// Its accounting survives restarts: every change to the day's peak equity, a bot's losing
+/// streak or its halt is recorded through `with_persistence`, and `new` resumes from the
+/// `RiskState` read back. Halts end at their recorded expiry, checked by
+/// `release_expired_halts`, and `roll_over_if_due` starts each trading day at the
+/// configured `rollover_hour_utc`.
 pub struct GlobalRiskManager {
     // --- Configuration ---
     config: GlobalRiskConfig,
@@ -25,32 +35,350 @@ pub struct GlobalRiskManager {
     /// A shared map of trading flags that this manager controls.
     trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
     /// The broadcast sender for sending alerts.
-    event_tx: broadcast::Sender<WsMessage>,
+    event_tx: EventBus,
+    /// The fraction of `risk_per_trade_pct` new entries take, lowered as the account's
+    /// drawdown deepens when dynamic leverage is configured.
+    risk_multiplier: Arc<Mutex<Decimal>>,
+    /// Records the changes to the risk state, if set.
+    persistence: Option<Persistence>,
 
     // --- Internal State ---
-    /// Tracks the peak equity reached during the current trading session.
+    /// Tracks the peak equity reached during the current trading day.
     peak_equity_today: Mutex<Decimal>,
+    /// The start of the trading day the last rollover began, if any.
+    last_rollover: Mutex<Option<DateTime<Utc>>>,
     /// Tracks the number of consecutive losses for each individual bot.
     consecutive_losses: Mutex<HashMap<String, u32>>,
+    /// The bots halted by the consecutive loss or drawdown limits, until their expiry.
+    halts: Mutex<HashMap<String, BotHalt>>,
+    /// The positions currently within the warning distance of their estimated liquidation
+    /// price, which have already been warned about.
+    near_liquidation: Mutex<HashSet<Uuid>>,
+    /// Follows the account's drawdown through the dynamic leverage tiers, if configured.
+    drawdown_scaler: Option<Mutex<DrawdownScaler>>,
+    /// The dynamic leverage peak last recorded, to record only its changes.
+    recorded_leverage_peak: Mutex<Option<Decimal>>,
+    /// Measures each trading day's loss against the daily loss limits, if configured. Shared
+    /// with the signal pipeline, which records the P&L of every fill into it.
+    daily_loss: Option<Arc<Mutex<DailyLossTracker>>>,
+    /// The symbols halted by a daily loss limit, re-enabled at the next rollover.
+    halted_for_the_day: Mutex<HashSet<String>>,
 }
 
 impl GlobalRiskManager {
-    /// Creates a new `GlobalRiskManager`.
+    /// Creates a new `GlobalRiskManager`, resuming from the `restored` state recorded before a
+    /// restart. A manager starting afresh takes `RiskState::default()`, with `initial_equity`
+    /// as the day's peak. The restored halts take effect at `resume_halts`.
     pub fn new(
         config: GlobalRiskConfig,
         portfolio: Arc<Mutex<Portfolio>>,
         trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
-        event_tx: broadcast::Sender<WsMessage>,
+        event_tx: EventBus,
         initial_equity: Decimal,
+        restored: RiskState,
     ) -> Self {
+        // The dynamic leverage peak outlasts trading days and restarts, so a restart in a
+        // drawdown resumes in the tier it left off in.
+        let drawdown_scaler = config.dynamic_leverage.as_ref().map(|dynamic_leverage| {
+            let mut scaler = DrawdownScaler::new(dynamic_leverage);
+            if let Some(peak) = restored.leverage_peak_equity {
+                scaler.update(peak);
+            }
+            scaler.update(initial_equity);
+            scaler
+        });
+        let risk_multiplier = drawdown_scaler.as_ref().map_or(Decimal::ONE, DrawdownScaler::multiplier);
+        let daily_loss = config.daily_loss.clone().map(|limits| Arc::new(Mutex::new(DailyLossTracker::new(limits))));
         Self {
             config,
             portfolio,
             trading_enabled_flags,
             event_tx,
-            peak_equity_today: Mutex::new(initial_equity),
-            consecutive_losses: Mutex::new(HashMap::new()),
+            risk_multiplier: Arc::new(Mutex::new(risk_multiplier)),
+            persistence: None,
+            peak_equity_today: Mutex::new(restored.peak_equity.unwrap_or(initial_equity)),
+            last_rollover: Mutex::new(restored.last_rollover),
+            consecutive_losses: Mutex::new(restored.consecutive_losses),
+            halts: Mutex::new(restored.halts),
+            near_liquidation: Mutex::new(HashSet::new()),
+            drawdown_scaler: drawdown_scaler.map(Mutex::new),
+            recorded_leverage_peak: Mutex::new(restored.leverage_peak_equity),
+            daily_loss,
+            halted_for_the_day: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Records every change to the risk state through `persistence`, for the next start to
+    /// resume from.
+    pub fn with_persistence(mut self, persistence: Persistence) -> Self {
+        self.persistence = Some(persistence);
+        self
+    }
+
+    /// The current risk state, as it is recorded.
+    pub async fn risk_state(&self) -> RiskState {
+        RiskState {
+            peak_equity: Some(*self.peak_equity_today.lock().await),
+            last_rollover: *self.last_rollover.lock().await,
+            consecutive_losses: self.consecutive_losses.lock().await.clone(),
+            halts: self.halts.lock().await.clone(),
+            leverage_peak_equity: match &self.drawdown_scaler {
+                Some(scaler) => scaler.lock().await.peak_equity(),
+                None => None,
+            },
+        }
+    }
+
+    /// Halts again the bots that were halted before a restart, until their recorded expiry.
+    /// Call it once the bots' trading flags are set; halts that expired while the engine was
+    /// down are lifted instead. Returns the symbols still halted.
+    pub async fn resume_halts(&self, now: DateTime<Utc>) -> Vec<String> {
+        self.release_expired_halts(now).await;
+        let halts = self.halts.lock().await.clone();
+        let mut flags = self.trading_enabled_flags.lock().await;
+        let mut resumed: Vec<String> = halts.keys().filter(|symbol| flags.contains_key(*symbol)).cloned().collect();
+        resumed.sort();
+        for symbol in &resumed {
+            flags.insert(symbol.clone(), false);
+            self.log(
+                LogLevel::Warn,
+                &format!("BOT HALTED: Trading for {} remains disabled until {}, as before the restart.", symbol, halts[symbol].until.format("%Y-%m-%d %H:%M UTC")),
+            );
+        }
+        resumed
+    }
+
+    /// Re-enables the bots whose halt expired by `now`. The engine calls it every second.
+    /// Returns the symbols re-enabled.
+    pub async fn release_expired_halts(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut released: Vec<(String, BotHalt)> = {
+            let mut halts = self.halts.lock().await;
+            let expired: Vec<String> = halts.iter().filter(|(_, halt)| halt.expired(now)).map(|(symbol, _)| symbol.clone()).collect();
+            expired.into_iter().filter_map(|symbol| halts.remove(&symbol).map(|halt| (symbol, halt))).collect()
+        };
+        if released.is_empty() {
+            return Vec::new();
+        }
+        released.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut flags = self.trading_enabled_flags.lock().await;
+        for (symbol, halt) in &released {
+            if let Some(is_enabled) = flags.get_mut(symbol) {
+                *is_enabled = true;
+            }
+            let message = match halt.reason {
+                HaltReason::ConsecutiveLosses => format!("BOT RE-ENABLED: Trading for {} has been automatically re-enabled after cool-down.", symbol),
+                HaltReason::Drawdown => format!("BOT RE-ENABLED: A new trading day started; trading for {} is re-enabled after the drawdown halt.", symbol),
+            };
+            self.log(LogLevel::Info, &message);
+            tracing::info!(symbol = %symbol, reason = ?halt.reason, "Bot has been re-enabled.");
+        }
+        drop(flags);
+
+        self.record(released.iter().map(|(symbol, _)| risk_state::cleared(symbol, HALT_KEY, now)).collect()).await;
+        released.into_iter().map(|(symbol, _)| symbol).collect()
+    }
+
+    /// Starts a new trading day if `now` is past the rollover hour the last rollover
+    /// was not: archives the state of the day that ended, then restarts the peak equity at the
+    /// current equity. Drawdown halts expire at the rollover. The engine calls it every
+    /// second. Returns whether a rollover happened.
+    pub async fn roll_over_if_due(&self, now: DateTime<Utc>) -> bool {
+        let day_start = risk::trading_day_start(now, self.config.rollover_hour_utc());
+        let previous = {
+            let mut last_rollover = self.last_rollover.lock().await;
+            if last_rollover.is_some_and(|last| last >= day_start) {
+                return false;
+            }
+            last_rollover.replace(day_start)
+        };
+
+        // The day that ended is archived before anything of it is reset.
+        if let (Some(previous), Some(persistence)) = (previous, &self.persistence) {
+            persistence.archive_risk_state(previous, now).await;
+        }
+        let equity = self.current_equity().await;
+        *self.peak_equity_today.lock().await = equity;
+        self.record(vec![
+            risk_state::entry(GLOBAL_SCOPE, PEAK_EQUITY_KEY, equity, now),
+            risk_state::entry(GLOBAL_SCOPE, LAST_ROLLOVER_KEY, day_start, now),
+        ])
+        .await;
+        self.log(LogLevel::Info, &format!("DAILY ROLLOVER: The trading day starting {} has begun with a peak equity of {}.", day_start.format("%Y-%m-%d %H:%M UTC"), equity.round_dp(2)));
+        true
+    }
+
+    /// Queues changed values of the risk state for the persistence task, if recording.
+    async fn record(&self, entries: Vec<RiskStateEntry>) {
+        if let Some(persistence) = &self.persistence {
+            persistence.record_risk_state(entries).await;
+        }
+    }
+
+    /// The shared fraction of `risk_per_trade_pct` new entries take, for the signal pipeline.
+    pub fn risk_multiplier(&self) -> Arc<Mutex<Decimal>> {
+        Arc::clone(&self.risk_multiplier)
+    }
+
+    /// Moves the account through the dynamic leverage tiers by its latest marked equity,
+    /// alerting on every change of tier and recording every new peak. Returns the change, if
+    /// any.
+    pub async fn on_equity(&self, equity: Decimal) -> Option<TierChange> {
+        let (change, peak) = {
+            let mut scaler = self.drawdown_scaler.as_ref()?.lock().await;
+            (scaler.update(equity), scaler.peak_equity())
+        };
+        // The peak the manager started from is recorded too, with the first equity.
+        let new_peak = {
+            let mut recorded = self.recorded_leverage_peak.lock().await;
+            let new_peak = peak.filter(|_| peak != *recorded);
+            *recorded = peak;
+            new_peak
+        };
+        if let Some(peak) = new_peak {
+            self.record(vec![risk_state::entry(GLOBAL_SCOPE, LEVERAGE_PEAK_EQUITY_KEY, peak, Utc::now())]).await;
+        }
+        let change = change?;
+        *self.risk_multiplier.lock().await = change.to;
+
+        let (level, verb) = if change.is_reduction() { (LogLevel::Warn, "reduced") } else { (LogLevel::Info, "restored") };
+        self.log(
+            level,
+            &format!(
+                "DYNAMIC LEVERAGE: Risk of new entries {} from {}x to {}x at a {:.2}% drawdown from peak equity.",
+                verb,
+                change.from.normalize(),
+                change.to.normalize(),
+                change.drawdown * Decimal::from(100)
+            ),
+        );
+        Some(change)
+    }
+
+    /// The shared daily loss tracker, for the signal pipeline, if daily loss limits are
+    /// configured.
+    pub fn daily_loss(&self) -> Option<Arc<Mutex<DailyLossTracker>>> {
+        self.daily_loss.clone()
+    }
+
+    /// The start of the trading day `at` falls in, if daily loss limits are configured.
+    pub fn trading_day_start(&self, at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        self.config.daily_loss.as_ref().map(|limits| risk::trading_day_start(at, limits.rollover_hour_utc))
+    }
+
+    /// Resumes the trading day that started at `day_start` from the live `fills` recorded
+    /// since, e.g. after a restart. `start_equity` is the account's equity at the rollover, if
+    /// recorded. Returns the P&L the fills realized, net of fees.
+    pub async fn restore_daily_loss(&self, day_start: DateTime<Utc>, start_equity: Option<Decimal>, fills: &[LiveFill]) -> Decimal {
+        let Some(tracker) = &self.daily_loss else {
+            return Decimal::ZERO;
+        };
+        let mut realized: BTreeMap<String, Decimal> = BTreeMap::new();
+        for fill in fills.iter().filter(|fill| fill.executed_at >= day_start) {
+            *realized.entry(fill.symbol.clone()).or_default() += fill.realized_pnl - fill.fee;
+        }
+        let total = realized.values().sum();
+        tracker.lock().await.start_day(day_start, start_equity, realized);
+        total
+    }
+
+    /// Checks the day's loss in a freshly marked portfolio `state` against the daily loss
+    /// limits. Alerts a warning at 80% of a limit; a breach halts the symbol's bot, or every
+    /// bot for the account's limit, until the next rollover, which re-enables them. Returns
+    /// the alerts raised.
+    pub async fn check_daily_loss(&self, state: &PortfolioState) -> Vec<DailyLossAlert> {
+        let Some(tracker) = &self.daily_loss else {
+            return Vec::new();
+        };
+        let (update, next_rollover) = {
+            let mut tracker = tracker.lock().await;
+            let update = tracker.update(state);
+            (update, tracker.next_rollover())
+        };
+
+        if update.rolled_over {
+            let released: Vec<String> = self.halted_for_the_day.lock().await.drain().collect();
+            if !released.is_empty() {
+                let mut flags = self.trading_enabled_flags.lock().await;
+                for symbol in &released {
+                    flags.insert(symbol.clone(), true);
+                }
+                drop(flags);
+                self.log(
+                    LogLevel::Info,
+                    &format!("BOT RE-ENABLED: A new trading day started; trading for {} is re-enabled after its daily loss halt.", released.join(", ")),
+                );
+            }
+        }
+
+        let until = next_rollover.map_or_else(|| "the next rollover".to_string(), |at| at.format("%Y-%m-%d %H:%M UTC").to_string());
+        for alert in &update.alerts {
+            let loss = alert.loss.round_dp(2);
+            let limit = alert.limit.round_dp(2);
+            match alert.level {
+                LossLevel::Warning => self.log(
+                    LogLevel::Warn,
+                    &format!(
+                        "DAILY LOSS: {} has lost {} today, {:.0}% of its daily loss limit of {}.",
+                        alert.scope,
+                        loss,
+                        alert.loss / alert.limit * Decimal::from(100),
+                        limit
+                    ),
+                ),
+                LossLevel::Breach => {
+                    self.log(
+                        LogLevel::Error,
+                        &format!("CRITICAL: {} has lost {} today, breaching its daily loss limit of {}. Halting until {}.", alert.scope, loss, limit, until),
+                    );
+                    self.halt_for_the_day(&alert.scope).await;
+                }
+            }
         }
+        update.alerts
+    }
+
+    /// Checks a freshly marked position against its estimated liquidation price.
+    ///
+    /// Raises a Warn alert when `mark_price` comes within `liquidation_warning_pct` of it.
+    /// Each approach is only warned about once; a position that moves back out of range is
+    /// warned about again if it returns. Returns whether an alert was raised.
+    pub async fn check_liquidation_distance(&self, position: &Position, mark_price: Decimal) -> bool {
+        let Some(liquidation_price) = position.estimated_liquidation_price else {
+            return false;
+        };
+        if mark_price.is_zero() {
+            return false;
+        }
+
+        let distance = (mark_price - liquidation_price).abs() / mark_price;
+        let mut near_liquidation = self.near_liquidation.lock().await;
+        if distance > self.config.liquidation_warning_pct {
+            near_liquidation.remove(&position.position_id);
+            return false;
+        }
+        if !near_liquidation.insert(position.position_id) {
+            return false;
+        }
+        drop(near_liquidation);
+
+        self.log(
+            LogLevel::Warn,
+            &format!(
+                "{} {:?} position is {:.2}% from its estimated liquidation price of {} (mark price {}).",
+                position.symbol,
+                position.side,
+                distance * Decimal::from(100),
+                liquidation_price.round_dp(8),
+                mark_price
+            ),
+        );
+        true
+    }
+
+    /// The current run of losing trades of each bot that has closed a trade.
+    pub async fn consecutive_losses(&self) -> HashMap<String, u32> {
+        self.consecutive_losses.lock().await.clone()
     }
 
     /// This is the primary event handler for the risk manager.
@@ -88,6 +416,8 @@ impl GlobalRiskManager {
 
         let current_streak = *loss_counter;
         drop(losses); // Release the lock before the next await call
+        let closed_at = trade.exit_execution.timestamp;
+        self.record(vec![risk_state::entry(&trade.symbol, CONSECUTIVE_LOSSES_KEY, current_streak, closed_at)]).await;
 
         // 3. Check if the consecutive loss limit has been breached.
         if current_streak >= self.config.max_consecutive_losses {
@@ -98,43 +428,52 @@ impl GlobalRiskManager {
                     trade.symbol, self.config.max_consecutive_losses
                 ),
             );
-            self.halt_bot(&trade.symbol).await; // This will be implemented in Task 4
+            self.halt_bot(&trade.symbol, closed_at).await;
         }
         
         // 4. After every trade, check the portfolio-wide drawdown.
-        self.check_daily_drawdown().await?;
+        self.check_daily_drawdown(closed_at).await?;
 
         Ok(())
     }
 
-    /// Checks the current portfolio equity against the session's peak to enforce max drawdown.
-    async fn check_daily_drawdown(&self) -> Result<(), EngineError> {
-        let current_equity = {
-            let portfolio = self.portfolio.lock().await;
-            // A full implementation would need to mark-to-market all open positions here.
-            // For now, we use a simplified equity measure.
-            portfolio.cash // Simplified equity for now
-        };
+    /// The equity the drawdown limit is checked against.
+    async fn current_equity(&self) -> Decimal {
+        let portfolio = self.portfolio.lock().await;
+        // A full implementation would need to mark-to-market all open positions here.
+        // For now, we use a simplified equity measure.
+        portfolio.cash // Simplified equity for now
+    }
 
-        let mut peak_equity = self.peak_equity_today.lock().await;
+    /// Checks the current portfolio equity against the day's peak to enforce max drawdown.
+    async fn check_daily_drawdown(&self, now: DateTime<Utc>) -> Result<(), EngineError> {
+        let current_equity = self.current_equity().await;
 
         // Update the peak equity if we've reached a new high.
-        if current_equity > *peak_equity {
-            *peak_equity = current_equity;
+        let (peak_equity, new_high) = {
+            let mut peak_equity = self.peak_equity_today.lock().await;
+            let new_high = current_equity > *peak_equity;
+            if new_high {
+                *peak_equity = current_equity;
+            }
+            (*peak_equity, new_high)
+        };
+        if new_high {
+            self.record(vec![risk_state::entry(GLOBAL_SCOPE, PEAK_EQUITY_KEY, current_equity, now)]).await;
         }
 
         // Calculate the current drawdown percentage.
-        let drawdown = (*peak_equity - current_equity) / *peak_equity;
+        let drawdown = (peak_equity - current_equity) / peak_equity;
 
         if drawdown >= self.config.max_daily_drawdown_pct {
             self.log(
                 LogLevel::Error,
                 &format!(
-                    "CRITICAL: Portfolio has breached the max daily drawdown limit of {:.2}%. Halting all trading.",
+                    "CRITICAL: Portfolio has breached the max daily drawdown limit of {:.2}%. Halting all trading until the next rollover.",
                     self.config.max_daily_drawdown_pct * Decimal::from(100)
                 )
             );
-            self.halt_all_bots().await; // This will be implemented in Task 4
+            self.halt_all_bots(now).await;
         }
         
         Ok(())
@@ -146,63 +485,79 @@ impl GlobalRiskManager {
             timestamp: Utc::now(),
             level,
             message: message.to_string(),
+            fields: None,
         });
         let _ = self.event_tx.send(msg);
         // In a real implementation, you might want to handle the send error
     }
 
-    /// Disables trading for a single bot and starts the cool-down timer.
-    async fn halt_bot(&self, symbol: &str) {
-        // 1. Lock the shared trading flags and disable the specific bot.
-        let mut flags = self.trading_enabled_flags.lock().await;
-        flags.insert(symbol.to_string(), false);
+    /// Disables trading for a single bot until `bot_cooldown_hours` after `now`. The halt is
+    /// recorded, so it outlasts a restart; `release_expired_halts` lifts it.
+    async fn halt_bot(&self, symbol: &str, now: DateTime<Utc>) {
+        let cooldown_hours = self.config.bot_cooldown_hours;
+        // A cool-down too long to represent never ends.
+        let until = i64::try_from(cooldown_hours)
+            .ok()
+            .and_then(Duration::try_hours)
+            .and_then(|cooldown| now.checked_add_signed(cooldown))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC);
+        let halt = self.halt(symbol, BotHalt { reason: HaltReason::ConsecutiveLosses, halted_at: now, until }).await;
 
         self.log(
             LogLevel::Error,
             &format!(
-                "BOT HALTED: Trading for {} has been disabled due to risk limits.",
-                symbol
+                "BOT HALTED: Trading for {} has been disabled due to risk limits until {}.",
+                symbol,
+                halt.until.format("%Y-%m-%d %H:%M UTC")
             ),
         );
+        tracing::warn!(symbol = %symbol, cooldown_hours = %cooldown_hours, until = %halt.until, "Bot entered cool-down period.");
+    }
 
-        // 2. Spawn a separate, concurrent task for the cool-down timer.
-        let symbol_clone = symbol.to_string();
-        let flags_clone = Arc::clone(&self.trading_enabled_flags);
-        let event_tx_clone = self.event_tx.clone();
-        let cooldown_hours = self.config.bot_cooldown_hours;
-        let cooldown_duration = Duration::from_secs(cooldown_hours * 3600);
-
-        tokio::spawn(async move {
-            tracing::warn!(
-                symbol = %symbol_clone,
-                cooldown_hours = %cooldown_hours,
-                "Bot entered cool-down period."
-            );
-
-            // 3. Wait for the configured duration.
-            sleep(cooldown_duration).await;
-
-            // 4. Re-enable the bot after the timer expires.
-            let mut flags = flags_clone.lock().await;
-            flags.insert(symbol_clone.clone(), true);
-            
-            // Log and broadcast the re-enabling event.
-            let log_msg = WsMessage::Log(LogMessage {
-                timestamp: Utc::now(),
-                level: LogLevel::Info,
-                message: format!("BOT RE-ENABLED: Trading for {} has been automatically re-enabled after cool-down.", symbol_clone),
-            });
-            let _ = event_tx_clone.send(log_msg);
-            tracing::info!(symbol = %symbol_clone, "Bot has been re-enabled after cool-down.");
-        });
+    /// Disables trading for `symbol`'s bot under `halt`, or under the halt already in force
+    /// if that one ends later, and records it. Returns the halt in force.
+    async fn halt(&self, symbol: &str, halt: BotHalt) -> BotHalt {
+        self.trading_enabled_flags.lock().await.insert(symbol.to_string(), false);
+        let halt = {
+            let mut halts = self.halts.lock().await;
+            let in_force = halts.entry(symbol.to_string()).or_insert(halt);
+            if in_force.until < halt.until {
+                *in_force = halt;
+            }
+            *in_force
+        };
+        self.record(vec![risk_state::entry(symbol, HALT_KEY, halt, halt.halted_at)]).await;
+        halt
     }
 
-    /// Disables trading for ALL bots in the system.
-    async fn halt_all_bots(&self) {
+    /// Disables trading for the bots `scope` covers until the next rollover, with no cool-down
+    /// timer.
+    async fn halt_for_the_day(&self, scope: &LossScope) {
         let mut flags = self.trading_enabled_flags.lock().await;
+        let mut halted = self.halted_for_the_day.lock().await;
         for (symbol, is_enabled) in flags.iter_mut() {
-            if *is_enabled {
+            let covered = match scope {
+                LossScope::Account => true,
+                LossScope::Symbol(scope_symbol) => scope_symbol == symbol,
+            };
+            if covered && *is_enabled {
                 *is_enabled = false;
+                halted.insert(symbol.clone());
+                self.log(
+                    LogLevel::Error,
+                    &format!("BOT HALTED: Trading for {} has been disabled for the rest of the trading day.", symbol),
+                );
+            }
+        }
+    }
+
+    /// Disables trading for ALL bots in the system until the next rollover.
+    async fn halt_all_bots(&self, now: DateTime<Utc>) {
+        let until = risk::trading_day_start(now, self.config.rollover_hour_utc()) + Duration::days(1);
+        let flags: Vec<(String, bool)> = self.trading_enabled_flags.lock().await.iter().map(|(symbol, enabled)| (symbol.clone(), *enabled)).collect();
+        for (symbol, was_enabled) in flags {
+            self.halt(&symbol, BotHalt { reason: HaltReason::Drawdown, halted_at: now, until }).await;
+            if was_enabled {
                 self.log(
                     LogLevel::Error,
                     &format!("PORTFOLIO HALTED: Trading for {} disabled due to portfolio drawdown.", symbol),
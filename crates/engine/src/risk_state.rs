@@ -0,0 +1,125 @@
+//! The global risk manager's state, kept across restarts.
+//!
+//! `GlobalRiskManager` records every change to its accounting as a value of the `risk_state`
+//! table: the trading day's peak equity, the dynamic leverage peak and the last daily rollover
+//! under the global scope, and each bot's losing streak and halt under its symbol. `RiskState` is what an engine reads
+//! back from the table when it restarts, so a restart neither resets a losing streak nor lifts
+//! a halt before its recorded expiry.
+
+use chrono::{DateTime, Utc};
+use database::RiskStateEntry;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+
+/// The scope of the values that belong to the whole account rather than one symbol.
+pub const GLOBAL_SCOPE: &str = "global";
+
+/// The peak equity of the current trading day, in the global scope.
+pub const PEAK_EQUITY_KEY: &str = "peak_equity";
+
+/// The highest equity the dynamic leverage tiers measure the drawdown from, in the global
+/// scope. Unlike the day's peak, it is not reset at the rollover.
+pub const LEVERAGE_PEAK_EQUITY_KEY: &str = "leverage_peak_equity";
+
+/// The start of the trading day the last rollover began, in the global scope.
+pub const LAST_ROLLOVER_KEY: &str = "last_rollover";
+
+/// A bot's current run of losing trades, in its symbol's scope.
+pub const CONSECUTIVE_LOSSES_KEY: &str = "consecutive_losses";
+
+/// A bot's halt, in its symbol's scope.
+pub const HALT_KEY: &str = "halt";
+
+/// Why the global risk manager halted a bot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HaltReason {
+    /// The bot hit `max_consecutive_losses`, and cools down for `bot_cooldown_hours`.
+    ConsecutiveLosses,
+    /// The account breached `max_daily_drawdown_pct`, halting every bot until the next
+    /// rollover.
+    Drawdown,
+}
+
+/// A bot halted by the global risk manager.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BotHalt {
+    pub reason: HaltReason,
+    pub halted_at: DateTime<Utc>,
+    /// When the bot is re-enabled.
+    pub until: DateTime<Utc>,
+}
+
+impl BotHalt {
+    /// Whether the halt is over at `now`.
+    pub fn expired(&self, now: DateTime<Utc>) -> bool {
+        self.until <= now
+    }
+}
+
+/// The global risk manager's state, as recorded in the `risk_state` table.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RiskState {
+    /// The peak equity of the current trading day. None before any was recorded.
+    pub peak_equity: Option<Decimal>,
+    /// The start of the trading day the last rollover began. None before the first one.
+    pub last_rollover: Option<DateTime<Utc>>,
+    /// Each bot's current run of losing trades, by symbol.
+    pub consecutive_losses: HashMap<String, u32>,
+    /// The halted bots, by symbol.
+    pub halts: HashMap<String, BotHalt>,
+    /// The peak equity of the dynamic leverage tiers. None before any was recorded, or
+    /// without dynamic leverage.
+    pub leverage_peak_equity: Option<Decimal>,
+}
+
+impl RiskState {
+    /// Rebuilds the state from the values of the `risk_state` table. A value that cannot be
+    /// read is left out with a warning.
+    pub fn from_entries(entries: impl IntoIterator<Item = RiskStateEntry>) -> Self {
+        let mut state = Self::default();
+        for entry in entries {
+            let value = entry.value;
+            let read = match (entry.scope.as_str(), entry.key.as_str()) {
+                (GLOBAL_SCOPE, PEAK_EQUITY_KEY) => serde_json::from_value(value).map(|peak| state.peak_equity = Some(peak)),
+                (GLOBAL_SCOPE, LEVERAGE_PEAK_EQUITY_KEY) => serde_json::from_value(value).map(|peak| state.leverage_peak_equity = Some(peak)),
+                (GLOBAL_SCOPE, LAST_ROLLOVER_KEY) => serde_json::from_value(value).map(|at| state.last_rollover = Some(at)),
+                (GLOBAL_SCOPE, _) => {
+                    tracing::warn!(key = %entry.key, "Ignoring an unknown global risk state value.");
+                    continue;
+                }
+                (symbol, CONSECUTIVE_LOSSES_KEY) => serde_json::from_value(value).map(|losses| {
+                    state.consecutive_losses.insert(symbol.to_string(), losses);
+                }),
+                (symbol, HALT_KEY) => serde_json::from_value(value).map(|halt| {
+                    state.halts.insert(symbol.to_string(), halt);
+                }),
+                (symbol, _) => {
+                    tracing::warn!(symbol = %symbol, key = %entry.key, "Ignoring an unknown risk state value.");
+                    continue;
+                }
+            };
+            if let Err(e) = read {
+                tracing::warn!(scope = %entry.scope, key = %entry.key, error = %e, "Ignoring an unreadable risk state value.");
+            }
+        }
+        state
+    }
+}
+
+/// The value `value` of `scope`'s `key`, changed at `updated_at`.
+pub fn entry(scope: &str, key: &str, value: impl Serialize, updated_at: DateTime<Utc>) -> RiskStateEntry {
+    RiskStateEntry {
+        scope: scope.to_string(),
+        key: key.to_string(),
+        value: serde_json::to_value(value).unwrap_or(JsonValue::Null),
+        updated_at,
+    }
+}
+
+/// Clears `scope`'s `key`, at `updated_at`.
+pub fn cleared(scope: &str, key: &str, updated_at: DateTime<Utc>) -> RiskStateEntry {
+    entry(scope, key, JsonValue::Null, updated_at)
+}
@@ -0,0 +1,153 @@
+//! Last-line safety checks applied to every order right before it reaches the executor.
+//!
+//! These are deliberately simple and independent of the risk manager: they guard against
+//! operational failures, like a bugged strategy firing every bar, rather than judge trades.
+//! A kill switch stops all new exposure, a global cap limits the number of open positions,
+//! and a rolling hourly throttle halts a bot that places too many orders.
+
+use chrono::Utc;
+use core_types::OrderRequest;
+use events::{EventBus, LogLevel, LogMessage, WsMessage};
+use executor::Portfolio;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+
+/// The environment variable that, when set to a non-empty value, engages the kill switch.
+pub const KILL_SWITCH_ENV: &str = "ZENITH_KILL_SWITCH";
+
+/// The window over which `max_orders_per_hour` is counted.
+const ORDER_WINDOW: Duration = Duration::from_secs(3600);
+
+/// Why a `SafetyGuard` refused an order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderBlock {
+    /// The kill switch is engaged; `source` names the file or variable that engaged it.
+    KillSwitch { source: String },
+    /// Opening another position would exceed `max_open_positions`.
+    MaxOpenPositions { open: usize, max: usize },
+    /// The bot already placed `max` orders in the last hour.
+    OrderRateExceeded { symbol: String, max: u32 },
+}
+
+impl fmt::Display for OrderBlock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KillSwitch { source } => write!(f, "the kill switch is engaged ({})", source),
+            Self::MaxOpenPositions { open, max } => write!(f, "{} positions are open, the maximum is {}", open, max),
+            Self::OrderRateExceeded { symbol, max } => write!(f, "{} placed {} orders in the last hour, the maximum", symbol, max),
+        }
+    }
+}
+
+/// Counts each bot's orders over a rolling window.
+#[derive(Debug)]
+struct OrderThrottle {
+    max_orders: u32,
+    sent: HashMap<String, VecDeque<Instant>>,
+}
+
+impl OrderThrottle {
+    /// Drops the orders of `symbol` that left the window and returns those still in it.
+    fn recent(&mut self, symbol: &str, now: Instant) -> &mut VecDeque<Instant> {
+        let sent = self.sent.entry(symbol.to_string()).or_default();
+        while sent.front().is_some_and(|&at| now.saturating_duration_since(at) >= ORDER_WINDOW) {
+            sent.pop_front();
+        }
+        sent
+    }
+}
+
+/// Applies the kill switch, the open-position cap and the order throttle to each order.
+///
+/// Orders that only close or reduce a position always pass, so the engine can still get
+/// flat; they do count towards the throttle.
+pub struct SafetyGuard {
+    kill_switch_file: Option<PathBuf>,
+    max_open_positions: Option<usize>,
+    throttle: Option<OrderThrottle>,
+    trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
+    event_tx: EventBus,
+}
+
+impl SafetyGuard {
+    /// Creates a new `SafetyGuard`. A `None` limit disables that check.
+    pub fn new(
+        kill_switch_file: Option<PathBuf>,
+        max_open_positions: Option<usize>,
+        max_orders_per_hour: Option<u32>,
+        trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
+        event_tx: EventBus,
+    ) -> Self {
+        let throttle = max_orders_per_hour.map(|max_orders| OrderThrottle { max_orders, sent: HashMap::new() });
+        Self { kill_switch_file, max_open_positions, throttle, trading_enabled_flags, event_tx }
+    }
+
+    /// Returns what engages the kill switch, if anything does.
+    pub fn kill_switch(&self) -> Option<String> {
+        if let Some(path) = self.kill_switch_file.as_ref().filter(|path| path.exists()) {
+            return Some(format!("file {} exists", path.display()));
+        }
+        std::env::var_os(KILL_SWITCH_ENV)
+            .filter(|value| !value.is_empty())
+            .map(|_| format!("{} is set", KILL_SWITCH_ENV))
+    }
+
+    /// Checks `order` against every safety net at `now`, counting it towards the throttle
+    /// if it passes. A refusal is logged and broadcast; exceeding the throttle also halts
+    /// the bot.
+    pub async fn check(&mut self, order: &OrderRequest, portfolio: &Portfolio, now: Instant) -> Result<(), OrderBlock> {
+        if let Err(block) = self.evaluate(order, portfolio, now) {
+            self.log(LogLevel::Error, &format!("ORDER BLOCKED: Refused {:?} order for {} {} because {}.", order.side, order.quantity, order.symbol, block));
+            if let OrderBlock::OrderRateExceeded { symbol, .. } = &block {
+                self.trading_enabled_flags.lock().await.insert(symbol.clone(), false);
+                self.log(LogLevel::Error, &format!("BOT HALTED: Trading for {} has been disabled for exceeding max_orders_per_hour. Restart the engine to resume it.", symbol));
+            }
+            return Err(block);
+        }
+        if let Some(throttle) = &mut self.throttle {
+            throttle.recent(&order.symbol, now).push_back(now);
+        }
+        Ok(())
+    }
+
+    fn evaluate(&mut self, order: &OrderRequest, portfolio: &Portfolio, now: Instant) -> Result<(), OrderBlock> {
+        if order.reduce_only {
+            return Ok(());
+        }
+        if let Some(source) = self.kill_switch() {
+            return Err(OrderBlock::KillSwitch { source });
+        }
+        let open = portfolio.positions.len();
+        if let Some(max) = self.max_open_positions
+            && !portfolio.positions.contains_key(&order.symbol)
+            && open >= max
+        {
+            return Err(OrderBlock::MaxOpenPositions { open, max });
+        }
+        if let Some(throttle) = &mut self.throttle {
+            let max = throttle.max_orders;
+            if throttle.recent(&order.symbol, now).len() >= max as usize {
+                return Err(OrderBlock::OrderRateExceeded { symbol: order.symbol.clone(), max });
+            }
+        }
+        Ok(())
+    }
+
+    fn log(&self, level: LogLevel, message: &str) {
+        match level {
+            LogLevel::Info => tracing::info!("{}", message),
+            LogLevel::Warn => tracing::warn!("{}", message),
+            LogLevel::Error => tracing::error!("{}", message),
+        }
+        let _ = self.event_tx.send(WsMessage::Log(LogMessage {
+            timestamp: Utc::now(),
+            level,
+            message: message.to_string(),
+            fields: None,
+        }));
+    }
+}
@@ -1,52 +1,13 @@
 use crate::error::EngineError;
-use configuration::{Config, LiveBotConfig, MACrossoverParams, ProbReversionParams, SuperTrendParams};
-use serde_json::from_value;
-use strategies::{create_strategy, Strategy, StrategyId, StrategyError, ml_strategy::MlStrategy};
+use configuration::{Config, LiveBotConfig};
+use strategies::{create_strategy_from_params, merge_params, Strategy};
 
-/// Creates a `Strategy` instance by merging the bot-specific parameters from the
-/// live config into a temporary copy of the base configuration.
+/// Creates a `Strategy` instance by overlaying the bot-specific parameters from the
+/// live config onto the base configuration's parameters for the bot's strategy.
 pub fn create_strategy_from_live_config(
     base_config: &Config,
     bot_config: &LiveBotConfig,
 ) -> Result<Box<dyn Strategy>, EngineError> {
-    let mut temp_config = base_config.clone();
-
-    // Deserialize the JSON `Value` from the bot's config into the appropriate
-    // concrete parameter struct, then overwrite the corresponding part of our temp config.
-    match bot_config.strategy_id {
-        StrategyId::MACrossover => {
-            let params: MACrossoverParams = from_value(bot_config.params.clone())
-                .map_err(|e| EngineError::Configuration(e.to_string()))?;
-            temp_config.strategies.ma_crossover = params;
-        }
-        StrategyId::SuperTrend => {
-            let params: SuperTrendParams = from_value(bot_config.params.clone())
-                .map_err(|e| EngineError::Configuration(e.to_string()))?;
-            temp_config.strategies.super_trend = params;
-        }
-        StrategyId::MlStrategy => {
-            // For ML strategy, we need to check if the model path is provided
-            let params = &temp_config.strategies.ml_strategy;
-            if params.model_path.as_os_str().is_empty() {
-                return Err(EngineError::Configuration(
-                    "MlStrategy requires a `model_path` in config.".to_string()
-                ));
-            }
-            // Create the ML strategy directly since it doesn't use the standard parameter system
-            return Ok(Box::new(MlStrategy::new(&params.model_path, bot_config.symbol.clone())
-                .map_err(|e| EngineError::Configuration(e.to_string()))?));
-        }
-        StrategyId::ProbReversion => {
-            let params: ProbReversionParams = from_value(bot_config.params.clone())
-                .map_err(|e| EngineError::Configuration(e.to_string()))?;
-            temp_config.strategies.prob_reversion = params;
-        }
-        _ => {
-            return Err(EngineError::Configuration(
-                "Strategy not supported in live engine".to_string(),
-            ))
-        }
-    }
-
-    Ok(create_strategy(bot_config.strategy_id, &temp_config, bot_config.symbol.as_str())?)
-}
\ No newline at end of file
+    let params = merge_params(bot_config.strategy_id, base_config, &bot_config.params)?;
+    Ok(create_strategy_from_params(bot_config.strategy_id, &params, &bot_config.symbol)?)
+}
@@ -0,0 +1,126 @@
+use crate::event::MarketState;
+use chrono::Utc;
+use core_types::{OrderSide, Position};
+use events::{EventBus, PortfolioState, WsMessage};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use tokio::sync::Mutex;
+
+/// Estimates the mark price at which an isolated-margin position would be liquidated.
+///
+/// This is an approximation, not the exchange's figure: it assumes the position's margin is
+/// exactly `entry_price / leverage` per unit, a single maintenance margin rate, and no fees
+/// or funding. Liquidation happens where the position's remaining margin falls to the
+/// maintenance margin, which for a long is
+/// `entry_price * (1 - 1/leverage) / (1 - maintenance_margin_rate)` and for a short
+/// `entry_price * (1 + 1/leverage) / (1 + maintenance_margin_rate)`.
+///
+/// Returns `None` for zero leverage.
+pub fn estimated_liquidation_price(
+    side: OrderSide,
+    entry_price: Decimal,
+    leverage: u8,
+    maintenance_margin_rate: Decimal,
+) -> Option<Decimal> {
+    if leverage == 0 {
+        return None;
+    }
+    let initial_margin_rate = Decimal::ONE / Decimal::from(leverage);
+    let price = match side {
+        OrderSide::Buy => entry_price * (Decimal::ONE - initial_margin_rate) / (Decimal::ONE - maintenance_margin_rate),
+        OrderSide::Sell => entry_price * (Decimal::ONE + initial_margin_rate) / (Decimal::ONE + maintenance_margin_rate),
+    };
+    Some(price.max(Decimal::ZERO))
+}
+
+/// The leverage of each traded symbol and the maintenance margin rate, from which positions'
+/// liquidation prices are estimated.
+#[derive(Debug, Clone, Default)]
+pub struct LiquidationEstimator {
+    leverage: HashMap<String, u8>,
+    maintenance_margin_rate: Decimal,
+}
+
+impl LiquidationEstimator {
+    pub fn new(maintenance_margin_rate: Decimal) -> Self {
+        Self { leverage: HashMap::new(), maintenance_margin_rate }
+    }
+
+    /// Records the leverage `symbol` trades at. Positions in symbols without one get no estimate.
+    pub fn set_leverage(&mut self, symbol: &str, leverage: u8) {
+        self.leverage.insert(symbol.to_string(), leverage);
+    }
+
+    /// The estimated liquidation price of `position` (see `estimated_liquidation_price`).
+    pub fn estimate(&self, position: &Position) -> Option<Decimal> {
+        let leverage = *self.leverage.get(&position.symbol)?;
+        estimated_liquidation_price(position.side, position.entry_price, leverage, self.maintenance_margin_rate)
+    }
+}
+
+/// Marks a single position to `price`, refreshing its unrealized P&L, estimated liquidation
+/// price and `last_updated`.
+pub fn mark_position(position: &mut Position, price: Decimal, liquidation: &LiquidationEstimator) {
+    let pnl_per_unit = match position.side {
+        OrderSide::Buy => price - position.entry_price,
+        OrderSide::Sell => position.entry_price - price,
+    };
+    position.unrealized_pnl = pnl_per_unit * position.quantity;
+    position.estimated_liquidation_price = liquidation.estimate(position);
+    position.last_updated = Utc::now();
+}
+
+/// Marks every open position to the latest known price and builds a `PortfolioState` snapshot.
+///
+/// Each position's `unrealized_pnl`, estimated liquidation price and `last_updated` are
+/// refreshed in place. Positions with no known price keep their previous unrealized P&L.
+/// `total_value` is cash plus the unrealized P&L of all open positions.
+pub fn mark_to_market(
+    portfolio: &mut Portfolio,
+    market_states: &HashMap<String, MarketState>,
+    liquidation: &LiquidationEstimator,
+) -> PortfolioState {
+    let mut unrealized_pnl = Decimal::ZERO;
+
+    for position in portfolio.positions.values_mut() {
+        match market_states.get(&position.symbol).and_then(MarketState::latest_price) {
+            Some(price) => mark_position(position, price, liquidation),
+            None => position.estimated_liquidation_price = liquidation.estimate(position),
+        }
+        unrealized_pnl += position.unrealized_pnl;
+    }
+
+    PortfolioState {
+        timestamp: Utc::now(),
+        quote_asset: portfolio.quote_asset.clone(),
+        cash: portfolio.cash,
+        balances: portfolio.balances.clone(),
+        total_value: portfolio.cash + unrealized_pnl,
+        positions: portfolio.positions.values().cloned().collect(),
+        realized_pnl: portfolio.realized_pnl,
+        total_fees_paid: portfolio.total_fees_paid,
+    }
+}
+
+/// One tick of the periodic portfolio broadcast.
+///
+/// The lock is only tried, never awaited: if the portfolio is busy (e.g. an execution is
+/// being applied) the tick is skipped, and `false` is returned. The next tick catches up.
+pub fn try_broadcast_portfolio(
+    portfolio: &Mutex<Portfolio>,
+    market_states: &HashMap<String, MarketState>,
+    liquidation: &LiquidationEstimator,
+    event_tx: &EventBus,
+) -> bool {
+    let Ok(mut portfolio) = portfolio.try_lock() else {
+        tracing::debug!("Portfolio lock contended; skipping periodic portfolio broadcast.");
+        return false;
+    };
+    let state = mark_to_market(&mut portfolio, market_states, liquidation);
+    drop(portfolio);
+
+    // We don't care if there are no subscribers, so we ignore the error.
+    let _ = event_tx.send(WsMessage::PortfolioState(state));
+    true
+}
@@ -0,0 +1,259 @@
+//! The dead-man's switch: watches the market data feed and acts when it goes silent.
+//!
+//! A `FeedWatchdog` tracks when data last arrived for each symbol and reports when a feed
+//! has been silent for too long. The `DeadMansSwitch` turns those reports into alerts and,
+//! if enabled, flattens every open position and halts all bots, since the engine can no
+//! longer see the market it is exposed to.
+
+use crate::event::MarketState;
+use chrono::Utc;
+use core_types::{Execution, Interval, Kline, OrderRequest, OrderType, PositionFill};
+use events::{EventBus, LogLevel, LogMessage, WsMessage};
+use executor::{Executor, Portfolio};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// The default feed timeout: three times the smallest of the given bot intervals. Basing it
+/// on the interval keeps quiet stretches on large intervals from tripping the watchdog.
+pub fn default_feed_timeout<'a>(intervals: impl IntoIterator<Item = &'a str>) -> Option<Duration> {
+    intervals
+        .into_iter()
+        .map(|interval| interval.parse::<Interval>().ok().map(Interval::duration))
+        .collect::<Option<Vec<_>>>()?
+        .into_iter()
+        .min()
+        .map(|interval| interval * 3)
+}
+
+/// An escalation reported by `FeedWatchdog::check`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FeedAlarm {
+    /// No data has arrived for `symbol` within the feed timeout.
+    Silent { symbol: String, silent_for: Duration },
+    /// `symbol` stayed silent for the flatten grace period after its `Silent` alarm.
+    Flatten { symbol: String, silent_for: Duration },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stage {
+    Healthy,
+    Alerted,
+    Flattened,
+}
+
+#[derive(Debug)]
+struct FeedStatus {
+    last_seen: Instant,
+    stage: Stage,
+}
+
+/// Tracks the last time data arrived for each symbol and escalates when a feed goes silent.
+///
+/// Each silent episode raises `Silent` once and then `Flatten` once. Data arriving for the
+/// symbol ends the episode and re-arms the watchdog.
+#[derive(Debug)]
+pub struct FeedWatchdog {
+    feed_timeout: Duration,
+    flatten_after: Duration,
+    feeds: BTreeMap<String, FeedStatus>,
+}
+
+impl FeedWatchdog {
+    /// Creates a watchdog for `symbols`, all counted as having just received data at `now`.
+    pub fn new(
+        symbols: impl IntoIterator<Item = String>,
+        feed_timeout: Duration,
+        flatten_after: Duration,
+        now: Instant,
+    ) -> Self {
+        let feeds = symbols
+            .into_iter()
+            .map(|symbol| (symbol, FeedStatus { last_seen: now, stage: Stage::Healthy }))
+            .collect();
+        Self { feed_timeout, flatten_after, feeds }
+    }
+
+    /// Records that data arrived for `symbol`. Returns `true` if this ends a silent episode.
+    pub fn record(&mut self, symbol: &str, now: Instant) -> bool {
+        let Some(feed) = self.feeds.get_mut(symbol) else {
+            return false;
+        };
+        feed.last_seen = now;
+        std::mem::replace(&mut feed.stage, Stage::Healthy) != Stage::Healthy
+    }
+
+    /// Returns the escalations due at `now`, in symbol order.
+    pub fn check(&mut self, now: Instant) -> Vec<FeedAlarm> {
+        let mut alarms = Vec::new();
+        for (symbol, feed) in &mut self.feeds {
+            let silent_for = now.saturating_duration_since(feed.last_seen);
+            match feed.stage {
+                Stage::Healthy if silent_for > self.feed_timeout => {
+                    feed.stage = Stage::Alerted;
+                    alarms.push(FeedAlarm::Silent { symbol: symbol.clone(), silent_for });
+                }
+                Stage::Alerted if silent_for > self.feed_timeout + self.flatten_after => {
+                    feed.stage = Stage::Flattened;
+                    alarms.push(FeedAlarm::Flatten { symbol: symbol.clone(), silent_for });
+                }
+                _ => {}
+            }
+        }
+        alarms
+    }
+}
+
+/// Acts on the alarms of a `FeedWatchdog`: alerts on a silent feed and, when enabled,
+/// flattens all open positions through the executor and halts all bots.
+pub struct DeadMansSwitch {
+    watchdog: FeedWatchdog,
+    /// When false, the switch only alerts.
+    flatten_enabled: bool,
+    portfolio: Arc<Mutex<Portfolio>>,
+    executor: Arc<dyn Executor>,
+    trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
+    event_tx: EventBus,
+}
+
+impl DeadMansSwitch {
+    /// Creates a new `DeadMansSwitch` around a watchdog and the engine's shared components.
+    pub fn new(
+        watchdog: FeedWatchdog,
+        flatten_enabled: bool,
+        portfolio: Arc<Mutex<Portfolio>>,
+        executor: Arc<dyn Executor>,
+        trading_enabled_flags: Arc<Mutex<HashMap<String, bool>>>,
+        event_tx: EventBus,
+    ) -> Self {
+        Self { watchdog, flatten_enabled, portfolio, executor, trading_enabled_flags, event_tx }
+    }
+
+    /// Records that market data arrived for `symbol`, re-arming the switch if it had gone silent.
+    pub fn on_data(&mut self, symbol: &str, now: Instant) {
+        if self.watchdog.record(symbol, now) {
+            self.log(LogLevel::Info, &format!("DATA FEED RESUMED: Market data for {} is arriving again; the dead-man's switch is re-armed.", symbol));
+        }
+    }
+
+    /// Raises the escalations due at `now`. Returns the executions of any flattening orders,
+    /// each with the position fills it produced.
+    ///
+    /// Symbols reaching the flatten stage in the same check are handled together, so a feed
+    /// outage covering every symbol flattens the portfolio once.
+    pub async fn check(&mut self, now: Instant, market_states: &HashMap<String, MarketState>) -> Vec<(Execution, Vec<PositionFill>)> {
+        let mut flatten = Vec::new();
+        for alarm in self.watchdog.check(now) {
+            match alarm {
+                FeedAlarm::Silent { symbol, silent_for } => {
+                    let next_step = if self.flatten_enabled {
+                        format!("Positions will be flattened if it stays silent for another {}s.", self.watchdog.flatten_after.as_secs())
+                    } else {
+                        "The dead-man's switch is disabled, so positions will be left open.".to_string()
+                    };
+                    self.log(LogLevel::Warn, &format!("DATA FEED SILENT: No market data for {} in {}s. {}", symbol, silent_for.as_secs(), next_step));
+                }
+                FeedAlarm::Flatten { symbol, silent_for } => flatten.push(format!("{} ({}s)", symbol, silent_for.as_secs())),
+            }
+        }
+
+        if flatten.is_empty() {
+            return Vec::new();
+        }
+        let silent = flatten.join(", ");
+        if !self.flatten_enabled {
+            self.log(LogLevel::Error, &format!("CRITICAL: Data feed still silent for {}. The dead-man's switch is disabled; open positions are unmonitored.", silent));
+            return Vec::new();
+        }
+        self.log(LogLevel::Error, &format!("CRITICAL: DEAD-MAN'S SWITCH: Data feed silent for {}. Halting all bots and flattening all positions.", silent));
+        self.flatten_and_halt(market_states).await
+    }
+
+    /// Halts every bot, then closes every open position with a market order.
+    async fn flatten_and_halt(&self, market_states: &HashMap<String, MarketState>) -> Vec<(Execution, Vec<PositionFill>)> {
+        // Halt first, so no kline arriving mid-flatten can open a new position.
+        for enabled in self.trading_enabled_flags.lock().await.values_mut() {
+            *enabled = false;
+        }
+
+        let mut positions: Vec<_> = self.portfolio.lock().await.positions.values().cloned().collect();
+        positions.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+        let mut executions = Vec::new();
+        for position in positions {
+            let state = market_states.get(&position.symbol).cloned().unwrap_or_default();
+            let Some(kline) = reference_kline(&state) else {
+                self.log(LogLevel::Error, &format!("CRITICAL: Cannot flatten {}: no price has ever been received for it.", position.symbol));
+                continue;
+            };
+            let order = OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: position.symbol.clone(),
+                side: position.side.opposite(),
+                order_type: OrderType::Market,
+                quantity: position.quantity,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: true,
+                decision_id: None,
+            };
+
+            match self.executor.execute(&order, &kline, state.best_bid, state.best_ask).await {
+                Ok(execution) => {
+                    let fills = match self.portfolio.lock().await.update_with_execution(&execution) {
+                        Ok(fills) => fills,
+                        Err(e) => {
+                            self.log(LogLevel::Error, &format!("Flattened {} but failed to update the portfolio: {}", position.symbol, e));
+                            Vec::new()
+                        }
+                    };
+                    let _ = self.event_tx.send(WsMessage::TradeExecuted(execution.clone()));
+                    executions.push((execution, fills));
+                }
+                Err(e) => {
+                    self.log(LogLevel::Error, &format!("CRITICAL: Failed to flatten {}: {}", position.symbol, e));
+                }
+            }
+        }
+
+        self.log(LogLevel::Error, "ALL BOTS HALTED: The dead-man's switch fired. Restart the engine to resume trading.");
+        executions
+    }
+
+    fn log(&self, level: LogLevel, message: &str) {
+        match level {
+            LogLevel::Info => tracing::info!("{}", message),
+            LogLevel::Warn => tracing::warn!("{}", message),
+            LogLevel::Error => tracing::error!("{}", message),
+        }
+        let _ = self.event_tx.send(WsMessage::Log(LogMessage {
+            timestamp: Utc::now(),
+            level,
+            message: message.to_string(),
+            fields: None,
+        }));
+    }
+}
+
+/// The kline handed to the executor for a flattening order: the last one received, or a
+/// flat bar at the latest mark price if no kline has arrived yet.
+pub(crate) fn reference_kline(state: &MarketState) -> Option<Kline> {
+    if let Some(kline) = &state.last_kline {
+        return Some(kline.clone());
+    }
+    let price = state.latest_price()?;
+    let now = Utc::now();
+    Some(Kline {
+        open_time: now,
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume: rust_decimal::Decimal::ZERO,
+        close_time: now,
+        interval: String::new(),
+    })
+}
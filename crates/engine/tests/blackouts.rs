@@ -0,0 +1,150 @@
+//! Drops live signals inside a trading blackout, and flattens positions ahead of one, on a
+//! paused clock. The klines start on a Tuesday at 22:00 UTC, one a minute.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use chrono::{TimeZone, Utc};
+use configuration::{LiveBotConfig, LiveConfig, TradingBlackouts, Versioned};
+use core_types::{Execution, Kline, OrderSide, StrategyId};
+use engine::LiveEngine;
+use events::{EventBus, WsMessage};
+use executor::SimulatedExecutor;
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use testing::{unreachable_repo, MockAccount};
+use tokio::time::Duration;
+
+/// A one-minute kline closing at `close`, the `n`th of the series.
+fn kline(n: i64, close: Decimal) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600 + n * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::minutes(1) - chrono::Duration::milliseconds(1),
+        interval: "1m".to_string(),
+    }
+}
+
+/// Runs a BTCUSDT bot over `klines` and returns the executions it broadcast.
+async fn run_engine(klines: Vec<Kline>, trading_blackouts: TradingBlackouts) -> Vec<Execution> {
+    let mut base_config = testing::test_config(10).expect("load config");
+    base_config.trading_blackouts = trading_blackouts;
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots: vec![LiveBotConfig {
+            enabled: true,
+            symbol: "BTCUSDT".to_string(),
+            strategy_id: StrategyId::MACrossover,
+            interval: Some("1m".to_string()),
+            leverage: Some(20),
+            kline_transform: Default::default(),
+            direction: Default::default(),
+            quote_asset: None,
+            risk: None,
+            params: serde_json::json!({ "ma_fast_period": 2, "ma_slow_period": 4, "trend_filter_period": 4 }),
+        }],
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+    let executor = Arc::new(SimulatedExecutor::new(base_config.simulation.clone()));
+    let event_tx = EventBus::new(1024);
+    let mut event_rx = event_tx.subscribe();
+
+    let script = klines.into_iter().map(|kline| ScriptEvent::EmitKline { symbol: "BTCUSDT".to_string(), kline }).collect();
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(ScriptedConnector::new().with_kline_script("1m", script));
+    let mut engine = LiveEngine::new(live_config, base_config, Arc::new(MockAccount::new()), executor, unreachable_repo(), risk_manager, event_tx)
+        .without_persistence()
+        .with_connector(connector);
+    tokio::spawn(async move { engine.run().await });
+
+    // Leave time for every kline to be processed.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let mut executions = Vec::new();
+    while let Ok(message) = event_rx.try_recv() {
+        if let WsMessage::TradeExecuted(execution) = message {
+            executions.push(execution);
+        }
+    }
+    executions
+}
+
+/// Klines closing at `closes`, one a minute.
+fn klines(closes: &[i64]) -> Vec<Kline> {
+    closes.iter().enumerate().map(|(n, close)| kline(n as i64, Decimal::from(*close))).collect()
+}
+
+/// A buy crossover on the sixth kline (closing at 22:05:59.999) and a sell crossover on the
+/// ninth (22:08:59.999).
+fn up_then_down() -> Vec<Kline> {
+    klines(&[100, 100, 100, 100, 100, 110, 120, 100, 80, 60, 60])
+}
+
+fn weekly(windows: &[&str]) -> TradingBlackouts {
+    TradingBlackouts { weekly: windows.iter().map(|window| window.parse().unwrap()).collect(), ..Default::default() }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_signal_inside_a_window_is_dropped_and_one_just_after_goes_through() {
+    let klines = up_then_down();
+
+    // Without a blackout, the buy opens a long that the sell reverses.
+    let executions = run_engine(klines.clone(), TradingBlackouts::default()).await;
+    assert_eq!(executions[0].side, OrderSide::Buy);
+    assert_eq!(executions[0].timestamp, klines[5].close_time);
+
+    // The buy falls inside the window; the sell, just after it ends, goes through.
+    let executions = run_engine(klines.clone(), weekly(&["Tue 22:05-22:06"])).await;
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].side, OrderSide::Sell);
+    assert_eq!(executions[0].timestamp, klines[8].close_time);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_signal_in_the_buffer_before_a_window_is_dropped() {
+    let klines = up_then_down();
+    let blackouts = TradingBlackouts { buffer_minutes: 1, ..weekly(&["Tue 22:06-22:07"]) };
+
+    let executions = run_engine(klines.clone(), blackouts).await;
+
+    assert_eq!(executions.len(), 1);
+    assert_eq!(executions[0].side, OrderSide::Sell);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_position_is_flattened_ahead_of_a_window() {
+    // A rally the strategy never exits.
+    let klines = klines(&[100, 100, 100, 100, 100, 110, 120, 130, 140, 150, 160, 170]);
+    let blackouts = TradingBlackouts { flatten_before_blackout_minutes: Some(2), ..weekly(&["Tue 22:10-22:20"]) };
+
+    let executions = run_engine(klines.clone(), blackouts).await;
+
+    assert_eq!(executions.len(), 2);
+    assert_eq!(executions[0].side, OrderSide::Buy);
+    // Closed on the first kline closing within two minutes of the window.
+    assert_eq!(executions[1].side, OrderSide::Sell);
+    assert_eq!(executions[1].quantity, executions[0].quantity);
+    assert_eq!(executions[1].timestamp, klines[8].close_time);
+}
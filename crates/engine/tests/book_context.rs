@@ -0,0 +1,78 @@
+//! Hands strategies the book ticker updates received during the kline they evaluate.
+
+use api_client::BookTickerUpdate;
+use chrono::{Duration, TimeZone, Utc};
+use core_types::Kline;
+use engine::event::MarketState;
+use rust_decimal_macros::dec;
+
+fn kline(minute: i64) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_040 + minute * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(101),
+        low: dec!(99),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + Duration::seconds(60) - Duration::milliseconds(1),
+        interval: "1m".to_string(),
+    }
+}
+
+fn update(payload: &str) -> BookTickerUpdate {
+    serde_json::from_str(payload).unwrap()
+}
+
+#[test]
+fn book_ticker_payload_carries_its_transaction_time() {
+    let payload = r#"{"e":"bookTicker","u":400900217,"E":1568014460893,"T":1568014460891,"s":"BNBUSDT","b":"25.35190000","B":"31.21000000","a":"25.36520000","A":"40.66000000"}"#;
+    let ticker = update(payload).to_book_ticker(Utc::now());
+
+    assert_eq!(ticker.timestamp, Utc.timestamp_millis_opt(1568014460891).unwrap());
+    assert_eq!((ticker.best_bid_qty, ticker.best_ask_qty), (dec!(31.21), dec!(40.66)));
+
+    // Spot streams send no transaction time: the update is stamped when it was received.
+    let received = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+    let spot = update(r#"{"u":400900217,"s":"BNBUSDT","b":"25.35","B":"31.21","a":"25.36","A":"40.66"}"#);
+    assert_eq!(spot.to_book_ticker(received).timestamp, received);
+}
+
+#[test]
+fn the_context_summarises_the_updates_of_the_evaluated_kline() {
+    let mut state = MarketState::default();
+    assert_eq!(state.context(&kline(0)).book, None);
+
+    let quote = |bid_qty: &str, ask_qty: &str, at: chrono::DateTime<Utc>| {
+        let payload = format!(r#"{{"s":"BTCUSDT","b":"99.5","B":"{bid_qty}","a":"100.5","A":"{ask_qty}","T":{}}}"#, at.timestamp_millis());
+        update(&payload).to_book_ticker(Utc::now())
+    };
+    state.apply_book_ticker(&quote("3", "1", kline(0).open_time + Duration::seconds(10)));
+    state.apply_book_ticker(&quote("1", "1", kline(0).close_time));
+    state.apply_book_ticker(&quote("1", "3", kline(1).open_time));
+
+    let context = state.context(&kline(0));
+    assert_eq!((context.best_bid, context.best_ask), (Some(dec!(99.5)), Some(dec!(100.5))));
+    let book = context.book.expect("book summary");
+    assert_eq!(book.imbalance_samples, 2);
+    assert_eq!(book.mean_imbalance(), Some(0.25));
+    assert!((book.mean_relative_spread().unwrap() - 0.01).abs() < 1e-12);
+
+    assert_eq!(state.context(&kline(1)).book.unwrap().mean_imbalance(), Some(-0.5));
+    assert_eq!(state.context(&kline(2)).book, None);
+}
+
+#[test]
+fn orders_execute_against_quotes_from_the_kline_only() {
+    let mut state = MarketState::default();
+    assert_eq!(state.execution_quotes(&kline(0)), (None, None));
+
+    let payload = format!(r#"{{"s":"BTCUSDT","b":"99.5","B":"1","a":"100.5","A":"1","T":{}}}"#, kline(0).close_time.timestamp_millis());
+    state.apply_book_ticker(&update(&payload).to_book_ticker(Utc::now()));
+
+    assert_eq!(state.execution_quotes(&kline(0)), (Some(dec!(99.5)), Some(dec!(100.5))));
+    // For the next kline the quote is stale, and for the one before it is the quote a replay
+    // sent ahead of the next kline, so the executor falls back to its own model.
+    assert_eq!(state.execution_quotes(&kline(1)), (None, None));
+    assert_eq!(state.execution_quotes(&kline(-1)), (None, None));
+}
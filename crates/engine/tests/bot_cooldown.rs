@@ -0,0 +1,87 @@
+//! Checks that a run of losses halts a bot for `bot_cooldown_hours`, and that a cool-down too
+//! long to represent halts it for good rather than panicking.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use configuration::settings::GlobalRiskConfig;
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use engine::risk_manager::GlobalRiskManager;
+use engine::risk_state::{BotHalt, HaltReason};
+use engine::RiskState;
+use events::EventBus;
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// A manager halting a bot after two losses in a row, for `bot_cooldown_hours`.
+fn manager(bot_cooldown_hours: u64) -> GlobalRiskManager {
+    let config = GlobalRiskConfig {
+        max_daily_drawdown_pct: dec!(0.5),
+        max_consecutive_losses: 2,
+        bot_cooldown_hours,
+        max_open_positions_per_asset: 1,
+        maintenance_margin_rate: Decimal::ZERO,
+        liquidation_warning_pct: dec!(0.05),
+        dynamic_leverage: None,
+        daily_loss: None,
+    };
+    let flags = Arc::new(Mutex::new(HashMap::from([("BTCUSDT".to_string(), true)])));
+    GlobalRiskManager::new(config, Arc::new(Mutex::new(Portfolio::new(dec!(10000)))), flags, EventBus::new(64), dec!(10000), RiskState::default())
+}
+
+fn at(hour: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 18, 0, 0, 0).unwrap() + Duration::hours(hour)
+}
+
+fn execution(side: OrderSide, price: Decimal, timestamp: DateTime<Utc>) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        quantity: dec!(1),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp,
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// A BTCUSDT long bought at 100 and sold at 99 at `closed_at`.
+fn loss(closed_at: DateTime<Utc>) -> Trade {
+    Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        entry_execution: execution(OrderSide::Buy, dec!(100), closed_at - Duration::minutes(30)),
+        exit_execution: execution(OrderSide::Sell, dec!(99), closed_at),
+        close_reason: CloseReason::StopLoss,
+        initial_risk: None,
+    }
+}
+
+async fn halt_after_two_losses(manager: &GlobalRiskManager) -> Option<BotHalt> {
+    manager.on_trade_closed(&loss(at(9))).await.unwrap();
+    manager.on_trade_closed(&loss(at(10))).await.unwrap();
+    manager.risk_state().await.halts.remove("BTCUSDT")
+}
+
+#[tokio::test]
+async fn a_bot_cools_down_for_the_configured_hours() {
+    let halt = halt_after_two_losses(&manager(4)).await;
+    assert_eq!(halt, Some(BotHalt { reason: HaltReason::ConsecutiveLosses, halted_at: at(10), until: at(14) }));
+}
+
+#[tokio::test]
+async fn a_cooldown_beyond_the_last_date_never_ends() {
+    for hours in [u64::MAX, i64::MAX as u64, 1 << 50] {
+        let halt = halt_after_two_losses(&manager(hours)).await.expect("halted");
+        assert_eq!(halt.until, DateTime::<Utc>::MAX_UTC, "{} hours", hours);
+    }
+}
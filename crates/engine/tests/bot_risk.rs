@@ -0,0 +1,150 @@
+//! Bots overriding `[risk_management]` in their `[bot.risk]` block size their signals by
+//! their own settings, while the other bots keep the global ones.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use chrono::{TimeZone, Utc};
+use configuration::{LiveBotConfig, LiveConfig, RiskOverrides, Versioned};
+use core_types::{Execution, Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent, StrategyId};
+use engine::{LiveEngine, StrategyFactory};
+use events::{EventBus, WsMessage};
+use executor::SimulatedExecutor;
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use strategies::{Strategy, StrategyError};
+use testing::{unreachable_repo, MockAccount};
+use tokio::time::Duration;
+use uuid::Uuid;
+
+/// Opens a long on the first kline it sees.
+struct BuyOnce {
+    symbol: String,
+    bought: bool,
+}
+
+impl Strategy for BuyOnce {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        if std::mem::replace(&mut self.bought, true) {
+            return Ok(None);
+        }
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: Some(SignalIntent::OpenLong),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: self.symbol.clone(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// The first one-minute kline, closing at 100.
+fn kline() -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(100),
+        low: dec!(100),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::minutes(1) - chrono::Duration::milliseconds(1),
+        interval: "1m".to_string(),
+    }
+}
+
+fn bot(symbol: &str, risk: Option<RiskOverrides>) -> LiveBotConfig {
+    LiveBotConfig {
+        enabled: true,
+        symbol: symbol.to_string(),
+        strategy_id: StrategyId::MACrossover,
+        interval: Some("1m".to_string()),
+        leverage: Some(20),
+        kline_transform: Default::default(),
+        direction: Default::default(),
+        quote_asset: None,
+        risk,
+        params: serde_json::json!({}),
+    }
+}
+
+/// Runs `bots`, each buying on the same kline at 100, and returns the executions by symbol.
+async fn run_engine(bots: Vec<LiveBotConfig>) -> HashMap<String, Execution> {
+    let mut base_config = testing::test_config(10).expect("load config");
+    base_config.risk_management.risk_per_trade_pct = dec!(0.01);
+    base_config.risk_management.stop_loss_pct = dec!(0.05);
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots,
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+    let executor = Arc::new(SimulatedExecutor::new(base_config.simulation.clone()));
+    let event_tx = EventBus::new(1024);
+    let mut event_rx = event_tx.subscribe();
+
+    let script = live_config.bots.iter().map(|bot| ScriptEvent::EmitKline { symbol: bot.symbol.clone(), kline: kline() }).collect();
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(ScriptedConnector::new().with_kline_script("1m", script));
+    let factory: StrategyFactory =
+        Arc::new(|_, bot_config: &LiveBotConfig| Ok(Box::new(BuyOnce { symbol: bot_config.symbol.clone(), bought: false }) as Box<dyn Strategy>));
+    let mut engine = LiveEngine::new(live_config, base_config, Arc::new(MockAccount::new().with_symbols(&["BTCUSDT", "ETHUSDT"])), executor, unreachable_repo(), risk_manager, event_tx)
+        .without_persistence()
+        .with_connector(connector)
+        .with_strategy_factory(factory);
+    tokio::spawn(async move { engine.run().await });
+
+    // Leave time for every kline to be processed.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let mut executions = HashMap::new();
+    while let Ok(message) = event_rx.try_recv() {
+        if let WsMessage::TradeExecuted(execution) = message {
+            executions.insert(execution.symbol.clone(), execution);
+        }
+    }
+    executions
+}
+
+#[tokio::test(start_paused = true)]
+async fn bots_size_the_same_signal_by_their_own_risk() {
+    let global = run_engine(vec![bot("BTCUSDT", None), bot("ETHUSDT", None)]).await;
+    // Half the risk over a fifth of the stop distance: two and a half times the position.
+    let tight = RiskOverrides { risk_per_trade_pct: Some(dec!(0.005)), stop_loss_pct: Some(dec!(0.01)), ..Default::default() };
+    let overridden = run_engine(vec![bot("BTCUSDT", None), bot("ETHUSDT", Some(tight))]).await;
+
+    // The bot without overrides sizes as before; the other sizes by its own settings.
+    assert!(global["BTCUSDT"].quantity > Decimal::ZERO);
+    assert_eq!(overridden["BTCUSDT"].quantity, global["BTCUSDT"].quantity);
+    let (before, after) = (global["ETHUSDT"].quantity, overridden["ETHUSDT"].quantity);
+    assert_eq!(after / before, dec!(2.5), "{} against {}", after, before);
+}
@@ -0,0 +1,141 @@
+//! Runs two bots on the same symbol at different intervals and checks that each strategy is
+//! only evaluated on the bars of its own interval.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use chrono::{DateTime, TimeZone, Utc};
+use configuration::{LiveBotConfig, LiveConfig, Versioned};
+use core_types::{Kline, Signal, StrategyId};
+use engine::{LiveEngine, StrategyFactory};
+use events::EventBus;
+use risk::SimpleRiskManager;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use strategies::{Strategy, StrategyError};
+use testing::{unreachable_repo, MockAccount, NoOrders};
+use tokio::time::Duration;
+
+/// The `(interval, close_time)` of every bar each bot's strategy evaluated, by the bot's interval.
+type Seen = Arc<Mutex<HashMap<String, Vec<(String, DateTime<Utc>)>>>>;
+
+/// Records the bars it evaluates and never signals.
+struct Recorder {
+    interval: String,
+    seen: Seen,
+}
+
+impl Strategy for Recorder {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let mut seen = self.seen.lock().unwrap();
+        seen.entry(self.interval.clone()).or_default().push((kline.interval.clone(), kline.close_time));
+        Ok(None)
+    }
+}
+
+fn kline(interval: &str, minutes: i64, n: i64) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600 + n * minutes * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(101),
+        low: dec!(99),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::minutes(minutes) - chrono::Duration::milliseconds(1),
+        interval: interval.to_string(),
+    }
+}
+
+fn emit(kline: Kline) -> ScriptEvent {
+    ScriptEvent::EmitKline { symbol: "BTCUSDT".to_string(), kline }
+}
+
+fn bot(interval: &str) -> LiveBotConfig {
+    LiveBotConfig {
+        enabled: true,
+        symbol: "BTCUSDT".to_string(),
+        strategy_id: StrategyId::MACrossover,
+        interval: Some(interval.to_string()),
+        leverage: Some(5),
+        kline_transform: Default::default(),
+        direction: Default::default(),
+        quote_asset: None,
+        risk: None,
+        params: serde_json::json!({}),
+    }
+}
+
+/// Starts an engine running `bots` on `connector` with recording strategies, returning what
+/// they see.
+fn start_engine(bots: Vec<LiveBotConfig>, connector: ScriptedConnector) -> Seen {
+    let base_config = testing::test_config(10).expect("load config");
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots,
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+
+    let seen = Seen::default();
+    let recorded = seen.clone();
+    let factory: StrategyFactory = Arc::new(move |_, bot_config: &LiveBotConfig| {
+        let interval = bot_config.interval.clone().expect("every bot sets its interval");
+        Ok(Box::new(Recorder { interval, seen: recorded.clone() }) as Box<dyn Strategy>)
+    });
+
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(connector);
+    let mut engine =
+        LiveEngine::new(live_config, base_config, Arc::new(MockAccount::new()), Arc::new(NoOrders), unreachable_repo(), risk_manager, EventBus::new(1024))
+            .without_persistence()
+            .with_connector(connector)
+            .with_strategy_factory(factory);
+    tokio::spawn(async move { engine.run().await });
+    seen
+}
+
+#[tokio::test(start_paused = true)]
+async fn each_bot_of_a_symbol_only_evaluates_the_bars_of_its_interval() {
+    let quarter = |n| kline("15m", 15, n);
+    let hour = |n| kline("1h", 60, n);
+    // A combined stream carries every interval of the symbol, interleaved by close time, and
+    // a bar of an interval no bot trades.
+    let connector = ScriptedConnector::new().with_kline_script(
+        "15m",
+        vec![
+            emit(quarter(0)),
+            emit(quarter(1)),
+            emit(quarter(2)),
+            emit(quarter(3)),
+            emit(hour(0)),
+            emit(kline("4h", 240, 0)),
+            emit(quarter(4)),
+        ],
+    );
+    let seen = start_engine(vec![bot("15m"), bot("1h")], connector);
+
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    let seen = seen.lock().unwrap();
+    let expected_quarters: Vec<_> = (0..5).map(|n| ("15m".to_string(), quarter(n).close_time)).collect();
+    assert_eq!(seen["15m"], expected_quarters);
+    // The hourly bar closes before the last quarter, yet is not dropped as late.
+    assert_eq!(seen["1h"], [("1h".to_string(), hour(0).close_time)]);
+    assert_eq!(seen.len(), 2);
+}
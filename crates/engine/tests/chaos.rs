@@ -0,0 +1,212 @@
+//! Runs the live engine against scripted market data feeds that drop, replay and reorder
+//! klines, on a paused clock.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use chrono::{DateTime, TimeZone, Utc};
+use configuration::{LiveBotConfig, LiveConfig, Versioned};
+use core_types::{Kline, StrategyId};
+use engine::LiveEngine;
+use events::{EventBus, EventSubscriber, WsMessage};
+use risk::SimpleRiskManager;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use testing::{unreachable_repo, MockAccount, NoOrders};
+use tokio::time::Duration;
+
+fn kline(interval: &str, minutes: i64, n: i64) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600 + n * minutes * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(101),
+        low: dec!(99),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::minutes(minutes) - chrono::Duration::milliseconds(1),
+        interval: interval.to_string(),
+    }
+}
+
+fn emit(symbol: &str, kline: Kline) -> ScriptEvent {
+    ScriptEvent::EmitKline { symbol: symbol.to_string(), kline }
+}
+
+/// Lets the broadcast of the klines processed so far reach the test before the next one: the
+/// bus only keeps the latest kline of each symbol.
+fn settle() -> ScriptEvent {
+    ScriptEvent::Wait { duration: Duration::from_secs(1) }
+}
+
+fn bot(symbol: &str, interval: &str) -> LiveBotConfig {
+    LiveBotConfig {
+        enabled: true,
+        symbol: symbol.to_string(),
+        strategy_id: StrategyId::MACrossover,
+        interval: Some(interval.to_string()),
+        leverage: Some(5),
+        kline_transform: Default::default(),
+        direction: Default::default(),
+        quote_asset: None,
+        risk: None,
+        params: serde_json::json!({}),
+    }
+}
+
+/// Starts an engine running `bots` on `connector` and returns the receiver of its broadcasts.
+fn start_engine(bots: Vec<LiveBotConfig>, connector: ScriptedConnector) -> EventSubscriber {
+    let base_config = testing::test_config(10).expect("load config");
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: true,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots,
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+    let event_tx = EventBus::new(1024);
+    let event_rx = event_tx.subscribe();
+
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(connector);
+    let mut engine = LiveEngine::new(live_config, base_config, Arc::new(MockAccount::new().with_symbols(&["BTCUSDT", "ETHUSDT"])), Arc::new(NoOrders), unreachable_repo(), risk_manager, event_tx)
+        .without_persistence()
+        .with_connector(connector);
+    tokio::spawn(async move { engine.run().await });
+    event_rx
+}
+
+/// The `(symbol, close_time)` of every kline the engine processed within `within`.
+async fn processed_klines(rx: &mut EventSubscriber, within: Duration) -> Vec<(String, DateTime<Utc>)> {
+    let mut processed = Vec::new();
+    let _ = tokio::time::timeout(within, async {
+        loop {
+            if let Ok(WsMessage::KlineData(data)) = rx.recv().await {
+                processed.push((data.symbol, data.kline.close_time));
+            }
+        }
+    })
+    .await;
+    processed
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_disconnect_mid_stream_processes_each_kline_exactly_once() {
+    let k = |n| kline("1m", 1, n);
+    let connector = ScriptedConnector::new().with_kline_script(
+        "1m",
+        vec![
+            emit("BTCUSDT", k(0)),
+            settle(),
+            emit("BTCUSDT", k(1)),
+            ScriptEvent::Disconnect { duration: Duration::from_secs(30) },
+            // On reconnect the exchange sends the last closed kline again.
+            ScriptEvent::EmitDuplicate,
+            emit("BTCUSDT", k(2)),
+            ScriptEvent::EmitDuplicate,
+            settle(),
+            emit("BTCUSDT", k(3)),
+        ],
+    );
+    let mut rx = start_engine(vec![bot("BTCUSDT", "1m")], connector);
+
+    let processed = processed_klines(&mut rx, Duration::from_secs(120)).await;
+    let expected: Vec<_> = (0..4).map(|n| ("BTCUSDT".to_string(), k(n).close_time)).collect();
+    assert_eq!(processed, expected);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_kline_delivered_late_is_dropped() {
+    let k = |n| kline("1m", 1, n);
+    let connector = ScriptedConnector::new().with_kline_script(
+        "1m",
+        vec![
+            emit("BTCUSDT", k(0)),
+            settle(),
+            ScriptEvent::Reorder,
+            emit("BTCUSDT", k(1)),
+            emit("BTCUSDT", k(2)),
+            settle(),
+            emit("BTCUSDT", k(3)),
+        ],
+    );
+    let mut rx = start_engine(vec![bot("BTCUSDT", "1m")], connector);
+
+    // The feed delivers 0, 2, 1, 3: the late kline is not fed to the strategy after a newer one.
+    let processed = processed_klines(&mut rx, Duration::from_secs(120)).await;
+    let expected: Vec<_> = [0, 2, 3].into_iter().map(|n| ("BTCUSDT".to_string(), k(n).close_time)).collect();
+    assert_eq!(processed, expected);
+}
+
+#[tokio::test(start_paused = true)]
+async fn bots_on_other_intervals_keep_trading_while_one_stream_is_down() {
+    let btc = |n| kline("1m", 1, n);
+    let eth = |n| kline("5m", 5, n);
+    let quiet = || ScriptEvent::Wait { duration: Duration::from_secs(10) };
+    let connector = ScriptedConnector::new()
+        .with_kline_script(
+            "1m",
+            vec![emit("BTCUSDT", btc(0)), ScriptEvent::Disconnect { duration: Duration::from_secs(45) }, emit("BTCUSDT", btc(1))],
+        )
+        .with_kline_script(
+            "5m",
+            vec![quiet(), emit("ETHUSDT", eth(0)), quiet(), emit("ETHUSDT", eth(1)), quiet(), emit("ETHUSDT", eth(2)), quiet(), quiet(), emit("ETHUSDT", eth(3))],
+        );
+    let mut rx = start_engine(vec![bot("BTCUSDT", "1m"), bot("ETHUSDT", "5m")], connector);
+
+    let processed = processed_klines(&mut rx, Duration::from_secs(120)).await;
+    let symbols: Vec<&str> = processed.iter().map(|(symbol, _)| symbol.as_str()).collect();
+    // ETHUSDT's klines at 10s, 20s and 30s arrive while BTCUSDT's stream is down; both
+    // streams carry on after it reconnects at 45s.
+    assert_eq!(symbols, ["BTCUSDT", "ETHUSDT", "ETHUSDT", "ETHUSDT", "BTCUSDT", "ETHUSDT"]);
+    assert_eq!(processed[4].1, btc(1).close_time);
+    assert_eq!(processed[5].1, eth(3).close_time);
+}
+
+#[tokio::test(start_paused = true)]
+async fn broadcast_klines_are_numbered_per_symbol_across_a_reconnect() {
+    let btc = |n| kline("1m", 1, n);
+    let eth = |n| kline("5m", 5, n);
+    let connector = ScriptedConnector::new()
+        .with_kline_script(
+            "1m",
+            vec![
+                emit("BTCUSDT", btc(0)),
+                settle(),
+                emit("BTCUSDT", btc(1)),
+                ScriptEvent::Disconnect { duration: Duration::from_secs(30) },
+                ScriptEvent::EmitDuplicate,
+                emit("BTCUSDT", btc(2)),
+            ],
+        )
+        .with_kline_script("5m", vec![settle(), emit("ETHUSDT", eth(0)), settle(), emit("ETHUSDT", eth(1))]);
+    let mut rx = start_engine(vec![bot("BTCUSDT", "1m"), bot("ETHUSDT", "5m")], connector);
+
+    let mut sequences: Vec<(String, u64)> = Vec::new();
+    let _ = tokio::time::timeout(Duration::from_secs(120), async {
+        loop {
+            if let Ok(WsMessage::KlineData(data)) = rx.recv().await {
+                sequences.push((data.symbol, data.seq));
+            }
+        }
+    })
+    .await;
+    let of = |symbol: &str| sequences.iter().filter(|(s, _)| s == symbol).map(|(_, seq)| *seq).collect::<Vec<_>>();
+    // The duplicate after the reconnect is not broadcast, so it takes no number.
+    assert_eq!(of("BTCUSDT"), [1, 2, 3]);
+    assert_eq!(of("ETHUSDT"), [1, 2]);
+}
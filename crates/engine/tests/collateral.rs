@@ -0,0 +1,115 @@
+//! Values accounts holding several collateral assets.
+
+use api_client::{BalanceResponse, PositionResponse};
+use chrono::Utc;
+use core_types::{CorrectionSource, Execution, OrderSide};
+use engine::collateral::fetch_collateral;
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::mock_account::balance;
+use testing::MockAccount;
+use uuid::Uuid;
+
+/// An exchange that quotes USDCUSDT and has no BNB pair.
+fn quotes() -> MockAccount {
+    MockAccount::new().with_mark_price("USDCUSDT", dec!(0.9998))
+}
+
+fn assets(names: &[&str]) -> Vec<String> {
+    names.iter().map(|name| name.to_string()).collect()
+}
+
+#[tokio::test]
+async fn a_mixed_usdt_and_usdc_account_sums_to_its_usdt_value() {
+    let balances = [balance("USDT", dec!(1000)), balance("USDC", dec!(500))];
+    let collateral = fetch_collateral(&quotes(), &balances, &assets(&["USDT", "USDC"]), "USDT").await;
+
+    let mut portfolio = Portfolio::new(Decimal::ZERO);
+    let unpriced = collateral.apply(&mut portfolio, CorrectionSource::StartupSync);
+
+    assert!(unpriced.is_empty());
+    assert_eq!(portfolio.cash, dec!(1499.9));
+    assert_eq!(portfolio.balances.get("USDT"), Some(&dec!(1000)));
+    assert_eq!(portfolio.balances.get("USDC"), Some(&dec!(500)));
+}
+
+#[tokio::test]
+async fn a_usdc_only_account_has_equity() {
+    let balances = [balance("USDC", dec!(2000))];
+    let collateral = fetch_collateral(&quotes(), &balances, &assets(&["USDT", "USDC"]), "USDT").await;
+
+    let mut portfolio = Portfolio::new(Decimal::ZERO);
+    collateral.apply(&mut portfolio, CorrectionSource::StartupSync);
+
+    assert_eq!(portfolio.cash, dec!(1999.6));
+}
+
+#[tokio::test]
+async fn an_asset_without_a_usdt_pair_is_left_out_of_equity() {
+    let balances = [balance("USDT", dec!(1000)), balance("BNB", dec!(3))];
+    let collateral = fetch_collateral(&quotes(), &balances, &assets(&["USDT", "BNB"]), "USDT").await;
+
+    let mut portfolio = Portfolio::new(Decimal::ZERO);
+    let unpriced = collateral.apply(&mut portfolio, CorrectionSource::StartupSync);
+
+    assert_eq!(unpriced, ["BNB"]);
+    assert_eq!(portfolio.cash, dec!(1000));
+    // The balance is still reported, just not counted.
+    assert_eq!(portfolio.balances.get("BNB"), Some(&dec!(3)));
+}
+
+#[tokio::test]
+async fn assets_not_accepted_as_collateral_are_ignored() {
+    let balances = [balance("USDT", dec!(1000)), balance("USDC", dec!(500)), balance("ETH", dec!(1))];
+    let collateral = fetch_collateral(&quotes(), &balances, &assets(&["USDT"]), "USDT").await;
+
+    assert_eq!(collateral.balances.keys().collect::<Vec<_>>(), ["USDT"]);
+    assert!(collateral.prices.is_empty());
+}
+
+#[tokio::test]
+async fn the_margin_left_free_is_counted_once_from_the_synced_wallet() {
+    // 2 ETH at 1,000 at 5x hold 400 of the 1,000 USDT wallet; the exchange has 600 available.
+    let balances = [BalanceResponse { available_balance: dec!(600), ..balance("USDT", dec!(1000)) }];
+    let collateral = fetch_collateral(&quotes(), &balances, &assets(&["USDT"]), "USDT").await;
+    let mut portfolio = Portfolio::new(Decimal::ZERO);
+    collateral.apply(&mut portfolio, CorrectionSource::StartupSync);
+    portfolio.sync_positions(
+        &[PositionResponse {
+            entry_price: dec!(1000),
+            leverage: "5".to_string(),
+            max_notional_value: "1000000".to_string(),
+            liquidation_price: Decimal::ZERO,
+            mark_price: dec!(1000),
+            position_amt: dec!(2),
+            symbol: "ETHUSDT".to_string(),
+            un_realized_profit: Decimal::ZERO,
+        }],
+        CorrectionSource::StartupSync,
+    );
+    let leverage = |symbol: &str| Some(if symbol == "ETHUSDT" { 5 } else { 10 });
+
+    assert_eq!(portfolio.cash, dec!(600));
+    assert_eq!(risk::free_margin(portfolio.wallet_balance(), portfolio.positions.values(), leverage), dec!(600));
+
+    // Before the next sync, a 300 entry at 10x posts 30 more, and its fee is paid.
+    portfolio
+        .update_with_execution(&Execution {
+            execution_id: Uuid::new_v4(),
+            client_order_id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            side: OrderSide::Buy,
+            price: dec!(30000),
+            quantity: dec!(0.01),
+            fee: dec!(0.12),
+            fee_asset: "USDT".to_string(),
+            timestamp: Utc::now(),
+            decision_id: None,
+            is_maker: false,
+            spread_cost: Decimal::ZERO,
+            placement: None,
+        })
+        .unwrap();
+    assert_eq!(risk::free_margin(portfolio.wallet_balance(), portfolio.positions.values(), leverage), dec!(569.88));
+}
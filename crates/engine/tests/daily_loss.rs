@@ -0,0 +1,203 @@
+//! Checks that the global risk manager alerts on the daily loss limits, halts bots on a
+//! breach until the next rollover, and resumes the trading day from the recorded fills
+//! after a restart.
+
+use chrono::{DateTime, TimeZone, Utc};
+use configuration::settings::GlobalRiskConfig;
+use configuration::{DailyLossLimit, DailyLossLimits};
+use core_types::{OrderSide, Position};
+use database::LiveFill;
+use engine::risk_manager::GlobalRiskManager;
+use engine::RiskState;
+use events::{EventBus, EventSubscriber, LogLevel, PortfolioState, WsMessage};
+use executor::Portfolio;
+use risk::LossLevel;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+type Flags = Arc<Mutex<HashMap<String, bool>>>;
+
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, day, hour, minute, 0).unwrap()
+}
+
+/// Days start at 00:00 UTC. The account may lose 500 a day, SOLUSDT 200. Bots trade SOLUSDT
+/// and BTCUSDT.
+fn manager(event_tx: EventBus) -> (GlobalRiskManager, Flags) {
+    manager_rolling_over_at(event_tx, 0)
+}
+
+/// The same, with days starting at `rollover_hour_utc`.
+fn manager_rolling_over_at(event_tx: EventBus, rollover_hour_utc: u32) -> (GlobalRiskManager, Flags) {
+    let config = GlobalRiskConfig {
+        max_daily_drawdown_pct: dec!(0.5),
+        max_consecutive_losses: 7,
+        bot_cooldown_hours: 4,
+        max_open_positions_per_asset: 1,
+        maintenance_margin_rate: Decimal::ZERO,
+        liquidation_warning_pct: dec!(0.05),
+        dynamic_leverage: None,
+        daily_loss: Some(DailyLossLimits {
+            rollover_hour_utc,
+            max_daily_loss_abs: Some(dec!(500)),
+            max_daily_loss_pct: None,
+            symbols: [("SOLUSDT".to_string(), DailyLossLimit { max_daily_loss_abs: Some(dec!(200)), max_daily_loss_pct: None })].into(),
+        }),
+    };
+    let flags: Flags = Arc::new(Mutex::new([("SOLUSDT".to_string(), true), ("BTCUSDT".to_string(), true)].into()));
+    let manager = GlobalRiskManager::new(config, Arc::new(Mutex::new(Portfolio::new(dec!(10000)))), Arc::clone(&flags), event_tx, dec!(10000), RiskState::default());
+    (manager, flags)
+}
+
+/// A 10,000 portfolio at `timestamp`, with a SOLUSDT long carrying `unrealized` if any.
+fn state(timestamp: DateTime<Utc>, unrealized: Option<Decimal>) -> PortfolioState {
+    let positions: Vec<Position> = unrealized
+        .map(|unrealized_pnl| Position {
+            position_id: Uuid::new_v4(),
+            symbol: "SOLUSDT".to_string(),
+            side: OrderSide::Buy,
+            quantity: dec!(10),
+            entry_price: dec!(150),
+            unrealized_pnl,
+            last_updated: timestamp,
+            adds: 0,
+            last_entry_price: dec!(150),
+            estimated_liquidation_price: None,
+        })
+        .into_iter()
+        .collect();
+    PortfolioState {
+        timestamp,
+        quote_asset: "USDT".to_string(),
+        cash: dec!(10000),
+        balances: Default::default(),
+        total_value: dec!(10000) + unrealized.unwrap_or_default(),
+        positions,
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    }
+}
+
+/// A closing fill on `symbol` at `executed_at` realizing `realized_pnl` for a fee of 1.
+fn fill(symbol: &str, realized_pnl: Decimal, executed_at: DateTime<Utc>) -> LiveFill {
+    LiveFill {
+        execution_id: Uuid::new_v4(),
+        position_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        position_side: OrderSide::Buy,
+        is_entry: false,
+        price: dec!(100),
+        quantity: dec!(1),
+        fee: dec!(1),
+        fee_asset: "USDT".to_string(),
+        is_maker: false,
+        realized_pnl,
+        close_reason: None,
+        executed_at,
+    }
+}
+
+fn logs(rx: &mut EventSubscriber) -> Vec<(LogLevel, String)> {
+    let mut logs = Vec::new();
+    while let Ok(message) = rx.try_recv() {
+        match message {
+            WsMessage::Log(log) => logs.push((log.level, log.message)),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+    logs
+}
+
+async fn enabled(flags: &Flags, symbol: &str) -> bool {
+    flags.lock().await.get(symbol).copied().unwrap_or(false)
+}
+
+#[tokio::test]
+async fn a_symbol_breach_halts_its_bot_until_the_rollover() {
+    let event_tx = EventBus::new(32);
+    let mut rx = event_tx.subscribe();
+    let (manager, flags) = manager(event_tx);
+    let tracker = manager.daily_loss().expect("a daily loss tracker");
+
+    tracker.lock().await.record_fill("SOLUSDT", dec!(-100), at(2, 21, 0));
+    let alerts = manager.check_daily_loss(&state(at(2, 21, 1), Some(dec!(-70)))).await;
+    assert_eq!(alerts.iter().map(|alert| alert.level).collect::<Vec<_>>(), [LossLevel::Warning]);
+    assert_eq!(
+        logs(&mut rx),
+        [(LogLevel::Warn, "DAILY LOSS: SOLUSDT has lost 170 today, 85% of its daily loss limit of 200.".to_string())]
+    );
+
+    tracker.lock().await.record_fill("SOLUSDT", dec!(-110), at(2, 23, 50));
+    let alerts = manager.check_daily_loss(&state(at(2, 23, 55), None)).await;
+    assert_eq!(alerts.iter().map(|alert| alert.level).collect::<Vec<_>>(), [LossLevel::Breach]);
+    let messages = logs(&mut rx);
+    assert_eq!(messages[0].0, LogLevel::Error);
+    assert_eq!(messages[0].1, "CRITICAL: SOLUSDT has lost 210 today, breaching its daily loss limit of 200. Halting until 2025-03-03 00:00 UTC.");
+    assert!(!enabled(&flags, "SOLUSDT").await);
+    assert!(enabled(&flags, "BTCUSDT").await);
+
+    // Still halted right before the rollover, and not alerted again.
+    assert!(manager.check_daily_loss(&state(at(2, 23, 59), None)).await.is_empty());
+    assert!(!enabled(&flags, "SOLUSDT").await);
+    assert!(logs(&mut rx).is_empty());
+
+    // The first check of the new day re-enables the bot.
+    assert!(manager.check_daily_loss(&state(at(3, 0, 0), None)).await.is_empty());
+    assert!(enabled(&flags, "SOLUSDT").await);
+    let messages = logs(&mut rx);
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].1.starts_with("BOT RE-ENABLED: A new trading day started; trading for SOLUSDT"), "{:?}", messages);
+}
+
+#[tokio::test]
+async fn an_account_breach_halts_every_bot() {
+    let event_tx = EventBus::new(32);
+    let (manager, flags) = manager(event_tx);
+    let tracker = manager.daily_loss().expect("a daily loss tracker");
+
+    tracker.lock().await.record_fill("BTCUSDT", dec!(-450), at(2, 12, 0));
+    let alerts = manager.check_daily_loss(&state(at(2, 12, 1), Some(dec!(-60)))).await;
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].level, LossLevel::Breach);
+    assert!(!enabled(&flags, "SOLUSDT").await);
+    assert!(!enabled(&flags, "BTCUSDT").await);
+
+    // A fill after the rollover starts the new day, whose first check lifts the halt.
+    tracker.lock().await.record_fill("BTCUSDT", dec!(5), at(3, 0, 30));
+    assert!(manager.check_daily_loss(&state(at(3, 0, 31), None)).await.is_empty());
+    assert!(enabled(&flags, "SOLUSDT").await);
+    assert!(enabled(&flags, "BTCUSDT").await);
+}
+
+#[tokio::test]
+async fn a_restart_resumes_the_day_from_its_recorded_fills() {
+    let event_tx = EventBus::new(32);
+    let (manager, flags) = manager(event_tx);
+    let now = at(2, 9, 0);
+    let day_start = manager.trading_day_start(now).expect("daily loss limits");
+    assert_eq!(day_start, at(2, 0, 0));
+
+    // The fill before the rollover belongs to the previous day.
+    let fills = [fill("SOLUSDT", dec!(-300), at(1, 23, 59)), fill("SOLUSDT", dec!(-120), at(2, 0, 5)), fill("SOLUSDT", dec!(-80), at(2, 8, 0))];
+    let realized = manager.restore_daily_loss(day_start, Some(dec!(10000)), &fills).await;
+    assert_eq!(realized, dec!(-202));
+
+    let alerts = manager.check_daily_loss(&state(now, None)).await;
+    assert_eq!(alerts.iter().map(|alert| (alert.level, alert.loss)).collect::<Vec<_>>(), [(LossLevel::Breach, dec!(202))]);
+    assert!(!enabled(&flags, "SOLUSDT").await);
+}
+
+#[tokio::test]
+async fn the_risk_state_rolls_over_when_the_daily_loss_day_starts() {
+    let (manager, _) = manager_rolling_over_at(EventBus::new(32), 8);
+
+    assert!(manager.roll_over_if_due(at(2, 8, 0)).await);
+    assert!(!manager.roll_over_if_due(at(3, 7, 59)).await);
+    assert_eq!(manager.trading_day_start(at(3, 7, 59)), Some(at(2, 8, 0)));
+    assert!(manager.roll_over_if_due(at(3, 8, 0)).await);
+    assert_eq!(manager.trading_day_start(at(3, 8, 0)), Some(at(3, 8, 0)));
+}
@@ -0,0 +1,238 @@
+//! Drives the dead-man's switch with a mock data feed that goes silent, on a simulated clock.
+
+use api_client::MarkPriceUpdate;
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use core_types::{Execution, Kline, OrderRequest, OrderSide, Position};
+use engine::event::{LiveEvent, MarketState};
+use engine::watchdog::{default_feed_timeout, DeadMansSwitch, FeedWatchdog};
+use events::{EventBus, EventSubscriber, LogLevel, WsMessage};
+use executor::{Executor, ExecutorError, Portfolio};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+const FEED_TIMEOUT: Duration = Duration::from_secs(180);
+const FLATTEN_AFTER: Duration = Duration::from_secs(300);
+
+/// Fills every order at the kline close and records the orders it was given.
+#[derive(Default)]
+struct RecordingExecutor {
+    orders: StdMutex<Vec<OrderRequest>>,
+}
+
+#[async_trait]
+impl Executor for RecordingExecutor {
+    async fn execute(&self, order: &OrderRequest, kline: &Kline, _: Option<Decimal>, _: Option<Decimal>) -> Result<Execution, ExecutorError> {
+        self.orders.lock().unwrap().push(order.clone());
+        Ok(Execution {
+            execution_id: Uuid::new_v4(),
+            client_order_id: order.client_order_id,
+            symbol: order.symbol.clone(),
+            side: order.side,
+            price: kline.close,
+            quantity: order.quantity,
+            fee: Decimal::ZERO,
+            fee_asset: "USDT".to_string(),
+            timestamp: kline.close_time,
+            decision_id: order.decision_id,
+            is_maker: false,
+            spread_cost: Decimal::ZERO,
+            placement: None,
+        })
+    }
+}
+
+fn kline(minute: i64) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 + minute * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(101),
+        low: dec!(99),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::seconds(59),
+        interval: "1m".to_string(),
+    }
+}
+
+/// A mock feed: a BTCUSDT kline and an ETHUSDT mark price each minute for `minutes` minutes,
+/// then nothing.
+fn mock_feed(minutes: i64) -> Vec<(Duration, LiveEvent)> {
+    (0..minutes)
+        .flat_map(|minute| {
+            let at = Duration::from_secs(minute as u64 * 60);
+            let mark = MarkPriceUpdate {
+                symbol: "ETHUSDT".to_string(),
+                mark_price: dec!(50),
+                index_price: None,
+                funding_rate: dec!(0.0001),
+                next_funding_time: 0,
+            };
+            [(at, LiveEvent::Kline(("BTCUSDT".to_string(), kline(minute)))), (at, LiveEvent::MarkPrice(mark))]
+        })
+        .collect()
+}
+
+fn position(symbol: &str, side: OrderSide, quantity: Decimal) -> Position {
+    Position {
+        position_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        side,
+        quantity,
+        entry_price: dec!(100),
+        unrealized_pnl: Decimal::ZERO,
+        last_updated: Utc::now(),
+        adds: 0,
+        last_entry_price: dec!(100),
+        estimated_liquidation_price: None,
+    }
+}
+
+struct Harness {
+    switch: DeadMansSwitch,
+    executor: Arc<RecordingExecutor>,
+    portfolio: Arc<Mutex<Portfolio>>,
+    flags: Arc<Mutex<HashMap<String, bool>>>,
+    rx: EventSubscriber,
+    start: Instant,
+    elapsed: Duration,
+    market_states: HashMap<String, MarketState>,
+}
+
+impl Harness {
+    fn new(flatten_enabled: bool) -> Self {
+        let mut portfolio = Portfolio::new(dec!(1000));
+        portfolio.positions.insert("BTCUSDT".to_string(), position("BTCUSDT", OrderSide::Buy, dec!(2)));
+        portfolio.positions.insert("ETHUSDT".to_string(), position("ETHUSDT", OrderSide::Sell, dec!(1)));
+        let portfolio = Arc::new(Mutex::new(portfolio));
+        let flags = Arc::new(Mutex::new(HashMap::from([("BTCUSDT".to_string(), true), ("ETHUSDT".to_string(), true)])));
+        let executor = Arc::new(RecordingExecutor::default());
+        let tx = EventBus::new(64);
+        let rx = tx.subscribe();
+
+        let start = Instant::now();
+        let symbols = ["BTCUSDT".to_string(), "ETHUSDT".to_string()];
+        let switch = DeadMansSwitch::new(
+            FeedWatchdog::new(symbols, FEED_TIMEOUT, FLATTEN_AFTER, start),
+            flatten_enabled,
+            Arc::clone(&portfolio),
+            executor.clone(),
+            Arc::clone(&flags),
+            tx,
+        );
+        Self { switch, executor, portfolio, flags, rx, start, elapsed: Duration::ZERO, market_states: HashMap::new() }
+    }
+
+    /// Replays `feed` and ticks the watchdog once per simulated second, from where the last
+    /// call stopped up to `until`, the way `LiveEngine::run` does.
+    async fn run(&mut self, feed: &[(Duration, LiveEvent)], until: Duration) {
+        let mut feed = feed.iter().peekable();
+        while self.elapsed <= until {
+            let now = self.start + self.elapsed;
+            while let Some((_, event)) = feed.next_if(|(at, _)| *at <= self.elapsed) {
+                if let Some(symbol) = event.data_symbol() {
+                    self.switch.on_data(symbol, now);
+                }
+                let state = self.market_states.entry(event.data_symbol().unwrap().to_string()).or_default();
+                match event {
+                    LiveEvent::Kline((_, kline)) => state.last_kline = Some(kline.clone()),
+                    LiveEvent::MarkPrice(update) => state.apply_mark_price(update, Utc::now()),
+                    LiveEvent::BookTicker(_) => {}
+                }
+            }
+            self.switch.check(now, &self.market_states).await;
+            self.elapsed += Duration::from_secs(1);
+        }
+    }
+
+    fn messages(&mut self) -> Vec<WsMessage> {
+        std::iter::from_fn(|| self.rx.try_recv().ok()).collect()
+    }
+}
+
+fn log(message: &WsMessage) -> Option<(LogLevel, &str)> {
+    match message {
+        WsMessage::Log(log) => Some((log.level.clone(), log.message.as_str())),
+        _ => None,
+    }
+}
+
+#[test]
+fn feed_timeout_is_three_times_the_smallest_bot_interval() {
+    assert_eq!(default_feed_timeout(["4h", "1m", "1d"]), Some(Duration::from_secs(180)));
+    // A daily bot tolerates three days of quiet, so a weekend doesn't trip it.
+    assert_eq!(default_feed_timeout(["1d"]), Some(Duration::from_secs(3 * 86_400)));
+    assert_eq!(default_feed_timeout(["1m", "bogus"]), None);
+}
+
+#[tokio::test]
+async fn silent_feed_alerts_then_flattens_in_order() {
+    let mut harness = Harness::new(true);
+    let feed = mock_feed(3);
+
+    // Last data at 120s: no alarm while the feed is within its timeout.
+    harness.run(&feed, Duration::from_secs(300)).await;
+    assert!(harness.messages().is_empty());
+
+    harness.run(&[], Duration::from_secs(900)).await;
+    let messages = harness.messages();
+    let levels: Vec<_> = messages.iter().filter_map(log).map(|(level, _)| level).collect();
+    assert_eq!(levels, [LogLevel::Warn, LogLevel::Warn, LogLevel::Error, LogLevel::Error]);
+    assert!(log(&messages[0]).unwrap().1.starts_with("DATA FEED SILENT: No market data for BTCUSDT"));
+    assert!(log(&messages[1]).unwrap().1.starts_with("DATA FEED SILENT: No market data for ETHUSDT"));
+    assert!(log(&messages[2]).unwrap().1.starts_with("CRITICAL: DEAD-MAN'S SWITCH"));
+
+    // The alert, then the flattening executions, then the halt notice.
+    let executed: Vec<_> = messages[3..5]
+        .iter()
+        .map(|message| match message {
+            WsMessage::TradeExecuted(execution) => (execution.symbol.as_str(), execution.side, execution.quantity),
+            other => panic!("expected an execution, got {:?}", other),
+        })
+        .collect();
+    assert_eq!(executed, [("BTCUSDT", OrderSide::Sell, dec!(2)), ("ETHUSDT", OrderSide::Buy, dec!(1))]);
+    assert!(log(&messages[5]).unwrap().1.starts_with("ALL BOTS HALTED"));
+    assert_eq!(messages.len(), 6);
+
+    assert_eq!(harness.executor.orders.lock().unwrap().len(), 2);
+    assert!(harness.portfolio.lock().await.positions.is_empty());
+    assert!(harness.flags.lock().await.values().all(|enabled| !enabled));
+}
+
+#[tokio::test]
+async fn resumed_feed_rearms_the_switch() {
+    let mut harness = Harness::new(true);
+    harness.run(&mock_feed(1), Duration::from_secs(200)).await;
+    assert_eq!(harness.messages().len(), 2);
+
+    // Data resumes before the flatten deadline: the episode ends and nothing is flattened.
+    let resumed: Vec<_> = mock_feed(2).into_iter().map(|(at, event)| (at + Duration::from_secs(201), event)).collect();
+    harness.run(&resumed, Duration::from_secs(400)).await;
+    let messages = harness.messages();
+    assert!(messages.iter().filter_map(log).all(|(level, message)| level == LogLevel::Info && message.starts_with("DATA FEED RESUMED")));
+    assert_eq!(messages.len(), 2);
+    assert!(harness.executor.orders.lock().unwrap().is_empty());
+
+    // A second outage escalates all over again.
+    harness.run(&[], Duration::from_secs(1200)).await;
+    assert!(harness.messages().iter().any(|message| matches!(log(message), Some((_, m)) if m.starts_with("CRITICAL: DEAD-MAN'S SWITCH"))));
+    assert_eq!(harness.executor.orders.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn alert_only_mode_never_executes() {
+    let mut harness = Harness::new(false);
+    harness.run(&mock_feed(3), Duration::from_secs(3600)).await;
+
+    let levels: Vec<_> = harness.messages().iter().filter_map(log).map(|(level, _)| level).collect();
+    assert_eq!(levels, [LogLevel::Warn, LogLevel::Warn, LogLevel::Error]);
+    assert!(harness.executor.orders.lock().unwrap().is_empty());
+    assert_eq!(harness.portfolio.lock().await.positions.len(), 2);
+    assert!(harness.flags.lock().await.values().all(|enabled| *enabled));
+}
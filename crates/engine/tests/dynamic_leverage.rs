@@ -0,0 +1,113 @@
+//! Checks that the global risk manager moves the shared risk multiplier through the dynamic
+//! leverage tiers as the account's equity changes, and alerts on each move.
+
+use configuration::settings::GlobalRiskConfig;
+use configuration::{DrawdownTier, DynamicLeverage};
+use engine::risk_manager::GlobalRiskManager;
+use engine::RiskState;
+use events::{EventBus, EventSubscriber, LogLevel, WsMessage};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+fn manager(dynamic_leverage: Option<DynamicLeverage>, event_tx: EventBus) -> GlobalRiskManager {
+    resumed_manager(dynamic_leverage, event_tx, dec!(1000), RiskState::default())
+}
+
+/// A manager starting at `equity` from the `restored` state of an earlier run.
+fn resumed_manager(dynamic_leverage: Option<DynamicLeverage>, event_tx: EventBus, equity: Decimal, restored: RiskState) -> GlobalRiskManager {
+    let config = GlobalRiskConfig {
+        max_daily_drawdown_pct: dec!(0.5),
+        max_consecutive_losses: 7,
+        bot_cooldown_hours: 4,
+        max_open_positions_per_asset: 1,
+        maintenance_margin_rate: Decimal::ZERO,
+        liquidation_warning_pct: dec!(0.05),
+        dynamic_leverage,
+        daily_loss: None,
+    };
+    GlobalRiskManager::new(
+        config,
+        Arc::new(Mutex::new(Portfolio::new(equity))),
+        Arc::new(Mutex::new(HashMap::new())),
+        event_tx,
+        equity,
+        restored,
+    )
+}
+
+fn next_log(rx: &mut EventSubscriber) -> Option<(LogLevel, String)> {
+    match rx.try_recv() {
+        Ok(WsMessage::Log(log)) => Some((log.level, log.message)),
+        Ok(other) => panic!("unexpected message: {:?}", other),
+        Err(_) => None,
+    }
+}
+
+/// Half risk from a 5% drawdown, a quarter from 10%, each left 1% past its boundary.
+fn tiers() -> DynamicLeverage {
+    DynamicLeverage {
+        tiers: vec![
+            DrawdownTier { drawdown_pct: dec!(0.05), multiplier: dec!(0.5) },
+            DrawdownTier { drawdown_pct: dec!(0.10), multiplier: dec!(0.25) },
+        ],
+        recovery_buffer_pct: dec!(0.01),
+        apply_in_backtests: false,
+    }
+}
+
+#[tokio::test]
+async fn drawdown_tiers_lower_and_restore_the_risk_multiplier() {
+    let event_tx = EventBus::new(16);
+    let mut rx = event_tx.subscribe();
+    let manager = manager(Some(tiers()), event_tx);
+    let multiplier = manager.risk_multiplier();
+
+    // The initial equity is the first peak.
+    assert!(manager.on_equity(dec!(960)).await.is_none());
+    assert_eq!(*multiplier.lock().await, dec!(1));
+
+    manager.on_equity(dec!(940)).await.expect("a tier change");
+    assert_eq!(*multiplier.lock().await, dec!(0.5));
+    let (level, message) = next_log(&mut rx).expect("an alert");
+    assert_eq!(level, LogLevel::Warn);
+    assert_eq!(message, "DYNAMIC LEVERAGE: Risk of new entries reduced from 1x to 0.5x at a 6.00% drawdown from peak equity.");
+
+    // Staying in the tier raises no further alert.
+    assert!(manager.on_equity(dec!(945)).await.is_none());
+    assert_eq!(next_log(&mut rx), None);
+
+    manager.on_equity(dec!(1000)).await.expect("a tier change");
+    assert_eq!(*multiplier.lock().await, dec!(1));
+    let (level, message) = next_log(&mut rx).expect("an alert");
+    assert_eq!(level, LogLevel::Info);
+    assert!(message.contains("restored from 0.5x to 1x"), "message: {}", message);
+}
+
+#[tokio::test]
+async fn without_tiers_the_multiplier_stays_at_full_risk() {
+    let event_tx = EventBus::new(16);
+    let mut rx = event_tx.subscribe();
+    let manager = manager(None, event_tx);
+
+    assert!(manager.on_equity(dec!(500)).await.is_none());
+    assert_eq!(*manager.risk_multiplier().lock().await, dec!(1));
+    assert_eq!(next_log(&mut rx), None);
+}
+
+#[tokio::test]
+async fn a_restart_in_a_drawdown_resumes_in_its_tier() {
+    let manager = manager(Some(tiers()), EventBus::new(16));
+    manager.on_equity(dec!(1100)).await;
+    let state = manager.risk_state().await;
+    assert_eq!(state.leverage_peak_equity, Some(dec!(1100)));
+
+    // Restarting 6% below the recorded peak takes half risk from the start, rather than
+    // measuring the drawdown from the restart's equity.
+    let manager = resumed_manager(Some(tiers()), EventBus::new(16), dec!(1034), state);
+    assert_eq!(*manager.risk_multiplier().lock().await, dec!(0.5));
+    assert_eq!(manager.risk_state().await.leverage_peak_equity, Some(dec!(1100)));
+}
@@ -0,0 +1,97 @@
+//! Checks that the broadcast feed status tells a live feed from a lagging or dead one, by how
+//! long ago each symbol and interval's latest kline closed.
+
+use api_client::MarkPriceUpdate;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{Interval, Kline};
+use engine::event::MarketState;
+use engine::feed_status::FeedMonitor;
+use events::{EventBus, FeedHealth, FeedStatus, WsMessage};
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 16, 12, 0, 0).unwrap()
+}
+
+fn kline(interval: Interval, close_time: DateTime<Utc>) -> Kline {
+    let open_time = close_time + Duration::milliseconds(1) - Duration::from_std(interval.duration()).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(100),
+        low: dec!(100),
+        close: dec!(100),
+        volume: dec!(1),
+        close_time,
+        interval: interval.to_string(),
+    }
+}
+
+/// Broadcasts the status at `now` and returns the message subscribers received.
+fn emitted(monitor: &FeedMonitor, market_states: &HashMap<String, MarketState>, now: DateTime<Utc>) -> FeedStatus {
+    let bus = EventBus::new(16);
+    let mut subscriber = bus.subscribe();
+    monitor.broadcast(market_states, now, &bus);
+    match subscriber.try_recv().expect("a feed status") {
+        WsMessage::FeedStatus(status) => status,
+        other => panic!("expected a feed status, got {:?}", other),
+    }
+}
+
+fn statuses(status: &FeedStatus) -> Vec<(&str, &str, FeedHealth)> {
+    status.feeds.iter().map(|feed| (feed.symbol.as_str(), feed.interval.as_str(), feed.status)).collect()
+}
+
+#[test]
+fn a_silent_feed_goes_from_healthy_to_delayed_to_stale() {
+    let monitor = FeedMonitor::new([("BTCUSDT".to_string(), Interval::M1)], start());
+    let mut market_states: HashMap<String, MarketState> = HashMap::new();
+    let state = market_states.entry("BTCUSDT".to_string()).or_default();
+    let closed = start();
+    state.apply_kline(&kline(Interval::M1, closed));
+    let mark_price = MarkPriceUpdate { symbol: "BTCUSDT".to_string(), mark_price: dec!(100), index_price: None, funding_rate: dec!(0.0001), next_funding_time: 0 };
+    state.apply_mark_price(&mark_price, start());
+
+    // The clock advances with no further kline: the next one is due a minute after the last.
+    let mut seen = Vec::new();
+    for secs in [0, 30, 60, 75, 76, 120, 180, 181, 600] {
+        let status = emitted(&monitor, &market_states, start() + Duration::seconds(secs));
+        assert_eq!(status.timestamp, start() + Duration::seconds(secs));
+        let feed = &status.feeds[0];
+        assert_eq!((feed.kline, feed.mark_price, feed.book_ticker), (Some(closed), Some(start()), None));
+        seen.push(feed.status);
+    }
+    use FeedHealth::*;
+    assert_eq!(seen, [Healthy, Healthy, Healthy, Healthy, Delayed, Delayed, Delayed, Stale, Stale]);
+
+    // A kline arriving makes it healthy again.
+    let now = start() + Duration::seconds(600);
+    market_states.get_mut("BTCUSDT").unwrap().apply_kline(&kline(Interval::M1, now - Duration::milliseconds(1)));
+    assert_eq!(emitted(&monitor, &market_states, now).feeds[0].status, Healthy);
+}
+
+#[test]
+fn each_interval_of_a_symbol_is_judged_by_its_own_klines() {
+    let feeds = [
+        ("ETHUSDT".to_string(), Interval::H1),
+        ("BTCUSDT".to_string(), Interval::H1),
+        ("BTCUSDT".to_string(), Interval::M1),
+    ];
+    let monitor = FeedMonitor::new(feeds, start());
+    let mut market_states: HashMap<String, MarketState> = HashMap::new();
+    let btc = market_states.entry("BTCUSDT".to_string()).or_default();
+    btc.apply_kline(&kline(Interval::H1, start() - Duration::milliseconds(1)));
+    btc.apply_kline(&kline(Interval::M1, start() - Duration::milliseconds(1)));
+
+    // Ten minutes on, the hourly klines are not due yet but the minute ones are long overdue.
+    let status = emitted(&monitor, &market_states, start() + Duration::minutes(10));
+    assert_eq!(
+        statuses(&status),
+        [("BTCUSDT", "1m", FeedHealth::Stale), ("BTCUSDT", "1h", FeedHealth::Healthy), ("ETHUSDT", "1h", FeedHealth::Healthy)]
+    );
+    // ETHUSDT has sent nothing since the feeds were subscribed to.
+    assert_eq!(status.feeds[2].kline, None);
+    let status = emitted(&monitor, &market_states, start() + Duration::minutes(90));
+    assert_eq!(status.feeds[2].status, FeedHealth::Delayed);
+}
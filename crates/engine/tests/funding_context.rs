@@ -0,0 +1,97 @@
+use api_client::MarkPriceUpdate;
+use chrono::{TimeZone, Utc};
+use core_types::{Kline, OrderSide, StrategyId};
+use engine::event::MarketState;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use strategies::create_strategy_from_params;
+
+fn kline(minute: i64) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 + minute * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(101),
+        low: dec!(99),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::seconds(59),
+        interval: "1m".to_string(),
+    }
+}
+
+/// A mark price update at 100.5, 0.1% above the index price.
+fn mark_price(funding_rate: Decimal) -> MarkPriceUpdate {
+    basis_update(funding_rate, dec!(100.4))
+}
+
+fn basis_update(funding_rate: Decimal, index_price: Decimal) -> MarkPriceUpdate {
+    MarkPriceUpdate {
+        symbol: "BTCUSDT".to_string(),
+        mark_price: dec!(100.5),
+        index_price: Some(index_price),
+        funding_rate,
+        next_funding_time: 1_700_006_400_000,
+    }
+}
+
+#[test]
+fn mark_price_stream_payload_carries_funding_information() {
+    let payload = r#"{"e":"markPriceUpdate","E":1562305380000,"s":"BTCUSDT","p":"11794.15000000","i":"11784.62659091","P":"11784.25641265","r":"0.00038167","T":1562306400000}"#;
+    let update: MarkPriceUpdate = serde_json::from_str(payload).unwrap();
+
+    let mut state = MarketState::default();
+    state.apply_mark_price(&update, Utc::now());
+    let context = state.context(&kline(0));
+
+    assert_eq!(context.mark_price, Some(dec!(11794.15)));
+    assert_eq!(context.index_price, Some(dec!(11784.62659091)));
+    assert_eq!(context.funding_rate, Some(dec!(0.00038167)));
+    assert_eq!(context.next_funding_time, Utc.timestamp_millis_opt(1562306400000).single());
+}
+
+#[test]
+fn funding_rate_arb_signals_when_injected_funding_crosses_threshold() {
+    let params = serde_json::json!({ "target_rate_threshold": 0.001, "basis_safety_threshold": 0.005 });
+    let mut strategy = create_strategy_from_params(StrategyId::FundingRateArb, &params, "BTCUSDT").unwrap();
+    let mut state = MarketState::default();
+
+    // No funding data yet, and plain `evaluate` never trades.
+    assert!(strategy.evaluate_with_context(&kline(0), &state.context(&kline(0))).unwrap().is_none());
+    assert!(strategy.evaluate(&kline(0)).unwrap().is_none());
+
+    state.apply_mark_price(&mark_price(dec!(0.0001)), Utc::now());
+    assert!(strategy.evaluate_with_context(&kline(1), &state.context(&kline(1))).unwrap().is_none());
+
+    // Funding turns expensive for longs: go short to collect it, once.
+    state.apply_mark_price(&mark_price(dec!(0.0015)), Utc::now());
+    let signal = strategy.evaluate_with_context(&kline(2), &state.context(&kline(2))).unwrap().expect("short signal");
+    assert_eq!(signal.order_request.side, OrderSide::Sell);
+    assert_eq!(signal.order_request.symbol, "BTCUSDT");
+    assert!(strategy.evaluate_with_context(&kline(3), &state.context(&kline(3))).unwrap().is_none());
+
+    // Funding flips strongly negative: go long.
+    state.apply_mark_price(&mark_price(dec!(-0.002)), Utc::now());
+    let signal = strategy.evaluate_with_context(&kline(4), &state.context(&kline(4))).unwrap().expect("long signal");
+    assert_eq!(signal.order_request.side, OrderSide::Buy);
+}
+
+#[test]
+fn funding_rate_arb_holds_its_entry_while_the_basis_is_wide() {
+    let params = serde_json::json!({ "target_rate_threshold": 0.001, "basis_safety_threshold": 0.005 });
+    let mut strategy = create_strategy_from_params(StrategyId::FundingRateArb, &params, "BTCUSDT").unwrap();
+    let mut state = MarketState::default();
+
+    // 100.5 against an index of 99.5 is a basis of about 1%, twice the threshold.
+    state.apply_mark_price(&basis_update(dec!(0.0015), dec!(99.5)), Utc::now());
+    assert!(strategy.evaluate_with_context(&kline(0), &state.context(&kline(0))).unwrap().is_none());
+
+    // Without an index price the basis cannot be checked either.
+    state.apply_mark_price(&MarkPriceUpdate { index_price: None, ..basis_update(dec!(0.0015), dec!(99.5)) }, Utc::now());
+    assert!(strategy.evaluate_with_context(&kline(1), &state.context(&kline(1))).unwrap().is_none());
+
+    // Once it narrows, the entry is taken in the same funding regime.
+    state.apply_mark_price(&mark_price(dec!(0.0015)), Utc::now());
+    let signal = strategy.evaluate_with_context(&kline(2), &state.context(&kline(2))).unwrap().expect("short signal");
+    assert_eq!(signal.order_request.side, OrderSide::Sell);
+}
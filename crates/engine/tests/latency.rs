@@ -0,0 +1,58 @@
+//! Checks that decision path latencies accumulate per symbol and stage, and that each report
+//! summarises one window.
+
+use engine::latency::{LatencyStage, LatencyTracker};
+use std::time::Duration;
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+#[test]
+fn histograms_accumulate_across_synthetic_events() {
+    let mut tracker = LatencyTracker::new();
+
+    // 100 klines for BTCUSDT taking 1..=100ms to decide, and two for ETHUSDT.
+    for i in 1..=100 {
+        tracker.record("BTCUSDT", LatencyStage::Strategy, ms(i / 2));
+        tracker.record("BTCUSDT", LatencyStage::Decision, ms(i));
+    }
+    tracker.record("ETHUSDT", LatencyStage::Decision, ms(7));
+    tracker.record("ETHUSDT", LatencyStage::Decision, ms(300));
+
+    let btc = tracker.histogram("BTCUSDT", LatencyStage::Decision).unwrap();
+    assert_eq!(btc.len(), 100);
+    assert_eq!(btc.percentile(0.5), Some(ms(50)));
+    assert_eq!(btc.percentile(0.99), Some(ms(99)));
+    assert_eq!(tracker.histogram("ETHUSDT", LatencyStage::Decision).unwrap().len(), 2);
+    assert!(tracker.histogram("ETHUSDT", LatencyStage::Risk).is_none());
+
+    let report = tracker.report(Duration::from_secs(60)).expect("a report");
+    assert_eq!(report.window_secs, 60);
+    let symbols: Vec<_> = report.symbols.iter().map(|s| s.symbol.as_str()).collect();
+    assert_eq!(symbols, ["BTCUSDT", "ETHUSDT"]);
+
+    let btc = report.symbols[0].decision.as_ref().unwrap();
+    assert_eq!((btc.samples, btc.p50_ms, btc.p99_ms, btc.max_ms), (100, 50.0, 99.0, 100.0));
+    assert_eq!(report.symbols[0].strategy.as_ref().unwrap().samples, 100);
+    assert!(report.symbols[0].risk.is_none());
+
+    let eth = report.symbols[1].decision.as_ref().unwrap();
+    assert_eq!((eth.p50_ms, eth.p99_ms), (7.0, 300.0));
+}
+
+#[test]
+fn each_report_starts_a_new_window() {
+    let mut tracker = LatencyTracker::new();
+    assert!(tracker.report(Duration::from_secs(60)).is_none());
+
+    tracker.record("BTCUSDT", LatencyStage::FeedDelay, ms(900));
+    assert!(tracker.report(Duration::from_secs(60)).is_some());
+    assert!(tracker.histogram("BTCUSDT", LatencyStage::FeedDelay).is_none());
+    assert!(tracker.report(Duration::from_secs(60)).is_none());
+
+    tracker.record("BTCUSDT", LatencyStage::FeedDelay, ms(20));
+    let report = tracker.report(Duration::from_secs(60)).unwrap();
+    let feed_delay = report.symbols[0].feed_delay.as_ref().unwrap();
+    assert_eq!((feed_delay.samples, feed_delay.max_ms), (1, 20.0));
+}
@@ -0,0 +1,123 @@
+use chrono::Utc;
+use configuration::settings::GlobalRiskConfig;
+use core_types::{OrderSide, Position};
+use engine::risk_manager::GlobalRiskManager;
+use engine::RiskState;
+use engine::valuation::{estimated_liquidation_price, mark_position, LiquidationEstimator};
+use events::{EventBus, EventSubscriber, LogLevel, WsMessage};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+fn position(side: OrderSide, entry_price: Decimal) -> Position {
+    Position {
+        position_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        quantity: dec!(2),
+        entry_price,
+        unrealized_pnl: Decimal::ZERO,
+        last_updated: Utc::now(),
+        adds: 0,
+        last_entry_price: entry_price,
+        estimated_liquidation_price: None,
+    }
+}
+
+#[test]
+fn long_liquidation_is_below_entry() {
+    // Without maintenance margin, a 10x long loses its whole margin after a 10% drop.
+    assert_eq!(estimated_liquidation_price(OrderSide::Buy, dec!(100), 10, Decimal::ZERO), Some(dec!(90)));
+    // Maintenance margin liquidates it earlier: 100 * 0.9 / 0.995.
+    let price = estimated_liquidation_price(OrderSide::Buy, dec!(100), 10, dec!(0.005)).unwrap();
+    assert_eq!(price.round_dp(4), dec!(90.4523));
+    // An unleveraged long can only be wiped out at zero.
+    assert_eq!(estimated_liquidation_price(OrderSide::Buy, dec!(100), 1, Decimal::ZERO), Some(Decimal::ZERO));
+}
+
+#[test]
+fn short_liquidation_is_above_entry() {
+    assert_eq!(estimated_liquidation_price(OrderSide::Sell, dec!(100), 10, Decimal::ZERO), Some(dec!(110)));
+    // 100 * 1.1 / 1.005.
+    let price = estimated_liquidation_price(OrderSide::Sell, dec!(100), 10, dec!(0.005)).unwrap();
+    assert_eq!(price.round_dp(4), dec!(109.4527));
+    // 20x liquidates twice as close as 10x.
+    assert_eq!(estimated_liquidation_price(OrderSide::Sell, dec!(200), 20, Decimal::ZERO), Some(dec!(210)));
+}
+
+#[test]
+fn zero_leverage_or_unknown_symbols_have_no_estimate() {
+    assert_eq!(estimated_liquidation_price(OrderSide::Buy, dec!(100), 0, Decimal::ZERO), None);
+    let estimator = LiquidationEstimator::new(dec!(0.004));
+    assert_eq!(estimator.estimate(&position(OrderSide::Buy, dec!(100))), None);
+}
+
+#[test]
+fn marking_a_position_refreshes_pnl_and_liquidation_price() {
+    let mut estimator = LiquidationEstimator::new(Decimal::ZERO);
+    estimator.set_leverage("BTCUSDT", 5);
+
+    let mut short = position(OrderSide::Sell, dec!(100));
+    mark_position(&mut short, dec!(104), &estimator);
+    assert_eq!(short.unrealized_pnl, dec!(-8));
+    assert_eq!(short.estimated_liquidation_price, Some(dec!(120)));
+}
+
+fn next_log(rx: &mut EventSubscriber) -> Option<(LogLevel, String)> {
+    match rx.try_recv() {
+        Ok(WsMessage::Log(log)) => Some((log.level, log.message)),
+        Ok(other) => panic!("unexpected message: {:?}", other),
+        Err(_) => None,
+    }
+}
+
+#[tokio::test]
+async fn warns_once_per_approach_to_liquidation() {
+    let config = GlobalRiskConfig {
+        max_daily_drawdown_pct: dec!(0.1),
+        max_consecutive_losses: 7,
+        bot_cooldown_hours: 4,
+        max_open_positions_per_asset: 1,
+        maintenance_margin_rate: Decimal::ZERO,
+        liquidation_warning_pct: dec!(0.05),
+        dynamic_leverage: None,
+        daily_loss: None,
+    };
+    let event_tx = EventBus::new(16);
+    let mut rx = event_tx.subscribe();
+    let manager = GlobalRiskManager::new(
+        config,
+        Arc::new(Mutex::new(Portfolio::new(dec!(1000)))),
+        Arc::new(Mutex::new(HashMap::new())),
+        event_tx,
+        dec!(1000),
+        RiskState::default(),
+    );
+    let mut estimator = LiquidationEstimator::new(Decimal::ZERO);
+    estimator.set_leverage("BTCUSDT", 10);
+    let mut long = position(OrderSide::Buy, dec!(100));
+
+    // Liquidation is at 90; 95 is 5.3% above it.
+    mark_position(&mut long, dec!(95), &estimator);
+    assert!(!manager.check_liquidation_distance(&long, dec!(95)).await);
+    assert_eq!(next_log(&mut rx), None);
+
+    // 94 is within 5%.
+    assert!(manager.check_liquidation_distance(&long, dec!(94)).await);
+    let (level, message) = next_log(&mut rx).expect("a liquidation warning");
+    assert_eq!(level, LogLevel::Warn);
+    assert!(message.contains("BTCUSDT") && message.contains("90"), "message: {}", message);
+
+    // Still near liquidation: no repeat.
+    assert!(!manager.check_liquidation_distance(&long, dec!(92)).await);
+    assert_eq!(next_log(&mut rx), None);
+
+    // Recovering re-arms the warning.
+    assert!(!manager.check_liquidation_distance(&long, dec!(100)).await);
+    assert!(manager.check_liquidation_distance(&long, dec!(93)).await);
+    assert!(next_log(&mut rx).is_some());
+}
@@ -0,0 +1,44 @@
+//! Checks that the engine's per-bot span fields support `RUST_LOG`-style filtering by symbol.
+
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use tracing_subscriber::EnvFilter;
+
+/// A writer that collects everything the subscriber formats.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn a_symbol_directive_enables_debug_events_for_that_bot_only() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new("engine[{symbol=BTCUSDT}]=debug"))
+        .with_writer(move || writer.clone())
+        .with_ansi(false)
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        for symbol in ["BTCUSDT", "ETHUSDT"] {
+            // The same shape as the engine's `kline_decision` span.
+            let span = tracing::info_span!(target: "engine", "kline_decision", symbol = %symbol, interval = "1m");
+            let _entered = span.enter();
+            tracing::debug!(target: "engine", order_qty = 0.5, "Risk manager approved orders.");
+        }
+    });
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    assert!(output.contains("symbol=BTCUSDT"), "{}", output);
+    assert!(!output.contains("ETHUSDT"), "{}", output);
+}
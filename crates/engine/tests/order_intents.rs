@@ -0,0 +1,223 @@
+//! Resolves the orders recorded as sent before a crash against a scripted exchange: orders
+//! that never filled expire, orders still working block entries, and orders that filled get
+//! the execution the engine never wrote.
+
+use api_client::{OrderResponse, UserTradeResponse};
+use chrono::{DateTime, TimeZone, Utc};
+use configuration::QuoteAssets;
+use core_types::{Execution, OrderRequest, OrderSide, OrderType, PositionFill, StrategyId};
+use database::{OrderIntent, PositionContext};
+use engine::order_ledger;
+use engine::{IntentRecovery, IntentResolution, OrderLedger};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::MockAccount;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap()
+}
+
+/// The intent of a market order for `quantity` BTCUSDT, sent a minute before the crash.
+fn sent(side: OrderSide, quantity: Decimal, reduce_only: bool) -> OrderIntent {
+    let order = OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        order_type: OrderType::Market,
+        quantity,
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only,
+        decision_id: Some(Uuid::new_v4()),
+    };
+    order_ledger::intent(&order, now() - chrono::Duration::minutes(1))
+}
+
+/// The exchange's view of `intent`'s order in `status`, `executed` of it filled at
+/// `avg_price`.
+fn order(intent: &OrderIntent, order_id: i64, status: &str, executed: Decimal, avg_price: Decimal) -> OrderResponse {
+    OrderResponse {
+        client_order_id: intent.client_order_id.to_string(),
+        cum_qty: Decimal::ZERO,
+        cum_quote: executed * avg_price,
+        executed_qty: executed,
+        order_id,
+        avg_price,
+        orig_qty: intent.quantity,
+        price: Decimal::ZERO,
+        reduce_only: intent.reduce_only,
+        side: intent.side,
+        status: status.to_string(),
+        stop_price: Decimal::ZERO,
+        symbol: intent.symbol.clone(),
+        time_in_force: "GTC".to_string(),
+        order_type: "MARKET".to_string(),
+        update_time: Some((now() - chrono::Duration::seconds(59)).timestamp_millis()),
+    }
+}
+
+/// A taker fill of `qty` of order `order_id` at `price`.
+fn trade(order_id: i64, side: OrderSide, qty: Decimal, price: Decimal, commission: Decimal, realized_pnl: Decimal) -> UserTradeResponse {
+    UserTradeResponse {
+        id: order_id * 10,
+        order_id,
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        qty,
+        quote_qty: qty * price,
+        realized_pnl,
+        commission,
+        commission_asset: "USDT".to_string(),
+        maker: false,
+        time: (now() - chrono::Duration::seconds(59)).timestamp_millis(),
+    }
+}
+
+/// A portfolio synced from an exchange holding `position`, a `(side, quantity)` of BTCUSDT at
+/// 100, if any.
+fn synced(position: Option<(OrderSide, Decimal)>) -> Mutex<Portfolio> {
+    let mut portfolio = Portfolio::new(dec!(10000));
+    if let Some((side, quantity)) = position {
+        let execution = Execution {
+            execution_id: Uuid::new_v4(),
+            client_order_id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            side,
+            price: dec!(100),
+            quantity,
+            fee: Decimal::ZERO,
+            fee_asset: "USDT".to_string(),
+            timestamp: now(),
+            decision_id: None,
+            is_maker: false,
+            spread_cost: Decimal::ZERO,
+            placement: None,
+        };
+        portfolio.update_with_execution(&execution).expect("open position");
+    }
+    Mutex::new(portfolio)
+}
+
+async fn recover(exchange: &MockAccount, stored: Vec<OrderIntent>, contexts: &[PositionContext], portfolio: &Mutex<Portfolio>, ledger: &OrderLedger) -> Vec<IntentResolution> {
+    let recovery = IntentRecovery { quote_assets: QuoteAssets::new("USDT") };
+    recovery.run(stored, contexts, portfolio, ledger, exchange, now()).await
+}
+
+#[tokio::test]
+async fn an_order_sent_but_never_received_expires() {
+    let intent = sent(OrderSide::Buy, dec!(1), false);
+    let ledger = OrderLedger::new();
+
+    let resolutions = recover(&MockAccount::new(), vec![intent.clone()], &[], &synced(None), &ledger).await;
+
+    assert_eq!(resolutions, [IntentResolution::Expired { intent, status: None }]);
+    assert!(ledger.unresolved_on("BTCUSDT").is_none());
+}
+
+#[tokio::test]
+async fn an_order_sent_and_still_working_blocks_entries_until_resolved() {
+    let working = sent(OrderSide::Buy, dec!(1), false);
+    let canceled = sent(OrderSide::Buy, dec!(1), false);
+    let unknown = OrderIntent { created_at: now() - chrono::Duration::seconds(30), ..sent(OrderSide::Sell, dec!(1), false) };
+    let exchange = MockAccount::new()
+        .with_order(order(&working, 1, "NEW", Decimal::ZERO, Decimal::ZERO), Vec::new())
+        .with_order(order(&canceled, 2, "CANCELED", Decimal::ZERO, Decimal::ZERO), Vec::new())
+        .with_unreachable_order(&unknown.client_order_id.to_string());
+    let ledger = OrderLedger::new();
+
+    let resolutions = recover(&exchange, vec![working.clone(), canceled.clone(), unknown.clone()], &[], &synced(None), &ledger).await;
+
+    assert_eq!(resolutions.len(), 3);
+    let IntentResolution::Unresolved { intent, reason } = &resolutions[0] else { panic!("{:?}", resolutions[0]) };
+    assert_eq!((intent, reason.as_str()), (&working, "the order is still NEW on the exchange"));
+    assert_eq!(resolutions[1], IntentResolution::Expired { intent: canceled, status: Some("CANCELED".to_string()) });
+    let IntentResolution::Unresolved { intent, reason } = &resolutions[2] else { panic!("{:?}", resolutions[2]) };
+    assert_eq!(intent, &unknown);
+    assert!(reason.starts_with("the order could not be looked up"), "{}", reason);
+
+    // Both orders of unknown fate stay in the ledger, the oldest blocking the symbol first.
+    assert_eq!(ledger.all().len(), 2);
+    assert_eq!(ledger.unresolved_on("BTCUSDT").map(|intent| intent.client_order_id), Some(working.client_order_id));
+}
+
+#[tokio::test]
+async fn an_entry_filled_before_its_execution_was_written_is_recorded_against_the_synced_position() {
+    let intent = sent(OrderSide::Buy, dec!(1), false);
+    let exchange = MockAccount::new()
+        .with_order(order(&intent, 7, "FILLED", dec!(1), dec!(100)), vec![trade(7, OrderSide::Buy, dec!(1), dec!(100), dec!(0.04), Decimal::ZERO)]);
+    // The exchange already reports the position the order opened.
+    let portfolio = synced(Some((OrderSide::Buy, dec!(1))));
+    let position = portfolio.lock().await.get_position("BTCUSDT").cloned().expect("synced position");
+    let cash = portfolio.lock().await.cash;
+    let ledger = OrderLedger::new();
+
+    let resolutions = recover(&exchange, vec![intent.clone()], &[], &portfolio, &ledger).await;
+
+    let [IntentResolution::Filled { intent: filled, execution, fills }] = resolutions.as_slice() else { panic!("{:?}", resolutions) };
+    assert_eq!(filled, &intent);
+    assert_eq!(execution.client_order_id, intent.client_order_id);
+    assert_eq!(execution.decision_id, intent.decision_id);
+    assert_eq!((execution.side, execution.price, execution.quantity), (OrderSide::Buy, dec!(100), dec!(1)));
+    assert_eq!((execution.fee, execution.fee_asset.as_str()), (dec!(0.04), "USDT"));
+    assert_eq!(execution.timestamp, now() - chrono::Duration::seconds(59));
+    assert_eq!(
+        fills,
+        &[PositionFill {
+            position_id: position.position_id,
+            position_side: OrderSide::Buy,
+            is_entry: true,
+            quantity: dec!(1),
+            fee: dec!(0.04),
+            realized_pnl: Decimal::ZERO,
+            position_quantity: dec!(1),
+        }]
+    );
+
+    // The fill is already in the synced portfolio, so recovery leaves it as it is.
+    let portfolio = portfolio.lock().await;
+    assert_eq!(portfolio.get_position("BTCUSDT").map(|position| position.quantity), Some(dec!(1)));
+    assert_eq!(portfolio.cash, cash);
+    assert!(ledger.all().is_empty());
+}
+
+#[tokio::test]
+async fn an_exit_filled_before_its_execution_was_written_closes_the_recorded_position() {
+    let intent = sent(OrderSide::Sell, dec!(1), true);
+    let exchange = MockAccount::new()
+        .with_order(order(&intent, 8, "FILLED", dec!(1), dec!(105)), vec![trade(8, OrderSide::Sell, dec!(1), dec!(105), dec!(0.042), dec!(5))]);
+    let context = PositionContext {
+        position_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        strategy_id: Some(StrategyId::MACrossover),
+        entry_signal_id: Some(Uuid::new_v4()),
+        stop_price: Some(dec!(98)),
+        take_profit_price: None,
+        opened_at: now() - chrono::Duration::hours(5),
+        closed_at: None,
+    };
+    let ledger = OrderLedger::new();
+
+    // The exchange no longer holds the position the order closed.
+    let resolutions = recover(&exchange, vec![intent.clone()], std::slice::from_ref(&context), &synced(None), &ledger).await;
+
+    let [IntentResolution::Filled { execution, fills, .. }] = resolutions.as_slice() else { panic!("{:?}", resolutions) };
+    assert_eq!((execution.side, execution.price), (OrderSide::Sell, dec!(105)));
+    assert_eq!(
+        fills,
+        &[PositionFill {
+            position_id: context.position_id,
+            position_side: OrderSide::Buy,
+            is_entry: false,
+            quantity: dec!(1),
+            fee: dec!(0.042),
+            realized_pnl: dec!(5),
+            position_quantity: Decimal::ZERO,
+        }]
+    );
+}
@@ -0,0 +1,218 @@
+//! Places the legs of an order plan one after another, and halts the bot with a critical
+//! alert when a later leg fails after an earlier one filled.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use configuration::{LiveBotConfig, LiveConfig, Versioned};
+use core_types::{Execution, Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent, StrategyId};
+use engine::{LiveEngine, StrategyFactory};
+use events::{EventBus, LogLevel, WsMessage};
+use executor::{Executor, ExecutorError, SimulatedExecutor};
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use strategies::{Strategy, StrategyError};
+use testing::{unreachable_repo, MockAccount};
+use tokio::time::Duration;
+use uuid::Uuid;
+
+/// Fills orders like the simulated executor, except the `fail_on`th order placed, which the
+/// exchange rejects.
+struct FailingExecutor {
+    inner: SimulatedExecutor,
+    fail_on: Option<usize>,
+    placed: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Executor for FailingExecutor {
+    async fn execute(&self, order: &OrderRequest, kline: &Kline, best_bid: Option<Decimal>, best_ask: Option<Decimal>) -> Result<Execution, ExecutorError> {
+        let placed = self.placed.fetch_add(1, Ordering::SeqCst) + 1;
+        if Some(placed) == self.fail_on {
+            return Err(ExecutorError::Rejected("order rejected".to_string()));
+        }
+        self.inner.execute(order, kline, best_bid, best_ask).await
+    }
+}
+
+/// Emits the `n`th of `signals` on the `n`th kline it sees.
+struct Scripted {
+    signals: Vec<Option<(SignalIntent, OrderSide)>>,
+    seen: usize,
+}
+
+impl Strategy for Scripted {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let signal = self.signals.get(self.seen).copied().flatten();
+        self.seen += 1;
+        Ok(signal.map(|(intent, side)| Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: Some(intent),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// The `n`th one-minute kline, closing at 100.
+fn kline(n: i64) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600 + n * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(100),
+        low: dec!(100),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::minutes(1) - chrono::Duration::milliseconds(1),
+        interval: "1m".to_string(),
+    }
+}
+
+/// What a run broadcast: the executions, and the messages of the error logs.
+struct Outcome {
+    executions: Vec<Execution>,
+    errors: Vec<String>,
+    orders_placed: usize,
+}
+
+/// Runs a BTCUSDT bot emitting `signals`, one kline each, with the `fail_on`th order rejected.
+async fn run_engine(signals: Vec<Option<(SignalIntent, OrderSide)>>, fail_on: Option<usize>) -> Outcome {
+    let base_config = testing::test_config(10).expect("load config");
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots: vec![LiveBotConfig {
+            enabled: true,
+            symbol: "BTCUSDT".to_string(),
+            strategy_id: StrategyId::MACrossover,
+            interval: Some("1m".to_string()),
+            leverage: Some(20),
+            kline_transform: Default::default(),
+            direction: Default::default(),
+            quote_asset: None,
+            risk: None,
+            params: serde_json::json!({}),
+        }],
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+    let placed = Arc::new(AtomicUsize::new(0));
+    let executor = Arc::new(FailingExecutor { inner: SimulatedExecutor::new(base_config.simulation.clone()), fail_on, placed: placed.clone() });
+    let event_tx = EventBus::new(1024);
+    let mut event_rx = event_tx.subscribe();
+
+    let script = (0..signals.len() as i64).map(|n| ScriptEvent::EmitKline { symbol: "BTCUSDT".to_string(), kline: kline(n) }).collect();
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(ScriptedConnector::new().with_kline_script("1m", script));
+    let factory: StrategyFactory = Arc::new(move |_, _: &LiveBotConfig| Ok(Box::new(Scripted { signals: signals.clone(), seen: 0 }) as Box<dyn Strategy>));
+    let mut engine = LiveEngine::new(live_config, base_config, Arc::new(MockAccount::new()), executor, unreachable_repo(), risk_manager, event_tx)
+        .without_persistence()
+        .with_connector(connector)
+        .with_strategy_factory(factory);
+    tokio::spawn(async move { engine.run().await });
+
+    // Leave time for every kline to be processed.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let mut outcome = Outcome { executions: Vec::new(), errors: Vec::new(), orders_placed: 0 };
+    while let Ok(message) = event_rx.try_recv() {
+        match message {
+            WsMessage::TradeExecuted(execution) => outcome.executions.push(execution),
+            WsMessage::Log(log) if log.level == LogLevel::Error => outcome.errors.push(log.message),
+            _ => {}
+        }
+    }
+    outcome.orders_placed = placed.load(Ordering::SeqCst);
+    outcome
+}
+
+/// The side and quantity of each execution.
+fn fills(executions: &[Execution]) -> Vec<(OrderSide, Decimal)> {
+    executions.iter().map(|execution| (execution.side, execution.quantity)).collect()
+}
+
+#[tokio::test(start_paused = true)]
+async fn an_open_against_the_position_closes_it_then_enters() {
+    let signals = vec![Some((SignalIntent::OpenLong, OrderSide::Buy)), Some((SignalIntent::OpenShort, OrderSide::Sell))];
+    let outcome = run_engine(signals, None).await;
+
+    let fills = fills(&outcome.executions);
+    assert_eq!(fills.len(), 3, "{:?}", fills);
+    assert_eq!(fills[0].0, OrderSide::Buy);
+    // The close flattens the long, and the entry opens the short on the same kline.
+    assert_eq!(fills[1], (OrderSide::Sell, fills[0].1));
+    assert_eq!(fills[2].0, OrderSide::Sell);
+    assert_eq!(outcome.executions[1].timestamp, outcome.executions[2].timestamp);
+    assert_eq!(outcome.errors, Vec::<String>::new());
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_failed_second_leg_halts_the_bot_with_a_critical_alert() {
+    let signals = vec![
+        Some((SignalIntent::OpenLong, OrderSide::Buy)),
+        Some((SignalIntent::Reverse, OrderSide::Sell)),
+        Some((SignalIntent::OpenLong, OrderSide::Buy)),
+    ];
+    // The long's entry and the reversal's close fill; the reversal's entry is rejected.
+    let outcome = run_engine(signals, Some(3)).await;
+
+    let fills = fills(&outcome.executions);
+    assert_eq!(fills.len(), 2, "{:?}", fills);
+    assert_eq!(fills[1], (OrderSide::Sell, fills[0].1));
+
+    let halt = outcome.errors.iter().find(|message| message.contains("BOT HALTED"));
+    let halt = halt.unwrap_or_else(|| panic!("no halt alert in {:?}", outcome.errors));
+    assert!(halt.starts_with("CRITICAL: Leg 2 of 2 for BTCUSDT failed after 1 filled"), "{}", halt);
+    // The halted bot places no more orders.
+    assert_eq!(outcome.orders_placed, 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_failed_first_leg_drops_the_plan_without_halting() {
+    let signals = vec![
+        Some((SignalIntent::OpenLong, OrderSide::Buy)),
+        Some((SignalIntent::Reverse, OrderSide::Sell)),
+        Some((SignalIntent::Reverse, OrderSide::Sell)),
+    ];
+    // The first reversal's close is rejected, so its entry is never placed; the bot keeps
+    // trading and the second reversal goes through.
+    let outcome = run_engine(signals, Some(2)).await;
+
+    let fills = fills(&outcome.executions);
+    assert_eq!(fills.len(), 3, "{:?}", fills);
+    assert_eq!(fills[1], (OrderSide::Sell, fills[0].1));
+    assert_eq!(fills[2].0, OrderSide::Sell);
+    assert!(outcome.errors.iter().all(|message| !message.contains("BOT HALTED")), "{:?}", outcome.errors);
+}
@@ -0,0 +1,559 @@
+//! Drives the signal pipeline on its own, with a scripted strategy, risk manager and
+//! executor, through each way a kline's decision can end.
+
+use api_client::OrderResponse;
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use configuration::{Config, MinExpectedMove, QuoteAssets};
+use core_types::{Execution, Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent, StrategyId, TradeDirection};
+use engine::event::MarketState;
+use engine::order_ledger;
+use engine::persistence::{Persistence, PersistenceSettings};
+use engine::{Bot, IntentRecovery, OrderLedger, PipelineOutcome, SignalPipeline};
+use events::{BotActivity, ChannelStats, EngineStats, EventBus, LogLevel, PortfolioState, WsMessage};
+use executor::{Executor, ExecutorError, LiveExecutor, Portfolio, SimulatedExecutor};
+use risk::{OrderPlan, RiskError, RiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use strategies::{KlineTransformer, Strategy, StrategyError};
+use testing::{unreachable_repo, MockAccount};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// What the strategy does on every kline.
+#[derive(Clone, Copy)]
+enum Evaluation {
+    Nothing,
+    Buy,
+    /// Signals a short entry.
+    Sell,
+    Fail,
+}
+
+struct Scripted(Evaluation);
+
+impl Strategy for Scripted {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let (side, intent) = match self.0 {
+            Evaluation::Nothing => return Ok(None),
+            Evaluation::Fail => return Err(StrategyError::IndicatorError("bad bar".to_string())),
+            Evaluation::Buy => (OrderSide::Buy, SignalIntent::OpenLong),
+            Evaluation::Sell => (OrderSide::Sell, SignalIntent::OpenShort),
+        };
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: Some(intent),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// Sizes every signal to one unit, or rejects it.
+struct FixedRisk {
+    approve: bool,
+}
+
+impl RiskManager for FixedRisk {
+    fn evaluate_signal(&self, signal: &Signal, _: &PortfolioState, _: Decimal) -> Result<OrderPlan, RiskError> {
+        if !self.approve {
+            return Err(RiskError::InsufficientEquity(Decimal::ZERO));
+        }
+        let mut order = signal.order_request.clone();
+        order.quantity = Decimal::ONE;
+        Ok(OrderPlan::single(order))
+    }
+}
+
+/// How the executor answers an order.
+#[derive(Clone, Copy)]
+enum Fill {
+    AsOrdered,
+    Rejected,
+    /// Reports a fill a thousand times the order's size, more than the portfolio can pay for.
+    Oversized,
+}
+
+struct MockExecutor {
+    inner: SimulatedExecutor,
+    fill: Fill,
+    placed: AtomicUsize,
+}
+
+#[async_trait]
+impl Executor for MockExecutor {
+    async fn execute(&self, order: &OrderRequest, kline: &Kline, best_bid: Option<Decimal>, best_ask: Option<Decimal>) -> Result<Execution, ExecutorError> {
+        self.placed.fetch_add(1, Ordering::SeqCst);
+        let mut execution = self.inner.execute(order, kline, best_bid, best_ask).await?;
+        match self.fill {
+            Fill::AsOrdered => {}
+            Fill::Rejected => return Err(ExecutorError::Rejected("order rejected".to_string())),
+            Fill::Oversized => execution.quantity *= dec!(1000),
+        }
+        Ok(execution)
+    }
+}
+
+/// A writer that collects everything a tracing subscriber formats.
+#[derive(Clone, Default)]
+struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl std::io::Write for Captured {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Harness {
+    pipeline: SignalPipeline,
+    bot: Bot,
+    executor: Arc<MockExecutor>,
+    portfolio: Arc<Mutex<Portfolio>>,
+    flags: Arc<Mutex<HashMap<String, bool>>>,
+    ledger: Arc<OrderLedger>,
+    events: events::EventSubscriber,
+    stats: Arc<EngineStats>,
+}
+
+impl Harness {
+    async fn process(&mut self, kline: &Kline) -> PipelineOutcome {
+        self.pipeline.process(&mut self.bot, kline, &MarketState::default(), Instant::now()).await
+    }
+
+    /// The messages of the error logs broadcast so far.
+    fn errors(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        while let Ok(message) = self.events.try_recv() {
+            if let WsMessage::Log(log) = message
+                && log.level == LogLevel::Error
+            {
+                errors.push(log.message);
+            }
+        }
+        errors
+    }
+}
+
+/// A pipeline trading BTCUSDT from 10,000 USDT, with the bot's strategy, the risk manager
+/// and the executor behaving as given.
+fn harness(evaluation: Evaluation, approve: bool, fill: Fill) -> Harness {
+    harness_with(testing::test_config(10).expect("load config"), evaluation, approve, fill)
+}
+
+fn harness_with(config: Config, evaluation: Evaluation, approve: bool, fill: Fill) -> Harness {
+    let executor = Arc::new(MockExecutor { inner: SimulatedExecutor::new(config.simulation.clone()), fill, placed: AtomicUsize::new(0) });
+    let portfolio = Arc::new(Mutex::new(Portfolio::new(dec!(10000))));
+    let flags = Arc::new(Mutex::new(HashMap::from([("BTCUSDT".to_string(), true)])));
+    let event_tx = EventBus::new(1024);
+    let events = event_tx.subscribe();
+    let stats = Arc::new(EngineStats::new());
+    let ledger = Arc::new(OrderLedger::new());
+    let pipeline = SignalPipeline::new(
+        &config,
+        Arc::new(FixedRisk { approve }),
+        executor.clone(),
+        Arc::clone(&portfolio),
+        // The decisions' audit trail is discarded.
+        Persistence::disabled(),
+        event_tx,
+    )
+    .with_trading_flags(Arc::clone(&flags))
+    .with_stats(Arc::clone(&stats))
+    .with_order_ledger(Arc::clone(&ledger))
+    .with_replay(true);
+    let bot = Bot {
+        symbol: "BTCUSDT".to_string(),
+        interval: "1h".to_string(),
+        leverage: 10,
+        strategy_id: StrategyId::MACrossover,
+        strategy: Box::new(Scripted(evaluation)),
+        kline_transform: KlineTransformer::new(Default::default()),
+        direction: Default::default(),
+        activity: Arc::new(BotActivity::default()),
+        holding_bars: 0,
+        last_close_time: None,
+        recent_klines: VecDeque::new(),
+        consecutive_errors: 0,
+        risk: None,
+    };
+    Harness { pipeline, bot, executor, portfolio, flags, ledger, events, stats }
+}
+
+/// Places the harness's orders on `account` through a `LiveExecutor`, recording their intents,
+/// and looks up the unresolved ones on `lookups`, if given.
+fn trade_on(harness: &mut Harness, account: Arc<MockAccount>, lookups: Option<Arc<MockAccount>>) {
+    let config = testing::test_config(10).expect("load config");
+    let executor = Arc::new(LiveExecutor::new(account));
+    let mut pipeline = SignalPipeline::new(&config, Arc::new(FixedRisk { approve: true }), executor, Arc::clone(&harness.portfolio), Persistence::disabled(), EventBus::new(1024))
+        .with_trading_flags(Arc::clone(&harness.flags))
+        .with_order_ledger(Arc::clone(&harness.ledger));
+    if let Some(lookups) = lookups {
+        pipeline = pipeline.with_order_lookups(lookups, IntentRecovery { quote_assets: QuoteAssets::new("USDT") });
+    }
+    harness.pipeline = pipeline;
+}
+
+/// The exchange's view of `order`, filled in full at `avg_price`.
+fn filled(order: &OrderRequest, avg_price: Decimal) -> OrderResponse {
+    OrderResponse {
+        client_order_id: order.client_order_id.to_string(),
+        cum_qty: order.quantity,
+        cum_quote: order.quantity * avg_price,
+        executed_qty: order.quantity,
+        order_id: 7,
+        avg_price,
+        orig_qty: order.quantity,
+        price: Decimal::ZERO,
+        reduce_only: order.reduce_only,
+        side: order.side,
+        status: "FILLED".to_string(),
+        stop_price: Decimal::ZERO,
+        symbol: order.symbol.clone(),
+        time_in_force: "GTC".to_string(),
+        order_type: "MARKET".to_string(),
+        update_time: None,
+    }
+}
+
+/// An hourly kline closing at 100.
+fn kline() -> Kline {
+    let open_time = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(100),
+        low: dec!(100),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::hours(1) - chrono::Duration::milliseconds(1),
+        interval: "1h".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn a_kline_without_a_signal_places_no_order() {
+    let mut harness = harness(Evaluation::Nothing, true, Fill::AsOrdered);
+    let outcome = harness.process(&kline()).await;
+
+    assert!(matches!(outcome, PipelineOutcome::NoSignal), "{:?}", outcome);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn a_halted_symbol_is_not_evaluated() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::AsOrdered);
+    harness.flags.lock().await.insert("BTCUSDT".to_string(), false);
+    let outcome = harness.process(&kline()).await;
+
+    assert!(matches!(outcome, PipelineOutcome::Halted), "{:?}", outcome);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn a_strategy_failure_is_handed_back() {
+    let mut harness = harness(Evaluation::Fail, true, Fill::AsOrdered);
+    let outcome = harness.process(&kline()).await;
+
+    assert!(matches!(outcome, PipelineOutcome::StrategyFailed { error: StrategyError::IndicatorError(_) }), "{:?}", outcome);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn a_rejected_signal_reports_the_reason() {
+    let mut harness = harness(Evaluation::Buy, false, Fill::AsOrdered);
+    let outcome = harness.process(&kline()).await;
+
+    let PipelineOutcome::RiskRejected { reason } = outcome else { panic!("{:?}", outcome) };
+    assert!(reason.contains("InsufficientEquity"), "{}", reason);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn log_fields_are_tracing_fields_as_well_as_broadcast() {
+    let captured = Captured::default();
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt().with_writer(move || writer.clone()).with_ansi(false).finish();
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    let mut harness = harness(Evaluation::Buy, false, Fill::AsOrdered);
+    harness.process(&kline()).await;
+
+    let output = String::from_utf8(captured.0.lock().unwrap().clone()).unwrap();
+    let line = output.lines().find(|line| line.contains("rejected; skipping it")).expect("the rejection is logged");
+    assert!(line.contains("signal_side=\"BUY\""), "{}", line);
+    assert!(line.contains("rejection_reason=\"Risk management rejected signal: InsufficientEquity(0)\""), "{}", line);
+    let fields = std::iter::from_fn(|| harness.events.try_recv().ok())
+        .find_map(|message| match message {
+            WsMessage::Log(log) if log.level == LogLevel::Warn => log.fields,
+            _ => None,
+        })
+        .expect("the rejection is broadcast");
+    assert_eq!(fields["signal_side"], "BUY");
+}
+
+#[tokio::test]
+async fn an_entry_that_cannot_cover_its_costs_is_filtered_and_counted() {
+    let mut config = testing::test_config(10).expect("load config");
+    config.simulation.taker_fee_pct = dec!(0.0004);
+    config.simulation.fee_discount_pct = Decimal::ZERO;
+    config.simulation.simulated_spread_pct = Decimal::ZERO;
+    config.risk_management.stop_loss_pct = dec!(0.0005);
+    config.risk_management.min_expected_move = Some(MinExpectedMove::default());
+    let mut harness = harness_with(config, Evaluation::Buy, true, Fill::AsOrdered);
+    let outcome = harness.process(&kline()).await;
+
+    let PipelineOutcome::RiskRejected { reason } = outcome else { panic!("{:?}", outcome) };
+    assert!(reason.contains("Expected move of 0.0005 is under 2x the round-trip cost of 0.0008"), "{}", reason);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+    assert_eq!(harness.stats.snapshot(Utc::now(), ChannelStats::new(0, 0, 0)).signals_filtered.last_1h, 1);
+}
+
+#[tokio::test]
+async fn an_approved_signal_is_executed_and_applied_to_the_portfolio() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::AsOrdered);
+    let outcome = harness.process(&kline()).await;
+
+    let PipelineOutcome::Executed { executions } = &outcome else { panic!("{:?}", outcome) };
+    assert_eq!(executions.len(), 1);
+    assert_eq!((executions[0].side, executions[0].quantity), (OrderSide::Buy, Decimal::ONE));
+    let position = harness.portfolio.lock().await.get_position("BTCUSDT").cloned().expect("an open position");
+    assert_eq!(position.quantity, Decimal::ONE);
+
+    // The position is open, so a second entry on the same side is dropped.
+    let outcome = harness.process(&kline()).await;
+    assert!(matches!(outcome, PipelineOutcome::Dropped { .. }), "{:?}", outcome);
+}
+
+#[tokio::test]
+async fn a_rejected_order_is_reported_without_halting() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::Rejected);
+    let outcome = harness.process(&kline()).await;
+
+    let PipelineOutcome::ExecutionFailed { error, executions } = &outcome else { panic!("{:?}", outcome) };
+    assert!(error.contains("order rejected"), "{}", error);
+    assert!(executions.is_empty());
+    assert!(!outcome.filled());
+    assert_eq!(harness.flags.lock().await.get("BTCUSDT"), Some(&true));
+}
+
+#[tokio::test]
+async fn a_fill_the_portfolio_cannot_apply_halts_the_bot() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::Oversized);
+    let outcome = harness.process(&kline()).await;
+
+    let PipelineOutcome::PortfolioUpdateFailed { execution, error, executions } = &outcome else { panic!("{:?}", outcome) };
+    assert_eq!(execution.quantity, dec!(1000));
+    assert!(error.contains("Not enough cash"), "{}", error);
+    assert!(executions.is_empty());
+    // The order filled on the exchange even though the portfolio does not show it.
+    assert!(outcome.filled());
+
+    assert_eq!(harness.flags.lock().await.get("BTCUSDT"), Some(&false));
+    let errors = harness.errors();
+    let halt = errors.iter().find(|message| message.contains("BOT HALTED"));
+    let halt = halt.unwrap_or_else(|| panic!("no halt alert in {:?}", errors));
+    assert!(halt.starts_with("CRITICAL: An execution for BTCUSDT filled but could not be applied to the portfolio"), "{}", halt);
+
+    let outcome = harness.process(&kline()).await;
+    assert!(matches!(outcome, PipelineOutcome::Halted), "{:?}", outcome);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn an_entry_waits_for_the_unresolved_order_on_its_symbol() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::AsOrdered);
+    let sent = OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        order_type: OrderType::Market,
+        quantity: Decimal::ONE,
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: false,
+        decision_id: None,
+    };
+    harness.ledger.insert(order_ledger::intent(&sent, Utc::now()));
+
+    let outcome = harness.process(&kline()).await;
+    let PipelineOutcome::ExecutionFailed { error, executions } = &outcome else { panic!("{:?}", outcome) };
+    assert!(error.starts_with("Order blocked: the buy 1 BTCUSDT order"), "{}", error);
+    assert!(executions.is_empty());
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+    // The entry is refused, not the bot.
+    assert_eq!(harness.flags.lock().await.get("BTCUSDT"), Some(&true));
+
+    harness.ledger.remove(sent.client_order_id);
+    let outcome = harness.process(&kline()).await;
+    assert!(matches!(outcome, PipelineOutcome::Executed { .. }), "{:?}", outcome);
+
+    // Exits are never held back.
+    harness.ledger.insert(order_ledger::intent(&sent, Utc::now()));
+    let mut dip = kline();
+    dip.low = dec!(97);
+    let outcome = harness.process(&dip).await;
+    let PipelineOutcome::Executed { executions } = &outcome else { panic!("{:?}", outcome) };
+    assert_eq!(executions[0].side, OrderSide::Sell);
+    // The executor's answer resolves only its own order.
+    assert_eq!(harness.ledger.all().len(), 1);
+}
+
+#[tokio::test]
+async fn only_exits_are_sent_when_their_intent_cannot_be_recorded() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::AsOrdered);
+    let unreachable = Persistence::spawn(unreachable_repo(), PersistenceSettings::default());
+    harness.pipeline = harness.pipeline.with_persistence(unreachable.clone()).with_replay(false);
+
+    // The entry is refused, without halting the bot or leaving an intent behind.
+    let outcome = harness.process(&kline()).await;
+    let PipelineOutcome::ExecutionFailed { error, executions } = &outcome else { panic!("{:?}", outcome) };
+    assert!(error.starts_with("Order blocked: its intent could not be recorded"), "{}", error);
+    assert!(executions.is_empty());
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+    assert_eq!(harness.flags.lock().await.get("BTCUSDT"), Some(&true));
+    assert!(harness.ledger.all().is_empty());
+
+    // A position opened while nothing is recorded...
+    harness.pipeline = harness.pipeline.with_persistence(Persistence::disabled()).with_replay(true);
+    harness.process(&kline()).await;
+    assert!(harness.portfolio.lock().await.get_position("BTCUSDT").is_some());
+
+    // ...is still closed by its stop once the ledger is unreachable.
+    harness.pipeline = harness.pipeline.with_persistence(unreachable).with_replay(false);
+    let mut dip = kline();
+    dip.low = dec!(97);
+    let outcome = harness.process(&dip).await;
+    let PipelineOutcome::Executed { executions } = &outcome else { panic!("{:?}", outcome) };
+    assert_eq!((executions[0].side, executions[0].quantity), (OrderSide::Sell, Decimal::ONE));
+    assert!(harness.portfolio.lock().await.get_position("BTCUSDT").is_none());
+}
+
+#[tokio::test]
+async fn an_order_that_filled_despite_a_placement_error_is_applied_before_the_next_entry() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::AsOrdered);
+    let account = Arc::new(MockAccount::new().with_placement_timeouts());
+    trade_on(&mut harness, account.clone(), None);
+
+    // The exchange times out, so the order may well have been placed.
+    let outcome = harness.process(&kline()).await;
+    assert!(matches!(outcome, PipelineOutcome::ExecutionFailed { .. }), "{:?}", outcome);
+    let sent = account.placed_orders().remove(0);
+    assert_eq!(harness.ledger.unresolved_on("BTCUSDT").map(|intent| intent.client_order_id), Some(sent.client_order_id));
+
+    // Until the exchange says what became of it, no entry is placed.
+    let outcome = harness.process(&kline()).await;
+    let PipelineOutcome::ExecutionFailed { error, .. } = &outcome else { panic!("{:?}", outcome) };
+    assert!(error.starts_with("Order blocked: the buy 1 BTCUSDT order"), "{}", error);
+    assert_eq!(account.placed_orders().len(), 1);
+
+    // It did fill. The next decision sees the position, instead of doubling it.
+    let exchange = Arc::new(MockAccount::new().with_order(filled(&sent, dec!(100.5)), Vec::new()));
+    trade_on(&mut harness, account.clone(), Some(exchange));
+    let outcome = harness.process(&kline()).await;
+    assert!(matches!(outcome, PipelineOutcome::Dropped { .. }), "{:?}", outcome);
+    let position = harness.portfolio.lock().await.get_position("BTCUSDT").cloned().expect("the filled order's position");
+    assert_eq!((position.quantity, position.entry_price), (Decimal::ONE, dec!(100.5)));
+    assert!(harness.ledger.all().is_empty());
+    assert_eq!(account.placed_orders().len(), 1);
+}
+
+#[tokio::test]
+async fn a_rejected_order_leaves_the_ledger() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::AsOrdered);
+    // Nothing is scripted, so the exchange rejects every order.
+    let account = Arc::new(MockAccount::new());
+    trade_on(&mut harness, account.clone(), None);
+
+    for _ in 0..2 {
+        let outcome = harness.process(&kline()).await;
+        assert!(matches!(outcome, PipelineOutcome::ExecutionFailed { .. }), "{:?}", outcome);
+        assert!(harness.ledger.all().is_empty());
+    }
+    assert_eq!(account.placed_orders().len(), 2);
+}
+
+#[tokio::test]
+async fn a_kline_through_the_stop_closes_the_position() {
+    let mut harness = harness(Evaluation::Buy, true, Fill::AsOrdered);
+    harness.process(&kline()).await;
+    // The risk manager's 2% stop sits at 98.
+    let mut dip = kline();
+    dip.low = dec!(97);
+
+    let outcome = harness.process(&dip).await;
+
+    let PipelineOutcome::Executed { executions } = &outcome else { panic!("{:?}", outcome) };
+    assert_eq!((executions[0].side, executions[0].quantity), (OrderSide::Sell, Decimal::ONE));
+    assert!(harness.portfolio.lock().await.get_position("BTCUSDT").is_none());
+}
+
+#[tokio::test]
+async fn a_long_only_bot_closes_on_a_short_signal_and_never_opens_one() {
+    let mut harness = harness(Evaluation::Sell, true, Fill::AsOrdered);
+    harness.bot.direction = TradeDirection::LongOnly;
+
+    // Flat, the short entry is dropped before it reaches the risk manager.
+    let outcome = harness.process(&kline()).await;
+    assert!(matches!(outcome, PipelineOutcome::Dropped { .. }), "{:?}", outcome);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 0);
+
+    harness.bot.strategy = Box::new(Scripted(Evaluation::Buy));
+    harness.process(&kline()).await;
+
+    // Long, it closes the position instead.
+    harness.bot.strategy = Box::new(Scripted(Evaluation::Sell));
+    let outcome = harness.process(&kline()).await;
+    let PipelineOutcome::Executed { executions } = &outcome else { panic!("{:?}", outcome) };
+    assert_eq!((executions[0].side, executions[0].quantity), (OrderSide::Sell, Decimal::ONE));
+    assert!(harness.portfolio.lock().await.get_position("BTCUSDT").is_none());
+
+    let outcome = harness.process(&kline()).await;
+    assert!(matches!(outcome, PipelineOutcome::Dropped { .. }), "{:?}", outcome);
+    assert_eq!(harness.executor.placed.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn a_position_is_flattened_between_klines_ahead_of_a_blackout() {
+    let mut config = testing::test_config(10).expect("load config");
+    // The kline closes on a Wednesday just before 01:00; the window opens at 02:00.
+    config.trading_blackouts = configuration::TradingBlackouts { weekly: vec!["Wed 02:00-03:00".parse().unwrap()], flatten_before_blackout_minutes: Some(10), ..Default::default() };
+    let mut harness = harness_with(config, Evaluation::Buy, true, Fill::AsOrdered);
+    harness.process(&kline()).await;
+    let market = MarketState { last_kline: Some(kline()), ..Default::default() };
+    let opened = kline().close_time;
+
+    // Outside the lead time there is nothing to do.
+    assert!(harness.pipeline.flatten_ahead_of_blackout(&mut harness.bot, &market, opened + chrono::Duration::minutes(30)).await.is_none());
+    assert!(harness.portfolio.lock().await.get_position("BTCUSDT").is_some());
+
+    let outcome = harness.pipeline.flatten_ahead_of_blackout(&mut harness.bot, &market, opened + chrono::Duration::minutes(55)).await;
+
+    let Some(PipelineOutcome::Executed { executions }) = &outcome else { panic!("{:?}", outcome) };
+    assert_eq!((executions[0].side, executions[0].quantity), (OrderSide::Sell, Decimal::ONE));
+    assert!(harness.portfolio.lock().await.get_position("BTCUSDT").is_none());
+    // Flat, a later tick has nothing left to close.
+    assert!(harness.pipeline.flatten_ahead_of_blackout(&mut harness.bot, &market, opened + chrono::Duration::minutes(56)).await.is_none());
+}
@@ -0,0 +1,114 @@
+use chrono::Utc;
+use core_types::{Kline, OrderSide, Position};
+use engine::event::MarketState;
+use engine::valuation::{try_broadcast_portfolio, LiquidationEstimator};
+use events::{EventBus, EventSubscriber, PortfolioState, WsMessage};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+/// A portfolio holding 2 BTC long from 100 and 1 ETH short from 50, with 1000 cash.
+fn portfolio() -> Portfolio {
+    let mut portfolio = Portfolio::new(dec!(1000));
+    for (symbol, side, quantity, entry_price) in [
+        ("BTCUSDT", OrderSide::Buy, dec!(2), dec!(100)),
+        ("ETHUSDT", OrderSide::Sell, dec!(1), dec!(50)),
+    ] {
+        portfolio.positions.insert(
+            symbol.to_string(),
+            Position {
+                position_id: Uuid::new_v4(),
+                symbol: symbol.to_string(),
+                side,
+                quantity,
+                entry_price,
+                unrealized_pnl: Decimal::ZERO,
+                last_updated: Utc::now(),
+                adds: 0,
+                last_entry_price: entry_price,
+                estimated_liquidation_price: None,
+            },
+        );
+    }
+    portfolio
+}
+
+fn mark(market_states: &mut HashMap<String, MarketState>, symbol: &str, price: Decimal) {
+    market_states.entry(symbol.to_string()).or_default().mark_price = Some(price);
+}
+
+fn next_portfolio_state(rx: &mut EventSubscriber) -> PortfolioState {
+    match rx.try_recv().expect("a portfolio broadcast") {
+        WsMessage::PortfolioState(state) => state,
+        other => panic!("unexpected message: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn broadcast_tracks_mark_price_updates() {
+    let portfolio = Arc::new(Mutex::new(portfolio()));
+    let event_tx = EventBus::new(16);
+    let mut rx = event_tx.subscribe();
+    let mut market_states = HashMap::new();
+    mark(&mut market_states, "BTCUSDT", dec!(110));
+    mark(&mut market_states, "ETHUSDT", dec!(45));
+
+    assert!(try_broadcast_portfolio(&portfolio, &market_states, &LiquidationEstimator::default(), &event_tx));
+    // BTC long: +10 * 2, ETH short: +5 * 1.
+    assert_eq!(next_portfolio_state(&mut rx).total_value, dec!(1025));
+
+    // A mark price update is reflected on the very next tick.
+    mark(&mut market_states, "BTCUSDT", dec!(90));
+    assert!(try_broadcast_portfolio(&portfolio, &market_states, &LiquidationEstimator::default(), &event_tx));
+    let state = next_portfolio_state(&mut rx);
+    // BTC long: -10 * 2, ETH short: +5 * 1.
+    assert_eq!(state.total_value, dec!(985));
+    assert_eq!(state.cash, dec!(1000));
+
+    let btc = portfolio.lock().await.get_position("BTCUSDT").cloned().unwrap();
+    assert_eq!(btc.unrealized_pnl, dec!(-20));
+}
+
+#[tokio::test]
+async fn broadcast_falls_back_to_last_kline_close() {
+    let portfolio = Arc::new(Mutex::new(portfolio()));
+    let event_tx = EventBus::new(16);
+    let mut rx = event_tx.subscribe();
+    let mut market_states = HashMap::new();
+    mark(&mut market_states, "ETHUSDT", dec!(50));
+    let now = Utc::now();
+    market_states.entry("BTCUSDT".to_string()).or_default().last_kline = Some(Kline {
+        open_time: now,
+        open: dec!(100),
+        high: dec!(106),
+        low: dec!(99),
+        close: dec!(105),
+        volume: dec!(1000),
+        close_time: now,
+        interval: "1m".to_string(),
+    });
+
+    assert!(try_broadcast_portfolio(&portfolio, &market_states, &LiquidationEstimator::default(), &event_tx));
+    assert_eq!(next_portfolio_state(&mut rx).total_value, dec!(1010));
+}
+
+#[tokio::test]
+async fn broadcast_skips_tick_when_portfolio_is_locked() {
+    let portfolio = Arc::new(Mutex::new(portfolio()));
+    let event_tx = EventBus::new(16);
+    let mut rx = event_tx.subscribe();
+    let market_states = HashMap::new();
+
+    let guard = portfolio.lock().await;
+    assert!(!try_broadcast_portfolio(&portfolio, &market_states, &LiquidationEstimator::default(), &event_tx));
+    assert!(rx.try_recv().is_err());
+    drop(guard);
+
+    assert!(try_broadcast_portfolio(&portfolio, &market_states, &LiquidationEstimator::default(), &event_tx));
+    // With no prices known, positions keep their previous (zero) unrealized P&L.
+    assert_eq!(next_portfolio_state(&mut rx).total_value, dec!(1000));
+}
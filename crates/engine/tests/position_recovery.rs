@@ -0,0 +1,192 @@
+//! Reattaches recorded position contexts after a restart, and applies each orphan position
+//! policy to the positions without one.
+
+use chrono::{DateTime, TimeZone, Utc};
+use configuration::OrphanPositionPolicy;
+use core_types::{Execution, OrderSide, StrategyId};
+use database::PositionContext;
+use engine::{PositionContexts, PositionRecovery, Recovery};
+use executor::{Portfolio, SimulatedExecutor};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::MockAccount;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 2, 0, 0, 0).unwrap()
+}
+
+/// A portfolio synced from an exchange holding a long of one BTCUSDT at 100 and a short of
+/// two ETHUSDT at 50.
+fn portfolio() -> Mutex<Portfolio> {
+    let mut portfolio = Portfolio::new(dec!(10000));
+    for (symbol, side, quantity, price) in [("BTCUSDT", OrderSide::Buy, dec!(1), dec!(100)), ("ETHUSDT", OrderSide::Sell, dec!(2), dec!(50))] {
+        let execution = Execution {
+            execution_id: Uuid::new_v4(),
+            client_order_id: Uuid::new_v4(),
+            symbol: symbol.to_string(),
+            side,
+            price,
+            quantity,
+            fee: Decimal::ZERO,
+            fee_asset: "USDT".to_string(),
+            timestamp: now(),
+            decision_id: None,
+            is_maker: false,
+            spread_cost: Decimal::ZERO,
+            placement: None,
+        };
+        portfolio.update_with_execution(&execution).expect("open position");
+    }
+    Mutex::new(portfolio)
+}
+
+/// The context recorded for a `side` position on `symbol` before the restart.
+fn recorded(symbol: &str, side: OrderSide, stop_price: Decimal) -> PositionContext {
+    PositionContext {
+        position_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        side,
+        strategy_id: Some(StrategyId::MACrossover),
+        entry_signal_id: Some(Uuid::new_v4()),
+        stop_price: Some(stop_price),
+        take_profit_price: None,
+        opened_at: now() - chrono::Duration::hours(5),
+        closed_at: None,
+    }
+}
+
+/// An exchange that quotes a mark price for BTCUSDT and ETHUSDT only.
+fn mark_prices() -> MockAccount {
+    MockAccount::new().with_mark_price("BTCUSDT", dec!(101)).with_mark_price("ETHUSDT", dec!(49))
+}
+
+async fn recover(policy: OrphanPositionPolicy, stored: Vec<PositionContext>, portfolio: &Mutex<Portfolio>, contexts: &PositionContexts) -> Vec<Recovery> {
+    let config = testing::test_config(10).expect("load config");
+    let executor = SimulatedExecutor::new(config.simulation.clone());
+    let recovery = PositionRecovery { policy, stop_loss_pct: dec!(0.02) };
+    recovery.run(stored, portfolio, contexts, &mark_prices(), &executor, now()).await
+}
+
+#[tokio::test]
+async fn recorded_contexts_are_restored_with_their_stops_and_position_ids() {
+    let portfolio = portfolio();
+    let contexts = PositionContexts::new();
+    let btc = recorded("BTCUSDT", OrderSide::Buy, dec!(90));
+    let eth = recorded("ETHUSDT", OrderSide::Sell, dec!(60));
+
+    let recoveries = recover(OrphanPositionPolicy::Flatten, vec![btc.clone(), eth.clone()], &portfolio, &contexts).await;
+
+    assert_eq!(recoveries, [Recovery::Restored { context: btc.clone() }, Recovery::Restored { context: eth.clone() }]);
+    assert_eq!(contexts.all(), [btc.clone(), eth]);
+    // The restored position's later fills join its recorded history.
+    let portfolio = portfolio.lock().await;
+    assert_eq!(portfolio.get_position("BTCUSDT").unwrap().position_id, btc.position_id);
+}
+
+#[tokio::test]
+async fn an_orphan_is_adopted_with_the_default_stop() {
+    let portfolio = portfolio();
+    let contexts = PositionContexts::new();
+
+    let recoveries = recover(OrphanPositionPolicy::AdoptWithDefaultStop, Vec::new(), &portfolio, &contexts).await;
+
+    let stops: Vec<_> = recoveries
+        .iter()
+        .map(|recovery| match recovery {
+            Recovery::Adopted { context } => (context.symbol.as_str(), context.stop_price, context.strategy_id, context.opened_at),
+            other => panic!("{:?}", other),
+        })
+        .collect();
+    assert_eq!(stops, [("BTCUSDT", Some(dec!(98)), None, now()), ("ETHUSDT", Some(dec!(51)), None, now())]);
+    assert_eq!(contexts.get("BTCUSDT").unwrap().position_id, portfolio.lock().await.get_position("BTCUSDT").unwrap().position_id);
+    assert_eq!(portfolio.lock().await.positions.len(), 2);
+}
+
+#[tokio::test]
+async fn an_orphan_is_flattened_at_the_mark_price() {
+    let portfolio = portfolio();
+    let contexts = PositionContexts::new();
+    let btc = recorded("BTCUSDT", OrderSide::Buy, dec!(90));
+
+    let recoveries = recover(OrphanPositionPolicy::Flatten, vec![btc.clone()], &portfolio, &contexts).await;
+
+    assert_eq!(recoveries.len(), 2);
+    assert_eq!(recoveries[0], Recovery::Restored { context: btc });
+    let Recovery::Flattened { symbol, execution, fills } = &recoveries[1] else { panic!("{:?}", recoveries[1]) };
+    assert_eq!(symbol, "ETHUSDT");
+    assert_eq!((execution.side, execution.quantity), (OrderSide::Buy, dec!(2)));
+    assert!(execution.price >= dec!(49), "{}", execution.price);
+    assert!(fills.iter().all(|fill| !fill.is_entry) && fills.last().unwrap().position_quantity.is_zero());
+
+    let portfolio = portfolio.lock().await;
+    assert!(portfolio.get_position("ETHUSDT").is_none());
+    assert!(portfolio.get_position("BTCUSDT").is_some());
+    assert!(contexts.get("ETHUSDT").is_none());
+}
+
+#[tokio::test]
+async fn an_orphan_that_cannot_be_priced_is_left_open() {
+    let mut portfolio = Portfolio::new(dec!(10000));
+    let execution = Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "SOLUSDT".to_string(),
+        side: OrderSide::Buy,
+        price: dec!(20),
+        quantity: dec!(1),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: now(),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    portfolio.update_with_execution(&execution).expect("open position");
+    let portfolio = Mutex::new(portfolio);
+    let contexts = PositionContexts::new();
+
+    let recoveries = recover(OrphanPositionPolicy::Flatten, Vec::new(), &portfolio, &contexts).await;
+
+    let [Recovery::FlattenFailed { symbol, error }] = recoveries.as_slice() else { panic!("{:?}", recoveries) };
+    assert_eq!(symbol, "SOLUSDT");
+    assert!(error.contains("no mark price"), "{}", error);
+    assert!(portfolio.lock().await.get_position("SOLUSDT").is_some());
+}
+
+#[tokio::test]
+async fn an_ignored_orphan_is_left_open_without_a_context() {
+    let portfolio = portfolio();
+    let contexts = PositionContexts::new();
+
+    let recoveries = recover(OrphanPositionPolicy::IgnoreWithAlert, Vec::new(), &portfolio, &contexts).await;
+
+    assert_eq!(recoveries, [Recovery::Ignored { symbol: "BTCUSDT".to_string() }, Recovery::Ignored { symbol: "ETHUSDT".to_string() }]);
+    assert!(contexts.all().is_empty());
+    assert_eq!(portfolio.lock().await.positions.len(), 2);
+}
+
+#[tokio::test]
+async fn a_context_whose_position_closed_while_down_is_reported_first() {
+    let portfolio = portfolio();
+    let contexts = PositionContexts::new();
+    // SOLUSDT closed while the engine was down, and BTCUSDT was reversed to a long.
+    let sol = recorded("SOLUSDT", OrderSide::Buy, dec!(18));
+    let btc = recorded("BTCUSDT", OrderSide::Sell, dec!(110));
+    let eth = recorded("ETHUSDT", OrderSide::Sell, dec!(60));
+
+    let recoveries = recover(OrphanPositionPolicy::IgnoreWithAlert, vec![sol.clone(), btc.clone(), eth.clone()], &portfolio, &contexts).await;
+
+    assert_eq!(
+        recoveries,
+        [
+            Recovery::ClosedWhileDown { context: btc },
+            Recovery::ClosedWhileDown { context: sol },
+            Recovery::Restored { context: eth.clone() },
+            Recovery::Ignored { symbol: "BTCUSDT".to_string() },
+        ]
+    );
+    assert_eq!(contexts.all(), [eth]);
+}
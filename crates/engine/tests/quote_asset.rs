@@ -0,0 +1,143 @@
+//! Runs the live engine with USDC as its quote asset against an account holding only USDC,
+//! on a paused clock.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use chrono::{TimeZone, Utc};
+use configuration::{Config, LiveBotConfig, LiveConfig, QuoteAssets, Versioned};
+use core_types::{Execution, Kline, OrderSide, StrategyId};
+use engine::error::EngineError;
+use engine::LiveEngine;
+use events::{EventBus, PortfolioState, WsMessage};
+use executor::SimulatedExecutor;
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use testing::{unreachable_repo, MockAccount};
+use tokio::time::Duration;
+
+/// An exchange account holding 2,500 USDC, nothing else, and no positions.
+fn usdc_account() -> MockAccount {
+    MockAccount::new().with_balance("USDC", dec!(2500)).with_symbols(&["BTCUSDC", "BTCUSDT"])
+}
+
+/// A one-minute kline closing at `close`, the `n`th of the series.
+fn kline(n: i64, close: Decimal) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600 + n * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::minutes(1) - chrono::Duration::milliseconds(1),
+        interval: "1m".to_string(),
+    }
+}
+
+/// Flat, then a rally the fast MA crosses up on, a buy.
+fn rally_klines() -> Vec<Kline> {
+    [100, 100, 100, 100, 100, 110, 120, 130]
+        .into_iter()
+        .enumerate()
+        .map(|(n, close)| kline(n as i64, Decimal::from(close)))
+        .collect()
+}
+
+/// A configuration quoted in USDC, with one bot on `symbol`.
+fn configs(symbol: &str) -> (Config, LiveConfig) {
+    let mut base_config = testing::test_config(10).expect("load config");
+    base_config.execution.quote_asset = "USDC".to_string();
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        // Only the default; the quote asset is always accepted.
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots: vec![LiveBotConfig {
+            enabled: true,
+            symbol: symbol.to_string(),
+            strategy_id: StrategyId::MACrossover,
+            interval: Some("1m".to_string()),
+            leverage: Some(20),
+            kline_transform: Default::default(),
+            direction: Default::default(),
+            quote_asset: None,
+            risk: None,
+            params: serde_json::json!({ "ma_fast_period": 2, "ma_slow_period": 4, "trend_filter_period": 4 }),
+        }],
+    };
+    (base_config, live_config)
+}
+
+fn engine(base_config: Config, live_config: LiveConfig, event_tx: EventBus) -> LiveEngine {
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+    let quote_assets = QuoteAssets::for_live(&base_config, &live_config);
+    let executor = Arc::new(SimulatedExecutor::new(base_config.simulation.clone()).with_quote_assets(quote_assets));
+    LiveEngine::new(live_config, base_config, Arc::new(usdc_account()), executor, unreachable_repo(), risk_manager, event_tx).without_persistence()
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_usdc_account_syncs_its_cash_and_sizes_orders() {
+    let (base_config, live_config) = configs("BTCUSDC");
+    let event_tx = EventBus::new(1024);
+    let mut event_rx = event_tx.subscribe();
+    let script = rally_klines().into_iter().map(|kline| ScriptEvent::EmitKline { symbol: "BTCUSDC".to_string(), kline }).collect();
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(ScriptedConnector::new().with_kline_script("1m", script));
+    let mut engine = engine(base_config, live_config, event_tx).with_connector(connector);
+    tokio::spawn(async move { engine.run().await });
+
+    // Leave time for every kline to be processed.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    let mut states: Vec<PortfolioState> = Vec::new();
+    let mut executions: Vec<Execution> = Vec::new();
+    while let Ok(message) = event_rx.try_recv() {
+        match message {
+            WsMessage::PortfolioState(state) => states.push(state),
+            WsMessage::TradeExecuted(execution) => executions.push(execution),
+            _ => {}
+        }
+    }
+
+    assert_eq!(executions.len(), 1);
+    let buy = &executions[0];
+    assert_eq!(buy.side, OrderSide::Buy);
+    assert!(buy.quantity > Decimal::ZERO);
+    assert_eq!(buy.fee_asset, "USDC");
+
+    // The synced USDC balance was the cash the buy was sized from and paid out of.
+    let state = states.last().expect("a portfolio broadcast");
+    assert_eq!(state.quote_asset, "USDC");
+    assert_eq!(state.balances.get("USDC"), Some(&dec!(2500)));
+    assert_eq!(state.cash + buy.price * buy.quantity + buy.fee, dec!(2500));
+}
+
+#[tokio::test]
+async fn a_bot_whose_symbol_does_not_settle_in_its_quote_asset_fails_initialization() {
+    let (base_config, live_config) = configs("BTCUSDT");
+    let mut engine = engine(base_config, live_config, EventBus::new(16));
+
+    match engine.init().await {
+        Err(EngineError::Configuration(message)) => {
+            assert!(message.contains("bot BTCUSDT has quote asset USDC"), "{}", message)
+        }
+        other => panic!("expected a configuration error, got {:?}", other.map(|_| ())),
+    }
+}
@@ -0,0 +1,139 @@
+//! Checks the engine's last-line safety nets: the kill switch, the global open-position cap
+//! and the hourly order throttle.
+
+use chrono::Utc;
+use core_types::{OrderRequest, OrderSide, OrderType, Position};
+use engine::safety::{OrderBlock, SafetyGuard};
+use events::{EventBus, EventSubscriber, LogLevel, WsMessage};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::{Duration, Instant};
+use uuid::Uuid;
+
+fn order(symbol: &str, side: OrderSide, reduce_only: bool) -> OrderRequest {
+    OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        side,
+        order_type: OrderType::Market,
+        quantity: dec!(1),
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only,
+        decision_id: None,
+    }
+}
+
+fn position(symbol: &str) -> Position {
+    Position {
+        position_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        side: OrderSide::Buy,
+        quantity: dec!(1),
+        entry_price: dec!(100),
+        unrealized_pnl: Decimal::ZERO,
+        last_updated: Utc::now(),
+        adds: 0,
+        last_entry_price: dec!(100),
+        estimated_liquidation_price: None,
+    }
+}
+
+struct Harness {
+    guard: SafetyGuard,
+    flags: Arc<Mutex<HashMap<String, bool>>>,
+    rx: EventSubscriber,
+}
+
+impl Harness {
+    fn new(kill_switch_file: Option<PathBuf>, max_open_positions: Option<usize>, max_orders_per_hour: Option<u32>) -> Self {
+        let flags = Arc::new(Mutex::new(HashMap::from([("BTCUSDT".to_string(), true), ("ETHUSDT".to_string(), true)])));
+        let tx = EventBus::new(64);
+        let rx = tx.subscribe();
+        let guard = SafetyGuard::new(kill_switch_file, max_open_positions, max_orders_per_hour, Arc::clone(&flags), tx);
+        Self { guard, flags, rx }
+    }
+
+    fn errors(&mut self) -> Vec<String> {
+        std::iter::from_fn(|| self.rx.try_recv().ok())
+            .filter_map(|message| match message {
+                WsMessage::Log(log) if log.level == LogLevel::Error => Some(log.message),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn touching_the_kill_switch_file_blocks_the_next_entry_but_not_closes() {
+    let path = std::env::temp_dir().join(format!("zenith-kill-switch-{}", Uuid::new_v4()));
+    let mut harness = Harness::new(Some(path.clone()), None, None);
+    let portfolio = Portfolio::new(dec!(1000));
+    let now = Instant::now();
+
+    assert_eq!(harness.guard.check(&order("BTCUSDT", OrderSide::Buy, false), &portfolio, now).await, Ok(()));
+
+    std::fs::write(&path, "").unwrap();
+    let blocked = harness.guard.check(&order("BTCUSDT", OrderSide::Sell, false), &portfolio, now).await;
+    let closed = harness.guard.check(&order("BTCUSDT", OrderSide::Sell, true), &portfolio, now).await;
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(matches!(blocked, Err(OrderBlock::KillSwitch { .. })), "{:?}", blocked);
+    assert_eq!(closed, Ok(()));
+    let errors = harness.errors();
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("ORDER BLOCKED: Refused Sell order for 1 BTCUSDT because the kill switch is engaged"), "{}", errors[0]);
+
+    // Removing the file releases the switch.
+    assert_eq!(harness.guard.check(&order("BTCUSDT", OrderSide::Buy, false), &portfolio, now).await, Ok(()));
+}
+
+#[tokio::test]
+async fn the_open_position_cap_only_blocks_new_symbols() {
+    let mut harness = Harness::new(None, Some(1), None);
+    let mut portfolio = Portfolio::new(dec!(1000));
+    portfolio.positions.insert("BTCUSDT".to_string(), position("BTCUSDT"));
+    let now = Instant::now();
+
+    // Adding to or reversing the open position is not a new position.
+    assert_eq!(harness.guard.check(&order("BTCUSDT", OrderSide::Buy, false), &portfolio, now).await, Ok(()));
+    assert_eq!(
+        harness.guard.check(&order("ETHUSDT", OrderSide::Buy, false), &portfolio, now).await,
+        Err(OrderBlock::MaxOpenPositions { open: 1, max: 1 })
+    );
+    assert_eq!(harness.flags.lock().await.get("ETHUSDT"), Some(&true));
+}
+
+#[tokio::test]
+async fn exceeding_the_hourly_order_cap_halts_the_bot() {
+    let mut harness = Harness::new(None, None, Some(3));
+    let portfolio = Portfolio::new(dec!(1000));
+    let start = Instant::now();
+    let minute = |n: u64| start + Duration::from_secs(60 * n);
+
+    // A strategy churning an entry and a close every minute.
+    for n in 0..3 {
+        let side = if n % 2 == 0 { OrderSide::Buy } else { OrderSide::Sell };
+        assert_eq!(harness.guard.check(&order("BTCUSDT", side, n % 2 == 1), &portfolio, minute(n)).await, Ok(()));
+    }
+    // Other bots have their own budget.
+    assert_eq!(harness.guard.check(&order("ETHUSDT", OrderSide::Buy, false), &portfolio, minute(3)).await, Ok(()));
+
+    let blocked = harness.guard.check(&order("BTCUSDT", OrderSide::Buy, false), &portfolio, minute(3)).await;
+    assert_eq!(blocked, Err(OrderBlock::OrderRateExceeded { symbol: "BTCUSDT".to_string(), max: 3 }));
+    assert_eq!(harness.flags.lock().await.get("BTCUSDT"), Some(&false));
+    assert_eq!(harness.flags.lock().await.get("ETHUSDT"), Some(&true));
+    let errors = harness.errors();
+    assert_eq!(errors.len(), 2);
+    assert!(errors[1].starts_with("BOT HALTED: Trading for BTCUSDT"), "{}", errors[1]);
+
+    // The window rolls: an hour after the first order, one slot is free again.
+    assert!(harness.guard.check(&order("BTCUSDT", OrderSide::Buy, false), &portfolio, minute(59)).await.is_err());
+    assert_eq!(harness.guard.check(&order("BTCUSDT", OrderSide::Buy, false), &portfolio, minute(60)).await, Ok(()));
+}
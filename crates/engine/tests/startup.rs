@@ -0,0 +1,77 @@
+//! The live engine refuses to start against an account it cannot trade safely, or without
+//! the order ledger that tells it what became of the orders sent before a restart.
+
+use configuration::{LiveBotConfig, LiveConfig, Versioned};
+use core_types::StrategyId;
+use database::DbRepository;
+use engine::error::EngineError;
+use engine::LiveEngine;
+use events::EventBus;
+use executor::SimulatedExecutor;
+use risk::SimpleRiskManager;
+use std::sync::Arc;
+use testing::{unreachable_repo, MockAccount};
+
+/// An engine trading BTCUSDT on `account`, recording to `repo`.
+fn engine(account: MockAccount, repo: DbRepository) -> LiveEngine {
+    let base_config = testing::test_config(10).expect("load config");
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots: vec![LiveBotConfig {
+            enabled: true,
+            symbol: "BTCUSDT".to_string(),
+            strategy_id: StrategyId::MACrossover,
+            interval: Some("1m".to_string()),
+            leverage: Some(20),
+            kline_transform: Default::default(),
+            direction: Default::default(),
+            quote_asset: None,
+            risk: None,
+            params: serde_json::json!({ "ma_fast_period": 2, "ma_slow_period": 4, "trend_filter_period": 4 }),
+        }],
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+    let executor = Arc::new(SimulatedExecutor::new(base_config.simulation.clone()));
+    LiveEngine::new(live_config, base_config, Arc::new(account), executor, repo, risk_manager, EventBus::new(64))
+}
+
+#[tokio::test]
+async fn an_account_in_hedge_mode_fails_initialization() {
+    let mut engine = engine(MockAccount::new().with_hedge_mode(), unreachable_repo());
+
+    match engine.init().await {
+        Err(EngineError::Configuration(message)) => assert!(message.contains("hedge mode"), "{}", message),
+        other => panic!("expected a configuration error, got {:?}", other.map(|_| ())),
+    }
+}
+
+#[tokio::test]
+async fn an_order_ledger_that_fails_to_load_fails_initialization() {
+    let mut unrecorded = engine(MockAccount::new(), unreachable_repo());
+    match unrecorded.init().await {
+        Err(EngineError::Database(_)) => {}
+        other => panic!("expected a database error, got {:?}", other.map(|_| ())),
+    }
+
+    // Without persistence there is no ledger to load, and the same engine starts.
+    let mut engine = engine(MockAccount::new(), unreachable_repo()).without_persistence();
+    engine.init().await.expect("initialize");
+}
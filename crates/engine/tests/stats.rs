@@ -0,0 +1,131 @@
+//! Drives klines through the live engine and reads its activity stats back, on a paused clock.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use chrono::{TimeZone, Utc};
+use configuration::{LiveBotConfig, LiveConfig, Versioned};
+use core_types::{Kline, StrategyId};
+use engine::LiveEngine;
+use events::{ChannelStats, EngineStats, EngineStatsSnapshot, EventBus};
+use executor::SimulatedExecutor;
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use testing::{unreachable_repo, MockAccount};
+use tokio::time::Duration;
+
+/// A one-minute kline closing at `close`, the `n`th of the series.
+fn kline(n: i64, close: Decimal) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600 + n * 60, 0).unwrap();
+    Kline {
+        open_time,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::minutes(1) - chrono::Duration::milliseconds(1),
+        interval: "1m".to_string(),
+    }
+}
+
+/// Flat, then a rally the fast MA crosses up on (a buy), a drop it crosses down on (a sell,
+/// which closes the long and goes short) and another rally (a buy, which closes the short and
+/// goes long again).
+fn crossing_klines() -> Vec<Kline> {
+    [100, 100, 100, 100, 100, 110, 120, 130, 100, 80, 60, 60, 90]
+        .into_iter()
+        .enumerate()
+        .map(|(n, close)| kline(n as i64, Decimal::from(close)))
+        .collect()
+}
+
+/// Runs a BTCUSDT bot over `klines` and returns the stats once the engine has settled.
+async fn run_engine(klines: Vec<Kline>, max_orders_per_hour: Option<u32>) -> EngineStatsSnapshot {
+    let mut base_config = testing::test_config(10).expect("load config");
+    // Wide enough that no stop fires, so every exit is a reversal.
+    base_config.risk_management.stop_loss_pct = dec!(0.5);
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots: vec![LiveBotConfig {
+            enabled: true,
+            symbol: "BTCUSDT".to_string(),
+            strategy_id: StrategyId::MACrossover,
+            interval: Some("1m".to_string()),
+            leverage: Some(20),
+            kline_transform: Default::default(),
+            direction: Default::default(),
+            quote_asset: None,
+            risk: None,
+            params: serde_json::json!({ "ma_fast_period": 2, "ma_slow_period": 4, "trend_filter_period": 4 }),
+        }],
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+    let executor = Arc::new(SimulatedExecutor::new(base_config.simulation.clone()));
+    let event_tx = EventBus::new(1024);
+    let _event_rx = event_tx.subscribe();
+
+    let script = klines.into_iter().map(|kline| ScriptEvent::EmitKline { symbol: "BTCUSDT".to_string(), kline }).collect();
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(ScriptedConnector::new().with_kline_script("1m", script));
+    let stats = Arc::new(EngineStats::new());
+    let mut engine = LiveEngine::new(live_config, base_config, Arc::new(MockAccount::new()), executor, unreachable_repo(), risk_manager, event_tx)
+        .without_persistence()
+        .with_connector(connector)
+        .with_stats(Arc::clone(&stats));
+    tokio::spawn(async move { engine.run().await });
+
+    // Leave time for every kline to be processed and for the once-a-second stats refresh.
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    stats.snapshot(Utc::now(), ChannelStats::new(0, 0, 0))
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_snapshot_counts_klines_signals_and_fills() {
+    let klines = crossing_klines();
+    let last_close_time = klines.last().unwrap().close_time;
+
+    let snapshot = run_engine(klines, None).await;
+
+    assert_eq!(snapshot.signals.last_1h, 3);
+    assert_eq!(snapshot.signals.last_24h, 3);
+    // The entry, then a close and an entry for each of the two reversals.
+    assert_eq!(snapshot.orders_filled.last_1h, 5);
+    assert_eq!(snapshot.orders_filled.last_24h, 5);
+    assert_eq!(snapshot.bots.len(), 1);
+    assert_eq!(snapshot.bots[0].symbol, "BTCUSDT");
+    assert_eq!(snapshot.bots[0].last_kline_close_time, Some(last_close_time));
+    assert!(!snapshot.bots[0].halted);
+    assert_eq!(snapshot.bots[0].consecutive_losses, 0);
+    assert!(snapshot.halted_bots.is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_bot_halted_by_the_order_throttle_is_reported() {
+    // The throttle lets the first buy through and the sell's close bypasses it, but the short
+    // entry after it is refused and halts the bot before the last crossing.
+    let snapshot = run_engine(crossing_klines(), Some(1)).await;
+
+    assert_eq!(snapshot.signals.last_1h, 2);
+    assert_eq!(snapshot.orders_filled.last_1h, 2);
+    assert!(snapshot.bots[0].halted);
+    assert_eq!(snapshot.halted_bots, ["BTCUSDT"]);
+}
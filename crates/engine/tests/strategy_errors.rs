@@ -0,0 +1,182 @@
+//! A bot whose strategy fails is reset and re-warmed from its recent klines, and halted with
+//! an alert once the failures run past `max_strategy_errors`.
+
+use api_client::scripted::{ScriptEvent, ScriptedConnector};
+use api_client::MarketDataConnector;
+use chrono::{TimeZone, Utc};
+use configuration::{LiveBotConfig, LiveConfig, Versioned};
+use core_types::{Kline, Signal, StrategyId};
+use engine::{LiveEngine, StrategyFactory};
+use events::{EventBus, EventSubscriber, LogLevel, WsMessage};
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::{Arc, Mutex};
+use strategies::{Strategy, StrategyError};
+use testing::{unreachable_repo, MockAccount, NoOrders};
+use tokio::time::Duration;
+
+/// What a flaky strategy was asked to do: evaluate the kline numbered `n`, or reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Call {
+    Evaluate(i64),
+    Reset,
+}
+
+type Calls = Arc<Mutex<Vec<Call>>>;
+
+/// Needs two bars to warm up, never signals and fails on the klines numbered in `failing`.
+/// Each kline is numbered by its close price.
+struct Flaky {
+    failing: Vec<i64>,
+    calls: Calls,
+}
+
+impl Strategy for Flaky {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let n = kline.close.try_into().unwrap();
+        self.calls.lock().unwrap().push(Call::Evaluate(n));
+        if self.failing.contains(&n) {
+            return Err(StrategyError::IndicatorError(format!("bad bar {}", n)));
+        }
+        Ok(None)
+    }
+
+    fn required_warmup_bars(&self) -> usize {
+        2
+    }
+
+    fn reset(&mut self) {
+        self.calls.lock().unwrap().push(Call::Reset);
+    }
+}
+
+/// The hourly kline numbered `n`, closing at `n`.
+fn kline(n: i64) -> Kline {
+    let open_time = Utc.timestamp_opt(1_700_000_000 - 1_700_000_000 % 3600 + n * 3600, 0).unwrap();
+    let price = Decimal::from(n);
+    Kline {
+        open_time,
+        open: price,
+        high: price,
+        low: price,
+        close: price,
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::hours(1) - chrono::Duration::milliseconds(1),
+        interval: "1h".to_string(),
+    }
+}
+
+/// Starts an engine with one hourly BTCUSDT bot whose strategy fails on the klines numbered
+/// in `failing`, fed klines `0..count`.
+fn start_engine(failing: Vec<i64>, max_strategy_errors: u32, count: i64) -> (Calls, EventSubscriber) {
+    let base_config = testing::test_config(10).expect("load config");
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: "1h".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: Some(3600 * 24),
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots: vec![LiveBotConfig {
+            enabled: true,
+            symbol: "BTCUSDT".to_string(),
+            strategy_id: StrategyId::MACrossover,
+            interval: Some("1h".to_string()),
+            leverage: Some(5),
+            kline_transform: Default::default(),
+            direction: Default::default(),
+            quote_asset: None,
+            risk: None,
+            params: serde_json::json!({}),
+        }],
+    };
+    let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone()).unwrap());
+
+    let calls = Calls::default();
+    let recorded = calls.clone();
+    let factory: StrategyFactory = Arc::new(move |_, _: &LiveBotConfig| {
+        Ok(Box::new(Flaky { failing: failing.clone(), calls: recorded.clone() }) as Box<dyn Strategy>)
+    });
+
+    let script = (0..count).map(|n| ScriptEvent::EmitKline { symbol: "BTCUSDT".to_string(), kline: kline(n) }).collect();
+    let connector: Arc<dyn MarketDataConnector> = Arc::new(ScriptedConnector::new().with_kline_script("1h", script));
+    let event_tx = EventBus::new(1024);
+    let event_rx = event_tx.subscribe();
+    let mut engine =
+        LiveEngine::new(live_config, base_config, Arc::new(MockAccount::new()), Arc::new(NoOrders), unreachable_repo(), risk_manager, event_tx)
+            .without_persistence()
+            .with_connector(connector)
+            .with_strategy_factory(factory);
+    tokio::spawn(async move { engine.run().await });
+    (calls, event_rx)
+}
+
+/// The messages of the error logs broadcast so far.
+fn error_logs(rx: &mut EventSubscriber) -> Vec<String> {
+    let mut errors = Vec::new();
+    while let Ok(message) = rx.try_recv() {
+        if let WsMessage::Log(log) = message
+            && log.level == LogLevel::Error
+        {
+            errors.push(log.message);
+        }
+    }
+    errors
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_failing_strategy_is_rewarmed_then_its_bot_halted() {
+    let (calls, mut rx) = start_engine(vec![3, 4, 5], 3, 8);
+    tokio::time::sleep(Duration::from_secs(60)).await;
+
+    use Call::*;
+    // Each of the first two failures resets the strategy and replays the last two good bars;
+    // the third halts the bot, which then evaluates nothing more.
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            Evaluate(0), Evaluate(1), Evaluate(2),
+            Evaluate(3), Reset, Evaluate(1), Evaluate(2),
+            Evaluate(4), Reset, Evaluate(1), Evaluate(2),
+            Evaluate(5),
+        ]
+    );
+    let errors = error_logs(&mut rx);
+    assert_eq!(errors.len(), 1, "{:?}", errors);
+    assert!(errors[0].contains("BOT HALTED"), "{}", errors[0]);
+    assert!(errors[0].contains("BTCUSDT 1h"), "{}", errors[0]);
+    assert!(errors[0].contains("bad bar 5"), "{}", errors[0]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_good_kline_clears_the_error_count() {
+    let (calls, mut rx) = start_engine(vec![2, 4], 2, 7);
+    tokio::time::sleep(Duration::from_secs(60)).await;
+
+    use Call::*;
+    assert_eq!(
+        *calls.lock().unwrap(),
+        [
+            Evaluate(0), Evaluate(1),
+            Evaluate(2), Reset, Evaluate(0), Evaluate(1),
+            Evaluate(3),
+            Evaluate(4), Reset, Evaluate(1), Evaluate(3),
+            Evaluate(5), Evaluate(6),
+        ]
+    );
+    assert_eq!(error_logs(&mut rx), Vec::<String>::new());
+}
@@ -0,0 +1,16 @@
+//! Writes the JSON Schema of the WebSocket protocol, for the frontend to generate its types
+//! from, to the given file or stdout:
+//!
+//! ```text
+//! cargo run -p events --bin ws-protocol -- frontend/src/types/ws-protocol.json
+//! ```
+
+use std::io::Write;
+
+fn main() -> std::io::Result<()> {
+    let schema = serde_json::to_string_pretty(&events::ws_protocol_schema())? + "\n";
+    match std::env::args().nth(1) {
+        Some(path) => std::fs::write(path, schema),
+        None => std::io::stdout().write_all(schema.as_bytes()),
+    }
+}
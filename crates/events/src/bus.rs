@@ -0,0 +1,187 @@
+//! The channel carrying `WsMessage`s from the engine to the web server and alerter.
+//!
+//! Discrete events (logs, trades, backtest progress) go through a bounded broadcast channel,
+//! so every subscriber sees each one. State updates, where only the latest value matters
+//! (the portfolio, each symbol and interval's kline, the latency report, the feed status and
+//! the rolling stats), are coalesced instead: a subscriber that falls behind skips the
+//! superseded values rather than lagging on, and the frequent kline updates never crowd
+//! discrete events out of the broadcast buffer. Consumers that must see every kline, such as
+//! the server's kline history, subscribe to them on a broadcast channel of their own.
+
+use crate::messages::{KlineData, WsMessage};
+use std::collections::{BTreeMap, VecDeque};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::{broadcast, watch};
+
+/// The latest value of a state topic, stamped with the bus-wide sequence number it was sent at.
+type Stamped = (u64, WsMessage);
+
+/// The latest value of every state topic.
+#[derive(Debug, Default)]
+struct LatestState {
+    /// The sequence number of the most recent update.
+    sequence: u64,
+    portfolio: Option<Stamped>,
+    /// By symbol and interval.
+    klines: BTreeMap<(String, String), Stamped>,
+    latency: Option<Stamped>,
+    feed_status: Option<Stamped>,
+    rolling_stats: Option<Stamped>,
+}
+
+impl LatestState {
+    /// Replaces the value of `message`'s topic.
+    fn update(&mut self, message: WsMessage) {
+        self.sequence += 1;
+        let stamped = (self.sequence, message);
+        match &stamped.1 {
+            WsMessage::PortfolioState(_) => self.portfolio = Some(stamped),
+            WsMessage::KlineData(data) => {
+                self.klines.insert((data.symbol.clone(), data.interval.clone()), stamped);
+            }
+            WsMessage::LatencyReport(_) => self.latency = Some(stamped),
+            WsMessage::FeedStatus(_) => self.feed_status = Some(stamped),
+            WsMessage::RollingStats(_) => self.rolling_stats = Some(stamped),
+            _ => unreachable!("only state messages are coalesced"),
+        }
+    }
+
+    /// The values updated after `sequence`, oldest first.
+    fn since(&self, sequence: u64) -> Vec<WsMessage> {
+        let mut updates: Vec<&Stamped> = self
+            .portfolio
+            .iter()
+            .chain(self.klines.values())
+            .chain(self.latency.iter())
+            .chain(self.feed_status.iter())
+            .chain(self.rolling_stats.iter())
+            .filter(|(stamp, _)| *stamp > sequence)
+            .collect();
+        updates.sort_by_key(|(stamp, _)| *stamp);
+        updates.into_iter().map(|(_, message)| message.clone()).collect()
+    }
+}
+
+/// Whether only the latest `message` of its kind (per symbol and interval, for klines) is
+/// worth delivering.
+fn is_state(message: &WsMessage) -> bool {
+    matches!(message, WsMessage::PortfolioState(_) | WsMessage::KlineData(_) | WsMessage::LatencyReport(_) | WsMessage::FeedStatus(_) | WsMessage::RollingStats(_))
+}
+
+/// The sending side of the bus. Cloning it gives another sender on the same bus.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    events: broadcast::Sender<WsMessage>,
+    state: watch::Sender<LatestState>,
+    /// Every kline, uncoalesced.
+    klines: broadcast::Sender<KlineData>,
+}
+
+impl EventBus {
+    /// Creates a bus whose discrete events, and klines subscribed to on their own, are
+    /// buffered up to `capacity` per subscriber.
+    pub fn new(capacity: usize) -> Self {
+        let (events, _) = broadcast::channel(capacity);
+        let (state, _) = watch::channel(LatestState::default());
+        let (klines, _) = broadcast::channel(capacity);
+        Self { events, state, klines }
+    }
+
+    /// Sends `message` to every subscriber, returning how many there are. A state update
+    /// replaces any earlier one of its topic that a subscriber has not received yet.
+    pub fn send(&self, message: WsMessage) -> usize {
+        if is_state(&message) {
+            if let WsMessage::KlineData(data) = &message {
+                // Only fails when nobody subscribed to the klines.
+                let _ = self.klines.send(data.clone());
+            }
+            self.state.send_modify(|state| state.update(message));
+            self.state.receiver_count()
+        } else {
+            // A send only fails when nobody is subscribed.
+            self.events.send(message).unwrap_or(0)
+        }
+    }
+
+    /// Subscribes to the messages sent from now on.
+    pub fn subscribe(&self) -> EventSubscriber {
+        let state = self.state.subscribe();
+        let seen = state.borrow().sequence;
+        EventSubscriber { events: self.events.subscribe(), state, seen, pending: VecDeque::new() }
+    }
+
+    /// Subscribes to every kline sent from now on, none of them coalesced. `Lagged` reports
+    /// klines lost because the subscriber fell more than the bus capacity behind.
+    pub fn subscribe_klines(&self) -> broadcast::Receiver<KlineData> {
+        self.klines.subscribe()
+    }
+
+    /// The number of subscribers.
+    pub fn receiver_count(&self) -> usize {
+        self.events.receiver_count()
+    }
+
+    /// The number of discrete events the slowest subscriber has yet to receive.
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Whether every subscriber has received every discrete event.
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// The receiving side of the bus.
+#[derive(Debug)]
+pub struct EventSubscriber {
+    events: broadcast::Receiver<WsMessage>,
+    state: watch::Receiver<LatestState>,
+    /// The sequence number of the latest state update taken.
+    seen: u64,
+    /// State updates taken but not yet returned.
+    pending: VecDeque<WsMessage>,
+}
+
+impl EventSubscriber {
+    /// Waits for the next message. Discrete events come before state updates; the state
+    /// updates returned are the latest of each topic. `Lagged` reports discrete events lost
+    /// because this subscriber fell more than the bus capacity behind.
+    pub async fn recv(&mut self) -> Result<WsMessage, RecvError> {
+        loop {
+            match self.events.try_recv() {
+                Ok(message) => return Ok(message),
+                Err(TryRecvError::Lagged(skipped)) => return Err(RecvError::Lagged(skipped)),
+                Err(TryRecvError::Empty | TryRecvError::Closed) => {}
+            }
+            if let Some(message) = self.next_state() {
+                return Ok(message);
+            }
+            tokio::select! {
+                biased;
+                event = self.events.recv() => return event,
+                changed = self.state.changed() => changed.map_err(|_| RecvError::Closed)?,
+            }
+        }
+    }
+
+    /// Returns the next message if one is ready, like `recv` without waiting.
+    pub fn try_recv(&mut self) -> Result<WsMessage, TryRecvError> {
+        match self.events.try_recv() {
+            Err(TryRecvError::Empty) => {}
+            result => return result,
+        }
+        self.next_state().ok_or(TryRecvError::Empty)
+    }
+
+    /// The next state update not yet returned, taking the latest ones once those taken
+    /// before have all been returned.
+    fn next_state(&mut self) -> Option<WsMessage> {
+        if self.pending.is_empty() {
+            let state = self.state.borrow_and_update();
+            self.pending.extend(state.since(self.seen));
+            self.seen = state.sequence;
+        }
+        self.pending.pop_front()
+    }
+}
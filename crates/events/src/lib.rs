@@ -7,9 +7,15 @@
 //! language for all real-time state synchronization.
 
 // Declare the modules that make up this crate.
+pub mod bus;
 pub mod error;
 pub mod messages;
+pub mod protocol;
+pub mod stats;
 
 // Re-export the core types to provide a clean public API.
+pub use bus::{EventBus, EventSubscriber};
 pub use error::EventsError;
-pub use messages::{LogLevel, LogMessage, PortfolioState, WsMessage, KlineData};
\ No newline at end of file
+pub use messages::{BacktestProgress, FeedHealth, FeedState, FeedStatus, LatencyReport, LatencyStats, LogLevel, LogMessage, PortfolioState, RollingStats, RollingWindowStats, SymbolLatency, WsClientMessage, WsMessage, KlineData};
+pub use protocol::{encode_frame, negotiate_version, ws_protocol_schema, WsEnvelope, DEFAULT_WS_PROTOCOL_VERSION, MIN_WS_PROTOCOL_VERSION, WS_PROTOCOL_VERSION};
+pub use stats::{ActivityCounts, BotActivity, BotStats, ChannelStats, EngineStats, EngineStatsSnapshot, ExecutionQualityStats, EVENT_CHANNEL_CAPACITY};
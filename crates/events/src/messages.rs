@@ -1,10 +1,13 @@
 use chrono::{DateTime, Utc};
 use core_types::{Execution, Position, Kline};
 use rust_decimal::Decimal;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
 
 /// Enum representing the severity of a log message for structured logging.
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub enum LogLevel {
     Info,
     Warn,
@@ -12,36 +15,171 @@ pub enum LogLevel {
 }
 
 /// A structured log message to be sent over WebSocket.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct LogMessage {
     pub timestamp: DateTime<Utc>,
     pub level: LogLevel,
     pub message: String,
+    /// Structured context for the message (symbol, decision ID, prices, ...), if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<serde_json::Value>,
 }
 
 /// A complete snapshot of the portfolio's current state.
 /// This message provides the frontend with all the data needed to render the main dashboard.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct PortfolioState {
     pub timestamp: DateTime<Utc>,
+    /// The asset cash, total value and P&L are denominated in, e.g. "USDT".
+    #[serde(default = "default_quote_asset")]
+    pub quote_asset: String,
+    /// The available cash in the quote asset, including the converted value of other
+    /// collateral assets.
     pub cash: Decimal,
+    /// The collateral balances by asset, in each asset's own units.
+    #[serde(default)]
+    pub balances: BTreeMap<String, Decimal>,
     pub total_value: Decimal,
     pub positions: Vec<Position>,
+    /// The cumulative realized P&L, net of all fees paid.
+    pub realized_pnl: Decimal,
+    /// The cumulative fees paid across all executions.
+    pub total_fees_paid: Decimal,
+}
+
+/// Snapshots from before the quote asset was configurable are in USDT.
+fn default_quote_asset() -> String {
+    "USDT".to_string()
 }
 
 /// A kline data message containing symbol and kline information.
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
 pub struct KlineData {
     pub symbol: String,
+    /// The interval of the bot the kline closed for. A symbol traded in several intervals has
+    /// a stream of klines in each.
+    pub interval: String,
     pub kline: Kline,
+    /// The kline's place among the broadcast klines of its symbol and interval, counting from
+    /// 1 when the engine starts. A client joining from `GET /api/live/klines/:symbol` skips
+    /// the klines up to the snapshot's `snapshot_seq`, and refetches the snapshot when a
+    /// number is skipped.
+    #[serde(default)]
+    pub seq: u64,
+}
+
+/// Percentiles of one latency measurement over a reporting window, in milliseconds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LatencyStats {
+    pub samples: u64,
+    pub p50_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// The latency of one bot's live decision path over a reporting window. A stage is `None`
+/// when no kline reached it during the window (e.g., no signal was generated).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SymbolLatency {
+    pub symbol: String,
+    /// From the kline's exchange close time to its local receipt.
+    pub feed_delay: Option<LatencyStats>,
+    /// From receipt to the strategy's decision.
+    pub strategy: Option<LatencyStats>,
+    /// From the strategy's decision to the risk manager's.
+    pub risk: Option<LatencyStats>,
+    /// From the order's submission to the executor's acknowledgement.
+    pub execution: Option<LatencyStats>,
+    /// From receipt to the executor's acknowledgement.
+    pub decision: Option<LatencyStats>,
+}
+
+/// Per-symbol latency percentiles for the klines processed since the previous report.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct LatencyReport {
+    pub timestamp: DateTime<Utc>,
+    /// The length of the window the report covers.
+    pub window_secs: u64,
+    pub symbols: Vec<SymbolLatency>,
+}
+
+/// How current a feed is, judged by how long ago its latest kline closed against its
+/// interval. A quiet market still closes a kline every interval, so an overdue kline means
+/// the feed is lagging or dead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub enum FeedHealth {
+    /// The next kline is not overdue by more than a quarter of the interval.
+    Healthy,
+    /// The next kline is overdue, but the feed is not yet stale.
+    Delayed,
+    /// Nothing for three intervals, the default feed timeout of the dead-man's switch.
+    Stale,
+}
+
+/// The market data last received for one subscribed symbol and interval. The book ticker
+/// and mark price streams are per symbol, so every interval of a symbol shares them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FeedState {
+    pub symbol: String,
+    pub interval: String,
+    /// The close time of the latest kline received.
+    pub kline: Option<DateTime<Utc>>,
+    /// When the latest book ticker update was received.
+    pub book_ticker: Option<DateTime<Utc>>,
+    /// When the latest mark price update was received.
+    pub mark_price: Option<DateTime<Utc>>,
+    pub status: FeedHealth,
+}
+
+/// The health of every subscribed feed, so a dashboard can tell a quiet market from a dead
+/// feed. Broadcast periodically by the live engine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct FeedStatus {
+    pub timestamp: DateTime<Utc>,
+    pub feeds: Vec<FeedState>,
+}
+
+/// The live performance of one trailing window, e.g. the last 7 days. Every figure is null
+/// until the live history spans the whole window; the win rate also without closed trades, and
+/// the Sharpe Ratio without volatility in the window's daily returns.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RollingWindowStats {
+    /// The length of the window in days.
+    pub days: u32,
+    /// The start of the window. It ends when its stats were computed.
+    pub from: DateTime<Utc>,
+    /// The realized P&L of the trades closed in the window, net of the fees paid in it.
+    pub net_pnl: Option<Decimal>,
+    /// The trades closed in the window, partial closes included.
+    pub trade_count: Option<u32>,
+    pub win_rate_pct: Option<Decimal>,
+    /// The annualized Sharpe Ratio of the daily returns of the live equity.
+    pub sharpe_ratio: Option<Decimal>,
+}
+
+/// The live performance over trailing windows, shortest first. Recomputed hourly by the web
+/// server.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RollingStats {
+    pub computed_at: DateTime<Utc>,
+    pub windows: Vec<RollingWindowStats>,
+}
+
+/// How far a backtest started through the API has got.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct BacktestProgress {
+    pub run_id: Uuid,
+    /// The share of the run's bars simulated so far, from 0 to 100.
+    pub pct: f64,
+    pub bars_done: u64,
 }
 
 /// The top-level WebSocket message enum.
 /// All communication from the server to the client will be one of these variants.
 ///
-/// The `#[serde(tag = "type", content = "payload")]` attribute is a powerful `serde` feature.
-/// It serializes the enum into a clean JSON object, which is easy for the frontend to handle.
-/// For example, a `Log` variant would look like:
+/// Clients receive it wrapped in a `WsEnvelope`, which pins its shape to a protocol version.
+/// Its own serialized form is the version 1 wire format, still sent to clients that only
+/// support that. For example, a `Log` variant would look like:
 /// `{
 ///   "type": "Log",
 ///   "payload": {
@@ -63,4 +201,42 @@ pub enum WsMessage {
     Connected,
     /// Real-time kline data for a symbol.
     KlineData(KlineData),
-}
\ No newline at end of file
+    /// Periodic per-symbol latency percentiles of the live decision path.
+    LatencyReport(LatencyReport),
+    /// Periodic progress of a backtest started through the API.
+    BacktestProgress(BacktestProgress),
+    /// Periodic freshness of each subscribed symbol and interval's market data.
+    FeedStatus(FeedStatus),
+    /// Hourly live performance over trailing windows.
+    RollingStats(RollingStats),
+}
+
+impl WsMessage {
+    /// The name of the variant, the `type` of its frames.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            WsMessage::Log(_) => "Log",
+            WsMessage::PortfolioState(_) => "PortfolioState",
+            WsMessage::TradeExecuted(_) => "TradeExecuted",
+            WsMessage::Connected => "Connected",
+            WsMessage::KlineData(_) => "KlineData",
+            WsMessage::LatencyReport(_) => "LatencyReport",
+            WsMessage::BacktestProgress(_) => "BacktestProgress",
+            WsMessage::FeedStatus(_) => "FeedStatus",
+            WsMessage::RollingStats(_) => "RollingStats",
+        }
+    }
+}
+
+/// A message sent by a WebSocket client to the server, tagged like `WsMessage`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "type", content = "payload")]
+pub enum WsClientMessage {
+    /// Authenticates the connection, as the first message, when the server requires a token:
+    /// `{"type": "Auth", "payload": {"token": "..."}}`.
+    Auth { token: String },
+    /// Announces the newest protocol version the client understands, so the server can speak
+    /// an older one to it or close the socket if it is too old:
+    /// `{"type": "ClientHello", "payload": {"supported_version": 2}}`.
+    ClientHello { supported_version: u32 },
+}
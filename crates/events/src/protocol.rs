@@ -0,0 +1,152 @@
+//! The versioned wire format of the WebSocket.
+//!
+//! Since version 2, every frame the server sends is a `WsEnvelope`:
+//! `{"v": 2, "type": "PortfolioState", "data": {...}}`, without `data` for variants that
+//! carry none. Version 1 frames are the bare `WsMessage`, `{"type": ..., "payload": ...}`.
+//! A client announces the newest version it understands with a `ClientHello`; the server
+//! then speaks the newest version both support, or closes the socket if the client is older
+//! than `MIN_WS_PROTOCOL_VERSION`. Clients that never say hello get version 1, the frames
+//! they were written against.
+//!
+//! `ws_protocol_schema` describes every frame as JSON Schema, for generating client types.
+
+use crate::error::EventsError;
+use crate::messages::{BacktestProgress, FeedStatus, KlineData, LatencyReport, LogMessage, PortfolioState, RollingStats, WsClientMessage, WsMessage};
+use core_types::Execution;
+use schemars::{Schema, SchemaGenerator};
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{json, Value};
+
+/// The newest protocol version this server speaks.
+pub const WS_PROTOCOL_VERSION: u32 = 2;
+
+/// The protocol version spoken to a client until its `ClientHello`, so clients predating the
+/// handshake keep getting bare messages.
+pub const DEFAULT_WS_PROTOCOL_VERSION: u32 = 1;
+
+/// The oldest protocol version this server can still speak.
+pub const MIN_WS_PROTOCOL_VERSION: u32 = 1;
+
+/// The version to speak with a client whose `ClientHello` announced `supported_version`:
+/// the newest both sides support, or `None` if the client is too old.
+pub fn negotiate_version(supported_version: u32) -> Option<u32> {
+    (supported_version >= MIN_WS_PROTOCOL_VERSION).then(|| supported_version.min(WS_PROTOCOL_VERSION))
+}
+
+/// Encodes `message` as a text frame of protocol `version`, which must be a supported one.
+pub fn encode_frame(message: WsMessage, version: u32) -> Result<String, EventsError> {
+    let encoded = if version >= 2 { serde_json::to_string(&WsEnvelope::new(message)) } else { serde_json::to_string(&message) };
+    encoded.map_err(|e| EventsError::Serialization(e.to_string()))
+}
+
+/// A server message as sent to clients of protocol version 2 and later.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WsEnvelope {
+    /// The protocol version the frame was written in.
+    pub v: u32,
+    pub message: WsMessage,
+}
+
+impl WsEnvelope {
+    /// Wraps `message` for the current protocol version.
+    pub fn new(message: WsMessage) -> Self {
+        Self { v: WS_PROTOCOL_VERSION, message }
+    }
+}
+
+impl Serialize for WsEnvelope {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Every variant is listed, so a new one cannot be sent without choosing its data.
+        let kind = self.message.kind();
+        match &self.message {
+            WsMessage::Log(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+            WsMessage::PortfolioState(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+            WsMessage::TradeExecuted(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+            WsMessage::Connected => serialize_envelope::<S, ()>(serializer, self.v, kind, None),
+            WsMessage::KlineData(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+            WsMessage::LatencyReport(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+            WsMessage::BacktestProgress(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+            WsMessage::FeedStatus(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+            WsMessage::RollingStats(data) => serialize_envelope(serializer, self.v, kind, Some(data)),
+        }
+    }
+}
+
+fn serialize_envelope<S: Serializer, T: Serialize>(serializer: S, v: u32, kind: &'static str, data: Option<&T>) -> Result<S::Ok, S::Error> {
+    let mut envelope = serializer.serialize_struct("WsEnvelope", if data.is_some() { 3 } else { 2 })?;
+    envelope.serialize_field("v", &v)?;
+    envelope.serialize_field("type", kind)?;
+    if let Some(data) = data {
+        envelope.serialize_field("data", data)?;
+    }
+    envelope.end()
+}
+
+impl<'de> Deserialize<'de> for WsEnvelope {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct RawEnvelope {
+            v: u32,
+            #[serde(rename = "type")]
+            kind: String,
+            data: Option<Value>,
+        }
+
+        let raw = RawEnvelope::deserialize(deserializer)?;
+        let message = match raw.data {
+            Some(data) => json!({ "type": raw.kind, "payload": data }),
+            None => json!({ "type": raw.kind }),
+        };
+        let message = serde_json::from_value(message).map_err(D::Error::custom)?;
+        Ok(Self { v: raw.v, message })
+    }
+}
+
+/// The JSON Schema of the current protocol: `ServerFrame`, every frame the server sends,
+/// `WsClientMessage`, every frame a client may send, and the types they carry, under `$defs`.
+pub fn ws_protocol_schema() -> Value {
+    let mut generator = SchemaGenerator::default();
+    let frames = vec![
+        frame_schema("Log", Some(generator.subschema_for::<LogMessage>())),
+        frame_schema("PortfolioState", Some(generator.subschema_for::<PortfolioState>())),
+        frame_schema("TradeExecuted", Some(generator.subschema_for::<Execution>())),
+        frame_schema("Connected", None),
+        frame_schema("KlineData", Some(generator.subschema_for::<KlineData>())),
+        frame_schema("LatencyReport", Some(generator.subschema_for::<LatencyReport>())),
+        frame_schema("BacktestProgress", Some(generator.subschema_for::<BacktestProgress>())),
+        frame_schema("FeedStatus", Some(generator.subschema_for::<FeedStatus>())),
+        frame_schema("RollingStats", Some(generator.subschema_for::<RollingStats>())),
+    ];
+    let client_message = generator.subschema_for::<WsClientMessage>();
+    let server_frame = json!({ "$ref": "#/$defs/ServerFrame" });
+
+    let mut definitions = generator.take_definitions(true);
+    definitions.insert(
+        "ServerFrame".to_string(),
+        json!({ "description": "A frame sent by the server, wrapping a `WsMessage`.", "oneOf": frames }),
+    );
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "Zenith WebSocket protocol",
+        "description": format!("Version {} of the frames exchanged over /ws.", WS_PROTOCOL_VERSION),
+        "anyOf": [server_frame, client_message],
+        "$defs": definitions,
+    })
+}
+
+/// The schema of a server frame of `kind`, carrying data of the `data` schema, if any.
+fn frame_schema(kind: &str, data: Option<Schema>) -> Value {
+    let mut properties = json!({
+        "v": { "type": "integer", "const": WS_PROTOCOL_VERSION },
+        "type": { "type": "string", "const": kind },
+    });
+    let mut required = vec!["v", "type"];
+    if let Some(data) = data {
+        properties["data"] = data.to_value();
+        required.push("data");
+    }
+    json!({ "title": kind, "type": "object", "properties": properties, "required": required })
+}
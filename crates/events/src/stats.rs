@@ -0,0 +1,316 @@
+//! A cheap snapshot of the live engine's recent activity, for dashboards.
+//!
+//! The engine updates `EngineStats` on its hot paths with atomics only, so recording a kline,
+//! signal or fill never waits on a lock. Each bot updates its own `BotActivity`, which it
+//! holds directly; the registry's lock is only taken when a bot is registered and when a
+//! snapshot is read.
+
+use chrono::{DateTime, Utc};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// How many discrete events (logs, trades, backtest progress) the `EventBus` buffers for each
+/// subscriber. State updates are coalesced, so they take no room here.
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// The longest window activity is counted over, in minutes.
+const WINDOW_MINUTES: i64 = 24 * 60;
+
+/// Counts events over the last 24 hours in one-minute buckets.
+///
+/// A bucket is reset the first time it is used in a new minute. Concurrent updates racing a
+/// reset can lose a count, which is acceptable for monitoring.
+struct RollingCounter {
+    buckets: Box<[Bucket]>,
+}
+
+#[derive(Default)]
+struct Bucket {
+    /// The minute (since the Unix epoch) the count belongs to.
+    minute: AtomicI64,
+    count: AtomicU64,
+}
+
+impl RollingCounter {
+    fn new() -> Self {
+        Self { buckets: (0..WINDOW_MINUTES).map(|_| Bucket::default()).collect() }
+    }
+
+    fn record(&self, now: DateTime<Utc>) {
+        let minute = now.timestamp().div_euclid(60);
+        let bucket = &self.buckets[minute.rem_euclid(WINDOW_MINUTES) as usize];
+        let seen = bucket.minute.load(Ordering::Acquire);
+        if seen != minute && bucket.minute.compare_exchange(seen, minute, Ordering::AcqRel, Ordering::Acquire).is_ok() {
+            bucket.count.store(0, Ordering::Release);
+        }
+        bucket.count.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// The events recorded in the `minutes` minutes up to and including `now`'s.
+    fn count(&self, now: DateTime<Utc>, minutes: i64) -> u64 {
+        let current = now.timestamp().div_euclid(60);
+        self.buckets
+            .iter()
+            .filter(|bucket| {
+                let age = current - bucket.minute.load(Ordering::Acquire);
+                (0..minutes).contains(&age)
+            })
+            .map(|bucket| bucket.count.load(Ordering::Acquire))
+            .sum()
+    }
+
+    fn counts(&self, now: DateTime<Utc>) -> ActivityCounts {
+        ActivityCounts { last_1h: self.count(now, 60), last_24h: self.count(now, WINDOW_MINUTES) }
+    }
+}
+
+/// One bot's activity, updated by the engine without locking.
+#[derive(Debug)]
+pub struct BotActivity {
+    /// The close time of the last kline processed, in milliseconds; `i64::MIN` before the first.
+    last_kline_close_ms: AtomicI64,
+    halted: AtomicBool,
+    consecutive_losses: AtomicU32,
+    limit_fills: AtomicU64,
+    maker_fills: AtomicU64,
+    /// The limit fills whose price improvement is known, and the sum of their improvements
+    /// in hundredths of a basis point.
+    measured_fills: AtomicU64,
+    improvement_centi_bps: AtomicI64,
+}
+
+impl Default for BotActivity {
+    fn default() -> Self {
+        Self {
+            last_kline_close_ms: AtomicI64::new(i64::MIN),
+            halted: AtomicBool::new(false),
+            consecutive_losses: AtomicU32::new(0),
+            limit_fills: AtomicU64::new(0),
+            maker_fills: AtomicU64::new(0),
+            measured_fills: AtomicU64::new(0),
+            improvement_centi_bps: AtomicI64::new(0),
+        }
+    }
+}
+
+impl BotActivity {
+    /// Records that a kline closing at `close_time` was processed.
+    pub fn record_kline(&self, close_time: DateTime<Utc>) {
+        self.last_kline_close_ms.store(close_time.timestamp_millis(), Ordering::Release);
+    }
+
+    pub fn set_halted(&self, halted: bool) {
+        self.halted.store(halted, Ordering::Release);
+    }
+
+    pub fn set_consecutive_losses(&self, losses: u32) {
+        self.consecutive_losses.store(losses, Ordering::Release);
+    }
+
+    /// Records a fill of a limit order, whether it filled as a maker, and what it saved
+    /// against crossing the spread, if known.
+    pub fn record_limit_fill(&self, is_maker: bool, improvement_bps: Option<Decimal>) {
+        self.limit_fills.fetch_add(1, Ordering::AcqRel);
+        if is_maker {
+            self.maker_fills.fetch_add(1, Ordering::AcqRel);
+        }
+        if let Some(centi_bps) = improvement_bps.and_then(|bps| (bps * Decimal::ONE_HUNDRED).round().to_i64()) {
+            self.improvement_centi_bps.fetch_add(centi_bps, Ordering::AcqRel);
+            self.measured_fills.fetch_add(1, Ordering::AcqRel);
+        }
+    }
+
+    /// Adds this bot's limit fills to `quality`.
+    fn add_execution_quality(&self, quality: &mut ExecutionQualityTotals) {
+        quality.limit_fills += self.limit_fills.load(Ordering::Acquire);
+        quality.maker_fills += self.maker_fills.load(Ordering::Acquire);
+        quality.measured_fills += self.measured_fills.load(Ordering::Acquire);
+        quality.improvement_centi_bps += self.improvement_centi_bps.load(Ordering::Acquire);
+    }
+
+    fn snapshot(&self, symbol: &str, interval: &str) -> BotStats {
+        let last_kline_ms = self.last_kline_close_ms.load(Ordering::Acquire);
+        BotStats {
+            symbol: symbol.to_string(),
+            interval: interval.to_string(),
+            last_kline_close_time: (last_kline_ms != i64::MIN).then(|| DateTime::from_timestamp_millis(last_kline_ms)).flatten(),
+            halted: self.halted.load(Ordering::Acquire),
+            consecutive_losses: self.consecutive_losses.load(Ordering::Acquire),
+        }
+    }
+}
+
+/// The live engine's activity counters, shared between the engine and the web server.
+pub struct EngineStats {
+    started_at: DateTime<Utc>,
+    signals: RollingCounter,
+    /// Signals approved by the risk manager but refused by the minimum expected-move filter.
+    filtered_signals: RollingCounter,
+    fills: RollingCounter,
+    /// Keyed by symbol and interval, as a symbol may run one bot per interval.
+    bots: RwLock<BTreeMap<(String, String), Arc<BotActivity>>>,
+}
+
+impl Default for EngineStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EngineStats {
+    /// Creates empty stats for an engine starting now.
+    pub fn new() -> Self {
+        Self {
+            started_at: Utc::now(),
+            signals: RollingCounter::new(),
+            filtered_signals: RollingCounter::new(),
+            fills: RollingCounter::new(),
+            bots: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Registers the bot trading `symbol` on `interval` and returns the activity record it
+    /// updates. Registering the same bot again returns its existing record.
+    pub fn register_bot(&self, symbol: &str, interval: &str) -> Arc<BotActivity> {
+        let mut bots = self.bots.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        Arc::clone(bots.entry((symbol.to_string(), interval.to_string())).or_default())
+    }
+
+    /// Records that a strategy generated a signal.
+    pub fn record_signal(&self, now: DateTime<Utc>) {
+        self.signals.record(now);
+    }
+
+    /// Records that a signal was refused for an expected move too small to cover its costs.
+    pub fn record_filtered_signal(&self, now: DateTime<Utc>) {
+        self.filtered_signals.record(now);
+    }
+
+    /// Records that an order was filled.
+    pub fn record_fill(&self, now: DateTime<Utc>) {
+        self.fills.record(now);
+    }
+
+    /// A snapshot of the stats as of `now`. The event channel's figures are measured by the
+    /// caller, which holds the channel.
+    pub fn snapshot(&self, now: DateTime<Utc>, event_channel: ChannelStats) -> EngineStatsSnapshot {
+        let (bots, execution_quality) = {
+            let registry = self.bots.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            let mut bots = Vec::with_capacity(registry.len());
+            let mut quality: BTreeMap<&str, ExecutionQualityTotals> = BTreeMap::new();
+            for ((symbol, interval), activity) in registry.iter() {
+                bots.push(activity.snapshot(symbol, interval));
+                activity.add_execution_quality(quality.entry(symbol).or_default());
+            }
+            let execution_quality: Vec<ExecutionQualityStats> =
+                quality.iter().filter(|(_, totals)| totals.limit_fills > 0).map(|(symbol, totals)| totals.stats(symbol)).collect();
+            (bots, execution_quality)
+        };
+        // Bots are halted per symbol, so the bots of one symbol are halted together.
+        let mut halted_bots: Vec<String> = bots.iter().filter(|bot| bot.halted).map(|bot| bot.symbol.clone()).collect();
+        halted_bots.dedup();
+        EngineStatsSnapshot {
+            started_at: self.started_at,
+            uptime_secs: (now - self.started_at).num_seconds().max(0) as u64,
+            signals: self.signals.counts(now),
+            signals_filtered: self.filtered_signals.counts(now),
+            orders_filled: self.fills.counts(now),
+            halted_bots,
+            bots,
+            execution_quality,
+            event_channel,
+        }
+    }
+}
+
+/// One symbol's limit fills, summed over its bots.
+#[derive(Default)]
+struct ExecutionQualityTotals {
+    limit_fills: u64,
+    maker_fills: u64,
+    measured_fills: u64,
+    improvement_centi_bps: i64,
+}
+
+impl ExecutionQualityTotals {
+    fn stats(&self, symbol: &str) -> ExecutionQualityStats {
+        ExecutionQualityStats {
+            symbol: symbol.to_string(),
+            limit_fills: self.limit_fills,
+            maker_fills: self.maker_fills,
+            maker_fill_ratio: (self.limit_fills > 0).then(|| Decimal::from(self.maker_fills) / Decimal::from(self.limit_fills)),
+            avg_improvement_bps: (self.measured_fills > 0)
+                .then(|| Decimal::new(self.improvement_centi_bps, 2) / Decimal::from(self.measured_fills)),
+        }
+    }
+}
+
+/// Event counts over the trailing hour and day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ActivityCounts {
+    pub last_1h: u64,
+    pub last_24h: u64,
+}
+
+/// One bot's entry in an `EngineStatsSnapshot`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BotStats {
+    pub symbol: String,
+    pub interval: String,
+    /// The close time of the last kline processed, if any.
+    pub last_kline_close_time: Option<DateTime<Utc>>,
+    pub halted: bool,
+    /// The bot's current run of losing trades, as tracked by the global risk manager.
+    pub consecutive_losses: u32,
+}
+
+/// How one symbol's live limit orders have filled since the engine started.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionQualityStats {
+    pub symbol: String,
+    pub limit_fills: u64,
+    /// The limit fills that rested on the book, rather than being chased across the spread.
+    pub maker_fills: u64,
+    pub maker_fill_ratio: Option<Decimal>,
+    /// The mean saving of the fills against crossing the spread, in basis points of the mid
+    /// price, to a hundredth of a basis point.
+    pub avg_improvement_bps: Option<Decimal>,
+}
+
+/// The occupancy of the engine's event broadcast channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChannelStats {
+    /// The subscribed receivers (WebSocket clients, alerter, caches).
+    pub receivers: usize,
+    /// The messages not yet received by the slowest receiver.
+    pub queued: usize,
+    pub capacity: usize,
+    /// The messages that can still be sent before the slowest receiver starts lagging.
+    pub headroom: usize,
+}
+
+impl ChannelStats {
+    pub fn new(receivers: usize, queued: usize, capacity: usize) -> Self {
+        Self { receivers, queued, capacity, headroom: capacity.saturating_sub(queued) }
+    }
+}
+
+/// The response of `GET /api/engine/stats`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EngineStatsSnapshot {
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: u64,
+    pub signals: ActivityCounts,
+    /// The signals the minimum expected-move filter refused.
+    pub signals_filtered: ActivityCounts,
+    pub orders_filled: ActivityCounts,
+    pub bots: Vec<BotStats>,
+    pub halted_bots: Vec<String>,
+    /// The symbols with limit fills, and how those filled.
+    pub execution_quality: Vec<ExecutionQualityStats>,
+    pub event_channel: ChannelStats,
+}
@@ -0,0 +1,204 @@
+//! A slow subscriber of the event bus gets every discrete event but only the latest state,
+//! unless it subscribed to every kline.
+
+use chrono::{DateTime, TimeZone, Utc};
+use core_types::{Execution, Kline, OrderSide};
+use events::{EventBus, KlineData, LogLevel, LogMessage, PortfolioState, WsMessage};
+use rust_decimal::Decimal;
+use std::time::Duration;
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use uuid::Uuid;
+
+const UPDATES: i64 = 2000;
+/// A trade and a log are sent with every `EVENT_EVERY`th state update.
+const EVENT_EVERY: i64 = 20;
+
+fn at(i: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::minutes(i)
+}
+
+fn kline(symbol: &str, i: i64) -> WsMessage {
+    kline_in(symbol, "1m", i)
+}
+
+fn kline_in(symbol: &str, interval: &str, i: i64) -> WsMessage {
+    let price = Decimal::from(100 + i);
+    WsMessage::KlineData(KlineData {
+        symbol: symbol.to_string(),
+        interval: interval.to_string(),
+        kline: Kline {
+            open_time: at(i),
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume: Decimal::ONE,
+            close_time: at(i + 1),
+            interval: interval.to_string(),
+        },
+        seq: i as u64 + 1,
+    })
+}
+
+fn portfolio(i: i64) -> WsMessage {
+    WsMessage::PortfolioState(PortfolioState {
+        timestamp: at(i),
+        quote_asset: "USDT".to_string(),
+        cash: Decimal::from(i),
+        balances: Default::default(),
+        total_value: Decimal::from(i),
+        positions: Vec::new(),
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    })
+}
+
+fn trade(i: i64) -> WsMessage {
+    WsMessage::TradeExecuted(Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        price: Decimal::from(i),
+        quantity: Decimal::ONE,
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: at(i),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    })
+}
+
+fn log(i: i64) -> WsMessage {
+    WsMessage::Log(LogMessage { timestamp: at(i), level: LogLevel::Info, message: format!("log {}", i), fields: None })
+}
+
+/// What a subscriber received, by kind, each identified by its update index.
+#[derive(Default)]
+struct Received {
+    klines: Vec<(String, i64)>,
+    portfolios: Vec<i64>,
+    trades: Vec<i64>,
+    logs: Vec<String>,
+}
+
+impl Received {
+    fn record(&mut self, message: WsMessage) {
+        match message {
+            WsMessage::KlineData(data) => self.klines.push((data.symbol, (data.kline.open_time - at(0)).num_minutes())),
+            WsMessage::PortfolioState(state) => self.portfolios.push(state.cash.try_into().unwrap()),
+            WsMessage::TradeExecuted(execution) => self.trades.push(execution.price.try_into().unwrap()),
+            WsMessage::Log(log) => self.logs.push(log.message),
+            other => panic!("unexpected message: {:?}", other),
+        }
+    }
+
+    fn klines_of(&self, symbol: &str) -> Vec<i64> {
+        self.klines.iter().filter(|(s, _)| s == symbol).map(|(_, i)| *i).collect()
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_throttled_subscriber_skips_superseded_state_but_no_events() {
+    // Far fewer slots than updates sent, as in the live engine.
+    let bus = EventBus::new(64);
+    let mut subscriber = bus.subscribe();
+
+    // Takes 5ms per message while updates arrive every 1ms.
+    let reader = tokio::spawn(async move {
+        let mut received = Received::default();
+        loop {
+            match subscriber.recv().await {
+                Ok(message) => received.record(message),
+                Err(RecvError::Lagged(skipped)) => panic!("lost {} discrete events", skipped),
+                Err(RecvError::Closed) => return received,
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    });
+
+    for i in 0..UPDATES {
+        bus.send(kline("BTCUSDT", i));
+        bus.send(kline("ETHUSDT", i));
+        bus.send(portfolio(i));
+        if i % EVENT_EVERY == 0 {
+            bus.send(trade(i));
+            bus.send(log(i));
+        }
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    drop(bus);
+    let received = reader.await.unwrap();
+
+    // Every trade and log arrives, in order.
+    let expected: Vec<i64> = (0..UPDATES).step_by(EVENT_EVERY as usize).collect();
+    assert_eq!(received.trades, expected);
+    assert_eq!(received.logs, expected.iter().map(|i| format!("log {}", i)).collect::<Vec<_>>());
+
+    // State updates skip intermediates but stay in order and end on the latest value.
+    for updates in [received.klines_of("BTCUSDT"), received.klines_of("ETHUSDT"), received.portfolios] {
+        assert!(updates.len() < UPDATES as usize / 2, "{} of {} updates delivered", updates.len(), UPDATES);
+        assert!(updates.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(updates.last(), Some(&(UPDATES - 1)));
+    }
+}
+
+#[test]
+fn a_state_update_replaces_the_undelivered_one_of_its_topic() {
+    let bus = EventBus::new(4);
+    let mut subscriber = bus.subscribe();
+
+    bus.send(kline("BTCUSDT", 1));
+    bus.send(kline("ETHUSDT", 1));
+    bus.send(log(1));
+    bus.send(kline("BTCUSDT", 2));
+    bus.send(log(2));
+
+    let mut received = Received::default();
+    while let Ok(message) = subscriber.try_recv() {
+        received.record(message);
+    }
+    // Events first, then the latest state, oldest update first.
+    assert_eq!(received.logs, ["log 1", "log 2"]);
+    assert_eq!(received.klines, [("ETHUSDT".to_string(), 1), ("BTCUSDT".to_string(), 2)]);
+    assert_eq!(subscriber.try_recv(), Err(TryRecvError::Empty));
+
+    // A new subscriber only sees what is sent after it subscribed.
+    let mut late = bus.subscribe();
+    assert_eq!(late.try_recv(), Err(TryRecvError::Empty));
+    bus.send(portfolio(3));
+    assert_eq!(late.try_recv(), Ok(portfolio(3)));
+}
+
+#[test]
+fn each_interval_of_a_symbol_keeps_its_latest_kline() {
+    let bus = EventBus::new(4);
+    let mut subscriber = bus.subscribe();
+
+    bus.send(kline_in("BTCUSDT", "1m", 1));
+    bus.send(kline_in("BTCUSDT", "1h", 1));
+    bus.send(kline_in("BTCUSDT", "1m", 2));
+
+    let mut intervals = Vec::new();
+    while let Ok(WsMessage::KlineData(data)) = subscriber.try_recv() {
+        intervals.push((data.interval, data.seq));
+    }
+    intervals.sort();
+    assert_eq!(intervals, [("1h".to_string(), 2), ("1m".to_string(), 3)]);
+}
+
+#[test]
+fn a_kline_subscriber_receives_every_kline() {
+    let bus = EventBus::new(16);
+    let mut klines = bus.subscribe_klines();
+
+    for i in 0..10 {
+        bus.send(kline("BTCUSDT", i));
+        bus.send(log(i));
+    }
+
+    let seqs: Vec<u64> = std::iter::from_fn(|| klines.try_recv().ok()).map(|data| data.seq).collect();
+    assert_eq!(seqs, (1..=10).collect::<Vec<_>>());
+}
@@ -0,0 +1,33 @@
+//! Checks the wire format of `LogMessage`'s optional structured fields.
+
+use chrono::Utc;
+use events::{LogLevel, LogMessage, WsMessage};
+use serde_json::json;
+
+fn message(fields: Option<serde_json::Value>) -> LogMessage {
+    LogMessage { timestamp: Utc::now(), level: LogLevel::Info, message: "Signal generated for BTCUSDT.".to_string(), fields }
+}
+
+#[test]
+fn structured_fields_round_trip_in_the_payload() {
+    let log = message(Some(json!({ "symbol": "BTCUSDT", "signal_side": "Buy", "price": "100.5" })));
+    let wire = serde_json::to_value(WsMessage::Log(log.clone())).unwrap();
+
+    assert_eq!(wire["payload"]["fields"]["signal_side"], "Buy");
+    let WsMessage::Log(parsed) = serde_json::from_value(wire).unwrap() else { panic!("expected a log message") };
+    assert_eq!(parsed, log);
+}
+
+#[test]
+fn messages_without_fields_keep_the_old_shape() {
+    let wire = serde_json::to_value(message(None)).unwrap();
+    assert!(wire.get("fields").is_none());
+
+    let parsed: LogMessage = serde_json::from_value(json!({
+        "timestamp": "2025-08-25T12:00:00Z",
+        "level": "Warn",
+        "message": "Feed is silent.",
+    }))
+    .unwrap();
+    assert_eq!(parsed.fields, None);
+}
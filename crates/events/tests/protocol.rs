@@ -0,0 +1,279 @@
+//! Pins the wire format of every server message, in both protocol versions, and checks the
+//! checked-in schema of the protocol is current. A failure here means the frontend would
+//! see a changed shape: bump `WS_PROTOCOL_VERSION` if the change is deliberate.
+
+use chrono::{TimeZone, Utc};
+use core_types::{Execution, Kline, OrderSide, Position};
+use events::{
+    encode_frame, negotiate_version, ws_protocol_schema, BacktestProgress, FeedHealth, FeedState, FeedStatus, KlineData, LatencyReport,
+    LatencyStats, LogLevel, LogMessage, PortfolioState, RollingStats, RollingWindowStats, SymbolLatency, WsEnvelope, WsMessage,
+    WS_PROTOCOL_VERSION,
+};
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::path::Path;
+use uuid::Uuid;
+
+/// The checked-in schema, relative to this crate.
+const SCHEMA_PATH: &str = "../../frontend/src/types/ws-protocol.json";
+
+/// One message of every variant, with the data of its version 2 frame.
+fn snapshots() -> Vec<(WsMessage, Option<Value>)> {
+    let at = Utc.with_ymd_and_hms(2025, 8, 25, 12, 0, 0).unwrap();
+    let id = Uuid::from_u128(0x1234);
+    let price = Decimal::new(10050, 2);
+    vec![
+        (
+            WsMessage::Log(LogMessage { timestamp: at, level: LogLevel::Info, message: "Signal generated.".to_string(), fields: Some(json!({ "symbol": "BTCUSDT" })) }),
+            Some(json!({ "timestamp": "2025-08-25T12:00:00Z", "level": "Info", "message": "Signal generated.", "fields": { "symbol": "BTCUSDT" } })),
+        ),
+        (
+            WsMessage::PortfolioState(PortfolioState {
+                timestamp: at,
+                quote_asset: "USDT".to_string(),
+                cash: Decimal::new(9000, 0),
+                balances: [("USDT".to_string(), Decimal::new(9000, 0))].into(),
+                total_value: Decimal::new(10000, 0),
+                positions: vec![Position {
+                    position_id: id,
+                    symbol: "BTCUSDT".to_string(),
+                    side: OrderSide::Buy,
+                    quantity: Decimal::ONE,
+                    entry_price: price,
+                    unrealized_pnl: Decimal::new(-25, 1),
+                    last_updated: at,
+                    adds: 0,
+                    last_entry_price: price,
+                    estimated_liquidation_price: None,
+                }],
+                realized_pnl: Decimal::ZERO,
+                total_fees_paid: Decimal::new(4, 2),
+            }),
+            Some(json!({
+                "timestamp": "2025-08-25T12:00:00Z",
+                "quote_asset": "USDT",
+                "cash": "9000",
+                "balances": { "USDT": "9000" },
+                "total_value": "10000",
+                "positions": [{
+                    "position_id": "00000000-0000-0000-0000-000000001234",
+                    "symbol": "BTCUSDT",
+                    "side": "BUY",
+                    "quantity": "1",
+                    "entry_price": "100.50",
+                    "unrealized_pnl": "-2.5",
+                    "last_updated": "2025-08-25T12:00:00Z",
+                    "adds": 0,
+                    "last_entry_price": "100.50",
+                    "estimated_liquidation_price": null,
+                }],
+                "realized_pnl": "0",
+                "total_fees_paid": "0.04",
+            })),
+        ),
+        (
+            WsMessage::TradeExecuted(Execution {
+                execution_id: id,
+                client_order_id: id,
+                symbol: "BTCUSDT".to_string(),
+                side: OrderSide::Sell,
+                price,
+                quantity: Decimal::ONE,
+                fee: Decimal::new(4, 2),
+                fee_asset: "USDT".to_string(),
+                timestamp: at,
+                decision_id: None,
+                is_maker: true,
+                spread_cost: Decimal::ZERO,
+                placement: None,
+            }),
+            Some(json!({
+                "execution_id": "00000000-0000-0000-0000-000000001234",
+                "client_order_id": "00000000-0000-0000-0000-000000001234",
+                "symbol": "BTCUSDT",
+                "side": "SELL",
+                "price": "100.50",
+                "quantity": "1",
+                "fee": "0.04",
+                "fee_asset": "USDT",
+                "timestamp": "2025-08-25T12:00:00Z",
+                "decision_id": null,
+                "is_maker": true,
+                "spread_cost": "0",
+            })),
+        ),
+        (WsMessage::Connected, None),
+        (
+            WsMessage::KlineData(KlineData {
+                symbol: "BTCUSDT".to_string(),
+                interval: "1m".to_string(),
+                kline: Kline {
+                    open_time: at,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume: Decimal::ONE,
+                    close_time: at,
+                    interval: "1m".to_string(),
+                },
+                seq: 7,
+            }),
+            Some(json!({
+                "symbol": "BTCUSDT",
+                "interval": "1m",
+                "kline": {
+                    "open_time": "2025-08-25T12:00:00Z",
+                    "open": "100.50",
+                    "high": "100.50",
+                    "low": "100.50",
+                    "close": "100.50",
+                    "volume": "1",
+                    "close_time": "2025-08-25T12:00:00Z",
+                    "interval": "1m",
+                },
+                "seq": 7,
+            })),
+        ),
+        (
+            WsMessage::LatencyReport(LatencyReport {
+                timestamp: at,
+                window_secs: 60,
+                symbols: vec![SymbolLatency {
+                    symbol: "BTCUSDT".to_string(),
+                    feed_delay: Some(LatencyStats { samples: 3, p50_ms: 1.5, p99_ms: 4.0, max_ms: 4.0 }),
+                    strategy: None,
+                    risk: None,
+                    execution: None,
+                    decision: None,
+                }],
+            }),
+            Some(json!({
+                "timestamp": "2025-08-25T12:00:00Z",
+                "window_secs": 60,
+                "symbols": [{
+                    "symbol": "BTCUSDT",
+                    "feed_delay": { "samples": 3, "p50_ms": 1.5, "p99_ms": 4.0, "max_ms": 4.0 },
+                    "strategy": null,
+                    "risk": null,
+                    "execution": null,
+                    "decision": null,
+                }],
+            })),
+        ),
+        (
+            WsMessage::BacktestProgress(BacktestProgress { run_id: id, pct: 42.5, bars_done: 425 }),
+            Some(json!({ "run_id": "00000000-0000-0000-0000-000000001234", "pct": 42.5, "bars_done": 425 })),
+        ),
+        (
+            WsMessage::FeedStatus(FeedStatus {
+                timestamp: at,
+                feeds: vec![FeedState {
+                    symbol: "BTCUSDT".to_string(),
+                    interval: "1m".to_string(),
+                    kline: Some(at),
+                    book_ticker: None,
+                    mark_price: Some(at),
+                    status: FeedHealth::Delayed,
+                }],
+            }),
+            Some(json!({
+                "timestamp": "2025-08-25T12:00:00Z",
+                "feeds": [{
+                    "symbol": "BTCUSDT",
+                    "interval": "1m",
+                    "kline": "2025-08-25T12:00:00Z",
+                    "book_ticker": null,
+                    "mark_price": "2025-08-25T12:00:00Z",
+                    "status": "Delayed",
+                }],
+            })),
+        ),
+        (
+            WsMessage::RollingStats(RollingStats {
+                computed_at: at,
+                windows: vec![RollingWindowStats {
+                    days: 7,
+                    from: at - chrono::Duration::days(7),
+                    net_pnl: Some(Decimal::new(-125, 1)),
+                    trade_count: Some(4),
+                    win_rate_pct: Some(Decimal::from(25)),
+                    sharpe_ratio: None,
+                }],
+            }),
+            Some(json!({
+                "computed_at": "2025-08-25T12:00:00Z",
+                "windows": [{
+                    "days": 7,
+                    "from": "2025-08-18T12:00:00Z",
+                    "net_pnl": "-12.5",
+                    "trade_count": 4,
+                    "win_rate_pct": "25",
+                    "sharpe_ratio": null,
+                }],
+            })),
+        ),
+    ]
+}
+
+fn frame(message: &WsMessage, version: u32) -> Value {
+    serde_json::from_str(&encode_frame(message.clone(), version).unwrap()).unwrap()
+}
+
+#[test]
+fn version_2_frames_keep_their_shape() {
+    for (message, data) in snapshots() {
+        let mut expected = json!({ "v": 2, "type": message.kind() });
+        if let Some(data) = data {
+            expected["data"] = data;
+        }
+        assert_eq!(frame(&message, 2), expected, "{} changed shape", message.kind());
+
+        let parsed: WsEnvelope = serde_json::from_value(expected).unwrap();
+        assert_eq!(parsed, WsEnvelope::new(message));
+    }
+}
+
+#[test]
+fn version_1_frames_are_bare_messages() {
+    for (message, data) in snapshots() {
+        let mut expected = json!({ "type": message.kind() });
+        if let Some(data) = data {
+            expected["payload"] = data;
+        }
+        assert_eq!(frame(&message, 1), expected, "{} changed shape", message.kind());
+    }
+}
+
+#[test]
+fn versions_are_negotiated_down_to_the_client_s() {
+    assert_eq!(negotiate_version(0), None);
+    assert_eq!(negotiate_version(1), Some(1));
+    assert_eq!(negotiate_version(WS_PROTOCOL_VERSION), Some(WS_PROTOCOL_VERSION));
+    assert_eq!(negotiate_version(WS_PROTOCOL_VERSION + 1), Some(WS_PROTOCOL_VERSION));
+}
+
+#[test]
+fn the_schema_describes_every_variant() {
+    let schema = ws_protocol_schema();
+    let frames = schema["$defs"]["ServerFrame"]["oneOf"].as_array().unwrap();
+    let titles: Vec<&str> = frames.iter().map(|frame| frame["title"].as_str().unwrap()).collect();
+    let kinds: Vec<&str> = snapshots().iter().map(|(message, _)| message.kind()).collect();
+    assert_eq!(titles, kinds);
+
+    for (frame, (_, data)) in frames.iter().zip(snapshots()) {
+        assert_eq!(frame["properties"].get("data").is_some(), data.is_some(), "{}", frame["title"]);
+    }
+    assert_eq!(schema["$defs"]["OrderSide"]["enum"], json!(["BUY", "SELL"]));
+}
+
+#[test]
+fn the_checked_in_schema_is_current() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(SCHEMA_PATH);
+    let checked_in: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert!(
+        checked_in == ws_protocol_schema(),
+        "{} is out of date; regenerate it with `cargo run -p events --bin ws-protocol -- frontend/src/types/ws-protocol.json`",
+        SCHEMA_PATH
+    );
+}
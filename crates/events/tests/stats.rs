@@ -0,0 +1,80 @@
+//! Counts engine activity over rolling windows and reports per-bot state.
+
+use chrono::{Duration, TimeZone, Utc};
+use events::{ChannelStats, EngineStats};
+use rust_decimal::Decimal;
+
+#[test]
+fn activity_ages_out_of_the_hour_and_then_the_day() {
+    let stats = EngineStats::new();
+    let start = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    stats.record_signal(start);
+    stats.record_signal(start + Duration::minutes(90));
+    stats.record_fill(start + Duration::minutes(90));
+    stats.record_filtered_signal(start);
+
+    let snapshot = stats.snapshot(start + Duration::minutes(100), ChannelStats::new(0, 0, 0));
+    assert_eq!((snapshot.signals.last_1h, snapshot.signals.last_24h), (1, 2));
+    assert_eq!((snapshot.signals_filtered.last_1h, snapshot.signals_filtered.last_24h), (0, 1));
+    assert_eq!((snapshot.orders_filled.last_1h, snapshot.orders_filled.last_24h), (1, 1));
+
+    let snapshot = stats.snapshot(start + Duration::hours(25), ChannelStats::new(0, 0, 0));
+    assert_eq!((snapshot.signals.last_1h, snapshot.signals.last_24h), (0, 1));
+
+    // A day later the buckets are reused, and the old counts do not leak into the new ones.
+    stats.record_signal(start + Duration::hours(48));
+    let snapshot = stats.snapshot(start + Duration::hours(48), ChannelStats::new(0, 0, 0));
+    assert_eq!((snapshot.signals.last_1h, snapshot.signals.last_24h), (1, 1));
+}
+
+#[test]
+fn bots_report_their_last_kline_and_halts() {
+    let stats = EngineStats::new();
+    let btc = stats.register_bot("BTCUSDT", "1m");
+    let eth = stats.register_bot("ETHUSDT", "1h");
+    let close_time = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 59).unwrap();
+    btc.record_kline(close_time);
+    eth.set_halted(true);
+    eth.set_consecutive_losses(3);
+
+    let snapshot = stats.snapshot(Utc::now(), ChannelStats::new(2, 1000, 1024));
+    assert_eq!((snapshot.bots[0].symbol.as_str(), snapshot.bots[0].interval.as_str()), ("BTCUSDT", "1m"));
+    assert_eq!(snapshot.bots[0].last_kline_close_time, Some(close_time));
+    assert_eq!(snapshot.bots[1].last_kline_close_time, None);
+    assert_eq!(snapshot.bots[1].consecutive_losses, 3);
+    assert_eq!(snapshot.halted_bots, ["ETHUSDT"]);
+    assert_eq!(snapshot.event_channel.headroom, 24);
+}
+
+#[test]
+fn the_bots_of_one_symbol_are_reported_apart_but_halted_once() {
+    let stats = EngineStats::new();
+    for interval in ["15m", "1h"] {
+        stats.register_bot("BTCUSDT", interval).set_halted(true);
+    }
+
+    let snapshot = stats.snapshot(Utc::now(), ChannelStats::new(0, 0, 0));
+    let intervals: Vec<&str> = snapshot.bots.iter().map(|bot| bot.interval.as_str()).collect();
+    assert_eq!(intervals, ["15m", "1h"]);
+    assert_eq!(snapshot.halted_bots, ["BTCUSDT"]);
+}
+
+#[test]
+fn limit_fills_are_summed_per_symbol() {
+    let stats = EngineStats::new();
+    let btc_fast = stats.register_bot("BTCUSDT", "1m");
+    let btc_slow = stats.register_bot("BTCUSDT", "1h");
+    stats.register_bot("ETHUSDT", "1h");
+    btc_fast.record_limit_fill(true, Some(Decimal::new(150, 2)));
+    btc_fast.record_limit_fill(false, Some(Decimal::new(-50, 2)));
+    btc_slow.record_limit_fill(true, None);
+
+    let snapshot = stats.snapshot(Utc::now(), ChannelStats::new(0, 0, 0));
+    // ETHUSDT has no limit fills, so it is left out.
+    assert_eq!(snapshot.execution_quality.len(), 1);
+    let btc = &snapshot.execution_quality[0];
+    assert_eq!((btc.symbol.as_str(), btc.limit_fills, btc.maker_fills), ("BTCUSDT", 3, 2));
+    assert_eq!(btc.maker_fill_ratio, Some(Decimal::from(2) / Decimal::from(3)));
+    // The fill without a known improvement is left out of the mean.
+    assert_eq!(btc.avg_improvement_bps, Some(Decimal::new(50, 2)));
+}
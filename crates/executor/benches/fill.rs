@@ -0,0 +1,63 @@
+//! Measures the cost of filling an order: the simulated executor's fill, then the portfolio
+//! update applying it, which the backtester does for every order.
+//!
+//! Run with `cargo bench -p executor`. Each iteration opens and closes a position on
+//! consecutive bars of a seeded random walk, a thousand times over, from a fresh portfolio.
+
+use core_types::{OrderRequest, OrderSide, OrderType};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use executor::{Executor, Portfolio, SimulatedExecutor};
+use rust_decimal::Decimal;
+use testing::synthetic::{gbm, SeriesSpec};
+use testing::{test_config, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// The round trips per iteration, each opening a position on one bar and closing it on the next.
+const ROUND_TRIPS: usize = 1_000;
+
+fn market_order(side: OrderSide) -> OrderRequest {
+    OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side,
+        order_type: OrderType::Market,
+        quantity: Decimal::ONE,
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: false,
+        decision_id: None,
+    }
+}
+
+fn fill_round_trip(c: &mut Criterion) {
+    let config = test_config(10).expect("load config");
+    let executor = SimulatedExecutor::new(config.simulation.clone());
+    let klines = gbm(&SeriesSpec::default(), ROUND_TRIPS * 2, 0.0, 0.01, 7);
+    let (buy, sell) = (market_order(OrderSide::Buy), market_order(OrderSide::Sell));
+    let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+
+    let mut group = c.benchmark_group("executor");
+    group.throughput(Throughput::Elements(ROUND_TRIPS as u64 * 2));
+    group.bench_function("fill_round_trips_1k", |b| {
+        b.iter_batched(
+            || Portfolio::new(Decimal::from(1_000_000)),
+            |mut portfolio| {
+                runtime.block_on(async {
+                    for bars in klines.chunks(2) {
+                        for (order, kline) in [(&buy, &bars[0]), (&sell, &bars[1])] {
+                            let execution = executor.execute(order, kline, None, None).await.expect("fill");
+                            black_box(portfolio.update_with_execution(&execution).expect("apply fill"));
+                        }
+                    }
+                });
+                portfolio
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, fill_round_trip);
+criterion_main!(benches);
@@ -8,12 +8,24 @@ pub enum ExecutorError {
     #[error("Position not found for symbol: {0}")]
     PositionNotFound(String),
 
-    #[error("Invalid order quantity for closing position. Requested: {requested}, Available: {available}")]
-    InvalidClosingQuantity { requested: String, available: String },
-    
     #[error("An unexpected portfolio state was encountered: {0}")]
     PortfolioError(String),
 
+    /// The order was refused before it was sent, or the exchange rejected it: it is certainly
+    /// not on the exchange.
+    #[error("The order was rejected: {0}")]
+    Rejected(String),
+
+    /// The exchange could not be reached, or did not say what became of the order, which may
+    /// have been placed, and filled, all the same.
     #[error("API error: {0}")]
     Api(String),
+}
+
+impl ExecutorError {
+    /// Whether the order certainly did not reach the exchange. Only an `Api` error leaves it
+    /// unknown whether the order was placed.
+    pub fn is_rejection(&self) -> bool {
+        !matches!(self, ExecutorError::Api(_))
+    }
 }
\ No newline at end of file
@@ -1,13 +1,15 @@
 use crate::error::ExecutorError;
 use async_trait::async_trait;
-use configuration::Simulation;
-use core_types::{Execution, Kline, OrderRequest, OrderSide, OrderType};
+use configuration::{QuoteAssets, Simulation};
+use core_types::{Execution, Kline, OrderPlacement, OrderRequest, OrderSide, OrderType};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use std::sync::Arc;
-use api_client::ApiClient;
+use api_client::error::ApiError;
+use api_client::{ApiClient, UserTradeResponse};
+use std::collections::HashMap;
 use tracing;
 
 /// Rounds a price to the appropriate tick size for the given symbol.
@@ -44,6 +46,16 @@ fn round_quantity_to_step_size(symbol: &str, quantity: Decimal) -> Decimal {
     }
 }
 
+/// The error of an order placement. An exchange error code rejects the order, except the
+/// codes with which Binance leaves its status unknown. Any other failure, such as a timeout or
+/// a dropped connection, may have come after the exchange took the order.
+fn placement_error(e: ApiError) -> ExecutorError {
+    match e {
+        ApiError::BinanceError(code, _) if !matches!(code, -1000 | -1001 | -1006 | -1007) => ExecutorError::Rejected(e.to_string()),
+        e => ExecutorError::Api(e.to_string()),
+    }
+}
+
 /// A generic trait for an execution engine.
 ///
 /// This trait allows the backtester and live engine to be agnostic about whether
@@ -67,27 +79,47 @@ pub trait Executor: Send + Sync {
 /// The "virtual exchange" for backtesting.
 ///
 /// It holds the simulation parameters and implements the `Executor` trait to
-/// create trade executions with realistic costs.
+/// create trade executions with realistic costs. Fees are charged in each symbol's quote asset.
 pub struct SimulatedExecutor {
     params: Simulation,
+    quote_assets: QuoteAssets,
 }
 
 impl SimulatedExecutor {
     pub fn new(params: Simulation) -> Self {
-        Self { params }
+        Self { params, quote_assets: QuoteAssets::default() }
+    }
+
+    /// Charges fees in `quote_assets` rather than the default quote asset.
+    pub fn with_quote_assets(mut self, quote_assets: QuoteAssets) -> Self {
+        self.quote_assets = quote_assets;
+        self
+    }
+
+    /// The best bid and ask an order fills against. Real quotes take precedence; without
+    /// them, a synthetic spread of the symbol's `spread_pct` is centred on the bar's close.
+    fn quotes(&self, symbol: &str, kline: &Kline, best_bid: Option<Decimal>, best_ask: Option<Decimal>) -> (Decimal, Decimal) {
+        match (best_bid, best_ask) {
+            (Some(bid), Some(ask)) if bid.is_sign_positive() && bid <= ask => (bid, ask),
+            _ => {
+                let half_spread = kline.close * self.params.spread_pct(symbol) / Decimal::TWO;
+                (kline.close - half_spread, kline.close + half_spread)
+            }
+        }
     }
 
     /// Calculates the execution price, modeling for slippage.
     ///
     /// For a simple model, we assume slippage moves the price against us
-    /// by a certain percentage of the bar's high-low range.
-    fn calculate_slippage_price(&self, order_side: OrderSide, kline: &Kline) -> Decimal {
+    /// by a certain percentage of the bar's high-low range, starting from the quote the
+    /// order crosses the spread to.
+    fn calculate_slippage_price(&self, order_side: OrderSide, kline: &Kline, quote: Decimal) -> Decimal {
         let bar_range = kline.high - kline.low;
         tracing::debug!("Slippage calculation: bar_range={}, slippage_pct={}", bar_range, self.params.slippage_pct);
         
         if bar_range.is_zero() {
-            tracing::debug!("No bar range, returning close price: {}", kline.close);
-            return kline.close; // No range, no slippage possible
+            tracing::debug!("No bar range, returning quote price: {}", quote);
+            return quote; // No range, no slippage possible
         }
 
         let slippage_amount = bar_range * self.params.slippage_pct;
@@ -95,34 +127,54 @@ impl SimulatedExecutor {
 
         let result = match order_side {
             // For a buy, slippage makes the price HIGHER (worse).
-            OrderSide::Buy => kline.close + slippage_amount,
+            OrderSide::Buy => quote + slippage_amount,
             // For a sell, slippage makes the price LOWER (worse).
-            OrderSide::Sell => kline.close - slippage_amount,
+            OrderSide::Sell => quote - slippage_amount,
         };
         
-        tracing::debug!("Final execution price: {} (close: {}, side: {:?})", result, kline.close, order_side);
+        tracing::debug!("Final execution price: {} (quote: {}, side: {:?})", result, quote, order_side);
         result
     }
+
+    /// The fee rate for an order: limit orders rest on the book and pay the maker fee,
+    /// market orders pay the taker fee. The fee discount applies to both.
+    fn fee_rate(&self, order_type: OrderType) -> Decimal {
+        let base_rate = match order_type {
+            OrderType::Limit => self.params.maker_fee_pct,
+            OrderType::Market => self.params.taker_fee_pct,
+        };
+        base_rate * (Decimal::ONE - self.params.fee_discount_pct)
+    }
 }
 
 #[async_trait]
 impl Executor for SimulatedExecutor {
-    /// Simulates the execution of a market order.
+    /// Simulates the execution of an order at the bar's close. Buys fill at the ask and sells
+    /// at the bid, from `best_bid`/`best_ask` when both are given or a synthetic spread around
+    /// the close otherwise. Limit orders are assumed to fill as makers at the same price as a
+    /// market order would.
     async fn execute(
         &self,
         order: &OrderRequest,
         kline: &Kline,
-        best_bid: Option<Decimal>, // <-- ADDED
-        best_ask: Option<Decimal>, // <-- ADDED
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
     ) -> Result<Execution, ExecutorError> {
         tracing::debug!("SimulatedExecutor: Executing order {:?} with kline {:?}", order, kline);
-        
-        // 1. Calculate the execution price with slippage.
-        let execution_price = self.calculate_slippage_price(order.side, kline);
-        tracing::debug!("SimulatedExecutor: Calculated execution price: {} (original close: {})", execution_price, kline.close);
+
+        // 1. Cross the spread, then calculate the execution price with slippage.
+        let (bid, ask) = self.quotes(&order.symbol, kline, best_bid, best_ask);
+        let quote = match order.side {
+            OrderSide::Buy => ask,
+            OrderSide::Sell => bid,
+        };
+        let spread_cost = (quote - (bid + ask) / Decimal::TWO).abs() * order.quantity;
+        let execution_price = self.calculate_slippage_price(order.side, kline, quote);
+        tracing::debug!("SimulatedExecutor: Calculated execution price: {} (original close: {}, bid: {}, ask: {})", execution_price, kline.close, bid, ask);
 
         // 2. Calculate the trading fee.
-        let fee = execution_price * order.quantity * self.params.taker_fee_pct;
+        let is_maker = order.order_type == OrderType::Limit;
+        let fee = execution_price * order.quantity * self.fee_rate(order.order_type);
         tracing::debug!("SimulatedExecutor: Calculated fee: {}", fee);
 
         // 3. Construct the execution receipt.
@@ -133,9 +185,15 @@ impl Executor for SimulatedExecutor {
             price: execution_price,
             quantity: order.quantity,
             fee,
-            fee_asset: "USDT".to_string(), // Assuming quote asset is the fee asset
-            timestamp: Utc::now(), // In a real backtest, this would be kline.close_time
+            fee_asset: self.quote_assets.for_symbol(&order.symbol).to_string(),
+            // The fill happens at the bar's close, so it is stamped with the bar's close time.
+            // This keeps holding periods meaningful and re-runs of a backtest identical.
+            timestamp: kline.close_time,
             side: order.side, // Add the side to the execution
+            decision_id: order.decision_id,
+            is_maker,
+            spread_cost,
+            placement: None,
         };
 
         tracing::debug!("SimulatedExecutor: Created execution: {:?}", execution);
@@ -143,16 +201,81 @@ impl Executor for SimulatedExecutor {
     }
 }
 
+/// The commission charged across `fills`, in `quote_asset`.
+///
+/// Commissions charged in another asset, such as BNB under the fee discount, are converted at
+/// the mark price of that asset's pair with the quote asset. A commission whose pair cannot be
+/// priced is logged and left out, rather than taking an amount of one asset from the cash of
+/// another.
+pub async fn quote_fee(api_client: &dyn ApiClient, fills: &[UserTradeResponse], quote_asset: &str) -> Decimal {
+    let mut prices: HashMap<&str, Option<Decimal>> = HashMap::new();
+    let mut fee = Decimal::ZERO;
+    for fill in fills {
+        if fill.commission_asset == quote_asset {
+            fee += fill.commission;
+            continue;
+        }
+        let price = match prices.get(fill.commission_asset.as_str()) {
+            Some(price) => *price,
+            None => {
+                let pair = format!("{}{}", fill.commission_asset, quote_asset);
+                let price = match api_client.get_mark_price(&pair).await {
+                    Ok(price) => Some(price),
+                    Err(e) => {
+                        tracing::warn!(asset = %fill.commission_asset, pair = %pair, error = %e, "Failed to price a commission asset. Leaving its fees out.");
+                        None
+                    }
+                };
+                prices.insert(&fill.commission_asset, price);
+                price
+            }
+        };
+        if let Some(price) = price {
+            fee += fill.commission * price;
+        }
+    }
+    fee
+}
+
 // --- NEW IMPLEMENTATION ---
 
 /// The "live" executor that sends real orders to the exchange via the ApiClient.
 pub struct LiveExecutor {
     api_client: Arc<dyn ApiClient>,
+    /// The quote asset of each symbol, which fees are recorded in.
+    quote_assets: QuoteAssets,
 }
 
 impl LiveExecutor {
     pub fn new(api_client: Arc<dyn ApiClient>) -> Self {
-        Self { api_client }
+        Self { api_client, quote_assets: QuoteAssets::default() }
+    }
+
+    /// Records fees in the quote asset each symbol has in `quote_assets`.
+    pub fn with_quote_assets(mut self, quote_assets: QuoteAssets) -> Self {
+        self.quote_assets = quote_assets;
+        self
+    }
+
+    /// Sums the commissions charged across all fills of an order in the symbol's quote asset
+    /// (see `quote_fee`), and reports whether every fill was a maker fill.
+    ///
+    /// The order response does not carry the fee, so it has to be queried from the
+    /// account's trade history. The order has already been placed at this point, so a
+    /// failed lookup is logged and reported as a zero taker fee rather than failing the execution.
+    async fn fetch_order_fee(&self, symbol: &str, order_id: i64) -> (Decimal, String, bool) {
+        let quote_asset = self.quote_assets.for_symbol(symbol).to_string();
+        match self.api_client.get_user_trades(symbol, order_id).await {
+            Ok(fills) => {
+                let fee = quote_fee(&*self.api_client, &fills, &quote_asset).await;
+                let is_maker = !fills.is_empty() && fills.iter().all(|f| f.maker);
+                (fee, quote_asset, is_maker)
+            }
+            Err(e) => {
+                tracing::warn!("LiveExecutor: Failed to fetch fills for order {} on {}: {}. Recording a zero fee.", order_id, symbol, e);
+                (Decimal::ZERO, quote_asset, false)
+            }
+        }
     }
 }
 
@@ -173,9 +296,11 @@ impl Executor for LiveExecutor {
             .api_client
             .place_order(order)
             .await
-            .map_err(|e| ExecutorError::Api(e.to_string()))?; // Convert ApiError to ExecutorError
+            .map_err(placement_error)?;
 
         tracing::debug!("LiveExecutor: Received order response: {:?}", order_response);
+
+        let (fee, fee_asset, is_maker) = self.fetch_order_fee(&order_response.symbol, order_response.order_id).await;
         
         // Transform the exchange's OrderResponse into our internal Execution receipt.
         let execution = Execution {
@@ -186,9 +311,13 @@ impl Executor for LiveExecutor {
             side: order_response.side,
             price: order_response.avg_price,
             quantity: order_response.executed_qty,
-            fee: "0".parse().unwrap(), // The response doesn't contain the fee directly, needs another query
-            fee_asset: "USDT".to_string(), // Assume USDT for now
+            fee,
+            fee_asset,
             timestamp: Utc::now(), // Use current time for live execution
+            decision_id: order.decision_id,
+            is_maker,
+            spread_cost: Decimal::ZERO, // The exchange does not report it
+            placement: None,
         };
 
         tracing::debug!("LiveExecutor: Created execution: {:?}", execution);
@@ -199,11 +328,39 @@ impl Executor for LiveExecutor {
 /// An executor that places "Post-Only" LIMIT orders to act as a market maker.
 pub struct LimitOrderExecutor {
     api_client: Arc<dyn ApiClient>,
+    quote_assets: QuoteAssets,
 }
 
 impl LimitOrderExecutor {
     pub fn new(api_client: Arc<dyn ApiClient>) -> Self {
-        Self { api_client }
+        Self { api_client, quote_assets: QuoteAssets::default() }
+    }
+
+    /// Records fees in `quote_assets` rather than the default quote asset.
+    pub fn with_quote_assets(mut self, quote_assets: QuoteAssets) -> Self {
+        self.quote_assets = quote_assets;
+        self
+    }
+
+    /// Records on `placement` the fills of the `side` order `order_id` the account's trade
+    /// history holds: their average price, whether all were maker fills, and when the last
+    /// happened. A failed lookup, or one that finds no fill, leaves it unconfirmed.
+    async fn confirm_fill(&self, placement: &mut OrderPlacement, symbol: &str, side: OrderSide, order_id: i64) {
+        let fills = match self.api_client.get_user_trades(symbol, order_id).await {
+            Ok(fills) => fills,
+            Err(e) => {
+                tracing::warn!("LimitOrderExecutor: Failed to fetch fills for order {} on {}: {}. Leaving its fill unconfirmed.", order_id, symbol, e);
+                return;
+            }
+        };
+        let quantity: Decimal = fills.iter().map(|fill| fill.qty).sum();
+        if quantity <= Decimal::ZERO {
+            return;
+        }
+        let fill_price = fills.iter().map(|fill| fill.price * fill.qty).sum::<Decimal>() / quantity;
+        let is_maker = fills.iter().all(|fill| fill.maker);
+        let filled_at = fills.iter().map(|fill| fill.time).max().and_then(DateTime::from_timestamp_millis).unwrap_or_else(Utc::now);
+        placement.record_fill(side, fill_price, is_maker, filled_at);
     }
 }
 
@@ -220,12 +377,12 @@ impl Executor for LimitOrderExecutor {
         // Calculate a price inside the spread to ensure the order acts as a maker
         let (bid, ask) = match (best_bid, best_ask) {
             (Some(bid), Some(ask)) => (bid, ask),
-            _ => return Err(ExecutorError::Api("Best bid and ask prices not available for LIMIT order.".to_string())),
+            _ => return Err(ExecutorError::Rejected("Best bid and ask prices not available for LIMIT order.".to_string())),
         };
         
         // Ensure we have a valid spread
         if bid >= ask {
-            return Err(ExecutorError::Api("Invalid spread: bid >= ask".to_string()));
+            return Err(ExecutorError::Rejected("Invalid spread: bid >= ask".to_string()));
         }
         
         let calculated_price = match order.side {
@@ -270,22 +427,34 @@ impl Executor for LimitOrderExecutor {
             .api_client
             .place_limit_order(&limit_order) // We will build this in Task 5
             .await
-            .map_err(|e| ExecutorError::Api(e.to_string()))?;
+            .map_err(placement_error)?;
 
         // Transform the response into our internal Execution format.
         // NOTE: A LIMIT order may not fill immediately. This `Execution` is an acknowledgement
         // that the order was PLACED. A separate process (User Data Stream) will be needed
-        // to confirm the FILL. For now, we optimistically create the execution.
+        // to confirm the FILL. Its placement is recorded now, and its fill price, maker flag
+        // and price improvement only once the exchange reports a fill.
+        let mut placement = OrderPlacement::new(bid, ask, order_response.price, Utc::now());
+        if order_response.executed_qty > Decimal::ZERO {
+            self.confirm_fill(&mut placement, &order_response.symbol, order_response.side, order_response.order_id).await;
+        }
         let execution = Execution {
             execution_id: Uuid::new_v4(),
             client_order_id: Uuid::parse_str(&order_response.client_order_id).unwrap_or(order.client_order_id),
             symbol: order_response.symbol,
             side: order_response.side,
-            price: order_response.price, // This will be the limit price, not necessarily the fill price
+            // The limit price, until a fill is confirmed.
+            price: placement.fill_price.unwrap_or(order_response.price),
             quantity: order_response.orig_qty, // The full quantity is placed
             fee: "0".parse().unwrap(),
-            fee_asset: "USDT".to_string(),
+            fee_asset: self.quote_assets.for_symbol(&limit_order.symbol).to_string(),
             timestamp: Utc::now(),
+            decision_id: order.decision_id,
+            // Post-only orders are rejected rather than take liquidity, so until the fills say
+            // otherwise, the order can only fill as a maker.
+            is_maker: placement.is_maker.unwrap_or(true),
+            spread_cost: Decimal::ZERO, // Resting inside the spread does not cross it
+            placement: Some(placement),
         };
 
         Ok(execution)
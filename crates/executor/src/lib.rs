@@ -20,6 +20,7 @@
 //! - `Executor`: The core trait for all execution engines.
 //! - `SimulatedExecutor`: The "virtual exchange" for backtesting.
 //! - `Portfolio`: The in-memory state manager for a trading account.
+//! - `quote_fee`: The commission of an order's fills, converted into the quote asset.
 //! - `ExecutorError`: The specific error types that can be returned from this crate.
 
 // Declare the modules that constitute this crate.
@@ -29,5 +30,5 @@ pub mod portfolio;
 
 // Re-export the key components to provide a clean, public-facing API.
 pub use error::ExecutorError;
-pub use exchange::{Executor, LiveExecutor, SimulatedExecutor, LimitOrderExecutor};
-pub use portfolio::Portfolio;
\ No newline at end of file
+pub use exchange::{quote_fee, Executor, LiveExecutor, SimulatedExecutor, LimitOrderExecutor};
+pub use portfolio::{Portfolio, PortfolioEventSink, DEFAULT_EVENT_LOG_CAPACITY};
\ No newline at end of file
@@ -1,16 +1,55 @@
 use crate::error::ExecutorError;
-use core_types::{Execution, OrderSide, Position};
+use api_client::PositionResponse;
+use core_types::{Correction, CorrectionSource, Execution, Holding, OrderSide, PortfolioEvent, Position, PositionFill, RecordedPortfolioEvent};
 use rust_decimal::Decimal;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
+use configuration::DEFAULT_QUOTE_ASSET;
+
+/// How many of its most recent events a portfolio keeps in memory, unless told otherwise.
+pub const DEFAULT_EVENT_LOG_CAPACITY: usize = 1000;
+
+/// Takes a portfolio's events as they are applied, e.g. to store them.
+pub trait PortfolioEventSink: Send + Sync + fmt::Debug {
+    /// Takes an event just applied. It is called with the portfolio locked, so it must not
+    /// block.
+    fn record(&self, event: &RecordedPortfolioEvent);
+}
 
 /// Manages the state of a trading account, including cash, positions, and equity.
 /// Its sole responsibility is to accurately reflect the current state based on trade executions.
+///
+/// The state changes only through [`Portfolio::apply`], one [`PortfolioEvent`] at a time, and
+/// the most recent events are kept in a bounded log. A position's unrealized P&L and
+/// liquidation estimate are valuations at the latest price rather than state, and are marked
+/// on the positions directly.
 #[derive(Debug, Clone)]
 pub struct Portfolio {
+    /// The available cash, in the quote asset. With several collateral assets, their combined
+    /// value in it.
     pub cash: Decimal,
+    /// The asset that cash, equity and P&L are denominated in, e.g. "USDT".
+    pub quote_asset: String,
+    /// The account's collateral balances by asset, in each asset's own units, as last synced
+    /// from the exchange. Empty when the portfolio is simulated.
+    pub balances: BTreeMap<String, Decimal>,
     pub positions: HashMap<String, Position>,
+    /// The cumulative P&L locked in by closing (or reducing) positions, net of all fees paid.
+    pub realized_pnl: Decimal,
+    /// The cumulative fees paid across every execution, opening and closing legs alike.
+    pub total_fees_paid: Decimal,
+    /// The wallet balance last synced from the exchange, moved since by the P&L realized.
+    /// `None` when the portfolio is simulated.
+    synced_wallet_balance: Option<Decimal>,
+    /// The most recently applied events, oldest first.
+    events: VecDeque<RecordedPortfolioEvent>,
+    event_log_capacity: usize,
+    /// The sequence number of the last applied event.
+    last_sequence: u64,
+    event_sink: Option<Arc<dyn PortfolioEventSink>>,
 }
 
 impl Portfolio {
@@ -18,60 +57,185 @@ impl Portfolio {
     pub fn new(initial_capital: Decimal) -> Self {
         Self {
             cash: initial_capital,
+            quote_asset: DEFAULT_QUOTE_ASSET.to_string(),
+            balances: BTreeMap::new(),
             positions: HashMap::new(),
+            realized_pnl: Decimal::ZERO,
+            total_fees_paid: Decimal::ZERO,
+            synced_wallet_balance: None,
+            events: VecDeque::new(),
+            event_log_capacity: DEFAULT_EVENT_LOG_CAPACITY,
+            last_sequence: 0,
+            event_sink: None,
+        }
+    }
+
+    /// Denominates the portfolio in `quote_asset` rather than the default.
+    pub fn with_quote_asset(mut self, quote_asset: impl Into<String>) -> Self {
+        self.quote_asset = quote_asset.into();
+        self
+    }
+
+    /// Keeps up to `capacity` of the most recent events in memory rather than the default.
+    pub fn with_event_log_capacity(mut self, capacity: usize) -> Self {
+        self.event_log_capacity = capacity;
+        while self.events.len() > capacity {
+            self.events.pop_front();
         }
+        self
+    }
+
+    /// Hands every event applied from now on to `sink` as well.
+    pub fn set_event_sink(&mut self, sink: Arc<dyn PortfolioEventSink>) {
+        self.event_sink = Some(sink);
     }
 
-    /// Updates the portfolio state based on a trade execution.
-    /// This is the core state transition logic. It does not calculate P&L, it only mutates state.
+    /// Updates the portfolio state based on a trade execution, by applying it as an
+    /// `ExecutionApplied` event. Any position it opens gets a new random ID.
+    ///
+    /// Returns what the execution did to each position it touched, so the fills can be
+    /// linked to their positions when they are recorded.
     pub fn update_with_execution(
         &mut self,
         execution: &Execution,
-    ) -> Result<(), ExecutorError> {
+    ) -> Result<Vec<PositionFill>, ExecutorError> {
+        self.apply(PortfolioEvent::ExecutionApplied { execution: execution.clone(), opened_position_id: Uuid::new_v4() })
+    }
+
+    /// Applies `event` to the portfolio and records it in the event log. This is the one path
+    /// through which cash, balances and positions change, so that the log explains every
+    /// change and replaying it rebuilds the same state.
+    ///
+    /// Returns the position fills of an execution, and nothing for the other events. An event
+    /// that fails (an execution the cash cannot cover) changes nothing and is not recorded.
+    pub fn apply(&mut self, event: PortfolioEvent) -> Result<Vec<PositionFill>, ExecutorError> {
+        let realized_before = self.realized_pnl;
+        let fills = match &event {
+            PortfolioEvent::ExecutionApplied { execution, opened_position_id } => self.apply_execution(execution, *opened_position_id)?,
+            PortfolioEvent::FundingApplied { amount, .. } => {
+                // Funding is settled in cash as it is paid, so it is realized on the spot.
+                self.cash += amount;
+                self.realized_pnl += amount;
+                Vec::new()
+            }
+            PortfolioEvent::ReconcilerCorrection { correction, at, .. } | PortfolioEvent::ManualAdjustment { correction, at, .. } => {
+                self.apply_correction(correction, *at);
+                Vec::new()
+            }
+        };
+        if let Some(wallet_balance) = &mut self.synced_wallet_balance {
+            *wallet_balance += self.realized_pnl - realized_before;
+        }
+        self.record(event);
+        Ok(fills)
+    }
+
+    /// Applies `events` in order to this portfolio, usually a fresh one with the same starting
+    /// capital as the portfolio that recorded them, and returns it.
+    pub fn rebuild_from(mut self, events: impl IntoIterator<Item = PortfolioEvent>) -> Result<Self, ExecutorError> {
+        for event in events {
+            self.apply(event)?;
+        }
+        Ok(self)
+    }
+
+    /// Brings the portfolio in line with the exchange by applying `correction`, recorded as a
+    /// `ReconcilerCorrection` from `source` at the current time.
+    pub fn correct(&mut self, correction: Correction, source: CorrectionSource) {
+        let at = Utc::now();
+        self.apply_correction(&correction, at);
+        self.record(PortfolioEvent::ReconcilerCorrection { correction, source, at });
+    }
+
+    /// The most recently applied events, oldest first.
+    pub fn events(&self) -> &VecDeque<RecordedPortfolioEvent> {
+        &self.events
+    }
+
+    /// The core state transition of an execution. Besides cash and positions, it keeps the
+    /// realized P&L and fee ledgers in step: the fee is deducted from cash exactly once here,
+    /// and booked against `realized_pnl` whether the execution opens or closes a position.
+    fn apply_execution(&mut self, execution: &Execution, opened_position_id: Uuid) -> Result<Vec<PositionFill>, ExecutorError> {
         let cost = execution.price * execution.quantity;
         let symbol = &execution.symbol;
 
         // --- Cash Update ---
         // For a Buy, cash decreases. For a Sell, cash increases.
         // We also subtract the fee regardless of direction.
-        match execution.side {
-            OrderSide::Buy => self.cash -= cost,
-            OrderSide::Sell => self.cash += cost,
-        }
-        self.cash -= execution.fee;
+        let cash = match execution.side {
+            OrderSide::Buy => self.cash - cost,
+            OrderSide::Sell => self.cash + cost,
+        } - execution.fee;
 
-        if self.cash.is_sign_negative() {
+        if cash.is_sign_negative() {
             return Err(ExecutorError::InsufficientCash {
                 required: cost.to_string(),
-                available: (self.cash + cost + execution.fee).to_string(), // Add fee back for display
+                available: self.cash.to_string(),
             });
         }
+        self.cash = cash;
 
         // --- Position Update ---
         let position = self.positions.entry(symbol.clone()).or_insert_with(|| {
             // If the position does not exist, create a new one.
             Position {
-                position_id: Uuid::new_v4(),
+                position_id: opened_position_id,
                 symbol: symbol.clone(),
                 side: execution.side,
                 quantity: Decimal::ZERO,
                 entry_price: Decimal::ZERO, // Will be calculated below
                 unrealized_pnl: Decimal::ZERO, // Will be calculated by the backtester loop
-                last_updated: Utc::now(),
+                last_updated: execution.timestamp,
+                adds: 0,
+                last_entry_price: Decimal::ZERO, // Will be set below
+                estimated_liquidation_price: None,
             }
         });
 
         let is_closing_trade = position.quantity.is_sign_positive() && position.side != execution.side;
+        let mut fills = Vec::with_capacity(1);
 
         if is_closing_trade {
-            // Logic for closing or reducing a position.
-            if execution.quantity > position.quantity {
-                return Err(ExecutorError::InvalidClosingQuantity {
-                    requested: execution.quantity.to_string(),
-                    available: position.quantity.to_string(),
+            // Logic for closing or reducing a position. A fill larger than the position
+            // (a netted reversal) closes it and opens the remainder on the other side.
+            let closed_quantity = execution.quantity.min(position.quantity);
+            // Lock in the gross P&L of the closed quantity against the average entry price.
+            let pnl_per_unit = match position.side {
+                OrderSide::Buy => execution.price - position.entry_price,
+                OrderSide::Sell => position.entry_price - execution.price,
+            };
+            self.realized_pnl += pnl_per_unit * closed_quantity;
+            position.quantity -= closed_quantity;
+            // The fee is shared pro rata between the closing and any reversing part.
+            let closing_fee = if execution.quantity.is_zero() { execution.fee } else { execution.fee * closed_quantity / execution.quantity };
+            fills.push(PositionFill {
+                position_id: position.position_id,
+                position_side: position.side,
+                is_entry: false,
+                quantity: closed_quantity,
+                fee: closing_fee,
+                realized_pnl: pnl_per_unit * closed_quantity,
+                position_quantity: position.quantity,
+            });
+
+            let reversed_quantity = execution.quantity - closed_quantity;
+            if !reversed_quantity.is_zero() {
+                position.position_id = opened_position_id;
+                position.side = execution.side;
+                position.quantity = reversed_quantity;
+                position.entry_price = execution.price;
+                position.last_entry_price = execution.price;
+                position.adds = 0;
+                fills.push(PositionFill {
+                    position_id: position.position_id,
+                    position_side: position.side,
+                    is_entry: true,
+                    quantity: reversed_quantity,
+                    fee: execution.fee - closing_fee,
+                    realized_pnl: Decimal::ZERO,
+                    position_quantity: reversed_quantity,
                 });
             }
-            position.quantity -= execution.quantity;
         } else {
             // Logic for opening or increasing a position.
             // Calculate the new average entry price.
@@ -84,17 +248,36 @@ impl Portfolio {
             if !total_quantity.is_zero() {
                 position.entry_price = (existing_value + new_value) / total_quantity;
             }
+            // Track scale-ins so the risk manager can enforce pyramiding limits.
+            if !position.quantity.is_zero() {
+                position.adds += 1;
+            }
+            position.last_entry_price = execution.price;
             position.quantity += execution.quantity;
+            fills.push(PositionFill {
+                position_id: position.position_id,
+                position_side: position.side,
+                is_entry: true,
+                quantity: execution.quantity,
+                fee: execution.fee,
+                realized_pnl: Decimal::ZERO,
+                position_quantity: position.quantity,
+            });
         }
 
         position.last_updated = execution.timestamp;
 
+        // --- Fee Ledger ---
+        // Fees are a realized cost on every leg, so they are booked here in addition to cash.
+        self.total_fees_paid += execution.fee;
+        self.realized_pnl -= execution.fee;
+
         // If position quantity is zero after an update, remove it from the map.
         if position.quantity.is_zero() {
             self.positions.remove(symbol);
         }
 
-        Ok(())
+        Ok(fills)
     }
 
     /// Calculates the total equity of the portfolio at a given set of market prices.
@@ -125,6 +308,200 @@ impl Portfolio {
         Ok(self.cash + positions_value)
     }
 
+    /// A fast path of `calculate_total_equity` for portfolios that only trade one symbol.
+    /// It avoids building a price map, which matters in the per-bar loop of a backtest.
+    pub fn calculate_total_equity_single(
+        &self,
+        symbol: &str,
+        price: Decimal,
+    ) -> Result<Decimal, ExecutorError> {
+        let mut positions_value = Decimal::ZERO;
+
+        for position in self.positions.values() {
+            if position.symbol != symbol {
+                return Err(ExecutorError::PortfolioError(format!(
+                    "Missing market price for symbol: {}",
+                    position.symbol
+                )));
+            }
+
+            let pnl_per_unit = match position.side {
+                OrderSide::Buy => price - position.entry_price,
+                OrderSide::Sell => position.entry_price - price,
+            };
+            positions_value += (position.entry_price * position.quantity) + (pnl_per_unit * position.quantity);
+        }
+
+        Ok(self.cash + positions_value)
+    }
+
+    /// Replaces the collateral balances and sets `cash` to their combined value in the quote
+    /// asset, recording a correction from `source` for each balance that changed, and for
+    /// the cash if it did.
+    ///
+    /// `prices` holds the price of each other asset in the quote asset. Assets without a price
+    /// are kept in `balances` but left out of `cash`; they are returned so the caller can
+    /// warn about them.
+    pub fn set_collateral(&mut self, balances: BTreeMap<String, Decimal>, prices: &HashMap<String, Decimal>, source: CorrectionSource) -> Vec<String> {
+        let mut cash = Decimal::ZERO;
+        let mut unpriced = Vec::new();
+        for (asset, amount) in &balances {
+            if *asset == self.quote_asset {
+                cash += amount;
+            } else if let Some(price) = prices.get(asset) {
+                cash += amount * price;
+            } else {
+                unpriced.push(asset.clone());
+            }
+        }
+
+        let assets: BTreeSet<&String> = balances.keys().chain(self.balances.keys()).collect();
+        let changed: Vec<Correction> = assets
+            .into_iter()
+            .filter_map(|asset| {
+                let old = self.balances.get(asset).copied().unwrap_or_default();
+                let new = balances.get(asset).copied().unwrap_or_default();
+                (old != new).then(|| Correction::Balance { asset: asset.clone(), old, new })
+            })
+            .collect();
+        for correction in changed {
+            self.correct(correction, source);
+        }
+        if cash != self.cash {
+            self.correct(Correction::Cash { old: self.cash, new: cash }, source);
+        }
+        unpriced
+    }
+
+    /// Sets the account's wallet balance, its collateral before any margin is held back, as
+    /// synced from the exchange in the quote asset.
+    pub fn set_wallet_balance(&mut self, wallet_balance: Decimal) {
+        self.synced_wallet_balance = Some(wallet_balance);
+    }
+
+    /// The account's collateral before any margin is held back, the figure the open
+    /// positions' margin is posted from. Synced from the exchange, whose available balance
+    /// in `cash` already excludes the margin, and moved since by the P&L realized. A
+    /// simulated portfolio pays for its positions in full out of `cash`, so its wallet is
+    /// `cash` plus the cost of its open longs, less the proceeds of its open shorts.
+    pub fn wallet_balance(&self) -> Decimal {
+        self.synced_wallet_balance.unwrap_or_else(|| {
+            self.positions
+                .values()
+                .map(|position| match position.side {
+                    OrderSide::Buy => position.quantity * position.entry_price,
+                    OrderSide::Sell => -position.quantity * position.entry_price,
+                })
+                .sum::<Decimal>()
+                + self.cash
+        })
+    }
+
+    /// Brings the positions in line with the exchange's open positions, recording a
+    /// correction from `source` for each symbol whose position differs, and returns the
+    /// corrections.
+    ///
+    /// A position the exchange holds on the same side keeps its ID, scale-in count and last
+    /// entry price, so its fills and context stay linked; one it holds on the other side, or
+    /// that was not held locally, is a new position. The exchange's unrealized P&L is taken
+    /// as each position's valuation.
+    pub fn sync_positions(&mut self, exchange: &[PositionResponse], source: CorrectionSource) -> Vec<Correction> {
+        let live: BTreeMap<&str, &PositionResponse> = exchange
+            .iter()
+            .filter(|position| !position.position_amt.is_zero())
+            .map(|position| (position.symbol.as_str(), position))
+            .collect();
+
+        let mut closed: Vec<String> = self.positions.keys().filter(|symbol| !live.contains_key(symbol.as_str())).cloned().collect();
+        closed.sort();
+        let mut corrections: Vec<Correction> = closed
+            .into_iter()
+            .map(|symbol| {
+                let old = self.positions.get(&symbol).map(Holding::of);
+                Correction::Position { symbol, old, new: None }
+            })
+            .collect();
+        for (symbol, position) in &live {
+            let side = if position.position_amt.is_sign_positive() { OrderSide::Buy } else { OrderSide::Sell };
+            let old = self.positions.get(*symbol).map(Holding::of);
+            let position_id = old.as_ref().filter(|old| old.side == side).map_or_else(Uuid::new_v4, |old| old.position_id);
+            let new = Holding { position_id, side, quantity: position.position_amt.abs(), entry_price: position.entry_price };
+            if old.as_ref() != Some(&new) {
+                corrections.push(Correction::Position { symbol: symbol.to_string(), old, new: Some(new) });
+            }
+        }
+
+        for correction in &corrections {
+            self.correct(correction.clone(), source);
+        }
+        for (symbol, position) in live {
+            if let Some(local) = self.positions.get_mut(symbol) {
+                local.unrealized_pnl = position.un_realized_profit;
+            }
+        }
+        corrections
+    }
+
+    /// Sets a field to a correction's new value. A position kept on the same side keeps its
+    /// scale-in count and last entry price.
+    fn apply_correction(&mut self, correction: &Correction, at: DateTime<Utc>) {
+        match correction {
+            Correction::Cash { new, .. } => self.cash = *new,
+            Correction::Balance { asset, new, .. } => {
+                if new.is_zero() {
+                    self.balances.remove(asset);
+                } else {
+                    self.balances.insert(asset.clone(), *new);
+                }
+            }
+            Correction::Position { symbol, new: None, .. } => {
+                self.positions.remove(symbol);
+            }
+            Correction::Position { symbol, new: Some(holding), .. } => match self.positions.get_mut(symbol) {
+                Some(position) if position.side == holding.side => {
+                    position.position_id = holding.position_id;
+                    position.quantity = holding.quantity;
+                    position.entry_price = holding.entry_price;
+                    position.last_updated = at;
+                }
+                _ => {
+                    self.positions.insert(
+                        symbol.clone(),
+                        Position {
+                            position_id: holding.position_id,
+                            symbol: symbol.clone(),
+                            side: holding.side,
+                            quantity: holding.quantity,
+                            entry_price: holding.entry_price,
+                            unrealized_pnl: Decimal::ZERO,
+                            last_updated: at,
+                            adds: 0,
+                            last_entry_price: holding.entry_price,
+                            estimated_liquidation_price: None,
+                        },
+                    );
+                }
+            },
+        }
+    }
+
+    /// Appends an applied event to the log, dropping the oldest one when it is full, and
+    /// hands it to the sink.
+    fn record(&mut self, event: PortfolioEvent) {
+        self.last_sequence += 1;
+        let recorded = RecordedPortfolioEvent { sequence: self.last_sequence, event };
+        if let Some(sink) = &self.event_sink {
+            sink.record(&recorded);
+        }
+        if self.event_log_capacity == 0 {
+            return;
+        }
+        if self.events.len() == self.event_log_capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(recorded);
+    }
+
     /// A simple utility to get a snapshot of a single position.
     pub fn get_position(&self, symbol: &str) -> Option<&Position> {
         self.positions.get(symbol)
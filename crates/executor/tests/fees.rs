@@ -0,0 +1,72 @@
+//! Fees are recorded in the quote asset and charged to the portfolio once per fill.
+
+use api_client::UserTradeResponse;
+use chrono::Utc;
+use core_types::{Execution, OrderSide};
+use executor::{quote_fee, Portfolio};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::MockAccount;
+use uuid::Uuid;
+
+fn trade(commission: Decimal, commission_asset: &str) -> UserTradeResponse {
+    UserTradeResponse {
+        id: 1,
+        order_id: 1,
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        price: dec!(100),
+        qty: dec!(1),
+        quote_qty: dec!(100),
+        realized_pnl: Decimal::ZERO,
+        commission,
+        commission_asset: commission_asset.to_string(),
+        maker: false,
+        time: 0,
+    }
+}
+
+fn execution(side: OrderSide, fee: Decimal) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price: dec!(100),
+        quantity: dec!(10),
+        fee,
+        fee_asset: "USDT".to_string(),
+        timestamp: Utc::now(),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+#[tokio::test]
+async fn commissions_in_other_assets_are_converted_to_the_quote_asset() {
+    let account = MockAccount::new().with_mark_price("BNBUSDT", dec!(600));
+
+    let fills = [trade(dec!(0.5), "USDT"), trade(dec!(0.001), "BNB"), trade(dec!(0.002), "BNB")];
+    assert_eq!(quote_fee(&account, &fills, "USDT").await, dec!(2.3));
+
+    // A commission whose pair has no price is left out rather than counted as quote.
+    let fills = [trade(dec!(0.5), "USDT"), trade(dec!(3), "XYZ")];
+    assert_eq!(quote_fee(&account, &fills, "USDT").await, dec!(0.5));
+}
+
+#[test]
+fn a_round_trip_is_charged_both_fees_once() {
+    let initial = dec!(10000);
+    let fee = dec!(0.4);
+    let mut portfolio = Portfolio::new(initial);
+
+    portfolio.update_with_execution(&execution(OrderSide::Buy, fee)).unwrap();
+    portfolio.update_with_execution(&execution(OrderSide::Sell, fee)).unwrap();
+
+    assert!(portfolio.positions.is_empty());
+    assert_eq!(portfolio.cash, initial - fee * dec!(2));
+    assert_eq!(portfolio.realized_pnl, -fee * dec!(2));
+    assert_eq!(portfolio.total_fees_paid, fee * dec!(2));
+}
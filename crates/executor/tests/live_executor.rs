@@ -0,0 +1,149 @@
+//! `LiveExecutor` and `LimitOrderExecutor` against a scripted exchange account.
+
+use api_client::{OrderResponse, UserTradeResponse};
+use chrono::{TimeZone, Utc};
+use core_types::{Kline, OrderRequest, OrderSide, OrderType};
+use executor::{Executor, LimitOrderExecutor, LiveExecutor};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::sync::Arc;
+use testing::MockAccount;
+use uuid::Uuid;
+
+fn market_buy(quantity: Decimal) -> OrderRequest {
+    OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        order_type: OrderType::Market,
+        quantity,
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: false,
+        decision_id: None,
+    }
+}
+
+/// The exchange's answer to `order`, filled in full at `avg_price`.
+fn filled(order: &OrderRequest, avg_price: Decimal) -> OrderResponse {
+    OrderResponse {
+        client_order_id: order.client_order_id.to_string(),
+        cum_qty: order.quantity,
+        cum_quote: order.quantity * avg_price,
+        executed_qty: order.quantity,
+        order_id: 7,
+        avg_price,
+        orig_qty: order.quantity,
+        price: Decimal::ZERO,
+        reduce_only: order.reduce_only,
+        side: order.side,
+        status: "FILLED".to_string(),
+        stop_price: Decimal::ZERO,
+        symbol: order.symbol.clone(),
+        time_in_force: "GTC".to_string(),
+        order_type: "MARKET".to_string(),
+        update_time: None,
+    }
+}
+
+fn kline() -> Kline {
+    let open_time = Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap();
+    Kline {
+        open_time,
+        open: dec!(100),
+        high: dec!(100),
+        low: dec!(100),
+        close: dec!(100),
+        volume: dec!(1000),
+        close_time: open_time + chrono::Duration::hours(1) - chrono::Duration::milliseconds(1),
+        interval: "1h".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn an_accepted_order_executes_at_its_average_fill_price() {
+    let order = market_buy(dec!(0.5));
+    let account = Arc::new(MockAccount::new().with_order(filled(&order, dec!(100.2)), Vec::new()));
+    let executor = LiveExecutor::new(account.clone());
+
+    let execution = executor.execute(&order, &kline(), None, None).await.expect("execute");
+
+    assert_eq!((execution.price, execution.quantity), (dec!(100.2), dec!(0.5)));
+    assert_eq!(execution.client_order_id, order.client_order_id);
+    assert_eq!(account.placed_orders(), [order]);
+}
+
+#[tokio::test]
+async fn a_rejected_order_fails_to_execute() {
+    let account = Arc::new(MockAccount::new());
+    let executor = LiveExecutor::new(account.clone());
+
+    assert!(executor.execute(&market_buy(dec!(0.5)), &kline(), None, None).await.is_err());
+    assert_eq!(account.placed_orders().len(), 1);
+}
+
+/// The exchange's answer to a limit buy of `order` resting at `price`, with `executed_qty`
+/// of it filled.
+fn limit_placed(order: &OrderRequest, price: Decimal, executed_qty: Decimal) -> OrderResponse {
+    OrderResponse {
+        executed_qty,
+        price,
+        avg_price: Decimal::ZERO,
+        status: if executed_qty > Decimal::ZERO { "FILLED" } else { "NEW" }.to_string(),
+        time_in_force: "GTX".to_string(),
+        order_type: "LIMIT".to_string(),
+        ..filled(order, Decimal::ZERO)
+    }
+}
+
+/// A buy fill of order 7 for `qty` at `price`, `seconds` into 2025.
+fn buy_fill(price: Decimal, qty: Decimal, maker: bool, seconds: i64) -> UserTradeResponse {
+    UserTradeResponse {
+        id: seconds,
+        order_id: 7,
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        price,
+        qty,
+        quote_qty: price * qty,
+        realized_pnl: Decimal::ZERO,
+        commission: Decimal::ZERO,
+        commission_asset: "USDT".to_string(),
+        maker,
+        time: (Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap() + chrono::Duration::seconds(seconds)).timestamp_millis(),
+    }
+}
+
+#[tokio::test]
+async fn a_resting_limit_order_records_its_placement_without_a_fill() {
+    let order = market_buy(dec!(0.5));
+    let account = Arc::new(MockAccount::new().with_order(limit_placed(&order, dec!(99.9), Decimal::ZERO), Vec::new()));
+    let executor = LimitOrderExecutor::new(account);
+
+    let execution = executor.execute(&order, &kline(), Some(dec!(99.9)), Some(dec!(100.1))).await.expect("execute");
+
+    let placement = execution.placement.expect("placement");
+    assert_eq!((placement.best_bid, placement.best_ask, placement.limit_price), (dec!(99.9), dec!(100.1), dec!(99.9)));
+    assert!(!placement.is_confirmed());
+    assert_eq!((placement.fill_price, placement.is_maker, placement.filled_at, placement.price_improvement_bps), (None, None, None, None));
+    assert_eq!(execution.price, dec!(99.9));
+}
+
+#[tokio::test]
+async fn a_filled_limit_order_is_measured_at_its_confirmed_fills() {
+    let order = market_buy(dec!(0.5));
+    let fills = vec![buy_fill(dec!(99.9), dec!(0.3), true, 1), buy_fill(dec!(100.1), dec!(0.2), false, 2)];
+    let account = Arc::new(MockAccount::new().with_order(limit_placed(&order, dec!(99.9), dec!(0.5)), fills));
+    let executor = LimitOrderExecutor::new(account);
+
+    let execution = executor.execute(&order, &kline(), Some(dec!(99.9)), Some(dec!(100.1))).await.expect("execute");
+
+    let placement = execution.placement.expect("placement");
+    // 0.3 at 99.9 and 0.2 at 100.1 average 99.98, 12 bps under the ask; one fill took.
+    assert_eq!(placement.fill_price, Some(dec!(99.98)));
+    assert_eq!(placement.is_maker, Some(false));
+    assert_eq!(placement.price_improvement_bps, Some(dec!(12)));
+    assert_eq!(placement.filled_at, Some(Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 2).unwrap()));
+    assert_eq!((execution.price, execution.is_maker), (dec!(99.98), false));
+}
@@ -0,0 +1,166 @@
+//! Every change to a portfolio is an event in its log, and replaying the log rebuilds it.
+
+use api_client::PositionResponse;
+use chrono::{DateTime, TimeZone, Utc};
+use core_types::{Correction, CorrectionSource, Execution, Holding, OrderSide, PortfolioEvent, Position, RecordedPortfolioEvent};
+use executor::{Portfolio, PortfolioEventSink};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+fn at(minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 8, 12, minute, 0).unwrap()
+}
+
+fn execution(symbol: &str, side: OrderSide, quantity: Decimal, price: Decimal, minute: u32) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        side,
+        price,
+        quantity,
+        fee: price * quantity * dec!(0.0004),
+        fee_asset: "USDT".to_string(),
+        timestamp: at(minute),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+fn exchange_position(symbol: &str, position_amt: Decimal, entry_price: Decimal) -> PositionResponse {
+    PositionResponse {
+        entry_price,
+        leverage: "10".to_string(),
+        max_notional_value: "1000000".to_string(),
+        liquidation_price: Decimal::ZERO,
+        mark_price: entry_price,
+        position_amt,
+        symbol: symbol.to_string(),
+        un_realized_profit: dec!(1.5),
+    }
+}
+
+/// The portfolio's state, leaving out the positions' valuation, which is marked rather than
+/// replayed.
+fn state(portfolio: &Portfolio) -> (Decimal, BTreeMap<String, Decimal>, BTreeMap<String, Position>, Decimal, Decimal) {
+    let positions = portfolio
+        .positions
+        .iter()
+        .map(|(symbol, position)| (symbol.clone(), Position { unrealized_pnl: Decimal::ZERO, ..position.clone() }))
+        .collect();
+    (portfolio.cash, portfolio.balances.clone(), positions, portfolio.realized_pnl, portfolio.total_fees_paid)
+}
+
+fn recorded(portfolio: &Portfolio) -> Vec<PortfolioEvent> {
+    portfolio.events().iter().map(|recorded| recorded.event.clone()).collect()
+}
+
+#[test]
+fn replaying_recorded_events_reproduces_the_final_state() {
+    let mut portfolio = Portfolio::new(dec!(10000));
+    portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Buy, dec!(1), dec!(100), 0)).unwrap();
+    portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Buy, dec!(1), dec!(110), 1)).unwrap();
+    portfolio.update_with_execution(&execution("ETHUSDT", OrderSide::Sell, dec!(2), dec!(50), 2)).unwrap();
+    portfolio.apply(PortfolioEvent::FundingApplied { symbol: "BTCUSDT".to_string(), amount: dec!(-2.5), at: at(3) }).unwrap();
+    // A netted reversal: closes the long 2 and opens a short 1.
+    portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Sell, dec!(3), dec!(120), 4)).unwrap();
+    portfolio
+        .apply(PortfolioEvent::ManualAdjustment { correction: Correction::Cash { old: portfolio.cash, new: dec!(9000) }, reason: "withdrawal".to_string(), at: at(5) })
+        .unwrap();
+    let balances = BTreeMap::from([("USDT".to_string(), dec!(8000)), ("USDC".to_string(), dec!(1000))]);
+    portfolio.set_collateral(balances, &HashMap::from([("USDC".to_string(), dec!(0.999))]), CorrectionSource::StartupSync);
+    let corrections = portfolio.sync_positions(
+        &[exchange_position("BTCUSDT", dec!(-1.5), dec!(119)), exchange_position("SOLUSDT", dec!(4), dec!(20))],
+        CorrectionSource::Reconciler,
+    );
+    assert_eq!(corrections.len(), 3, "BTCUSDT resized, ETHUSDT closed and SOLUSDT opened: {:?}", corrections);
+
+    let rebuilt = Portfolio::new(dec!(10000)).rebuild_from(recorded(&portfolio)).unwrap();
+
+    assert_eq!(state(&rebuilt), state(&portfolio));
+    assert_eq!(rebuilt.events(), portfolio.events());
+    assert_eq!(portfolio.events().iter().map(|recorded| recorded.sequence).collect::<Vec<_>>(), (1..=12).collect::<Vec<_>>());
+}
+
+#[test]
+fn the_reconciler_corrects_positions_without_losing_local_history() {
+    let mut portfolio = Portfolio::new(dec!(10000));
+    portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Buy, dec!(0.010), dec!(100), 0)).unwrap();
+    portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Buy, dec!(0.002), dec!(90), 1)).unwrap();
+    let before = portfolio.get_position("BTCUSDT").unwrap().clone();
+
+    let corrections = portfolio.sync_positions(&[exchange_position("BTCUSDT", dec!(0.013), before.entry_price)], CorrectionSource::Reconciler);
+
+    // The position keeps its ID and entry decomposition; only the quantity was overwritten.
+    let after = portfolio.get_position("BTCUSDT").unwrap();
+    assert_eq!((after.position_id, after.adds, after.last_entry_price), (before.position_id, 1, dec!(90)));
+    assert_eq!(after.quantity, dec!(0.013));
+    assert_eq!(after.unrealized_pnl, dec!(1.5));
+    assert_eq!(corrections, [Correction::Position { symbol: "BTCUSDT".to_string(), old: Some(Holding::of(&before)), new: Some(Holding::of(after)) }]);
+
+    let event = &portfolio.events().back().unwrap().event;
+    assert!(matches!(event, PortfolioEvent::ReconcilerCorrection { source: CorrectionSource::Reconciler, .. }));
+    let description = event.to_string();
+    assert!(description.starts_with("reconciler changed BTCUSDT quantity from 0.012 to 0.013 at "), "{}", description);
+
+    // Nothing differs now, so there is nothing to correct.
+    let events = portfolio.events().len();
+    assert!(portfolio.sync_positions(&[exchange_position("BTCUSDT", dec!(0.013), before.entry_price)], CorrectionSource::Reconciler).is_empty());
+    assert_eq!(portfolio.events().len(), events);
+
+    // A position the exchange holds on the other side is a new one.
+    portfolio.sync_positions(&[exchange_position("BTCUSDT", dec!(-0.005), dec!(101))], CorrectionSource::Reconciler);
+    let flipped = portfolio.get_position("BTCUSDT").unwrap();
+    assert_ne!(flipped.position_id, before.position_id);
+    assert_eq!((flipped.side, flipped.adds), (OrderSide::Sell, 0));
+    assert!(portfolio.events().back().unwrap().event.to_string().contains("BTCUSDT from long 0.013 @ "));
+}
+
+/// Keeps every event it is handed.
+#[derive(Debug, Default)]
+struct Collect(Mutex<Vec<RecordedPortfolioEvent>>);
+
+impl PortfolioEventSink for Collect {
+    fn record(&self, event: &RecordedPortfolioEvent) {
+        self.0.lock().unwrap().push(event.clone());
+    }
+}
+
+#[test]
+fn the_log_is_bounded_and_a_failed_event_changes_nothing() {
+    let sink = Arc::new(Collect::default());
+    let mut portfolio = Portfolio::new(dec!(100)).with_event_log_capacity(3);
+    portfolio.set_event_sink(sink.clone());
+    for minute in 0..5 {
+        portfolio.apply(PortfolioEvent::FundingApplied { symbol: "BTCUSDT".to_string(), amount: dec!(1), at: at(minute) }).unwrap();
+    }
+    assert_eq!(portfolio.events().iter().map(|recorded| recorded.sequence).collect::<Vec<_>>(), [3, 4, 5]);
+    // The sink saw them all.
+    assert_eq!(sink.0.lock().unwrap().len(), 5);
+
+    let before = state(&portfolio);
+    assert!(portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Buy, dec!(1), dec!(1000), 6)).is_err());
+    assert_eq!(state(&portfolio), before);
+    assert_eq!(portfolio.events().back().unwrap().sequence, 5);
+    assert_eq!(sink.0.lock().unwrap().len(), 5);
+}
+
+#[test]
+fn a_simulated_wallet_balance_holds_the_cost_of_open_positions() {
+    let mut portfolio = Portfolio::new(dec!(10000));
+    portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Buy, dec!(0.1), dec!(30000), 0)).unwrap();
+    portfolio.update_with_execution(&execution("ETHUSDT", OrderSide::Sell, dec!(1), dec!(2000), 1)).unwrap();
+
+    // Cash paid 3,000 for the long and took 2,000 for the short, less 2 in fees.
+    assert_eq!(portfolio.cash, dec!(8998));
+    assert_eq!(portfolio.wallet_balance(), dec!(9998));
+
+    // Closing the long at 31,000 realizes 100 less 1.24 in fees.
+    portfolio.update_with_execution(&execution("BTCUSDT", OrderSide::Sell, dec!(0.1), dec!(31000), 2)).unwrap();
+    assert_eq!(portfolio.wallet_balance(), dec!(10096.76));
+}
@@ -1,10 +1,51 @@
 use anyhow::Result;
-use core_types::Kline;
+use core_types::{BookSummary, Kline};
 use polars::prelude::*;
 use rust_decimal::prelude::*;
 use ta::indicators::{RelativeStrengthIndex as Rsi, MovingAverageConvergenceDivergence as Macd};
 use ta::Next;
 use chrono::{Timelike, Datelike};
+use microstructure::MicrostructureFeatures;
+use std::collections::VecDeque;
+
+pub mod microstructure;
+pub mod parity;
+
+/// The window of the percentile rank that normalizes the RSI.
+pub const RSI_RANK_PERIOD: usize = 252;
+
+/// How many klines each kline feature needs before it has a value, for the features that need
+/// more than the kline itself. The RSI and MACD are exponential and have a value from the
+/// first kline, though it only settles later.
+pub const FEATURE_LOOKBACKS: [(&str, usize); 11] = [
+    ("rsi_14_rank", RSI_RANK_PERIOD),
+    ("rsi_momentum", 2),
+    ("returns_1h", 2),
+    ("returns_4h", 5),
+    ("returns_24h", 25),
+    ("volatility_1h", 2),
+    ("volatility_4h", 5),
+    ("volatility_24h", 25),
+    ("price_vs_sma20", 20),
+    ("price_vs_sma50", 50),
+    ("bb_position", 20),
+];
+
+/// The klines needed before every kline feature has a value: the longest lookback in
+/// `FEATURE_LOOKBACKS`. A model trained on datasets without null rows has never seen fewer.
+pub const MAX_FEATURE_LOOKBACK: usize = max_lookback(&FEATURE_LOOKBACKS);
+
+const fn max_lookback(lookbacks: &[(&str, usize)]) -> usize {
+    let mut max = 0;
+    let mut i = 0;
+    while i < lookbacks.len() {
+        if lookbacks[i].1 > max {
+            max = lookbacks[i].1;
+        }
+        i += 1;
+    }
+    max
+}
 
 /// Generates a DataFrame of predictive features from a slice of Kline data.
 pub fn generate_features(klines: &[Kline]) -> Result<DataFrame> {
@@ -21,7 +62,7 @@ pub fn generate_features(klines: &[Kline]) -> Result<DataFrame> {
     
     // --- Enhanced Technical Indicators ---
     let rsi_14 = calculate_rsi(&closes, 14);
-    let rsi_14_rank = rank_normalize(Series::new("rsi_14_raw", rsi_14.clone()), 252);
+    let rsi_14_rank = rank_normalize(Series::new("rsi_14_raw", rsi_14.clone()), RSI_RANK_PERIOD);
     
     let macd_hist = calculate_macd_hist(&closes, 12, 26, 9);
     let macd_signal = calculate_macd_signal(&closes, 12, 26, 9);
@@ -89,6 +130,89 @@ pub fn generate_features(klines: &[Kline]) -> Result<DataFrame> {
     Ok(df)
 }
 
+/// Generates the kline features followed by the microstructure features, from one book
+/// summary per kline (None for klines without book data).
+pub fn generate_features_with_book(klines: &[Kline], summaries: &[Option<BookSummary>]) -> Result<DataFrame> {
+    anyhow::ensure!(
+        klines.len() == summaries.len(),
+        "{} book summaries for {} klines",
+        summaries.len(),
+        klines.len()
+    );
+    let book = microstructure::generate_microstructure_features(summaries)?;
+    Ok(generate_features(klines)?.hstack(book.get_columns())?)
+}
+
+/// The features of each newest kline, computed the way a live strategy does: the kline
+/// features over a rolling buffer of recent klines and, when enabled, the microstructure
+/// features incrementally.
+#[derive(Debug, Clone)]
+pub struct LiveFeatures {
+    klines: VecDeque<Kline>,
+    capacity: usize,
+    microstructure: Option<MicrostructureFeatures>,
+}
+
+impl LiveFeatures {
+    /// The most klines kept by default; older ones no longer affect the kline features.
+    pub const DEFAULT_CAPACITY: usize = 500;
+
+    pub fn new(with_book: bool) -> Self {
+        Self::with_capacity(with_book, Self::DEFAULT_CAPACITY)
+    }
+
+    /// Keeps the newest `capacity` klines, at least one. Below `MAX_FEATURE_LOOKBACK`, some
+    /// features never get a value.
+    pub fn with_capacity(with_book: bool, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            // Twice the room, so the buffer only wraps, and is moved back into one slice for
+            // the features, once every `capacity` klines.
+            klines: VecDeque::with_capacity(2 * capacity),
+            capacity,
+            microstructure: with_book.then(MicrostructureFeatures::new),
+        }
+    }
+
+    /// The most klines kept.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The buffered klines, oldest first.
+    pub fn klines(&self) -> &VecDeque<Kline> {
+        &self.klines
+    }
+
+    /// Adds a closed kline, with the summary of the book tickers received during it, and
+    /// returns the single-row frame of its features. Features that cannot be computed yet
+    /// are null.
+    pub fn push(&mut self, kline: &Kline, book: Option<&BookSummary>) -> Result<DataFrame> {
+        if self.klines.len() == self.capacity {
+            self.klines.pop_front();
+        }
+        self.klines.push_back(kline.clone());
+        let row = generate_features(self.klines.make_contiguous())?.tail(Some(1));
+        let Some(microstructure) = self.microstructure.as_mut() else {
+            return Ok(row);
+        };
+        let values = microstructure.push(book).values();
+        let columns: Vec<Series> = microstructure::MICROSTRUCTURE_FEATURES
+            .iter()
+            .zip(values)
+            .map(|(name, value)| Series::new(name, [value]))
+            .collect();
+        Ok(row.hstack(&columns)?)
+    }
+
+    pub fn reset(&mut self) {
+        self.klines.clear();
+        if let Some(microstructure) = self.microstructure.as_mut() {
+            microstructure.reset();
+        }
+    }
+}
+
 /// Helper to calculate RSI for a series of closing prices.
 fn calculate_rsi(closes: &[f64], period: usize) -> Vec<Option<f64>> {
     let mut rsi = Rsi::new(period).unwrap();
@@ -121,7 +245,7 @@ fn rank_normalize(series: Series, period: usize) -> Series {
         }
         
         // Get the window of values
-        let window_start = i - period + 1;
+        let window_start = i + 1 - period;
         let window_values: Vec<f64> = values
             .into_no_null_iter()
             .skip(window_start)
@@ -218,7 +342,7 @@ fn calculate_sma(closes: &[f64], period: usize) -> Vec<Option<f64>> {
             continue;
         }
         
-        let sum: f64 = closes[i - period + 1..=i].iter().sum();
+        let sum: f64 = closes[i + 1 - period..=i].iter().sum();
         sma.push(Some(sum / period as f64));
     }
     
@@ -242,7 +366,7 @@ fn calculate_bollinger_position(closes: &[f64], period: usize, std_dev: f64) ->
             continue;
         }
         
-        let window = &closes[i - period + 1..=i];
+        let window = &closes[i + 1 - period..=i];
         let sma = window.iter().sum::<f64>() / period as f64;
         
         let variance = window.iter()
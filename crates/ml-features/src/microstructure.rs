@@ -0,0 +1,137 @@
+//! Order book microstructure features, from the book ticker updates received during each
+//! kline: the mean relative spread, its rolling mean, and the mean top-of-book imbalance.
+//!
+//! Datasets compute them in one pass over recorded book tickers; the live engine summarises
+//! the updates as they arrive and `MicrostructureFeatures` extends the columns one kline at
+//! a time. Both paths must agree; see `parity`.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use core_types::{BookSummary, BookTicker, Kline};
+use polars::prelude::*;
+use std::collections::VecDeque;
+
+/// The mean relative spread over the kline.
+pub const RELATIVE_SPREAD: &str = "relative_spread";
+/// The mean of `relative_spread` over the last `SPREAD_MEAN_PERIOD` klines.
+pub const RELATIVE_SPREAD_MEAN: &str = "relative_spread_mean_24";
+/// The mean top-of-book imbalance over the kline.
+pub const BOOK_IMBALANCE: &str = "book_imbalance";
+
+/// The microstructure columns, in the order they are generated.
+pub const MICROSTRUCTURE_FEATURES: [&str; 3] = [RELATIVE_SPREAD, RELATIVE_SPREAD_MEAN, BOOK_IMBALANCE];
+
+/// The number of klines `relative_spread_mean_24` averages over.
+pub const SPREAD_MEAN_PERIOD: usize = 24;
+
+/// Summarises recorded book tickers per kline, both ends of each kline included. Klines
+/// without updates get None. `tickers` must be sorted by timestamp.
+pub fn summarise_book_tickers(klines: &[Kline], tickers: &[BookTicker]) -> Vec<Option<BookSummary>> {
+    klines
+        .iter()
+        .map(|kline| {
+            let start = tickers.partition_point(|ticker| ticker.timestamp < kline.open_time);
+            let end = tickers.partition_point(|ticker| ticker.timestamp <= kline.close_time);
+            let mut summary = BookSummary::default();
+            for ticker in &tickers[start..end.max(start)] {
+                summary.record(ticker);
+            }
+            (!summary.is_empty()).then_some(summary)
+        })
+        .collect()
+}
+
+/// Lines summaries keyed by kline open time, such as those `DbRepository::get_book_summaries`
+/// returns, up with `klines`. Klines without a summary get None.
+pub fn align_book_summaries(klines: &[Kline], summaries: &[(DateTime<Utc>, BookSummary)]) -> Vec<Option<BookSummary>> {
+    klines
+        .iter()
+        .map(|kline| {
+            summaries
+                .binary_search_by_key(&kline.open_time, |(open_time, _)| *open_time)
+                .ok()
+                .map(|index| summaries[index].1)
+        })
+        .collect()
+}
+
+/// Generates the microstructure columns, one row per kline summary.
+pub fn generate_microstructure_features(summaries: &[Option<BookSummary>]) -> Result<DataFrame> {
+    let spreads: Vec<Option<f64>> = summaries.iter().map(|summary| summary.and_then(|s| s.mean_relative_spread())).collect();
+    let imbalances: Vec<Option<f64>> = summaries.iter().map(|summary| summary.and_then(|s| s.mean_imbalance())).collect();
+    let spread_means = calculate_rolling_mean(&spreads, SPREAD_MEAN_PERIOD);
+
+    Ok(DataFrame::new(vec![
+        Series::new(RELATIVE_SPREAD, spreads),
+        Series::new(RELATIVE_SPREAD_MEAN, spread_means),
+        Series::new(BOOK_IMBALANCE, imbalances),
+    ])?)
+}
+
+/// The mean of the values present in each trailing window of `period`, once a full window
+/// has passed. Klines without book data don't count towards the mean.
+fn calculate_rolling_mean(values: &[Option<f64>], period: usize) -> Vec<Option<f64>> {
+    (0..values.len())
+        .map(|i| {
+            if i + 1 < period {
+                return None;
+            }
+            let present: Vec<f64> = values[i + 1 - period..=i].iter().flatten().copied().collect();
+            (!present.is_empty()).then(|| present.iter().sum::<f64>() / present.len() as f64)
+        })
+        .collect()
+}
+
+/// The microstructure features of the newest kline.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct MicrostructureRow {
+    pub relative_spread: Option<f64>,
+    pub relative_spread_mean: Option<f64>,
+    pub book_imbalance: Option<f64>,
+}
+
+impl MicrostructureRow {
+    /// The row's values, in the order of `MICROSTRUCTURE_FEATURES`.
+    pub fn values(&self) -> [Option<f64>; 3] {
+        [self.relative_spread, self.relative_spread_mean, self.book_imbalance]
+    }
+}
+
+/// Computes the microstructure features incrementally, one kline summary at a time, as the
+/// live engine sees them.
+#[derive(Debug, Clone, Default)]
+pub struct MicrostructureFeatures {
+    spreads: VecDeque<Option<f64>>,
+    klines_seen: usize,
+}
+
+impl MicrostructureFeatures {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the summary of the kline that just closed, None if no book tickers arrived
+    /// during it, and returns its features.
+    pub fn push(&mut self, summary: Option<&BookSummary>) -> MicrostructureRow {
+        let relative_spread = summary.and_then(BookSummary::mean_relative_spread);
+        self.spreads.push_back(relative_spread);
+        if self.spreads.len() > SPREAD_MEAN_PERIOD {
+            self.spreads.pop_front();
+        }
+        self.klines_seen += 1;
+
+        let relative_spread_mean = if self.klines_seen >= SPREAD_MEAN_PERIOD {
+            let present: Vec<f64> = self.spreads.iter().flatten().copied().collect();
+            (!present.is_empty()).then(|| present.iter().sum::<f64>() / present.len() as f64)
+        } else {
+            None
+        };
+
+        MicrostructureRow { relative_spread, relative_spread_mean, book_imbalance: summary.and_then(BookSummary::mean_imbalance) }
+    }
+
+    pub fn reset(&mut self) {
+        self.spreads.clear();
+        self.klines_seen = 0;
+    }
+}
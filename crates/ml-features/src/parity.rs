@@ -0,0 +1,89 @@
+//! Checks that the features a model is trained on match the features it sees live.
+//!
+//! Datasets are generated in one batch over recorded klines and book tickers, while a live
+//! strategy computes each kline's features as it closes, from a rolling buffer and the book
+//! updates the engine summarised. Any difference between the two is train/serve skew that
+//! silently degrades the model, so both paths are run over the same recorded period and
+//! compared value by value.
+
+use crate::microstructure::summarise_book_tickers;
+use crate::{generate_features_with_book, LiveFeatures};
+use anyhow::Result;
+use core_types::{BookTicker, BookWindow, Kline};
+use polars::prelude::*;
+use std::fmt;
+
+/// A feature value that differs between the batch and live paths.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureMismatch {
+    pub column: String,
+    pub row: usize,
+    pub batch: Option<f64>,
+    pub live: Option<f64>,
+}
+
+impl fmt::Display for FeatureMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at row {}: batch {:?}, live {:?}", self.column, self.row, self.batch, self.live)
+    }
+}
+
+/// The features of `klines` as a dataset is generated: in one pass, with the book tickers
+/// summarised per kline. `tickers` must be sorted by timestamp.
+pub fn batch_features(klines: &[Kline], tickers: &[BookTicker]) -> Result<DataFrame> {
+    generate_features_with_book(klines, &summarise_book_tickers(klines, tickers))
+}
+
+/// The features of `klines` as a live strategy computes them: the book tickers are fed to a
+/// `BookWindow` as they would arrive, interleaved with the klines by time, and each kline's
+/// features are computed by `LiveFeatures` when it closes. `tickers` must be sorted by
+/// timestamp.
+pub fn live_features(klines: &[Kline], tickers: &[BookTicker]) -> Result<DataFrame> {
+    let mut window = BookWindow::default();
+    let mut features = LiveFeatures::new(true);
+    let mut remaining = tickers.iter().peekable();
+    let mut rows: Option<DataFrame> = None;
+
+    for kline in klines {
+        while let Some(ticker) = remaining.next_if(|ticker| ticker.timestamp <= kline.close_time) {
+            window.record(ticker);
+        }
+        let row = features.push(kline, window.summary(kline.open_time, kline.close_time).as_ref())?;
+        match rows.as_mut() {
+            Some(rows) => {
+                rows.vstack_mut(&row)?;
+            }
+            None => rows = Some(row),
+        }
+    }
+    Ok(rows.unwrap_or_default())
+}
+
+/// Compares two feature frames value by value. Values match when both are null or they
+/// differ by at most `tolerance`. The frames must have the same columns and height.
+pub fn compare_features(batch: &DataFrame, live: &DataFrame, tolerance: f64) -> Result<Vec<FeatureMismatch>> {
+    anyhow::ensure!(
+        batch.get_column_names() == live.get_column_names(),
+        "the batch features {:?} differ from the live features {:?}",
+        batch.get_column_names(),
+        live.get_column_names()
+    );
+    anyhow::ensure!(batch.height() == live.height(), "{} batch rows but {} live rows", batch.height(), live.height());
+
+    let mut mismatches = Vec::new();
+    for (batch_column, live_column) in batch.get_columns().iter().zip(live.get_columns()) {
+        let batch_values = batch_column.cast(&DataType::Float64)?;
+        let live_values = live_column.cast(&DataType::Float64)?;
+        for (row, (batch_value, live_value)) in batch_values.f64()?.into_iter().zip(live_values.f64()?).enumerate() {
+            let matches = match (batch_value, live_value) {
+                (Some(batch_value), Some(live_value)) => (batch_value - live_value).abs() <= tolerance,
+                (None, None) => true,
+                _ => false,
+            };
+            if !matches {
+                mismatches.push(FeatureMismatch { column: batch_column.name().to_string(), row, batch: batch_value, live: live_value });
+            }
+        }
+    }
+    Ok(mismatches)
+}
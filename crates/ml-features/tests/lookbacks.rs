@@ -0,0 +1,89 @@
+//! Checks the lookbacks the live buffer is sized from against the features themselves, and
+//! that a live buffer, however it wraps, computes the trainer's batch values.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::Kline;
+use ml_features::{generate_features, LiveFeatures, FEATURE_LOOKBACKS, MAX_FEATURE_LOOKBACK, RSI_RANK_PERIOD};
+use polars::prelude::*;
+use rust_decimal::prelude::*;
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Hourly klines on two waves, so the RSI and its rank keep moving.
+fn klines(count: usize) -> Vec<Kline> {
+    (0..count)
+        .map(|i| {
+            let open_time = start() + Duration::hours(i as i64);
+            let close = 100.0 + 10.0 * (i as f64 / 7.0).sin() + 4.0 * (i as f64 / 61.0).cos() + (i % 5) as f64 * 0.3;
+            let close = Decimal::from_f64(close).unwrap().round_dp(2);
+            Kline {
+                open_time,
+                open: close - Decimal::ONE,
+                high: close + Decimal::TWO,
+                low: close - Decimal::TWO,
+                close,
+                volume: Decimal::from(1000 + (i % 13) as i64 * 10),
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: "1h".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// The value of `column` in the last row of `frame`.
+fn last(frame: &DataFrame, column: &str) -> Option<f64> {
+    let values = frame.column(column).unwrap().cast(&DataType::Float64).unwrap();
+    values.f64().unwrap().get(frame.height() - 1)
+}
+
+#[test]
+fn each_feature_has_a_value_once_its_lookback_is_met() {
+    let batch = generate_features(&klines(300)).unwrap();
+    for column in batch.get_columns() {
+        // The klines up to and including the first with a value.
+        let needed = column.is_not_null().into_iter().position(|has_value| has_value == Some(true)).unwrap() + 1;
+        let lookback = FEATURE_LOOKBACKS.iter().find(|(name, _)| *name == column.name()).map_or(1, |(_, bars)| *bars);
+        assert_eq!(needed, lookback, "{} first has a value at kline {}", column.name(), needed);
+        assert_eq!(column.slice((needed - 1) as i64, batch.height()).null_count(), 0, "{} loses its value", column.name());
+    }
+    assert_eq!(MAX_FEATURE_LOOKBACK, RSI_RANK_PERIOD);
+}
+
+#[test]
+fn the_live_rank_at_its_first_bar_matches_the_trainers_batch() {
+    let klines = klines(400);
+    let batch = generate_features(&klines).unwrap();
+    let batch_rank = batch.column("rsi_14_rank").unwrap().f64().unwrap().clone();
+
+    let mut live = LiveFeatures::new(false);
+    for (i, kline) in klines[..MAX_FEATURE_LOOKBACK].iter().enumerate() {
+        let row = live.push(kline, None).unwrap();
+        if i + 1 < RSI_RANK_PERIOD {
+            assert_eq!(last(&row, "rsi_14_rank"), None, "the rank has a value at kline {}", i + 1);
+        } else {
+            assert_eq!(last(&row, "rsi_14_rank"), batch_rank.get(i));
+            assert_eq!(row.drop_nulls::<&str>(None).unwrap().height(), 1, "a feature is still null at kline {}", i + 1);
+        }
+    }
+    assert!(batch_rank.get(MAX_FEATURE_LOOKBACK - 1).is_some());
+}
+
+#[test]
+fn a_wrapped_buffer_computes_the_features_of_its_newest_klines() {
+    const CAPACITY: usize = 260;
+    let klines = klines(3 * CAPACITY + 17);
+    let mut live = LiveFeatures::with_capacity(false, CAPACITY);
+
+    for (i, kline) in klines.iter().enumerate() {
+        let row = live.push(kline, None).unwrap();
+        assert_eq!(live.klines().len(), (i + 1).min(CAPACITY));
+        if i + 1 >= CAPACITY && i % 50 == 0 || i + 1 == klines.len() {
+            let window = &klines[i + 1 - live.klines().len()..=i];
+            let expected = generate_features(window).unwrap().tail(Some(1));
+            assert!(row.equals_missing(&expected), "kline {}: {:?} != {:?}", i + 1, row, expected);
+        }
+    }
+    assert_eq!(live.klines().front(), Some(&klines[klines.len() - CAPACITY]));
+}
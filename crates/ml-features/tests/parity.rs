@@ -0,0 +1,116 @@
+//! Computes the features of a recorded period both as a dataset does and as a live strategy
+//! does, and checks that they match.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{BookTicker, Kline};
+use ml_features::microstructure::{MICROSTRUCTURE_FEATURES, RELATIVE_SPREAD_MEAN};
+use ml_features::parity::{batch_features, compare_features, live_features};
+use ml_features::LiveFeatures;
+use polars::prelude::*;
+use rust_decimal::prelude::*;
+
+const TOLERANCE: f64 = 1e-9;
+
+fn start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Hourly klines on a wave, so the indicators see both rises and falls.
+fn klines(count: usize) -> Vec<Kline> {
+    (0..count)
+        .map(|i| {
+            let open_time = start() + Duration::hours(i as i64);
+            let close = 100.0 + 10.0 * (i as f64 / 7.0).sin() + (i % 5) as f64 * 0.3;
+            let close = Decimal::from_f64(close).unwrap().round_dp(2);
+            Kline {
+                open_time,
+                open: close - Decimal::ONE,
+                high: close + Decimal::TWO,
+                low: close - Decimal::TWO,
+                close,
+                volume: Decimal::from(1000 + (i % 13) as i64 * 10),
+                close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+                interval: "1h".to_string(),
+            }
+        })
+        .collect()
+}
+
+/// A few quotes per kline, with gaps: every seventh kline gets none, and quotes land on both
+/// edges of kline boundaries.
+fn tickers(klines: &[Kline]) -> Vec<BookTicker> {
+    let mut tickers = Vec::new();
+    for (i, kline) in klines.iter().enumerate() {
+        if i % 7 == 3 {
+            continue;
+        }
+        let offsets = [Duration::zero(), Duration::minutes(17), Duration::minutes(42), Duration::hours(1) - Duration::milliseconds(1)];
+        for (j, offset) in offsets.into_iter().enumerate() {
+            let spread = Decimal::new(1 + ((i + j) % 4) as i64, 2);
+            tickers.push(BookTicker {
+                timestamp: kline.open_time + offset,
+                best_bid_price: kline.close - spread,
+                best_bid_qty: Decimal::from(1 + (i * 3 + j) % 9),
+                best_ask_price: kline.close + spread,
+                best_ask_qty: Decimal::from(1 + (i + j * 5) % 7),
+            });
+        }
+    }
+    tickers
+}
+
+fn assert_parity(batch: &DataFrame, live: &DataFrame) {
+    let mismatches = compare_features(batch, live, TOLERANCE).unwrap();
+    let report: Vec<String> = mismatches.iter().take(10).map(ToString::to_string).collect();
+    assert!(mismatches.is_empty(), "{} features differ, including:\n{}", mismatches.len(), report.join("\n"));
+}
+
+#[test]
+fn every_feature_matches_within_the_live_buffer() {
+    let klines = klines(LiveFeatures::DEFAULT_CAPACITY - 100);
+    let tickers = tickers(&klines);
+
+    let batch = batch_features(&klines, &tickers).unwrap();
+    let live = live_features(&klines, &tickers).unwrap();
+
+    assert_eq!(batch.shape(), live.shape());
+    assert_parity(&batch, &live);
+    // Once warmed up, most klines have a full row of features to compare.
+    assert!(batch.drop_nulls::<&str>(None).unwrap().height() > 100);
+}
+
+#[test]
+fn the_microstructure_features_match_beyond_the_live_buffer() {
+    let klines = klines(LiveFeatures::DEFAULT_CAPACITY + 200);
+    let tickers = tickers(&klines);
+
+    let batch = batch_features(&klines, &tickers).unwrap().select(MICROSTRUCTURE_FEATURES).unwrap();
+    let live = live_features(&klines, &tickers).unwrap().select(MICROSTRUCTURE_FEATURES).unwrap();
+
+    assert_parity(&batch, &live);
+    assert!(batch.column(RELATIVE_SPREAD_MEAN).unwrap().null_count() < batch.height());
+}
+
+#[test]
+fn a_skewed_live_path_is_reported() {
+    let klines = klines(60);
+    let tickers = tickers(&klines);
+    let batch = batch_features(&klines, &tickers).unwrap().select(MICROSTRUCTURE_FEATURES).unwrap();
+
+    // Quotes stamped a minute late shift the boundary quotes into the next kline.
+    let late: Vec<BookTicker> = tickers.iter().map(|ticker| BookTicker { timestamp: ticker.timestamp + Duration::minutes(1), ..ticker.clone() }).collect();
+    let live = live_features(&klines, &late).unwrap().select(MICROSTRUCTURE_FEATURES).unwrap();
+
+    let mismatches = compare_features(&batch, &live, TOLERANCE).unwrap();
+    assert!(mismatches.iter().any(|mismatch| mismatch.column == "book_imbalance"));
+}
+
+#[test]
+fn frames_with_different_columns_cannot_be_compared() {
+    let klines = klines(30);
+    let tickers = tickers(&klines);
+    let batch = batch_features(&klines, &tickers).unwrap();
+    let kline_only = ml_features::generate_features(&klines).unwrap();
+
+    assert!(compare_features(&batch, &kline_only, TOLERANCE).is_err());
+}
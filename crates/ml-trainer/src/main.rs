@@ -200,6 +200,10 @@ struct GenerateDatasetArgs {
     /// The output file path for the Parquet dataset.
     #[arg(long, short)]
     output: PathBuf,
+    /// Adds the order book features, from the book tickers the live engine recorded with
+    /// `record_book_tickers`. Klines without recorded book tickers are left out.
+    #[arg(long)]
+    with_book: bool,
 }
 
 #[derive(Parser)]
@@ -249,18 +253,26 @@ async fn handle_generate_dataset(args: GenerateDatasetArgs) -> Result<()> {
         "Fetching kline data from database... (symbol: {}, interval: {})",
         args.symbol, args.interval
     );
-    let klines = db_repo.get_klines_by_date_range(
-        &args.symbol,
-        &args.interval,
-        args.from.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Utc).unwrap(),
-        args.to.and_hms_opt(23, 59, 59).unwrap().and_local_timezone(Utc).unwrap(),
-    ).await?;
+    let from = args.from.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Utc).unwrap();
+    let to = args.to.and_hms_opt(23, 59, 59).unwrap().and_local_timezone(Utc).unwrap();
+    let klines = db_repo.get_klines_by_date_range(&args.symbol, &args.interval, from, to).await?;
     println!("Found {} klines.", klines.len());
 
     // 3. Generate Features
     println!("Generating features...");
     let mut features_df = features::generate_features(&klines)?;
+    if args.with_book {
+        let summaries = db_repo.get_book_summaries(&args.symbol, &args.interval, from, to).await?;
+        println!("Found recorded book tickers for {} of {} klines.", summaries.len(), klines.len());
+        let summaries = ml_features::microstructure::align_book_summaries(&klines, &summaries);
+        let book_df = ml_features::microstructure::generate_microstructure_features(&summaries)?;
+        features_df = features_df.hstack(book_df.get_columns())?;
+    }
     println!("Generated DataFrame with shape: {:?}", features_df.shape());
+    println!(
+        "The first {} klines only warm the features up and are dropped; MlStrategy waits as long before trading.",
+        features::MAX_FEATURE_LOOKBACK - 1
+    );
 
     // 4. Generate Labels
     println!("Applying Triple Barrier labeling...");
@@ -1,10 +1,44 @@
 use crate::error::OptimizerError;
 use configuration::optimizer_config::{OptimizerConfig, ParameterRange};
+use configuration::{normalize_params, Config};
 use itertools::Itertools;
 use serde_json::{json, Map, Value};
 use std::collections::HashMap;
+use strategies::{merge_params, parameter_violations};
 
-/// Generates every unique combination of parameters from the defined parameter space.
+/// The parameter sets worth running, and how many generated sets were left out because the
+/// strategy would reject them.
+#[derive(Debug, Clone)]
+pub struct ValidParameterSets {
+    pub sets: Vec<Value>,
+    pub skipped: usize,
+}
+
+/// Generates every combination of the parameter space, merged over `base_config`'s
+/// parameters, and keeps those the strategy accepts, so no run is spent on a set (such as a
+/// fast MA slower than the slow one) that can only fail at construction.
+///
+/// A set that does not deserialize (e.g. a misspelled parameter) fails the whole generation,
+/// since every combination would share the mistake.
+pub fn generate_valid_parameter_sets(
+    config: &OptimizerConfig,
+    base_config: &Config,
+) -> Result<ValidParameterSets, OptimizerError> {
+    let strategy_id = config.base_config.strategy_id;
+    let mut valid = ValidParameterSets { sets: Vec::new(), skipped: 0 };
+    for params in generate_parameter_sets(config)? {
+        let merged = merge_params(strategy_id, base_config, &params)?;
+        if parameter_violations(strategy_id, &merged)?.is_empty() {
+            valid.sets.push(params);
+        } else {
+            valid.skipped += 1;
+        }
+    }
+    Ok(valid)
+}
+
+/// Generates every unique combination of parameters from the defined parameter space, each
+/// in the canonical form of `normalize_params`, as it is stored.
 pub fn generate_parameter_sets(
     config: &OptimizerConfig,
 ) -> Result<Vec<Value>, OptimizerError> {
@@ -15,6 +49,7 @@ pub fn generate_parameter_sets(
         let values = match range {
             ParameterRange::DiscreteInt(vals) => vals.iter().map(|&v| json!(v)).collect(),
             ParameterRange::DiscreteDecimal(vals) => vals.iter().map(|v| json!(v)).collect(),
+            ParameterRange::DiscreteString(vals) => vals.iter().map(|v| json!(v)).collect(),
             ParameterRange::LinearInt { start, end, step } => {
                 if *step <= 0 {
                     return Err(OptimizerError::ParameterGeneration(format!(
@@ -54,7 +89,7 @@ pub fn generate_parameter_sets(
             for (i, value) in product.into_iter().enumerate() {
                 map.insert(param_names[i].clone(), value);
             }
-            Value::Object(map)
+            normalize_params(Value::Object(map))
         })
         .collect();
 
@@ -1,30 +1,50 @@
-use crate::generator::generate_parameter_sets;
-use backtester::Backtester;
+use crate::generator::generate_valid_parameter_sets;
+use analyzer::{Analyzer, ReportMetrics};
+use backtester::error::BacktestError;
+use backtester::{Backtester, KlineSource};
 use configuration::optimizer_config::OptimizerConfig;
-use configuration::Config;
+use configuration::{Config, JobConfigSnapshot, QuoteAssets};
+use core_types::{Kline, PriceType};
 use database::{DbBacktestRun, DbRepository};
 use executor::{Portfolio, SimulatedExecutor};
 use indicatif::{ProgressBar, ProgressStyle};
 use risk::SimpleRiskManager;
 use rust_decimal::Decimal;
-use rust_decimal::prelude::*;
 use serde_json::Value as JsonValue;
-use strategies::{create_strategy, StrategyId};
+use strategies::{create_strategy_from_params_with_cache, merge_params, IndicatorCache};
+use futures::stream::{self, StreamExt};
+use std::sync::Arc;
+use std::time::Instant;
 use tokio::runtime::Handle;
 use tracing;
 use uuid::Uuid;
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 
 pub mod error;
 pub mod generator;
+pub mod objective;
+pub mod progress;
 
 pub use error::OptimizerError;
+pub use objective::{MetricName, Objective};
+pub use progress::{JobProgress, ProgressTracker};
+
+/// The market data every run of a job replays, loaded once for all of them.
+struct JobData {
+    klines: KlineSource,
+    /// The mark price klines of the range, when the runs are valued at the mark price.
+    mark_prices: Vec<Kline>,
+    indicators: Arc<IndicatorCache>,
+}
 
 pub struct Optimizer {
     job_id: Uuid,
     config: OptimizerConfig,
     base_config: Config,
     db_repo: DbRepository,
+    objective: Objective,
+    /// Applies the hard filters a run must pass to get an objective value.
+    analyzer: Analyzer,
 }
 
 impl Optimizer {
@@ -35,6 +55,8 @@ impl Optimizer {
     ) -> Self {
         Self {
             job_id: Uuid::new_v4(),
+            objective: Objective::from_config(&config),
+            analyzer: Analyzer::new(config.analysis.clone()),
             config,
             base_config,
             db_repo,
@@ -46,6 +68,12 @@ impl Optimizer {
         self.job_id
     }
 
+    /// The objective value of a run with `report`: its objective's score, or `None` if it
+    /// fails the hard filters (it has too few trades, say) or lacks the metric.
+    pub fn objective_value(&self, report: &impl ReportMetrics) -> Option<Decimal> {
+        self.analyzer.passes_filters(report).then(|| self.objective.score(report)).flatten()
+    }
+
     pub async fn run(&self) -> Result<(), OptimizerError> {
         self.initialize_job().await?;
 
@@ -53,16 +81,21 @@ impl Optimizer {
         let total_runs = pending_runs.len();
         if total_runs == 0 {
             tracing::info!("No pending runs found for job {}. It may have been completed previously.", self.job_id);
+            self.db_repo.update_job_status(self.job_id, "Completed").await?;
             return Ok(());
         }
         
+        let max_concurrency = self.config.max_concurrency.unwrap_or_else(num_cpus::get_physical).max(1);
         tracing::info!(
-            "Starting optimization job {} with {} pending runs on {} CPU cores.",
+            "Starting optimization job {} with {} pending runs, up to {} at a time.",
             self.job_id,
             total_runs,
-            rayon::current_num_threads()
+            max_concurrency
         );
 
+        let job_data = self.load_job_data(&pending_runs).await?;
+        let job_data = &job_data;
+
         let progress_bar = ProgressBar::new(total_runs as u64);
         progress_bar.set_style(
              ProgressStyle::default_bar()
@@ -70,42 +103,70 @@ impl Optimizer {
                 .progress_chars("=>-"),
         );
 
-        let tokio_handle = Handle::current();
-
-        rayon::scope(|s| {
-            for run in pending_runs {
-                let handle_clone = tokio_handle.clone();
-                let progress_bar_clone = progress_bar.clone();
+        // Runs are driven as futures on the current runtime, with at most `max_concurrency`
+        // in flight. Each in-flight run holds at most one pooled DB connection at a time,
+        // so the pool must be at least `max_concurrency` connections wide.
+        let mut tracker = ProgressTracker::new(total_runs, max_concurrency, Utc::now());
+        self.save_progress(tracker.progress(Utc::now())).await;
+        let mut results = stream::iter(pending_runs)
+            .map(|run| async move {
+                let started = Instant::now();
+                let result = self.execute_single_backtest(run, job_data).await;
+                (result, started.elapsed())
+            })
+            .buffer_unordered(max_concurrency);
 
-                s.spawn(move |_| {
-                    let result = handle_clone.block_on(self.execute_single_backtest(run));
-
-                    if let Err(e) = result {
-                        tracing::error!(error = ?e, "A backtest run failed.");
-                    }
-                    progress_bar_clone.inc(1);
-                });
+        while let Some((result, duration)) = results.next().await {
+            if let Err(e) = result {
+                tracing::error!(error = ?e, "A backtest run failed.");
+            }
+            progress_bar.inc(1);
+            if let Some(progress) = tracker.record(duration, Utc::now()) {
+                self.save_progress(progress).await;
             }
-        });
+        }
         
         progress_bar.finish_with_message("Optimization runs complete.");
+        // Failed runs are recorded as such; the job itself is done once no run is left.
+        self.db_repo.update_job_status(self.job_id, "Completed").await?;
 
         tracing::info!("Job {} complete. Run `analyze {}` to see the results.", self.job_id, self.job_id);
         
         Ok(())
     }
 
+    /// Saves the job's progress for the web UI. A failed save only costs the UI an update, so
+    /// it is logged rather than failing the job.
+    async fn save_progress(&self, progress: JobProgress) {
+        let saved = self
+            .db_repo
+            .save_job_progress(self.job_id, progress.completed_runs as i32, progress.total_runs as i32, progress.estimated_completion_at)
+            .await;
+        if let Err(e) = saved {
+            tracing::warn!(job_id = %self.job_id, error = ?e, "Failed to save the job's progress.");
+        }
+    }
+
     async fn initialize_job(&self) -> Result<(), OptimizerError> {
+        let job_config = serde_json::to_value(JobConfigSnapshot::new(&self.config, &self.base_config))?;
         self.db_repo.save_optimization_job(
             self.job_id,
             &format!("{:?}", self.config.base_config.strategy_id),
             &self.config.base_config.symbol,
             "Running", // Set status to Running
+            Some(&job_config),
         ).await?;
 
-        let param_sets = generate_parameter_sets(&self.config)?;
+        let param_sets = generate_valid_parameter_sets(&self.config, &self.base_config)?;
+        if param_sets.skipped > 0 {
+            tracing::info!(
+                "Skipped {} invalid parameter combinations; {} remain.",
+                param_sets.skipped,
+                param_sets.sets.len()
+            );
+        }
 
-        for params in param_sets {
+        for params in param_sets.sets {
             self.db_repo.save_backtest_run(
                 Uuid::new_v4(),
                 self.job_id,
@@ -116,15 +177,52 @@ impl Optimizer {
         Ok(())
     }
     
-    /// This is the core function that runs inside each parallel thread.
-    async fn execute_single_backtest(&self, run: DbBacktestRun) -> Result<(), OptimizerError> {
+    /// The first and last moments of the backtest range.
+    fn date_range(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let start_date = self.base_config.backtest.start_date.and_hms_opt(0,0,0).unwrap().and_local_timezone(Utc).unwrap();
+        let end_date = self.base_config.backtest.end_date.and_hms_opt(23,59,59).unwrap().and_local_timezone(Utc).unwrap();
+        (start_date, end_date)
+    }
+
+    /// Loads the klines of the range, preceded by as many as the longest warmup of `runs`,
+    /// and the range's mark prices if runs are valued at them, in one go for the whole job.
+    /// Each run then takes its slice of them rather than querying the database itself.
+    async fn load_job_data(&self, runs: &[DbBacktestRun]) -> Result<JobData, OptimizerError> {
+        let symbol = &self.config.base_config.symbol;
+        let interval = &self.config.base_config.interval;
+        let (start_date, end_date) = self.date_range();
+        // A run whose strategy cannot be built fails without warming up.
+        let warmup_bars = runs
+            .iter()
+            .filter_map(|run| self.create_strategy_instance(&run.parameters).ok())
+            .map(|strategy| strategy.required_warmup_bars())
+            .max()
+            .unwrap_or(0);
+
+        let mut klines = self.db_repo.get_klines_before(symbol, interval, start_date, warmup_bars).await?;
+        klines.extend(self.db_repo.get_klines_by_date_range(symbol, interval, start_date, end_date).await?);
+        let mark_prices = match self.base_config.backtest.valuation_price {
+            PriceType::Last => Vec::new(),
+            PriceType::Mark => self.db_repo.get_mark_price_klines_by_date_range(symbol, interval, start_date, end_date).await?,
+        };
+        let klines = Arc::new(klines);
+        Ok(JobData {
+            indicators: Arc::new(IndicatorCache::new(klines.clone())),
+            klines: KlineSource::Shared(klines),
+            mark_prices,
+        })
+    }
+
+    /// This is the core function that runs for each pending parameter set.
+    async fn execute_single_backtest(&self, run: DbBacktestRun, job_data: &JobData) -> Result<(), OptimizerError> {
         let run_id = run.run_id;
         
         let analytics_engine = analytics::AnalyticsEngine::new();
-        let portfolio = Portfolio::new(self.base_config.backtest.initial_capital);
-        let executor = Box::new(SimulatedExecutor::new(self.base_config.simulation.clone()));
+        let quote_asset = &self.base_config.execution.quote_asset;
+        let portfolio = Portfolio::new(self.base_config.backtest.initial_capital).with_quote_asset(quote_asset.clone());
+        let executor = Box::new(SimulatedExecutor::new(self.base_config.simulation.clone()).with_quote_assets(QuoteAssets::new(quote_asset.clone())));
         let risk_manager = Box::new(SimpleRiskManager::new(self.base_config.risk_management.clone())?);
-        let strategy = self.create_strategy_instance(&run.parameters)?;
+        let strategy = self.build_strategy(&run.parameters, Some(job_data.indicators.clone()))?;
 
         // --- THE KEY CHANGE IS HERE ---
         // We now pass the `run_id` from the database task directly into the Backtester.
@@ -139,13 +237,38 @@ impl Optimizer {
             executor,
             analytics_engine,
             self.db_repo.clone(),
-        );
+        )
+        .with_equity_curve_resolution(self.config.equity_curve_resolution)
+        .with_mark_prices(job_data.mark_prices.clone());
         // --- END OF CHANGE ---
 
-        let backtest_result = backtester.run(
-            self.base_config.backtest.start_date.and_hms_opt(0,0,0).unwrap().and_local_timezone(Utc).unwrap(),
-            self.base_config.backtest.end_date.and_hms_opt(23,59,59).unwrap().and_local_timezone(Utc).unwrap(),
-        ).await;
+        let (start_date, end_date) = self.date_range();
+
+        let backtest_result: Result<(), OptimizerError> = async {
+            let (warmup, klines) = job_data.klines.load(
+                &self.config.base_config.symbol,
+                &self.config.base_config.interval,
+                start_date,
+                end_date,
+                backtester.required_warmup_bars(),
+            ).await?;
+            if klines.is_empty() { return Err(BacktestError::DataUnavailable.into()); }
+
+            // The simulation is CPU-bound, so it runs on the blocking pool rather than
+            // starving the reactor that the other in-flight runs' DB calls depend on.
+            let handle = Handle::current();
+            let (mut backtester, simulated) = tokio::task::spawn_blocking(move || {
+                let simulated = backtester.warm_up(&warmup).and_then(|()| handle.block_on(backtester.simulate(&klines)));
+                (backtester, simulated)
+            })
+            .await
+            .map_err(|e| OptimizerError::JoinError(e.to_string()))?;
+            let (completed_trades, equity_curve) = simulated?;
+
+            let report = backtester.finalize(&completed_trades, &equity_curve).await?;
+            self.db_repo.save_run_objective(run_id, self.objective_value(&report)).await?;
+            Ok(())
+        }.await;
 
         match backtest_result {
             Ok(_) => {
@@ -161,94 +284,19 @@ impl Optimizer {
         Ok(())
     }
 
-    fn parse_decimal_param(val: &JsonValue, param_name: &str) -> Result<Decimal, OptimizerError> {
-        if let Some(f64_val) = val.as_f64() {
-            Decimal::from_f64(f64_val)
-        } else if let Some(str_val) = val.as_str() {
-            str_val.parse::<Decimal>().ok()
-        } else {
-            None
-        }.ok_or_else(|| OptimizerError::ParameterGeneration(format!("Cannot parse {}: {:?}", param_name, val)))
+    /// Builds the strategy for a run by overlaying its optimized parameters on the base config.
+    pub fn create_strategy_instance(&self, optimized_params: &JsonValue) -> Result<Box<dyn strategies::Strategy>, OptimizerError> {
+        self.build_strategy(optimized_params, None)
     }
 
-    pub fn create_strategy_instance(&self, optimized_params: &JsonValue) -> Result<Box<dyn strategies::Strategy>, OptimizerError> {
+    /// Like `create_strategy_instance`, with the strategy reading its indicators from `cache`.
+    fn build_strategy(
+        &self,
+        optimized_params: &JsonValue,
+        cache: Option<Arc<IndicatorCache>>,
+    ) -> Result<Box<dyn strategies::Strategy>, OptimizerError> {
         let strategy_id = self.config.base_config.strategy_id;
-        let mut temp_config = self.base_config.clone();
-        
-        let params_map: serde_json::Map<String, JsonValue> = serde_json::from_value(optimized_params.clone())?;
-
-        match strategy_id {
-            StrategyId::MACrossover => {
-                let mut p = temp_config.strategies.ma_crossover.clone();
-                if let Some(val) = params_map.get("ma_fast_period") { 
-                    p.ma_fast_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid ma_fast_period".to_string()))? as usize; 
-                }
-                if let Some(val) = params_map.get("ma_slow_period") { 
-                    p.ma_slow_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid ma_slow_period".to_string()))? as usize; 
-                }
-                if let Some(val) = params_map.get("trend_filter_period") { 
-                    p.trend_filter_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid trend_filter_period".to_string()))? as usize; 
-                }
-                temp_config.strategies.ma_crossover = p;
-            },
-            StrategyId::SuperTrend => {
-                let mut p = temp_config.strategies.super_trend.clone();
-                if let Some(val) = params_map.get("atr_period") { 
-                    p.atr_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid atr_period".to_string()))? as usize; 
-                }
-                if let Some(val) = params_map.get("atr_multiplier") { 
-                    p.atr_multiplier = Self::parse_decimal_param(val, "atr_multiplier")?;
-                }
-                if let Some(val) = params_map.get("adx_threshold") { 
-                    p.adx_threshold = Self::parse_decimal_param(val, "adx_threshold")?;
-                }
-                if let Some(val) = params_map.get("adx_period") { 
-                    p.adx_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid adx_period".to_string()))? as usize; 
-                }
-                temp_config.strategies.super_trend = p;
-            },
-            StrategyId::ProbReversion => {
-                let mut p = temp_config.strategies.prob_reversion.clone();
-                if let Some(val) = params_map.get("bb_period") { 
-                    p.bb_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid bb_period".to_string()))? as usize; 
-                }
-                if let Some(val) = params_map.get("bb_std_dev") { 
-                    p.bb_std_dev = Self::parse_decimal_param(val, "bb_std_dev")?;
-                }
-                if let Some(val) = params_map.get("rsi_period") { 
-                    p.rsi_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid rsi_period".to_string()))? as usize; 
-                }
-                if let Some(val) = params_map.get("rsi_oversold") { 
-                    p.rsi_oversold = Self::parse_decimal_param(val, "rsi_oversold")?;
-                }
-                if let Some(val) = params_map.get("rsi_overbought") { 
-                    p.rsi_overbought = Self::parse_decimal_param(val, "rsi_overbought")?;
-                }
-                if let Some(val) = params_map.get("adx_threshold") { 
-                    p.adx_threshold = Self::parse_decimal_param(val, "adx_threshold")?;
-                }
-                if let Some(val) = params_map.get("adx_period") { 
-                    p.adx_period = val.as_u64().ok_or_else(|| OptimizerError::ParameterGeneration("Invalid adx_period".to_string()))? as usize; 
-                }
-                temp_config.strategies.prob_reversion = p;
-            },
-            StrategyId::FundingRateArb => {
-                let mut p = temp_config.strategies.funding_rate_arb.clone();
-                if let Some(val) = params_map.get("target_rate_threshold") { 
-                    p.target_rate_threshold = Self::parse_decimal_param(val, "target_rate_threshold")?;
-                }
-                if let Some(val) = params_map.get("basis_safety_threshold") { 
-                    p.basis_safety_threshold = Self::parse_decimal_param(val, "basis_safety_threshold")?;
-                }
-                temp_config.strategies.funding_rate_arb = p;
-            },
-            StrategyId::MlStrategy => {
-                // ML strategy doesn't use the standard parameter optimization system
-                // It uses a pre-trained model, so we just use the existing config
-                // No parameter updates needed
-            },
-        }
-        
-        Ok(create_strategy(strategy_id, &temp_config, &self.config.base_config.symbol)?)
+        let params = merge_params(strategy_id, &self.base_config, optimized_params)?;
+        Ok(create_strategy_from_params_with_cache(strategy_id, &params, &self.config.base_config.symbol, cache)?)
     }
-}
\ No newline at end of file
+}
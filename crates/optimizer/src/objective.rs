@@ -0,0 +1,50 @@
+//! What an optimization job maximizes.
+//!
+//! Each run is scored as soon as it finishes, and the value is stored with the run, so search
+//! methods that decide what to run next from earlier results (successive halving, Bayesian
+//! optimization) do not have to wait for `analyze` to rank the whole job.
+
+use analyzer::{weighted_score, ReportMetrics};
+use configuration::optimizer_config::{AnalysisConfig, ObjectiveName, OptimizerConfig};
+use rust_decimal::Decimal;
+
+/// A single report metric an objective can maximize.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricName {
+    SharpeRatio,
+    CalmarRatio,
+    NetProfit,
+}
+
+/// The value an optimization job maximizes. Higher is better.
+#[derive(Debug, Clone)]
+pub enum Objective {
+    Metric(MetricName),
+    /// The analyzer's weighted score on the run's own metrics. The analyzer normalizes each
+    /// metric across a job's runs, which a single run cannot see, so the two can rank
+    /// differently.
+    AnalyzerScore(AnalysisConfig),
+}
+
+impl Objective {
+    /// The objective named in the optimizer config.
+    pub fn from_config(config: &OptimizerConfig) -> Self {
+        match config.objective {
+            ObjectiveName::SharpeRatio => Self::Metric(MetricName::SharpeRatio),
+            ObjectiveName::CalmarRatio => Self::Metric(MetricName::CalmarRatio),
+            ObjectiveName::NetProfit => Self::Metric(MetricName::NetProfit),
+            ObjectiveName::AnalyzerScore => Self::AnalyzerScore(config.analysis.clone()),
+        }
+    }
+
+    /// Scores a run. `None` when the report lacks the metric (e.g., no Sharpe ratio for a
+    /// flat equity curve).
+    pub fn score(&self, report: &impl ReportMetrics) -> Option<Decimal> {
+        match self {
+            Self::Metric(MetricName::SharpeRatio) => report.sharpe_ratio(),
+            Self::Metric(MetricName::CalmarRatio) => report.calmar_ratio(),
+            Self::Metric(MetricName::NetProfit) => report.total_net_profit(),
+            Self::AnalyzerScore(analysis) => Some(weighted_score(&analysis.scoring_weights, report)),
+        }
+    }
+}
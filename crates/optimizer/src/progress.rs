@@ -0,0 +1,98 @@
+//! How far along an optimization job is, for clients other than the CLI running it.
+//!
+//! The job's progress is saved on its `optimization_jobs` row as runs finish. Writing after
+//! every run would cost a write per run on large grids of fast runs, so progress is saved in
+//! batches, and at least every `SAVE_INTERVAL` while runs keep finishing.
+
+use chrono::{DateTime, Duration, Utc};
+use std::collections::VecDeque;
+
+/// Progress is saved at least once per this many finished runs.
+pub const SAVE_BATCH: usize = 5;
+/// Progress is saved when a run finishes this long after the last save, even mid-batch.
+pub const SAVE_INTERVAL: Duration = Duration::seconds(10);
+/// The number of most recent run durations the completion estimate averages.
+const DURATION_WINDOW: usize = 20;
+
+/// A job's progress as saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JobProgress {
+    /// The runs finished so far, failed ones included.
+    pub completed_runs: usize,
+    pub total_runs: usize,
+    /// When the remaining runs should be done, once a run has finished to go by.
+    pub estimated_completion_at: Option<DateTime<Utc>>,
+}
+
+impl JobProgress {
+    /// Whether every run has finished.
+    pub fn is_complete(&self) -> bool {
+        self.completed_runs >= self.total_runs
+    }
+}
+
+/// Counts a job's finished runs and decides when their progress is worth saving.
+#[derive(Debug)]
+pub struct ProgressTracker {
+    total_runs: usize,
+    completed_runs: usize,
+    /// How many runs are in flight at a time, which divides the remaining time.
+    concurrency: usize,
+    durations: VecDeque<std::time::Duration>,
+    saved_runs: usize,
+    saved_at: DateTime<Utc>,
+}
+
+impl ProgressTracker {
+    /// Starts tracking `total_runs` runs executed `concurrency` at a time, none finished as
+    /// of `now`.
+    pub fn new(total_runs: usize, concurrency: usize, now: DateTime<Utc>) -> Self {
+        Self {
+            total_runs,
+            completed_runs: 0,
+            concurrency: concurrency.max(1),
+            durations: VecDeque::with_capacity(DURATION_WINDOW),
+            saved_runs: 0,
+            saved_at: now,
+        }
+    }
+
+    /// The progress as of `now`.
+    pub fn progress(&self, now: DateTime<Utc>) -> JobProgress {
+        JobProgress {
+            completed_runs: self.completed_runs,
+            total_runs: self.total_runs,
+            estimated_completion_at: self.estimated_completion_at(now),
+        }
+    }
+
+    /// Counts a run that finished at `now` after `duration`. Returns the progress to save,
+    /// if a batch is full, the last save is `SAVE_INTERVAL` old or this was the last run.
+    pub fn record(&mut self, duration: std::time::Duration, now: DateTime<Utc>) -> Option<JobProgress> {
+        self.completed_runs = (self.completed_runs + 1).min(self.total_runs);
+        if self.durations.len() == DURATION_WINDOW {
+            self.durations.pop_front();
+        }
+        self.durations.push_back(duration);
+
+        let progress = self.progress(now);
+        let due = progress.is_complete() || self.completed_runs - self.saved_runs >= SAVE_BATCH || now - self.saved_at >= SAVE_INTERVAL;
+        if !due {
+            return None;
+        }
+        self.saved_runs = self.completed_runs;
+        self.saved_at = now;
+        Some(progress)
+    }
+
+    /// `now` plus the remaining runs' share of the in-flight slots, at the recent runs'
+    /// average duration.
+    fn estimated_completion_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.durations.is_empty() {
+            return None;
+        }
+        let average = self.durations.iter().sum::<std::time::Duration>() / self.durations.len() as u32;
+        let remaining = (self.total_runs - self.completed_runs) as f64 / self.concurrency as f64;
+        Duration::from_std(average.mul_f64(remaining)).ok().map(|left| now + left)
+    }
+}
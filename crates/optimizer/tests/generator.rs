@@ -0,0 +1,70 @@
+//! Tests for generating the parameter sets of an optimization job.
+
+use configuration::optimizer_config::{AnalysisConfig, BaseConfig, EquityCurveResolution, ObjectiveName, OptimizerConfig, ParameterRange};
+use configuration::Versioned;
+use core_types::StrategyId;
+use optimizer::generator::{generate_parameter_sets, generate_valid_parameter_sets};
+use optimizer::OptimizerError;
+use std::collections::HashMap;
+use testing::{test_config, TEST_INTERVAL, TEST_SYMBOL};
+
+fn optimizer_config(parameter_space: HashMap<String, ParameterRange>) -> OptimizerConfig {
+    OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space,
+        analysis: AnalysisConfig::default(),
+        wfo: None,
+        max_concurrency: None,
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::AnalyzerScore,
+    }
+}
+
+#[test]
+fn combinations_the_strategy_rejects_are_skipped_and_counted() {
+    // 10 fast x 3 slow x 2 trend filter periods = 60 combinations. The fast period is not below
+    // the slow one in 7 + 5 + 3 = 15 fast/slow pairs, for 30 combinations; a 35-bar trend
+    // filter is too short for the 7 remaining pairs with a 40-bar slow MA.
+    let config = optimizer_config(HashMap::from([
+        ("ma_fast_period".to_string(), ParameterRange::LinearInt { start: 5, end: 50, step: 5 }),
+        ("ma_slow_period".to_string(), ParameterRange::DiscreteInt(vec![20, 30, 40])),
+        ("trend_filter_period".to_string(), ParameterRange::DiscreteInt(vec![0, 35])),
+    ]));
+    let base_config = test_config(100).expect("load config");
+
+    assert_eq!(generate_parameter_sets(&config).unwrap().len(), 60);
+    let valid = generate_valid_parameter_sets(&config, &base_config).expect("generate");
+    assert_eq!(valid.skipped, 37);
+    assert_eq!(valid.sets.len(), 23);
+    for params in &valid.sets {
+        let (fast, slow, trend) = (&params["ma_fast_period"], &params["ma_slow_period"], &params["trend_filter_period"]);
+        let (fast, slow, trend) = (fast.as_i64().unwrap(), slow.as_i64().unwrap(), trend.as_i64().unwrap());
+        assert!(fast < slow && (trend == 0 || trend >= slow), "{}", params);
+    }
+}
+
+#[test]
+fn parameters_overridden_only_in_the_base_config_are_checked_too() {
+    // The base config's 60-bar slow MA makes every fast period from 60 up invalid.
+    let config = optimizer_config(HashMap::from([(
+        "ma_fast_period".to_string(),
+        ParameterRange::DiscreteInt(vec![10, 59, 60, 80]),
+    )]));
+    let base_config = test_config(100).expect("load config");
+
+    let valid = generate_valid_parameter_sets(&config, &base_config).expect("generate");
+    assert_eq!((valid.sets.len(), valid.skipped), (2, 2));
+}
+
+#[test]
+fn a_misspelled_parameter_fails_generation() {
+    let config = optimizer_config(HashMap::from([("ma_slow_perod".to_string(), ParameterRange::DiscreteInt(vec![40]))]));
+    let base_config = test_config(100).expect("load config");
+
+    assert!(matches!(generate_valid_parameter_sets(&config, &base_config), Err(OptimizerError::Strategy(_))));
+}
@@ -0,0 +1,56 @@
+//! Tests for tracking an optimization job's progress and deciding when to save it.
+
+use chrono::{Duration, TimeZone, Utc};
+use optimizer::progress::{JobProgress, ProgressTracker, SAVE_BATCH, SAVE_INTERVAL};
+use std::time::Duration as StdDuration;
+
+#[test]
+fn progress_of_a_ten_run_job_is_saved_in_batches_and_ends_complete() {
+    let start = Utc.with_ymd_and_hms(2025, 9, 1, 12, 0, 0).unwrap();
+    let mut tracker = ProgressTracker::new(10, 2, start);
+    assert_eq!(tracker.progress(start), JobProgress { completed_runs: 0, total_runs: 10, estimated_completion_at: None });
+
+    // Two runs at a time, each taking two seconds: one finishes every second.
+    let saved: Vec<JobProgress> = (1..=10)
+        .filter_map(|n| tracker.record(StdDuration::from_secs(2), start + Duration::seconds(n)))
+        .collect();
+
+    assert_eq!(saved.iter().map(|p| p.completed_runs).collect::<Vec<_>>(), [SAVE_BATCH, 10]);
+    assert!(saved.windows(2).all(|w| w[0].completed_runs < w[1].completed_runs));
+    // Five runs left, two at a time, at two seconds each.
+    assert_eq!(saved[0].estimated_completion_at, Some(start + Duration::seconds(5 + 5)));
+    assert!(saved[1].is_complete());
+    assert_eq!(saved[1].estimated_completion_at, Some(start + Duration::seconds(10)));
+}
+
+#[test]
+fn slow_runs_are_saved_before_a_batch_fills() {
+    let start = Utc.with_ymd_and_hms(2025, 9, 1, 12, 0, 0).unwrap();
+    let mut tracker = ProgressTracker::new(100, 1, start);
+    let run = StdDuration::from_secs(3);
+
+    let saved_at: Vec<usize> = (1..=9)
+        .filter_map(|n| tracker.record(run, start + Duration::seconds(3 * n)).map(|p| p.completed_runs))
+        .collect();
+
+    // Every fourth run finishes at least `SAVE_INTERVAL` after the last save.
+    assert!(SAVE_INTERVAL <= Duration::seconds(12) && SAVE_BATCH > 4);
+    assert_eq!(saved_at, [4, 8]);
+}
+
+#[test]
+fn the_estimate_follows_the_recent_run_durations() {
+    let start = Utc.with_ymd_and_hms(2025, 9, 1, 12, 0, 0).unwrap();
+    let mut tracker = ProgressTracker::new(50, 1, start);
+    let mut now = start;
+    for _ in 0..20 {
+        now += Duration::seconds(10);
+        tracker.record(StdDuration::from_secs(10), now);
+    }
+    // The runs got faster: once the window holds only the new durations, so does the estimate.
+    for _ in 0..20 {
+        now += Duration::seconds(1);
+        tracker.record(StdDuration::from_secs(1), now);
+    }
+    assert_eq!(tracker.progress(now).estimated_completion_at, Some(now + Duration::seconds(10)));
+}
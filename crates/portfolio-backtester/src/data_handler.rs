@@ -40,9 +40,15 @@ pub async fn load_and_prepare_data(
     end_date: DateTime<Utc>,
 ) -> Result<Vec<Event>, PortfolioError> {
     // 1. Concurrently fetch kline data for all unique symbols.
-    let unique_symbols: HashSet<_> = portfolio_config.bots.iter().map(|b| &b.symbol).collect();
+    let unique_symbols: Vec<_> = portfolio_config
+        .bots
+        .iter()
+        .map(|b| &b.symbol)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
     
-    let fetch_futures = unique_symbols.into_iter().map(|symbol| {
+    let fetch_futures = unique_symbols.iter().map(|symbol| {
         db_repo.get_klines_by_date_range(symbol, interval, start_date, end_date)
     });
 
@@ -50,9 +56,8 @@ pub async fn load_and_prepare_data(
     
     // 2. Collect and transform all klines into a single flat event vector.
     let mut all_events = Vec::new();
-    for (i, result) in results.into_iter().enumerate() {
+    for (symbol, result) in unique_symbols.into_iter().zip(results) {
         let klines = result?; // Propagate any DB errors
-        let symbol = portfolio_config.bots[i].symbol.clone(); // This is a simplification; a HashMap would be better for robustness
         for kline in klines {
             all_events.push(Event::Kline(MarketEvent {
                 symbol: symbol.clone(),
@@ -10,4 +10,4 @@ pub mod manager;
 
 pub use data_handler::{load_and_prepare_data, Event, MarketEvent};
 pub use error::PortfolioError;
-pub use manager::PortfolioManager;
\ No newline at end of file
+pub use manager::{PortfolioBot, PortfolioManager, PortfolioReport};
\ No newline at end of file
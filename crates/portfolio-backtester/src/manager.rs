@@ -2,49 +2,94 @@ use crate::data_handler::{Event, MarketEvent};
 use crate::error::PortfolioError;
 use analytics::{AnalyticsEngine, PerformanceReport};
 
-use configuration::Config;
-use core_types::{Execution, Trade};
+use configuration::{Config, OverlapPolicy};
+use core_types::{CloseReason, Execution, Trade, TradeDirection};
 use executor::{Executor, Portfolio};
 use indicatif::{ProgressBar, ProgressStyle};
-use risk::RiskManager;
+use risk::{constrain_direction, RiskManager};
 use rust_decimal::Decimal;
 use std::collections::HashMap;
-use strategies::Strategy;
+use strategies::{KlineTransformer, Strategy};
 use uuid::Uuid;
 
+/// One bot of the portfolio: a strategy, the kline preprocessing applied before it evaluates,
+/// the sides it may trade, and the risk manager sizing its signals by the bot's risk settings.
+pub struct PortfolioBot {
+    pub strategy: Box<dyn Strategy>,
+    pub kline_transform: KlineTransformer,
+    pub direction: TradeDirection,
+    pub risk_manager: Box<dyn RiskManager>,
+}
+
+/// The results of a portfolio run.
+#[derive(Debug, Clone)]
+pub struct PortfolioReport {
+    pub performance: PerformanceReport,
+    /// Entries that placed no order because `max_positions_per_symbol` other bots already
+    /// held their symbol's position: skipped, or netted down to nothing.
+    pub overlapping_entries_skipped: usize,
+}
+
 pub struct PortfolioManager {
     portfolio: Portfolio,
-    risk_manager: Box<dyn RiskManager>,
     executor: Box<dyn Executor>,
     analytics_engine: AnalyticsEngine,
-    strategies: HashMap<String, Box<dyn Strategy>>,
+    /// The bots trading each symbol, in definition order. Bots on the same symbol share its
+    /// position, as they would on a single one-way account.
+    bots: HashMap<String, Vec<PortfolioBot>>,
     base_config: Config,
+    /// How many bots may hold a symbol's position at a time; unlimited if `None`.
+    max_positions_per_symbol: Option<u32>,
+    overlap_policy: OverlapPolicy,
+    /// The bots, by index into `bots`, whose entries make up each symbol's open position.
+    holders: HashMap<String, Vec<usize>>,
 }
 
 impl PortfolioManager {
     pub fn new(
         base_config: Config,
         portfolio: Portfolio,
-        risk_manager: Box<dyn RiskManager>,
         executor: Box<dyn Executor>,
         analytics_engine: AnalyticsEngine,
-        strategies: HashMap<String, Box<dyn Strategy>>,
+        bots: HashMap<String, Vec<PortfolioBot>>,
     ) -> Self {
         Self {
             base_config,
             portfolio,
-            risk_manager,
             executor,
             analytics_engine,
-            strategies,
+            bots,
+            max_positions_per_symbol: None,
+            overlap_policy: OverlapPolicy::default(),
+            holders: HashMap::new(),
         }
     }
 
+    /// Limits each symbol's position to the entries of `max_positions_per_symbol` bots,
+    /// treating the entries of further bots by `overlap_policy`.
+    pub fn with_position_limit(mut self, max_positions_per_symbol: Option<u32>, overlap_policy: OverlapPolicy) -> Self {
+        self.max_positions_per_symbol = max_positions_per_symbol;
+        self.overlap_policy = overlap_policy;
+        self
+    }
+
+    /// The shared portfolio, as of the end of the run.
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.portfolio
+    }
+
+    /// Whether the position on `symbol` is already held by as many bots other than the
+    /// `index`th as the limit allows.
+    fn is_held_by_others(&self, symbol: &str, index: usize) -> bool {
+        let others = self.holders.get(symbol).map_or(0, |holders| holders.iter().filter(|&&holder| holder != index).count());
+        self.max_positions_per_symbol.is_some_and(|max| others >= max as usize)
+    }
+
     /// Runs the portfolio-level backtest by processing a pre-sorted event stream.
     pub async fn run(
         &mut self,
         events: Vec<Event>,
-    ) -> Result<PerformanceReport, PortfolioError> {
+    ) -> Result<PortfolioReport, PortfolioError> {
         if events.is_empty() {
             return Err(PortfolioError::Data("Event stream is empty.".to_string()));
         }
@@ -53,6 +98,7 @@ impl PortfolioManager {
         let mut completed_trades = Vec::new();
         // We now need to track pending entries on a per-symbol basis.
         let mut pending_entries: HashMap<String, Execution> = HashMap::new();
+        let mut overlapping_entries_skipped = 0;
 
         let progress_bar = ProgressBar::new(events.len() as u64);
         progress_bar.set_style(
@@ -68,45 +114,127 @@ impl PortfolioManager {
                 Event::Kline(MarketEvent { symbol, kline }) => (kline.close_time, symbol, kline),
             };
 
-            // 1. Route the kline to the correct strategy for evaluation.
-            if let Some(strategy) = self.strategies.get_mut(symbol) {
-                let position_before = self.portfolio.get_position(symbol).cloned();
-
-                if let Some(signal) = strategy.evaluate(kline).unwrap() { // Simplified error handling
-                    // 2. Process the signal through the shared risk and execution components.
-                    let total_equity = self.get_latest_equity()?;
-                    
-                    let order_request = self.risk_manager.evaluate_signal(
-                        &signal,
-                        &events::PortfolioState {
-                            timestamp: event_time,
-                            cash: self.portfolio.cash,
-                            total_value: total_equity,
-                            positions: self.portfolio.positions.values().cloned().collect(),
-                        },
-                        kline.close,
-                    ).unwrap();
+            // 1. Route the kline to every bot trading the symbol for evaluation.
+            // Only the strategies see the transformed kline; execution uses the real one.
+            let signals: Vec<_> = self
+                .bots
+                .get_mut(symbol)
+                .into_iter()
+                .flatten()
+                .enumerate()
+                .filter_map(|(index, bot)| {
+                    let strategy_kline = bot.kline_transform.apply(kline);
+                    let signal = bot.strategy.evaluate(&strategy_kline).unwrap(); // Simplified error handling
+                    signal.map(|signal| (index, signal))
+                })
+                .collect();
+
+            for (index, signal) in signals {
+                // 2. Hold the signal to its bot's direction, size it with its bot's risk
+                // manager, then execute it through the shared components.
+                let bot = &self.bots[symbol][index];
+                let position = self.portfolio.get_position(symbol);
+                let Some(signal) = constrain_direction(bot.direction, signal, position) else {
+                    continue;
+                };
+                let total_equity = self.get_latest_equity()?;
+                let risk_manager = &bot.risk_manager;
+                let mut portfolio_state = events::PortfolioState {
+                    timestamp: event_time,
+                    quote_asset: self.portfolio.quote_asset.clone(),
+                    cash: self.portfolio.cash,
+                    balances: self.portfolio.balances.clone(),
+                    total_value: total_equity,
+                    positions: self.portfolio.positions.values().cloned().collect(),
+                    realized_pnl: self.portfolio.realized_pnl,
+                    total_fees_paid: self.portfolio.total_fees_paid,
+                };
 
+                // An entry adding to a position other bots already hold up to the limit is
+                // skipped, or sized as if the bot were flat and netted against the position.
+                let overlapping = position
+                    .filter(|position| signal.target_side() == Some(position.side) && self.is_held_by_others(symbol, index))
+                    .map(|position| position.quantity);
+                let netted = match (overlapping, self.overlap_policy) {
+                    (Some(_), OverlapPolicy::Skip) => {
+                        tracing::debug!("Skipping an entry on {} at {}: its position is held by other bots up to the limit.", symbol, event_time);
+                        overlapping_entries_skipped += 1;
+                        continue;
+                    }
+                    (Some(held), OverlapPolicy::Net) => {
+                        portfolio_state.positions.retain(|position| &position.symbol != symbol);
+                        Some(held)
+                    }
+                    _ => None,
+                };
+
+                let mut order_plan = match risk_manager.evaluate_signal(&signal, &portfolio_state, kline.close) {
+                    Ok(order_plan) => order_plan,
+                    Err(e) if e.is_declined() => Default::default(),
+                    Err(e) => panic!("{:?}", e), // Simplified error handling
+                };
+                if let Some(held) = netted
+                    && !order_plan.legs.is_empty()
+                {
+                    order_plan.legs.retain_mut(|leg| {
+                        leg.quantity -= held;
+                        leg.quantity > Decimal::ZERO
+                    });
+                    if order_plan.legs.is_empty() {
+                        tracing::debug!("Netted an entry on {} at {} to nothing: the position already holds {}.", symbol, event_time, held);
+                        overlapping_entries_skipped += 1;
+                        continue;
+                    }
+                }
+
+                // Each leg is placed once the previous one has filled and updated the portfolio.
+                for order_request in order_plan.legs {
+                    let position_before = self.portfolio.get_position(symbol).cloned();
                     let execution = self.executor.execute(&order_request, kline, None, None).await.unwrap();
-                    
+
                     // 3. Update the single, shared portfolio state.
                     self.portfolio.update_with_execution(&execution).unwrap();
 
                     // 4. Match trades for the specific symbol that was just traded.
+                    // A netted reversal both closes the old position and opens the new one.
                     let position_after = self.portfolio.get_position(symbol);
-                    match (position_before, position_after) {
-                        (None, Some(_)) => { pending_entries.insert(symbol.clone(), execution); }
-                        (Some(_), None) => {
-                            if let Some(entry_execution) = pending_entries.remove(symbol) {
-                                completed_trades.push(Trade {
-                                    trade_id: Uuid::new_v4(),
-                                    symbol: symbol.clone(),
-                                    entry_execution,
-                                    exit_execution: execution,
-                                });
-                            }
+                    let (exit_execution, entry_execution) = match (&position_before, position_after) {
+                        (None, Some(_)) => (None, Some(execution)),
+                        (Some(_), None) => (Some(execution), None),
+                        (Some(before), Some(after)) if before.side != after.side => {
+                            let (closing, opening) = execution.split_at(before.quantity);
+                            (Some(closing), Some(opening))
+                        }
+                        _ => (None, None), // Position was modified or no change
+                    };
+                    if let Some(exit_execution) = exit_execution
+                        && let Some(entry_execution) = pending_entries.remove(symbol)
+                    {
+                        completed_trades.push(Trade {
+                            trade_id: Uuid::new_v4(),
+                            symbol: symbol.clone(),
+                            entry_execution,
+                            exit_execution,
+                            close_reason: CloseReason::Signal,
+                            // Portfolio runs place no stop-losses.
+                            initial_risk: None,
+                        });
+                    }
+                    if let Some(entry_execution) = entry_execution {
+                        pending_entries.insert(symbol.clone(), entry_execution);
+                    }
+
+                    // The bot whose order opened, added to or reversed the position holds it.
+                    let holders = self.holders.entry(symbol.clone()).or_default();
+                    match (&position_before, position_after) {
+                        (_, None) => holders.clear(),
+                        (Some(before), Some(after)) if before.side != after.side => *holders = vec![index],
+                        (before, Some(after))
+                            if before.as_ref().is_none_or(|before| after.quantity > before.quantity) && !holders.contains(&index) =>
+                        {
+                            holders.push(index)
                         }
-                        _ => {} // Position was modified or no change
+                        _ => {}
                     }
                 }
             }
@@ -119,15 +247,17 @@ impl PortfolioManager {
 
         progress_bar.finish_with_message("Portfolio simulation complete.");
 
-        // 6. Generate the final, unified performance report.
-        let report = self.analytics_engine.calculate(
+        // 6. Generate the final, unified performance report. The equity is only the cash (see
+        // `get_latest_equity`), so no exposure is recorded against it.
+        let performance = self.analytics_engine.calculate(
             &completed_trades,
             &equity_curve,
+            &[],
             self.base_config.backtest.initial_capital,
             &self.base_config.backtest.interval,
         ).unwrap(); // Simplified error handling
 
-        Ok(report)
+        Ok(PortfolioReport { performance, overlapping_entries_skipped })
     }
 
     /// Helper to get the most recent portfolio equity.
@@ -0,0 +1,89 @@
+//! Checks how `max_positions_per_symbol` treats a second bot entering on a symbol whose
+//! position another bot already holds, under each overlap policy.
+
+use analytics::AnalyticsEngine;
+use configuration::OverlapPolicy;
+use core_types::{Signal, TradeDirection};
+use events::PortfolioState;
+use executor::{Portfolio, SimulatedExecutor};
+use portfolio_backtester::{Event, MarketEvent, PortfolioBot, PortfolioManager, PortfolioReport};
+use risk::{OrderPlan, RiskError, RiskManager};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use strategies::KlineTransformer;
+use testing::{generate_klines, test_config, EnterAt, TEST_SYMBOL};
+
+const BARS: usize = 6;
+
+/// Sizes every entry at a fixed number of units, adding to an open position like pyramiding.
+struct Units(u32);
+
+impl RiskManager for Units {
+    fn evaluate_signal(&self, signal: &Signal, _: &PortfolioState, _: Decimal) -> Result<OrderPlan, RiskError> {
+        let mut order = signal.order_request.clone();
+        order.quantity = Decimal::from(self.0);
+        Ok(OrderPlan::single(order))
+    }
+}
+
+fn bot(bars: Vec<usize>, units: u32) -> PortfolioBot {
+    PortfolioBot {
+        strategy: Box::new(EnterAt::new(bars)),
+        kline_transform: KlineTransformer::new(Default::default()),
+        direction: TradeDirection::Both,
+        risk_manager: Box::new(Units(units)),
+    }
+}
+
+/// Runs a one-unit bot entering on the first bar and a three-unit bot entering on the
+/// third, both on one symbol, and returns the report and the quantity held at the end.
+async fn run(max_positions_per_symbol: Option<u32>, overlap_policy: OverlapPolicy) -> (PortfolioReport, Decimal) {
+    let config = test_config(BARS).expect("load config");
+    let events = generate_klines(BARS)
+        .into_iter()
+        .map(|kline| Event::Kline(MarketEvent { symbol: TEST_SYMBOL.to_string(), kline }))
+        .collect();
+    let bots = HashMap::from([(TEST_SYMBOL.to_string(), vec![bot(vec![0], 1), bot(vec![2], 3)])]);
+    let mut manager = PortfolioManager::new(
+        config.clone(),
+        Portfolio::new(config.backtest.initial_capital),
+        Box::new(SimulatedExecutor::new(config.simulation.clone())),
+        AnalyticsEngine::new(),
+        bots,
+    )
+    .with_position_limit(max_positions_per_symbol, overlap_policy);
+
+    let report = manager.run(events).await.expect("run");
+    let held = manager.portfolio().get_position(TEST_SYMBOL).map_or(Decimal::ZERO, |position| position.quantity);
+    (report, held)
+}
+
+#[tokio::test]
+async fn without_a_limit_the_entries_add_up() {
+    let (report, held) = run(None, OverlapPolicy::Skip).await;
+    assert_eq!((held, report.overlapping_entries_skipped), (Decimal::from(4), 0));
+}
+
+#[tokio::test]
+async fn skip_keeps_the_first_bots_position_and_counts_the_second_entry() {
+    let (report, held) = run(Some(1), OverlapPolicy::Skip).await;
+    assert_eq!((held, report.overlapping_entries_skipped), (Decimal::ONE, 1));
+}
+
+#[tokio::test]
+async fn net_tops_the_position_up_to_the_second_bots_own_size() {
+    let (report, held) = run(Some(1), OverlapPolicy::Net).await;
+    assert_eq!((held, report.overlapping_entries_skipped), (Decimal::from(3), 0));
+}
+
+#[tokio::test]
+async fn allow_places_the_second_entry_in_full() {
+    let (report, held) = run(Some(1), OverlapPolicy::Allow).await;
+    assert_eq!((held, report.overlapping_entries_skipped), (Decimal::from(4), 0));
+}
+
+#[tokio::test]
+async fn a_limit_of_two_lets_both_bots_hold_the_position() {
+    let (report, held) = run(Some(2), OverlapPolicy::Skip).await;
+    assert_eq!((held, report.overlapping_entries_skipped), (Decimal::from(4), 0));
+}
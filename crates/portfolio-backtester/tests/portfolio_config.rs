@@ -0,0 +1,120 @@
+//! Tests for loading portfolio definitions and resolving their settings.
+
+use configuration::error::ConfigError;
+use configuration::{load_portfolio_config, OverlapPolicy, PortfolioConfig};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use testing::test_config;
+use uuid::Uuid;
+
+/// Writes `contents` to a uniquely named TOML file in the temp directory.
+fn write_portfolio(contents: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("portfolio-{}.toml", Uuid::new_v4()));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+fn load(contents: &str) -> Result<PortfolioConfig, ConfigError> {
+    let path = write_portfolio(contents);
+    let result = load_portfolio_config(&path);
+    std::fs::remove_file(path).unwrap();
+    result
+}
+
+#[test]
+fn duplicate_bot_is_rejected() {
+    let result = load(
+        r#"
+        [[bot]]
+        symbol = "BTCUSDT"
+        strategy_id = "MACrossover"
+        [bot.params]
+        ma_fast_period = 10
+
+        [[bot]]
+        symbol = "BTCUSDT"
+        strategy_id = "MACrossover"
+        [bot.params]
+        ma_fast_period = 20
+        "#,
+    );
+
+    match result {
+        Err(ConfigError::ValidationError(msg)) => assert!(msg.contains("BTCUSDT"), "message: {}", msg),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn named_bots_may_share_a_symbol_and_strategy() {
+    let bot = |name: &str| {
+        format!(
+            r#"
+            [[bot]]
+            symbol = "BTCUSDT"
+            strategy_id = "MACrossover"
+            name = "{}"
+            [bot.params]
+            ma_fast_period = 10
+            "#,
+            name
+        )
+    };
+
+    let portfolio = load(&[bot("rank-1"), bot("rank-2")].concat()).expect("load portfolio");
+    assert_eq!(portfolio.bots[1].name.as_deref(), Some("rank-2"));
+
+    match load(&[bot("rank-1"), bot("rank-1")].concat()) {
+        Err(ConfigError::ValidationError(msg)) => assert!(msg.contains("rank-1"), "message: {}", msg),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
+
+#[test]
+fn capital_override_reaches_the_portfolio() {
+    let base_config = test_config(100).expect("load config");
+    let mut portfolio_config = load(
+        r#"
+        initial_capital = 25000
+        interval = "4h"
+
+        [[bot]]
+        symbol = "BTCUSDT"
+        strategy_id = "MACrossover"
+        [bot.params]
+        ma_fast_period = 10
+        "#,
+    )
+    .expect("load portfolio");
+
+    let resolved = portfolio_config.resolve_base_config(&base_config);
+    assert_eq!(Portfolio::new(resolved.backtest.initial_capital).cash, Decimal::from(25_000));
+    assert_eq!(resolved.backtest.interval, "4h");
+
+    // A CLI override takes precedence over the portfolio file.
+    portfolio_config.initial_capital = Some(Decimal::from(5_000));
+    let resolved = portfolio_config.resolve_base_config(&base_config);
+    assert_eq!(Portfolio::new(resolved.backtest.initial_capital).cash, Decimal::from(5_000));
+}
+
+#[test]
+fn the_position_limit_is_read_and_must_allow_a_bot() {
+    let bot = r#"
+        [[bot]]
+        symbol = "BTCUSDT"
+        strategy_id = "MACrossover"
+        [bot.params]
+        ma_fast_period = 10
+        "#;
+    let unlimited = load(bot).expect("load portfolio");
+    assert_eq!((unlimited.max_positions_per_symbol, unlimited.overlap_policy), (None, OverlapPolicy::Skip));
+
+    let limited = load(&format!("max_positions_per_symbol = 1\noverlap_policy = \"net\"\n{}", bot)).expect("load portfolio");
+    assert_eq!((limited.max_positions_per_symbol, limited.overlap_policy), (Some(1), OverlapPolicy::Net));
+
+    match load(&format!("max_positions_per_symbol = 0\n{}", bot)) {
+        Err(ConfigError::ValidationError(msg)) => assert!(msg.contains("max_positions_per_symbol"), "message: {}", msg),
+        other => panic!("expected a validation error, got {:?}", other),
+    }
+}
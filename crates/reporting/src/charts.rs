@@ -0,0 +1,117 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::fmt::Write;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 240.0;
+const MARGIN_LEFT: f64 = 80.0;
+const MARGIN_RIGHT: f64 = 16.0;
+const MARGIN_TOP: f64 = 12.0;
+const MARGIN_BOTTOM: f64 = 28.0;
+const Y_TICKS: usize = 4;
+
+/// A minimal time-series line chart rendered to an inline SVG string.
+#[derive(Debug, Clone)]
+pub struct LineChart<'a> {
+    pub points: &'a [(DateTime<Utc>, Decimal)],
+    /// The stroke color of the line, as a CSS color.
+    pub color: &'a str,
+    /// If set, the area between the line and this y-value is shaded.
+    pub fill_to: Option<Decimal>,
+}
+
+impl LineChart<'_> {
+    /// Renders the chart. An empty series renders a placeholder message instead of axes.
+    pub fn to_svg(&self) -> String {
+        let mut svg = String::new();
+        let _ = write!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 {WIDTH} {HEIGHT}" width="100%" role="img">"#
+        );
+
+        if self.points.is_empty() {
+            let _ = write!(
+                svg,
+                r##"<text x="{}" y="{}" text-anchor="middle" fill="#888">No data</text></svg>"##,
+                WIDTH / 2.0,
+                HEIGHT / 2.0
+            );
+            return svg;
+        }
+
+        let values: Vec<f64> = self.points.iter().map(|(_, v)| to_f64(*v)).collect();
+        let mut y_min = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let mut y_max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if let Some(fill_to) = self.fill_to.map(to_f64) {
+            y_min = y_min.min(fill_to);
+            y_max = y_max.max(fill_to);
+        }
+        if (y_max - y_min).abs() < f64::EPSILON {
+            y_min -= 1.0;
+            y_max += 1.0;
+        }
+
+        let x_start = self.points[0].0.timestamp() as f64;
+        let x_end = self.points[self.points.len() - 1].0.timestamp() as f64;
+        let x_span = (x_end - x_start).max(1.0);
+        let plot_width = WIDTH - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = HEIGHT - MARGIN_TOP - MARGIN_BOTTOM;
+        let x_of = |t: &DateTime<Utc>| MARGIN_LEFT + (t.timestamp() as f64 - x_start) / x_span * plot_width;
+        let y_of = |v: f64| MARGIN_TOP + (y_max - v) / (y_max - y_min) * plot_height;
+
+        // --- Grid lines and y-axis labels ---
+        for i in 0..=Y_TICKS {
+            let value = y_min + (y_max - y_min) * i as f64 / Y_TICKS as f64;
+            let y = y_of(value);
+            let _ = write!(
+                svg,
+                r##"<line x1="{MARGIN_LEFT}" y1="{y:.1}" x2="{:.1}" y2="{y:.1}" stroke="#e3e3e3"/><text x="{:.1}" y="{:.1}" text-anchor="end" font-size="11" fill="#555">{value:.2}</text>"##,
+                WIDTH - MARGIN_RIGHT,
+                MARGIN_LEFT - 6.0,
+                y + 4.0
+            );
+        }
+
+        // --- X-axis labels: first and last date ---
+        let label_y = HEIGHT - 8.0;
+        let _ = write!(
+            svg,
+            r##"<text x="{MARGIN_LEFT}" y="{label_y}" font-size="11" fill="#555">{}</text><text x="{:.1}" y="{label_y}" text-anchor="end" font-size="11" fill="#555">{}</text>"##,
+            self.points[0].0.format("%Y-%m-%d"),
+            WIDTH - MARGIN_RIGHT,
+            self.points[self.points.len() - 1].0.format("%Y-%m-%d")
+        );
+
+        // --- Series ---
+        let coords: Vec<String> = self
+            .points
+            .iter()
+            .zip(&values)
+            .map(|((t, _), v)| format!("{:.1},{:.1}", x_of(t), y_of(*v)))
+            .collect();
+
+        if let Some(fill_to) = self.fill_to.map(to_f64) {
+            let baseline = y_of(fill_to);
+            let _ = write!(
+                svg,
+                r#"<polygon points="{:.1},{baseline:.1} {} {:.1},{baseline:.1}" fill="{}" fill-opacity="0.2" stroke="none"/>"#,
+                x_of(&self.points[0].0),
+                coords.join(" "),
+                x_of(&self.points[self.points.len() - 1].0),
+                self.color
+            );
+        }
+        let _ = write!(
+            svg,
+            r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="1.5"/></svg>"#,
+            coords.join(" "),
+            self.color
+        );
+        svg
+    }
+}
+
+fn to_f64(value: Decimal) -> f64 {
+    value.to_f64().unwrap_or(0.0)
+}
@@ -0,0 +1,179 @@
+use crate::charts::LineChart;
+use crate::series::{drawdown_pct, monthly_returns_pct, trade_pnl};
+use chrono::{DateTime, Utc};
+use core_types::OrderSide;
+use database::{BacktestRunDetails, FullReport};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+use std::fmt::Write;
+
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+const STYLE: &str = "body{font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;margin:2rem auto;max-width:960px;color:#222}\
+h1{font-size:1.6rem;margin-bottom:0.2rem}h2{font-size:1.2rem;margin-top:2rem;border-bottom:1px solid #ddd;padding-bottom:0.3rem}\
+.meta{color:#666;font-size:0.9rem}table{border-collapse:collapse;width:100%;font-size:0.85rem}\
+th,td{border:1px solid #e3e3e3;padding:4px 8px;text-align:right}th{background:#f6f6f6}\
+td.label,th.label{text-align:left}.pos{color:#1a7f37}.neg{color:#cf222e}pre{background:#f6f6f6;padding:0.6rem;overflow-x:auto}";
+
+/// Renders a complete, self-contained HTML report for a single backtest run.
+///
+/// The page contains a metrics summary, equity and drawdown charts as inline SVG,
+/// a monthly-returns heatmap and the list of trades.
+pub fn render_html(details: &BacktestRunDetails) -> String {
+    let report = &details.report;
+    let equity_curve: Vec<(DateTime<Utc>, Decimal)> =
+        details.equity_curve.iter().map(|p| (p.timestamp, p.equity)).collect();
+
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Backtest Report {}</title>\n<style>{}</style>\n</head>\n<body>\n",
+        report.run_id, STYLE
+    );
+    let _ = writeln!(html, "<h1>Backtest Report</h1>");
+    let _ = writeln!(html, "<p class=\"meta\">Run {} &middot; Job {}</p>", report.run_id, report.job_id);
+    let parameters = serde_json::to_string_pretty(&report.parameters).unwrap_or_default();
+    let _ = writeln!(html, "<pre>{}</pre>", escape(&parameters));
+
+    let _ = writeln!(html, "<h2>Summary</h2>");
+    html.push_str(&metrics_table(report));
+
+    let _ = writeln!(html, "<h2>Equity Curve</h2>");
+    html.push_str(&LineChart { points: &equity_curve, color: "#0969da", fill_to: None }.to_svg());
+    html.push('\n');
+
+    let _ = writeln!(html, "<h2>Drawdown (%)</h2>");
+    let drawdown = drawdown_pct(&equity_curve);
+    html.push_str(&LineChart { points: &drawdown, color: "#cf222e", fill_to: Some(Decimal::ZERO) }.to_svg());
+    html.push('\n');
+
+    let _ = writeln!(html, "<h2>Monthly Returns (%)</h2>");
+    html.push_str(&monthly_returns_table(&equity_curve));
+
+    let _ = writeln!(html, "<h2>Trades</h2>");
+    html.push_str(&trades_table(details));
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn metrics_table(report: &FullReport) -> String {
+    let rows: [(&str, String); 26] = [
+        ("Total Net Profit", fmt_opt(report.total_net_profit)),
+        ("Total Return %", fmt_opt(report.total_return_pct)),
+        ("Gross Profit", fmt_opt(report.gross_profit)),
+        ("Gross Loss", fmt_opt(report.gross_loss)),
+        ("Profit Factor", fmt_opt(report.profit_factor)),
+        ("Max Drawdown", fmt_opt(report.max_drawdown)),
+        ("Max Drawdown %", fmt_opt(report.max_drawdown_pct)),
+        ("Sharpe Ratio", fmt_opt(report.sharpe_ratio)),
+        ("Calmar Ratio", fmt_opt(report.calmar_ratio)),
+        ("Total Trades", report.total_trades.map_or_else(dash, |v| v.to_string())),
+        ("Winning Trades", report.winning_trades.map_or_else(dash, |v| v.to_string())),
+        ("Losing Trades", report.losing_trades.map_or_else(dash, |v| v.to_string())),
+        ("Win Rate %", fmt_opt(report.win_rate_pct)),
+        ("Average Win", fmt_opt(report.average_win)),
+        ("Average Loss", fmt_opt(report.average_loss)),
+        ("Payoff Ratio", fmt_opt(report.payoff_ratio)),
+        ("Average Holding Period", report.average_holding_period.as_deref().map_or_else(dash, escape)),
+        ("Maker Fees Paid", fmt_opt(report.maker_fees_paid)),
+        ("Taker Fees Paid", fmt_opt(report.taker_fees_paid)),
+        ("Spread Cost", fmt_opt(report.total_spread_cost)),
+        ("Gap Slippage", fmt_opt(report.gap_slippage)),
+        ("Average Exposure %", fmt_opt(report.average_exposure_pct)),
+        ("Peak Exposure %", fmt_opt(report.peak_exposure_pct)),
+        ("Time in Market %", fmt_opt(report.time_in_market_pct)),
+        ("Return on Margin %", fmt_opt(report.return_on_margin_pct)),
+        ("Exposure-Adjusted Sharpe", fmt_opt(report.exposure_adjusted_sharpe)),
+    ];
+
+    let mut table = String::from("<table>\n");
+    for (label, value) in rows {
+        let _ = writeln!(table, "<tr><th class=\"label\">{}</th><td>{}</td></tr>", label, value);
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn monthly_returns_table(equity_curve: &[(DateTime<Utc>, Decimal)]) -> String {
+    let returns = monthly_returns_pct(equity_curve);
+    if returns.is_empty() {
+        return "<p class=\"meta\">No data</p>\n".to_string();
+    }
+
+    // Cell shading scales with the largest absolute monthly return.
+    let max_abs = returns.values().map(|r| r.abs()).max().unwrap_or(Decimal::ONE).max(Decimal::new(1, 2));
+    let first_year = returns.keys().next().map_or(0, |(year, _)| *year);
+    let last_year = returns.keys().next_back().map_or(0, |(year, _)| *year);
+
+    let mut table = String::from("<table>\n<tr><th class=\"label\">Year</th>");
+    for month in MONTHS {
+        let _ = write!(table, "<th>{}</th>", month);
+    }
+    table.push_str("</tr>\n");
+
+    for year in first_year..=last_year {
+        let _ = write!(table, "<tr><th class=\"label\">{}</th>", year);
+        for month in 1..=12 {
+            match returns.get(&(year, month)) {
+                Some(r) => {
+                    let alpha = 0.15 + 0.6 * (r.abs() / max_abs).to_f64().unwrap_or(0.0);
+                    let rgb = if r.is_sign_negative() { "207,34,46" } else { "26,127,55" };
+                    let _ = write!(table, "<td style=\"background:rgba({},{:.2})\">{:.2}</td>", rgb, alpha, r);
+                }
+                None => table.push_str("<td></td>"),
+            }
+        }
+        table.push_str("</tr>\n");
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn trades_table(details: &BacktestRunDetails) -> String {
+    if details.trades.is_empty() {
+        return "<p class=\"meta\">No trades</p>\n".to_string();
+    }
+
+    let mut table = String::from(
+        "<table>\n<tr><th>#</th><th class=\"label\">Symbol</th><th class=\"label\">Side</th><th class=\"label\">Entry Time</th><th>Entry Price</th><th class=\"label\">Exit Time</th><th>Exit Price</th><th>Quantity</th><th>P&amp;L</th></tr>\n",
+    );
+    for (i, trade) in details.trades.iter().enumerate() {
+        let entry = &trade.entry_execution;
+        let exit = &trade.exit_execution;
+        let pnl = trade_pnl(trade);
+        let side = match entry.side {
+            OrderSide::Buy => "Long",
+            OrderSide::Sell => "Short",
+        };
+        let _ = writeln!(
+            table,
+            "<tr><td>{}</td><td class=\"label\">{}</td><td class=\"label\">{}</td><td class=\"label\">{}</td><td>{:.2}</td><td class=\"label\">{}</td><td>{:.2}</td><td>{}</td><td class=\"{}\">{:.2}</td></tr>",
+            i + 1,
+            escape(&trade.symbol),
+            side,
+            entry.timestamp.format("%Y-%m-%d %H:%M"),
+            entry.price,
+            exit.timestamp.format("%Y-%m-%d %H:%M"),
+            exit.price,
+            exit.quantity.normalize(),
+            if pnl.is_sign_negative() { "neg" } else { "pos" },
+            pnl
+        );
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn fmt_opt(value: Option<Decimal>) -> String {
+    value.map_or_else(dash, |v| format!("{:.2}", v))
+}
+
+fn dash() -> String {
+    "&mdash;".to_string()
+}
+
+/// Escapes text for safe inclusion in HTML element content.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
@@ -0,0 +1,25 @@
+//! # Zenith Reporting
+//!
+//! This crate renders the results of a single backtest run into a self-contained HTML
+//! file that can be shared with people who do not run the stack.
+//!
+//! ## Architectural Principles
+//!
+//! - **Pure Logic:** Rendering takes the already-loaded `BacktestRunDetails` as input and
+//!   returns a `String`. It performs no I/O, so callers decide where the HTML goes.
+//! - **Self-Contained Output:** Styles and charts (as inline SVG) are embedded in the page.
+//!   The file references no external JS, CSS or fonts, so it works offline.
+//! - **Deterministic:** The same input always produces byte-identical output, which keeps
+//!   the snapshot tests stable.
+//!
+//! ## Public API
+//!
+//! - `render_html`: Renders a full HTML report for a backtest run.
+
+// Declare the modules that constitute this crate.
+pub mod charts;
+pub mod html;
+pub mod series;
+
+// Re-export the public components to provide a clean API.
+pub use html::render_html;
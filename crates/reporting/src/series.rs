@@ -0,0 +1,54 @@
+use chrono::{DateTime, Datelike, Utc};
+use core_types::{OrderSide, Trade};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+
+/// Converts an equity curve into a drawdown curve, in percent below the running peak.
+/// Values are zero or negative.
+pub fn drawdown_pct(equity_curve: &[(DateTime<Utc>, Decimal)]) -> Vec<(DateTime<Utc>, Decimal)> {
+    let mut peak = Decimal::ZERO;
+    equity_curve
+        .iter()
+        .map(|&(timestamp, equity)| {
+            peak = peak.max(equity);
+            let drawdown = if peak.is_zero() {
+                Decimal::ZERO
+            } else {
+                (equity - peak) / peak * Decimal::ONE_HUNDRED
+            };
+            (timestamp, drawdown)
+        })
+        .collect()
+}
+
+/// Computes the return of each calendar month, in percent, keyed by (year, month).
+///
+/// A month's return compares its last equity value against the last value of the previous
+/// month; the first month compares against the first point of the curve.
+pub fn monthly_returns_pct(equity_curve: &[(DateTime<Utc>, Decimal)]) -> BTreeMap<(i32, u32), Decimal> {
+    let mut month_end: BTreeMap<(i32, u32), Decimal> = BTreeMap::new();
+    for &(timestamp, equity) in equity_curve {
+        month_end.insert((timestamp.year(), timestamp.month()), equity);
+    }
+
+    let mut returns = BTreeMap::new();
+    let mut previous = equity_curve.first().map(|&(_, equity)| equity);
+    for (month, equity) in month_end {
+        if let Some(start) = previous.filter(|start| !start.is_zero()) {
+            returns.insert(month, (equity - start) / start * Decimal::ONE_HUNDRED);
+        }
+        previous = Some(equity);
+    }
+    returns
+}
+
+/// The gross P&L of a trade, before fees.
+pub fn trade_pnl(trade: &Trade) -> Decimal {
+    let entry = &trade.entry_execution;
+    let exit = &trade.exit_execution;
+    let pnl_per_unit = match entry.side {
+        OrderSide::Buy => exit.price - entry.price,
+        OrderSide::Sell => entry.price - exit.price,
+    };
+    pnl_per_unit * exit.quantity
+}
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use database::{BacktestRunDetails, EquityDataPoint, FullReport};
+use reporting::render_html;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::path::PathBuf;
+use uuid::Uuid;
+
+fn execution(id: u128, side: OrderSide, price: Decimal, timestamp: DateTime<Utc>) -> Execution {
+    Execution {
+        execution_id: Uuid::from_u128(id),
+        client_order_id: Uuid::from_u128(id + 1000),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        quantity: dec!(0.5),
+        fee: dec!(0.1),
+        fee_asset: "USDT".to_string(),
+        timestamp,
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// A small, fully deterministic run spanning two months with one winning and one losing trade.
+fn fixture() -> BacktestRunDetails {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let equity = [
+        dec!(10000), dec!(10150), dec!(10300), dec!(10220), dec!(10050), dec!(9900),
+        dec!(9980), dec!(10100), dec!(10240), dec!(10410), dec!(10380), dec!(10500),
+    ];
+    let equity_curve = equity
+        .iter()
+        .enumerate()
+        .map(|(i, &equity)| EquityDataPoint { timestamp: start + Duration::days(i as i64 * 5), equity })
+        .collect();
+
+    let trades = vec![
+        Trade {
+            trade_id: Uuid::from_u128(1),
+            symbol: "BTCUSDT".to_string(),
+            entry_execution: execution(10, OrderSide::Buy, dec!(42000), start + Duration::days(1)),
+            exit_execution: execution(11, OrderSide::Sell, dec!(42600), start + Duration::days(9)),
+            close_reason: CloseReason::Signal,
+            initial_risk: None,
+        },
+        Trade {
+            trade_id: Uuid::from_u128(2),
+            symbol: "BTCUSDT".to_string(),
+            entry_execution: execution(20, OrderSide::Sell, dec!(43000), start + Duration::days(31)),
+            exit_execution: execution(21, OrderSide::Buy, dec!(43400), start + Duration::days(38)),
+            close_reason: CloseReason::Signal,
+            initial_risk: None,
+        },
+    ];
+
+    BacktestRunDetails {
+        report: FullReport {
+            run_id: Uuid::from_u128(0xabc),
+            job_id: Uuid::from_u128(0xdef),
+            parameters: serde_json::json!({ "ma_crossover": { "short_period": 10, "long_period": 30 } }),
+            report_id: Some(Uuid::from_u128(0x123)),
+            total_net_profit: Some(dec!(500)),
+            gross_profit: Some(dec!(300)),
+            gross_loss: Some(dec!(200)),
+            profit_factor: Some(dec!(1.5)),
+            total_return_pct: Some(dec!(5)),
+            max_drawdown: Some(dec!(400)),
+            max_drawdown_pct: Some(dec!(3.883)),
+            sharpe_ratio: Some(dec!(1.234)),
+            calmar_ratio: None,
+            total_trades: Some(2),
+            winning_trades: Some(1),
+            losing_trades: Some(1),
+            win_rate_pct: Some(dec!(50)),
+            average_win: Some(dec!(300)),
+            average_loss: Some(dec!(200)),
+            payoff_ratio: Some(dec!(1.5)),
+            max_consecutive_losses: None,
+            average_holding_period: Some("7 days 12:00:00".to_string()),
+            maker_fees_paid: None,
+            taker_fees_paid: Some(dec!(8.4)),
+            total_spread_cost: None,
+            gap_slippage: None,
+            exit_breakdown: None,
+            average_r: None,
+            expectancy_r: None,
+            r_distribution: None,
+            average_exposure_pct: Some(dec!(42.5)),
+            peak_exposure_pct: Some(dec!(98.2)),
+            time_in_market_pct: Some(dec!(55)),
+            return_on_margin_pct: Some(dec!(29.4)),
+            exposure_adjusted_sharpe: Some(dec!(1.6)),
+            started_at: None,
+            finished_at: None,
+            bars_processed: None,
+            signals_filtered: None,
+            brackets_stop_first: None,
+            brackets_target_first: None,
+            engine_version: None,
+            config_hash: None,
+            objective_value: None,
+        },
+        trades,
+        equity_curve,
+    }
+}
+
+#[test]
+fn report_matches_snapshot() {
+    let html = render_html(&fixture());
+
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/snapshots/report.html");
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        std::fs::write(&path, &html).unwrap();
+    }
+    let expected = std::fs::read_to_string(&path).expect("snapshot missing; rerun with UPDATE_SNAPSHOTS=1");
+    assert_eq!(html, expected, "report differs from snapshot; rerun with UPDATE_SNAPSHOTS=1 to accept");
+}
+
+#[test]
+fn report_is_self_contained() {
+    let html = render_html(&fixture());
+
+    assert!(!html.contains("<script"));
+    assert!(!html.contains("<link"));
+    // The SVG namespace is the only URL allowed in the document.
+    assert_eq!(html.matches("http").count(), html.matches("http://www.w3.org/2000/svg").count());
+}
+
+#[test]
+fn report_handles_an_empty_run() {
+    let mut details = fixture();
+    details.trades.clear();
+    details.equity_curve.clear();
+
+    let html = render_html(&details);
+
+    assert!(html.contains("No trades"));
+    assert!(html.ends_with("</html>\n"));
+}
@@ -0,0 +1,219 @@
+//! Caps the loss of each trading day, per symbol and for the whole account.
+//!
+//! A trading day starts at the configured UTC rollover hour. Its loss is the P&L realized
+//! since the rollover, net of fees, plus the change in the open positions' unrealized P&L
+//! since the rollover, so a position carried over the rollover counts only what it lost or
+//! gained today, whether it is still open or closed since. Both are known from the live
+//! executions and the portfolio states, which lets a restarted engine rebuild the day from
+//! its recorded fills. A day the tracker did not see start, e.g. after a restart, counts the
+//! open positions' whole unrealized P&L, erring towards the limit. Each limit is alerted once a day as a warning at
+//! `DAILY_LOSS_WARNING_FRACTION` of it and once as a breach.
+
+use chrono::{DateTime, Duration, Utc};
+use configuration::{DailyLossLimit, DailyLossLimits};
+use events::PortfolioState;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// The fraction of a limit the day's loss must reach to raise a warning.
+pub const DAILY_LOSS_WARNING_FRACTION: Decimal = dec!(0.8);
+
+/// The start of the trading day `at` falls in, for days starting at `rollover_hour_utc`.
+pub fn trading_day_start(at: DateTime<Utc>, rollover_hour_utc: u32) -> DateTime<Utc> {
+    let rollover = at
+        .date_naive()
+        .and_hms_opt(rollover_hour_utc.min(23), 0, 0)
+        .expect("a valid hour")
+        .and_utc();
+    if rollover > at { rollover - Duration::days(1) } else { rollover }
+}
+
+/// What a daily loss limit applies to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum LossScope {
+    /// Every symbol together.
+    Account,
+    Symbol(String),
+}
+
+impl fmt::Display for LossScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LossScope::Account => f.write_str("the account"),
+            LossScope::Symbol(symbol) => f.write_str(symbol),
+        }
+    }
+}
+
+/// How close a day's loss has come to its limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LossLevel {
+    /// At `DAILY_LOSS_WARNING_FRACTION` of the limit or more.
+    Warning,
+    /// At the limit or past it.
+    Breach,
+}
+
+/// A daily loss reaching a new level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyLossAlert {
+    pub scope: LossScope,
+    pub level: LossLevel,
+    /// The day's loss so far, in quote currency.
+    pub loss: Decimal,
+    /// The loss the limit allows, in quote currency.
+    pub limit: Decimal,
+}
+
+/// The result of checking a portfolio state against the limits.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DailyLossUpdate {
+    /// True when a new trading day started since the last check. Its breaches are over.
+    pub rolled_over: bool,
+    /// The limits whose loss reached a new level.
+    pub alerts: Vec<DailyLossAlert>,
+}
+
+/// Accumulates the day's realized P&L and checks each day's loss against the limits.
+#[derive(Debug, Clone)]
+pub struct DailyLossTracker {
+    limits: DailyLossLimits,
+    /// The start of the current trading day; `None` until the first fill or check.
+    day_start: Option<DateTime<Utc>>,
+    /// The account's equity at the rollover, the base of percentage limits. Estimated at the
+    /// day's first check when not known.
+    start_equity: Option<Decimal>,
+    /// The P&L realized since the rollover, net of fees, by symbol.
+    realized: BTreeMap<String, Decimal>,
+    /// The open positions' unrealized P&L at the rollover, by symbol, counted off the day's
+    /// P&L. Empty when no check preceded the rollover.
+    carried: BTreeMap<String, Decimal>,
+    /// The time of the latest check and the open positions' unrealized P&L then, by symbol.
+    last_check: Option<(DateTime<Utc>, BTreeMap<String, Decimal>)>,
+    /// The highest level each limit has been alerted at today.
+    alerted: HashMap<LossScope, LossLevel>,
+    /// Whether a rollover happened since the last check.
+    rolled_over: bool,
+}
+
+impl DailyLossTracker {
+    pub fn new(limits: DailyLossLimits) -> Self {
+        Self {
+            limits,
+            day_start: None,
+            start_equity: None,
+            realized: BTreeMap::new(),
+            carried: BTreeMap::new(),
+            last_check: None,
+            alerted: HashMap::new(),
+            rolled_over: false,
+        }
+    }
+
+    /// The start of the trading day `at` falls in.
+    pub fn day_start_at(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        trading_day_start(at, self.limits.rollover_hour_utc)
+    }
+
+    /// The start of the current trading day, once one has begun.
+    pub fn day_start(&self) -> Option<DateTime<Utc>> {
+        self.day_start
+    }
+
+    /// When the current trading day ends and its breaches are lifted.
+    pub fn next_rollover(&self) -> Option<DateTime<Utc>> {
+        self.day_start.map(|start| start + Duration::days(1))
+    }
+
+    /// The P&L realized today, net of fees, by symbol.
+    pub fn realized(&self) -> &BTreeMap<String, Decimal> {
+        &self.realized
+    }
+
+    /// Starts the trading day beginning at `day_start` with the P&L already realized in it,
+    /// e.g. rebuilt from the recorded fills after a restart. `start_equity` is the account's
+    /// equity at the rollover, if known. The open positions' unrealized P&L counts in full.
+    pub fn start_day(&mut self, day_start: DateTime<Utc>, start_equity: Option<Decimal>, realized: BTreeMap<String, Decimal>) {
+        self.day_start = Some(day_start);
+        self.start_equity = start_equity;
+        self.realized = realized;
+        self.carried.clear();
+        self.alerted.clear();
+    }
+
+    /// Records the P&L a fill on `symbol` at `at` realized, net of its fee. Fills of an
+    /// earlier trading day are ignored.
+    pub fn record_fill(&mut self, symbol: &str, net_pnl: Decimal, at: DateTime<Utc>) {
+        self.roll(at);
+        if self.day_start.is_some_and(|start| at < start) {
+            return;
+        }
+        *self.realized.entry(symbol.to_string()).or_default() += net_pnl;
+    }
+
+    /// Checks the day's loss in `state` against every limit, starting a new trading day
+    /// first if the state's timestamp is past the rollover.
+    pub fn update(&mut self, state: &PortfolioState) -> DailyLossUpdate {
+        self.roll(state.timestamp);
+
+        let mut unrealized: BTreeMap<String, Decimal> = BTreeMap::new();
+        for position in &state.positions {
+            *unrealized.entry(position.symbol.clone()).or_default() += position.unrealized_pnl;
+        }
+        let mut pnl = self.realized.clone();
+        for (symbol, carried) in &self.carried {
+            *pnl.entry(symbol.clone()).or_default() -= *carried;
+        }
+        for (symbol, unrealized) in &unrealized {
+            *pnl.entry(symbol.clone()).or_default() += *unrealized;
+        }
+        self.last_check = Some((state.timestamp, unrealized));
+        let account_pnl: Decimal = pnl.values().sum();
+        let start_equity = *self.start_equity.get_or_insert(state.total_value - account_pnl);
+
+        let symbols = self.limits.symbols.iter().map(|(symbol, limit)| {
+            (LossScope::Symbol(symbol.clone()), *limit, pnl.get(symbol).copied().unwrap_or_default())
+        });
+        let scopes: Vec<(LossScope, DailyLossLimit, Decimal)> =
+            std::iter::once((LossScope::Account, self.limits.account(), account_pnl)).chain(symbols).collect();
+
+        let mut alerts = Vec::new();
+        for (scope, limit, pnl) in scopes {
+            let Some(limit) = limit.allowed_loss(start_equity) else { continue };
+            let loss = -pnl;
+            let level = if loss >= limit {
+                LossLevel::Breach
+            } else if loss >= limit * DAILY_LOSS_WARNING_FRACTION {
+                LossLevel::Warning
+            } else {
+                continue;
+            };
+            if self.alerted.get(&scope).is_some_and(|alerted| *alerted >= level) {
+                continue;
+            }
+            self.alerted.insert(scope.clone(), level);
+            alerts.push(DailyLossAlert { scope, level, loss, limit });
+        }
+
+        DailyLossUpdate { rolled_over: std::mem::take(&mut self.rolled_over), alerts }
+    }
+
+    /// Starts a new trading day if `at` is past the current one's end, carrying into it the
+    /// unrealized P&L of the day's last check.
+    fn roll(&mut self, at: DateTime<Utc>) {
+        let day_start = self.day_start_at(at);
+        match self.day_start {
+            Some(current) if day_start <= current => {}
+            current => {
+                self.rolled_over |= current.is_some();
+                self.start_day(day_start, None, BTreeMap::new());
+                match (current, self.last_check.take()) {
+                    (Some(current), Some((checked_at, unrealized))) if checked_at >= current => self.carried = unrealized,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Restricting a bot to long or short positions.
+//!
+//! The backtester, the portfolio backtester and the live engine all pass each signal through
+//! `constrain_direction` before the risk manager sees it, so a long-only or short-only bot
+//! trades the same way in all three.
+
+use core_types::{OrderSide, Position, Signal, SignalIntent, TradeDirection};
+
+/// The signal a bot trading in `direction` acts on, given its open `position`, or None if
+/// the signal is dropped.
+///
+/// Closes pass through. A signal to enter on a side `direction` does not allow becomes a
+/// close of the position held on the other side, and is dropped if there is none. A signal
+/// without an intent is read the way the risk manager reads it: as a close if it is against
+/// the position, and as an entry on its order side otherwise.
+pub fn constrain_direction(direction: TradeDirection, signal: Signal, position: Option<&Position>) -> Option<Signal> {
+    let side = signal.order_request.side;
+    let target = match signal.intent {
+        Some(SignalIntent::Close) => return Some(signal),
+        None if position.is_some_and(|position| position.side != side) => return Some(signal),
+        Some(SignalIntent::OpenLong) => OrderSide::Buy,
+        Some(SignalIntent::OpenShort) => OrderSide::Sell,
+        Some(SignalIntent::Reverse) | None => side,
+    };
+    if direction.allows(target) {
+        return Some(signal);
+    }
+
+    match position.filter(|position| position.side != target) {
+        Some(position) => {
+            tracing::debug!(
+                "Closing the {:?} position on {} instead of entering {:?}, which the {:?} direction does not allow",
+                position.side, position.symbol, target, direction
+            );
+            let mut close = signal;
+            close.intent = Some(SignalIntent::Close);
+            close.order_request.side = position.side.opposite();
+            close.order_request.quantity = position.quantity;
+            close.order_request.reduce_only = true;
+            Some(close)
+        }
+        None => {
+            tracing::debug!(
+                "Dropping the {:?} entry on {} at {}, which the {:?} direction does not allow",
+                target, signal.order_request.symbol, signal.timestamp, direction
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,79 @@
+//! Scales the risk of new entries down as the account's drawdown deepens.
+//!
+//! The scaler follows the account's equity and reports the multiplier of the deepest
+//! `DrawdownTier` the drawdown from peak equity has reached. Tiers are entered as soon as
+//! their boundary is crossed, but only left once the drawdown has recovered
+//! `recovery_buffer_pct` past it, so equity hovering at a boundary does not flap between
+//! tiers. The live engine and the backtester feed it their own equity and size entries by
+//! its multiplier.
+
+use configuration::{DrawdownTier, DynamicLeverage};
+use rust_decimal::Decimal;
+
+/// A move from one tier to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TierChange {
+    /// The drawdown from peak equity that caused the move.
+    pub drawdown: Decimal,
+    /// The multiplier before the move.
+    pub from: Decimal,
+    /// The multiplier after the move.
+    pub to: Decimal,
+}
+
+impl TierChange {
+    /// True when the move scales risk down.
+    pub fn is_reduction(&self) -> bool {
+        self.to < self.from
+    }
+}
+
+/// Tracks the account's drawdown and the tier it puts new entries in.
+#[derive(Debug, Clone)]
+pub struct DrawdownScaler {
+    tiers: Vec<DrawdownTier>,
+    recovery_buffer_pct: Decimal,
+    peak_equity: Option<Decimal>,
+    /// The number of tiers entered; 0 is full risk.
+    level: usize,
+}
+
+impl DrawdownScaler {
+    pub fn new(config: &DynamicLeverage) -> Self {
+        let mut tiers = config.tiers.clone();
+        tiers.sort_by_key(|tier| tier.drawdown_pct);
+        Self { tiers, recovery_buffer_pct: config.recovery_buffer_pct, peak_equity: None, level: 0 }
+    }
+
+    /// The fraction of `risk_per_trade_pct` new entries take.
+    pub fn multiplier(&self) -> Decimal {
+        self.level.checked_sub(1).map_or(Decimal::ONE, |tier| self.tiers[tier].multiplier)
+    }
+
+    /// The highest equity seen so far.
+    pub fn peak_equity(&self) -> Option<Decimal> {
+        self.peak_equity
+    }
+
+    /// Records the account's latest equity. Returns the tier change it caused, if any.
+    pub fn update(&mut self, equity: Decimal) -> Option<TierChange> {
+        let peak = self.peak_equity.map_or(equity, |peak| peak.max(equity));
+        self.peak_equity = Some(peak);
+        if peak <= Decimal::ZERO {
+            return None;
+        }
+
+        let drawdown = (peak - equity) / peak;
+        let entered = self.tiers.iter().filter(|tier| drawdown >= tier.drawdown_pct).count();
+        // The tiers whose boundary the drawdown has not yet recovered past by the buffer.
+        let held = self.tiers.iter().filter(|tier| drawdown > tier.drawdown_pct - self.recovery_buffer_pct).count();
+        let level = if entered > self.level { entered } else { self.level.min(held) };
+        if level == self.level {
+            return None;
+        }
+
+        let from = self.multiplier();
+        self.level = level;
+        Some(TierChange { drawdown, from, to: self.multiplier() })
+    }
+}
@@ -15,6 +15,39 @@ pub enum RiskError {
     #[error("The provided entry price ({0}) is zero or negative.")]
     InvalidEntryPrice(Decimal),
 
+    #[error("Adding to the open position is not allowed: {0}")]
+    AddRejected(String),
+
+    #[error("There is no open {0} position to close.")]
+    NoPositionToClose(String),
+
+    #[error("Insufficient margin: order requires {required} but only {available} is available.")]
+    InsufficientMargin { required: Decimal, available: Decimal },
+
+    #[error("The order is over its size limits: {0}")]
+    OrderLimitExceeded(String),
+
+    #[error("Expected move of {expected_move} is under {cost_multiple}x the round-trip cost of {round_trip_cost}.")]
+    InsufficientExpectedMove { expected_move: Decimal, round_trip_cost: Decimal, cost_multiple: Decimal },
+
+    #[error("Risk is scaled to {multiplier}x of its full size; the current position of {quantity} is kept.")]
+    PositionKeptWhileScaled { multiplier: Decimal, quantity: Decimal },
+
     #[error("A calculation error occurred: {0}")]
     Calculation(String),
-}
\ No newline at end of file
+}
+
+impl RiskError {
+    /// True for signals declined in the normal course of trading, such as a refused scale-in,
+    /// a close with nothing to close, an entry too small to pay for its costs or a resize
+    /// skipped while risk is scaled down, rather than because something went wrong.
+    pub fn is_declined(&self) -> bool {
+        matches!(
+            self,
+            RiskError::AddRejected(_)
+                | RiskError::NoPositionToClose(_)
+                | RiskError::InsufficientExpectedMove { .. }
+                | RiskError::PositionKeptWhileScaled { .. }
+        )
+    }
+}
@@ -0,0 +1,69 @@
+//! The minimum expected-move filter: refusing entries that are unlikely to pay for themselves.
+//!
+//! A trade whose target is a smaller move than its fees, spread and slippage loses money even
+//! when it is right. Both the backtester and the live engine pass each approved plan through
+//! `ExpectedMoveFilter::check` before placing it, so the two refuse the same signals.
+
+use crate::error::RiskError;
+use crate::plan::OrderPlan;
+use configuration::{MinExpectedMove, RiskManagement, Simulation};
+use core_types::Kline;
+use rust_decimal::Decimal;
+
+/// Compares an entry's expected move with its round-trip cost, as set by
+/// `RiskManagement::min_expected_move`.
+#[derive(Debug, Clone)]
+pub struct ExpectedMoveFilter {
+    min_expected_move: Option<MinExpectedMove>,
+    stop_loss_pct: Decimal,
+    take_profit_pct: Option<Decimal>,
+    simulation: Simulation,
+}
+
+impl ExpectedMoveFilter {
+    pub fn new(params: &RiskManagement, simulation: &Simulation) -> Self {
+        Self { min_expected_move: params.min_expected_move, stop_loss_pct: params.stop_loss_pct, take_profit_pct: params.take_profit_pct, simulation: simulation.clone() }
+    }
+
+    /// True when `min_expected_move` is set.
+    pub fn is_enabled(&self) -> bool {
+        self.min_expected_move.is_some()
+    }
+
+    /// The move an entry is expected to capture, as a fraction of its price: the take-profit
+    /// distance when one is set, otherwise the stop distance times the assumed payoff ratio.
+    pub fn expected_move(&self) -> Decimal {
+        if let Some(take_profit_pct) = self.take_profit_pct {
+            return take_profit_pct;
+        }
+        let payoff_ratio = self.min_expected_move.map_or(Decimal::ONE, |min| min.payoff_ratio);
+        self.stop_loss_pct * payoff_ratio
+    }
+
+    /// What opening and closing a position on `symbol` at `kline` costs, as a fraction of its
+    /// price: a taker fee each way, the spread, and the slippage of two market orders on the
+    /// bar's range, as `SimulatedExecutor` charges them.
+    pub fn round_trip_cost(&self, symbol: &str, kline: &Kline) -> Decimal {
+        let fee = self.simulation.taker_fee_pct * (Decimal::ONE - self.simulation.fee_discount_pct);
+        let slippage = if kline.close > Decimal::ZERO { self.simulation.slippage_pct * (kline.high - kline.low) / kline.close } else { Decimal::ZERO };
+        Decimal::TWO * fee + self.simulation.spread_pct(symbol) + Decimal::TWO * slippage
+    }
+
+    /// Refuses `plan` if it enters a position whose expected move is less than
+    /// `cost_multiple` times its round-trip cost at `kline`. Plans that only close pass.
+    pub fn check(&self, plan: &OrderPlan, kline: &Kline) -> Result<(), RiskError> {
+        let Some(min_expected_move) = self.min_expected_move else { return Ok(()) };
+        let Some(entry) = plan.legs.iter().find(|leg| !leg.reduce_only) else { return Ok(()) };
+
+        let expected_move = self.expected_move();
+        let round_trip_cost = self.round_trip_cost(&entry.symbol, kline);
+        if expected_move < min_expected_move.cost_multiple * round_trip_cost {
+            return Err(RiskError::InsufficientExpectedMove {
+                expected_move: expected_move.normalize(),
+                round_trip_cost: round_trip_cost.normalize(),
+                cost_multiple: min_expected_move.cost_multiple,
+            });
+        }
+        Ok(())
+    }
+}
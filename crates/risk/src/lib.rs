@@ -12,19 +12,44 @@
 //! ## Public API
 //! - `RiskManager`: The core trait that defines the interface for all risk modules.
 //! - `SimpleRiskManager`: The concrete implementation of our fixed-fractional sizing logic.
+//! - `margin_check`: A pre-trade check that downsizes orders to fit leveraged initial margin.
+//! - `free_margin`: The wallet balance left for new margin once open positions' margin is held back.
+//! - `apply_order_limits`: Caps entries at the configured per-symbol notional and quantity.
+//! - `constrain_direction`: Keeps a long-only or short-only bot from entering on the other side.
+//! - `TimeExit`: Decides when a position has been held too long and must be closed.
+//! - `ExpectedMoveFilter`: Refuses entries whose expected move does not cover their costs.
+//! - `DrawdownScaler`: Scales the risk of new entries down as the account's drawdown deepens.
+//! - `DailyLossTracker`: Checks each trading day's loss against the daily loss limits.
+//! - `OrderPlan`: The orders carrying out a signal, placed in order under a `LegPolicy`.
 //! - `RiskError`: The specific error types that can be returned from this crate.
 
-use core_types::{OrderRequest, Signal};
+use core_types::Signal;
 use events::PortfolioState;
 use rust_decimal::Decimal;
 
 // Declare the modules that constitute this crate.
+pub mod daily_loss;
+pub mod direction;
+pub mod dynamic_leverage;
 pub mod error;
+pub mod expected_move;
+pub mod limits;
+pub mod margin;
+pub mod plan;
 pub mod simple_manager;
+pub mod time_exit;
 
 // Re-export the public components to provide a clean API.
+pub use daily_loss::{trading_day_start, DailyLossAlert, DailyLossTracker, DailyLossUpdate, LossLevel, LossScope, DAILY_LOSS_WARNING_FRACTION};
+pub use direction::constrain_direction;
+pub use dynamic_leverage::{DrawdownScaler, TierChange};
 pub use error::RiskError;
+pub use expected_move::ExpectedMoveFilter;
+pub use limits::apply_order_limits;
+pub use margin::{free_margin, margin_check};
+pub use plan::{LegPolicy, OrderPlan};
 pub use simple_manager::SimpleRiskManager;
+pub use time_exit::TimeExit;
 
 /// The core trait that all risk management modules must implement.
 ///
@@ -32,7 +57,8 @@ pub use simple_manager::SimpleRiskManager;
 /// the portfolio's state and risk parameters. The `Send + Sync` bounds are
 /// required to allow the risk manager to be used across multiple threads.
 pub trait RiskManager: Send + Sync {
-    /// Evaluates a raw strategy signal and returns a new, risk-managed OrderRequest.
+    /// Evaluates a raw strategy signal and returns the plan of risk-managed orders that
+    /// carry out its intent.
     ///
     /// # Arguments
     /// * `signal`: The raw `Signal` generated by a strategy.
@@ -41,12 +67,31 @@ pub trait RiskManager: Send + Sync {
     ///
     /// # Returns
     /// A `Result` containing either:
-    /// - `Ok(OrderRequest)`: A new order request with a precisely calculated quantity.
+    /// - `Ok(OrderPlan)`: The orders to place, in order, with precisely calculated quantities.
+    ///   A reversal may be a close followed by an entry; anything else is one order.
     /// - `Err(RiskError)`: An error indicating why the trade cannot be sized or placed.
     fn evaluate_signal(
         &self,
         signal: &Signal,
         portfolio_state: &PortfolioState,
         entry_price: Decimal,
-    ) -> Result<OrderRequest, RiskError>;
+    ) -> Result<OrderPlan, RiskError>;
+
+    /// Like `evaluate_signal`, but new entries take only `risk_multiplier` of the risk they
+    /// otherwise would, e.g. while the account is in a drawdown. The default scales the
+    /// signal's confidence, which entries are sized in proportion to.
+    fn evaluate_scaled_signal(
+        &self,
+        signal: &Signal,
+        portfolio_state: &PortfolioState,
+        entry_price: Decimal,
+        risk_multiplier: Decimal,
+    ) -> Result<OrderPlan, RiskError> {
+        if risk_multiplier == Decimal::ONE {
+            return self.evaluate_signal(signal, portfolio_state, entry_price);
+        }
+        let mut scaled = signal.clone();
+        scaled.confidence *= risk_multiplier;
+        self.evaluate_signal(&scaled, portfolio_state, entry_price)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+//! Absolute per-order caps, applied after an entry has been sized.
+//!
+//! These guard against configurations whose sizing math produces absurd orders, such as a
+//! large `risk_per_trade_pct` with a tiny stop: whatever the sizing says, an entry never
+//! exceeds its symbol's `max_notional` or `max_quantity`.
+
+use crate::error::RiskError;
+use crate::simple_manager::quantity_precision;
+use configuration::{LimitAction, OrderLimits};
+use core_types::OrderRequest;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Enforces `limits` on `order` at `price`.
+///
+/// An entry over its caps is shrunk to the largest quantity within them that the symbol's
+/// precision allows, or refused with `LimitAction::Reject`. Reduce-only orders pass
+/// untouched: capping them would leave part of a position open that should be closed.
+pub fn apply_order_limits(
+    order: &mut OrderRequest,
+    limits: &OrderLimits,
+    action: LimitAction,
+    price: Decimal,
+) -> Result<(), RiskError> {
+    if order.reduce_only || limits.is_unlimited() {
+        return Ok(());
+    }
+
+    let notional_cap = limits.max_notional.filter(|_| price > Decimal::ZERO).map(|max| max / price);
+    let Some(cap) = [notional_cap, limits.max_quantity].into_iter().flatten().min() else {
+        return Ok(());
+    };
+    if order.quantity <= cap {
+        return Ok(());
+    }
+
+    if action == LimitAction::Reject {
+        return Err(RiskError::OrderLimitExceeded(format!(
+            "{:?} order for {} {} at {} exceeds {}",
+            order.side, order.quantity, order.symbol, price, describe(limits)
+        )));
+    }
+
+    // Rounding down keeps the clamped order within the caps.
+    let clamped = cap.round_dp_with_strategy(quantity_precision(&order.symbol), RoundingStrategy::ToZero);
+    if clamped <= Decimal::ZERO {
+        return Err(RiskError::OrderLimitExceeded(format!(
+            "{} is below the smallest {} order at {}",
+            describe(limits), order.symbol, price
+        )));
+    }
+    tracing::warn!(
+        "Clamped {:?} order for {} from {} to {} at {} to respect {}.",
+        order.side, order.symbol, order.quantity, clamped, price, describe(limits)
+    );
+    order.quantity = clamped;
+    Ok(())
+}
+
+fn describe(limits: &OrderLimits) -> String {
+    let caps: Vec<String> = [
+        limits.max_notional.map(|max| format!("max_notional = {}", max)),
+        limits.max_quantity.map(|max| format!("max_quantity = {}", max)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    caps.join(", ")
+}
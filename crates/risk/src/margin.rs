@@ -0,0 +1,78 @@
+use crate::error::RiskError;
+use core_types::{OrderRequest, Position};
+use rust_decimal::{Decimal, RoundingStrategy};
+use rust_decimal_macros::dec;
+
+/// Verifies that an order's initial margin requirement fits within the available balance.
+///
+/// The required initial margin is `notional / leverage`. The usable balance is the
+/// available balance reduced by `buffer_pct`. If the order does not fit, its quantity is
+/// scaled down to the largest size that does; if nothing fits, the order is rejected.
+///
+/// # Arguments
+/// * `order`: The risk-sized order about to be sent to the executor.
+/// * `entry_price`: The expected fill price, used to compute the order's notional value.
+/// * `available_balance`: The free margin available to post, see `free_margin`.
+/// * `leverage`: The leverage configured for the symbol on the exchange.
+/// * `buffer_pct`: The fraction of the available balance to hold back (e.g., 0.05).
+pub fn margin_check(
+    order: OrderRequest,
+    entry_price: Decimal,
+    available_balance: Decimal,
+    leverage: u8,
+    buffer_pct: Decimal,
+) -> Result<OrderRequest, RiskError> {
+    if entry_price <= dec!(0) {
+        return Err(RiskError::InvalidEntryPrice(entry_price));
+    }
+    if leverage == 0 {
+        return Err(RiskError::InvalidParameters("leverage must be at least 1".to_string()));
+    }
+
+    let leverage = Decimal::from(leverage);
+    let usable_margin = available_balance * (dec!(1) - buffer_pct);
+    let required_margin = order.quantity * entry_price / leverage;
+    if required_margin <= usable_margin {
+        return Ok(order);
+    }
+
+    // Downsize to the largest quantity whose margin fits. Truncate to the same 2dp
+    // exchange precision used for sizing, never rounding up past the usable margin.
+    let max_notional = usable_margin.max(dec!(0)) * leverage;
+    let max_quantity = (max_notional / entry_price).round_dp_with_strategy(2, RoundingStrategy::ToZero);
+    if max_quantity <= dec!(0) {
+        return Err(RiskError::InsufficientMargin { required: required_margin, available: usable_margin });
+    }
+
+    tracing::warn!(
+        "Margin check - {} requires {} margin at {}x but only {} is usable. Downsizing quantity from {} to {}.",
+        order.symbol, required_margin, leverage, usable_margin, order.quantity, max_quantity
+    );
+
+    let mut downsized = order;
+    downsized.quantity = max_quantity;
+    Ok(downsized)
+}
+
+/// The collateral left to post as margin for new orders: `wallet_balance` less the initial
+/// margin already posted to `positions`, each `quantity * entry_price / leverage`.
+///
+/// `wallet_balance` must not have the positions' margin or cost taken out already, as an
+/// exchange's available balance does; see `Portfolio::wallet_balance`. `leverage` gives the
+/// leverage each position's symbol trades at. A position in a symbol without one is counted
+/// at 1x, so its full notional is held back. The result is negative when the open
+/// positions' margin exceeds `wallet_balance`.
+pub fn free_margin<'a>(
+    wallet_balance: Decimal,
+    positions: impl IntoIterator<Item = &'a Position>,
+    leverage: impl Fn(&str) -> Option<u8>,
+) -> Decimal {
+    let posted: Decimal = positions
+        .into_iter()
+        .map(|position| {
+            let leverage = leverage(&position.symbol).filter(|leverage| *leverage > 0).unwrap_or(1);
+            position.quantity * position.entry_price / Decimal::from(leverage)
+        })
+        .sum();
+    wallet_balance - posted
+}
@@ -0,0 +1,46 @@
+//! Order plans: the orders that carry out one signal, placed one after another.
+//!
+//! A reversal is a close followed by an entry. Both the backtester and the live engine place
+//! a plan's legs in order, updating the portfolio after each fill, so the entry is only placed
+//! once the close has filled. When a leg fails, the plan's policy decides whether the rest are
+//! still placed. A failure after an earlier leg has filled leaves the position other than the
+//! strategy intended, so the engine halts the bot and raises a critical alert.
+
+use core_types::OrderRequest;
+use serde::Serialize;
+
+/// What happens to the remaining legs of a plan when one fails.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum LegPolicy {
+    /// The remaining legs are dropped. For legs that only make sense together, such as the
+    /// close and entry of a reversal.
+    #[default]
+    AbortOnFailure,
+    /// The remaining legs are still placed. For legs that stand on their own.
+    ContinueOnFailure,
+}
+
+/// The orders that carry out a signal, in the order they are placed. The default plan places
+/// no orders.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct OrderPlan {
+    pub legs: Vec<OrderRequest>,
+    pub policy: LegPolicy,
+}
+
+impl OrderPlan {
+    /// A plan of a single order.
+    pub fn single(order: OrderRequest) -> Self {
+        Self { legs: vec![order], policy: LegPolicy::AbortOnFailure }
+    }
+
+    /// A plan of `legs` placed in order, abandoned at the first that fails.
+    pub fn sequence(legs: Vec<OrderRequest>) -> Self {
+        Self { legs, policy: LegPolicy::AbortOnFailure }
+    }
+
+    /// Whether the legs after a failed one are still placed.
+    pub fn continues_after_failure(&self) -> bool {
+        self.policy == LegPolicy::ContinueOnFailure
+    }
+}
@@ -1,32 +1,40 @@
 use crate::error::RiskError;
+use crate::limits::apply_order_limits;
+use crate::plan::OrderPlan;
 use crate::RiskManager;
-use configuration::RiskManagement;
-use core_types::{OrderRequest, OrderSide, Signal};
+use configuration::{ReverseMode, RiskManagement};
+use core_types::{OrderRequest, OrderSide, Position, Signal, SignalIntent};
 use core_types::enums::PositionSide;
 use events::PortfolioState;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use std::sync::Once;
 use tracing;
 
-/// Rounds quantity to the appropriate precision for the given symbol.
-/// This prevents "Precision is over the maximum defined for this asset" errors.
-fn round_quantity_to_precision(symbol: &str, quantity: Decimal) -> Decimal {
+/// The number of decimal places orders on `symbol` are rounded to.
+pub(crate) fn quantity_precision(symbol: &str) -> u32 {
     // Conservative precision mapping for common symbols
     // These are based on Binance futures requirements
-    let precision = match symbol {
+    match symbol {
         "BTCUSDT" => 2,  // BTC precision is 0.01 (more conservative)
         "ETHUSDT" => 2,  // ETH precision is 0.01 (more conservative)
         _ => 2,          // Default to 2 decimal places
-    };
-    
+    }
+}
+
+/// Rounds quantity to the appropriate precision for the given symbol.
+/// This prevents "Precision is over the maximum defined for this asset" errors.
+fn round_quantity_to_precision(symbol: &str, quantity: Decimal) -> Decimal {
+    let precision = quantity_precision(symbol);
+
     // Round to the specified precision
-    let scale = Decimal::from(10_i64.pow(precision as u32));
+    let scale = Decimal::from(10_i64.pow(precision));
     let rounded = (quantity * scale).round() / scale;
     
     // Ensure we don't return zero if the original quantity was positive
     if quantity > Decimal::ZERO && rounded == Decimal::ZERO {
         // Return the minimum quantity for this precision
-        Decimal::from(10_i64.pow((precision - 1) as u32)) / scale
+        Decimal::from(10_i64.pow(precision - 1)) / scale
     } else {
         rounded
     }
@@ -39,6 +47,8 @@ fn round_quantity_to_precision(symbol: &str, quantity: Decimal) -> Decimal {
 #[derive(Debug, Clone)]
 pub struct SimpleRiskManager {
     params: RiskManagement,
+    /// Whether orders are for an account in hedge mode, where each carries its position side.
+    hedge_mode: bool,
 }
 
 impl SimpleRiskManager {
@@ -55,45 +65,88 @@ impl SimpleRiskManager {
                 "stop_loss_pct must be greater than 0".to_string(),
             ));
         }
-        Ok(Self { params })
+        if params.add_spacing_pct < dec!(0) {
+            return Err(RiskError::InvalidParameters(
+                "add_spacing_pct must not be negative".to_string(),
+            ));
+        }
+        if let Some(trigger) = params.break_even_trigger_pct
+            && (params.break_even_buffer_pct < dec!(0) || params.break_even_buffer_pct >= trigger)
+        {
+            return Err(RiskError::InvalidParameters(
+                "break_even_buffer_pct must be between 0 and break_even_trigger_pct".to_string(),
+            ));
+        }
+        if params.take_profit_pct.is_some_and(|take_profit| take_profit <= dec!(0)) {
+            return Err(RiskError::InvalidParameters(
+                "take_profit_pct must be greater than 0".to_string(),
+            ));
+        }
+        if params.max_holding_bars == Some(0) {
+            return Err(RiskError::InvalidParameters(
+                "max_holding_bars must be greater than 0".to_string(),
+            ));
+        }
+        Ok(Self { params, hedge_mode: false })
+    }
+
+    /// Sizes orders for an account in hedge mode: each order carries the position side it
+    /// trades, and closes trade against it. Without it, orders are for one-way mode, where
+    /// they carry no position side and closes are reduce-only.
+    pub fn with_hedge_mode(mut self, hedge_mode: bool) -> Self {
+        self.hedge_mode = hedge_mode;
+        self
+    }
+
+    /// Enforces the pyramiding rules for adding to an open position: fewer than `max_adds`
+    /// prior adds, and a favorable move of at least `add_spacing_pct` since the last entry.
+    fn check_add_allowed(&self, position: &Position, max_adds: u32, price: Decimal) -> Result<(), RiskError> {
+        if position.adds >= max_adds {
+            return Err(RiskError::AddRejected(format!(
+                "{} position already has {} of {} allowed adds",
+                position.symbol, position.adds, max_adds
+            )));
+        }
+
+        let last_entry = if position.last_entry_price > dec!(0) { position.last_entry_price } else { position.entry_price };
+        if last_entry <= dec!(0) {
+            return Err(RiskError::InvalidEntryPrice(last_entry));
+        }
+        let favorable_move = match position.side {
+            OrderSide::Buy => (price - last_entry) / last_entry,
+            OrderSide::Sell => (last_entry - price) / last_entry,
+        };
+        if favorable_move < self.params.add_spacing_pct {
+            return Err(RiskError::AddRejected(format!(
+                "{} has moved {} in favor since the last entry at {}, below the required spacing of {}",
+                position.symbol, favorable_move.round_dp(4), last_entry, self.params.add_spacing_pct
+            )));
+        }
+        Ok(())
     }
 }
 
-impl RiskManager for SimpleRiskManager {
-    /// Performs the stop-loss-driven, fixed-fractional position sizing calculation.
-    fn evaluate_signal(
+impl SimpleRiskManager {
+    /// Sizes an entry on `side`, or an add to (or resize of) `position` on that side, taking
+    /// `risk_multiplier` of the configured risk.
+    fn open_order(
         &self,
         signal: &Signal,
+        side: OrderSide,
+        position: Option<&Position>,
         portfolio_state: &PortfolioState,
         entry_price: Decimal,
+        risk_multiplier: Decimal,
     ) -> Result<OrderRequest, RiskError> {
-        // --- 1. Validation ---
-        if entry_price <= dec!(0) {
-            return Err(RiskError::InvalidEntryPrice(entry_price));
-        }
         if portfolio_state.total_value <= dec!(0) {
             return Err(RiskError::InsufficientEquity(portfolio_state.total_value));
         }
-
-        // --- 2. Check for existing position ---
-        let current_position = portfolio_state.positions.iter()
-            .find(|p| p.symbol == signal.order_request.symbol);
-
-        // If we have a position in the opposite direction, close it first
-        if let Some(position) = current_position {
-            if position.side != signal.order_request.side {
-                // Create a market order to close the entire position
-                let mut close_order = signal.order_request.clone();
-                close_order.quantity = position.quantity;
-                close_order.side = position.side.opposite();
-                close_order.position_side = Some(PositionSide::from_order_side(close_order.side));
-                return Ok(close_order);
-            }
-            // If we have a position in the same direction, we'll handle it below
+        if let (Some(position), Some(max_adds)) = (position, self.params.max_position_adds) {
+            self.check_add_allowed(position, max_adds, entry_price)?;
         }
 
-        // --- 3. Calculate Stop-Loss Price and Distance ---
-        let stop_loss_price = match signal.order_request.side {
+        // --- 1. Calculate Stop-Loss Price and Distance ---
+        let stop_loss_price = match side {
             OrderSide::Buy => entry_price * (dec!(1) - self.params.stop_loss_pct),
             OrderSide::Sell => entry_price * (dec!(1) + self.params.stop_loss_pct),
         };
@@ -105,9 +158,9 @@ impl RiskManager for SimpleRiskManager {
             ));
         }
 
-        // --- 4. Calculate Risk Capital and Final Quantity ---
+        // --- 2. Calculate Risk Capital and Final Quantity ---
         // Determine the total capital to risk on this specific trade.
-        let risk_capital = portfolio_state.total_value * self.params.risk_per_trade_pct;
+        let risk_capital = portfolio_state.total_value * self.params.risk_per_trade_pct * risk_multiplier;
 
         // Scale the risk down by the strategy's confidence in the signal.
         // A confidence of 0.5 means we risk half the standard amount.
@@ -147,23 +200,26 @@ impl RiskManager for SimpleRiskManager {
         tracing::info!("Risk params - Risk per trade: {}, Stop loss: {}, Confidence: {}, Total value: {}, Cash: {}",
             self.params.risk_per_trade_pct, self.params.stop_loss_pct, signal.confidence, portfolio_state.total_value, portfolio_state.cash);
         
-        // If we already have a position, calculate how much to add or reduce
-        let quantity = if let Some(position) = current_position {
+        // If we already have a position, calculate how much to add or reduce.
+        // With pyramiding enabled, each add is instead a full risk-sized unit.
+        let quantity = if let Some(position) = position.filter(|_| self.params.max_position_adds.is_none()) {
             // For the same direction, we can add to the position
             if target_quantity > position.quantity {
                 target_quantity - position.quantity
             } else if target_quantity < position.quantity {
+                // Scaled-down risk only sizes new exposure: the position is reduced to what
+                // a full-risk entry would be, never below it.
+                let full_quantity = if risk_multiplier < dec!(1) { (target_quantity / risk_multiplier).round_dp(6) } else { target_quantity };
+                if full_quantity >= position.quantity {
+                    return Err(RiskError::PositionKeptWhileScaled { multiplier: risk_multiplier, quantity: position.quantity });
+                }
                 // If target is smaller, we need to reduce the position
                 // Calculate how much to reduce by
-                let reduction_amount = position.quantity - target_quantity;
-                tracing::info!("Target quantity {} is smaller than current position {}. Reducing position by {}.", target_quantity, position.quantity, reduction_amount);
+                let reduction_amount = position.quantity - full_quantity;
+                tracing::info!("Target quantity {} is smaller than current position {}. Reducing position by {}.", full_quantity, position.quantity, reduction_amount);
                 
                 // Create a closing order for the reduction amount
-                let mut close_order = signal.order_request.clone();
-                close_order.quantity = reduction_amount;
-                close_order.side = position.side.opposite(); // Opposite side to close
-                close_order.position_side = Some(PositionSide::from_order_side(close_order.side));
-                return Ok(close_order);
+                return Ok(close_order(signal, position, self.hedge_mode, reduction_amount));
             } else {
                 // If target equals current, no order needed
                 tracing::info!("Target quantity {} equals current position {}. No order needed.", target_quantity, position.quantity);
@@ -175,7 +231,7 @@ impl RiskManager for SimpleRiskManager {
             target_quantity
         };
 
-        // --- 5. Round Quantity to Exchange Precision ---
+        // --- 3. Round Quantity to Exchange Precision ---
         // Round the quantity to the appropriate precision for the exchange
         let rounded_quantity = round_quantity_to_precision(&signal.order_request.symbol, quantity);
         tracing::info!("Precision rounding - Symbol: {}, Original: {}, Rounded: {}", 
@@ -188,18 +244,139 @@ impl RiskManager for SimpleRiskManager {
             ));
         }
         
-        // --- 6. Construct Final Order Request ---
+        // --- 4. Construct Final Order Request ---
         // Create a new order request, using the original as a template but
         // overriding the quantity with our calculated, risk-managed value.
         let mut final_order = signal.order_request.clone();
+        final_order.side = side;
         final_order.quantity = rounded_quantity;
+        final_order.reduce_only = false;
         
-        // Set the position side based on the order side
-        final_order.position_side = Some(PositionSide::from_order_side(signal.order_request.side));
+        // In hedge mode an entry opens the position side of its order side.
+        final_order.position_side = self.hedge_mode.then(|| PositionSide::from_order_side(side));
+
+        // --- 5. Enforce the Absolute Order Limits ---
+        let limits = self.params.limits_for(&final_order.symbol);
+        apply_order_limits(&mut final_order, &limits, self.params.limit_action, entry_price)?;
 
         tracing::info!("Final order - Symbol: {}, Side: {:?}, Position Side: {:?}, Quantity: {}, Price: {:?}",
             final_order.symbol, final_order.side, final_order.position_side, final_order.quantity, final_order.price);
 
         Ok(final_order)
     }
-}
\ No newline at end of file
+
+    /// Closes `position` and enters the other side, as one netted order or as a close
+    /// followed by an entry sized as if the position were already flat. The entry is
+    /// abandoned if the close fails.
+    fn reverse_orders(
+        &self,
+        signal: &Signal,
+        position: &Position,
+        portfolio_state: &PortfolioState,
+        entry_price: Decimal,
+        risk_multiplier: Decimal,
+    ) -> Result<OrderPlan, RiskError> {
+        let close = close_order(signal, position, self.hedge_mode, position.quantity);
+
+        // The entry is sized against the cash the close is expected to leave.
+        let close_value = position.quantity * entry_price;
+        let mut flat_state = portfolio_state.clone();
+        flat_state.cash += match position.side {
+            OrderSide::Buy => close_value,
+            OrderSide::Sell => -close_value,
+        };
+        flat_state.positions.retain(|p| p.symbol != position.symbol);
+        let open = self.open_order(signal, position.side.opposite(), None, &flat_state, entry_price, risk_multiplier)?;
+
+        Ok(match self.params.reverse_mode {
+            ReverseMode::Separate => OrderPlan::sequence(vec![close, open]),
+            ReverseMode::Netted => {
+                let mut netted = open;
+                netted.quantity += close.quantity;
+                OrderPlan::single(netted)
+            }
+        })
+    }
+}
+
+/// A reduce-only order that closes `quantity` of `position`.
+fn close_order(signal: &Signal, position: &Position, hedge_mode: bool, quantity: Decimal) -> OrderRequest {
+    let mut close_order = signal.order_request.clone();
+    close_order.quantity = quantity;
+    close_order.side = position.side.opposite();
+    // In hedge mode a close trades against the position's own side.
+    close_order.position_side = hedge_mode.then(|| PositionSide::from_order_side(position.side));
+    close_order.reduce_only = true;
+    close_order
+}
+
+/// The signal's intent. Signals from strategies that do not set one yet get the intent the
+/// risk manager used to infer: close an opposite position, otherwise open on the order side.
+fn resolve_intent(signal: &Signal, position: Option<&Position>) -> SignalIntent {
+    if let Some(intent) = signal.intent {
+        return intent;
+    }
+    static INFERRED_INTENT: Once = Once::new();
+    INFERRED_INTENT.call_once(|| {
+        tracing::warn!("A signal without an intent was received; inferring it from the order side. This is deprecated: strategies should set `Signal::intent`.");
+    });
+    match (position, signal.order_request.side) {
+        (Some(position), side) if position.side != side => SignalIntent::Close,
+        (_, OrderSide::Buy) => SignalIntent::OpenLong,
+        (_, OrderSide::Sell) => SignalIntent::OpenShort,
+    }
+}
+
+impl RiskManager for SimpleRiskManager {
+    /// Turns the signal's intent into orders. Entries are sized by the stop-loss-driven,
+    /// fixed-fractional calculation, then capped by the symbol's order limits; closes are
+    /// never capped, so they always flatten the whole position.
+    ///
+    /// A one-way account cannot hold both sides, so an Open against an opposite position
+    /// closes it first and then enters, like a Reverse. A Reverse with no opposite position
+    /// to close is an Open on the order's side.
+    fn evaluate_signal(
+        &self,
+        signal: &Signal,
+        portfolio_state: &PortfolioState,
+        entry_price: Decimal,
+    ) -> Result<OrderPlan, RiskError> {
+        self.evaluate_scaled_signal(signal, portfolio_state, entry_price, dec!(1))
+    }
+
+    /// Sizes entries from `risk_multiplier` times `risk_per_trade_pct`. A same-side signal
+    /// never shrinks an open position below what a full-risk entry would be.
+    fn evaluate_scaled_signal(
+        &self,
+        signal: &Signal,
+        portfolio_state: &PortfolioState,
+        entry_price: Decimal,
+        risk_multiplier: Decimal,
+    ) -> Result<OrderPlan, RiskError> {
+        if entry_price <= dec!(0) {
+            return Err(RiskError::InvalidEntryPrice(entry_price));
+        }
+
+        let symbol = &signal.order_request.symbol;
+        let position = portfolio_state.positions.iter().find(|p| &p.symbol == symbol);
+        let side = match resolve_intent(signal, position) {
+            SignalIntent::Close => {
+                let position = position.ok_or_else(|| RiskError::NoPositionToClose(symbol.clone()))?;
+                return Ok(OrderPlan::single(close_order(signal, position, self.hedge_mode, position.quantity)));
+            }
+            SignalIntent::Reverse => {
+                if let Some(position) = position.filter(|p| p.side != signal.order_request.side) {
+                    return self.reverse_orders(signal, position, portfolio_state, entry_price, risk_multiplier);
+                }
+                signal.order_request.side
+            }
+            SignalIntent::OpenLong => OrderSide::Buy,
+            SignalIntent::OpenShort => OrderSide::Sell,
+        };
+
+        match position {
+            Some(position) if position.side != side => self.reverse_orders(signal, position, portfolio_state, entry_price, risk_multiplier),
+            position => Ok(OrderPlan::single(self.open_order(signal, side, position, portfolio_state, entry_price, risk_multiplier)?)),
+        }
+    }
+}
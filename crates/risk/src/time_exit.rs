@@ -0,0 +1,40 @@
+//! Time-based exits: closing positions that have been held too long, or that are still open
+//! at the end of the trading session.
+//!
+//! Both the backtester and the live engine count the bars each position has been held and
+//! ask `TimeExit` whether it is due, so the two close positions on the same bar.
+
+use configuration::RiskManagement;
+use core_types::Kline;
+
+/// The length of a session, which runs from one UTC midnight to the next.
+const SESSION_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+/// The time-based exit rules from `RiskManagement`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TimeExit {
+    pub max_holding_bars: Option<u32>,
+    pub exit_at_session_end: bool,
+}
+
+impl TimeExit {
+    pub fn new(params: &RiskManagement) -> Self {
+        Self { max_holding_bars: params.max_holding_bars, exit_at_session_end: params.exit_at_session_end }
+    }
+
+    /// True when either rule is set.
+    pub fn is_enabled(&self) -> bool {
+        self.max_holding_bars.is_some() || self.exit_at_session_end
+    }
+
+    /// Whether a position that has been held for `bars_held` bars as of `kline` (not
+    /// counting the bar it was opened on) is closed at `kline`'s close.
+    pub fn is_due(&self, bars_held: u32, kline: &Kline) -> bool {
+        self.max_holding_bars.is_some_and(|max| bars_held >= max) || (self.exit_at_session_end && closes_session(kline))
+    }
+}
+
+/// Whether `kline` is the last bar of its UTC day: the next bar opens at midnight.
+pub fn closes_session(kline: &Kline) -> bool {
+    (kline.close_time.timestamp_millis() + 1).rem_euclid(SESSION_MILLIS) == 0
+}
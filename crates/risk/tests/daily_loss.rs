@@ -0,0 +1,174 @@
+//! Checks how the daily loss tracker counts a trading day's loss around its rollover and
+//! when it alerts on each limit.
+
+use chrono::{DateTime, TimeZone, Utc};
+use configuration::{DailyLossLimit, DailyLossLimits};
+use core_types::{OrderSide, Position};
+use events::PortfolioState;
+use risk::{trading_day_start, DailyLossAlert, DailyLossTracker, LossLevel, LossScope};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, day, hour, minute, 0).unwrap()
+}
+
+/// Days start at 08:00 UTC. The account may lose 500 a day, SOLUSDT 200.
+fn tracker() -> DailyLossTracker {
+    DailyLossTracker::new(DailyLossLimits {
+        rollover_hour_utc: 8,
+        max_daily_loss_abs: Some(dec!(500)),
+        max_daily_loss_pct: None,
+        symbols: [("SOLUSDT".to_string(), DailyLossLimit { max_daily_loss_abs: Some(dec!(200)), max_daily_loss_pct: None })].into(),
+    })
+}
+
+/// A 10,000 portfolio at `timestamp`, with a SOLUSDT long carrying `unrealized` if any.
+fn state(timestamp: DateTime<Utc>, unrealized: Option<Decimal>) -> PortfolioState {
+    let positions: Vec<Position> = unrealized
+        .map(|unrealized_pnl| Position {
+            position_id: Uuid::new_v4(),
+            symbol: "SOLUSDT".to_string(),
+            side: OrderSide::Buy,
+            quantity: dec!(10),
+            entry_price: dec!(150),
+            unrealized_pnl,
+            last_updated: timestamp,
+            adds: 0,
+            last_entry_price: dec!(150),
+            estimated_liquidation_price: None,
+        })
+        .into_iter()
+        .collect();
+    PortfolioState {
+        timestamp,
+        quote_asset: "USDT".to_string(),
+        cash: dec!(10000),
+        balances: Default::default(),
+        total_value: dec!(10000) + unrealized.unwrap_or_default(),
+        positions,
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    }
+}
+
+fn levels(alerts: &[DailyLossAlert]) -> Vec<(LossScope, LossLevel)> {
+    alerts.iter().map(|alert| (alert.scope.clone(), alert.level)).collect()
+}
+
+fn sol() -> LossScope {
+    LossScope::Symbol("SOLUSDT".to_string())
+}
+
+#[test]
+fn trading_days_start_at_the_rollover_hour() {
+    assert_eq!(trading_day_start(at(2, 7, 59), 8), at(1, 8, 0));
+    assert_eq!(trading_day_start(at(2, 8, 0), 8), at(2, 8, 0));
+    assert_eq!(trading_day_start(at(2, 23, 30), 0), at(2, 0, 0));
+}
+
+#[test]
+fn realized_and_unrealized_losses_warn_then_breach_once_each() {
+    let mut tracker = tracker();
+
+    tracker.record_fill("SOLUSDT", dec!(-120), at(2, 9, 0));
+    assert!(tracker.update(&state(at(2, 9, 1), None)).alerts.is_empty());
+
+    // 120 realized and 40 unrealized are 80% of SOLUSDT's limit.
+    let update = tracker.update(&state(at(2, 9, 5), Some(dec!(-40))));
+    assert_eq!(levels(&update.alerts), vec![(sol(), LossLevel::Warning)]);
+    assert_eq!((update.alerts[0].loss, update.alerts[0].limit), (dec!(160), dec!(200)));
+    assert!(tracker.update(&state(at(2, 9, 6), Some(dec!(-50)))).alerts.is_empty());
+
+    let update = tracker.update(&state(at(2, 9, 10), Some(dec!(-80))));
+    assert_eq!(levels(&update.alerts), vec![(sol(), LossLevel::Breach)]);
+
+    // Other symbols count towards the account's limit only.
+    tracker.record_fill("BTCUSDT", dec!(-250), at(2, 10, 0));
+    let update = tracker.update(&state(at(2, 10, 1), Some(dec!(-80))));
+    assert_eq!(levels(&update.alerts), vec![(LossScope::Account, LossLevel::Warning)]);
+    tracker.record_fill("BTCUSDT", dec!(-60), at(2, 10, 30));
+    let update = tracker.update(&state(at(2, 10, 31), Some(dec!(-80))));
+    assert_eq!(levels(&update.alerts), vec![(LossScope::Account, LossLevel::Breach)]);
+    assert!(!update.rolled_over);
+}
+
+#[test]
+fn the_rollover_starts_a_new_day_with_fresh_alerts() {
+    let mut tracker = tracker();
+
+    // Just before the rollover: a breach.
+    tracker.record_fill("SOLUSDT", dec!(-210), at(2, 7, 50));
+    let update = tracker.update(&state(at(2, 7, 55), None));
+    assert_eq!(levels(&update.alerts), vec![(sol(), LossLevel::Breach)]);
+    assert_eq!(tracker.next_rollover(), Some(at(2, 8, 0)));
+
+    // The first fill of the new day starts it; the previous day's losses no longer count.
+    tracker.record_fill("SOLUSDT", dec!(-100), at(2, 8, 0));
+    assert_eq!(tracker.day_start(), Some(at(2, 8, 0)));
+    assert_eq!(tracker.realized().get("SOLUSDT"), Some(&dec!(-100)));
+    let update = tracker.update(&state(at(2, 8, 1), None));
+    assert!(update.rolled_over);
+    assert!(update.alerts.is_empty());
+
+    // A late fill of the previous day is ignored.
+    tracker.record_fill("SOLUSDT", dec!(-500), at(2, 7, 59));
+    assert_eq!(tracker.realized().get("SOLUSDT"), Some(&dec!(-100)));
+
+    // The new day alerts afresh, counting the unrealized loss of a position opened today.
+    let update = tracker.update(&state(at(2, 12, 0), Some(dec!(-65))));
+    assert!(!update.rolled_over);
+    assert_eq!(levels(&update.alerts), vec![(sol(), LossLevel::Warning)]);
+
+    // A check past the next rollover starts the day after, without any fill.
+    let update = tracker.update(&state(at(3, 8, 0), Some(dec!(-65))));
+    assert!(update.rolled_over);
+    assert!(update.alerts.is_empty());
+    assert!(tracker.realized().is_empty());
+}
+
+#[test]
+fn a_position_carried_over_the_rollover_counts_only_todays_change() {
+    let mut tracker = tracker();
+
+    // 160 lost yesterday by a position opened then, still open at the rollover.
+    let update = tracker.update(&state(at(2, 7, 55), Some(dec!(-160))));
+    assert_eq!(levels(&update.alerts), vec![(sol(), LossLevel::Warning)]);
+    let update = tracker.update(&state(at(2, 8, 5), Some(dec!(-170))));
+    assert!(update.rolled_over);
+    assert!(update.alerts.is_empty());
+
+    // 160 lost since the rollover is 80% of SOLUSDT's limit.
+    let update = tracker.update(&state(at(2, 9, 0), Some(dec!(-320))));
+    assert_eq!(levels(&update.alerts), vec![(sol(), LossLevel::Warning)]);
+    assert_eq!(update.alerts[0].loss, dec!(160));
+
+    // Closing it realizes yesterday's loss too, which still does not count.
+    tracker.record_fill("SOLUSDT", dec!(-350), at(2, 9, 30));
+    assert!(tracker.update(&state(at(2, 9, 31), None)).alerts.is_empty());
+    tracker.record_fill("SOLUSDT", dec!(-10), at(2, 10, 0));
+    let update = tracker.update(&state(at(2, 10, 1), None));
+    assert_eq!(levels(&update.alerts), vec![(sol(), LossLevel::Breach)]);
+    assert_eq!(update.alerts[0].loss, dec!(200));
+}
+
+#[test]
+fn percentage_limits_apply_to_the_equity_at_the_rollover() {
+    let mut tracker = DailyLossTracker::new(DailyLossLimits {
+        rollover_hour_utc: 0,
+        max_daily_loss_abs: Some(dec!(1000)),
+        max_daily_loss_pct: Some(dec!(0.02)),
+        symbols: Default::default(),
+    });
+
+    // Restored after a restart: 150 lost today from a rollover equity of 10,000.
+    tracker.start_day(at(2, 0, 0), Some(dec!(10000)), [("BTCUSDT".to_string(), dec!(-150))].into());
+    assert!(tracker.update(&state(at(2, 12, 0), None)).alerts.is_empty());
+
+    // 2% of 10,000 is tighter than 1,000.
+    tracker.record_fill("BTCUSDT", dec!(-20), at(2, 12, 5));
+    let update = tracker.update(&state(at(2, 12, 6), None));
+    assert_eq!(levels(&update.alerts), vec![(LossScope::Account, LossLevel::Warning)]);
+    assert_eq!(update.alerts[0].limit, dec!(200));
+}
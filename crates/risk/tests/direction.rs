@@ -0,0 +1,113 @@
+//! Checks which signals a long-only or short-only bot acts on, and how.
+
+use chrono::Utc;
+use core_types::{OrderRequest, OrderSide, OrderType, Position, Signal, SignalIntent, TradeDirection};
+use risk::constrain_direction;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+fn signal(intent: Option<SignalIntent>, side: OrderSide) -> Signal {
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Decimal::ZERO,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        },
+        confidence: Decimal::ONE,
+        intent,
+    }
+}
+
+/// Three units held on `side`.
+fn position(side: OrderSide) -> Position {
+    Position {
+        position_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        quantity: dec!(3),
+        entry_price: dec!(100),
+        unrealized_pnl: Decimal::ZERO,
+        last_updated: Utc::now(),
+        adds: 0,
+        last_entry_price: dec!(100),
+        estimated_liquidation_price: None,
+    }
+}
+
+#[test]
+fn both_directions_pass_every_signal() {
+    for (intent, side) in [(Some(SignalIntent::OpenShort), OrderSide::Sell), (Some(SignalIntent::Reverse), OrderSide::Buy), (None, OrderSide::Sell)] {
+        let signal = signal(intent, side);
+        assert_eq!(constrain_direction(TradeDirection::Both, signal.clone(), None), Some(signal));
+    }
+}
+
+#[test]
+fn allowed_entries_and_closes_pass_unchanged() {
+    let long = position(OrderSide::Buy);
+    for (direction, signal, position) in [
+        (TradeDirection::LongOnly, signal(Some(SignalIntent::OpenLong), OrderSide::Buy), None),
+        (TradeDirection::LongOnly, signal(Some(SignalIntent::Reverse), OrderSide::Buy), None),
+        (TradeDirection::LongOnly, signal(None, OrderSide::Buy), None),
+        (TradeDirection::LongOnly, signal(Some(SignalIntent::Close), OrderSide::Sell), Some(&long)),
+        (TradeDirection::ShortOnly, signal(Some(SignalIntent::OpenShort), OrderSide::Sell), None),
+        // Without an intent, a signal against the position reads as its close.
+        (TradeDirection::ShortOnly, signal(None, OrderSide::Sell), Some(&long)),
+    ] {
+        assert_eq!(constrain_direction(direction, signal.clone(), position), Some(signal));
+    }
+}
+
+#[test]
+fn a_disallowed_entry_while_flat_is_dropped() {
+    for (direction, intent, side) in [
+        (TradeDirection::LongOnly, Some(SignalIntent::OpenShort), OrderSide::Sell),
+        (TradeDirection::LongOnly, Some(SignalIntent::Reverse), OrderSide::Sell),
+        (TradeDirection::LongOnly, None, OrderSide::Sell),
+        (TradeDirection::ShortOnly, Some(SignalIntent::OpenLong), OrderSide::Buy),
+        (TradeDirection::ShortOnly, None, OrderSide::Buy),
+    ] {
+        assert_eq!(constrain_direction(direction, signal(intent, side), None), None, "{:?} {:?} {:?}", direction, intent, side);
+    }
+}
+
+#[test]
+fn a_disallowed_entry_against_the_position_closes_it() {
+    for (direction, held, intent) in [
+        (TradeDirection::LongOnly, OrderSide::Buy, Some(SignalIntent::OpenShort)),
+        (TradeDirection::LongOnly, OrderSide::Buy, Some(SignalIntent::Reverse)),
+        (TradeDirection::ShortOnly, OrderSide::Sell, Some(SignalIntent::OpenLong)),
+        (TradeDirection::ShortOnly, OrderSide::Sell, Some(SignalIntent::Reverse)),
+    ] {
+        let original = signal(intent, held.opposite());
+        let close = constrain_direction(direction, original.clone(), Some(&position(held))).expect("a close");
+        assert_eq!(close.intent, Some(SignalIntent::Close));
+        assert_eq!(close.order_request.side, held.opposite());
+        assert_eq!(close.order_request.quantity, dec!(3));
+        assert!(close.order_request.reduce_only);
+        assert_eq!((close.signal_id, close.decision_id), (original.signal_id, original.decision_id));
+    }
+}
+
+#[test]
+fn a_position_on_the_disallowed_side_is_not_added_to() {
+    let short = position(OrderSide::Sell);
+    let add = signal(Some(SignalIntent::OpenShort), OrderSide::Sell);
+    assert_eq!(constrain_direction(TradeDirection::LongOnly, add, Some(&short)), None);
+    // But it can still be closed, or reversed onto the allowed side.
+    let close = signal(Some(SignalIntent::Close), OrderSide::Buy);
+    assert_eq!(constrain_direction(TradeDirection::LongOnly, close.clone(), Some(&short)), Some(close));
+    let reverse = signal(Some(SignalIntent::Reverse), OrderSide::Buy);
+    assert_eq!(constrain_direction(TradeDirection::LongOnly, reverse.clone(), Some(&short)), Some(reverse));
+}
@@ -0,0 +1,164 @@
+//! Checks the drawdown tiers the scaler moves through and how a scaled-down risk sizes
+//! entries and resizes.
+
+use chrono::Utc;
+use configuration::{DrawdownTier, DynamicLeverage, LimitAction, OrderLimits, ReverseMode, RiskManagement};
+use core_types::{OrderRequest, OrderSide, OrderType, Position, Signal, SignalIntent};
+use events::PortfolioState;
+use risk::{DrawdownScaler, RiskError, RiskManager, SimpleRiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+const PRICE: Decimal = dec!(100);
+
+/// Half risk from a 5% drawdown, a quarter from 10%, with a 1% recovery buffer.
+fn scaler() -> DrawdownScaler {
+    DrawdownScaler::new(&DynamicLeverage {
+        tiers: vec![
+            DrawdownTier { drawdown_pct: dec!(0.10), multiplier: dec!(0.25) },
+            DrawdownTier { drawdown_pct: dec!(0.05), multiplier: dec!(0.5) },
+        ],
+        recovery_buffer_pct: dec!(0.01),
+        apply_in_backtests: false,
+    })
+}
+
+fn manager() -> SimpleRiskManager {
+    SimpleRiskManager::new(RiskManagement {
+        risk_per_trade_pct: dec!(0.01),
+        stop_loss_pct: dec!(0.02),
+        margin_buffer_pct: dec!(0.05),
+        max_position_adds: None,
+        add_spacing_pct: Decimal::ZERO,
+        reverse_mode: ReverseMode::Separate,
+        break_even_trigger_pct: None,
+        break_even_buffer_pct: Decimal::ZERO,
+        take_profit_pct: None,
+        order_limits: OrderLimits::default(),
+        symbol_limits: Default::default(),
+        limit_action: LimitAction::Clamp,
+        max_holding_bars: None,
+        exit_at_session_end: false,
+        min_expected_move: None,
+    })
+    .unwrap()
+}
+
+fn signal(intent: SignalIntent, side: OrderSide) -> Signal {
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Decimal::ZERO,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        },
+        confidence: Decimal::ONE,
+        intent: Some(intent),
+    }
+}
+
+/// A 10,000 portfolio, holding `long` units long if any. A full-risk entry is 50 units.
+fn portfolio(long: Option<Decimal>) -> PortfolioState {
+    PortfolioState {
+        timestamp: Utc::now(),
+        quote_asset: "USDT".to_string(),
+        cash: dec!(10000),
+        balances: Default::default(),
+        total_value: dec!(10000),
+        positions: long
+            .map(|quantity| Position {
+                position_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                side: OrderSide::Buy,
+                quantity,
+                entry_price: PRICE,
+                unrealized_pnl: Decimal::ZERO,
+                last_updated: Utc::now(),
+                adds: 0,
+                last_entry_price: PRICE,
+                estimated_liquidation_price: None,
+            })
+            .into_iter()
+            .collect(),
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    }
+}
+
+#[test]
+fn tiers_are_entered_at_their_boundary_and_left_past_the_buffer() {
+    let mut scaler = scaler();
+    let path = [
+        (dec!(1000), dec!(1)),
+        (dec!(960), dec!(1)),    // 4%
+        (dec!(950), dec!(0.5)),  // 5%: first tier
+        (dec!(955), dec!(0.5)),  // 4.5%: inside the buffer
+        (dec!(900), dec!(0.25)), // 10%: second tier
+        (dec!(905), dec!(0.25)), // 9.5%: inside the buffer
+        (dec!(915), dec!(0.5)),  // 8.5%: back to the first tier
+        (dec!(945), dec!(0.5)),  // 5.5%
+        (dec!(959), dec!(0.5)),  // 4.1%
+        (dec!(960), dec!(1)),    // 4%: recovered past the buffer
+        (dec!(1100), dec!(1)),   // A new peak
+        (dec!(1045), dec!(0.5)), // 5% from the new peak
+    ];
+    for (equity, multiplier) in path {
+        scaler.update(equity);
+        assert_eq!(scaler.multiplier(), multiplier, "at an equity of {}", equity);
+    }
+    assert_eq!(scaler.peak_equity(), Some(dec!(1100)));
+}
+
+#[test]
+fn changes_are_reported_once_and_can_skip_tiers() {
+    let mut scaler = scaler();
+    assert_eq!(scaler.update(dec!(1000)), None);
+
+    let change = scaler.update(dec!(880)).expect("a change");
+    assert_eq!((change.drawdown, change.from, change.to), (dec!(0.12), dec!(1), dec!(0.25)));
+    assert!(change.is_reduction());
+    assert_eq!(scaler.update(dec!(870)), None);
+
+    let change = scaler.update(dec!(1000)).expect("a change");
+    assert_eq!((change.from, change.to), (dec!(0.25), dec!(1)));
+    assert!(!change.is_reduction());
+}
+
+#[test]
+fn scaled_risk_sizes_fresh_entries_down() {
+    let manager = manager();
+    let open_long = signal(SignalIntent::OpenLong, OrderSide::Buy);
+
+    for (multiplier, quantity) in [(dec!(1), dec!(50)), (dec!(0.5), dec!(25)), (dec!(0.25), dec!(12.5))] {
+        let plan = manager.evaluate_scaled_signal(&open_long, &portfolio(None), PRICE, multiplier).unwrap();
+        assert_eq!(plan.legs.len(), 1);
+        assert_eq!(plan.legs[0].quantity, quantity);
+    }
+}
+
+#[test]
+fn scaled_risk_never_shrinks_a_position_below_a_full_entry() {
+    let manager = manager();
+    let open_long = signal(SignalIntent::OpenLong, OrderSide::Buy);
+
+    // At full risk, 30 units would be topped up to 50; at half risk they are kept.
+    let error = manager.evaluate_scaled_signal(&open_long, &portfolio(Some(dec!(30))), PRICE, dec!(0.5)).unwrap_err();
+    assert!(matches!(error, RiskError::PositionKeptWhileScaled { .. }), "{:?}", error);
+    assert!(error.is_declined());
+
+    // An oversized position is still reduced, but only to a full-risk entry.
+    let plan = manager.evaluate_scaled_signal(&open_long, &portfolio(Some(dec!(80))), PRICE, dec!(0.5)).unwrap();
+    assert_eq!(plan.legs.len(), 1);
+    assert!(plan.legs[0].reduce_only);
+    assert_eq!((plan.legs[0].side, plan.legs[0].quantity), (OrderSide::Sell, dec!(30)));
+}
@@ -0,0 +1,193 @@
+//! Checks that entries are refused when their expected move does not cover their round-trip
+//! costs, and that closes always pass.
+
+use chrono::Utc;
+use configuration::{BracketResolution, LimitAction, MinExpectedMove, OrderLimits, ReverseMode, RiskManagement, Simulation, StopFillModel};
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Position, Signal, SignalIntent};
+use events::PortfolioState;
+use risk::{ExpectedMoveFilter, OrderPlan, RiskError, RiskManager, SimpleRiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+const PRICE: Decimal = dec!(100);
+
+fn risk_management(stop_loss_pct: Decimal, min_expected_move: Option<MinExpectedMove>) -> RiskManagement {
+    RiskManagement {
+        risk_per_trade_pct: dec!(0.01),
+        stop_loss_pct,
+        margin_buffer_pct: dec!(0.05),
+        max_position_adds: None,
+        add_spacing_pct: Decimal::ZERO,
+        reverse_mode: ReverseMode::Separate,
+        break_even_trigger_pct: None,
+        break_even_buffer_pct: Decimal::ZERO,
+        take_profit_pct: None,
+        order_limits: OrderLimits::default(),
+        symbol_limits: BTreeMap::new(),
+        limit_action: LimitAction::Clamp,
+        max_holding_bars: None,
+        exit_at_session_end: false,
+        min_expected_move,
+    }
+}
+
+/// A 0.04% taker fee, 10% slippage on the bar's range and no spread.
+fn simulation() -> Simulation {
+    Simulation {
+        taker_fee_pct: dec!(0.0004),
+        maker_fee_pct: dec!(0.0002),
+        fee_discount_pct: Decimal::ZERO,
+        slippage_pct: dec!(0.1),
+        simulated_spread_pct: Decimal::ZERO,
+        symbol_spread_pct: BTreeMap::new(),
+        stop_fill_model: StopFillModel::Exact,
+        bracket_resolution: BracketResolution::Assumption,
+    }
+}
+
+/// A bar closing at `PRICE` with a range of 0.1% of it.
+fn kline() -> Kline {
+    Kline {
+        open_time: Utc::now(),
+        open: PRICE,
+        high: dec!(100.05),
+        low: dec!(99.95),
+        close: PRICE,
+        volume: dec!(10),
+        close_time: Utc::now(),
+        interval: "1m".to_string(),
+    }
+}
+
+fn signal(intent: SignalIntent, side: OrderSide) -> Signal {
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Decimal::ZERO,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        },
+        confidence: Decimal::ONE,
+        intent: Some(intent),
+    }
+}
+
+/// A 10,000 portfolio, holding 30 BTCUSDT long if `long` is set.
+fn portfolio(long: bool) -> PortfolioState {
+    PortfolioState {
+        timestamp: Utc::now(),
+        quote_asset: "USDT".to_string(),
+        cash: dec!(10000),
+        balances: Default::default(),
+        total_value: dec!(10000),
+        positions: long
+            .then(|| Position {
+                position_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                side: OrderSide::Buy,
+                quantity: dec!(30),
+                entry_price: PRICE,
+                unrealized_pnl: Decimal::ZERO,
+                last_updated: Utc::now(),
+                adds: 0,
+                last_entry_price: PRICE,
+                estimated_liquidation_price: None,
+            })
+            .into_iter()
+            .collect(),
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    }
+}
+
+/// The plan for `signal` under a `stop_loss_pct` stop, and the filter's verdict on it.
+fn evaluate(stop_loss_pct: Decimal, signal: &Signal, long: bool) -> (OrderPlan, Result<(), RiskError>) {
+    let params = risk_management(stop_loss_pct, Some(MinExpectedMove::default()));
+    let plan = SimpleRiskManager::new(params.clone()).unwrap().evaluate_signal(signal, &portfolio(long), PRICE).unwrap();
+    let verdict = ExpectedMoveFilter::new(&params, &simulation()).check(&plan, &kline());
+    (plan, verdict)
+}
+
+#[test]
+fn the_round_trip_cost_is_two_fees_the_spread_and_two_slippages() {
+    let mut simulation = simulation();
+    simulation.fee_discount_pct = dec!(0.25);
+    simulation.symbol_spread_pct.insert("BTCUSDT".to_string(), dec!(0.0002));
+    let filter = ExpectedMoveFilter::new(&risk_management(dec!(0.01), Some(MinExpectedMove::default())), &simulation);
+
+    // 2 x 0.03% in fees, a 0.02% spread and 2 x 0.01% of slippage.
+    assert_eq!(filter.round_trip_cost("BTCUSDT", &kline()), dec!(0.001));
+    assert_eq!(filter.round_trip_cost("ETHUSDT", &kline()), dec!(0.0008));
+}
+
+#[test]
+fn a_stop_closer_than_twice_the_costs_refuses_every_entry() {
+    for (intent, side) in [(SignalIntent::OpenLong, OrderSide::Buy), (SignalIntent::OpenShort, OrderSide::Sell), (SignalIntent::Reverse, OrderSide::Sell)] {
+        let (_, verdict) = evaluate(dec!(0.0005), &signal(intent, side), false);
+
+        // 0.05% against 2 x (0.08% in fees + 0.02% of slippage).
+        let error = verdict.unwrap_err();
+        assert!(error.is_declined());
+        assert!(
+            matches!(error, RiskError::InsufficientExpectedMove { expected_move, round_trip_cost, cost_multiple } if expected_move == dec!(0.0005) && round_trip_cost == dec!(0.001) && cost_multiple == dec!(2)),
+            "{:?}",
+            error
+        );
+        assert_eq!(error.to_string(), "Expected move of 0.0005 is under 2x the round-trip cost of 0.001.");
+    }
+
+    // A reversal is refused as a whole, close and all.
+    let (plan, verdict) = evaluate(dec!(0.0005), &signal(SignalIntent::Reverse, OrderSide::Sell), true);
+    assert_eq!(plan.legs.len(), 2);
+    assert!(verdict.is_err());
+}
+
+#[test]
+fn a_wider_stop_lets_the_same_entries_through() {
+    for (intent, side) in [(SignalIntent::OpenLong, OrderSide::Buy), (SignalIntent::OpenShort, OrderSide::Sell)] {
+        let (plan, verdict) = evaluate(dec!(0.005), &signal(intent, side), false);
+        assert_eq!(plan.legs.len(), 1);
+        verdict.unwrap();
+    }
+}
+
+#[test]
+fn closes_and_unset_filters_always_pass() {
+    let (plan, verdict) = evaluate(dec!(0.0005), &signal(SignalIntent::Close, OrderSide::Sell), true);
+    assert!(plan.legs.iter().all(|leg| leg.reduce_only));
+    verdict.unwrap();
+
+    let params = risk_management(dec!(0.0005), None);
+    let plan = SimpleRiskManager::new(params.clone()).unwrap().evaluate_signal(&signal(SignalIntent::OpenLong, OrderSide::Buy), &portfolio(false), PRICE).unwrap();
+    let filter = ExpectedMoveFilter::new(&params, &simulation());
+    assert!(!filter.is_enabled());
+    filter.check(&plan, &kline()).unwrap();
+}
+
+#[test]
+fn a_take_profit_sets_the_expected_move_in_place_of_the_payoff_ratio() {
+    let mut params = risk_management(dec!(0.0005), Some(MinExpectedMove { payoff_ratio: dec!(10), ..Default::default() }));
+    let plan = SimpleRiskManager::new(params.clone()).unwrap().evaluate_signal(&signal(SignalIntent::OpenLong, OrderSide::Buy), &portfolio(false), PRICE).unwrap();
+    // Ten times the 0.05% stop clears twice the 0.1% cost.
+    ExpectedMoveFilter::new(&params, &simulation()).check(&plan, &kline()).unwrap();
+
+    // A 0.15% take-profit is the move the trade is after, and it does not.
+    params.take_profit_pct = Some(dec!(0.0015));
+    let filter = ExpectedMoveFilter::new(&params, &simulation());
+    assert_eq!(filter.expected_move(), dec!(0.0015));
+    assert!(matches!(filter.check(&plan, &kline()), Err(RiskError::InsufficientExpectedMove { .. })));
+
+    params.take_profit_pct = Some(dec!(0.003));
+    ExpectedMoveFilter::new(&params, &simulation()).check(&plan, &kline()).unwrap();
+}
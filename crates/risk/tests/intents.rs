@@ -0,0 +1,155 @@
+//! Checks the orders the risk manager produces for each signal intent.
+
+use chrono::Utc;
+use configuration::{LimitAction, OrderLimits, ReverseMode, RiskManagement};
+use core_types::{OrderRequest, OrderSide, OrderType, Position, Signal, SignalIntent};
+use events::PortfolioState;
+use risk::{LegPolicy, RiskError, RiskManager, SimpleRiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+const PRICE: Decimal = dec!(100);
+
+fn manager(reverse_mode: ReverseMode) -> SimpleRiskManager {
+    SimpleRiskManager::new(RiskManagement {
+        risk_per_trade_pct: dec!(0.01),
+        stop_loss_pct: dec!(0.02),
+        margin_buffer_pct: dec!(0.05),
+        max_position_adds: None,
+        add_spacing_pct: Decimal::ZERO,
+        reverse_mode,
+        break_even_trigger_pct: None,
+        break_even_buffer_pct: Decimal::ZERO,
+        take_profit_pct: None,
+        order_limits: OrderLimits::default(),
+        symbol_limits: Default::default(),
+        limit_action: LimitAction::Clamp,
+        max_holding_bars: None,
+        exit_at_session_end: false,
+        min_expected_move: None,
+    })
+    .unwrap()
+}
+
+fn signal(intent: Option<SignalIntent>, side: OrderSide) -> Signal {
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: "BTCUSDT".to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Decimal::ZERO,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        },
+        confidence: Decimal::ONE,
+        intent,
+    }
+}
+
+/// A 10,000 portfolio, holding three units on `side` if given. Risking 1% with a 2% stop
+/// sizes a fresh entry at 5,000 of notional: 50 units at `PRICE`.
+fn portfolio(side: Option<OrderSide>) -> PortfolioState {
+    PortfolioState {
+        timestamp: Utc::now(),
+        quote_asset: "USDT".to_string(),
+        cash: dec!(10000),
+        balances: Default::default(),
+        total_value: dec!(10000),
+        positions: side
+            .map(|side| Position {
+                position_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                side,
+                quantity: dec!(3),
+                entry_price: PRICE,
+                unrealized_pnl: Decimal::ZERO,
+                last_updated: Utc::now(),
+                adds: 0,
+                last_entry_price: PRICE,
+                estimated_liquidation_price: None,
+            })
+            .into_iter()
+            .collect(),
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    }
+}
+
+/// The side, quantity and reduce-only flag of each order.
+fn evaluate(manager: &SimpleRiskManager, signal: &Signal, portfolio: &PortfolioState) -> Vec<(OrderSide, Decimal, bool)> {
+    manager
+        .evaluate_signal(signal, portfolio, PRICE)
+        .expect("orders")
+        .legs
+        .iter()
+        .map(|order| (order.side, order.quantity, order.reduce_only))
+        .collect()
+}
+
+#[test]
+fn open_sizes_an_entry_on_the_intended_side() {
+    let manager = manager(ReverseMode::Separate);
+    let open_long = signal(Some(SignalIntent::OpenLong), OrderSide::Sell);
+    assert_eq!(evaluate(&manager, &open_long, &portfolio(None)), [(OrderSide::Buy, dec!(50), false)]);
+
+    let open_short = signal(Some(SignalIntent::OpenShort), OrderSide::Sell);
+    assert_eq!(evaluate(&manager, &open_short, &portfolio(None)), [(OrderSide::Sell, dec!(50), false)]);
+
+    // A one-way account cannot hold both sides, so an open against a short closes it first.
+    assert_eq!(
+        evaluate(&manager, &open_long, &portfolio(Some(OrderSide::Sell))),
+        [(OrderSide::Buy, dec!(3), true), (OrderSide::Buy, dec!(50), false)]
+    );
+}
+
+#[test]
+fn close_is_a_reduce_only_order_for_the_whole_position() {
+    let manager = manager(ReverseMode::Separate);
+    let close = signal(Some(SignalIntent::Close), OrderSide::Buy);
+    assert_eq!(evaluate(&manager, &close, &portfolio(Some(OrderSide::Buy))), [(OrderSide::Sell, dec!(3), true)]);
+    assert_eq!(evaluate(&manager, &close, &portfolio(Some(OrderSide::Sell))), [(OrderSide::Buy, dec!(3), true)]);
+
+    let error = manager.evaluate_signal(&close, &portfolio(None), PRICE).unwrap_err();
+    assert!(matches!(error, RiskError::NoPositionToClose(_)));
+    assert!(error.is_declined());
+}
+
+#[test]
+fn reverse_closes_then_opens_a_separately_sized_entry() {
+    let manager = manager(ReverseMode::Separate);
+    let reverse = signal(Some(SignalIntent::Reverse), OrderSide::Sell);
+    assert_eq!(
+        evaluate(&manager, &reverse, &portfolio(Some(OrderSide::Buy))),
+        [(OrderSide::Sell, dec!(3), true), (OrderSide::Sell, dec!(50), false)]
+    );
+
+    // With nothing to reverse, a reverse is an open on the order's side.
+    assert_eq!(evaluate(&manager, &reverse, &portfolio(None)), [(OrderSide::Sell, dec!(50), false)]);
+
+    // The entry only makes sense once the close has filled.
+    let plan = manager.evaluate_signal(&reverse, &portfolio(Some(OrderSide::Buy)), PRICE).unwrap();
+    assert_eq!(plan.policy, LegPolicy::AbortOnFailure);
+}
+
+#[test]
+fn netted_reverse_is_one_order_for_both_quantities() {
+    let manager = manager(ReverseMode::Netted);
+    let reverse = signal(Some(SignalIntent::Reverse), OrderSide::Buy);
+    assert_eq!(evaluate(&manager, &reverse, &portfolio(Some(OrderSide::Sell))), [(OrderSide::Buy, dec!(53), false)]);
+}
+
+#[test]
+fn signals_without_an_intent_have_it_inferred_from_the_side() {
+    let manager = manager(ReverseMode::Separate);
+    let buy = signal(None, OrderSide::Buy);
+    assert_eq!(evaluate(&manager, &buy, &portfolio(None)), [(OrderSide::Buy, dec!(50), false)]);
+    assert_eq!(evaluate(&manager, &buy, &portfolio(Some(OrderSide::Sell))), [(OrderSide::Buy, dec!(3), true)]);
+}
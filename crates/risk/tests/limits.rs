@@ -0,0 +1,136 @@
+//! Checks that entries are capped at the configured order limits and closes never are.
+
+use chrono::Utc;
+use configuration::{LimitAction, OrderLimits, ReverseMode, RiskManagement};
+use core_types::{OrderRequest, OrderSide, OrderType, Position, Signal, SignalIntent};
+use events::PortfolioState;
+use risk::{OrderPlan, RiskError, RiskManager, SimpleRiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+const PRICE: Decimal = dec!(100);
+
+/// A manager that sizes a fresh entry at 50 units at `PRICE`, with `btc_limits` on BTCUSDT
+/// and a global quantity cap of 20.
+fn manager(btc_limits: OrderLimits, limit_action: LimitAction, reverse_mode: ReverseMode) -> SimpleRiskManager {
+    SimpleRiskManager::new(RiskManagement {
+        risk_per_trade_pct: dec!(0.01),
+        stop_loss_pct: dec!(0.02),
+        margin_buffer_pct: dec!(0.05),
+        max_position_adds: None,
+        add_spacing_pct: Decimal::ZERO,
+        reverse_mode,
+        break_even_trigger_pct: None,
+        break_even_buffer_pct: Decimal::ZERO,
+        take_profit_pct: None,
+        order_limits: OrderLimits { max_notional: None, max_quantity: Some(dec!(20)) },
+        symbol_limits: BTreeMap::from([("BTCUSDT".to_string(), btc_limits)]),
+        limit_action,
+        max_holding_bars: None,
+        exit_at_session_end: false,
+        min_expected_move: None,
+    })
+    .unwrap()
+}
+
+fn signal(symbol: &str, intent: SignalIntent, side: OrderSide) -> Signal {
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: symbol.to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Decimal::ZERO,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        },
+        confidence: Decimal::ONE,
+        intent: Some(intent),
+    }
+}
+
+/// A 10,000 portfolio, holding 30 BTCUSDT long if `long` is set.
+fn portfolio(long: bool) -> PortfolioState {
+    PortfolioState {
+        timestamp: Utc::now(),
+        quote_asset: "USDT".to_string(),
+        cash: dec!(10000),
+        balances: Default::default(),
+        total_value: dec!(10000),
+        positions: long
+            .then(|| Position {
+                position_id: Uuid::new_v4(),
+                symbol: "BTCUSDT".to_string(),
+                side: OrderSide::Buy,
+                quantity: dec!(30),
+                entry_price: PRICE,
+                unrealized_pnl: Decimal::ZERO,
+                last_updated: Utc::now(),
+                adds: 0,
+                last_entry_price: PRICE,
+                estimated_liquidation_price: None,
+            })
+            .into_iter()
+            .collect(),
+        realized_pnl: Decimal::ZERO,
+        total_fees_paid: Decimal::ZERO,
+    }
+}
+
+fn quantities(plan: OrderPlan) -> Vec<(Decimal, bool)> {
+    plan.legs.into_iter().map(|order| (order.quantity, order.reduce_only)).collect()
+}
+
+#[test]
+fn an_oversized_entry_is_clamped_to_the_cap_after_rounding() {
+    // 1,234.5 of notional at 100 is 12.345 units, rounded down to the 0.01 precision.
+    let notional_capped = manager(OrderLimits { max_notional: Some(dec!(1234.5)), max_quantity: None }, LimitAction::Clamp, ReverseMode::Separate);
+    let orders = notional_capped.evaluate_signal(&signal("BTCUSDT", SignalIntent::OpenLong, OrderSide::Buy), &portfolio(false), PRICE).unwrap();
+    assert_eq!(quantities(orders), [(dec!(12.34), false)]);
+
+    let quantity_capped = manager(OrderLimits { max_notional: None, max_quantity: Some(dec!(7.5)) }, LimitAction::Clamp, ReverseMode::Separate);
+    let orders = quantity_capped.evaluate_signal(&signal("BTCUSDT", SignalIntent::OpenShort, OrderSide::Sell), &portfolio(false), PRICE).unwrap();
+    assert_eq!(quantities(orders), [(dec!(7.5), false)]);
+
+    // Symbols without their own limits fall back to the global cap.
+    let orders = quantity_capped.evaluate_signal(&signal("ETHUSDT", SignalIntent::OpenLong, OrderSide::Buy), &portfolio(false), PRICE).unwrap();
+    assert_eq!(quantities(orders), [(dec!(20), false)]);
+}
+
+#[test]
+fn reject_refuses_the_entry_instead() {
+    let manager = manager(OrderLimits { max_notional: Some(dec!(1000)), max_quantity: None }, LimitAction::Reject, ReverseMode::Separate);
+    let error = manager.evaluate_signal(&signal("BTCUSDT", SignalIntent::OpenLong, OrderSide::Buy), &portfolio(false), PRICE).unwrap_err();
+    assert!(matches!(error, RiskError::OrderLimitExceeded(_)), "{:?}", error);
+    assert!(!error.is_declined());
+}
+
+#[test]
+fn closes_are_never_capped_below_the_position_size() {
+    let limits = OrderLimits { max_notional: None, max_quantity: Some(dec!(5)) };
+
+    let manager_separate = manager(limits, LimitAction::Clamp, ReverseMode::Separate);
+    let close = signal("BTCUSDT", SignalIntent::Close, OrderSide::Sell);
+    assert_eq!(quantities(manager_separate.evaluate_signal(&close, &portfolio(true), PRICE).unwrap()), [(dec!(30), true)]);
+
+    let reverse = signal("BTCUSDT", SignalIntent::Reverse, OrderSide::Sell);
+    assert_eq!(
+        quantities(manager_separate.evaluate_signal(&reverse, &portfolio(true), PRICE).unwrap()),
+        [(dec!(30), true), (dec!(5), false)]
+    );
+
+    // A netted reversal still closes all 30 units; only the new entry is capped.
+    let manager_netted = manager(limits, LimitAction::Reject, ReverseMode::Netted);
+    let error = manager_netted.evaluate_signal(&reverse, &portfolio(true), PRICE).unwrap_err();
+    assert!(matches!(error, RiskError::OrderLimitExceeded(_)));
+    let manager_netted = manager(limits, LimitAction::Clamp, ReverseMode::Netted);
+    assert_eq!(quantities(manager_netted.evaluate_signal(&reverse, &portfolio(true), PRICE).unwrap()), [(dec!(35), false)]);
+}
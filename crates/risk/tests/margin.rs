@@ -0,0 +1,74 @@
+//! Checks that entries are downsized to the leveraged margin left free by the open positions.
+
+use chrono::Utc;
+use core_types::{OrderRequest, OrderSide, OrderType, Position};
+use risk::{free_margin, margin_check, RiskError};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+const PRICE: Decimal = dec!(100);
+const BUFFER: Decimal = dec!(0.05);
+
+/// A market buy of BTCUSDT worth `notional` at `PRICE`.
+fn entry(notional: Decimal) -> OrderRequest {
+    OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        order_type: OrderType::Market,
+        quantity: notional / PRICE,
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: false,
+        decision_id: None,
+    }
+}
+
+fn position(symbol: &str, quantity: Decimal, entry_price: Decimal) -> Position {
+    Position {
+        position_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        side: OrderSide::Buy,
+        quantity,
+        entry_price,
+        unrealized_pnl: Decimal::ZERO,
+        last_updated: Utc::now(),
+        adds: 0,
+        last_entry_price: entry_price,
+        estimated_liquidation_price: None,
+    }
+}
+
+#[test]
+fn an_entry_too_large_for_the_margin_is_downsized_to_fit() {
+    // $1,000 at 10x with a 5% buffer covers $9,500 of notional, not $15,000.
+    let order = margin_check(entry(dec!(15000)), PRICE, dec!(1000), 10, BUFFER).unwrap();
+    assert!(order.quantity * PRICE <= dec!(9500), "{} units", order.quantity);
+    assert_eq!(order.quantity, dec!(95));
+
+    // An entry that fits is left alone.
+    let order = margin_check(entry(dec!(9000)), PRICE, dec!(1000), 10, BUFFER).unwrap();
+    assert_eq!(order.quantity, dec!(90));
+}
+
+#[test]
+fn open_positions_hold_back_their_margin() {
+    // $2,000 of ETHUSDT at 5x holds $400 of the $1,000.
+    let positions = [position("ETHUSDT", dec!(1), dec!(2000))];
+    let free = free_margin(dec!(1000), &positions, |symbol| (symbol == "ETHUSDT").then_some(5));
+    assert_eq!(free, dec!(600));
+
+    // The $600 left covers $5,700 of notional at 10x.
+    let order = margin_check(entry(dec!(15000)), PRICE, free, 10, BUFFER).unwrap();
+    assert_eq!(order.quantity * PRICE, dec!(5700));
+
+    // A position in a symbol without a known leverage holds back its full notional.
+    let free = free_margin(dec!(1000), &positions, |_| None);
+    assert_eq!(free, dec!(-1000));
+    assert!(matches!(
+        margin_check(entry(dec!(15000)), PRICE, free, 10, BUFFER),
+        Err(RiskError::InsufficientMargin { .. })
+    ));
+}
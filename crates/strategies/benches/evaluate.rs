@@ -0,0 +1,43 @@
+//! Measures the throughput of each rule-based strategy's `evaluate`, which an optimization
+//! job calls once per bar for every parameter set it tries.
+//!
+//! Run with `cargo bench -p strategies`. Each strategy is built fresh for every iteration and
+//! fed the same seeded random walk, so it sees trends and chop alike and signals often.
+
+use core_types::StrategyId;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use strategies::create_strategy;
+use testing::synthetic::{gbm, SeriesSpec};
+use testing::{test_config, TEST_SYMBOL};
+
+const BARS: usize = 100_000;
+
+/// The strategies driven by indicators alone. `MlStrategy` needs a trained model, and
+/// `FundingRateArb` only trades on the funding rates the live engine supplies; its
+/// `evaluate` does nothing.
+const RULE_BASED: [StrategyId; 4] = [StrategyId::MACrossover, StrategyId::SuperTrend, StrategyId::ProbReversion, StrategyId::Ensemble];
+
+fn evaluate(c: &mut Criterion) {
+    let config = test_config(BARS).expect("load config");
+    let klines = gbm(&SeriesSpec::default(), BARS, 0.0, 0.01, 7);
+
+    let mut group = c.benchmark_group("strategies");
+    group.sample_size(10).throughput(Throughput::Elements(BARS as u64));
+    for id in RULE_BASED {
+        group.bench_with_input(BenchmarkId::new("evaluate_100k_bars", format!("{:?}", id)), &id, |b, &id| {
+            b.iter_batched(
+                || create_strategy(id, &config, TEST_SYMBOL).expect("build strategy"),
+                |mut strategy| {
+                    for kline in &klines {
+                        black_box(strategy.evaluate(kline).ok());
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, evaluate);
+criterion_main!(benches);
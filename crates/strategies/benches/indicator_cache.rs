@@ -0,0 +1,65 @@
+//! Measures what an `IndicatorCache` saves an optimization job: a grid of 100 MACrossover
+//! parameter sets evaluated over the same klines, each computing its own averages or all of
+//! them reading the averages from one cache.
+//!
+//! Run with `cargo bench -p strategies --bench indicator_cache`. A fresh cache is built for
+//! every iteration, so its timings include computing each series once.
+
+use configuration::MACrossoverParams;
+use core_types::StrategyId;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use strategies::{create_strategy_from_params_with_cache, IndicatorCache};
+use testing::synthetic::{gbm, SeriesSpec};
+use testing::TEST_SYMBOL;
+
+const BARS: usize = 10_000;
+
+/// Ten fast periods by ten slow ones, all with the same 200-bar trend filter.
+fn parameter_sets() -> Vec<JsonValue> {
+    let mut sets = Vec::new();
+    for fast in (5..=50).step_by(5) {
+        for slow in (60..=150).step_by(10) {
+            let params = MACrossoverParams {
+                ma_fast_period: fast,
+                ma_slow_period: slow,
+                trend_filter_period: 200,
+                ma_type: "sma".to_string(),
+                confirmation_bars: 0,
+            };
+            sets.push(serde_json::to_value(params).expect("serializable params"));
+        }
+    }
+    sets
+}
+
+fn indicator_cache(c: &mut Criterion) {
+    let klines = Arc::new(gbm(&SeriesSpec::default(), BARS, 0.0, 0.01, 7));
+    let sets = parameter_sets();
+
+    let mut group = c.benchmark_group("strategies");
+    group.sample_size(10).throughput(Throughput::Elements((BARS * sets.len()) as u64));
+    for cached in [false, true] {
+        let name = if cached { "evaluate_100_param_sets/cached" } else { "evaluate_100_param_sets/uncached" };
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || cached.then(|| Arc::new(IndicatorCache::new(klines.clone()))),
+                |cache| {
+                    for params in &sets {
+                        let mut strategy = create_strategy_from_params_with_cache(StrategyId::MACrossover, params, TEST_SYMBOL, cache.clone())
+                            .expect("build strategy");
+                        for kline in klines.iter() {
+                            black_box(strategy.evaluate(kline).ok());
+                        }
+                    }
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, indicator_cache);
+criterion_main!(benches);
@@ -0,0 +1,202 @@
+use crate::error::StrategyError;
+use crate::factory::{create_strategy_from_params, parameter_violations};
+use crate::Strategy;
+use configuration::EnsembleParams;
+use core_types::{Kline, MarketContext, OrderRequest, OrderSide, OrderType, Signal, SignalIntent, StrategyId};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+/// A meta-strategy that trades on a vote of its member strategies.
+///
+/// Each member's latest signal gives it a stance: long, short or flat. The ensemble is long
+/// when at least `min_agreement` members are long and more are long than short, short the
+/// other way round, and flat otherwise, ties included. It only signals when that side
+/// changes, with a confidence of the vote margin (longs minus shorts) over the member count.
+pub struct Ensemble {
+    symbol: String,
+    members: Vec<Member>,
+    min_agreement: usize,
+    stance_bars: usize,
+    // State: The side the ensemble last signalled; None when flat.
+    side: Option<OrderSide>,
+}
+
+struct Member {
+    strategy: Box<dyn Strategy>,
+    // State: The side of the member's latest signal (None for flat), and how many bars ago
+    // it came.
+    stance: Option<OrderSide>,
+    age: usize,
+}
+
+impl Ensemble {
+    /// Creates a new `Ensemble`, building each member from its entry in `member_params`.
+    ///
+    /// It fails with every rule of `parameter_violations` the parameters break.
+    pub fn new(params: EnsembleParams, symbol: String) -> Result<Self, StrategyError> {
+        StrategyError::check_parameters("Ensemble", Self::parameter_violations(&params))?;
+        let members = params
+            .members
+            .iter()
+            .map(|id| create_strategy_from_params(*id, &params.member_params[id], &symbol))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::with_members(members, params.min_agreement, params.stance_bars, symbol)
+    }
+
+    /// Creates an `Ensemble` of already built strategies, such as ones outside the factory.
+    pub fn with_members(
+        members: Vec<Box<dyn Strategy>>,
+        min_agreement: usize,
+        stance_bars: usize,
+        symbol: String,
+    ) -> Result<Self, StrategyError> {
+        StrategyError::check_parameters("Ensemble", vote_violations(members.len(), min_agreement))?;
+        Ok(Self {
+            symbol,
+            members: members.into_iter().map(|strategy| Member { strategy, stance: None, age: 0 }).collect(),
+            min_agreement,
+            stance_bars,
+            side: None,
+        })
+    }
+
+    /// The rules `params` break, each described in one message: there must be members, each
+    /// listed once, none of them an ensemble, and `min_agreement` must be between 1 and the
+    /// member count. Each member's parameters must be present and break none of its own rules.
+    pub fn parameter_violations(params: &EnsembleParams) -> Vec<String> {
+        let mut violations = vote_violations(params.members.len(), params.min_agreement);
+        for (i, id) in params.members.iter().enumerate() {
+            if params.members[..i].contains(id) {
+                violations.push(format!("member {:?} is listed more than once", id));
+            } else if *id == StrategyId::Ensemble {
+                violations.push("an ensemble cannot be a member of an ensemble".to_string());
+            } else {
+                match params.member_params.get(id).map(|member_params| parameter_violations(*id, member_params)) {
+                    None => violations.push(format!("member {:?} has no parameters", id)),
+                    Some(Ok(member_violations)) => {
+                        violations.extend(member_violations.into_iter().map(|violation| format!("{:?}: {}", id, violation)))
+                    }
+                    Some(Err(StrategyError::InvalidParameters(message))) => violations.push(message),
+                    Some(Err(e)) => violations.push(format!("{:?}: {}", id, e)),
+                }
+            }
+        }
+        violations
+    }
+
+    /// Records the members' signals for this bar, then signals if the vote's outcome changed.
+    fn vote(&mut self, kline: &Kline, signals: Vec<Option<Signal>>) -> Option<Signal> {
+        for (member, signal) in self.members.iter_mut().zip(signals) {
+            match signal {
+                Some(signal) => {
+                    member.stance = stance(&signal);
+                    member.age = 0;
+                }
+                None => member.age += 1,
+            }
+            if self.stance_bars > 0 && member.age >= self.stance_bars {
+                member.stance = None;
+            }
+        }
+
+        let longs = self.members.iter().filter(|member| member.stance == Some(OrderSide::Buy)).count();
+        let shorts = self.members.iter().filter(|member| member.stance == Some(OrderSide::Sell)).count();
+        let side = if longs >= self.min_agreement && longs > shorts {
+            Some(OrderSide::Buy)
+        } else if shorts >= self.min_agreement && shorts > longs {
+            Some(OrderSide::Sell)
+        } else {
+            None
+        };
+        tracing::debug!("Ensemble: {} long, {} short of {} members; side {:?}", longs, shorts, self.members.len(), side);
+
+        let previous = std::mem::replace(&mut self.side, side);
+        let (intent, order_side) = match (previous, side) {
+            (None, None) => return None,
+            (Some(previous), Some(side)) if previous == side => return None,
+            (None, Some(OrderSide::Buy)) => (SignalIntent::OpenLong, OrderSide::Buy),
+            (None, Some(OrderSide::Sell)) => (SignalIntent::OpenShort, OrderSide::Sell),
+            (Some(_), Some(side)) => (SignalIntent::Reverse, side),
+            // Flat again: close with an order against the position.
+            (Some(previous), None) => (SignalIntent::Close, previous.opposite()),
+        };
+        let margin = longs.abs_diff(shorts);
+        Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::from(margin) / Decimal::from(self.members.len()),
+            intent: Some(intent),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: self.symbol.clone(),
+                side: order_side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO, // Let the risk manager determine the size
+                price: None,
+                position_side: None, // Will be set by engine
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None, // Linked to the signal's decision by the engine
+            },
+        })
+    }
+}
+
+fn vote_violations(members: usize, min_agreement: usize) -> Vec<String> {
+    let mut violations = Vec::new();
+    if members == 0 {
+        violations.push("members must list at least one strategy".to_string());
+    }
+    if min_agreement == 0 || min_agreement > members {
+        violations.push(format!(
+            "min_agreement ({}) must be between 1 and the number of members ({})",
+            min_agreement, members
+        ));
+    }
+    violations
+}
+
+/// The side a member's signal leaves it on. Signals without an intent open on their order side.
+fn stance(signal: &Signal) -> Option<OrderSide> {
+    match signal.intent {
+        Some(SignalIntent::OpenLong) => Some(OrderSide::Buy),
+        Some(SignalIntent::OpenShort) => Some(OrderSide::Sell),
+        Some(SignalIntent::Close) => None,
+        Some(SignalIntent::Reverse) | None => Some(signal.order_request.side),
+    }
+}
+
+impl Strategy for Ensemble {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let signals = self
+            .members
+            .iter_mut()
+            .map(|member| member.strategy.evaluate(kline))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.vote(kline, signals))
+    }
+
+    /// Passes the market context on to every member, so members that need it still get it.
+    fn evaluate_with_context(&mut self, kline: &Kline, context: &MarketContext) -> Result<Option<Signal>, StrategyError> {
+        let signals = self
+            .members
+            .iter_mut()
+            .map(|member| member.strategy.evaluate_with_context(kline, context))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(self.vote(kline, signals))
+    }
+
+    fn required_warmup_bars(&self) -> usize {
+        self.members.iter().map(|member| member.strategy.required_warmup_bars()).max().unwrap_or(0)
+    }
+
+    fn reset(&mut self) {
+        for member in &mut self.members {
+            member.strategy.reset();
+            member.stance = None;
+            member.age = 0;
+        }
+        self.side = None;
+    }
+}
@@ -10,4 +10,15 @@ pub enum StrategyError {
 
     #[error("Strategy of type '{0}' not found or implemented")]
     StrategyNotFound(String),
+}
+
+impl StrategyError {
+    /// Fails with every one of `violations` of `strategy`'s parameter rules in a single
+    /// `InvalidParameters` error, so they can all be fixed at once.
+    pub fn check_parameters(strategy: &str, violations: Vec<String>) -> Result<(), StrategyError> {
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(StrategyError::InvalidParameters(format!("{}: {}", strategy, violations.join("; "))))
+    }
 }
\ No newline at end of file
@@ -1,19 +1,46 @@
+use crate::ensemble::Ensemble;
 use crate::error::StrategyError;
 use crate::funding_rate_arb::FundingRateArb;
+use crate::indicator_cache::IndicatorCache;
 use crate::ma_crossover::MACrossover;
 use crate::ml_strategy::MlStrategy;
 use crate::prob_reversion::ProbReversion;
 use crate::super_trend::SuperTrend;
+use crate::volume_filter::VolumeFilter;
 use crate::Strategy;
-use configuration::Config;
+use configuration::{
+    Config, EnsembleParams, FundingRateArbParams, MACrossoverParams, ProbReversionParams, SuperTrendParams, VolumeFilterParams,
+};
+use configuration::normalize_params;
+use configuration::settings::MlStrategyParams;
 use core_types::enums::StrategyId;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+
+/// The key of a parameter set holding the strategy's volume filter, if it has one.
+const VOLUME_FILTER_KEY: &str = "volume_filter";
 
 /// Creates a new strategy instance based on the provided ID and configuration.
-// ... (documentation is unchanged)
+///
+/// Strategies listed in `[strategies.volume_filter]` come wrapped in a `VolumeFilter`.
 pub fn create_strategy(
     id: StrategyId,
     config: &Config,
     symbol: &str,
+) -> Result<Box<dyn Strategy>, StrategyError> {
+    let strategy = build_strategy(id, config, symbol)?;
+    match configured_volume_filter(id, config) {
+        Some(filter) => Ok(Box::new(VolumeFilter::new(strategy, filter)?)),
+        None => Ok(strategy),
+    }
+}
+
+fn build_strategy(
+    id: StrategyId,
+    config: &Config,
+    symbol: &str,
 ) -> Result<Box<dyn Strategy>, StrategyError> {
     // With all strategies implemented, we can use a complete match statement.
     // The compiler will now error if a new StrategyId is added but not handled here.
@@ -30,18 +57,193 @@ pub fn create_strategy(
             let params = config.strategies.prob_reversion.clone();
             Ok(Box::new(ProbReversion::new(params, symbol.to_string())?))
         }
-        StrategyId::FundingRateArb => { // <-- ADD THIS BLOCK
+        StrategyId::FundingRateArb => {
             let params = config.strategies.funding_rate_arb.clone();
-            Ok(Box::new(FundingRateArb::new(params)?))
+            Ok(Box::new(FundingRateArb::new(params, symbol.to_string())?))
+        }
+        StrategyId::MlStrategy => {
+            let params = config.strategies.ml_strategy.clone();
+            Ok(Box::new(MlStrategy::new(params, symbol.to_string())?))
+        }
+        StrategyId::Ensemble => {
+            let params: EnsembleParams = parse_params(id, &strategy_params(id, config)?)?;
+            Ok(Box::new(Ensemble::new(params, symbol.to_string())?))
+        }
+    }
+}
+
+/// Creates a new strategy instance directly from a JSON parameter set.
+///
+/// The parameters are deserialized into the strategy's strongly-typed params struct,
+/// which rejects unknown keys, so a typo in a TOML or optimizer parameter space fails
+/// loudly instead of silently falling back to a default. A set with a `volume_filter` key
+/// builds the strategy wrapped in a `VolumeFilter` of those parameters.
+pub fn create_strategy_from_params(
+    id: StrategyId,
+    params: &JsonValue,
+    symbol: &str,
+) -> Result<Box<dyn Strategy>, StrategyError> {
+    create_strategy_from_params_with_cache(id, params, symbol, None)
+}
+
+/// Like `create_strategy_from_params`, but the strategy reads the indicators `cache` computes
+/// from it, where it can. MACrossover (with SMAs or EMAs) and ProbReversion (its ATR) do; the
+/// other strategies compute everything themselves, as they all do without a cache.
+pub fn create_strategy_from_params_with_cache(
+    id: StrategyId,
+    params: &JsonValue,
+    symbol: &str,
+    cache: Option<Arc<IndicatorCache>>,
+) -> Result<Box<dyn Strategy>, StrategyError> {
+    let (params, volume_filter) = split_volume_filter(id, params)?;
+    let strategy = build_strategy_from_params(id, &params, symbol, cache)?;
+    match volume_filter {
+        Some(filter) => Ok(Box::new(VolumeFilter::new(strategy, filter)?)),
+        None => Ok(strategy),
+    }
+}
+
+fn build_strategy_from_params(
+    id: StrategyId,
+    params: &JsonValue,
+    symbol: &str,
+    cache: Option<Arc<IndicatorCache>>,
+) -> Result<Box<dyn Strategy>, StrategyError> {
+    match id {
+        StrategyId::MACrossover => {
+            let params: MACrossoverParams = parse_params(id, params)?;
+            let strategy = MACrossover::new(params, symbol.to_string())?;
+            Ok(Box::new(match cache {
+                Some(cache) => strategy.with_indicator_cache(cache),
+                None => strategy,
+            }))
+        }
+        StrategyId::SuperTrend => {
+            let params: SuperTrendParams = parse_params(id, params)?;
+            Ok(Box::new(SuperTrend::new(params, symbol.to_string())?))
+        }
+        StrategyId::ProbReversion => {
+            let params: ProbReversionParams = parse_params(id, params)?;
+            let strategy = ProbReversion::new(params, symbol.to_string())?;
+            Ok(Box::new(match cache {
+                Some(cache) => strategy.with_indicator_cache(cache),
+                None => strategy,
+            }))
+        }
+        StrategyId::FundingRateArb => {
+            let params: FundingRateArbParams = parse_params(id, params)?;
+            Ok(Box::new(FundingRateArb::new(params, symbol.to_string())?))
         }
         StrategyId::MlStrategy => {
-            let params = &config.strategies.ml_strategy;
-            if params.model_path.as_os_str().is_empty() {
-                return Err(StrategyError::InvalidParameters(
-                    "MlStrategy requires a `model_path` in config.".to_string()
-                ));
+            let params: MlStrategyParams = parse_params(id, params)?;
+            Ok(Box::new(MlStrategy::new(params, symbol.to_string())?))
+        }
+        StrategyId::Ensemble => {
+            let params: EnsembleParams = parse_params(id, params)?;
+            Ok(Box::new(Ensemble::new(params, symbol.to_string())?))
+        }
+    }
+}
+
+/// The rules a JSON parameter set for `id` breaks, without building the strategy (or loading
+/// an ML model). An empty list means `create_strategy_from_params` will accept the set; a
+/// set that does not deserialize at all (e.g. an unknown key) is an error instead.
+pub fn parameter_violations(id: StrategyId, params: &JsonValue) -> Result<Vec<String>, StrategyError> {
+    let (params, volume_filter) = split_volume_filter(id, params)?;
+    let params = &params;
+    let mut violations = match id {
+        StrategyId::MACrossover => MACrossover::parameter_violations(&parse_params(id, params)?),
+        StrategyId::SuperTrend => SuperTrend::parameter_violations(&parse_params(id, params)?),
+        StrategyId::ProbReversion => ProbReversion::parameter_violations(&parse_params(id, params)?),
+        StrategyId::FundingRateArb => FundingRateArb::parameter_violations(&parse_params(id, params)?),
+        StrategyId::MlStrategy => MlStrategy::parameter_violations(&parse_params(id, params)?),
+        StrategyId::Ensemble => Ensemble::parameter_violations(&parse_params(id, params)?),
+    };
+    if let Some(filter) = volume_filter {
+        violations.extend(
+            VolumeFilter::parameter_violations(&filter).into_iter().map(|violation| format!("{}: {}", VOLUME_FILTER_KEY, violation)),
+        );
+    }
+    Ok(violations)
+}
+
+/// Returns the parameter set for `id` from the base configuration as JSON.
+///
+/// An ensemble's set carries each of its members' sets, taken from their own sections of
+/// `[strategies]` unless `member_params` already has them, so it builds on its own. A
+/// strategy listed in `[strategies.volume_filter]` carries the filter under `volume_filter`.
+/// The set is in the canonical form of `normalize_params`.
+pub fn strategy_params(id: StrategyId, config: &Config) -> Result<JsonValue, StrategyError> {
+    let mut params = match id {
+        StrategyId::MACrossover => serde_json::to_value(&config.strategies.ma_crossover),
+        StrategyId::SuperTrend => serde_json::to_value(&config.strategies.super_trend),
+        StrategyId::ProbReversion => serde_json::to_value(&config.strategies.prob_reversion),
+        StrategyId::FundingRateArb => serde_json::to_value(&config.strategies.funding_rate_arb),
+        StrategyId::MlStrategy => serde_json::to_value(&config.strategies.ml_strategy),
+        StrategyId::Ensemble => {
+            let mut ensemble = config.strategies.ensemble.clone();
+            for member in &config.strategies.ensemble.members {
+                // Nested ensembles are rejected when the ensemble is built.
+                if *member != StrategyId::Ensemble && !ensemble.member_params.contains_key(member) {
+                    ensemble.member_params.insert(*member, strategy_params(*member, config)?);
+                }
             }
-            Ok(Box::new(MlStrategy::new(&params.model_path, symbol.to_string())?))
+            serde_json::to_value(&ensemble)
+        }
+    }
+    .map_err(|e| StrategyError::InvalidParameters(format!("{:?}: {}", id, e)))?;
+
+    if let Some(filter) = configured_volume_filter(id, config)
+        && let JsonValue::Object(params) = &mut params
+    {
+        let filter = serde_json::to_value(&filter).map_err(|e| StrategyError::InvalidParameters(format!("{:?}: {}", id, e)))?;
+        params.insert(VOLUME_FILTER_KEY.to_string(), filter);
+    }
+    Ok(normalize_params(params))
+}
+
+/// The volume filter `[strategies.volume_filter]` puts on `id`, without its strategy list.
+fn configured_volume_filter(id: StrategyId, config: &Config) -> Option<VolumeFilterParams> {
+    let filter = config.strategies.volume_filter.as_ref().filter(|filter| filter.strategies.contains(&id))?;
+    Some(VolumeFilterParams { strategies: Vec::new(), ..filter.clone() })
+}
+
+/// Takes the `volume_filter` key, if any, out of a parameter set, leaving the strategy's
+/// own parameters.
+fn split_volume_filter(id: StrategyId, params: &JsonValue) -> Result<(JsonValue, Option<VolumeFilterParams>), StrategyError> {
+    let mut params = params.clone();
+    let filter = match params.as_object_mut().and_then(|params| params.remove(VOLUME_FILTER_KEY)) {
+        Some(JsonValue::Null) | None => None,
+        Some(filter) => Some(
+            VolumeFilterParams::deserialize(filter)
+                .map_err(|e| StrategyError::InvalidParameters(format!("{:?}: {}: {}", id, VOLUME_FILTER_KEY, e)))?,
+        ),
+    };
+    Ok((params, filter))
+}
+
+/// Overlays a (possibly partial) set of parameter overrides onto the base configuration's
+/// parameters for `id`. Keys in `overrides` replace the base values; unknown keys are kept
+/// so that `create_strategy_from_params` can reject them. The merged set is in the canonical
+/// form of `normalize_params`, whichever form the overrides' decimals were written in.
+pub fn merge_params(
+    id: StrategyId,
+    config: &Config,
+    overrides: &JsonValue,
+) -> Result<JsonValue, StrategyError> {
+    let mut params = strategy_params(id, config)?;
+    let overrides = overrides.as_object().ok_or_else(|| {
+        StrategyError::InvalidParameters(format!("{:?}: parameters must be a table, got {}", id, overrides))
+    })?;
+
+    if let JsonValue::Object(base) = &mut params {
+        for (key, value) in overrides {
+            base.insert(key.clone(), normalize_params(value.clone()));
         }
     }
-}
\ No newline at end of file
+    Ok(params)
+}
+
+fn parse_params<T: DeserializeOwned>(id: StrategyId, params: &JsonValue) -> Result<T, StrategyError> {
+    T::deserialize(params).map_err(|e| StrategyError::InvalidParameters(format!("{:?}: {}", id, e)))
+}
@@ -1,51 +1,113 @@
 use crate::error::StrategyError;
 use crate::Strategy;
 use configuration::FundingRateArbParams;
-use core_types::{Kline, Signal};
+use core_types::{Kline, MarketContext, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
 
 /// The Funding Rate Arbitrage strategy.
 ///
-/// **ARCHITECTURAL NOTE:** This strategy is a placeholder scaffold.
+/// Perpetual futures periodically transfer a funding payment between longs and shorts.
+/// When the funding rate is strongly positive, longs pay shorts, so this strategy goes
+/// short to collect it; when it is strongly negative, it goes long.
 ///
-/// Unlike other strategies, funding rate arbitrage does not operate on `Kline` data.
-/// It requires real-time access to:
-/// 1. The funding rate of a perpetual contract.
-/// 2. The mark price of the perpetual contract.
-/// 3. The index price (or spot price) of the underlying asset.
+/// Funding rates are not part of `Kline` data, so the strategy only trades through
+/// `evaluate_with_context`, which the live engine calls with the latest funding rate.
+/// Plain `evaluate` (used by backtests) never generates a signal.
 ///
-/// The current `Strategy::evaluate` signature only provides a `&Kline`. The live
-/// `Engine` (to be built in a later phase) will need to be enhanced to provide a
-/// more complex `MarketData` struct to strategies like this, which require more
-/// than just candlestick data.
-///
-/// For now, this implementation satisfies the `Strategy` trait but will not
-/// generate signals. Its purpose is to complete the architectural skeleton.
+/// A signal is emitted only when the funding rate crosses into a regime, i.e. when its
+/// magnitude first reaches `target_rate_threshold`, not on every bar while it stays there.
+/// The entry waits while the basis between the mark and index prices is wider than
+/// `basis_safety_threshold`, or unknown, and is taken once it narrows within the regime.
 pub struct FundingRateArb {
-    _params: FundingRateArbParams,
+    params: FundingRateArbParams,
+    symbol: String,
+    /// The side of the last signal, cleared when the funding rate falls back below the threshold.
+    regime: Option<OrderSide>,
 }
 
 impl FundingRateArb {
     /// Creates a new `FundingRateArb` instance.
-    pub fn new(params: FundingRateArbParams) -> Result<Self, StrategyError> {
-        Ok(Self { _params: params })
+    pub fn new(params: FundingRateArbParams, symbol: String) -> Result<Self, StrategyError> {
+        StrategyError::check_parameters("FundingRateArb", Self::parameter_violations(&params))?;
+        Ok(Self { params, symbol, regime: None })
+    }
+
+    /// The rules `params` break, each described in one message.
+    pub fn parameter_violations(params: &FundingRateArbParams) -> Vec<String> {
+        let mut violations = Vec::new();
+        if params.target_rate_threshold <= Decimal::ZERO {
+            violations.push(format!("target_rate_threshold ({}) must be greater than 0", params.target_rate_threshold));
+        }
+        if params.basis_safety_threshold <= Decimal::ZERO {
+            violations.push(format!("basis_safety_threshold ({}) must be greater than 0", params.basis_safety_threshold));
+        }
+        violations
     }
 }
 
 impl Strategy for FundingRateArb {
-    /// This evaluation function is a no-op by design.
-    ///
-    /// It will always return `Ok(None)` because it cannot receive the necessary
-    /// funding rate and price data through the current `evaluate` method signature.
-    /// The actual logic will be implemented once the live `Engine`'s data routing
-    /// capabilities are expanded.
+    /// Klines alone carry no funding information, so this never generates a signal.
     fn evaluate(&mut self, _kline: &Kline) -> Result<Option<Signal>, StrategyError> {
-        // The logic would look something like this in the future:
-        //
-        // if market_data.funding_rate > self.params.target_rate_threshold {
-        //     // Generate a signal to short the perpetual and buy spot.
-        // }
-        //
-        // Since we don't have `market_data`, we do nothing.
         Ok(None)
     }
-}
\ No newline at end of file
+
+    fn reset(&mut self) {
+        self.regime = None;
+    }
+
+    fn evaluate_with_context(&mut self, kline: &Kline, context: &MarketContext) -> Result<Option<Signal>, StrategyError> {
+        let Some(funding_rate) = context.funding_rate else {
+            return Ok(None);
+        };
+
+        let threshold = self.params.target_rate_threshold;
+        let regime = if funding_rate >= threshold {
+            // Longs pay shorts: be short to receive funding.
+            Some(OrderSide::Sell)
+        } else if funding_rate <= -threshold {
+            // Shorts pay longs: be long to receive funding.
+            Some(OrderSide::Buy)
+        } else {
+            None
+        };
+
+        let Some(side) = regime.filter(|_| regime != self.regime) else {
+            self.regime = regime;
+            return Ok(None);
+        };
+        // The regime is only entered with the signal, so an entry held back by the basis is
+        // taken on a later bar of the same regime.
+        let basis = match (context.mark_price, context.index_price) {
+            (Some(mark), Some(index)) if index > Decimal::ZERO => (mark - index).abs() / index,
+            _ => return Ok(None),
+        };
+        if basis > self.params.basis_safety_threshold {
+            tracing::debug!("FundingRateArb: basis {} exceeds {}, holding back the {:?} entry", basis, self.params.basis_safety_threshold, side);
+            return Ok(None);
+        }
+        self.regime = regime;
+
+        tracing::debug!("FundingRateArb: funding rate {} crossed threshold {}, generating {:?} signal", funding_rate, threshold, side);
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: dec!(1.0),
+            intent: Some(SignalIntent::Reverse),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: self.symbol.clone(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO, // Let the risk manager determine the size
+                price: None,
+                position_side: None, // Will be set by engine
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None, // Linked to the signal's decision by the engine
+            },
+        }))
+    }
+}
@@ -0,0 +1,208 @@
+//! A cache of indicator series shared by the strategies of an optimization job.
+//!
+//! Every run of a job replays the same klines, so parameter sets with an indicator in common
+//! (the same 200-bar trend filter, say) each compute the same series. An `IndicatorCache`
+//! holds the job's klines and computes each series once, when it is first asked for;
+//! strategies given one read their indicators from it bar by bar. Strategies without one, as
+//! in live trading, update their indicators themselves.
+
+use core_types::Kline;
+use rust_decimal::prelude::ToPrimitive;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use ta::indicators::{AverageTrueRange, ExponentialMovingAverage as Ema, SimpleMovingAverage as Sma};
+use ta::{Next, Reset};
+
+use crate::moving_average::MovingAverage;
+
+/// An indicator the cache computes, with its period. Each is fed closes, as the strategies
+/// feed their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Indicator {
+    Sma(usize),
+    Ema(usize),
+    /// The average true range of closes alone, i.e. of the moves from one close to the next.
+    Atr(usize),
+}
+
+impl Indicator {
+    /// The indicator's values after each of `closes`, or `None` if its period is invalid.
+    fn compute(self, closes: impl Iterator<Item = f64>) -> Option<Vec<f64>> {
+        Some(match self {
+            Self::Sma(period) => {
+                let mut sma = Sma::new(period).ok()?;
+                closes.map(|close| Next::next(&mut sma, close)).collect()
+            }
+            Self::Ema(period) => {
+                let mut ema = Ema::new(period).ok()?;
+                closes.map(|close| Next::next(&mut ema, close)).collect()
+            }
+            Self::Atr(period) => {
+                let mut atr = AverageTrueRange::new(period).ok()?;
+                closes.map(|close| Next::next(&mut atr, close)).collect()
+            }
+        })
+    }
+}
+
+/// A series, computed by whichever reader asks for it first; `None` if its period is invalid.
+type SharedSeries = Arc<OnceLock<Option<Arc<Vec<f64>>>>>;
+
+/// Indicator series over one run of klines, computed on demand and shared between threads.
+///
+/// The averages start up over their first bars (and an EMA never forgets them), so a series
+/// depends on the bar it starts from as well as on the indicator: it is only shared by
+/// strategies that see their first kline on the same bar, i.e. runs warming up for as long.
+pub struct IndicatorCache {
+    klines: Arc<Vec<Kline>>,
+    series: Mutex<HashMap<(Indicator, usize), SharedSeries>>,
+    computed: AtomicUsize,
+}
+
+impl IndicatorCache {
+    /// A cache of series over `klines`, which must be in open-time order.
+    pub fn new(klines: Arc<Vec<Kline>>) -> Self {
+        Self { klines, series: Mutex::new(HashMap::new()), computed: AtomicUsize::new(0) }
+    }
+
+    pub fn klines(&self) -> &[Kline] {
+        &self.klines
+    }
+
+    /// How many series have been computed so far. Each is computed once, however many
+    /// strategies read it.
+    pub fn computed_series(&self) -> usize {
+        self.computed.load(Ordering::Relaxed)
+    }
+
+    /// `indicator` over the klines from position `start` on. Callers asking for a series
+    /// while another computes it wait for that computation rather than repeating it.
+    fn series(&self, indicator: Indicator, start: usize) -> Option<Arc<Vec<f64>>> {
+        let cell = self.series.lock().unwrap().entry((indicator, start)).or_default().clone();
+        cell.get_or_init(|| {
+            self.computed.fetch_add(1, Ordering::Relaxed);
+            indicator.compute(self.klines[start..].iter().map(close)).map(Arc::new)
+        })
+        .clone()
+    }
+
+    /// The position of `kline` among the cached klines, if it is one of them.
+    fn position(&self, kline: &Kline) -> Option<usize> {
+        let position = self.klines.binary_search_by_key(&kline.open_time, |cached| cached.open_time).ok()?;
+        same_bar(&self.klines[position], kline).then_some(position)
+    }
+}
+
+/// Whether a strategy fed `kline` would feed its indicators what the cache fed them for
+/// `cached`. A transformed kline (Heikin-Ashi, say) opens at the same time but closes elsewhere.
+fn same_bar(cached: &Kline, kline: &Kline) -> bool {
+    cached.open_time == kline.open_time && cached.close == kline.close
+}
+
+fn close(kline: &Kline) -> f64 {
+    kline.close.to_f64().unwrap_or(f64::NAN)
+}
+
+/// An indicator a strategy updates with one close at a time.
+pub trait CloseIndicator: Send + Sync {
+    fn next(&mut self, close: f64) -> f64;
+    fn reset(&mut self);
+}
+
+impl CloseIndicator for MovingAverage {
+    fn next(&mut self, close: f64) -> f64 {
+        MovingAverage::next(self, close)
+    }
+
+    fn reset(&mut self) {
+        MovingAverage::reset(self)
+    }
+}
+
+impl CloseIndicator for Sma {
+    fn next(&mut self, close: f64) -> f64 {
+        Next::next(self, close)
+    }
+
+    fn reset(&mut self) {
+        Reset::reset(self)
+    }
+}
+
+impl CloseIndicator for AverageTrueRange {
+    fn next(&mut self, close: f64) -> f64 {
+        Next::next(self, close)
+    }
+
+    fn reset(&mut self) {
+        Reset::reset(self)
+    }
+}
+
+/// A strategy's indicator, read from an `IndicatorCache` while the strategy is fed the cached
+/// klines, and computed by the strategy itself otherwise.
+///
+/// Without a cache, or from the first kline the cache does not hold, the values come from
+/// `live`. Leaving the cached klines part way through first replays the bars seen so far
+/// into `live`, so the values are the same either way.
+pub struct CachedIndicator<I> {
+    live: I,
+    /// What `live` computes, if the cache can compute it too.
+    indicator: Option<Indicator>,
+    cache: Option<Arc<IndicatorCache>>,
+    progress: Progress,
+}
+
+enum Progress {
+    /// No kline seen since creation or the last reset.
+    Fresh,
+    /// Reading `series`, which starts at the cached kline at `start`, after `seen` klines.
+    Cached { series: Arc<Vec<f64>>, start: usize, seen: usize },
+    /// Computing the values with the live indicator.
+    Live,
+}
+
+impl<I: CloseIndicator> CachedIndicator<I> {
+    /// Wraps `live`, which computes `indicator`, or something the cache cannot if `None`.
+    pub fn new(live: I, indicator: Option<Indicator>) -> Self {
+        Self { live, indicator, cache: None, progress: Progress::Fresh }
+    }
+
+    /// Reads the indicator from `cache` from the next reset on (or from the first kline).
+    pub fn use_cache(&mut self, cache: Arc<IndicatorCache>) {
+        self.cache = Some(cache);
+    }
+
+    /// The indicator's value after `kline`'s close.
+    pub fn next(&mut self, kline: &Kline) -> f64 {
+        if let (Some(cache), Some(indicator)) = (&self.cache, self.indicator) {
+            if let Progress::Fresh = self.progress {
+                self.progress = cache
+                    .position(kline)
+                    .and_then(|start| Some(Progress::Cached { series: cache.series(indicator, start)?, start, seen: 0 }))
+                    .unwrap_or(Progress::Live);
+            }
+            if let Progress::Cached { series, start, seen } = &mut self.progress {
+                let position = *start + *seen;
+                if let (Some(cached), Some(value)) = (cache.klines.get(position), series.get(*seen))
+                    && same_bar(cached, kline)
+                {
+                    *seen += 1;
+                    return *value;
+                }
+                for cached in &cache.klines[*start..position] {
+                    self.live.next(close(cached));
+                }
+            }
+        }
+        self.progress = Progress::Live;
+        self.live.next(close(kline))
+    }
+
+    /// Forgets every kline seen so far.
+    pub fn reset(&mut self) {
+        self.live.reset();
+        self.progress = Progress::Fresh;
+    }
+}
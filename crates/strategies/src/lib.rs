@@ -16,31 +16,50 @@
 //! ## Public API
 //!
 //! The primary public components are:
-//! - `Strategy`: The core trait all strategies implement.
+//! - `Strategy`: The core trait all strategies implement. Strategies that need more than
+//!   klines (e.g., funding rates) override `evaluate_with_context`.
 //! - `StrategyId`: A simple enum to identify which strategy to create.
 //! - `create_strategy`: The factory function to construct a strategy instance.
-//! - The concrete strategy structs themselves (e.g., `MACrossover`).
+//! - `create_strategy_from_params`: Constructs a strategy from a raw JSON parameter set.
+//! - `parameter_violations`: Lists the rules a raw JSON parameter set breaks, without
+//!   constructing the strategy.
+//! - `create_strategy_from_params_with_cache`: The same, with the strategy reading its
+//!   indicators from an `IndicatorCache` shared by an optimization job's runs.
+//! - `KlineTransformer`: Preprocesses klines (e.g., Heikin-Ashi) before a strategy sees them.
+//! - The concrete strategy structs themselves (e.g., `MACrossover`), `Ensemble`, which
+//!   trades on a vote of other strategies, and `VolumeFilter`, which drops another
+//!   strategy's signals on low-volume bars.
 
 // Declare all the modules that constitute this crate.
+pub mod ensemble;
 pub mod error;
 pub mod factory;
 pub mod funding_rate_arb;
+pub mod indicator_cache;
 pub mod ma_crossover;
+pub mod moving_average;
 pub mod prob_reversion;
 pub mod super_trend;
 pub mod ml_strategy;
+pub mod transform;
+pub mod volume_filter;
 // Re-export the key components to create a clean, public-facing API.
+pub use ensemble::Ensemble;
 pub use error::StrategyError;
-pub use factory::create_strategy;
+pub use factory::{create_strategy, create_strategy_from_params, create_strategy_from_params_with_cache, merge_params, parameter_violations, strategy_params};
 pub use funding_rate_arb::FundingRateArb;
+pub use indicator_cache::{Indicator, IndicatorCache};
 pub use ma_crossover::MACrossover;
+pub use moving_average::{MaType, MovingAverage};
 pub use prob_reversion::ProbReversion;
 pub use super_trend::SuperTrend;
+pub use transform::{HeikinAshi, KlineTransformer};
+pub use volume_filter::{RollingMean, VolumeFilter};
 
 // Re-export StrategyId from core_types
 pub use core_types::enums::StrategyId;
 
-use core_types::{Kline, Signal};
+use core_types::{Kline, MarketContext, Signal};
 
 /// The core trait that all trading strategies must implement.
 ///
@@ -61,7 +80,34 @@ pub trait Strategy: Send + Sync {
     /// # Returns
     ///
     /// * `Ok(Some(Signal))` - if the strategy's conditions are met to generate a trade signal.
+    ///   The signal's `intent` says whether it opens, closes or reverses the position.
     /// * `Ok(None)` - if the strategy's conditions are not met, and no action should be taken.
     /// * `Err(StrategyError)` - if an error occurs during evaluation.
     fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError>;
+
+    /// Evaluates the strategy with real-time market context (funding rate, mark price, etc.)
+    /// alongside the new Kline bar. The live engine calls this method.
+    ///
+    /// Strategies that only need klines can ignore it: the default implementation simply
+    /// delegates to `evaluate`.
+    fn evaluate_with_context(&mut self, kline: &Kline, _context: &MarketContext) -> Result<Option<Signal>, StrategyError> {
+        self.evaluate(kline)
+    }
+
+    /// The number of bars the strategy must see before its signals are meaningful, i.e. how
+    /// long its indicators take to warm up.
+    ///
+    /// The backtester feeds this many bars from before the start of the test range through
+    /// the strategy first, so the range is traded from its first bar. The default is zero,
+    /// for strategies without indicators.
+    fn required_warmup_bars(&self) -> usize {
+        0
+    }
+
+    /// Clears everything the strategy has learned from the klines seen so far, leaving it as
+    /// freshly created.
+    ///
+    /// The live engine calls this after `evaluate` fails, then feeds the strategy its recent
+    /// klines again to re-warm it. The default does nothing, for stateless strategies.
+    fn reset(&mut self) {}
 }
\ No newline at end of file
@@ -1,48 +1,128 @@
 use crate::error::StrategyError;
+use crate::indicator_cache::{CachedIndicator, Indicator, IndicatorCache};
+use crate::moving_average::{MaType, MovingAverage};
 use crate::Strategy;
 use configuration::MACrossoverParams;
-use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal};
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 use ta::indicators::SimpleMovingAverage as Sma;
-use ta::Next;
+use std::sync::Arc;
 use uuid::Uuid;
 
-/// The Triple Moving Average Crossover strategy.
+/// The Triple Moving Average Crossover strategy, or a plain dual crossover with the trend
+/// filter disabled.
 pub struct MACrossover {
     symbol: String,
-    ma_fast: Sma,
-    ma_slow: Sma,
-    trend_filter: Sma,
+    ma_fast: CachedIndicator<MovingAverage>,
+    ma_slow: CachedIndicator<MovingAverage>,
+    // None when `trend_filter_period` is zero, which trades every confirmed crossover.
+    trend_filter: Option<CachedIndicator<Sma>>,
     // State: The previous values of the fast and slow MAs to detect a crossover event.
-    prev_fast_ma: Option<Decimal>,
-    prev_slow_ma: Option<Decimal>,
+    prev_fast_ma: Option<f64>,
+    prev_slow_ma: Option<f64>,
+    // How many further closes a crossover must hold for before it is traded.
+    confirmation_bars: usize,
+    // State: The side of the latest crossover still awaiting confirmation, and how many
+    // closes it has held for since.
+    pending_cross: Option<(OrderSide, usize)>,
+    // The longest of the MA periods, a disabled trend filter aside.
+    warmup_bars: usize,
 }
 
 impl MACrossover {
     /// Creates a new `MACrossover` instance with the given parameters.
     ///
-    /// It performs validation to ensure the parameters are logical.
+    /// It fails with every rule of `parameter_violations` the parameters break.
     pub fn new(params: MACrossoverParams, symbol: String) -> Result<Self, StrategyError> {
-        // Validation: Ensure periods are logical.
-        if params.ma_fast_period >= params.ma_slow_period {
-            return Err(StrategyError::InvalidParameters(
-                "Fast MA period must be less than Slow MA period".to_string(),
-            ));
-        }
+        StrategyError::check_parameters("MACrossover", Self::parameter_violations(&params))?;
+        let ma_type: MaType = params.ma_type.parse()?;
+        let trend_filter = match params.trend_filter_period {
+            0 => None,
+            period => Some(CachedIndicator::new(
+                Sma::new(period).map_err(|e| {
+                    StrategyError::InvalidParameters(format!("Failed to initialize trend filter: {:?}", e))
+                })?,
+                Some(Indicator::Sma(period)),
+            )),
+        };
 
         Ok(Self {
             symbol,
-            ma_fast: Sma::new(params.ma_fast_period).unwrap(),
-            ma_slow: Sma::new(params.ma_slow_period).unwrap(),
-            trend_filter: Sma::new(params.trend_filter_period).unwrap(),
+            ma_fast: CachedIndicator::new(
+                MovingAverage::new(ma_type, params.ma_fast_period)?,
+                ma_type.cached_indicator(params.ma_fast_period),
+            ),
+            ma_slow: CachedIndicator::new(
+                MovingAverage::new(ma_type, params.ma_slow_period)?,
+                ma_type.cached_indicator(params.ma_slow_period),
+            ),
+            trend_filter,
             prev_fast_ma: None,
             prev_slow_ma: None,
+            confirmation_bars: params.confirmation_bars,
+            pending_cross: None,
+            warmup_bars: ma_type
+                .warmup_bars(params.ma_slow_period)
+                .max(params.trend_filter_period)
+                + params.confirmation_bars,
         })
     }
+
+    /// Reads the moving averages from `cache` where it holds the klines evaluated, rather
+    /// than computing them.
+    pub fn with_indicator_cache(mut self, cache: Arc<IndicatorCache>) -> Self {
+        self.ma_fast.use_cache(cache.clone());
+        self.ma_slow.use_cache(cache.clone());
+        if let Some(trend_filter) = &mut self.trend_filter {
+            trend_filter.use_cache(cache);
+        }
+        self
+    }
+
+    /// The rules `params` break, each described in one message: the fast MA must be
+    /// shorter than the slow one, and the trend filter at least as long as the slow MA, or
+    /// zero to disable it.
+    pub fn parameter_violations(params: &MACrossoverParams) -> Vec<String> {
+        let mut violations = Vec::new();
+        if params.ma_fast_period == 0 {
+            violations.push("ma_fast_period must be greater than 0".to_string());
+        }
+        if params.ma_fast_period >= params.ma_slow_period {
+            violations.push(format!(
+                "ma_fast_period ({}) must be less than ma_slow_period ({})",
+                params.ma_fast_period, params.ma_slow_period
+            ));
+        }
+        if params.trend_filter_period != 0 && params.trend_filter_period < params.ma_slow_period {
+            violations.push(format!(
+                "trend_filter_period ({}) must be at least ma_slow_period ({}), or 0 to disable the filter",
+                params.trend_filter_period, params.ma_slow_period
+            ));
+        }
+        if let Err(StrategyError::InvalidParameters(message)) = params.ma_type.parse::<MaType>() {
+            violations.push(message);
+        }
+        violations
+    }
 }
 
 impl Strategy for MACrossover {
+    fn required_warmup_bars(&self) -> usize {
+        self.warmup_bars
+    }
+
+    fn reset(&mut self) {
+        self.ma_fast.reset();
+        self.ma_slow.reset();
+        if let Some(trend_filter) = &mut self.trend_filter {
+            trend_filter.reset();
+        }
+        self.prev_fast_ma = None;
+        self.prev_slow_ma = None;
+        self.pending_cross = None;
+    }
+
     /// Evaluates the triple MA strategy.
     ///
     /// A buy signal is generated when the fast MA crosses above the slow MA,
@@ -50,19 +130,24 @@ impl Strategy for MACrossover {
     ///
     /// A sell signal is generated when the fast MA crosses below the slow MA,
     /// AND the closing price is below the long-term trend filter MA.
+    ///
+    /// With the trend filter disabled, every crossover is traded.
+    ///
+    /// With `confirmation_bars` set, a crossover is only acted on once the fast MA has
+    /// stayed on the new side for that many further closes; the trend filter is checked on
+    /// the confirming bar. Crossing back before then discards it.
     fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
         tracing::debug!("MACrossover: Evaluating kline for symbol {}: {:?}", self.symbol, kline);
+
+        // Calculate the current values for all three moving averages. The indicators work
+        // in `f64`, a controlled and accepted precision trade-off against our `Decimal`s. The
+        // crossover is detected on those values; only the trend filter, compared with the
+        // close, is converted, and only on a bar that confirms a crossover.
+        let current_fast_ma = self.ma_fast.next(kline);
+        let current_slow_ma = self.ma_slow.next(kline);
+        let trend_filter_ma = self.trend_filter.as_mut().map(|sma| sma.next(kline));
         
-        // The `ta` crate uses `f64`. We must convert from our high-precision `Decimal`.
-        // This is a controlled and accepted precision trade-off for using the library.
-        let close_f64 = kline.close.to_f64().unwrap();
-
-        // Calculate the current values for all three moving averages.
-        let current_fast_ma = Decimal::from_f64(self.ma_fast.next(close_f64)).unwrap();
-        let current_slow_ma = Decimal::from_f64(self.ma_slow.next(close_f64)).unwrap();
-        let trend_filter_ma = Decimal::from_f64(self.trend_filter.next(close_f64)).unwrap();
-        
-        tracing::debug!("MACrossover: MAs - Fast: {}, Slow: {}, Trend: {}", current_fast_ma, current_slow_ma, trend_filter_ma);
+        tracing::debug!("MACrossover: MAs - Fast: {}, Slow: {}, Trend: {:?}", current_fast_ma, current_slow_ma, trend_filter_ma);
 
         let mut signal = None;
 
@@ -75,23 +160,46 @@ impl Strategy for MACrossover {
 
             // Bullish Crossover Check (Fast crosses Above Slow)
             let is_bullish_cross = prev_fast <= prev_slow && current_fast_ma > current_slow_ma;
-            // Trend Filter Check
-            let is_uptrend = kline.close > trend_filter_ma;
-
             // Bearish Crossover Check (Fast crosses Below Slow)
             let is_bearish_cross = prev_fast >= prev_slow && current_fast_ma < current_slow_ma;
-            // Trend Filter Check
-            let is_downtrend = kline.close < trend_filter_ma;
+
+            // A new crossover starts its confirmation count; an existing one counts another
+            // close if it still holds and is discarded if it has un-crossed.
+            if is_bullish_cross {
+                self.pending_cross = Some((OrderSide::Buy, 0));
+            } else if is_bearish_cross {
+                self.pending_cross = Some((OrderSide::Sell, 0));
+            } else if let Some((side, held)) = self.pending_cross {
+                let still_crossed = match side {
+                    OrderSide::Buy => current_fast_ma > current_slow_ma,
+                    OrderSide::Sell => current_fast_ma < current_slow_ma,
+                };
+                self.pending_cross = still_crossed.then_some((side, held + 1));
+            }
+            let confirmed = match self.pending_cross {
+                Some((side, held)) if held == self.confirmation_bars => {
+                    self.pending_cross = None;
+                    Some(side)
+                }
+                _ => None,
+            };
+
+            // Trend Filter Checks (both pass with the filter disabled)
+            let trend_filter_ma = trend_filter_ma.filter(|_| confirmed.is_some()).map(|ma| Decimal::from_f64(ma).unwrap());
+            let is_uptrend = trend_filter_ma.is_none_or(|ma| kline.close > ma);
+            let is_downtrend = trend_filter_ma.is_none_or(|ma| kline.close < ma);
             
-            tracing::debug!("MACrossover: Checks - Bullish cross: {}, Uptrend: {}, Bearish cross: {}, Downtrend: {}", 
-                           is_bullish_cross, is_uptrend, is_bearish_cross, is_downtrend);
+            tracing::debug!("MACrossover: Checks - Bullish cross: {}, Bearish cross: {}, Confirmed: {:?}, Uptrend: {}, Downtrend: {}", 
+                           is_bullish_cross, is_bearish_cross, confirmed, is_uptrend, is_downtrend);
             
-            if is_bullish_cross && is_uptrend {
+            if confirmed == Some(OrderSide::Buy) && is_uptrend {
                 tracing::debug!("MACrossover: Generating BUY signal");
                 signal = Some(Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence: dec!(1.0), // Full confidence on clear signal
+                    intent: Some(SignalIntent::OpenLong),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -100,14 +208,19 @@ impl Strategy for MACrossover {
                         quantity: Decimal::ZERO, // Let the risk manager determine the size
                         price: None,
                         position_side: None, // Will be set by engine
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 });
-            } else if is_bearish_cross && is_downtrend {
+            } else if confirmed == Some(OrderSide::Sell) && is_downtrend {
                 tracing::debug!("MACrossover: Generating SELL signal");
                 signal = Some(Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence: dec!(1.0), // Full confidence on clear signal
+                    intent: Some(SignalIntent::OpenShort),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -116,6 +229,9 @@ impl Strategy for MACrossover {
                         quantity: Decimal::ZERO, // Let the risk manager determine the size
                         price: None,
                         position_side: None, // Will be set by engine
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 });
             }
@@ -1,11 +1,13 @@
+use crate::volume_filter::RollingMean;
 use crate::{Strategy, StrategyError};
-use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal};
-use ml_features::generate_features;
+use configuration::settings::MlStrategyParams;
+use core_types::{BookSummary, Kline, MarketContext, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
+use ml_features::microstructure::MICROSTRUCTURE_FEATURES;
+use ml_features::{LiveFeatures, FEATURE_LOOKBACKS, MAX_FEATURE_LOOKBACK};
 use polars::prelude::*;
 use smartcore::ensemble::random_forest_classifier::RandomForestClassifier;
 use smartcore::linalg::basic::matrix::DenseMatrix;
 use std::fs::File;
-use std::path::PathBuf;
 use uuid::Uuid;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::*;
@@ -108,16 +110,26 @@ type ModelArtifact = (
 /// The MlStrategy uses a pre-trained model to make decisions.
 pub struct MlStrategy {
     model: RandomForestClassifier<f64, i32, DenseMatrix<f64>, Vec<i32>>,
-    kline_buffer: Vec<Kline>,
-    min_buffer_size: usize,
+    /// Computes each kline's features, including the microstructure features when the model
+    /// was trained on them.
+    features: LiveFeatures,
+    /// No signal is emitted before this many klines have been seen.
+    min_bars_before_trading: usize,
     symbol: String,
     scaler: FeatureScaler,
     prediction_threshold: f64,
+    /// The mean volume of the recent bars, which raises the confidence of signals on busy bars.
+    volume: RollingMean,
 }
 
 impl MlStrategy {
     /// Creates a new `MlStrategy` by loading a serialized model from disk.
-    pub fn new(model_path: &PathBuf, symbol: String) -> Result<Self, StrategyError> {
+    ///
+    /// A `min_bars_before_trading` below the longest feature lookback would feed the model
+    /// features it was never trained on, so it is raised to that lookback, with a warning.
+    pub fn new(params: MlStrategyParams, symbol: String) -> Result<Self, StrategyError> {
+        StrategyError::check_parameters("MlStrategy", Self::parameter_violations(&params))?;
+        let model_path = &params.model_path;
         let file = File::open(model_path).map_err(|e| {
             StrategyError::InvalidParameters(format!(
                 "Failed to open model file at {:?}: {}",
@@ -144,42 +156,102 @@ impl MlStrategy {
             artifact_metadata.preprocessing_info.scaler_stds.clone(),
         );
 
+        let with_book = artifact_metadata
+            .feature_names
+            .iter()
+            .any(|name| MICROSTRUCTURE_FEATURES.contains(&name.as_str()));
+        if with_book {
+            tracing::info!(symbol = %symbol, "ML model uses order book features; klines without book data are skipped.");
+        }
+
+        let min_bars_before_trading = match params.min_bars_before_trading {
+            Some(min_bars) if min_bars < MAX_FEATURE_LOOKBACK => {
+                let lookbacks: Vec<String> = FEATURE_LOOKBACKS.iter().map(|(name, bars)| format!("{} {}", name, bars)).collect();
+                tracing::warn!(
+                    symbol = %symbol,
+                    "MlStrategy's min_bars_before_trading of {} is below its features' lookbacks ({}); waiting for {} bars instead.",
+                    min_bars,
+                    lookbacks.join(", "),
+                    MAX_FEATURE_LOOKBACK
+                );
+                MAX_FEATURE_LOOKBACK
+            }
+            Some(min_bars) => min_bars,
+            None => MAX_FEATURE_LOOKBACK,
+        };
+
         Ok(Self {
             model,
-            kline_buffer: Vec::with_capacity(500), // Pre-allocate buffer
-            min_buffer_size: 5, // Reduced from 252 to 60 for faster warm-up
+            features: LiveFeatures::with_capacity(with_book, params.buffer_capacity.unwrap_or(LiveFeatures::DEFAULT_CAPACITY)),
+            min_bars_before_trading,
             symbol,
             scaler,
             prediction_threshold: 0.5, // Only trade when model is confident
+            volume: RollingMean::new(20),
         })
     }
+
+    /// The rules `params` breaks. An empty list means `new` accepts them, if the model loads.
+    pub fn parameter_violations(params: &MlStrategyParams) -> Vec<String> {
+        let mut violations = Vec::new();
+        if params.model_path.as_os_str().is_empty() {
+            violations.push("a `model_path` is required".to_string());
+        }
+        let capacity = params.buffer_capacity.unwrap_or(LiveFeatures::DEFAULT_CAPACITY);
+        if capacity < MAX_FEATURE_LOOKBACK {
+            violations.push(format!(
+                "buffer_capacity ({}) must cover the longest feature lookback ({} bars)",
+                capacity, MAX_FEATURE_LOOKBACK
+            ));
+        }
+        if let Some(min_bars) = params.min_bars_before_trading
+            && min_bars > capacity
+        {
+            violations.push(format!("min_bars_before_trading ({}) must not exceed buffer_capacity ({})", min_bars, capacity));
+        }
+        violations
+    }
 }
 
 impl Strategy for MlStrategy {
-    #[tracing::instrument(name = "ml_strategy_evaluate", skip(self, kline))]
+    fn required_warmup_bars(&self) -> usize {
+        self.min_bars_before_trading
+    }
+
+    fn reset(&mut self) {
+        self.features.reset();
+        self.volume.reset();
+    }
+
+    /// Without market context there is no book data, so a model trained on order book
+    /// features sees them missing and makes no prediction.
     fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        self.evaluate_with_book(kline, None)
+    }
 
-        // 1. Update the historical buffer.
-        self.kline_buffer.push(kline.clone());
-        
-        // Maintain a max buffer size to prevent memory leaks over long runs.
-        if self.kline_buffer.len() > 500 {
-            self.kline_buffer.remove(0);
-        }
+    fn evaluate_with_context(&mut self, kline: &Kline, context: &MarketContext) -> Result<Option<Signal>, StrategyError> {
+        self.evaluate_with_book(kline, context.book.as_ref())
+    }
+}
 
-        // 2. Wait for the buffer to warm up.
-        if self.kline_buffer.len() < self.min_buffer_size {
-            return Ok(None); // Not enough data to generate features yet.
-        }
-        
-        // 3. Generate features for the entire buffer.
-        let features_df = generate_features(&self.kline_buffer)
+impl MlStrategy {
+    #[tracing::instrument(name = "ml_strategy_evaluate", skip(self, kline, book))]
+    fn evaluate_with_book(&mut self, kline: &Kline, book: Option<&BookSummary>) -> Result<Option<Signal>, StrategyError> {
+
+        self.volume.push(kline.volume);
+
+        // 1. Compute the kline's features, exactly as the dataset generator does.
+        let last_features = self.features.push(kline, book)
             .map_err(|e| StrategyError::IndicatorError(e.to_string()))?
             .drop_nulls::<&str>(None)
             .map_err(|e| StrategyError::IndicatorError(e.to_string()))?;
+
+        // 2. Wait until every feature has the klines it looks back over.
+        if self.features.klines().len() < self.min_bars_before_trading {
+            return Ok(None);
+        }
         
-        // We only care about the features for the most recent kline.
-        let last_features = features_df.tail(Some(1));
+        // 3. We only care about the features for the most recent kline.
         if last_features.height() == 0 {
             return Ok(None); // Not enough data to generate a full feature set for the last bar
         }
@@ -213,31 +285,11 @@ impl Strategy for MlStrategy {
             
         let prediction_value = prediction.first().unwrap_or(&0);
 
-        // 6. Add market condition filters before generating signals
-        let current_volume = kline.volume.to_f64().unwrap_or(0.0);
-        let avg_volume = if self.kline_buffer.len() >= 20 {
-            let recent_volumes: Vec<f64> = self.kline_buffer
-                .iter()
-                .rev()
-                .take(20)
-                .map(|k| k.volume.to_f64().unwrap_or(0.0))
-                .collect();
-            recent_volumes.iter().sum::<f64>() / recent_volumes.len() as f64
-        } else {
-            current_volume
-        };
-
-        // Only trade during higher volume periods (liquidity filter)
-        if current_volume < avg_volume * 0.8 {
-            tracing::debug!(
-                current_volume = %current_volume,
-                avg_volume = %avg_volume,
-                "Skipping signal due to low volume"
-            );
-            return Ok(None);
-        }
+        // 6. Avoid extreme volatility periods. Low-volume bars are left to a `VolumeFilter`
+        //    around the strategy.
+        let current_volume = kline.volume;
+        let avg_volume = self.volume.mean().unwrap_or(current_volume);
 
-        // Add volatility filter - avoid extreme volatility periods
         let price_range = (kline.high - kline.low).to_f64().unwrap_or(0.0);
         let close_price = kline.close.to_f64().unwrap_or(1.0);
         let volatility_ratio = price_range / close_price;
@@ -257,7 +309,7 @@ impl Strategy for MlStrategy {
                 // Win prediction - Generate BUY signal
                 // Calculate dynamic confidence based on multiple factors
                 let base_confidence: f64 = 0.6; // Base confidence for wins
-                let volume_boost: f64 = if current_volume > avg_volume * 1.2 { 0.1 } else { 0.0 };
+                let volume_boost: f64 = if current_volume > avg_volume * Decimal::new(12, 1) { 0.1 } else { 0.0 };
                 let volatility_penalty: f64 = if volatility_ratio > 0.02 { -0.1 } else { 0.0 };
                 
                 let final_confidence = (base_confidence + volume_boost + volatility_penalty)
@@ -269,8 +321,10 @@ impl Strategy for MlStrategy {
 
                 let signal = Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence,
+                    intent: Some(SignalIntent::OpenLong),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -279,6 +333,9 @@ impl Strategy for MlStrategy {
                         quantity: "1.0".parse().unwrap(), // Placeholder, risk manager will resize
                         price: None,
                         position_side: None, // Use one-way mode for now
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 };
                 tracing::info!(
@@ -293,7 +350,7 @@ impl Strategy for MlStrategy {
                 // Loss prediction - Generate SELL signal
                 // Apply same dynamic confidence calculation
                 let base_confidence: f64 = 0.65; // Slightly higher for loss predictions
-                let volume_boost: f64 = if current_volume > avg_volume * 1.2 { 0.1 } else { 0.0 };
+                let volume_boost: f64 = if current_volume > avg_volume * Decimal::new(12, 1) { 0.1 } else { 0.0 };
                 let volatility_penalty: f64 = if volatility_ratio > 0.02 { -0.1 } else { 0.0 };
                 
                 let final_confidence = (base_confidence + volume_boost + volatility_penalty)
@@ -305,8 +362,10 @@ impl Strategy for MlStrategy {
 
                 let signal = Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence,
+                    intent: Some(SignalIntent::OpenShort),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -315,6 +374,9 @@ impl Strategy for MlStrategy {
                         quantity: "1.0".parse().unwrap(), // Placeholder, risk manager will resize
                         price: None,
                         position_side: None, // Use one-way mode for now
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 };
                 tracing::info!(
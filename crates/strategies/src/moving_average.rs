@@ -0,0 +1,167 @@
+//! The moving averages `MACrossover` can be configured with.
+//!
+//! `ta` provides the simple and exponential averages. The weighted average is implemented
+//! here, and the Hull average is composed from weighted ones.
+
+use crate::error::StrategyError;
+use crate::indicator_cache::Indicator;
+use std::collections::VecDeque;
+use std::str::FromStr;
+use ta::indicators::{ExponentialMovingAverage as Ema, SimpleMovingAverage as Sma};
+use ta::{Next, Reset};
+
+/// The kind of moving average, as named in the strategy parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MaType {
+    Sma,
+    Ema,
+    Wma,
+    Hma,
+}
+
+impl FromStr for MaType {
+    type Err = StrategyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sma" => Ok(Self::Sma),
+            "ema" => Ok(Self::Ema),
+            "wma" => Ok(Self::Wma),
+            "hma" => Ok(Self::Hma),
+            _ => Err(StrategyError::InvalidParameters(format!(
+                "Unknown ma_type '{}'; expected one of \"sma\", \"ema\", \"wma\" or \"hma\"",
+                s
+            ))),
+        }
+    }
+}
+
+impl MaType {
+    /// The number of bars before an average of this type over `period` bars is fully formed.
+    pub fn warmup_bars(self, period: usize) -> usize {
+        match self {
+            Self::Hma => period + hull_smoothing_period(period) - 1,
+            _ => period,
+        }
+    }
+
+    /// The cached indicator an average of this type over `period` bars is, if the
+    /// `IndicatorCache` computes it.
+    pub fn cached_indicator(self, period: usize) -> Option<Indicator> {
+        match self {
+            Self::Sma => Some(Indicator::Sma(period)),
+            Self::Ema => Some(Indicator::Ema(period)),
+            Self::Wma | Self::Hma => None,
+        }
+    }
+}
+
+/// A moving average of any supported type.
+pub enum MovingAverage {
+    Sma(Sma),
+    Ema(Ema),
+    Wma(WeightedMovingAverage),
+    Hma(HullMovingAverage),
+}
+
+impl MovingAverage {
+    pub fn new(ma_type: MaType, period: usize) -> Result<Self, StrategyError> {
+        let invalid = |e: ta::errors::TaError| StrategyError::InvalidParameters(format!("{:?} period {}: {}", ma_type, period, e));
+        Ok(match ma_type {
+            MaType::Sma => Self::Sma(Sma::new(period).map_err(invalid)?),
+            MaType::Ema => Self::Ema(Ema::new(period).map_err(invalid)?),
+            MaType::Wma => Self::Wma(WeightedMovingAverage::new(period)?),
+            MaType::Hma => Self::Hma(HullMovingAverage::new(period)?),
+        })
+    }
+
+    pub fn next(&mut self, value: f64) -> f64 {
+        match self {
+            Self::Sma(ma) => ma.next(value),
+            Self::Ema(ma) => ma.next(value),
+            Self::Wma(ma) => ma.next(value),
+            Self::Hma(ma) => ma.next(value),
+        }
+    }
+
+    /// Forgets every value seen so far.
+    pub fn reset(&mut self) {
+        match self {
+            Self::Sma(ma) => ma.reset(),
+            Self::Ema(ma) => ma.reset(),
+            Self::Wma(ma) => ma.reset(),
+            Self::Hma(ma) => ma.reset(),
+        }
+    }
+}
+
+/// A linearly weighted moving average: the newest value has weight `period`, the oldest 1.
+///
+/// Like `ta`'s averages, it averages over the values seen so far until the window is full.
+pub struct WeightedMovingAverage {
+    period: usize,
+    window: VecDeque<f64>,
+}
+
+impl WeightedMovingAverage {
+    pub fn new(period: usize) -> Result<Self, StrategyError> {
+        if period == 0 {
+            return Err(StrategyError::InvalidParameters("WMA period cannot be zero".to_string()));
+        }
+        Ok(Self { period, window: VecDeque::with_capacity(period) })
+    }
+
+    pub fn next(&mut self, value: f64) -> f64 {
+        if self.window.len() == self.period {
+            self.window.pop_front();
+        }
+        self.window.push_back(value);
+
+        let (weighted_sum, total_weight) = self
+            .window
+            .iter()
+            .zip(1..)
+            .fold((0.0, 0.0), |(sum, weights), (value, weight)| (sum + value * weight as f64, weights + weight as f64));
+        weighted_sum / total_weight
+    }
+
+    pub fn reset(&mut self) {
+        self.window.clear();
+    }
+}
+
+/// The Hull moving average: `WMA(2·WMA(n/2) − WMA(n))` over `√n` bars, which follows price
+/// more closely than an SMA of the same period while staying smooth.
+pub struct HullMovingAverage {
+    half: WeightedMovingAverage,
+    full: WeightedMovingAverage,
+    smoothing: WeightedMovingAverage,
+}
+
+impl HullMovingAverage {
+    pub fn new(period: usize) -> Result<Self, StrategyError> {
+        if period == 0 {
+            return Err(StrategyError::InvalidParameters("HMA period cannot be zero".to_string()));
+        }
+        Ok(Self {
+            half: WeightedMovingAverage::new((period / 2).max(1))?,
+            full: WeightedMovingAverage::new(period)?,
+            smoothing: WeightedMovingAverage::new(hull_smoothing_period(period))?,
+        })
+    }
+
+    pub fn next(&mut self, value: f64) -> f64 {
+        let raw = 2.0 * self.half.next(value) - self.full.next(value);
+        self.smoothing.next(raw)
+    }
+
+    pub fn reset(&mut self) {
+        self.half.reset();
+        self.full.reset();
+        self.smoothing.reset();
+    }
+}
+
+fn hull_smoothing_period(period: usize) -> usize {
+    ((period as f64).sqrt().round() as usize).max(1)
+}
@@ -1,11 +1,13 @@
 use crate::error::StrategyError;
+use crate::indicator_cache::{CachedIndicator, Indicator, IndicatorCache};
 use crate::Strategy;
 use configuration::ProbReversionParams;
-use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal};
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 use ta::indicators::{BollingerBands, RelativeStrengthIndex as Rsi, AverageTrueRange};
-use ta::Next as _;
+use std::sync::Arc;
+use ta::{Next as _, Reset as _};
 use uuid::Uuid;
 
 /// The Probabilistic Mean Reversion strategy.
@@ -20,18 +22,14 @@ pub struct ProbReversion {
     symbol: String,
     bb: BollingerBands,
     rsi: Rsi,
-    atr: AverageTrueRange,  // Using ATR as a trend strength indicator
+    atr: CachedIndicator<AverageTrueRange>,  // Using ATR as a trend strength indicator
     prev_close: f64,        // Track previous close for trend detection
 }
 
 impl ProbReversion {
     /// Creates a new `ProbReversion` instance.
     pub fn new(params: ProbReversionParams, symbol: String) -> Result<Self, StrategyError> {
-        if params.bb_period == 0 || params.rsi_period == 0 || params.adx_period == 0 {
-            return Err(StrategyError::InvalidParameters(
-                "Indicator periods cannot be zero".to_string(),
-            ));
-        }
+        StrategyError::check_parameters("ProbReversion", Self::parameter_violations(&params))?;
 
         Ok(Self {
             bb: BollingerBands::new(
@@ -41,17 +39,69 @@ impl ProbReversion {
             rsi: Rsi::new(params.rsi_period as usize).map_err(|e| 
                 StrategyError::InvalidParameters(format!("Failed to initialize RSI: {:?}", e))
             )?,
-            atr: AverageTrueRange::new(params.adx_period as usize).map_err(|e| 
-                StrategyError::InvalidParameters(format!("Failed to initialize ATR: {:?}", e))
-            )?,
+            atr: CachedIndicator::new(
+                AverageTrueRange::new(params.adx_period).map_err(|e| 
+                    StrategyError::InvalidParameters(format!("Failed to initialize ATR: {:?}", e))
+                )?,
+                Some(Indicator::Atr(params.adx_period)),
+            ),
             params,
             symbol,
             prev_close: 0.0,
         })
     }
+
+    /// Reads the ATR from `cache` where it holds the klines evaluated, rather than computing it.
+    pub fn with_indicator_cache(mut self, cache: Arc<IndicatorCache>) -> Self {
+        self.atr.use_cache(cache);
+        self
+    }
+
+    /// The rules `params` break, each described in one message: non-zero periods, a
+    /// positive band width, an oversold RSI level below the overbought one, and RSI levels
+    /// and ADX threshold between 0 and 100.
+    pub fn parameter_violations(params: &ProbReversionParams) -> Vec<String> {
+        let mut violations = Vec::new();
+        for (name, period) in [("bb_period", params.bb_period), ("rsi_period", params.rsi_period), ("adx_period", params.adx_period)] {
+            if period == 0 {
+                violations.push(format!("{} must be greater than 0", name));
+            }
+        }
+        if params.bb_std_dev <= Decimal::ZERO {
+            violations.push(format!("bb_std_dev ({}) must be greater than 0", params.bb_std_dev));
+        }
+        if params.rsi_oversold >= params.rsi_overbought {
+            violations.push(format!(
+                "rsi_oversold ({}) must be less than rsi_overbought ({})",
+                params.rsi_oversold, params.rsi_overbought
+            ));
+        }
+        for (name, level) in [
+            ("rsi_oversold", params.rsi_oversold),
+            ("rsi_overbought", params.rsi_overbought),
+            ("adx_threshold", params.adx_threshold),
+        ] {
+            if level < Decimal::ZERO || level > Decimal::ONE_HUNDRED {
+                violations.push(format!("{} ({}) must be between 0 and 100", name, level));
+            }
+        }
+        violations
+    }
 }
 
 impl Strategy for ProbReversion {
+    /// The RSI needs one bar beyond its period to form its first price change.
+    fn required_warmup_bars(&self) -> usize {
+        self.params.bb_period.max(self.params.rsi_period + 1).max(self.params.adx_period)
+    }
+
+    fn reset(&mut self) {
+        self.bb.reset();
+        self.rsi.reset();
+        self.atr.reset();
+        self.prev_close = 0.0;
+    }
+
     fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
         // Convert to f64 for `ta` crate compatibility
         let close_f64 = kline.close.to_f64().ok_or_else(|| 
@@ -69,7 +119,7 @@ impl Strategy for ProbReversion {
         // Calculate indicator values
         let bb = self.bb.next(close_f64);
         let rsi_val = self.rsi.next(close_f64);
-        let atr = self.atr.next(close_f64);
+        let atr = self.atr.next(kline);
         
         // Convert to Decimal for comparison with strategy parameters
         let rsi_decimal = Decimal::from_f64(rsi_val).unwrap_or(dec!(0));
@@ -104,8 +154,10 @@ impl Strategy for ProbReversion {
                 // All three conditions met for a BUY signal.
                 signal = Some(Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence: dec!(1.0),
+                    intent: Some(SignalIntent::OpenLong),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -114,14 +166,19 @@ impl Strategy for ProbReversion {
                         quantity: Decimal::ZERO, // Let the risk manager determine the size
                         price: None,
                         position_side: None, // Will be set by engine
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 });
             } else if is_overbought {
                 // All three conditions met for a SELL signal.
                 signal = Some(Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence: dec!(1.0),
+                    intent: Some(SignalIntent::OpenShort),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -130,6 +187,9 @@ impl Strategy for ProbReversion {
                         quantity: Decimal::ZERO, // Let the risk manager determine the size
                         price: None,
                         position_side: None, // Will be set by engine
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 });
             }
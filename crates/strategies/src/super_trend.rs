@@ -1,8 +1,8 @@
-use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal};
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
 use rust_decimal::prelude::*;
 use rust_decimal_macros::dec;
 use ta::indicators::AverageTrueRange;
-use ta::Next as _;
+use ta::{Next as _, Reset as _};
 use uuid::Uuid;
 
 use crate::error::StrategyError;
@@ -31,11 +31,7 @@ pub enum Trend {
 impl SuperTrend {
     /// Creates a new `SuperTrend` instance.
     pub fn new(params: SuperTrendParams, symbol: String) -> Result<Self, StrategyError> {
-        if params.atr_period == 0 {
-            return Err(StrategyError::InvalidParameters(
-                "ATR period cannot be zero".to_string(),
-            ));
-        }
+        StrategyError::check_parameters("SuperTrend", Self::parameter_violations(&params))?;
 
         Ok(Self {
             atr: AverageTrueRange::new(params.atr_period as usize).map_err(|e| {
@@ -48,6 +44,25 @@ impl SuperTrend {
             prev_close: None,
         })
     }
+
+    /// The rules `params` break, each described in one message: non-zero periods, a
+    /// positive ATR multiplier and an ADX threshold between 0 and 100.
+    pub fn parameter_violations(params: &SuperTrendParams) -> Vec<String> {
+        let mut violations = Vec::new();
+        if params.atr_period == 0 {
+            violations.push("atr_period must be greater than 0".to_string());
+        }
+        if params.adx_period == 0 {
+            violations.push("adx_period must be greater than 0".to_string());
+        }
+        if params.atr_multiplier <= Decimal::ZERO {
+            violations.push(format!("atr_multiplier ({}) must be greater than 0", params.atr_multiplier));
+        }
+        if params.adx_threshold < Decimal::ZERO || params.adx_threshold > Decimal::ONE_HUNDRED {
+            violations.push(format!("adx_threshold ({}) must be between 0 and 100", params.adx_threshold));
+        }
+        violations
+    }
     
     /// Calculate the SuperTrend indicator value
     fn calculate_supertrend(&mut self, high: f64, low: f64, close: f64) -> (f64, Trend) {
@@ -110,6 +125,19 @@ impl SuperTrend {
 }
 
 impl Strategy for SuperTrend {
+    /// The ATR must fill before the bands settle, and the ADX trend filter then needs its own
+    /// period on top of that.
+    fn required_warmup_bars(&self) -> usize {
+        self.params.atr_period + self.params.adx_period
+    }
+
+    fn reset(&mut self) {
+        self.atr.reset();
+        self.supertrend_value = 0.0;
+        self.trend_direction = None;
+        self.prev_close = None;
+    }
+
     fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
         // Convert Decimals to f64 for the `ta` crate.
         let high = kline.high.to_f64().ok_or_else(|| {
@@ -139,8 +167,10 @@ impl Strategy for SuperTrend {
             if is_bullish_flip {
                 signal = Some(Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence: dec!(1.0),
+                    intent: Some(SignalIntent::Reverse),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -149,13 +179,18 @@ impl Strategy for SuperTrend {
                         quantity: Decimal::ZERO, // Let the risk manager determine the size
                         price: None,
                         position_side: None, // Will be set by engine
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 });
             } else if is_bearish_flip {
                 signal = Some(Signal {
                     signal_id: Uuid::new_v4(),
+                    decision_id: Uuid::new_v4(),
                     timestamp: kline.close_time,
                     confidence: dec!(1.0),
+                    intent: Some(SignalIntent::Reverse),
                     order_request: OrderRequest {
                         client_order_id: Uuid::new_v4(),
                         symbol: self.symbol.clone(),
@@ -164,6 +199,9 @@ impl Strategy for SuperTrend {
                         quantity: Decimal::ZERO, // Let the risk manager determine the size
                         price: None,
                         position_side: None, // Will be set by engine
+                        time_in_force: None,
+                        reduce_only: false,
+                        decision_id: None, // Linked to the signal's decision by the engine
                     },
                 });
             }
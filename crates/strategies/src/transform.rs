@@ -0,0 +1,69 @@
+use core_types::{Kline, KlineTransform};
+use rust_decimal::Decimal;
+use std::borrow::Cow;
+
+/// The stateful counterpart of a `KlineTransform` setting.
+///
+/// One instance is kept per symbol, next to that symbol's strategy, and must see every
+/// kline in order. Only the strategy is fed the transformed kline; stop-loss checks and
+/// executions keep using the real one.
+#[derive(Debug, Clone)]
+pub enum KlineTransformer {
+    None,
+    HeikinAshi(HeikinAshi),
+}
+
+impl KlineTransformer {
+    /// Creates a fresh transformer for the given setting.
+    pub fn new(transform: KlineTransform) -> Self {
+        match transform {
+            KlineTransform::None => KlineTransformer::None,
+            KlineTransform::HeikinAshi => KlineTransformer::HeikinAshi(HeikinAshi::default()),
+        }
+    }
+
+    /// Advances the transform by one kline and returns the kline the strategy should see.
+    /// Without a transform, the original kline is borrowed rather than copied.
+    pub fn apply<'a>(&mut self, kline: &'a Kline) -> Cow<'a, Kline> {
+        match self {
+            KlineTransformer::None => Cow::Borrowed(kline),
+            KlineTransformer::HeikinAshi(ha) => Cow::Owned(ha.next(kline)),
+        }
+    }
+}
+
+/// Converts a stream of regular candles into Heikin-Ashi candles.
+///
+/// - HA close = (open + high + low + close) / 4
+/// - HA open  = (previous HA open + previous HA close) / 2, or (open + close) / 2 on the first bar
+/// - HA high  = max(high, HA open, HA close)
+/// - HA low   = min(low, HA open, HA close)
+#[derive(Debug, Clone, Default)]
+pub struct HeikinAshi {
+    // State: The previous HA open and close.
+    prev: Option<(Decimal, Decimal)>,
+}
+
+impl HeikinAshi {
+    /// Computes the Heikin-Ashi candle for `kline` and updates the internal state.
+    pub fn next(&mut self, kline: &Kline) -> Kline {
+        let two = Decimal::TWO;
+        let ha_close = (kline.open + kline.high + kline.low + kline.close) / Decimal::from(4);
+        let ha_open = match self.prev {
+            Some((prev_open, prev_close)) => (prev_open + prev_close) / two,
+            None => (kline.open + kline.close) / two,
+        };
+        self.prev = Some((ha_open, ha_close));
+
+        Kline {
+            open_time: kline.open_time,
+            open: ha_open,
+            high: kline.high.max(ha_open).max(ha_close),
+            low: kline.low.min(ha_open).min(ha_close),
+            close: ha_close,
+            volume: kline.volume,
+            close_time: kline.close_time,
+            interval: kline.interval.clone(),
+        }
+    }
+}
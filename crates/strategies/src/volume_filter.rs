@@ -0,0 +1,133 @@
+use crate::error::StrategyError;
+use crate::Strategy;
+use configuration::{FilteredSignals, VolumeFilterParams};
+use core_types::{Kline, MarketContext, Signal, SignalIntent};
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+
+/// Wraps a strategy and drops its signals on bars that trade too little.
+///
+/// A signal passes when its bar's volume is at least `min_relative_volume` times the mean
+/// volume of the last `lookback` bars, its own included, and its dollar volume (close times
+/// volume) is at least `min_dollar_volume`, if set. Until `lookback` bars have been seen the
+/// relative check passes. With `FilteredSignals::Entries`, closing signals always pass.
+///
+/// The inner strategy sees every kline, so its state is the same as it would be unwrapped.
+pub struct VolumeFilter {
+    inner: Box<dyn Strategy>,
+    params: VolumeFilterParams,
+    // State: The mean volume of the recent bars.
+    volume: RollingMean,
+}
+
+impl VolumeFilter {
+    /// Wraps `inner` in a filter of `params`.
+    ///
+    /// It fails with every rule of `parameter_violations` the parameters break.
+    pub fn new(inner: Box<dyn Strategy>, params: VolumeFilterParams) -> Result<Self, StrategyError> {
+        StrategyError::check_parameters("VolumeFilter", Self::parameter_violations(&params))?;
+        Ok(Self { inner, volume: RollingMean::new(params.lookback), params })
+    }
+
+    /// The rules `params` break, each described in one message: a non-zero lookback and
+    /// non-negative minimums.
+    pub fn parameter_violations(params: &VolumeFilterParams) -> Vec<String> {
+        let mut violations = Vec::new();
+        if params.lookback == 0 {
+            violations.push("lookback must be greater than 0".to_string());
+        }
+        if params.min_relative_volume < Decimal::ZERO {
+            violations.push(format!("min_relative_volume ({}) must not be negative", params.min_relative_volume));
+        }
+        if let Some(min_dollar_volume) = params.min_dollar_volume
+            && min_dollar_volume < Decimal::ZERO
+        {
+            violations.push(format!("min_dollar_volume ({}) must not be negative", min_dollar_volume));
+        }
+        violations
+    }
+
+    /// Records the kline's volume, then lets `signal` through if the bar traded enough.
+    fn filter(&mut self, kline: &Kline, signal: Option<Signal>) -> Option<Signal> {
+        self.volume.push(kline.volume);
+        let signal = signal?;
+        let filtered = match self.params.signals {
+            FilteredSignals::Entries => signal.intent != Some(SignalIntent::Close),
+            FilteredSignals::All => true,
+        };
+        if !filtered {
+            return Some(signal);
+        }
+
+        if let Some(mean) = self.volume.mean()
+            && kline.volume < mean * self.params.min_relative_volume
+        {
+            tracing::debug!(volume = %kline.volume, mean_volume = %mean, "Skipping signal due to low volume");
+            return None;
+        }
+        if let Some(min_dollar_volume) = self.params.min_dollar_volume {
+            let dollar_volume = kline.close * kline.volume;
+            if dollar_volume < min_dollar_volume {
+                tracing::debug!(dollar_volume = %dollar_volume, "Skipping signal due to low dollar volume");
+                return None;
+            }
+        }
+        Some(signal)
+    }
+}
+
+impl Strategy for VolumeFilter {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let signal = self.inner.evaluate(kline)?;
+        Ok(self.filter(kline, signal))
+    }
+
+    fn evaluate_with_context(&mut self, kline: &Kline, context: &MarketContext) -> Result<Option<Signal>, StrategyError> {
+        let signal = self.inner.evaluate_with_context(kline, context)?;
+        Ok(self.filter(kline, signal))
+    }
+
+    fn required_warmup_bars(&self) -> usize {
+        self.inner.required_warmup_bars().max(self.params.lookback)
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.volume.reset();
+    }
+}
+
+/// The mean of the last `window` values pushed, kept as a running sum so each push costs
+/// the same however long the window.
+#[derive(Debug, Clone)]
+pub struct RollingMean {
+    window: usize,
+    values: VecDeque<Decimal>,
+    sum: Decimal,
+}
+
+impl RollingMean {
+    pub fn new(window: usize) -> Self {
+        Self { window, values: VecDeque::with_capacity(window + 1), sum: Decimal::ZERO }
+    }
+
+    pub fn push(&mut self, value: Decimal) {
+        self.values.push_back(value);
+        self.sum += value;
+        if self.values.len() > self.window
+            && let Some(oldest) = self.values.pop_front()
+        {
+            self.sum -= oldest;
+        }
+    }
+
+    /// The mean of the window, or `None` until it is full.
+    pub fn mean(&self) -> Option<Decimal> {
+        (self.window > 0 && self.values.len() == self.window).then(|| self.sum / Decimal::from(self.window))
+    }
+
+    pub fn reset(&mut self) {
+        self.values.clear();
+        self.sum = Decimal::ZERO;
+    }
+}
@@ -0,0 +1,209 @@
+//! Tests for the Ensemble meta-strategy's vote, with scripted members.
+
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
+use rust_decimal::Decimal;
+use serde_json::json;
+use strategies::{create_strategy, create_strategy_from_params, strategy_params, Ensemble, Strategy, StrategyError, StrategyId};
+use testing::{generate_klines, test_config, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// Emits the scripted intent on each bar, then nothing once the script runs out.
+struct Scripted {
+    script: Vec<Option<SignalIntent>>,
+    bar: usize,
+    warmup_bars: usize,
+}
+
+impl Strategy for Scripted {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let intent = self.script.get(self.bar).copied().flatten();
+        self.bar += 1;
+        Ok(intent.map(|intent| signal(kline, intent)))
+    }
+
+    fn required_warmup_bars(&self) -> usize {
+        self.warmup_bars
+    }
+
+    fn reset(&mut self) {
+        self.bar = 0;
+    }
+}
+
+fn signal(kline: &Kline, intent: SignalIntent) -> Signal {
+    let side = match intent {
+        SignalIntent::OpenLong | SignalIntent::Reverse => OrderSide::Buy,
+        SignalIntent::OpenShort | SignalIntent::Close => OrderSide::Sell,
+    };
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: kline.close_time,
+        confidence: Decimal::ONE,
+        intent: Some(intent),
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: TEST_SYMBOL.to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Decimal::ZERO,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        },
+    }
+}
+
+const LONG: Option<SignalIntent> = Some(SignalIntent::OpenLong);
+const SHORT: Option<SignalIntent> = Some(SignalIntent::OpenShort);
+const CLOSE: Option<SignalIntent> = Some(SignalIntent::Close);
+
+fn voting(scripts: Vec<Vec<Option<SignalIntent>>>, min_agreement: usize, stance_bars: usize) -> Ensemble {
+    let members: Vec<Box<dyn Strategy>> = scripts
+        .into_iter()
+        .map(|script| Box::new(Scripted { script, bar: 0, warmup_bars: 0 }) as Box<dyn Strategy>)
+        .collect();
+    Ensemble::with_members(members, min_agreement, stance_bars, TEST_SYMBOL.to_string()).expect("valid ensemble")
+}
+
+/// The bar index, intent, side and confidence of every signal `strategy` emits over `bars` bars.
+fn signals(strategy: &mut dyn Strategy, bars: usize) -> Vec<(usize, SignalIntent, OrderSide, Decimal)> {
+    generate_klines(bars)
+        .iter()
+        .enumerate()
+        .filter_map(|(i, kline)| {
+            strategy
+                .evaluate(kline)
+                .expect("evaluate")
+                .map(|signal| (i, signal.intent.unwrap(), signal.order_request.side, signal.confidence))
+        })
+        .collect()
+}
+
+fn ratio(numerator: i64, denominator: i64) -> Decimal {
+    Decimal::from(numerator) / Decimal::from(denominator)
+}
+
+#[test]
+fn the_ensemble_opens_once_enough_members_agree() {
+    let mut ensemble = voting(vec![vec![LONG], vec![None, LONG], vec![None, None, None, SHORT]], 2, 0);
+
+    // One long vote is not enough; the second opens with a margin of two of three; the short
+    // vote narrows the margin but leaves the side, so nothing more is signalled.
+    assert_eq!(signals(&mut ensemble, 6), [(1, SignalIntent::OpenLong, OrderSide::Buy, ratio(2, 3))]);
+}
+
+#[test]
+fn a_majority_switching_sides_reverses_and_members_closing_flattens() {
+    let mut ensemble = voting(vec![vec![SHORT, None, LONG], vec![SHORT, LONG], vec![SHORT, None, None, CLOSE, CLOSE]], 2, 0);
+
+    assert_eq!(
+        signals(&mut ensemble, 6),
+        [
+            (0, SignalIntent::OpenShort, OrderSide::Sell, Decimal::ONE),
+            // One member turning long leaves two shorts, then a second turns the vote.
+            (2, SignalIntent::Reverse, OrderSide::Buy, ratio(1, 3)),
+        ]
+    );
+
+    // Without a short, the two longs hold until one of them closes.
+    let mut ensemble = ensemble_closing_after(3);
+    assert_eq!(
+        signals(&mut ensemble, 6),
+        [(0, SignalIntent::OpenLong, OrderSide::Buy, Decimal::ONE), (3, SignalIntent::Close, OrderSide::Sell, ratio(1, 2))]
+    );
+}
+
+fn ensemble_closing_after(bars: usize) -> Ensemble {
+    let mut closing = vec![None; bars];
+    closing[0] = LONG;
+    closing.push(CLOSE);
+    voting(vec![vec![LONG], closing], 2, 0)
+}
+
+#[test]
+fn a_tied_vote_is_flat() {
+    // Either side alone is enough, but not against an equal number on the other.
+    let mut ensemble = voting(vec![vec![LONG], vec![None, SHORT], vec![None, None, None, SHORT]], 1, 0);
+
+    assert_eq!(
+        signals(&mut ensemble, 5),
+        [
+            (0, SignalIntent::OpenLong, OrderSide::Buy, ratio(1, 3)),
+            (1, SignalIntent::Close, OrderSide::Sell, Decimal::ZERO),
+            (3, SignalIntent::OpenShort, OrderSide::Sell, ratio(1, 3)),
+        ]
+    );
+}
+
+#[test]
+fn a_stance_lapses_after_stance_bars() {
+    // The long vote counts on its own bar and the two after it.
+    let mut ensemble = voting(vec![vec![LONG, None, None, None, LONG]], 1, 3);
+
+    assert_eq!(
+        signals(&mut ensemble, 6),
+        [
+            (0, SignalIntent::OpenLong, OrderSide::Buy, Decimal::ONE),
+            (3, SignalIntent::Close, OrderSide::Sell, Decimal::ZERO),
+            (4, SignalIntent::OpenLong, OrderSide::Buy, Decimal::ONE),
+        ]
+    );
+
+    // A fresh signal restarts the count.
+    let mut refreshed = voting(vec![vec![LONG, None, LONG]], 1, 3);
+    assert_eq!(
+        signals(&mut refreshed, 7),
+        [(0, SignalIntent::OpenLong, OrderSide::Buy, Decimal::ONE), (5, SignalIntent::Close, OrderSide::Sell, Decimal::ZERO)]
+    );
+}
+
+#[test]
+fn reset_forgets_the_votes_and_warmup_is_the_longest_members() {
+    let members: Vec<Box<dyn Strategy>> = vec![
+        Box::new(Scripted { script: vec![LONG], bar: 0, warmup_bars: 20 }),
+        Box::new(Scripted { script: vec![LONG], bar: 0, warmup_bars: 50 }),
+    ];
+    let mut strategy = Ensemble::with_members(members, 2, 0, TEST_SYMBOL.to_string()).unwrap();
+    assert_eq!(strategy.required_warmup_bars(), 50);
+
+    let expected = signals(&mut strategy, 3);
+    strategy.reset();
+    assert_eq!(signals(&mut strategy, 3), expected);
+}
+
+#[test]
+fn the_factory_builds_an_ensemble_from_the_members_config_sections() {
+    let config = test_config(10).expect("load config");
+    let params = strategy_params(StrategyId::Ensemble, &config).unwrap();
+    assert_eq!(params["member_params"]["MACrossover"], strategy_params(StrategyId::MACrossover, &config).unwrap());
+
+    let mut strategy = create_strategy(StrategyId::Ensemble, &config, TEST_SYMBOL).expect("ensemble from config");
+    for kline in generate_klines(500) {
+        strategy.evaluate(&kline).expect("evaluate");
+    }
+}
+
+#[test]
+fn invalid_ensembles_are_rejected() {
+    let ma_crossover = json!({ "ma_fast_period": 10, "ma_slow_period": 60, "trend_filter_period": 0 });
+    let rejection = |params: serde_json::Value| match create_strategy_from_params(StrategyId::Ensemble, &params, TEST_SYMBOL).err() {
+        Some(StrategyError::InvalidParameters(message)) => message,
+        other => panic!("expected invalid parameters, got {:?}", other.map(|e| e.to_string())),
+    };
+
+    let message = rejection(json!({ "members": ["MACrossover"], "min_agreement": 2, "member_params": { "MACrossover": ma_crossover } }));
+    assert_eq!(message, "Ensemble: min_agreement (2) must be between 1 and the number of members (1)");
+
+    let message = rejection(json!({ "members": ["MACrossover", "Ensemble"], "min_agreement": 1, "member_params": { "MACrossover": ma_crossover } }));
+    assert_eq!(message, "Ensemble: an ensemble cannot be a member of an ensemble");
+
+    let message = rejection(json!({ "members": ["MACrossover", "SuperTrend"], "min_agreement": 1, "member_params": { "MACrossover": ma_crossover } }));
+    assert_eq!(message, "Ensemble: member SuperTrend has no parameters");
+
+    let bad_member = json!({ "ma_fast_period": 60, "ma_slow_period": 10, "trend_filter_period": 0 });
+    let message = rejection(json!({ "members": ["MACrossover"], "min_agreement": 1, "member_params": { "MACrossover": bad_member } }));
+    assert_eq!(message, "Ensemble: MACrossover: ma_fast_period (60) must be less than ma_slow_period (10)");
+}
@@ -0,0 +1,45 @@
+//! Tests for constructing strategies from raw JSON parameter sets.
+
+use serde_json::json;
+use strategies::{create_strategy_from_params, StrategyError, StrategyId};
+use testing::{generate_klines, TEST_SYMBOL};
+
+#[test]
+fn unknown_param_is_rejected_by_name() {
+    let params = json!({
+        "ma_fast_period": 10,
+        "ma_slow_period": 60,
+        "trend_filter_period": 100,
+        "ma_slow_perod": 70,
+    });
+
+    let err = create_strategy_from_params(StrategyId::MACrossover, &params, TEST_SYMBOL)
+        .err()
+        .expect("unknown field must be rejected");
+    match err {
+        StrategyError::InvalidParameters(msg) => assert!(msg.contains("ma_slow_perod"), "message: {}", msg),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+fn valid_ma_crossover_params_build_a_working_strategy() {
+    let params = json!({
+        "ma_fast_period": 10,
+        "ma_slow_period": 60,
+        "trend_filter_period": 100,
+    });
+
+    let mut strategy = create_strategy_from_params(StrategyId::MACrossover, &params, TEST_SYMBOL)
+        .expect("valid params");
+
+    let mut signals = Vec::new();
+    for kline in generate_klines(2000) {
+        if let Some(signal) = strategy.evaluate(&kline).expect("evaluate") {
+            signals.push(signal);
+        }
+    }
+
+    assert!(!signals.is_empty());
+    assert!(signals.iter().all(|s| s.order_request.symbol == TEST_SYMBOL));
+}
@@ -0,0 +1,112 @@
+//! Checks that strategies reading their indicators from an `IndicatorCache` signal exactly as
+//! they do computing them, and that the cache computes each series once.
+
+use chrono::{DateTime, Utc};
+use configuration::MACrossoverParams;
+use core_types::{Kline, OrderSide, SignalIntent};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use strategies::{
+    create_strategy_from_params, create_strategy_from_params_with_cache, strategy_params, HeikinAshi, IndicatorCache, Strategy, StrategyId,
+};
+use testing::synthetic::{regime_switching, Regime, SeriesSpec};
+use testing::{test_config, TEST_SYMBOL};
+
+fn klines() -> Vec<Kline> {
+    let regimes = [Regime::trend(400, 0.002, 0.01), Regime::chop(400, 0.02), Regime::trend(400, -0.002, 0.01)];
+    regime_switching(&SeriesSpec::default(), &regimes, 5)
+}
+
+fn ma_crossover(fast: usize, slow: usize, trend_filter: usize, ma_type: &str) -> JsonValue {
+    serde_json::to_value(MACrossoverParams {
+        ma_fast_period: fast,
+        ma_slow_period: slow,
+        trend_filter_period: trend_filter,
+        ma_type: ma_type.to_string(),
+        confirmation_bars: 0,
+    })
+    .unwrap()
+}
+
+/// The parameter sets compared, with the strategies' cached indicators in common.
+fn parameter_sets() -> Vec<(StrategyId, JsonValue)> {
+    let prob_reversion = strategy_params(StrategyId::ProbReversion, &test_config(100).unwrap()).unwrap();
+    vec![
+        (StrategyId::MACrossover, ma_crossover(10, 60, 200, "sma")),
+        (StrategyId::MACrossover, ma_crossover(20, 60, 200, "sma")),
+        (StrategyId::MACrossover, ma_crossover(10, 60, 0, "ema")),
+        // The cache has no WMA, so only its trend filter is read from it.
+        (StrategyId::MACrossover, ma_crossover(10, 60, 100, "wma")),
+        (StrategyId::ProbReversion, prob_reversion),
+    ]
+}
+
+/// The time, intent and side of each signal `strategy` gives over `klines`.
+fn signals(mut strategy: Box<dyn Strategy>, klines: &[Kline]) -> Vec<(DateTime<Utc>, Option<SignalIntent>, OrderSide)> {
+    klines
+        .iter()
+        .filter_map(|kline| strategy.evaluate(kline).unwrap())
+        .map(|signal| (signal.timestamp, signal.intent, signal.order_request.side))
+        .collect()
+}
+
+#[test]
+fn cached_strategies_signal_as_uncached_ones() {
+    let klines = klines();
+    let cache = Arc::new(IndicatorCache::new(Arc::new(klines.clone())));
+    // Runs of a job warm up for different lengths, so they start on different bars.
+    for start in [0, 37, 200] {
+        for (id, params) in parameter_sets() {
+            let uncached = create_strategy_from_params(id, &params, TEST_SYMBOL).unwrap();
+            let cached = create_strategy_from_params_with_cache(id, &params, TEST_SYMBOL, Some(cache.clone())).unwrap();
+            let expected = signals(uncached, &klines[start..]);
+            assert!(!expected.is_empty(), "{:?} {} gives no signals to compare", id, params);
+            assert_eq!(signals(cached, &klines[start..]), expected, "{:?} {} from bar {}", id, params, start);
+        }
+    }
+}
+
+#[test]
+fn each_series_is_computed_once() {
+    let klines = klines();
+    let cache = Arc::new(IndicatorCache::new(Arc::new(klines.clone())));
+    let params = ma_crossover(10, 60, 200, "sma");
+    let build = || create_strategy_from_params_with_cache(StrategyId::MACrossover, &params, TEST_SYMBOL, Some(cache.clone())).unwrap();
+
+    signals(build(), &klines);
+    assert_eq!(cache.computed_series(), 3);
+    // A run sharing the slow MA and trend filter only adds its fast MA.
+    let faster = ma_crossover(20, 60, 200, "sma");
+    signals(create_strategy_from_params_with_cache(StrategyId::MACrossover, &faster, TEST_SYMBOL, Some(cache.clone())).unwrap(), &klines);
+    assert_eq!(cache.computed_series(), 4);
+
+    // Runs in parallel share the series as well.
+    std::thread::scope(|scope| {
+        for _ in 0..8 {
+            scope.spawn(|| signals(build(), &klines));
+        }
+    });
+    assert_eq!(cache.computed_series(), 4);
+    // A run starting on another bar gets series of its own.
+    signals(build(), &klines[1..]);
+    assert_eq!(cache.computed_series(), 7);
+}
+
+#[test]
+fn strategies_fed_other_klines_compute_their_own_indicators() {
+    let klines = klines();
+    let cache = Arc::new(IndicatorCache::new(Arc::new(klines[..600].to_vec())));
+    let params = ma_crossover(10, 60, 200, "ema");
+    let uncached = || create_strategy_from_params(StrategyId::MACrossover, &params, TEST_SYMBOL).unwrap();
+    let cached = || create_strategy_from_params_with_cache(StrategyId::MACrossover, &params, TEST_SYMBOL, Some(cache.clone())).unwrap();
+
+    // Past the end of the cached klines, the strategy picks up where the cache left off.
+    assert_eq!(signals(cached(), &klines), signals(uncached(), &klines));
+
+    // Transformed klines share their open times with the cached ones, but not their closes.
+    let mut heikin_ashi = HeikinAshi::default();
+    let transformed: Vec<Kline> = klines.iter().map(|kline| heikin_ashi.next(kline)).collect();
+    let before = cache.computed_series();
+    assert_eq!(signals(cached(), &transformed), signals(uncached(), &transformed));
+    assert_eq!(cache.computed_series(), before);
+}
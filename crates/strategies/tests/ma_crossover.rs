@@ -0,0 +1,160 @@
+//! Tests for the MACrossover moving-average types and crossover confirmation.
+
+use chrono::{Duration, TimeZone, Utc};
+use core_types::{Kline, OrderSide, Signal};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use strategies::{create_strategy_from_params, StrategyError, StrategyId};
+use testing::{generate_klines, TEST_SYMBOL};
+
+fn kline(i: i64, close: Decimal) -> Kline {
+    let open_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(i);
+    Kline {
+        open_time,
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: dec!(1),
+        close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+        interval: "1h".to_string(),
+    }
+}
+
+/// Flat at 100 long enough to settle every average, then `tail`, then flat again.
+fn closes_around(tail: &[Decimal]) -> Vec<Kline> {
+    std::iter::repeat_n(dec!(100), 10)
+        .chain(tail.iter().copied())
+        .chain(std::iter::repeat_n(dec!(100), 5))
+        .enumerate()
+        .map(|(i, close)| kline(i as i64, close))
+        .collect()
+}
+
+fn signals(params: &Value, klines: &[Kline]) -> Vec<(usize, Signal)> {
+    let mut strategy = create_strategy_from_params(StrategyId::MACrossover, params, TEST_SYMBOL).expect("valid params");
+    klines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, kline)| strategy.evaluate(kline).expect("evaluate").map(|signal| (i, signal)))
+        .collect()
+}
+
+/// The close itself against a 3-bar average, with a 10-bar trend filter.
+fn fast_params(confirmation_bars: usize) -> Value {
+    json!({
+        "ma_fast_period": 1,
+        "ma_slow_period": 3,
+        "trend_filter_period": 10,
+        "confirmation_bars": confirmation_bars,
+    })
+}
+
+#[test]
+fn ema_crosses_at_different_times_than_sma() {
+    let klines = generate_klines(2000);
+    let timings = |ma_type: &str| -> Vec<usize> {
+        let params = json!({
+            "ma_fast_period": 10,
+            "ma_slow_period": 60,
+            "trend_filter_period": 100,
+            "ma_type": ma_type,
+        });
+        signals(&params, &klines).into_iter().map(|(i, _)| i).collect()
+    };
+
+    let sma = timings("sma");
+    let ema = timings("ema");
+    assert!(!sma.is_empty() && !ema.is_empty());
+    assert_ne!(sma, ema);
+    // The remaining types build and trade on the same series too.
+    assert!(!timings("wma").is_empty());
+    assert!(!timings("hma").is_empty());
+}
+
+#[test]
+fn confirmation_suppresses_a_one_bar_whipsaw() {
+    // Up through the slow MA for one bar, straight back down, then back to flat.
+    let klines = closes_around(&[dec!(103), dec!(97)]);
+
+    let unconfirmed = signals(&fast_params(0), &klines);
+    let sides: Vec<_> = unconfirmed.iter().map(|(i, s)| (*i, s.order_request.side)).collect();
+    assert_eq!(sides, vec![(10, OrderSide::Buy), (11, OrderSide::Sell)]);
+
+    assert!(signals(&fast_params(2), &klines).is_empty());
+}
+
+#[test]
+fn a_cross_that_holds_is_traded_once_confirmed() {
+    let klines = closes_around(&[dec!(103), dec!(104), dec!(105)]);
+    // The drop back to flat afterwards is a bearish cross of its own.
+    let buys = |confirmation_bars| -> Vec<usize> {
+        signals(&fast_params(confirmation_bars), &klines)
+            .into_iter()
+            .filter(|(_, signal)| signal.order_request.side == OrderSide::Buy)
+            .map(|(i, _)| i)
+            .collect()
+    };
+
+    assert_eq!(buys(0), vec![10]);
+    // Two further closes above the slow MA after the crossing bar.
+    assert_eq!(buys(2), vec![12]);
+}
+
+#[test]
+fn unknown_ma_type_is_rejected() {
+    let mut params = fast_params(0);
+    params["ma_type"] = json!("kama");
+    let err = create_strategy_from_params(StrategyId::MACrossover, &params, TEST_SYMBOL)
+        .err()
+        .expect("unknown ma_type must be rejected");
+    match err {
+        StrategyError::InvalidParameters(msg) => assert!(msg.contains("kama"), "message: {}", msg),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+/// The close against a 3-bar average, with a trend filter of `trend_filter_period` bars.
+fn filtered_params(trend_filter_period: usize) -> Value {
+    json!({ "ma_fast_period": 1, "ma_slow_period": 3, "trend_filter_period": trend_filter_period })
+}
+
+fn timings(params: &Value, klines: &[Kline]) -> Vec<(usize, OrderSide)> {
+    signals(params, klines).into_iter().map(|(i, signal)| (i, signal.order_request.side)).collect()
+}
+
+#[test]
+fn a_disabled_filter_trades_like_a_filter_that_never_blocks() {
+    // Steps away from 100 that grow each time: every rise ends above, and every fall below,
+    // anything the closes so far average to, so even a long filter lets each crossover pass.
+    let steps = [110, 90, 120, 80, 130, 70, 140, 60].map(Decimal::from);
+    let closes = std::iter::repeat_n(dec!(100), 60).chain(steps.iter().flat_map(|&close| std::iter::repeat_n(close, 8)));
+    let klines: Vec<Kline> = closes.enumerate().map(|(i, close)| kline(i as i64, close)).collect();
+
+    let unfiltered = timings(&filtered_params(0), &klines);
+    assert_eq!(unfiltered.len(), steps.len());
+    assert_eq!(timings(&filtered_params(50), &klines), unfiltered);
+    // Leaving the period out disables the filter as well.
+    assert_eq!(timings(&json!({ "ma_fast_period": 1, "ma_slow_period": 3 }), &klines), unfiltered);
+}
+
+#[test]
+fn the_filter_blocks_crossovers_against_the_trend() {
+    // The fixture's uptrend has pullbacks whose crossovers stay on the wrong side of a long
+    // average; without the filter they are traded too.
+    let klines = generate_klines(2000);
+    let params = |trend_filter_period| json!({ "ma_fast_period": 10, "ma_slow_period": 60, "trend_filter_period": trend_filter_period });
+
+    let unfiltered = timings(&params(0), &klines);
+    let filtered = timings(&params(200), &klines);
+    assert!(filtered.len() < unfiltered.len(), "{} filtered, {} unfiltered", filtered.len(), unfiltered.len());
+    assert!(filtered.iter().all(|signal| unfiltered.contains(signal)), "the filter added a signal");
+}
+
+#[test]
+fn a_disabled_filter_needs_no_warmup_of_its_own() {
+    let warmup = |params: &Value| create_strategy_from_params(StrategyId::MACrossover, params, TEST_SYMBOL).unwrap().required_warmup_bars();
+    assert_eq!(warmup(&filtered_params(0)), 3);
+    assert_eq!(warmup(&filtered_params(50)), 50);
+}
@@ -0,0 +1,182 @@
+//! Tests of when `MlStrategy` starts trading, against a small model artifact written as the
+//! trainer writes one.
+
+use configuration::settings::MlStrategyParams;
+use core_types::Kline;
+use ml_features::{generate_features, MAX_FEATURE_LOOKBACK};
+use polars::prelude::*;
+use serde::Serialize;
+use serde_json::json;
+use smartcore::ensemble::random_forest_classifier::{RandomForestClassifier, RandomForestClassifierParameters};
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use strategies::ml_strategy::MlStrategy;
+use strategies::{parameter_violations, Strategy, StrategyId};
+use testing::{generate_klines, TEST_SYMBOL};
+
+// The trainer's artifact metadata, field for field, as the strategy reads it.
+#[derive(Serialize)]
+struct TrainedModel {
+    feature_names: Vec<String>,
+    model_type: String,
+    training_info: ModelInfo,
+    training_metadata: TrainingMetadata,
+    preprocessing_info: PreprocessingInfo,
+}
+
+#[derive(Serialize)]
+struct ModelInfo {
+    n_samples: usize,
+    n_features: usize,
+    classes: Vec<usize>,
+    class_distribution: HashMap<i32, usize>,
+}
+
+#[derive(Serialize)]
+struct TrainingMetadata {
+    training_date: String,
+    model_parameters: ModelParameters,
+    performance_metrics: PerformanceMetrics,
+    cross_validation_results: Option<CrossValidationResults>,
+}
+
+#[derive(Serialize)]
+struct ModelParameters {
+    n_trees: usize,
+    max_depth: Option<usize>,
+    min_samples_leaf: usize,
+    min_samples_split: usize,
+}
+
+#[derive(Serialize)]
+struct PerformanceMetrics {
+    accuracy: f64,
+    precision: f64,
+    recall: f64,
+    f1_score: f64,
+    confusion_matrix: Vec<Vec<usize>>,
+}
+
+#[derive(Serialize)]
+struct CrossValidationResults {
+    mean_score: f64,
+    std_score: f64,
+    fold_scores: Vec<f64>,
+}
+
+#[derive(Serialize)]
+struct PreprocessingInfo {
+    feature_scaling: bool,
+    feature_selection: Option<Vec<usize>>,
+    missing_value_strategy: String,
+    scaler_means: Vec<f64>,
+    scaler_stds: Vec<f64>,
+}
+
+/// A model artifact in a temporary file, removed on drop.
+struct Artifact(PathBuf);
+
+impl Drop for Artifact {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Writes a model that predicts a win for every complete feature row of `klines`. It is
+/// trained on those rows, labelled wins, and a few rows far from them labelled losses.
+fn write_model(klines: &[Kline]) -> Artifact {
+    let features = generate_features(klines).unwrap().drop_nulls::<&str>(None).unwrap();
+    let feature_names: Vec<String> = features.get_column_names().iter().map(|name| name.to_string()).collect();
+    let mut rows: Vec<Vec<f64>> = features
+        .to_ndarray::<Float64Type>(IndexOrder::C)
+        .unwrap()
+        .outer_iter()
+        .map(|row| row.to_vec())
+        .collect();
+    let mut labels = vec![1; rows.len()];
+    rows.extend((0..20).map(|_| vec![1e6; feature_names.len()]));
+    labels.extend([-1; 20]);
+
+    let x = DenseMatrix::from_2d_vec(&rows).unwrap();
+    let parameters = RandomForestClassifierParameters::default().with_n_trees(5).with_max_depth(3);
+    let model = RandomForestClassifier::fit(&x, &labels, parameters).unwrap();
+
+    let metadata = TrainedModel {
+        model_type: "RandomForestClassifier".to_string(),
+        training_info: ModelInfo {
+            n_samples: rows.len(),
+            n_features: feature_names.len(),
+            classes: vec![0, 1],
+            class_distribution: HashMap::from([(1, rows.len() - 20), (-1, 20)]),
+        },
+        training_metadata: TrainingMetadata {
+            training_date: "2025-01-01".to_string(),
+            model_parameters: ModelParameters { n_trees: 5, max_depth: Some(3), min_samples_leaf: 1, min_samples_split: 2 },
+            performance_metrics: PerformanceMetrics {
+                accuracy: 1.0,
+                precision: 1.0,
+                recall: 1.0,
+                f1_score: 1.0,
+                confusion_matrix: vec![vec![20, 0], vec![0, rows.len() - 20]],
+            },
+            cross_validation_results: None,
+        },
+        preprocessing_info: PreprocessingInfo {
+            feature_scaling: false,
+            feature_selection: None,
+            missing_value_strategy: "drop".to_string(),
+            scaler_means: vec![0.0; feature_names.len()],
+            scaler_stds: vec![1.0; feature_names.len()],
+        },
+        feature_names,
+    };
+
+    let path = std::env::temp_dir().join(format!("ml-strategy-{}.bin", uuid::Uuid::new_v4()));
+    let file = std::fs::File::create(&path).unwrap();
+    bincode::serialize_into(file, &(model, metadata)).unwrap();
+    Artifact(path)
+}
+
+fn params(model_path: &Path, buffer_capacity: Option<usize>, min_bars_before_trading: Option<usize>) -> MlStrategyParams {
+    MlStrategyParams { model_path: model_path.to_path_buf(), buffer_capacity, min_bars_before_trading }
+}
+
+/// The number of klines `strategy` had seen when it first signalled.
+fn first_signal_at(strategy: &mut MlStrategy, klines: &[Kline]) -> Option<usize> {
+    klines.iter().position(|kline| strategy.evaluate(kline).unwrap().is_some()).map(|i| i + 1)
+}
+
+#[test]
+fn no_signal_before_the_longest_feature_lookback() {
+    let klines = generate_klines(600);
+    let model = write_model(&klines);
+
+    // Unset, and set below the lookbacks, the strategy waits for the longest of them.
+    for min_bars in [None, Some(5)] {
+        let mut strategy = MlStrategy::new(params(&model.0, None, min_bars), TEST_SYMBOL.to_string()).unwrap();
+        assert_eq!(strategy.required_warmup_bars(), MAX_FEATURE_LOOKBACK);
+        assert_eq!(first_signal_at(&mut strategy, &klines), Some(MAX_FEATURE_LOOKBACK), "min_bars_before_trading {:?}", min_bars);
+    }
+
+    // A longer wait is honoured.
+    let mut strategy = MlStrategy::new(params(&model.0, Some(400), Some(300)), TEST_SYMBOL.to_string()).unwrap();
+    assert_eq!(strategy.required_warmup_bars(), 300);
+    assert_eq!(first_signal_at(&mut strategy, &klines), Some(300));
+}
+
+#[test]
+fn buffers_too_small_for_the_features_are_rejected() {
+    let violations = |params: serde_json::Value| parameter_violations(StrategyId::MlStrategy, &params).unwrap();
+
+    assert!(violations(json!({ "model_path": "model.bin" })).is_empty());
+    assert!(violations(json!({ "model_path": "model.bin", "buffer_capacity": 300, "min_bars_before_trading": 300 })).is_empty());
+
+    let too_small = violations(json!({ "model_path": "model.bin", "buffer_capacity": 100 }));
+    assert_eq!(too_small.len(), 1, "{:?}", too_small);
+    assert!(too_small[0].contains("buffer_capacity (100)"), "{:?}", too_small);
+
+    let too_late = violations(json!({ "model_path": "model.bin", "buffer_capacity": 300, "min_bars_before_trading": 301 }));
+    assert_eq!(too_late.len(), 1, "{:?}", too_late);
+    assert!(too_late[0].contains("min_bars_before_trading (301)"), "{:?}", too_late);
+}
@@ -0,0 +1,64 @@
+//! Checks that a reset strategy signals exactly like a freshly created one.
+
+use core_types::{Kline, OrderSide};
+use serde_json::{json, Value};
+use strategies::{create_strategy_from_params, Strategy, StrategyId};
+use testing::synthetic::{regime_switching, Regime, SeriesSpec};
+use testing::TEST_SYMBOL;
+
+/// The bar index and side of every signal `strategy` emits over `klines`.
+fn signals(strategy: &mut dyn Strategy, klines: &[Kline]) -> Vec<(usize, OrderSide)> {
+    klines
+        .iter()
+        .enumerate()
+        .filter_map(|(i, kline)| strategy.evaluate(kline).expect("evaluate").map(|signal| (i, signal.order_request.side)))
+        .collect()
+}
+
+fn assert_reset_forgets_history(id: StrategyId, params: Value) {
+    let regimes = [Regime::trend(400, 0.002, 0.004), Regime::chop(400, 0.006), Regime::trend(400, -0.002, 0.004)];
+    let klines = regime_switching(&SeriesSpec::default(), &regimes, 7);
+    let (history, fresh_run) = klines.split_at(600);
+
+    let expected = signals(create_strategy_from_params(id, &params, TEST_SYMBOL).unwrap().as_mut(), fresh_run);
+    assert!(!expected.is_empty(), "{:?} never signals", id);
+
+    let mut strategy = create_strategy_from_params(id, &params, TEST_SYMBOL).unwrap();
+    signals(strategy.as_mut(), history);
+    strategy.reset();
+    assert_eq!(signals(strategy.as_mut(), fresh_run), expected, "{:?}", id);
+}
+
+#[test]
+fn a_reset_ma_crossover_signals_like_a_new_one() {
+    for ma_type in ["sma", "ema", "wma", "hma"] {
+        let params = json!({
+            "ma_fast_period": 10,
+            "ma_slow_period": 30,
+            "trend_filter_period": 50,
+            "confirmation_bars": 1,
+            "ma_type": ma_type,
+        });
+        assert_reset_forgets_history(StrategyId::MACrossover, params);
+    }
+}
+
+#[test]
+fn a_reset_super_trend_signals_like_a_new_one() {
+    let params = json!({ "atr_period": 14, "atr_multiplier": 3.0, "adx_threshold": 25.0, "adx_period": 14 });
+    assert_reset_forgets_history(StrategyId::SuperTrend, params);
+}
+
+#[test]
+fn a_reset_prob_reversion_signals_like_a_new_one() {
+    let params = json!({
+        "bb_period": 20,
+        "bb_std_dev": 2.0,
+        "rsi_period": 14,
+        "rsi_oversold": 30.0,
+        "rsi_overbought": 70.0,
+        "adx_threshold": 20.0,
+        "adx_period": 14,
+    });
+    assert_reset_forgets_history(StrategyId::ProbReversion, params);
+}
@@ -0,0 +1,30 @@
+//! Property-style checks of SuperTrend's signals on seeded synthetic series.
+
+use core_types::OrderSide;
+use serde_json::json;
+use strategies::{create_strategy_from_params, StrategyId};
+use testing::synthetic::{gbm, regime_switching, Regime, SeriesSpec};
+use testing::TEST_SYMBOL;
+
+/// The side of every signal SuperTrend emits over `klines`, in order.
+fn signal_sides(klines: &[core_types::Kline]) -> Vec<OrderSide> {
+    let params = json!({ "atr_period": 14, "atr_multiplier": 3.0, "adx_threshold": 25.0, "adx_period": 14 });
+    let mut strategy = create_strategy_from_params(StrategyId::SuperTrend, &params, TEST_SYMBOL).expect("valid params");
+    klines
+        .iter()
+        .filter_map(|kline| strategy.evaluate(kline).expect("evaluate"))
+        .map(|signal| signal.order_request.side)
+        .collect()
+}
+
+#[test]
+fn super_trend_never_signals_the_same_direction_twice_in_a_row() {
+    let regimes = [Regime::trend(500, 0.002, 0.004), Regime::chop(500, 0.006), Regime::trend(500, -0.002, 0.004)];
+    for seed in 1..=10 {
+        for klines in [regime_switching(&SeriesSpec::default(), &regimes, seed), gbm(&SeriesSpec::new("15m"), 1500, 0.0, 0.004, seed)] {
+            let sides = signal_sides(&klines);
+            assert!(sides.len() > 2, "seed {}: only {} signals", seed, sides.len());
+            assert!(sides.windows(2).all(|pair| pair[0] != pair[1]), "seed {}: {:?}", seed, sides);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+//! Tests for the kline preprocessing transforms.
+
+use chrono::{Duration, TimeZone, Utc};
+use core_types::{Kline, KlineTransform};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use strategies::KlineTransformer;
+
+fn kline(i: i64, open: Decimal, high: Decimal, low: Decimal, close: Decimal) -> Kline {
+    let open_time = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::hours(i);
+    Kline {
+        open_time,
+        open,
+        high,
+        low,
+        close,
+        volume: dec!(1),
+        close_time: open_time + Duration::hours(1) - Duration::milliseconds(1),
+        interval: "1h".to_string(),
+    }
+}
+
+#[test]
+fn heikin_ashi_matches_reference_values() {
+    let klines = [
+        kline(0, dec!(10), dec!(12), dec!(9), dec!(11)),
+        kline(1, dec!(11), dec!(13), dec!(10), dec!(12)),
+        kline(2, dec!(12), dec!(12.5), dec!(8), dec!(9)),
+    ];
+    // (open, high, low, close), computed by hand from the Heikin-Ashi definition.
+    let expected = [
+        (dec!(10.5), dec!(12), dec!(9), dec!(10.5)),
+        (dec!(10.5), dec!(13), dec!(10), dec!(11.5)),
+        (dec!(11), dec!(12.5), dec!(8), dec!(10.375)),
+    ];
+
+    let mut transform = KlineTransformer::new(KlineTransform::HeikinAshi);
+    for (raw, (open, high, low, close)) in klines.iter().zip(expected) {
+        let ha = transform.apply(raw).into_owned();
+        assert_eq!((ha.open, ha.high, ha.low, ha.close), (open, high, low, close));
+        assert_eq!((ha.open_time, ha.close_time, ha.volume), (raw.open_time, raw.close_time, raw.volume));
+    }
+}
+
+#[test]
+fn no_transform_passes_klines_through() {
+    let raw = kline(0, dec!(10), dec!(12), dec!(9), dec!(11));
+    let mut transform = KlineTransformer::new(KlineTransform::None);
+    assert_eq!(*transform.apply(&raw), raw);
+}
@@ -0,0 +1,120 @@
+//! Tests for the parameter rules each strategy checks at construction.
+
+use serde_json::{json, Value};
+use strategies::{create_strategy_from_params, parameter_violations, StrategyError, StrategyId};
+use testing::TEST_SYMBOL;
+
+fn ma_crossover(fast: usize, slow: usize, trend: usize) -> Value {
+    json!({ "ma_fast_period": fast, "ma_slow_period": slow, "trend_filter_period": trend })
+}
+
+fn super_trend() -> Value {
+    json!({ "atr_period": 14, "atr_multiplier": 3.0, "adx_threshold": 25.0, "adx_period": 14 })
+}
+
+fn prob_reversion() -> Value {
+    json!({
+        "bb_period": 20,
+        "bb_std_dev": 2.0,
+        "rsi_period": 14,
+        "rsi_oversold": 30.0,
+        "rsi_overbought": 70.0,
+        "adx_threshold": 20.0,
+        "adx_period": 14,
+    })
+}
+
+/// `params` with each of `overrides` replaced.
+fn with(mut params: Value, overrides: Value) -> Value {
+    for (key, value) in overrides.as_object().unwrap() {
+        params[key] = value.clone();
+    }
+    params
+}
+
+/// The message of the error constructing `id` from `params` fails with.
+fn rejection(id: StrategyId, params: &Value) -> String {
+    match create_strategy_from_params(id, params, TEST_SYMBOL).err().expect("invalid params must be rejected") {
+        StrategyError::InvalidParameters(message) => message,
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+/// Asserts that `params` break exactly one rule of `id`, described by a message containing
+/// `expected`.
+fn assert_single_violation(id: StrategyId, params: &Value, expected: &str) {
+    let violations = parameter_violations(id, params).expect("params deserialize");
+    assert_eq!(violations.len(), 1, "{:?}", violations);
+    assert!(violations[0].contains(expected), "{:?} does not mention {:?}", violations, expected);
+    assert!(rejection(id, params).contains(expected));
+}
+
+#[test]
+fn ma_crossover_rules() {
+    let id = StrategyId::MACrossover;
+    assert_single_violation(id, &ma_crossover(20, 20, 50), "ma_fast_period (20) must be less than ma_slow_period (20)");
+    assert_single_violation(id, &ma_crossover(10, 60, 50), "trend_filter_period (50) must be at least ma_slow_period (60)");
+    assert_single_violation(id, &with(ma_crossover(10, 60, 60), json!({ "ma_type": "vwma" })), "Unknown ma_type 'vwma'");
+
+    // A zero trend filter disables it; one as long as the slow MA is fine too.
+    for trend in [0, 60, 200] {
+        assert_eq!(parameter_violations(id, &ma_crossover(10, 60, trend)).unwrap(), Vec::<String>::new());
+        create_strategy_from_params(id, &ma_crossover(10, 60, trend), TEST_SYMBOL).expect("valid params");
+    }
+}
+
+#[test]
+fn super_trend_rules() {
+    let id = StrategyId::SuperTrend;
+    assert_single_violation(id, &with(super_trend(), json!({ "atr_period": 0 })), "atr_period must be greater than 0");
+    assert_single_violation(id, &with(super_trend(), json!({ "adx_period": 0 })), "adx_period must be greater than 0");
+    assert_single_violation(id, &with(super_trend(), json!({ "atr_multiplier": 0.0 })), "atr_multiplier (0) must be greater than 0");
+    assert_single_violation(id, &with(super_trend(), json!({ "adx_threshold": -1.0 })), "adx_threshold (-1) must be between 0 and 100");
+    assert_single_violation(id, &with(super_trend(), json!({ "adx_threshold": 101.0 })), "adx_threshold (101) must be between 0 and 100");
+    assert!(parameter_violations(id, &super_trend()).unwrap().is_empty());
+}
+
+#[test]
+fn prob_reversion_rules() {
+    let id = StrategyId::ProbReversion;
+    for period in ["bb_period", "rsi_period", "adx_period"] {
+        assert_single_violation(id, &with(prob_reversion(), json!({ period: 0 })), &format!("{} must be greater than 0", period));
+    }
+    assert_single_violation(id, &with(prob_reversion(), json!({ "bb_std_dev": -2.0 })), "bb_std_dev (-2) must be greater than 0");
+    assert_single_violation(
+        id,
+        &with(prob_reversion(), json!({ "rsi_oversold": 70.0, "rsi_overbought": 30.0 })),
+        "rsi_oversold (70) must be less than rsi_overbought (30)",
+    );
+    assert_single_violation(id, &with(prob_reversion(), json!({ "rsi_overbought": 120.0 })), "rsi_overbought (120) must be between 0 and 100");
+    assert_single_violation(id, &with(prob_reversion(), json!({ "adx_threshold": 150.0 })), "adx_threshold (150) must be between 0 and 100");
+    assert!(parameter_violations(id, &prob_reversion()).unwrap().is_empty());
+}
+
+#[test]
+fn funding_rate_arb_rules() {
+    let params = json!({ "target_rate_threshold": 0.0, "basis_safety_threshold": 0.01 });
+    assert_single_violation(StrategyId::FundingRateArb, &params, "target_rate_threshold (0) must be greater than 0");
+    let params = json!({ "target_rate_threshold": 0.001, "basis_safety_threshold": 0.0 });
+    assert_single_violation(StrategyId::FundingRateArb, &params, "basis_safety_threshold (0) must be greater than 0");
+}
+
+#[test]
+fn every_violation_is_reported_at_once() {
+    let params = with(
+        prob_reversion(),
+        json!({ "bb_period": 0, "bb_std_dev": 0.0, "rsi_oversold": 80.0, "rsi_overbought": 20.0, "adx_threshold": 200.0 }),
+    );
+    assert_eq!(parameter_violations(StrategyId::ProbReversion, &params).unwrap().len(), 4);
+    assert_eq!(
+        rejection(StrategyId::ProbReversion, &params),
+        "ProbReversion: bb_period must be greater than 0; bb_std_dev (0) must be greater than 0; \
+         rsi_oversold (80) must be less than rsi_overbought (20); adx_threshold (200) must be between 0 and 100"
+    );
+}
+
+#[test]
+fn unparseable_params_are_an_error_rather_than_a_violation() {
+    let params = with(ma_crossover(10, 60, 100), json!({ "ma_slow_perod": 70 }));
+    assert!(parameter_violations(StrategyId::MACrossover, &params).is_err());
+}
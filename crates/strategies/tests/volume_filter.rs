@@ -0,0 +1,136 @@
+//! Tests for the VolumeFilter wrapper and its rolling mean, around a strategy that signals
+//! on every bar.
+
+use configuration::{FilteredSignals, VolumeFilterParams};
+use core_types::{Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
+use rust_decimal::Decimal;
+use serde_json::json;
+use strategies::{create_strategy_from_params, parameter_violations, strategy_params, RollingMean, Strategy, StrategyError, StrategyId, VolumeFilter};
+use testing::synthetic::{regime_switching, Regime, SeriesSpec};
+use testing::{generate_klines, test_config, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// Signals `intent` on every bar.
+struct Always(SignalIntent);
+
+impl Strategy for Always {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: Some(self.0),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side: OrderSide::Buy,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: false,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+fn params(lookback: usize, min_relative_volume: Decimal, signals: FilteredSignals) -> VolumeFilterParams {
+    VolumeFilterParams { strategies: Vec::new(), lookback, min_relative_volume, min_dollar_volume: None, signals }
+}
+
+/// Fixture klines with the given volumes.
+fn with_volumes(volumes: &[i64]) -> Vec<Kline> {
+    generate_klines(volumes.len())
+        .into_iter()
+        .zip(volumes)
+        .map(|(kline, volume)| Kline { volume: Decimal::from(*volume), ..kline })
+        .collect()
+}
+
+/// Whether each bar's signal passed the filter.
+fn passed(filter: &mut VolumeFilter, klines: &[Kline]) -> Vec<bool> {
+    klines.iter().map(|kline| filter.evaluate(kline).unwrap().is_some()).collect()
+}
+
+#[test]
+fn signals_on_low_volume_bars_are_dropped() {
+    let mut filter = VolumeFilter::new(Box::new(Always(SignalIntent::OpenLong)), params(4, Decimal::new(8, 1), FilteredSignals::Entries)).unwrap();
+    // The window fills on the fourth bar (mean 100), then the mean moves with each bar.
+    let klines = with_volumes(&[100, 100, 100, 100, 70, 130, 80, 200]);
+    // Means: -, -, -, 100, 92.5, 100, 95, 120, so bars of 70 and 80 fall short of 0.8 times
+    // the mean.
+    assert_eq!(passed(&mut filter, &klines), [true, true, true, true, false, true, true, true]);
+
+    let mut strict = VolumeFilter::new(Box::new(Always(SignalIntent::OpenLong)), params(4, Decimal::ONE, FilteredSignals::Entries)).unwrap();
+    assert_eq!(passed(&mut strict, &klines), [true, true, true, true, false, true, false, true]);
+}
+
+#[test]
+fn exits_pass_unless_every_signal_is_filtered() {
+    let klines = with_volumes(&[100, 100, 10]);
+    let mut entries_only = VolumeFilter::new(Box::new(Always(SignalIntent::Close)), params(2, Decimal::ONE, FilteredSignals::Entries)).unwrap();
+    assert_eq!(passed(&mut entries_only, &klines), [true, true, true]);
+
+    let mut all = VolumeFilter::new(Box::new(Always(SignalIntent::Close)), params(2, Decimal::ONE, FilteredSignals::All)).unwrap();
+    assert_eq!(passed(&mut all, &klines), [true, true, false]);
+}
+
+#[test]
+fn signals_below_the_dollar_volume_floor_are_dropped() {
+    let klines = with_volumes(&[10, 1, 10]);
+    let floor = klines[0].close * Decimal::from(5);
+    let filter_params = VolumeFilterParams { min_dollar_volume: Some(floor), ..params(1, Decimal::ZERO, FilteredSignals::Entries) };
+    let mut filter = VolumeFilter::new(Box::new(Always(SignalIntent::OpenShort)), filter_params).unwrap();
+    assert_eq!(passed(&mut filter, &klines), [true, false, true]);
+}
+
+#[test]
+fn the_rolling_mean_matches_a_recomputation() {
+    let regimes = [Regime::trend(300, 0.002, 0.01), Regime::chop(300, 0.02)];
+    let volumes: Vec<Decimal> = regime_switching(&SeriesSpec::default(), &regimes, 11).iter().map(|kline| kline.volume).collect();
+    assert!(volumes.windows(2).any(|pair| pair[0] != pair[1]), "the series needs varying volumes");
+
+    for window in [1, 7, 20] {
+        let mut mean = RollingMean::new(window);
+        for (i, volume) in volumes.iter().enumerate() {
+            mean.push(*volume);
+            let expected = (i + 1 >= window).then(|| volumes[i + 1 - window..=i].iter().sum::<Decimal>() / Decimal::from(window));
+            assert_eq!(mean.mean(), expected, "window {} at bar {}", window, i);
+        }
+        mean.reset();
+        assert_eq!(mean.mean(), None);
+    }
+}
+
+#[test]
+fn listed_strategies_carry_the_configured_filter() {
+    let mut config = test_config(100).unwrap();
+    config.strategies.volume_filter = Some(VolumeFilterParams {
+        strategies: vec![StrategyId::MACrossover],
+        ..params(20, Decimal::new(8, 1), FilteredSignals::All)
+    });
+
+    let ma_crossover = strategy_params(StrategyId::MACrossover, &config).unwrap();
+    assert_eq!(ma_crossover["volume_filter"], json!({ "lookback": 20, "min_relative_volume": "0.8", "signals": "all" }));
+    assert!(strategy_params(StrategyId::SuperTrend, &config).unwrap().get("volume_filter").is_none());
+
+    let strategy = create_strategy_from_params(StrategyId::MACrossover, &ma_crossover, TEST_SYMBOL).unwrap();
+    assert!(strategy.required_warmup_bars() >= 20);
+}
+
+#[test]
+fn invalid_filter_parameters_are_reported() {
+    let mut params = strategy_params(StrategyId::SuperTrend, &test_config(100).unwrap()).unwrap();
+    params["volume_filter"] = json!({ "lookback": 0, "min_relative_volume": -1 });
+    assert_eq!(
+        parameter_violations(StrategyId::SuperTrend, &params).unwrap(),
+        ["volume_filter: lookback must be greater than 0", "volume_filter: min_relative_volume (-1) must not be negative"]
+    );
+    assert!(create_strategy_from_params(StrategyId::SuperTrend, &params, TEST_SYMBOL).is_err());
+
+    params["volume_filter"] = json!({ "lookback": 20, "min_relative_volume": 1, "min_volume": 5 });
+    assert!(matches!(parameter_violations(StrategyId::SuperTrend, &params), Err(StrategyError::InvalidParameters(_))));
+}
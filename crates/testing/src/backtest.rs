@@ -0,0 +1,203 @@
+//! Strategy and executor doubles for backtests and engine runs, and a factory wiring a
+//! strategy into a `Backtester` of the fixture symbol.
+
+use async_trait::async_trait;
+use backtester::Backtester;
+use configuration::{Config, Simulation};
+use core_types::{Execution, Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent};
+use executor::{Executor, ExecutorError, Portfolio, SimulatedExecutor};
+use risk::{RiskManager, SimpleRiskManager};
+use rust_decimal::Decimal;
+use std::sync::{Arc, Mutex};
+use strategies::{Strategy, StrategyError};
+use uuid::Uuid;
+
+use crate::database::unreachable_repo;
+use crate::fixtures::{TEST_INTERVAL, TEST_SYMBOL};
+
+/// A signal to place a `side` market order on `TEST_SYMBOL` at `kline`'s close, left for the
+/// risk manager to size.
+pub fn market_signal(kline: &Kline, side: OrderSide, intent: Option<SignalIntent>) -> Signal {
+    Signal {
+        signal_id: Uuid::new_v4(),
+        decision_id: Uuid::new_v4(),
+        timestamp: kline.close_time,
+        confidence: Decimal::ONE,
+        intent,
+        order_request: OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: TEST_SYMBOL.to_string(),
+            side,
+            order_type: OrderType::Market,
+            quantity: Decimal::ZERO,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        },
+    }
+}
+
+/// Opens a position on the first bar, then holds it.
+pub struct EnterOnce {
+    side: OrderSide,
+    entered: bool,
+}
+
+impl EnterOnce {
+    /// Opens a long, or a short for `OrderSide::Sell`.
+    pub fn new(side: OrderSide) -> Self {
+        Self { side, entered: false }
+    }
+
+    pub fn long() -> Self {
+        Self::new(OrderSide::Buy)
+    }
+}
+
+impl Strategy for EnterOnce {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        if std::mem::replace(&mut self.entered, true) {
+            return Ok(None);
+        }
+        let intent = if self.side == OrderSide::Buy { SignalIntent::OpenLong } else { SignalIntent::OpenShort };
+        Ok(Some(market_signal(kline, self.side, Some(intent))))
+    }
+}
+
+/// Opens a long on each of its bars, counting from zero, and signals nothing otherwise.
+pub struct EnterAt {
+    bars: Vec<usize>,
+    seen: usize,
+}
+
+impl EnterAt {
+    pub fn new(bars: Vec<usize>) -> Self {
+        Self { bars, seen: 0 }
+    }
+
+    /// Opens a long on the `bar`th bar only, then holds it.
+    pub fn bar(bar: usize) -> Self {
+        Self::new(vec![bar])
+    }
+}
+
+impl Strategy for EnterAt {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let bar = self.seen;
+        self.seen += 1;
+        Ok(self.bars.contains(&bar).then(|| market_signal(kline, OrderSide::Buy, Some(SignalIntent::OpenLong))))
+    }
+}
+
+/// Opens a long on every even bar and closes it on the next.
+#[derive(Default)]
+pub struct InAndOut {
+    seen: usize,
+}
+
+impl Strategy for InAndOut {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        let (intent, side) = if self.seen.is_multiple_of(2) { (SignalIntent::OpenLong, OrderSide::Buy) } else { (SignalIntent::Close, OrderSide::Sell) };
+        self.seen += 1;
+        Ok(Some(market_signal(kline, side, Some(intent))))
+    }
+}
+
+/// An executor for runs that must not trade: any order it is handed fails the test.
+pub struct NoOrders;
+
+#[async_trait]
+impl Executor for NoOrders {
+    async fn execute(&self, order: &OrderRequest, _: &Kline, _: Option<Decimal>, _: Option<Decimal>) -> Result<Execution, ExecutorError> {
+        panic!("unexpected order {:?}", order);
+    }
+}
+
+/// A `SimulatedExecutor` that keeps a copy of every execution it fills.
+pub struct RecordingExecutor {
+    inner: SimulatedExecutor,
+    executions: Arc<Mutex<Vec<Execution>>>,
+}
+
+impl RecordingExecutor {
+    pub fn new(simulation: Simulation) -> Self {
+        Self { inner: SimulatedExecutor::new(simulation), executions: Arc::default() }
+    }
+
+    /// The executions filled so far, shared with the executor once it is boxed.
+    pub fn executions(&self) -> Arc<Mutex<Vec<Execution>>> {
+        Arc::clone(&self.executions)
+    }
+}
+
+#[async_trait]
+impl Executor for RecordingExecutor {
+    async fn execute(
+        &self,
+        order: &OrderRequest,
+        kline: &Kline,
+        best_bid: Option<Decimal>,
+        best_ask: Option<Decimal>,
+    ) -> Result<Execution, ExecutorError> {
+        let execution = self.inner.execute(order, kline, best_bid, best_ask).await?;
+        self.executions.lock().unwrap().push(execution.clone());
+        Ok(execution)
+    }
+}
+
+/// A backtester of `TEST_SYMBOL` on `TEST_INTERVAL` under a config, trading a strategy from
+/// the config's initial capital. The risk manager and executor default to the simple risk
+/// manager and the simulated executor the config sets up; its repository is never reached.
+pub struct TestBacktester {
+    run_id: Uuid,
+    config: Config,
+    strategy: Box<dyn Strategy>,
+    risk_manager: Option<Box<dyn RiskManager>>,
+    executor: Option<Box<dyn Executor>>,
+}
+
+impl TestBacktester {
+    pub fn new(config: &Config, strategy: Box<dyn Strategy>) -> Self {
+        Self { run_id: Uuid::new_v4(), config: config.clone(), strategy, risk_manager: None, executor: None }
+    }
+
+    pub fn with_run_id(mut self, run_id: Uuid) -> Self {
+        self.run_id = run_id;
+        self
+    }
+
+    pub fn with_risk_manager(mut self, risk_manager: Box<dyn RiskManager>) -> Self {
+        self.risk_manager = Some(risk_manager);
+        self
+    }
+
+    pub fn with_executor(mut self, executor: Box<dyn Executor>) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    pub fn build(self) -> Backtester {
+        let config = self.config;
+        let risk_manager = self.risk_manager.unwrap_or_else(|| Box::new(SimpleRiskManager::new(config.risk_management.clone()).expect("valid risk settings")));
+        let executor = self.executor.unwrap_or_else(|| Box::new(SimulatedExecutor::new(config.simulation.clone())));
+        Backtester::new(
+            self.run_id,
+            TEST_SYMBOL.to_string(),
+            TEST_INTERVAL.to_string(),
+            config.clone(),
+            Portfolio::new(config.backtest.initial_capital),
+            self.strategy,
+            risk_manager,
+            executor,
+            analytics::AnalyticsEngine::new(),
+            unreachable_repo(),
+        )
+    }
+}
+
+/// A `TestBacktester` of `strategy` under `config`, with its default parts.
+pub fn backtester(config: &Config, strategy: Box<dyn Strategy>) -> Backtester {
+    TestBacktester::new(config, strategy).build()
+}
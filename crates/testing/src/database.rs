@@ -0,0 +1,102 @@
+use database::{connect_sqlite, run_migrations, run_sqlite_migrations, DbError, DbRepository};
+use dotenvy::dotenv;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{Connection, PgConnection, PgPool, SqlitePool};
+use std::env;
+use std::path::PathBuf;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// A repository whose database cannot be reached: every query fails within 50 ms. For tests
+/// of code that needs a repository but only logs the reads and writes that fail.
+pub fn unreachable_repo() -> DbRepository {
+    let pool = PgPoolOptions::new()
+        .acquire_timeout(std::time::Duration::from_millis(50))
+        .connect_lazy("postgres://unused@127.0.0.1:1/unused")
+        .expect("a lazy pool does not connect");
+    DbRepository::new(pool)
+}
+
+/// A uniquely named, fully migrated database that lives for the duration of one test.
+///
+/// The server is taken from `DATABASE_URL`; only the database name is replaced. Call
+/// `teardown` at the end of the test to drop it again.
+pub struct TestDatabase {
+    pub pool: PgPool,
+    admin_options: PgConnectOptions,
+    name: String,
+}
+
+impl TestDatabase {
+    /// Creates a new empty database on the configured server and applies all migrations.
+    pub async fn create() -> Result<Self, DbError> {
+        // A missing .env file is fine here; the variable may come from the environment.
+        let _ = dotenv();
+        let database_url = env::var("DATABASE_URL")
+            .map_err(|_e| DbError::ConnectionConfigError("DATABASE_URL must be set.".to_string()))?;
+        let admin_options = PgConnectOptions::from_str(&database_url)?;
+
+        let name = format!("zenith_test_{}", Uuid::new_v4().simple());
+        let mut admin = PgConnection::connect_with(&admin_options).await?;
+        sqlx::query(&format!(r#"CREATE DATABASE "{}""#, name))
+            .execute(&mut admin)
+            .await?;
+        admin.close().await?;
+
+        let pool = PgPoolOptions::new()
+            .max_connections(10)
+            .connect_with(admin_options.clone().database(&name))
+            .await?;
+        run_migrations(&pool).await?;
+
+        tracing::debug!("Created test database {}", name);
+        Ok(Self { pool, admin_options, name })
+    }
+
+    /// Returns a repository backed by this database.
+    pub fn repo(&self) -> DbRepository {
+        DbRepository::new(self.pool.clone())
+    }
+
+    /// Closes all connections and drops the database.
+    pub async fn teardown(self) -> Result<(), DbError> {
+        self.pool.close().await;
+        let mut admin = PgConnection::connect_with(&self.admin_options).await?;
+        sqlx::query(&format!(r#"DROP DATABASE IF EXISTS "{}" WITH (FORCE)"#, self.name))
+            .execute(&mut admin)
+            .await?;
+        admin.close().await?;
+        Ok(())
+    }
+}
+
+/// A fully migrated SQLite database file in the temporary directory that lives for the
+/// duration of one test. Needs no server, so tests on it need not be ignored.
+pub struct SqliteTestDatabase {
+    pub pool: SqlitePool,
+    path: PathBuf,
+}
+
+impl SqliteTestDatabase {
+    /// Creates a new empty database file and applies the SQLite migrations.
+    pub async fn create() -> Result<Self, DbError> {
+        let path = env::temp_dir().join(format!("zenith_test_{}.db", Uuid::new_v4().simple()));
+        let pool = connect_sqlite(&format!("sqlite://{}", path.display())).await?;
+        run_sqlite_migrations(&pool).await?;
+        Ok(Self { pool, path })
+    }
+
+    /// Returns a repository backed by this database.
+    pub fn repo(&self) -> DbRepository {
+        DbRepository::sqlite(self.pool.clone())
+    }
+
+    /// Closes all connections and deletes the database file.
+    pub async fn teardown(self) -> Result<(), DbError> {
+        self.pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", self.path.display(), suffix));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use configuration::error::ConfigError;
+use configuration::{load_config, Config};
+use core_types::{Kline, StrategyId};
+use database::{DbError, DbRepository};
+use rust_decimal::Decimal;
+
+use crate::synthetic::{sine_trend, SeriesSpec, SineTrend};
+
+/// The symbol under which all fixture data is seeded.
+pub const TEST_SYMBOL: &str = "TESTUSDT";
+/// The interval of the seeded klines. `generate_klines` produces one bar per hour.
+pub const TEST_INTERVAL: &str = "1h";
+
+/// The open time of the first seeded bar (2024-01-01 00:00:00 UTC).
+pub fn seed_start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Generates `count` hourly klines following a slow uptrend overlaid with a sine wave.
+///
+/// The close of bar `i` is `100 + 0.01 * i + 10 * sin(2 * PI * i / 240)`, rounded to
+/// two decimal places. Each bar opens at the previous close, and its wicks extend a
+/// fixed 0.25 beyond the body. The series is fully determined by `count`.
+pub fn generate_klines(count: usize) -> Vec<Kline> {
+    sine_trend(&SeriesSpec::default(), count, &SineTrend::default())
+}
+
+/// Persists the given klines under `symbol` through the repository.
+pub async fn seed_klines(repo: &DbRepository, symbol: &str, klines: &[Kline]) -> Result<(), DbError> {
+    for kline in klines {
+        repo.save_kline(symbol, kline).await?;
+    }
+    Ok(())
+}
+
+/// Loads the workspace `config.toml` and points its backtest section at the fixture data.
+///
+/// The backtest runs MACrossover on `TEST_SYMBOL` over the first `bars` hours after
+/// `seed_start`, starting with 10,000 of capital.
+pub fn test_config(bars: usize) -> Result<Config, ConfigError> {
+    let path = concat!(env!("CARGO_MANIFEST_DIR"), "/../../config.toml");
+    let mut config = load_config(Some(path))?;
+
+    let end = seed_start() + Duration::hours(bars as i64 - 1);
+    config.backtest.strategy_id = StrategyId::MACrossover;
+    config.backtest.symbol = TEST_SYMBOL.to_string();
+    config.backtest.interval = TEST_INTERVAL.to_string();
+    config.backtest.initial_capital = Decimal::from(10_000);
+    config.backtest.start_date = seed_start().date_naive();
+    config.backtest.end_date = end.date_naive();
+    Ok(config)
+}
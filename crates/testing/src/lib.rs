@@ -0,0 +1,42 @@
+//! # Zenith Testing Support
+//!
+//! This crate provides the shared scaffolding for end-to-end tests of the
+//! optimizer -> backtester -> analyzer pipeline, all of which require a live
+//! PostgreSQL database with the correct schema.
+//!
+//! ## Architectural Principles
+//! - **Isolation:** Every test gets its own freshly created and migrated database,
+//!   so tests can run concurrently and never see each other's data.
+//! - **Determinism:** Market data is generated from a closed-form series rather than
+//!   downloaded, so results can be pinned across runs and machines.
+//! - **Opt-in:** Tests built on this crate are marked `#[ignore]` and only run with
+//!   `cargo test -- --ignored`, keeping normal builds database-free.
+//!
+//! ## Public API
+//! - `TestDatabase`: Creates, migrates and drops a throwaway database.
+//! - `SqliteTestDatabase`: The same for a throwaway SQLite file, which needs no server.
+//! - `unreachable_repo`: A repository whose every query fails fast, for tests without a database.
+//! - `generate_klines`: Produces a deterministic sine-plus-trend kline series.
+//! - `synthetic`: Seedable generators of random-walk, regime-switching and sine-plus-trend
+//!   kline series, for strategy smoke tests and benchmarks.
+//! - `seed_klines`: Persists a kline series through the `DbRepository`.
+//! - `test_config`: Loads the workspace `Config` and points its backtest at the seeded data.
+//! - `WsTestClient`: A typed client of the server's WebSocket, aware of its protocol versions.
+//! - `MockAccount`: A configurable exchange account standing in for `ApiClient`.
+//! - `backtest`: Strategy and executor doubles, and `TestBacktester`, which wires a strategy into a
+//!   `Backtester` of the fixture symbol.
+
+// Declare the modules that constitute this crate.
+pub mod backtest;
+pub mod database;
+pub mod fixtures;
+pub mod mock_account;
+pub mod synthetic;
+pub mod ws_client;
+
+// Re-export the public components to provide a clean API.
+pub use backtest::{backtester, market_signal, EnterAt, EnterOnce, InAndOut, NoOrders, RecordingExecutor, TestBacktester};
+pub use database::{unreachable_repo, SqliteTestDatabase, TestDatabase};
+pub use fixtures::{generate_klines, seed_klines, seed_start, test_config, TEST_INTERVAL, TEST_SYMBOL};
+pub use mock_account::MockAccount;
+pub use ws_client::WsTestClient;
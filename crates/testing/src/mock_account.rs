@@ -0,0 +1,294 @@
+//! A configurable exchange account standing in for `ApiClient` in tests.
+//!
+//! `MockAccount::new` is an account holding 10,000 USDT and no positions, trading BTCUSDT in
+//! one-way mode, that answers every read and rejects every order nothing was scripted for.
+//! Each test changes only what its scenario needs, with the `with_*` methods or the public
+//! fields.
+
+use api_client::error::ApiError;
+use api_client::responses::SymbolInfo;
+use api_client::{
+    ApiClient, BalanceResponse, ExchangeInfoResponse, IncomeRecord, LeverageBracket, OrderResponse, PositionResponse, SymbolBracketsResponse, UserTradeResponse,
+};
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use core_types::{Kline, OrderRequest};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// A scripted exchange account. See the module documentation for its defaults.
+pub struct MockAccount {
+    /// The account's balances.
+    pub balances: Vec<BalanceResponse>,
+    /// The symbols the exchange lists as trading.
+    pub symbols: Vec<String>,
+    /// The mark price of each symbol that has one. Other symbols are invalid.
+    pub mark_prices: HashMap<String, Decimal>,
+    /// The leverage brackets of every symbol.
+    pub leverage_brackets: Vec<LeverageBracket>,
+    /// Whether the account trades in hedge mode.
+    pub hedge_mode: bool,
+    /// The orders looked up by `get_order`, and accepted when placed, by client order ID.
+    pub orders: HashMap<String, OrderResponse>,
+    /// The fills of each order, by order ID.
+    pub trades: HashMap<i64, Vec<UserTradeResponse>>,
+    /// The client order IDs whose lookup fails, as if the exchange timed out.
+    pub unreachable_orders: Vec<String>,
+    /// Whether placing an order fails as if the exchange timed out, leaving it unknown whether
+    /// the order was placed. A scripted order is placed all the same.
+    pub placements_time_out: bool,
+    /// Whether balance requests are rejected, as for API keys without permission.
+    pub keys_rejected: bool,
+    /// Whether anything that changes the account panics, for code that must only read it.
+    pub read_only: bool,
+    /// Whether each kline request is answered with one daily kline opening at the start of
+    /// the range. Otherwise there are no klines.
+    pub serves_klines: bool,
+    /// Kline requests for ranges starting at or after this never complete, like a stalled
+    /// connection.
+    pub klines_hang_from: Option<DateTime<Utc>>,
+    /// The start of every kline range requested, in order.
+    kline_requests: Mutex<Vec<DateTime<Utc>>>,
+    /// Every order placed, in order.
+    placed_orders: Mutex<Vec<OrderRequest>>,
+}
+
+impl Default for MockAccount {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockAccount {
+    /// An account holding 10,000 USDT and no positions, trading BTCUSDT.
+    pub fn new() -> Self {
+        Self {
+            balances: vec![balance("USDT", Decimal::from(10_000))],
+            symbols: vec!["BTCUSDT".to_string()],
+            mark_prices: HashMap::new(),
+            leverage_brackets: Vec::new(),
+            hedge_mode: false,
+            orders: HashMap::new(),
+            trades: HashMap::new(),
+            unreachable_orders: Vec::new(),
+            placements_time_out: false,
+            keys_rejected: false,
+            read_only: false,
+            serves_klines: false,
+            klines_hang_from: None,
+            kline_requests: Mutex::new(Vec::new()),
+            placed_orders: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Holds `amount` of `asset` and nothing else.
+    pub fn with_balance(mut self, asset: &str, amount: Decimal) -> Self {
+        self.balances = vec![balance(asset, amount)];
+        self
+    }
+
+    /// Lists `symbols` as the exchange's trading symbols.
+    pub fn with_symbols(mut self, symbols: &[&str]) -> Self {
+        self.symbols = symbols.iter().map(|symbol| symbol.to_string()).collect();
+        self
+    }
+
+    /// Marks `symbol` at `price`.
+    pub fn with_mark_price(mut self, symbol: &str, price: Decimal) -> Self {
+        self.mark_prices.insert(symbol.to_string(), price);
+        self
+    }
+
+    /// Gives every symbol the leverage `brackets`.
+    pub fn with_leverage_brackets(mut self, brackets: Vec<LeverageBracket>) -> Self {
+        self.leverage_brackets = brackets;
+        self
+    }
+
+    /// Trades in hedge mode.
+    pub fn with_hedge_mode(mut self) -> Self {
+        self.hedge_mode = true;
+        self
+    }
+
+    /// Knows of `order`, filled by `trades`, and accepts it when it is placed.
+    pub fn with_order(mut self, order: OrderResponse, trades: Vec<UserTradeResponse>) -> Self {
+        if !trades.is_empty() {
+            self.trades.insert(order.order_id, trades);
+        }
+        self.orders.insert(order.client_order_id.clone(), order);
+        self
+    }
+
+    /// Fails every lookup of the order with `client_order_id`.
+    pub fn with_unreachable_order(mut self, client_order_id: &str) -> Self {
+        self.unreachable_orders.push(client_order_id.to_string());
+        self
+    }
+
+    /// Fails every order placement as if the exchange timed out.
+    pub fn with_placement_timeouts(mut self) -> Self {
+        self.placements_time_out = true;
+        self
+    }
+
+    /// Rejects balance requests, as for API keys without permission.
+    pub fn with_rejected_keys(mut self) -> Self {
+        self.keys_rejected = true;
+        self
+    }
+
+    /// Panics on anything that changes the account.
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    /// Answers each kline request with one daily kline opening at the start of the range.
+    pub fn serving_klines(mut self) -> Self {
+        self.serves_klines = true;
+        self
+    }
+
+    /// Never completes kline requests for ranges starting at or after `at`.
+    pub fn hanging_from(mut self, at: DateTime<Utc>) -> Self {
+        self.klines_hang_from = Some(at);
+        self
+    }
+
+    /// The start of every kline range requested so far, in order.
+    pub fn kline_requests(&self) -> Vec<DateTime<Utc>> {
+        self.kline_requests.lock().unwrap().clone()
+    }
+
+    /// Every order placed so far, in order, whether it was accepted or not.
+    pub fn placed_orders(&self) -> Vec<OrderRequest> {
+        self.placed_orders.lock().unwrap().clone()
+    }
+
+    fn check_writable(&self, what: &str) {
+        assert!(!self.read_only, "the account is read-only, but was asked to {}", what);
+    }
+
+    /// Records `order` as placed and answers with its scripted response. An order without one
+    /// is rejected.
+    fn place(&self, order: &OrderRequest) -> Result<OrderResponse, ApiError> {
+        self.check_writable("place an order");
+        self.placed_orders.lock().unwrap().push(order.clone());
+        if self.placements_time_out {
+            return Err(ApiError::BinanceError(-1007, "Timeout waiting for response from backend server. Send status unknown; execution status unknown.".to_string()));
+        }
+        let client_order_id = order.client_order_id.to_string();
+        self.orders
+            .get(&client_order_id)
+            .cloned()
+            .ok_or_else(|| ApiError::BinanceError(-2010, format!("No response is scripted for order {}.", client_order_id)))
+    }
+}
+
+/// A balance of `amount` of `asset`, all of it available.
+pub fn balance(asset: &str, amount: Decimal) -> BalanceResponse {
+    BalanceResponse {
+        account_alias: String::new(),
+        asset: asset.to_string(),
+        balance: amount,
+        cross_wallet_balance: amount,
+        cross_un_pnl: Decimal::ZERO,
+        available_balance: amount,
+        max_withdraw_amount: amount,
+    }
+}
+
+#[async_trait]
+impl ApiClient for MockAccount {
+    async fn fetch_klines(&self, _: &str, interval: &str, start_time: DateTime<Utc>, _: DateTime<Utc>) -> Result<Vec<Kline>, ApiError> {
+        self.kline_requests.lock().unwrap().push(start_time);
+        if self.klines_hang_from.is_some_and(|hang_from| start_time >= hang_from) {
+            std::future::pending::<()>().await;
+        }
+        if !self.serves_klines {
+            return Ok(Vec::new());
+        }
+        let price = Decimal::from(100);
+        Ok(vec![Kline {
+            open_time: start_time,
+            open: price,
+            high: price + Decimal::ONE,
+            low: price - Decimal::ONE,
+            close: price,
+            volume: Decimal::TEN,
+            close_time: start_time + Duration::days(1) - Duration::milliseconds(1),
+            interval: interval.to_string(),
+        }])
+    }
+
+    async fn fetch_mark_price_klines(&self, symbol: &str, interval: &str, start_time: DateTime<Utc>, end_time: DateTime<Utc>) -> Result<Vec<Kline>, ApiError> {
+        self.fetch_klines(symbol, interval, start_time, end_time).await
+    }
+
+    async fn set_leverage(&self, _: &str, _: u8) -> Result<(), ApiError> {
+        self.check_writable("set the leverage");
+        Ok(())
+    }
+
+    async fn place_order(&self, order: &OrderRequest) -> Result<OrderResponse, ApiError> {
+        self.place(order)
+    }
+
+    async fn place_limit_order(&self, order: &OrderRequest) -> Result<OrderResponse, ApiError> {
+        self.place(order)
+    }
+
+    async fn get_user_trades(&self, _: &str, order_id: i64) -> Result<Vec<UserTradeResponse>, ApiError> {
+        Ok(self.trades.get(&order_id).cloned().unwrap_or_default())
+    }
+
+    async fn get_order(&self, _: &str, client_order_id: &str) -> Result<Option<OrderResponse>, ApiError> {
+        if self.unreachable_orders.iter().any(|id| id == client_order_id) {
+            return Err(ApiError::BinanceError(-1007, "Timeout waiting for response from backend server.".to_string()));
+        }
+        Ok(self.orders.get(client_order_id).cloned())
+    }
+
+    async fn get_account_balance(&self) -> Result<Vec<BalanceResponse>, ApiError> {
+        if self.keys_rejected {
+            return Err(ApiError::BinanceError(-2015, "Invalid API-key, IP, or permissions for action.".to_string()));
+        }
+        Ok(self.balances.clone())
+    }
+
+    async fn get_open_positions(&self) -> Result<Vec<PositionResponse>, ApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_exchange_info(&self) -> Result<ExchangeInfoResponse, ApiError> {
+        let symbols = self
+            .symbols
+            .iter()
+            .map(|symbol| SymbolInfo { symbol: symbol.clone(), status: Some("TRADING".to_string()), filters: Vec::new() })
+            .collect();
+        Ok(ExchangeInfoResponse { symbols })
+    }
+
+    async fn get_mark_price(&self, symbol: &str) -> Result<Decimal, ApiError> {
+        self.mark_prices.get(symbol).copied().ok_or_else(|| ApiError::BinanceError(-1121, "Invalid symbol.".to_string()))
+    }
+
+    async fn get_leverage_brackets(&self, symbol: &str) -> Result<SymbolBracketsResponse, ApiError> {
+        Ok(SymbolBracketsResponse { symbol: symbol.to_string(), brackets: self.leverage_brackets.clone() })
+    }
+
+    async fn get_income_history(&self, _: DateTime<Utc>, _: DateTime<Utc>) -> Result<Vec<IncomeRecord>, ApiError> {
+        Ok(Vec::new())
+    }
+
+    async fn get_position_mode(&self) -> Result<bool, ApiError> {
+        Ok(self.hedge_mode)
+    }
+
+    async fn set_position_mode(&self, _: bool) -> Result<(), ApiError> {
+        self.check_writable("change the position mode");
+        Ok(())
+    }
+}
@@ -0,0 +1,178 @@
+//! Seedable synthetic kline series for strategy smoke tests, demos and benchmarks.
+//!
+//! Every generator returns bars with consistent OHLC relationships (the high is at least
+//! the open and the close, the low at most both) that open where the previous bar closed,
+//! one interval apart. The random series are driven by a ChaCha RNG seeded from a `u64`,
+//! so a seed always yields the same series, on any machine.
+
+use chrono::{DateTime, Duration, Utc};
+use core_types::{Interval, Kline};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::StandardNormal;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use std::f64::consts::PI;
+
+use crate::fixtures::{seed_start, TEST_INTERVAL};
+
+/// Where a generated series starts and how far apart its bars are.
+#[derive(Debug, Clone)]
+pub struct SeriesSpec {
+    /// The open time of the first bar, rounded down to a multiple of the interval since the
+    /// Unix epoch.
+    pub start: DateTime<Utc>,
+    /// The Binance kline interval of the bars, e.g. "15m" or "1h".
+    pub interval: String,
+    /// The open of the first bar.
+    pub start_price: f64,
+    /// The decimal places prices are rounded to.
+    pub decimals: u32,
+}
+
+impl Default for SeriesSpec {
+    /// Hourly bars from `seed_start`, opening at 100, like the seeded fixture data.
+    fn default() -> Self {
+        Self { start: seed_start(), interval: TEST_INTERVAL.to_string(), start_price: 100.0, decimals: 2 }
+    }
+}
+
+impl SeriesSpec {
+    /// Bars of `interval` from `seed_start`, opening at 100.
+    pub fn new(interval: &str) -> Self {
+        Self { interval: interval.to_string(), ..Self::default() }
+    }
+
+    /// The length of a bar. Panics if `interval` is not a supported interval.
+    fn step(&self) -> Duration {
+        let interval: Interval = self.interval.parse().unwrap_or_else(|e| panic!("{}", e));
+        Duration::from_std(interval.duration()).expect("interval fits a chrono duration")
+    }
+
+    /// The open time of bar `index`.
+    fn open_time(&self, index: usize) -> DateTime<Utc> {
+        let step = self.step();
+        let step_ms = step.num_milliseconds();
+        let start_ms = self.start.timestamp_millis();
+        let aligned = DateTime::from_timestamp_millis(start_ms - start_ms.rem_euclid(step_ms)).expect("start is in range");
+        aligned + step * index as i32
+    }
+
+    /// `value` as a price of the series.
+    fn price(&self, value: f64) -> Decimal {
+        Decimal::from_f64(value).unwrap_or_default().round_dp(self.decimals)
+    }
+}
+
+/// A stretch of a `regime_switching` series with its own dynamics. Drift and volatility are
+/// per bar, in log-price terms: a drift of 0.001 is roughly +0.1% a bar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Regime {
+    pub bars: usize,
+    pub drift: f64,
+    pub volatility: f64,
+    /// How strongly the price is pulled back to where the regime started each bar, from 0
+    /// (a free random walk) to 1 (straight back). A chopping market reverts.
+    pub reversion: f64,
+}
+
+impl Regime {
+    /// A trending stretch: a random walk with `drift`.
+    pub fn trend(bars: usize, drift: f64, volatility: f64) -> Self {
+        Self { bars, drift, volatility, reversion: 0.0 }
+    }
+
+    /// A range-bound stretch that keeps reverting to the level it started at.
+    pub fn chop(bars: usize, volatility: f64) -> Self {
+        Self { bars, drift: 0.0, volatility, reversion: 0.2 }
+    }
+}
+
+/// The shape of a `sine_trend` series: a straight trend with a sine wave on top.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SineTrend {
+    /// The change in price per bar.
+    pub slope: f64,
+    pub amplitude: f64,
+    /// The length of a full wave, in bars.
+    pub period: f64,
+    /// How far each bar's wicks extend beyond its body.
+    pub wick: f64,
+}
+
+impl Default for SineTrend {
+    /// The shape of `generate_klines`.
+    fn default() -> Self {
+        Self { slope: 0.01, amplitude: 10.0, period: 240.0, wick: 0.25 }
+    }
+}
+
+/// Generates `count` bars following a geometric Brownian motion with the given per-bar
+/// `drift` and `volatility`.
+pub fn gbm(spec: &SeriesSpec, count: usize, drift: f64, volatility: f64, seed: u64) -> Vec<Kline> {
+    regime_switching(spec, &[Regime::trend(count, drift, volatility)], seed)
+}
+
+/// Generates the bars of each regime in turn, e.g. a trend, then chop, then a trend again.
+pub fn regime_switching(spec: &SeriesSpec, regimes: &[Regime], seed: u64) -> Vec<Kline> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut klines = Vec::with_capacity(regimes.iter().map(|regime| regime.bars).sum());
+    let mut log_price = spec.start_price.ln();
+
+    for regime in regimes {
+        let anchor = log_price;
+        for _ in 0..regime.bars {
+            let open = log_price.exp();
+            let shock: f64 = rng.sample(StandardNormal);
+            log_price += regime.drift - regime.volatility * regime.volatility / 2.0
+                + regime.reversion * (anchor - log_price)
+                + regime.volatility * shock;
+            let close = log_price.exp();
+
+            // The wicks reach up to about half a bar's volatility beyond the body.
+            let upper: f64 = rng.sample::<f64, _>(StandardNormal).abs() * regime.volatility / 2.0;
+            let lower: f64 = rng.sample::<f64, _>(StandardNormal).abs() * regime.volatility / 2.0;
+            let volume = 1000.0 * (0.25 * rng.sample::<f64, _>(StandardNormal)).exp();
+            let high = open.max(close) * (1.0 + upper);
+            let low = open.min(close) * (1.0 - lower).max(0.0);
+            let prices = [open, high, low, close].map(|price| spec.price(price));
+            klines.push(bar(spec, klines.len(), prices, Decimal::from_f64(volume).unwrap_or_default().round_dp(2)));
+        }
+    }
+    klines
+}
+
+/// Generates `count` bars whose close follows `shape` from `spec.start_price`, with no
+/// randomness at all. Each bar opens at the previous close and its wicks extend a fixed
+/// `shape.wick` beyond the body.
+pub fn sine_trend(spec: &SeriesSpec, count: usize, shape: &SineTrend) -> Vec<Kline> {
+    let wick = Decimal::from_f64(shape.wick).unwrap_or_default();
+    let mut klines = Vec::with_capacity(count);
+    let mut previous_close: Option<Decimal> = None;
+
+    for i in 0..count {
+        let x = i as f64;
+        let close = spec.price(spec.start_price + shape.slope * x + shape.amplitude * (2.0 * PI * x / shape.period).sin());
+        let open = previous_close.unwrap_or(close);
+        klines.push(bar(spec, i, [open, open.max(close) + wick, open.min(close) - wick, close], Decimal::from(1000)));
+        previous_close = Some(close);
+    }
+    klines
+}
+
+/// Builds bar `index` of a series from its rounded `[open, high, low, close]` prices.
+/// Rounding can move the body past a wick, so the wicks are widened to cover it again.
+fn bar(spec: &SeriesSpec, index: usize, prices: [Decimal; 4], volume: Decimal) -> Kline {
+    let [open, high, low, close] = prices;
+    let open_time = spec.open_time(index);
+    Kline {
+        open_time,
+        open,
+        high: high.max(open).max(close),
+        low: low.min(open).min(close),
+        close,
+        volume,
+        close_time: open_time + spec.step() - Duration::milliseconds(1),
+        interval: spec.interval.clone(),
+    }
+}
@@ -0,0 +1,88 @@
+//! A typed client of the server's `/ws` endpoint, which decodes frames into `WsMessage`s
+//! according to the protocol version it negotiated. The server's heartbeats, sent at times
+//! tests cannot control, are skipped.
+
+use events::{WsClientMessage, WsEnvelope, WsMessage, DEFAULT_WS_PROTOCOL_VERSION, WS_PROTOCOL_VERSION};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+use tokio_tungstenite::tungstenite::{self, Message};
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+pub struct WsTestClient {
+    socket: WebSocketStream<MaybeTlsStream<TcpStream>>,
+    /// The protocol version the server's frames are expected in.
+    version: u32,
+    close_frame: Option<CloseFrame<'static>>,
+}
+
+impl WsTestClient {
+    /// Connects to `url`, e.g. `ws://127.0.0.1:8080/ws?token=...`. Until a `hello`, frames
+    /// are expected in the default protocol version.
+    pub async fn connect(url: &str) -> Result<Self, tungstenite::Error> {
+        let (socket, _) = tokio_tungstenite::connect_async(url).await?;
+        Ok(Self { socket, version: DEFAULT_WS_PROTOCOL_VERSION, close_frame: None })
+    }
+
+    /// The protocol version the server's frames are expected in.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub async fn send(&mut self, message: &WsClientMessage) {
+        let text = serde_json::to_string(message).expect("a serializable client message");
+        self.socket.send(Message::Text(text)).await.expect("the socket accepts the frame");
+    }
+
+    /// Announces `supported_version`, and expects the frames after the server's reply in the
+    /// newest version both sides support.
+    pub async fn hello(&mut self, supported_version: u32) {
+        self.send(&WsClientMessage::ClientHello { supported_version }).await;
+        self.version = supported_version.min(WS_PROTOCOL_VERSION);
+    }
+
+    /// The next frame as sent, or `None` once the server closes the socket.
+    pub async fn next_frame(&mut self) -> Option<Value> {
+        loop {
+            match self.socket.next().await? {
+                Ok(Message::Text(text)) => {
+                    let frame: Value = serde_json::from_str(&text).unwrap_or_else(|e| panic!("not JSON ({}): {}", e, text));
+                    if !is_heartbeat(&frame) {
+                        return Some(frame);
+                    }
+                }
+                Ok(Message::Close(frame)) => {
+                    self.close_frame = frame.map(CloseFrame::into_owned);
+                    return None;
+                }
+                Ok(Message::Ping(_) | Message::Pong(_)) => continue,
+                other => panic!("unexpected frame: {:?}", other),
+            }
+        }
+    }
+
+    /// The next server message, or `None` once the server closes the socket. Panics if the
+    /// frame is not a message of the expected protocol version.
+    pub async fn next_message(&mut self) -> Option<WsMessage> {
+        let frame = self.next_frame().await?;
+        if self.version >= 2 {
+            let envelope: WsEnvelope = serde_json::from_value(frame.clone()).unwrap_or_else(|e| panic!("not an envelope ({}): {}", e, frame));
+            assert_eq!(envelope.v, self.version, "frame of the wrong version: {}", frame);
+            Some(envelope.message)
+        } else {
+            Some(serde_json::from_value(frame.clone()).unwrap_or_else(|e| panic!("not a version 1 message ({}): {}", e, frame)))
+        }
+    }
+
+    /// The frame the server closed the socket with, once it has.
+    pub fn close_frame(&self) -> Option<&CloseFrame<'static>> {
+        self.close_frame.as_ref()
+    }
+}
+
+/// Whether `frame`, of either version, is one of the server's heartbeat logs.
+fn is_heartbeat(frame: &Value) -> bool {
+    let data = frame.get("data").or_else(|| frame.get("payload"));
+    frame["type"] == "Log" && data.is_some_and(|data| data["message"] == "WebSocket heartbeat")
+}
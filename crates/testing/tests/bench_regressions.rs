@@ -0,0 +1,52 @@
+//! Flags benchmarks that got more than 20% slower than the recorded baseline.
+//!
+//! Compares criterion's latest estimates under `target/criterion` with the workspace's
+//! `benches/baseline.json`, so run the benchmarks first. Timings depend on the machine: the
+//! baseline only means something on the machine it was recorded on, and is re-recorded there
+//! (see `benches/BASELINE.md`).
+//!
+//! ```text
+//! cargo bench -p strategies -p executor -p backtester
+//! cargo test -p testing --test bench_regressions -- --ignored
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// How much slower than its baseline a benchmark may get.
+const TOLERANCE: f64 = 0.20;
+
+/// Where criterion writes its estimates.
+fn criterion_dir() -> PathBuf {
+    let target = std::env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(env!("CARGO_MANIFEST_DIR")).join("../../target"));
+    target.join("criterion")
+}
+
+/// The mean time of benchmark `id` in its latest run, in nanoseconds.
+fn latest_mean_ns(id: &str) -> Result<f64, String> {
+    let path = criterion_dir().join(id).join("new/estimates.json");
+    let estimates = std::fs::read_to_string(&path).map_err(|e| format!("{} has not been benchmarked ({}: {})", id, path.display(), e))?;
+    let estimates: serde_json::Value = serde_json::from_str(&estimates).map_err(|e| format!("{}: {}", path.display(), e))?;
+    estimates["mean"]["point_estimate"].as_f64().ok_or_else(|| format!("{} has no mean estimate", path.display()))
+}
+
+#[test]
+#[ignore = "compares the output of `cargo bench` with the recorded baseline"]
+fn no_benchmark_regressed_past_the_tolerance() {
+    let baseline = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/../../benches/baseline.json")).expect("read baseline");
+    let baseline: BTreeMap<String, f64> = serde_json::from_str(&baseline).expect("parse baseline");
+
+    let mut regressions = Vec::new();
+    for (id, baseline_ns) in &baseline {
+        match latest_mean_ns(id) {
+            Ok(latest_ns) if latest_ns > baseline_ns * (1.0 + TOLERANCE) => {
+                regressions.push(format!("{}: {:.0} ns, {:+.1}% against {:.0} ns", id, latest_ns, (latest_ns / baseline_ns - 1.0) * 100.0, baseline_ns));
+            }
+            Ok(_) => {}
+            Err(e) => regressions.push(e),
+        }
+    }
+    assert!(regressions.is_empty(), "benchmarks regressed by more than {}%:\n{}", TOLERANCE * 100.0, regressions.join("\n"));
+}
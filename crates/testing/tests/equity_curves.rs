@@ -0,0 +1,151 @@
+//! Tests of equity curve storage: batched inserts, thinned persistence and pruning.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use analytics::downsample::max_drawdown_points;
+use analyzer::Analyzer;
+use backtester::Backtester;
+use chrono::{Duration, Utc};
+use configuration::optimizer_config::{AnalysisConfig, BaseConfig, EquityCurveResolution, Filters, ObjectiveName, OptimizerConfig, ParameterRange};
+use configuration::Versioned;
+use core_types::StrategyId;
+use database::DbRepository;
+use executor::{Portfolio, SimulatedExecutor};
+use optimizer::Optimizer;
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use strategies::create_strategy;
+use testing::{generate_klines, seed_klines, test_config, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+const BARS: usize = 5000;
+
+async fn new_run(repo: &DbRepository) -> Uuid {
+    let job_id = Uuid::new_v4();
+    let run_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Running", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &serde_json::json!({}), "Pending").await.unwrap();
+    run_id
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn batched_insert_round_trips_a_curve_spanning_several_chunks() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let run_id = new_run(&repo).await;
+
+    let start = Utc::now();
+    let curve: Vec<_> = (0..12_345i64).map(|i| (start + Duration::minutes(i), Decimal::new(1_000_000 + i * 7 % 1013, 2))).collect();
+    repo.save_equity_curve(run_id, &curve).await.expect("save equity curve");
+
+    let stored = repo.get_equity_curve(run_id).await.expect("load equity curve");
+    assert_eq!(stored.len(), curve.len());
+    assert!(stored.iter().zip(&curve).all(|(a, b)| a.0.timestamp_micros() == b.0.timestamp_micros() && a.1 == b.1));
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn thinned_persistence_stores_the_exact_drawdown_trough() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let klines = generate_klines(BARS);
+    let config = test_config(BARS).expect("load config");
+
+    for resolution in [EquityCurveResolution::EveryNth(97), EquityCurveResolution::Lttb(200)] {
+        let run_id = new_run(&repo).await;
+        let mut backtester = Backtester::new(
+            run_id,
+            TEST_SYMBOL.to_string(),
+            TEST_INTERVAL.to_string(),
+            config.clone(),
+            Portfolio::new(config.backtest.initial_capital),
+            create_strategy(StrategyId::MACrossover, &config, TEST_SYMBOL).unwrap(),
+            Box::new(SimpleRiskManager::new(config.risk_management.clone()).unwrap()),
+            Box::new(SimulatedExecutor::new(config.simulation.clone())),
+            analytics::AnalyticsEngine::new(),
+            repo.clone(),
+        )
+        .with_equity_curve_resolution(resolution);
+
+        let (trades, full_curve) = backtester.simulate(&klines).await.expect("simulate");
+        let report = backtester.finalize(&trades, &full_curve).await.expect("finalize");
+        let stored = repo.get_equity_curve(run_id).await.expect("load equity curve");
+
+        let (peak, trough) = max_drawdown_points(&full_curve).expect("the run has a drawdown");
+        assert!(stored.len() < full_curve.len() / 20, "{:?} stored {} points", resolution, stored.len());
+        assert!(stored.contains(&full_curve[trough]), "{:?} dropped the trough", resolution);
+        assert!(stored.contains(&full_curve[peak]), "{:?} dropped the peak", resolution);
+
+        let (stored_peak, stored_trough) = max_drawdown_points(&stored).unwrap();
+        assert_eq!(stored[stored_peak].1 - stored[stored_trough].1, report.max_drawdown);
+    }
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn pruning_thins_all_but_the_top_runs_of_old_jobs() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(BARS)).await.expect("seed klines");
+
+    let optimizer_config = OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space: HashMap::from([
+            ("ma_fast_period".to_string(), ParameterRange::DiscreteInt(vec![5, 10])),
+            ("ma_slow_period".to_string(), ParameterRange::DiscreteInt(vec![40, 60])),
+        ]),
+        analysis: AnalysisConfig {
+            filters: Filters { min_total_trades: 1, max_drawdown_pct: Decimal::from(100), ..Filters::default() },
+            ..AnalysisConfig::default()
+        },
+        wfo: None,
+        max_concurrency: None,
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::AnalyzerScore,
+    };
+    let optimizer = Optimizer::new(optimizer_config.clone(), test_config(BARS).expect("load config"), repo.clone());
+    let job_id = optimizer.job_id();
+    optimizer.run().await.expect("optimizer run");
+
+    let analyzer = Analyzer::new(optimizer_config.analysis);
+    let best = analyzer.run(&repo, job_id).await.expect("analyze")[0].report.run_id;
+
+    // Jobs newer than the cutoff are left alone.
+    let summary = analyzer.prune_equity_curves(&repo, Utc::now() - Duration::days(90), 1, 100).await.unwrap();
+    assert_eq!(summary.runs_pruned, 0);
+
+    let summary = analyzer.prune_equity_curves(&repo, Utc::now() + Duration::minutes(1), 1, 100).await.unwrap();
+    assert_eq!((summary.jobs_scanned, summary.runs_pruned), (1, 3));
+
+    for report in repo.get_full_reports_for_job(job_id).await.unwrap() {
+        let stored = repo.get_equity_curve(report.run_id).await.unwrap();
+        if report.run_id == best {
+            assert_eq!(stored.len(), BARS);
+        } else {
+            assert!(stored.len() <= 102, "{} points left", stored.len());
+            let (peak, trough) = max_drawdown_points(&stored).unwrap();
+            assert_eq!(stored[peak].1 - stored[trough].1, report.max_drawdown.unwrap());
+        }
+    }
+
+    // Pruning again finds nothing left to thin.
+    let summary = analyzer.prune_equity_curves(&repo, Utc::now() + Duration::minutes(1), 1, 100).await.unwrap();
+    assert_eq!(summary.runs_pruned, 0);
+
+    db.teardown().await.expect("drop test database");
+}
@@ -0,0 +1,96 @@
+//! Tests of the stored execution quality of live limit orders.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{Execution, OrderPlacement, OrderSide};
+use database::DbRepository;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{TestDatabase, TEST_SYMBOL};
+use uuid::Uuid;
+
+fn at(minutes: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 6, 2, 0, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+/// Records a `side` limit order of `symbol`, placed against a 99.9 / 100.1 book `minutes` into
+/// the day and, if given, confirmed filled at `fill`'s price, as a maker or not.
+async fn record(repo: &DbRepository, client_order_id: Uuid, symbol: &str, side: OrderSide, fill: Option<(Decimal, bool)>, minutes: i64) {
+    let mut placement = OrderPlacement::new(dec!(99.9), dec!(100.1), dec!(100), at(minutes));
+    if let Some((fill_price, is_maker)) = fill {
+        placement.record_fill(side, fill_price, is_maker, at(minutes));
+    }
+    let execution = Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id,
+        symbol: symbol.to_string(),
+        side,
+        price: placement.fill_price.unwrap_or(placement.limit_price),
+        quantity: dec!(1),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: at(minutes),
+        decision_id: None,
+        is_maker: placement.is_maker.unwrap_or(true),
+        spread_cost: Decimal::ZERO,
+        placement: Some(placement.clone()),
+    };
+    repo.save_execution_quality(&execution, &placement).await.expect("save execution quality");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn fills_are_summarised_by_symbol_within_the_range() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let order = Uuid::new_v4();
+    // Saves 18 bps at the first confirmation; the later one, 2 bps.
+    record(&repo, order, TEST_SYMBOL, OrderSide::Buy, Some((dec!(99.92), true)), 0).await;
+    record(&repo, order, TEST_SYMBOL, OrderSide::Buy, Some((dec!(100.08), true)), 1).await;
+    // Saves 10 bps, and was chased across the spread.
+    record(&repo, Uuid::new_v4(), TEST_SYMBOL, OrderSide::Sell, Some((dec!(100), false)), 5).await;
+    record(&repo, Uuid::new_v4(), "ETHUSDT", OrderSide::Sell, Some((dec!(100.1), true)), 60).await;
+
+    let quality = repo.get_execution_quality(None, None).await.expect("load execution quality");
+    assert_eq!(quality.len(), 2);
+    let btc = quality.iter().find(|symbol| symbol.symbol == TEST_SYMBOL).unwrap();
+    assert_eq!((btc.limit_fills, btc.maker_fills), (2, 1));
+    assert_eq!(btc.maker_fill_ratio, dec!(0.5));
+    assert_eq!(btc.avg_improvement_bps, Some(dec!(6)));
+
+    let early = repo.get_execution_quality(Some(at(0)), Some(at(60))).await.expect("load execution quality");
+    assert_eq!(early.iter().map(|symbol| symbol.symbol.as_str()).collect::<Vec<_>>(), [TEST_SYMBOL]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn unconfirmed_orders_are_stored_but_left_out_of_the_summary() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let resting = Uuid::new_v4();
+    record(&repo, resting, TEST_SYMBOL, OrderSide::Buy, None, 0).await;
+    record(&repo, Uuid::new_v4(), TEST_SYMBOL, OrderSide::Buy, Some((dec!(99.92), false)), 1).await;
+
+    let unconfirmed: (Option<Decimal>, Option<bool>, Option<Decimal>) =
+        sqlx::query_as("SELECT fill_price, is_maker, price_improvement_bps FROM live_execution_quality WHERE client_order_id = $1")
+            .bind(resting)
+            .fetch_one(&db.pool)
+            .await
+            .expect("load the unconfirmed order");
+    assert_eq!(unconfirmed, (None, None, None));
+    let quality = repo.get_execution_quality(None, None).await.expect("load execution quality");
+    assert_eq!((quality[0].limit_fills, quality[0].maker_fills), (1, 0));
+    assert_eq!(quality[0].maker_fill_ratio, Decimal::ZERO);
+
+    // Its fill, once confirmed, counts; a later unconfirmed record does not undo that.
+    record(&repo, resting, TEST_SYMBOL, OrderSide::Buy, Some((dec!(99.92), true)), 2).await;
+    record(&repo, resting, TEST_SYMBOL, OrderSide::Buy, None, 3).await;
+    let quality = repo.get_execution_quality(None, None).await.expect("load execution quality");
+    assert_eq!((quality[0].limit_fills, quality[0].maker_fills), (2, 1));
+    assert_eq!(quality[0].avg_improvement_bps, Some(dec!(18)));
+}
@@ -0,0 +1,124 @@
+//! Tests of the live position history rebuilt from recorded executions.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use chrono::{DateTime, Duration, Utc};
+use core_types::{CloseReason, Execution, OrderSide, StrategyId};
+use database::{DbRepository, LivePositionFilter, LivePositionStatus, PositionContext};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{TestDatabase, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// Fills `side` for `quantity` at `price` against `portfolio` and records it, as the live
+/// engine does.
+async fn fill(repo: &DbRepository, portfolio: &mut Portfolio, minutes: i64, side: OrderSide, quantity: Decimal, price: Decimal) {
+    let execution = Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side,
+        price,
+        quantity,
+        fee: dec!(0.1),
+        fee_asset: "USDT".to_string(),
+        timestamp: Utc::now() - Duration::hours(1) + Duration::minutes(minutes),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    let fills = portfolio.update_with_execution(&execution).expect("apply execution");
+    repo.save_live_execution(&execution, &fills, Some(CloseReason::Signal)).await.expect("save execution");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn open_add_partial_close_and_close_make_one_position() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let mut portfolio = Portfolio::new(dec!(10000));
+
+    fill(&repo, &mut portfolio, 0, OrderSide::Buy, dec!(1), dec!(100)).await;
+    fill(&repo, &mut portfolio, 1, OrderSide::Buy, dec!(1), dec!(110)).await;
+    fill(&repo, &mut portfolio, 2, OrderSide::Sell, dec!(1), dec!(120)).await;
+
+    let open = repo.get_live_positions(&LivePositionFilter::default()).await.expect("load positions");
+    assert_eq!(open.len(), 1);
+    assert_eq!(open[0].status, LivePositionStatus::Open);
+    assert_eq!(open[0].quantity, dec!(1));
+    assert_eq!(open[0].avg_entry_price, Some(dec!(105)));
+    assert_eq!(open[0].realized_pnl, dec!(15) - dec!(0.3));
+    assert_eq!(open[0].close_reason, None);
+
+    fill(&repo, &mut portfolio, 3, OrderSide::Sell, dec!(1), dec!(130)).await;
+
+    let positions = repo.get_live_positions(&LivePositionFilter::default()).await.expect("load positions");
+    assert_eq!(positions.len(), 1);
+    let position = &positions[0];
+    assert_eq!(position.position_id, open[0].position_id);
+    assert_eq!(position.symbol, TEST_SYMBOL);
+    assert_eq!(position.side, OrderSide::Buy);
+    assert_eq!(position.status, LivePositionStatus::Closed);
+    assert!(position.exit_time.is_some());
+    assert_eq!(position.avg_entry_price, Some(dec!(105)));
+    assert_eq!(position.avg_exit_price, Some(dec!(125)));
+    assert_eq!(position.quantity, Decimal::ZERO);
+    assert_eq!(position.max_quantity, dec!(2));
+    assert_eq!(position.fees, dec!(0.4));
+    assert_eq!(position.realized_pnl, dec!(40) - dec!(0.4));
+    assert_eq!(position.realized_pnl, portfolio.realized_pnl);
+    assert_eq!(position.close_reason, Some(CloseReason::Signal));
+
+    let open_only = LivePositionFilter { status: Some(LivePositionStatus::Open), ..LivePositionFilter::default() };
+    assert!(repo.get_live_positions(&open_only).await.expect("load positions").is_empty());
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn only_the_latest_position_context_per_symbol_stays_open() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    // Whole seconds, as the database keeps microseconds.
+    let opened_at = DateTime::from_timestamp(Utc::now().timestamp(), 0).unwrap() - Duration::hours(2);
+    let first = PositionContext {
+        position_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side: OrderSide::Buy,
+        strategy_id: Some(StrategyId::MACrossover),
+        entry_signal_id: Some(Uuid::new_v4()),
+        stop_price: Some(dec!(98.50)),
+        take_profit_price: None,
+        opened_at,
+        closed_at: None,
+    };
+    repo.open_position_context(&first).await.expect("save context");
+    assert_eq!(repo.get_open_position_contexts().await.expect("load contexts"), vec![first.clone()]);
+
+    // A newer position on the symbol replaces the one that was left open.
+    let second = PositionContext {
+        position_id: Uuid::new_v4(),
+        side: OrderSide::Sell,
+        strategy_id: None,
+        entry_signal_id: None,
+        stop_price: Some(dec!(101)),
+        opened_at: opened_at + Duration::hours(1),
+        ..first
+    };
+    repo.open_position_context(&second).await.expect("save context");
+    let open = repo.get_open_position_contexts().await.expect("load contexts");
+    assert_eq!(open.len(), 1);
+    assert_eq!((open[0].position_id, open[0].side, open[0].strategy_id), (second.position_id, OrderSide::Sell, None));
+
+    repo.close_position_context(TEST_SYMBOL, Utc::now()).await.expect("close context");
+    assert!(repo.get_open_position_contexts().await.expect("load contexts").is_empty());
+
+    db.teardown().await.expect("drop test database");
+}
@@ -0,0 +1,111 @@
+//! Tests of the order ledger in the database: an order's intent is written before it is sent,
+//! resolved as filled with its execution's write, and left unresolved for startup recovery
+//! when neither the execution nor a resolution was written.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use chrono::{DateTime, TimeZone, Utc};
+use core_types::{Execution, OrderRequest, OrderSide, OrderType, PositionFill};
+use database::{OrderIntent, OrderIntentState};
+use engine::order_ledger;
+use engine::persistence::{Persistence, PersistenceSettings};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::TestDatabase;
+use uuid::Uuid;
+
+fn at(minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 19, 12, minute, 0).unwrap()
+}
+
+/// The intent of a market buy of one BTCUSDT, sent at `minute`.
+fn intent(minute: u32) -> OrderIntent {
+    let order = OrderRequest {
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side: OrderSide::Buy,
+        order_type: OrderType::Market,
+        quantity: dec!(1),
+        price: None,
+        position_side: None,
+        time_in_force: None,
+        reduce_only: false,
+        decision_id: Some(Uuid::new_v4()),
+    };
+    order_ledger::intent(&order, at(minute))
+}
+
+/// The fill of `intent`'s order at 100, at `minute`, opening a position.
+fn filled(intent: &OrderIntent, minute: u32) -> (Execution, PositionFill) {
+    let execution = Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: intent.client_order_id,
+        symbol: intent.symbol.clone(),
+        side: intent.side,
+        price: dec!(100),
+        quantity: intent.quantity,
+        fee: dec!(0.04),
+        fee_asset: "USDT".to_string(),
+        timestamp: at(minute),
+        decision_id: intent.decision_id,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    let fill = PositionFill {
+        position_id: Uuid::new_v4(),
+        position_side: intent.side,
+        is_entry: true,
+        quantity: intent.quantity,
+        fee: execution.fee,
+        realized_pnl: Decimal::ZERO,
+        position_quantity: intent.quantity,
+    };
+    (execution, fill)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn an_intent_is_resolved_by_its_execution_or_left_for_recovery() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let persistence = Persistence::spawn(repo.clone(), PersistenceSettings::default());
+
+    // Three orders are sent: one fills, one is refused, and the engine crashes before it hears
+    // back about the third.
+    let (confirmed, refused, in_flight) = (intent(0), intent(1), intent(2));
+    for intent in [&confirmed, &refused, &in_flight] {
+        persistence.record_order_intent(intent).await.expect("record intent");
+    }
+    assert_eq!(repo.get_unresolved_order_intents().await.expect("load ledger").len(), 3);
+
+    let (execution, fill) = filled(&confirmed, 3);
+    assert_eq!(persistence.record_execution(&execution, &[fill], None).await.await, Ok(true));
+    persistence.resolve_order_intent(refused.client_order_id, OrderIntentState::Failed, None, at(4)).await;
+    persistence.shutdown().await;
+
+    let stored = repo.get_order_intent(confirmed.client_order_id).await.expect("load intent").expect("recorded intent");
+    assert_eq!(stored.state, OrderIntentState::Filled);
+    assert_eq!((stored.execution_id, stored.resolved_at), (Some(execution.execution_id), Some(at(3))));
+    assert_eq!((stored.symbol.as_str(), stored.side, stored.quantity, stored.decision_id), ("BTCUSDT", OrderSide::Buy, dec!(1), confirmed.decision_id));
+    let stored = repo.get_order_intent(refused.client_order_id).await.expect("load intent").expect("recorded intent");
+    assert_eq!((stored.state, stored.execution_id), (OrderIntentState::Failed, None));
+
+    // Only the order whose fate was never written is left for startup recovery.
+    assert_eq!(repo.get_unresolved_order_intents().await.expect("load ledger"), std::slice::from_ref(&in_flight));
+
+    // Recovery's resolution is final: a second resolution does not change it, nor does
+    // writing the intent again reopen it.
+    assert!(repo.resolve_order_intent(in_flight.client_order_id, OrderIntentState::Expired, None, at(10)).await.expect("resolve"));
+    assert!(!repo.resolve_order_intent(in_flight.client_order_id, OrderIntentState::Failed, None, at(11)).await.expect("resolve"));
+    repo.save_order_intent(&in_flight).await.expect("rewrite intent");
+    let stored = repo.get_order_intent(in_flight.client_order_id).await.expect("load intent").expect("recorded intent");
+    assert_eq!((stored.state, stored.resolved_at), (OrderIntentState::Expired, Some(at(10))));
+    assert!(repo.get_unresolved_order_intents().await.expect("load ledger").is_empty());
+
+    db.teardown().await.expect("teardown");
+}
@@ -0,0 +1,132 @@
+//! Decimal parameters travelling from the optimizer's generator through the database to the
+//! strategy, checking that no value loses a digit on the way.
+//!
+//! The PostgreSQL round trip is ignored by default. Run it with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing --test parameter_round_trip -- --ignored
+//! ```
+
+use configuration::optimizer_config::{AnalysisConfig, BaseConfig, EquityCurveResolution, ObjectiveName, OptimizerConfig, ParameterRange};
+use configuration::{normalize_params, Config, FundingRateArbParams, Versioned};
+use core_types::StrategyId;
+use optimizer::generator::generate_valid_parameter_sets;
+use optimizer::Optimizer;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::{json, Value};
+use std::collections::{BTreeSet, HashMap};
+use strategies::{create_strategy_from_params, merge_params};
+use testing::{test_config, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+const SEED: u64 = 1910;
+
+/// `count` positive decimals with up to 12 decimal places, drawn from a seeded RNG, and the
+/// small thresholds that once came back from a float slightly off.
+fn thresholds(count: usize) -> Vec<Decimal> {
+    let mut rng = ChaCha8Rng::seed_from_u64(SEED);
+    let mut thresholds: Vec<Decimal> = (0..count).map(|_| Decimal::new(rng.gen_range(1..=10_000_000), rng.gen_range(0..=12))).collect();
+    thresholds.extend([dec!(0.0001), Decimal::from_scientific("1e-6").unwrap(), dec!(0.00000100)]);
+    thresholds
+}
+
+/// A FundingRateArb job over `thresholds` and five basis limits.
+fn optimizer_config(thresholds: Vec<Decimal>) -> OptimizerConfig {
+    OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::FundingRateArb,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space: HashMap::from([
+            ("target_rate_threshold".to_string(), ParameterRange::DiscreteDecimal(thresholds)),
+            (
+                "basis_safety_threshold".to_string(),
+                ParameterRange::LinearDecimal { start: dec!(0.0001), end: dec!(0.0005), step: dec!(0.0001) },
+            ),
+        ]),
+        analysis: AnalysisConfig::default(),
+        wfo: None,
+        max_concurrency: None,
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::AnalyzerScore,
+    }
+}
+
+/// The thresholds the strategy is built with from the stored set `params`.
+fn strategy_thresholds(base_config: &Config, params: &Value) -> (Decimal, Decimal) {
+    let merged = merge_params(StrategyId::FundingRateArb, base_config, params).unwrap();
+    create_strategy_from_params(StrategyId::FundingRateArb, &merged, TEST_SYMBOL).unwrap();
+    let params: FundingRateArbParams = serde_json::from_value(merged).unwrap();
+    (params.target_rate_threshold, params.basis_safety_threshold)
+}
+
+#[test]
+fn generated_decimals_reach_the_strategy_exactly() {
+    let thresholds = thresholds(200);
+    let base_config = test_config(100).unwrap();
+    let sets = generate_valid_parameter_sets(&optimizer_config(thresholds.clone()), &base_config).unwrap();
+    assert_eq!((sets.sets.len(), sets.skipped), (thresholds.len() * 5, 0));
+
+    let mut targets = BTreeSet::new();
+    let mut bases = BTreeSet::new();
+    for params in &sets.sets {
+        assert_eq!(&normalize_params(params.clone()), params, "a generated set is not canonical");
+        let (target, basis) = strategy_thresholds(&base_config, params);
+        targets.insert(target);
+        bases.insert(basis);
+    }
+    assert_eq!(targets, thresholds.into_iter().collect());
+    assert_eq!(bases, BTreeSet::from([dec!(0.0001), dec!(0.0002), dec!(0.0003), dec!(0.0004), dec!(0.0005)]));
+}
+
+#[test]
+fn small_thresholds_in_any_form_build_the_same_strategy() {
+    let base_config = test_config(100).unwrap();
+    for (params, expected) in [
+        (json!({ "target_rate_threshold": 0.0001 }), dec!(0.0001)),
+        (json!({ "target_rate_threshold": "1e-4" }), dec!(0.0001)),
+        (json!({ "target_rate_threshold": 1e-6 }), dec!(0.000001)),
+        (json!({ "target_rate_threshold": "1e-6" }), dec!(0.000001)),
+        (json!({ "target_rate_threshold": "0.0000010" }), dec!(0.000001)),
+    ] {
+        assert_eq!(strategy_thresholds(&base_config, &params).0, expected, "{}", params);
+        let merged = merge_params(StrategyId::FundingRateArb, &base_config, &params).unwrap();
+        assert_eq!(merged["target_rate_threshold"], json!(expected.normalize().to_string()), "{}", params);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn generated_decimals_survive_the_database_exactly() {
+    let db = TestDatabase::create().await.unwrap();
+    let repo = db.repo();
+    let base_config = test_config(100).unwrap();
+    let optimizer_config = optimizer_config(thresholds(50));
+    let sets = generate_valid_parameter_sets(&optimizer_config, &base_config).unwrap().sets;
+    let optimizer = Optimizer::new(optimizer_config, base_config.clone(), repo.clone());
+    let job_id = optimizer.job_id();
+    repo.save_optimization_job(job_id, "FundingRateArb", TEST_SYMBOL, "Running", None).await.unwrap();
+
+    // The generated sets, and two written as older runs and hand-edited ones were.
+    let mut saved = HashMap::new();
+    let legacy = [json!({ "target_rate_threshold": 0.0001 }), json!({ "target_rate_threshold": "1e-6" })];
+    for params in sets.into_iter().chain(legacy) {
+        let run_id = Uuid::new_v4();
+        repo.save_backtest_run(run_id, job_id, &params, "Pending").await.unwrap();
+        saved.insert(run_id, params);
+    }
+
+    let runs = repo.get_pending_runs(job_id).await.unwrap();
+    assert_eq!(runs.len(), saved.len());
+    for run in runs {
+        let params = &saved[&run.run_id];
+        assert_eq!(&run.parameters, params, "run {} came back changed", run.run_id);
+        optimizer.create_strategy_instance(&run.parameters).unwrap();
+        assert_eq!(strategy_thresholds(&base_config, &run.parameters), strategy_thresholds(&base_config, params));
+    }
+}
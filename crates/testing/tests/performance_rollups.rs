@@ -0,0 +1,159 @@
+//! Tests of the daily and weekly rollups of live performance.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use analyzer::rollup;
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide};
+use database::{DbRepository, PerformanceRollup, RollupGranularity};
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{TestDatabase, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// A Monday, so both seeded days fall in the same week.
+fn monday() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 6, 2).unwrap()
+}
+
+/// `hour` hours into the Monday.
+fn at(hour: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 6, 2, 0, 0, 0).unwrap() + chrono::Duration::hours(hour.into())
+}
+
+/// Fills `side` for one unit at `price` against `portfolio` and records it, as the live
+/// engine does.
+async fn fill(repo: &DbRepository, portfolio: &mut Portfolio, hour: u32, side: OrderSide, price: Decimal) {
+    let execution = Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side,
+        price,
+        quantity: dec!(1),
+        fee: dec!(0.1),
+        fee_asset: "USDT".to_string(),
+        timestamp: at(hour),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    let fills = portfolio.update_with_execution(&execution).expect("apply execution");
+    repo.save_live_execution(&execution, &fills, Some(CloseReason::Signal)).await.expect("save execution");
+}
+
+/// Seeds two days of live trading.
+///
+/// Monday: a long is opened at 100, added to at 110 and half closed at 120, locking in 15.
+/// Tuesday: the rest is closed at 95, losing 10, and a short opened at 90 is closed at 80,
+/// gaining 10. Every fill pays a 0.1 fee.
+async fn seed_two_days(repo: &DbRepository) {
+    let mut portfolio = Portfolio::new(dec!(10000));
+    fill(repo, &mut portfolio, 10, OrderSide::Buy, dec!(100)).await;
+    fill(repo, &mut portfolio, 11, OrderSide::Buy, dec!(110)).await;
+    fill(repo, &mut portfolio, 15, OrderSide::Sell, dec!(120)).await;
+    fill(repo, &mut portfolio, 24 + 9, OrderSide::Sell, dec!(95)).await;
+    fill(repo, &mut portfolio, 24 + 12, OrderSide::Sell, dec!(90)).await;
+    fill(repo, &mut portfolio, 24 + 14, OrderSide::Buy, dec!(80)).await;
+
+    let equity = [
+        (0, dec!(10000)),
+        (12, dec!(10020)),
+        (13, dec!(9990)),
+        (18, dec!(10014.7)),
+        (24 + 10, dec!(10004.6)),
+        (24 + 16, dec!(10014.4)),
+    ];
+    for (hour, value) in equity {
+        repo.save_live_equity(at(hour), value).await.expect("save equity");
+    }
+}
+
+/// The stored rollups without the time they were computed at.
+async fn stored(repo: &DbRepository, granularity: RollupGranularity) -> Vec<PerformanceRollup> {
+    let mut rollups = repo.get_performance_rollups(granularity, None, None).await.expect("load rollups");
+    for rollup in &mut rollups {
+        rollup.computed_at = DateTime::<Utc>::MIN_UTC;
+    }
+    rollups
+}
+
+/// A rollup of `(count, wins)` trades whose largest drawdown was `(amount, peak it fell from)`.
+fn expected(
+    period_start: NaiveDate,
+    net_pnl: Decimal,
+    fees: Decimal,
+    (trades, wins): (i32, i32),
+    (max_drawdown, peak): (Decimal, Decimal),
+    ending_equity: Decimal,
+) -> PerformanceRollup {
+    PerformanceRollup {
+        period_start,
+        net_pnl,
+        fees,
+        trade_count: trades,
+        winning_trades: wins,
+        win_rate_pct: Some(Decimal::from(wins) / Decimal::from(trades) * dec!(100)),
+        max_drawdown,
+        max_drawdown_pct: max_drawdown / peak * dec!(100),
+        ending_equity: Some(ending_equity),
+        computed_at: DateTime::<Utc>::MIN_UTC,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn two_days_roll_up_into_days_and_their_week() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_two_days(&repo).await;
+    let tuesday = monday().succ_opt().unwrap();
+
+    let summary = rollup::recompute(&repo, monday(), tuesday).await.expect("recompute");
+    assert_eq!(summary, rollup::RecomputeSummary { days: 2, weeks: 1 });
+
+    let days = stored(&repo, RollupGranularity::Daily).await;
+    assert_eq!(
+        days,
+        vec![
+            // 15 locked in less three fees; equity fell from 10020 to 9990.
+            expected(monday(), dec!(14.7), dec!(0.3), (1, 1), (dec!(30), dec!(10020)), dec!(10014.7)),
+            // -10 and +10 less three fees; measured from Monday's closing equity.
+            expected(tuesday, dec!(-0.3), dec!(0.3), (2, 1), (dec!(10.1), dec!(10014.7)), dec!(10014.4)),
+        ]
+    );
+    let weeks = stored(&repo, RollupGranularity::Weekly).await;
+    assert_eq!(weeks, vec![expected(monday(), dec!(14.4), dec!(0.6), (3, 2), (dec!(30), dec!(10020)), dec!(10014.4))]);
+
+    // Recomputing stores the same figures, and a day with no activity carries the equity over.
+    let wednesday = tuesday.succ_opt().unwrap();
+    rollup::recompute(&repo, monday(), wednesday).await.expect("recompute again");
+    let days_again = stored(&repo, RollupGranularity::Daily).await;
+    assert_eq!(days_again[..2], days[..]);
+    assert_eq!(days_again[2].trade_count, 0);
+    assert_eq!(days_again[2].win_rate_pct, None);
+    assert_eq!(days_again[2].net_pnl, Decimal::ZERO);
+    assert_eq!(days_again[2].ending_equity, Some(dec!(10014.4)));
+    assert_eq!(stored(&repo, RollupGranularity::Weekly).await, weeks);
+
+    let tuesday_only = repo.get_performance_rollups(RollupGranularity::Daily, Some(tuesday), Some(tuesday)).await.expect("load rollups");
+    assert_eq!(tuesday_only.len(), 1);
+    assert_eq!(tuesday_only[0].period_start, tuesday);
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[test]
+fn periods_start_on_the_day_or_its_monday() {
+    let sunday = NaiveDate::from_ymd_opt(2025, 6, 8).unwrap();
+    assert_eq!(rollup::period_start(RollupGranularity::Daily, sunday), sunday);
+    assert_eq!(rollup::period_start(RollupGranularity::Weekly, sunday), monday());
+    assert_eq!(rollup::period_start(RollupGranularity::Weekly, monday()), monday());
+    assert_eq!(rollup::period_bounds(RollupGranularity::Weekly, monday()), (at(0), at(7 * 24)));
+}
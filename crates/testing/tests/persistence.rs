@@ -0,0 +1,356 @@
+//! Tests of the live engine's write-behind persistence: what a full queue drops, what a
+//! shutdown writes, that the portfolio's stored events rebuild it, and what queueing costs
+//! the decision path, beside the order ledger's write it waits for.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing --test persistence -- --ignored
+//! ```
+
+use chrono::{Duration, Utc};
+use core_types::{CloseReason, Correction, CorrectionSource, DecisionStage, Execution, Kline, OrderRequest, OrderSide, OrderType, Signal, SignalIntent, StrategyId};
+use database::{DecisionAuditEntry, OrderIntentState};
+use engine::event::MarketState;
+use engine::persistence::{Persistence, PersistenceSettings};
+use engine::order_ledger;
+use engine::{Bot, PipelineOutcome, SignalPipeline};
+use events::{BotActivity, EventBus, PortfolioState};
+use executor::{Portfolio, SimulatedExecutor};
+use risk::{OrderPlan, RiskError, RiskManager};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration as StdDuration;
+use strategies::{KlineTransformer, Strategy, StrategyError};
+use testing::{generate_klines, seed_start, test_config, TestDatabase, TEST_SYMBOL};
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Settings that never write a batch on their own within a test.
+fn slow_batches(queue_capacity: usize) -> PersistenceSettings {
+    PersistenceSettings { queue_capacity, batch_interval: StdDuration::from_secs(3600), batch_size: 10_000 }
+}
+
+fn audit(decision_id: Uuid) -> DecisionAuditEntry {
+    DecisionAuditEntry {
+        decision_id,
+        stage: DecisionStage::Signal,
+        symbol: TEST_SYMBOL.to_string(),
+        payload: serde_json::json!({ "price": 100 }),
+        recorded_at: Utc::now(),
+        sequence: 1,
+    }
+}
+
+/// A market buy of one unit at 100, `minutes` after the seeded series starts.
+fn execution(minutes: i64) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side: OrderSide::Buy,
+        price: dec!(100),
+        quantity: Decimal::ONE,
+        fee: dec!(0.04),
+        fee_asset: "USDT".to_string(),
+        timestamp: seed_start() + Duration::minutes(minutes),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// Applies `execution` to `portfolio` and queues it, as the pipeline does.
+async fn record(persistence: &Persistence, portfolio: &mut Portfolio, execution: &Execution) -> engine::persistence::Ack {
+    let fills = portfolio.update_with_execution(execution).expect("apply execution");
+    persistence.record_execution(execution, &fills, Some(CloseReason::Signal)).await
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn shutdown_writes_every_queued_record() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let persistence = Persistence::spawn(repo.clone(), slow_batches(1000));
+
+    let mut portfolio = Portfolio::new(dec!(100000));
+    let decision_ids: Vec<Uuid> = (0..50).map(|_| Uuid::new_v4()).collect();
+    let mut acks = Vec::new();
+    for (minute, decision_id) in decision_ids.iter().enumerate() {
+        persistence.record_audit(audit(*decision_id));
+        acks.push(record(&persistence, &mut portfolio, &execution(minute as i64)).await);
+    }
+    let equity_at = Utc::now();
+    persistence.record_equity(equity_at, dec!(12345));
+    persistence.shutdown().await;
+
+    // Every execution was written and acknowledged before the shutdown returned.
+    for ack in acks {
+        assert_eq!(ack.await, Ok(true));
+    }
+    let fills = repo.get_live_fills(seed_start(), seed_start() + Duration::hours(1)).await.expect("load fills");
+    assert_eq!(fills.len(), 50);
+    // So were the batched records queued after the last of them.
+    for decision_id in &decision_ids {
+        assert_eq!(repo.get_decision_audit(*decision_id).await.expect("load audit").len(), 1);
+    }
+    let equity = repo.get_live_equity(equity_at, equity_at + Duration::seconds(1)).await.expect("load equity");
+    assert_eq!(equity.iter().map(|(_, equity)| *equity).collect::<Vec<_>>(), [dec!(12345)]);
+
+    // The task has stopped: later records are dropped and counted.
+    assert_eq!(persistence.dropped(), 0);
+    persistence.record_audit(audit(Uuid::new_v4()));
+    assert!(record(&persistence, &mut portfolio, &execution(50)).await.await.is_err(), "a dropped execution was acknowledged");
+    assert_eq!(persistence.dropped(), 2);
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn the_stored_portfolio_events_rebuild_the_portfolio() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let persistence = Persistence::spawn(repo.clone(), slow_batches(1000));
+
+    let mut portfolio = Portfolio::new(dec!(100000));
+    portfolio.set_event_sink(Arc::new(persistence.clone()));
+    for minute in 0..3 {
+        portfolio.update_with_execution(&execution(minute)).expect("apply execution");
+    }
+    portfolio.correct(Correction::Cash { old: portfolio.cash, new: dec!(90000) }, CorrectionSource::Reconciler);
+    persistence.shutdown().await;
+
+    let mut stored = repo.get_portfolio_events(None, 100).await.expect("load portfolio events");
+    stored.reverse();
+    assert_eq!(stored.iter().map(|stored| stored.sequence).collect::<Vec<_>>(), [1, 2, 3, 4]);
+    let rebuilt = Portfolio::new(dec!(100000)).rebuild_from(stored.into_iter().map(|stored| stored.event)).expect("rebuild portfolio");
+    assert_eq!((rebuilt.cash, &rebuilt.positions), (portfolio.cash, &portfolio.positions));
+
+    // The cash correction concerns no position.
+    assert_eq!(repo.get_portfolio_events(Some(TEST_SYMBOL), 100).await.expect("load portfolio events").len(), 3);
+    let latest = repo.get_portfolio_events(None, 1).await.expect("load portfolio events");
+    assert!(latest[0].event.to_string().starts_with("reconciler changed cash from "), "{}", latest[0].event);
+
+    db.teardown().await.expect("drop test database");
+}
+
+// The writer runs on the test's only thread, so nothing is dequeued until the test yields.
+#[tokio::test]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn a_full_queue_drops_audits_but_waits_to_queue_executions() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let persistence = Persistence::spawn(repo.clone(), slow_batches(4));
+
+    let decision_ids: Vec<Uuid> = (0..10).map(|_| Uuid::new_v4()).collect();
+    for decision_id in &decision_ids {
+        persistence.record_audit(audit(*decision_id));
+    }
+    assert_eq!(persistence.dropped(), 6);
+
+    // The queue is still full; the executions wait for room rather than being dropped.
+    let mut portfolio = Portfolio::new(dec!(100000));
+    let mut acks = Vec::new();
+    for minute in 0..10 {
+        acks.push(record(&persistence, &mut portfolio, &execution(minute)).await);
+    }
+    persistence.shutdown().await;
+    for ack in acks {
+        assert_eq!(ack.await, Ok(true));
+    }
+    assert_eq!(persistence.dropped(), 6);
+    assert_eq!(repo.get_live_fills(seed_start(), seed_start() + Duration::hours(1)).await.expect("load fills").len(), 10);
+    let mut audited = 0;
+    for decision_id in &decision_ids {
+        audited += repo.get_decision_audit(*decision_id).await.expect("load audit").len();
+    }
+    assert_eq!(audited, 4);
+
+    db.teardown().await.expect("drop test database");
+}
+
+/// Opens a long position on one kline and closes it on the next, so every kline trades.
+struct Alternating {
+    long: bool,
+}
+
+impl Strategy for Alternating {
+    fn evaluate(&mut self, kline: &Kline) -> Result<Option<Signal>, StrategyError> {
+        self.long = !self.long;
+        let (side, intent) = if self.long { (OrderSide::Buy, SignalIntent::OpenLong) } else { (OrderSide::Sell, SignalIntent::Close) };
+        Ok(Some(Signal {
+            signal_id: Uuid::new_v4(),
+            decision_id: Uuid::new_v4(),
+            timestamp: kline.close_time,
+            confidence: Decimal::ONE,
+            intent: Some(intent),
+            order_request: OrderRequest {
+                client_order_id: Uuid::new_v4(),
+                symbol: TEST_SYMBOL.to_string(),
+                side,
+                order_type: OrderType::Market,
+                quantity: Decimal::ZERO,
+                price: None,
+                position_side: None,
+                time_in_force: None,
+                reduce_only: !self.long,
+                decision_id: None,
+            },
+        }))
+    }
+}
+
+/// Sizes every signal to one unit.
+struct OneUnit;
+
+impl RiskManager for OneUnit {
+    fn evaluate_signal(&self, signal: &Signal, _: &PortfolioState, _: Decimal) -> Result<OrderPlan, RiskError> {
+        let mut order = signal.order_request.clone();
+        order.quantity = Decimal::ONE;
+        Ok(OrderPlan::single(order))
+    }
+}
+
+/// A pipeline trading one unit of the fixture symbol per signal, queueing its records to
+/// `persistence`.
+fn pipeline(klines: usize, persistence: Persistence) -> SignalPipeline {
+    let config = test_config(klines).expect("load config");
+    let portfolio = Arc::new(Mutex::new(Portfolio::new(dec!(100000))));
+    let flags = Arc::new(Mutex::new(HashMap::from([(TEST_SYMBOL.to_string(), true)])));
+    let executor = Arc::new(SimulatedExecutor::new(config.simulation.clone()));
+    SignalPipeline::new(&config, Arc::new(OneUnit), executor, portfolio, persistence, EventBus::new(16)).with_trading_flags(flags)
+}
+
+/// A bot of the fixture symbol trading on every kline.
+fn alternating_bot() -> Bot {
+    Bot {
+        symbol: TEST_SYMBOL.to_string(),
+        interval: "1h".to_string(),
+        leverage: 10,
+        strategy_id: StrategyId::MACrossover,
+        strategy: Box::new(Alternating { long: false }),
+        kline_transform: KlineTransformer::new(Default::default()),
+        direction: Default::default(),
+        activity: Arc::new(BotActivity::default()),
+        holding_bars: 0,
+        last_close_time: None,
+        recent_klines: VecDeque::new(),
+        consecutive_errors: 0,
+        risk: None,
+    }
+}
+
+/// The median time the pipeline takes to decide and execute each of `klines`, queueing its
+/// records to `persistence`. Every kline fills an order.
+async fn median_decision_time(persistence: Persistence, klines: &[Kline]) -> StdDuration {
+    let pipeline = pipeline(klines.len(), persistence);
+    let mut bot = alternating_bot();
+
+    let mut times = Vec::with_capacity(klines.len());
+    for kline in klines {
+        let received = Instant::now();
+        let outcome = pipeline.process(&mut bot, kline, &MarketState::default(), received).await;
+        times.push(received.elapsed());
+        assert!(matches!(outcome, PipelineOutcome::Executed { .. }), "{:?}", outcome);
+    }
+    times.sort();
+    times[times.len() / 2]
+}
+
+/// The median time `persistence` takes to write an order's intent to the order ledger, which
+/// the pipeline waits for before each order. The intents are then resolved, out of the way.
+async fn median_intent_write(persistence: &Persistence, samples: usize) -> StdDuration {
+    let mut times = Vec::with_capacity(samples);
+    for _ in 0..samples {
+        let order = OrderRequest {
+            client_order_id: Uuid::new_v4(),
+            symbol: TEST_SYMBOL.to_string(),
+            side: OrderSide::Buy,
+            order_type: OrderType::Market,
+            quantity: Decimal::ONE,
+            price: None,
+            position_side: None,
+            time_in_force: None,
+            reduce_only: false,
+            decision_id: None,
+        };
+        let started = Instant::now();
+        persistence.record_order_intent(&order_ledger::intent(&order, Utc::now())).await.expect("record intent");
+        times.push(started.elapsed());
+        persistence.resolve_order_intent(order.client_order_id, OrderIntentState::Expired, None, Utc::now()).await;
+    }
+    times.sort();
+    times[times.len() / 2]
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn queueing_records_keeps_the_decision_path_within_half_again_of_not_persisting() {
+    const KLINES: usize = 400;
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let klines = generate_klines(KLINES);
+
+    // Warm up, then take the baseline without persistence.
+    median_decision_time(Persistence::disabled(), &klines).await;
+    let baseline = median_decision_time(Persistence::disabled(), &klines).await;
+
+    let persistence = Persistence::spawn(repo.clone(), PersistenceSettings::default());
+    let intent_write = median_intent_write(&persistence, 50).await;
+    let persisting = median_decision_time(persistence.clone(), &klines).await;
+    persistence.shutdown().await;
+
+    // Every order waits for its intent's write, whose commit may wait in turn for one of the
+    // persistence task's. Everything else is only queued, which is cheap. The margin absorbs
+    // scheduling noise on a busy machine.
+    assert!(
+        persisting <= baseline.mul_f64(1.5) + intent_write * 2 + StdDuration::from_micros(50),
+        "median decision took {:?} with persistence against {:?} without and {:?} per intent",
+        persisting,
+        baseline,
+        intent_write
+    );
+    // Nothing was dropped, and every execution made it to the database.
+    assert_eq!(persistence.dropped(), 0);
+    let fills = repo.get_live_fills(klines[0].open_time, klines[KLINES - 1].close_time + Duration::hours(1)).await.expect("load fills");
+    assert_eq!(fills.len(), KLINES);
+    // Their writes resolved every intent the pipeline recorded.
+    assert!(repo.get_unresolved_order_intents().await.expect("load ledger").is_empty());
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn a_decision_s_audit_reads_back_in_the_order_its_stages_were_decided() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let persistence = Persistence::spawn(repo.clone(), slow_batches(1000));
+    let klines = generate_klines(1);
+
+    let outcome = pipeline(1, persistence.clone()).process(&mut alternating_bot(), &klines[0], &MarketState::default(), Instant::now()).await;
+    let PipelineOutcome::Executed { executions } = outcome else { panic!("{:?}", outcome) };
+    let decision_id = executions[0].decision_id.expect("a decision ID");
+    persistence.flush().await;
+    let stages: Vec<String> = repo.get_decision_audit(decision_id).await.expect("load audit").into_iter().map(|record| record.stage).collect();
+    assert_eq!(stages, ["Signal", "RiskApproved", "OrderSubmitted", "Executed", "PortfolioUpdated"]);
+
+    // Stages sharing a timestamp, or recorded by a clock that stepped back, keep their order.
+    let decision_id = Uuid::new_v4();
+    let at = Utc::now();
+    let stages = [DecisionStage::Signal, DecisionStage::RiskApproved, DecisionStage::OrderSubmitted, DecisionStage::Executed];
+    for (sequence, stage) in stages.into_iter().enumerate().rev() {
+        let recorded_at = if sequence == 3 { at - Duration::seconds(1) } else { at };
+        persistence.record_audit(DecisionAuditEntry { stage, recorded_at, sequence: sequence as u64, ..audit(decision_id) });
+    }
+    persistence.shutdown().await;
+    let recorded: Vec<String> = repo.get_decision_audit(decision_id).await.expect("load audit").into_iter().map(|record| record.stage).collect();
+    assert_eq!(recorded, ["Signal", "RiskApproved", "OrderSubmitted", "Executed"]);
+
+    db.teardown().await.expect("drop test database");
+}
@@ -0,0 +1,379 @@
+//! End-to-end tests of the backtest pipeline against a real PostgreSQL database.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use analyzer::Analyzer;
+use backtester::Backtester;
+use chrono::Duration;
+use configuration::optimizer_config::{AnalysisConfig, BaseConfig, EquityCurveResolution, Filters, ObjectiveName, OptimizerConfig, ParameterRange};
+use configuration::Versioned;
+use core_types::{CloseReason, Execution, OrderSide, StrategyId, Trade};
+use executor::{Portfolio, SimulatedExecutor};
+use optimizer::Optimizer;
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::HashMap;
+use std::time::Duration as StdDuration;
+use strategies::create_strategy;
+use testing::{generate_klines, seed_klines, seed_start, test_config, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+const BARS: usize = 5000;
+
+// Pinned results of the default MACrossover parameters over the seeded series.
+// These change whenever sizing, execution or accounting logic changes.
+const EXPECTED_TRADES: usize = 41;
+const EXPECTED_NET_PROFIT: Decimal = dec!(450068.88132);
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn ma_crossover_backtest_produces_pinned_report() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(BARS)).await.expect("seed klines");
+
+    let config = test_config(BARS).expect("load config");
+    let job_id = Uuid::new_v4();
+    let run_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &serde_json::json!({}), "Pending").await.unwrap();
+
+    let mut backtester = Backtester::new(
+        run_id,
+        TEST_SYMBOL.to_string(),
+        TEST_INTERVAL.to_string(),
+        config.clone(),
+        Portfolio::new(config.backtest.initial_capital),
+        create_strategy(StrategyId::MACrossover, &config, TEST_SYMBOL).unwrap(),
+        Box::new(SimpleRiskManager::new(config.risk_management.clone()).unwrap()),
+        Box::new(SimulatedExecutor::new(config.simulation.clone())),
+        analytics::AnalyticsEngine::new(),
+        repo.clone(),
+    );
+    let report = backtester
+        .run(seed_start(), seed_start() + Duration::hours(BARS as i64))
+        .await
+        .expect("backtest run");
+
+    assert_eq!(report.total_trades, EXPECTED_TRADES);
+    let tolerance = Decimal::new(1, 2);
+    assert!(
+        (report.total_net_profit - EXPECTED_NET_PROFIT).abs() <= tolerance,
+        "net profit {} differs from pinned {}",
+        report.total_net_profit,
+        EXPECTED_NET_PROFIT
+    );
+
+    let saved = repo.get_full_report_for_run(run_id).await.expect("saved report");
+    assert_eq!(saved.bars_processed, Some(BARS as i64));
+    assert_eq!(saved.engine_version.as_deref(), Some(backtester::ENGINE_VERSION));
+    assert_eq!(
+        saved.config_hash,
+        Some(configuration::config_hash(&config.backtest, &config.simulation, &config.risk_management))
+    );
+    assert!(saved.started_at.unwrap() <= saved.finished_at.unwrap());
+
+    let range = repo.get_run_kline_range(run_id).await.expect("saved kline range");
+    let klines = generate_klines(BARS);
+    assert_eq!((range.symbol.as_str(), range.interval.as_str()), (TEST_SYMBOL, TEST_INTERVAL));
+    assert_eq!(range.data_start, klines[0].open_time);
+    assert_eq!(range.data_end, klines[BARS - 1].close_time);
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn optimizer_grid_completes_and_ranks_deterministically() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(BARS)).await.expect("seed klines");
+
+    let base_config = test_config(BARS).expect("load config");
+    let optimizer_config = OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space: HashMap::from([
+            ("ma_fast_period".to_string(), ParameterRange::DiscreteInt(vec![5, 10])),
+            ("ma_slow_period".to_string(), ParameterRange::DiscreteInt(vec![40, 60])),
+        ]),
+        analysis: AnalysisConfig {
+            filters: Filters { min_total_trades: 1, max_drawdown_pct: Decimal::from(100), ..Filters::default() },
+            ..AnalysisConfig::default()
+        },
+        wfo: None,
+        max_concurrency: None,
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::AnalyzerScore,
+    };
+
+    let optimizer = Optimizer::new(optimizer_config.clone(), base_config, repo.clone());
+    let job_id = optimizer.job_id();
+    optimizer.run().await.expect("optimizer run");
+
+    let statuses: Vec<String> = sqlx::query_scalar("SELECT run_status FROM backtest_runs WHERE job_id = $1")
+        .bind(job_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(statuses.len(), 4);
+    assert!(statuses.iter().all(|s| s == "Completed"), "statuses: {:?}", statuses);
+
+    let analyzer = Analyzer::new(optimizer_config.analysis);
+    let first = analyzer.run(&repo, job_id).await.expect("analyze");
+    let second = analyzer.run(&repo, job_id).await.expect("analyze again");
+    assert_eq!(first.len(), 4);
+    assert!(first.windows(2).all(|w| w[0].score >= w[1].score));
+    let order = |ranked: &[analyzer::RankedReport]| ranked.iter().map(|r| r.report.run_id).collect::<Vec<_>>();
+    assert_eq!(order(&first), order(&second));
+
+    db.teardown().await.expect("drop test database");
+}
+
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn each_run_stores_the_objective_value_of_its_report() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(BARS)).await.expect("seed klines");
+
+    let base_config = test_config(BARS).expect("load config");
+    let optimizer_config = OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space: HashMap::from([("ma_fast_period".to_string(), ParameterRange::DiscreteInt(vec![5, 10]))]),
+        analysis: AnalysisConfig {
+            filters: Filters { min_total_trades: 1, max_drawdown_pct: Decimal::from(100), ..Filters::default() },
+            ..AnalysisConfig::default()
+        },
+        wfo: None,
+        max_concurrency: None,
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::SharpeRatio,
+    };
+
+    let optimizer = Optimizer::new(optimizer_config.clone(), base_config.clone(), repo.clone());
+    let job_id = optimizer.job_id();
+    optimizer.run().await.expect("optimizer run");
+
+    let reports = repo.get_full_reports_for_job(job_id).await.expect("load reports");
+    assert_eq!(reports.len(), 2);
+    for report in &reports {
+        assert!(report.objective_value.is_some(), "run {} has no objective value", report.run_id);
+        assert_eq!(report.objective_value, optimizer.objective_value(report));
+        assert_eq!(report.objective_value, report.sharpe_ratio);
+    }
+
+    // A run failing the hard filters has no objective value.
+    let strict_config = OptimizerConfig {
+        analysis: AnalysisConfig {
+            filters: Filters { min_total_trades: usize::MAX, max_drawdown_pct: Decimal::from(100), ..Filters::default() },
+            ..AnalysisConfig::default()
+        },
+        ..optimizer_config
+    };
+    let strict = Optimizer::new(strict_config, base_config, repo.clone());
+    assert_eq!(strict.objective_value(&reports[0]), None);
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn optimizer_runs_many_concurrent_backtests_without_deadlock() {
+    const STRESS_BARS: usize = 300;
+
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(STRESS_BARS)).await.expect("seed klines");
+
+    // 5 x 10 = 50 tiny runs, with more in flight than there are runtime worker threads.
+    let base_config = test_config(STRESS_BARS).expect("load config");
+    let optimizer_config = OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space: HashMap::from([
+            ("ma_fast_period".to_string(), ParameterRange::LinearInt { start: 2, end: 10, step: 2 }),
+            ("ma_slow_period".to_string(), ParameterRange::LinearInt { start: 20, end: 65, step: 5 }),
+            ("trend_filter_period".to_string(), ParameterRange::DiscreteInt(vec![65])),
+        ]),
+        analysis: AnalysisConfig::default(),
+        wfo: None,
+        // Kept within the harness pool's 10 connections.
+        max_concurrency: Some(8),
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::AnalyzerScore,
+    };
+
+    let optimizer = Optimizer::new(optimizer_config, base_config, repo.clone());
+    let job_id = optimizer.job_id();
+    tokio::time::timeout(StdDuration::from_secs(120), optimizer.run())
+        .await
+        .expect("optimizer deadlocked")
+        .expect("optimizer run");
+
+    let statuses: Vec<String> = sqlx::query_scalar("SELECT run_status FROM backtest_runs WHERE job_id = $1")
+        .bind(job_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+    assert_eq!(statuses.len(), 50);
+    assert!(statuses.iter().all(|s| s == "Completed"), "statuses: {:?}", statuses);
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn optimizer_job_progress_rises_to_completed() {
+    const PROGRESS_BARS: usize = 300;
+
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(PROGRESS_BARS)).await.expect("seed klines");
+
+    // 5 x 2 = 10 runs, two at a time.
+    let base_config = test_config(PROGRESS_BARS).expect("load config");
+    let optimizer_config = OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space: HashMap::from([
+            ("ma_fast_period".to_string(), ParameterRange::LinearInt { start: 2, end: 10, step: 2 }),
+            ("ma_slow_period".to_string(), ParameterRange::DiscreteInt(vec![20, 40])),
+            ("trend_filter_period".to_string(), ParameterRange::DiscreteInt(vec![65])),
+        ]),
+        analysis: AnalysisConfig::default(),
+        wfo: None,
+        max_concurrency: Some(2),
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::AnalyzerScore,
+    };
+
+    let optimizer = Optimizer::new(optimizer_config, base_config, repo.clone());
+    let job_id = optimizer.job_id();
+    // Watch the job's saved progress while it runs, as the web UI would.
+    let watcher = {
+        let repo = repo.clone();
+        tokio::spawn(async move {
+            let mut seen = Vec::new();
+            loop {
+                if let Ok(job) = repo.get_optimization_job(job_id).await {
+                    seen.push((job.completed_runs, job.total_runs));
+                    if job.job_status == "Completed" {
+                        return seen;
+                    }
+                }
+                tokio::time::sleep(StdDuration::from_millis(5)).await;
+            }
+        })
+    };
+    optimizer.run().await.expect("optimizer run");
+    let seen = tokio::time::timeout(StdDuration::from_secs(10), watcher).await.expect("job never completed").unwrap();
+
+    assert!(seen.windows(2).all(|w| w[0].0 <= w[1].0), "progress went backwards: {:?}", seen);
+    assert_eq!(seen.last(), Some(&(10, 10)));
+    let job = repo.get_optimization_job(job_id).await.unwrap();
+    assert_eq!(job.job_status, "Completed");
+    assert!(job.estimated_completion_at.is_some_and(|eta| eta <= job.updated_at));
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn saved_short_trade_round_trips_with_its_side_and_pnl() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let job_id = Uuid::new_v4();
+    let run_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &serde_json::json!({}), "Completed").await.unwrap();
+
+    let execution = |side, price, hours| Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side,
+        price,
+        quantity: dec!(2),
+        fee: dec!(0.08),
+        fee_asset: "USDT".to_string(),
+        timestamp: seed_start() + Duration::hours(hours),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    // A winning short: sold at 110 with a stop at 115, bought back at 100 when its time
+    // limit ran out, for 2R.
+    let trades = vec![Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        entry_execution: execution(OrderSide::Sell, dec!(110), 1),
+        exit_execution: execution(OrderSide::Buy, dec!(100), 5),
+        close_reason: CloseReason::TimeLimit,
+        initial_risk: Some(dec!(10)),
+    }];
+    let equity_curve: Vec<_> = [dec!(10000), dec!(10010), dec!(10020)]
+        .into_iter()
+        .enumerate()
+        .map(|(i, equity)| (seed_start() + Duration::hours(i as i64 * 3), equity))
+        .collect();
+    // The short was open over the middle point only.
+    let exposure = [dec!(0), dec!(1000), dec!(0)];
+
+    let analytics = analytics::AnalyticsEngine::new();
+    let original = analytics.calculate(&trades, &equity_curve, &exposure, dec!(10000), TEST_INTERVAL).unwrap();
+    repo.save_performance_report(run_id, &original).await.unwrap();
+    repo.save_trades(run_id, &trades).await.unwrap();
+    repo.save_equity_curve(run_id, &equity_curve).await.unwrap();
+
+    let details = repo.get_run_details(run_id).await.expect("run details");
+    let saved = &details.trades[0];
+    assert_eq!(saved.entry_execution.side, OrderSide::Sell);
+    assert_eq!(saved.exit_execution.side, OrderSide::Buy);
+    assert_eq!(saved.entry_execution.fee, dec!(0.08));
+    assert_eq!(saved.exit_execution.fee_asset, "USDT");
+    assert_eq!(saved.close_reason, CloseReason::TimeLimit);
+    let exit_breakdown = details.report.exit_breakdown.as_ref().expect("exit breakdown").0.clone();
+    assert_eq!(exit_breakdown, original.exit_breakdown);
+    assert_eq!(exit_breakdown[0].close_reason, CloseReason::TimeLimit);
+    assert_eq!(saved.initial_risk, Some(dec!(10)));
+    assert_eq!((details.report.average_r, details.report.expectancy_r), (Some(dec!(2)), Some(dec!(2))));
+    assert_eq!(details.report.r_distribution.as_ref().map(|distribution| distribution.0), Some(original.r_distribution));
+    assert_eq!(details.report.time_in_market_pct.map(|pct| pct.round_dp(4)), Some(dec!(33.3333)));
+    assert_eq!(
+        (details.report.peak_exposure_pct, details.report.return_on_margin_pct),
+        (original.peak_exposure_pct, original.return_on_margin_pct)
+    );
+
+    let saved_curve: Vec<_> = details.equity_curve.iter().map(|p| (p.timestamp, p.equity)).collect();
+    let recomputed = analytics.calculate(&details.trades, &saved_curve, &[], dec!(10000), TEST_INTERVAL).unwrap();
+    assert_eq!(recomputed.total_net_profit, original.total_net_profit);
+    assert_eq!(recomputed.total_net_profit, dec!(20));
+    assert_eq!(recomputed.average_r, original.average_r);
+
+    db.teardown().await.expect("drop test database");
+}
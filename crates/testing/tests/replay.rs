@@ -0,0 +1,117 @@
+//! Replays the seeded klines through the live engine and checks that it trades exactly like
+//! the backtester over the same data.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use api_client::{ApiClient, BinanceClient};
+use backtester::{run_backtest, BacktestSpec, KlineSource};
+use configuration::{LiveBotConfig, LiveConfig, ReverseMode, Versioned};
+use core_types::{Execution, StrategyId};
+use engine::{LiveEngine, ReplayConnector};
+use events::{EventBus, WsMessage};
+use executor::SimulatedExecutor;
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use testing::{generate_klines, seed_klines, test_config, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+
+const BARS: usize = 2000;
+
+/// Fast enough to replay the whole series in well under a second.
+const REPLAY_SPEED: f64 = 1e9;
+
+/// What must agree between a backtest fill and the matching replayed fill.
+fn fill(execution: &Execution) -> (chrono::DateTime<chrono::Utc>, core_types::OrderSide, Decimal, Decimal, Decimal) {
+    (execution.timestamp, execution.side, execution.price, execution.quantity, execution.fee)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn replayed_ma_crossover_trades_match_the_backtest() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(BARS)).await.expect("seed klines");
+
+    let mut config = test_config(BARS).expect("load config");
+    // The live engine places no protective stops, so keep the backtest's out of reach, and
+    // flip positions with separate orders so every fill belongs to exactly one trade.
+    config.risk_management.stop_loss_pct = Decimal::new(9, 1);
+    config.risk_management.reverse_mode = ReverseMode::Separate;
+
+    let spec = BacktestSpec::from_config(&config, KlineSource::Database(repo.clone())).expect("backtest spec");
+    let (start, end) = (spec.start, spec.end);
+    let backtest = run_backtest(spec).await.expect("backtest run");
+    assert!(backtest.trades.len() > 5, "the fixture should produce several trades, got {}", backtest.trades.len());
+
+    let live_config = LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled: false,
+        interval: TEST_INTERVAL.to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: None,
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots: vec![LiveBotConfig {
+            enabled: true,
+            symbol: TEST_SYMBOL.to_string(),
+            strategy_id: StrategyId::MACrossover,
+            interval: Some(TEST_INTERVAL.to_string()),
+            // High enough that the margin check never trims the backtest's position sizes.
+            leverage: Some(125),
+            kline_transform: Default::default(),
+            direction: Default::default(),
+            quote_asset: None,
+            risk: None,
+            params: serde_json::json!({}),
+        }],
+    };
+    // A replay never reaches the exchange; any call would fail against this address.
+    let api_client: Arc<dyn ApiClient> = Arc::new(BinanceClient::with_base_url("http://127.0.0.1:1", &config.api.testnet));
+    let risk_manager = Arc::new(SimpleRiskManager::new(config.risk_management.clone()).unwrap());
+    let executor = Arc::new(SimulatedExecutor::new(config.simulation.clone()));
+    let event_tx = EventBus::new(1 << 16);
+    let mut event_rx = event_tx.subscribe();
+
+    let connector = Arc::new(ReplayConnector::new(repo.clone(), start, end, REPLAY_SPEED, Decimal::ZERO));
+    let mut engine = LiveEngine::new(live_config, config, api_client, executor, repo, risk_manager, event_tx).with_replay(connector);
+    tokio::time::timeout(std::time::Duration::from_secs(60), engine.run())
+        .await
+        .expect("the replay should end with the recorded data")
+        .expect("replay run");
+
+    let mut replayed = Vec::new();
+    while let Ok(message) = event_rx.try_recv() {
+        if let WsMessage::TradeExecuted(execution) = message {
+            replayed.push(execution);
+        }
+    }
+
+    // The replay may end with a position still open, which the backtest does not report.
+    let expected: Vec<_> = backtest.trades.iter().flat_map(|trade| [fill(&trade.entry_execution), fill(&trade.exit_execution)]).collect();
+    let actual: Vec<_> = replayed.iter().map(fill).collect();
+    assert!(
+        actual.len() == expected.len() || actual.len() == expected.len() + 1,
+        "replayed {} fills for {} backtest trades",
+        actual.len(),
+        backtest.trades.len()
+    );
+    assert_eq!(actual[..expected.len()], expected[..]);
+
+    db.teardown().await.expect("drop test database");
+}
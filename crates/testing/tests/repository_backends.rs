@@ -0,0 +1,360 @@
+//! The repository operations SQLite supports, run against both backends.
+//!
+//! The SQLite tests need no server and always run. The PostgreSQL ones are ignored by
+//! default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use backtester::error::BacktestError;
+use backtester::Backtester;
+use chrono::Duration;
+use configuration::optimizer_config::{AnalysisConfig, BaseConfig, EquityCurveResolution, Filters, ObjectiveName, ParameterRange, WfoConfig, Weights};
+use configuration::{Config, JobConfigSnapshot, OptimizerConfig, Versioned};
+use core_types::{BookTicker, InvalidKlinePolicy, Kline, KlineViolation, PriceType, StrategyId};
+use database::{DbError, DbRepository};
+use executor::{Portfolio, SimulatedExecutor};
+use ml_features::microstructure::{align_book_summaries, summarise_book_tickers};
+use risk::SimpleRiskManager;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use strategies::create_strategy;
+use testing::{generate_klines, seed_klines, seed_start, test_config, SqliteTestDatabase, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+
+const BARS: usize = 500;
+
+/// Stores klines and reads them back by range and ahead of a start.
+async fn klines_round_trip(repo: &DbRepository) {
+    let klines = generate_klines(BARS);
+    seed_klines(repo, TEST_SYMBOL, &klines).await.expect("seed klines");
+    // Saving a bar again leaves the stored one alone.
+    repo.save_kline(TEST_SYMBOL, &klines[0]).await.expect("save duplicate kline");
+
+    assert_eq!(repo.get_kline_symbols().await.unwrap(), [TEST_SYMBOL]);
+    let all = repo
+        .get_klines_by_date_range(TEST_SYMBOL, TEST_INTERVAL, klines[0].open_time, klines[BARS - 1].open_time)
+        .await
+        .unwrap();
+    assert_eq!(all, klines);
+
+    let before = repo.get_klines_before(TEST_SYMBOL, TEST_INTERVAL, klines[100].open_time, 10).await.unwrap();
+    assert_eq!(before, klines[90..100]);
+    assert!(repo.get_klines_before(TEST_SYMBOL, TEST_INTERVAL, klines[100].open_time, 0).await.unwrap().is_empty());
+}
+
+/// Refuses to store an invalid kline, failing the whole batch, unless told to flag it.
+async fn invalid_klines_are_rejected_unless_flagged(repo: &DbRepository) {
+    let mut klines = generate_klines(10);
+    klines[4].high = klines[4].low - Decimal::ONE;
+    let (first, last) = (klines[0].open_time, klines[9].open_time);
+
+    match repo.save_kline("BADUSDT", &klines[4]).await {
+        Err(DbError::InvalidKline { symbol, source }) => {
+            assert_eq!((symbol.as_str(), source.open_time), ("BADUSDT", klines[4].open_time));
+            assert!(matches!(source.violations[0], KlineViolation::HighBelowBody { .. }));
+        }
+        other => panic!("an invalid kline was not rejected: {:?}", other),
+    }
+    assert!(matches!(repo.save_klines("BADUSDT", &klines).await, Err(DbError::InvalidKline { .. })));
+    assert!(repo.get_klines_by_date_range("BADUSDT", TEST_INTERVAL, first, last).await.unwrap().is_empty());
+
+    let flagging = repo.clone().with_invalid_kline_policy(InvalidKlinePolicy::Flag);
+    assert_eq!(flagging.save_klines("BADUSDT", &klines).await.unwrap(), 10);
+    assert_eq!(repo.get_klines_by_date_range("BADUSDT", TEST_INTERVAL, first, last).await.unwrap(), klines);
+}
+
+/// Records backfill progress, which never moves backwards.
+async fn backfill_progress_only_advances(repo: &DbRepository) {
+    let start = seed_start();
+    repo.save_backfill_progress(TEST_SYMBOL, TEST_INTERVAL, PriceType::Last, start, start + Duration::days(60)).await.unwrap();
+    repo.save_backfill_progress(TEST_SYMBOL, TEST_INTERVAL, PriceType::Last, start, start + Duration::days(30)).await.unwrap();
+
+    let progress = repo.get_backfill_progress(TEST_SYMBOL, TEST_INTERVAL, PriceType::Last).await.unwrap();
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0].range_start, start);
+    assert_eq!(progress[0].last_completed_range_end, start + Duration::days(60));
+}
+
+/// Stores mark price klines apart from the last price ones, without their volume, and
+/// tracks their backfill progress apart too.
+async fn mark_price_klines_round_trip(repo: &DbRepository) {
+    let klines = generate_klines(BARS);
+    for kline in &klines[..BARS / 2] {
+        repo.save_mark_price_kline(TEST_SYMBOL, kline).await.expect("save mark price kline");
+    }
+    repo.save_mark_price_kline(TEST_SYMBOL, &klines[0]).await.expect("save duplicate mark price kline");
+
+    let marks = repo
+        .get_mark_price_klines_by_date_range(TEST_SYMBOL, TEST_INTERVAL, klines[0].open_time, klines[BARS - 1].open_time)
+        .await
+        .unwrap();
+    let without_volume: Vec<Kline> = klines[..BARS / 2].iter().map(|kline| Kline { volume: Decimal::ZERO, ..kline.clone() }).collect();
+    assert_eq!(marks, without_volume);
+
+    let start = seed_start();
+    repo.save_backfill_progress(TEST_SYMBOL, TEST_INTERVAL, PriceType::Mark, start, start + Duration::days(10)).await.unwrap();
+    let progress = repo.get_backfill_progress(TEST_SYMBOL, TEST_INTERVAL, PriceType::Mark).await.unwrap();
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0].last_completed_range_end, start + Duration::days(10));
+    let progress = repo.get_backfill_progress(TEST_SYMBOL, TEST_INTERVAL, PriceType::Last).await.unwrap();
+    assert_eq!(progress[0].last_completed_range_end, start + Duration::days(60));
+}
+
+/// Summarises recorded book tickers per stored kline exactly as the datasets' in-memory
+/// summary does, so features from either match.
+async fn book_summaries_match_the_recorded_tickers(repo: &DbRepository) {
+    let klines = generate_klines(BARS);
+    let tickers: Vec<BookTicker> = klines[..20]
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| i % 4 != 1)
+        .flat_map(|(i, kline)| {
+            [Duration::zero(), Duration::minutes(30), kline.close_time - kline.open_time].map(|offset| BookTicker {
+                timestamp: kline.open_time + offset,
+                best_bid_price: kline.close - Decimal::new(5, 2),
+                best_bid_qty: Decimal::from(i as i64 % 3),
+                best_ask_price: kline.close + Decimal::new(5, 2),
+                best_ask_qty: Decimal::from(1 + i as i64 % 5),
+            })
+        })
+        .collect();
+    repo.save_book_tickers(TEST_SYMBOL, &tickers).await.expect("save book tickers");
+
+    let stored = repo
+        .get_book_summaries(TEST_SYMBOL, TEST_INTERVAL, klines[0].open_time, klines[BARS - 1].open_time)
+        .await
+        .unwrap();
+    assert_eq!(stored.len(), 15);
+
+    let from_db = align_book_summaries(&klines, &stored);
+    let in_memory = summarise_book_tickers(&klines, &tickers);
+    for (from_db, in_memory) in from_db.iter().zip(&in_memory) {
+        let (Some(from_db), Some(in_memory)) = (from_db, in_memory) else {
+            assert_eq!(from_db.is_some(), in_memory.is_some());
+            continue;
+        };
+        assert_eq!((from_db.spread_samples, from_db.imbalance_samples), (in_memory.spread_samples, in_memory.imbalance_samples));
+        assert!((from_db.relative_spread_sum - in_memory.relative_spread_sum).abs() < 1e-12);
+        assert!((from_db.imbalance_sum - in_memory.imbalance_sum).abs() < 1e-12);
+    }
+}
+
+fn backtester(repo: &DbRepository, config: &Config, run_id: Uuid) -> Backtester {
+    Backtester::new(
+        run_id,
+        TEST_SYMBOL.to_string(),
+        TEST_INTERVAL.to_string(),
+        config.clone(),
+        Portfolio::new(config.backtest.initial_capital),
+        create_strategy(StrategyId::MACrossover, config, TEST_SYMBOL).unwrap(),
+        Box::new(SimpleRiskManager::new(config.risk_management.clone()).unwrap()),
+        Box::new(SimulatedExecutor::new(config.simulation.clone())),
+        analytics::AnalyticsEngine::new(),
+        repo.clone(),
+    )
+}
+
+/// Runs a single backtest over the stored klines, as the `single-run` command does, and
+/// reads its saved results back.
+async fn single_run_saves_its_results(repo: &DbRepository) {
+    let config = test_config(BARS).expect("load config");
+    let job_id = Uuid::new_v4();
+    let run_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &serde_json::json!({ "ma_fast_period": 10 }), "Pending").await.unwrap();
+    assert_eq!(repo.get_run_status(run_id).await.unwrap(), "Pending");
+
+    let report = backtester(repo, &config, run_id)
+        .run(seed_start(), seed_start() + Duration::hours(BARS as i64))
+        .await
+        .expect("backtest run");
+    assert!(report.total_trades > 0);
+    repo.update_run_status(run_id, "Completed").await.unwrap();
+    repo.save_run_objective(run_id, Some(report.total_return_pct)).await.unwrap();
+
+    assert_eq!(repo.get_run_status(run_id).await.unwrap(), "Completed");
+    let saved = repo.get_full_report_for_run(run_id).await.expect("saved report");
+    assert_eq!(saved.job_id, job_id);
+    assert_eq!(saved.parameters, serde_json::json!({ "ma_fast_period": 10 }));
+    assert_eq!(saved.total_net_profit, Some(report.total_net_profit));
+    assert_eq!(saved.profit_factor, report.profit_factor);
+    assert_eq!(saved.total_trades, Some(report.total_trades as i32));
+    assert_eq!(saved.exit_breakdown.map(|breakdown| breakdown.0), Some(report.exit_breakdown.clone()));
+    assert!(report.expectancy_r.is_some(), "the backtest's trades have stops");
+    assert_eq!(saved.expectancy_r, report.expectancy_r);
+    assert_eq!(saved.r_distribution.map(|distribution| distribution.0), Some(report.r_distribution));
+    assert_eq!(saved.bars_processed, Some(BARS as i64));
+    assert_eq!(saved.signals_filtered, Some(0));
+    assert_eq!(saved.objective_value, Some(report.total_return_pct));
+
+    let curve = repo.get_equity_curve(run_id).await.expect("saved equity curve");
+    assert!(!curve.is_empty());
+    assert!(curve.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    assert!(matches!(repo.get_full_report_for_run(Uuid::new_v4()).await, Err(DbError::NotFound)));
+}
+
+/// A run valued at the mark price fails before simulating while the stored mark prices
+/// cover only half of its range.
+async fn a_mark_valued_run_needs_mark_prices(repo: &DbRepository) {
+    let mut config = test_config(BARS).expect("load config");
+    config.backtest.valuation_price = PriceType::Mark;
+
+    let error = backtester(repo, &config, Uuid::new_v4())
+        .run(seed_start(), seed_start() + Duration::hours(BARS as i64))
+        .await
+        .unwrap_err();
+    let BacktestError::MarkPricesUnavailable { missing, bars, first_missing, .. } = error else {
+        panic!("expected missing mark prices, got {error}");
+    };
+    assert_eq!((missing, bars), (BARS - BARS / 2, BARS));
+    assert_eq!(first_missing, generate_klines(BARS)[BARS / 2].open_time);
+}
+
+/// Stores an optimization job's configuration with it and reads it back unchanged, down to
+/// the trailing zeros of its decimals.
+async fn job_config_round_trips(repo: &DbRepository) {
+    let decimal = |value: &str| value.parse::<Decimal>().unwrap();
+    let mut config = test_config(BARS).expect("load config");
+    config.simulation.taker_fee_pct = decimal("0.000400");
+    let optimizer = OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: TEST_INTERVAL.to_string(),
+        },
+        parameter_space: HashMap::from([
+            ("ma_fast_period".to_string(), ParameterRange::DiscreteInt(vec![5, 10])),
+            ("trend_filter".to_string(), ParameterRange::DiscreteString(vec!["sma".to_string(), "ema".to_string()])),
+            (
+                "atr_multiplier".to_string(),
+                ParameterRange::LinearDecimal { start: decimal("1.50"), end: decimal("3.00"), step: decimal("0.25") },
+            ),
+        ]),
+        analysis: AnalysisConfig {
+            filters: Filters { min_total_trades: 7, max_drawdown_pct: decimal("12.50"), min_sharpe_ratio: Some(decimal("0.750")), ..Filters::default() },
+            scoring_weights: Weights { weight_expectancy: decimal("0.10"), ..Weights::default() },
+        },
+        wfo: Some(WfoConfig { in_sample_weeks: 12, out_of_sample_weeks: 4 }),
+        max_concurrency: Some(3),
+        equity_curve_resolution: EquityCurveResolution::EveryNth(5),
+        objective: ObjectiveName::CalmarRatio,
+    };
+    let job_id = Uuid::new_v4();
+    let snapshot = serde_json::to_value(JobConfigSnapshot::new(&optimizer, &config)).unwrap();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Running", Some(&snapshot)).await.unwrap();
+
+    let stored = repo.get_job_config(job_id).await.unwrap().expect("a stored configuration");
+    assert_eq!(stored, snapshot);
+    let stored: JobConfigSnapshot = serde_json::from_value(stored).unwrap();
+    assert_eq!(stored.simulation.taker_fee_pct.to_string(), "0.000400");
+    assert_eq!(stored.risk_management.risk_per_trade_pct, config.risk_management.risk_per_trade_pct);
+    assert_eq!(
+        (stored.backtest.start_date, stored.backtest.end_date, stored.backtest.initial_capital),
+        (config.backtest.start_date, config.backtest.end_date, config.backtest.initial_capital)
+    );
+
+    let stored = stored.optimizer;
+    assert_eq!(stored.base_config.strategy_id, StrategyId::MACrossover);
+    assert_eq!(stored.base_config.interval, TEST_INTERVAL);
+    assert!(matches!(stored.parameter_space["ma_fast_period"], ParameterRange::DiscreteInt(ref values) if *values == [5, 10]));
+    assert!(matches!(stored.parameter_space["trend_filter"], ParameterRange::DiscreteString(ref values) if *values == ["sma", "ema"]));
+    let ParameterRange::LinearDecimal { start, end, step } = stored.parameter_space["atr_multiplier"] else {
+        panic!("{:?}", stored.parameter_space["atr_multiplier"]);
+    };
+    assert_eq!([start.to_string(), end.to_string(), step.to_string()], ["1.50", "3.00", "0.25"]);
+    let filters = &stored.analysis.filters;
+    assert_eq!(filters.min_total_trades, 7);
+    assert_eq!(filters.max_drawdown_pct.to_string(), "12.50");
+    assert_eq!(filters.min_sharpe_ratio.map(|ratio| ratio.to_string()).as_deref(), Some("0.750"));
+    assert_eq!(stored.analysis.scoring_weights.weight_expectancy.to_string(), "0.10");
+    assert_eq!(stored.wfo.map(|wfo| (wfo.in_sample_weeks, wfo.out_of_sample_weeks)), Some((12, 4)));
+    assert_eq!(stored.max_concurrency, Some(3));
+    assert_eq!(stored.equity_curve_resolution, EquityCurveResolution::EveryNth(5));
+    assert_eq!(stored.objective, ObjectiveName::CalmarRatio);
+
+    // Jobs saved without a configuration have none to read back; unknown jobs are not found.
+    let single_run = Uuid::new_v4();
+    repo.save_optimization_job(single_run, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    assert_eq!(repo.get_job_config(single_run).await.unwrap(), None);
+    assert!(matches!(repo.get_job_config(Uuid::new_v4()).await, Err(DbError::NotFound)));
+}
+
+async fn shared_subset(repo: &DbRepository) {
+    klines_round_trip(repo).await;
+    backfill_progress_only_advances(repo).await;
+    mark_price_klines_round_trip(repo).await;
+    book_summaries_match_the_recorded_tickers(repo).await;
+    single_run_saves_its_results(repo).await;
+    job_config_round_trips(repo).await;
+    a_mark_valued_run_needs_mark_prices(repo).await;
+    invalid_klines_are_rejected_unless_flagged(repo).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_shared_subset_works_on_sqlite() {
+    let db = SqliteTestDatabase::create().await.expect("create test database");
+    shared_subset(&db.repo()).await;
+    db.teardown().await.expect("delete test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn the_shared_subset_works_on_postgres() {
+    let db = TestDatabase::create().await.expect("create test database");
+    shared_subset(&db.repo()).await;
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn stored_klines_are_scanned_for_invalid_and_flagged_bars() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let klines = generate_klines(BARS);
+    seed_klines(&repo, TEST_SYMBOL, &klines).await.expect("seed klines");
+    assert!(repo.find_suspect_klines(None).await.unwrap().is_empty());
+
+    // One bar flagged on the way in, and one broken behind the repository's back, as rows
+    // stored before klines were checked can be.
+    let mut flagged = generate_klines(1).remove(0);
+    flagged.volume = -Decimal::ONE;
+    repo.clone().with_invalid_kline_policy(InvalidKlinePolicy::Flag).save_kline("ETHUSDT", &flagged).await.unwrap();
+    sqlx::query("UPDATE klines SET close_time = open_time WHERE symbol = $1 AND open_time = $2")
+        .bind(TEST_SYMBOL)
+        .bind(klines[7].open_time)
+        .execute(&db.pool)
+        .await
+        .unwrap();
+
+    let found = repo.find_suspect_klines(None).await.unwrap();
+    assert_eq!(found.iter().map(|kline| (kline.symbol.as_str(), kline.is_suspect)).collect::<Vec<_>>(), [("ETHUSDT", true), (TEST_SYMBOL, false)]);
+    assert_eq!(found[0].error.as_ref().unwrap().violations, [KlineViolation::NegativeVolume(-Decimal::ONE)]);
+    assert_eq!(found[1].kline.open_time, klines[7].open_time);
+    assert_eq!(found[1].error.as_ref().unwrap().violations, [KlineViolation::CloseNotAfterOpen]);
+    assert_eq!(repo.find_suspect_klines(Some("ETHUSDT")).await.unwrap().len(), 1);
+
+    let coverage = repo.get_kline_coverage().await.unwrap();
+    assert_eq!(
+        coverage.iter().map(|coverage| (coverage.symbol.as_str(), coverage.bars, coverage.suspect)).collect::<Vec<_>>(),
+        [("ETHUSDT", 1, 1), (TEST_SYMBOL, BARS as i64, 0)]
+    );
+    assert_eq!((coverage[1].first_open_time, coverage[1].last_open_time), (klines[0].open_time, klines[BARS - 1].open_time));
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test]
+async fn postgres_only_operations_are_unsupported_on_sqlite() {
+    let db = SqliteTestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+
+    let error = repo.get_all_wfo_jobs().await.unwrap_err();
+    assert!(matches!(error, DbError::Unsupported("get_all_wfo_jobs")));
+    assert!(error.to_string().contains("needs a PostgreSQL database"));
+    assert!(matches!(repo.get_live_equity_before(seed_start()).await, Err(DbError::Unsupported(_))));
+
+    db.teardown().await.expect("delete test database");
+}
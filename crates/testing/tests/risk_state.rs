@@ -0,0 +1,187 @@
+//! Tests of the global risk manager's state surviving a restart: a halted bot stays halted
+//! until its recorded expiry, and the daily rollover archives the day it ends.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use configuration::settings::GlobalRiskConfig;
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use database::DbRepository;
+use engine::persistence::{Persistence, PersistenceSettings};
+use engine::risk_manager::GlobalRiskManager;
+use engine::risk_state::{BotHalt, HaltReason, GLOBAL_SCOPE, LAST_ROLLOVER_KEY, PEAK_EQUITY_KEY};
+use engine::RiskState;
+use events::EventBus;
+use executor::Portfolio;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use testing::TestDatabase;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+type Flags = Arc<Mutex<HashMap<String, bool>>>;
+
+/// Two losing trades in a row halt a bot for 4 hours; a 10% drawdown halts them all.
+fn config() -> GlobalRiskConfig {
+    GlobalRiskConfig {
+        max_daily_drawdown_pct: dec!(0.1),
+        max_consecutive_losses: 2,
+        bot_cooldown_hours: 4,
+        max_open_positions_per_asset: 1,
+        maintenance_margin_rate: Decimal::ZERO,
+        liquidation_warning_pct: dec!(0.05),
+        dynamic_leverage: None,
+        daily_loss: None,
+    }
+}
+
+fn at(hour: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 18, 0, 0, 0).unwrap() + Duration::hours(hour)
+}
+
+/// What the engine does on a start: enables every bot, then builds the manager from the state
+/// recorded before, recording its changes from now on.
+async fn start(repo: &DbRepository, cash: Decimal) -> (GlobalRiskManager, Flags, Persistence) {
+    let flags: Flags = Arc::new(Mutex::new([("BTCUSDT".to_string(), true), ("ETHUSDT".to_string(), true)].into()));
+    let restored = RiskState::from_entries(repo.get_risk_state().await.expect("load risk state"));
+    let persistence = Persistence::spawn(repo.clone(), PersistenceSettings::default());
+    let manager = GlobalRiskManager::new(
+        config(),
+        Arc::new(Mutex::new(Portfolio::new(cash))),
+        Arc::clone(&flags),
+        EventBus::new(64),
+        dec!(10000),
+        restored,
+    )
+    .with_persistence(persistence.clone());
+    (manager, flags, persistence)
+}
+
+fn execution(side: OrderSide, price: Decimal, timestamp: DateTime<Utc>) -> Execution {
+    Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        side,
+        price,
+        quantity: dec!(1),
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp,
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    }
+}
+
+/// A BTCUSDT long bought at 100 and sold at `exit_price` at `closed_at`.
+fn trade(exit_price: Decimal, closed_at: DateTime<Utc>) -> Trade {
+    Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: "BTCUSDT".to_string(),
+        entry_execution: execution(OrderSide::Buy, dec!(100), closed_at - Duration::minutes(30)),
+        exit_execution: execution(OrderSide::Sell, exit_price, closed_at),
+        close_reason: CloseReason::StopLoss,
+        initial_risk: None,
+    }
+}
+
+async fn enabled(flags: &Flags, symbol: &str) -> bool {
+    flags.lock().await[symbol]
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn a_halted_bot_stays_halted_across_a_restart_until_its_cooldown_expires() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+
+    let (manager, flags, persistence) = start(&repo, dec!(10000)).await;
+    manager.on_trade_closed(&trade(dec!(99), at(9))).await.unwrap();
+    manager.on_trade_closed(&trade(dec!(98), at(10))).await.unwrap();
+    assert!(!enabled(&flags, "BTCUSDT").await);
+    persistence.shutdown().await;
+    drop(manager);
+
+    // The restarted engine enables every bot, and the recorded halt disables BTCUSDT again.
+    let (manager, flags, persistence) = start(&repo, dec!(10000)).await;
+    let expected = BotHalt { reason: HaltReason::ConsecutiveLosses, halted_at: at(10), until: at(14) };
+    assert_eq!(manager.risk_state().await.halts, [("BTCUSDT".to_string(), expected)].into());
+    assert_eq!(manager.consecutive_losses().await, [("BTCUSDT".to_string(), 2)].into());
+    assert!(enabled(&flags, "BTCUSDT").await);
+    assert_eq!(manager.resume_halts(at(11)).await, ["BTCUSDT"]);
+    assert!(!enabled(&flags, "BTCUSDT").await);
+    assert!(enabled(&flags, "ETHUSDT").await);
+
+    // It is re-enabled 4 hours after the halt, not 4 hours after the restart.
+    assert!(manager.release_expired_halts(at(14) - Duration::seconds(1)).await.is_empty());
+    assert!(!enabled(&flags, "BTCUSDT").await);
+    assert_eq!(manager.release_expired_halts(at(14)).await, ["BTCUSDT"]);
+    assert!(enabled(&flags, "BTCUSDT").await);
+    persistence.shutdown().await;
+
+    // A restart after the expiry finds no halt to resume.
+    let (manager, flags, _persistence) = start(&repo, dec!(10000)).await;
+    assert!(manager.resume_halts(at(15)).await.is_empty());
+    assert!(enabled(&flags, "BTCUSDT").await);
+
+    db.teardown().await.expect("teardown");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn the_daily_rollover_archives_the_day_and_lifts_drawdown_halts() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+
+    // The day's first rollover, then a new peak.
+    let (manager, _flags, persistence) = start(&repo, dec!(12000)).await;
+    assert!(manager.roll_over_if_due(at(1)).await);
+    assert!(!manager.roll_over_if_due(at(2)).await);
+    manager.on_trade_closed(&trade(dec!(101), at(3))).await.unwrap();
+    persistence.shutdown().await;
+
+    // Restarted with 10,000 left, 16.7% below the recorded peak: the next loss halts every
+    // bot until the rollover.
+    let (manager, flags, persistence) = start(&repo, dec!(10000)).await;
+    assert_eq!(manager.risk_state().await.peak_equity, Some(dec!(12000)));
+    manager.on_trade_closed(&trade(dec!(99), at(20))).await.unwrap();
+    assert!(!enabled(&flags, "BTCUSDT").await && !enabled(&flags, "ETHUSDT").await);
+    let halts = manager.risk_state().await.halts;
+    assert_eq!(halts["ETHUSDT"], BotHalt { reason: HaltReason::Drawdown, halted_at: at(20), until: at(24) });
+
+    assert!(manager.roll_over_if_due(at(24)).await);
+    assert_eq!(manager.release_expired_halts(at(24)).await, ["BTCUSDT", "ETHUSDT"]);
+    assert!(enabled(&flags, "BTCUSDT").await && enabled(&flags, "ETHUSDT").await);
+    assert_eq!(manager.risk_state().await.peak_equity, Some(dec!(10000)));
+    persistence.shutdown().await;
+
+    // The archive holds the day as the rollover found it.
+    let archived: HashMap<(String, String), serde_json::Value> = repo
+        .get_archived_risk_state(at(0))
+        .await
+        .expect("load archived risk state")
+        .into_iter()
+        .map(|entry| ((entry.scope, entry.key), entry.value))
+        .collect();
+    let global = |key: &str| archived[&(GLOBAL_SCOPE.to_string(), key.to_string())].clone();
+    assert_eq!(global(PEAK_EQUITY_KEY), json!("12000"));
+    assert_eq!(global(LAST_ROLLOVER_KEY), json!(at(0)));
+    assert_eq!(archived[&("BTCUSDT".to_string(), "consecutive_losses".to_string())], json!(1));
+    assert!(archived.contains_key(&("ETHUSDT".to_string(), "halt".to_string())));
+
+    // The live state moved on to the new day, without the lifted halts.
+    let state = RiskState::from_entries(repo.get_risk_state().await.expect("load risk state"));
+    assert_eq!((state.peak_equity, state.last_rollover), (Some(dec!(10000)), Some(at(24))));
+    assert!(state.halts.is_empty());
+
+    db.teardown().await.expect("teardown");
+}
@@ -0,0 +1,158 @@
+//! Tests of the live performance over trailing windows shown on the live dashboard.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p testing -- --ignored
+//! ```
+
+use analyzer::rolling;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use core_types::{CloseReason, Execution, OrderSide};
+use database::DbRepository;
+use events::RollingWindowStats;
+use executor::Portfolio;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::{TestDatabase, TEST_SYMBOL};
+use uuid::Uuid;
+
+/// When the stats are computed.
+fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 9, 30, 12, 0, 0).unwrap()
+}
+
+/// `hours` hours before `now`.
+fn hours_ago(hours: i64) -> DateTime<Utc> {
+    now() - Duration::hours(hours)
+}
+
+/// Fills `side` for one unit at `price` against `portfolio` and records it, as the live
+/// engine does.
+async fn fill(repo: &DbRepository, portfolio: &mut Portfolio, at: DateTime<Utc>, side: OrderSide, price: Decimal) {
+    let execution = Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side,
+        price,
+        quantity: dec!(1),
+        fee: dec!(0.1),
+        fee_asset: "USDT".to_string(),
+        timestamp: at,
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    let fills = portfolio.update_with_execution(&execution).expect("apply execution");
+    repo.save_live_execution(&execution, &fills, Some(CloseReason::Signal)).await.expect("save execution");
+}
+
+/// Records a long bought at 100 an hour before `closed_hours_ago` and sold at `exit`.
+async fn round_trip(repo: &DbRepository, portfolio: &mut Portfolio, closed_hours_ago: i64, exit: Decimal) {
+    fill(repo, portfolio, hours_ago(closed_hours_ago + 1), OrderSide::Buy, dec!(100)).await;
+    fill(repo, portfolio, hours_ago(closed_hours_ago), OrderSide::Sell, exit).await;
+}
+
+/// The equity recorded `step` six-hour steps into a 40-day history. It moves within each day,
+/// so a daily sample only matches it at the day's boundary.
+fn equity_at(step: i64) -> Decimal {
+    let intraday = [0, 25, -40, 10][(step % 4) as usize];
+    Decimal::from(10_000 + 3 * step + intraday + step * step % 17)
+}
+
+/// The six-hour steps of a 40-day history ending at `now`.
+const STEPS: i64 = 40 * 4;
+
+/// When `step` was recorded.
+fn step_at(step: i64) -> DateTime<Utc> {
+    hours_ago((STEPS - step) * 6)
+}
+
+/// The Sharpe Ratio of `equity`'s successive returns, annualized over 252 days, computed
+/// independently in floating point.
+fn expected_sharpe(equity: &[Decimal]) -> f64 {
+    let equity: Vec<f64> = equity.iter().map(|value| value.to_f64().unwrap()).collect();
+    let returns: Vec<f64> = equity.windows(2).map(|w| (w[1] - w[0]) / w[0]).collect();
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / returns.len() as f64;
+    mean / variance.sqrt() * 252f64.sqrt()
+}
+
+fn assert_sharpe(window: &RollingWindowStats, equity: &[Decimal]) {
+    let sharpe = window.sharpe_ratio.expect("sharpe ratio").to_f64().unwrap();
+    let expected = expected_sharpe(equity);
+    assert!((sharpe - expected).abs() < 1e-9 * expected.abs().max(1.0), "{}d Sharpe {} != {}", window.days, sharpe, expected);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn windows_score_only_their_own_trades_and_daily_equity() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    for step in 0..STEPS {
+        repo.save_live_equity(step_at(step), equity_at(step)).await.expect("save equity");
+    }
+    let mut portfolio = Portfolio::new(dec!(10000));
+    // Closed 1.5, 3 and 6 days ago, then 8, 12, 25 and 35 days ago.
+    for (closed_hours_ago, exit) in [(36, 110), (72, 95), (144, 104), (192, 120), (288, 90), (600, 105), (840, 130)] {
+        round_trip(&repo, &mut portfolio, closed_hours_ago, Decimal::from(exit)).await;
+    }
+
+    let stats = rolling::compute_rolling_stats(&repo, now()).await.expect("compute rolling stats");
+    assert_eq!(stats.computed_at, now());
+    let [week, month] = stats.windows.as_slice() else { panic!("expected two windows, got {:?}", stats.windows) };
+
+    // The last 7 days hold +10, -5 and +4, less six fees; the trade closed 8 days ago is left out.
+    assert_eq!((week.days, week.from), (7, hours_ago(7 * 24)));
+    assert_eq!(week.trade_count, Some(3));
+    assert_eq!(week.net_pnl, Some(dec!(8.4)));
+    assert_eq!(week.win_rate_pct, Some(Decimal::from(2) / Decimal::from(3) * dec!(100)));
+
+    // The last 30 days add +20, -10 and +5, but not the trade closed 35 days ago.
+    assert_eq!((month.days, month.from), (30, hours_ago(30 * 24)));
+    assert_eq!(month.trade_count, Some(6));
+    assert_eq!(month.net_pnl, Some(dec!(22.8)));
+    assert_eq!(month.win_rate_pct, Some(Decimal::from(4) / Decimal::from(6) * dec!(100)));
+
+    // The Sharpe Ratio is of the equity at each day boundary of the window, and at its end
+    // the last equity recorded, six hours before.
+    let daily_equity = |days: i64| -> Vec<Decimal> {
+        let first = STEPS - days * 4;
+        (0..days).map(|day| equity_at(first + day * 4)).chain([equity_at(STEPS - 1)]).collect()
+    };
+    assert_sharpe(week, &daily_equity(7));
+    assert_sharpe(month, &daily_equity(30));
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn windows_the_history_does_not_span_report_nulls() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+
+    // Nothing recorded at all.
+    let stats = rolling::compute_rolling_stats(&repo, now()).await.expect("compute rolling stats");
+    let nulls = |window: &RollingWindowStats| (window.net_pnl, window.trade_count, window.win_rate_pct, window.sharpe_ratio);
+    assert!(stats.windows.iter().all(|window| nulls(window) == (None, None, None, None)));
+
+    // Ten days of flat equity and one trade two days ago.
+    for day in 0..10 {
+        repo.save_live_equity(hours_ago((10 - day) * 24), dec!(10000)).await.expect("save equity");
+    }
+    let mut portfolio = Portfolio::new(dec!(10000));
+    round_trip(&repo, &mut portfolio, 48, dec!(104)).await;
+
+    let stats = rolling::compute_rolling_stats(&repo, now()).await.expect("compute rolling stats");
+    let [week, month] = stats.windows.as_slice() else { panic!("expected two windows, got {:?}", stats.windows) };
+    // The week is covered, but its equity never moved, so it has no Sharpe Ratio.
+    assert_eq!(nulls(week), (Some(dec!(3.8)), Some(1), Some(dec!(100)), None));
+    // The history does not reach back 30 days.
+    assert_eq!(nulls(month), (None, None, None, None));
+
+    db.teardown().await.expect("drop test database");
+}
@@ -0,0 +1,72 @@
+//! Checks that the synthetic series are well-formed bars and reproducible from their seed.
+
+use chrono::{Duration, TimeZone, Utc};
+use core_types::Kline;
+use rust_decimal_macros::dec;
+use testing::generate_klines;
+use testing::synthetic::{gbm, regime_switching, sine_trend, Regime, SeriesSpec, SineTrend};
+
+/// Asserts the OHLC relationships and time alignment every generated series must have.
+fn assert_well_formed(klines: &[Kline], interval: &str, step: Duration) {
+    for kline in klines {
+        assert!(kline.high >= kline.open.max(kline.close), "{:?}", kline);
+        assert!(kline.low <= kline.open.min(kline.close), "{:?}", kline);
+        assert!(kline.low.is_sign_positive() && kline.volume.is_sign_positive(), "{:?}", kline);
+        assert_eq!(kline.close_time, kline.open_time + step - Duration::milliseconds(1));
+        assert_eq!(kline.open_time.timestamp_millis() % step.num_milliseconds(), 0);
+        assert_eq!(kline.interval, interval);
+    }
+    for pair in klines.windows(2) {
+        assert_eq!(pair[1].open, pair[0].close);
+        assert_eq!(pair[1].open_time, pair[0].open_time + step);
+    }
+}
+
+#[test]
+fn every_generator_produces_consistent_aligned_bars() {
+    let mut spec = SeriesSpec::new("15m");
+    // Not on a 15 minute boundary: the first bar opens at 10:00.
+    spec.start = Utc.with_ymd_and_hms(2024, 3, 1, 10, 7, 0).unwrap();
+    spec.decimals = 4;
+
+    let random_walk = gbm(&spec, 1000, 0.0005, 0.01, 7);
+    assert_eq!(random_walk.len(), 1000);
+    assert_eq!(random_walk[0].open_time, Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap());
+    assert_well_formed(&random_walk, "15m", Duration::minutes(15));
+
+    let regimes = [Regime::trend(300, 0.002, 0.005), Regime::chop(300, 0.005), Regime::trend(300, 0.002, 0.005)];
+    assert_well_formed(&regime_switching(&spec, &regimes, 7), "15m", Duration::minutes(15));
+
+    let shape = SineTrend { slope: 0.5, amplitude: 3.0, period: 50.0, wick: 0.1 };
+    assert_well_formed(&sine_trend(&SeriesSpec::new("1d"), 200, &shape), "1d", Duration::days(1));
+}
+
+#[test]
+fn a_seed_always_yields_the_same_series() {
+    let spec = SeriesSpec::default();
+    assert_eq!(gbm(&spec, 500, 0.0, 0.01, 42), gbm(&spec, 500, 0.0, 0.01, 42));
+    assert_ne!(gbm(&spec, 500, 0.0, 0.01, 42), gbm(&spec, 500, 0.0, 0.01, 43));
+}
+
+#[test]
+fn chop_stays_near_its_level_and_trends_move_away() {
+    let spec = SeriesSpec::default();
+    let klines = regime_switching(&spec, &[Regime::trend(500, 0.002, 0.002), Regime::chop(500, 0.01), Regime::trend(500, -0.002, 0.002)], 3);
+    let close = |i: usize| klines[i].close;
+
+    // About +100% over the first trend, back down over the last; the chop in between ends
+    // within a few percent of where it started, for all its volatility.
+    assert!(close(499) > close(0) * dec!(1.6));
+    let chop_drift = (close(999) - close(499)).abs() / close(499);
+    assert!(chop_drift < dec!(0.05), "{}", chop_drift);
+    assert!(close(1499) < close(999) * dec!(0.5));
+}
+
+#[test]
+fn the_fixture_series_follows_its_formula() {
+    let klines = generate_klines(1000);
+    assert_well_formed(&klines, "1h", Duration::hours(1));
+    // 100 + 0.01 * 60 + 10 * sin(PI / 2), at the crest of the first wave.
+    assert_eq!(klines[60].close, dec!(110.60));
+    assert_eq!(klines[60].high, klines[59].close.max(dec!(110.60)) + dec!(0.25));
+}
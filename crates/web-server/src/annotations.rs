@@ -0,0 +1,109 @@
+//! Trade journal notes.
+//!
+//! Notes are attached by hand to backtest trades and runs and to live positions, and come
+//! back with them: each trade of the run details and each live position carries a `notes`
+//! array, oldest first.
+
+use crate::error::AppError;
+use chrono::{SubsecRound, Utc};
+use core_types::Trade;
+use database::{Annotation, AnnotationTarget, BacktestRunDetails, EquityDataPoint, FullReport, LivePosition};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// The body of `POST /api/annotations`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewAnnotation {
+    pub target_type: AnnotationTarget,
+    pub target_id: Uuid,
+    pub author: String,
+    pub text: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl NewAnnotation {
+    /// Validates the request and turns it into an annotation created now. Surrounding
+    /// whitespace is trimmed and repeated tags are dropped.
+    pub fn into_annotation(self) -> Result<Annotation, AppError> {
+        let author = self.author.trim().to_string();
+        let text = self.text.trim().to_string();
+        if author.is_empty() {
+            return Err(AppError::BadRequest("An annotation needs an author".to_string()));
+        }
+        if text.is_empty() {
+            return Err(AppError::BadRequest("An annotation needs a text".to_string()));
+        }
+        let mut tags: Vec<String> = Vec::with_capacity(self.tags.len());
+        for tag in self.tags {
+            let tag = tag.trim().to_string();
+            if tag.is_empty() {
+                return Err(AppError::BadRequest("Annotation tags cannot be empty".to_string()));
+            }
+            if !tags.contains(&tag) {
+                tags.push(tag);
+            }
+        }
+        Ok(Annotation {
+            annotation_id: Uuid::new_v4(),
+            target_type: self.target_type,
+            target_id: self.target_id,
+            author,
+            text,
+            tags,
+            // The database keeps microseconds.
+            created_at: Utc::now().trunc_subsecs(6),
+        })
+    }
+}
+
+/// A backtest trade with its notes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedTrade {
+    #[serde(flatten)]
+    pub trade: Trade,
+    pub notes: Vec<Annotation>,
+}
+
+/// The response of `GET /api/backtest-runs/:run_id/details`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedRunDetails {
+    pub report: FullReport,
+    pub trades: Vec<AnnotatedTrade>,
+    pub equity_curve: Vec<EquityDataPoint>,
+    /// The notes on the run itself.
+    pub notes: Vec<Annotation>,
+}
+
+impl AnnotatedRunDetails {
+    /// Attaches the `trade_notes` of each trade and the notes of the run to `details`.
+    pub fn new(details: BacktestRunDetails, mut trade_notes: HashMap<Uuid, Vec<Annotation>>, notes: Vec<Annotation>) -> Self {
+        Self {
+            report: details.report,
+            trades: details
+                .trades
+                .into_iter()
+                .map(|trade| AnnotatedTrade { notes: trade_notes.remove(&trade.trade_id).unwrap_or_default(), trade })
+                .collect(),
+            equity_curve: details.equity_curve,
+            notes,
+        }
+    }
+}
+
+/// A live position with its notes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnnotatedPosition {
+    #[serde(flatten)]
+    pub position: LivePosition,
+    pub notes: Vec<Annotation>,
+}
+
+/// Attaches the notes of each position to `positions`.
+pub fn annotate_positions(positions: Vec<LivePosition>, mut notes: HashMap<Uuid, Vec<Annotation>>) -> Vec<AnnotatedPosition> {
+    positions
+        .into_iter()
+        .map(|position| AnnotatedPosition { notes: notes.remove(&position.position_id).unwrap_or_default(), position })
+        .collect()
+}
@@ -0,0 +1,88 @@
+//! Token authentication of the REST API and the WebSocket.
+//!
+//! When the server is configured with an API token, REST requests must present it as a
+//! bearer token, which the `require_token` middleware checks. Browsers cannot set headers on
+//! a WebSocket upgrade, so WebSocket clients instead pass it as a `token` query parameter or
+//! send it in an `Auth` frame as their first message.
+
+use crate::{error::AppError, AppState};
+use axum::{
+    extract::{
+        ws::{close_code, CloseFrame, Message, WebSocket},
+        Request,
+        State,
+    },
+    http::header::AUTHORIZATION,
+    middleware::Next,
+    response::Response,
+};
+use events::WsClientMessage;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// How long a WebSocket client that did not authenticate on upgrade has to send its
+/// `Auth` frame before the socket is closed.
+pub const WS_AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The API token the server requires, if any.
+#[derive(Debug, Clone, Default)]
+pub struct ApiToken(Option<String>);
+
+impl ApiToken {
+    /// An empty token counts as none, so an unset environment override disables auth
+    /// rather than requiring an empty bearer token.
+    pub fn new(token: Option<String>) -> Self {
+        Self(token.filter(|token| !token.is_empty()))
+    }
+
+    pub fn is_required(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Whether `presented` grants access. Anything does if no token is configured.
+    pub fn accepts(&self, presented: Option<&str>) -> bool {
+        match &self.0 {
+            None => true,
+            Some(expected) => presented.is_some_and(|presented| constant_time_eq(presented.as_bytes(), expected.as_bytes())),
+        }
+    }
+}
+
+/// Compares without returning early, so response timing does not reveal how much of a
+/// guessed token was right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Rejects requests without the configured bearer token with 401 Unauthorized.
+pub async fn require_token(State(state): State<Arc<AppState>>, request: Request, next: Next) -> Result<Response, AppError> {
+    let presented = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !state.api_token.accepts(presented) {
+        return Err(AppError::Unauthorized);
+    }
+    Ok(next.run(request).await)
+}
+
+/// Waits up to `timeout` for the client's `Auth` frame. If it does not arrive in time, or
+/// carries the wrong token, the socket is closed and `false` returned.
+pub async fn authenticate_socket(socket: &mut WebSocket, token: &ApiToken, timeout: Duration) -> bool {
+    let presented = match tokio::time::timeout(timeout, socket.recv()).await {
+        Ok(Some(Ok(Message::Text(text)))) => match serde_json::from_str(&text) {
+            Ok(WsClientMessage::Auth { token }) => Some(token),
+            Ok(_) | Err(_) => None,
+        },
+        _ => None,
+    };
+    if token.accepts(presented.as_deref()) {
+        return true;
+    }
+
+    tracing::warn!("[WS] Closing a client that did not authenticate.");
+    let close = CloseFrame { code: close_code::POLICY, reason: "unauthenticated".into() };
+    let _ = socket.send(Message::Close(Some(close))).await;
+    false
+}
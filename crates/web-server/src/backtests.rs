@@ -0,0 +1,152 @@
+//! Single backtests started through the API.
+//!
+//! A submission is validated and recorded as a Pending single run straight away, the same
+//! way the `single-run` command records one. The run itself happens on a background task
+//! once one of the server's backtest slots is free, broadcasting its progress as
+//! `WsMessage::BacktestProgress`.
+
+use crate::error::AppError;
+use backtester::{run_backtest_with_progress, BacktestSpec, KlineSource, ProgressCallback};
+use chrono::{DateTime, Utc};
+use configuration::Config;
+use core_types::{Interval, StrategyId};
+use database::DbRepository;
+use events::{EventBus, WsMessage};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::sync::Arc;
+use strategies::{create_strategy_from_params, merge_params};
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+/// The backtests that run at once when `server.max_concurrent_backtests` is not set.
+pub const DEFAULT_MAX_CONCURRENT_BACKTESTS: usize = 2;
+
+/// The body of `POST /api/backtest-runs`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewBacktestRun {
+    pub strategy_id: StrategyId,
+    /// Overrides of the strategy's parameters in `config.toml`; may be partial.
+    #[serde(default = "empty_params")]
+    pub params: JsonValue,
+    pub symbol: String,
+    pub interval: String,
+    /// The first kline open time to replay, inclusive.
+    pub from: DateTime<Utc>,
+    /// The last kline open time to replay, inclusive.
+    pub to: DateTime<Utc>,
+    pub initial_capital: Decimal,
+}
+
+fn empty_params() -> JsonValue {
+    JsonValue::Object(Default::default())
+}
+
+/// The response of `POST /api/backtest-runs`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BacktestRunCreated {
+    pub run_id: Uuid,
+}
+
+/// Starts API-submitted backtests, at most `max_concurrent_backtests` at a time. The
+/// simulation settings and the strategies' base parameters come from the configuration.
+#[derive(Clone)]
+pub struct BacktestRunner {
+    config: Arc<Config>,
+    slots: Arc<Semaphore>,
+}
+
+impl BacktestRunner {
+    pub fn new(config: Config) -> Self {
+        let max_concurrent = config.server.max_concurrent_backtests.unwrap_or(DEFAULT_MAX_CONCURRENT_BACKTESTS).max(1);
+        Self { config: Arc::new(config), slots: Arc::new(Semaphore::new(max_concurrent)) }
+    }
+
+    /// Validates `request`, records it as a Pending single run and starts it in the
+    /// background. Returns the new run's ID without waiting for the run.
+    pub async fn submit(
+        &self,
+        db_repo: &DbRepository,
+        event_tx: &EventBus,
+        request: NewBacktestRun,
+    ) -> Result<Uuid, AppError> {
+        let spec = self.spec(request, db_repo.clone())?;
+        let (job_id, run_id) = (Uuid::new_v4(), spec.run_id);
+        db_repo.save_optimization_job(job_id, &format!("{:?}", spec.strategy_id), &spec.symbol, "Single Run", None).await?;
+        db_repo.save_backtest_run(run_id, job_id, &spec.params, "Pending").await?;
+
+        tokio::spawn(run(spec, db_repo.clone(), event_tx.clone(), Arc::clone(&self.slots)));
+        Ok(run_id)
+    }
+
+    fn spec(&self, request: NewBacktestRun, db_repo: DbRepository) -> Result<BacktestSpec, AppError> {
+        if request.symbol.trim().is_empty() {
+            return Err(AppError::BadRequest("`symbol` must not be empty".to_string()));
+        }
+        if let Err(e) = request.interval.parse::<Interval>() {
+            return Err(AppError::BadRequest(e.to_string()));
+        }
+        if request.from >= request.to {
+            return Err(AppError::BadRequest(format!("`from` ({}) is not before `to` ({})", request.from, request.to)));
+        }
+        if request.initial_capital <= Decimal::ZERO {
+            return Err(AppError::BadRequest("`initial_capital` must be positive".to_string()));
+        }
+        let params = merge_params(request.strategy_id, &self.config, &request.params)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+        create_strategy_from_params(request.strategy_id, &params, &request.symbol)
+            .map_err(|e| AppError::BadRequest(e.to_string()))?;
+
+        Ok(BacktestSpec {
+            run_id: Uuid::new_v4(),
+            strategy_id: request.strategy_id,
+            params,
+            symbol: request.symbol,
+            interval: request.interval,
+            start: request.from,
+            end: request.to,
+            initial_capital: request.initial_capital,
+            quote_asset: self.config.execution.quote_asset.clone(),
+            simulation: self.config.simulation.clone(),
+            risk_management: self.config.risk_management.clone(),
+            kline_transform: self.config.backtest.kline_transform,
+            direction: self.config.backtest.direction,
+            valuation_price: self.config.backtest.valuation_price,
+            trading_blackouts: self.config.trading_blackouts.clone(),
+            dynamic_leverage: self.config.global_risk.dynamic_leverage.clone(),
+            klines: KlineSource::Database(db_repo),
+        })
+    }
+}
+
+/// Waits for a free slot, then runs the backtest and saves its results, moving the run from
+/// Pending to Running to Completed or Failed.
+async fn run(spec: BacktestSpec, db_repo: DbRepository, event_tx: EventBus, slots: Arc<Semaphore>) {
+    let run_id = spec.run_id;
+    // The semaphore is never closed.
+    let Ok(_slot) = slots.acquire_owned().await else { return };
+    if let Err(e) = db_repo.update_run_status(run_id, "Running").await {
+        tracing::error!(run_id = %run_id, error = ?e, "Failed to mark the backtest as running.");
+    }
+
+    let progress: ProgressCallback = Arc::new(move |progress| {
+        // Nobody may be listening, which is fine.
+        let _ = event_tx.send(WsMessage::BacktestProgress(progress));
+    });
+    let result = match run_backtest_with_progress(spec, Some(progress)).await {
+        Ok(output) => output.save(&db_repo).await,
+        Err(e) => Err(e),
+    };
+
+    let status = match result {
+        Ok(()) => "Completed",
+        Err(e) => {
+            tracing::error!(run_id = %run_id, error = ?e, "Backtest Failed.");
+            "Failed"
+        }
+    };
+    if let Err(e) = db_repo.update_run_status(run_id, status).await {
+        tracing::error!(run_id = %run_id, error = ?e, "Failed to record the backtest's final status.");
+    }
+}
@@ -17,6 +17,10 @@ pub enum AppError {
     Config(#[from] configuration::error::ConfigError),
     #[error("Not found: {0}")]
     NotFound(String),
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    #[error("Missing or invalid API token")]
+    Unauthorized,
 }
 
 /// Converts our custom `AppError` into an HTTP response.
@@ -45,6 +49,8 @@ impl IntoResponse for AppError {
                 )
             }
             AppError::NotFound(message) => (StatusCode::NOT_FOUND, message),
+            AppError::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "Missing or invalid API token".to_string()),
         };
 
         let body = Json(json!({ "error": error_message }));
@@ -1,23 +1,32 @@
-use crate::{error::AppError, AppState};
-use analyzer::{Analyzer, RankedReport};
-use database::repository::BacktestRunDetails;
+use crate::annotations::{annotate_positions, AnnotatedPosition, AnnotatedRunDetails, NewAnnotation};
+use crate::backtests::{BacktestRunCreated, NewBacktestRun};
+use crate::kline_history::KlineSnapshot;
+use crate::positions::blend_open_positions;
+use crate::{auth, error::AppError, AppState};
+use analyzer::error::AnalyzerError;
+use analyzer::{compare_runs, stored_job_config, Analyzer, ClusterOptions, CompareOptions, RankedReport, RunComparison};
+use analytics::downsample::{aggregate_klines, kline_bucket_size};
 use tracing;
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, Utc};
 use axum::{
     extract::{
-        ws::{Message, WebSocket, WebSocketUpgrade},
+        ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade},
         Path,
         Query,
         State,
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{Html, IntoResponse},
     Json,
 };
-use configuration::load_optimizer_config;
-use database::{DbOptimizationJob, FullReport, WfoJob, WfoRun};
+use configuration::{load_optimizer_config, JobConfigSnapshot};
+use database::{Annotation, AnnotationFilter, AnnotationTarget, DbError, DbOptimizationJob, DecisionAuditRecord, FullReport, LivePositionFilter, PerformanceRollup, RollupGranularity, StoredPortfolioEvent, SymbolExecutionQuality, WfoJob, WfoRun};
+use events::{ChannelStats, EngineStatsSnapshot, RollingStats, EVENT_CHANNEL_CAPACITY};
 use futures_util::StreamExt;
+use tokio::sync::broadcast::error::RecvError;
 
-use serde::Deserialize;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -46,6 +55,44 @@ pub async fn get_optimization_jobs(
     Ok(Json(jobs))
 }
 
+/// The response of `GET /api/optimization-jobs/:job_id/progress`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OptimizationJobProgress {
+    pub job_id: Uuid,
+    /// Running, then Completed once every run has finished.
+    pub job_status: String,
+    pub completed_runs: i32,
+    pub total_runs: i32,
+    /// When the progress was last saved. The optimizer saves it every few runs.
+    pub updated_at: DateTime<Utc>,
+    pub estimated_completion_at: Option<DateTime<Utc>>,
+}
+
+impl From<DbOptimizationJob> for OptimizationJobProgress {
+    fn from(job: DbOptimizationJob) -> Self {
+        Self {
+            job_id: job.job_id,
+            job_status: job.job_status,
+            completed_runs: job.completed_runs,
+            total_runs: job.total_runs,
+            updated_at: job.updated_at,
+            estimated_completion_at: job.estimated_completion_at,
+        }
+    }
+}
+
+/// # GET /api/optimization-jobs/:job_id/progress
+pub async fn get_optimization_job_progress(
+    Path(job_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<OptimizationJobProgress>, AppError> {
+    let job = state.db_repo.get_optimization_job(job_id).await.map_err(|e| match e {
+        DbError::NotFound => AppError::NotFound(format!("No optimization job {}", job_id)),
+        e => AppError::Database(e),
+    })?;
+    Ok(Json(job.into()))
+}
+
 /// # GET /api/single-runs (NEW ENDPOINT)
 /// Fetches a list of all completed single backtest runs.
 pub async fn get_single_runs(
@@ -56,15 +103,45 @@ pub async fn get_single_runs(
     Ok(Json(runs))
 }
 
+/// A ranked run of an optimization job, with the cluster of good parameter sets it belongs to.
+#[derive(Debug, Serialize)]
+pub struct ClusteredReport {
+    #[serde(flatten)]
+    pub ranked: RankedReport,
+    /// None for runs outside the job's best or too far from any other good run.
+    pub cluster: Option<usize>,
+}
+
 /// # GET /api/optimization-jobs/:job_id
+/// Ranks the job's runs with the filters and weights stored with the job, or with those in
+/// `optimizer.toml` for jobs stored without a configuration.
 pub async fn get_optimization_job_details(
     Path(job_id): Path<Uuid>,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<Vec<RankedReport>>, AppError> {
-    let optimizer_config = load_optimizer_config(&PathBuf::from("optimizer.toml"))?;
-    let analyzer = Analyzer::new(optimizer_config.analysis);
-    let ranked_reports = analyzer.run(&state.db_repo, job_id).await?;
-    Ok(Json(ranked_reports))
+) -> Result<Json<Vec<ClusteredReport>>, AppError> {
+    let analysis = match stored_job_config(&state.db_repo, job_id).await? {
+        Some(job_config) => job_config.optimizer.analysis,
+        None => load_optimizer_config(&PathBuf::from("optimizer.toml"))?.analysis,
+    };
+    let analyzer = Analyzer::new(analysis);
+    let (ranked_reports, _, clustering) = analyzer.run_with_clusters(&state.db_repo, job_id, &ClusterOptions::default()).await?;
+    let reports = ranked_reports
+        .into_iter()
+        .map(|ranked| ClusteredReport { cluster: clustering.cluster_of(ranked.report.run_id), ranked })
+        .collect();
+    Ok(Json(reports))
+}
+
+/// # GET /api/optimization-jobs/:job_id/config
+/// The configuration the job was run with.
+pub async fn get_optimization_job_config(
+    Path(job_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<JobConfigSnapshot>, AppError> {
+    let job_config = stored_job_config(&state.db_repo, job_id).await?;
+    job_config
+        .map(Json)
+        .ok_or_else(|| AppError::NotFound(format!("No configuration stored for optimization job {}", job_id)))
 }
 
 /// # GET /api/backtest-runs/:run_id
@@ -76,14 +153,186 @@ pub async fn get_backtest_run_details(
     Ok(Json(report))
 }
 
+/// # POST /api/backtest-runs
+/// Starts a single backtest in the background and returns its run ID right away. Follow it
+/// through `/api/backtest-runs/:run_id/status` and the `BacktestProgress` WebSocket messages.
+pub async fn create_backtest_run(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<NewBacktestRun>,
+) -> Result<(StatusCode, Json<BacktestRunCreated>), AppError> {
+    let run_id = state.backtests.submit(&state.db_repo, &state.event_tx, request).await?;
+    Ok((StatusCode::ACCEPTED, Json(BacktestRunCreated { run_id })))
+}
+
+/// The response of `GET /api/backtest-runs/:run_id/status`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BacktestRunStatus {
+    pub run_id: Uuid,
+    /// Pending, Running, Completed or Failed.
+    pub status: String,
+}
+
+/// # GET /api/backtest-runs/:run_id/status
+pub async fn get_backtest_run_status(
+    Path(run_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<BacktestRunStatus>, AppError> {
+    let status = state.db_repo.get_run_status(run_id).await.map_err(|e| match e {
+        DbError::NotFound => AppError::NotFound(format!("No backtest run {}", run_id)),
+        e => AppError::Database(e),
+    })?;
+    Ok(Json(BacktestRunStatus { run_id, status }))
+}
+
 /// # GET /api/backtest-runs/:run_id/details
 /// Fetches the full details for a single backtest run, including trades and equity curve.
+/// The run and each trade carry their notes.
 pub async fn get_backtest_run_full_details(
     Path(run_id): Path<Uuid>,
     State(state): State<Arc<AppState>>,
-) -> Result<Json<BacktestRunDetails>, AppError> {
+) -> Result<Json<AnnotatedRunDetails>, AppError> {
     let details = state.db_repo.get_run_details(run_id).await?;
-    Ok(Json(details))
+    let trade_ids: Vec<Uuid> = details.trades.iter().map(|trade| trade.trade_id).collect();
+    let run_ids = [run_id];
+    let (trade_notes, mut run_notes) = tokio::try_join!(
+        state.db_repo.get_annotations_for(AnnotationTarget::Trade, &trade_ids),
+        state.db_repo.get_annotations_for(AnnotationTarget::Run, &run_ids),
+    )?;
+    let notes = run_notes.remove(&run_id).unwrap_or_default();
+    Ok(Json(AnnotatedRunDetails::new(details, trade_notes, notes)))
+}
+
+/// # GET /api/backtest-runs/:run_id/report.html
+/// Renders the full details of a backtest run as a self-contained HTML report.
+pub async fn get_backtest_run_report(
+    Path(run_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Html<String>, AppError> {
+    let details = state.db_repo.get_run_details(run_id).await?;
+    Ok(Html(reporting::render_html(&details)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareQuery {
+    /// The two run IDs to compare, separated by a comma.
+    runs: String,
+    /// The largest equity difference that does not count as a divergence.
+    epsilon: Option<rust_decimal::Decimal>,
+}
+
+/// # GET /api/compare?runs=a,b
+/// Compares run `b` against run `a`: metric deltas, trades unique to each, and the point
+/// where their equity curves first diverge.
+pub async fn compare_backtest_runs(
+    Query(query): Query<CompareQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<RunComparison>, AppError> {
+    let run_ids = query
+        .runs
+        .split(',')
+        .map(|id| Uuid::parse_str(id.trim()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::BadRequest(format!("Invalid run ID: {}", e)))?;
+    let [run_a, run_b] = run_ids[..] else {
+        return Err(AppError::BadRequest(format!("Expected exactly two run IDs, got {}", run_ids.len())));
+    };
+
+    let (a, b) = tokio::try_join!(state.db_repo.get_run_details(run_a), state.db_repo.get_run_details(run_b))?;
+    let mut options = CompareOptions::default();
+    if let Some(epsilon) = query.epsilon {
+        options.equity_epsilon = epsilon;
+    }
+    let comparison = compare_runs(&a, &b, &options).map_err(|e| match e {
+        AnalyzerError::NoOverlap(..) => AppError::BadRequest(e.to_string()),
+        e => AppError::Analyzer(e),
+    })?;
+    Ok(Json(comparison))
+}
+
+/// The number of candles a klines response holds when the request does not set `max_points`.
+pub const DEFAULT_MAX_KLINE_POINTS: usize = 2000;
+
+#[derive(Debug, Deserialize)]
+pub struct KlinesQuery {
+    symbol: String,
+    interval: String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    max_points: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RunKlinesQuery {
+    max_points: Option<usize>,
+}
+
+/// A compact price series for charting. Each candle is an array with the fields named in
+/// `columns`; times are Unix milliseconds and prices decimal strings.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct KlineSeries {
+    pub symbol: String,
+    pub interval: String,
+    /// How many stored klines each candle merges. 1 when the range fit in `max_points`.
+    pub bucket_size: usize,
+    pub columns: Vec<String>,
+    pub candles: Vec<(i64, Decimal, Decimal, Decimal, Decimal, Decimal, i64)>,
+}
+
+const KLINE_COLUMNS: [&str; 7] = ["open_time", "open", "high", "low", "close", "volume", "close_time"];
+
+/// # GET /api/klines?symbol=&interval=&from=&to=&max_points=
+/// Fetches the stored klines of a symbol that open within `[from, to]`. Ranges with more
+/// than `max_points` klines are merged into wider candles rather than thinned.
+pub async fn get_klines(
+    Query(query): Query<KlinesQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<KlineSeries>, AppError> {
+    if query.from > query.to {
+        return Err(AppError::BadRequest(format!("`from` ({}) is after `to` ({})", query.from, query.to)));
+    }
+    kline_series(&state, query.symbol, query.interval, query.from, query.to, query.max_points).await
+}
+
+/// # GET /api/backtest-runs/:run_id/klines?max_points=
+/// Fetches the klines a backtest run replayed, for drawing its trades on a price chart.
+pub async fn get_backtest_run_klines(
+    Path(run_id): Path<Uuid>,
+    Query(query): Query<RunKlinesQuery>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<KlineSeries>, AppError> {
+    let range = state.db_repo.get_run_kline_range(run_id).await.map_err(|e| match e {
+        DbError::NotFound => AppError::NotFound(format!("No recorded kline range for run {}", run_id)),
+        e => AppError::Database(e),
+    })?;
+    kline_series(&state, range.symbol, range.interval, range.data_start, range.data_end, query.max_points).await
+}
+
+async fn kline_series(
+    state: &AppState,
+    symbol: String,
+    interval: String,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    max_points: Option<usize>,
+) -> Result<Json<KlineSeries>, AppError> {
+    let max_points = max_points.unwrap_or(DEFAULT_MAX_KLINE_POINTS);
+    if max_points == 0 {
+        return Err(AppError::BadRequest("`max_points` must be positive".to_string()));
+    }
+
+    let klines = state.db_repo.get_klines_by_date_range(&symbol, &interval, from, to).await?;
+    let bucket_size = kline_bucket_size(klines.len(), max_points);
+    let candles = aggregate_klines(&klines, bucket_size)
+        .iter()
+        .map(|k| (k.open_time.timestamp_millis(), k.open, k.high, k.low, k.close, k.volume, k.close_time.timestamp_millis()))
+        .collect();
+    Ok(Json(KlineSeries {
+        symbol,
+        interval,
+        bucket_size,
+        columns: KLINE_COLUMNS.iter().map(|c| c.to_string()).collect(),
+        candles,
+    }))
 }
 
 /// # GET /api/wfo-jobs
@@ -105,26 +354,255 @@ pub async fn get_wfo_job_runs(
     let runs = state.db_repo.get_wfo_runs_for_job(wfo_job_id).await?;
     Ok(Json(runs))
 }
-/// # GET /ws
-/// The WebSocket endpoint for real-time communication.
+
+/// # GET /api/audit/:decision_id
+/// Fetches every recorded stage of a live trading decision, from signal to portfolio update.
+pub async fn get_decision_audit(
+    Path(decision_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<DecisionAuditRecord>>, AppError> {
+    let records = state.db_repo.get_decision_audit(decision_id).await?;
+    if records.is_empty() {
+        return Err(AppError::NotFound(format!("No audit records found for decision {}", decision_id)));
+    }
+    Ok(Json(records))
+}
+/// # GET /api/live/positions?status=open|closed&symbol=...&from=...&to=...
+/// The live engine's positions, rebuilt from its recorded executions, open ones first. Open
+/// positions carry the size, entry price and unrealized P&L of the latest portfolio broadcast.
+/// `from` and `to` bound the entry time. Each position carries its notes.
+pub async fn get_live_positions(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<LivePositionFilter>,
+) -> Result<Json<Vec<AnnotatedPosition>>, AppError> {
+    // The entry-time range is applied after blending, since an open position's history is
+    // needed whenever it was opened.
+    let recorded = state.db_repo.get_live_positions(&LivePositionFilter { from: None, to: None, ..filter.clone() }).await?;
+    let snapshot = state.portfolio_state_cache.lock().await.clone();
+    let positions = blend_open_positions(recorded, snapshot.as_ref(), &filter);
+    let position_ids: Vec<Uuid> = positions.iter().map(|position| position.position_id).collect();
+    let notes = state.db_repo.get_annotations_for(AnnotationTarget::Position, &position_ids).await?;
+    Ok(Json(annotate_positions(positions, notes)))
+}
+
+/// # POST /api/annotations
+/// Attaches a note to a backtest trade or run, or to a live position. The target must exist.
+pub async fn create_annotation(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<NewAnnotation>,
+) -> Result<(StatusCode, Json<Annotation>), AppError> {
+    let annotation = request.into_annotation()?;
+    if !state.db_repo.annotation_target_exists(annotation.target_type, annotation.target_id).await? {
+        return Err(AppError::NotFound(format!("No {} {}", annotation.target_type.as_str(), annotation.target_id)));
+    }
+    state.db_repo.save_annotation(&annotation).await?;
+    Ok((StatusCode::CREATED, Json(annotation)))
+}
+
+/// # GET /api/annotations?target_type=trade|run|position&target_id=...&tag=...
+/// The annotations matching every given parameter, oldest first.
+pub async fn get_annotations(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<AnnotationFilter>,
+) -> Result<Json<Vec<Annotation>>, AppError> {
+    Ok(Json(state.db_repo.get_annotations(&filter).await?))
+}
+
+/// # DELETE /api/annotations/:annotation_id
+pub async fn delete_annotation(
+    Path(annotation_id): Path<Uuid>,
+    State(state): State<Arc<AppState>>,
+) -> Result<StatusCode, AppError> {
+    state.db_repo.delete_annotation(annotation_id).await.map_err(|e| match e {
+        DbError::NotFound => AppError::NotFound(format!("No annotation {}", annotation_id)),
+        e => AppError::Database(e),
+    })?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Query parameters of `GET /api/live/performance`.
+#[derive(Debug, Deserialize)]
+pub struct PerformanceQuery {
+    #[serde(default)]
+    pub granularity: RollupGranularity,
+    /// The earliest period start, inclusive.
+    pub from: Option<NaiveDate>,
+    /// The latest period start, inclusive.
+    pub to: Option<NaiveDate>,
+}
+
+/// # GET /api/live/performance?granularity=daily|weekly&from=...&to=...
+/// The live engine's stored performance rollups, oldest first. Days are rolled up after UTC
+/// midnight; the current week's rollup covers the days completed so far.
+pub async fn get_live_performance(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PerformanceQuery>,
+) -> Result<Json<Vec<PerformanceRollup>>, AppError> {
+    let rollups = state.db_repo.get_performance_rollups(query.granularity, query.from, query.to).await?;
+    Ok(Json(rollups))
+}
+
+/// Query parameters of `GET /api/live/execution-quality`.
+#[derive(Debug, Deserialize)]
+pub struct ExecutionQualityQuery {
+    /// The earliest fill time, inclusive.
+    pub from: Option<DateTime<Utc>>,
+    /// The latest fill time, exclusive.
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// # GET /api/live/execution-quality?from=...&to=...
+/// How the live engine's limit orders filled, by symbol: the share that filled as makers and
+/// their mean saving against crossing the spread, in basis points.
+pub async fn get_live_execution_quality(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<ExecutionQualityQuery>,
+) -> Result<Json<Vec<SymbolExecutionQuality>>, AppError> {
+    let quality = state.db_repo.get_execution_quality(query.from, query.to).await?;
+    Ok(Json(quality))
+}
+
+/// # GET /api/live/rolling-stats
+/// The live performance over the trailing 7 and 30 days: net P&L, trade count, win rate and
+/// Sharpe Ratio, as of the last hourly refresh. Computed on the spot until a refresh succeeds.
+pub async fn get_live_rolling_stats(State(state): State<Arc<AppState>>) -> Result<Json<RollingStats>, AppError> {
+    if let Some(stats) = state.rolling_stats.lock().await.clone() {
+        return Ok(Json(stats));
+    }
+    let stats = analyzer::rolling::compute_rolling_stats(&state.db_repo, Utc::now()).await?;
+    *state.rolling_stats.lock().await = Some(stats.clone());
+    Ok(Json(stats))
+}
+
+/// Query parameters of `GET /api/live/portfolio-events`.
+#[derive(Debug, Deserialize)]
+pub struct PortfolioEventsQuery {
+    /// Only the events of this symbol's position.
+    pub symbol: Option<String>,
+    #[serde(default = "default_portfolio_events_limit")]
+    pub limit: i64,
+}
+
+fn default_portfolio_events_limit() -> i64 { 100 }
+
+/// A stored portfolio event, with a sentence describing it.
+#[derive(Debug, Serialize)]
+pub struct DescribedPortfolioEvent {
+    #[serde(flatten)]
+    pub record: StoredPortfolioEvent,
+    /// E.g. "reconciler changed BTCUSDT quantity from 0.010 to 0.012 at 2025-09-08 12:03:00 UTC".
+    pub description: String,
+}
+
+/// # GET /api/live/portfolio-events?symbol=&limit=
+/// The most recent events of the live portfolio, newest first: its executions, funding, and
+/// every correction made to bring it in line with the exchange, with what it replaced.
+pub async fn get_live_portfolio_events(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<PortfolioEventsQuery>,
+) -> Result<Json<Vec<DescribedPortfolioEvent>>, AppError> {
+    let symbol = query.symbol.map(|symbol| symbol.to_uppercase());
+    let events = state.db_repo.get_portfolio_events(symbol.as_deref(), query.limit.clamp(1, 1000)).await?;
+    Ok(Json(
+        events
+            .into_iter()
+            .map(|record| DescribedPortfolioEvent { description: record.event.to_string(), record })
+            .collect(),
+    ))
+}
+
+/// Query parameters of `GET /api/live/klines/:symbol`.
+#[derive(Debug, Deserialize)]
+pub struct LiveKlinesQuery {
+    /// The interval of the klines. May be omitted while the symbol trades in one interval.
+    pub interval: Option<String>,
+    /// The most klines to return, the newest. All the buffered ones when omitted.
+    pub limit: Option<usize>,
+}
+
+/// # GET /api/live/klines/:symbol?interval=&limit=
+/// The symbol's recently closed live klines in `interval`, oldest first, with the `seq` of the
+/// newest. Subscribe to the WebSocket before fetching it, then skip the `KlineData` of the
+/// symbol and interval numbered up to `snapshot_seq`; a skipped number means klines were
+/// missed, and the snapshot is refetched. Without `interval`, the symbol's only interval is
+/// served; a symbol seen in several needs one named.
+pub async fn get_live_klines(
+    State(state): State<Arc<AppState>>,
+    Path(symbol): Path<String>,
+    Query(query): Query<LiveKlinesQuery>,
+) -> Result<Json<KlineSnapshot>, AppError> {
+    let symbol = symbol.to_uppercase();
+    let interval = match query.interval {
+        Some(interval) => interval,
+        None => {
+            let mut intervals = state.kline_history.intervals(&symbol);
+            match intervals.len() {
+                0 => return Ok(Json(KlineSnapshot { symbol, interval: None, snapshot_seq: 0, klines: Vec::new() })),
+                1 => intervals.remove(0),
+                _ => {
+                    return Err(AppError::BadRequest(format!(
+                        "{} trades in several intervals ({}); name one with `interval`",
+                        symbol,
+                        intervals.join(", ")
+                    )))
+                }
+            }
+        }
+    };
+    Ok(Json(state.kline_history.snapshot(&symbol, &interval, query.limit)))
+}
+
+/// # GET /api/engine/stats
+/// A snapshot of the live engine's recent activity: uptime, each bot's last kline, signals
+/// and fills over the last hour and day, halted bots, each symbol's limit fills and the
+/// event channel's occupancy.
+pub async fn get_engine_stats(State(state): State<Arc<AppState>>) -> Result<Json<EngineStatsSnapshot>, AppError> {
+    let stats = state
+        .engine_stats
+        .as_ref()
+        .ok_or_else(|| AppError::NotFound("No live engine is running in this server".to_string()))?;
+    let event_channel = ChannelStats::new(state.event_tx.receiver_count(), state.event_tx.len(), EVENT_CHANNEL_CAPACITY);
+    Ok(Json(stats.snapshot(Utc::now(), event_channel)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WsAuthQuery {
+    token: Option<String>,
+}
+
+/// # GET /ws?token=...
+/// The WebSocket endpoint for real-time communication. When the server requires a token,
+/// clients pass it as the `token` query parameter or in an `Auth` frame sent first; a wrong
+/// query token is rejected with 401 before the upgrade.
 pub async fn websocket_handler(
     State(state): State<Arc<AppState>>,
+    Query(query): Query<WsAuthQuery>,
     ws: WebSocketUpgrade,
-) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, state))
+) -> Result<impl IntoResponse, AppError> {
+    let authenticated = match query.token.as_deref() {
+        Some(token) if !state.api_token.accepts(Some(token)) => return Err(AppError::Unauthorized),
+        Some(_) => true,
+        None => !state.api_token.is_required(),
+    };
+    Ok(ws.on_upgrade(move |socket| handle_socket(socket, state, authenticated)))
 }
 
 /// The actual logic for handling a single WebSocket connection.
-async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
+async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>, authenticated: bool) {
     tracing::info!("[WS] New client connected.");
 
-    // 1. Subscribe this client to the broadcast channel.
+    // 0. Nothing is sent until the client has authenticated.
+    if !authenticated && !auth::authenticate_socket(&mut socket, &state.api_token, state.ws_auth_timeout).await {
+        return;
+    }
+
+    // 1. Subscribe this client to the event bus.
     let mut event_rx = state.event_tx.subscribe();
+    // Clients get version 1 until a `ClientHello` asks for a newer one.
+    let mut version = events::DEFAULT_WS_PROTOCOL_VERSION;
 
     // 2. Send a test message to confirm connection
-    let test_msg = events::WsMessage::Connected;
-    let test_payload = serde_json::to_string(&test_msg).unwrap();
-    if socket.send(Message::Text(test_payload)).await.is_err() {
+    if !send_message(&mut socket, events::WsMessage::Connected, version).await {
         tracing::warn!("[WS] Failed to send test message to new client.");
         return; // Client disconnected immediately
     }
@@ -134,13 +612,19 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
     let initial_state = { // Scoped lock
         state.portfolio_state_cache.lock().await.clone()
     };
-    if let Some(portfolio_state) = initial_state {
-        let msg = events::WsMessage::PortfolioState(portfolio_state);
-        let payload = serde_json::to_string(&msg).unwrap();
-        if socket.send(Message::Text(payload)).await.is_err() {
-            tracing::warn!("[WS] Failed to send initial state to new client.");
-            return; // Client disconnected immediately
-        }
+    if let Some(portfolio_state) = initial_state
+        && !send_message(&mut socket, events::WsMessage::PortfolioState(portfolio_state), version).await
+    {
+        tracing::warn!("[WS] Failed to send initial state to new client.");
+        return; // Client disconnected immediately
+    }
+    // The rolling stats are only refreshed hourly, so a new client gets the latest right away.
+    let rolling_stats = state.rolling_stats.lock().await.clone();
+    if let Some(rolling_stats) = rolling_stats
+        && !send_message(&mut socket, events::WsMessage::RollingStats(rolling_stats), version).await
+    {
+        tracing::warn!("[WS] Failed to send the rolling stats to new client.");
+        return;
     }
 
     // 3. The main concurrent loop.
@@ -154,35 +638,32 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                     timestamp: chrono::Utc::now(),
                     level: events::LogLevel::Info,
                     message: "WebSocket heartbeat".to_string(),
+                    fields: None,
                 });
-                let payload = serde_json::to_string(&heartbeat_msg).unwrap();
-                if socket.send(Message::Text(payload)).await.is_err() {
+                if !send_message(&mut socket, heartbeat_msg, version).await {
                     tracing::error!("[WS] Failed to send heartbeat. Client may have disconnected.");
                     break;
                 }
                 tracing::debug!("[WS] Sent heartbeat to client");
             }
-            // A message was received from the broadcast channel (i.e., from the LiveEngine).
+            // A message was received from the event bus (i.e., from the LiveEngine). A slow
+            // client skips superseded state updates rather than falling behind on them.
             msg = event_rx.recv() => {
                 match msg {
                     Ok(msg) => {
                         tracing::info!("[WS] Received message from broadcast channel: {:?}", msg);
-                        let payload = serde_json::to_string(&msg).unwrap();
-                        tracing::debug!("[WS] Sending payload to client: {}", payload);
-                        match socket.send(Message::Text(payload)).await {
-                            Ok(_) => {
-                                tracing::debug!("[WS] Successfully sent message to client");
-                            }
-                            Err(e) => {
-                                tracing::error!("[WS] Failed to send message to client: {:?}", e);
-                                tracing::info!("[WS] Client disconnected. Breaking send loop.");
-                                break;
-                            }
+                        if send_message(&mut socket, msg, version).await {
+                            tracing::debug!("[WS] Successfully sent message to client");
+                        } else {
+                            tracing::info!("[WS] Client disconnected. Breaking send loop.");
+                            break;
                         }
                     }
-                    Err(e) => {
-                        tracing::error!("[WS] Broadcast channel error: {:?}", e);
-                        tracing::info!("[WS] Breaking send loop due to broadcast channel error.");
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("[WS] Client fell behind; skipped {} events.", skipped);
+                    }
+                    Err(RecvError::Closed) => {
+                        tracing::info!("[WS] Event bus closed. Breaking send loop.");
                         break;
                     }
                 }
@@ -199,13 +680,35 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
                         // The client is checking if we're alive.
                         // `axum` handles sending the `Pong` frame automatically.
                     }
+                    Message::Text(text) => {
+                        if let Ok(events::WsClientMessage::ClientHello { supported_version }) = serde_json::from_str(&text) {
+                            let Some(negotiated) = events::negotiate_version(supported_version) else {
+                                tracing::warn!("[WS] Closing a client that only supports protocol version {}.", supported_version);
+                                let reason = format!(
+                                    "protocol version {} is not supported; this server speaks {} to {}",
+                                    supported_version,
+                                    events::MIN_WS_PROTOCOL_VERSION,
+                                    events::WS_PROTOCOL_VERSION
+                                );
+                                let close = CloseFrame { code: close_code::PROTOCOL, reason: reason.into() };
+                                let _ = socket.send(Message::Close(Some(close))).await;
+                                break;
+                            };
+                            version = negotiated;
+                            tracing::info!("[WS] Client speaks protocol version {}.", version);
+                            // Confirms the version, in it.
+                            if !send_message(&mut socket, events::WsMessage::Connected, version).await {
+                                break;
+                            }
+                        }
+                    }
                     _ => {
                         // We don't process other messages from the client.
                     }
                 }
             }
             
-            // If either the broadcast channel lags or the client disconnects, we exit.
+            // If the client disconnects, we exit.
             else => {
                 break;
             }
@@ -213,4 +716,21 @@ async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
     }
 
     tracing::info!("[WS] Connection closed.");
+}
+
+/// Sends `message` to the client in protocol `version`. Returns false once the client is gone.
+async fn send_message(socket: &mut WebSocket, message: events::WsMessage, version: u32) -> bool {
+    let frame = match events::encode_frame(message, version) {
+        Ok(frame) => frame,
+        Err(e) => {
+            tracing::error!("[WS] Failed to encode a message: {}", e);
+            return true;
+        }
+    };
+    tracing::debug!("[WS] Sending payload to client: {}", frame);
+    if let Err(e) = socket.send(Message::Text(frame)).await {
+        tracing::error!("[WS] Failed to send message to client: {:?}", e);
+        return false;
+    }
+    true
 }
\ No newline at end of file
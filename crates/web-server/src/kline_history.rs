@@ -0,0 +1,104 @@
+//! The recent live klines of each symbol and interval, for dashboards joining mid-session.
+//!
+//! Klines are only broadcast as they close, so a client connecting later has no chart until
+//! the next one. The server keeps the last closed klines of every symbol and interval it has
+//! seen in a bounded ring buffer, filled from the event bus's uncoalesced kline channel, and
+//! serves them with the `seq` of the newest. A client subscribes to the WebSocket first, then fetches the snapshot, and applies
+//! the streamed klines numbered after `snapshot_seq`: each kline is then drawn exactly once.
+
+use core_types::Kline;
+use events::KlineData;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// The klines kept per symbol and interval when `server.kline_history_bars` is not set.
+pub const DEFAULT_KLINE_HISTORY_BARS: usize = 500;
+
+/// The buffered klines of one symbol and interval, oldest first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KlineSnapshot {
+    pub symbol: String,
+    /// `None` when no kline of the symbol has been broadcast yet and none was asked for.
+    pub interval: Option<String>,
+    /// The `seq` of the last of `klines`, or of the interval's latest kline if none is sent.
+    /// 0 when no kline of the symbol and interval has been broadcast yet.
+    pub snapshot_seq: u64,
+    /// Consecutive klines, the last numbered `snapshot_seq`.
+    pub klines: Vec<Kline>,
+}
+
+#[derive(Debug, Default)]
+struct SymbolHistory {
+    /// The `seq` of the newest kline received.
+    last_seq: u64,
+    klines: VecDeque<Kline>,
+}
+
+/// The last `capacity` closed klines of every symbol and interval, shared between the task
+/// filling it and the handlers reading it.
+#[derive(Debug)]
+pub struct KlineHistory {
+    capacity: usize,
+    /// By symbol and interval.
+    symbols: Mutex<HashMap<(String, String), SymbolHistory>>,
+}
+
+impl KlineHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, symbols: Mutex::new(HashMap::new()) }
+    }
+
+    /// Appends a broadcast kline to the history of its symbol and interval, evicting the
+    /// oldest beyond the capacity. The history only ever holds consecutive klines: a kline
+    /// numbered past the next one (the server fell behind the bus) or not after the last (the
+    /// engine restarted) starts that history over.
+    pub fn record(&self, data: &KlineData) {
+        let mut symbols = self.symbols.lock().unwrap();
+        let history = symbols.entry((data.symbol.clone(), data.interval.clone())).or_default();
+        if history.last_seq != 0 && data.seq != history.last_seq + 1 {
+            tracing::warn!(
+                symbol = %data.symbol,
+                interval = %data.interval,
+                last_seq = history.last_seq,
+                seq = data.seq,
+                "Kline sequence broke; restarting the kline history of the symbol and interval."
+            );
+            history.klines.clear();
+        }
+        history.last_seq = data.seq;
+        history.klines.push_back(data.kline.clone());
+        while history.klines.len() > self.capacity {
+            history.klines.pop_front();
+        }
+    }
+
+    /// The newest `limit` klines of `symbol` in `interval`, or all of them. An interval
+    /// without klines yet has an empty snapshot numbered 0, so a client can still join its
+    /// stream from the start.
+    pub fn snapshot(&self, symbol: &str, interval: &str, limit: Option<usize>) -> KlineSnapshot {
+        let symbols = self.symbols.lock().unwrap();
+        let (snapshot_seq, klines) = match symbols.get(&(symbol.to_string(), interval.to_string())) {
+            Some(history) => {
+                let skip = history.klines.len().saturating_sub(limit.unwrap_or(usize::MAX));
+                (history.last_seq, history.klines.iter().skip(skip).cloned().collect())
+            }
+            None => (0, Vec::new()),
+        };
+        KlineSnapshot { symbol: symbol.to_string(), interval: Some(interval.to_string()), snapshot_seq, klines }
+    }
+
+    /// The intervals `symbol` has a history in, sorted.
+    pub fn intervals(&self, symbol: &str) -> Vec<String> {
+        let symbols = self.symbols.lock().unwrap();
+        let mut intervals: Vec<String> =
+            symbols.keys().filter(|(s, _)| s == symbol).map(|(_, interval)| interval.clone()).collect();
+        intervals.sort();
+        intervals
+    }
+
+    /// The number of symbol and interval pairs with a history.
+    pub fn series_count(&self) -> usize {
+        self.symbols.lock().unwrap().len()
+    }
+}
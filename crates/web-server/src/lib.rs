@@ -1,88 +1,194 @@
 use axum::{
-    routing::get,
+    http::HeaderValue,
+    middleware,
+    routing::{delete, get, post},
     Router,
 };
+use configuration::Config;
 use database::DbRepository;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tokio::sync::broadcast;
-use events::WsMessage;
+use std::time::Duration;
+use events::{EventBus, WsMessage};
 use tower_http::{
-    cors::{Any, CorsLayer},
+    cors::{AllowOrigin, Any, CorsLayer},
     trace::TraceLayer, // <-- Import the TraceLayer
 };
 // Add Mutex for the cache
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 use events::PortfolioState; // We need this type for the cache
+use events::RollingStats;
+use events::EngineStats;
 
 // Note: Advanced tracing imports removed - using config-based tracing instead
 
 
 
+pub mod annotations;
+pub mod auth;
+pub mod backtests;
 pub mod error;
 pub mod handlers; // <-- ADD THIS
+pub mod kline_history;
+pub mod positions;
+pub mod rolling_stats;
+pub mod rollups;
+
+use auth::ApiToken;
+use backtests::BacktestRunner;
+use kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
 
 /// The shared application state that all handlers can access.
 #[derive(Clone)]
 pub struct AppState {
     pub db_repo: DbRepository,
-    pub event_tx: broadcast::Sender<WsMessage>,
+    pub event_tx: EventBus,
     /// Caches the most recent portfolio state for new clients.
     pub portfolio_state_cache: Arc<Mutex<Option<PortfolioState>>>,
+    /// The recent live klines of each symbol, for clients joining mid-session.
+    pub kline_history: Arc<KlineHistory>,
+    /// The live performance over trailing windows, as last computed.
+    pub rolling_stats: Arc<Mutex<Option<RollingStats>>>,
+    /// The token REST and WebSocket clients must present, if any.
+    pub api_token: ApiToken,
+    /// How long a WebSocket client has to send its `Auth` frame.
+    pub ws_auth_timeout: Duration,
+    /// The live engine's activity counters, when an engine runs in this process.
+    pub engine_stats: Option<Arc<EngineStats>>,
+    /// Starts the backtests submitted to `POST /api/backtest-runs`.
+    pub backtests: BacktestRunner,
 }
 
 
 
 
-/// The main function to configure and run the web server.
-pub async fn run_server(addr: SocketAddr, db_repo: DbRepository, event_tx: broadcast::Sender<WsMessage>) -> anyhow::Result<()> {
+/// The main function to configure and run the web server. Its settings come from the
+/// `[server]` section of `config`; backtests started through the API use the rest.
+pub async fn run_server(
+    addr: SocketAddr,
+    db_repo: DbRepository,
+    event_tx: EventBus,
+    config: Config,
+    engine_stats: Option<Arc<EngineStats>>,
+) -> anyhow::Result<()> {
     // Note: Tracing is already initialized in main.rs via config.toml
     // We don't need to initialize it again here to avoid conflicts
 
     // Create the cache for portfolio state
     let portfolio_state_cache = Arc::new(Mutex::new(None));
+    let kline_history = Arc::new(KlineHistory::new(config.server.kline_history_bars.unwrap_or(DEFAULT_KLINE_HISTORY_BARS)));
+    spawn_cache_task(&event_tx, Arc::clone(&portfolio_state_cache), Arc::clone(&kline_history));
+    
+    tokio::spawn(rollups::run_rollup_worker(db_repo.clone()));
+    let rolling_stats = Arc::new(Mutex::new(None));
+    tokio::spawn(rolling_stats::run_rolling_stats_worker(db_repo.clone(), event_tx.clone(), Arc::clone(&rolling_stats)));
+
+    // Create Shared State
+    let server_config = config.server.clone();
+    let app_state = Arc::new(AppState {
+        db_repo,
+        event_tx,
+        portfolio_state_cache,
+        kline_history,
+        rolling_stats,
+        api_token: ApiToken::new(server_config.api_token),
+        ws_auth_timeout: auth::WS_AUTH_TIMEOUT,
+        engine_stats,
+        backtests: BacktestRunner::new(config),
+    });
+    if !app_state.api_token.is_required() {
+        tracing::warn!("No `server.api_token` is configured; the API and WebSocket are open to anyone who can reach {}.", addr);
+    }
+    let app = router(app_state, &server_config.allowed_origins)?;
+
+    // Start the Server
+    tracing::info!("Web server starting and listening on http://{}", addr);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
 
+/// Keeps `portfolio_state_cache` and `kline_history` up to date with the messages sent on
+/// `event_tx` from now on. The history is fed from the bus's uncoalesced kline channel, so it
+/// sees every kline rather than only the latest of each symbol and interval.
+pub fn spawn_cache_task(event_tx: &EventBus, portfolio_state_cache: Arc<Mutex<Option<PortfolioState>>>, kline_history: Arc<KlineHistory>) {
     let mut rx = event_tx.subscribe();
-    let cache_clone = Arc::clone(&portfolio_state_cache);
     tokio::spawn(async move {
         while let Ok(msg) = rx.recv().await {
             if let WsMessage::PortfolioState(state) = msg {
-                let mut cache = cache_clone.lock().await;
-                *cache = Some(state);
+                *portfolio_state_cache.lock().await = Some(state);
             }
         }
     });
-    
-    // Create Shared State
-    let app_state = Arc::new(AppState {
-        db_repo,
-        event_tx,
-        portfolio_state_cache,
+    let mut klines = event_tx.subscribe_klines();
+    tokio::spawn(async move {
+        loop {
+            match klines.recv().await {
+                Ok(data) => kline_history.record(&data),
+                // The next kline's seq gap restarts the histories that lost klines.
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "The kline history fell behind the event bus.");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
     });
-    
-    // Define CORS
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+}
 
-    // Define the Application Routes
-    let app = Router::new()
-        .route("/api/health", get(|| async { "OK" }))
+/// Builds the application's routes. Everything but the health check and the WebSocket, which
+/// authenticates its own clients, requires the API token.
+pub fn router(app_state: Arc<AppState>, allowed_origins: &[String]) -> anyhow::Result<Router> {
+    let protected = Router::new()
         .route("/api/optimization-jobs", get(handlers::get_optimization_jobs))
         .route("/api/single-runs", get(handlers::get_single_runs))
         .route("/api/wfo-jobs", get(handlers::get_wfo_jobs))
         .route("/api/optimization-jobs/:job_id", get(handlers::get_optimization_job_details))
+        .route("/api/optimization-jobs/:job_id/progress", get(handlers::get_optimization_job_progress))
+        .route("/api/optimization-jobs/:job_id/config", get(handlers::get_optimization_job_config))
+        .route("/api/backtest-runs", post(handlers::create_backtest_run))
         .route("/api/backtest-runs/:run_id", get(handlers::get_backtest_run_details))
+        .route("/api/backtest-runs/:run_id/details", get(handlers::get_backtest_run_full_details))
+        .route("/api/backtest-runs/:run_id/status", get(handlers::get_backtest_run_status))
+        .route("/api/backtest-runs/:run_id/report.html", get(handlers::get_backtest_run_report))
+        .route("/api/backtest-runs/:run_id/klines", get(handlers::get_backtest_run_klines))
+        .route("/api/klines", get(handlers::get_klines))
+        .route("/api/compare", get(handlers::compare_backtest_runs))
+        .route("/api/audit/:decision_id", get(handlers::get_decision_audit))
+        .route("/api/engine/stats", get(handlers::get_engine_stats))
+        .route("/api/live/positions", get(handlers::get_live_positions))
+        .route("/api/live/performance", get(handlers::get_live_performance))
+        .route("/api/live/execution-quality", get(handlers::get_live_execution_quality))
+        .route("/api/live/rolling-stats", get(handlers::get_live_rolling_stats))
+        .route("/api/live/portfolio-events", get(handlers::get_live_portfolio_events))
+        .route("/api/live/klines/:symbol", get(handlers::get_live_klines))
+        .route("/api/annotations", get(handlers::get_annotations).post(handlers::create_annotation))
+        .route("/api/annotations/:annotation_id", delete(handlers::delete_annotation))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), auth::require_token));
+
+    Ok(Router::new()
+        .route("/api/health", get(|| async { "OK" }))
         .route("/ws", get(handlers::websocket_handler))
+        .merge(protected)
         .with_state(app_state)
-        .layer(cors)
-        .layer(TraceLayer::new_for_http());
-
-    // Start the Server
-    tracing::info!("Web server starting and listening on http://{}", addr);
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+        .layer(cors_layer(allowed_origins)?)
+        .layer(TraceLayer::new_for_http()))
+}
 
-    Ok(())
+/// Allows cross-origin requests from `allowed_origins`, or from anywhere if it is empty or
+/// contains "*".
+fn cors_layer(allowed_origins: &[String]) -> anyhow::Result<CorsLayer> {
+    let allow_origin = if allowed_origins.is_empty() || allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::from(Any)
+    } else {
+        let origins = allowed_origins
+            .iter()
+            .map(|origin| HeaderValue::from_str(origin).map_err(|_| anyhow::anyhow!("Invalid allowed origin: {:?}", origin)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        AllowOrigin::list(origins)
+    };
+    Ok(CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods(Any)
+        .allow_headers(Any))
 }
\ No newline at end of file
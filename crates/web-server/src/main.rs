@@ -1,16 +1,17 @@
-use tokio::sync::broadcast;
+use events::EventBus;
 use std::net::SocketAddr;
 use database::{connect, run_migrations, DbRepository};
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    // When run directly, it creates its own db connection and a broadcast channel.
+    // When run directly, it creates its own db connection and event bus.
     dotenvy::dotenv().ok();
+    let config = configuration::load_config(None)?;
     let db_pool = connect().await?;
     run_migrations(&db_pool).await?;
     let db_repo = DbRepository::new(db_pool);
-    let (event_tx, _) = broadcast::channel(1024);
+    let event_tx = EventBus::new(events::EVENT_CHANNEL_CAPACITY);
 
     let addr = SocketAddr::from(([0, 0, 0, 0], 8080));
-    web_server::run_server(addr, db_repo, event_tx).await
+    web_server::run_server(addr, db_repo, event_tx, config, None).await
 }
\ No newline at end of file
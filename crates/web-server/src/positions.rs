@@ -0,0 +1,69 @@
+//! The live position history.
+//!
+//! Positions are rebuilt from the executions the live engine records. Open positions are
+//! then brought up to date with the portfolio the engine last broadcast, so their numbers
+//! agree with the WebSocket feed.
+
+use database::{LivePosition, LivePositionFilter, LivePositionStatus};
+use events::PortfolioState;
+use rust_decimal::Decimal;
+
+/// Merges the `recorded` positions with the portfolio `snapshot` the engine last broadcast,
+/// keeping the positions that match `filter`.
+///
+/// With a snapshot, the open positions are exactly the snapshot's. Their size, average entry
+/// price and unrealized P&L come from the snapshot, and their entry time, fees, realized P&L
+/// and largest size from the matching recorded position, if there is one. Recorded positions
+/// still open but missing from the snapshot were left open by an earlier session and are
+/// dropped. Without a snapshot, the recorded positions are kept as they are.
+pub fn blend_open_positions(
+    recorded: Vec<LivePosition>,
+    snapshot: Option<&PortfolioState>,
+    filter: &LivePositionFilter,
+) -> Vec<LivePosition> {
+    let Some(snapshot) = snapshot else {
+        return recorded.into_iter().filter(|position| matches(position, filter)).collect();
+    };
+
+    let (mut open, closed): (Vec<LivePosition>, Vec<LivePosition>) =
+        recorded.into_iter().partition(|position| position.status == LivePositionStatus::Open);
+    let mut blended: Vec<LivePosition> = snapshot
+        .positions
+        .iter()
+        .map(|current| {
+            let history = open
+                .iter()
+                .position(|position| position.position_id == current.position_id)
+                .map(|index| open.swap_remove(index));
+            LivePosition {
+                position_id: current.position_id,
+                symbol: current.symbol.clone(),
+                side: current.side,
+                status: LivePositionStatus::Open,
+                entry_time: history.as_ref().and_then(|history| history.entry_time),
+                exit_time: None,
+                avg_entry_price: Some(current.entry_price),
+                avg_exit_price: history.as_ref().and_then(|history| history.avg_exit_price),
+                quantity: current.quantity,
+                max_quantity: history.as_ref().map_or(current.quantity, |history| history.max_quantity.max(current.quantity)),
+                realized_pnl: history.as_ref().map_or(Decimal::ZERO, |history| history.realized_pnl),
+                fees: history.as_ref().map_or(Decimal::ZERO, |history| history.fees),
+                unrealized_pnl: Some(current.unrealized_pnl),
+                close_reason: None,
+            }
+        })
+        .collect();
+    // Open positions first, most recently opened first; those without a recorded entry are
+    // the oldest.
+    blended.sort_by_key(|position| std::cmp::Reverse(position.entry_time));
+    blended.extend(closed);
+    blended.retain(|position| matches(position, filter));
+    blended
+}
+
+fn matches(position: &LivePosition, filter: &LivePositionFilter) -> bool {
+    filter.status.is_none_or(|status| position.status == status)
+        && filter.symbol.as_ref().is_none_or(|symbol| &position.symbol == symbol)
+        && filter.from.is_none_or(|from| position.entry_time.is_some_and(|entry| entry >= from))
+        && filter.to.is_none_or(|to| position.entry_time.is_some_and(|entry| entry <= to))
+}
@@ -0,0 +1,37 @@
+//! The background worker that keeps the live dashboard's rolling-window performance current.
+
+use chrono::{DateTime, Utc};
+use database::DbRepository;
+use events::{EventBus, RollingStats, WsMessage};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+/// How often the rolling stats are recomputed.
+pub const ROLLING_STATS_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+/// Refreshes the rolling stats on start and every `ROLLING_STATS_PERIOD` after.
+pub async fn run_rolling_stats_worker(db_repo: DbRepository, event_tx: EventBus, cache: Arc<Mutex<Option<RollingStats>>>) {
+    let mut timer = tokio::time::interval(ROLLING_STATS_PERIOD);
+    loop {
+        timer.tick().await;
+        refresh(&db_repo, &event_tx, &cache, Utc::now()).await;
+    }
+}
+
+/// Computes the rolling stats of the windows ending at `now`, caches them for
+/// `GET /api/live/rolling-stats` and new WebSocket clients, and broadcasts them. A failure is
+/// logged and leaves the last stats cached.
+pub async fn refresh(db_repo: &DbRepository, event_tx: &EventBus, cache: &Mutex<Option<RollingStats>>, now: DateTime<Utc>) -> Option<RollingStats> {
+    match analyzer::rolling::compute_rolling_stats(db_repo, now).await {
+        Ok(stats) => {
+            *cache.lock().await = Some(stats.clone());
+            event_tx.send(WsMessage::RollingStats(stats.clone()));
+            Some(stats)
+        }
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to compute the rolling live stats.");
+            None
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! The background worker that rolls up live performance at each UTC midnight.
+
+use chrono::{Days, NaiveDate, Utc};
+use database::DbRepository;
+
+/// Rolls up each day that ends, and the week it belongs to, shortly after UTC midnight.
+///
+/// The last completed day is rolled up on start as well, in case the server was down at
+/// midnight. Rolling up is idempotent, so doing it again is harmless. Failures are logged
+/// and the day is retried at the next start or with `rollup --recompute`.
+pub async fn run_rollup_worker(db_repo: DbRepository) {
+    let mut day = Utc::now().date_naive() - Days::new(1);
+    loop {
+        roll_up(&db_repo, day).await;
+
+        let now = Utc::now();
+        let next_midnight = (now.date_naive() + Days::new(1)).and_time(Default::default()).and_utc();
+        tokio::time::sleep((next_midnight - now).to_std().unwrap_or_default()).await;
+        day = next_midnight.date_naive() - Days::new(1);
+    }
+}
+
+async fn roll_up(db_repo: &DbRepository, day: NaiveDate) {
+    match analyzer::rollup::roll_up_day(db_repo, day).await {
+        Ok(()) => tracing::info!("Rolled up live performance for {}.", day),
+        Err(e) => tracing::warn!(day = %day, error = %e, "Failed to roll up live performance."),
+    }
+}
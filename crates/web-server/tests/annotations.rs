@@ -0,0 +1,156 @@
+//! Attaches notes to backtest trades and runs through `/api/annotations`, and reads them back
+//! with the run's details.
+//!
+//! The tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p web-server -- --ignored
+//! ```
+
+use chrono::Duration;
+use core_types::{CloseReason, Execution, OrderSide, Trade};
+use database::{Annotation, DbRepository};
+use events::EventBus;
+use reqwest::StatusCode;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use testing::{seed_start, test_config, TestDatabase, TEST_SYMBOL};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use web_server::annotations::AnnotatedRunDetails;
+use web_server::auth::ApiToken;
+use web_server::backtests::BacktestRunner;
+use web_server::kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
+use web_server::{router, AppState};
+
+async fn serve(db_repo: DbRepository) -> SocketAddr {
+    let state = Arc::new(AppState {
+        db_repo,
+        event_tx: EventBus::new(64),
+        portfolio_state_cache: Arc::new(Mutex::new(None)),
+        kline_history: Arc::new(KlineHistory::new(DEFAULT_KLINE_HISTORY_BARS)),
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(None),
+        ws_auth_timeout: std::time::Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(test_config(10).unwrap()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(state, &[]).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+/// A long bought at 100 `hour` hours into the seeded series and sold at 100 + `pnl` an hour later.
+fn trade(hour: i64, pnl: i64) -> Trade {
+    let execution = |side, price: i64, hours| Execution {
+        execution_id: Uuid::new_v4(),
+        client_order_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        side,
+        price: Decimal::from(price),
+        quantity: Decimal::ONE,
+        fee: Decimal::ZERO,
+        fee_asset: "USDT".to_string(),
+        timestamp: seed_start() + Duration::hours(hours),
+        decision_id: None,
+        is_maker: false,
+        spread_cost: Decimal::ZERO,
+        placement: None,
+    };
+    Trade {
+        trade_id: Uuid::new_v4(),
+        symbol: TEST_SYMBOL.to_string(),
+        entry_execution: execution(OrderSide::Buy, 100, hour),
+        exit_execution: execution(OrderSide::Sell, 100 + pnl, hour + 1),
+        close_reason: CloseReason::Signal,
+        initial_risk: None,
+    }
+}
+
+/// A completed run holding `trades`.
+async fn seed_run(repo: &DbRepository, trades: &[Trade]) -> Uuid {
+    let job_id = Uuid::new_v4();
+    let run_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &json!({}), "Completed").await.unwrap();
+    repo.save_performance_report(run_id, &analytics::PerformanceReport::new()).await.unwrap();
+    repo.save_trades(run_id, trades).await.unwrap();
+    run_id
+}
+
+async fn annotate(addr: SocketAddr, body: serde_json::Value) -> reqwest::Response {
+    reqwest::Client::new().post(format!("http://{}/api/annotations", addr)).json(&body).send().await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn annotations_are_created_listed_by_tag_and_deleted() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let trades = [trade(0, 5), trade(2, -3)];
+    seed_run(&repo, &trades).await;
+    let addr = serve(repo).await;
+
+    let response = annotate(
+        addr,
+        json!({ "target_type": "trade", "target_id": trades[0].trade_id, "author": "ana", "text": " Entered during the CPI print ", "tags": ["news-event", "cpi", "news-event"] }),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: Annotation = response.json().await.unwrap();
+    assert_eq!(created.text, "Entered during the CPI print");
+    assert_eq!(created.tags, ["news-event", "cpi"]);
+    let response = annotate(addr, json!({ "target_type": "trade", "target_id": trades[1].trade_id, "author": "ana", "text": "Slippage was terrible" })).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let tagged: Vec<Annotation> = reqwest::get(format!("http://{}/api/annotations?tag=news-event", addr)).await.unwrap().json().await.unwrap();
+    assert_eq!(tagged, std::slice::from_ref(&created));
+    let all: Vec<Annotation> = reqwest::get(format!("http://{}/api/annotations?target_type=trade", addr)).await.unwrap().json().await.unwrap();
+    assert_eq!(all.len(), 2);
+
+    let unknown = annotate(addr, json!({ "target_type": "run", "target_id": Uuid::new_v4(), "author": "ana", "text": "Nothing here" })).await;
+    assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+    let blank = annotate(addr, json!({ "target_type": "trade", "target_id": trades[0].trade_id, "author": "ana", "text": "  " })).await;
+    assert_eq!(blank.status(), StatusCode::BAD_REQUEST);
+
+    let client = reqwest::Client::new();
+    let deleted = client.delete(format!("http://{}/api/annotations/{}", addr, created.annotation_id)).send().await.unwrap();
+    assert_eq!(deleted.status(), StatusCode::NO_CONTENT);
+    let again = client.delete(format!("http://{}/api/annotations/{}", addr, created.annotation_id)).send().await.unwrap();
+    assert_eq!(again.status(), StatusCode::NOT_FOUND);
+    let tagged: Vec<Annotation> = reqwest::get(format!("http://{}/api/annotations?tag=news-event", addr)).await.unwrap().json().await.unwrap();
+    assert!(tagged.is_empty());
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn run_details_carry_the_notes_of_the_run_and_each_trade() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let trades = [trade(0, 5), trade(2, -3)];
+    let run_id = seed_run(&repo, &trades).await;
+    let addr = serve(repo).await;
+
+    for text in ["First look", "Second look"] {
+        annotate(addr, json!({ "target_type": "trade", "target_id": trades[1].trade_id, "author": "ana", "text": text })).await;
+    }
+    annotate(addr, json!({ "target_type": "run", "target_id": run_id, "author": "ben", "text": "Baseline run" })).await;
+
+    let details: AnnotatedRunDetails =
+        reqwest::get(format!("http://{}/api/backtest-runs/{}/details", addr, run_id)).await.unwrap().json().await.unwrap();
+    assert_eq!(details.trades.len(), 2);
+    assert_eq!(details.trades[0].trade.trade_id, trades[0].trade_id);
+    assert!(details.trades[0].notes.is_empty());
+    let notes: Vec<&str> = details.trades[1].notes.iter().map(|note| note.text.as_str()).collect();
+    assert_eq!(notes, ["First look", "Second look"]);
+    assert_eq!(details.notes.len(), 1);
+    assert_eq!(details.notes[0].author, "ben");
+
+    db.teardown().await.expect("drop test database");
+}
@@ -0,0 +1,132 @@
+//! Checks the API token on REST requests and the WebSocket authentication handshake.
+
+use axum::{middleware, routing::get, Router};
+use database::DbRepository;
+use events::{EventBus, WsClientMessage, WsMessage};
+use reqwest::StatusCode;
+use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite;
+use web_server::auth::{require_token, ApiToken};
+use web_server::backtests::BacktestRunner;
+use web_server::kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
+use testing::WsTestClient;
+use web_server::{router, AppState};
+
+const TOKEN: &str = "s3cret";
+
+fn state(token: Option<&str>) -> Arc<AppState> {
+    Arc::new(AppState {
+        db_repo: DbRepository::new(PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap()),
+        event_tx: EventBus::new(16),
+        portfolio_state_cache: Arc::new(Mutex::new(None)),
+        kline_history: Arc::new(KlineHistory::new(DEFAULT_KLINE_HISTORY_BARS)),
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(token.map(str::to_string)),
+        ws_auth_timeout: Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(testing::test_config(100).unwrap()),
+    })
+}
+
+async fn serve(app: Router) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+async fn get_status(url: String, token: Option<&str>) -> StatusCode {
+    let mut request = reqwest::Client::new().get(url);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    request.send().await.unwrap().status()
+}
+
+#[tokio::test]
+async fn middleware_requires_the_bearer_token() {
+    let state = state(Some(TOKEN));
+    let app = Router::new()
+        .route("/secret", get(|| async { "secret" }))
+        .route_layer(middleware::from_fn_with_state(state.clone(), require_token))
+        .with_state(state);
+    let url = format!("http://{}/secret", serve(app).await);
+
+    assert_eq!(get_status(url.clone(), Some(TOKEN)).await, StatusCode::OK);
+    assert_eq!(get_status(url.clone(), None).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(get_status(url.clone(), Some("wrong")).await, StatusCode::UNAUTHORIZED);
+    assert_eq!(get_status(url, Some("s3cre")).await, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn api_routes_are_protected_but_health_is_public() {
+    let addr = serve(router(state(Some(TOKEN)), &[]).unwrap()).await;
+
+    assert_eq!(get_status(format!("http://{}/api/health", addr), None).await, StatusCode::OK);
+    assert_eq!(get_status(format!("http://{}/api/compare?runs=x", addr), None).await, StatusCode::UNAUTHORIZED);
+    // With the token, the request reaches the handler, which rejects the malformed run IDs.
+    assert_eq!(get_status(format!("http://{}/api/compare?runs=x", addr), Some(TOKEN)).await, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn without_a_configured_token_everything_is_open() {
+    let addr = serve(router(state(None), &[]).unwrap()).await;
+    assert_eq!(get_status(format!("http://{}/api/compare?runs=x", addr), None).await, StatusCode::BAD_REQUEST);
+
+    let mut client = WsTestClient::connect(&format!("ws://{}/ws", addr)).await.unwrap();
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+}
+
+fn auth(token: &str) -> WsClientMessage {
+    WsClientMessage::Auth { token: token.to_string() }
+}
+
+/// Whether the server closed `client`'s socket for failing to authenticate.
+fn closed_unauthenticated(client: &WsTestClient) -> bool {
+    client.close_frame().map(|frame| frame.code) == Some(CloseCode::Policy)
+}
+
+#[tokio::test]
+async fn websocket_accepts_the_token_as_a_query_parameter() {
+    let addr = serve(router(state(Some(TOKEN)), &[]).unwrap()).await;
+
+    let mut client = WsTestClient::connect(&format!("ws://{}/ws?token={}", addr, TOKEN)).await.unwrap();
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+
+    match WsTestClient::connect(&format!("ws://{}/ws?token=wrong", addr)).await {
+        Err(tungstenite::Error::Http(response)) => assert_eq!(response.status(), 401),
+        Err(other) => panic!("expected the upgrade to be refused, got {:?}", other),
+        Ok(_) => panic!("expected the upgrade to be refused"),
+    }
+}
+
+#[tokio::test]
+async fn websocket_accepts_the_token_as_a_first_message() {
+    let addr = serve(router(state(Some(TOKEN)), &[]).unwrap()).await;
+
+    let mut client = WsTestClient::connect(&format!("ws://{}/ws", addr)).await.unwrap();
+    client.send(&auth(TOKEN)).await;
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+}
+
+#[tokio::test]
+async fn unauthenticated_websockets_are_closed_before_any_data() {
+    let addr = serve(router(state(Some(TOKEN)), &[]).unwrap()).await;
+
+    // A wrong token in the auth frame.
+    let mut client = WsTestClient::connect(&format!("ws://{}/ws", addr)).await.unwrap();
+    client.send(&auth("wrong")).await;
+    assert_eq!(client.next_message().await, None);
+    assert!(closed_unauthenticated(&client));
+
+    // No auth frame within the timeout.
+    let mut client = WsTestClient::connect(&format!("ws://{}/ws", addr)).await.unwrap();
+    let closed = tokio::time::timeout(Duration::from_secs(5), client.next_message()).await;
+    assert_eq!(closed.expect("the server closes the socket"), None);
+    assert!(closed_unauthenticated(&client));
+}
@@ -0,0 +1,122 @@
+//! Starts backtests through `POST /api/backtest-runs` and follows them to completion.
+//!
+//! The end-to-end test is ignored by default. Run it with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p web-server -- --ignored
+//! ```
+
+use database::{DbRepository, FullReport};
+use events::{EventBus, WsMessage};
+use reqwest::StatusCode;
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use testing::{generate_klines, seed_klines, seed_start, test_config, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+use tokio::sync::Mutex;
+use web_server::auth::ApiToken;
+use web_server::backtests::{BacktestRunCreated, BacktestRunner};
+use web_server::handlers::BacktestRunStatus;
+use web_server::kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
+use web_server::{router, AppState};
+
+const BARS: usize = 6000;
+
+async fn serve(db_repo: DbRepository, event_tx: EventBus) -> SocketAddr {
+    let state = Arc::new(AppState {
+        db_repo,
+        event_tx,
+        portfolio_state_cache: Arc::new(Mutex::new(None)),
+        kline_history: Arc::new(KlineHistory::new(DEFAULT_KLINE_HISTORY_BARS)),
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(None),
+        ws_auth_timeout: Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(test_config(BARS).unwrap()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(state, &[]).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+/// A run of the MA crossover over the whole seeded series.
+fn new_run() -> serde_json::Value {
+    json!({
+        "strategy_id": "MACrossover",
+        "params": { "ma_fast_period": 10, "ma_slow_period": 40 },
+        "symbol": TEST_SYMBOL,
+        "interval": TEST_INTERVAL,
+        "from": seed_start(),
+        "to": seed_start() + chrono::Duration::hours(BARS as i64 - 1),
+        "initial_capital": "10000",
+    })
+}
+
+async fn post(addr: SocketAddr, body: &serde_json::Value) -> reqwest::Response {
+    reqwest::Client::new().post(format!("http://{}/api/backtest-runs", addr)).json(body).send().await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn a_submitted_run_completes_and_reports_its_progress() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    seed_klines(&repo, TEST_SYMBOL, &generate_klines(BARS)).await.expect("seed klines");
+    let event_tx = EventBus::new(64);
+    let mut event_rx = event_tx.subscribe();
+    let addr = serve(repo, event_tx).await;
+
+    let response = post(addr, &new_run()).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let BacktestRunCreated { run_id } = response.json().await.unwrap();
+
+    let mut status = String::new();
+    for _ in 0..300 {
+        let response = reqwest::get(format!("http://{}/api/backtest-runs/{}/status", addr, run_id)).await.unwrap();
+        status = response.json::<BacktestRunStatus>().await.unwrap().status;
+        if status == "Completed" || status == "Failed" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(status, "Completed");
+
+    let report: FullReport = reqwest::get(format!("http://{}/api/backtest-runs/{}", addr, run_id)).await.unwrap().json().await.unwrap();
+    assert_eq!(report.run_id, run_id);
+    assert!(report.total_trades.unwrap_or_default() > 0);
+    assert_eq!(report.parameters["ma_fast_period"], 10);
+    assert_eq!(report.bars_processed, Some(BARS as i64));
+
+    // One report every 5000 bars, and one when the simulation is done.
+    let mut progress = Vec::new();
+    while let Ok(WsMessage::BacktestProgress(update)) = event_rx.try_recv() {
+        progress.push((update.run_id, update.bars_done, update.pct));
+    }
+    assert_eq!(progress, [(run_id, 5000, 5000.0 * 100.0 / BARS as f64), (run_id, BARS as u64, 100.0)]);
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test]
+async fn invalid_submissions_are_rejected_before_anything_is_recorded() {
+    // The database is never reached.
+    let repo = DbRepository::new(PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap());
+    let addr = serve(repo, EventBus::new(16)).await;
+
+    let mut reversed = new_run();
+    reversed["to"] = json!(seed_start() - chrono::Duration::days(1));
+    let mut unknown_param = new_run();
+    unknown_param["params"]["ma_fastest_period"] = json!(3);
+    let mut no_capital = new_run();
+    no_capital["initial_capital"] = json!("0");
+    let mut bad_interval = new_run();
+    bad_interval["interval"] = json!("1x");
+
+    for body in [reversed, unknown_param, no_capital, bad_interval] {
+        assert_eq!(post(addr, &body).await.status(), StatusCode::BAD_REQUEST, "{}", body);
+    }
+}
@@ -0,0 +1,136 @@
+//! Tests of the klines endpoints against a real PostgreSQL database.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p web-server -- --ignored
+//! ```
+
+use chrono::Utc;
+use database::{DbRepository, RunMetadata};
+use events::EventBus;
+use reqwest::StatusCode;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use testing::{generate_klines, seed_klines, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use web_server::auth::ApiToken;
+use web_server::backtests::BacktestRunner;
+use web_server::kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
+use web_server::handlers::KlineSeries;
+use web_server::{router, AppState};
+
+const BARS: usize = 1000;
+
+async fn serve(db_repo: DbRepository) -> SocketAddr {
+    let state = Arc::new(AppState {
+        db_repo,
+        event_tx: EventBus::new(16),
+        portfolio_state_cache: Arc::new(Mutex::new(None)),
+        kline_history: Arc::new(KlineHistory::new(DEFAULT_KLINE_HISTORY_BARS)),
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(None),
+        ws_auth_timeout: Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(testing::test_config(BARS).unwrap()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(state, &[]).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+async fn get(url: String) -> reqwest::Response {
+    reqwest::get(url).await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn run_klines_are_aggregated_into_buckets() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let klines = generate_klines(BARS);
+    seed_klines(&repo, TEST_SYMBOL, &klines).await.expect("seed klines");
+
+    let (job_id, run_id) = (Uuid::new_v4(), Uuid::new_v4());
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &serde_json::json!({}), "Completed").await.unwrap();
+    let metadata = RunMetadata {
+        started_at: Utc::now(),
+        finished_at: Utc::now(),
+        bars_processed: BARS as i64,
+        signals_filtered: 0,
+        brackets_stop_first: 0,
+        brackets_target_first: 0,
+        engine_version: "test".to_string(),
+        config_hash: "test".to_string(),
+        interval: TEST_INTERVAL.to_string(),
+        data_start: Some(klines[0].open_time),
+        data_end: Some(klines[BARS - 1].close_time),
+    };
+    repo.save_run_metadata(run_id, &metadata).await.unwrap();
+
+    let addr = serve(repo).await;
+    let response = get(format!("http://{}/api/backtest-runs/{}/klines?max_points=300", addr, run_id)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let series: KlineSeries = response.json().await.unwrap();
+
+    assert_eq!((series.symbol.as_str(), series.interval.as_str()), (TEST_SYMBOL, TEST_INTERVAL));
+    assert_eq!(series.bucket_size, 4);
+    assert_eq!(series.candles.len(), BARS / 4);
+    assert_eq!(series.columns, ["open_time", "open", "high", "low", "close", "volume", "close_time"]);
+
+    let (open_time, open, high, low, close, volume, close_time) = series.candles[1];
+    let bucket = &klines[4..8];
+    assert_eq!(open_time, bucket[0].open_time.timestamp_millis());
+    assert_eq!(open, bucket[0].open);
+    assert_eq!(high, bucket.iter().map(|k| k.high).max().unwrap());
+    assert_eq!(low, bucket.iter().map(|k| k.low).min().unwrap());
+    assert_eq!(close, bucket[3].close);
+    assert_eq!(volume, bucket.iter().map(|k| k.volume).sum());
+    assert_eq!(close_time, bucket[3].close_time.timestamp_millis());
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn klines_by_range_and_their_errors() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let klines = generate_klines(BARS);
+    seed_klines(&repo, TEST_SYMBOL, &klines).await.expect("seed klines");
+    let addr = serve(repo.clone()).await;
+
+    let range = |from: usize, to: usize| {
+        format!(
+            "http://{}/api/klines?symbol={}&interval={}&from={}&to={}",
+            addr,
+            TEST_SYMBOL,
+            TEST_INTERVAL,
+            klines[from].open_time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            klines[to].open_time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        )
+    };
+
+    // Within the default limit, every kline comes back unmerged.
+    let series: KlineSeries = get(range(10, 109)).await.json().await.unwrap();
+    assert_eq!(series.bucket_size, 1);
+    assert_eq!(series.candles.len(), 100);
+    assert_eq!(series.candles[0].2, klines[10].high);
+
+    assert_eq!(get(range(109, 10)).await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(get(format!("{}&max_points=0", range(10, 109))).await.status(), StatusCode::BAD_REQUEST);
+
+    // A run that never recorded its range has no klines to show.
+    let (job_id, run_id) = (Uuid::new_v4(), Uuid::new_v4());
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(run_id, job_id, &serde_json::json!({}), "Pending").await.unwrap();
+    let url = format!("http://{}/api/backtest-runs/{}/klines", addr, run_id);
+    assert_eq!(get(url).await.status(), StatusCode::NOT_FOUND);
+
+    db.teardown().await.expect("drop test database");
+}
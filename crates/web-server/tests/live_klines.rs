@@ -0,0 +1,237 @@
+//! The live kline history: its eviction, and the handoff from a snapshot of it to the
+//! WebSocket stream for a client joining mid-session.
+
+use core_types::Kline;
+use database::DbRepository;
+use events::{EventBus, KlineData, WsMessage};
+use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use testing::{generate_klines, WsTestClient, TEST_INTERVAL, TEST_SYMBOL};
+use tokio::sync::Mutex;
+use web_server::auth::ApiToken;
+use web_server::backtests::BacktestRunner;
+use web_server::kline_history::{KlineHistory, KlineSnapshot};
+use web_server::{router, spawn_cache_task, AppState};
+
+/// The broadcast of `klines[seq - 1]` for `symbol`.
+fn broadcast(symbol: &str, klines: &[Kline], seq: u64) -> KlineData {
+    broadcast_in(symbol, TEST_INTERVAL, klines, seq)
+}
+
+/// The broadcast of `klines[seq - 1]` for `symbol` traded in `interval`.
+fn broadcast_in(symbol: &str, interval: &str, klines: &[Kline], seq: u64) -> KlineData {
+    KlineData { symbol: symbol.to_string(), interval: interval.to_string(), kline: klines[seq as usize - 1].clone(), seq }
+}
+
+/// Joins `snapshot` with the klines streamed since the client subscribed, as a dashboard
+/// does: the streamed klines up to `snapshot_seq` are already in the snapshot, and the rest
+/// must follow it one by one. Returns the number expected where a kline was missed.
+fn join(snapshot: &KlineSnapshot, streamed: &[KlineData]) -> Result<Vec<Kline>, u64> {
+    let mut chart = snapshot.klines.clone();
+    let after_snapshot = streamed.iter().filter(|data| data.seq > snapshot.snapshot_seq);
+    for (expected, data) in (snapshot.snapshot_seq + 1..).zip(after_snapshot) {
+        if data.seq != expected {
+            return Err(expected);
+        }
+        chart.push(data.kline.clone());
+    }
+    Ok(chart)
+}
+
+#[test]
+fn each_symbol_keeps_its_newest_klines() {
+    let klines = generate_klines(10);
+    let history = KlineHistory::new(3);
+    for seq in 1..=5 {
+        history.record(&broadcast(TEST_SYMBOL, &klines, seq));
+    }
+    // A symbol first seen after others gets a history of its own.
+    for seq in 1..=2 {
+        history.record(&broadcast("ETHUSDT", &klines, seq));
+    }
+
+    let btc = history.snapshot(TEST_SYMBOL, TEST_INTERVAL, None);
+    assert_eq!((btc.snapshot_seq, btc.klines.as_slice()), (5, &klines[2..5]));
+    assert_eq!(history.snapshot(TEST_SYMBOL, TEST_INTERVAL, Some(2)).klines, &klines[3..5]);
+    let latest_only = history.snapshot(TEST_SYMBOL, TEST_INTERVAL, Some(0));
+    assert_eq!((latest_only.snapshot_seq, latest_only.klines.len()), (5, 0));
+    assert_eq!(history.snapshot("ETHUSDT", TEST_INTERVAL, None).klines, &klines[0..2]);
+    assert_eq!(history.series_count(), 2);
+
+    let unseen = history.snapshot("SOLUSDT", TEST_INTERVAL, None);
+    assert_eq!((unseen.snapshot_seq, unseen.klines.len()), (0, 0));
+}
+
+#[test]
+fn each_interval_of_a_symbol_keeps_a_history_of_its_own() {
+    let klines = generate_klines(10);
+    let history = KlineHistory::new(10);
+    // Two bots trade the symbol, their klines interleaved and each numbered on its own.
+    for seq in 1..=4 {
+        history.record(&broadcast_in(TEST_SYMBOL, "1h", &klines, seq));
+        history.record(&broadcast_in(TEST_SYMBOL, "4h", &klines, seq + 5));
+    }
+    history.record(&broadcast_in(TEST_SYMBOL, "1h", &klines, 5));
+
+    let hourly = history.snapshot(TEST_SYMBOL, "1h", None);
+    assert_eq!((hourly.snapshot_seq, hourly.klines.as_slice()), (5, &klines[0..5]));
+    let four_hourly = history.snapshot(TEST_SYMBOL, "4h", None);
+    assert_eq!((four_hourly.snapshot_seq, four_hourly.klines.as_slice()), (9, &klines[5..9]));
+    assert_eq!(history.intervals(TEST_SYMBOL), ["1h", "4h"]);
+    assert_eq!(history.series_count(), 2);
+}
+
+#[test]
+fn a_broken_sequence_starts_the_history_over() {
+    let klines = generate_klines(10);
+    let history = KlineHistory::new(10);
+    for seq in [1, 2, 3, 5] {
+        history.record(&broadcast(TEST_SYMBOL, &klines, seq));
+    }
+    let snapshot = history.snapshot(TEST_SYMBOL, TEST_INTERVAL, None);
+    assert_eq!((snapshot.snapshot_seq, snapshot.klines.as_slice()), (5, &klines[4..5]));
+
+    // The engine restarted and numbers from 1 again.
+    history.record(&broadcast(TEST_SYMBOL, &klines, 1));
+    let snapshot = history.snapshot(TEST_SYMBOL, TEST_INTERVAL, None);
+    assert_eq!((snapshot.snapshot_seq, snapshot.klines.as_slice()), (1, &klines[0..1]));
+}
+
+#[test]
+fn every_snapshot_hands_off_to_the_stream_without_gaps_or_duplicates() {
+    const BARS: u64 = 30;
+    let klines = generate_klines(BARS as usize);
+    // A client subscribes after `subscribed` klines and takes its snapshot after `taken`.
+    for capacity in [5, 50] {
+        for subscribed in 0..=BARS {
+            for taken in subscribed..=BARS {
+                let history = KlineHistory::new(capacity);
+                (1..=taken).for_each(|seq| history.record(&broadcast(TEST_SYMBOL, &klines, seq)));
+                let snapshot = history.snapshot(TEST_SYMBOL, TEST_INTERVAL, None);
+                let streamed: Vec<KlineData> = (subscribed + 1..=BARS).map(|seq| broadcast(TEST_SYMBOL, &klines, seq)).collect();
+
+                let chart = join(&snapshot, &streamed).unwrap();
+                let first = (taken as usize).saturating_sub(capacity);
+                assert_eq!(chart, &klines[first..], "capacity {}, subscribed {}, taken {}", capacity, subscribed, taken);
+            }
+        }
+    }
+
+    // A stream that skipped a kline is caught rather than drawn with a hole.
+    let history = KlineHistory::new(50);
+    (1..=10).for_each(|seq| history.record(&broadcast(TEST_SYMBOL, &klines, seq)));
+    let streamed = [broadcast(TEST_SYMBOL, &klines, 11), broadcast(TEST_SYMBOL, &klines, 13)];
+    assert_eq!(join(&history.snapshot(TEST_SYMBOL, TEST_INTERVAL, None), &streamed), Err(12));
+}
+
+/// Serves an open server whose kline history is filled from the returned bus.
+async fn serve(history: Arc<KlineHistory>) -> (SocketAddr, EventBus) {
+    let event_tx = EventBus::new(16);
+    let portfolio_state_cache = Arc::new(Mutex::new(None));
+    spawn_cache_task(&event_tx, Arc::clone(&portfolio_state_cache), Arc::clone(&history));
+    let state = Arc::new(AppState {
+        db_repo: DbRepository::new(PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap()),
+        event_tx: event_tx.clone(),
+        portfolio_state_cache,
+        kline_history: history,
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(None),
+        ws_auth_timeout: Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(testing::test_config(100).unwrap()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(state, &[]).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (addr, event_tx)
+}
+
+/// Broadcasts kline `seq` and waits for the history to record it, as the engine sends one
+/// kline per symbol per bar.
+async fn send(event_tx: &EventBus, history: &KlineHistory, klines: &[Kline], seq: u64) {
+    event_tx.send(WsMessage::KlineData(broadcast(TEST_SYMBOL, klines, seq)));
+    while history.snapshot(TEST_SYMBOL, TEST_INTERVAL, Some(0)).snapshot_seq != seq {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+}
+
+/// The next kline `client` receives.
+async fn next_kline(client: &mut WsTestClient) -> KlineData {
+    loop {
+        match client.next_message().await {
+            Some(WsMessage::KlineData(data)) => return data,
+            Some(_) => continue,
+            None => panic!("the server closed the socket"),
+        }
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_client_joining_mid_session_draws_every_kline_once() {
+    let klines = generate_klines(15);
+    let history = Arc::new(KlineHistory::new(8));
+    let (addr, event_tx) = serve(Arc::clone(&history)).await;
+    for seq in 1..=10 {
+        send(&event_tx, &history, &klines, seq).await;
+    }
+
+    // The client subscribes, and two klines close before it fetches the snapshot.
+    let mut client = WsTestClient::connect(&format!("ws://{}/ws", addr)).await.unwrap();
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+    let mut streamed = Vec::new();
+    for seq in 11..=12 {
+        send(&event_tx, &history, &klines, seq).await;
+        streamed.push(next_kline(&mut client).await);
+    }
+    let url = format!("http://{}/api/live/klines/{}", addr, TEST_SYMBOL.to_lowercase());
+    let snapshot: KlineSnapshot = reqwest::get(&url).await.unwrap().json().await.unwrap();
+    assert_eq!((snapshot.symbol.as_str(), snapshot.snapshot_seq, snapshot.klines.len()), (TEST_SYMBOL, 12, 8));
+    for seq in 13..=15 {
+        send(&event_tx, &history, &klines, seq).await;
+        streamed.push(next_kline(&mut client).await);
+    }
+
+    assert_eq!(join(&snapshot, &streamed).unwrap(), &klines[4..]);
+
+    let limited: KlineSnapshot = reqwest::get(format!("{}?limit=3", url)).await.unwrap().json().await.unwrap();
+    assert_eq!((limited.snapshot_seq, limited.klines.as_slice()), (15, &klines[12..]));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn a_burst_of_klines_lands_in_the_history_in_full() {
+    // Far more klines than the bus buffers in its coalesced state, sent without waiting.
+    let klines = generate_klines(12);
+    let history = Arc::new(KlineHistory::new(50));
+    let (_, event_tx) = serve(Arc::clone(&history)).await;
+    for seq in 1..=11 {
+        event_tx.send(WsMessage::KlineData(broadcast(TEST_SYMBOL, &klines, seq)));
+    }
+    send(&event_tx, &history, &klines, 12).await;
+
+    let snapshot = history.snapshot(TEST_SYMBOL, TEST_INTERVAL, None);
+    assert_eq!((snapshot.snapshot_seq, snapshot.klines.as_slice()), (12, klines.as_slice()));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn the_interval_is_only_optional_for_a_symbol_traded_in_one() {
+    let klines = generate_klines(5);
+    let history = Arc::new(KlineHistory::new(50));
+    let (addr, _event_tx) = serve(Arc::clone(&history)).await;
+    let url = format!("http://{}/api/live/klines/{}", addr, TEST_SYMBOL);
+
+    let unseen: KlineSnapshot = reqwest::get(&url).await.unwrap().json().await.unwrap();
+    assert_eq!((unseen.interval, unseen.snapshot_seq, unseen.klines.len()), (None, 0, 0));
+
+    history.record(&broadcast_in(TEST_SYMBOL, "1h", &klines, 1));
+    let only: KlineSnapshot = reqwest::get(&url).await.unwrap().json().await.unwrap();
+    assert_eq!((only.interval.as_deref(), only.snapshot_seq), (Some("1h"), 1));
+
+    history.record(&broadcast_in(TEST_SYMBOL, "4h", &klines, 1));
+    history.record(&broadcast_in(TEST_SYMBOL, "4h", &klines, 2));
+    assert_eq!(reqwest::get(&url).await.unwrap().status(), reqwest::StatusCode::BAD_REQUEST);
+    let named: KlineSnapshot = reqwest::get(format!("{}?interval=4h", url)).await.unwrap().json().await.unwrap();
+    assert_eq!((named.interval.as_deref(), named.snapshot_seq, named.klines.as_slice()), (Some("4h"), 2, &klines[0..2]));
+}
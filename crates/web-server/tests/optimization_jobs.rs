@@ -0,0 +1,69 @@
+//! Reads optimization jobs' saved progress through the API.
+//!
+//! The tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p web-server -- --ignored
+//! ```
+
+use database::{DbOptimizationJob, DbRepository};
+use events::EventBus;
+use reqwest::StatusCode;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use testing::{test_config, TestDatabase, TEST_SYMBOL};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use web_server::auth::ApiToken;
+use web_server::backtests::BacktestRunner;
+use web_server::kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
+use web_server::handlers::OptimizationJobProgress;
+use web_server::{router, AppState};
+
+async fn serve(db_repo: DbRepository) -> SocketAddr {
+    let state = Arc::new(AppState {
+        db_repo,
+        event_tx: EventBus::new(64),
+        portfolio_state_cache: Arc::new(Mutex::new(None)),
+        kline_history: Arc::new(KlineHistory::new(DEFAULT_KLINE_HISTORY_BARS)),
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(None),
+        ws_auth_timeout: Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(test_config(10).unwrap()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(state, &[]).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn a_job_reports_its_saved_progress_alone_and_in_the_list() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let job_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, "MACrossover", TEST_SYMBOL, "Running", None).await.unwrap();
+    let eta = chrono::Utc::now() + chrono::Duration::minutes(5);
+    repo.save_job_progress(job_id, 4, 10, Some(eta)).await.unwrap();
+    let addr = serve(repo.clone()).await;
+
+    let response = reqwest::get(format!("http://{}/api/optimization-jobs/{}/progress", addr, job_id)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let progress: OptimizationJobProgress = response.json().await.unwrap();
+    assert_eq!((progress.job_status.as_str(), progress.completed_runs, progress.total_runs), ("Running", 4, 10));
+    assert_eq!(progress.estimated_completion_at.map(|at| at.timestamp_millis()), Some(eta.timestamp_millis()));
+
+    repo.update_job_status(job_id, "Completed").await.unwrap();
+    let jobs: Vec<DbOptimizationJob> = reqwest::get(format!("http://{}/api/optimization-jobs", addr)).await.unwrap().json().await.unwrap();
+    assert_eq!(jobs.len(), 1);
+    assert_eq!((jobs[0].job_status.as_str(), jobs[0].completed_runs, jobs[0].total_runs), ("Completed", 4, 10));
+
+    let unknown = reqwest::get(format!("http://{}/api/optimization-jobs/{}/progress", addr, Uuid::new_v4())).await.unwrap();
+    assert_eq!(unknown.status(), StatusCode::NOT_FOUND);
+
+    db.teardown().await.expect("drop test database");
+}
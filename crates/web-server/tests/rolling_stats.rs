@@ -0,0 +1,71 @@
+//! Serves the live rolling-window performance through `GET /api/live/rolling-stats` and the
+//! WebSocket broadcast of each refresh.
+//!
+//! The tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test -p web-server -- --ignored
+//! ```
+
+use chrono::{Duration, Utc};
+use events::{EventBus, RollingStats, WsMessage};
+use rust_decimal::Decimal;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use testing::{test_config, TestDatabase};
+use tokio::sync::Mutex;
+use web_server::auth::ApiToken;
+use web_server::backtests::BacktestRunner;
+use web_server::kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
+use web_server::{rolling_stats, router, AppState};
+
+async fn serve(state: Arc<AppState>) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = router(state, &[]).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    addr
+}
+
+async fn get_rolling_stats(addr: SocketAddr) -> RollingStats {
+    let response = reqwest::get(format!("http://{}/api/live/rolling-stats", addr)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+    response.json().await.unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn each_refresh_is_broadcast_and_served_until_the_next() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let state = Arc::new(AppState {
+        db_repo: db.repo(),
+        event_tx: EventBus::new(16),
+        portfolio_state_cache: Arc::new(Mutex::new(None)),
+        kline_history: Arc::new(KlineHistory::new(DEFAULT_KLINE_HISTORY_BARS)),
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(None),
+        ws_auth_timeout: std::time::Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(test_config(10).unwrap()),
+    });
+    let addr = serve(Arc::clone(&state)).await;
+
+    // Before any refresh, the stats are computed on request. Without live history every
+    // window is null.
+    let stats = get_rolling_stats(addr).await;
+    assert_eq!(stats.windows.iter().map(|window| window.days).collect::<Vec<_>>(), [7, 30]);
+    assert!(stats.windows.iter().all(|window| window.net_pnl.is_none() && window.trade_count.is_none()));
+
+    // Once the history spans both windows, a refresh broadcasts them.
+    let now = Utc::now();
+    state.db_repo.save_live_equity(now - Duration::days(31), Decimal::from(10_000)).await.expect("save equity");
+    let mut rx = state.event_tx.subscribe();
+    let refreshed = rolling_stats::refresh(&state.db_repo, &state.event_tx, &state.rolling_stats, now).await.expect("refresh");
+    assert!(refreshed.windows.iter().all(|window| window.trade_count == Some(0) && window.net_pnl == Some(Decimal::ZERO)));
+    assert_eq!(rx.recv().await.expect("broadcast"), WsMessage::RollingStats(refreshed.clone()));
+
+    // The endpoint serves the refreshed stats until the next refresh.
+    assert_eq!(get_rolling_stats(addr).await, refreshed);
+
+    db.teardown().await.expect("drop test database");
+}
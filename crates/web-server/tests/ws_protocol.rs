@@ -0,0 +1,122 @@
+//! Checks the versioned WebSocket frames and the `ClientHello` version negotiation.
+
+use chrono::{TimeZone, Utc};
+use database::DbRepository;
+use events::{EventBus, LogLevel, LogMessage, WsMessage, WS_PROTOCOL_VERSION};
+use serde_json::json;
+use sqlx::postgres::PgPoolOptions;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use testing::WsTestClient;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use web_server::auth::ApiToken;
+use web_server::backtests::BacktestRunner;
+use web_server::kline_history::{KlineHistory, DEFAULT_KLINE_HISTORY_BARS};
+use web_server::{router, AppState};
+
+/// Serves an open server, returning its `/ws` URL and the bus it broadcasts.
+async fn serve() -> (String, EventBus) {
+    let event_tx = EventBus::new(16);
+    let state = Arc::new(AppState {
+        db_repo: DbRepository::new(PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap()),
+        event_tx: event_tx.clone(),
+        portfolio_state_cache: Arc::new(Mutex::new(None)),
+        kline_history: Arc::new(KlineHistory::new(DEFAULT_KLINE_HISTORY_BARS)),
+        rolling_stats: Arc::new(Mutex::new(None)),
+        api_token: ApiToken::new(None),
+        ws_auth_timeout: Duration::from_millis(200),
+        engine_stats: None,
+        backtests: BacktestRunner::new(testing::test_config(100).unwrap()),
+    });
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr: SocketAddr = listener.local_addr().unwrap();
+    let app = router(state, &[]).unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (format!("ws://{}/ws", addr), event_tx)
+}
+
+fn log() -> WsMessage {
+    WsMessage::Log(LogMessage {
+        timestamp: Utc.with_ymd_and_hms(2025, 8, 25, 12, 0, 0).unwrap(),
+        level: LogLevel::Warn,
+        message: "Feed is silent.".to_string(),
+        fields: None,
+    })
+}
+
+#[tokio::test]
+async fn clients_get_bare_messages_until_they_say_hello() {
+    let (url, event_tx) = serve().await;
+    let mut client = WsTestClient::connect(&url).await.unwrap();
+
+    assert_eq!(client.next_frame().await.unwrap(), json!({ "type": "Connected" }));
+    event_tx.send(log());
+    assert_eq!(
+        client.next_frame().await.unwrap(),
+        json!({
+            "type": "Log",
+            "payload": { "timestamp": "2025-08-25T12:00:00Z", "level": "Warn", "message": "Feed is silent." },
+        })
+    );
+}
+
+#[tokio::test]
+async fn a_version_2_client_gets_enveloped_frames() {
+    let (url, event_tx) = serve().await;
+    let mut client = WsTestClient::connect(&url).await.unwrap();
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+
+    client.hello(2).await;
+    // The server confirms the version in it.
+    assert_eq!(client.next_frame().await.unwrap(), json!({ "v": 2, "type": "Connected" }));
+    event_tx.send(log());
+    assert_eq!(
+        client.next_frame().await.unwrap(),
+        json!({
+            "v": 2,
+            "type": "Log",
+            "data": { "timestamp": "2025-08-25T12:00:00Z", "level": "Warn", "message": "Feed is silent." },
+        })
+    );
+}
+
+#[tokio::test]
+async fn a_version_1_client_gets_bare_messages() {
+    let (url, event_tx) = serve().await;
+    let mut client = WsTestClient::connect(&url).await.unwrap();
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+
+    client.hello(1).await;
+    // The server confirms the version in it.
+    assert_eq!(client.next_frame().await.unwrap(), json!({ "type": "Connected" }));
+    event_tx.send(log());
+    assert_eq!(client.next_message().await, Some(log()));
+}
+
+#[tokio::test]
+async fn a_newer_client_gets_the_current_version() {
+    let (url, event_tx) = serve().await;
+    let mut client = WsTestClient::connect(&url).await.unwrap();
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+
+    client.hello(WS_PROTOCOL_VERSION + 1).await;
+    assert_eq!(client.version(), WS_PROTOCOL_VERSION);
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+    event_tx.send(log());
+    assert_eq!(client.next_message().await, Some(log()));
+}
+
+#[tokio::test]
+async fn a_client_older_than_every_supported_version_is_refused() {
+    let (url, _event_tx) = serve().await;
+    let mut client = WsTestClient::connect(&url).await.unwrap();
+    assert_eq!(client.next_message().await, Some(WsMessage::Connected));
+
+    client.hello(0).await;
+    assert_eq!(client.next_message().await, None);
+    let close = client.close_frame().expect("a close frame");
+    assert_eq!(close.code, CloseCode::Protocol);
+    assert_eq!(close.reason, "protocol version 0 is not supported; this server speaks 1 to 2");
+}
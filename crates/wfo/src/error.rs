@@ -23,6 +23,12 @@ pub enum WfoError {
     #[error("No best parameter set found during in-sample optimization for period {start} to {end}.")]
     NoBestParamsFound { start: String, end: String },
 
+    #[error("Failed to serialize the job's configuration snapshot: {0}")]
+    Json(#[from] serde_json::Error),
+
     #[error("Date range or period error: {0}")]
     DateError(String),
+
+    #[error("The in-sample window holds {in_sample_bars} bars, but the parameter space needs {required_bars} to warm up.")]
+    InSampleTooShort { in_sample_bars: usize, required_bars: usize },
 }
\ No newline at end of file
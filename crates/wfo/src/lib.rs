@@ -3,11 +3,14 @@ use analyzer::Analyzer;
 use backtester::Backtester;
 use chrono::{DateTime, Duration, Utc};
 use configuration::optimizer_config::{OptimizerConfig, WfoConfig};
-use configuration::Config;
+use configuration::{normalize_params, Config, JobConfigSnapshot, QuoteAssets};
+use core_types::{Interval, Period};
 use database::DbRepository;
 use executor::{Portfolio, SimulatedExecutor};
+use optimizer::generator::generate_valid_parameter_sets;
 use optimizer::Optimizer;
 use risk::SimpleRiskManager;
+use strategies::{create_strategy_from_params, merge_params};
 use analytics; // For AnalyticsEngine
 
 use uuid::Uuid;
@@ -53,7 +56,11 @@ impl WfoEngine {
     ) -> Result<(), WfoError> {
         let wfo_config = self.optimizer_config.wfo.as_ref().ok_or(WfoError::ConfigMissing)?;
 
-        // 1. Create and save the master WFO job record
+        // 1. Generate all the walk-forward periods
+        let periods = self.generate_walk_forward_periods(start_date, end_date, wfo_config)?;
+
+        // 2. Create and save the master WFO job record, with the configuration it runs with
+        let job_config = serde_json::to_value(JobConfigSnapshot::new(&self.optimizer_config, &self.base_config))?;
         self.db_repo.save_wfo_job(
             self.wfo_job_id,
             &format!("{:?}", self.optimizer_config.base_config.strategy_id),
@@ -61,11 +68,9 @@ impl WfoEngine {
             wfo_config.in_sample_weeks as i32,
             wfo_config.out_of_sample_weeks as i32,
             "Running",
+            Some(&job_config),
         ).await?;
 
-        // 2. Generate all the walk-forward periods
-        let periods = self.generate_walk_forward_periods(start_date, end_date, wfo_config)?;
-
         tracing::info!("Starting WFO Job {} with {} walk-forward periods.", self.wfo_job_id, periods.len());
 
         // 3. Loop through each period and execute the walk
@@ -106,14 +111,15 @@ impl WfoEngine {
             start: period.is_start.to_string(),
             end: period.is_end.to_string(),
         })?;
-        let best_params = best_run.report.parameters.clone();
+        let best_params = normalize_params(best_run.report.parameters.clone());
         tracing::info!("  Found best IS params: {}", best_params);
 
         // C. Run Out-of-Sample Backtest with the best parameters
         let oos_run_id = Uuid::new_v4();
         
-        let portfolio = Portfolio::new(self.base_config.backtest.initial_capital);
-        let executor = Box::new(SimulatedExecutor::new(self.base_config.simulation.clone()));
+        let quote_asset = &self.base_config.execution.quote_asset;
+        let portfolio = Portfolio::new(self.base_config.backtest.initial_capital).with_quote_asset(quote_asset.clone());
+        let executor = Box::new(SimulatedExecutor::new(self.base_config.simulation.clone()).with_quote_assets(QuoteAssets::new(quote_asset.clone())));
         let risk_manager = Box::new(SimpleRiskManager::new(self.base_config.risk_management.clone())?);
         let analytics_engine = analytics::AnalyticsEngine::new();
 
@@ -153,12 +159,17 @@ impl WfoEngine {
     }
 
     /// Generates a vector of non-overlapping walk-forward periods.
+    ///
+    /// Fails before any walk is run if the in-sample window is too short to warm up the
+    /// strategy for every parameter set.
     fn generate_walk_forward_periods(
         &self,
         start_date: DateTime<Utc>,
         end_date: DateTime<Utc>,
         config: &WfoConfig,
     ) -> Result<Vec<WalkPeriod>, WfoError> {
+        check_in_sample_warmup(&self.optimizer_config, &self.base_config, config)?;
+
         let mut periods = Vec::new();
         let mut current_start = start_date;
 
@@ -184,4 +195,35 @@ impl WfoEngine {
 
         Ok(periods)
     }
-}
\ No newline at end of file
+}
+
+/// Checks that an in-sample window of `config.in_sample_weeks` holds at least as many bars as
+/// the most demanding parameter set in the optimizer's space needs to warm up. Otherwise the
+/// first walk's in-sample runs could not trade from the start of their window.
+pub fn check_in_sample_warmup(
+    optimizer_config: &OptimizerConfig,
+    base_config: &Config,
+    config: &WfoConfig,
+) -> Result<(), WfoError> {
+    let base = &optimizer_config.base_config;
+    let interval: Interval = base.interval.parse().map_err(|e| WfoError::DateError(format!("{}.", e)))?;
+    let in_sample_bars = (interval.bars_per(Period::Week) * config.in_sample_weeks.max(0) as f64) as usize;
+
+    // Sets the strategy rejects (e.g., a fast MA slower than the slow one) are never run by
+    // the optimizer, so they need no warm-up.
+    let required_bars = generate_valid_parameter_sets(optimizer_config, base_config)?
+        .sets
+        .iter()
+        .filter_map(|params| {
+            let params = merge_params(base.strategy_id, base_config, params).ok()?;
+            create_strategy_from_params(base.strategy_id, &params, &base.symbol).ok()
+        })
+        .map(|strategy| strategy.required_warmup_bars())
+        .max()
+        .unwrap_or(0);
+
+    if in_sample_bars < required_bars {
+        return Err(WfoError::InSampleTooShort { in_sample_bars, required_bars });
+    }
+    Ok(())
+}
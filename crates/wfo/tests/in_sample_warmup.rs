@@ -0,0 +1,54 @@
+//! Checks that a walk-forward job rejects in-sample windows too short to warm its strategy up.
+
+use configuration::optimizer_config::{AnalysisConfig, BaseConfig, EquityCurveResolution, ObjectiveName, OptimizerConfig, ParameterRange, WfoConfig};
+use configuration::Versioned;
+use core_types::StrategyId;
+use std::collections::HashMap;
+use testing::{test_config, TEST_SYMBOL};
+use wfo::check_in_sample_warmup;
+use wfo::error::WfoError;
+
+/// A daily MACrossover sweep whose slowest valid parameter set needs 40 bars. The fast period
+/// of 50 is never valid (it exceeds every slow period), and neither is a 30-bar trend filter
+/// on the 40-bar slow MA, so they must not count.
+fn optimizer_config() -> OptimizerConfig {
+    OptimizerConfig {
+        config_version: OptimizerConfig::CURRENT_VERSION,
+        base_config: BaseConfig {
+            strategy_id: StrategyId::MACrossover,
+            symbol: TEST_SYMBOL.to_string(),
+            interval: "1d".to_string(),
+        },
+        parameter_space: HashMap::from([
+            ("ma_fast_period".to_string(), ParameterRange::DiscreteInt(vec![5, 50])),
+            ("ma_slow_period".to_string(), ParameterRange::DiscreteInt(vec![10, 40])),
+            ("trend_filter_period".to_string(), ParameterRange::DiscreteInt(vec![0, 30])),
+        ]),
+        analysis: AnalysisConfig::default(),
+        wfo: None,
+        max_concurrency: None,
+        equity_curve_resolution: EquityCurveResolution::Full,
+        objective: ObjectiveName::AnalyzerScore,
+    }
+}
+
+#[test]
+fn in_sample_windows_shorter_than_the_warmup_are_rejected() {
+    let base_config = test_config(100).expect("load config");
+    let wfo = WfoConfig { in_sample_weeks: 5, out_of_sample_weeks: 1 };
+
+    let result = check_in_sample_warmup(&optimizer_config(), &base_config, &wfo);
+    assert!(
+        matches!(result, Err(WfoError::InSampleTooShort { in_sample_bars: 35, required_bars: 40 })),
+        "{:?}",
+        result
+    );
+}
+
+#[test]
+fn in_sample_windows_covering_the_warmup_are_accepted() {
+    let base_config = test_config(100).expect("load config");
+    let wfo = WfoConfig { in_sample_weeks: 6, out_of_sample_weeks: 1 };
+
+    check_in_sample_warmup(&optimizer_config(), &base_config, &wfo).expect("42 daily bars cover a 40-bar warm-up");
+}
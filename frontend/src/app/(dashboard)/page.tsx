@@ -7,6 +7,9 @@ import { OpenPositionsTable } from "@/components/dashboard/live/OpenPositionsTab
 import { LiveLogStream } from "@/components/dashboard/live/LiveLogStream";
 import { SessionEquityChart } from "@/components/dashboard/live/SessionEquityChart";
 import { KlineDataDisplay } from "@/components/dashboard/live/KlineDataDisplay";
+import { DecisionLatencyTable } from "@/components/dashboard/live/DecisionLatencyTable";
+import { FeedHealthTable } from "@/components/dashboard/live/FeedHealthTable";
+import { RollingStatsTable } from "@/components/dashboard/live/RollingStatsTable";
 
 export default function LiveDashboardPage() {
   useLiveSocket();
@@ -20,9 +23,12 @@ export default function LiveDashboardPage() {
         {/* Main Column (takes up 3/5 of the space on large screens) */}
         <div className="lg:col-span-3 flex flex-col gap-4">
           <PortfolioVitals />
+          <RollingStatsTable />
           <div className="flex-1">
             <OpenPositionsTable />
           </div>
+          <DecisionLatencyTable />
+          <FeedHealthTable />
         </div>
 
         {/* Right Column (takes up 2/5 of the space on large screens) */}
@@ -15,6 +15,11 @@ export function FullMetricsTable({ report }: { report: FullReport }) {
         { label: "Payoff Ratio", value: parseFloat(report.payoff_ratio || '0').toFixed(2) },
         { label: "Average Win", value: parseFloat(report.average_win).toFixed(2) },
         { label: "Average Loss", value: parseFloat(report.average_loss).toFixed(2) },
+        { label: "Expectancy", value: report.expectancy_r ? `${parseFloat(report.expectancy_r).toFixed(2)}R` : "—" },
+        { label: "Time in Market %", value: report.time_in_market_pct ? `${parseFloat(report.time_in_market_pct).toFixed(2)}%` : "—" },
+        { label: "Average Exposure %", value: report.average_exposure_pct ? `${parseFloat(report.average_exposure_pct).toFixed(2)}%` : "—" },
+        { label: "Return on Margin %", value: report.return_on_margin_pct ? `${parseFloat(report.return_on_margin_pct).toFixed(2)}%` : "—" },
+        { label: "Exposure-Adjusted Sharpe", value: report.exposure_adjusted_sharpe ? parseFloat(report.exposure_adjusted_sharpe).toFixed(2) : "—" },
         // Add more metrics as desired
     ];
 
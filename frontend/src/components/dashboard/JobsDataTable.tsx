@@ -17,6 +17,17 @@ function formatTimestamp(timestamp: string): string {
     }
 }
 
+function formatProgress(job: OptimizationJob): string {
+    if (job.total_runs === 0) {
+        return "-";
+    }
+    const progress = `${job.completed_runs}/${job.total_runs}`;
+    if (job.job_status === "Running" && job.estimated_completion_at) {
+        return `${progress} (ETA ${formatTimestamp(job.estimated_completion_at)})`;
+    }
+    return progress;
+}
+
 export function JobsDataTable({ data }: { data: OptimizationJob[] }) {
   return (
     <Table>
@@ -26,6 +37,7 @@ export function JobsDataTable({ data }: { data: OptimizationJob[] }) {
           <TableHead>Strategy</TableHead>
           <TableHead>Symbol</TableHead>
           <TableHead>Status</TableHead>
+          <TableHead>Progress</TableHead>
           <TableHead>Created At</TableHead>
         </TableRow>
       </TableHeader>
@@ -40,6 +52,7 @@ export function JobsDataTable({ data }: { data: OptimizationJob[] }) {
             <TableCell>{job.strategy_id}</TableCell>
             <TableCell>{job.symbol}</TableCell>
             <TableCell><Badge>{job.job_status}</Badge></TableCell>
+            <TableCell>{formatProgress(job)}</TableCell>
             <TableCell>{formatTimestamp(job.created_at)}</TableCell>
           </TableRow>
         ))}
@@ -0,0 +1,54 @@
+"use client";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "@/components/ui/table";
+import { useLiveStore } from "@/store/live";
+import { LatencyStats } from "@/types/zenith";
+
+const formatMs = (stats: LatencyStats | null, pick: (s: LatencyStats) => number) =>
+  stats ? `${pick(stats).toFixed(1)} ms` : "-";
+
+export function DecisionLatencyTable() {
+  const report = useLiveStore((state) => state.latencyReport);
+  const symbols = report?.symbols || [];
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>
+          Decision Latency
+          {report && <span className="ml-2 text-sm font-normal text-muted-foreground">last {report.window_secs}s</span>}
+        </CardTitle>
+      </CardHeader>
+      <CardContent>
+        <Table>
+          <TableHeader>
+            <TableRow>
+              <TableHead>Symbol</TableHead>
+              <TableHead className="text-right">Decision p50</TableHead>
+              <TableHead className="text-right">Decision p99</TableHead>
+              <TableHead className="text-right">Strategy p99</TableHead>
+              <TableHead className="text-right">Feed Delay p50</TableHead>
+            </TableRow>
+          </TableHeader>
+          <TableBody>
+            {symbols.length > 0 ? (
+              symbols.map((s) => (
+                <TableRow key={s.symbol}>
+                  <TableCell className="font-medium">{s.symbol}</TableCell>
+                  <TableCell className="text-right font-mono">{formatMs(s.decision, (d) => d.p50_ms)}</TableCell>
+                  <TableCell className="text-right font-mono">{formatMs(s.decision, (d) => d.p99_ms)}</TableCell>
+                  <TableCell className="text-right font-mono">{formatMs(s.strategy, (d) => d.p99_ms)}</TableCell>
+                  <TableCell className="text-right font-mono">{formatMs(s.feed_delay, (d) => d.p50_ms)}</TableCell>
+                </TableRow>
+              ))
+            ) : (
+              <TableRow>
+                <TableCell colSpan={5} className="text-center text-muted-foreground">No latency report yet.</TableCell>
+              </TableRow>
+            )}
+          </TableBody>
+        </Table>
+      </CardContent>
+    </Card>
+  );
+}
@@ -0,0 +1,68 @@
+"use client";
+import { Badge } from "@/components/ui/badge";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "@/components/ui/table";
+import { useLiveStore } from "@/store/live";
+import { FeedHealth } from "@/types/zenith";
+
+const healthVariant = (health: FeedHealth) => {
+  switch (health) {
+    case "Healthy": return "default";
+    case "Delayed": return "secondary";
+    case "Stale": return "destructive";
+  }
+};
+
+// How long before the status was taken `at` was, e.g. "12s ago".
+const ago = (at: string | null, now: string) => {
+  if (!at) return "-";
+  const secs = Math.max(0, Math.round((Date.parse(now) - Date.parse(at)) / 1000));
+  return secs < 120 ? `${secs}s ago` : `${Math.round(secs / 60)}m ago`;
+};
+
+export function FeedHealthTable() {
+  const status = useLiveStore((state) => state.feedStatus);
+  const feeds = status?.feeds || [];
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>Feed Health</CardTitle>
+      </CardHeader>
+      <CardContent>
+        <Table>
+          <TableHeader>
+            <TableRow>
+              <TableHead>Symbol</TableHead>
+              <TableHead>Interval</TableHead>
+              <TableHead className="text-right">Last Kline Close</TableHead>
+              <TableHead className="text-right">Book Ticker</TableHead>
+              <TableHead className="text-right">Mark Price</TableHead>
+              <TableHead className="text-right">Status</TableHead>
+            </TableRow>
+          </TableHeader>
+          <TableBody>
+            {status && feeds.length > 0 ? (
+              feeds.map((feed) => (
+                <TableRow key={`${feed.symbol}-${feed.interval}`}>
+                  <TableCell className="font-medium">{feed.symbol}</TableCell>
+                  <TableCell>{feed.interval}</TableCell>
+                  <TableCell className="text-right font-mono">{ago(feed.kline, status.timestamp)}</TableCell>
+                  <TableCell className="text-right font-mono">{ago(feed.book_ticker, status.timestamp)}</TableCell>
+                  <TableCell className="text-right font-mono">{ago(feed.mark_price, status.timestamp)}</TableCell>
+                  <TableCell className="text-right">
+                    <Badge variant={healthVariant(feed.status)}>{feed.status}</Badge>
+                  </TableCell>
+                </TableRow>
+              ))
+            ) : (
+              <TableRow>
+                <TableCell colSpan={6} className="text-center text-muted-foreground">No feed status yet.</TableCell>
+              </TableRow>
+            )}
+          </TableBody>
+        </Table>
+      </CardContent>
+    </Card>
+  );
+}
@@ -10,6 +10,13 @@ const logLevelColor: Record<LogLevel, string> = {
     Error: "text-red-500",
 };
 
+/** Renders structured log fields as `key=value` pairs. */
+function formatFields(fields: Record<string, unknown>): string {
+    return Object.entries(fields)
+        .map(([key, value]) => `${key}=${typeof value === "string" ? value : JSON.stringify(value)}`)
+        .join(" ");
+}
+
 export function LiveLogStream() {
   const logs = useLiveStore((state) => state.logs);
 
@@ -25,6 +32,9 @@ export function LiveLogStream() {
                     <span className={cn(logLevelColor[log.level], "font-bold")}>[{log.level.toUpperCase()}]</span>
                     <span className="text-gray-500 ml-2">{new Date(log.timestamp).toLocaleTimeString()}</span>
                     <span className="ml-2">{log.message}</span>
+                    {log.fields && (
+                        <span className="text-gray-500 ml-2">{formatFields(log.fields)}</span>
+                    )}
                 </div>
             ))}
         </div>
@@ -22,6 +22,7 @@ export function OpenPositionsTable() {
               <TableHead>Side</TableHead>
               <TableHead className="text-right">Size</TableHead>
               <TableHead className="text-right">Entry Price</TableHead>
+              <TableHead className="text-right" title="Estimated from the bot's leverage, assuming isolated margin">Est. Liq. Price</TableHead>
               <TableHead className="text-right">Unrealized P&L</TableHead>
             </TableRow>
           </TableHeader>
@@ -37,6 +38,9 @@ export function OpenPositionsTable() {
                   </TableCell>
                   <TableCell className="text-right font-mono">{parseFloat(pos.quantity).toFixed(4)}</TableCell>
                   <TableCell className="text-right font-mono">{parseFloat(pos.entry_price).toFixed(2)}</TableCell>
+                  <TableCell className="text-right font-mono text-muted-foreground">
+                    {pos.estimated_liquidation_price ? `~${parseFloat(pos.estimated_liquidation_price).toFixed(2)}` : "—"}
+                  </TableCell>
                   <TableCell className={`text-right font-mono ${parseFloat(pos.unrealized_pnl) >= 0 ? 'text-green-500' : 'text-red-500'}`}>
                     ${parseFloat(pos.unrealized_pnl).toFixed(2)}
                   </TableCell>
@@ -44,7 +48,7 @@ export function OpenPositionsTable() {
               ))
             ) : (
               <TableRow>
-                <TableCell colSpan={5} className="text-center text-muted-foreground">No open positions.</TableCell>
+                <TableCell colSpan={6} className="text-center text-muted-foreground">No open positions.</TableCell>
               </TableRow>
             )}
           </TableBody>
@@ -0,0 +1,56 @@
+"use client";
+import { Card, CardContent, CardHeader, CardTitle } from "@/components/ui/card";
+import { Table, TableBody, TableCell, TableHead, TableHeader, TableRow } from "@/components/ui/table";
+import { useLiveStore } from "@/store/live";
+
+// A decimal string to `digits` places, or "-" while the window lacks the data.
+const fixed = (value: string | null, digits: number, suffix = "") =>
+  value === null ? "-" : `${parseFloat(value).toFixed(digits)}${suffix}`;
+
+const pnlClass = (value: string | null) => {
+  if (value === null) return "";
+  return parseFloat(value) >= 0 ? "text-green-500" : "text-red-500";
+};
+
+export function RollingStatsTable() {
+  const stats = useLiveStore((state) => state.rollingStats);
+  const windows = stats?.windows || [];
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle>Rolling Performance</CardTitle>
+      </CardHeader>
+      <CardContent>
+        <Table>
+          <TableHeader>
+            <TableRow>
+              <TableHead>Window</TableHead>
+              <TableHead className="text-right">Net P&amp;L</TableHead>
+              <TableHead className="text-right">Trades</TableHead>
+              <TableHead className="text-right">Win Rate</TableHead>
+              <TableHead className="text-right">Sharpe</TableHead>
+            </TableRow>
+          </TableHeader>
+          <TableBody>
+            {windows.length > 0 ? (
+              windows.map((window) => (
+                <TableRow key={window.days}>
+                  <TableCell className="font-medium">Last {window.days}d</TableCell>
+                  <TableCell className={`text-right font-mono ${pnlClass(window.net_pnl)}`}>{fixed(window.net_pnl, 2)}</TableCell>
+                  <TableCell className="text-right font-mono">{window.trade_count ?? "-"}</TableCell>
+                  <TableCell className="text-right font-mono">{fixed(window.win_rate_pct, 1, "%")}</TableCell>
+                  <TableCell className="text-right font-mono">{fixed(window.sharpe_ratio, 2)}</TableCell>
+                </TableRow>
+              ))
+            ) : (
+              <TableRow>
+                <TableCell colSpan={5} className="text-center text-muted-foreground">No rolling stats yet.</TableCell>
+              </TableRow>
+            )}
+          </TableBody>
+        </Table>
+      </CardContent>
+    </Card>
+  );
+}
@@ -1,14 +1,15 @@
 "use client";
 import { useEffect, useRef } from 'react';
 import { useLiveStore } from '@/store/live';
-import { WsMessage } from '@/types/zenith';
+import { WS_PROTOCOL_VERSION, WsMessage } from '@/types/zenith';
+import { API_TOKEN } from '@/services/api';
 
 // The URL for our backend WebSocket.
 // In a real app, this would come from an environment variable.
 const WS_URL = "ws://127.0.0.1:8080/ws";
 
 export const useLiveSocket = () => {
-  const { setStatus, setPortfolioState, addLog, updateKlineData } = useLiveStore();
+  const { setStatus, setPortfolioState, addLog, updateKlineData, setLatencyReport, setFeedStatus, setRollingStats } = useLiveStore();
   const socketRef = useRef<WebSocket | null>(null);
 
   useEffect(() => {
@@ -19,6 +20,12 @@ export const useLiveSocket = () => {
       socketRef.current = socket;
 
       socket.onopen = () => {
+        // The server sends nothing until the connection is authenticated.
+        if (API_TOKEN) {
+          socket.send(JSON.stringify({ type: "Auth", payload: { token: API_TOKEN } }));
+        }
+        // Pins the shape of the frames to the version these types describe.
+        socket.send(JSON.stringify({ type: "ClientHello", payload: { supported_version: WS_PROTOCOL_VERSION } }));
         console.log("WebSocket connection established.");
         setStatus("Connected");
       };
@@ -31,14 +38,23 @@ export const useLiveSocket = () => {
           // Use a switch on the message type to update the correct part of the store.
           switch (message.type) {
             case "Log":
-              addLog(message.payload);
+              addLog(message.data);
               break;
             case "PortfolioState":
-              setPortfolioState(message.payload);
+              setPortfolioState(message.data);
               break;
             case "KlineData":
-              console.log('KlineData received:', message.payload);
-              updateKlineData(message.payload);
+              console.log('KlineData received:', message.data);
+              updateKlineData(message.data);
+              break;
+            case "LatencyReport":
+              setLatencyReport(message.data);
+              break;
+            case "FeedStatus":
+              setFeedStatus(message.data);
+              break;
+            case "RollingStats":
+              setRollingStats(message.data);
               break;
             case "Connected":
               console.log('WebSocket connection confirmed');
@@ -1,11 +1,16 @@
-import { OptimizationJob, RankedReport, BacktestRunDetails, WfoJob, WfoRun } from "@/types/zenith";
+import { OptimizationJob, OptimizationJobProgress, RankedReport, BacktestRunDetails, WfoJob, WfoRun, KlineSeries } from "@/types/zenith";
 
 // The base URL for our Zenith backend API.
 // In a real app, this would come from an environment variable.
 const API_BASE_URL = "http://localhost:8080/api";
 
+// The backend's `server.api_token`, if it requires one.
+export const API_TOKEN = process.env.NEXT_PUBLIC_ZENITH_API_TOKEN;
+
 async function fetcher<T>(url: string): Promise<T> {
-  const res = await fetch(url);
+  const res = await fetch(url, {
+    headers: API_TOKEN ? { Authorization: `Bearer ${API_TOKEN}` } : undefined,
+  });
   if (!res.ok) {
     const errorBody = await res.json().catch(() => ({ error: "An unknown error occurred" }));
     throw new Error(errorBody.error || `Request failed with status ${res.status}`);
@@ -21,6 +26,10 @@ export const getJobDetails = (jobId: string): Promise<RankedReport[]> => {
   return fetcher(`${API_BASE_URL}/optimization-jobs/${jobId}`);
 };
 
+export const getJobProgress = (jobId: string): Promise<OptimizationJobProgress> => {
+  return fetcher(`${API_BASE_URL}/optimization-jobs/${jobId}/progress`);
+};
+
 export const getRunDetails = (runId: string): Promise<BacktestRunDetails> => {
     return fetcher(`${API_BASE_URL}/backtest-runs/${runId}/details`);
 };
@@ -29,6 +38,17 @@ export const getSingleRuns = (): Promise<RankedReport[]> => {
     return fetcher(`${API_BASE_URL}/single-runs`);
 }
 
+export const getRunKlines = (runId: string, maxPoints?: number): Promise<KlineSeries> => {
+    const query = maxPoints ? `?max_points=${maxPoints}` : "";
+    return fetcher(`${API_BASE_URL}/backtest-runs/${runId}/klines${query}`);
+}
+
+export const getKlines = (symbol: string, interval: string, from: string, to: string, maxPoints?: number): Promise<KlineSeries> => {
+    const params = new URLSearchParams({ symbol, interval, from, to });
+    if (maxPoints) params.set("max_points", String(maxPoints));
+    return fetcher(`${API_BASE_URL}/klines?${params}`);
+}
+
 export const testCors = (): Promise<string> => {
     return fetcher(`${API_BASE_URL}/cors-test`);
 }
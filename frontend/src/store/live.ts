@@ -1,5 +1,5 @@
 import { create } from 'zustand';
-import { LogMessage, PortfolioState, KlineData } from '@/types/zenith';
+import { LogMessage, PortfolioState, KlineData, LatencyReport, FeedStatus, RollingStats } from '@/types/zenith';
 
 export type ConnectionStatus = "Connecting" | "Connected" | "Disconnected";
 
@@ -8,6 +8,9 @@ interface LiveState {
   portfolioState: PortfolioState | null;
   logs: LogMessage[];
   klineData: Map<string, KlineData>; // Store latest kline data per symbol
+  latencyReport: LatencyReport | null; // The most recent per-bot decision latency report
+  feedStatus: FeedStatus | null; // The most recent health of each bot's market data feed
+  rollingStats: RollingStats | null; // The live performance over the trailing 7 and 30 days
 }
 
 interface LiveActions {
@@ -15,6 +18,9 @@ interface LiveActions {
   setPortfolioState: (state: PortfolioState) => void;
   addLog: (log: LogMessage) => void;
   updateKlineData: (klineData: KlineData) => void;
+  setLatencyReport: (report: LatencyReport) => void;
+  setFeedStatus: (status: FeedStatus) => void;
+  setRollingStats: (stats: RollingStats) => void;
 }
 
 const MAX_LOGS = 200; // The maximum number of log messages to keep in memory
@@ -24,6 +30,9 @@ export const useLiveStore = create<LiveState & LiveActions>((set) => ({
   portfolioState: null,
   logs: [],
   klineData: new Map(),
+  latencyReport: null,
+  feedStatus: null,
+  rollingStats: null,
   setStatus: (status) => set({ status }),
   setPortfolioState: (state) => set({ portfolioState: state }),
   addLog: (log) =>
@@ -37,4 +46,7 @@ export const useLiveStore = create<LiveState & LiveActions>((set) => ({
       newKlineData.set(klineData.symbol, klineData);
       return { klineData: newKlineData };
     }),
+  setLatencyReport: (report) => set({ latencyReport: report }),
+  setFeedStatus: (status) => set({ feedStatus: status }),
+  setRollingStats: (stats) => set({ rollingStats: stats }),
 }));
\ No newline at end of file
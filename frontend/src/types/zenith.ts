@@ -6,12 +6,17 @@ export interface OptimizationJob {
     symbol: string;
     job_status: string;
     created_at: string; // ISO 8601 date string
+    completed_runs: number;
+    total_runs: number;
+    updated_at: string;
+    estimated_completion_at: string | null;
   }
   
   export interface RankedReport {
     parameters: Record<string, number | string>;
     score: string; // Comes as a string to preserve decimal precision
     report: FullReport;
+    cluster?: number | null; // The job's cluster of good parameter sets; null outside any
   }
   
   export interface FullReport {
@@ -36,7 +41,39 @@ export interface OptimizationJob {
     average_loss: string;
   
     payoff_ratio: string | null;
+    // Longest losing streak; null for runs recorded before it was tracked
+    max_consecutive_losses: number | null;
     average_holding_period: string;
+    // Fees split by liquidity; null for runs recorded before the split
+    maker_fees_paid: string | null;
+    taker_fees_paid: string | null;
+    // The cost of crossing the spread; null for runs recorded before it was tracked
+    total_spread_cost: string | null;
+    // What stops lost to gaps; null for runs recorded before it was tracked
+    gap_slippage: string | null;
+    // Statistics per close reason; null for runs recorded before it was computed
+    exit_breakdown: ExitStats[] | null;
+    // Returns in units of the risk taken at entry; null when no trade had a known risk
+    average_r: string | null;
+    expectancy_r: string | null;
+    r_distribution: RDistribution | null;
+    // Capital efficiency; null for runs that did not record their exposure
+    average_exposure_pct: string | null;
+    peak_exposure_pct: string | null;
+    time_in_market_pct: string | null;
+    return_on_margin_pct: string | null;
+    exposure_adjusted_sharpe: string | null;
+    // Execution metadata; null for runs recorded before it was tracked
+    started_at: string | null;
+    finished_at: string | null;
+    bars_processed: number | null;
+    signals_filtered: number | null; // Signals refused by the minimum expected-move filter
+    brackets_stop_first: number | null; // Bars reaching both exits that closed at the stop-loss
+    brackets_target_first: number | null; // Bars reaching both exits that closed at the take-profit
+    engine_version: string | null;
+    config_hash: string | null;
+    // The optimizer objective value stored when the run finished; null if it failed the hard filters
+    objective_value: string | null;
     // This is a placeholder for the full trade and equity data
     trades?: Trade[];
     equity_curve?: EquityDataPoint[];
@@ -52,13 +89,48 @@ export interface OptimizationJob {
     fee: string;
     fee_asset: string;
     timestamp: string;
+    decision_id?: string | null;
+    placement?: OrderPlacement; // Live limit orders only
+  }
+
+  // The book a live limit order was placed against, and what its confirmed fill saved.
+  export interface OrderPlacement {
+    best_bid: string;
+    best_ask: string;
+    limit_price: string;
+    placed_at: string;
+    fill_price?: string | null; // Null until the fill is confirmed
+    is_maker?: boolean | null;
+    filled_at?: string | null;
+    price_improvement_bps: string | null; // Positive when better than crossing the spread
   }
   
+  export interface ExitStats {
+    close_reason: CloseReason;
+    trades: number;
+    net_pnl: string;
+    win_rate_pct: string;
+    average_pnl: string;
+  }
+
+  // Trade counts by R-multiple band, each including its lower bound
+  export interface RDistribution {
+    below_minus_one: number;
+    minus_one_to_zero: number;
+    zero_to_one: number;
+    one_to_two: number;
+    two_and_above: number;
+  }
+
+  export type CloseReason = "Signal" | "StopLoss" | "TakeProfit" | "TimeLimit" | "Blackout";
+
   export interface Trade {
     trade_id: string;
     symbol: string;
     entry_execution: Execution;
     exit_execution: Execution;
+    close_reason: CloseReason; // Why the position was closed
+    initial_risk: string | null; // Entry-to-stop distance times quantity; null without a stop
   }
   
   export interface EquityDataPoint {
@@ -66,11 +138,27 @@ export interface OptimizationJob {
     equity: string;
   }
   
+  // A note attached by hand to a trade, run or live position (/api/annotations).
+  export interface Annotation {
+    annotation_id: string;
+    target_type: "trade" | "run" | "position";
+    target_id: string;
+    author: string;
+    text: string;
+    tags: string[];
+    created_at: string;
+  }
+
+  export interface AnnotatedTrade extends Trade {
+    notes: Annotation[]; // Oldest first
+  }
+
   // We will need a more detailed type for a full backtest run
   export interface BacktestRunDetails {
       report: FullReport;
-      trades: Trade[];
+      trades: AnnotatedTrade[];
       equity_curve: EquityDataPoint[];
+      notes: Annotation[]; // The notes on the run itself
   }
 
   export interface WfoJob {
@@ -99,6 +187,7 @@ export interface LogMessage {
   timestamp: string;
   level: LogLevel;
   message: string;
+  fields?: Record<string, unknown>;
 }
 
 export interface Position {
@@ -109,13 +198,63 @@ export interface Position {
   entry_price: string;
   unrealized_pnl: string;
   last_updated: string;
+  // An isolated-margin estimate, not the exchange's figure. Null without a known leverage.
+  estimated_liquidation_price: string | null;
 }
 
 export interface PortfolioState {
   timestamp: string;
-  cash: string;
+  quote_asset: string; // The asset cash, total value and P&L are in, e.g. "USDT"
+  cash: string; // In the quote asset, including the converted value of other collateral assets
+  balances: Record<string, string>; // Collateral balances by asset, in each asset's own units
   total_value: string;
   positions: Position[];
+  realized_pnl: string;
+  total_fees_paid: string;
+}
+
+// A live position rebuilt from the engine's recorded executions (GET /api/live/positions).
+export interface LivePosition {
+  position_id: string;
+  symbol: string;
+  side: "Buy" | "Sell";
+  status: "open" | "closed";
+  entry_time: string | null;
+  exit_time: string | null;
+  avg_entry_price: string | null;
+  avg_exit_price: string | null;
+  quantity: string;
+  max_quantity: string;
+  realized_pnl: string; // Net of fees
+  fees: string;
+  unrealized_pnl: string | null; // Open positions only
+  close_reason: CloseReason | null;
+  notes: Annotation[]; // Oldest first
+}
+
+export type RollupGranularity = "daily" | "weekly";
+
+// Live performance over one UTC day or Monday-to-Sunday week (GET /api/live/performance).
+export interface PerformanceRollup {
+  period_start: string; // YYYY-MM-DD; the Monday for a week
+  net_pnl: string; // Net of fees
+  fees: string;
+  trade_count: number; // Reducing and closing fills
+  winning_trades: number;
+  win_rate_pct: string | null;
+  max_drawdown: string;
+  max_drawdown_pct: string;
+  ending_equity: string | null;
+  computed_at: string;
+}
+
+// Live limit fills of one symbol (GET /api/live/execution-quality).
+export interface SymbolExecutionQuality {
+  symbol: string;
+  limit_fills: number;
+  maker_fills: number;
+  maker_fill_ratio: string;
+  avg_improvement_bps: string | null; // Basis points of the mid price
 }
 
 export interface Kline {
@@ -131,12 +270,167 @@ export interface Kline {
 
 export interface KlineData {
   symbol: string;
+  interval: string;
   kline: Kline;
+  seq: number; // Per symbol and interval, from 1 when the engine starts
+}
+
+// The recent live klines of a symbol in one interval, oldest first
+// (GET /api/live/klines/:symbol?interval=). Skip the streamed KlineData of the symbol and
+// interval up to `snapshot_seq`, and refetch when a `seq` is skipped.
+export interface LiveKlineSnapshot {
+  symbol: string;
+  interval: string | null; // null before the symbol's first kline, when none was asked for
+  snapshot_seq: number;
+  klines: Kline[];
 }
 
-// This is the discriminated union for all possible incoming WebSocket messages.
+// [open_time_ms, open, high, low, close, volume, close_time_ms]
+export type CompactKline = [number, string, string, string, string, string, number];
+
+// A price series for charting. Long ranges come back merged into `bucket_size`-bar candles.
+export interface KlineSeries {
+  symbol: string;
+  interval: string;
+  bucket_size: number;
+  columns: string[];
+  candles: CompactKline[];
+}
+
+// Latency percentiles of one stage of the live decision path, in milliseconds.
+export interface LatencyStats {
+  samples: number;
+  p50_ms: number;
+  p99_ms: number;
+  max_ms: number;
+}
+
+export interface SymbolLatency {
+  symbol: string;
+  feed_delay: LatencyStats | null;
+  strategy: LatencyStats | null;
+  risk: LatencyStats | null;
+  execution: LatencyStats | null;
+  decision: LatencyStats | null;
+}
+
+export interface LatencyReport {
+  timestamp: string;
+  window_secs: number;
+  symbols: SymbolLatency[];
+}
+
+// How current a feed's klines are against its interval.
+export type FeedHealth = "Healthy" | "Delayed" | "Stale";
+
+export interface FeedState {
+  symbol: string;
+  interval: string;
+  kline: string | null; // Close time of the latest kline
+  book_ticker: string | null; // When the latest book ticker update arrived
+  mark_price: string | null; // When the latest mark price update arrived
+  status: FeedHealth;
+}
+
+export interface FeedStatus {
+  timestamp: string;
+  feeds: FeedState[];
+}
+
+// Live performance over one trailing window. Every figure is null until the live history
+// spans the window.
+export interface RollingWindowStats {
+  days: number;
+  from: string;
+  net_pnl: string | null; // Net of fees
+  trade_count: number | null; // Reducing and closing fills
+  win_rate_pct: string | null; // Also null without trades
+  sharpe_ratio: string | null; // Annualized, from daily equity; null without volatility
+}
+
+// Live performance over the trailing 7 and 30 days, refreshed hourly
+// (also GET /api/live/rolling-stats).
+export interface RollingStats {
+  computed_at: string;
+  windows: RollingWindowStats[]; // Shortest first
+}
+
+// Response of GET /api/engine/stats.
+export interface ActivityCounts {
+  last_1h: number;
+  last_24h: number;
+}
+
+export interface BotStats {
+  symbol: string;
+  interval: string;
+  last_kline_close_time: string | null;
+  halted: boolean;
+  consecutive_losses: number;
+}
+
+export interface ExecutionQualityStats {
+  symbol: string;
+  limit_fills: number;
+  maker_fills: number;
+  maker_fill_ratio: string | null;
+  avg_improvement_bps: string | null;
+}
+
+export interface EngineStatsSnapshot {
+  started_at: string;
+  uptime_secs: number;
+  signals: ActivityCounts;
+  signals_filtered: ActivityCounts; // Refused by the minimum expected-move filter
+  orders_filled: ActivityCounts;
+  bots: BotStats[];
+  halted_bots: string[];
+  execution_quality: ExecutionQualityStats[]; // Symbols with limit fills
+  event_channel: {
+    receivers: number;
+    queued: number;
+    capacity: number;
+    headroom: number; // Messages that can be sent before the slowest receiver lags
+  };
+}
+
+export interface BacktestProgress {
+  run_id: string;
+  pct: number; // 0 to 100
+  bars_done: number;
+}
+
+// Response of POST /api/backtest-runs.
+export interface BacktestRunCreated {
+  run_id: string;
+}
+
+// Response of GET /api/optimization-jobs/:job_id/progress.
+export interface OptimizationJobProgress {
+  job_id: string;
+  job_status: string;
+  completed_runs: number;
+  total_runs: number;
+  updated_at: string;
+  estimated_completion_at: string | null;
+}
+
+// Response of GET /api/backtest-runs/:run_id/status.
+export interface BacktestRunStatus {
+  run_id: string;
+  status: "Pending" | "Running" | "Completed" | "Failed";
+}
+
+// The WebSocket protocol version these types describe. Its JSON Schema is ws-protocol.json.
+export const WS_PROTOCOL_VERSION = 2;
+
+// This is the discriminated union for all possible incoming WebSocket frames.
 export type WsMessage =
-  | { type: "Log"; payload: LogMessage }
-  | { type: "PortfolioState"; payload: PortfolioState }
-  | { type: "KlineData"; payload: KlineData }
-  | { type: "Connected" };
\ No newline at end of file
+  | { v: 2; type: "Log"; data: LogMessage }
+  | { v: 2; type: "PortfolioState"; data: PortfolioState }
+  | { v: 2; type: "KlineData"; data: KlineData }
+  | { v: 2; type: "LatencyReport"; data: LatencyReport }
+  | { v: 2; type: "BacktestProgress"; data: BacktestProgress }
+  | { v: 2; type: "FeedStatus"; data: FeedStatus }
+  | { v: 2; type: "RollingStats"; data: RollingStats }
+  | { v: 2; type: "Connected" };
\ No newline at end of file
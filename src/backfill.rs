@@ -0,0 +1,130 @@
+//! Resumable historical kline backfills.
+//!
+//! A backfill is split into calendar months, fetched oldest-first with a few months in
+//! flight at once. As each month completes without gaps since the start, the progress is
+//! saved to the database, so an interrupted backfill skips the months it already saved when
+//! run again.
+
+use anyhow::Result;
+use api_client::ApiClient;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use core_types::PriceType;
+use database::{BackfillProgress, DbRepository};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use indicatif::ProgressBar;
+use std::sync::Arc;
+
+/// How many monthly ranges are fetched at once. Higher values trip the exchange rate limits.
+pub const MAX_CONCURRENT_RANGES: usize = 3;
+
+/// What to backfill: the klines of `symbol` on `interval` from `from` to `to`, inclusive.
+#[derive(Debug, Clone)]
+pub struct BackfillRequest {
+    pub symbol: String,
+    pub interval: String,
+    /// Backfill last price klines, or mark price klines into their own table.
+    pub price_type: PriceType,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Fetch every month again, including those already saved.
+    pub force: bool,
+}
+
+/// How many monthly ranges a backfill fetched, and how many it skipped as already saved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BackfillSummary {
+    pub fetched: usize,
+    pub skipped: usize,
+}
+
+/// Splits `from..=to` into calendar-month ranges, the first and last clipped to the dates.
+pub fn monthly_ranges(mut from: NaiveDate, to: NaiveDate) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut ranges = Vec::new();
+    while from <= to {
+        let end_of_month = from
+            .with_day(1)
+            .unwrap()
+            .with_month(from.month() + 1)
+            .unwrap_or_else(|| from.with_year(from.year() + 1).unwrap().with_month(1).unwrap())
+            .pred_opt().unwrap();
+
+        let end_date = std::cmp::min(end_of_month, to);
+        ranges.push((
+            from.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Utc).unwrap(),
+            end_date.and_hms_opt(23, 59, 59).unwrap().and_local_timezone(Utc).unwrap(),
+        ));
+
+        from = end_date + Duration::days(1);
+    }
+    ranges
+}
+
+/// Whether the saved progress of any earlier backfill covers the range.
+fn is_saved(progress: &[BackfillProgress], (start, end): (DateTime<Utc>, DateTime<Utc>)) -> bool {
+    progress
+        .iter()
+        .any(|saved| saved.range_start <= start && end <= saved.last_completed_range_end)
+}
+
+/// Fetches and saves the requested klines, skipping the months earlier runs already saved
+/// unless the request forces them.
+///
+/// The first failing month stops the backfill; the months completed before it stay saved.
+pub async fn run_backfill(
+    api_client: Arc<dyn ApiClient>,
+    db_repo: &DbRepository,
+    request: &BackfillRequest,
+    progress_bar: &ProgressBar,
+) -> Result<BackfillSummary> {
+    let BackfillRequest { symbol, interval, price_type, from, to, force } = request;
+    let ranges = monthly_ranges(*from, *to);
+    let Some(&(range_start, _)) = ranges.first() else {
+        return Ok(BackfillSummary { fetched: 0, skipped: 0 });
+    };
+
+    let saved = if *force { Vec::new() } else { db_repo.get_backfill_progress(symbol, interval, *price_type).await? };
+    let (skipped, pending): (Vec<_>, Vec<_>) = ranges.into_iter().partition(|&range| is_saved(&saved, range));
+    let mut summary = BackfillSummary { fetched: 0, skipped: skipped.len() };
+
+    progress_bar.set_length((summary.skipped + pending.len()) as u64);
+    if summary.skipped > 0 {
+        progress_bar.println(format!("Skipping {} month(s) already backfilled; pass --force to fetch them again.", summary.skipped));
+        progress_bar.inc(summary.skipped as u64);
+    }
+
+    // `buffered` yields the months in order, so progress only ever advances over months
+    // with every earlier month saved.
+    let mut completed = stream::iter(pending)
+        .map(|(start, end)| {
+            let api_client = Arc::clone(&api_client);
+            async move {
+                match price_type {
+                    PriceType::Last => {
+                        for kline in &api_client.fetch_klines(symbol, interval, start, end).await? {
+                            db_repo.save_kline(symbol, kline).await?;
+                        }
+                    }
+                    PriceType::Mark => {
+                        for kline in &api_client.fetch_mark_price_klines(symbol, interval, start, end).await? {
+                            db_repo.save_mark_price_kline(symbol, kline).await?;
+                        }
+                    }
+                }
+                Ok::<_, anyhow::Error>((start, end))
+            }
+        })
+        .buffered(MAX_CONCURRENT_RANGES);
+
+    while let Some((start, end)) = completed.try_next().await? {
+        db_repo.save_backfill_progress(symbol, interval, *price_type, range_start, end).await?;
+        summary.fetched += 1;
+        progress_bar.inc(1);
+        progress_bar.set_message(format!(
+            "Fetched {} ({} fetched, {} skipped)",
+            start.format("%Y-%m"),
+            summary.fetched,
+            summary.skipped
+        ));
+    }
+    Ok(summary)
+}
@@ -0,0 +1,79 @@
+//! Database backups for `zenith backup` and `zenith restore`, and the scheduled backups the
+//! serve and run modes take when `[backup]` is configured.
+//!
+//! Backups are named after the UTC time they were taken, as
+//! `zenith-backup-20250908T030000Z.jsonl.gz`, so they sort oldest first. After each scheduled
+//! backup, all but the newest `keep` in the directory are deleted. Backups taken by hand go
+//! to the same directory unless given another path, and count towards `keep` too.
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use configuration::BackupConfig;
+use database::DbRepository;
+use std::path::{Path, PathBuf};
+
+const BACKUP_PREFIX: &str = "zenith-backup-";
+const BACKUP_SUFFIX: &str = ".jsonl.gz";
+
+/// The file name of a backup taken at `at`.
+pub fn backup_file_name(at: DateTime<Utc>) -> String {
+    format!("{}{}{}", BACKUP_PREFIX, at.format("%Y%m%dT%H%M%SZ"), BACKUP_SUFFIX)
+}
+
+/// Backs the database up to a new file in `directory`, creating the directory if needed, and
+/// returns the file's path.
+pub async fn backup_to_directory(db_repo: &DbRepository, directory: &Path) -> anyhow::Result<PathBuf> {
+    std::fs::create_dir_all(directory).with_context(|| format!("creating {}", directory.display()))?;
+    let path = directory.join(backup_file_name(Utc::now()));
+    db_repo.backup(&path, backtester::ENGINE_VERSION).await?;
+    Ok(path)
+}
+
+/// Deletes all but the newest `keep` backups in `directory` and returns the deleted paths.
+/// Other files in the directory are left alone.
+pub fn rotate_backups(directory: &Path, keep: usize) -> std::io::Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(directory)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(BACKUP_PREFIX) && name.ends_with(BACKUP_SUFFIX))
+        })
+        .collect();
+    backups.sort();
+    let stale = backups.len().saturating_sub(keep);
+    let deleted: Vec<PathBuf> = backups.into_iter().take(stale).collect();
+    for path in &deleted {
+        std::fs::remove_file(path)?;
+    }
+    Ok(deleted)
+}
+
+/// Backs the database up each time `config.schedule` fires, then rotates the backups down to
+/// `config.keep`. Failures are logged and the backup is skipped until the next time.
+pub async fn run_scheduled_backups(db_repo: DbRepository, config: BackupConfig) {
+    loop {
+        let now = Utc::now();
+        let Some(run_at) = config.schedule.next_after(now) else {
+            // Parsing rejects schedules that never fire, so this is not reached.
+            tracing::error!("The backup schedule \"{}\" never fires; no backups will be taken.", config.schedule);
+            return;
+        };
+        tokio::time::sleep((run_at - now).to_std().unwrap_or_default()).await;
+
+        match backup_to_directory(&db_repo, &config.directory).await {
+            Ok(path) => {
+                tracing::info!("Scheduled backup written to {}.", path.display());
+                match rotate_backups(&config.directory, config.keep) {
+                    Ok(deleted) => {
+                        for path in deleted {
+                            tracing::info!("Deleted old backup {}.", path.display());
+                        }
+                    }
+                    Err(e) => tracing::warn!("Failed to rotate the backups in {}: {}", config.directory.display(), e),
+                }
+            }
+            Err(e) => tracing::error!("Scheduled backup failed: {:#}", e),
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Export of stored klines to Parquet files, and their import into another database, so a
+//! research environment can be reproduced without backfilling from the exchange.
+//!
+//! An export writes one zstd-compressed Parquet file per symbol and interval, with the
+//! columns of `Kline`, and a `manifest.json` listing each file with its row count and SHA-256
+//! checksum. Prices and volumes are written as decimal strings, which keep every digit and
+//! the scale, so an imported kline is identical to the exported one. Timestamps are UTC
+//! microseconds, the database's own precision.
+//!
+//! An import checks every file against the manifest before loading any of them, then loads
+//! them through the batched insert, skipping the klines already stored.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use core_types::{Interval, Kline};
+use database::DbRepository;
+use polars::prelude::*;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::Path;
+use std::str::FromStr;
+
+/// The name of the manifest in an export directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The version of the export format, recorded in the manifest. Imports refuse other versions.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// What to export: the klines of every combination of `symbols` and `intervals` opening
+/// from `from` to `to`, inclusive.
+#[derive(Debug, Clone)]
+pub struct ExportRequest {
+    pub symbols: Vec<String>,
+    pub intervals: Vec<Interval>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+/// The contents of an export directory.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format_version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub files: Vec<ManifestEntry>,
+}
+
+/// One Parquet file of an export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// The file name, relative to the export directory.
+    pub file: String,
+    pub symbol: String,
+    pub interval: String,
+    pub rows: usize,
+    pub first_open_time: DateTime<Utc>,
+    pub last_open_time: DateTime<Utc>,
+    /// The SHA-256 checksum of the file, in lowercase hex.
+    pub sha256: String,
+}
+
+/// A run of bars missing between two stored klines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KlineGap {
+    /// The open time of the last kline before the gap.
+    pub after: DateTime<Utc>,
+    pub missing_bars: usize,
+}
+
+/// What the import of one file loaded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImportedFile {
+    pub symbol: String,
+    pub interval: String,
+    pub rows: usize,
+    /// The rows that were not stored yet; the others were skipped.
+    pub inserted: u64,
+    /// The bars from the file's first open time to its last, had none been missing.
+    pub expected_bars: usize,
+    pub gaps: Vec<KlineGap>,
+}
+
+/// Writes the requested klines to `dir`, creating it if needed, and returns the manifest
+/// written with them. Combinations without stored klines are left out.
+pub async fn export_klines(db_repo: &DbRepository, request: &ExportRequest, dir: &Path) -> Result<Manifest> {
+    if request.from > request.to {
+        bail!("The export starts on {} but ends on {}.", request.from, request.to);
+    }
+    std::fs::create_dir_all(dir).with_context(|| format!("Cannot create {}", dir.display()))?;
+    let start = request.from.and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let end = request.to.and_hms_micro_opt(23, 59, 59, 999_999).unwrap().and_utc();
+
+    let mut files = Vec::new();
+    for symbol in &request.symbols {
+        for interval in &request.intervals {
+            let klines = db_repo.get_klines_by_date_range(symbol, interval.as_str(), start, end).await?;
+            let (Some(first), Some(last)) = (klines.first(), klines.last()) else {
+                tracing::warn!(symbol = %symbol, interval = %interval, "No stored klines to export.");
+                continue;
+            };
+            let file = format!("{}_{}.parquet", symbol, interval);
+            let path = dir.join(&file);
+            write_parquet(&klines, File::create(&path).with_context(|| format!("Cannot create {}", path.display()))?)?;
+            files.push(ManifestEntry {
+                sha256: sha256_of(&path)?,
+                file,
+                symbol: symbol.clone(),
+                interval: interval.to_string(),
+                rows: klines.len(),
+                first_open_time: first.open_time,
+                last_open_time: last.open_time,
+            });
+        }
+    }
+
+    let manifest = Manifest { format_version: FORMAT_VERSION, exported_at: Utc::now(), from: request.from, to: request.to, files };
+    std::fs::write(dir.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?)?;
+    Ok(manifest)
+}
+
+/// Loads the klines exported to `dir`. Every file is checked against the manifest first, so
+/// a corrupt or altered export loads nothing.
+pub async fn import_klines(db_repo: &DbRepository, dir: &Path) -> Result<Vec<ImportedFile>> {
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let manifest: Manifest = serde_json::from_str(
+        &std::fs::read_to_string(&manifest_path).with_context(|| format!("Cannot read {}", manifest_path.display()))?,
+    )
+    .with_context(|| format!("{} is not a kline export manifest", manifest_path.display()))?;
+    if manifest.format_version != FORMAT_VERSION {
+        bail!("The export is in format version {}; this build reads version {}.", manifest.format_version, FORMAT_VERSION);
+    }
+
+    let mut exports = Vec::with_capacity(manifest.files.len());
+    for entry in &manifest.files {
+        let path = dir.join(&entry.file);
+        let checksum = sha256_of(&path)?;
+        if checksum != entry.sha256 {
+            bail!("{} does not match its checksum in the manifest ({} != {}).", entry.file, checksum, entry.sha256);
+        }
+        let interval = Interval::from_str(&entry.interval).map_err(|e| anyhow::anyhow!("{}: {}", entry.file, e))?;
+        let klines = read_parquet(File::open(&path)?).with_context(|| format!("Cannot read {}", entry.file))?;
+        if klines.len() != entry.rows {
+            bail!("{} holds {} klines, but the manifest lists {}.", entry.file, klines.len(), entry.rows);
+        }
+        exports.push((entry, interval, klines));
+    }
+
+    let mut imported = Vec::with_capacity(exports.len());
+    for (entry, interval, klines) in exports {
+        let inserted = db_repo.save_klines(&entry.symbol, &klines).await?;
+        imported.push(ImportedFile {
+            symbol: entry.symbol.clone(),
+            interval: entry.interval.clone(),
+            rows: klines.len(),
+            inserted,
+            expected_bars: expected_bars(&klines, interval),
+            gaps: find_gaps(&klines, interval),
+        });
+    }
+    Ok(imported)
+}
+
+/// The bars from the first of `klines` to the last, had none been missing.
+pub fn expected_bars(klines: &[Kline], interval: Interval) -> usize {
+    match (klines.first(), klines.last()) {
+        (Some(first), Some(last)) => bars_between(first.open_time, last.open_time, interval) + 1,
+        _ => 0,
+    }
+}
+
+/// The runs of bars missing from `klines`, which are in open-time order.
+pub fn find_gaps(klines: &[Kline], interval: Interval) -> Vec<KlineGap> {
+    klines
+        .windows(2)
+        .filter_map(|pair| {
+            let missing_bars = bars_between(pair[0].open_time, pair[1].open_time, interval).saturating_sub(1);
+            (missing_bars > 0).then_some(KlineGap { after: pair[0].open_time, missing_bars })
+        })
+        .collect()
+}
+
+fn bars_between(from: DateTime<Utc>, to: DateTime<Utc>, interval: Interval) -> usize {
+    let bar_micros = interval.duration().as_micros() as i64;
+    ((to - from).num_microseconds().unwrap_or(i64::MAX) / bar_micros).max(0) as usize
+}
+
+fn sha256_of(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// The UTC microsecond timestamps of the export.
+fn timestamp_type() -> DataType {
+    DataType::Datetime(TimeUnit::Microseconds, Some("UTC".to_string()))
+}
+
+/// Writes `klines` as a zstd-compressed Parquet file.
+pub fn write_parquet(klines: &[Kline], writer: impl std::io::Write) -> Result<()> {
+    let timestamps = |name: &str, time: fn(&Kline) -> DateTime<Utc>| {
+        Series::new(name, klines.iter().map(|kline| time(kline).timestamp_micros()).collect::<Vec<i64>>()).cast(&timestamp_type())
+    };
+    let decimals = |name: &str, value: fn(&Kline) -> Decimal| {
+        Series::new(name, klines.iter().map(|kline| value(kline).to_string()).collect::<Vec<String>>())
+    };
+    let mut frame = DataFrame::new(vec![
+        timestamps("open_time", |kline| kline.open_time)?,
+        decimals("open", |kline| kline.open),
+        decimals("high", |kline| kline.high),
+        decimals("low", |kline| kline.low),
+        decimals("close", |kline| kline.close),
+        decimals("volume", |kline| kline.volume),
+        timestamps("close_time", |kline| kline.close_time)?,
+        Series::new("interval", klines.iter().map(|kline| kline.interval.as_str()).collect::<Vec<&str>>()),
+    ])?;
+    ParquetWriter::new(writer).with_compression(ParquetCompression::Zstd(None)).finish(&mut frame)?;
+    Ok(())
+}
+
+/// Reads klines written by `write_parquet`.
+pub fn read_parquet(reader: File) -> Result<Vec<Kline>> {
+    let frame = ParquetReader::new(reader).finish()?;
+    let timestamps = |name: &str| -> Result<Vec<DateTime<Utc>>> {
+        let column = frame.column(name)?.cast(&timestamp_type())?;
+        column
+            .datetime()?
+            .into_iter()
+            .map(|micros| micros.and_then(DateTime::from_timestamp_micros).with_context(|| format!("A null or invalid {}", name)))
+            .collect()
+    };
+    let decimals = |name: &str| -> Result<Vec<Decimal>> {
+        frame
+            .column(name)?
+            .str()?
+            .into_iter()
+            .map(|text| {
+                let text = text.with_context(|| format!("A null {}", name))?;
+                Decimal::from_str_exact(text).with_context(|| format!("An invalid {}: {}", name, text))
+            })
+            .collect()
+    };
+    let intervals: Vec<String> = frame
+        .column("interval")?
+        .str()?
+        .into_iter()
+        .map(|interval| interval.map(str::to_string).context("A null interval"))
+        .collect::<Result<_>>()?;
+
+    let (open_times, close_times) = (timestamps("open_time")?, timestamps("close_time")?);
+    let (opens, highs, lows, closes, volumes) =
+        (decimals("open")?, decimals("high")?, decimals("low")?, decimals("close")?, decimals("volume")?);
+    Ok(intervals
+        .into_iter()
+        .enumerate()
+        .map(|(i, interval)| Kline {
+            open_time: open_times[i],
+            open: opens[i],
+            high: highs[i],
+            low: lows[i],
+            close: closes[i],
+            volume: volumes[i],
+            close_time: close_times[i],
+            interval,
+        })
+        .collect())
+}
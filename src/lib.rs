@@ -5,4 +5,28 @@ pub use api_client;
 pub use core_types;
 pub use database;
 
+// The library-level backtest API, for embedding Zenith backtests in other tools.
+pub use backtester::{run_backtest, BacktestOutput, BacktestSpec, KlineSource};
+
+// Resumable historical kline backfills.
+pub mod backfill;
+
+// Checks of configured symbols against the exchange or the stored klines.
+pub mod symbols;
+
+// Pre-deployment checks of a live configuration.
+pub mod validate_live;
+
+// Reconciliation of the live P&L against the exchange's income history.
+pub mod reconcile_pnl;
+
+// Export of stored klines to Parquet files, and their import elsewhere.
+pub mod kline_export;
+
+// Re-runs of a stored backtest across a grid of fees and slippages.
+pub mod sensitivity;
+
+// Database backups, by hand and on a schedule, and their restore.
+pub mod backup;
+
 // Define any shared types or functionality here
@@ -1,31 +1,36 @@
 use anyhow::Result;
 use alerter::{run_alerter_service, TelegramAlerter}; // <-- ADD THIS
 use api_client::{ApiClient, BinanceClient};
-use backtester::Backtester;
-use chrono::{DateTime, NaiveDate, Utc, Duration, Datelike};
-use clap::{Parser, Subcommand};
+use backtester::{run_backtest, BacktestSpec, KlineSource};
+use chrono::{NaiveDate, Utc, Duration};
+use clap::{Parser, Subcommand, ValueEnum};
 use comfy_table::{presets::UTF8_FULL, Cell, ContentArrangement, Table};
-use configuration::{load_config, load_live_config, load_optimizer_config, load_portfolio_config, PortfolioBotConfig, MACrossoverParams, ProbReversionParams, SuperTrendParams, ExecutionMode};
-use database::{connect, run_migrations, DbRepository};
-use engine::LiveEngine;
+use configuration::{load_config, load_live_config, load_optimizer_config, load_portfolio_config, validate_portfolio_config, PortfolioBotConfig, ExecutionMode, Versioned};
+use configuration::{Config, LiveConfig, OptimizerConfig, PortfolioConfig, QuoteAssets};
+use core_types::{Interval, PriceType};
+use database::{connect, connect_repository, run_migrations, DbRepository};
+use engine::{LiveEngine, ReplayConnector, RiskState};
 use executor::{Portfolio, SimulatedExecutor, LiveExecutor, LimitOrderExecutor};
-use events::WsMessage;
-use futures::future::join_all;
+use events::{EngineStats, EventBus, EVENT_CHANNEL_CAPACITY};
 use indicatif::{ProgressBar, ProgressStyle};
 use optimizer::Optimizer;
-use portfolio_backtester::{load_and_prepare_data, PortfolioManager};
+use portfolio_backtester::{load_and_prepare_data, PortfolioBot, PortfolioManager};
 use risk::SimpleRiskManager;
-use serde_json::{from_value, json, Value as JsonValue};
-use strategies::{create_strategy, StrategyId};
+use strategies::{create_strategy_from_params, KlineTransformer, merge_params};
 use std::collections::HashMap;
 use std::net::SocketAddr; // For parsing socket addresses
-use std::ops::Add;
 use std::path::PathBuf;
 use std::sync::Arc;
-use tokio::sync::broadcast; // <-- ADD THIS
 use uuid::Uuid;
-use analyzer::Analyzer;
+use analyzer::{cluster_runs, compare_runs, export_ranked_reports, portfolio_toml, sort_by_objective, stored_job_config, Analyzer, ClusterOptions, CompareOptions, RankedReport};
 use wfo::WfoEngine;
+use zenith::backup::{backup_to_directory, run_scheduled_backups};
+use zenith::backfill::{run_backfill, BackfillRequest};
+use zenith::kline_export::{export_klines, import_klines, ExportRequest};
+use zenith::sensitivity::{run_sensitivity, stored_run_spec, Breakeven, SensitivityGrid, SensitivityMatrix};
+use zenith::symbols::validate_symbols;
+use zenith::reconcile_pnl::{reconcile_pnl, run_weekly_reconciliation, PnlReconciliation};
+use zenith::validate_live::{check_database, check_deployment, load_configs, CheckStatus, Checklist};
 use web_server;
 
 // Note: Advanced tracing imports removed - using config-based tracing instead
@@ -42,7 +47,8 @@ async fn main() -> Result<()> {
     configuration::init_tracing(&config.logging)?;
     // --- END INITIALIZATION ---
 
-    dotenvy::dotenv().expect(".env file not found");
+    // A missing .env file is fine; DATABASE_URL may come from the environment.
+    let _ = dotenvy::dotenv();
     
     tracing::info!("Zenith CLI application started.");
 
@@ -64,7 +70,20 @@ async fn main() -> Result<()> {
         Commands::Wfo(args) => handle_wfo(args).await?,
         Commands::PortfolioRun(args) => handle_portfolio_run(args).await?,
         Commands::Run(args) => handle_run(args).await?,
+        Commands::ValidateLive(args) => handle_validate_live(args).await?,
         Commands::Serve(args) => handle_serve(args).await?,
+        Commands::Report(args) => handle_report(args).await?,
+        Commands::PruneEquity(args) => handle_prune_equity(args).await?,
+        Commands::Compare(args) => handle_compare(args).await?,
+        Commands::Rollup(args) => handle_rollup(args).await?,
+        Commands::ReconcilePnl(args) => handle_reconcile_pnl(args).await?,
+        Commands::ExportData(args) => handle_export_data(args).await?,
+        Commands::ImportData(args) => handle_import_data(args).await?,
+        Commands::Sensitivity(args) => handle_sensitivity(args).await?,
+        Commands::Backup(args) => handle_backup(args).await?,
+        Commands::Restore(args) => handle_restore(args).await?,
+        Commands::DataCheck(args) => handle_data_check(args).await?,
+        Commands::Config(args) => handle_config(args)?,
     }
     
     tracing::info!("Zenith CLI application finished.");
@@ -94,8 +113,39 @@ enum Commands {
     Wfo(WfoArgs),
     PortfolioRun(PortfolioRunArgs),
     Run(RunArgs),
+    /// Check a live configuration can be deployed, without starting the engine. Exits with
+    /// an error if any check fails.
+    ValidateLive(ValidateLiveArgs),
     /// Start the web server to serve the API.
     Serve(ServeArgs),
+    /// Render a self-contained HTML report for a saved backtest run.
+    Report(ReportArgs),
+    /// Thin the stored equity curves of low-ranked runs in old optimization jobs.
+    PruneEquity(PruneEquityArgs),
+    /// Compare two saved backtest runs side by side.
+    Compare(CompareArgs),
+    /// Roll up live trading performance per day and week.
+    Rollup(RollupArgs),
+    /// Reconcile the live P&L recorded per symbol and day against the exchange's income
+    /// history.
+    ReconcilePnl(ReconcilePnlArgs),
+    /// Write stored klines to compressed Parquet files with a checksummed manifest, so
+    /// another environment can load them without a backfill.
+    ExportData(ExportDataArgs),
+    /// Load klines written by `export-data`, checking them against its manifest.
+    ImportData(ImportDataArgs),
+    /// Re-run a saved backtest across a grid of fees and slippages, to see how much of its
+    /// profit survives worse execution.
+    Sensitivity(SensitivityArgs),
+    /// Write a consistent snapshot of the database to a compressed backup file.
+    Backup(BackupArgs),
+    /// Load a backup written by `backup` into the database.
+    Restore(RestoreArgs),
+    /// Summarize the stored klines of each symbol and interval, and with `--validate`, list
+    /// those that fail validation. Exits with an error if any do.
+    DataCheck(DataCheckArgs),
+    /// Maintain the configuration files.
+    Config(ConfigArgs),
 }
 
 // ... (Other arg structs are unchanged) ...
@@ -103,12 +153,20 @@ enum Commands {
 struct BackfillArgs {
     #[arg(long)]
     symbol: String,
+    /// The kline interval, e.g. "1h".
     #[arg(long)]
-    interval: String,
+    interval: Interval,
     #[arg(long)]
     from: NaiveDate,
     #[arg(long)]
     to: NaiveDate,
+    /// Fetch every month again, including those an earlier backfill already saved.
+    #[arg(long)]
+    force: bool,
+    /// The price series to backfill: `last` traded prices, or `mark` prices for backtests
+    /// with `valuation_price = "mark"`.
+    #[arg(long, default_value = "last")]
+    price_type: PriceType,
 }
 
 #[derive(Parser)]
@@ -128,8 +186,29 @@ struct OptimizeArgs {
 #[derive(Parser)]
 struct AnalyzeArgs {
     job_id: Uuid,
-    #[arg(long, short, default_value = "optimizer.toml")]
-    config: PathBuf,
+    /// Analyse with this optimizer config's filters and weights instead of those stored with
+    /// the job. Jobs stored without a configuration fall back to `optimizer.toml`.
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+    /// Print the configuration the job was run with.
+    #[arg(long)]
+    show_config: bool,
+    /// Write every ranked run, with all metrics and parameters, to a .csv or .json file.
+    #[arg(long)]
+    export: Option<PathBuf>,
+    /// Write the best parameter sets as a portfolio definition for `portfolio-run`.
+    #[arg(long)]
+    emit_portfolio: Option<PathBuf>,
+    /// How many of the best parameter sets `--emit-portfolio` turns into bots.
+    #[arg(long, default_value_t = 5)]
+    top: usize,
+    /// Rank runs by the objective value stored when each finished, instead of the score.
+    #[arg(long)]
+    sort_by_objective: bool,
+    /// Group the best runs into distinct regions of the parameter grid and print one table
+    /// per region, instead of the top 20.
+    #[arg(long)]
+    clusters: bool,
 }
 
 #[derive(Parser)]
@@ -150,6 +229,12 @@ struct PortfolioRunArgs {
     to: Option<NaiveDate>,
     #[arg(long, short, default_value = "portfolio.toml")]
     portfolio: PathBuf,
+    /// Overrides the portfolio's starting capital.
+    #[arg(long)]
+    capital: Option<rust_decimal::Decimal>,
+    /// Overrides the portfolio's kline interval (e.g., "1h").
+    #[arg(long)]
+    interval: Option<Interval>,
 }
 
 #[derive(Parser)]
@@ -161,6 +246,29 @@ struct RunArgs {
     /// Path to the live trading configuration file.
     #[arg(long, short, default_value = "live.toml")]
     config: PathBuf,
+
+    /// Replay mode: the first day of recorded data to replay (YYYY-MM-DD).
+    #[arg(long, required_if_eq("mode", "replay"))]
+    from: Option<NaiveDate>,
+
+    /// Replay mode: the last day of recorded data to replay, inclusive (YYYY-MM-DD).
+    #[arg(long, required_if_eq("mode", "replay"))]
+    to: Option<NaiveDate>,
+
+    /// Replay mode: overrides `replay.speed` from the live config.
+    #[arg(long)]
+    replay_speed: Option<f64>,
+}
+
+#[derive(Parser)]
+struct ValidateLiveArgs {
+    /// Path to the live trading configuration file.
+    #[arg(long, short, default_value = "live.toml")]
+    config: PathBuf,
+
+    /// The execution mode the configuration will be run in.
+    #[arg(long, value_enum, default_value_t = ExecutionMode::Paper)]
+    mode: ExecutionMode,
 }
 
 #[derive(Parser)]
@@ -170,36 +278,489 @@ struct ServeArgs {
     addr: SocketAddr,
 }
 
+#[derive(Parser)]
+struct ReportArgs {
+    /// The ID of the backtest run to report on.
+    #[arg(long)]
+    run_id: Uuid,
+    /// The file to write the HTML report to.
+    #[arg(long, short, default_value = "report.html")]
+    output: PathBuf,
+}
+
+#[derive(Parser)]
+struct PruneEquityArgs {
+    /// Only prune jobs created longer ago than this (e.g., "90d", "12w", "36h").
+    #[arg(long, value_parser = parse_age)]
+    older_than: Duration,
+    /// The number of best-ranked runs per job whose curves are left at full resolution.
+    #[arg(long, default_value_t = 50)]
+    keep_top: usize,
+    /// The approximate number of points a pruned curve keeps.
+    #[arg(long, default_value_t = 500)]
+    points: usize,
+    /// The optimizer config whose analysis rules rank the runs.
+    #[arg(long, short, default_value = "optimizer.toml")]
+    config: PathBuf,
+}
+
+#[derive(Parser)]
+struct CompareArgs {
+    /// The baseline run.
+    run_id_a: Uuid,
+    /// The run compared against the baseline.
+    run_id_b: Uuid,
+    /// The largest equity difference that does not count as a divergence.
+    #[arg(long, default_value = "0.01")]
+    epsilon: rust_decimal::Decimal,
+}
+
+#[derive(Parser)]
+struct RollupArgs {
+    /// Recompute every day from `--from` on from the stored live fills and equity, replacing
+    /// the stored rollups. Without it, only the last completed day and its week are rolled up.
+    #[arg(long, requires = "from")]
+    recompute: bool,
+    /// The first day to recompute.
+    #[arg(long, requires = "recompute")]
+    from: Option<NaiveDate>,
+    /// The last day to recompute. Defaults to yesterday, the last completed day.
+    #[arg(long, requires = "recompute")]
+    to: Option<NaiveDate>,
+}
+
+#[derive(Parser)]
+struct ReconcilePnlArgs {
+    /// The first day to reconcile (YYYY-MM-DD).
+    #[arg(long)]
+    from: NaiveDate,
+    /// The last day to reconcile, inclusive (YYYY-MM-DD).
+    #[arg(long)]
+    to: NaiveDate,
+    /// Reconcile the testnet account instead of the live one.
+    #[arg(long)]
+    testnet: bool,
+    /// The largest difference of a figure that still counts as a match.
+    #[arg(long, default_value = "0.01")]
+    tolerance: rust_decimal::Decimal,
+}
+
+#[derive(Parser)]
+struct ExportDataArgs {
+    /// The symbols to export, comma-separated.
+    #[arg(long, value_delimiter = ',', required = true)]
+    symbols: Vec<String>,
+    /// The kline intervals to export, comma-separated, e.g. "1h,4h".
+    #[arg(long, value_delimiter = ',', required = true)]
+    intervals: Vec<Interval>,
+    #[arg(long)]
+    from: NaiveDate,
+    #[arg(long)]
+    to: NaiveDate,
+    /// The directory to write the files and their manifest to.
+    #[arg(long, short, default_value = "klines-export")]
+    output: PathBuf,
+}
+
+#[derive(Parser)]
+struct ImportDataArgs {
+    /// The directory `export-data` wrote.
+    dir: PathBuf,
+}
+
+#[derive(Parser)]
+struct SensitivityArgs {
+    /// The completed run to re-run.
+    #[arg(long)]
+    run_id: Uuid,
+    /// The taker fees to try, in percent, comma-separated, e.g. "0.02,0.04,0.06". The maker
+    /// fee keeps its ratio to the taker fee.
+    #[arg(long, value_delimiter = ',', required = true)]
+    fees: Vec<rust_decimal::Decimal>,
+    /// The slippages to try, as fractions of the bar's range like `slippage_pct`,
+    /// comma-separated, e.g. "0.01,0.05,0.1".
+    #[arg(long, value_delimiter = ',', required = true)]
+    slippage: Vec<rust_decimal::Decimal>,
+    /// The re-runs in flight at once. Defaults to the number of CPUs.
+    #[arg(long)]
+    concurrency: Option<usize>,
+    /// Save the re-runs as runs of a new job. Without it, nothing is written to the database.
+    #[arg(long)]
+    save: bool,
+}
+
+#[derive(Parser)]
+struct BackupArgs {
+    /// The file to write. Defaults to a timestamped file in `backup.directory`, or in
+    /// "backups" when `[backup]` is not configured.
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+struct RestoreArgs {
+    /// The backup file to load.
+    file: PathBuf,
+    /// Empty every table before loading. Without it, a database that holds any data is
+    /// refused.
+    #[arg(long)]
+    replace: bool,
+}
+
+#[derive(Parser)]
+struct DataCheckArgs {
+    /// Scan the stored klines for invalid bars and those saved flagged as suspect.
+    #[arg(long)]
+    validate: bool,
+    /// Only scan this symbol's klines.
+    #[arg(long)]
+    symbol: Option<String>,
+}
+
+#[derive(Parser)]
+struct ConfigArgs {
+    #[command(subcommand)]
+    command: ConfigCommand,
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Bring a configuration file up to this build's schema version, adding the settings
+    /// introduced since as commented defaults. Prints the result unless `--write` is given.
+    Migrate(MigrateArgs),
+}
+
+#[derive(Parser)]
+struct MigrateArgs {
+    /// The file to migrate.
+    #[arg(default_value = "config.toml")]
+    path: PathBuf,
+    /// Which configuration the file holds. Inferred from the file name when omitted.
+    #[arg(long, value_enum)]
+    kind: Option<ConfigKind>,
+    /// Rewrite the file in place instead of printing the migrated file.
+    #[arg(long)]
+    write: bool,
+}
+
+/// The configuration files with a versioned schema.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ConfigKind {
+    Config,
+    Live,
+    Optimizer,
+    Portfolio,
+}
+
+impl ConfigKind {
+    /// The kind whose usual file name starts the name of `path`, e.g. `optimizer` for
+    /// `optimizer_supertrend_example.toml`.
+    fn infer(path: &std::path::Path) -> Option<Self> {
+        let stem = path.file_stem()?.to_str()?.to_ascii_lowercase();
+        [Self::Config, Self::Live, Self::Optimizer, Self::Portfolio]
+            .into_iter()
+            .find(|kind| stem.starts_with(kind.to_possible_value().unwrap().get_name()))
+    }
+}
+
+/// Parses an age such as "90d" into a duration. Units: h (hours), d (days), w (weeks). The
+/// count is a whole, non-negative number; an age too long for a duration is an error.
+fn parse_age(age: &str) -> Result<Duration, String> {
+    let invalid = || format!("invalid age '{}': expected a non-negative whole number followed by h, d or w", age);
+    let too_long = || format!("age '{}' is too long", age);
+    let unit = age.chars().last().ok_or_else(invalid)?;
+    let count: u64 = age[..age.len() - unit.len_utf8()].parse().map_err(|e: std::num::ParseIntError| match e.kind() {
+        std::num::IntErrorKind::PosOverflow => too_long(),
+        _ => invalid(),
+    })?;
+    let count = i64::try_from(count).ok();
+    let duration = match unit {
+        'h' => count.and_then(Duration::try_hours),
+        'd' => count.and_then(Duration::try_days),
+        'w' => count.and_then(Duration::try_weeks),
+        _ => return Err(invalid()),
+    };
+    duration.ok_or_else(too_long)
+}
+
 // ==============================================================================
 // Command Handlers
 // ==============================================================================
 
 /// Handler for the `serve` command.
 async fn handle_serve(args: ServeArgs) -> Result<()> {
+    let config = load_config(None)?;
+
     // Initialize database connection
     let db_pool = connect().await?;
     run_migrations(&db_pool).await?;
     let db_repo = DbRepository::new(db_pool);
+
+    // Back the database up on the `[backup]` schedule, if one is set.
+    if let Some(backup) = config.backup.clone() {
+        tokio::spawn(run_scheduled_backups(db_repo.clone(), backup));
+        tracing::info!("Database backups scheduled.");
+    }
     
-    // Create broadcast channel for WebSocket events
-    let (event_tx, _) = broadcast::channel::<WsMessage>(10000); // Much larger capacity for kline data
+    // Create the event bus for WebSocket events
+    let event_tx = EventBus::new(EVENT_CHANNEL_CAPACITY);
     
     // We call the library function from our `web-server` crate.
-    web_server::run_server(args.addr, db_repo, event_tx).await
+    web_server::run_server(args.addr, db_repo, event_tx, config, None).await
+}
+
+/// Handler for the `report` command.
+async fn handle_report(args: ReportArgs) -> Result<()> {
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+
+    let details = db_repo.get_run_details(args.run_id).await?;
+    let html = reporting::render_html(&details);
+    std::fs::write(&args.output, html)?;
+
+    tracing::info!(run_id = %args.run_id, output = %args.output.display(), "Backtest report written.");
+    Ok(())
+}
+
+/// Handler for the `export-data` command.
+async fn handle_export_data(args: ExportDataArgs) -> Result<()> {
+    // Also runs on SQLite, like the backfill that stored the klines.
+    let db_repo = connect_repository().await?;
+    let request = ExportRequest { symbols: args.symbols, intervals: args.intervals, from: args.from, to: args.to };
+    let manifest = export_klines(&db_repo, &request, &args.output).await?;
+
+    for entry in &manifest.files {
+        println!("{}: {} klines of {} {}, {} to {}", entry.file, entry.rows, entry.symbol, entry.interval, entry.first_open_time, entry.last_open_time);
+    }
+    println!("Exported {} file(s) to {}.", manifest.files.len(), args.output.display());
+    Ok(())
+}
+
+/// Handler for the `backup` command.
+async fn handle_backup(args: BackupArgs) -> Result<()> {
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+
+    let path = match args.output {
+        Some(path) => {
+            db_repo.backup(&path, backtester::ENGINE_VERSION).await?;
+            path
+        }
+        None => {
+            let directory = load_config(None)?.backup.map_or_else(|| PathBuf::from("backups"), |backup| backup.directory);
+            backup_to_directory(&db_repo, &directory).await?
+        }
+    };
+    println!("Backed the database up to {}.", path.display());
+    Ok(())
+}
+
+/// Handler for the `restore` command.
+async fn handle_restore(args: RestoreArgs) -> Result<()> {
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+
+    let manifest = db_repo.restore(&args.file, args.replace).await?;
+    for table in &manifest.tables {
+        println!("{}: {} rows", table.name, table.rows);
+    }
+    println!(
+        "Restored the backup taken at {} by zenith {} (migration {}).",
+        manifest.created_at, manifest.engine_version, manifest.migration_version
+    );
+    Ok(())
+}
+
+/// Handler for the `data-check` command.
+async fn handle_data_check(args: DataCheckArgs) -> Result<()> {
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+
+    for coverage in db_repo.get_kline_coverage().await? {
+        if args.symbol.as_ref().is_some_and(|symbol| *symbol != coverage.symbol) {
+            continue;
+        }
+        println!(
+            "{} {}: {} klines from {} to {}, {} suspect",
+            coverage.symbol, coverage.interval, coverage.bars, coverage.first_open_time, coverage.last_open_time, coverage.suspect
+        );
+    }
+    if !args.validate {
+        return Ok(());
+    }
+
+    let suspect = db_repo.find_suspect_klines(args.symbol.as_deref()).await?;
+    for kline in &suspect {
+        match &kline.error {
+            Some(error) => println!("{}: {}{}", kline.symbol, error, if kline.is_suspect { " (flagged)" } else { "" }),
+            None => println!("{}: the {} kline opening at {} was flagged but is valid", kline.symbol, kline.kline.interval, kline.kline.open_time),
+        }
+    }
+    let invalid = suspect.iter().filter(|kline| kline.error.is_some()).count();
+    if invalid > 0 {
+        anyhow::bail!("{} stored kline(s) are invalid.", invalid);
+    }
+    println!("Every stored kline is valid.");
+    Ok(())
+}
+
+/// Handler for the `sensitivity` command.
+async fn handle_sensitivity(args: SensitivityArgs) -> Result<()> {
+    let grid = SensitivityGrid::new(args.fees, args.slippage)?;
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+
+    let spec = stored_run_spec(&db_repo, &load_config(None)?, args.run_id).await?;
+    tracing::info!("Re-running {:?} on {} {} from {} to {} at {} fee/slippage combinations.", spec.strategy_id, spec.symbol, spec.interval, spec.start, spec.end, grid.fees_pct.len() * grid.slippages.len());
+    let concurrency = args.concurrency.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |cpus| cpus.get()));
+    let matrix = run_sensitivity(&spec, grid, concurrency, args.save.then_some(&db_repo)).await?;
+
+    println!("Net profit / max drawdown of run {} by taker fee and slippage:", matrix.run_id);
+    println!("{}", sensitivity_table(&matrix));
+    if let Some(job_id) = matrix.saved_job_id {
+        println!("Saved the re-runs as job {}.", job_id);
+    }
+    Ok(())
+}
+
+/// Handler for the `import-data` command.
+async fn handle_import_data(args: ImportDataArgs) -> Result<()> {
+    let db_repo = connect_repository().await?.with_invalid_kline_policy(load_config(None)?.data.invalid_klines);
+    let imported = import_klines(&db_repo, &args.dir).await?;
+
+    for file in &imported {
+        println!(
+            "{} {}: {} klines, {} new, {} already stored; {} of {} expected bars present.",
+            file.symbol,
+            file.interval,
+            file.rows,
+            file.inserted,
+            file.rows as u64 - file.inserted,
+            file.rows,
+            file.expected_bars
+        );
+        for gap in &file.gaps {
+            println!("  {} bar(s) missing after {}", gap.missing_bars, gap.after);
+        }
+    }
+    Ok(())
+}
+
+/// Handler for the `config` command.
+fn handle_config(args: ConfigArgs) -> Result<()> {
+    match args.command {
+        ConfigCommand::Migrate(args) => handle_config_migrate(args),
+    }
+}
+
+/// Handler for the `config migrate` command.
+fn handle_config_migrate(args: MigrateArgs) -> Result<()> {
+    let kind = args.kind.or_else(|| ConfigKind::infer(&args.path)).ok_or_else(|| {
+        anyhow::anyhow!("Cannot tell which configuration {} holds; pass --kind", args.path.display())
+    })?;
+    let source = std::fs::read_to_string(&args.path)?;
+    let (report, migrated) = match kind {
+        ConfigKind::Config => migrate_source::<Config>(&source)?,
+        ConfigKind::Live => migrate_source::<LiveConfig>(&source)?,
+        ConfigKind::Optimizer => migrate_source::<OptimizerConfig>(&source)?,
+        ConfigKind::Portfolio => migrate_source::<PortfolioConfig>(&source)?,
+    };
+
+    if !report.is_outdated() {
+        println!("{} is already at config_version {}.", args.path.display(), report.file_version);
+        return Ok(());
+    }
+    if !args.write {
+        print!("{}", migrated);
+        return Ok(());
+    }
+    std::fs::write(&args.path, migrated)?;
+    println!(
+        "Migrated {} from config_version {} to {}.",
+        args.path.display(),
+        report.file_version,
+        report.current_version
+    );
+    for setting in &report.new_settings {
+        println!("  added {}", setting);
+    }
+    Ok(())
+}
+
+fn migrate_source<T: Versioned>(source: &str) -> Result<(configuration::VersionReport, String)> {
+    Ok((configuration::check_version::<T>(source)?, configuration::migrate::<T>(source)?))
+}
+
+/// Handler for the `compare` command.
+async fn handle_compare(args: CompareArgs) -> Result<()> {
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+
+    let (a, b) = tokio::try_join!(db_repo.get_run_details(args.run_id_a), db_repo.get_run_details(args.run_id_b))?;
+    let options = CompareOptions { equity_epsilon: args.epsilon, ..CompareOptions::default() };
+    let comparison = compare_runs(&a, &b, &options)?;
+
+    tracing::info!("---===[ Comparing Run {} (A) with Run {} (B) ]===---", comparison.run_a, comparison.run_b);
+    let window = &comparison.window;
+    if window.overlap_only {
+        tracing::warn!(
+            "The runs cover different ranges: they are compared over their overlap only ({} to {}), with metrics recomputed over it and left unannualized.",
+            window.start, window.end
+        );
+    } else {
+        tracing::info!("Both runs cover {} to {}.", window.start, window.end);
+    }
+
+    let optional = |value: Option<rust_decimal::Decimal>| value.map_or("-".to_string(), |v| format!("{:.2}", v));
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Metric", "A", "B", "Delta", "Delta %"]);
+    for metric in &comparison.metrics {
+        table.add_row(vec![
+            Cell::new(metric.metric),
+            Cell::new(optional(metric.a)),
+            Cell::new(optional(metric.b)),
+            Cell::new(optional(metric.delta)),
+            Cell::new(metric.delta_pct.map_or("-".to_string(), |pct| format!("{:+.2}%", pct))),
+        ]);
+    }
+    tracing::info!("{table}");
+
+    let trades = &comparison.trades;
+    tracing::info!("Trades: {} shared, {} only in A, {} only in B.", trades.shared, trades.only_in_a, trades.only_in_b);
+    match comparison.equity_divergence {
+        Some(timestamp) => tracing::info!("Equity curves first diverge by more than {} at {}.", args.epsilon, timestamp),
+        None => tracing::info!("Equity curves never diverge by more than {}.", args.epsilon),
+    }
+    Ok(())
 }
 
 async fn handle_run(args: RunArgs) -> Result<()> {
     // 1. Load Configurations
     let base_config = load_config(None)?;
     let live_config = load_live_config(&args.config)?;
+    configuration::validate_quote_assets(&base_config, &live_config)?;
+    configuration::validate_bot_risk(&base_config, &live_config)?;
+    configuration::validate_bot_intervals(&base_config, &live_config)?;
+    let quote_assets = QuoteAssets::for_live(&base_config, &live_config);
 
     // 2. Create Shared Components
     let db_pool = connect().await?;
     run_migrations(&db_pool).await?;
     let db_repo = DbRepository::new(db_pool);
     
-    // --- THE CENTRAL BROADCAST CHANNEL ---
-    let (event_tx, _) = broadcast::channel(1024);
+    // --- THE CENTRAL EVENT BUS ---
+    let event_tx = EventBus::new(EVENT_CHANNEL_CAPACITY);
+    // The engine's activity counters, served at /api/engine/stats.
+    let engine_stats = Arc::new(EngineStats::new());
 
     // 3. Instantiate and Spawn the Alerter Service (if configured)
     if let Some(alerter) = TelegramAlerter::new(&base_config.telegram) {
@@ -212,8 +773,10 @@ async fn handle_run(args: RunArgs) -> Result<()> {
     let web_server_addr = "0.0.0.0:8080".parse()?;
     let web_server_repo = db_repo.clone();
     let web_server_tx = event_tx.clone();
+    let web_server_config = base_config.clone();
+    let web_server_stats = Arc::clone(&engine_stats);
     tokio::spawn(async move {
-        if let Err(e) = web_server::run_server(web_server_addr, web_server_repo, web_server_tx).await {
+        if let Err(e) = web_server::run_server(web_server_addr, web_server_repo, web_server_tx, web_server_config, Some(web_server_stats)).await {
             tracing::error!(error = ?e, "Web server task failed.");
         }
     });
@@ -231,7 +794,12 @@ async fn handle_run(args: RunArgs) -> Result<()> {
         ExecutionMode::Paper => {
             println!("[INFO] INITIALIZING IN PAPER TRADING MODE");
             println!("[INFO] >> Live data feed | Simulated local execution <<");
-            Arc::new(SimulatedExecutor::new(base_config.simulation.clone()))
+            Arc::new(SimulatedExecutor::new(base_config.simulation.clone()).with_quote_assets(quote_assets))
+        }
+        ExecutionMode::Replay => {
+            println!("[INFO] INITIALIZING IN REPLAY MODE");
+            println!("[INFO] >> Recorded data feed | Simulated local execution <<");
+            Arc::new(SimulatedExecutor::new(base_config.simulation.clone()).with_quote_assets(quote_assets))
         }
         ExecutionMode::Testnet | ExecutionMode::Live => {
             // For both Testnet and Live, we use a real executor. The ApiClient's
@@ -242,20 +810,61 @@ async fn handle_run(args: RunArgs) -> Result<()> {
             match order_type.as_str() {
                 "Market" => {
                     println!("[INFO] >> Executor: LiveExecutor (Market Orders) <<");
-                    Arc::new(LiveExecutor::new(Arc::clone(&api_client)))
+                    Arc::new(LiveExecutor::new(Arc::clone(&api_client)).with_quote_assets(quote_assets))
                 }
                 "Limit" => {
                     println!("[INFO] >> Executor: LimitOrderExecutor (Post-Only Limit Orders) <<");
-                    Arc::new(LimitOrderExecutor::new(Arc::clone(&api_client)))
+                    Arc::new(LimitOrderExecutor::new(Arc::clone(&api_client)).with_quote_assets(quote_assets))
                 }
                 _ => anyhow::bail!("Invalid `order_type` in config.toml. Must be 'Market' or 'Limit'."),
             }
         }
     };
 
+    // Reconcile each week's P&L against the exchange once it ends.
+    if let (ExecutionMode::Testnet | ExecutionMode::Live, Some(reconciliation)) = (mode, live_config.pnl_reconciliation) {
+        tokio::spawn(run_weekly_reconciliation(Arc::clone(&api_client), db_repo.clone(), event_tx.clone(), reconciliation));
+        tracing::info!("Weekly P&L reconciliation scheduled.");
+    }
+
+    // Back the database up on the `[backup]` schedule, if one is set.
+    if let Some(backup) = base_config.backup.clone() {
+        tokio::spawn(run_scheduled_backups(db_repo.clone(), backup));
+        tracing::info!("Database backups scheduled.");
+    }
+
     // 6. Create and Run the LiveEngine (this is the main, blocking task)
     let risk_manager = Arc::new(SimpleRiskManager::new(base_config.risk_management.clone())?);
 
+    let replay_connector = match (mode, args.from, args.to) {
+        (ExecutionMode::Replay, Some(from), Some(to)) => {
+            let speed = args.replay_speed.unwrap_or(live_config.replay.speed);
+            if !speed.is_finite() || speed <= 0.0 {
+                anyhow::bail!("--replay-speed must be greater than 0.");
+            }
+            if from > to {
+                anyhow::bail!("--from must not be after --to.");
+            }
+            let start = from.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            let end = to.and_hms_opt(23, 59, 59).unwrap().and_utc();
+            println!("[INFO] >> Replaying {} to {} at {}x speed <<", from, to, speed);
+            Some(Arc::new(ReplayConnector::new(db_repo.clone(), start, end, speed, live_config.replay.spread_pct)))
+        }
+        _ => None,
+    };
+
+    // A live engine resumes the risk accounting and halts recorded before it stopped.
+    let risk_state = match replay_connector {
+        Some(_) => None,
+        None => Some(match db_repo.get_risk_state().await {
+            Ok(entries) => RiskState::from_entries(entries),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to load the recorded risk state; risk accounting starts afresh.");
+                RiskState::default()
+            }
+        }),
+    };
+
     let mut engine = LiveEngine::new(
         live_config,
         base_config,
@@ -264,72 +873,86 @@ async fn handle_run(args: RunArgs) -> Result<()> {
         db_repo,
         risk_manager,
         event_tx, // Give the engine the original sender
-    );
+    )
+    .with_stats(engine_stats);
+    if let Some(connector) = replay_connector {
+        engine = engine.with_replay(connector);
+    }
+    if let Some(risk_state) = risk_state {
+        engine = engine.with_risk_state(risk_state);
+    }
 
-    engine.run().await?;
+    // An interrupt stops the engine gracefully, writing the records it has queued.
+    tokio::select! {
+        result = engine.run() => result?,
+        _ = tokio::signal::ctrl_c() => {
+            tracing::info!("Interrupted; writing the queued records before stopping.");
+            engine.shutdown().await;
+        }
+    }
     
     tracing::info!("Engine has stopped.");
     Ok(())
 }
 
 
+/// Handler for the `validate-live` command.
+async fn handle_validate_live(args: ValidateLiveArgs) -> Result<()> {
+    let mut checklist = Checklist::default();
+    if let Some((base_config, live_config)) = load_configs(&mut checklist, &args.config) {
+        // The same exchange environment `run` would connect to in this mode.
+        let api_client = BinanceClient::new(matches!(args.mode, ExecutionMode::Live), &base_config.api);
+        check_deployment(&mut checklist, &base_config, &live_config, args.mode, &api_client).await;
+    }
+    check_database(&mut checklist, connect().await).await;
+
+    println!("{}", checklist);
+    if !checklist.passed() {
+        anyhow::bail!("{} of {} checks failed.", checklist.count(CheckStatus::Fail), checklist.checks.len());
+    }
+    Ok(())
+}
+
 // ... (all other handler functions now need to initialize their own DB connection) ...
 
 // Example modification for one handler:
 async fn handle_backfill(args: BackfillArgs) -> Result<()> {
-    let db_pool = connect().await?;
-    run_migrations(&db_pool).await?;
-    let db_repo = DbRepository::new(db_pool);
+    // Also runs on SQLite, for backtesting on a machine without PostgreSQL.
+    let config = load_config(None)?;
+    let db_repo = connect_repository().await?.with_invalid_kline_policy(config.data.invalid_klines);
     // ... rest of the function ...
     tracing::info!(
-        "Starting backfill for {} on interval {} from {} to {}",
-        args.symbol, args.from, args.interval, args.to
+        "Starting {} price backfill for {} on interval {} from {} to {}",
+        args.price_type.as_str(), args.symbol, args.interval, args.from, args.to
     );
 
-    let api_client = BinanceClient::new(false, &load_config(None)?.api);
+    let api_client: Arc<dyn ApiClient> = Arc::new(BinanceClient::new(false, &config.api));
+    validate_symbols(&[args.symbol.as_str()], Some(api_client.as_ref()), &db_repo).await?;
 
-    let date_ranges = generate_monthly_ranges(args.from, args.to);
-    
-    let progress_bar = ProgressBar::new(date_ranges.len() as u64);
+    let progress_bar = ProgressBar::new(0);
     progress_bar.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {pos}/{len} ({eta}) {msg}")?
             .progress_chars("#>-"),
     );
 
-    let tasks: Vec<_> = date_ranges
-        .into_iter()
-        .map(|(start, end)| {
-            let api_client_clone = api_client.clone();
-            let db_repo_clone = db_repo.clone();
-            let symbol = args.symbol.clone();
-            let interval = args.interval.clone();
-            let pb_clone = progress_bar.clone();
-
-            tokio::spawn(async move {
-                pb_clone.set_message(format!("Fetching {}...", start.format("%Y-%m")));
-                let klines = api_client_clone.fetch_klines(&symbol, &interval, start, end).await?;
-                
-                for kline in klines {
-                    db_repo_clone.save_kline(&symbol, &kline).await?;
-                }
-                
-                pb_clone.inc(1);
-                pb_clone.set_message(format!("Done {}!", start.format("%Y-%m")));
-                Ok::<(), anyhow::Error>(())
-            })
-        })
-        .collect();
-
-    let results = join_all(tasks).await;
-
-    progress_bar.finish_with_message("Backfill complete!");
-
-    for result in results {
-        if let Err(e) = result {
-            tracing::error!(error = %e, "A task failed.");
-        }
+    let request = BackfillRequest {
+        symbol: args.symbol,
+        interval: args.interval.to_string(),
+        price_type: args.price_type,
+        from: args.from,
+        to: args.to,
+        force: args.force,
+    };
+    let result = run_backfill(api_client, &db_repo, &request, &progress_bar).await;
+    match &result {
+        Ok(summary) => progress_bar.finish_with_message(format!(
+            "Backfill complete! {} month(s) fetched, {} skipped.",
+            summary.fetched, summary.skipped
+        )),
+        Err(_) => progress_bar.abandon_with_message("Backfill stopped; run it again to resume."),
     }
+    result?;
 
     Ok(())
 }
@@ -337,17 +960,35 @@ async fn handle_backfill(args: BackfillArgs) -> Result<()> {
 async fn handle_portfolio_run(args: PortfolioRunArgs) -> Result<()> {
     tracing::info!("---===[ Starting Portfolio-Level Backtest ]===---");
 
-    let base_config = load_config(None)?;
-    let portfolio_config = load_portfolio_config(&args.portfolio)?;
+    let mut portfolio_config = load_portfolio_config(&args.portfolio)?;
     tracing::info!("Loaded portfolio definition with {} bots.", portfolio_config.bots.len());
 
+    // CLI flags take precedence over the portfolio file, which takes precedence over config.toml.
+    if args.capital.is_some() {
+        portfolio_config.initial_capital = args.capital;
+    }
+    if args.interval.is_some() {
+        portfolio_config.interval = args.interval.map(|interval| interval.to_string());
+    }
+    validate_portfolio_config(&portfolio_config)?;
+    let base_config = portfolio_config.resolve_base_config(&load_config(None)?);
+    configuration::validate_portfolio_risk(&base_config, &portfolio_config)?;
+    tracing::info!(
+        "Portfolio capital: {}, interval: {}",
+        base_config.backtest.initial_capital,
+        base_config.backtest.interval
+    );
+
     let db_pool = connect().await?;
     run_migrations(&db_pool).await?;
     let db_repo = DbRepository::new(db_pool);
+    let symbols: Vec<&str> = portfolio_config.bots.iter().map(|bot| bot.symbol.as_str()).collect();
+    // The run reads only stored klines, so the bots' symbols are checked against those.
+    validate_symbols(&symbols, None, &db_repo).await?;
     let analytics_engine = analytics::AnalyticsEngine::new();
-    let portfolio = Portfolio::new(base_config.backtest.initial_capital);
-    let executor = Box::new(SimulatedExecutor::new(base_config.simulation.clone()));
-    let risk_manager = Box::new(SimpleRiskManager::new(base_config.risk_management.clone())?);
+    let quote_asset = &base_config.execution.quote_asset;
+    let portfolio = Portfolio::new(base_config.backtest.initial_capital).with_quote_asset(quote_asset.clone());
+    let executor = Box::new(SimulatedExecutor::new(base_config.simulation.clone()).with_quote_assets(QuoteAssets::new(quote_asset.clone())));
 
     let start_date = args.from.unwrap_or(base_config.backtest.start_date);
     let end_date = args.to.unwrap_or(base_config.backtest.end_date);
@@ -362,21 +1003,39 @@ async fn handle_portfolio_run(args: PortfolioRunArgs) -> Result<()> {
     ).await?;
     tracing::info!("Master event stream created with {} events.", event_stream.len());
 
-    let mut strategies = HashMap::<String, Box<dyn strategies::Strategy>>::new();
+    // The portfolio backtester tracks one position per symbol, so several bots on the same
+    // symbol evaluate every kline in turn and trade that one position together.
+    let (max_positions_per_symbol, overlap_policy) = (portfolio_config.max_positions_per_symbol, portfolio_config.overlap_policy);
+    let mut bots = HashMap::<String, Vec<PortfolioBot>>::new();
     for bot_config in portfolio_config.bots {
         let strategy = create_strategy_from_portfolio_config(&base_config, &bot_config)?;
-        strategies.insert(bot_config.symbol, strategy);
+        let kline_transform = KlineTransformer::new(bot_config.kline_transform);
+        // Bots with a `[bot.risk]` block size by their own settings, as they would live.
+        let risk_manager = Box::new(SimpleRiskManager::new(bot_config.risk_management(&base_config)?)?);
+        bots.entry(bot_config.symbol).or_default().push(PortfolioBot { strategy, kline_transform, direction: bot_config.direction, risk_manager });
+    }
+    for (symbol, symbol_bots) in bots.iter().filter(|(_, symbol_bots)| symbol_bots.len() > 1) {
+        match max_positions_per_symbol {
+            Some(max) => tracing::info!(
+                "{} bots trade {} and share its position, held by at most {} of them at a time ({:?} past that).",
+                symbol_bots.len(), symbol, max, overlap_policy
+            ),
+            None => tracing::warn!(
+                "{} bots trade {} and share its position without a limit, so their entries on the same side add up to more exposure than any of their single runs showed. Set `max_positions_per_symbol` to cap it.",
+                symbol_bots.len(), symbol
+            ),
+        }
     }
 
     let mut manager = PortfolioManager::new(
         base_config,
         portfolio,
-        risk_manager,
         executor,
         analytics_engine,
-        strategies,
-    );
-    
+        bots,
+    )
+    .with_position_limit(max_positions_per_symbol, overlap_policy);
+
     let report = manager.run(event_stream).await?;
 
     tracing::info!("---===[ Portfolio Backtest Report ]===---");
@@ -388,25 +1047,8 @@ fn create_strategy_from_portfolio_config(
     base_config: &configuration::Config,
     bot_config: &PortfolioBotConfig,
 ) -> Result<Box<dyn strategies::Strategy>> {
-    let mut temp_config = base_config.clone();
-    
-    match bot_config.strategy_id {
-        StrategyId::MACrossover => {
-            let params: MACrossoverParams = from_value(bot_config.params.clone())?;
-            temp_config.strategies.ma_crossover = params;
-        }
-        StrategyId::SuperTrend => {
-            let params: SuperTrendParams = from_value(bot_config.params.clone())?;
-            temp_config.strategies.super_trend = params;
-        }
-        StrategyId::ProbReversion => {
-            let params: ProbReversionParams = from_value(bot_config.params.clone())?;
-            temp_config.strategies.prob_reversion = params;
-        }
-        _ => anyhow::bail!("Portfolio backtesting for this strategy is not yet supported."),
-    }
-
-    Ok(create_strategy(bot_config.strategy_id, &temp_config, &bot_config.symbol)?)
+    let params = merge_params(bot_config.strategy_id, base_config, &bot_config.params)?;
+    Ok(create_strategy_from_params(bot_config.strategy_id, &params, &bot_config.symbol)?)
 }
 async fn handle_wfo(args: WfoArgs) -> Result<()> {
     tracing::info!("---===[ Starting Walk-Forward Optimization Job ]===---");
@@ -436,31 +1078,105 @@ async fn handle_wfo(args: WfoArgs) -> Result<()> {
 async fn handle_analyze(args: AnalyzeArgs) -> Result<()> {
     tracing::info!("---===[ Analyzing Optimization Job: {} ]===---", args.job_id);
 
-    let optimizer_config = load_optimizer_config(&args.config)?;
     let db_pool = connect().await?;
     run_migrations(&db_pool).await?;
     let db_repo = DbRepository::new(db_pool);
-    let analyzer = Analyzer::new(optimizer_config.analysis);
+    let job_config = stored_job_config(&db_repo, args.job_id).await?;
+    if args.show_config {
+        match &job_config {
+            Some(job_config) => tracing::info!("Job configuration:\n{}", serde_json::to_string_pretty(job_config)?),
+            None => tracing::warn!("Job {} was stored without its configuration.", args.job_id),
+        }
+    }
+    let analysis = match (&args.config, job_config) {
+        (Some(path), _) => load_optimizer_config(path)?.analysis,
+        (None, Some(job_config)) => job_config.optimizer.analysis,
+        (None, None) => load_optimizer_config(&PathBuf::from("optimizer.toml"))?.analysis,
+    };
+    let analyzer = Analyzer::new(analysis);
+
+    let cluster_options = ClusterOptions::default();
+    let (mut ranked_reports, funnel, mut clustering) = if args.clusters {
+        let (ranked, funnel, clustering) = analyzer.run_with_clusters(&db_repo, args.job_id, &cluster_options).await?;
+        (ranked, funnel, Some(clustering))
+    } else {
+        let (ranked, funnel) = analyzer.run_with_funnel(&db_repo, args.job_id).await?;
+        (ranked, funnel, None)
+    };
+    if args.sort_by_objective {
+        sort_by_objective(&mut ranked_reports);
+        // The best runs are now those with the best objective, so they are clustered again.
+        clustering = clustering.map(|clustering| cluster_runs(clustering.space, &ranked_reports, &cluster_options));
+    }
 
-    let ranked_reports = analyzer.run(&db_repo, args.job_id).await?;
+    let mut funnel_table = Table::new();
+    funnel_table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Filter", "Eliminated", "Remaining"]);
+    funnel_table.add_row(vec![Cell::new("(all runs)"), Cell::new(""), Cell::new(funnel.total)]);
+    for stage in &funnel.stages {
+        funnel_table.add_row(vec![Cell::new(&stage.filter), Cell::new(stage.eliminated), Cell::new(stage.remaining)]);
+    }
+    tracing::info!("{} of {} runs passed the filters:\n{funnel_table}", funnel.passed(), funnel.total);
+
+    if let Some(path) = &args.export {
+        export_ranked_reports(&ranked_reports, path)?;
+        tracing::info!("Exported {} ranked runs to {:?}.", ranked_reports.len(), path);
+    }
+    if let Some(path) = &args.emit_portfolio {
+        let job = db_repo.get_optimization_job(args.job_id).await?;
+        let portfolio = analyzer.portfolio_from_top_runs(&job, &ranked_reports, args.top)?;
+        std::fs::write(path, portfolio_toml(&job, &ranked_reports, &portfolio)?)?;
+        tracing::info!("Wrote the top {} parameter sets as a portfolio to {:?}.", args.top, path);
+    }
 
     if ranked_reports.is_empty() {
         tracing::warn!("No reports found for this job, or all were filtered out.");
         return Ok(());
     }
 
+    let Some(clustering) = clustering else {
+        let table = ranked_table(ranked_reports.iter().enumerate().take(20));
+        tracing::info!("{table}");
+        return Ok(());
+    };
+
+    if clustering.clusters.is_empty() {
+        tracing::warn!("None of the best runs have enough neighbours in the parameter grid to form a cluster.");
+    }
+    for cluster in &clustering.clusters {
+        let centroid: Vec<String> = cluster.centroid.iter().map(|(name, value)| format!("{name} = {value:.2}")).collect();
+        tracing::info!(
+            "Cluster {}: {} runs, best score {:.4}, mean score {:.4}, centroid {}",
+            cluster.cluster + 1,
+            cluster.members(),
+            cluster.best_score,
+            cluster.mean_score,
+            centroid.join(", ")
+        );
+        let members = ranked_reports.iter().enumerate().filter(|(_, ranked)| clustering.cluster_of(ranked.report.run_id) == Some(cluster.cluster));
+        let table = ranked_table(members.take(20));
+        tracing::info!("{table}");
+    }
+    Ok(())
+}
+
+/// A table of ranked runs, each with its index in the full ranking.
+fn ranked_table<'a>(rows: impl Iterator<Item = (usize, &'a RankedReport)>) -> Table {
     let mut table = Table::new();
     table
         .load_preset(UTF8_FULL)
         .set_content_arrangement(ContentArrangement::Dynamic)
         .set_header(vec![
-            "Rank", "Score", "Net Profit", "Drawdown %", "Calmar", "Profit Factor", "Trades", "Params",
+            "Rank", "Score", "Objective", "Net Profit", "Drawdown %", "Calmar", "Profit Factor", "Trades", "Params",
         ]);
 
-    for (i, ranked) in ranked_reports.iter().take(20).enumerate() {
+    for (i, ranked) in rows {
         table.add_row(vec![
             Cell::new(i + 1),
             Cell::new(format!("{:.4}", ranked.score)),
+            Cell::new(ranked.report.objective_value.map(|value| format!("{value:.4}")).unwrap_or_else(|| "-".to_string())),
             Cell::new(format!("{:.2}", ranked.report.total_net_profit.unwrap_or_default())),
             Cell::new(format!("{:.2}%", ranked.report.max_drawdown_pct.unwrap_or_default())),
             Cell::new(format!("{:.2}", ranked.report.calmar_ratio.unwrap_or_default())),
@@ -469,10 +1185,77 @@ async fn handle_analyze(args: AnalyzeArgs) -> Result<()> {
             Cell::new(ranked.report.parameters.to_string()),
         ]);
     }
+    table
+}
+/// Handler for the `prune-equity` command.
+async fn handle_prune_equity(args: PruneEquityArgs) -> Result<()> {
+    let optimizer_config = load_optimizer_config(&args.config)?;
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
 
-    tracing::info!("{table}");
+    let cutoff = Utc::now()
+        .checked_sub_signed(args.older_than)
+        .ok_or_else(|| anyhow::anyhow!("--older-than reaches back further than dates go."))?;
+    tracing::info!("Pruning equity curves of optimization jobs created before {}, keeping the top {} runs of each.", cutoff, args.keep_top);
+
+    let analyzer = Analyzer::new(optimizer_config.analysis);
+    let summary = analyzer.prune_equity_curves(&db_repo, cutoff, args.keep_top, args.points).await?;
+
+    tracing::info!(
+        "Scanned {} jobs: thinned {} equity curves, deleting {} points.",
+        summary.jobs_scanned,
+        summary.runs_pruned,
+        summary.points_deleted
+    );
+    Ok(())
+}
+
+/// Handler for the `rollup` command.
+async fn handle_rollup(args: RollupArgs) -> Result<()> {
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+
+    let yesterday = Utc::now().date_naive() - Duration::days(1);
+    match args.from {
+        Some(from) if args.recompute => {
+            let to = args.to.unwrap_or(yesterday);
+            tracing::info!("Recomputing live performance rollups from {} to {}.", from, to);
+            let summary = analyzer::rollup::recompute(&db_repo, from, to).await?;
+            tracing::info!("Recomputed {} daily and {} weekly rollups.", summary.days, summary.weeks);
+        }
+        _ => {
+            analyzer::rollup::roll_up_day(&db_repo, yesterday).await?;
+            tracing::info!("Rolled up live performance for {}.", yesterday);
+        }
+    }
+    Ok(())
+}
+
+/// Handler for the `reconcile-pnl` command.
+async fn handle_reconcile_pnl(args: ReconcilePnlArgs) -> Result<()> {
+    if args.from > args.to {
+        anyhow::bail!("--from must not be after --to.");
+    }
+    let db_pool = connect().await?;
+    run_migrations(&db_pool).await?;
+    let db_repo = DbRepository::new(db_pool);
+    let api_client = BinanceClient::new(!args.testnet, &load_config(None)?.api);
+
+    let reconciliation = reconcile_pnl(&api_client, &db_repo, args.from, args.to, args.tolerance).await?;
+    println!("{}", reconciliation_table(&reconciliation));
+    let mismatches = reconciliation.mismatches().count();
+    println!(
+        "{} of {} symbol-days match within {}; the mismatches diverge by {:.2} in total.",
+        reconciliation.days.len() - mismatches,
+        reconciliation.days.len(),
+        reconciliation.tolerance,
+        reconciliation.total_divergence()
+    );
     Ok(())
 }
+
 async fn handle_optimize(args: OptimizeArgs) -> Result<()> {
     tracing::info!("---===[ Starting Optimization Job ]===---");
 
@@ -493,114 +1276,125 @@ async fn handle_optimize(args: OptimizeArgs) -> Result<()> {
     tracing::info!("Optimization process finished.");
     Ok(())
 }
-/// Generates parameters for a strategy based on the configuration.
-fn generate_strategy_params(config: &configuration::Config, strategy_id: StrategyId) -> Result<JsonValue> {
-    match strategy_id {
-        StrategyId::MACrossover => {
-            Ok(json!({
-                "ma_fast_period": config.strategies.ma_crossover.ma_fast_period,
-                "ma_slow_period": config.strategies.ma_crossover.ma_slow_period,
-                "trend_filter_period": config.strategies.ma_crossover.trend_filter_period,
-            }))
-        },
-        StrategyId::SuperTrend => {
-            Ok(json!({
-                "atr_period": config.strategies.super_trend.atr_period,
-                "atr_multiplier": config.strategies.super_trend.atr_multiplier,
-                "adx_threshold": config.strategies.super_trend.adx_threshold,
-                "adx_period": config.strategies.super_trend.adx_period,
-            }))
-        },
-        StrategyId::ProbReversion => {
-            Ok(json!({
-                "bb_period": config.strategies.prob_reversion.bb_period,
-                "bb_std_dev": config.strategies.prob_reversion.bb_std_dev,
-                "rsi_period": config.strategies.prob_reversion.rsi_period,
-                "rsi_oversold": config.strategies.prob_reversion.rsi_oversold,
-                "rsi_overbought": config.strategies.prob_reversion.rsi_overbought,
-                "adx_threshold": config.strategies.prob_reversion.adx_threshold,
-                "adx_period": config.strategies.prob_reversion.adx_period,
-            }))
-        },
-        StrategyId::FundingRateArb => {
-            Ok(json!({
-                "target_rate_threshold": config.strategies.funding_rate_arb.target_rate_threshold,
-                "basis_safety_threshold": config.strategies.funding_rate_arb.basis_safety_threshold,
-            }))
-        },
-        StrategyId::MlStrategy => {
-            // ML strategy doesn't use traditional parameters, it uses a pre-trained model
-            // Return the model path as a parameter for tracking purposes
-            Ok(json!({
-                "model_path": config.strategies.ml_strategy.model_path.to_string_lossy(),
-            }))
-        },
+/// Renders a report's per-exit-type statistics as a table.
+fn exit_breakdown_table(breakdown: &[analytics::ExitStats]) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Close Reason", "Trades", "Net PnL", "Win Rate", "Avg PnL"]);
+    for exit in breakdown {
+        table.add_row(vec![
+            Cell::new(format!("{:?}", exit.close_reason)),
+            Cell::new(exit.trades),
+            Cell::new(format!("{:.2}", exit.net_pnl)),
+            Cell::new(format!("{:.2}%", exit.win_rate_pct)),
+            Cell::new(format!("{:.2}", exit.average_pnl)),
+        ]);
+    }
+    table
+}
+
+/// Renders a sensitivity sweep as a table with a row per fee and a column per slippage,
+/// followed by each column's breakeven fee.
+fn sensitivity_table(matrix: &SensitivityMatrix) -> Table {
+    let mut header = vec!["Taker fee".to_string()];
+    header.extend(matrix.grid.slippages.iter().map(|slippage| format!("Slippage {}", slippage)));
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL).set_content_arrangement(ContentArrangement::Dynamic).set_header(header);
+    for (fee, fee_pct) in matrix.grid.fees_pct.iter().enumerate() {
+        let mut row = vec![Cell::new(format!("{}%", fee_pct))];
+        row.extend((0..matrix.grid.slippages.len()).map(|slippage| {
+            let cell = matrix.cell(fee, slippage);
+            Cell::new(format!("{:.2} / {:.2}%", cell.net_profit(), cell.max_drawdown_pct()))
+        }));
+        table.add_row(row);
+    }
+    let (lowest, highest) = (matrix.grid.fees_pct[0], matrix.grid.fees_pct[matrix.grid.fees_pct.len() - 1]);
+    let mut breakevens = vec![Cell::new("Breakeven fee")];
+    breakevens.extend((0..matrix.grid.slippages.len()).map(|slippage| {
+        Cell::new(match matrix.breakeven_fee(slippage) {
+            Breakeven::At(fee_pct) => format!("{:.4}%", fee_pct),
+            Breakeven::AboveGrid => format!("above {}%", highest),
+            Breakeven::BelowGrid => format!("below {}%", lowest),
+        })
+    }));
+    table.add_row(breakevens);
+    table
+}
+
+/// Renders each symbol's days of a P&L reconciliation as a table, local figures against the
+/// exchange's, with the funding the exchange booked.
+fn reconciliation_table(reconciliation: &PnlReconciliation) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Day", "Symbol", "Realized PnL", "Commission", "Funding (exchange)", "Divergence", "Status"]);
+    for day in &reconciliation.days {
+        let figure = |local: rust_decimal::Decimal, exchange: rust_decimal::Decimal| Cell::new(format!("{:.4} / {:.4}", local, exchange));
+        let status = if day.matches(reconciliation.tolerance) { "Match" } else { "MISMATCH" };
+        table.add_row(vec![
+            Cell::new(day.day),
+            Cell::new(&day.symbol),
+            figure(day.local.realized_pnl, day.exchange.realized_pnl),
+            figure(day.local.commission, day.exchange.commission),
+            Cell::new(format!("{:.4}", day.funding)),
+            Cell::new(format!("{:.4}", day.total_divergence())),
+            Cell::new(status),
+        ]);
     }
+    table
 }
 
 async fn handle_single_run(args: SingleRunArgs) -> Result<()> {
     let config = load_config(None)?;
-    let db_pool = connect().await?;
-    run_migrations(&db_pool).await?;
-    let db_repo = DbRepository::new(db_pool);
+    // Also runs on SQLite, for backtesting on a machine without PostgreSQL.
+    let db_repo = connect_repository().await?;
 
     tracing::info!("---===[ Starting Single Backtest Run ]===---");
 
-    let job_id = Uuid::new_v4();
-    let run_id = Uuid::new_v4();
-    let strategy_id = config.backtest.strategy_id;
+    // The run reads only stored klines, so the symbol is checked against those.
+    validate_symbols(&[config.backtest.symbol.as_str()], None, &db_repo).await?;
 
-    let params = generate_strategy_params(&config, strategy_id)?;
-    
+    let mut spec = BacktestSpec::from_config(&config, KlineSource::Database(db_repo.clone()))?;
+    if let Some(from) = args.from {
+        spec.start = from.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Utc).unwrap();
+    }
+    if let Some(to) = args.to {
+        spec.end = to.and_hms_opt(23, 59, 59).unwrap().and_local_timezone(Utc).unwrap();
+    }
+
+    let job_id = Uuid::new_v4();
+    let run_id = spec.run_id;
     db_repo.save_optimization_job(
         job_id,
-        &format!("{:?}", strategy_id),
-        &config.backtest.symbol,
+        &format!("{:?}", spec.strategy_id),
+        &spec.symbol,
         "Single Run",
+        None,
     ).await?;
     
-    db_repo.save_backtest_run(run_id, job_id, &params, "Pending").await?;
+    db_repo.save_backtest_run(run_id, job_id, &spec.params, "Pending").await?;
     tracing::info!("Created database record for Run ID: {}", run_id);
 
-    let backtest_config = config.backtest.clone();
-    let start_date = args.from.unwrap_or(backtest_config.start_date);
-    let end_date = args.to.unwrap_or(backtest_config.end_date);
-    let symbol = backtest_config.symbol.clone();
-    let interval = backtest_config.interval.clone();
+    tracing::info!("Period: {} to {}", spec.start, spec.end);
+    tracing::info!("Symbol: {}, Interval: {}", spec.symbol, spec.interval);
+    tracing::info!("Strategy: {:?}", spec.strategy_id);
 
-    tracing::info!("Period: {} to {}", start_date, end_date);
-    tracing::info!("Symbol: {}, Interval: {}", symbol, interval);
-
-    let analytics_engine = analytics::AnalyticsEngine::new();
-    let portfolio = Portfolio::new(backtest_config.initial_capital);
-    let executor = Box::new(SimulatedExecutor::new(config.simulation.clone()));
-    let risk_manager = Box::new(SimpleRiskManager::new(config.risk_management.clone())?);
-    let strategy = create_strategy(strategy_id, &config, &config.backtest.symbol)?;
-    tracing::info!("Strategy: {:?}", strategy_id);
-
-    let mut backtester = Backtester::new(
-        run_id,
-        symbol,
-        interval,
-        config, // Pass the full config for stop-loss access
-        portfolio,
-        strategy,
-        risk_manager,
-        executor,
-        analytics_engine,
-        db_repo.clone(),
-    );
-    
-    let report_result = backtester.run(
-        start_date.and_hms_opt(0,0,0).unwrap().and_local_timezone(Utc).unwrap(),
-        end_date.and_hms_opt(23,59,59).unwrap().and_local_timezone(Utc).unwrap()
-    ).await;
+    let result = match run_backtest(spec).await {
+        Ok(output) => output.save(&db_repo).await.map(|_| output.report),
+        Err(e) => Err(e),
+    };
 
-    match report_result {
+    match result {
         Ok(report) => {
             db_repo.update_run_status(run_id, "Completed").await?;
             tracing::info!("---===[ Backtest Report (Run ID: {}) ]===---", run_id);
             tracing::info!("{:#?}", report);
+            if !report.exit_breakdown.is_empty() {
+                tracing::info!("Exits by close reason:\n{}", exit_breakdown_table(&report.exit_breakdown));
+            }
         }
         Err(e) => {
             db_repo.update_run_status(run_id, "Failed").await?;
@@ -613,30 +1407,6 @@ async fn handle_single_run(args: SingleRunArgs) -> Result<()> {
 }
 
 
-fn generate_monthly_ranges(
-    mut from: NaiveDate,
-    to: NaiveDate,
-) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
-    let mut ranges = Vec::new();
-    while from <= to {
-        let end_of_month = from
-            .with_day(1)
-            .unwrap()
-            .with_month(from.month() + 1)
-            .unwrap_or_else(|| from.with_year(from.year() + 1).unwrap().with_month(1).unwrap())
-            .pred_opt().unwrap();
-        
-        let end_date = std::cmp::min(end_of_month, to);
-        ranges.push((
-            from.and_hms_opt(0, 0, 0).unwrap().and_local_timezone(Utc).unwrap(),
-            end_date.and_hms_opt(23, 59, 59).unwrap().and_local_timezone(Utc).unwrap(),
-        ));
-        
-        from = end_date.add(Duration::days(1));
-    }
-    ranges
-}
-
 /// Initialize file logging system
 fn init_file_logging(logging_config: &configuration::LoggingConfig) {
     // Ensure the log directory exists
@@ -0,0 +1,226 @@
+//! Reconciliation of the live P&L against the exchange's income history, for
+//! `zenith reconcile-pnl` and the live engine's weekly check.
+//!
+//! For every symbol and UTC day, the realized P&L and commissions of the recorded live
+//! executions are compared with what the exchange booked. The exchange books a fill's
+//! realized P&L and commission a moment after the fill, which can be past midnight, so each
+//! of those records counts towards the day of the recorded fill on its symbol nearest in
+//! time, if one is within `BOOKING_GRACE_SECS`. The engine does not book funding, so the
+//! funding the exchange booked is reported alongside, but neither compared nor counted
+//! towards the divergence.
+
+use api_client::{ApiClient, IncomeRecord, IncomeType};
+use chrono::{DateTime, Datelike, Days, Duration, NaiveDate, Utc};
+use configuration::PnlReconciliationConfig;
+use database::{DbRepository, LiveFill};
+use events::{EventBus, LogLevel, LogMessage, WsMessage};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// How long after a fill the exchange may book its realized P&L and commission.
+pub const BOOKING_GRACE_SECS: i64 = 60;
+
+/// How long after the end of a week the weekly reconciliation waits for the exchange to book
+/// the week's last income.
+const WEEKLY_DELAY_SECS: i64 = 15 * 60;
+
+/// The compared P&L figures of a symbol's day. Commissions paid are negative.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PnlFigures {
+    pub realized_pnl: Decimal,
+    pub commission: Decimal,
+}
+
+impl PnlFigures {
+    fn minus(&self, other: &PnlFigures) -> PnlFigures {
+        PnlFigures {
+            realized_pnl: self.realized_pnl - other.realized_pnl,
+            commission: self.commission - other.commission,
+        }
+    }
+
+    fn values(&self) -> [Decimal; 2] {
+        [self.realized_pnl, self.commission]
+    }
+}
+
+/// The local and exchange figures of one symbol on one UTC day.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DayReconciliation {
+    pub day: NaiveDate,
+    pub symbol: String,
+    pub local: PnlFigures,
+    pub exchange: PnlFigures,
+    /// The funding the exchange booked, negative when paid. Not compared, as the engine
+    /// does not book funding.
+    pub funding: Decimal,
+}
+
+impl DayReconciliation {
+    /// The exchange's figures less the local ones.
+    pub fn divergence(&self) -> PnlFigures {
+        self.exchange.minus(&self.local)
+    }
+
+    /// True when no figure differs by more than `tolerance`.
+    pub fn matches(&self, tolerance: Decimal) -> bool {
+        self.divergence().values().iter().all(|value| value.abs() <= tolerance)
+    }
+
+    /// The sum of the absolute differences of the figures.
+    pub fn total_divergence(&self) -> Decimal {
+        self.divergence().values().iter().map(|value| value.abs()).sum()
+    }
+}
+
+/// The reconciliation of the days from `from` to `to`, both inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PnlReconciliation {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub tolerance: Decimal,
+    /// Every symbol and day with a recorded fill or booked income, by day, then symbol.
+    pub days: Vec<DayReconciliation>,
+}
+
+impl PnlReconciliation {
+    /// Reconciles the recorded `fills` with the exchange's `income`. Either may reach past the
+    /// range, e.g. by the booking grace; only the days in it are kept.
+    pub fn new(from: NaiveDate, to: NaiveDate, tolerance: Decimal, fills: &[LiveFill], income: &[IncomeRecord]) -> Self {
+        let mut days: BTreeMap<(NaiveDate, String), DayReconciliation> = BTreeMap::new();
+        for fill in fills {
+            let local = &mut entry(&mut days, fill.executed_at.date_naive(), &fill.symbol).local;
+            local.realized_pnl += fill.realized_pnl;
+            local.commission -= fill.fee;
+        }
+        for record in income.iter().filter(|record| !record.symbol.is_empty()) {
+            let day = match record.income_type {
+                IncomeType::RealizedPnl | IncomeType::Commission => booked_day(record, fills),
+                IncomeType::FundingFee => record.time.date_naive(),
+                IncomeType::Other => continue,
+            };
+            let reconciliation = entry(&mut days, day, &record.symbol);
+            match record.income_type {
+                IncomeType::RealizedPnl => reconciliation.exchange.realized_pnl += record.income,
+                IncomeType::Commission => reconciliation.exchange.commission += record.income,
+                IncomeType::FundingFee => reconciliation.funding += record.income,
+                IncomeType::Other => {}
+            }
+        }
+
+        Self {
+            from,
+            to,
+            tolerance,
+            days: days.into_values().filter(|day| (from..=to).contains(&day.day)).collect(),
+        }
+    }
+
+    /// The symbols' days whose figures differ by more than the tolerance.
+    pub fn mismatches(&self) -> impl Iterator<Item = &DayReconciliation> {
+        self.days.iter().filter(|day| !day.matches(self.tolerance))
+    }
+
+    /// The total absolute divergence of the mismatched days.
+    pub fn total_divergence(&self) -> Decimal {
+        self.mismatches().map(DayReconciliation::total_divergence).sum()
+    }
+}
+
+fn entry<'a>(days: &'a mut BTreeMap<(NaiveDate, String), DayReconciliation>, day: NaiveDate, symbol: &str) -> &'a mut DayReconciliation {
+    days.entry((day, symbol.to_string())).or_insert_with(|| DayReconciliation {
+        day,
+        symbol: symbol.to_string(),
+        local: PnlFigures::default(),
+        exchange: PnlFigures::default(),
+        funding: Decimal::ZERO,
+    })
+}
+
+/// The day a realized P&L or commission record belongs to: that of the recorded fill on its
+/// symbol nearest in time within the booking grace, or else its own.
+fn booked_day(record: &IncomeRecord, fills: &[LiveFill]) -> NaiveDate {
+    let grace = Duration::seconds(BOOKING_GRACE_SECS);
+    fills
+        .iter()
+        .filter(|fill| fill.symbol == record.symbol)
+        .map(|fill| ((record.time - fill.executed_at).abs(), fill.executed_at))
+        .filter(|(distance, _)| *distance <= grace)
+        .min_by_key(|(distance, _)| *distance)
+        .map_or(record.time.date_naive(), |(_, executed_at)| executed_at.date_naive())
+}
+
+/// Reconciles the days from `from` to `to`, both inclusive, from the live fills recorded in
+/// `db_repo` and the income history of the account `api_client` trades.
+pub async fn reconcile_pnl(
+    api_client: &dyn ApiClient,
+    db_repo: &DbRepository,
+    from: NaiveDate,
+    to: NaiveDate,
+    tolerance: Decimal,
+) -> anyhow::Result<PnlReconciliation> {
+    let start = from.and_time(Default::default()).and_utc();
+    let end = (to + Days::new(1)).and_time(Default::default()).and_utc();
+    let grace = Duration::seconds(BOOKING_GRACE_SECS);
+    let fills = db_repo.get_live_fills(start - grace, end + grace).await?;
+    let income = api_client.get_income_history(start, end + grace).await?;
+    Ok(PnlReconciliation::new(from, to, tolerance, &fills, &income))
+}
+
+/// Reconciles each completed week, Monday to Sunday, shortly after it ends, raising an error
+/// alert when its mismatches diverge by more than `config.alert_threshold` in total. Failures
+/// are logged and the week is skipped; `zenith reconcile-pnl` can reconcile it again.
+pub async fn run_weekly_reconciliation(api_client: Arc<dyn ApiClient>, db_repo: DbRepository, event_tx: EventBus, config: PnlReconciliationConfig) {
+    loop {
+        let now = Utc::now();
+        let run_at = next_week_start(now) + Duration::seconds(WEEKLY_DELAY_SECS);
+        tokio::time::sleep((run_at - now).to_std().unwrap_or_default()).await;
+
+        let to = run_at.date_naive() - Days::new(1);
+        let from = to - Days::new(6);
+        let (level, message) = match reconcile_pnl(api_client.as_ref(), &db_repo, from, to, config.tolerance).await {
+            Ok(reconciliation) => weekly_alert(&reconciliation, config.alert_threshold),
+            Err(e) => (LogLevel::Warn, format!("PNL RECONCILIATION: Failed to reconcile {} to {}: {:#}", from, to, e)),
+        };
+        tracing::info!("{}", message);
+        event_tx.send(WsMessage::Log(LogMessage { timestamp: Utc::now(), level, message, fields: None }));
+    }
+}
+
+/// The alert the weekly reconciliation raises for `reconciliation`.
+pub fn weekly_alert(reconciliation: &PnlReconciliation, alert_threshold: Decimal) -> (LogLevel, String) {
+    let mismatches = reconciliation.mismatches().count();
+    let divergence = reconciliation.total_divergence();
+    let summary = format!(
+        "{} of {} symbol-days from {} to {} match the exchange within {}",
+        reconciliation.days.len() - mismatches,
+        reconciliation.days.len(),
+        reconciliation.from,
+        reconciliation.to,
+        reconciliation.tolerance
+    );
+    if divergence > alert_threshold {
+        let worst: Vec<String> = reconciliation.mismatches().map(|day| format!("{} {}", day.symbol, day.day)).collect();
+        (
+            LogLevel::Error,
+            format!(
+                "PNL RECONCILIATION: {}; the mismatches diverge by {} in total, above the {} threshold ({}). Run `zenith reconcile-pnl --from {} --to {}` for details.",
+                summary,
+                divergence,
+                alert_threshold,
+                worst.join(", "),
+                reconciliation.from,
+                reconciliation.to
+            ),
+        )
+    } else {
+        (LogLevel::Info, format!("PNL RECONCILIATION: {}.", summary))
+    }
+}
+
+/// The next Monday midnight, UTC, strictly after `now`.
+fn next_week_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    let days_to_monday = 7 - u64::from(now.weekday().num_days_from_monday());
+    (now.date_naive() + Days::new(days_to_monday)).and_time(Default::default()).and_utc()
+}
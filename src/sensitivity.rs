@@ -0,0 +1,251 @@
+//! Sensitivity of a backtest run to its execution assumptions, for `zenith sensitivity`.
+//!
+//! The stored run is re-run once per combination of a taker fee and a slippage, with its
+//! strategy parameters, symbol, interval and replayed bars unchanged. The settings of the run
+//! come from its job's configuration snapshot where the job stored one (optimization and WFO
+//! jobs), and from `config.toml` otherwise; the hash of the recovered settings is checked
+//! against the one recorded with the run.
+
+use anyhow::{bail, Context, Result};
+use backtester::{run_backtest, BacktestOutput, BacktestSpec, KlineSource};
+use configuration::{Config, JobConfigSnapshot};
+use core_types::{PriceType, StrategyId};
+use database::{DbError, DbRepository};
+use futures::stream::{self, StreamExt, TryStreamExt};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use strategies::{create_strategy_from_params, merge_params};
+use uuid::Uuid;
+
+/// The job status marking the jobs `--save` records the re-runs under.
+pub const SENSITIVITY_JOB_STATUS: &str = "Sensitivity";
+
+/// The execution assumptions to re-run a backtest under.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SensitivityGrid {
+    /// Taker fees in percent, ascending: 0.04 is a `taker_fee_pct` of 0.0004. The maker fee
+    /// keeps its ratio to the taker fee.
+    pub fees_pct: Vec<Decimal>,
+    /// Slippages as `slippage_pct` takes them, a fraction of the bar's range, ascending.
+    pub slippages: Vec<Decimal>,
+}
+
+impl SensitivityGrid {
+    /// Sorts and deduplicates the values. Refuses an empty or negative list.
+    pub fn new(mut fees_pct: Vec<Decimal>, mut slippages: Vec<Decimal>) -> Result<Self> {
+        for (name, values) in [("fee", &mut fees_pct), ("slippage", &mut slippages)] {
+            if values.is_empty() {
+                bail!("At least one {} is required.", name);
+            }
+            if let Some(negative) = values.iter().find(|value| **value < Decimal::ZERO) {
+                bail!("The {} {} is negative.", name, negative);
+            }
+            values.sort();
+            values.dedup();
+        }
+        Ok(Self { fees_pct, slippages })
+    }
+}
+
+/// The result of re-running at one fee and slippage.
+#[derive(Debug, Clone)]
+pub struct SensitivityCell {
+    pub fee_pct: Decimal,
+    pub slippage: Decimal,
+    pub output: BacktestOutput,
+}
+
+impl SensitivityCell {
+    pub fn net_profit(&self) -> Decimal {
+        self.output.report.total_net_profit
+    }
+
+    pub fn max_drawdown_pct(&self) -> Decimal {
+        self.output.report.max_drawdown_pct
+    }
+}
+
+/// Where net profit crosses zero as the fee rises, at one slippage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakeven {
+    /// The taker fee in percent, interpolated linearly between the neighbouring fees.
+    At(Decimal),
+    /// Still profitable at the highest fee of the grid.
+    AboveGrid,
+    /// Not profitable even at the lowest fee of the grid.
+    BelowGrid,
+}
+
+/// The breakeven fee of `points`, pairs of a fee and the net profit at it in ascending order
+/// of fee. The first crossing counts, should the profit rise again past it.
+pub fn breakeven_fee(points: &[(Decimal, Decimal)]) -> Breakeven {
+    match points.first() {
+        None => return Breakeven::BelowGrid,
+        Some(&(fee, profit)) if profit.is_zero() => return Breakeven::At(fee),
+        Some(&(_, profit)) if profit < Decimal::ZERO => return Breakeven::BelowGrid,
+        Some(_) => {}
+    }
+    points
+        .windows(2)
+        .find(|pair| pair[1].1 <= Decimal::ZERO)
+        .map_or(Breakeven::AboveGrid, |pair| {
+            let ((low_fee, low_profit), (high_fee, high_profit)) = (pair[0], pair[1]);
+            Breakeven::At(low_fee + (high_fee - low_fee) * low_profit / (low_profit - high_profit))
+        })
+}
+
+/// The re-runs of one backtest run across a grid, by fee, then slippage.
+#[derive(Debug, Clone)]
+pub struct SensitivityMatrix {
+    /// The run that was re-run.
+    pub run_id: Uuid,
+    pub grid: SensitivityGrid,
+    pub cells: Vec<SensitivityCell>,
+    /// The job the re-runs were saved under, with `--save`.
+    pub saved_job_id: Option<Uuid>,
+}
+
+impl SensitivityMatrix {
+    /// The re-run at the `fee`th fee and the `slippage`th slippage of the grid.
+    pub fn cell(&self, fee: usize, slippage: usize) -> &SensitivityCell {
+        &self.cells[fee * self.grid.slippages.len() + slippage]
+    }
+
+    /// The breakeven fee at the `slippage`th slippage of the grid.
+    pub fn breakeven_fee(&self, slippage: usize) -> Breakeven {
+        let points: Vec<(Decimal, Decimal)> = (0..self.grid.fees_pct.len())
+            .map(|fee| self.cell(fee, slippage))
+            .map(|cell| (cell.fee_pct, cell.net_profit()))
+            .collect();
+        breakeven_fee(&points)
+    }
+}
+
+/// Rebuilds the backtest of the stored run `run_id`: its job's strategy and symbol, its own
+/// parameters over the strategy's table in `config`, and the interval and bars it replayed.
+/// The settings come from the job's configuration snapshot, or from `config` for jobs
+/// without one. A run that has not finished has no replayed bars recorded and is refused.
+pub async fn stored_run_spec(db_repo: &DbRepository, config: &Config, run_id: Uuid) -> Result<BacktestSpec> {
+    let report = match db_repo.get_full_report_for_run(run_id).await {
+        Err(DbError::NotFound) => bail!("Run {} has no performance report; only completed runs can be re-run.", run_id),
+        report => report?,
+    };
+    let job = db_repo.get_optimization_job(report.job_id).await?;
+    let range = match db_repo.get_run_kline_range(run_id).await {
+        Err(DbError::NotFound) => bail!("Run {} predates the recording of the bars it replayed, so it cannot be re-run exactly.", run_id),
+        range => range?,
+    };
+    let strategy_id: StrategyId = serde_json::from_value(serde_json::Value::String(job.strategy_id.clone()))
+        .with_context(|| format!("Job {} has an unknown strategy {}", job.job_id, job.strategy_id))?;
+    let snapshot: Option<JobConfigSnapshot> = match db_repo.get_job_config(job.job_id).await {
+        Err(DbError::NotFound) => None,
+        job_config => job_config?.map(serde_json::from_value).transpose()?,
+    };
+
+    let params = merge_params(strategy_id, config, &report.parameters)?;
+    create_strategy_from_params(strategy_id, &params, &job.symbol)?;
+    let mut spec = BacktestSpec::from_config(config, KlineSource::Database(db_repo.clone()))?;
+    spec.run_id = run_id;
+    spec.strategy_id = strategy_id;
+    spec.params = params;
+    spec.symbol = job.symbol;
+    spec.interval = range.interval;
+    spec.start = range.data_start;
+    spec.end = range.data_end;
+    if let Some(snapshot) = snapshot {
+        spec.initial_capital = snapshot.backtest.initial_capital;
+        spec.kline_transform = snapshot.backtest.kline_transform;
+        spec.direction = snapshot.backtest.direction;
+        spec.valuation_price = snapshot.backtest.valuation_price;
+        spec.simulation = snapshot.simulation;
+        spec.risk_management = snapshot.risk_management;
+    } else {
+        tracing::info!(run_id = %run_id, "The run's job stored no configuration; its settings are taken from config.toml.");
+    }
+
+    if let Some(stored) = &report.config_hash
+        && *stored != spec.config_hash()
+    {
+        tracing::warn!(run_id = %run_id, "The run was made under settings other than those recovered for it; the re-runs will not match it exactly.");
+    }
+    Ok(spec)
+}
+
+/// `spec` with the taker fee `fee_pct`, in percent, and `slippage`. The maker fee keeps its
+/// ratio to the taker fee, or equals it if the taker fee was zero.
+fn cell_spec(spec: &BacktestSpec, fee_pct: Decimal, slippage: Decimal) -> BacktestSpec {
+    let mut spec = spec.clone();
+    let taker_fee = fee_pct / Decimal::ONE_HUNDRED;
+    let simulation = &mut spec.simulation;
+    simulation.maker_fee_pct = if simulation.taker_fee_pct.is_zero() {
+        taker_fee
+    } else {
+        simulation.maker_fee_pct * taker_fee / simulation.taker_fee_pct
+    };
+    simulation.taker_fee_pct = taker_fee;
+    simulation.slippage_pct = slippage;
+    spec.run_id = Uuid::new_v4();
+    spec
+}
+
+/// Loads the klines of a database-backed `spec` once, so its re-runs share them rather than
+/// each querying the database. Runs valued at the mark price load their mark prices from the
+/// database, so they keep loading their own.
+async fn shared_klines(spec: &BacktestSpec) -> Result<KlineSource> {
+    if !matches!(spec.klines, KlineSource::Database(_)) || spec.valuation_price == PriceType::Mark {
+        return Ok(spec.klines.clone());
+    }
+    let warmup_bars = create_strategy_from_params(spec.strategy_id, &spec.params, &spec.symbol)?.required_warmup_bars();
+    let (mut klines, replayed) = spec.klines.load(&spec.symbol, &spec.interval, spec.start, spec.end, warmup_bars).await?;
+    klines.extend(replayed);
+    Ok(KlineSource::Shared(Arc::new(klines)))
+}
+
+/// Re-runs `spec` at every fee and slippage of `grid`, at most `max_concurrency` at a time.
+/// Nothing is written to the database unless `save_to` is given, in which case each re-run
+/// is saved as a run of a new job marked `SENSITIVITY_JOB_STATUS`.
+pub async fn run_sensitivity(
+    spec: &BacktestSpec,
+    grid: SensitivityGrid,
+    max_concurrency: usize,
+    save_to: Option<&DbRepository>,
+) -> Result<SensitivityMatrix> {
+    let mut spec = spec.clone();
+    spec.klines = shared_klines(&spec).await?;
+    let run_id = spec.run_id;
+
+    let combinations: Vec<(Decimal, Decimal)> = grid
+        .fees_pct
+        .iter()
+        .flat_map(|&fee_pct| grid.slippages.iter().map(move |&slippage| (fee_pct, slippage)))
+        .collect();
+    let cells: Vec<SensitivityCell> = stream::iter(combinations)
+        .map(|(fee_pct, slippage)| {
+            let spec = cell_spec(&spec, fee_pct, slippage);
+            async move {
+                let output = run_backtest(spec).await.with_context(|| format!("Re-run at fee {}% and slippage {} failed", fee_pct, slippage))?;
+                anyhow::Ok(SensitivityCell { fee_pct, slippage, output })
+            }
+        })
+        .buffered(max_concurrency.max(1))
+        .try_collect()
+        .await?;
+
+    let saved_job_id = match save_to {
+        Some(db_repo) => Some(save_cells(db_repo, &spec, &cells).await?),
+        None => None,
+    };
+    Ok(SensitivityMatrix { run_id, grid, cells, saved_job_id })
+}
+
+/// Saves every re-run as a completed run of a new job, returning the job's ID.
+async fn save_cells(db_repo: &DbRepository, spec: &BacktestSpec, cells: &[SensitivityCell]) -> Result<Uuid> {
+    let job_id = Uuid::new_v4();
+    db_repo.save_optimization_job(job_id, &format!("{:?}", spec.strategy_id), &spec.symbol, SENSITIVITY_JOB_STATUS, None).await?;
+    for cell in cells {
+        db_repo.save_backtest_run(cell.output.run_id, job_id, &spec.params, "Pending").await?;
+        cell.output.save(db_repo).await?;
+        db_repo.update_run_status(cell.output.run_id, "Completed").await?;
+    }
+    Ok(job_id)
+}
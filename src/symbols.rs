@@ -0,0 +1,48 @@
+//! Early symbol checks for the CLI commands, so a typo in a configuration or portfolio file
+//! fails before any data is fetched or simulated.
+
+use anyhow::{anyhow, Result};
+use api_client::ApiClient;
+use core_types::check_symbols;
+use database::DbRepository;
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Where `known_symbols` found the symbols it checks against.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SymbolSource {
+    /// The exchange's tradable symbols.
+    Exchange,
+    /// The symbols with stored klines, used for backtests and when the exchange cannot be reached.
+    Klines,
+}
+
+impl fmt::Display for SymbolSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SymbolSource::Exchange => f.write_str("the exchange's tradable symbols"),
+            SymbolSource::Klines => f.write_str("the symbols with stored klines"),
+        }
+    }
+}
+
+/// The symbols to check against: the exchange's tradable symbols when `api_client` is given
+/// and reachable, otherwise the symbols with stored klines. Backtests read only stored klines,
+/// so they pass no client.
+pub async fn known_symbols(api_client: Option<&dyn ApiClient>, db_repo: &DbRepository) -> Result<(BTreeSet<String>, SymbolSource)> {
+    if let Some(api_client) = api_client {
+        match api_client.get_exchange_info().await {
+            Ok(info) => return Ok((info.tradable_symbols(), SymbolSource::Exchange)),
+            Err(e) => tracing::warn!(error = %e, "Could not fetch the exchange's symbols; checking against the stored klines instead."),
+        }
+    }
+    let symbols = db_repo.get_kline_symbols().await?;
+    Ok((symbols.into_iter().collect(), SymbolSource::Klines))
+}
+
+/// Fails with every one of `symbols` missing from `known_symbols`, each with the closest
+/// known symbol as a suggestion.
+pub async fn validate_symbols(symbols: &[&str], api_client: Option<&dyn ApiClient>, db_repo: &DbRepository) -> Result<()> {
+    let (known, source) = known_symbols(api_client, db_repo).await?;
+    check_symbols(symbols.iter().copied(), &known).map_err(|unknown| anyhow!("{}\n(checked against {})", unknown, source))
+}
@@ -0,0 +1,241 @@
+//! Pre-deployment checks of a live configuration, for `zenith validate-live`.
+//!
+//! Every check the engine would only fail at startup, or worse, at its first order, is run
+//! up front without starting anything: the configurations load, every enabled bot's strategy
+//! builds, the API keys work against the environment the mode trades on, each symbol trades
+//! at its configured leverage, each kline stream exists, and the database is reachable and
+//! migrated. The results form a checklist a deploy script can gate on.
+
+use api_client::ApiClient;
+use configuration::{load_config, load_live_config, validate_bot_intervals, validate_bot_risk, validate_quote_assets, Config, ExecutionMode, LiveConfig};
+use core_types::{check_symbols, Interval};
+use database::{pending_migrations, DbError};
+use engine::util::create_strategy_from_live_config;
+use engine::DEFAULT_LEVERAGE;
+use sqlx::PgPool;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+/// The outcome of one check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Pass,
+    /// Deployable, but probably not what was intended.
+    Warn,
+    /// The engine would refuse to start or fail once running.
+    Fail,
+}
+
+impl fmt::Display for CheckStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        })
+    }
+}
+
+/// One item of the checklist.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+/// The checks run so far, in order.
+#[derive(Debug, Clone, Default)]
+pub struct Checklist {
+    pub checks: Vec<Check>,
+}
+
+impl Checklist {
+    pub fn record(&mut self, name: impl Into<String>, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(Check { name: name.into(), status, detail: detail.into() });
+    }
+
+    pub fn pass(&mut self, name: impl Into<String>, detail: impl Into<String>) {
+        self.record(name, CheckStatus::Pass, detail);
+    }
+
+    pub fn warn(&mut self, name: impl Into<String>, detail: impl Into<String>) {
+        self.record(name, CheckStatus::Warn, detail);
+    }
+
+    pub fn fail(&mut self, name: impl Into<String>, detail: impl Into<String>) {
+        self.record(name, CheckStatus::Fail, detail);
+    }
+
+    /// The status of the check named `name`, if it ran.
+    pub fn status(&self, name: &str) -> Option<CheckStatus> {
+        self.checks.iter().find(|check| check.name == name).map(|check| check.status)
+    }
+
+    /// The number of checks with `status`.
+    pub fn count(&self, status: CheckStatus) -> usize {
+        self.checks.iter().filter(|check| check.status == status).count()
+    }
+
+    /// True when no check failed. Warnings do not block a deployment.
+    pub fn passed(&self) -> bool {
+        self.count(CheckStatus::Fail) == 0
+    }
+}
+
+impl fmt::Display for Checklist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            writeln!(f, "[{}] {}: {}", check.status, check.name, check.detail)?;
+        }
+        write!(
+            f,
+            "{} passed, {} warnings, {} failed.",
+            self.count(CheckStatus::Pass),
+            self.count(CheckStatus::Warn),
+            self.count(CheckStatus::Fail)
+        )
+    }
+}
+
+/// Loads `config.toml` and the live configuration at `live_config_path` as `run` does.
+/// Returns `None`, with the failure recorded, when either does not load.
+pub fn load_configs(checklist: &mut Checklist, live_config_path: &Path) -> Option<(Config, LiveConfig)> {
+    let loaded = load_config(None).map_err(anyhow::Error::from).and_then(|base_config| {
+        let live_config = load_live_config(live_config_path)?;
+        Ok((base_config, live_config))
+    });
+    match loaded {
+        Ok(configs) => {
+            checklist.pass("Configuration", format!("config.toml and {} loaded", live_config_path.display()));
+            Some(configs)
+        }
+        Err(e) => {
+            checklist.fail("Configuration", format!("{:#}", e));
+            None
+        }
+    }
+}
+
+/// Checks that `live_config` can be deployed in `mode`, with `api_client` connected to the
+/// exchange environment the mode trades on.
+pub async fn check_deployment(checklist: &mut Checklist, base_config: &Config, live_config: &LiveConfig, mode: ExecutionMode, api_client: &dyn ApiClient) {
+    check_mode(checklist, live_config, mode);
+    match validate_quote_assets(base_config, live_config) {
+        Ok(()) => checklist.pass("Quote assets", "every bot's quote asset is a collateral asset"),
+        Err(e) => checklist.fail("Quote assets", e.to_string()),
+    }
+    match validate_bot_risk(base_config, live_config) {
+        Ok(()) => checklist.pass("Risk overrides", "every bot's risk settings are valid"),
+        Err(e) => checklist.fail("Risk overrides", e.to_string()),
+    }
+    match validate_bot_intervals(base_config, live_config) {
+        Ok(()) => checklist.pass("Bot intervals", "each symbol runs one enabled bot per interval"),
+        Err(e) => checklist.fail("Bot intervals", e.to_string()),
+    }
+
+    let bots: Vec<_> = live_config.bots.iter().filter(|bot| bot.enabled).collect();
+    if bots.is_empty() {
+        checklist.warn("Bots", "no bot is enabled; the engine would exit at once");
+        return;
+    }
+    checklist.pass("Bots", format!("{} enabled", bots.len()));
+
+    let default_interval = &base_config.backtest.interval;
+    let mut streams: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for bot in &bots {
+        let interval = bot.interval.as_deref().unwrap_or(default_interval);
+        streams.entry(interval).or_default().push(&bot.symbol);
+        let name = format!("Strategy {} {}", bot.symbol, interval);
+        match create_strategy_from_live_config(base_config, bot) {
+            Ok(_) => checklist.pass(name, format!("{:?} builds from its parameters", bot.strategy_id)),
+            Err(e) => checklist.fail(name, e.to_string()),
+        }
+    }
+    for (interval, symbols) in streams {
+        let name = format!("Kline stream {}", interval);
+        match interval.parse::<Interval>() {
+            Ok(_) => checklist.pass(name, symbols.join(", ")),
+            Err(e) => checklist.fail(name, format!("{} (for {})", e, symbols.join(", "))),
+        }
+    }
+
+    if matches!(mode, ExecutionMode::Replay) {
+        checklist.pass("Exchange", "not used by replays");
+        return;
+    }
+    let environment = if matches!(mode, ExecutionMode::Live) { "production" } else { "testnet" };
+    let keys_work = match api_client.get_account_balance().await {
+        Ok(balances) => {
+            checklist.pass("API keys", format!("authenticated on the {} exchange ({} balances)", environment, balances.len()));
+            true
+        }
+        Err(e) => {
+            checklist.fail("API keys", format!("rejected by the {} exchange: {}", environment, e));
+            false
+        }
+    };
+
+    match api_client.get_exchange_info().await {
+        Ok(info) => {
+            let tradable = info.tradable_symbols();
+            for bot in &bots {
+                let name = format!("Symbol {}", bot.symbol);
+                match check_symbols([bot.symbol.as_str()], &tradable) {
+                    Ok(()) => checklist.pass(name, "trading"),
+                    Err(unknown) => checklist.fail(name, unknown.to_string()),
+                }
+            }
+        }
+        Err(e) => checklist.fail("Exchange info", e.to_string()),
+    }
+
+    for bot in &bots {
+        let name = format!("Leverage {}", bot.symbol);
+        let leverage = bot.leverage.unwrap_or(DEFAULT_LEVERAGE);
+        if !keys_work {
+            checklist.warn(name, format!("{}x not checked without working API keys", leverage));
+            continue;
+        }
+        match api_client.get_leverage_brackets(&bot.symbol).await {
+            Ok(brackets) => match brackets.max_leverage() {
+                Some(max) if u32::from(leverage) <= max => checklist.pass(name, format!("{}x, up to {}x allowed", leverage, max)),
+                Some(max) => checklist.fail(name, format!("{}x is above the {}x the exchange allows", leverage, max)),
+                None => checklist.fail(name, "the exchange returned no leverage brackets"),
+            },
+            Err(e) => checklist.fail(name, e.to_string()),
+        }
+    }
+}
+
+/// Compares `live_trading_enabled` with the mode the deployment is meant to run in.
+fn check_mode(checklist: &mut Checklist, live_config: &LiveConfig, mode: ExecutionMode) {
+    let name = "Trading mode";
+    match (mode, live_config.live_trading_enabled) {
+        (ExecutionMode::Live, true) => checklist.pass(name, "live trading is enabled"),
+        (ExecutionMode::Live, false) => checklist.fail(name, "live mode needs `live_trading_enabled = true`; the engine would refuse to start"),
+        (mode, true) => checklist.warn(name, format!("`live_trading_enabled` is true but the deployment runs in {:?} mode", mode)),
+        (mode, false) => checklist.pass(name, format!("{:?} mode with live trading disabled", mode)),
+    }
+}
+
+/// Checks that the database `connection` reached has every migration applied.
+pub async fn check_database(checklist: &mut Checklist, connection: Result<PgPool, DbError>) {
+    let pool = match connection {
+        Ok(pool) => {
+            checklist.pass("Database connection", "connected");
+            pool
+        }
+        Err(e) => {
+            checklist.fail("Database connection", e.to_string());
+            return;
+        }
+    };
+    match pending_migrations(&pool).await {
+        Ok(pending) if pending.is_empty() => checklist.pass("Database migrations", "up to date"),
+        Ok(pending) => checklist.fail("Database migrations", format!("{} pending: {}", pending.len(), pending.join(", "))),
+        Err(e) => checklist.fail("Database migrations", e.to_string()),
+    }
+}
+
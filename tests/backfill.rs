@@ -0,0 +1,122 @@
+//! Interrupts a backfill against a real PostgreSQL database and checks that running it again
+//! only fetches the months that were not saved.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test --test backfill -- --ignored
+//! ```
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use indicatif::ProgressBar;
+use std::sync::Arc;
+use std::time::Duration;
+use testing::{MockAccount, TestDatabase};
+use zenith::backfill::{run_backfill, BackfillRequest, BackfillSummary};
+use zenith::core_types::PriceType;
+
+const SYMBOL: &str = "BTCUSDT";
+const INTERVAL: &str = "1d";
+
+/// The months of every kline range `client` was asked for, sorted.
+fn requested_months(client: &MockAccount) -> Vec<String> {
+    let mut months: Vec<_> = client.kline_requests().iter().map(|start| start.format("%Y-%m").to_string()).collect();
+    months.sort();
+    months
+}
+
+fn request(force: bool) -> BackfillRequest {
+    BackfillRequest {
+        symbol: SYMBOL.to_string(),
+        interval: INTERVAL.to_string(),
+        price_type: PriceType::Last,
+        from: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        to: NaiveDate::from_ymd_opt(2024, 6, 30).unwrap(),
+        force,
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn interrupted_backfill_resumes_with_the_remaining_months() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+
+    // The first run stalls from April on and is aborted once March is saved.
+    let april = Utc.with_ymd_and_hms(2024, 4, 1, 0, 0, 0).unwrap();
+    let stalled = Arc::new(MockAccount::new().serving_klines().hanging_from(april));
+    let task = tokio::spawn({
+        let (client, repo) = (stalled.clone(), repo.clone());
+        async move { run_backfill(client, &repo, &request(false), &ProgressBar::hidden()).await }
+    });
+
+    let end_of_march = Utc.with_ymd_and_hms(2024, 3, 31, 23, 59, 59).unwrap();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let progress = repo.get_backfill_progress(SYMBOL, INTERVAL, PriceType::Last).await.expect("read progress");
+            if progress.iter().any(|saved| saved.last_completed_range_end == end_of_march) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("the first three months were never saved");
+    task.abort();
+    assert!(task.await.unwrap_err().is_cancelled());
+
+    let progress = repo.get_backfill_progress(SYMBOL, INTERVAL, PriceType::Last).await.unwrap();
+    assert_eq!(progress.len(), 1);
+    assert_eq!(progress[0].range_start, Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap());
+    assert_eq!(progress[0].last_completed_range_end, end_of_march);
+
+    // Running it again fetches only April to June.
+    let resumed = Arc::new(MockAccount::new().serving_klines());
+    let summary = run_backfill(resumed.clone(), &repo, &request(false), &ProgressBar::hidden()).await.expect("resume backfill");
+    assert_eq!(summary, BackfillSummary { fetched: 3, skipped: 3 });
+    assert_eq!(requested_months(&resumed), ["2024-04", "2024-05", "2024-06"]);
+
+    let end_of_june = Utc.with_ymd_and_hms(2024, 6, 30, 23, 59, 59).unwrap();
+    let progress = repo.get_backfill_progress(SYMBOL, INTERVAL, PriceType::Last).await.unwrap();
+    assert_eq!(progress[0].last_completed_range_end, end_of_june);
+    let saved = repo
+        .get_klines_by_date_range(SYMBOL, INTERVAL, progress[0].range_start, end_of_june)
+        .await
+        .unwrap();
+    assert_eq!(saved.len(), 6);
+
+    // A completed backfill fetches nothing, unless forced.
+    let idle = Arc::new(MockAccount::new().serving_klines());
+    let summary = run_backfill(idle.clone(), &repo, &request(false), &ProgressBar::hidden()).await.unwrap();
+    assert_eq!(summary, BackfillSummary { fetched: 0, skipped: 6 });
+    assert!(requested_months(&idle).is_empty());
+
+    let forced = Arc::new(MockAccount::new().serving_klines());
+    let summary = run_backfill(forced.clone(), &repo, &request(true), &ProgressBar::hidden()).await.unwrap();
+    assert_eq!(summary, BackfillSummary { fetched: 6, skipped: 0 });
+    assert_eq!(requested_months(&forced).len(), 6);
+
+    db.teardown().await.expect("drop test database");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn at_most_three_months_are_fetched_at_once_oldest_first() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+
+    // Every request stalls, so the months requested are exactly those in flight.
+    let january = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    let stalled = Arc::new(MockAccount::new().serving_klines().hanging_from(january));
+    let task = tokio::spawn({
+        let (client, repo) = (stalled.clone(), repo.clone());
+        async move { run_backfill(client, &repo, &request(false), &ProgressBar::hidden()).await }
+    });
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    task.abort();
+
+    assert_eq!(requested_months(&stalled), ["2024-01", "2024-02", "2024-03"]);
+    assert!(repo.get_backfill_progress(SYMBOL, INTERVAL, PriceType::Last).await.unwrap().is_empty());
+
+    db.teardown().await.expect("drop test database");
+}
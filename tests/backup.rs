@@ -0,0 +1,207 @@
+//! Backs a seeded database up, wipes it, restores it, and checks nothing was lost; and checks
+//! the backups a restore refuses and how scheduled backups are rotated.
+//!
+//! The tests against a database are ignored by default. Run them with a reachable
+//! `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test --test backup -- --ignored
+//! ```
+
+use chrono::{TimeZone, Utc};
+use core_types::{DecisionStage, Trade};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use testing::synthetic::{gbm, SeriesSpec};
+use testing::{seed_klines, test_config, TestDatabase, TEST_SYMBOL};
+use uuid::Uuid;
+use zenith::backup::{backup_file_name, rotate_backups};
+use zenith::database::{latest_migration, run_migrations, Annotation, AnnotationFilter, AnnotationTarget, BackupManifest, DbError, DbRepository};
+use zenith::{run_backtest, BacktestSpec, KlineSource};
+
+const BARS: usize = 60 * 24;
+const SEED: u64 = 28;
+
+/// A fresh, empty directory under the system's temp directory.
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("zenith-backup-{}-{}", name, Uuid::new_v4().simple()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+#[test]
+fn rotation_keeps_the_newest_backups_and_ignores_other_files() {
+    let dir = temp_dir("rotation");
+    let names: Vec<String> = (1..=5).map(|day| backup_file_name(Utc.with_ymd_and_hms(2025, 9, day, 3, 0, 0).unwrap())).collect();
+    assert_eq!(names[0], "zenith-backup-20250901T030000Z.jsonl.gz");
+    for name in names.iter().chain([&"notes.txt".to_string(), &"zenith-backup-old.tar".to_string()]) {
+        std::fs::write(dir.join(name), b"").unwrap();
+    }
+
+    let mut deleted = rotate_backups(&dir, 2).unwrap();
+    deleted.sort();
+    assert_eq!(deleted, names[..3].iter().map(|name| dir.join(name)).collect::<Vec<_>>());
+    let mut left: Vec<String> = std::fs::read_dir(&dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+    left.sort();
+    assert_eq!(left, ["notes.txt", names[3].as_str(), names[4].as_str(), "zenith-backup-old.tar"]);
+    assert!(rotate_backups(&dir, 2).unwrap().is_empty());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+/// Saves a completed single run over seeded klines, the way the `single-run` command does,
+/// plus an annotation on it and a decision audit trail, and returns the run's ID.
+async fn seed(repo: &DbRepository) -> Uuid {
+    seed_klines(repo, TEST_SYMBOL, &gbm(&SeriesSpec::default(), BARS, 0.0001, 0.004, SEED)).await.unwrap();
+    let spec = BacktestSpec::from_config(&test_config(BARS).unwrap(), KlineSource::Database(repo.clone())).unwrap();
+    let job_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, &format!("{:?}", spec.strategy_id), &spec.symbol, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(spec.run_id, job_id, &spec.params, "Pending").await.unwrap();
+    let output = run_backtest(spec).await.unwrap();
+    output.save(repo).await.unwrap();
+    repo.update_run_status(output.run_id, "Completed").await.unwrap();
+
+    repo.save_annotation(&Annotation {
+        annotation_id: Uuid::new_v4(),
+        target_type: AnnotationTarget::Run,
+        target_id: output.run_id,
+        author: "ana".to_string(),
+        // Quotes and line breaks survive the trip through a line-per-row file.
+        text: "Looks \"too good\";\nre-check the fees".to_string(),
+        tags: vec!["review".to_string(), "fees".to_string()],
+        created_at: Utc::now(),
+    })
+    .await
+    .unwrap();
+    let decision_id = Uuid::new_v4();
+    for (sequence, stage) in [DecisionStage::Signal, DecisionStage::RiskApproved].into_iter().enumerate() {
+        repo.save_decision_audit(decision_id, stage, TEST_SYMBOL, &json!({ "stage": format!("{:?}", stage), "price": 101.5 }), sequence as u64).await.unwrap();
+    }
+    output.run_id
+}
+
+/// The number of rows in each table the manifest of a fresh backup lists.
+async fn row_counts(repo: &DbRepository, dir: &Path) -> BTreeMap<String, u64> {
+    let manifest = repo.backup(&dir.join("counts.jsonl.gz"), "test").await.unwrap();
+    manifest.tables.into_iter().map(|table| (table.name, table.rows)).collect()
+}
+
+/// The stored fields of `trades`; the IDs of their executions are made up on every read.
+fn stored_fields(trades: &[Trade]) -> Vec<serde_json::Value> {
+    trades
+        .iter()
+        .map(|trade| {
+            let (entry, exit) = (&trade.entry_execution, &trade.exit_execution);
+            json!([trade.trade_id, trade.close_reason, entry.side, entry.price, entry.quantity, entry.fee, entry.timestamp, exit.price, exit.quantity, exit.fee, exit.timestamp])
+        })
+        .collect()
+}
+
+/// Empties the database the way losing its volume would, and migrates it afresh.
+async fn wipe(db: &TestDatabase) {
+    sqlx::query("DROP SCHEMA public CASCADE").execute(&db.pool).await.unwrap();
+    sqlx::query("CREATE SCHEMA public").execute(&db.pool).await.unwrap();
+    run_migrations(&db.pool).await.unwrap();
+}
+
+/// Rewrites the manifest of the backup at `from` with `edit`, into `to`.
+fn doctor(from: &Path, to: &Path, edit: impl FnOnce(&mut BackupManifest)) {
+    let mut lines = BufReader::new(GzDecoder::new(std::fs::File::open(from).unwrap())).lines();
+    let mut manifest: BackupManifest = serde_json::from_str(&lines.next().unwrap().unwrap()).unwrap();
+    edit(&mut manifest);
+    let mut out = GzEncoder::new(std::fs::File::create(to).unwrap(), Compression::fast());
+    writeln!(out, "{}", serde_json::to_string(&manifest).unwrap()).unwrap();
+    for line in lines {
+        writeln!(out, "{}", line.unwrap()).unwrap();
+    }
+    out.finish().unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn a_wiped_database_is_restored_from_its_backup() {
+    let db = TestDatabase::create().await.unwrap();
+    let repo = db.repo();
+    let dir = temp_dir("restore");
+    let run_id = seed(&repo).await;
+
+    let counts = row_counts(&repo, &dir).await;
+    for table in ["klines", "backtest_runs", "performance_reports", "trades", "equity_curves", "annotations", "decision_audit"] {
+        assert!(counts[table] > 0, "nothing was seeded into {}", table);
+    }
+    let details = repo.get_run_details(run_id).await.unwrap();
+    let annotations = repo.get_annotations(&AnnotationFilter::default()).await.unwrap();
+    let audit_ids: Vec<i64> = sqlx::query_scalar("SELECT audit_id FROM decision_audit ORDER BY audit_id").fetch_all(&db.pool).await.unwrap();
+
+    let path = dir.join("backup.jsonl.gz");
+    let manifest = repo.backup(&path, "9.9.9").await.unwrap();
+    assert_eq!((manifest.engine_version.as_str(), manifest.migration_version), ("9.9.9", latest_migration()));
+    assert!(!dir.join("backup.jsonl.gz.partial").exists());
+    // Referenced tables come before the tables referencing them.
+    let position = |name: &str| manifest.tables.iter().position(|table| table.name == name).unwrap();
+    assert!(position("optimization_jobs") < position("backtest_runs"));
+    assert!(position("backtest_runs") < position("trades"));
+
+    // A database holding data is only overwritten when asked to.
+    assert!(matches!(repo.restore(&path, false).await, Err(DbError::NotEmpty(_))));
+
+    wipe(&db).await;
+    assert!(row_counts(&repo, &dir).await.values().all(|rows| *rows == 0));
+    assert_eq!(repo.restore(&path, false).await.unwrap(), manifest);
+
+    assert_eq!(row_counts(&repo, &dir).await, counts);
+    let restored = repo.get_run_details(run_id).await.unwrap();
+    assert_eq!(serde_json::to_value(&restored.report).unwrap(), serde_json::to_value(&details.report).unwrap());
+    assert_eq!(serde_json::to_value(&restored.equity_curve).unwrap(), serde_json::to_value(&details.equity_curve).unwrap());
+    assert_eq!(stored_fields(&restored.trades), stored_fields(&details.trades));
+    assert_eq!(repo.get_annotations(&AnnotationFilter::default()).await.unwrap(), annotations);
+
+    // New audit rows continue after the restored ones rather than colliding with them.
+    repo.save_decision_audit(Uuid::new_v4(), DecisionStage::Executed, TEST_SYMBOL, &json!({}), 1).await.unwrap();
+    let next: i64 = sqlx::query_scalar("SELECT MAX(audit_id) FROM decision_audit").fetch_one(&db.pool).await.unwrap();
+    assert!(next > *audit_ids.last().unwrap());
+
+    // And --replace restores over the data again.
+    repo.restore(&path, true).await.unwrap();
+    assert_eq!(row_counts(&repo, &dir).await, counts);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+    db.teardown().await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn backups_from_a_newer_schema_or_format_are_refused_untouched() {
+    let db = TestDatabase::create().await.unwrap();
+    let repo = db.repo();
+    let dir = temp_dir("refuse");
+    seed(&repo).await;
+    let path = dir.join("backup.jsonl.gz");
+    repo.backup(&path, "test").await.unwrap();
+    let counts = row_counts(&repo, &dir).await;
+
+    let newer = dir.join("newer.jsonl.gz");
+    doctor(&path, &newer, |manifest| manifest.migration_version = latest_migration() + 1);
+    match repo.restore(&newer, true).await {
+        Err(DbError::NewerSchema { backup, supported }) => assert_eq!((backup, supported), (latest_migration() + 1, latest_migration())),
+        other => panic!("a newer schema was not refused: {:?}", other),
+    }
+
+    let other_format = dir.join("format.jsonl.gz");
+    doctor(&path, &other_format, |manifest| manifest.format_version += 1);
+    assert!(matches!(repo.restore(&other_format, true).await, Err(DbError::InvalidBackup(_))));
+
+    // A manifest promising more rows than the file holds fails part way, and rolls back.
+    let short = dir.join("short.jsonl.gz");
+    doctor(&path, &short, |manifest| manifest.tables.last_mut().unwrap().rows += 1);
+    assert!(matches!(repo.restore(&short, true).await, Err(DbError::InvalidBackup(_))));
+
+    assert_eq!(row_counts(&repo, &dir).await, counts);
+    std::fs::remove_dir_all(&dir).unwrap();
+    db.teardown().await.unwrap();
+}
@@ -0,0 +1,132 @@
+//! Exports klines to Parquet and imports them into another database, checking that every
+//! decimal comes back with the same digits and scale, and that the manifest is enforced.
+//!
+//! The PostgreSQL round trip is ignored by default. Run it with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test --test kline_export -- --ignored
+//! ```
+
+use chrono::Duration;
+use rust_decimal::Decimal;
+use std::path::PathBuf;
+use std::str::FromStr;
+use testing::{generate_klines, seed_start, SqliteTestDatabase, TestDatabase, TEST_INTERVAL, TEST_SYMBOL};
+use uuid::Uuid;
+use zenith::core_types::{Interval, Kline};
+use zenith::database::DbRepository;
+use zenith::kline_export::{export_klines, find_gaps, import_klines, ExportRequest, KlineGap};
+
+/// A fresh directory for one export.
+fn export_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("zenith_export_{}", Uuid::new_v4().simple()))
+}
+
+fn decimal(text: &str) -> Decimal {
+    Decimal::from_str_exact(text).unwrap()
+}
+
+/// Hourly klines whose prices and volumes have many decimal places, trailing zeros and
+/// tiny magnitudes, with the bars at 5 and 6 missing. Each bar's prices are ordered so it
+/// passes validation: the lowest is its low and the highest its high.
+fn klines() -> Vec<Kline> {
+    let mut klines = generate_klines(12);
+    let prices = ["27123.456789012345678", "0.00000001", "100.10", "42.000000000000000000", "1.2345678901234567890123456"];
+    for (i, kline) in klines.iter_mut().enumerate() {
+        let mut bar: Vec<Decimal> = (0..4).map(|j| decimal(prices[(i + j) % prices.len()])).collect();
+        bar.sort();
+        // Alternate rising and falling bars.
+        let (open, close) = if i % 2 == 0 { (bar[1], bar[2]) } else { (bar[2], bar[1]) };
+        (kline.low, kline.open, kline.close, kline.high) = (bar[0], open, close, bar[3]);
+        kline.volume = decimal("1234.567800000000000001");
+    }
+    klines.drain(5..7);
+    klines
+}
+
+fn request() -> ExportRequest {
+    let day = seed_start().date_naive();
+    ExportRequest { symbols: vec![TEST_SYMBOL.to_string()], intervals: vec![Interval::H1], from: day, to: day }
+}
+
+/// Each kline's fields, with every decimal as its exact binary representation.
+fn exact(klines: &[Kline]) -> Vec<(String, [[u8; 16]; 5], String)> {
+    klines
+        .iter()
+        .map(|kline| {
+            let decimals = [kline.open, kline.high, kline.low, kline.close, kline.volume].map(|value| value.serialize());
+            (format!("{:?}/{:?}", kline.open_time, kline.close_time), decimals, kline.interval.clone())
+        })
+        .collect()
+}
+
+async fn stored(repo: &DbRepository) -> Vec<Kline> {
+    let start = seed_start();
+    repo.get_klines_by_date_range(TEST_SYMBOL, TEST_INTERVAL, start, start + Duration::days(1)).await.unwrap()
+}
+
+/// Exports the klines of `source` and imports them into `target`, checking the copy is exact.
+async fn round_trip(source: &DbRepository, target: &DbRepository) {
+    let klines = klines();
+    assert_eq!(source.save_klines(TEST_SYMBOL, &klines).await.unwrap(), klines.len() as u64);
+    assert_eq!(exact(&stored(source).await), exact(&klines), "the source must store the klines exactly");
+
+    let dir = export_dir();
+    let manifest = export_klines(source, &request(), &dir).await.unwrap();
+    assert_eq!(manifest.files.len(), 1);
+    assert_eq!(manifest.files[0].rows, klines.len());
+
+    let imported = import_klines(target, &dir).await.unwrap();
+    assert_eq!((imported[0].rows, imported[0].inserted, imported[0].expected_bars), (10, 10, 12));
+    assert_eq!(imported[0].gaps, [KlineGap { after: klines[4].open_time, missing_bars: 2 }]);
+    assert_eq!(exact(&stored(target).await), exact(&klines));
+
+    // Importing again adds nothing.
+    assert_eq!(import_klines(target, &dir).await.unwrap()[0].inserted, 0);
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test]
+async fn a_sqlite_round_trip_keeps_every_decimal_exactly() {
+    let (source, target) = (SqliteTestDatabase::create().await.unwrap(), SqliteTestDatabase::create().await.unwrap());
+    round_trip(&source.repo(), &target.repo()).await;
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn a_postgres_round_trip_keeps_every_decimal_exactly() {
+    let (source, target) = (TestDatabase::create().await.unwrap(), TestDatabase::create().await.unwrap());
+    round_trip(&source.repo(), &target.repo()).await;
+}
+
+#[tokio::test]
+async fn an_altered_export_loads_nothing() {
+    let source = SqliteTestDatabase::create().await.unwrap();
+    source.repo().save_klines(TEST_SYMBOL, &klines()).await.unwrap();
+    let dir = export_dir();
+    let manifest = export_klines(&source.repo(), &request(), &dir).await.unwrap();
+
+    let path = dir.join(&manifest.files[0].file);
+    let mut bytes = std::fs::read(&path).unwrap();
+    let middle = bytes.len() / 2;
+    bytes[middle] ^= 0xff;
+    std::fs::write(&path, bytes).unwrap();
+
+    let target = SqliteTestDatabase::create().await.unwrap();
+    let error = import_klines(&target.repo(), &dir).await.unwrap_err();
+    assert!(error.to_string().contains("checksum"), "{}", error);
+    assert!(stored(&target.repo()).await.is_empty());
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn gaps_are_counted_in_bars() {
+    let klines = generate_klines(10);
+    let with_gaps: Vec<Kline> = klines.iter().enumerate().filter(|(i, _)| ![2, 6, 7, 8].contains(i)).map(|(_, kline)| kline.clone()).collect();
+    let interval = Interval::from_str(TEST_INTERVAL).unwrap();
+    assert_eq!(
+        find_gaps(&with_gaps, interval),
+        [KlineGap { after: klines[1].open_time, missing_bars: 1 }, KlineGap { after: klines[5].open_time, missing_bars: 3 }]
+    );
+    assert!(find_gaps(&klines, interval).is_empty());
+}
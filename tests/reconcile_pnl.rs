@@ -0,0 +1,139 @@
+//! Reconciles recorded live fills against fixture income history payloads, as returned by
+//! Binance's `GET /fapi/v1/income`.
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+use events::LogLevel;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+use zenith::api_client::IncomeRecord;
+use zenith::core_types::OrderSide;
+use zenith::database::LiveFill;
+use zenith::reconcile_pnl::{weekly_alert, PnlFigures, PnlReconciliation};
+
+fn day(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+}
+
+fn at(day: u32, hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 3, day, hour, minute, second).unwrap()
+}
+
+/// A closing fill on `symbol` at `executed_at`.
+fn fill(symbol: &str, realized_pnl: Decimal, fee: Decimal, executed_at: DateTime<Utc>) -> LiveFill {
+    LiveFill {
+        execution_id: Uuid::new_v4(),
+        position_id: Uuid::new_v4(),
+        symbol: symbol.to_string(),
+        position_side: OrderSide::Buy,
+        is_entry: false,
+        price: dec!(100),
+        quantity: dec!(1),
+        fee,
+        fee_asset: "USDT".to_string(),
+        is_maker: false,
+        realized_pnl,
+        close_reason: None,
+        executed_at,
+    }
+}
+
+fn income(payload: &str) -> Vec<IncomeRecord> {
+    serde_json::from_str(payload).expect("an income payload")
+}
+
+/// A BTCUSDT close on March 1st realizing 50 for a fee of 2, and the income the exchange
+/// booked for it, 0.004 apart, with a transfer that is not reconciled.
+fn btc_day() -> (Vec<LiveFill>, Vec<IncomeRecord>) {
+    let fills = vec![fill("BTCUSDT", dec!(50), dec!(2), at(1, 10, 0, 0))];
+    let income = income(
+        r#"[
+            {"symbol": "BTCUSDT", "incomeType": "REALIZED_PNL", "income": "50.004", "asset": "USDT", "info": "", "time": 1740823200000, "tranId": 101, "tradeId": "7001"},
+            {"symbol": "BTCUSDT", "incomeType": "COMMISSION", "income": "-2.00000000", "asset": "USDT", "info": "", "time": 1740823200000, "tranId": 102, "tradeId": "7001"},
+            {"symbol": "", "incomeType": "TRANSFER", "income": "1000", "asset": "USDT", "info": "TRANSFER", "time": 1740830400000, "tranId": 103, "tradeId": ""}
+        ]"#,
+    );
+    (fills, income)
+}
+
+#[test]
+fn differences_within_the_tolerance_match() {
+    let (fills, income) = btc_day();
+
+    let reconciliation = PnlReconciliation::new(day(1), day(1), dec!(0.01), &fills, &income);
+    assert_eq!(reconciliation.days.len(), 1);
+    let btc = &reconciliation.days[0];
+    assert_eq!((btc.day, btc.symbol.as_str()), (day(1), "BTCUSDT"));
+    assert_eq!(btc.local, PnlFigures { realized_pnl: dec!(50), commission: dec!(-2) });
+    assert_eq!(btc.divergence().realized_pnl, dec!(0.004));
+    assert!(btc.matches(dec!(0.01)));
+    assert_eq!(reconciliation.mismatches().count(), 0);
+    assert_eq!(reconciliation.total_divergence(), Decimal::ZERO);
+
+    // A tighter tolerance flags the same day.
+    let reconciliation = PnlReconciliation::new(day(1), day(1), dec!(0.001), &fills, &income);
+    assert_eq!(reconciliation.mismatches().count(), 1);
+    assert_eq!(reconciliation.total_divergence(), dec!(0.004));
+}
+
+#[test]
+fn a_commission_booked_after_midnight_counts_towards_its_fill_s_day() {
+    let fills = vec![fill("SOLUSDT", dec!(-20), dec!(1.2), at(1, 23, 59, 50))];
+    // The realized P&L is booked on March 1st, the commission 15 seconds later, on March 2nd.
+    let income = income(
+        r#"[
+            {"symbol": "SOLUSDT", "incomeType": "REALIZED_PNL", "income": "-20", "asset": "USDT", "time": 1740873590000, "tranId": 201, "tradeId": "8001"},
+            {"symbol": "SOLUSDT", "incomeType": "COMMISSION", "income": "-1.2", "asset": "USDT", "time": 1740873605000, "tranId": 202, "tradeId": "8001"}
+        ]"#,
+    );
+    assert_eq!(income[1].time, at(2, 0, 0, 5));
+
+    let reconciliation = PnlReconciliation::new(day(1), day(2), dec!(0.01), &fills, &income);
+    assert_eq!(reconciliation.days.len(), 1);
+    assert_eq!(reconciliation.days[0].day, day(1));
+    assert_eq!(reconciliation.days[0].exchange.commission, dec!(-1.2));
+    assert_eq!(reconciliation.mismatches().count(), 0);
+
+    // Reconciling March 2nd alone leaves nothing to compare.
+    assert!(PnlReconciliation::new(day(2), day(2), dec!(0.01), &fills, &income).days.is_empty());
+}
+
+#[test]
+fn unrecorded_income_is_flagged_on_its_own_day_and_funding_only_reported() {
+    let fills = vec![fill("ETHUSDT", dec!(12), dec!(0.5), at(3, 8, 0, 0))];
+    // The funding payment is not booked locally, and the P&L of a trade the engine never
+    // recorded shows up three hours after the last fill. A funding payment on its own day
+    // matches.
+    let income = income(
+        r#"[
+            {"symbol": "ETHUSDT", "incomeType": "REALIZED_PNL", "income": "12", "asset": "USDT", "time": 1740988800000, "tranId": 301, "tradeId": "9001"},
+            {"symbol": "ETHUSDT", "incomeType": "COMMISSION", "income": "-0.5", "asset": "USDT", "time": 1740988800000, "tranId": 302, "tradeId": "9001"},
+            {"symbol": "ETHUSDT", "incomeType": "FUNDING_FEE", "income": "-0.35", "asset": "USDT", "time": 1740988800000, "tranId": 303, "tradeId": ""},
+            {"symbol": "ETHUSDT", "incomeType": "REALIZED_PNL", "income": "-7.5", "asset": "USDT", "time": 1740999600000, "tranId": 304, "tradeId": "9002"},
+            {"symbol": "ETHUSDT", "incomeType": "FUNDING_FEE", "income": "0.2", "asset": "USDT", "time": 1741075200000, "tranId": 305, "tradeId": ""}
+        ]"#,
+    );
+
+    let reconciliation = PnlReconciliation::new(day(3), day(4), dec!(0.01), &fills, &income);
+    let mismatches: Vec<_> = reconciliation.mismatches().collect();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches[0].divergence(), PnlFigures { realized_pnl: dec!(-7.5), commission: Decimal::ZERO });
+    assert_eq!(mismatches[0].funding, dec!(-0.35));
+    assert_eq!(reconciliation.total_divergence(), dec!(7.5));
+    assert_eq!((reconciliation.days[1].day, reconciliation.days[1].funding), (day(4), dec!(0.2)));
+    assert!(reconciliation.days[1].matches(dec!(0.01)));
+}
+
+#[test]
+fn the_weekly_alert_is_raised_above_the_threshold() {
+    let (fills, income) = btc_day();
+    let reconciliation = PnlReconciliation::new(day(1), day(7), dec!(0.001), &fills, &income);
+
+    let (level, message) = weekly_alert(&reconciliation, dec!(5));
+    assert_eq!(level, LogLevel::Info);
+    assert_eq!(message, "PNL RECONCILIATION: 0 of 1 symbol-days from 2025-03-01 to 2025-03-07 match the exchange within 0.001.");
+
+    let (level, message) = weekly_alert(&reconciliation, dec!(0.001));
+    assert_eq!(level, LogLevel::Error);
+    assert!(message.contains("diverge by 0.004 in total, above the 0.001 threshold (BTCUSDT 2025-03-01)"), "{}", message);
+}
@@ -0,0 +1,117 @@
+//! Re-runs a stored backtest across fees and slippages, checking the recovered run matches
+//! the original and that the costs eat into its profit as they should.
+//!
+//! The re-runs of a stored run are ignored by default. Run them with a reachable
+//! `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test --test sensitivity -- --ignored
+//! ```
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use testing::synthetic::{gbm, SeriesSpec};
+use testing::{seed_klines, test_config, TestDatabase, TEST_SYMBOL};
+use uuid::Uuid;
+use zenith::database::DbRepository;
+use zenith::sensitivity::{breakeven_fee, run_sensitivity, stored_run_spec, Breakeven, SensitivityGrid, SENSITIVITY_JOB_STATUS};
+use zenith::{run_backtest, BacktestSpec, KlineSource};
+
+const BARS: usize = 60 * 24;
+const SEED: u64 = 28;
+
+#[test]
+fn the_breakeven_fee_is_interpolated_at_the_first_crossing() {
+    let points = [(dec!(0.02), dec!(100)), (dec!(0.04), dec!(20)), (dec!(0.06), dec!(-60)), (dec!(0.08), dec!(10))];
+    assert_eq!(breakeven_fee(&points), Breakeven::At(dec!(0.045)));
+    assert_eq!(breakeven_fee(&points[..2]), Breakeven::AboveGrid);
+    assert_eq!(breakeven_fee(&points[2..]), Breakeven::BelowGrid);
+    assert_eq!(breakeven_fee(&[(dec!(0.02), dec!(50)), (dec!(0.04), Decimal::ZERO)]), Breakeven::At(dec!(0.04)));
+    assert_eq!(breakeven_fee(&[(dec!(0.02), Decimal::ZERO)]), Breakeven::At(dec!(0.02)));
+    assert_eq!(breakeven_fee(&[]), Breakeven::BelowGrid);
+}
+
+#[test]
+fn the_grid_is_sorted_and_refuses_negative_or_missing_values() {
+    let grid = SensitivityGrid::new(vec![dec!(0.06), dec!(0.02), dec!(0.04), dec!(0.02)], vec![dec!(0.1), dec!(0.01)]).unwrap();
+    assert_eq!(grid.fees_pct, [dec!(0.02), dec!(0.04), dec!(0.06)]);
+    assert_eq!(grid.slippages, [dec!(0.01), dec!(0.1)]);
+    assert!(SensitivityGrid::new(vec![dec!(-0.02)], vec![dec!(0.1)]).is_err());
+    assert!(SensitivityGrid::new(vec![dec!(0.02)], Vec::new()).is_err());
+}
+
+/// Saves a completed single run over a seeded uptrend, the way the `single-run` command
+/// does, and returns its ID and net profit.
+async fn single_run(repo: &DbRepository) -> (Uuid, Decimal) {
+    seed_klines(repo, TEST_SYMBOL, &gbm(&SeriesSpec::default(), BARS, 0.0001, 0.004, SEED)).await.unwrap();
+    let spec = BacktestSpec::from_config(&test_config(BARS).unwrap(), KlineSource::Database(repo.clone())).unwrap();
+    let job_id = Uuid::new_v4();
+    repo.save_optimization_job(job_id, &format!("{:?}", spec.strategy_id), &spec.symbol, "Single Run", None).await.unwrap();
+    repo.save_backtest_run(spec.run_id, job_id, &spec.params, "Pending").await.unwrap();
+    let output = run_backtest(spec).await.unwrap();
+    output.save(repo).await.unwrap();
+    repo.update_run_status(output.run_id, "Completed").await.unwrap();
+    (output.run_id, output.report.total_net_profit)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn profit_falls_as_costs_rise_and_crosses_zero_at_the_breakeven_fee() {
+    let db = TestDatabase::create().await.unwrap();
+    let repo = db.repo();
+    let (run_id, net_profit) = single_run(&repo).await;
+    let config = test_config(BARS).unwrap();
+
+    // The recovered run is the original: same settings, same result at its own costs.
+    let spec = stored_run_spec(&repo, &config, run_id).await.unwrap();
+    let stored = repo.get_full_report_for_run(run_id).await.unwrap();
+    assert_eq!(Some(spec.config_hash()), stored.config_hash);
+    let (fee_pct, slippage) = (config.simulation.taker_fee_pct * Decimal::ONE_HUNDRED, config.simulation.slippage_pct);
+    let original = run_sensitivity(&spec, SensitivityGrid::new(vec![fee_pct], vec![slippage]).unwrap(), 1, None).await.unwrap();
+    assert_eq!(original.cell(0, 0).net_profit(), net_profit);
+
+    // The run trades rarely, so it takes fees far above real ones to lose its edge.
+    let fees = vec![dec!(0), dec!(0.02), dec!(0.04), dec!(0.06), dec!(0.1), dec!(0.5), dec!(1), dec!(2)];
+    let grid = SensitivityGrid::new(fees, vec![dec!(0), dec!(0.05), dec!(0.1)]).unwrap();
+    let jobs_before = repo.get_all_optimization_jobs().await.unwrap().len();
+    let matrix = run_sensitivity(&spec, grid.clone(), 4, None).await.unwrap();
+    assert_eq!((matrix.run_id, matrix.cells.len(), matrix.saved_job_id), (run_id, 24, None));
+    assert_eq!(repo.get_all_optimization_jobs().await.unwrap().len(), jobs_before, "an unsaved sweep wrote a job");
+
+    for fee in 0..grid.fees_pct.len() {
+        let profits: Vec<Decimal> = (0..grid.slippages.len()).map(|slippage| matrix.cell(fee, slippage).net_profit()).collect();
+        assert!(profits.windows(2).all(|pair| pair[1] < pair[0]), "profit does not fall with the slippage: {:?}", profits);
+    }
+    for slippage in 0..grid.slippages.len() {
+        let profits: Vec<Decimal> = (0..grid.fees_pct.len()).map(|fee| matrix.cell(fee, slippage).net_profit()).collect();
+        assert!(profits.windows(2).all(|pair| pair[1] < pair[0]), "profit does not fall with the fee: {:?}", profits);
+        if slippage == 0 {
+            // Without slippage, it still pays at the highest fee.
+            assert_eq!(matrix.breakeven_fee(slippage), Breakeven::AboveGrid);
+            continue;
+        }
+
+        // With slippage, the run pays at low fees and not at the highest, so the breakeven lies
+        // in between, where a re-run at it makes next to nothing.
+        let Breakeven::At(breakeven) = matrix.breakeven_fee(slippage) else {
+            panic!("no breakeven within the grid: {:?}", profits);
+        };
+        let above = grid.fees_pct.iter().position(|&fee| fee >= breakeven).unwrap();
+        assert!(profits[above] <= Decimal::ZERO && profits[above - 1] > Decimal::ZERO);
+        let at_breakeven = run_sensitivity(&spec, SensitivityGrid::new(vec![breakeven], vec![grid.slippages[slippage]]).unwrap(), 1, None).await.unwrap();
+        let spread = profits[above - 1] - profits[above];
+        assert!(at_breakeven.cell(0, 0).net_profit().abs() < spread / dec!(10), "{} at a breakeven of {}%", at_breakeven.cell(0, 0).net_profit(), breakeven);
+    }
+
+    // Saved re-runs land under a job of their own, one completed run per cell.
+    let small = SensitivityGrid::new(vec![dec!(0.02), dec!(0.04)], vec![dec!(0.1)]).unwrap();
+    let saved = run_sensitivity(&spec, small, 2, Some(&repo)).await.unwrap();
+    let job = repo.get_optimization_job(saved.saved_job_id.unwrap()).await.unwrap();
+    assert_eq!(job.job_status, SENSITIVITY_JOB_STATUS);
+    let mut reports = repo.get_full_reports_for_job(job.job_id).await.unwrap();
+    reports.sort_by_key(|report| report.total_net_profit);
+    let expected: Vec<Option<Decimal>> = saved.cells.iter().rev().map(|cell| Some(cell.net_profit())).collect();
+    assert_eq!(reports.iter().map(|report| report.total_net_profit).collect::<Vec<_>>(), expected);
+
+    db.teardown().await.unwrap();
+}
@@ -0,0 +1,30 @@
+//! Checks symbols against the stored klines, as the CLI does when the exchange is offline.
+//!
+//! These tests are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test --test symbols -- --ignored
+//! ```
+
+use testing::{generate_klines, seed_klines, TestDatabase, TEST_SYMBOL};
+use zenith::symbols::{known_symbols, validate_symbols, SymbolSource};
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn without_an_exchange_symbols_are_checked_against_the_stored_klines() {
+    let db = TestDatabase::create().await.expect("create test database");
+    let repo = db.repo();
+    let klines = generate_klines(10);
+    seed_klines(&repo, TEST_SYMBOL, &klines).await.expect("seed klines");
+    seed_klines(&repo, "BTCUSDT", &klines).await.expect("seed klines");
+
+    let (known, source) = known_symbols(None, &repo).await.unwrap();
+    assert_eq!(source, SymbolSource::Klines);
+    assert_eq!(known.into_iter().collect::<Vec<_>>(), ["BTCUSDT", TEST_SYMBOL]);
+
+    validate_symbols(&["BTCUSDT", TEST_SYMBOL], None, &repo).await.expect("known symbols pass");
+    let error = validate_symbols(&["BTCUSD"], None, &repo).await.unwrap_err();
+    assert_eq!(error.to_string(), "symbol 'BTCUSD' not found; did you mean BTCUSDT?\n(checked against the symbols with stored klines)");
+
+    db.teardown().await.expect("drop test database");
+}
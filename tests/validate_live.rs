@@ -0,0 +1,204 @@
+//! Runs the pre-deployment checks of `zenith validate-live` against a mock exchange, with
+//! deliberately broken configurations.
+//!
+//! The database checks are ignored by default. Run them with a reachable `DATABASE_URL`:
+//!
+//! ```text
+//! cargo test --test validate_live -- --ignored
+//! ```
+
+use configuration::{Config, ExecutionMode, LiveBotConfig, LiveConfig, Versioned};
+use rust_decimal_macros::dec;
+use std::path::Path;
+use testing::{MockAccount, TestDatabase};
+use zenith::api_client::LeverageBracket;
+use zenith::core_types::StrategyId;
+use zenith::database::DbError;
+use zenith::validate_live::{check_database, check_deployment, load_configs, CheckStatus, Checklist};
+
+/// An exchange trading BTCUSDT and ETHUSDT at up to 20x, which panics if the checks change
+/// the account.
+fn exchange() -> MockAccount {
+    let bracket = |bracket, initial_leverage, notional_floor, notional_cap| LeverageBracket {
+        bracket,
+        initial_leverage,
+        notional_cap,
+        notional_floor,
+        maint_margin_ratio: dec!(0.004),
+    };
+    MockAccount::new()
+        .with_balance("USDT", dec!(1000))
+        .with_symbols(&["BTCUSDT", "ETHUSDT"])
+        .with_leverage_brackets(vec![bracket(1, 20, dec!(0), dec!(50000)), bracket(2, 10, dec!(50000), dec!(250000))])
+        .read_only()
+}
+
+fn bot(symbol: &str, leverage: u8, params: serde_json::Value) -> LiveBotConfig {
+    LiveBotConfig {
+        enabled: true,
+        symbol: symbol.to_string(),
+        strategy_id: StrategyId::MACrossover,
+        interval: Some("1m".to_string()),
+        leverage: Some(leverage),
+        kline_transform: Default::default(),
+        direction: Default::default(),
+        quote_asset: None,
+        risk: None,
+        params,
+    }
+}
+
+fn live_config(live_trading_enabled: bool, bots: Vec<LiveBotConfig>) -> LiveConfig {
+    LiveConfig {
+        config_version: LiveConfig::CURRENT_VERSION,
+        live_trading_enabled,
+        interval: "1m".to_string(),
+        broadcast_klines: false,
+        portfolio_broadcast_secs: 15,
+        dead_mans_switch_enabled: false,
+        feed_timeout_secs: None,
+        flatten_after_secs: 300,
+        latency_report_secs: 60,
+        feed_status_secs: 5,
+        kill_switch_file: None,
+        max_open_positions: None,
+        max_orders_per_hour: None,
+        max_strategy_errors: 3,
+        collateral_assets: vec!["USDT".to_string()],
+        record_book_tickers: false,
+        orphan_position_policy: Default::default(),
+        pnl_reconciliation: None,
+        replay: Default::default(),
+        bots,
+    }
+}
+
+fn base_config() -> Config {
+    testing::test_config(10).expect("load config")
+}
+
+async fn check(live_config: &LiveConfig, mode: ExecutionMode, exchange: MockAccount) -> Checklist {
+    let mut checklist = Checklist::default();
+    check_deployment(&mut checklist, &base_config(), live_config, mode, &exchange).await;
+    checklist
+}
+
+#[tokio::test]
+async fn a_sound_configuration_passes_every_check() {
+    let config = live_config(true, vec![bot("BTCUSDT", 10, serde_json::json!({})), bot("ETHUSDT", 20, serde_json::json!({}))]);
+    let checklist = check(&config, ExecutionMode::Live, exchange()).await;
+
+    assert_eq!(checklist.count(CheckStatus::Pass), checklist.checks.len(), "{}", checklist);
+    for name in ["Trading mode", "Strategy BTCUSDT 1m", "Kline stream 1m", "API keys", "Symbol ETHUSDT", "Leverage ETHUSDT"] {
+        assert_eq!(checklist.status(name), Some(CheckStatus::Pass), "{}", name);
+    }
+    assert!(checklist.passed());
+    assert!(checklist.to_string().starts_with("[PASS] Trading mode: live trading is enabled\n"), "{}", checklist);
+}
+
+#[tokio::test]
+async fn broken_bots_fail_their_own_checks() {
+    let config = live_config(
+        false,
+        vec![
+            bot("BTCUSDT", 50, serde_json::json!({})),
+            bot("ETHUSDT", 5, serde_json::json!({ "ma_fast_period": 0 })),
+            bot("BTCCUSDT", 5, serde_json::json!({})),
+        ],
+    );
+    let checklist = check(&config, ExecutionMode::Paper, exchange()).await;
+
+    assert_eq!(checklist.status("Leverage BTCUSDT"), Some(CheckStatus::Fail));
+    assert_eq!(checklist.status("Strategy ETHUSDT 1m"), Some(CheckStatus::Fail));
+    assert_eq!(checklist.status("Symbol BTCCUSDT"), Some(CheckStatus::Fail));
+    // The other checks of the same bots still pass.
+    assert_eq!(checklist.status("Strategy BTCUSDT 1m"), Some(CheckStatus::Pass));
+    assert_eq!(checklist.status("Leverage ETHUSDT"), Some(CheckStatus::Pass));
+    assert_eq!(checklist.count(CheckStatus::Fail), 3, "{}", checklist);
+    assert!(!checklist.passed());
+
+    let leverage = checklist.checks.iter().find(|check| check.name == "Leverage BTCUSDT").unwrap();
+    assert_eq!(leverage.detail, "50x is above the 20x the exchange allows");
+    let symbol = checklist.checks.iter().find(|check| check.name == "Symbol BTCCUSDT").unwrap();
+    assert!(symbol.detail.contains("did you mean BTCUSDT?"), "{}", symbol.detail);
+}
+
+#[tokio::test]
+async fn rejected_keys_fail_and_leave_the_leverage_unchecked() {
+    let config = live_config(false, vec![bot("BTCUSDT", 10, serde_json::json!({}))]);
+    let checklist = check(&config, ExecutionMode::Testnet, exchange().with_rejected_keys()).await;
+
+    let keys = checklist.checks.iter().find(|check| check.name == "API keys").unwrap();
+    assert_eq!(keys.status, CheckStatus::Fail);
+    assert!(keys.detail.starts_with("rejected by the testnet exchange"), "{}", keys.detail);
+    assert_eq!(checklist.status("Leverage BTCUSDT"), Some(CheckStatus::Warn));
+    assert!(!checklist.passed());
+}
+
+#[tokio::test]
+async fn the_live_trading_switch_is_checked_against_the_mode() {
+    let bots = vec![bot("BTCUSDT", 10, serde_json::json!({}))];
+
+    let checklist = check(&live_config(false, bots.clone()), ExecutionMode::Live, exchange()).await;
+    assert_eq!(checklist.status("Trading mode"), Some(CheckStatus::Fail));
+
+    // Enabled live trading outside live mode deploys, with a warning.
+    let checklist = check(&live_config(true, bots.clone()), ExecutionMode::Paper, exchange()).await;
+    assert_eq!(checklist.status("Trading mode"), Some(CheckStatus::Warn));
+    assert!(checklist.passed(), "{}", checklist);
+
+    let checklist = check(&live_config(false, bots), ExecutionMode::Paper, exchange()).await;
+    assert_eq!(checklist.status("Trading mode"), Some(CheckStatus::Pass));
+}
+
+#[tokio::test]
+async fn unsupported_intervals_and_empty_configurations_are_reported() {
+    let mut hourly = bot("BTCUSDT", 10, serde_json::json!({}));
+    hourly.interval = Some("60m".to_string());
+    let checklist = check(&live_config(false, vec![hourly]), ExecutionMode::Paper, exchange()).await;
+    assert_eq!(checklist.status("Kline stream 60m"), Some(CheckStatus::Fail));
+
+    let mut disabled = bot("BTCUSDT", 10, serde_json::json!({}));
+    disabled.enabled = false;
+    let checklist = check(&live_config(false, vec![disabled]), ExecutionMode::Paper, exchange()).await;
+    assert_eq!(checklist.status("Bots"), Some(CheckStatus::Warn));
+    assert!(checklist.passed());
+}
+
+#[test]
+fn a_missing_live_config_fails_to_load() {
+    let mut checklist = Checklist::default();
+    assert!(load_configs(&mut checklist, Path::new("does-not-exist.toml")).is_none());
+    assert_eq!(checklist.status("Configuration"), Some(CheckStatus::Fail));
+}
+
+#[tokio::test]
+async fn an_unreachable_database_fails() {
+    let mut checklist = Checklist::default();
+    check_database(&mut checklist, Err(DbError::ConnectionConfigError("DATABASE_URL must be set.".to_string()))).await;
+    assert_eq!(checklist.status("Database connection"), Some(CheckStatus::Fail));
+    assert_eq!(checklist.status("Database migrations"), None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+#[ignore = "requires a PostgreSQL server at DATABASE_URL"]
+async fn pending_migrations_fail_the_database_check() {
+    let db = TestDatabase::create().await.expect("create test database");
+
+    let mut checklist = Checklist::default();
+    check_database(&mut checklist, Ok(db.pool.clone())).await;
+    assert_eq!(checklist.status("Database migrations"), Some(CheckStatus::Pass), "{}", checklist);
+
+    // Forget the latest migration, as on a database the new build has not migrated yet.
+    sqlx::query("DELETE FROM _sqlx_migrations WHERE version = (SELECT MAX(version) FROM _sqlx_migrations)")
+        .execute(&db.pool)
+        .await
+        .unwrap();
+    let mut checklist = Checklist::default();
+    check_database(&mut checklist, Ok(db.pool.clone())).await;
+    let migrations = checklist.checks.iter().find(|check| check.name == "Database migrations").unwrap();
+    assert_eq!(migrations.status, CheckStatus::Fail);
+    assert!(migrations.detail.starts_with("1 pending: "), "{}", migrations.detail);
+
+    db.teardown().await.expect("drop test database");
+}